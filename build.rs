@@ -1,3 +1,6 @@
+use std::path::Path;
+use std::process::Command;
+
 fn main() {
     // ビルド時刻を環境変数として設定
     let timestamp = chrono::Utc::now()
@@ -5,7 +8,24 @@ fn main() {
         .to_string();
     println!("cargo:rustc-env=BUILD_TIMESTAMP={}", timestamp);
 
+    // ビルド元のコミット（gitがない環境では unknown）
+    let git_hash = Command::new("git")
+        .args(["rev-parse", "--short", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|hash| hash.trim().to_string())
+        .filter(|hash| !hash.is_empty())
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=GIT_HASH={}", git_hash);
+
     // ソースファイルが変更されたときのみ再ビルド
     println!("cargo:rerun-if-changed=src/");
     println!("cargo:rerun-if-changed=build.rs");
+    for git_path in [".git/HEAD", ".git/refs/heads", ".git/packed-refs"] {
+        if Path::new(git_path).exists() {
+            println!("cargo:rerun-if-changed={}", git_path);
+        }
+    }
 }
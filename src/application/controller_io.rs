@@ -0,0 +1,278 @@
+//! コントローラー操作の実行
+//!
+//! コントローラーへの送信はブロッキングI/Oとsleepを含むため、描画・キャリブレーションは
+//! `run_controller_io` で起動した専用スレッドから、ここのタップ操作を使って送信する。
+
+use crate::domain::controller::{
+    ActionType, Button, ControllerAction, ControllerCommand, ControllerEmulator, DPad,
+};
+use crate::domain::hardware::errors::HardwareError;
+use std::cell::Cell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+thread_local! {
+    /// `run_controller_io` から起動されたスレッドかどうか
+    static CONTROLLER_IO_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// コントローラー操作（ブロッキングI/Oとsleepを含む）を専用スレッドで実行する
+///
+/// 非同期ハンドラから直接コントローラーを操作するとtokioのワーカースレッドが
+/// 停止し、他のHTTPリクエストまで応答しなくなるため、必ずこの関数を経由する。
+pub async fn run_controller_io<F, R>(operation: F) -> Result<R, tokio::task::JoinError>
+where
+    F: FnOnce() -> R + Send + 'static,
+    R: Send + 'static,
+{
+    tokio::task::spawn_blocking(move || {
+        CONTROLLER_IO_THREAD.with(|flag| flag.set(true));
+        operation()
+    })
+    .await
+}
+
+/// 非同期ランタイムのワーカースレッド上でブロッキング操作が行われていないか検査する
+pub(crate) fn debug_assert_blocking_allowed() {
+    debug_assert!(
+        CONTROLLER_IO_THREAD.with(Cell::get) || tokio::runtime::Handle::try_current().is_err(),
+        "blocking controller operation on an async worker thread; use run_controller_io"
+    );
+}
+
+/// 起動時の初期化を再試行するまでの最初の間隔（失敗するたびに倍にする）
+const INIT_RETRY_INITIAL_DELAY: Duration = Duration::from_millis(250);
+/// 起動時の初期化を再試行する間隔の上限
+const INIT_RETRY_MAX_DELAY: Duration = Duration::from_secs(5);
+
+/// コントローラーを初期化し、失敗したら間隔を空けながら `window` の間だけ繰り返す
+///
+/// 起動直後はガジェットのデバイスファイルがまだ無いことがあるため、1回の失敗で諦めない。
+/// `window` が0なら1回だけ試す。
+pub(crate) fn initialize_with_retry(
+    controller: &dyn ControllerEmulator,
+    window: Duration,
+) -> Result<(), HardwareError> {
+    debug_assert_blocking_allowed();
+    retry_with_backoff(window, INIT_RETRY_INITIAL_DELAY, || controller.initialize())
+}
+
+/// `attempt` が成功するまで、間隔を `initial_delay` から倍にしながら繰り返す
+///
+/// 次の試行が `window` を過ぎる場合は待たずに最後のエラーを返す。
+fn retry_with_backoff<T, E: std::fmt::Display>(
+    window: Duration,
+    initial_delay: Duration,
+    mut attempt: impl FnMut() -> Result<T, E>,
+) -> Result<T, E> {
+    let started = Instant::now();
+    let mut delay = initial_delay;
+    loop {
+        match attempt() {
+            Ok(value) => return Ok(value),
+            Err(e) if started.elapsed() + delay > window => return Err(e),
+            Err(e) => {
+                warn!(
+                    "Controller initialization failed ({}); retrying in {:?}",
+                    e, delay
+                );
+                std::thread::sleep(delay);
+                delay = (delay * 2).min(INIT_RETRY_MAX_DELAY);
+            }
+        }
+    }
+}
+
+/// ボタンを1回タップする共通処理（時間指定版）
+pub(crate) fn tap_button_with_duration(
+    controller: &Arc<dyn ControllerEmulator>,
+    button: Button,
+    name: &str,
+    press_ms: u32,
+    release_ms: u32,
+    wait_ms: u64,
+) -> Result<(), HardwareError> {
+    debug_assert_blocking_allowed();
+    let tap_cmd = ControllerCommand::new(name)
+        .add_action(ControllerAction::press_button(button, press_ms))
+        .add_action(ControllerAction::release_button(button, release_ms));
+    controller.execute_command(&tap_cmd)?;
+    if wait_ms > 0 {
+        std::thread::sleep(std::time::Duration::from_millis(wait_ms));
+    }
+    Ok(())
+}
+
+/// 十字キーを1回タップする共通処理（デフォルト: 押下100ms、離す50ms、待機50ms）
+#[allow(dead_code)]
+pub(crate) fn tap_dpad(
+    controller: &Arc<dyn ControllerEmulator>,
+    dpad: DPad,
+    name: &str,
+) -> Result<(), HardwareError> {
+    tap_dpad_with_duration(controller, dpad, name, 100, 50, 50)
+}
+
+/// 十字キーを1回タップする共通処理（時間指定版）
+pub(crate) fn tap_dpad_with_duration(
+    controller: &Arc<dyn ControllerEmulator>,
+    dpad: DPad,
+    name: &str,
+    press_ms: u32,
+    release_ms: u32,
+    wait_ms: u64,
+) -> Result<(), HardwareError> {
+    debug_assert_blocking_allowed();
+    let tap_cmd = ControllerCommand::new(name)
+        .add_action(ControllerAction::set_dpad(dpad, press_ms))
+        .add_action(ControllerAction::set_dpad(DPad::NEUTRAL, release_ms));
+    controller.execute_command(&tap_cmd)?;
+    if wait_ms > 0 {
+        std::thread::sleep(std::time::Duration::from_millis(wait_ms));
+    }
+    Ok(())
+}
+
+/// 最後に送信した十字キーの状態を覚えておくコントローラーの薄いラッパー
+///
+/// 描画ループが重複するニュートラルクリアを省くために使う。送信エラー・中断・初期化の後は
+/// 実際の状態が分からないため `None` に戻し、次のクリアを省かせない。
+pub(crate) struct DPadTracker {
+    inner: Arc<dyn ControllerEmulator>,
+    last_dpad: Mutex<Option<DPad>>,
+}
+
+impl DPadTracker {
+    pub(crate) fn new(inner: Arc<dyn ControllerEmulator>) -> Self {
+        Self {
+            inner,
+            last_dpad: Mutex::new(None),
+        }
+    }
+
+    /// 最後に送信できた十字キーの状態（分からない場合は `None`）
+    pub(crate) fn last_dpad(&self) -> Option<DPad> {
+        *self.last_dpad.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// コマンドを実行し、成功したら最後の十字キーの操作を記録する
+    fn track(
+        &self,
+        command: &ControllerCommand,
+        execute: impl FnOnce() -> Result<(), HardwareError>,
+    ) -> Result<(), HardwareError> {
+        let previous = self
+            .last_dpad
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        let result = execute();
+        if result.is_ok() {
+            let sent = command
+                .sequence
+                .iter()
+                .rev()
+                .find_map(|action| match action.action_type {
+                    ActionType::SetDPad(dpad) => Some(dpad),
+                    _ => None,
+                });
+            *self.last_dpad.lock().unwrap_or_else(|e| e.into_inner()) = sent.or(previous);
+        }
+        result
+    }
+}
+
+impl ControllerEmulator for DPadTracker {
+    fn initialize(&self) -> Result<(), HardwareError> {
+        *self.last_dpad.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.inner.initialize()
+    }
+
+    fn is_connected(&self) -> Result<bool, HardwareError> {
+        self.inner.is_connected()
+    }
+
+    fn execute_command(&self, command: &ControllerCommand) -> Result<(), HardwareError> {
+        self.track(command, || self.inner.execute_command(command))
+    }
+
+    fn execute_command_cancellable(
+        &self,
+        command: &ControllerCommand,
+        cancel: &AtomicBool,
+    ) -> Result<(), HardwareError> {
+        let result = self.track(command, || {
+            self.inner.execute_command_cancellable(command, cancel)
+        });
+        // 打ち切った場合はどこまで送信したか分からない
+        if cancel.load(Ordering::SeqCst) {
+            *self.last_dpad.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        }
+        result
+    }
+
+    fn write_raw_report(&self, report: &[u8]) -> Result<usize, HardwareError> {
+        // 任意のレポートを送った後の十字キーの状態は分からない
+        *self.last_dpad.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.inner.write_raw_report(report)
+    }
+
+    fn shutdown(&self) -> Result<(), HardwareError> {
+        *self.last_dpad.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        self.inner.shutdown()
+    }
+
+    fn host_unresponsive_for(&self) -> Option<Duration> {
+        self.inner.host_unresponsive_for()
+    }
+
+    fn last_report(&self) -> Option<[u8; 8]> {
+        self.inner.last_report()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_with_backoff_retries_until_success_within_the_window() {
+        let mut attempts = 0;
+        let started = Instant::now();
+        let result = retry_with_backoff(Duration::from_secs(5), Duration::from_millis(20), || {
+            attempts += 1;
+            if attempts < 3 {
+                Err("not ready")
+            } else {
+                Ok(attempts)
+            }
+        });
+        assert_eq!(result, Ok(3));
+        // 20ms・40ms待ってから3回目で成功する
+        assert!(started.elapsed() >= Duration::from_millis(60));
+    }
+
+    #[test]
+    fn test_retry_with_backoff_gives_up_after_the_window() {
+        let mut attempts = 0;
+        let result: Result<(), _> = retry_with_backoff(
+            Duration::from_millis(100),
+            Duration::from_millis(20),
+            || {
+                attempts += 1;
+                Err(format!("attempt {attempts}"))
+            },
+        );
+        // 0ms・20ms・60msに試し、次の140msは上限を過ぎるため諦める
+        assert_eq!(result, Err("attempt 3".to_string()));
+
+        let mut attempts = 0;
+        let _ = retry_with_backoff(Duration::ZERO, Duration::from_millis(20), || {
+            attempts += 1;
+            Err::<(), _>("unavailable")
+        });
+        assert_eq!(attempts, 1);
+    }
+}
@@ -0,0 +1,197 @@
+//! 描画の見積もり補正モデルの保存先
+//!
+//! データディレクトリの `ESTIMATE_MODEL_FILE` に保存し、描画を終えるたびに更新する。
+//! シミュレーションでは読み書きせず常に補正しないため、テストの見積もりは設定したタイミングだけで決まる。
+
+use crate::domain::painting::{CorrectedEstimate, EstimateModel, PaintTiming};
+use crate::infrastructure::data_dir::create_data_dir;
+use chrono::{SecondsFormat, Utc};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// データディレクトリの中の補正モデルのファイル名
+pub const ESTIMATE_MODEL_FILE: &str = "estimate_model.json";
+
+#[derive(Debug, Error)]
+pub enum EstimateModelError {
+    #[error("Failed to save the estimate model to {path}: {source}")]
+    Save { path: PathBuf, source: io::Error },
+    #[error("Failed to remove the estimate model {path}: {source}")]
+    Remove { path: PathBuf, source: io::Error },
+}
+
+/// 見積もりの補正モデルと、その保存先（`None` なら補正も保存もしない）
+#[derive(Debug, Clone)]
+pub struct EstimateModelStore {
+    path: Option<PathBuf>,
+    model: Arc<Mutex<EstimateModel>>,
+}
+
+impl EstimateModelStore {
+    /// `data_dir` に保存済みのモデルを読み込む（読めなければ警告して記録の無い状態から始める）
+    pub fn open(data_dir: impl AsRef<Path>) -> Self {
+        let path = data_dir.as_ref().join(ESTIMATE_MODEL_FILE);
+        let model = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!(
+                    "Ignoring corrupted estimate model {}: {}",
+                    path.display(),
+                    e
+                );
+                EstimateModel::default()
+            }),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => EstimateModel::default(),
+            Err(e) => {
+                warn!("Failed to read estimate model {}: {}", path.display(), e);
+                EstimateModel::default()
+            }
+        };
+        Self {
+            path: Some(path),
+            model: Arc::new(Mutex::new(model)),
+        }
+    }
+
+    /// 補正も保存もしない（シミュレーション用）
+    pub fn disabled() -> Self {
+        Self {
+            path: None,
+            model: Arc::new(Mutex::new(EstimateModel::default())),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.path.is_some()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, EstimateModel> {
+        self.model.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 現在の補正係数
+    pub fn model(&self) -> EstimateModel {
+        self.lock().clone()
+    }
+
+    /// タイミングだけから計算した見積もりに、そのタイミングの補正係数を組み合わせる
+    pub fn estimate(&self, timing: &PaintTiming, raw_ms: u64) -> CorrectedEstimate {
+        let correction = if self.is_enabled() {
+            self.lock().correction(timing)
+        } else {
+            1.0
+        };
+        CorrectedEstimate::new(raw_ms, correction)
+    }
+
+    /// 描画1回分の見積もりと実測を記録して保存する（更新後の係数を返す。無効なら何もしない）
+    pub fn record(
+        &self,
+        timing: PaintTiming,
+        estimated_ms: u64,
+        actual_ms: u64,
+    ) -> Result<Option<f64>, EstimateModelError> {
+        let Some(path) = &self.path else {
+            return Ok(None);
+        };
+        let mut model = self.lock();
+        let recorded_at = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+        let Some(factor) = model.record(timing, estimated_ms, actual_ms, recorded_at) else {
+            return Ok(None);
+        };
+        info!(
+            "Estimate correction for {}+{}+{}ms is now {:.2}x (estimated {:.1}s, took {:.1}s)",
+            timing.press_ms,
+            timing.release_ms,
+            timing.wait_ms,
+            factor,
+            estimated_ms as f64 / 1000.0,
+            actual_ms as f64 / 1000.0
+        );
+        let save_error = |source| EstimateModelError::Save {
+            path: path.clone(),
+            source,
+        };
+        if let Some(dir) = path.parent() {
+            create_data_dir(dir).map_err(save_error)?;
+        }
+        let json = serde_json::to_string_pretty(&*model)
+            .map_err(io::Error::from)
+            .map_err(save_error)?;
+        fs::write(path, json).map_err(save_error)?;
+        Ok(Some(factor))
+    }
+
+    /// 記録した係数を捨てる（保存先のファイルは消さない。データディレクトリの初期化後に呼ぶ）
+    pub fn forget(&self) {
+        *self.lock() = EstimateModel::default();
+    }
+
+    /// 記録した係数を捨てて、保存先のファイルも削除する
+    pub fn reset(&self) -> Result<(), EstimateModelError> {
+        self.forget();
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(EstimateModelError::Remove {
+                path: path.clone(),
+                source: e,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_are_saved_reloaded_and_reset() {
+        let dir = std::env::temp_dir().join(format!("estimate-model-{}", uuid::Uuid::new_v4()));
+        let timing = PaintTiming::new(50, 30, 20);
+        let store = EstimateModelStore::open(&dir);
+        assert_eq!(store.estimate(&timing, 1000).correction, 1.0);
+
+        assert_eq!(store.record(timing, 1000, 1200).unwrap(), Some(1.2));
+        assert_eq!(store.estimate(&timing, 1000).corrected_ms(), 1200);
+
+        let reopened = EstimateModelStore::open(&dir);
+        assert_eq!(reopened.model(), store.model());
+        assert_eq!(reopened.estimate(&timing, 1000).correction, 1.2);
+
+        reopened.reset().unwrap();
+        assert!(!dir.join(ESTIMATE_MODEL_FILE).exists());
+        assert_eq!(
+            EstimateModelStore::open(&dir).model(),
+            EstimateModel::default()
+        );
+        reopened.reset().unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_disabled_store_never_corrects_or_saves() {
+        let timing = PaintTiming::default();
+        let store = EstimateModelStore::disabled();
+        assert_eq!(store.record(timing, 1000, 2000).unwrap(), None);
+        assert_eq!(store.model(), EstimateModel::default());
+        assert_eq!(store.estimate(&timing, 1000).corrected_ms(), 1000);
+    }
+
+    #[test]
+    fn test_corrupted_file_starts_from_an_empty_model() {
+        let dir = std::env::temp_dir().join(format!("estimate-model-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(ESTIMATE_MODEL_FILE), "not json").unwrap();
+        assert_eq!(
+            EstimateModelStore::open(&dir).model(),
+            EstimateModel::default()
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
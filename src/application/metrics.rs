@@ -0,0 +1,350 @@
+//! HIDレポートとログの書き込み量の集計
+//!
+//! 長時間の描画でSDカードとHIDデバイスにどれだけ書き込んだかを、起動からの累計と
+//! 直近の描画の分に分けて数える。コントローラーには `ReportMetricsSink` として渡す。
+
+use crate::domain::hardware::{ReportErrorClass, ReportMetricsSink};
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// この間隔より空いたレポートは連続した送信とみなさない（待機やコマンドの切れ目）
+const MAX_STREAM_GAP: Duration = Duration::from_millis(50);
+
+/// 1つの集計期間のカウンター
+#[derive(Debug, Default)]
+struct Counters {
+    reports_sent: AtomicU64,
+    bytes_sent: AtomicU64,
+    write_errors: [AtomicU64; ReportErrorClass::ALL.len()],
+    jitter_sum_us: AtomicU64,
+    jitter_samples: AtomicU64,
+}
+
+impl Counters {
+    fn reset(&self) {
+        for counter in [
+            &self.reports_sent,
+            &self.bytes_sent,
+            &self.jitter_sum_us,
+            &self.jitter_samples,
+        ]
+        .into_iter()
+        .chain(&self.write_errors)
+        {
+            counter.store(0, Ordering::Relaxed);
+        }
+    }
+
+    fn snapshot(&self, log_bytes_written: u64) -> MetricsSnapshot {
+        let samples = self.jitter_samples.load(Ordering::Relaxed);
+        MetricsSnapshot {
+            reports_sent: self.reports_sent.load(Ordering::Relaxed),
+            bytes_sent: self.bytes_sent.load(Ordering::Relaxed),
+            write_errors: ReportErrorClass::ALL
+                .iter()
+                .zip(&self.write_errors)
+                .map(|(class, count)| (class.to_string(), count.load(Ordering::Relaxed)))
+                .collect(),
+            avg_jitter_us: (samples > 0)
+                .then(|| self.jitter_sum_us.load(Ordering::Relaxed) / samples),
+            log_bytes_written,
+        }
+    }
+}
+
+/// 連続して送ったレポートの間隔
+#[derive(Debug, Default)]
+struct IntervalState {
+    last_sent: Option<Instant>,
+    last_interval: Option<Duration>,
+}
+
+/// 送信したレポートとログの書き込み量の集計
+///
+/// 描画の開始時に `start_run` で描画ごとのカウンターを0に戻す（起動からの累計はそのまま）。
+#[derive(Debug)]
+pub struct Metrics {
+    started: Instant,
+    total: Counters,
+    run: Counters,
+    intervals: Mutex<IntervalState>,
+    run_started_at: Mutex<Option<DateTime<Utc>>>,
+    /// 描画の開始時点のログの書き込み量
+    run_log_baseline: AtomicU64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self {
+            started: Instant::now(),
+            total: Counters::default(),
+            run: Counters::default(),
+            intervals: Mutex::new(IntervalState::default()),
+            run_started_at: Mutex::new(None),
+            run_log_baseline: AtomicU64::new(0),
+        }
+    }
+
+    /// 描画ごとのカウンターを0に戻す
+    pub fn start_run(&self) {
+        self.run.reset();
+        self.run_log_baseline
+            .store(crate::debug::log_bytes_written(), Ordering::Relaxed);
+        *self
+            .run_started_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(Utc::now());
+    }
+
+    /// 起動からの累計と直近の描画の集計
+    pub fn report(&self) -> MetricsReport {
+        let log_bytes = crate::debug::log_bytes_written();
+        let run_started_at = *self
+            .run_started_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+        MetricsReport {
+            uptime_seconds: self.started.elapsed().as_secs(),
+            process: self.total.snapshot(log_bytes),
+            run: run_started_at.map(|started_at| RunMetrics {
+                started_at: started_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+                metrics: self.run.snapshot(
+                    log_bytes.saturating_sub(self.run_log_baseline.load(Ordering::Relaxed)),
+                ),
+            }),
+        }
+    }
+
+    fn each(&self, f: impl Fn(&Counters)) {
+        f(&self.total);
+        f(&self.run);
+    }
+}
+
+impl ReportMetricsSink for Metrics {
+    fn report_sent(&self, bytes: usize, at: Instant) {
+        self.each(|counters| {
+            counters.reports_sent.fetch_add(1, Ordering::Relaxed);
+            counters
+                .bytes_sent
+                .fetch_add(bytes as u64, Ordering::Relaxed);
+        });
+
+        // 連続した送信の中で、前の間隔からのずれをジッターとして平均する
+        let mut intervals = self.intervals.lock().unwrap_or_else(|e| e.into_inner());
+        let interval = intervals
+            .last_sent
+            .replace(at)
+            .map(|last| at.saturating_duration_since(last))
+            .filter(|interval| *interval <= MAX_STREAM_GAP);
+        if let (Some(interval), Some(last_interval)) = (interval, intervals.last_interval) {
+            let jitter_us = interval.abs_diff(last_interval).as_micros() as u64;
+            self.each(|counters| {
+                counters
+                    .jitter_sum_us
+                    .fetch_add(jitter_us, Ordering::Relaxed);
+                counters.jitter_samples.fetch_add(1, Ordering::Relaxed);
+            });
+        }
+        intervals.last_interval = interval;
+    }
+
+    fn report_failed(&self, class: ReportErrorClass) {
+        let index = ReportErrorClass::ALL
+            .iter()
+            .position(|c| *c == class)
+            .expect("every class is listed in ALL");
+        self.each(|counters| {
+            counters.write_errors[index].fetch_add(1, Ordering::Relaxed);
+        });
+    }
+}
+
+/// ある期間に送ったレポートと書き込んだログの量
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct MetricsSnapshot {
+    /// 書き込めたHIDレポートの数
+    pub reports_sent: u64,
+    /// 書き込めたHIDレポートのバイト数
+    pub bytes_sent: u64,
+    /// 分類ごとの書き込みエラーの数（`would_block`、`disconnected` など）
+    pub write_errors: BTreeMap<String, u64>,
+    /// 連続して送ったレポートの間隔のずれの平均（マイクロ秒、連続した送信が無ければ `null`）
+    pub avg_jitter_us: Option<u64>,
+    /// ログファイル（監査ログを含む）に書き込んだバイト数
+    pub log_bytes_written: u64,
+}
+
+/// 直近の描画の集計
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct RunMetrics {
+    /// 描画を開始した時刻（RFC 3339）
+    pub started_at: String,
+    pub metrics: MetricsSnapshot,
+}
+
+/// `GET /api/metrics` の応答
+#[derive(Debug, Clone, PartialEq, Serialize, ToSchema)]
+pub struct MetricsReport {
+    /// 起動してからの秒数
+    pub uptime_seconds: u64,
+    /// 起動からの累計
+    pub process: MetricsSnapshot,
+    /// 直近の描画の分（起動してから描画していなければ `null`）
+    pub run: Option<RunMetrics>,
+}
+
+impl MetricsReport {
+    /// Prometheusのテキスト形式
+    ///
+    /// 起動からの累計はカウンター（`_total`）、直近の描画の分はゲージ（`ghost_drawer_run_*`）にする。
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, u64)]| {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} {kind}");
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{name}{labels} {value}");
+            }
+        };
+        metric(
+            "ghost_drawer_uptime_seconds",
+            "gauge",
+            "Seconds since the process started",
+            &[("", self.uptime_seconds)],
+        );
+
+        let run = self.run.as_ref().map(|run| &run.metrics);
+        for (prefix, kind, suffix, snapshot) in [
+            ("ghost_drawer", "counter", "_total", Some(&self.process)),
+            ("ghost_drawer_run", "gauge", "", run),
+        ] {
+            let Some(snapshot) = snapshot else {
+                continue;
+            };
+            let scope = if suffix.is_empty() {
+                "in the current or last painting run"
+            } else {
+                "since the process started"
+            };
+            metric(
+                &format!("{prefix}_hid_reports_sent{suffix}"),
+                kind,
+                &format!("HID reports written {scope}"),
+                &[("", snapshot.reports_sent)],
+            );
+            metric(
+                &format!("{prefix}_hid_bytes_sent{suffix}"),
+                kind,
+                &format!("HID report bytes written {scope}"),
+                &[("", snapshot.bytes_sent)],
+            );
+            let labels: Vec<(String, u64)> = snapshot
+                .write_errors
+                .iter()
+                .map(|(class, count)| (format!("{{class=\"{class}\"}}"), *count))
+                .collect();
+            let samples: Vec<(&str, u64)> = labels
+                .iter()
+                .map(|(labels, count)| (labels.as_str(), *count))
+                .collect();
+            metric(
+                &format!("{prefix}_hid_write_errors{suffix}"),
+                kind,
+                &format!("HID report write errors by class {scope}"),
+                &samples,
+            );
+            metric(
+                &format!("{prefix}_log_bytes_written{suffix}"),
+                kind,
+                &format!("Bytes written to log files {scope}"),
+                &[("", snapshot.log_bytes_written)],
+            );
+            if let Some(jitter) = snapshot.avg_jitter_us {
+                let name = if suffix.is_empty() {
+                    "ghost_drawer_run_hid_report_jitter_microseconds"
+                } else {
+                    "ghost_drawer_hid_report_jitter_microseconds"
+                };
+                metric(
+                    name,
+                    "gauge",
+                    &format!("Average HID report interval jitter {scope}"),
+                    &[("", jitter)],
+                );
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_counters_reset_while_totals_continue() {
+        let metrics = Metrics::new();
+        let start = Instant::now();
+        let at = |ms: u64| start + Duration::from_millis(ms);
+
+        // 8ms, 10ms, 8ms の間隔で送ると、間隔のずれは 2ms, 2ms
+        for ms in [0, 8, 18, 26] {
+            metrics.report_sent(8, at(ms));
+        }
+        metrics.report_failed(ReportErrorClass::WouldBlock);
+        let report = metrics.report();
+        assert!(report.run.is_none());
+        assert_eq!(report.process.reports_sent, 4);
+        assert_eq!(report.process.bytes_sent, 32);
+        assert_eq!(report.process.write_errors["would_block"], 1);
+        assert_eq!(report.process.write_errors["disconnected"], 0);
+        assert_eq!(report.process.avg_jitter_us, Some(2000));
+
+        // 描画を始めると描画ごとの分だけ0に戻る。空いた間隔はジッターに数えない
+        metrics.start_run();
+        metrics.report_sent(8, at(1000));
+        metrics.report_failed(ReportErrorClass::Disconnected);
+        let report = metrics.report();
+        let run = report.run.unwrap().metrics;
+        assert_eq!(run.reports_sent, 1);
+        assert_eq!(run.write_errors["would_block"], 0);
+        assert_eq!(run.write_errors["disconnected"], 1);
+        assert_eq!(run.avg_jitter_us, None);
+        assert_eq!(report.process.reports_sent, 5);
+        assert_eq!(report.process.avg_jitter_us, Some(2000));
+    }
+
+    #[test]
+    fn test_prometheus_text() {
+        let metrics = Metrics::new();
+        metrics.report_sent(8, Instant::now());
+        metrics.report_failed(ReportErrorClass::HostNotReady);
+        let text = metrics.report().to_prometheus();
+        assert!(text.contains("# TYPE ghost_drawer_hid_reports_sent_total counter\n"));
+        assert!(text.contains("\nghost_drawer_hid_reports_sent_total 1\n"));
+        assert!(text.contains("\nghost_drawer_hid_bytes_sent_total 8\n"));
+        assert!(
+            text.contains("\nghost_drawer_hid_write_errors_total{class=\"host_not_ready\"} 1\n")
+        );
+        // 描画する前は描画ごとのゲージを出さない
+        assert!(!text.contains("ghost_drawer_run_"));
+
+        metrics.start_run();
+        let text = metrics.report().to_prometheus();
+        assert!(text.contains("# TYPE ghost_drawer_run_hid_reports_sent gauge\n"));
+        assert!(text.contains("\nghost_drawer_run_hid_reports_sent 0\n"));
+    }
+}
@@ -0,0 +1,362 @@
+//! 描画のタイミングの記録（ドットごとの予定と実測）
+//!
+//! `PaintRequest.trace` で有効にした描画で、ドットごとに予定の押下・解放・待機時間と、
+//! `execute_command` の前後で測った各段階の実際の時間、やり直しの有無を1行ずつ記録する。
+//! 行は描画開始時に確保したバッファに貯め、上限を超えたら一時ファイルへ書き出してバッファを使い回す。
+//! 描画中の記録でヒープ確保をしないよう、行は固定長の値にする。
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tracing::warn;
+
+/// CSVの列（列の追加は末尾に限り、既存の列の名前・意味・順序は変えない）
+///
+/// - `index`: 描画パスでの順番（0始まり）
+/// - `x`, `y`: ゲーム内キャンバスの座標
+/// - `planned_press_ms`, `planned_release_ms`, `planned_wait_ms`: そのドットで使った予定の時間
+///   （自動調整が有効なら `planned_wait_ms` は調整後の値）
+/// - `presses`: 押したボタンの回数（繰り返し回数ぶん、やり直しで押し直さなかった分は含まない）
+/// - `move_us`: 移動と描画前のニュートラルクリアにかかった時間
+/// - `press_release_us`: ボタンの押下・解放の `execute_command` にかかった時間の合計
+/// - `wait_us`: 押した後の待機にかかった時間の合計
+/// - `attempts`: 試行回数（1ならやり直し無し）
+/// - `outcome`: `painted` / `retried` / `skipped`
+///
+/// 時間（`_us`）はマイクロ秒で、やり直した場合は全試行の合計。
+pub const TRACE_CSV_HEADER: &str = "index,x,y,planned_press_ms,planned_release_ms,planned_wait_ms,presses,move_us,press_release_us,wait_us,attempts,outcome\n";
+
+/// メモリに貯める行数の上限（超えた分は一時ファイルへ書き出す）
+pub const MAX_BUFFERED_TRACE_ROWS: usize = 65_536;
+
+/// 保持する描画の記録の数（古いものから捨てる）
+const MAX_KEPT_TRACES: usize = 4;
+
+/// ドットの描画結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TraceOutcome {
+    Painted,
+    Retried,
+    Skipped,
+}
+
+impl TraceOutcome {
+    fn name(self) -> &'static str {
+        match self {
+            TraceOutcome::Painted => "painted",
+            TraceOutcome::Retried => "retried",
+            TraceOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+/// ドット1つ分の記録
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceRow {
+    pub index: u32,
+    pub x: u16,
+    pub y: u16,
+    pub planned_press_ms: u32,
+    pub planned_release_ms: u32,
+    pub planned_wait_ms: u32,
+    pub presses: u32,
+    pub move_us: u64,
+    pub press_release_us: u64,
+    pub wait_us: u64,
+    pub attempts: u32,
+    pub outcome: TraceOutcome,
+}
+
+impl TraceRow {
+    fn write_csv(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            self.index,
+            self.x,
+            self.y,
+            self.planned_press_ms,
+            self.planned_release_ms,
+            self.planned_wait_ms,
+            self.presses,
+            self.move_us,
+            self.press_release_us,
+            self.wait_us,
+            self.attempts,
+            self.outcome.name()
+        )
+    }
+}
+
+/// 書き出し先の一時ファイル
+#[derive(Debug)]
+struct SpillFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+/// 1回の描画の記録
+#[derive(Debug)]
+pub struct PaintingTrace {
+    rows: Vec<TraceRow>,
+    buffer_rows: usize,
+    spill: Option<SpillFile>,
+    spilled_rows: usize,
+    finished: bool,
+}
+
+impl PaintingTrace {
+    /// `expected_rows`（描画するドット数）に合わせて、上限までのバッファを確保する
+    pub fn new(expected_rows: usize) -> Self {
+        Self::with_buffer_rows(expected_rows, MAX_BUFFERED_TRACE_ROWS)
+    }
+
+    fn with_buffer_rows(expected_rows: usize, buffer_rows: usize) -> Self {
+        let buffer_rows = buffer_rows.max(1);
+        Self {
+            rows: Vec::with_capacity(expected_rows.min(buffer_rows)),
+            buffer_rows,
+            spill: None,
+            spilled_rows: 0,
+            finished: false,
+        }
+    }
+
+    /// 1行を記録する（バッファが一杯なら先に一時ファイルへ書き出す）
+    pub fn record(&mut self, row: TraceRow) {
+        if self.rows.len() >= self.buffer_rows {
+            if let Err(e) = self.spill() {
+                warn!(
+                    "Could not spill the painting trace to a temp file; dropping {} rows: {}",
+                    self.rows.len(),
+                    e
+                );
+            }
+            self.rows.clear();
+        }
+        self.rows.push(row);
+    }
+
+    fn spill(&mut self) -> io::Result<()> {
+        let spill = match &mut self.spill {
+            Some(spill) => spill,
+            None => {
+                let path = std::env::temp_dir()
+                    .join(format!("painting-trace-{}.csv", uuid::Uuid::new_v4()));
+                let writer = BufWriter::new(File::create(&path)?);
+                self.spill.insert(SpillFile { path, writer })
+            }
+        };
+        for row in &self.rows {
+            row.write_csv(&mut spill.writer)?;
+        }
+        self.spilled_rows += self.rows.len();
+        Ok(())
+    }
+
+    /// 描画の終了時に呼び、一時ファイルへの書き出しを確定する
+    pub fn finish(&mut self) {
+        if let Some(spill) = &mut self.spill
+            && let Err(e) = spill.writer.flush()
+        {
+            warn!("Could not flush the painting trace: {}", e);
+        }
+        self.finished = true;
+    }
+
+    /// 描画が終わって、CSVを読み出せるか
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// 記録した行数（書き出しに失敗して捨てた行は含まない）
+    pub fn len(&self) -> usize {
+        self.spilled_rows + self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// ヘッダーを含むCSVを、`chunk_bytes` 程度の塊に分けて `send` に渡す
+    ///
+    /// `send` が `false` を返したら（受け取り側が切断したら）途中でやめる。
+    pub fn write_csv_chunks(&self, chunk_bytes: usize, mut send: impl FnMut(Vec<u8>) -> bool) {
+        let mut chunk = Vec::with_capacity(chunk_bytes);
+        chunk.extend_from_slice(TRACE_CSV_HEADER.as_bytes());
+        if let Some(spill) = &self.spill {
+            let mut file = match File::open(&spill.path) {
+                Ok(file) => file,
+                Err(e) => {
+                    warn!("Could not read the spilled painting trace: {}", e);
+                    return;
+                }
+            };
+            loop {
+                let start = chunk.len();
+                chunk.resize(chunk_bytes.max(start + 1), 0);
+                match file.read(&mut chunk[start..]) {
+                    Ok(0) => {
+                        chunk.truncate(start);
+                        break;
+                    }
+                    Ok(read) => chunk.truncate(start + read),
+                    Err(e) => {
+                        warn!("Could not read the spilled painting trace: {}", e);
+                        return;
+                    }
+                }
+                if chunk.len() >= chunk_bytes
+                    && !send(std::mem::replace(
+                        &mut chunk,
+                        Vec::with_capacity(chunk_bytes),
+                    ))
+                {
+                    return;
+                }
+            }
+        }
+        for row in &self.rows {
+            // Vecへの書き込みは失敗しない
+            let _ = row.write_csv(&mut chunk);
+            if chunk.len() >= chunk_bytes
+                && !send(std::mem::replace(
+                    &mut chunk,
+                    Vec::with_capacity(chunk_bytes),
+                ))
+            {
+                return;
+            }
+        }
+        if !chunk.is_empty() {
+            send(chunk);
+        }
+    }
+}
+
+impl Drop for PaintingTrace {
+    fn drop(&mut self) {
+        if let Some(spill) = &self.spill {
+            let _ = std::fs::remove_file(&spill.path);
+        }
+    }
+}
+
+/// 描画スレッドと読み出し側で共有する記録
+pub type SharedPaintingTrace = Arc<Mutex<PaintingTrace>>;
+
+/// 直近の描画の記録（実行IDごと、`MAX_KEPT_TRACES` 件まで）
+#[derive(Debug, Clone, Default)]
+pub struct PaintingTraceStore {
+    traces: Arc<Mutex<VecDeque<(String, SharedPaintingTrace)>>>,
+}
+
+impl PaintingTraceStore {
+    /// 記録を追加し、古い記録（と一時ファイル）を捨てる
+    pub fn insert(&self, run_id: &str, trace: SharedPaintingTrace) {
+        let mut traces = self.traces.lock().unwrap_or_else(|e| e.into_inner());
+        traces.push_back((run_id.to_string(), trace));
+        while traces.len() > MAX_KEPT_TRACES {
+            traces.pop_front();
+        }
+    }
+
+    pub fn get(&self, run_id: &str) -> Option<SharedPaintingTrace> {
+        self.traces
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .find(|(id, _)| id == run_id)
+            .map(|(_, trace)| trace.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(index: u32) -> TraceRow {
+        TraceRow {
+            index,
+            x: index as u16,
+            y: 2,
+            planned_press_ms: 50,
+            planned_release_ms: 50,
+            planned_wait_ms: 10,
+            presses: 1,
+            move_us: 1200,
+            press_release_us: 100_500,
+            wait_us: 10_100,
+            attempts: if index == 1 { 2 } else { 1 },
+            outcome: if index == 1 {
+                TraceOutcome::Retried
+            } else {
+                TraceOutcome::Painted
+            },
+        }
+    }
+
+    fn csv(trace: &PaintingTrace, chunk_bytes: usize) -> String {
+        let mut bytes = Vec::new();
+        trace.write_csv_chunks(chunk_bytes, |chunk| {
+            bytes.extend(chunk);
+            true
+        });
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn test_csv_layout_is_stable() {
+        let mut trace = PaintingTrace::new(2);
+        trace.record(row(0));
+        trace.record(row(1));
+        trace.finish();
+        assert_eq!(
+            csv(&trace, 4096),
+            "index,x,y,planned_press_ms,planned_release_ms,planned_wait_ms,presses,move_us,press_release_us,wait_us,attempts,outcome\n\
+             0,0,2,50,50,10,1,1200,100500,10100,1,painted\n\
+             1,1,2,50,50,10,1,1200,100500,10100,2,retried\n"
+        );
+    }
+
+    #[test]
+    fn test_rows_beyond_the_buffer_spill_to_a_temp_file() {
+        let mut trace = PaintingTrace::with_buffer_rows(100, 8);
+        for index in 0..20 {
+            trace.record(row(index));
+        }
+        trace.finish();
+        // バッファは最初に確保した大きさのまま使い回す
+        assert_eq!(trace.rows.capacity(), 8);
+        assert_eq!(trace.len(), 20);
+        let path = trace.spill.as_ref().unwrap().path.clone();
+        assert!(path.exists());
+
+        let text = csv(&trace, 64);
+        let indices: Vec<u32> = text
+            .lines()
+            .skip(1)
+            .map(|line| line.split(',').next().unwrap().parse().unwrap())
+            .collect();
+        assert_eq!(indices, (0..20).collect::<Vec<_>>());
+        assert_eq!(text, csv(&trace, 1 << 20));
+
+        drop(trace);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_store_keeps_only_recent_traces() {
+        let store = PaintingTraceStore::default();
+        for run in 0..=MAX_KEPT_TRACES {
+            store.insert(
+                &format!("run-{run}"),
+                Arc::new(Mutex::new(PaintingTrace::new(0))),
+            );
+        }
+        assert!(store.get("run-0").is_none());
+        assert!(store.get(&format!("run-{MAX_KEPT_TRACES}")).is_some());
+    }
+}
@@ -0,0 +1,21 @@
+//! 描画・キャリブレーションの進捗の配信先
+//!
+//! 描画スレッドはJSONのメッセージをこのチャンネルへ送り、WebUIはWebSocket（`/ws/logs`）で受け取る。
+
+use crate::domain::shared::i18n::{Locale, Message};
+use tokio::sync::broadcast;
+
+lazy_static::lazy_static! {
+    pub static ref PROGRESS_CHANNEL: broadcast::Sender<String> = {
+        let (tx, _) = broadcast::channel(100);
+        tx
+    };
+}
+
+/// 文言を `field` に入れて進捗を送る
+///
+/// 文言はサーバーの既定の言語で入れておき、WebSocketの接続ごとにその接続の言語で組み立て直す。
+pub fn send_progress(mut value: serde_json::Value, field: &str, message: &Message) {
+    message.insert_into(&mut value, field, Locale::default());
+    let _ = PROGRESS_CHANNEL.send(value.to_string());
+}
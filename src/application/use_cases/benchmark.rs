@@ -0,0 +1,482 @@
+//! このボードで実際に出せる描画速度のベンチマーク
+//!
+//! ボード（Pi Zero・Zero 2W・Orange Piなど）ごとに送れるレポートの速さが違うため、実際のHIDデバイス
+//! （シミュレーションではtmpfsのファイル）へのレポートの書き込み、負荷の掛かった状態での8ms間隔の
+//! 送信ループの揺らぎ、1万ドットの描画パスの生成速度を測り、押下・離す・待機時間の下限の目安を求める。
+//! Switchが入力を読んでいなくても実行でき、受け取られなかった書き込みは失敗として数える。
+//! 全体は `MAX_BENCHMARK_DURATION` で打ち切る。
+
+use crate::domain::artwork::entities::{Canvas, Dot};
+use crate::domain::controller::HidReport;
+use crate::domain::painting::{
+    ArtworkToCommandConverter, DrawingCanvasConfig, DrawingStrategy, PaintTiming, TwoOptSettings,
+};
+use crate::domain::shared::value_objects::Coordinates;
+use crate::infrastructure::data_dir::create_data_dir;
+use crate::infrastructure::hardware::linux_usb_gadget_manager::HID_DEVICE_PATH;
+use crate::infrastructure::platform;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// データディレクトリの中の直近の結果のファイル名
+pub const BENCHMARK_FILE: &str = "benchmark.json";
+/// ベンチマーク全体に掛ける時間の上限
+pub const MAX_BENCHMARK_DURATION: Duration = Duration::from_secs(15);
+/// 続けて書き込むレポートの数
+const REPORT_COUNT: u32 = 1000;
+/// レポートの連続書き込みに掛ける時間の上限
+const WRITE_PHASE_BUDGET: Duration = Duration::from_secs(5);
+/// 入力中にレポートを送り続ける間隔（コントローラーと同じ125Hz）
+const REPORT_INTERVAL: Duration = Duration::from_millis(8);
+/// ホストがレポートを受け取るまで書き込みを再試行する時間（コントローラーと同じ）
+const WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+/// 書き込みを再試行する間隔
+const WRITE_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+/// 送信ループを測る最小の回数（パスの生成が先に終わっても、これだけは測る）
+const MIN_LOOP_TICKS: u32 = 250;
+/// 描画パスの生成に使う合成キャンバスのドット数
+const PATH_DOTS: usize = 10_000;
+/// 合成キャンバスの大きさ（ゲーム内のキャンバスと同じ）
+const PATH_CANVAS: (u16, u16) = (320, 120);
+/// パスの生成で2-opt最適化に掛ける時間の上限（サーバーの設定がこれより長くても丸める）
+const PATH_TWO_OPT_BUDGET: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Error)]
+pub enum BenchmarkError {
+    #[error("Cannot open {path} for writing reports: {source}")]
+    Open { path: PathBuf, source: io::Error },
+    #[error("Failed to save the benchmark result to {path}: {source}")]
+    Save { path: PathBuf, source: io::Error },
+    #[error("Failed to read the benchmark result {path}: {source}")]
+    Read { path: PathBuf, source: io::Error },
+    #[error("Benchmark result {path} is corrupted: {source}")]
+    Corrupted {
+        path: PathBuf,
+        source: serde_json::Error,
+    },
+}
+
+/// 所要時間の分布（マイクロ秒）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct LatencyPercentiles {
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+impl LatencyPercentiles {
+    /// 標本の分布（標本が無ければすべて0）
+    fn of(mut samples: Vec<Duration>) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let percentile = |p: usize| {
+            let index = (samples.len() * p).div_ceil(100).saturating_sub(1);
+            samples[index].as_micros() as u64
+        };
+        Self {
+            p50_us: percentile(50),
+            p90_us: percentile(90),
+            p99_us: percentile(99),
+            max_us: percentile(100),
+        }
+    }
+}
+
+/// レポートを続けて書き込んだ結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReportWriteBenchmark {
+    /// 書き込もうとしたレポートの数（時間の上限で打ち切った場合は `REPORT_COUNT` より少ない）
+    pub attempted: u32,
+    pub written: u32,
+    /// 再試行の時間内に受け取られなかった書き込み
+    pub failed: u32,
+    pub elapsed_ms: u64,
+    pub reports_per_second: f64,
+    /// 書き込めたレポート1件ごとの所要時間（デバイスを開く時間を含む）
+    pub latency: LatencyPercentiles,
+}
+
+/// パスの生成と同時に、8ms間隔でレポートを送り続けた結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct ReportLoopBenchmark {
+    /// 送信の合間に待つ時間
+    pub interval_ms: u64,
+    pub ticks: u32,
+    /// 実際の送信間隔の平均（書き込みの時間を含む）
+    pub mean_interval_us: u64,
+    /// 送信間隔の平均からのずれ
+    pub jitter: LatencyPercentiles,
+}
+
+/// 合成キャンバスの描画パスを生成した結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct PathGenerationBenchmark {
+    pub dots: usize,
+    pub strategy: DrawingStrategy,
+    pub elapsed_ms: u64,
+    pub dots_per_second: f64,
+}
+
+/// ベンチマークの結果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct BenchmarkReport {
+    /// 測定した日時（RFC 3339）
+    pub measured_at: String,
+    /// レポートを書き込んだファイル
+    pub target: String,
+    /// HIDデバイスの代わりにtmpfsのファイルへ書き込んだか
+    pub simulated: bool,
+    pub duration_ms: u64,
+    /// 時間の上限で測定を打ち切ったか
+    pub truncated: bool,
+    pub report_writes: ReportWriteBenchmark,
+    pub report_loop: ReportLoopBenchmark,
+    pub path_generation: PathGenerationBenchmark,
+    /// 書き込みのp99と送信ループの揺らぎから求めた、押下・離す・待機時間の下限の目安
+    pub suggested_timing: PaintTiming,
+}
+
+/// 書き込みの所要時間と揺らぎから、入力を取りこぼさない時間の下限の目安を求める
+///
+/// 押下・離すはそれぞれ、Switchが読み取る2回分の間隔に遅れを足した時間だけ保つ。
+/// 待機は遅れの分だけ確保する。
+fn suggest_timing(write_p99: Duration, jitter_p99: Duration) -> PaintTiming {
+    let delay = write_p99 + jitter_p99;
+    let ceil_ms = |duration: Duration| duration.as_micros().div_ceil(1000) as u32;
+    let hold = ceil_ms(REPORT_INTERVAL * 2 + delay);
+    PaintTiming::new(hold, hold, ceil_ms(delay))
+}
+
+/// ベンチマークを実行し、直近の結果をデータディレクトリに保存するユースケース
+#[derive(Debug, Clone)]
+pub struct BenchmarkUseCase {
+    data_dir: PathBuf,
+    /// レポートを書き込むHIDデバイス（`None` ならtmpfsのファイル）
+    device: Option<PathBuf>,
+    two_opt: TwoOptSettings,
+}
+
+impl BenchmarkUseCase {
+    /// ガジェットのHIDデバイス（`/dev/hidg0`）へ書き込む
+    pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            data_dir: data_dir.into(),
+            device: Some(PathBuf::from(HID_DEVICE_PATH)),
+            two_opt: TwoOptSettings::default(),
+        }
+    }
+
+    /// HIDデバイスの代わりにtmpfsのファイルへ書き込む
+    pub fn simulated(mut self) -> Self {
+        self.device = None;
+        self
+    }
+
+    pub fn with_device(mut self, device: impl Into<PathBuf>) -> Self {
+        self.device = Some(device.into());
+        self
+    }
+
+    /// パスの生成に使う2-opt最適化の設定（時間の上限は `PATH_TWO_OPT_BUDGET` で丸める）
+    pub fn with_two_opt_settings(mut self, two_opt: TwoOptSettings) -> Self {
+        self.two_opt = two_opt;
+        self
+    }
+
+    pub fn result_path(&self) -> PathBuf {
+        self.data_dir.join(BENCHMARK_FILE)
+    }
+
+    /// 直近の結果（まだ実行していなければ `None`）
+    pub fn latest(&self) -> Result<Option<BenchmarkReport>, BenchmarkError> {
+        let path = self.result_path();
+        let json = match fs::read_to_string(&path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => return Err(BenchmarkError::Read { path, source }),
+        };
+        serde_json::from_str(&json)
+            .map(Some)
+            .map_err(|source| BenchmarkError::Corrupted { path, source })
+    }
+
+    /// ベンチマークを実行して結果を保存する（数秒〜`MAX_BENCHMARK_DURATION` 掛かるため、ブロッキングスレッドで呼ぶ）
+    pub fn execute(&self) -> Result<BenchmarkReport, BenchmarkError> {
+        let started = Instant::now();
+        let deadline = started + MAX_BENCHMARK_DURATION;
+        let (target, temporary) = match &self.device {
+            Some(device) => (device.clone(), false),
+            None => (simulated_target(), true),
+        };
+        let open_error = |source| BenchmarkError::Open {
+            path: target.clone(),
+            source,
+        };
+        if temporary {
+            fs::write(&target, []).map_err(open_error)?;
+        }
+        platform::open_nonblocking_write(&target).map_err(open_error)?;
+        info!("Benchmarking report writes to {}", target.display());
+
+        let report = HidReport::default().to_bytes();
+        let write_deadline = deadline.min(started + WRITE_PHASE_BUDGET);
+        let report_writes = benchmark_writes(&target, &report, write_deadline);
+        let (report_loop, path_generation) =
+            self.benchmark_loop_under_load(&target, &report, deadline);
+
+        if temporary && let Err(e) = fs::remove_file(&target) {
+            warn!("Failed to remove {}: {}", target.display(), e);
+        }
+
+        let suggested_timing = suggest_timing(
+            Duration::from_micros(report_writes.latency.p99_us),
+            Duration::from_micros(report_loop.jitter.p99_us),
+        );
+        let result = BenchmarkReport {
+            measured_at: Utc::now().to_rfc3339(),
+            target: target.display().to_string(),
+            simulated: temporary,
+            duration_ms: started.elapsed().as_millis() as u64,
+            truncated: report_writes.attempted < REPORT_COUNT || report_loop.ticks < MIN_LOOP_TICKS,
+            report_writes,
+            report_loop,
+            path_generation,
+            suggested_timing,
+        };
+        self.save(&result)?;
+        info!(
+            "Benchmark finished in {}ms: write p99 {}us, loop jitter p99 {}us, {:.0} path dots/s",
+            result.duration_ms,
+            result.report_writes.latency.p99_us,
+            result.report_loop.jitter.p99_us,
+            result.path_generation.dots_per_second
+        );
+        Ok(result)
+    }
+
+    /// 別スレッドでパスを生成しながら、8ms間隔でレポートを送り続ける
+    fn benchmark_loop_under_load(
+        &self,
+        target: &Path,
+        report: &[u8; 8],
+        deadline: Instant,
+    ) -> (ReportLoopBenchmark, PathGenerationBenchmark) {
+        let two_opt = TwoOptSettings {
+            time_budget: self.two_opt.time_budget.min(PATH_TWO_OPT_BUDGET),
+            ..self.two_opt
+        };
+        let generation = thread::spawn(move || benchmark_path_generation(two_opt));
+
+        let mut intervals = Vec::new();
+        let mut last_tick = Instant::now();
+        loop {
+            let ticks = intervals.len() as u32;
+            let loaded = !generation.is_finished();
+            if Instant::now() >= deadline || (!loaded && ticks >= MIN_LOOP_TICKS) {
+                break;
+            }
+            let _ = write_report(target, report);
+            thread::sleep(REPORT_INTERVAL);
+            let now = Instant::now();
+            intervals.push(now - last_tick);
+            last_tick = now;
+        }
+        let path_generation = generation
+            .join()
+            .expect("path generation benchmark panicked");
+
+        let mean = intervals
+            .iter()
+            .sum::<Duration>()
+            .checked_div(intervals.len() as u32)
+            .unwrap_or_default();
+        let jitter = intervals
+            .iter()
+            .map(|interval| interval.abs_diff(mean))
+            .collect();
+        let report_loop = ReportLoopBenchmark {
+            interval_ms: REPORT_INTERVAL.as_millis() as u64,
+            ticks: intervals.len() as u32,
+            mean_interval_us: mean.as_micros() as u64,
+            jitter: LatencyPercentiles::of(jitter),
+        };
+        (report_loop, path_generation)
+    }
+
+    fn save(&self, result: &BenchmarkReport) -> Result<(), BenchmarkError> {
+        let path = self.result_path();
+        let save_error = |source| BenchmarkError::Save {
+            path: path.clone(),
+            source,
+        };
+        create_data_dir(&self.data_dir).map_err(save_error)?;
+        let json = serde_json::to_string_pretty(result)
+            .map_err(io::Error::from)
+            .map_err(save_error)?;
+        fs::write(&path, json).map_err(save_error)
+    }
+}
+
+/// シミュレーションでレポートを書き込むファイル（tmpfsの `/dev/shm` が無ければ一時ディレクトリ）
+fn simulated_target() -> PathBuf {
+    let shm = Path::new("/dev/shm");
+    let dir = if shm.is_dir() {
+        shm.to_path_buf()
+    } else {
+        std::env::temp_dir()
+    };
+    dir.join(format!(
+        "splatoon3-ghost-drawer-benchmark-{}.hid",
+        std::process::id()
+    ))
+}
+
+/// レポートを1件書き込み、掛かった時間を返す（`WRITE_TIMEOUT` までに受け取られなければ `None`）
+///
+/// コントローラーと同じく、書き込みのたびにノンブロッキングでデバイスを開く。
+fn write_report(target: &Path, report: &[u8; 8]) -> Option<Duration> {
+    let started = Instant::now();
+    let mut file = platform::open_nonblocking_write(target).ok()?;
+    loop {
+        match file.write_all(report) {
+            Ok(()) => return Some(started.elapsed()),
+            Err(e)
+                if e.kind() == io::ErrorKind::WouldBlock && started.elapsed() < WRITE_TIMEOUT =>
+            {
+                thread::sleep(WRITE_RETRY_INTERVAL);
+            }
+            Err(_) => return None,
+        }
+    }
+}
+
+/// `REPORT_COUNT` 件のレポートを間を空けずに書き込む
+fn benchmark_writes(target: &Path, report: &[u8; 8], deadline: Instant) -> ReportWriteBenchmark {
+    let started = Instant::now();
+    let mut latencies = Vec::with_capacity(REPORT_COUNT as usize);
+    let mut attempted = 0;
+    while attempted < REPORT_COUNT && Instant::now() < deadline {
+        attempted += 1;
+        if let Some(latency) = write_report(target, report) {
+            latencies.push(latency);
+        }
+    }
+    let elapsed = started.elapsed();
+    let written = latencies.len() as u32;
+    ReportWriteBenchmark {
+        attempted,
+        written,
+        failed: attempted - written,
+        elapsed_ms: elapsed.as_millis() as u64,
+        reports_per_second: written as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+        latency: LatencyPercentiles::of(latencies),
+    }
+}
+
+/// ドットが散らばった合成キャンバス（毎回同じ配置）
+fn synthetic_canvas() -> Canvas {
+    let (width, height) = PATH_CANVAS;
+    let cells = width as usize * height as usize;
+    let mut canvas = Canvas::new(width, height);
+    // セル数と互いに素な歩幅で巡り、重複せずに全体へ散らす
+    let step = 7919;
+    for i in 0..PATH_DOTS {
+        let cell = i * step % cells;
+        let coordinates = Coordinates::new(
+            (cell % width as usize) as u16,
+            (cell / width as usize) as u16,
+        );
+        canvas
+            .set_dot(coordinates, Dot::black())
+            .expect("synthetic dots are inside the canvas");
+    }
+    canvas
+}
+
+fn benchmark_path_generation(two_opt: TwoOptSettings) -> PathGenerationBenchmark {
+    let canvas = synthetic_canvas();
+    let strategy = DrawingStrategy::GreedyTwoOpt;
+    let converter = ArtworkToCommandConverter::new(DrawingCanvasConfig::default(), strategy)
+        .with_two_opt_settings(two_opt);
+    let started = Instant::now();
+    let path = converter.create_drawing_path(&canvas);
+    let elapsed = started.elapsed();
+    PathGenerationBenchmark {
+        dots: path.coordinates.len(),
+        strategy,
+        elapsed_ms: elapsed.as_millis() as u64,
+        dots_per_second: path.coordinates.len() as f64 / elapsed.as_secs_f64().max(f64::EPSILON),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_and_suggested_timing() {
+        let samples = (1..=100).map(Duration::from_micros).collect();
+        let percentiles = LatencyPercentiles::of(samples);
+        assert_eq!(
+            percentiles,
+            LatencyPercentiles {
+                p50_us: 50,
+                p90_us: 90,
+                p99_us: 99,
+                max_us: 100,
+            }
+        );
+        assert_eq!(
+            LatencyPercentiles::of(Vec::new()),
+            LatencyPercentiles::default()
+        );
+
+        // 16ms + 2.5ms + 0.6ms を切り上げる
+        let timing = suggest_timing(Duration::from_micros(2500), Duration::from_micros(600));
+        assert_eq!(timing, PaintTiming::new(20, 20, 4));
+    }
+
+    #[test]
+    fn test_synthetic_canvas_has_distinct_dots() {
+        let canvas = synthetic_canvas();
+        assert_eq!(canvas.dots.len(), PATH_DOTS);
+        assert!(canvas.dots.values().all(|dot| dot.is_drawable()));
+    }
+
+    #[test]
+    fn test_simulated_benchmark_is_saved_and_capped() {
+        let dir = std::env::temp_dir().join(format!("benchmark-{}", uuid::Uuid::new_v4()));
+        let use_case = BenchmarkUseCase::new(&dir)
+            .simulated()
+            .with_two_opt_settings(TwoOptSettings::default().with_time_budget_ms(100));
+        assert_eq!(use_case.latest().unwrap(), None);
+
+        let report = use_case.execute().unwrap();
+
+        assert!(report.simulated);
+        assert!(report.duration_ms <= MAX_BENCHMARK_DURATION.as_millis() as u64 + 1000);
+        assert_eq!(report.report_writes.attempted, REPORT_COUNT);
+        assert_eq!(report.report_writes.written, REPORT_COUNT);
+        assert!(report.report_loop.ticks >= MIN_LOOP_TICKS);
+        assert!(report.report_loop.mean_interval_us >= 8000);
+        assert_eq!(report.path_generation.dots, PATH_DOTS);
+        assert!(report.suggested_timing.press_ms >= 16);
+        assert!(
+            !Path::new(&report.target).exists(),
+            "temporary file must be removed"
+        );
+        assert_eq!(use_case.latest().unwrap(), Some(report));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,595 @@
+//! 速度キャリブレーションと移動テスト
+//!
+//! 描画と同じタップ操作でパターンを描き、Switchが取りこぼさずに受け付ける速度を確かめる。
+
+use super::run_painting::run_init_sequence;
+use crate::application::controller_io::{
+    debug_assert_blocking_allowed, tap_button_with_duration, tap_dpad_with_duration,
+};
+use crate::domain::controller::{Button, ControllerEmulator, DPad};
+use crate::domain::hardware::errors::HardwareError;
+use crate::domain::painting::{CalibrationPlan, DrawingMode, InitSequence};
+use crate::domain::shared::value_objects::Coordinates;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info};
+
+/// キャリブレーションで使うSwitchキャンバスの大きさ（ピクセル）
+pub const CALIBRATION_CANVAS_WIDTH: u16 = 320;
+pub const CALIBRATION_CANVAS_HEIGHT: u16 = 180;
+
+/// キャリブレーションで描いたドットと、カーソルの位置の記録
+///
+/// 後片付け（`perform_calibration_cleanup`）は描画パターンを計算し直さず、この記録だけを辿る。
+/// カーソルの位置が分からなくなった場合（初期化の途中で止めた、他の操作でカーソルが動いたなど）は
+/// `cursor()` が `None` になり、後片付けできない。
+#[derive(Debug, Default)]
+pub struct CalibrationTrace {
+    state: Mutex<CalibrationTraceState>,
+}
+
+#[derive(Debug, Default)]
+struct CalibrationTraceState {
+    cursor: Option<Coordinates>,
+    drawn: Vec<Coordinates>,
+}
+
+impl CalibrationTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CalibrationTraceState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 現在のカーソル位置（分からなければ `None`）
+    pub fn cursor(&self) -> Option<Coordinates> {
+        self.lock().cursor
+    }
+
+    /// まだ消していない描いたドット（描いた順）
+    pub fn drawn_dots(&self) -> Vec<Coordinates> {
+        self.lock().drawn.clone()
+    }
+
+    /// カーソルが記録にない操作で動いたため、位置を分からないものとして扱う
+    pub fn forget_cursor(&self) {
+        self.lock().cursor = None;
+    }
+
+    fn set_cursor(&self, cursor: Coordinates) {
+        self.lock().cursor = Some(cursor);
+    }
+
+    /// 1マス移動した後のカーソル位置
+    fn step(&self, step: DPad) {
+        let mut state = self.lock();
+        if let Some(cursor) = state.cursor {
+            let (dx, dy) = step.offset();
+            state.cursor = cursor.move_by(dx, dy).or(Some(cursor));
+        }
+    }
+
+    fn record_dot(&self, dot: Coordinates) {
+        self.lock().drawn.push(dot);
+    }
+
+    fn last_dot(&self) -> Option<Coordinates> {
+        self.lock().drawn.last().copied()
+    }
+
+    fn erase_last_dot(&self) {
+        self.lock().drawn.pop();
+    }
+}
+
+/// 速度キャリブレーションテスト
+/// 指定された速度パラメータでキャリブレーションパターンを描画
+/// ドットが乱れたらその速度はSwitchの限界を超えている
+#[allow(clippy::too_many_arguments)]
+pub fn perform_speed_calibration(
+    controller: Arc<dyn ControllerEmulator>,
+    stop_signal: Arc<AtomicBool>,
+    press_ms: u32,
+    release_ms: u32,
+    wait_ms: u32,
+    init_sequence: Option<&InitSequence>,
+    plan: &CalibrationPlan,
+    trace: &CalibrationTrace,
+) -> Result<(), HardwareError> {
+    debug_assert_blocking_allowed();
+    let total_ms = press_ms + release_ms + wait_ms;
+    info!(
+        "Starting speed calibration test ({}ms/pixel: press={}ms, release={}ms, wait={}ms, skip_init={}, dots={})...",
+        total_ms,
+        press_ms,
+        release_ms,
+        wait_ms,
+        init_sequence.is_none(),
+        plan.dots.len()
+    );
+
+    // Initialize controller
+    controller.initialize()?;
+
+    if let Some(init_sequence) = init_sequence {
+        // 描画と同じ初期化手順でペンサイズを合わせ、左上に移動する
+        if !run_init_sequence(&controller, init_sequence, &stop_signal, |_| {})? {
+            return Ok(());
+        }
+        trace.set_cursor(Coordinates::origin());
+
+        // パターンの左上に移動（D-padで確実に移動）
+        // 既定の5行×20ドットはキャンバス中央の (150, 85) から描画する
+        info!("Moving to calibration origin {}...", plan.origin);
+
+        // 右に移動（速めのパラメータで高速化）
+        for _ in 0..plan.origin.x {
+            if stop_signal.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            tap_dpad_with_duration(&controller, DPad::RIGHT, "Move Right", 30, 15, 5)?;
+            trace.step(DPad::RIGHT);
+        }
+
+        // 下に移動
+        for _ in 0..plan.origin.y {
+            if stop_signal.load(Ordering::SeqCst) {
+                return Ok(());
+            }
+            tap_dpad_with_duration(&controller, DPad::DOWN, "Move Down", 30, 15, 5)?;
+            trace.step(DPad::DOWN);
+        }
+
+        info!("Calibration test position reached: {}", plan.origin);
+        std::thread::sleep(std::time::Duration::from_millis(500));
+    } else {
+        info!("Skipping initialization (pen size, home position, origin position)");
+        // 現在のカーソル位置をパターンの左上とみなす
+        trace.set_cursor(plan.origin);
+        std::thread::sleep(std::time::Duration::from_millis(200));
+    }
+
+    // 初期化完了後、確実にNEUTRAL状態にリセット
+    tap_dpad_with_duration(
+        &controller,
+        DPad::NEUTRAL,
+        "Reset after initialization",
+        50,
+        50,
+        0,
+    )?;
+    std::thread::sleep(std::time::Duration::from_millis(100));
+
+    // パターンのドットを描画順に辿る（移動はユーザー指定のパラメータを使用）
+    let mut cursor = plan.origin;
+    for (index, dot) in plan.dots.iter().enumerate() {
+        if stop_signal.load(Ordering::SeqCst) {
+            info!("Calibration stopped by user");
+            // 停止時も必ずNEUTRAL状態にリセット
+            tap_dpad_with_duration(
+                &controller,
+                DPad::NEUTRAL,
+                "Final Reset on Stop",
+                100,
+                100,
+                0,
+            )?;
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            return Ok(());
+        }
+
+        for step in cursor.steps_to(dot, false) {
+            tap_dpad_with_duration(
+                &controller,
+                step,
+                "Move",
+                press_ms,
+                release_ms,
+                wait_ms as u64,
+            )?;
+            trace.step(step);
+        }
+        cursor = *dot;
+
+        // D-pad状態を完全にクリア（描画前）
+        tap_dpad_with_duration(
+            &controller,
+            DPad::NEUTRAL,
+            "Clear DPad Before Paint",
+            10,
+            10,
+            0,
+        )?;
+
+        // ドットを打つ（送信が途中で失敗しても描けている場合があるので、押す前に記録する）
+        trace.record_dot(*dot);
+        tap_button_with_duration(
+            &controller,
+            Button::A,
+            "Paint Dot",
+            press_ms,
+            release_ms,
+            wait_ms as u64,
+        )?;
+
+        // D-pad状態を完全にクリア（移動前）
+        tap_dpad_with_duration(
+            &controller,
+            DPad::NEUTRAL,
+            "Clear DPad Before Move",
+            10,
+            10,
+            0,
+        )?;
+
+        debug!(
+            "Calibration dot {}/{} painted at {}",
+            index + 1,
+            plan.dots.len(),
+            dot
+        );
+    }
+
+    // テスト完了後、確実にNEUTRAL状態にリセット
+    tap_dpad_with_duration(&controller, DPad::NEUTRAL, "Final Reset", 100, 100, 0)?;
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    info!("Speed calibration test completed!");
+    info!("Check the screen: If dots are aligned correctly, this speed is safe.");
+    Ok(())
+}
+
+/// キャリブレーションで描いたドットを、描いた順と逆に辿って消しゴム（Bボタン）で消す
+///
+/// 消せたドットから記録を取り除くため、途中で停止しても続きから消せる。消したドットの数を返す。
+pub fn perform_calibration_cleanup(
+    controller: Arc<dyn ControllerEmulator>,
+    stop_signal: Arc<AtomicBool>,
+    trace: &CalibrationTrace,
+    press_ms: u32,
+    release_ms: u32,
+    wait_ms: u32,
+) -> Result<usize, HardwareError> {
+    debug_assert_blocking_allowed();
+    let Some(mut cursor) = trace.cursor() else {
+        return Err(HardwareError::InvalidParameter(
+            "The cursor position after the calibration is unknown".to_string(),
+        ));
+    };
+    let erase_button = DrawingMode::Eraser.dot_button();
+    info!(
+        "Erasing {} calibration dots from {}...",
+        trace.drawn_dots().len(),
+        cursor
+    );
+
+    let mut erased = 0;
+    while let Some(dot) = trace.last_dot() {
+        if stop_signal.load(Ordering::SeqCst) {
+            info!("Calibration cleanup stopped by user");
+            break;
+        }
+        for step in cursor.steps_to(&dot, false) {
+            tap_dpad_with_duration(
+                &controller,
+                step,
+                "Move",
+                press_ms,
+                release_ms,
+                wait_ms as u64,
+            )?;
+            trace.step(step);
+        }
+        cursor = dot;
+
+        tap_dpad_with_duration(
+            &controller,
+            DPad::NEUTRAL,
+            "Clear DPad Before Erase",
+            10,
+            10,
+            0,
+        )?;
+        tap_button_with_duration(
+            &controller,
+            erase_button,
+            "Erase Dot",
+            press_ms,
+            release_ms,
+            wait_ms as u64,
+        )?;
+        trace.erase_last_dot();
+        erased += 1;
+        tap_dpad_with_duration(
+            &controller,
+            DPad::NEUTRAL,
+            "Clear DPad Before Move",
+            10,
+            10,
+            0,
+        )?;
+    }
+
+    tap_dpad_with_duration(&controller, DPad::NEUTRAL, "Final Reset", 100, 100, 0)?;
+    info!("Erased {} calibration dots", erased);
+    Ok(erased)
+}
+
+/// 移動テストの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveTestKind {
+    /// 描画移動テスト（ドットを打ってから右移動）
+    PaintMove,
+    /// 空白移動テスト（Aボタンなしで右移動）
+    GapMove,
+}
+
+impl MoveTestKind {
+    /// ログに使う名前
+    pub fn name(self) -> &'static str {
+        match self {
+            MoveTestKind::PaintMove => "Paint move",
+            MoveTestKind::GapMove => "Gap move",
+        }
+    }
+
+    /// テスト中に送る入力の説明
+    fn inputs(self) -> &'static str {
+        match self {
+            MoveTestKind::PaintMove => "A button + RIGHT",
+            MoveTestKind::GapMove => "RIGHT only, no A button",
+        }
+    }
+}
+
+/// 移動テストで右に移動する回数
+pub const MOVE_TEST_STEPS: u32 = 10;
+
+/// 移動テスト（右に10回移動し、描画移動テストでは移動の前にドットを打つ）
+pub fn perform_move_test(
+    controller: Arc<dyn ControllerEmulator>,
+    stop_signal: Arc<AtomicBool>,
+    kind: MoveTestKind,
+    press_ms: u32,
+    release_ms: u32,
+    wait_ms: u32,
+) -> Result<(), HardwareError> {
+    debug_assert_blocking_allowed();
+    info!(
+        "Starting {} test ({})",
+        kind.name().to_lowercase(),
+        kind.inputs()
+    );
+
+    for i in 0..MOVE_TEST_STEPS {
+        if stop_signal.load(Ordering::SeqCst) {
+            // 停止時も必ずNEUTRAL状態にリセット
+            tap_dpad_with_duration(
+                &controller,
+                DPad::NEUTRAL,
+                "Final Reset on Stop",
+                100,
+                100,
+                0,
+            )?;
+            std::thread::sleep(std::time::Duration::from_millis(200));
+            return Ok(());
+        }
+
+        info!("{} {}/{}", kind.name(), i + 1, MOVE_TEST_STEPS);
+
+        // D-pad状態をクリア
+        tap_dpad_with_duration(&controller, DPad::NEUTRAL, "Clear DPad", 10, 10, 0)?;
+
+        if kind == MoveTestKind::PaintMove {
+            // ドットを打つ
+            tap_button_with_duration(
+                &controller,
+                Button::A,
+                "Paint Dot",
+                press_ms,
+                release_ms,
+                wait_ms as u64,
+            )?;
+
+            // D-pad状態をクリア
+            tap_dpad_with_duration(&controller, DPad::NEUTRAL, "Clear DPad", 10, 10, 0)?;
+        }
+
+        // 右に移動
+        tap_dpad_with_duration(
+            &controller,
+            DPad::RIGHT,
+            "Move Right",
+            press_ms,
+            release_ms,
+            wait_ms as u64,
+        )?;
+    }
+
+    // テスト完了後、確実にNEUTRAL状態にリセット
+    tap_dpad_with_duration(&controller, DPad::NEUTRAL, "Final Reset", 100, 100, 0)?;
+    std::thread::sleep(std::time::Duration::from_millis(200));
+
+    info!("{} test completed", kind.name());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::painting::{CalibrationLayout, CalibrationPattern, calibration_plan};
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    #[test]
+    fn test_speed_calibration_paints_plan_dots() {
+        let layout = CalibrationLayout {
+            pattern: CalibrationPattern::Vertical,
+            rows: 3,
+            width: 6,
+            ..Default::default()
+        };
+        let plan = calibration_plan(&layout, 320, 180).unwrap();
+        let moves: u64 = std::iter::once(plan.origin)
+            .chain(plan.dots.iter().copied())
+            .collect::<Vec<_>>()
+            .windows(2)
+            .map(|pair| pair[0].steps_to(&pair[1], false).count() as u64)
+            .sum();
+
+        let mock = Arc::new(MockController::new().without_delays());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        perform_speed_calibration(
+            controller,
+            Arc::new(AtomicBool::new(false)),
+            1,
+            1,
+            0,
+            None,
+            &plan,
+            &CalibrationTrace::new(),
+        )
+        .unwrap();
+
+        let recorded = mock.recorded_operations();
+        assert_eq!(recorded.a_presses, plan.dots.len() as u64);
+        assert_eq!(recorded.dpad_ops, moves);
+    }
+
+    #[test]
+    fn test_cleanup_erases_recorded_dots_in_reverse() {
+        use crate::domain::controller::ActionType;
+
+        let layout = CalibrationLayout {
+            pattern: CalibrationPattern::Horizontal,
+            rows: 2,
+            width: 4,
+            ..Default::default()
+        };
+        let plan = calibration_plan(&layout, 320, 180).unwrap();
+        let mock = Arc::new(MockController::new().without_delays().with_command_log());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let trace = CalibrationTrace::new();
+        perform_speed_calibration(
+            controller.clone(),
+            Arc::new(AtomicBool::new(false)),
+            1,
+            1,
+            0,
+            None,
+            &plan,
+            &trace,
+        )
+        .unwrap();
+        assert_eq!(trace.drawn_dots(), plan.dots);
+        assert_eq!(trace.cursor(), plan.dots.last().copied());
+        let calibration_commands = mock.recorded_commands().len();
+
+        let erased = perform_calibration_cleanup(
+            controller.clone(),
+            Arc::new(AtomicBool::new(false)),
+            &trace,
+            1,
+            1,
+            0,
+        )
+        .unwrap();
+        assert_eq!(erased, plan.dots.len());
+        assert!(trace.drawn_dots().is_empty());
+
+        // 最後に描いたドットから順にBで消し、Aは押さない
+        let actions: Vec<ActionType> = mock
+            .recorded_commands()
+            .into_iter()
+            .skip(calibration_commands)
+            .flat_map(|command| command.sequence)
+            .map(|action| action.action_type)
+            .collect();
+        let mut cursor = *plan.dots.last().unwrap();
+        let mut erased_at = Vec::new();
+        for action in &actions {
+            match action {
+                ActionType::SetDPad(dpad) if *dpad != DPad::NEUTRAL => {
+                    let (dx, dy) = dpad.offset();
+                    cursor = cursor.move_by(dx, dy).unwrap();
+                }
+                ActionType::PressButton(Button::B) => erased_at.push(cursor),
+                ActionType::PressButton(button) => panic!("unexpected {button:?}"),
+                _ => {}
+            }
+        }
+        let mut expected = plan.dots.clone();
+        expected.reverse();
+        assert_eq!(erased_at, expected);
+    }
+
+    #[test]
+    fn test_cleanup_refuses_unknown_cursor() {
+        let plan = calibration_plan(&CalibrationLayout::default(), 320, 180).unwrap();
+        let mock = Arc::new(MockController::new().without_delays());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let trace = CalibrationTrace::new();
+        // 初期化の前に止めたため、カーソルの位置が分からない
+        perform_speed_calibration(
+            controller.clone(),
+            Arc::new(AtomicBool::new(true)),
+            1,
+            1,
+            0,
+            Some(&InitSequence::default()),
+            &plan,
+            &trace,
+        )
+        .unwrap();
+        assert_eq!(trace.cursor(), None);
+        assert!(matches!(
+            perform_calibration_cleanup(
+                controller,
+                Arc::new(AtomicBool::new(false)),
+                &trace,
+                1,
+                1,
+                0
+            ),
+            Err(HardwareError::InvalidParameter(_))
+        ));
+        assert_eq!(mock.recorded_operations().a_presses, 0);
+    }
+
+    #[test]
+    fn test_move_tests_differ_only_in_painting_dots() {
+        for (kind, a_presses) in [
+            (MoveTestKind::PaintMove, MOVE_TEST_STEPS as u64),
+            (MoveTestKind::GapMove, 0),
+        ] {
+            let mock = Arc::new(MockController::new().without_delays());
+            let controller: Arc<dyn ControllerEmulator> = mock.clone();
+            perform_move_test(controller, Arc::new(AtomicBool::new(false)), kind, 1, 1, 0).unwrap();
+
+            let recorded = mock.recorded_operations();
+            assert_eq!(recorded.a_presses, a_presses, "{kind:?}");
+            assert_eq!(recorded.dpad_ops, MOVE_TEST_STEPS as u64, "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_stopped_move_test_only_resets_controller() {
+        let mock = Arc::new(MockController::new().without_delays());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        perform_move_test(
+            controller,
+            Arc::new(AtomicBool::new(true)),
+            MoveTestKind::PaintMove,
+            1,
+            1,
+            0,
+        )
+        .unwrap();
+
+        let recorded = mock.recorded_operations();
+        assert_eq!(recorded.a_presses, 0);
+        assert_eq!(recorded.dpad_ops, 0);
+    }
+}
@@ -1,15 +1,29 @@
-use crate::domain::setup::repositories::SetupError;
+use crate::domain::setup::entities::{AuditInitiator, GadgetAuditEntry, GadgetOperation};
+use crate::domain::setup::repositories::{GadgetAuditLog, SetupError};
 use std::fs;
 use std::path::Path;
+use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
 
 /// USB Gadgetの設定をクリーンアップするユースケース
-pub struct CleanupGadgetUseCase;
+pub struct CleanupGadgetUseCase {
+    audit: Option<(Arc<dyn GadgetAuditLog>, AuditInitiator)>,
+}
 
 impl CleanupGadgetUseCase {
     pub fn new() -> Self {
-        Self
+        Self { audit: None }
+    }
+
+    /// クリーンアップの結果を監査ログに記録する
+    pub fn with_audit_log(
+        mut self,
+        audit_log: Arc<dyn GadgetAuditLog>,
+        initiator: AuditInitiator,
+    ) -> Self {
+        self.audit = Some((audit_log, initiator));
+        self
     }
 }
 
@@ -21,6 +35,18 @@ impl Default for CleanupGadgetUseCase {
 
 impl CleanupGadgetUseCase {
     pub fn execute(&self) -> Result<(), SetupError> {
+        let result = self.cleanup();
+        if let Some((audit_log, initiator)) = &self.audit {
+            audit_log.record(GadgetAuditEntry::new(
+                GadgetOperation::Cleanup,
+                *initiator,
+                &result,
+            ));
+        }
+        result
+    }
+
+    fn cleanup(&self) -> Result<(), SetupError> {
         println!("🧹 Cleaning up USB Gadget configuration...");
         println!("=====================================\n");
 
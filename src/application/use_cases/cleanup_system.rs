@@ -65,7 +65,7 @@ impl CleanupSystemUseCase {
 }
 
 fn is_running_as_root() -> bool {
-    unsafe { libc::geteuid() == 0 }
+    crate::infrastructure::platform::is_root()
 }
 
 fn cleanup_usb_gadget() -> Result<(), SetupError> {
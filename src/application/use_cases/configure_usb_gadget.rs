@@ -1,7 +1,8 @@
 use crate::domain::hardware::repositories::UsbGadgetManager;
+use crate::domain::hardware::verification::GadgetVerification;
 use crate::domain::setup::repositories::SetupError;
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
 pub struct ConfigureUsbGadgetUseCase {
     usb_gadget_manager: Arc<dyn UsbGadgetManager>,
@@ -12,7 +13,8 @@ impl ConfigureUsbGadgetUseCase {
         Self { usb_gadget_manager }
     }
 
-    pub fn execute(&self) -> Result<(), SetupError> {
+    /// ガジェットを構成し、カーネルに反映された内容の検証結果を返す
+    pub fn execute(&self) -> Result<GadgetVerification, SetupError> {
         info!("Configuring USB Gadget as Nintendo Switch Pro Controller...");
 
         // Check if running as root
@@ -25,17 +27,21 @@ impl ConfigureUsbGadgetUseCase {
         // Check if already configured
         if self.usb_gadget_manager.is_gadget_configured()? {
             info!("USB Gadget is already configured.");
-            return Ok(());
+        } else {
+            // Configure USB Gadget
+            self.usb_gadget_manager.configure_as_pro_controller()?;
+            info!("USB Gadget configured successfully!");
         }
 
-        // Configure USB Gadget
-        self.usb_gadget_manager.configure_as_pro_controller()?;
-
-        info!("USB Gadget configured successfully!");
-        Ok(())
+        // Verify what the kernel actually accepted
+        let verification = self.usb_gadget_manager.verify_gadget()?;
+        if !verification.is_ok() {
+            warn!("USB Gadget verification found problems");
+        }
+        Ok(verification)
     }
 }
 
 fn is_running_as_root() -> bool {
-    unsafe { libc::geteuid() == 0 }
+    crate::infrastructure::platform::is_root()
 }
@@ -1,70 +1,238 @@
 use crate::domain::hardware::errors::HardwareError;
+use crate::domain::hardware::verification::{CheckStatus, GadgetCheck};
+use crate::domain::setup::repositories::BoardDetector;
+use crate::infrastructure::hardware::linux_usb_gadget_manager::{
+    GADGET_CONFIG_FILE, HID_DEVICE_PATH, PRO_CONTROLLER_REPORT_DESCRIPTOR, REPORT_LENGTH,
+    read_pinned_udc,
+};
+use crate::infrastructure::hardware::orange_pi_udc::OrangePiUdcPreparer;
+use crate::infrastructure::platform;
+use crate::infrastructure::setup::{
+    GADGET_SERVICE_FILE, GADGET_UNIT_ORDERING, WEB_SERVICE_FILE, WEB_UNIT_ORDERING,
+    missing_unit_directives,
+};
+use serde::Serialize;
 use std::fs;
 use std::io::Write;
 use std::path::Path;
 use std::process::Command;
+use std::sync::Arc;
+
+/// `diagnose --json` の出力形式の版（項目の名前や意味を変えたら上げる）
+pub const DIAGNOSTIC_REPORT_SCHEMA_VERSION: u32 = 1;
+
+/// 失敗があると描画できない項目（他の項目はボードによっては当てはまらないため判定に使わない）
+const REQUIRED_SECTIONS: [&str; 3] = ["udc", "gadget_configuration", "hid_devices"];
+
+/// 診断項目の判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiagnosticStatus {
+    Ok,
+    Warning,
+    Failed,
+    /// 判定を伴わない情報（カーネルのバージョンなど）
+    Info,
+}
 
-/// 接続問題を診断するユースケース
-pub struct DiagnoseConnectionUseCase;
+impl DiagnosticStatus {
+    fn from_bool(ok: bool) -> Self {
+        if ok {
+            DiagnosticStatus::Ok
+        } else {
+            DiagnosticStatus::Failed
+        }
+    }
 
-impl DiagnoseConnectionUseCase {
-    pub fn new() -> Self {
-        Self
+    fn icon(&self) -> Option<&'static str> {
+        match self {
+            DiagnosticStatus::Ok => Some("✅"),
+            DiagnosticStatus::Warning => Some("⚠️"),
+            DiagnosticStatus::Failed => Some("❌"),
+            DiagnosticStatus::Info => None,
+        }
     }
 }
 
-impl Default for DiagnoseConnectionUseCase {
-    fn default() -> Self {
-        Self::new()
+impl From<CheckStatus> for DiagnosticStatus {
+    fn from(status: CheckStatus) -> Self {
+        match status {
+            CheckStatus::Passed => DiagnosticStatus::Ok,
+            CheckStatus::Warning => DiagnosticStatus::Warning,
+            CheckStatus::Failed => DiagnosticStatus::Failed,
+        }
     }
 }
 
-impl DiagnoseConnectionUseCase {
-    pub fn execute(&self) -> Result<(), HardwareError> {
-        println!("🔍 Connection Diagnostics");
-        println!("=======================\n");
+/// 1つの診断項目
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticCheck {
+    pub name: String,
+    pub status: DiagnosticStatus,
+    pub detail: String,
+    /// 対処方法
+    pub hints: Vec<String>,
+}
 
-        // 1. システム情報の確認
-        self.check_system_info()?;
+impl DiagnosticCheck {
+    fn new(name: impl Into<String>, status: DiagnosticStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            status,
+            detail: detail.into(),
+            hints: Vec::new(),
+        }
+    }
 
-        // 2. ブート設定の確認
-        self.check_boot_configuration()?;
+    fn info(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self::new(name, DiagnosticStatus::Info, detail)
+    }
 
-        // 3. カーネルモジュールの確認
-        self.check_kernel_modules()?;
+    fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hints.push(hint.into());
+        self
+    }
+}
 
-        // 3.5. 競合の確認
-        self.check_g_ether_conflict()?;
+impl From<GadgetCheck> for DiagnosticCheck {
+    fn from(check: GadgetCheck) -> Self {
+        Self::new(check.name, check.status.into(), check.detail)
+    }
+}
 
-        // 4. UDC（USB Device Controller）の確認
-        self.check_udc_status()?;
+/// 診断の区分（表示の見出し1つ分）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticSection {
+    pub id: &'static str,
+    pub title: &'static str,
+    #[serde(skip)]
+    icon: &'static str,
+    pub checks: Vec<DiagnosticCheck>,
+    /// ログなどの参考情報
+    pub messages: Vec<String>,
+}
 
-        // 5. USB Gadgetの設定確認
-        self.check_gadget_configuration()?;
+impl DiagnosticSection {
+    fn new(id: &'static str, icon: &'static str, title: &'static str) -> Self {
+        Self {
+            id,
+            title,
+            icon,
+            checks: Vec::new(),
+            messages: Vec::new(),
+        }
+    }
 
-        // 6. HIDデバイスの確認
-        self.check_hid_devices()?;
+    fn check(&mut self, check: DiagnosticCheck) {
+        self.checks.push(check);
+    }
 
-        // 7. USB OTGモードの確認
-        self.check_otg_mode()?;
+    fn has_failures(&self) -> bool {
+        self.checks
+            .iter()
+            .any(|check| check.status == DiagnosticStatus::Failed)
+    }
+}
 
-        // 8. サービス状態の確認
-        self.check_service_status()?;
+/// 接続診断の結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct DiagnosticReport {
+    pub schema_version: u32,
+    /// UDC・ガジェット・HIDデバイスの項目に失敗が無いか
+    pub healthy: bool,
+    pub sections: Vec<DiagnosticSection>,
+    pub recommendations: Vec<String>,
+}
 
-        // 9. USB接続の確認
-        self.check_usb_connection()?;
+impl DiagnosticReport {
+    fn new(sections: Vec<DiagnosticSection>) -> Self {
+        let healthy = sections
+            .iter()
+            .filter(|section| REQUIRED_SECTIONS.contains(&section.id))
+            .all(|section| !section.has_failures());
+        Self {
+            schema_version: DIAGNOSTIC_REPORT_SCHEMA_VERSION,
+            healthy,
+            sections,
+            recommendations: recommendations(),
+        }
+    }
 
-        // 5. dmesgログの確認
-        self.check_dmesg_logs()?;
+    /// 見出しと絵文字付きで表示する
+    pub fn print(&self) {
+        println!("🔍 Connection Diagnostics");
+        println!("=======================\n");
 
-        // 6. 推奨される対処法
-        self.show_recommendations();
+        for section in &self.sections {
+            println!("{} {}:", section.icon, section.title);
+            for check in &section.checks {
+                match check.status.icon() {
+                    Some(icon) => println!("   {}: {icon} {}", check.name, check.detail),
+                    None => println!("   {}: {}", check.name, check.detail),
+                }
+                for hint in &check.hints {
+                    println!("      {hint}");
+                }
+            }
+            for message in &section.messages {
+                println!("   - {message}");
+            }
+            println!();
+        }
 
+        println!("💡 Recommendations:");
+        for recommendation in &self.recommendations {
+            println!("   {recommendation}");
+        }
+    }
+}
+
+/// 接続問題を診断するユースケース
+pub struct DiagnoseConnectionUseCase {
+    board_detector: Arc<dyn BoardDetector>,
+}
+
+impl DiagnoseConnectionUseCase {
+    pub fn new(board_detector: Arc<dyn BoardDetector>) -> Self {
+        Self { board_detector }
+    }
+}
+
+impl DiagnoseConnectionUseCase {
+    pub fn execute(&self) -> Result<(), HardwareError> {
+        self.collect()?.print();
         Ok(())
     }
 
-    fn check_g_ether_conflict(&self) -> Result<(), HardwareError> {
-        println!("🚫 Checking for conflicts:");
+    pub fn collect(&self) -> Result<DiagnosticReport, HardwareError> {
+        Ok(DiagnosticReport::new(vec![
+            // 1. システム情報の確認
+            self.check_system_info(),
+            // 2. ブート設定の確認
+            self.check_boot_configuration(),
+            // 3. カーネルモジュールの確認
+            self.check_kernel_modules()?,
+            // 3.5. 競合の確認
+            self.check_g_ether_conflict()?,
+            // 4. UDC（USB Device Controller）の確認
+            self.check_udc_status(),
+            // 5. USB Gadgetの設定確認
+            self.check_gadget_configuration(),
+            // 6. HIDデバイスの確認
+            self.check_hid_devices(),
+            // 7. USB OTGモードの確認
+            self.check_otg_mode(),
+            // 8. サービス状態の確認
+            self.check_service_status(),
+            // 9. USB接続の確認
+            self.check_usb_connection(),
+            // 10. dmesgログの確認
+            self.check_dmesg_logs(),
+        ]))
+    }
+
+    fn check_g_ether_conflict(&self) -> Result<DiagnosticSection, HardwareError> {
+        let mut section = DiagnosticSection::new("conflicts", "🚫", "Checking for conflicts");
 
         let output = Command::new("lsmod")
             .output()
@@ -72,20 +240,25 @@ impl DiagnoseConnectionUseCase {
         let lsmod = String::from_utf8_lossy(&output.stdout);
 
         if lsmod.contains("g_ether") {
-            println!("   ❌ g_ether module detected!");
-            println!("      This module conflicts with the Nintendo Switch gadget.");
-            println!("      Please remove 'modules-load=dwc2,g_ether' from /boot/cmdline.txt");
-            println!("      or /boot/firmware/cmdline.txt and reboot.");
+            section.check(
+                DiagnosticCheck::new("g_ether", DiagnosticStatus::Failed, "module detected")
+                    .with_hint("This module conflicts with the Nintendo Switch gadget.")
+                    .with_hint("Please remove 'modules-load=dwc2,g_ether' from /boot/cmdline.txt")
+                    .with_hint("or /boot/firmware/cmdline.txt and reboot."),
+            );
         } else {
-            println!("   ✅ No g_ether conflict detected");
+            section.check(DiagnosticCheck::new(
+                "g_ether",
+                DiagnosticStatus::Ok,
+                "No conflict detected",
+            ));
         }
 
-        println!();
-        Ok(())
+        Ok(section)
     }
 
-    fn check_kernel_modules(&self) -> Result<(), HardwareError> {
-        println!("📦 Kernel Modules:");
+    fn check_kernel_modules(&self) -> Result<DiagnosticSection, HardwareError> {
+        let mut section = DiagnosticSection::new("kernel_modules", "📦", "Kernel Modules");
 
         let required_modules = vec![
             ("libcomposite", "USB Gadget framework"),
@@ -103,151 +276,187 @@ impl DiagnoseConnectionUseCase {
 
         for (module, description) in required_modules {
             let is_loaded = lsmod_text.lines().any(|line| line.starts_with(module));
-            println!(
-                "   {} ({}): {}",
-                module,
-                description,
-                if is_loaded {
-                    "✅ Loaded"
-                } else {
-                    "❌ Not loaded"
-                }
-            );
+            section.check(DiagnosticCheck::new(
+                format!("{module} ({description})"),
+                DiagnosticStatus::from_bool(is_loaded),
+                if is_loaded { "Loaded" } else { "Not loaded" },
+            ));
         }
 
-        println!();
-        Ok(())
+        Ok(section)
     }
 
-    fn check_gadget_configuration(&self) -> Result<(), HardwareError> {
-        println!("🔌 USB Gadget Configuration:");
+    fn check_gadget_configuration(&self) -> DiagnosticSection {
+        let mut section =
+            DiagnosticSection::new("gadget_configuration", "🔌", "USB Gadget Configuration");
 
         let gadget_path = "/sys/kernel/config/usb_gadget/nintendo_controller";
 
         if !Path::new(gadget_path).exists() {
-            println!("   ❌ Gadget not configured");
-            return Ok(());
+            section.check(DiagnosticCheck::new(
+                "Gadget",
+                DiagnosticStatus::Failed,
+                "Not configured",
+            ));
+            return section;
         }
 
-        println!("   ✅ Gadget directory exists");
+        section.check(DiagnosticCheck::new(
+            "Gadget",
+            DiagnosticStatus::Ok,
+            "Directory exists",
+        ));
 
         // UDCの確認
         let udc_path = format!("{gadget_path}/UDC");
         if let Ok(udc) = fs::read_to_string(&udc_path) {
             let udc = udc.trim();
             if udc.is_empty() {
-                println!("   ❌ UDC not bound");
+                section.check(DiagnosticCheck::new(
+                    "UDC",
+                    DiagnosticStatus::Failed,
+                    "Not bound",
+                ));
             } else {
-                println!("   ✅ UDC bound to: {udc}");
+                section.check(DiagnosticCheck::new(
+                    "UDC",
+                    DiagnosticStatus::Ok,
+                    format!("Bound to {udc}"),
+                ));
 
                 // UDCの詳細情報
                 let udc_state_path = format!("/sys/class/udc/{udc}/state");
                 if let Ok(state) = fs::read_to_string(&udc_state_path) {
-                    println!("   📊 UDC state: {}", state.trim());
+                    section.check(DiagnosticCheck::info("UDC state", state.trim()));
                 }
             }
         } else {
-            println!("   ❌ Cannot read UDC status");
+            section.check(DiagnosticCheck::new(
+                "UDC",
+                DiagnosticStatus::Failed,
+                "Cannot read UDC status",
+            ));
         }
 
         // HID functionの確認
         let hid_path = format!("{gadget_path}/functions/hid.usb0");
-        if Path::new(&hid_path).exists() {
-            println!("   ✅ HID function configured");
+        if !Path::new(&hid_path).exists() {
+            section.check(DiagnosticCheck::new(
+                "HID function",
+                DiagnosticStatus::Failed,
+                "Not configured",
+            ));
+            return section;
+        }
 
-            if let Ok(report_length) = fs::read_to_string(format!("{hid_path}/report_length")) {
-                println!("   📏 Report length: {} bytes", report_length.trim());
-            }
-        } else {
-            println!("   ❌ HID function not configured");
+        section.check(DiagnosticCheck::new(
+            "HID function",
+            DiagnosticStatus::Ok,
+            "Configured",
+        ));
+
+        // カーネルに反映された値を書き込んだ内容と照合
+        let report_desc = fs::read(format!("{hid_path}/report_desc")).ok();
+        let report_length = fs::read_to_string(format!("{hid_path}/report_length")).ok();
+        let dev = fs::read_to_string(format!("{hid_path}/dev")).ok();
+        let node = fs::metadata(HID_DEVICE_PATH)
+            .ok()
+            .filter(platform::is_char_device)
+            .and_then(|metadata| platform::device_number(&metadata));
+        let descriptor_check = GadgetCheck::report_descriptor(
+            PRO_CONTROLLER_REPORT_DESCRIPTOR,
+            report_desc.as_deref(),
+        );
+        let mut descriptor_check = DiagnosticCheck::from(descriptor_check);
+        if descriptor_check.status == DiagnosticStatus::Failed {
+            descriptor_check = descriptor_check.with_hint(
+                "The Switch ignores reports when the descriptor differs; re-run 'sudo splatoon3-ghost-drawer setup --force'",
+            );
         }
+        section.check(descriptor_check);
+        section.check(GadgetCheck::report_length(REPORT_LENGTH, report_length.as_deref()).into());
+        section.check(GadgetCheck::device_node(HID_DEVICE_PATH, dev.as_deref(), node).into());
 
-        println!();
-        Ok(())
+        section
     }
 
-    fn check_hid_devices(&self) -> Result<(), HardwareError> {
-        println!("🎮 HID Devices:");
+    fn check_hid_devices(&self) -> DiagnosticSection {
+        let mut section = DiagnosticSection::new("hid_devices", "🎮", "HID Devices");
 
         let hid_devices = vec!["/dev/hidg0", "/dev/hidg1", "/dev/hidg2", "/dev/hidg3"];
 
         for device in hid_devices {
-            if Path::new(device).exists() {
-                println!("   ✅ {device} exists");
-
-                // 権限の確認
-                if let Ok(metadata) = fs::metadata(device) {
-                    use std::os::unix::fs::PermissionsExt;
-                    let mode = metadata.permissions().mode();
-                    println!("      Permissions: {:o}", mode & 0o777);
-                }
+            if !Path::new(device).exists() {
+                continue;
+            }
 
-                // 書き込みテスト
-                match fs::OpenOptions::new().write(true).open(device) {
-                    Ok(mut file) => {
-                        let test_data = [0u8; 64];
-                        match file.write_all(&test_data) {
-                            Ok(_) => println!("      ✅ Write test successful"),
-                            Err(e) => {
-                                if e.raw_os_error() == Some(108) {
-                                    println!(
-                                        "      ❌ Write test failed: Transport endpoint not connected"
-                                    );
-                                } else {
-                                    println!("      ❌ Write test failed: {e}");
-                                }
-                            }
-                        }
-                    }
-                    Err(e) => {
-                        if e.kind() == std::io::ErrorKind::PermissionDenied {
-                            println!("      ❌ Permission denied (need sudo)");
-                        } else {
-                            println!("      ❌ Cannot open: {e}");
-                        }
+            // 権限の確認
+            let permissions = fs::metadata(device)
+                .ok()
+                .and_then(|metadata| platform::permission_bits(&metadata))
+                .map(|mode| format!(" (permissions {mode:o})"))
+                .unwrap_or_default();
+
+            // 書き込みテスト
+            let check = match fs::OpenOptions::new().write(true).open(device) {
+                Ok(mut file) => {
+                    let test_data = [0u8; 64];
+                    match file.write_all(&test_data) {
+                        Ok(_) => DiagnosticCheck::new(
+                            device,
+                            DiagnosticStatus::Ok,
+                            format!("Write test successful{permissions}"),
+                        ),
+                        Err(e) => DiagnosticCheck::new(
+                            device,
+                            DiagnosticStatus::Failed,
+                            HardwareError::classify_io(e, "Write test failed").to_string(),
+                        ),
                     }
                 }
-            }
+                Err(e) => DiagnosticCheck::new(
+                    device,
+                    DiagnosticStatus::Failed,
+                    HardwareError::classify_io(e, &format!("Cannot open{permissions}")).to_string(),
+                ),
+            };
+            section.check(check);
         }
 
-        println!();
-        Ok(())
+        if section.checks.is_empty() {
+            section.check(DiagnosticCheck::new(
+                "Devices",
+                DiagnosticStatus::Failed,
+                "No HID gadget devices found",
+            ));
+        }
+
+        section
     }
 
-    fn check_otg_mode(&self) -> Result<(), HardwareError> {
-        println!("🔄 USB OTG Mode:");
-
-        // Find musb-hdrc mode files
-        let musb_dirs = vec!["/sys/devices/platform/soc", "/sys/devices/platform"];
-
-        let mut found_otg = false;
-
-        for base_dir in musb_dirs {
-            if let Ok(entries) = fs::read_dir(base_dir) {
-                for entry in entries.flatten() {
-                    let path = entry.path();
-                    let name = entry.file_name().to_string_lossy().to_string();
-
-                    if name.contains("usb") {
-                        let mode_path = path.join("musb-hdrc.4.auto/mode");
-                        if mode_path.exists() {
-                            found_otg = true;
-                            if let Ok(mode) = fs::read_to_string(&mode_path) {
-                                let mode = mode.trim();
-                                println!("   Mode: {mode}");
-
-                                if mode == "peripheral" || mode == "b_peripheral" {
-                                    println!("   ✅ USB OTG is in peripheral mode");
-                                } else {
-                                    println!(
-                                        "   ⚠️  USB OTG is in {mode} mode (should be peripheral)"
-                                    );
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    fn check_otg_mode(&self) -> DiagnosticSection {
+        let mut section = DiagnosticSection::new("otg_mode", "🔄", "USB OTG Mode");
+
+        // musbのデバイス名はカーネルによって変わるため、UDCの準備と同じ方法で探す（ここでは書き換えない）
+        let mode_files = OrangePiUdcPreparer::new().mode_files();
+        let found_otg = !mode_files.is_empty();
+        for mode_file in mode_files {
+            let Some(mode) = &mode_file.mode else {
+                continue;
+            };
+            section.check(if mode_file.is_peripheral() {
+                DiagnosticCheck::new("Mode", DiagnosticStatus::Ok, mode)
+            } else {
+                DiagnosticCheck::new(
+                    "Mode",
+                    DiagnosticStatus::Warning,
+                    format!("{mode} (should be peripheral)"),
+                )
+                .with_hint(format!("Mode file: {}", mode_file.path.display()))
+                .with_hint(
+                    "Run 'sudo splatoon3-ghost-drawer fix-connection' to force peripheral mode",
+                )
+            });
         }
 
         if !found_otg {
@@ -260,67 +469,81 @@ impl DiagnoseConnectionUseCase {
             };
 
             if is_dwc2_loaded {
-                println!("   ✅ dwc2 module loaded (Raspberry Pi)");
+                section.check(DiagnosticCheck::new(
+                    "Mode",
+                    DiagnosticStatus::Ok,
+                    "dwc2 module loaded (Raspberry Pi)",
+                ));
             } else {
-                println!("   ❌ USB OTG mode file not found");
-                println!("   This may indicate USB OTG is not enabled in Device Tree");
+                section.check(
+                    DiagnosticCheck::new(
+                        "Mode",
+                        DiagnosticStatus::Failed,
+                        "USB OTG mode file not found",
+                    )
+                    .with_hint("This may indicate USB OTG is not enabled in Device Tree"),
+                );
             }
         }
 
-        println!();
-        Ok(())
+        section
     }
 
-    fn check_usb_connection(&self) -> Result<(), HardwareError> {
-        println!("🔗 USB Connection Status:");
+    fn check_usb_connection(&self) -> DiagnosticSection {
+        let mut section = DiagnosticSection::new("usb_connection", "🔗", "USB Connection Status");
 
         // lsusbの出力を確認
         if let Ok(output) = Command::new("lsusb").output() {
             let lsusb = String::from_utf8_lossy(&output.stdout);
-            if lsusb.contains("057e:2009") {
-                println!("   ℹ️  Nintendo Pro Controller detected by host (Self-check)");
-            } else {
-                println!("   ℹ️  Pro Controller not detected by host (Normal for Gadget mode)");
-            }
+            section.check(DiagnosticCheck::info(
+                "Host view",
+                if lsusb.contains("057e:2009") {
+                    "Nintendo Pro Controller detected by host (Self-check)"
+                } else {
+                    "Pro Controller not detected by host (Normal for Gadget mode)"
+                },
+            ));
         }
 
         // USB gadgetの状態を確認
         let state_path = "/sys/kernel/config/usb_gadget/nintendo_controller/state";
         if let Ok(state) = fs::read_to_string(state_path) {
-            println!("   📊 Gadget state: {}", state.trim());
+            section.check(DiagnosticCheck::info("Gadget state", state.trim()));
         }
 
-        println!();
-        Ok(())
+        section
     }
 
-    fn check_system_info(&self) -> Result<(), HardwareError> {
-        println!("🖥️ System Information:");
+    fn check_system_info(&self) -> DiagnosticSection {
+        let mut section = DiagnosticSection::new("system", "🖥️", "System Information");
 
         // Check board model
         if let Ok(model) = fs::read_to_string("/proc/device-tree/model") {
-            println!("   Board Model: {}", model.trim_end_matches('\0'));
+            section.check(DiagnosticCheck::info(
+                "Board Model",
+                model.trim_end_matches('\0'),
+            ));
         }
 
         // Check kernel version
         if let Ok(version) = fs::read_to_string("/proc/version") {
             let kernel_line = version.lines().next().unwrap_or("Unknown");
-            println!("   Kernel: {kernel_line}");
+            section.check(DiagnosticCheck::info("Kernel", kernel_line));
         }
 
         // Check if running as root
-        let is_root = unsafe { libc::geteuid() == 0 };
-        println!(
-            "   Running as root: {}",
-            if is_root { "✅ Yes" } else { "❌ No" }
-        );
-
-        println!();
-        Ok(())
+        let is_root = platform::is_root();
+        section.check(DiagnosticCheck::new(
+            "Running as root",
+            DiagnosticStatus::from_bool(is_root),
+            if is_root { "Yes" } else { "No" },
+        ));
+
+        section
     }
 
-    fn check_boot_configuration(&self) -> Result<(), HardwareError> {
-        println!("🔧 Boot Configuration:");
+    fn check_boot_configuration(&self) -> DiagnosticSection {
+        let mut section = DiagnosticSection::new("boot_configuration", "🔧", "Boot Configuration");
 
         // Check config.txt files
         let config_files = vec!["/boot/firmware/config.txt", "/boot/config.txt"];
@@ -329,7 +552,11 @@ impl DiagnoseConnectionUseCase {
         for config_file in &config_files {
             if Path::new(config_file).exists() {
                 found_config = true;
-                println!("   Config file: {config_file} ✅");
+                section.check(DiagnosticCheck::new(
+                    "Config file",
+                    DiagnosticStatus::Ok,
+                    *config_file,
+                ));
 
                 if let Ok(content) = fs::read_to_string(config_file) {
                     let has_dwc2 = content.lines().any(|line| {
@@ -337,15 +564,20 @@ impl DiagnoseConnectionUseCase {
                         trimmed == "dtoverlay=dwc2" && !trimmed.starts_with('#')
                     });
 
-                    println!(
-                        "   dtoverlay=dwc2: {}",
-                        if has_dwc2 { "✅ Found" } else { "❌ Missing" }
-                    );
+                    section.check(DiagnosticCheck::new(
+                        "dtoverlay=dwc2",
+                        DiagnosticStatus::from_bool(has_dwc2),
+                        if has_dwc2 { "Found" } else { "Missing" },
+                    ));
 
                     // Check for conflicting configurations
                     let has_dwc2_host = content.contains("dtoverlay=dwc2,dr_mode=host");
                     if has_dwc2_host {
-                        println!("   ⚠️  Found conflicting dwc2 host mode configuration");
+                        section.check(DiagnosticCheck::new(
+                            "dtoverlay=dwc2,dr_mode=host",
+                            DiagnosticStatus::Warning,
+                            "Found conflicting dwc2 host mode configuration",
+                        ));
                     }
                 }
                 break;
@@ -353,58 +585,56 @@ impl DiagnoseConnectionUseCase {
         }
 
         if !found_config {
-            println!("   Config file: ❌ Not found");
+            section.check(DiagnosticCheck::new(
+                "Config file",
+                DiagnosticStatus::Failed,
+                "Not found",
+            ));
         }
 
         // Check /etc/modules
         if Path::new("/etc/modules").exists()
             && let Ok(content) = fs::read_to_string("/etc/modules")
         {
-            let has_dwc2 = content.lines().any(|line| line.trim() == "dwc2");
-            let has_libcomposite = content.lines().any(|line| line.trim() == "libcomposite");
-
-            println!(
-                "   /etc/modules dwc2: {}",
-                if has_dwc2 { "✅ Found" } else { "❌ Missing" }
-            );
-            println!(
-                "   /etc/modules libcomposite: {}",
-                if has_libcomposite {
-                    "✅ Found"
-                } else {
-                    "❌ Missing"
-                }
-            );
+            for module in ["dwc2", "libcomposite"] {
+                let listed = content.lines().any(|line| line.trim() == module);
+                section.check(DiagnosticCheck::new(
+                    format!("/etc/modules {module}"),
+                    DiagnosticStatus::from_bool(listed),
+                    if listed { "Found" } else { "Missing" },
+                ));
+            }
         }
 
         // Check blacklist
         let blacklist_file = "/etc/modprobe.d/blacklist-dwc_otg.conf";
         let blacklist_exists = Path::new(blacklist_file).exists();
-        println!(
-            "   dwc_otg blacklisted: {}",
-            if blacklist_exists {
-                "✅ Yes"
-            } else {
-                "❌ No"
-            }
-        );
+        section.check(DiagnosticCheck::new(
+            "dwc_otg blacklisted",
+            DiagnosticStatus::from_bool(blacklist_exists),
+            if blacklist_exists { "Yes" } else { "No" },
+        ));
 
-        println!();
-        Ok(())
+        section
     }
 
-    fn check_udc_status(&self) -> Result<(), HardwareError> {
-        println!("🔌 USB Device Controller (UDC):");
+    fn check_udc_status(&self) -> DiagnosticSection {
+        let mut section = DiagnosticSection::new("udc", "🔌", "USB Device Controller (UDC)");
 
         let udc_dir = "/sys/class/udc";
         if !Path::new(udc_dir).exists() {
-            println!("   UDC directory: ❌ Not found");
-            println!("   This indicates USB OTG is not enabled or dwc2 is not loaded");
-            println!();
-            return Ok(());
+            section.check(
+                DiagnosticCheck::new("UDC directory", DiagnosticStatus::Failed, "Not found")
+                    .with_hint("This indicates USB OTG is not enabled or dwc2 is not loaded"),
+            );
+            return section;
         }
 
-        println!("   UDC directory: ✅ Found");
+        section.check(DiagnosticCheck::new(
+            "UDC directory",
+            DiagnosticStatus::Ok,
+            "Found",
+        ));
 
         // List available UDCs
         if let Ok(entries) = fs::read_dir(udc_dir) {
@@ -414,19 +644,72 @@ impl DiagnoseConnectionUseCase {
                 .collect();
 
             if udcs.is_empty() {
-                println!("   Available UDCs: ❌ None found");
-                println!("   Check if dwc2 module is loaded with correct parameters");
+                section.check(
+                    DiagnosticCheck::new("Available UDCs", DiagnosticStatus::Failed, "None found")
+                        .with_hint("Check if dwc2 module is loaded with correct parameters")
+                        .with_hint(
+                            "On Orange Pi Zero 2W the musb controller may be in host mode; run 'sudo splatoon3-ghost-drawer fix-connection'",
+                        ),
+                );
             } else {
-                println!("   Available UDCs: ✅ {}", udcs.join(", "));
+                section.check(DiagnosticCheck::new(
+                    "Available UDCs",
+                    DiagnosticStatus::Ok,
+                    udcs.join(", "),
+                ));
             }
         }
 
-        println!();
-        Ok(())
+        let pinned = read_pinned_udc();
+        if let Some(pinned) = &pinned {
+            section.check(DiagnosticCheck::info(
+                "Pinned UDC",
+                format!("{pinned} (from {GADGET_CONFIG_FILE})"),
+            ));
+        }
+
+        // バインド中のUDCがボードの想定と一致しているか
+        let bound = fs::read_to_string("/sys/kernel/config/usb_gadget/nintendo_controller/UDC")
+            .map(|content| content.trim().to_string())
+            .unwrap_or_default();
+        if !bound.is_empty() {
+            let expected = self
+                .board_detector
+                .detect_board()
+                .map(|board| board.expected_udc_names())
+                .unwrap_or_default();
+
+            if expected.is_empty() || expected.contains(&bound.as_str()) {
+                section.check(DiagnosticCheck::new(
+                    "Bound UDC",
+                    DiagnosticStatus::Ok,
+                    bound,
+                ));
+            } else if pinned.as_deref() == Some(bound.as_str()) {
+                section.check(DiagnosticCheck::new(
+                    "Bound UDC",
+                    DiagnosticStatus::Ok,
+                    format!("{bound} (pinned)"),
+                ));
+            } else {
+                section.check(
+                    DiagnosticCheck::new("Bound UDC", DiagnosticStatus::Warning, bound)
+                        .with_hint(format!(
+                            "This board normally uses {}; the Switch may not see the controller",
+                            expected.join(" or ")
+                        ))
+                        .with_hint(format!(
+                            "Pin the correct UDC with 'udc = <name>' in {GADGET_CONFIG_FILE}"
+                        )),
+                );
+            }
+        }
+
+        section
     }
 
-    fn check_service_status(&self) -> Result<(), HardwareError> {
-        println!("🔄 Service Status:");
+    fn check_service_status(&self) -> DiagnosticSection {
+        let mut section = DiagnosticSection::new("services", "🔄", "Service Status");
 
         let services = vec![
             ("splatoon3-gadget.service", "USB Gadget Configuration"),
@@ -434,50 +717,66 @@ impl DiagnoseConnectionUseCase {
         ];
 
         for (service_name, description) in services {
-            if let Ok(output) = Command::new("systemctl")
+            let name = format!("{service_name} ({description})");
+            let Ok(output) = Command::new("systemctl")
                 .arg("is-active")
                 .arg(service_name)
                 .output()
+            else {
+                section.check(DiagnosticCheck::new(
+                    name,
+                    DiagnosticStatus::Warning,
+                    "unknown",
+                ));
+                continue;
+            };
+
+            let status = String::from_utf8_lossy(&output.stdout).trim().to_string();
+            let check_status = match status.as_str() {
+                "active" => DiagnosticStatus::Ok,
+                "failed" => DiagnosticStatus::Failed,
+                _ => DiagnosticStatus::Warning,
+            };
+            let mut check = DiagnosticCheck::new(name, check_status, status.as_str());
+
+            // If failed, show recent logs
+            if status == "failed"
+                && let Ok(log_output) = Command::new("journalctl")
+                    .arg("-u")
+                    .arg(service_name)
+                    .arg("--no-pager")
+                    .arg("-n")
+                    .arg("3")
+                    .output()
             {
-                let status = String::from_utf8_lossy(&output.stdout).trim().to_string();
-                let status_icon = match status.as_str() {
-                    "active" => "✅",
-                    "inactive" => "⏸️",
-                    "failed" => "❌",
-                    _ => "❓",
-                };
-
-                println!("   {service_name} ({description}): {status_icon} {status}");
-
-                // If failed, show recent logs
-                if status == "failed"
-                    && let Ok(log_output) = Command::new("journalctl")
-                        .arg("-u")
-                        .arg(service_name)
-                        .arg("--no-pager")
-                        .arg("-n")
-                        .arg("3")
-                        .output()
-                {
-                    let logs = String::from_utf8_lossy(&log_output.stdout);
-                    if !logs.trim().is_empty() {
-                        println!("     Recent logs:");
-                        for line in logs.lines().take(3) {
-                            println!("       {line}");
-                        }
+                let logs = String::from_utf8_lossy(&log_output.stdout);
+                if !logs.trim().is_empty() {
+                    check = check.with_hint("Recent logs:");
+                    for line in logs.lines().take(3) {
+                        check = check.with_hint(format!("  {line}"));
                     }
                 }
-            } else {
-                println!("   {service_name} ({description}): ❓ Unknown");
             }
+            section.check(check);
         }
 
-        println!();
-        Ok(())
+        // 順序付けが無いと、起動時にWebサービスが /dev/hidg0 の作成より先に始まる
+        for (path, required) in [
+            (GADGET_SERVICE_FILE, &GADGET_UNIT_ORDERING[..]),
+            (WEB_SERVICE_FILE, &WEB_UNIT_ORDERING[..]),
+        ] {
+            section.check(unit_ordering_check(
+                path,
+                fs::read_to_string(path).ok(),
+                required,
+            ));
+        }
+
+        section
     }
 
-    fn check_dmesg_logs(&self) -> Result<(), HardwareError> {
-        println!("📋 Recent USB/HID Messages:");
+    fn check_dmesg_logs(&self) -> DiagnosticSection {
+        let mut section = DiagnosticSection::new("dmesg", "📋", "Recent USB/HID Messages");
 
         if let Ok(output) = Command::new("dmesg")
             .args(["-t", "--level=err,warn"])
@@ -499,27 +798,162 @@ impl DiagnoseConnectionUseCase {
                 .collect();
 
             if relevant_lines.is_empty() {
-                println!("   No recent USB/HID messages found");
+                section.check(DiagnosticCheck::info(
+                    "Messages",
+                    "No recent USB/HID messages found",
+                ));
             } else {
-                for line in relevant_lines.iter().rev() {
-                    println!("   - {line}");
-                }
+                section.messages = relevant_lines
+                    .iter()
+                    .rev()
+                    .map(|line| line.to_string())
+                    .collect();
             }
         }
 
-        println!();
-        Ok(())
+        section
     }
+}
 
-    fn show_recommendations(&self) {
-        println!("💡 Recommendations:");
-        println!("   If connection fails on Orange Pi Zero 2W:");
-        println!("   1. Ensure USB OTG is enabled in device tree");
-        println!("   2. Try: sudo modprobe sunxi musb_hdrc");
-        println!("   3. Restart gadget: sudo systemctl restart splatoon3-gadget.service");
-        println!("   4. Check Nintendo Switch is on Home screen");
-        println!("   5. Try reconnecting USB cable");
-        println!();
-        println!("   For detailed logs: sudo dmesg | grep -E '(musb|gadget|hid)'");
+/// 推奨される対処法
+fn recommendations() -> Vec<String> {
+    [
+        "If connection fails on Orange Pi Zero 2W:",
+        "1. Ensure USB OTG is enabled in device tree",
+        "2. Try: sudo modprobe sunxi musb_hdrc",
+        "3. Restart gadget: sudo systemctl restart splatoon3-gadget.service",
+        "4. Check Nintendo Switch is on Home screen",
+        "5. Try reconnecting USB cable",
+        "For detailed logs: sudo dmesg | grep -E '(musb|gadget|hid)'",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .collect()
+}
+
+/// インストール済みのユニットファイルに順序付けの指定がそろっているか
+fn unit_ordering_check(
+    path: &str,
+    unit: Option<String>,
+    required: &[(&str, &str)],
+) -> DiagnosticCheck {
+    let name = format!("{path} ordering");
+    let Some(unit) = unit else {
+        return DiagnosticCheck::new(name, DiagnosticStatus::Warning, "not installed")
+            .with_hint("Run: sudo splatoon3-ghost-drawer setup");
+    };
+    let missing = missing_unit_directives(&unit, required);
+    if missing.is_empty() {
+        DiagnosticCheck::new(
+            name,
+            DiagnosticStatus::Ok,
+            "boot ordering directives present",
+        )
+    } else {
+        DiagnosticCheck::new(
+            name,
+            DiagnosticStatus::Failed,
+            format!("missing {}", missing.join(", ")),
+        )
+        .with_hint("Rewrite the unit files: sudo splatoon3-ghost-drawer setup --force")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn section(id: &'static str, checks: Vec<DiagnosticCheck>) -> DiagnosticSection {
+        let mut section = DiagnosticSection::new(id, "🔌", "Test");
+        section.checks = checks;
+        section
+    }
+
+    #[test]
+    fn test_diagnostic_report_json_schema() {
+        let report = DiagnosticReport::new(vec![section(
+            "hid_devices",
+            vec![
+                DiagnosticCheck::new(
+                    "/dev/hidg0",
+                    DiagnosticStatus::Failed,
+                    "Write test failed: the Switch is not accepting input",
+                )
+                .with_hint("Check the USB cable"),
+            ],
+        )]);
+
+        // 監視スクリプトが読む項目名を変えたら `DIAGNOSTIC_REPORT_SCHEMA_VERSION` を上げること
+        let mut value = serde_json::to_value(&report).unwrap();
+        assert_eq!(value["recommendations"].as_array().unwrap().len(), 7);
+        value.as_object_mut().unwrap().remove("recommendations");
+        assert_eq!(
+            value,
+            json!({
+                "schema_version": 1,
+                "healthy": false,
+                "sections": [{
+                    "id": "hid_devices",
+                    "title": "Test",
+                    "checks": [{
+                        "name": "/dev/hidg0",
+                        "status": "failed",
+                        "detail": "Write test failed: the Switch is not accepting input",
+                        "hints": ["Check the USB cable"]
+                    }],
+                    "messages": []
+                }]
+            })
+        );
+    }
+
+    #[test]
+    fn test_report_is_healthy_unless_a_required_section_fails() {
+        let not_loaded = || DiagnosticCheck::new("dwc2", DiagnosticStatus::Failed, "Not loaded");
+        let ok = || DiagnosticCheck::new("UDC", DiagnosticStatus::Ok, "Bound");
+
+        // ボードによっては当てはまらない項目の失敗は判定に含めない
+        let report = DiagnosticReport::new(vec![
+            section("kernel_modules", vec![not_loaded()]),
+            section("gadget_configuration", vec![ok()]),
+        ]);
+        assert!(report.healthy);
+
+        let report = DiagnosticReport::new(vec![
+            section("kernel_modules", vec![ok()]),
+            section(
+                "udc",
+                vec![DiagnosticCheck::new(
+                    "Available UDCs",
+                    DiagnosticStatus::Failed,
+                    "None found",
+                )],
+            ),
+        ]);
+        assert!(!report.healthy);
+    }
+
+    #[test]
+    fn test_unit_ordering_check_reports_missing_directives() {
+        let required = [("After", "a.service"), ("Wants", "b.device")];
+        let check = unit_ordering_check(
+            "/etc/x.service",
+            Some("[Unit]\nAfter=network.target a.service\nWants=b.device\n".to_string()),
+            &required,
+        );
+        assert_eq!(check.status, DiagnosticStatus::Ok);
+
+        let check = unit_ordering_check(
+            "/etc/x.service",
+            Some("[Unit]\nAfter=a.service\n".to_string()),
+            &required,
+        );
+        assert_eq!(check.status, DiagnosticStatus::Failed);
+        assert_eq!(check.detail, "missing Wants=b.device");
+        assert!(check.hints[0].contains("setup --force"));
+
+        let check = unit_ordering_check("/etc/x.service", None, &required);
+        assert_eq!(check.status, DiagnosticStatus::Warning);
     }
 }
@@ -1,283 +1,272 @@
-use crate::domain::hardware::repositories::UsbGadgetManager;
-use crate::domain::setup::repositories::SetupError;
-use std::fs;
-use std::io::Write;
-use std::path::Path;
-use std::process::Command;
+use crate::domain::setup::entities::{
+    AuditInitiator, FixConnectionOutcome, FixConnectionStep, FixConnectionStepResult,
+    GadgetAuditEntry, GadgetOperation,
+};
+use crate::domain::setup::repositories::{ConnectionRepairer, GadgetAuditLog, SetupError};
 use std::sync::Arc;
-use std::thread;
-use std::time::Duration;
-use tracing::error;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{info, warn};
+
+/// 接続修正の進捗通知
+#[derive(Debug, Clone)]
+pub enum FixConnectionEvent {
+    StepStarted {
+        index: usize,
+        step: FixConnectionStep,
+    },
+    StepFinished {
+        index: usize,
+        result: FixConnectionStepResult,
+    },
+}
+
+/// 接続修正の実行結果
+#[derive(Debug, Clone)]
+pub struct FixConnectionReport {
+    pub results: Vec<FixConnectionStepResult>,
+    pub outcome: FixConnectionOutcome,
+}
 
 /// 接続問題を修正するユースケース（主にOrange Pi Zero 2W向け）
-pub struct FixConnectionUseCase<G: UsbGadgetManager> {
-    gadget_manager: Arc<G>,
+///
+/// 手順を1つずつ実行し、失敗した時点または中断要求があった時点で終了する。
+/// CLIからは `execute`、Webのウィザードからは `run` を使う。
+pub struct FixConnectionUseCase {
+    repairer: Arc<dyn ConnectionRepairer>,
+    audit: Option<(Arc<dyn GadgetAuditLog>, AuditInitiator)>,
 }
 
-impl<G: UsbGadgetManager> FixConnectionUseCase<G> {
-    pub fn new(gadget_manager: Arc<G>) -> Self {
-        Self { gadget_manager }
+impl FixConnectionUseCase {
+    pub fn new(repairer: Arc<dyn ConnectionRepairer>) -> Self {
+        Self {
+            repairer,
+            audit: None,
+        }
     }
 
-    pub fn execute(&self) -> Result<(), SetupError> {
-        println!("🔧 USB Gadget Connection Fix");
-        println!("============================\n");
-
-        // 1. 必要なカーネルモジュールをロード
-        self.load_kernel_modules()?;
-
-        // 2. USB Gadgetサービスを停止
-        self.stop_gadget_service()?;
-
-        // 3. USB Gadgetをリセット
-        self.reset_usb_gadget()?;
-
-        // 4. USB Gadgetサービスを再起動
-        self.start_gadget_service()?;
-
-        // 5. USB OTGモードを確認・設定
-        self.check_and_fix_otg_mode()?;
-
-        // 6. 接続状態を確認
-        self.check_connection_status()?;
-
-        // 7. 推奨事項を表示
-        self.show_recommendations();
-
-        Ok(())
+    /// 接続修正の結果（中断・失敗した手順を含む）を監査ログに記録する
+    pub fn with_audit_log(
+        mut self,
+        audit_log: Arc<dyn GadgetAuditLog>,
+        initiator: AuditInitiator,
+    ) -> Self {
+        self.audit = Some((audit_log, initiator));
+        self
     }
 
-    fn load_kernel_modules(&self) -> Result<(), SetupError> {
-        println!("📦 Loading kernel modules...");
-
-        let modules = vec![
-            ("sunxi", "Allwinner platform support"),
-            ("musb_hdrc", "MUSB HDRC driver"),
-            ("usb_f_hid", "USB HID function"),
-            ("libcomposite", "USB Gadget framework"),
-        ];
-
-        for (module, description) in modules {
-            print!("   {module} ({description}): ");
-
-            let output = Command::new("modprobe")
-                .arg(module)
-                .output()
-                .map_err(|e| SetupError::Unknown(format!("Failed to run modprobe: {e}")))?;
-
-            if output.status.success() {
-                println!("✅ Loaded");
-            } else {
-                let stderr = String::from_utf8_lossy(&output.stderr);
-                if stderr.contains("Module") && stderr.contains("not found") {
-                    println!("⚠️  Module not available");
-                } else if stderr.is_empty() {
-                    // モジュールが既にロードされている場合
-                    println!("✅ Already loaded");
-                } else {
-                    println!("⚠️  Failed: {}", stderr.trim());
+    pub fn run(
+        &self,
+        abort: &AtomicBool,
+        on_event: impl FnMut(FixConnectionEvent),
+    ) -> FixConnectionReport {
+        let report = self.run_steps(abort, on_event);
+        if let Some((audit_log, initiator)) = &self.audit {
+            let result = match report.outcome {
+                FixConnectionOutcome::Completed => Ok(()),
+                FixConnectionOutcome::Failed { step } => {
+                    Err(format!("{} failed", step.description()))
                 }
-            }
+                FixConnectionOutcome::Aborted => Err("Aborted".to_string()),
+            };
+            audit_log.record(
+                GadgetAuditEntry::new(GadgetOperation::FixConnection, *initiator, &result)
+                    .with_parameter("steps_run", report.results.len().to_string()),
+            );
         }
-
-        println!();
-        Ok(())
+        report
     }
 
-    fn stop_gadget_service(&self) -> Result<(), SetupError> {
-        println!("⏹️  Stopping USB Gadget service...");
-
-        let output = Command::new("systemctl")
-            .args(["stop", "splatoon3-gadget.service"])
-            .output()
-            .map_err(|e| SetupError::Unknown(format!("Failed to stop service: {e}")))?;
-
-        if output.status.success() {
-            println!("   ✅ Service stopped");
-        } else {
-            println!("   ⚠️  Service may not be running");
-        }
-
-        // 少し待機
-        thread::sleep(Duration::from_millis(500));
-
-        Ok(())
-    }
-
-    fn reset_usb_gadget(&self) -> Result<(), SetupError> {
-        println!("🔄 Resetting USB Gadget...");
-
-        let udc_path = "/sys/kernel/config/usb_gadget/nintendo_controller/UDC";
-
-        if Path::new(udc_path).exists() {
-            // UDCをアンバインド
-            fs::write(udc_path, "").map_err(|e| {
-                error!("Failed to unbind UDC: {}", e);
-                SetupError::FileSystemError(e)
-            })?;
-            println!("   ✅ UDC unbound");
+    fn run_steps(
+        &self,
+        abort: &AtomicBool,
+        mut on_event: impl FnMut(FixConnectionEvent),
+    ) -> FixConnectionReport {
+        let mut results = Vec::new();
+
+        for (index, step) in FixConnectionStep::ALL.into_iter().enumerate() {
+            if abort.load(Ordering::SeqCst) {
+                info!("Connection fix aborted before {:?}", step);
+                return FixConnectionReport {
+                    results,
+                    outcome: FixConnectionOutcome::Aborted,
+                };
+            }
 
-            // 少し待機
-            thread::sleep(Duration::from_millis(500));
+            on_event(FixConnectionEvent::StepStarted { index, step });
+            let result = self.repairer.run_step(step);
+            if !result.success {
+                warn!("Connection fix step {:?} failed: {}", step, result.message);
+            }
+            let success = result.success;
+            results.push(result.clone());
+            on_event(FixConnectionEvent::StepFinished { index, result });
+
+            if !success {
+                return FixConnectionReport {
+                    results,
+                    outcome: FixConnectionOutcome::Failed { step },
+                };
+            }
         }
 
-        Ok(())
-    }
-
-    fn start_gadget_service(&self) -> Result<(), SetupError> {
-        println!("▶️  Starting USB Gadget service...");
-
-        let output = Command::new("systemctl")
-            .args(["start", "splatoon3-gadget.service"])
-            .output()
-            .map_err(|e| SetupError::Unknown(format!("Failed to start service: {e}")))?;
-
-        if output.status.success() {
-            println!("   ✅ Service started");
-        } else {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            return Err(SetupError::Unknown(format!(
-                "Failed to start service: {stderr}"
-            )));
+        FixConnectionReport {
+            results,
+            outcome: FixConnectionOutcome::Completed,
         }
-
-        // サービスが完全に起動するまで待機
-        println!("   ⏳ Waiting for service to initialize...");
-        thread::sleep(Duration::from_secs(2));
-
-        Ok(())
     }
 
-    fn check_connection_status(&self) -> Result<(), SetupError> {
-        println!("\n🔍 Checking connection status...");
-
-        // USB Gadgetの設定確認
-        if self.gadget_manager.is_gadget_configured()? {
-            println!("   ✅ USB Gadget configured");
+    pub fn execute(&self) -> Result<(), SetupError> {
+        println!("🔧 USB Gadget Connection Fix");
+        println!("============================\n");
 
-            // UDCの状態確認
-            let udc_path = "/sys/kernel/config/usb_gadget/nintendo_controller/UDC";
-            if let Ok(udc) = fs::read_to_string(udc_path) {
-                let udc = udc.trim();
-                if !udc.is_empty() {
-                    println!("   ✅ UDC bound to: {udc}");
-                } else {
-                    println!("   ❌ UDC not bound");
-                }
+        let total = FixConnectionStep::ALL.len();
+        let report = self.run(&AtomicBool::new(false), |event| match event {
+            FixConnectionEvent::StepStarted { index, step } => {
+                println!("▶️  [{}/{total}] {}...", index + 1, step.description());
             }
-        } else {
-            println!("   ❌ USB Gadget not configured");
-        }
-
-        // HIDデバイスの確認
-        if Path::new("/dev/hidg0").exists() {
-            println!("   ✅ HID device /dev/hidg0 exists");
-
-            // 書き込みテスト
-            match fs::OpenOptions::new().write(true).open("/dev/hidg0") {
-                Ok(mut file) => {
-                    let test_data = vec![0u8; 64];
-                    match file.write_all(&test_data) {
-                        Ok(_) => println!("   ✅ HID device is writable"),
-                        Err(e) => {
-                            if e.raw_os_error() == Some(108) {
-                                println!(
-                                    "   ⚠️  HID device not ready (Nintendo Switch may not be connected)"
-                                );
-                            } else {
-                                println!("   ❌ HID device write test failed: {e}");
-                            }
-                        }
-                    }
-                }
-                Err(e) => {
-                    println!("   ❌ Cannot open HID device: {e}");
+            FixConnectionEvent::StepFinished { result, .. } => {
+                let mark = if result.success { "✅" } else { "❌" };
+                println!("   {mark} {}", result.message);
+                for hint in &result.hints {
+                    println!("   💡 {hint}");
                 }
+                println!();
+            }
+        });
+
+        match report.outcome {
+            FixConnectionOutcome::Failed { step } => Err(SetupError::Unknown(format!(
+                "{} failed",
+                step.description()
+            ))),
+            _ => {
+                self.show_recommendations();
+                Ok(())
             }
-        } else {
-            println!("   ❌ HID device not found");
         }
+    }
 
+    fn show_recommendations(&self) {
+        println!("💡 Next steps:");
+        println!("   1. Ensure Nintendo Switch is on the Home screen");
+        println!("   2. Connect your device to Nintendo Switch via USB-C");
+        println!("   3. Run: sudo splatoon3-ghost-drawer test");
         println!();
-        Ok(())
+        println!("   If still having issues:");
+        println!("   - Run: sudo splatoon3-ghost-drawer diagnose");
+        println!("   - Check dmesg: sudo dmesg | grep -E '(musb|gadget|hid)'");
+        println!("   - Try rebooting your device");
     }
+}
 
-    fn check_and_fix_otg_mode(&self) -> Result<(), SetupError> {
-        println!("🔄 Checking USB OTG mode...");
-
-        // Find musb-hdrc mode file
-        let musb_pattern = "/sys/devices/platform/soc/*.usb/musb-hdrc.*.auto/mode";
-        let mode_files = glob::glob(musb_pattern)
-            .map_err(|e| SetupError::Unknown(format!("Failed to glob pattern: {e}")))?;
-
-        let mut found_mode_file = false;
-
-        for path in mode_files.flatten() {
-            found_mode_file = true;
-
-            // Read current mode
-            if let Ok(current_mode) = fs::read_to_string(&path) {
-                let current_mode = current_mode.trim();
-                println!("   Current mode: {current_mode}");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
 
-                // Check if mode needs to be changed
-                if current_mode != "peripheral" && current_mode != "b_peripheral" {
-                    println!("   ⚠️  USB OTG not in peripheral mode");
+    /// 指定した手順で失敗し、実行した手順を記録する
+    #[derive(Default)]
+    struct FakeRepairer {
+        fail_at: Option<FixConnectionStep>,
+        ran: Mutex<Vec<FixConnectionStep>>,
+    }
 
-                    // Try to set peripheral mode
-                    match fs::write(&path, "peripheral") {
-                        Ok(_) => {
-                            println!("   ✅ Set to peripheral mode");
-                            thread::sleep(Duration::from_millis(500));
-                        }
-                        Err(e) => {
-                            println!("   ❌ Failed to set peripheral mode: {e}");
-                            println!("   💡 You may need to enable USB OTG in Device Tree");
-                        }
-                    }
-                } else {
-                    println!("   ✅ Already in peripheral mode");
-                }
+    impl ConnectionRepairer for FakeRepairer {
+        fn run_step(&self, step: FixConnectionStep) -> FixConnectionStepResult {
+            self.ran.lock().unwrap().push(step);
+            if self.fail_at == Some(step) {
+                FixConnectionStepResult::failed(step, "boom").with_hint("try again")
             } else {
-                println!("   ⚠️  Cannot read USB mode file");
+                FixConnectionStepResult::succeeded(step, "ok")
             }
         }
+    }
 
-        if !found_mode_file {
-            println!("   ❌ No USB OTG mode file found");
-            println!("   💡 This may indicate:");
-            println!("      1. USB OTG is not enabled in Device Tree");
-            println!("      2. The musb driver is not loaded");
-            println!("      3. Different USB controller is being used");
-        }
+    #[test]
+    fn test_run_stops_at_failed_step() {
+        let repairer = Arc::new(FakeRepairer {
+            fail_at: Some(FixConnectionStep::RebuildGadget),
+            ..Default::default()
+        });
+        let use_case = FixConnectionUseCase::new(repairer.clone());
+
+        let mut events = Vec::new();
+        let report = use_case.run(&AtomicBool::new(false), |event| events.push(event));
+
+        assert_eq!(
+            report.outcome,
+            FixConnectionOutcome::Failed {
+                step: FixConnectionStep::RebuildGadget
+            }
+        );
+        assert_eq!(repairer.ran.lock().unwrap().len(), 3);
+        assert_eq!(report.results.last().unwrap().hints, vec!["try again"]);
+        // 各手順で開始と結果の2件
+        assert_eq!(events.len(), 6);
+    }
 
-        // Check Device Tree overlay configuration
-        let env_file = "/boot/orangepiEnv.txt";
-        if Path::new(env_file).exists() {
-            println!("\n📄 Checking Device Tree configuration...");
-            if let Ok(content) = fs::read_to_string(env_file) {
-                if content.contains("usb-otg") {
-                    println!("   ✅ usb-otg overlay is configured");
-                } else {
-                    println!("   ⚠️  usb-otg overlay not found in {env_file}");
-                    println!("   💡 Add 'overlays=usb-otg' to {env_file}");
-                }
+    #[test]
+    fn test_run_checks_abort_between_steps() {
+        let repairer = Arc::new(FakeRepairer::default());
+        let use_case = FixConnectionUseCase::new(repairer.clone());
+
+        let abort = AtomicBool::new(false);
+        let report = use_case.run(&abort, |event| {
+            if let FixConnectionEvent::StepFinished { index: 1, .. } = event {
+                abort.store(true, Ordering::SeqCst);
             }
+        });
+
+        assert_eq!(report.outcome, FixConnectionOutcome::Aborted);
+        assert_eq!(
+            *repairer.ran.lock().unwrap(),
+            vec![
+                FixConnectionStep::UnbindUdc,
+                FixConnectionStep::ReloadModules
+            ]
+        );
+
+        let report = use_case.run(&AtomicBool::new(false), |_| {});
+        assert_eq!(report.outcome, FixConnectionOutcome::Completed);
+        assert_eq!(report.results.len(), FixConnectionStep::ALL.len());
+    }
+
+    #[derive(Default)]
+    struct RecordingAuditLog(Mutex<Vec<GadgetAuditEntry>>);
+
+    impl GadgetAuditLog for RecordingAuditLog {
+        fn record(&self, entry: GadgetAuditEntry) {
+            self.0.lock().unwrap().push(entry);
         }
 
-        println!();
-        Ok(())
+        fn recent(&self, limit: usize) -> Result<Vec<GadgetAuditEntry>, SetupError> {
+            let entries = self.0.lock().unwrap();
+            Ok(entries[entries.len().saturating_sub(limit)..].to_vec())
+        }
     }
 
-    fn show_recommendations(&self) {
-        println!("💡 Next steps:");
-        println!("   1. Ensure Nintendo Switch is on the Home screen");
-        println!("   2. Connect your device to Nintendo Switch via USB-C");
-        println!("   3. Run: sudo splatoon3-ghost-drawer test");
-        println!();
-        println!("   If still having issues:");
-        println!("   - Run: sudo splatoon3-ghost-drawer diagnose");
-        println!("   - Check dmesg: sudo dmesg | grep -E '(musb|gadget|hid)'");
-        println!("   - Try rebooting your device");
+    #[test]
+    fn test_run_records_outcome_in_audit_log() {
+        let audit_log = Arc::new(RecordingAuditLog::default());
+        let use_case = FixConnectionUseCase::new(Arc::new(FakeRepairer {
+            fail_at: Some(FixConnectionStep::RebuildGadget),
+            ..Default::default()
+        }))
+        .with_audit_log(audit_log.clone(), AuditInitiator::Web);
+
+        use_case.run(&AtomicBool::new(false), |_| {});
+        use_case.run(&AtomicBool::new(true), |_| {});
+
+        let entries = audit_log.recent(10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, GadgetOperation::FixConnection);
+        assert_eq!(entries[0].initiator, AuditInitiator::Web);
+        assert!(!entries[0].success);
+        assert_eq!(
+            entries[0].error.as_deref(),
+            Some("Rebuilding USB Gadget failed")
+        );
+        assert_eq!(entries[0].parameters["steps_run"], "3");
+        assert_eq!(entries[1].error.as_deref(), Some("Aborted"));
     }
 }
@@ -1,18 +1,45 @@
 use crate::domain::hardware::repositories::UsbGadgetManager;
-use crate::domain::setup::repositories::SetupError;
+use crate::domain::setup::entities::{AuditInitiator, GadgetAuditEntry, GadgetOperation};
+use crate::domain::setup::repositories::{GadgetAuditLog, SetupError};
 use std::sync::Arc;
 use tracing::info;
 
 pub struct FixPermissionsUseCase {
     usb_gadget_manager: Arc<dyn UsbGadgetManager>,
+    audit: Option<(Arc<dyn GadgetAuditLog>, AuditInitiator)>,
 }
 
 impl FixPermissionsUseCase {
     pub fn new(usb_gadget_manager: Arc<dyn UsbGadgetManager>) -> Self {
-        Self { usb_gadget_manager }
+        Self {
+            usb_gadget_manager,
+            audit: None,
+        }
+    }
+
+    /// 権限の修正の結果を監査ログに記録する
+    pub fn with_audit_log(
+        mut self,
+        audit_log: Arc<dyn GadgetAuditLog>,
+        initiator: AuditInitiator,
+    ) -> Self {
+        self.audit = Some((audit_log, initiator));
+        self
     }
 
     pub fn execute(&self) -> Result<(), SetupError> {
+        let result = self.fix_permissions();
+        if let Some((audit_log, initiator)) = &self.audit {
+            audit_log.record(GadgetAuditEntry::new(
+                GadgetOperation::FixPermissions,
+                *initiator,
+                &result,
+            ));
+        }
+        result
+    }
+
+    fn fix_permissions(&self) -> Result<(), SetupError> {
         info!("Fixing HID device permissions...");
 
         // Check if gadget is configured
@@ -50,8 +77,9 @@ impl FixPermissionsUseCase {
                     if let Ok(uid_int) = uid.parse::<u32>()
                         && let Ok(gid_int) = gid.parse::<u32>()
                     {
-                        use std::os::unix::fs::chown;
-                        if let Err(e) = chown(&hid_path, Some(uid_int), Some(gid_int)) {
+                        if let Err(e) =
+                            crate::infrastructure::platform::chown(&hid_path, uid_int, gid_int)
+                        {
                             warn!("Failed to chown {}: {}", hid_path, e);
                         } else {
                             info!("Successfully changed ownership of {}", hid_path);
@@ -4,7 +4,10 @@ use crate::domain::controller::{
     ControllerSessionRepository, DPad, HidDeviceRepository, ProController, StickPosition,
 };
 use crate::domain::hardware::repositories::UsbGadgetManager;
-use crate::domain::painting::{ArtworkToCommandConverter, DrawingCanvasConfig, DrawingStrategy};
+use crate::domain::painting::{
+    ArtworkToCommandConverter, CompletionReport, DrawingCanvasConfig, DrawingStrategy, PaintTiming,
+    RunOptions,
+};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::time::sleep;
@@ -92,11 +95,7 @@ where
         }
 
         // 5. アートワークをコマンドに変換
-        let drawing_config = DrawingCanvasConfig {
-            cursor_speed_ms: config.cursor_speed_ms,
-            dot_draw_delay_ms: config.dot_draw_delay_ms,
-            ..Default::default()
-        };
+        let drawing_config = DrawingCanvasConfig::new(config.timing, RunOptions::default());
 
         let converter = ArtworkToCommandConverter::new(drawing_config, config.strategy);
         let commands = converter.convert(artwork);
@@ -199,23 +198,37 @@ where
         session.stop();
         self.session_repo.update_session(&session).await?;
 
-        // 10. 結果をまとめる
+        // 10. 結果をまとめる（送信エラーは `?` で中断するため、ここまで来れば全ドットを描画している）
         let drawable_dots = artwork.canvas.drawable_dots().len();
+        let duration_ms = session
+            .started_at
+            .map(|start| {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .unwrap()
+                    .as_millis() as u64;
+                now - start
+            })
+            .unwrap_or(0);
         let result = PaintResult {
             success: true,
             dots_painted: drawable_dots,
             commands_executed: executed_commands,
-            duration_ms: session
-                .started_at
-                .map(|start| {
-                    let now = std::time::SystemTime::now()
-                        .duration_since(std::time::UNIX_EPOCH)
-                        .unwrap()
-                        .as_millis() as u64;
-                    now - start
-                })
-                .unwrap_or(0),
+            duration_ms,
             device_used: device_path.clone(),
+            report: CompletionReport {
+                total_dots: drawable_dots,
+                succeeded: drawable_dots,
+                retried: 0,
+                skipped: 0,
+                skipped_dots: Vec::new(),
+                total_retries: 0,
+                duration_ms,
+                paused_ms: 0,
+                limit_reached: None,
+                preflight_rejected: None,
+                last_completed_row: None,
+            },
         };
 
         info!("アートワークの描画が完了しました: {:?}", result);
@@ -296,10 +309,8 @@ where
 
 #[derive(Debug, Clone)]
 pub struct PaintConfig {
-    /// カーソル移動速度（ミリ秒/ピクセル）
-    pub cursor_speed_ms: u32,
-    /// ドット描画待機時間（ミリ秒）
-    pub dot_draw_delay_ms: u32,
+    /// 1入力あたりのタイミング（移動・描画の両方に使う）
+    pub timing: PaintTiming,
     /// 描画戦略
     pub strategy: DrawingStrategy,
     /// 中断チェックを行うか
@@ -309,8 +320,7 @@ pub struct PaintConfig {
 impl Default for PaintConfig {
     fn default() -> Self {
         Self {
-            cursor_speed_ms: 100,
-            dot_draw_delay_ms: 100,
+            timing: PaintTiming::default(),
             strategy: DrawingStrategy::ZigZag,
             check_interrupt: true,
         }
@@ -324,6 +334,8 @@ pub struct PaintResult {
     pub commands_executed: usize,
     pub duration_ms: u64,
     pub device_used: String,
+    /// ドットごとの結果の集計
+    pub report: CompletionReport,
 }
 
 impl PaintResult {
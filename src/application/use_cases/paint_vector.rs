@@ -0,0 +1,110 @@
+//! 線画のスティック描画
+//!
+//! ドット描画（`run_painting`）とは別の経路で、描画済みの記録・一時停止・送信エラーのやり直しには対応しない。
+
+use super::run_painting::run_init_sequence;
+use crate::application::controller_io::debug_assert_blocking_allowed;
+use crate::application::progress::PROGRESS_CHANNEL;
+use crate::domain::controller::ControllerEmulator;
+use crate::domain::hardware::errors::HardwareError;
+use crate::domain::painting::{InitSequence, VectorPlan};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::info;
+
+/// 線画をスティック操作で描き、引き終えた線の数を返す
+///
+/// 停止した場合は実行中のコマンドを中断し、コントローラーがすべての入力を離してから戻る。
+pub fn perform_vector_painting(
+    controller: Arc<dyn ControllerEmulator>,
+    stop_signal: Arc<AtomicBool>,
+    init_sequence: Option<&InitSequence>,
+    plan: &VectorPlan,
+) -> Result<usize, HardwareError> {
+    debug_assert_blocking_allowed();
+    info!(
+        "Starting vector painting ({} strokes, {:.0}px, ~{:.1}s, max deviation ~{:.1}px)",
+        plan.strokes,
+        plan.length_px,
+        plan.total_ms as f64 / 1000.0,
+        plan.max_deviation_px
+    );
+    controller.initialize()?;
+
+    if let Some(init_sequence) = init_sequence
+        && !run_init_sequence(&controller, init_sequence, &stop_signal, |_| {})?
+    {
+        return Ok(0);
+    }
+
+    let mut strokes = 0;
+    for command in &plan.commands {
+        if stop_signal.load(Ordering::SeqCst) {
+            info!("Vector painting stopped after {} strokes", strokes);
+            return Ok(strokes);
+        }
+        controller.execute_command_cancellable(command, &stop_signal)?;
+        if command.name.starts_with("Stroke ") && !stop_signal.load(Ordering::SeqCst) {
+            strokes += 1;
+            let _ = PROGRESS_CHANNEL.send(
+                serde_json::json!({
+                    "type": "vector_progress",
+                    "strokes": strokes,
+                    "total": plan.strokes
+                })
+                .to_string(),
+            );
+        }
+    }
+
+    info!("Vector painting finished ({} strokes)", strokes);
+    Ok(strokes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::artwork::value_objects::Polyline;
+    use crate::domain::controller::{ActionType, Button};
+    use crate::domain::painting::{VectorSettings, vector_plan};
+    use crate::domain::shared::value_objects::Coordinates;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    #[test]
+    fn test_vector_painting_runs_every_stroke() {
+        let mock = Arc::new(MockController::new().without_delays().with_command_log());
+        let settings = VectorSettings {
+            px_per_sec: 120.0,
+            tilt: 1.0,
+            pen_button: Button::A,
+            press_ms: 1,
+            release_ms: 1,
+            origin: Coordinates::origin(),
+            canvas_width: 320,
+            canvas_height: 180,
+        };
+        let paths = vec![
+            Polyline::new(vec![Coordinates::new(4, 4), Coordinates::new(40, 4)]),
+            Polyline::new(vec![Coordinates::new(8, 20)]),
+        ];
+        let plan = vector_plan(&paths, &settings).unwrap();
+
+        let strokes =
+            perform_vector_painting(mock.clone(), Arc::new(AtomicBool::new(false)), None, &plan)
+                .unwrap();
+
+        assert_eq!(strokes, 2);
+        assert_eq!(mock.recorded_commands(), plan.commands);
+        assert_eq!(mock.recorded_operations().a_presses, 2);
+        // ドット描画と違い十字キーは使わない
+        assert!(
+            mock.recorded_commands()
+                .iter()
+                .flat_map(|command| &command.sequence)
+                .all(|action| !matches!(action.action_type, ActionType::SetDPad(_)))
+        );
+
+        let stopped = perform_vector_painting(mock, Arc::new(AtomicBool::new(true)), None, &plan);
+        assert_eq!(stopped.unwrap(), 0);
+    }
+}
@@ -0,0 +1,400 @@
+//! アプリケーションのデータ（アートワーク・設定・描画履歴・監査ログ・アクセストークンなど）の初期化
+//!
+//! データディレクトリの中身だけを削除する。/boot・configfs・systemdのユニットには触れない
+//! （それらは `cleanup` の役割）。アプリケーションが作った目印（[`DATA_DIR_MARKER`]）の
+//! 無いディレクトリは、`--data-dir` の指定の誤りとみなして削除しない。
+
+use crate::infrastructure::data_dir::{DATA_DIR_MARKER, create_data_dir, is_marked};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// 初期化を実行するために指定する確認用の文字列
+pub const RESET_DATA_CONFIRMATION: &str = "reset-all-data";
+
+/// データディレクトリに指定できないディレクトリ（この配下も含む）
+const PROTECTED_DIRECTORIES: &[&str] = &[
+    "/boot", "/sys", "/proc", "/dev", "/etc", "/usr", "/bin", "/sbin", "/lib",
+];
+
+/// 初期化後に作り直すデータディレクトリのサブディレクトリ
+const DATA_SUBDIRECTORIES: &[&str] = &["tls"];
+
+#[derive(Debug, Error)]
+pub enum ResetDataError {
+    #[error("Refusing to reset {path}: it is not an application data directory")]
+    ProtectedDirectory { path: PathBuf },
+    #[error(
+        "Refusing to reset {path}: it has no {marker} marker, so it may not be an application data directory (create the file if it is)",
+        marker = DATA_DIR_MARKER
+    )]
+    NotMarked { path: PathBuf },
+    #[error("Data directory {path} is not a directory")]
+    NotADirectory { path: PathBuf },
+    #[error("Failed to read {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to remove {path} ({removed} entries already removed): {source}")]
+    Remove {
+        path: PathBuf,
+        removed: usize,
+        source: std::io::Error,
+    },
+    #[error("Failed to recreate data directory {path}: {source}")]
+    CreateDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+/// 削除したエントリーの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DataEntryKind {
+    File,
+    Directory,
+    /// シンボリックリンク自体（リンク先には触れない）
+    Symlink,
+}
+
+/// 削除したエントリー
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct RemovedDataEntry {
+    /// データディレクトリからの相対パス
+    pub path: String,
+    pub kind: DataEntryKind,
+    /// ファイルのサイズ（ディレクトリとシンボリックリンクは0）
+    pub bytes: u64,
+}
+
+/// 初期化の結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DataResetReport {
+    pub data_dir: String,
+    /// 削除したエントリー（ディレクトリは中身の後に並ぶ）
+    pub removed: Vec<RemovedDataEntry>,
+    /// 実行中のプロセスが使っているため削除しなかったエントリー
+    pub kept: Vec<String>,
+    /// 削除したファイルの合計サイズ
+    pub bytes_removed: u64,
+}
+
+/// データディレクトリの中身を削除し、空のディレクトリを作り直すユースケース
+#[derive(Debug, Clone)]
+pub struct ResetDataUseCase {
+    data_dir: PathBuf,
+    keep: Vec<String>,
+}
+
+impl ResetDataUseCase {
+    pub fn new(data_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            data_dir: data_dir.into(),
+            keep: Vec::new(),
+        }
+    }
+
+    /// データディレクトリ直下の `name` を削除しない（開いたままのデータベースなど、呼び出し側で中身を消すもの）
+    pub fn keeping(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.keep.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn data_dir(&self) -> &Path {
+        &self.data_dir
+    }
+
+    /// 削除の対象になるデータディレクトリ直下のエントリー（名前順、目印は含まない）
+    pub fn entries(&self) -> Result<Vec<String>, ResetDataError> {
+        let Some(root) = self.resolve_root()? else {
+            return Ok(Vec::new());
+        };
+        Ok(self
+            .top_level(&root)?
+            .into_iter()
+            .filter(|name| !self.keep.contains(name))
+            .collect())
+    }
+
+    pub fn execute(&self) -> Result<DataResetReport, ResetDataError> {
+        let mut report = DataResetReport {
+            data_dir: self.data_dir.display().to_string(),
+            removed: Vec::new(),
+            kept: Vec::new(),
+            bytes_removed: 0,
+        };
+        let root = match self.resolve_root()? {
+            Some(root) => {
+                for name in self.top_level(&root)? {
+                    if self.keep.contains(&name) {
+                        report.kept.push(name);
+                        continue;
+                    }
+                    remove_entry(&root, Path::new(&name), &mut report.removed)?;
+                }
+                root
+            }
+            None => {
+                create_data_dir(&self.data_dir).map_err(|source| {
+                    ResetDataError::CreateDirectory {
+                        path: self.data_dir.clone(),
+                        source,
+                    }
+                })?;
+                self.data_dir.clone()
+            }
+        };
+        for name in DATA_SUBDIRECTORIES {
+            let path = root.join(name);
+            fs::create_dir_all(&path)
+                .map_err(|source| ResetDataError::CreateDirectory { path, source })?;
+        }
+        report.bytes_removed = report.removed.iter().map(|entry| entry.bytes).sum();
+        Ok(report)
+    }
+
+    /// 実体のデータディレクトリ（存在しない場合は `None`）
+    ///
+    /// データディレクトリ自体へのシンボリックリンクは辿るが、指定したパスとリンク先のどちらかが
+    /// システムのディレクトリの場合と、目印の無いディレクトリの場合は拒否する。
+    fn resolve_root(&self) -> Result<Option<PathBuf>, ResetDataError> {
+        let requested =
+            std::path::absolute(&self.data_dir).map_err(|source| ResetDataError::Read {
+                path: self.data_dir.clone(),
+                source,
+            })?;
+        ensure_not_protected(&requested)?;
+        let root = match fs::canonicalize(&requested) {
+            Ok(root) => root,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(source) => {
+                return Err(ResetDataError::Read {
+                    path: self.data_dir.clone(),
+                    source,
+                });
+            }
+        };
+        ensure_not_protected(&root)?;
+        if !root.is_dir() {
+            return Err(ResetDataError::NotADirectory { path: root });
+        }
+        if !is_marked(&root) {
+            return Err(ResetDataError::NotMarked { path: root });
+        }
+        Ok(Some(root))
+    }
+
+    fn top_level(&self, root: &Path) -> Result<Vec<String>, ResetDataError> {
+        let mut names = read_names(root)?;
+        names.retain(|name| name != DATA_DIR_MARKER);
+        names.sort();
+        Ok(names)
+    }
+}
+
+fn ensure_not_protected(path: &Path) -> Result<(), ResetDataError> {
+    // `..` を含むパスは、存在しないと実体を確かめられないため受け付けない
+    let protected = path.parent().is_none()
+        || path
+            .components()
+            .any(|component| component == Component::ParentDir)
+        || PROTECTED_DIRECTORIES
+            .iter()
+            .any(|protected| path.starts_with(protected));
+    if protected {
+        return Err(ResetDataError::ProtectedDirectory {
+            path: path.to_path_buf(),
+        });
+    }
+    Ok(())
+}
+
+fn read_names(dir: &Path) -> Result<Vec<String>, ResetDataError> {
+    let read_error = |source| ResetDataError::Read {
+        path: dir.to_path_buf(),
+        source,
+    };
+    fs::read_dir(dir)
+        .map_err(read_error)?
+        .map(|entry| {
+            entry
+                .map(|entry| entry.file_name().to_string_lossy().into_owned())
+                .map_err(read_error)
+        })
+        .collect()
+}
+
+/// `root` 配下の `relative` を削除する
+///
+/// 種類は `symlink_metadata` で判定するため、シンボリックリンクはリンク自体だけを消し、
+/// リンク先のディレクトリには入らない。
+fn remove_entry(
+    root: &Path,
+    relative: &Path,
+    removed: &mut Vec<RemovedDataEntry>,
+) -> Result<(), ResetDataError> {
+    let path = root.join(relative);
+    let remove_error = |removed: &Vec<RemovedDataEntry>, source| ResetDataError::Remove {
+        path: path.clone(),
+        removed: removed.len(),
+        source,
+    };
+    let metadata = fs::symlink_metadata(&path).map_err(|source| remove_error(removed, source))?;
+    let file_type = metadata.file_type();
+    let (kind, bytes) = if file_type.is_symlink() {
+        fs::remove_file(&path).map_err(|source| remove_error(removed, source))?;
+        (DataEntryKind::Symlink, 0)
+    } else if file_type.is_dir() {
+        let mut names = read_names(&path)?;
+        names.sort();
+        for name in names {
+            remove_entry(root, &relative.join(name), removed)?;
+        }
+        fs::remove_dir(&path).map_err(|source| remove_error(removed, source))?;
+        (DataEntryKind::Directory, 0)
+    } else {
+        fs::remove_file(&path).map_err(|source| remove_error(removed, source))?;
+        (DataEntryKind::File, metadata.len())
+    };
+    removed.push(RemovedDataEntry {
+        path: relative.display().to_string(),
+        kind,
+        bytes,
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(prefix: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{prefix}-{}", uuid::Uuid::new_v4()))
+    }
+
+    #[test]
+    fn test_reset_removes_contents_and_keeps_listed_entries() {
+        let dir = temp_dir("reset-data");
+        create_data_dir(&dir).unwrap();
+        fs::create_dir_all(dir.join("tls")).unwrap();
+        fs::write(dir.join("auth-token"), "token").unwrap();
+        fs::write(dir.join("audit.log"), "{}\n").unwrap();
+        fs::write(dir.join("tls/cert.pem"), "cert").unwrap();
+        fs::write(dir.join("ghost-drawer.db"), "db").unwrap();
+
+        let use_case = ResetDataUseCase::new(&dir).keeping(["ghost-drawer.db"]);
+        assert_eq!(
+            use_case.entries().unwrap(),
+            ["audit.log", "auth-token", "tls"]
+        );
+
+        let report = use_case.execute().unwrap();
+        let removed: Vec<_> = report
+            .removed
+            .iter()
+            .map(|entry| (entry.path.as_str(), entry.kind))
+            .collect();
+        assert_eq!(
+            removed,
+            [
+                ("audit.log", DataEntryKind::File),
+                ("auth-token", DataEntryKind::File),
+                ("tls/cert.pem", DataEntryKind::File),
+                ("tls", DataEntryKind::Directory),
+            ]
+        );
+        assert_eq!(report.kept, ["ghost-drawer.db"]);
+        assert_eq!(report.bytes_removed, 3 + 5 + 4);
+        let mut names = read_names(&dir).unwrap();
+        names.sort();
+        assert_eq!(names, [DATA_DIR_MARKER, "ghost-drawer.db", "tls"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+        let report = ResetDataUseCase::new(&dir).execute().unwrap();
+        assert!(report.removed.is_empty());
+        assert!(
+            is_marked(&dir),
+            "the data directory is recreated with its marker"
+        );
+        assert!(dir.join("tls").is_dir());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_reset_does_not_follow_symlinks_out_of_the_directory() {
+        let dir = temp_dir("reset-data");
+        let outside = temp_dir("reset-data-outside");
+        create_data_dir(&dir).unwrap();
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("keep.txt"), "outside").unwrap();
+        std::os::unix::fs::symlink(&outside, dir.join("linked-dir")).unwrap();
+        std::os::unix::fs::symlink(outside.join("keep.txt"), dir.join("linked-file")).unwrap();
+
+        let report = ResetDataUseCase::new(&dir).execute().unwrap();
+        assert!(
+            report
+                .removed
+                .iter()
+                .all(|entry| entry.kind == DataEntryKind::Symlink)
+        );
+        assert_eq!(report.removed.len(), 2);
+        assert_eq!(ResetDataUseCase::new(&dir).entries().unwrap(), ["tls"]);
+        assert_eq!(
+            fs::read_to_string(outside.join("keep.txt")).unwrap(),
+            "outside"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+        fs::remove_dir_all(&outside).unwrap();
+    }
+
+    #[test]
+    fn test_reset_refuses_system_directories() {
+        for path in [
+            "/",
+            "/boot",
+            "/sys/kernel/config",
+            "/var/lib/../../boot/new",
+        ] {
+            let result = ResetDataUseCase::new(path).execute();
+            assert!(
+                matches!(result, Err(ResetDataError::ProtectedDirectory { .. })),
+                "{path} must be refused: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_reset_refuses_directories_without_the_marker() {
+        let dir = temp_dir("reset-data-home");
+        fs::create_dir_all(dir.join("Documents")).unwrap();
+        fs::write(dir.join(".bashrc"), "export PATH").unwrap();
+
+        let use_case = ResetDataUseCase::new(&dir);
+        for result in [
+            use_case.entries().map(|_| ()),
+            use_case.execute().map(|_| ()),
+        ] {
+            assert!(
+                matches!(result, Err(ResetDataError::NotMarked { .. })),
+                "an unmarked directory must be refused: {result:?}"
+            );
+        }
+        assert_eq!(
+            fs::read_to_string(dir.join(".bashrc")).unwrap(),
+            "export PATH"
+        );
+        assert!(dir.join("Documents").is_dir());
+
+        // 既にファイルのあるディレクトリは、データディレクトリとして使っても目印を置かない
+        create_data_dir(&dir).unwrap();
+        assert!(use_case.entries().is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
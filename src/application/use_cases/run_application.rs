@@ -1,4 +1,6 @@
-use crate::interfaces::web::server::create_server;
+use crate::infrastructure::mdns::MdnsAdvertisement;
+use crate::interfaces::web::server::{ServerConfig, create_server};
+use tracing::{info, warn};
 
 #[derive(Default)]
 pub struct RunApplicationUseCase {
@@ -10,8 +12,25 @@ impl RunApplicationUseCase {
         Self::default()
     }
 
-    pub async fn execute(&self, host: String, port: u16) -> anyhow::Result<()> {
+    pub async fn execute(&self, config: ServerConfig) -> anyhow::Result<()> {
+        // mDNSの告知に失敗してもIPアドレスで開けるので、警告だけにして起動を続ける
+        let advertisement = config.mdns_hostname.as_deref().and_then(|hostname| {
+            match MdnsAdvertisement::start(hostname, config.port, config.tls.is_some()) {
+                Ok(advertisement) => {
+                    info!("Advertising web UI over mDNS as {}", advertisement.url());
+                    Some(advertisement)
+                }
+                Err(e) => {
+                    warn!("mDNS advertisement disabled: {}", e);
+                    None
+                }
+            }
+        });
+
         // Delegate to the web server module
-        create_server(host, port).await
+        let result = create_server(config).await;
+        // 終了時に告知を取り消す
+        drop(advertisement);
+        result
     }
 }
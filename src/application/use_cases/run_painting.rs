@@ -0,0 +1,2216 @@
+//! 描画の実行
+//!
+//! 描画パスの順にコントローラーを操作する描画スレッドと、実行中の描画を停止・一時停止するための
+//! 制御、描画の終了時に実行記録とドメインイベントを確定する終了処理を提供する。
+
+use crate::application::controller_io::{
+    DPadTracker, debug_assert_blocking_allowed, tap_button_with_duration, tap_dpad_with_duration,
+};
+use crate::application::painting_trace::{SharedPaintingTrace, TraceOutcome, TraceRow};
+use crate::application::progress::{PROGRESS_CHANNEL, send_progress};
+use crate::domain::artwork::entities::ArtworkId;
+use crate::domain::controller::{
+    Button, ControllerAction, ControllerCommand, ControllerEmulator, DPad, StickPosition,
+};
+use crate::domain::events::ArtworkEvent;
+use crate::domain::hardware::errors::HardwareError;
+use crate::domain::painting::{
+    AdaptiveTimingController, CompletionReport, CompletionTracker, CorrectedEstimate,
+    DIRECTION_CHANGE_DELAY_MS, DRIFT_PAUSE_EVERY_DPAD_OPS, DRIFT_PAUSE_MS, DotOutcome,
+    DrawingCanvasConfig, DrawingPath, InitSequence, PaintTiming, PaintingRun,
+    PaintingRunRepository, PauseMode, PauseSettings, PreflightRejection, PreflightSettings,
+    RunOptions, RunOutcome,
+};
+use crate::domain::shared::events::EventMetadata;
+use crate::domain::shared::i18n::{Message, MessageKey};
+use crate::domain::shared::value_objects::Coordinates;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use tokio::sync::RwLock;
+use tracing::{debug, error, info, warn};
+
+#[derive(Clone)]
+pub struct PaintingControl {
+    pub stop_signal: Arc<AtomicBool>,
+    pub pause_signal: Arc<AtomicBool>,
+    pub repeats: Arc<AtomicU32>,
+    pub press_ms: Arc<AtomicU64>,
+    pub release_ms: Arc<AtomicU64>,
+    pub wait_ms: Arc<AtomicU64>,
+    /// 描画済みのドット数
+    pub painted: Arc<AtomicUsize>,
+    /// ドットごとの描画結果（`perform_painting` の開始時に作り直す）
+    pub completion: Arc<std::sync::Mutex<CompletionTracker>>,
+    /// 描画開始時の設定（キャリブレーションなどでは `None`）
+    pub config: Option<Arc<DrawingCanvasConfig>>,
+    /// 描画中のエラーを記録するイベントログ（未設定なら記録しない）
+    pub event_log: Option<PaintingEventLog>,
+    /// 描画開始時の見積もり（未設定なら `stats` で残り時間を通知しない）
+    pub estimate: Option<CorrectedEstimate>,
+    /// ドットごとのタイミングの記録（`PaintRequest.trace` で有効にした場合のみ）
+    pub trace: Option<SharedPaintingTrace>,
+    /// 実行ごとに割り当てる世代番号（停止・一時停止の対象の確認に使う）
+    pub generation: u64,
+    /// 描画スレッドが終了したか（停止・一時停止の受け付けと終了処理はこのロックで排他する）
+    finished: Arc<std::sync::Mutex<bool>>,
+    /// 停止要求を受けて描画スレッドが終了したか（以降コントローラーには何も送らない）
+    pub stop_acknowledged: Arc<AtomicBool>,
+    /// 描画スレッドが一時停止して待機しているか
+    pub pause_acknowledged: Arc<AtomicBool>,
+    /// 現在のカーソル位置（`x << 16 | y`、描画を始めるまでは `NO_CURSOR`）
+    cursor: Arc<AtomicU32>,
+    /// 描画前の確認の状態とユーザーの回答
+    preflight: Arc<std::sync::Mutex<PreflightGate>>,
+}
+
+/// 描画前の確認の進み具合
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PreflightPhase {
+    /// 確認用の印を描いている
+    DrawingMark,
+    /// ユーザーの回答を待っている
+    AwaitingConfirmation { deadline: DateTime<Utc> },
+    /// 確認され、印を片付けて描画に進んだ
+    Confirmed,
+    /// 拒否または時間切れで描画を中止した
+    Rejected(PreflightRejection),
+}
+
+/// 描画前の確認の状態（確認しない描画では `phase` は `None` のまま）
+#[derive(Debug, Default)]
+struct PreflightGate {
+    phase: Option<PreflightPhase>,
+    answer: Option<bool>,
+}
+
+/// カーソル位置がまだ分からないことを表す値
+const NO_CURSOR: u32 = u32::MAX;
+
+/// 次に開始する描画・キャリブレーションの世代番号
+static NEXT_GENERATION: AtomicU64 = AtomicU64::new(1);
+
+/// 描画中に発生したドメインイベントの記録先
+#[derive(Clone)]
+pub struct PaintingEventLog {
+    pub artwork_id: ArtworkId,
+    pub version: u32,
+    pub events: ArtworkEventLog,
+}
+
+/// 購読者へ配信できなかったイベントを溜めておく数（超えた分は購読者側で読み飛ばす）
+const EVENT_SUBSCRIBER_CAPACITY: usize = 256;
+
+/// アートワーク集約のドメインイベントログ（記録したイベントは購読者にも配信する）
+#[derive(Clone)]
+pub struct ArtworkEventLog {
+    events: Arc<RwLock<Vec<ArtworkEvent>>>,
+    subscribers: tokio::sync::broadcast::Sender<ArtworkEvent>,
+}
+
+impl Default for ArtworkEventLog {
+    fn default() -> Self {
+        Self {
+            events: Arc::new(RwLock::new(Vec::new())),
+            subscribers: tokio::sync::broadcast::channel(EVENT_SUBSCRIBER_CAPACITY).0,
+        }
+    }
+}
+
+impl ArtworkEventLog {
+    pub async fn push(&self, event: ArtworkEvent) {
+        self.events.write().await.push(event.clone());
+        // 購読者がいなくても記録はする
+        let _ = self.subscribers.send(event);
+    }
+
+    /// 非同期ランタイムの外（描画スレッドなど）から記録する
+    pub fn blocking_push(&self, event: ArtworkEvent) {
+        self.events.blocking_write().push(event.clone());
+        let _ = self.subscribers.send(event);
+    }
+
+    /// これまでに記録したイベント
+    pub async fn read(&self) -> tokio::sync::RwLockReadGuard<'_, Vec<ArtworkEvent>> {
+        self.events.read().await
+    }
+
+    /// 記録したイベントをすべて捨てる（購読者はそのまま）
+    pub async fn clear(&self) {
+        self.events.write().await.clear();
+    }
+
+    /// 以降に記録されるイベントを受け取る
+    pub fn subscribe(&self) -> tokio::sync::broadcast::Receiver<ArtworkEvent> {
+        self.subscribers.subscribe()
+    }
+}
+
+impl PaintingControl {
+    pub fn new(initial_repeats: u32, press_ms: u32, release_ms: u32, wait_ms: u32) -> Self {
+        Self {
+            stop_signal: Arc::new(AtomicBool::new(false)),
+            pause_signal: Arc::new(AtomicBool::new(false)),
+            repeats: Arc::new(AtomicU32::new(initial_repeats)),
+            press_ms: Arc::new(AtomicU64::new(press_ms as u64)),
+            release_ms: Arc::new(AtomicU64::new(release_ms as u64)),
+            wait_ms: Arc::new(AtomicU64::new(wait_ms as u64)),
+            painted: Arc::new(AtomicUsize::new(0)),
+            completion: Arc::new(std::sync::Mutex::new(CompletionTracker::new(0))),
+            config: None,
+            event_log: None,
+            estimate: None,
+            trace: None,
+            generation: NEXT_GENERATION.fetch_add(1, Ordering::SeqCst),
+            finished: Arc::new(std::sync::Mutex::new(false)),
+            stop_acknowledged: Arc::new(AtomicBool::new(false)),
+            pause_acknowledged: Arc::new(AtomicBool::new(false)),
+            cursor: Arc::new(AtomicU32::new(NO_CURSOR)),
+            preflight: Arc::new(std::sync::Mutex::new(PreflightGate::default())),
+        }
+    }
+
+    pub fn with_event_log(mut self, event_log: PaintingEventLog) -> Self {
+        self.event_log = Some(event_log);
+        self
+    }
+
+    pub fn with_estimate(mut self, estimate: CorrectedEstimate) -> Self {
+        self.estimate = Some(estimate);
+        self
+    }
+
+    pub fn with_trace(mut self, trace: SharedPaintingTrace) -> Self {
+        self.trace = Some(trace);
+        self
+    }
+
+    /// 描画設定のタイミングと繰り返し回数で制御を開始する
+    pub fn from_config(config: &DrawingCanvasConfig) -> Self {
+        let timing = config.timing;
+        Self {
+            config: Some(Arc::new(config.clone())),
+            ..Self::new(
+                config.options.repeats,
+                timing.press_ms,
+                timing.release_ms,
+                timing.wait_ms,
+            )
+        }
+    }
+
+    /// 描画スレッドから現在のカーソル位置を記録する
+    pub(crate) fn set_cursor(&self, position: Coordinates) {
+        self.cursor.store(
+            (position.x as u32) << 16 | position.y as u32,
+            Ordering::Relaxed,
+        );
+    }
+
+    /// 現在のカーソル位置（描画を始めるまでは `None`）
+    pub fn cursor(&self) -> Option<Coordinates> {
+        let packed = self.cursor.load(Ordering::Relaxed);
+        (packed != NO_CURSOR).then(|| Coordinates::new((packed >> 16) as u16, packed as u16))
+    }
+
+    fn lock_finished(&self) -> std::sync::MutexGuard<'_, bool> {
+        self.finished.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 描画スレッドが終了したか
+    pub fn is_finished(&self) -> bool {
+        *self.lock_finished()
+    }
+
+    /// 実行中の描画に停止を指示する（既に終了していて届かなかった場合は `false`）
+    ///
+    /// 終了処理と排他するため、`true` を返した停止要求は必ず描画スレッドの終了時に受領される。
+    pub fn request_stop(&self) -> bool {
+        let finished = self.lock_finished();
+        if *finished {
+            return false;
+        }
+        self.stop_signal.store(true, Ordering::SeqCst);
+        true
+    }
+
+    /// 一時停止を指定した状態にする（`None` なら切り替える）
+    ///
+    /// 変更後の状態を返す。既に終了していて届かなかった場合は `None`。
+    pub fn request_pause(&self, paused: Option<bool>) -> Option<bool> {
+        let finished = self.lock_finished();
+        if *finished {
+            return None;
+        }
+        // 連続した切り替えが同じ値を読んで打ち消し合わないよう、読み書きを1回の操作で行う
+        Some(match paused {
+            Some(paused) => {
+                self.pause_signal.store(paused, Ordering::SeqCst);
+                paused
+            }
+            None => !self.pause_signal.fetch_xor(true, Ordering::SeqCst),
+        })
+    }
+
+    /// 描画前の確認の状態（確認しない描画では `None`）
+    pub fn preflight_phase(&self) -> Option<PreflightPhase> {
+        self.lock_preflight().phase
+    }
+
+    /// 描画前の確認に回答する
+    ///
+    /// 回答を待っている間だけ受け付け、それ以外（確認しない描画、印の描画中、回答済み）は `false` を返す。
+    pub fn answer_preflight(&self, confirmed: bool) -> bool {
+        let mut preflight = self.lock_preflight();
+        if !matches!(
+            preflight.phase,
+            Some(PreflightPhase::AwaitingConfirmation { .. })
+        ) || preflight.answer.is_some()
+        {
+            return false;
+        }
+        preflight.answer = Some(confirmed);
+        true
+    }
+
+    /// 描画スレッドが回答を受け取って確認の待機を終えるまで待つ
+    pub async fn wait_for_preflight_answer(&self, timeout: std::time::Duration) -> bool {
+        self.wait_until(timeout, || {
+            !matches!(
+                self.preflight_phase(),
+                Some(PreflightPhase::AwaitingConfirmation { .. })
+            ) || self.is_finished()
+        })
+        .await
+    }
+
+    fn set_preflight_phase(&self, phase: PreflightPhase) {
+        self.lock_preflight().phase = Some(phase);
+    }
+
+    fn lock_preflight(&self) -> std::sync::MutexGuard<'_, PreflightGate> {
+        self.preflight.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 描画スレッドの終了を記録する（停止要求を受けていれば受領済みにする）
+    ///
+    /// 以降の停止・一時停止は届かなかったものとして扱う。
+    pub fn mark_finished(&self) {
+        let mut finished = self.lock_finished();
+        *finished = true;
+        if self.stop_signal.load(Ordering::SeqCst) {
+            self.stop_acknowledged.store(true, Ordering::SeqCst);
+        }
+        self.pause_acknowledged.store(false, Ordering::SeqCst);
+    }
+
+    /// 停止要求が受領されるまで最大 `timeout` 待つ（受領されたら `true`）
+    pub async fn wait_for_stop(&self, timeout: std::time::Duration) -> bool {
+        self.wait_until(timeout, || self.stop_acknowledged.load(Ordering::SeqCst))
+            .await
+    }
+
+    /// 描画スレッドの待機状態が `paused` になるか、描画が終了するまで最大 `timeout` 待つ
+    pub async fn wait_for_pause(&self, paused: bool, timeout: std::time::Duration) -> bool {
+        self.wait_until(timeout, || {
+            self.pause_acknowledged.load(Ordering::SeqCst) == paused || self.is_finished()
+        })
+        .await
+    }
+
+    async fn wait_until(&self, timeout: std::time::Duration, done: impl Fn() -> bool) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while !done() {
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        true
+    }
+
+    /// 実行中に変更されたタイミングと繰り返し回数を反映した設定
+    pub fn effective_config(&self) -> Option<DrawingCanvasConfig> {
+        self.config.as_deref().map(|config| {
+            let mut config = config.clone();
+            config.timing = current_timing(self);
+            config.options.repeats = self.repeats.load(Ordering::Relaxed);
+            config
+        })
+    }
+
+    /// ドットの描画の失敗を `PaintingErrorOccurred` として記録する（`attempt` は失敗した試行の番号）
+    ///
+    /// 描画スレッドから呼ぶため、非同期ランタイムの外でのみ使う。
+    fn record_dot_error(&self, coords: Coordinates, error: &HardwareError, attempt: u32) {
+        let Some(log) = &self.event_log else {
+            return;
+        };
+        let event = ArtworkEvent::painting_error_occurred(
+            log.artwork_id.clone(),
+            Some(coords),
+            error.to_string(),
+            attempt,
+            log.version,
+            EventMetadata::new("painting".to_string()),
+        );
+        log.events.blocking_push(event);
+    }
+
+    /// 描画を終了して、ドットごとの結果の集計を確定する
+    pub fn finish_report(&self) -> CompletionReport {
+        let mut completion = self.completion.lock().unwrap_or_else(|e| e.into_inner());
+        completion.finish();
+        completion.report()
+    }
+}
+
+/// 描画スレッドの終了処理
+///
+/// 正常終了・停止・エラー・パニックのいずれの場合も、ドロップ時に実行記録を確定して保存する。
+/// エラーやパニックで中断した場合は、ボタンが押されたままにならないようコントローラーをニュートラルに戻す。
+pub struct PaintingRunGuard {
+    controller: Arc<dyn ControllerEmulator>,
+    control: PaintingControl,
+    runs: Arc<dyn PaintingRunRepository>,
+    run: PaintingRun,
+    outcome: Option<RunOutcome>,
+}
+
+impl PaintingRunGuard {
+    pub fn new(
+        controller: Arc<dyn ControllerEmulator>,
+        control: PaintingControl,
+        runs: Arc<dyn PaintingRunRepository>,
+        run: PaintingRun,
+    ) -> Self {
+        Self {
+            controller,
+            control,
+            runs,
+            run,
+            outcome: None,
+        }
+    }
+
+    /// `perform_painting` の結果から終了理由を決める
+    pub fn finish(&mut self, result: &Result<CompletionReport, HardwareError>) {
+        self.outcome = Some(match result {
+            Ok(report) => report.outcome(),
+            Err(e) => RunOutcome::Error {
+                message: e.to_string(),
+            },
+        });
+    }
+}
+
+impl Drop for PaintingRunGuard {
+    fn drop(&mut self) {
+        let outcome = self.outcome.take().unwrap_or_else(|| RunOutcome::Error {
+            message: "Painting thread panicked".to_string(),
+        });
+
+        if matches!(outcome, RunOutcome::Error { .. })
+            && let Err(e) = tap_dpad_with_duration(
+                &self.controller,
+                DPad::NEUTRAL,
+                "Final Reset on Error",
+                100,
+                100,
+                0,
+            )
+        {
+            warn!("Failed to reset controller after painting error: {}", e);
+        }
+
+        let report = self.control.finish_report();
+        self.run
+            .finish(self.control.painted.load(Ordering::SeqCst), outcome);
+        info!(
+            "Painting run {} finished: {:?}, {}, {:.1} dots/min",
+            self.run.id,
+            self.run.outcome,
+            report.summary(),
+            self.run.dots_per_minute()
+        );
+        for dot in &report.skipped_dots {
+            warn!("Skipped dot ({}, {}): {}", dot.x, dot.y, dot.error);
+        }
+        send_completion_report(&report, self.run.outcome.as_ref());
+        if let (Some(log), Some(outcome)) = (&self.control.event_log, &self.run.outcome) {
+            log.events
+                .blocking_push(run_finished_event(&self.run, outcome, log));
+        }
+        self.run.report = Some(report);
+        self.runs.save(&self.run);
+        if let Some(trace) = &self.control.trace {
+            trace.lock().unwrap_or_else(|e| e.into_inner()).finish();
+        }
+    }
+}
+
+/// 描画の終了理由に応じたドメインイベント（完了・停止・エラー）
+fn run_finished_event(
+    run: &PaintingRun,
+    outcome: &RunOutcome,
+    log: &PaintingEventLog,
+) -> ArtworkEvent {
+    let metadata = EventMetadata::new("painting".to_string());
+    let artwork_id = log.artwork_id.clone();
+    match outcome {
+        RunOutcome::Completed | RunOutcome::CompletedWithErrors { .. } => {
+            ArtworkEvent::painting_completed(
+                artwork_id,
+                run.dots_painted,
+                run.duration_millis().unwrap_or(0) / 1000,
+                log.version,
+                metadata,
+            )
+        }
+        RunOutcome::Stopped
+        | RunOutcome::LimitReached { .. }
+        | RunOutcome::PreflightDeclined
+        | RunOutcome::PreflightTimedOut => ArtworkEvent::painting_cancelled(
+            artwork_id,
+            run.dots_painted,
+            run.dots_painted as f64 / run.dots_attempted.max(1) as f64,
+            outcome.kind().to_string(),
+            log.version,
+            metadata,
+        ),
+        RunOutcome::Error { message } => ArtworkEvent::painting_error_occurred(
+            artwork_id,
+            None,
+            message.clone(),
+            0,
+            log.version,
+            metadata,
+        ),
+    }
+}
+
+/// 描画中のカーソル位置と操作回数
+struct CursorState {
+    position: Coordinates,
+    dpad_operations: u32,
+    a_button_presses: u32,
+    /// 進捗メッセージの直列化に使い回す作業領域
+    progress_buffer: Vec<u8>,
+}
+
+/// ドットごとに送る進捗メッセージ
+#[derive(Serialize)]
+struct ProgressMessage {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    current: usize,
+    total: usize,
+    x: u16,
+    y: u16,
+    dpad_operations: u32,
+    a_button_presses: u32,
+    is_paint: bool,
+}
+
+impl CursorState {
+    fn new() -> Self {
+        Self {
+            position: Coordinates::origin(),
+            dpad_operations: 0,
+            a_button_presses: 0,
+            progress_buffer: Vec::with_capacity(256),
+        }
+    }
+
+    /// 進捗メッセージを作成（1ドットにつき数回呼ばれるため、確保は送信する文字列のみ）
+    fn progress_message(&mut self, current: usize, total: usize, is_paint: bool) -> String {
+        let message = ProgressMessage {
+            kind: "progress",
+            current,
+            total,
+            x: self.position.x,
+            y: self.position.y,
+            dpad_operations: self.dpad_operations,
+            a_button_presses: self.a_button_presses,
+            is_paint,
+        };
+        self.progress_buffer.clear();
+        serde_json::to_writer(&mut self.progress_buffer, &message)
+            .expect("progress message is always serializable");
+        String::from_utf8_lossy(&self.progress_buffer).into_owned()
+    }
+}
+
+/// 現在のタイミング設定を読み込む
+fn current_timing(control: &PaintingControl) -> PaintTiming {
+    PaintTiming::new(
+        control.press_ms.load(Ordering::Relaxed) as u32,
+        control.release_ms.load(Ordering::Relaxed) as u32,
+        control.wait_ms.load(Ordering::Relaxed) as u32,
+    )
+}
+
+/// 一時停止中に再開・停止の要求を確認する間隔
+const PAUSE_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// スリープ防止の入力で左スティックを傾ける位置（ゲーム内の不感帯に収まり、カーソルは動かない）
+const KEEPALIVE_STICK: StickPosition = StickPosition { x: 136, y: 128 };
+
+/// 描画スレッドが一時停止の待機に入っている間、`pause_acknowledged` を立てておく
+///
+/// 待機していた時間は、見積もりとの比較から除けるよう描画結果に記録する。
+struct PauseAcknowledgement<'a> {
+    control: &'a PaintingControl,
+    since: std::time::Instant,
+}
+
+impl<'a> PauseAcknowledgement<'a> {
+    fn new(control: &'a PaintingControl) -> Self {
+        control.pause_acknowledged.store(true, Ordering::SeqCst);
+        Self {
+            control,
+            since: std::time::Instant::now(),
+        }
+    }
+}
+
+impl Drop for PauseAcknowledgement<'_> {
+    fn drop(&mut self) {
+        self.control
+            .completion
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .add_paused(self.since.elapsed());
+        self.control
+            .pause_acknowledged
+            .store(false, Ordering::SeqCst);
+    }
+}
+
+/// 一時停止中に本体がスリープしないよう、左スティックをわずかに傾けて戻す
+///
+/// Switchが既にスリープしている場合などの送信エラーは、描画を中断せずに記録だけする
+fn send_keepalive(controller: &Arc<dyn ControllerEmulator>, idle: std::time::Duration) {
+    info!(
+        "Sending keepalive input after {:.1}s paused without input",
+        idle.as_secs_f64()
+    );
+    let keepalive = ControllerCommand::new("Keepalive")
+        .with_description("一時停止中のスリープ防止")
+        .add_action(ControllerAction::move_left_stick(KEEPALIVE_STICK, 50))
+        .add_action(ControllerAction::move_left_stick(StickPosition::CENTER, 50));
+    if let Err(e) = controller.execute_command(&keepalive) {
+        warn!("Failed to send keepalive input: {}", e);
+    }
+}
+
+/// 一時停止が要求されていれば、再開されるまで待機する
+///
+/// 停止要求を受けた場合は `Ok(false)` を返す。
+/// `Safe` モードでは待機前に十字キーをニュートラルに戻し、
+/// `rehome_on_resume` が有効なら再開時に左上へ戻ってカーソル位置をリセットする。
+/// `keepalive_idle_ms` が設定されていれば、待機中にその間隔でスリープ防止の入力を送る。
+fn wait_while_paused(
+    controller: &Arc<dyn ControllerEmulator>,
+    control: &PaintingControl,
+    cursor: &mut CursorState,
+    pause: PauseSettings,
+    next_index: usize,
+) -> Result<bool, HardwareError> {
+    if !control.pause_signal.load(Ordering::SeqCst) {
+        return Ok(true);
+    }
+
+    if pause.mode == PauseMode::Safe {
+        tap_dpad_with_duration(
+            controller,
+            DPad::NEUTRAL,
+            "Clear DPad Before Pause",
+            10,
+            10,
+            0,
+        )?;
+    }
+    info!(
+        "Painting paused at {} (next dot index: {})",
+        cursor.position, next_index
+    );
+    let _ = PROGRESS_CHANNEL.send(
+        serde_json::json!({
+            "type": "paused_at",
+            "mode": pause.mode,
+            "x": cursor.position.x,
+            "y": cursor.position.y,
+            "next_index": next_index
+        })
+        .to_string(),
+    );
+
+    let keepalive_idle = pause
+        .keepalive_idle_ms
+        .map(|idle_ms| std::time::Duration::from_millis(idle_ms as u64));
+    let mut last_input = std::time::Instant::now();
+    let waiting = PauseAcknowledgement::new(control);
+    while control.pause_signal.load(Ordering::SeqCst) {
+        if control.stop_signal.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        if keepalive_idle.is_some_and(|idle| last_input.elapsed() >= idle) {
+            send_keepalive(controller, last_input.elapsed());
+            last_input = std::time::Instant::now();
+        }
+        std::thread::sleep(PAUSE_POLL_INTERVAL.min(keepalive_idle.unwrap_or(PAUSE_POLL_INTERVAL)));
+    }
+    drop(waiting);
+
+    if pause.mode == PauseMode::Safe && pause.rehome_on_resume {
+        info!("Re-homing before resuming...");
+        move_home(controller, &control.stop_signal)?;
+        cursor.position = Coordinates::origin();
+    }
+    info!("Painting resumed");
+    Ok(true)
+}
+
+/// カーソルを目標座標まで十字キーで移動する
+///
+/// 停止要求を受けた場合は `Ok(false)` を返す。
+/// `PauseMode::Immediate` では十字キー1回分の入力ごとに一時停止を受け付ける。
+/// 移動手順と待機時間は `simulate_run` の見積もりと一致させている。
+fn move_cursor_to(
+    controller: &Arc<dyn ControllerEmulator>,
+    control: &PaintingControl,
+    cursor: &mut CursorState,
+    target: Coordinates,
+    options: &RunOptions,
+    timing: PaintTiming,
+    mut on_step: impl FnMut(&mut CursorState),
+) -> Result<bool, HardwareError> {
+    let mut previous: Option<DPad> = None;
+
+    for step in cursor.position.steps_to(&target, options.diagonal_moves) {
+        if control.stop_signal.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        if options.pause.mode == PauseMode::Immediate {
+            let next_index = control.painted.load(Ordering::SeqCst);
+            if !wait_while_paused(controller, control, cursor, options.pause, next_index)? {
+                return Ok(false);
+            }
+        }
+
+        // Direction change delay
+        if previous.is_some_and(|p| p != step) {
+            std::thread::sleep(std::time::Duration::from_millis(DIRECTION_CHANGE_DELAY_MS));
+        }
+
+        tap_dpad_with_duration(
+            controller,
+            step,
+            &format!("Move {}", step.name()),
+            timing.press_ms,
+            timing.release_ms,
+            timing.wait_ms as u64,
+        )?;
+        cursor.dpad_operations += 1;
+        let (dx, dy) = step.offset();
+        cursor.position = cursor.position.move_by(dx, dy).unwrap_or(cursor.position);
+        on_step(cursor);
+
+        // Periodic delay for long movements to prevent drift
+        if (cursor.dpad_operations as u64).is_multiple_of(DRIFT_PAUSE_EVERY_DPAD_OPS) {
+            std::thread::sleep(std::time::Duration::from_millis(DRIFT_PAUSE_MS));
+        }
+        previous = Some(step);
+    }
+
+    Ok(true)
+}
+
+/// 左スティックでカーソルを左上(0, 0)へ戻す
+///
+/// Switch-Fightstickは最小位置のスティック入力を約250フレーム（約4秒）行うため、
+/// 確実に端へ到達するよう5秒間入力してから待機する。
+/// 所要時間はシミュレーションの `REHOME_MS` と一致させること。
+/// `cancel` が立つとスティック入力の途中でも打ち切る。
+fn move_home(
+    controller: &Arc<dyn ControllerEmulator>,
+    cancel: &AtomicBool,
+) -> Result<(), HardwareError> {
+    // StickPosition: x=0 is LEFT, y=0 is UP, so (0,0) moves to top-left
+    let move_home_cmd = ControllerCommand::new("Move Home Left Stick")
+        .add_action(ControllerAction::move_left_stick(
+            StickPosition::new(0, 0),
+            5000,
+        ))
+        .add_action(ControllerAction::move_left_stick(
+            StickPosition::CENTER,
+            100,
+        ));
+    controller.execute_command_cancellable(&move_home_cmd, cancel)?;
+    if cancel.load(Ordering::SeqCst) {
+        info!("Moving home was cancelled");
+        return Ok(());
+    }
+    info!("Home position reached (0, 0)");
+
+    // Wait before starting dot painting
+    std::thread::sleep(std::time::Duration::from_millis(500));
+    Ok(())
+}
+
+/// 描画前の初期化手順を1手順ずつ実行する
+///
+/// 手順の間と、スティックの保持や待機の途中で停止要求を確認し、停止した場合は `Ok(false)` を返す。
+pub(crate) fn run_init_sequence(
+    controller: &Arc<dyn ControllerEmulator>,
+    sequence: &InitSequence,
+    cancel: &AtomicBool,
+    send_status: impl Fn(&Message),
+) -> Result<bool, HardwareError> {
+    let commands = sequence
+        .step_commands()
+        .map_err(|e| HardwareError::InvalidParameter(e.to_string()))?;
+    for (step, command) in sequence.steps.iter().zip(commands) {
+        if cancel.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        if let Some(description) = &command.description {
+            info!("{}: {}", command.name, description);
+        }
+        send_status(&step.message());
+        controller.execute_command_cancellable(&command, cancel)?;
+    }
+    Ok(!cancel.load(Ordering::SeqCst))
+}
+
+/// Switchの応答が止まってから猶予時間内に、描画をやり直すまでの待ち時間
+const HOST_RETRY_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+/// 自動一時停止中に `is_connected()` で復帰を確認する間隔
+const HOST_PROBE_INTERVAL: std::time::Duration = std::time::Duration::from_millis(500);
+/// 描画リクエストで指定できる1ドットの描画を試す回数の上限
+pub const MAX_DOT_ATTEMPTS_LIMIT: u32 = 10;
+/// 一時的な送信エラーでドットをやり直すまでの待ち時間（試行ごと、最後の値を繰り返す）
+const DOT_RETRY_BACKOFF: [std::time::Duration; 3] = [
+    std::time::Duration::from_millis(100),
+    std::time::Duration::from_millis(300),
+    std::time::Duration::from_secs(1),
+];
+/// 連続してスキップすると描画を中断するドット数（切断などで全ドットが失敗する場合）
+const MAX_CONSECUTIVE_SKIPPED_DOTS: u32 = 10;
+
+/// 描画終了時に、ドットごとの結果の集計を進捗チャンネルへ送る
+fn send_completion_report(report: &CompletionReport, outcome: Option<&RunOutcome>) {
+    let _ = PROGRESS_CHANNEL.send(
+        serde_json::json!({
+            "type": "completion_report",
+            "reason": outcome.map(RunOutcome::kind),
+            "outcome": outcome,
+            "report": report
+        })
+        .to_string(),
+    );
+}
+
+/// やり直せば成功する可能性がある送信エラーか（書き込みの失敗や一時的な切断）
+fn is_transient_dot_error(error: &HardwareError) -> bool {
+    matches!(
+        error,
+        HardwareError::IoError(_) | HardwareError::Other { .. } | HardwareError::NotConnected
+    )
+}
+
+/// ドット1つ分の移動と描画（`dot_button` を繰り返し回数だけ押す。消しゴム以外はAボタン）
+///
+/// 停止要求を受けた場合は `Ok(false)` を返す。途中でエラーになった場合は呼び出し側でやり直せるよう、
+/// カーソル位置は実際に送信できた移動の分だけ進め、押せたボタンの回数を `presses_done` に残す。
+#[allow(clippy::too_many_arguments)]
+fn paint_dot(
+    controller: &Arc<dyn ControllerEmulator>,
+    dpad: &DPadTracker,
+    control: &PaintingControl,
+    cursor: &mut CursorState,
+    coords: Coordinates,
+    options: &RunOptions,
+    dot_button: Button,
+    mut adaptive: Option<&mut AdaptiveTimingController>,
+    (index, total_dots): (usize, usize),
+    presses_done: &mut u32,
+    mut timings: Option<&mut DotTimings>,
+) -> Result<bool, HardwareError> {
+    let timing = current_timing(control);
+    let move_started = timings.is_some().then(std::time::Instant::now);
+
+    // Move to the target dot, sending an update every step for smooth preview
+    let reached = move_cursor_to(
+        controller,
+        control,
+        cursor,
+        coords,
+        options,
+        timing,
+        |cursor| {
+            control.set_cursor(cursor.position);
+            let _ = PROGRESS_CHANNEL.send(cursor.progress_message(index + 1, total_dots, false));
+        },
+    )?;
+    if !reached {
+        return Ok(false);
+    }
+
+    // Send cursor move update (only once per dot to avoid flooding)
+    let _ = PROGRESS_CHANNEL.send(cursor.progress_message(index + 1, total_dots, false));
+
+    // D-pad状態を完全にクリア（描画前）。ニュートラルと分かっている場合は設定の間隔でだけ送る
+    if options.neutral_clear.forced_at(index as u64) || dpad.last_dpad() != Some(DPad::NEUTRAL) {
+        tap_dpad_with_duration(
+            controller,
+            DPad::NEUTRAL,
+            "Clear DPad Before Paint",
+            10,
+            10,
+            0,
+        )?;
+    }
+    if let (Some(timings), Some(started)) = (timings.as_deref_mut(), move_started) {
+        timings.move_us += started.elapsed().as_micros() as u64;
+        timings.planned = timing;
+    }
+
+    // Paint Dot (Press A, or B for the eraser) - Repeat as requested
+    let current_repeats = control.repeats.load(Ordering::SeqCst);
+    // やり直しの場合は、前の試行で押せた分を押し直さない
+    for r in *presses_done..current_repeats {
+        if control.stop_signal.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+        let wait_ms = adaptive
+            .as_deref()
+            .map_or(timing.wait_ms, AdaptiveTimingController::wait_ms);
+        let started = std::time::Instant::now();
+        tap_button_with_duration(
+            controller,
+            dot_button,
+            &format!("Paint Dot {}/{}", r + 1, current_repeats),
+            timing.press_ms,
+            timing.release_ms,
+            0,
+        )?;
+        // 押下・解放の指定時間を超えた分を書き込み遅延とみなす
+        let expected =
+            std::time::Duration::from_millis(timing.press_ms as u64 + timing.release_ms as u64);
+        let press_release = started.elapsed();
+        if let Some(adaptive) = adaptive.as_deref_mut() {
+            adaptive.observe(press_release.saturating_sub(expected), timing.wait_ms);
+        }
+        let wait_started = timings.is_some().then(std::time::Instant::now);
+        if wait_ms > 0 {
+            std::thread::sleep(std::time::Duration::from_millis(wait_ms as u64));
+        }
+        if let (Some(timings), Some(wait_started)) = (timings.as_deref_mut(), wait_started) {
+            timings.press_release_us += press_release.as_micros() as u64;
+            timings.wait_us += wait_started.elapsed().as_micros() as u64;
+            timings.planned.wait_ms = wait_ms;
+            timings.presses += 1;
+        }
+        cursor.a_button_presses += 1;
+        *presses_done += 1;
+    }
+    Ok(true)
+}
+
+/// 描画の記録用に `paint_dot` で測る、ドット1つ分の実際の時間（やり直した場合は全試行の合計）
+#[derive(Debug, Clone, Copy, Default)]
+struct DotTimings {
+    /// 最後の試行で使った予定の時間（`wait_ms` は自動調整後の値）
+    planned: PaintTiming,
+    presses: u32,
+    move_us: u64,
+    press_release_us: u64,
+    wait_us: u64,
+}
+
+impl DotTimings {
+    fn row(&self, index: usize, coords: Coordinates, outcome: &DotOutcome) -> TraceRow {
+        let (attempts, outcome) = match outcome {
+            DotOutcome::Painted => (1, TraceOutcome::Painted),
+            DotOutcome::Retried { attempts } => (*attempts, TraceOutcome::Retried),
+            DotOutcome::Skipped { attempts, .. } => (*attempts, TraceOutcome::Skipped),
+        };
+        TraceRow {
+            index: index as u32,
+            x: coords.x,
+            y: coords.y,
+            planned_press_ms: self.planned.press_ms,
+            planned_release_ms: self.planned.release_ms,
+            planned_wait_ms: self.planned.wait_ms,
+            presses: self.presses,
+            move_us: self.move_us,
+            press_release_us: self.press_release_us,
+            wait_us: self.wait_us,
+            attempts,
+            outcome,
+        }
+    }
+}
+
+/// Switchがレポートを受け取らなくなった（スリープなど）ときの回復処理
+///
+/// 応答しなくなってから `host_grace_ms` 以内なら少し待って `Ok(true)` を返し、呼び出し側でやり直させる。
+/// 猶予を超えたら自動で一時停止し、`is_connected()` で復帰を確認し続ける。
+/// 復帰後も `auto_resume` でなければユーザーが再開するまで待つ（ゲームを開き直す必要がある場合があるため）。
+/// 再開時はカーソル位置が分からないため左上へ戻る。停止要求を受けた場合は `Ok(false)` を返す。
+fn recover_from_unresponsive_host(
+    controller: &Arc<dyn ControllerEmulator>,
+    control: &PaintingControl,
+    cursor: &mut CursorState,
+    pause: PauseSettings,
+    next_index: usize,
+) -> Result<bool, HardwareError> {
+    let grace = std::time::Duration::from_millis(pause.host_grace_ms as u64);
+
+    loop {
+        let unresponsive_for = controller.host_unresponsive_for().unwrap_or_default();
+        if unresponsive_for < grace {
+            std::thread::sleep(HOST_RETRY_INTERVAL);
+            return Ok(!control.stop_signal.load(Ordering::SeqCst));
+        }
+
+        warn!(
+            "Switch has not accepted reports for {:?}; pausing painting (next dot index: {})",
+            unresponsive_for, next_index
+        );
+        control.pause_signal.store(true, Ordering::SeqCst);
+        let _ = PROGRESS_CHANNEL.send(
+            serde_json::json!({
+                "type": "paused_at",
+                "paused_reason": "host_unresponsive",
+                "mode": pause.mode,
+                "x": cursor.position.x,
+                "y": cursor.position.y,
+                "next_index": next_index,
+                "auto_resume": pause.auto_resume
+            })
+            .to_string(),
+        );
+
+        let mut recovered = false;
+        let waiting = PauseAcknowledgement::new(control);
+        while control.pause_signal.load(Ordering::SeqCst) {
+            if control.stop_signal.load(Ordering::SeqCst) {
+                return Ok(false);
+            }
+            if !recovered
+                && controller.is_connected().unwrap_or(false)
+                && controller.host_unresponsive_for().is_none()
+            {
+                recovered = true;
+                info!("Switch is accepting reports again");
+                let _ = PROGRESS_CHANNEL.send(
+                    serde_json::json!({
+                        "type": "host_recovered",
+                        "auto_resume": pause.auto_resume
+                    })
+                    .to_string(),
+                );
+                if pause.auto_resume {
+                    control.pause_signal.store(false, Ordering::SeqCst);
+                    break;
+                }
+            }
+            std::thread::sleep(HOST_PROBE_INTERVAL);
+        }
+        drop(waiting);
+        if control.stop_signal.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+
+        // ユーザーが復帰前に再開した場合は、左上への移動に失敗して再び一時停止する
+        info!("Re-homing after the Switch was unresponsive...");
+        match move_home(controller, &control.stop_signal) {
+            Ok(()) => {
+                cursor.position = Coordinates::origin();
+                info!("Painting resumed");
+                return Ok(!control.stop_signal.load(Ordering::SeqCst));
+            }
+            Err(e) if e.is_host_unresponsive() => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// 描画前の確認の回答を確認する間隔
+const PREFLIGHT_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(50);
+
+/// 描画前の確認の結果
+enum PreflightResult {
+    /// 確認された。印のうちアートワークのドットとしてそのまま残したもの（ゲーム内キャンバスの座標）
+    Confirmed(HashSet<Coordinates>),
+    /// 拒否または時間切れ（理由は描画の完了状況に記録済み）
+    Rejected,
+    /// 確認中に停止要求を受けた
+    Stopped,
+}
+
+/// 確認用の印を描いてユーザーの回答を待ち、確認されたら印を片付ける
+///
+/// 印のドットがアートワークで描くドットと同じなら（Aで描く場合のみ）そのまま残し、それ以外はBで消す。
+/// 拒否・時間切れの場合は、カーソルがキャンバス上に無いおそれがあるため印を消さずに戻る。
+fn run_preflight(
+    controller: &Arc<dyn ControllerEmulator>,
+    control: &PaintingControl,
+    cursor: &mut CursorState,
+    config: &DrawingCanvasConfig,
+    drawing_path: &DrawingPath,
+    preflight: PreflightSettings,
+) -> Result<PreflightResult, HardwareError> {
+    let options = &config.options;
+    let timing = current_timing(control);
+    control.set_preflight_phase(PreflightPhase::DrawingMark);
+    info!("Drawing the preflight mark...");
+    for coords in PreflightSettings::mark() {
+        if !move_cursor_to(controller, control, cursor, coords, options, timing, |_| {})? {
+            return Ok(PreflightResult::Stopped);
+        }
+        tap_button_with_duration(
+            controller,
+            Button::A,
+            "Preflight Mark",
+            timing.press_ms,
+            timing.release_ms,
+            timing.wait_ms as u64,
+        )?;
+    }
+    control.set_cursor(cursor.position);
+
+    let deadline = Utc::now() + chrono::Duration::seconds(preflight.timeout_secs as i64);
+    control.set_preflight_phase(PreflightPhase::AwaitingConfirmation { deadline });
+    info!(
+        "Waiting for the preflight confirmation until {}",
+        deadline.to_rfc3339()
+    );
+    let _ = PROGRESS_CHANNEL.send(
+        serde_json::json!({
+            "type": "preflight",
+            "state": "awaiting_confirmation",
+            "deadline": deadline.to_rfc3339(),
+            "mark": PreflightSettings::mark()
+        })
+        .to_string(),
+    );
+    let answer = loop {
+        if control.stop_signal.load(Ordering::SeqCst) {
+            return Ok(PreflightResult::Stopped);
+        }
+        if let Some(answer) = control.lock_preflight().answer {
+            break Some(answer);
+        }
+        if Utc::now() >= deadline {
+            break None;
+        }
+        std::thread::sleep(PREFLIGHT_POLL_INTERVAL);
+    };
+    let rejection = match answer {
+        Some(true) => None,
+        Some(false) => Some(PreflightRejection::Declined),
+        None => Some(PreflightRejection::TimedOut),
+    };
+    if let Some(reason) = rejection {
+        warn!("Painting aborted by the preflight check: {:?}", reason);
+        control
+            .completion
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .reject_preflight(reason);
+        control.set_preflight_phase(PreflightPhase::Rejected(reason));
+        return Ok(PreflightResult::Rejected);
+    }
+
+    info!("Preflight mark confirmed, cleaning it up...");
+    let artwork_dots: HashSet<Coordinates> = drawing_path.coordinates.iter().copied().collect();
+    let mut kept = HashSet::new();
+    for coords in PreflightSettings::mark() {
+        if config.drawing_mode.dot_button() == Button::A && artwork_dots.contains(&coords) {
+            kept.insert(coords);
+            continue;
+        }
+        if !move_cursor_to(controller, control, cursor, coords, options, timing, |_| {})? {
+            return Ok(PreflightResult::Stopped);
+        }
+        tap_button_with_duration(
+            controller,
+            Button::B,
+            "Erase Preflight Mark",
+            timing.press_ms,
+            timing.release_ms,
+            timing.wait_ms as u64,
+        )?;
+    }
+    control.set_preflight_phase(PreflightPhase::Confirmed);
+    Ok(PreflightResult::Confirmed(kept))
+}
+
+/// 停止要求または `stop_after` の上限で描画を終える（必ずNEUTRAL状態にリセットする）
+fn finish_stopped(
+    controller: &Arc<dyn ControllerEmulator>,
+    control: &PaintingControl,
+) -> Result<CompletionReport, HardwareError> {
+    tap_dpad_with_duration(
+        controller,
+        DPad::NEUTRAL,
+        "Final Reset on Stop",
+        100,
+        100,
+        0,
+    )?;
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    Ok(control.finish_report())
+}
+
+/// 描画パスの順にドットを描画する（停止・一時停止・Switchの応答停止からの復帰を含む）
+///
+/// コントローラーを直接操作するため、`run_controller_io` で起動したスレッドから呼ぶ。
+pub fn perform_painting(
+    controller: Arc<dyn ControllerEmulator>,
+    drawing_path: DrawingPath,
+    config: &DrawingCanvasConfig,
+    control: PaintingControl,
+) -> Result<CompletionReport, HardwareError> {
+    debug_assert_blocking_allowed();
+    let options = &config.options;
+    // 十字キーの状態を覚えておき、描画前のニュートラルクリアの重複を省く
+    let dpad = Arc::new(DPadTracker::new(controller));
+    let controller: Arc<dyn ControllerEmulator> = dpad.clone();
+    debug!(
+        "perform_painting started: repeats={}, diagonal_moves={}",
+        control.repeats.load(Ordering::SeqCst),
+        options.diagonal_moves
+    );
+    info!("Initializing painting sequence...");
+    let total_dots = drawing_path.coordinates.len();
+    *control.completion.lock().unwrap_or_else(|e| e.into_inner()) =
+        CompletionTracker::new(total_dots).with_rows(drawing_path.row_dot_counts());
+
+    // Check stop signal
+    if control.stop_signal.load(Ordering::SeqCst) {
+        return finish_stopped(&controller, &control);
+    }
+
+    let send_status = |message: &Message| {
+        send_progress(
+            serde_json::json!({ "type": "progress" }),
+            "status_message",
+            message,
+        );
+    };
+
+    // 1. Initialization Sequence（ペンサイズの初期化や左上への移動など、描画する画面ごとの手順）
+    if !run_init_sequence(
+        &controller,
+        &config.init_sequence,
+        &control.stop_signal,
+        send_status,
+    )? {
+        info!("Painting stopped by user during initialization");
+        return finish_stopped(&controller, &control);
+    }
+
+    let mut cursor = CursorState::new();
+
+    // 2. 描画前の確認（確認用の印を描き、正しい位置に描かれたかの回答を待つ）
+    let mut preflight_dots = HashSet::new();
+    if let Some(preflight) = options.preflight {
+        send_status(&Message::new(MessageKey::StatusDrawingPreflightMark));
+        match run_preflight(
+            &controller,
+            &control,
+            &mut cursor,
+            config,
+            &drawing_path,
+            preflight,
+        )? {
+            PreflightResult::Confirmed(kept) => preflight_dots = kept,
+            PreflightResult::Rejected | PreflightResult::Stopped => {
+                return finish_stopped(&controller, &control);
+            }
+        }
+    }
+
+    info!("Starting dot painting... Total dots: {}", total_dots);
+
+    // 領域指定時は、まず領域の左上へ移動してから描画する
+    if let Some(entry_point) = options.entry_point {
+        info!("Moving to region corner {}...", entry_point);
+        send_status(&Message::new(MessageKey::StatusMovingToRegion));
+        let reached = move_cursor_to(
+            &controller,
+            &control,
+            &mut cursor,
+            entry_point,
+            options,
+            current_timing(&control),
+            |_| {},
+        )?;
+        if !reached {
+            info!("Painting stopped by user");
+            return Ok(control.finish_report());
+        }
+    }
+
+    let initial_timing = current_timing(&control);
+    info!(
+        "Using timing: press={}ms, release={}ms, wait={}ms, initial_repeats={}",
+        initial_timing.press_ms,
+        initial_timing.release_ms,
+        initial_timing.wait_ms,
+        control.repeats.load(Ordering::SeqCst)
+    );
+    send_status(&Message::new(MessageKey::StatusStartingPainting));
+
+    let mut adaptive = options
+        .adaptive
+        .map(|settings| AdaptiveTimingController::new(settings, initial_timing.wait_ms));
+    let mut last_stats_at = std::time::Instant::now();
+
+    let segments = drawing_path.layer_segments();
+    let layer_count = segments.len();
+    let mut painted = 0usize;
+    let mut consecutive_skips = 0u32;
+    for (layer_index, (layer, coordinates)) in segments.into_iter().enumerate() {
+        // レイヤーの境界では左上へ戻り、カーソル位置のずれをリセットする
+        if layer_index > 0 {
+            info!("Re-homing before layer {}...", layer);
+            send_status(
+                &Message::new(MessageKey::StatusRehomingBeforeLayer).with_arg("layer", layer),
+            );
+            move_home(&controller, &control.stop_signal)?;
+            cursor.position = Coordinates::origin();
+        }
+        info!(
+            "Painting layer {} ({}/{}): {} dots",
+            layer,
+            layer_index + 1,
+            layer_count,
+            coordinates.len()
+        );
+
+        for &coords in coordinates {
+            let i = painted;
+            painted += 1;
+            // Update timing from signals
+            let timing = current_timing(&control);
+
+            // Check stop signal
+            if control.stop_signal.load(Ordering::SeqCst) {
+                info!("Painting stopped by user");
+                return finish_stopped(&controller, &control);
+            }
+
+            // `stop_after` の上限に達したら、停止要求と同じ手順で終える
+            if let Some(limits) = &options.stop_after {
+                let mut completion = control.completion.lock().unwrap_or_else(|e| e.into_inner());
+                if let Some(limit) =
+                    limits.reached(completion.painted_dots().len(), completion.elapsed())
+                {
+                    info!("Painting stopped: stop_after {:?} limit reached", limit);
+                    completion.stop_at_limit(limit);
+                    drop(completion);
+                    return finish_stopped(&controller, &control);
+                }
+            }
+
+            // 前のドットの描画が終わった境界では、どちらのモードでも一時停止を受け付ける
+            if !wait_while_paused(&controller, &control, &mut cursor, options.pause, i)? {
+                info!("Painting stopped by user while paused");
+                return finish_stopped(&controller, &control);
+            }
+
+            // 確認用の印として描いたドットは、描き直さずに描画済みとして記録する
+            if preflight_dots.remove(&coords) {
+                control
+                    .completion
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .record(
+                        options.artwork_coordinates(coords).unwrap_or(coords),
+                        DotOutcome::Painted,
+                    );
+                control.painted.fetch_add(1, Ordering::SeqCst);
+                let _ = PROGRESS_CHANNEL.send(cursor.progress_message(i + 1, total_dots, true));
+                continue;
+            }
+
+            // Switchが応答しなくなった場合は、回復後にこのドットをやり直す。
+            // 一時的な送信エラーは待ち時間を延ばしながら `max_dot_attempts` 回まで試して、
+            // 描画できなければスキップする。それ以外のエラーは描画を中断する
+            let mut attempts = 1u32;
+            let mut failures = 0u32;
+            let mut presses_done = 0u32;
+            let mut timings = DotTimings::default();
+            let outcome = loop {
+                match paint_dot(
+                    &controller,
+                    &dpad,
+                    &control,
+                    &mut cursor,
+                    coords,
+                    options,
+                    config.drawing_mode.dot_button(),
+                    adaptive.as_mut(),
+                    (i, total_dots),
+                    &mut presses_done,
+                    control.trace.is_some().then_some(&mut timings),
+                ) {
+                    Ok(true) if attempts == 1 => break DotOutcome::Painted,
+                    Ok(true) => break DotOutcome::Retried { attempts },
+                    Ok(false) => return Ok(control.finish_report()),
+                    Err(e) if e.is_host_unresponsive() => {
+                        if !recover_from_unresponsive_host(
+                            &controller,
+                            &control,
+                            &mut cursor,
+                            options.pause,
+                            i,
+                        )? {
+                            info!("Painting stopped by user while the Switch was unresponsive");
+                            return Ok(control.finish_report());
+                        }
+                    }
+                    Err(e) if is_transient_dot_error(&e) => {
+                        failures += 1;
+                        control.record_dot_error(coords, &e, attempts);
+                        if failures < options.max_dot_attempts {
+                            let backoff = DOT_RETRY_BACKOFF
+                                [(failures as usize - 1).min(DOT_RETRY_BACKOFF.len() - 1)];
+                            warn!(
+                                "Retrying dot {} in {:?} (attempt {} failed): {}",
+                                coords, backoff, attempts, e
+                            );
+                            std::thread::sleep(backoff);
+                            // 押されたままの入力が残らないよう、やり直す前にニュートラルを送る
+                            if let Err(e) = tap_dpad_with_duration(
+                                &controller,
+                                DPad::NEUTRAL,
+                                "Clear Before Retry",
+                                10,
+                                10,
+                                0,
+                            ) {
+                                debug!("Failed to clear input before retrying: {}", e);
+                            }
+                        } else {
+                            consecutive_skips += 1;
+                            if consecutive_skips >= MAX_CONSECUTIVE_SKIPPED_DOTS {
+                                error!(
+                                    "Aborting painting after {} consecutive skipped dots",
+                                    consecutive_skips
+                                );
+                                return Err(e);
+                            }
+                            warn!("Skipping dot {} after {} attempts: {}", coords, attempts, e);
+                            break DotOutcome::Skipped {
+                                attempts,
+                                error: e.to_string(),
+                            };
+                        }
+                    }
+                    Err(e) => {
+                        error!("Aborting painting at dot {}: {}", coords, e);
+                        control.record_dot_error(coords, &e, attempts);
+                        return Err(e);
+                    }
+                }
+                attempts += 1;
+            };
+            let painted_dot = !matches!(outcome, DotOutcome::Skipped { .. });
+            if let Some(trace) = &control.trace {
+                trace
+                    .lock()
+                    .unwrap_or_else(|e| e.into_inner())
+                    .record(timings.row(i, coords, &outcome));
+            }
+            // 描画済みの記録はアートワークの座標で残す
+            control
+                .completion
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .record(
+                    options.artwork_coordinates(coords).unwrap_or(coords),
+                    outcome,
+                );
+            if painted_dot {
+                consecutive_skips = 0;
+                control.painted.fetch_add(1, Ordering::SeqCst);
+            }
+
+            // 1秒ごとに実効タイミングを通知
+            if last_stats_at.elapsed() >= std::time::Duration::from_secs(1) {
+                last_stats_at = std::time::Instant::now();
+                let eta = control
+                    .estimate
+                    .map(|estimate| estimate.remaining(i + 1, total_dots));
+                let _ = PROGRESS_CHANNEL.send(
+                    serde_json::json!({
+                        "type": "stats",
+                        "adaptive": adaptive.is_some(),
+                        "press_ms": timing.press_ms,
+                        "release_ms": timing.release_ms,
+                        "wait_ms": adaptive
+                            .as_ref()
+                            .map_or(timing.wait_ms, AdaptiveTimingController::wait_ms),
+                        "configured_wait_ms": timing.wait_ms,
+                        "latency_ewma_ms": adaptive
+                            .as_ref()
+                            .map_or(0.0, AdaptiveTimingController::latency_ewma_ms),
+                        "current": i + 1,
+                        "total": total_dots,
+                        "eta_seconds": eta.map(|eta| eta.raw_ms as f64 / 1000.0),
+                        "corrected_eta_seconds": eta.map(|eta| eta.corrected_ms() as f64 / 1000.0),
+                        "estimate_correction": eta.map(|eta| eta.correction)
+                    })
+                    .to_string(),
+                );
+            }
+
+            // Send paint progress update
+            control.set_cursor(cursor.position);
+            let _ = PROGRESS_CHANNEL.send(cursor.progress_message(i + 1, total_dots, painted_dot));
+
+            // Log progress every 100 dots
+            if i.is_multiple_of(100) {
+                info!("Painted {}/{} dots", i, total_dots);
+            }
+        }
+
+        if layer_index + 1 < layer_count {
+            let _ = PROGRESS_CHANNEL.send(
+                serde_json::json!({
+                    "type": "layer_complete",
+                    "layer": layer,
+                    "layer_index": layer_index + 1,
+                    "layer_count": layer_count,
+                    "current": painted,
+                    "total": total_dots
+                })
+                .to_string(),
+            );
+        }
+    }
+
+    let report = control.finish_report();
+    info!("Painting completed: {}", report.summary());
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::controller_io::run_controller_io;
+    use crate::application::painting_trace::PaintingTrace;
+    use crate::domain::painting::{
+        AdaptiveTimingSettings, InitPreset, NeutralClearSettings, StopAfter, StopLimit,
+        simulate_run,
+    };
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    #[test]
+    fn test_stop_signal_cancels_move_home_mid_stick_hold() {
+        let mock = Arc::new(MockController::new());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let stop_signal = Arc::new(AtomicBool::new(false));
+        let handle = {
+            let stop_signal = stop_signal.clone();
+            std::thread::spawn(move || move_home(&controller, &stop_signal))
+        };
+
+        // 5000msのスティック入力の途中で停止する
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        let neutral = crate::domain::controller::ProController::new("neutral").get_report_bytes();
+        assert_ne!(mock.last_report(), Some(neutral));
+        let cancelled_at = std::time::Instant::now();
+        stop_signal.store(true, Ordering::SeqCst);
+        handle.join().unwrap().unwrap();
+
+        let elapsed = cancelled_at.elapsed();
+        assert!(
+            elapsed < std::time::Duration::from_millis(50),
+            "took {elapsed:?}"
+        );
+        assert_eq!(mock.last_report(), Some(neutral));
+    }
+
+    #[test]
+    fn test_simulate_run_matches_mock_controller_operations() {
+        let drawing_path = DrawingPath::new(vec![
+            Coordinates::new(3, 1),
+            Coordinates::new(5, 4),
+            Coordinates::new(1, 4),
+            Coordinates::new(20, 2),
+        ]);
+        let config = DrawingCanvasConfig::new(
+            PaintTiming::new(1, 1, 0),
+            RunOptions {
+                repeats: 2,
+                diagonal_moves: true,
+                entry_point: Some(Coordinates::new(1, 1)),
+                // 待機時間の自動調整は操作回数に影響しない
+                adaptive: Some(AdaptiveTimingSettings::new(0, 5)),
+                ..RunOptions::default()
+            },
+        );
+        let control = PaintingControl::from_config(&config);
+        assert_eq!(current_timing(&control), config.timing);
+        let estimate = simulate_run(&drawing_path, &config.timing, &config.options);
+
+        let mock = Arc::new(MockController::new().without_delays());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        perform_painting(controller, drawing_path, &config, control).unwrap();
+
+        let recorded = mock.recorded_operations();
+        assert_eq!(recorded.dpad_ops, estimate.dpad_ops);
+        assert_eq!(recorded.a_presses, estimate.a_presses);
+        assert_eq!(recorded.neutral_clears, estimate.neutral_clears);
+    }
+
+    #[test]
+    fn test_redundant_neutral_clears_are_skipped_with_periodic_forced_clear() {
+        let run = |neutral_clear: NeutralClearSettings| {
+            let drawing_path =
+                DrawingPath::new((0..12).map(|x| Coordinates::new(x * 2, 1)).collect());
+            let config = DrawingCanvasConfig::new(
+                PaintTiming::new(1, 1, 0),
+                RunOptions {
+                    neutral_clear,
+                    ..RunOptions::default()
+                },
+            );
+            let estimate = simulate_run(&drawing_path, &config.timing, &config.options);
+            let mock = Arc::new(MockController::new().without_delays().with_command_log());
+            let control = PaintingControl::from_config(&config);
+            perform_painting(mock.clone(), drawing_path, &config, control).unwrap();
+
+            let commands = mock.recorded_commands();
+            // ドットの何番目の前にクリアを送ったか
+            let mut dot = 0;
+            let mut cleared_before = Vec::new();
+            for command in &commands {
+                if command.name == "Clear DPad Before Paint" {
+                    cleared_before.push(dot);
+                } else if command.name.starts_with("Paint Dot 1/") {
+                    dot += 1;
+                }
+            }
+            assert_eq!(
+                mock.recorded_operations().neutral_clears,
+                estimate.neutral_clears
+            );
+            (cleared_before, commands.len())
+        };
+
+        let (always, always_commands) = run(NeutralClearSettings::always());
+        assert_eq!(always, (0..12).collect::<Vec<_>>());
+
+        let (skipped, skipped_commands) = run(NeutralClearSettings::default());
+        assert_eq!(skipped, vec![0]);
+        assert_eq!(always_commands - skipped_commands, 11);
+
+        // 省略していても、設定した間隔では必ずクリアする
+        let (periodic, _) = run(NeutralClearSettings {
+            skip_redundant: true,
+            force_every_dots: 5,
+        });
+        assert_eq!(periodic, vec![0, 5, 10]);
+    }
+
+    /// 指定した名前のコマンドが `trigger_at` 回目に送られる直前に一時停止を要求する
+    struct PauseOnCommand {
+        inner: Arc<MockController>,
+        pause_signal: Arc<AtomicBool>,
+        prefix: &'static str,
+        trigger_at: usize,
+        seen: AtomicUsize,
+    }
+
+    impl ControllerEmulator for PauseOnCommand {
+        fn initialize(&self) -> Result<(), HardwareError> {
+            self.inner.initialize()
+        }
+
+        fn is_connected(&self) -> Result<bool, HardwareError> {
+            self.inner.is_connected()
+        }
+
+        fn execute_command(&self, command: &ControllerCommand) -> Result<(), HardwareError> {
+            if command.name.starts_with(self.prefix)
+                && self.seen.fetch_add(1, Ordering::SeqCst) + 1 == self.trigger_at
+            {
+                self.pause_signal.store(true, Ordering::SeqCst);
+            }
+            self.inner.execute_command(command)
+        }
+
+        fn write_raw_report(&self, report: &[u8]) -> Result<usize, HardwareError> {
+            self.inner.write_raw_report(report)
+        }
+
+        fn shutdown(&self) -> Result<(), HardwareError> {
+            self.inner.shutdown()
+        }
+    }
+
+    /// 一時停止が要求された時点から描画スレッドが待機に入るまでを再現する
+    ///
+    /// コマンドが送られなくなるまで待ち、その時点のコマンド記録を返す。
+    fn paint_until_paused(
+        pause: PauseSettings,
+        prefix: &'static str,
+        trigger_at: usize,
+    ) -> (
+        Arc<MockController>,
+        PaintingControl,
+        std::thread::JoinHandle<Result<CompletionReport, HardwareError>>,
+    ) {
+        let drawing_path = DrawingPath::new(vec![
+            Coordinates::new(3, 1),
+            Coordinates::new(6, 4),
+            Coordinates::new(1, 4),
+        ]);
+        let config = DrawingCanvasConfig::new(
+            PaintTiming::new(1, 1, 0),
+            RunOptions {
+                repeats: 2,
+                pause,
+                ..RunOptions::default()
+            },
+        );
+        let control = PaintingControl::from_config(&config);
+        let mock = Arc::new(MockController::new().without_delays().with_command_log());
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(PauseOnCommand {
+            inner: mock.clone(),
+            pause_signal: control.pause_signal.clone(),
+            prefix,
+            trigger_at,
+            seen: AtomicUsize::new(0),
+        });
+
+        let thread_control = control.clone();
+        let handle = std::thread::spawn(move || {
+            perform_painting(controller, drawing_path, &config, thread_control)
+        });
+
+        while !control.pause_signal.load(Ordering::SeqCst) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let mut sent = mock.recorded_commands().len();
+        loop {
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            let now = mock.recorded_commands().len();
+            if now == sent {
+                break;
+            }
+            sent = now;
+        }
+        (mock, control, handle)
+    }
+
+    fn assert_paused_between_dots(mock: &MockController, control: &PaintingControl) {
+        let neutral = crate::domain::controller::ProController::new("neutral").get_report_bytes();
+        assert_eq!(mock.last_report(), Some(neutral));
+        let painted = control.painted.load(Ordering::SeqCst) as u64;
+        assert_eq!(mock.recorded_operations().a_presses, painted * 2);
+    }
+
+    #[test]
+    fn test_safe_pause_waits_for_dot_boundary() {
+        // 最初のドットへ移動している途中で一時停止を要求する
+        let pause = PauseSettings {
+            mode: PauseMode::Safe,
+            rehome_on_resume: true,
+            ..PauseSettings::default()
+        };
+        let (mock, control, handle) = paint_until_paused(pause, "Move ", 3);
+
+        let commands = mock.recorded_commands();
+        assert_eq!(
+            commands.last().unwrap().name,
+            "Clear DPad Before Pause",
+            "paused without returning the D-pad to neutral"
+        );
+        assert_eq!(control.painted.load(Ordering::SeqCst), 1);
+        assert_paused_between_dots(&mock, &control);
+
+        // 再開時は左上へ戻ってから残りのドットを描画する
+        let paused_at = commands.len();
+        control.pause_signal.store(false, Ordering::SeqCst);
+        let report = handle.join().unwrap().unwrap();
+        let commands = mock.recorded_commands();
+        assert_eq!(commands[paused_at].name, "Move Home Left Stick");
+        // 待機していた時間（少なくとも `paint_until_paused` の待ち時間）は見積もりとの比較から除く
+        assert!(report.paused_ms >= 500, "{}", report.paused_ms);
+        assert!(report.paused_ms <= report.duration_ms);
+        assert_eq!(control.painted.load(Ordering::SeqCst), 3);
+        assert_eq!(mock.recorded_operations().a_presses, 6);
+    }
+
+    #[test]
+    fn test_pause_never_interrupts_button_press() {
+        std::thread::scope(|scope| {
+            for mode in [PauseMode::Safe, PauseMode::Immediate] {
+                scope.spawn(move || {
+                    // 2番目のドットのAボタン押下中に一時停止を要求する
+                    let pause = PauseSettings {
+                        mode,
+                        rehome_on_resume: false,
+                        ..PauseSettings::default()
+                    };
+                    let (mock, control, handle) = paint_until_paused(pause, "Paint Dot", 3);
+
+                    assert_eq!(control.painted.load(Ordering::SeqCst), 2, "{mode:?}");
+                    assert_paused_between_dots(&mock, &control);
+
+                    control.stop_signal.store(true, Ordering::SeqCst);
+                    handle.join().unwrap().unwrap();
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_keepalive_is_sent_while_paused() {
+        let mock = Arc::new(MockController::new().without_delays().with_command_log());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let control = PaintingControl::from_config(&DrawingCanvasConfig::new(
+            PaintTiming::new(1, 1, 0),
+            RunOptions::default(),
+        ));
+        control.pause_signal.store(true, Ordering::SeqCst);
+        let pause = PauseSettings {
+            mode: PauseMode::Immediate,
+            rehome_on_resume: false,
+            keepalive_idle_ms: Some(50),
+            ..PauseSettings::default()
+        };
+
+        let thread_control = control.clone();
+        let handle = std::thread::spawn(move || {
+            let mut cursor = CursorState::new();
+            wait_while_paused(&controller, &thread_control, &mut cursor, pause, 0)
+        });
+        std::thread::sleep(std::time::Duration::from_millis(400));
+        control.stop_signal.store(true, Ordering::SeqCst);
+        assert!(!handle.join().unwrap().unwrap());
+
+        // スティックを傾けて戻すだけで、ボタンは押さない
+        let keepalives = mock
+            .recorded_commands()
+            .iter()
+            .filter(|command| command.name == "Keepalive")
+            .count();
+        assert!(keepalives >= 2, "sent {keepalives} keepalive inputs");
+        assert_eq!(mock.recorded_operations().a_presses, 0);
+        let neutral = crate::domain::controller::ProController::new("neutral").get_report_bytes();
+        assert_eq!(mock.last_report(), Some(neutral));
+    }
+
+    /// 2番目のドットへの移動中にSwitchがスリープする描画を開始する
+    fn paint_until_host_sleeps(
+        auto_resume: bool,
+    ) -> (
+        Arc<MockController>,
+        PaintingControl,
+        std::thread::JoinHandle<Result<CompletionReport, HardwareError>>,
+    ) {
+        let drawing_path = DrawingPath::new(vec![
+            Coordinates::new(3, 1),
+            Coordinates::new(6, 4),
+            Coordinates::new(1, 4),
+        ]);
+        let config = DrawingCanvasConfig::new(
+            PaintTiming::new(1, 1, 0),
+            RunOptions {
+                pause: PauseSettings {
+                    host_grace_ms: 50,
+                    auto_resume,
+                    ..PauseSettings::default()
+                },
+                ..RunOptions::default()
+            },
+        );
+        let control = PaintingControl::from_config(&config);
+        // 初期化（Lボタン5回と左上への移動）と1番目のドット（移動4回、クリア、A）の後
+        let mock = Arc::new(
+            MockController::new()
+                .without_delays()
+                .with_command_log()
+                .with_host_sleep_after(14),
+        );
+
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let thread_control = control.clone();
+        let handle = std::thread::spawn(move || {
+            perform_painting(controller, drawing_path, &config, thread_control)
+        });
+
+        let started = std::time::Instant::now();
+        while !control.pause_signal.load(Ordering::SeqCst) {
+            assert!(
+                started.elapsed() < std::time::Duration::from_secs(15) && !handle.is_finished(),
+                "painting was not paused while the host was asleep"
+            );
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        (mock, control, handle)
+    }
+
+    #[test]
+    fn test_unresponsive_host_pauses_until_user_resumes() {
+        let (mock, control, handle) = paint_until_host_sleeps(false);
+        assert_eq!(control.painted.load(Ordering::SeqCst), 1);
+        let asleep_at = mock.recorded_commands().len();
+
+        // 復帰しても自動では再開しない
+        mock.wake_host();
+        std::thread::sleep(std::time::Duration::from_millis(800));
+        assert!(control.pause_signal.load(Ordering::SeqCst));
+        assert_eq!(mock.recorded_commands().len(), asleep_at);
+
+        // 再開時は左上へ戻ってから、中断したドットをやり直す
+        control.pause_signal.store(false, Ordering::SeqCst);
+        handle.join().unwrap().unwrap();
+        let commands = mock.recorded_commands();
+        assert_eq!(commands[asleep_at].name, "Move Home Left Stick");
+        assert_eq!(control.painted.load(Ordering::SeqCst), 3);
+        assert_eq!(mock.recorded_operations().a_presses, 3);
+    }
+
+    #[test]
+    fn test_unresponsive_host_auto_resumes_when_requested() {
+        let (mock, control, handle) = paint_until_host_sleeps(true);
+        let asleep_at = mock.recorded_commands().len();
+
+        mock.wake_host();
+        handle.join().unwrap().unwrap();
+        assert!(!control.pause_signal.load(Ordering::SeqCst));
+        assert_eq!(
+            mock.recorded_commands()[asleep_at].name,
+            "Move Home Left Stick"
+        );
+        assert_eq!(control.painted.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn test_stop_racing_run_end_is_never_lost() {
+        for _ in 0..500 {
+            let control = PaintingControl::new(1, 1, 1, 0);
+            let runner = {
+                let control = control.clone();
+                std::thread::spawn(move || control.mark_finished())
+            };
+            let delivered = control.request_stop();
+            runner.join().unwrap();
+
+            // 届いた停止は必ず受領され、届かなかったのは終了後に送った停止だけ
+            assert!(control.is_finished());
+            assert_eq!(control.stop_acknowledged.load(Ordering::SeqCst), delivered);
+        }
+    }
+
+    #[test]
+    fn test_concurrent_pause_toggles_are_not_lost() {
+        let control = PaintingControl::new(1, 1, 1, 0);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..1001 {
+                        control.request_pause(None).unwrap();
+                    }
+                });
+            }
+        });
+        // 切り替えは合計で偶数回なので再開した状態に戻る
+        assert!(!control.pause_signal.load(Ordering::SeqCst));
+
+        assert_eq!(control.request_pause(Some(true)), Some(true));
+        assert_eq!(control.request_pause(Some(true)), Some(true));
+        control.mark_finished();
+        assert_eq!(control.request_pause(None), None);
+        assert!(!control.request_stop());
+    }
+
+    #[tokio::test]
+    async fn test_transient_failure_retries_dot_without_repeating_presses() {
+        let mock = Arc::new(
+            MockController::new()
+                .without_delays()
+                .with_command_failures("Paint Dot", &[2]),
+        );
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let events = ArtworkEventLog::default();
+        let artwork_id = ArtworkId::generate();
+        let drawing_path = DrawingPath::new(vec![Coordinates::new(1, 0), Coordinates::new(2, 0)]);
+        let mut config = DrawingCanvasConfig::new(
+            PaintTiming::new(1, 1, 0),
+            RunOptions {
+                repeats: 2,
+                ..RunOptions::default()
+            },
+        );
+        config.init_sequence = InitSequence::preset(InitPreset::None);
+        let trace = Arc::new(std::sync::Mutex::new(PaintingTrace::new(2)));
+        let control = PaintingControl::from_config(&config)
+            .with_event_log(PaintingEventLog {
+                artwork_id: artwork_id.clone(),
+                version: 1,
+                events: events.clone(),
+            })
+            .with_trace(trace.clone());
+
+        // 1つ目のドットの2回目のAボタンが1回だけ失敗する
+        let started = std::time::Instant::now();
+        let report =
+            run_controller_io(move || perform_painting(controller, drawing_path, &config, control))
+                .await
+                .unwrap()
+                .unwrap();
+
+        assert_eq!(report.succeeded, 2);
+        assert_eq!(report.retried, 1);
+        assert_eq!(report.total_retries, 1);
+        assert_eq!(report.skipped, 0);
+        // 失敗する前に押せた1回目は押し直さない
+        assert_eq!(mock.recorded_operations().a_presses, 4);
+        assert!(started.elapsed() >= DOT_RETRY_BACKOFF[0]);
+        let mut csv = Vec::new();
+        trace.lock().unwrap().write_csv_chunks(1024, |chunk| {
+            csv.extend(chunk);
+            true
+        });
+        let csv = String::from_utf8(csv).unwrap();
+        let rows: Vec<Vec<&str>> = csv
+            .lines()
+            .skip(1)
+            .map(|l| l.split(',').collect())
+            .collect();
+        assert_eq!(rows.len(), 2, "{csv}");
+        // index,x,y,...,presses,...,attempts,outcome
+        assert_eq!(&rows[0][..3], ["0", "1", "0"]);
+        assert_eq!(rows[0][6], "2");
+        assert_eq!(&rows[0][10..], ["2", "retried"]);
+        assert_eq!(&rows[1][10..], ["1", "painted"]);
+
+        let events = events.read().await;
+        assert_eq!(events.len(), 1);
+        match &events[0] {
+            ArtworkEvent::PaintingErrorOccurred {
+                artwork_id: id,
+                coordinates,
+                retry_count,
+                ..
+            } => {
+                assert_eq!(id, &artwork_id);
+                assert_eq!(*coordinates, Some(Coordinates::new(1, 0)));
+                assert_eq!(*retry_count, 1);
+            }
+            other => panic!("unexpected event: {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_trace_adds_no_measurable_overhead_on_mock_controller() {
+        let drawing_path = DrawingPath::new(
+            (0..2)
+                .flat_map(|y| (0..20).map(move |x| Coordinates::new(x, y * 2)))
+                .collect(),
+        );
+        let mut config = DrawingCanvasConfig::new(PaintTiming::new(2, 2, 1), RunOptions::default());
+        config.init_sequence = InitSequence::preset(InitPreset::None);
+        let run = |traced: bool| {
+            let mut control = PaintingControl::from_config(&config);
+            let trace = Arc::new(std::sync::Mutex::new(PaintingTrace::new(
+                drawing_path.coordinates.len(),
+            )));
+            if traced {
+                control = control.with_trace(trace.clone());
+            }
+            // 遅延を再現するモックで、実機と同じく入力ごとに押下・解放の時間だけ待つ
+            let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+            let started = std::time::Instant::now();
+            perform_painting(controller, drawing_path.clone(), &config, control).unwrap();
+            let elapsed = started.elapsed();
+            let recorded = trace.lock().unwrap().len();
+            assert_eq!(recorded, if traced { 40 } else { 0 });
+            elapsed
+        };
+
+        // 実行ごとのばらつきを除くため、交互に数回ずつ実行して最短の時間を比べる
+        let (mut untraced, mut traced) = (std::time::Duration::MAX, std::time::Duration::MAX);
+        for _ in 0..3 {
+            untraced = untraced.min(run(false));
+            traced = traced.min(run(true));
+        }
+        assert!(
+            traced <= untraced + untraced / 10 + std::time::Duration::from_millis(20),
+            "traced {traced:?}, untraced {untraced:?}"
+        );
+    }
+
+    #[test]
+    fn test_layered_painting_matches_simulation() {
+        let drawing_path = DrawingPath::from_layers(vec![
+            (0, vec![Coordinates::new(4, 2), Coordinates::new(6, 2)]),
+            (3, vec![Coordinates::new(1, 5)]),
+        ]);
+        let config = DrawingCanvasConfig::new(PaintTiming::new(1, 1, 0), RunOptions::default());
+        let estimate = simulate_run(&drawing_path, &config.timing, &config.options);
+
+        let mock = Arc::new(MockController::new().without_delays());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let control = PaintingControl::from_config(&config);
+        perform_painting(controller, drawing_path, &config, control).unwrap();
+
+        let recorded = mock.recorded_operations();
+        assert_eq!(recorded.dpad_ops, estimate.dpad_ops);
+        assert_eq!(recorded.a_presses, estimate.a_presses);
+    }
+
+    #[test]
+    fn test_stop_after_ends_run_at_dot_boundary() {
+        let drawing_path = DrawingPath::new((0..6).map(|x| Coordinates::new(x * 2, 0)).collect());
+        let stop_after = StopAfter {
+            dots: Some(4),
+            completion_ratio: Some(0.5),
+            ..StopAfter::default()
+        };
+        let config = DrawingCanvasConfig::new(
+            PaintTiming::new(1, 1, 0),
+            RunOptions {
+                stop_after: Some(stop_after.resolve(1, 8).unwrap()),
+                ..RunOptions::default()
+            },
+        );
+
+        let mock = Arc::new(MockController::new().without_delays().with_command_log());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let control = PaintingControl::from_config(&config);
+        let report = perform_painting(controller, drawing_path, &config, control.clone()).unwrap();
+
+        // 描画済みの1ドットと合わせて8ドットの半分に達したところで止まる
+        assert_eq!(report.succeeded, 3);
+        assert_eq!(
+            report.outcome(),
+            RunOutcome::LimitReached {
+                limit: StopLimit::CompletionRatio
+            }
+        );
+        assert_eq!(mock.recorded_operations().a_presses, 3);
+        let commands = mock.recorded_commands();
+        assert_eq!(commands.last().unwrap().name, "Final Reset on Stop");
+        // ユーザーの停止要求とは区別する
+        assert!(!control.stop_signal.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_stop_after_minutes_counts_from_run_start() {
+        let limits = StopAfter {
+            minutes: Some(1),
+            ..StopAfter::default()
+        }
+        .resolve(0, 10)
+        .unwrap();
+        let minute = std::time::Duration::from_secs(60);
+
+        assert_eq!(limits.reached(9, minute / 2), None);
+        assert_eq!(limits.reached(9, minute), Some(StopLimit::Minutes));
+        assert_eq!(limits.remaining_seconds(minute / 2), Some(30));
+        assert_eq!(limits.remaining_dots(9), None);
+    }
+
+    /// 確認を求める描画を実行し、回答待ちになったら `answer` を返す（`None` なら回答しない）
+    fn run_with_preflight(
+        dots: Vec<Coordinates>,
+        timeout_secs: u32,
+        answer: Option<bool>,
+    ) -> (CompletionReport, Arc<MockController>, PaintingControl) {
+        let config = DrawingCanvasConfig::new(
+            PaintTiming::new(1, 1, 0),
+            RunOptions {
+                preflight: Some(PreflightSettings { timeout_secs }),
+                ..RunOptions::default()
+            },
+        );
+        let mock = Arc::new(MockController::new().without_delays().with_command_log());
+        let control = PaintingControl::from_config(&config);
+        let responder = {
+            let control = control.clone();
+            std::thread::spawn(move || {
+                let Some(answer) = answer else { return };
+                while !matches!(
+                    control.preflight_phase(),
+                    Some(PreflightPhase::AwaitingConfirmation { .. })
+                ) {
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                }
+                assert!(control.answer_preflight(answer));
+                // 回答は1度だけ受け付ける
+                assert!(!control.answer_preflight(!answer));
+            })
+        };
+        let report = perform_painting(
+            mock.clone(),
+            DrawingPath::new(dots),
+            &config,
+            control.clone(),
+        )
+        .unwrap();
+        responder.join().unwrap();
+        (report, mock, control)
+    }
+
+    fn command_count(mock: &MockController, name: &str) -> usize {
+        mock.recorded_commands()
+            .iter()
+            .filter(|command| command.name == name)
+            .count()
+    }
+
+    #[test]
+    fn test_confirmed_preflight_cleans_up_mark_and_keeps_artwork_dots() {
+        let (report, mock, control) = run_with_preflight(
+            vec![Coordinates::new(0, 0), Coordinates::new(5, 5)],
+            60,
+            Some(true),
+        );
+
+        assert_eq!(report.outcome(), RunOutcome::Completed);
+        assert_eq!(report.succeeded, 2);
+        assert_eq!(control.preflight_phase(), Some(PreflightPhase::Confirmed));
+        assert_eq!(command_count(&mock, "Preflight Mark"), 3);
+        // (0,0) はアートワークのドットなので残し、残りの2つだけ消す
+        assert_eq!(command_count(&mock, "Erase Preflight Mark"), 2);
+        assert_eq!(mock.recorded_operations().a_presses, 4);
+    }
+
+    #[test]
+    fn test_declined_or_unanswered_preflight_aborts_painting() {
+        let dots = vec![Coordinates::new(5, 5)];
+        let (declined, mock, control) = run_with_preflight(dots.clone(), 60, Some(false));
+        assert_eq!(declined.outcome(), RunOutcome::PreflightDeclined);
+        assert_eq!(declined.succeeded, 0);
+        assert_eq!(
+            control.preflight_phase(),
+            Some(PreflightPhase::Rejected(PreflightRejection::Declined))
+        );
+        // 印は消さずに、アートワークを描かないまま終える
+        assert_eq!(mock.recorded_operations().a_presses, 3);
+        assert_eq!(command_count(&mock, "Erase Preflight Mark"), 0);
+        assert_eq!(
+            mock.recorded_commands().last().unwrap().name,
+            "Final Reset on Stop"
+        );
+
+        let (timed_out, _, _) = run_with_preflight(dots, 1, None);
+        assert_eq!(timed_out.outcome(), RunOutcome::PreflightTimedOut);
+    }
+}
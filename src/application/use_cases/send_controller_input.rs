@@ -0,0 +1,98 @@
+use crate::domain::controller::{ControllerCommand, ControllerEmulator, ManualInput};
+use crate::domain::hardware::errors::HardwareError;
+use std::sync::Arc;
+use tracing::info;
+
+/// 手動入力の実行結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ControllerInputReport {
+    /// 上限で丸めた後の入力時間（ミリ秒）
+    pub duration_ms: u32,
+    /// 入力時と解除時に送信したHIDレポート（エミュレーターが記録しない場合は空）
+    pub reports: Vec<[u8; 8]>,
+}
+
+/// コントローラーに単一の入力を送るユースケース（Web APIとCLIで共有）
+///
+/// 入力を押し続けた後、必ずボタンを離す・ニュートラルに戻すところまで実行する。
+/// ブロッキング処理のため、非同期ハンドラからは `run_controller_io` 経由で呼ぶ。
+pub struct SendControllerInputUseCase {
+    emulator: Arc<dyn ControllerEmulator>,
+}
+
+impl SendControllerInputUseCase {
+    pub fn new(emulator: Arc<dyn ControllerEmulator>) -> Self {
+        Self { emulator }
+    }
+
+    pub fn execute(
+        &self,
+        input: ManualInput,
+        duration_ms: u32,
+    ) -> Result<ControllerInputReport, HardwareError> {
+        let duration_ms = ManualInput::clamp_duration(duration_ms);
+        info!("Sending manual input {:?} for {}ms", input, duration_ms);
+
+        let mut reports = Vec::with_capacity(2);
+        for action in input.to_actions(duration_ms) {
+            let command = ControllerCommand::new(format!("Manual {input:?}")).add_action(action);
+            self.emulator.execute_command(&command)?;
+            reports.extend(self.emulator.last_report());
+        }
+
+        Ok(ControllerInputReport {
+            duration_ms,
+            reports,
+        })
+    }
+}
+
+/// レポートを `04 00 08 80 80 80 80 00` 形式の16進文字列にする
+pub fn format_report(report: &[u8; 8]) -> String {
+    report
+        .iter()
+        .map(|byte| format!("{byte:02X}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::controller::{Button, ManualInputKind};
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    #[test]
+    fn test_button_input_presses_then_releases_and_clamps_duration() {
+        let use_case =
+            SendControllerInputUseCase::new(Arc::new(MockController::new().without_delays()));
+
+        let report = use_case
+            .execute(ManualInput::Button(Button::A), 60_000)
+            .unwrap();
+
+        assert_eq!(report.duration_ms, ManualInput::MAX_DURATION_MS);
+        assert_eq!(
+            report.reports,
+            vec![
+                [0x04, 0x00, 0x08, 0x80, 0x80, 0x80, 0x80, 0x00],
+                [0x00, 0x00, 0x08, 0x80, 0x80, 0x80, 0x80, 0x00],
+            ]
+        );
+        assert_eq!(format_report(&report.reports[0]), "04 00 08 80 80 80 80 00");
+    }
+
+    #[test]
+    fn test_stick_input_returns_to_center() {
+        let use_case =
+            SendControllerInputUseCase::new(Arc::new(MockController::new().without_delays()));
+        let input = ManualInput::parse(ManualInputKind::Stick, "up_left").unwrap();
+
+        let report = use_case.execute(input, 100).unwrap();
+
+        assert_eq!(report.reports[0][3..5], [0x00, 0x00]);
+        assert_eq!(report.reports[1][3..5], [0x80, 0x80]);
+        assert!(ManualInput::parse(ManualInputKind::Dpad, "neutral").is_err());
+        assert!(ManualInput::parse(ManualInputKind::Button, "START").is_err());
+    }
+}
@@ -2,12 +2,13 @@ use crate::domain::setup::repositories::{
     BoardDetector, BootConfigurator, SetupError, SystemdServiceManager,
 };
 use std::sync::Arc;
-use tracing::info;
+use tracing::{info, warn};
 
 pub struct SetupSystemUseCase {
     board_detector: Arc<dyn BoardDetector>,
     boot_configurator: Arc<dyn BootConfigurator>,
     systemd_manager: Arc<dyn SystemdServiceManager>,
+    safe_mode: bool,
 }
 
 impl SetupSystemUseCase {
@@ -20,14 +21,21 @@ impl SetupSystemUseCase {
             board_detector,
             boot_configurator,
             systemd_manager,
+            safe_mode: false,
         }
     }
 
+    /// セーフモードで実行する（書き込みは各リポジトリのラッパーが止めるため、root権限を求めずサービスも起動しない）
+    pub fn with_safe_mode(mut self) -> Self {
+        self.safe_mode = true;
+        self
+    }
+
     pub fn execute(&self, force: bool) -> Result<(), SetupError> {
         info!("Starting system setup...");
 
         // Check if running as root
-        if !is_running_as_root() {
+        if !self.safe_mode && !is_running_as_root() {
             return Err(SetupError::PermissionDenied(
                 "This command requires root privileges. Please run with sudo.".to_string(),
             ));
@@ -75,6 +83,20 @@ impl SetupSystemUseCase {
         self.systemd_manager.enable_web_service()?;
         info!("Web UI systemd service enabled.");
 
+        // 有効なガジェットのサービスは `--force` でしか書き直さない。古いテンプレートのままだと
+        // 起動時にWebサービスがガジェットより先に始まりうるため知らせる
+        for missing in self.systemd_manager.missing_unit_ordering() {
+            warn!(
+                "Installed unit lacks boot ordering ({}); run setup --force to rewrite it",
+                missing
+            );
+        }
+
+        if self.safe_mode {
+            info!("Safe mode: skipped starting the services");
+            return Ok(());
+        }
+
         // Try to start services immediately for testing
         info!("Attempting to start services for immediate testing...");
         if let Err(e) = self.try_start_services() {
@@ -128,5 +150,5 @@ impl SetupSystemUseCase {
 }
 
 fn is_running_as_root() -> bool {
-    unsafe { libc::geteuid() == 0 }
+    crate::infrastructure::platform::is_root()
 }
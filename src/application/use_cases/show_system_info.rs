@@ -1,11 +1,97 @@
 use crate::domain::hardware::repositories::UsbGadgetManager;
 use crate::domain::setup::entities::BoardModel;
 use crate::domain::setup::repositories::{BoardDetector, SetupError};
+use crate::infrastructure::platform;
+use serde::Serialize;
 use std::fs;
 use std::path::Path;
 use std::sync::Arc;
 
+/// `info --json` の出力形式の版（項目の名前や意味を変えたら上げる）
+pub const SYSTEM_INFO_SCHEMA_VERSION: u32 = 1;
+
+const GADGET_PATH: &str = "/sys/kernel/config/usb_gadget/nintendo_controller";
+
+/// システム情報の収集結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SystemInfoReport {
+    pub schema_version: u32,
+    pub board: BoardInfo,
+    pub usb_gadget: UsbGadgetInfo,
+    pub hid_devices: Vec<HidDeviceInfo>,
+    pub services: Vec<ServiceInfo>,
+    /// `--verbose` の場合のみ収集する
+    pub kernel_modules: Option<Vec<KernelModuleInfo>>,
+    /// Webのアクセストークンが生成済みか（読み込めない場合は `None`。トークン自体は含めない）
+    pub access_token_generated: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BoardInfo {
+    /// 検出できなかった場合は `None`（理由は `error`）
+    pub model: Option<String>,
+    pub usb_otg: bool,
+    pub error: Option<String>,
+    /// `--verbose` の場合のみ収集する
+    pub details: Option<BoardDetails>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BoardDetails {
+    pub device_tree_overlay: Option<String>,
+    pub requires_config_txt: bool,
+    pub usb_device_path: String,
+    /// Orange Pi Zero 2Wのブート環境ファイル
+    pub boot_env_file: Option<String>,
+    pub usb_otg_overlay_enabled: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UsbGadgetInfo {
+    /// 確認に失敗した場合は `None`（理由は `error`）
+    pub configured: Option<bool>,
+    /// UDCにバインドされているか（UDCの状態を読めない場合は `None`）
+    pub connected: Option<bool>,
+    pub udc: Option<String>,
+    pub error: Option<String>,
+    /// `--verbose` の場合のみ収集する
+    pub details: Option<GadgetDetails>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct GadgetDetails {
+    pub vendor_id: Option<String>,
+    pub product_id: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HidDeviceInfo {
+    pub path: String,
+    /// 8進数の権限（`660` など）
+    pub permissions: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ServiceInfo {
+    pub name: String,
+    pub description: String,
+    pub enabled: bool,
+    pub active: bool,
+    /// `systemctl status` の直近の行（`--verbose` の場合のみ）
+    pub recent_status: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct KernelModuleInfo {
+    pub name: String,
+    pub loaded: bool,
+}
+
 /// システム情報を表示するユースケース
+///
+/// `collect` で状態を集め、`execute` はそれを表示する（`--verbose` ではデバッグ情報も続けて表示する）。
 pub struct ShowSystemInfoUseCase<D: BoardDetector, G: UsbGadgetManager> {
     board_detector: Arc<D>,
     gadget_manager: Arc<G>,
@@ -20,25 +106,10 @@ impl<D: BoardDetector, G: UsbGadgetManager> ShowSystemInfoUseCase<D, G> {
     }
 
     pub fn execute(&self, verbose: bool) -> Result<(), SetupError> {
-        println!("🔍 System Information");
-        println!("====================");
-
-        // ボード情報
-        self.show_board_info(verbose)?;
-
-        // USB Gadget情報
-        self.show_usb_gadget_info(verbose)?;
-
-        // HIDデバイス情報
-        self.show_hid_device_info(verbose)?;
-
-        // systemdサービス情報
-        self.show_systemd_service_info(verbose)?;
+        let report = self.collect(verbose)?;
+        print_report(&report, verbose);
 
         if verbose {
-            // カーネルモジュール情報
-            self.show_kernel_module_info()?;
-
             // USB関連の詳細情報
             self.show_usb_detail_info()?;
 
@@ -49,243 +120,122 @@ impl<D: BoardDetector, G: UsbGadgetManager> ShowSystemInfoUseCase<D, G> {
         Ok(())
     }
 
-    fn show_board_info(&self, verbose: bool) -> Result<(), SetupError> {
-        println!("\n📋 Board Information:");
+    pub fn collect(&self, verbose: bool) -> Result<SystemInfoReport, SetupError> {
+        Ok(SystemInfoReport {
+            schema_version: SYSTEM_INFO_SCHEMA_VERSION,
+            board: self.collect_board_info(verbose),
+            usb_gadget: self.collect_usb_gadget_info(verbose),
+            hid_devices: collect_hid_devices(),
+            services: collect_services(verbose),
+            kernel_modules: if verbose {
+                Some(collect_kernel_modules()?)
+            } else {
+                None
+            },
+            access_token_generated: None,
+        })
+    }
 
-        match self.board_detector.detect_board() {
-            Ok(board) => {
-                let model_str = match &board {
-                    BoardModel::OrangePiZero2W => "Orange Pi Zero 2W",
-                    BoardModel::RaspberryPiZero => "Raspberry Pi Zero",
-                    BoardModel::RaspberryPiZero2W => "Raspberry Pi Zero 2W",
-                    BoardModel::Unknown(s) => s,
+    fn collect_board_info(&self, verbose: bool) -> BoardInfo {
+        let board = match self.board_detector.detect_board() {
+            Ok(board) => board,
+            Err(e) => {
+                return BoardInfo {
+                    model: None,
+                    usb_otg: false,
+                    error: Some(e.to_string()),
+                    details: None,
                 };
-                println!("   Model: {model_str}");
-
-                // All supported boards have USB OTG
-                let has_otg = !matches!(board, BoardModel::Unknown(_));
-                println!(
-                    "   USB OTG Support: {}",
-                    if has_otg { "✅ Yes" } else { "❌ No" }
-                );
-
-                if verbose {
-                    println!("   Details:");
-                    println!(
-                        "      - Device tree overlay: {}",
-                        board.otg_device_tree_overlay().unwrap_or("None")
-                    );
-                    println!(
-                        "      - Requires config.txt: {}",
-                        if board.requires_config_txt() {
-                            "Yes"
-                        } else {
-                            "No"
-                        }
-                    );
-                    println!("      - USB device path: {}", board.usb_device_path());
-
-                    // Check Orange Pi Zero 2W specific configuration
-                    if matches!(board, BoardModel::OrangePiZero2W) {
-                        let env_files = vec!["/boot/orangepiEnv.txt", "/boot/armbianEnv.txt"];
-                        for env_file in env_files {
-                            if Path::new(env_file).exists() {
-                                println!("      - Boot env file: {env_file}");
-                                if let Ok(content) = fs::read_to_string(env_file) {
-                                    if content.contains("usb-otg") {
-                                        println!("        ✅ USB OTG overlay enabled");
-                                    } else {
-                                        println!("        ❌ USB OTG overlay not enabled");
-                                        println!(
-                                            "        💡 Run 'sudo splatoon3-ghost-drawer setup' to configure"
-                                        );
-                                    }
-                                }
-                                break;
-                            }
-                        }
-                    }
-                }
             }
-            Err(e) => {
-                println!("   ❌ Failed to detect board: {e}");
+        };
+
+        let model = match &board {
+            BoardModel::OrangePiZero2W => "Orange Pi Zero 2W",
+            BoardModel::RaspberryPiZero => "Raspberry Pi Zero",
+            BoardModel::RaspberryPiZero2W => "Raspberry Pi Zero 2W",
+            BoardModel::Unknown(s) => s,
+        };
+
+        let details = verbose.then(|| {
+            // Check Orange Pi Zero 2W specific configuration
+            let boot_env = matches!(board, BoardModel::OrangePiZero2W)
+                .then(|| {
+                    ["/boot/orangepiEnv.txt", "/boot/armbianEnv.txt"]
+                        .into_iter()
+                        .find(|env_file| Path::new(env_file).exists())
+                })
+                .flatten();
+            BoardDetails {
+                device_tree_overlay: board.otg_device_tree_overlay().map(str::to_string),
+                requires_config_txt: board.requires_config_txt(),
+                usb_device_path: board.usb_device_path().to_string(),
+                boot_env_file: boot_env.map(str::to_string),
+                usb_otg_overlay_enabled: boot_env
+                    .and_then(|env_file| fs::read_to_string(env_file).ok())
+                    .map(|content| content.contains("usb-otg")),
             }
+        });
+
+        BoardInfo {
+            model: Some(model.to_string()),
+            // All supported boards have USB OTG
+            usb_otg: !matches!(board, BoardModel::Unknown(_)),
+            error: None,
+            details,
         }
-
-        Ok(())
     }
 
-    fn show_usb_gadget_info(&self, verbose: bool) -> Result<(), SetupError> {
-        println!("\n🔌 USB Gadget Status:");
+    fn collect_usb_gadget_info(&self, verbose: bool) -> UsbGadgetInfo {
+        let mut info = UsbGadgetInfo {
+            configured: None,
+            connected: None,
+            udc: None,
+            error: None,
+            details: None,
+        };
 
-        // Gadget設定確認
         match self.gadget_manager.is_gadget_configured() {
-            Ok(configured) => {
-                if configured {
-                    println!("   Configuration: ✅ Configured");
-
-                    // UDC状態確認
-                    let udc_path =
-                        Path::new("/sys/kernel/config/usb_gadget/nintendo_controller/UDC");
-                    if udc_path.exists() {
-                        match fs::read_to_string(udc_path) {
-                            Ok(udc) => {
-                                let udc = udc.trim();
-                                if udc.is_empty() {
-                                    println!("   Connection: ❌ Not connected (UDC not bound)");
-                                } else {
-                                    println!("   Connection: ✅ Connected (UDC: {udc})");
-                                }
-                            }
-                            Err(e) => {
-                                println!("   Connection: ⚠️  Unknown (Failed to read UDC: {e})");
-                            }
+            Ok(true) => {
+                info.configured = Some(true);
+
+                // UDC状態確認
+                let udc_path = Path::new(GADGET_PATH).join("UDC");
+                if udc_path.exists() {
+                    match fs::read_to_string(udc_path) {
+                        Ok(udc) => {
+                            let udc = udc.trim();
+                            info.connected = Some(!udc.is_empty());
+                            info.udc = (!udc.is_empty()).then(|| udc.to_string());
                         }
+                        Err(e) => info.error = Some(format!("Failed to read UDC: {e}")),
                     }
-                } else {
-                    println!("   Configuration: ❌ Not configured");
-                    println!("   Connection: ❌ Not connected");
                 }
             }
-            Err(e) => {
-                println!("   Status: ❌ Error checking gadget: {e}");
+            Ok(false) => {
+                info.configured = Some(false);
+                info.connected = Some(false);
             }
+            Err(e) => info.error = Some(e.to_string()),
         }
 
-        if verbose {
+        if verbose && Path::new(GADGET_PATH).exists() {
             // 詳細なGadget情報
-            let gadget_path = Path::new("/sys/kernel/config/usb_gadget/nintendo_controller");
-            if gadget_path.exists() {
-                println!("\n   Gadget Details:");
-
-                // Vendor/Product ID
-                if let Ok(vendor_id) = fs::read_to_string(gadget_path.join("idVendor")) {
-                    println!("      - Vendor ID: {}", vendor_id.trim());
-                }
-                if let Ok(product_id) = fs::read_to_string(gadget_path.join("idProduct")) {
-                    println!("      - Product ID: {}", product_id.trim());
-                }
-
-                // Strings
-                let strings_path = gadget_path.join("strings/0x409");
-                if let Ok(manufacturer) = fs::read_to_string(strings_path.join("manufacturer")) {
-                    println!("      - Manufacturer: {}", manufacturer.trim());
-                }
-                if let Ok(product) = fs::read_to_string(strings_path.join("product")) {
-                    println!("      - Product: {}", product.trim());
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    fn show_hid_device_info(&self, verbose: bool) -> Result<(), SetupError> {
-        println!("\n🎮 HID Device Status:");
-
-        let hid_devices = vec!["/dev/hidg0", "/dev/hidg1", "/dev/hidg2", "/dev/hidg3"];
-        let mut found_devices = Vec::new();
-
-        for device in &hid_devices {
-            if Path::new(device).exists() {
-                found_devices.push(*device);
-            }
-        }
-
-        if found_devices.is_empty() {
-            println!("   Devices: ❌ No HID gadget devices found");
-        } else {
-            println!("   Devices: ✅ Found {} device(s)", found_devices.len());
-            for device in &found_devices {
-                println!("      - {device}");
-
-                if verbose {
-                    // デバイスの権限情報
-                    if let Ok(metadata) = fs::metadata(device) {
-                        use std::os::unix::fs::PermissionsExt;
-                        let mode = metadata.permissions().mode();
-                        println!("        Permissions: {:o}", mode & 0o777);
-                    }
-                }
-            }
+            let gadget_path = Path::new(GADGET_PATH);
+            let strings_path = gadget_path.join("strings/0x409");
+            let read = |path: std::path::PathBuf| {
+                fs::read_to_string(path)
+                    .ok()
+                    .map(|value| value.trim().to_string())
+            };
+            info.details = Some(GadgetDetails {
+                vendor_id: read(gadget_path.join("idVendor")),
+                product_id: read(gadget_path.join("idProduct")),
+                manufacturer: read(strings_path.join("manufacturer")),
+                product: read(strings_path.join("product")),
+            });
         }
 
-        Ok(())
-    }
-
-    fn show_systemd_service_info(&self, verbose: bool) -> Result<(), SetupError> {
-        println!("\n⚙️  Systemd Services:");
-
-        let services = vec![
-            ("splatoon3-gadget.service", "USB Gadget Service"),
-            ("splatoon3-ghost-drawer.service", "Web UI Service"),
-        ];
-
-        for (service_name, description) in services {
-            print!("   {description}: ");
-
-            // systemctl is-enabled
-            let enabled_output = std::process::Command::new("systemctl")
-                .args(["is-enabled", service_name])
-                .output();
-
-            let is_enabled = enabled_output.map(|o| o.status.success()).unwrap_or(false);
-
-            // systemctl is-active
-            let active_output = std::process::Command::new("systemctl")
-                .args(["is-active", service_name])
-                .output();
-
-            let is_active = active_output.map(|o| o.status.success()).unwrap_or(false);
-
-            if is_enabled && is_active {
-                println!("✅ Enabled & Active");
-            } else if is_enabled && !is_active {
-                println!("⚠️  Enabled but Inactive");
-            } else if !is_enabled && is_active {
-                println!("⚠️  Active but not Enabled");
-            } else {
-                println!("❌ Disabled & Inactive");
-            }
-
-            if verbose && (is_enabled || is_active) {
-                // サービスの詳細状態
-                if let Ok(output) = std::process::Command::new("systemctl")
-                    .args(["status", service_name, "--no-pager", "-n", "3"])
-                    .output()
-                {
-                    let status = String::from_utf8_lossy(&output.stdout);
-                    for line in status.lines().skip(1).take(3) {
-                        println!("      {}", line.trim());
-                    }
-                }
-            }
-        }
-
-        Ok(())
-    }
-
-    fn show_kernel_module_info(&self) -> Result<(), SetupError> {
-        println!("\n🔧 Kernel Modules:");
-
-        let modules = vec!["dwc2", "libcomposite"];
-
-        for module in modules {
-            print!("   {module}: ");
-
-            let output = std::process::Command::new("lsmod")
-                .output()
-                .map_err(|e| SetupError::Unknown(format!("Failed to run lsmod: {e}")))?;
-
-            let lsmod_output = String::from_utf8_lossy(&output.stdout);
-            if lsmod_output.lines().any(|line| line.starts_with(module)) {
-                println!("✅ Loaded");
-            } else {
-                println!("❌ Not loaded");
-            }
-        }
-
-        Ok(())
+        info
     }
 
     fn show_usb_detail_info(&self) -> Result<(), SetupError> {
@@ -383,7 +333,7 @@ impl<D: BoardDetector, G: UsbGadgetManager> ShowSystemInfoUseCase<D, G> {
 
         // Gadgetディレクトリの詳細
         println!("\n   📂 Gadget Directory:");
-        let gadget_path = "/sys/kernel/config/usb_gadget/nintendo_controller";
+        let gadget_path = GADGET_PATH;
 
         if Path::new(gadget_path).exists() {
             println!("      ✅ {gadget_path} exists");
@@ -418,12 +368,11 @@ impl<D: BoardDetector, G: UsbGadgetManager> ShowSystemInfoUseCase<D, G> {
 
         for path in paths_to_check {
             if Path::new(path).exists()
-                && let Ok(metadata) = fs::metadata(path)
+                && let Some(mode) = fs::metadata(path)
+                    .ok()
+                    .and_then(|metadata| platform::permission_bits(&metadata))
             {
-                use std::os::unix::fs::PermissionsExt;
-                let mode = metadata.permissions().mode();
-                let perms = format!("{:o}", mode & 0o777);
-                println!("      {path} ({perms})");
+                println!("      {path} ({mode:o})");
             }
         }
 
@@ -456,3 +405,297 @@ impl<D: BoardDetector, G: UsbGadgetManager> ShowSystemInfoUseCase<D, G> {
         Ok(())
     }
 }
+
+fn collect_hid_devices() -> Vec<HidDeviceInfo> {
+    ["/dev/hidg0", "/dev/hidg1", "/dev/hidg2", "/dev/hidg3"]
+        .into_iter()
+        .filter(|device| Path::new(device).exists())
+        .map(|device| HidDeviceInfo {
+            path: device.to_string(),
+            permissions: fs::metadata(device)
+                .ok()
+                .and_then(|metadata| platform::permission_bits(&metadata))
+                .map(|mode| format!("{mode:o}")),
+        })
+        .collect()
+}
+
+fn collect_services(verbose: bool) -> Vec<ServiceInfo> {
+    let services = [
+        ("splatoon3-gadget.service", "USB Gadget Service"),
+        ("splatoon3-ghost-drawer.service", "Web UI Service"),
+    ];
+
+    services
+        .into_iter()
+        .map(|(service_name, description)| {
+            let systemctl_succeeds = |command: &str| {
+                std::process::Command::new("systemctl")
+                    .args([command, service_name])
+                    .output()
+                    .map(|o| o.status.success())
+                    .unwrap_or(false)
+            };
+            let enabled = systemctl_succeeds("is-enabled");
+            let active = systemctl_succeeds("is-active");
+
+            let mut recent_status = Vec::new();
+            if verbose
+                && (enabled || active)
+                && let Ok(output) = std::process::Command::new("systemctl")
+                    .args(["status", service_name, "--no-pager", "-n", "3"])
+                    .output()
+            {
+                // サービスの詳細状態
+                let status = String::from_utf8_lossy(&output.stdout);
+                recent_status = status
+                    .lines()
+                    .skip(1)
+                    .take(3)
+                    .map(|line| line.trim().to_string())
+                    .collect();
+            }
+
+            ServiceInfo {
+                name: service_name.to_string(),
+                description: description.to_string(),
+                enabled,
+                active,
+                recent_status,
+            }
+        })
+        .collect()
+}
+
+fn collect_kernel_modules() -> Result<Vec<KernelModuleInfo>, SetupError> {
+    let output = std::process::Command::new("lsmod")
+        .output()
+        .map_err(|e| SetupError::Unknown(format!("Failed to run lsmod: {e}")))?;
+    let lsmod_output = String::from_utf8_lossy(&output.stdout);
+
+    Ok(["dwc2", "libcomposite"]
+        .into_iter()
+        .map(|module| KernelModuleInfo {
+            name: module.to_string(),
+            loaded: lsmod_output.lines().any(|line| line.starts_with(module)),
+        })
+        .collect())
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "Yes" } else { "No" }
+}
+
+/// 収集したシステム情報を表示する
+pub fn print_report(report: &SystemInfoReport, verbose: bool) {
+    println!("🔍 System Information");
+    println!("====================");
+
+    println!("\n📋 Board Information:");
+    let board = &report.board;
+    match (&board.model, &board.error) {
+        (Some(model), _) => {
+            println!("   Model: {model}");
+            println!(
+                "   USB OTG Support: {}",
+                if board.usb_otg { "✅ Yes" } else { "❌ No" }
+            );
+            if let Some(details) = &board.details {
+                println!("   Details:");
+                println!(
+                    "      - Device tree overlay: {}",
+                    details.device_tree_overlay.as_deref().unwrap_or("None")
+                );
+                println!(
+                    "      - Requires config.txt: {}",
+                    yes_no(details.requires_config_txt)
+                );
+                println!("      - USB device path: {}", details.usb_device_path);
+                if let Some(env_file) = &details.boot_env_file {
+                    println!("      - Boot env file: {env_file}");
+                    match details.usb_otg_overlay_enabled {
+                        Some(true) => println!("        ✅ USB OTG overlay enabled"),
+                        Some(false) => {
+                            println!("        ❌ USB OTG overlay not enabled");
+                            println!(
+                                "        💡 Run 'sudo splatoon3-ghost-drawer setup' to configure"
+                            );
+                        }
+                        None => {}
+                    }
+                }
+            }
+        }
+        (None, error) => {
+            println!(
+                "   ❌ Failed to detect board: {}",
+                error.as_deref().unwrap_or("unknown")
+            );
+        }
+    }
+
+    println!("\n🔌 USB Gadget Status:");
+    let gadget = &report.usb_gadget;
+    match gadget.configured {
+        Some(true) => {
+            println!("   Configuration: ✅ Configured");
+            match (gadget.connected, &gadget.udc, &gadget.error) {
+                (Some(true), Some(udc), _) => println!("   Connection: ✅ Connected (UDC: {udc})"),
+                (Some(_), _, _) => println!("   Connection: ❌ Not connected (UDC not bound)"),
+                (None, _, Some(e)) => println!("   Connection: ⚠️  Unknown ({e})"),
+                (None, _, None) => {}
+            }
+        }
+        Some(false) => {
+            println!("   Configuration: ❌ Not configured");
+            println!("   Connection: ❌ Not connected");
+        }
+        None => println!(
+            "   Status: ❌ Error checking gadget: {}",
+            gadget.error.as_deref().unwrap_or("unknown")
+        ),
+    }
+    if let Some(details) = &gadget.details {
+        println!("\n   Gadget Details:");
+        for (label, value) in [
+            ("Vendor ID", &details.vendor_id),
+            ("Product ID", &details.product_id),
+            ("Manufacturer", &details.manufacturer),
+            ("Product", &details.product),
+        ] {
+            if let Some(value) = value {
+                println!("      - {label}: {value}");
+            }
+        }
+    }
+
+    println!("\n🎮 HID Device Status:");
+    if report.hid_devices.is_empty() {
+        println!("   Devices: ❌ No HID gadget devices found");
+    } else {
+        println!(
+            "   Devices: ✅ Found {} device(s)",
+            report.hid_devices.len()
+        );
+        for device in &report.hid_devices {
+            println!("      - {}", device.path);
+            if verbose && let Some(permissions) = &device.permissions {
+                println!("        Permissions: {permissions}");
+            }
+        }
+    }
+
+    println!("\n⚙️  Systemd Services:");
+    for service in &report.services {
+        let state = match (service.enabled, service.active) {
+            (true, true) => "✅ Enabled & Active",
+            (true, false) => "⚠️  Enabled but Inactive",
+            (false, true) => "⚠️  Active but not Enabled",
+            (false, false) => "❌ Disabled & Inactive",
+        };
+        println!("   {}: {state}", service.description);
+        for line in &service.recent_status {
+            println!("      {line}");
+        }
+    }
+
+    if let Some(modules) = &report.kernel_modules {
+        println!("\n🔧 Kernel Modules:");
+        for module in modules {
+            println!(
+                "   {}: {}",
+                module.name,
+                if module.loaded {
+                    "✅ Loaded"
+                } else {
+                    "❌ Not loaded"
+                }
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_system_info_report_json_schema() {
+        let report = SystemInfoReport {
+            schema_version: SYSTEM_INFO_SCHEMA_VERSION,
+            board: BoardInfo {
+                model: Some("Raspberry Pi Zero 2W".to_string()),
+                usb_otg: true,
+                error: None,
+                details: Some(BoardDetails {
+                    device_tree_overlay: Some("dwc2".to_string()),
+                    requires_config_txt: true,
+                    usb_device_path: "/sys/kernel/config/usb_gadget/g1".to_string(),
+                    boot_env_file: None,
+                    usb_otg_overlay_enabled: None,
+                }),
+            },
+            usb_gadget: UsbGadgetInfo {
+                configured: Some(true),
+                connected: Some(true),
+                udc: Some("3f980000.usb".to_string()),
+                error: None,
+                details: None,
+            },
+            hid_devices: vec![HidDeviceInfo {
+                path: "/dev/hidg0".to_string(),
+                permissions: Some("660".to_string()),
+            }],
+            services: vec![ServiceInfo {
+                name: "splatoon3-gadget.service".to_string(),
+                description: "USB Gadget Service".to_string(),
+                enabled: true,
+                active: false,
+                recent_status: Vec::new(),
+            }],
+            kernel_modules: Some(vec![KernelModuleInfo {
+                name: "dwc2".to_string(),
+                loaded: true,
+            }]),
+            access_token_generated: Some(false),
+        };
+
+        // 監視スクリプトが読む項目名を変えたら `SYSTEM_INFO_SCHEMA_VERSION` を上げること
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            json!({
+                "schema_version": 1,
+                "board": {
+                    "model": "Raspberry Pi Zero 2W",
+                    "usb_otg": true,
+                    "error": null,
+                    "details": {
+                        "device_tree_overlay": "dwc2",
+                        "requires_config_txt": true,
+                        "usb_device_path": "/sys/kernel/config/usb_gadget/g1",
+                        "boot_env_file": null,
+                        "usb_otg_overlay_enabled": null
+                    }
+                },
+                "usb_gadget": {
+                    "configured": true,
+                    "connected": true,
+                    "udc": "3f980000.usb",
+                    "error": null,
+                    "details": null
+                },
+                "hid_devices": [{"path": "/dev/hidg0", "permissions": "660"}],
+                "services": [{
+                    "name": "splatoon3-gadget.service",
+                    "description": "USB Gadget Service",
+                    "enabled": true,
+                    "active": false,
+                    "recent_status": []
+                }],
+                "kernel_modules": [{"name": "dwc2", "loaded": true}],
+                "access_token_generated": false
+            })
+        );
+    }
+}
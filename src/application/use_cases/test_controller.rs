@@ -1,26 +1,77 @@
-use crate::domain::controller::{Button, ControllerAction, ControllerCommand, ControllerEmulator};
+use crate::domain::controller::{
+    Button, ControllerAction, ControllerCommand, ControllerEmulator, ControllerInterlock,
+};
 use crate::domain::hardware::errors::HardwareError;
 use std::sync::Arc;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{error, info, warn};
 
+/// 待機中に中断の指示を確認する間隔
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// コントローラーのテストと動作確認を行うユースケース
-pub struct TestControllerUseCase<E: ControllerEmulator> {
+pub struct TestControllerUseCase<E: ControllerEmulator + ?Sized> {
     emulator: Arc<E>,
+    interlock: ControllerInterlock,
+    /// 立てるとテストを中断する（Ctrl-Cなど）
+    cancel: Arc<AtomicBool>,
+    /// 実行中のテストで送信したコマンドの数
+    commands_sent: AtomicUsize,
+}
+
+/// テストをどこまで実行したか
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControllerTestReport {
+    /// 送信したコマンドの数
+    pub commands_sent: usize,
+    pub elapsed: Duration,
+    /// 中断の指示で途中で終わったか
+    pub interrupted: bool,
 }
 
-impl<E: ControllerEmulator> TestControllerUseCase<E> {
+impl<E: ControllerEmulator + ?Sized> TestControllerUseCase<E> {
     pub fn new(emulator: Arc<E>) -> Self {
-        Self { emulator }
+        Self {
+            emulator,
+            interlock: ControllerInterlock::new(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            commands_sent: AtomicUsize::new(0),
+        }
+    }
+
+    /// 出力の可否を判断する安全装置（アームされていなければテストを実行しない）
+    pub fn with_interlock(mut self, interlock: ControllerInterlock) -> Self {
+        self.interlock = interlock;
+        self
+    }
+
+    /// 立てるとテストを中断するフラグ（操作の区切りと、実行中の操作の途中で確認する）
+    pub fn with_cancel_signal(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = cancel;
+        self
     }
 
-    pub async fn execute(&self, duration: u16, mode: &str) -> Result<(), HardwareError> {
+    /// テストを実行する
+    ///
+    /// 完了・中断・エラーのいずれでも、最後に `shutdown` でニュートラルのレポートを送る。
+    pub async fn execute(
+        &self,
+        duration: u16,
+        mode: &str,
+    ) -> Result<ControllerTestReport, HardwareError> {
         info!(
             "Starting controller test (mode: {}, duration: {}s)",
             mode, duration
         );
 
+        if !self.interlock.is_armed() {
+            return Err(HardwareError::NotArmed);
+        }
+        let started = Instant::now();
+        self.commands_sent.store(0, Ordering::SeqCst);
+
         // 初期化
         self.emulator.initialize()?;
         info!("Controller initialized successfully");
@@ -39,23 +90,74 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
         info!("Controller is connected to Nintendo Switch");
         println!("✅ Controller connected to Nintendo Switch!");
 
-        match mode {
-            "basic" => self.run_basic_test(duration).await?,
-            "buttons" => self.run_button_test(duration).await?,
-            "sticks" => self.run_stick_test(duration).await?,
-            "interactive" => self.run_interactive_test().await?,
+        let result = match mode {
+            "basic" => self.run_basic_test(duration).await,
+            "buttons" => self.run_button_test(duration).await,
+            "sticks" => self.run_stick_test(duration).await,
+            "interactive" => self.run_interactive_test().await,
             _ => {
                 error!("Unknown test mode: {}", mode);
-                return Err(HardwareError::InvalidParameter(format!(
+                Err(HardwareError::InvalidParameter(format!(
                     "Unknown test mode: {mode}"
-                )));
+                )))
             }
+        };
+
+        // ボタンが押されたままにならないよう、結果に関わらずニュートラルに戻す
+        let shutdown = self.emulator.shutdown();
+        result?;
+        shutdown?;
+
+        let report = ControllerTestReport {
+            commands_sent: self.commands_sent.load(Ordering::SeqCst),
+            elapsed: started.elapsed(),
+            interrupted: self.is_cancelled(),
+        };
+        if report.interrupted {
+            info!(
+                "Controller test interrupted after {} commands",
+                report.commands_sent
+            );
+        } else {
+            info!("Controller test completed");
         }
+        Ok(report)
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancel.load(Ordering::SeqCst)
+    }
 
-        info!("Controller test completed");
+    /// コマンドを送る（中断された後は何もしない）
+    fn send(&self, command: &ControllerCommand) -> Result<(), HardwareError> {
+        if self.is_cancelled() {
+            return Ok(());
+        }
+        self.emulator
+            .execute_command_cancellable(command, &self.cancel)?;
+        self.commands_sent.fetch_add(1, Ordering::SeqCst);
         Ok(())
     }
 
+    /// 中断されるまで最大 `duration` 待つ
+    async fn wait(&self, duration: Duration) {
+        let deadline = Instant::now() + duration;
+        while !self.is_cancelled() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                break;
+            }
+            sleep(remaining.min(CANCEL_POLL_INTERVAL)).await;
+        }
+    }
+
+    /// 中断されずに最後まで実行できた場合だけ完了を表示する
+    fn print_completed(&self, message: &str) {
+        if !self.is_cancelled() {
+            println!("{message}");
+        }
+    }
+
     /// 基本的な接続テスト
     async fn run_basic_test(&self, duration: u16) -> Result<(), HardwareError> {
         println!("\n🎮 Running basic controller test...");
@@ -63,14 +165,14 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
         println!("   - Press A button every 2 seconds");
         println!("   - Move left stick in a circle");
 
-        let start_time = std::time::Instant::now();
+        let start_time = Instant::now();
         let test_duration = if duration == 0 {
             Duration::from_secs(10)
         } else {
             Duration::from_secs(duration as u64)
         };
 
-        while start_time.elapsed() < test_duration {
+        while start_time.elapsed() < test_duration && !self.is_cancelled() {
             // Aボタンを押す
             println!("   Pressing A button...");
             let mut command = ControllerCommand::new("Test A button");
@@ -79,7 +181,7 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
                 .add_action(ControllerAction::release_button(Button::A, 100))
                 .add_action(ControllerAction::wait(1000));
 
-            self.emulator.execute_command(&command)?;
+            self.send(&command)?;
 
             // 左スティックを円を描くように動かす
             println!("   Moving left stick in circle...");
@@ -107,13 +209,13 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
                     ))
                     .add_action(ControllerAction::wait(200));
 
-                self.emulator.execute_command(&command)?;
+                self.send(&command)?;
             }
 
-            sleep(Duration::from_millis(500)).await;
+            self.wait(Duration::from_millis(500)).await;
         }
 
-        println!("✅ Basic test completed!");
+        self.print_completed("✅ Basic test completed!");
         Ok(())
     }
 
@@ -139,7 +241,7 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
             (Button::R_STICK, "R Stick"),
         ];
 
-        let start_time = std::time::Instant::now();
+        let start_time = Instant::now();
         let test_duration = if duration == 0 {
             Duration::from_secs(buttons.len() as u64 * 2)
         } else {
@@ -147,7 +249,10 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
         };
 
         let mut button_index = 0;
-        while start_time.elapsed() < test_duration && button_index < buttons.len() {
+        while start_time.elapsed() < test_duration
+            && button_index < buttons.len()
+            && !self.is_cancelled()
+        {
             let (button, name) = &buttons[button_index];
             println!("   Testing {name} button...");
 
@@ -157,13 +262,13 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
                 .add_action(ControllerAction::release_button(*button, 200))
                 .add_action(ControllerAction::wait(1000));
 
-            self.emulator.execute_command(&command)?;
+            self.send(&command)?;
 
             button_index = (button_index + 1) % buttons.len();
-            sleep(Duration::from_millis(500)).await;
+            self.wait(Duration::from_millis(500)).await;
         }
 
-        println!("✅ Button test completed!");
+        self.print_completed("✅ Button test completed!");
         Ok(())
     }
 
@@ -172,14 +277,14 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
         println!("\n🎮 Running stick test...");
         println!("   Testing both analog sticks:");
 
-        let start_time = std::time::Instant::now();
+        let start_time = Instant::now();
         let test_duration = if duration == 0 {
             Duration::from_secs(20)
         } else {
             Duration::from_secs(duration as u64)
         };
 
-        while start_time.elapsed() < test_duration {
+        while start_time.elapsed() < test_duration && !self.is_cancelled() {
             // 左スティックテスト
             println!("   Testing left stick...");
             for angle in (0..360).step_by(30) {
@@ -196,8 +301,8 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
                     100,
                 ));
 
-                self.emulator.execute_command(&command)?;
-                sleep(Duration::from_millis(100)).await;
+                self.send(&command)?;
+                self.wait(Duration::from_millis(100)).await;
             }
 
             // センターに戻す
@@ -206,9 +311,9 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
                 crate::domain::controller::StickPosition::new(128, 128),
                 100,
             ));
-            self.emulator.execute_command(&command)?;
+            self.send(&command)?;
 
-            sleep(Duration::from_millis(500)).await;
+            self.wait(Duration::from_millis(500)).await;
 
             // 右スティックテスト
             println!("   Testing right stick...");
@@ -226,8 +331,8 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
                     100,
                 ));
 
-                self.emulator.execute_command(&command)?;
-                sleep(Duration::from_millis(100)).await;
+                self.send(&command)?;
+                self.wait(Duration::from_millis(100)).await;
             }
 
             // センターに戻す
@@ -236,12 +341,12 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
                 crate::domain::controller::StickPosition::new(128, 128),
                 100,
             ));
-            self.emulator.execute_command(&command)?;
+            self.send(&command)?;
 
-            sleep(Duration::from_millis(1000)).await;
+            self.wait(Duration::from_millis(1000)).await;
         }
 
-        println!("✅ Stick test completed!");
+        self.print_completed("✅ Stick test completed!");
         Ok(())
     }
 
@@ -256,3 +361,50 @@ impl<E: ControllerEmulator> TestControllerUseCase<E> {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    const NEUTRAL_REPORT: [u8; 8] = [0x00, 0x00, 0x08, 0x80, 0x80, 0x80, 0x80, 0x00];
+
+    fn armed() -> ControllerInterlock {
+        let interlock = ControllerInterlock::new();
+        interlock.arm("test", None);
+        interlock
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_the_test_and_sends_the_neutral_report() {
+        let controller = Arc::new(MockController::new().without_delays());
+        let cancel = Arc::new(AtomicBool::new(false));
+        let use_case = TestControllerUseCase::new(controller.clone())
+            .with_interlock(armed())
+            .with_cancel_signal(cancel.clone());
+
+        let interrupt = tokio::spawn(async move {
+            sleep(Duration::from_millis(250)).await;
+            cancel.store(true, Ordering::SeqCst);
+        });
+        let report = use_case.execute(20, "sticks").await.unwrap();
+        interrupt.await.unwrap();
+
+        assert!(report.interrupted);
+        assert!(report.commands_sent > 0);
+        assert!(report.elapsed < Duration::from_secs(5), "{report:?}");
+        // スティックを傾けたまま中断しても、最後のレポートはニュートラル
+        assert_eq!(controller.last_report(), Some(NEUTRAL_REPORT));
+    }
+
+    #[tokio::test]
+    async fn test_refuses_to_run_unless_armed() {
+        let controller = Arc::new(MockController::new().without_delays());
+        let use_case = TestControllerUseCase::new(controller.clone());
+
+        let error = use_case.execute(1, "basic").await.unwrap_err();
+
+        assert!(matches!(error, HardwareError::NotArmed));
+        assert_eq!(controller.last_report(), None);
+    }
+}
@@ -1,4 +1,5 @@
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
+use std::path::PathBuf;
 
 #[derive(Parser, Debug)]
 #[command(
@@ -9,6 +10,17 @@ use clap::{Parser, Subcommand};
     long_about = "A drawing robot that creates art on Splatoon 3 by emulating a Nintendo Switch Pro Controller"
 )]
 pub struct Cli {
+    /// Log filter such as `debug` or `info,splatoon3_ghost_drawer::infrastructure::hardware=trace`
+    ///
+    /// Overrides RUST_LOG. While `run` is serving, it can be changed with PUT /api/system/log-level.
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+    /// Never write to system paths (USB gadget, boot files, systemd units, HID device)
+    ///
+    /// The operations are logged instead of performed, or refused where pretending they succeeded
+    /// would mislead later steps. Application data in --data-dir is still written.
+    #[arg(long, global = true, env = "SPLATOON3_SAFE_MODE", value_parser = clap::builder::BoolishValueParser::new())]
+    pub safe_mode: bool,
     #[command(subcommand)]
     pub command: Commands,
 }
@@ -26,9 +38,167 @@ pub enum Commands {
         /// Port to bind the web server to
         #[arg(short, long, default_value = "8080")]
         port: u16,
-        /// Host to bind the web server to
-        #[arg(short = 'H', long, default_value = "0.0.0.0")]
-        host: String,
+        /// Host to bind the web server to (IP address or resolvable hostname)
+        ///
+        /// When omitted, listens on all IPv4 and IPv6 addresses.
+        #[arg(short = 'H', long)]
+        host: Option<String>,
+        /// Serve HTTPS using a certificate generated automatically
+        #[arg(long, value_enum, conflicts_with_all = ["tls_cert", "tls_key"])]
+        tls: Option<TlsMode>,
+        /// PEM certificate file for HTTPS (requires --tls-key)
+        #[arg(long, requires = "tls_key")]
+        tls_cert: Option<PathBuf>,
+        /// PEM private key file for HTTPS (requires --tls-cert)
+        #[arg(long, requires = "tls_cert")]
+        tls_key: Option<PathBuf>,
+        /// Directory for application data such as generated certificates
+        #[arg(long, default_value = "/var/lib/splatoon3-ghost-drawer")]
+        data_dir: PathBuf,
+        /// Run without hardware using a simulated controller (for development)
+        #[arg(long, env = "SPLATOON3_SIMULATE", value_parser = clap::builder::BoolishValueParser::new())]
+        simulate: bool,
+        /// In simulation mode, reject painting and calibration requests
+        #[arg(long, requires = "simulate")]
+        strict_simulation: bool,
+        /// Do not require the access token for painting and other changes (trusted networks only)
+        #[arg(long, env = "SPLATOON3_NO_AUTH", value_parser = clap::builder::BoolishValueParser::new())]
+        no_auth: bool,
+        /// Enable debugging endpoints such as POST /api/debug/hid-report (protocol experiments only)
+        #[arg(long, env = "SPLATOON3_DEBUG", value_parser = clap::builder::BoolishValueParser::new())]
+        debug: bool,
+        /// When a pause request takes effect if a paint request does not specify it
+        #[arg(long, value_enum, default_value = "immediate")]
+        pause_mode: PauseModeArg,
+        /// In safe pause mode, return to the top-left corner before resuming
+        #[arg(long)]
+        rehome_on_resume: bool,
+        /// Pause painting automatically once the Switch stops accepting input for this long (ms)
+        #[arg(long, default_value = "3000")]
+        host_grace_ms: u32,
+        /// Resume automatically when the Switch accepts input again after such a pause
+        #[arg(long)]
+        auto_resume: bool,
+        /// While paused, send a harmless input after this long without input so the Switch stays awake (ms)
+        #[arg(long)]
+        keepalive_idle_ms: Option<u32>,
+        /// Warn when the estimated painting time exceeds this many minutes (the Switch auto-sleep timer, 0 disables)
+        #[arg(long, default_value = "60")]
+        max_uninterrupted_minutes: u32,
+        /// Refuse such paints unless the request sets acknowledge_sleep_risk
+        #[arg(long)]
+        strict_sleep_guard: bool,
+        /// Where artworks and painting history are stored
+        #[arg(long, value_enum, env = "SPLATOON3_STORAGE", default_value = "sqlite")]
+        storage: StorageMode,
+        /// Time budget for the 2-opt path optimization of the GreedyTwoOpt strategy (ms, max 30000)
+        #[arg(long, default_value = "2000")]
+        two_opt_budget_ms: u64,
+        /// Estimated memory all stored artworks may use; creating more returns 507 Insufficient Storage (MiB)
+        #[arg(
+            long,
+            env = "SPLATOON3_ARTWORK_MEMORY_BUDGET_MB",
+            default_value = "256"
+        )]
+        artwork_memory_budget_mb: usize,
+        /// Permanently delete artworks kept in the trash longer than this many days (at startup and via POST /api/artworks/trash/purge)
+        #[arg(long, default_value = "7")]
+        trash_retention_days: u64,
+        /// Keep the per-dot created/painted timestamps when saving artworks (larger database)
+        #[arg(long)]
+        keep_dot_timestamps: bool,
+        /// Initialization sequence sent before painting if a paint request does not specify one
+        #[arg(long, value_enum, default_value = "splatoon3-post-editor")]
+        init_preset: InitPresetArg,
+        /// Draw a small mark and wait for confirmation before painting if a paint request does not specify it
+        #[arg(long)]
+        preflight: bool,
+        /// Language of messages when a request does not choose one with Accept-Language
+        #[arg(long, value_enum, env = "SPLATOON3_LOCALE", default_value = "ja")]
+        locale: LocaleArg,
+        /// Serve web UI files from this directory first, falling back to the embedded assets
+        #[arg(long, env = "SPLATOON3_ASSETS_DIR")]
+        assets_dir: Option<PathBuf>,
+        /// Do not advertise the web UI on the local network over mDNS
+        #[arg(long, env = "SPLATOON3_NO_MDNS", value_parser = clap::builder::BoolishValueParser::new())]
+        no_mdns: bool,
+        /// Start with the read-only public gallery (/gallery) disabled; it can be re-enabled via PUT /api/system/gallery
+        #[arg(long, env = "SPLATOON3_NO_GALLERY", value_parser = clap::builder::BoolishValueParser::new())]
+        no_gallery: bool,
+        /// Do not check the connection to the Switch in the background (no live connection badge in the web UI)
+        #[arg(long, env = "SPLATOON3_NO_CONNECTION_MONITOR", value_parser = clap::builder::BoolishValueParser::new())]
+        no_connection_monitor: bool,
+        /// How often the background monitor checks the connection to the Switch (ms)
+        #[arg(long, default_value = "5000", value_parser = clap::value_parser!(u64).range(100..), conflicts_with = "no_connection_monitor")]
+        connection_monitor_interval_ms: u64,
+        /// Hostname advertised over mDNS (without .local)
+        #[arg(long, default_value = "splatoon3-drawer", conflicts_with = "no_mdns")]
+        mdns_hostname: String,
+        /// POST painting events (completed, cancelled, errors, ...) as JSON to this URL (repeatable)
+        #[arg(
+            long = "webhook-url",
+            env = "SPLATOON3_WEBHOOK_URLS",
+            value_delimiter = ','
+        )]
+        webhook_urls: Vec<String>,
+        /// Only send events of at least this severity to webhooks (default: the events shown to the user)
+        #[arg(long, value_enum, requires = "webhook_urls")]
+        webhook_min_severity: Option<WebhookSeverityArg>,
+        /// Only send events of these categories to webhooks (repeatable; default: all)
+        #[arg(long = "webhook-category", value_enum, requires = "webhook_urls")]
+        webhook_categories: Vec<WebhookCategoryArg>,
+        /// Exit once no painting is active or scheduled and no changes arrive for --drain-grace-secs
+        ///
+        /// For batch jobs: preload the artworks, paint them, and let a systemd unit power the board
+        /// off afterwards (e.g. `ExecStopPost=shutdown -h now`). Exits with status 0.
+        #[arg(long)]
+        drain: bool,
+        /// How long the server must stay idle before --drain exits (seconds)
+        #[arg(long, default_value = "30", requires = "drain")]
+        drain_grace_secs: u64,
+        /// Keep retrying controller initialization for this many seconds at startup (0 tries once)
+        ///
+        /// Covers boots where the gadget is still being configured; afterwards the server falls back to simulation.
+        #[arg(long, default_value = "30")]
+        controller_init_window_secs: u64,
+    },
+    /// Generate a built-in test pattern artwork for checking the hardware
+    ///
+    /// Prints the artwork JSON (accepted by POST /api/artworks) unless --server is given.
+    Generate {
+        /// Pattern to draw
+        #[arg(value_enum)]
+        pattern: TestPatternArg,
+        /// Canvas width in dots
+        #[arg(long, default_value = "320")]
+        width: u16,
+        /// Canvas height in dots
+        #[arg(long, default_value = "120")]
+        height: u16,
+        /// Size of one checkerboard cell in dots
+        #[arg(long)]
+        cell_size: Option<u16>,
+        /// Border thickness in dots
+        #[arg(long)]
+        thickness: Option<u16>,
+        /// Text for the text pattern (letters, digits and " -.:!?")
+        #[arg(long, required_if_eq("pattern", "text"))]
+        text: Option<String>,
+        /// Scale factor for the text pattern
+        #[arg(long)]
+        scale: Option<u16>,
+        /// Artwork name (defaults to test-<pattern>)
+        #[arg(long)]
+        name: Option<String>,
+        /// Create the artwork on a running server instead (e.g. http://localhost:8080)
+        #[arg(long)]
+        server: Option<String>,
+        /// Access token for --server (defaults to the token stored in --data-dir)
+        #[arg(long, env = "SPLATOON3_TOKEN")]
+        token: Option<String>,
+        /// Directory for application data (used to read the access token)
+        #[arg(long, default_value = "/var/lib/splatoon3-ghost-drawer")]
+        data_dir: PathBuf,
     },
     /// Remove all configurations created by setup (requires root privileges)
     Cleanup {
@@ -36,12 +206,108 @@ pub enum Commands {
         #[arg(long)]
         gadget_only: bool,
     },
+    /// Delete all application data (artworks, painting history, audit log, access token, certificates)
+    ///
+    /// Only the contents of --data-dir are removed; /boot, configfs and systemd units are left to `cleanup`.
+    /// Without --confirm, lists what would be removed.
+    ResetData {
+        /// Directory for application data to reset
+        #[arg(long, default_value = "/var/lib/splatoon3-ghost-drawer")]
+        data_dir: PathBuf,
+        /// Pass `reset-all-data` to actually delete the data
+        #[arg(long)]
+        confirm: Option<String>,
+        /// Reset a running server instead (e.g. http://localhost:8080); refused while it is painting
+        #[arg(long)]
+        server: Option<String>,
+        /// Access token for --server (defaults to the token stored in --data-dir)
+        #[arg(long, env = "SPLATOON3_TOKEN")]
+        token: Option<String>,
+    },
+    /// Measure the report cadence and path generation speed this board can sustain (about 15 seconds)
+    ///
+    /// Writes neutral reports to the HID gadget (a tmpfs file with --simulate), so the Switch does not
+    /// need to be in any particular state. The result is saved in --data-dir and shown by the web UI.
+    Benchmark {
+        /// Directory for application data where the latest result is saved
+        #[arg(long, default_value = "/var/lib/splatoon3-ghost-drawer")]
+        data_dir: PathBuf,
+        /// Write the reports to a tmpfs file instead of the HID gadget
+        #[arg(long)]
+        simulate: bool,
+        /// Run the benchmark in a running server instead (e.g. http://localhost:8080); refused while it is painting
+        #[arg(long)]
+        server: Option<String>,
+        /// Access token for --server (defaults to the token stored in --data-dir)
+        #[arg(long, env = "SPLATOON3_TOKEN")]
+        token: Option<String>,
+        /// Print the result as JSON for scripts
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "pretty")]
+        json: Option<JsonStyle>,
+    },
+    /// Show a live dashboard of a running server's painting in the terminal (read-only, q to quit)
+    ///
+    /// Polls the painting status and follows the progress stream of the web server, so it never
+    /// sends input to the controller itself. Keeps retrying while the server restarts.
+    Monitor {
+        /// Host of the web server to watch
+        #[arg(long, default_value = "127.0.0.1")]
+        host: String,
+        /// Port of the web server to watch
+        #[arg(short, long, default_value = "8080")]
+        port: u16,
+    },
+    /// Check, pause, resume or stop the painting of a running server (e.g. one started from the web UI)
+    ///
+    /// Exits with 1 when no painting was affected (for `status`, when none is running) and with 2
+    /// when the server could not be reached or refused the request.
+    Painting {
+        #[command(subcommand)]
+        action: PaintingActionArg,
+        /// Host of the web server
+        #[arg(long, default_value = "127.0.0.1", global = true)]
+        host: String,
+        /// Port of the web server
+        #[arg(short, long, default_value = "8080", global = true)]
+        port: u16,
+        /// Access token (defaults to the token stored in --data-dir)
+        #[arg(long, env = "SPLATOON3_TOKEN", global = true)]
+        token: Option<String>,
+        /// Directory for application data (used to read the access token)
+        #[arg(long, default_value = "/var/lib/splatoon3-ghost-drawer", global = true)]
+        data_dir: PathBuf,
+        /// Print the server's response as JSON for scripts
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "pretty", global = true)]
+        json: Option<JsonStyle>,
+    },
+    /// Run an end-to-end self-test in software, without the Switch or the HID gadget
+    ///
+    /// Converts a test image, generates paths with every strategy, paints with the mock controller and
+    /// calls the main endpoints of a web server on an ephemeral port. Prints a pass/fail table to stdout
+    /// (logs go to stderr) and exits with 1 naming the first failing stage.
+    Doctor {
+        /// Leave out the time column so the output is identical between runs
+        #[arg(long)]
+        no_timing: bool,
+    },
     /// Show system and connection information
     #[command(name = "info")]
     Info {
         /// Show verbose output with detailed information
         #[arg(short, long)]
         verbose: bool,
+        /// Directory for application data (used to show the web access token)
+        #[arg(long, default_value = "/var/lib/splatoon3-ghost-drawer")]
+        data_dir: PathBuf,
+        /// Print the report as JSON for scripts (the access token itself is never included)
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "pretty")]
+        json: Option<JsonStyle>,
+        /// Show the latest N gadget state changes (configure, reconnect, cleanup, ...) from the audit log instead
+        #[arg(long, value_name = "N", num_args = 0..=1, default_missing_value = "20")]
+        audit: Option<usize>,
+        /// Show the USB gadget attributes currently applied in configfs (VID/PID, report descriptor, UDC, /dev/hidg*) instead
+        #[arg(long, conflicts_with = "audit")]
+        gadget: bool,
     },
     /// Test controller connection and functionality
     #[command(name = "test")]
@@ -53,9 +319,29 @@ pub enum Commands {
         #[arg(short, long, default_value = "basic")]
         mode: String,
     },
+    /// Send a single controller input (e.g. to align the cursor before painting)
+    #[command(name = "input", group = clap::ArgGroup::new("input").required(true))]
+    Input {
+        /// Button to press: A, B, X, Y, L, R, ZL, ZR, PLUS, MINUS, HOME, CAPTURE, L_STICK, R_STICK
+        #[arg(long, group = "input")]
+        button: Option<String>,
+        /// D-pad direction: UP, DOWN, LEFT, RIGHT, UP_LEFT, UP_RIGHT, DOWN_LEFT, DOWN_RIGHT
+        #[arg(long, group = "input")]
+        dpad: Option<String>,
+        /// Left stick direction (same names as --dpad)
+        #[arg(long, group = "input")]
+        stick: Option<String>,
+        /// How long to hold the input in milliseconds (max 3000)
+        #[arg(short, long, default_value = "100")]
+        duration: u32,
+    },
     /// Diagnose connection issues with detailed information
     #[command(name = "diagnose")]
-    Diagnose,
+    Diagnose {
+        /// Print the report as JSON for scripts; exits with 1 when the connection is not healthy
+        #[arg(long, value_enum, num_args = 0..=1, default_missing_value = "pretty")]
+        json: Option<JsonStyle>,
+    },
     /// Fix USB connection issues (mainly for Orange Pi Zero 2W)
     #[command(name = "fix-connection")]
     FixConnection,
@@ -64,5 +350,115 @@ pub enum Commands {
     FixPermissions,
     /// [Internal] Configure USB gadget via configfs (called by systemd)
     #[command(name = "_internal_configure_gadget", hide = true)]
-    InternalConfigureGadget,
+    InternalConfigureGadget {
+        /// UDC to bind the gadget to (overrides /etc/splatoon3-ghost-drawer/gadget.conf)
+        #[arg(long)]
+        udc: Option<String>,
+        /// Wait up to this many seconds for /dev/hidg0 to appear before reporting success
+        #[arg(long, default_value = "20")]
+        device_timeout_secs: u64,
+        /// Wait up to this many seconds for a UDC to appear in /sys/class/udc at boot
+        #[arg(long, default_value = "30")]
+        wait_udc_secs: u64,
+    },
+}
+
+/// `painting` で行う操作
+#[derive(Subcommand, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PaintingActionArg {
+    /// Show the artwork, progress and ETA of the current painting
+    Status,
+    /// Pause the current painting
+    Pause,
+    /// Resume the paused painting
+    Resume,
+    /// Stop the current painting
+    Stop,
+}
+
+/// `--json` の出力形式
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JsonStyle {
+    /// Indented, one field per line
+    Pretty,
+    /// A single line
+    Compact,
+}
+
+/// 一時停止を受け付けるタイミング
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseModeArg {
+    /// Pause between individual D-pad taps, even while moving
+    Immediate,
+    /// Pause only after a dot is finished and the D-pad is back to neutral
+    Safe,
+}
+
+/// 描画前の初期化手順
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InitPresetArg {
+    /// Splatoon 3 post editor: press L 5 times for the small pen, then hold the stick to the top-left
+    Splatoon3PostEditor,
+    /// Send nothing; the pen and cursor are already set up by hand
+    None,
+}
+
+/// 文言の既定の言語
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LocaleArg {
+    /// Japanese
+    Ja,
+    /// English
+    En,
+}
+
+/// 生成するテストパターン
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TestPatternArg {
+    /// Checkerboard of --cell-size squares
+    Checkerboard,
+    /// Frame around the canvas edge
+    Border,
+    /// Horizontal and vertical lines through the center
+    Crosshair,
+    /// Line from the top-left to the bottom-right corner
+    Diagonal,
+    /// Text drawn with a built-in 3x5 font
+    Text,
+}
+
+/// アートワークと描画履歴の保存先
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StorageMode {
+    /// Keep everything in memory (lost on restart; for tests and simulation)
+    Memory,
+    /// Store in a SQLite database in the data directory
+    Sqlite,
+}
+
+/// Webhookに送るイベントの最低の重要度
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookSeverityArg {
+    /// All events except per-dot progress
+    Info,
+    /// Cancellations, deletions, resets and errors
+    Warning,
+    /// Errors only
+    Error,
+}
+
+/// Webhookに送るイベントのカテゴリ
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WebhookCategoryArg {
+    /// Artwork created, updated, deleted or reset
+    Artwork,
+    /// Painting started, paused, completed, cancelled or failed
+    Painting,
+}
+
+/// HTTPSの証明書モード
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TlsMode {
+    /// Generate and reuse a self-signed certificate in the data directory
+    SelfSigned,
 }
@@ -2,10 +2,26 @@
 //!
 //! プロジェクト全体のデバッグとログ機能を提供
 
-use std::fs;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 use tracing::{Level, debug, info};
-use tracing_appender::rolling::{RollingFileAppender, Rotation};
-use tracing_subscriber::EnvFilter;
+use tracing_subscriber::{EnvFilter, Registry, reload};
+
+/// `RUST_LOG` も `--log-level` も指定されていない場合のログフィルター
+pub const DEFAULT_LOG_FILTER: &str = "info,tokio_tungstenite=warn,tungstenite=warn";
+/// ログファイルの名前
+const LOG_FILE_NAME: &str = "splatoon3-ghost-drawer.log";
+
+/// 起動してから `SizeRotatingFile` に書き込んだバイト数
+static LOG_BYTES_WRITTEN: AtomicU64 = AtomicU64::new(0);
+
+/// 起動してからログファイル（監査ログを含む）に書き込んだバイト数
+pub fn log_bytes_written() -> u64 {
+    LOG_BYTES_WRITTEN.load(Ordering::Relaxed)
+}
 
 /// デバッグ設定
 #[derive(Debug, Clone)]
@@ -22,8 +38,16 @@ pub struct DebugConfig {
     pub use_json_format: bool,
     /// パフォーマンス測定を有効にするか
     pub enable_performance_tracking: bool,
+    /// ログファイルをローテーションするサイズ（バイト）
+    pub max_log_file_bytes: u64,
+    /// ローテーションで残す古いログファイルの数
+    pub max_log_files: usize,
 }
 
+/// 長時間の描画でもSDカードを使い切らないよう、ログファイルは合計60MiBまでにする
+const DEFAULT_MAX_LOG_FILE_BYTES: u64 = 10 * 1024 * 1024;
+const DEFAULT_MAX_LOG_FILES: usize = 5;
+
 impl Default for DebugConfig {
     fn default() -> Self {
         Self {
@@ -33,6 +57,8 @@ impl Default for DebugConfig {
             enable_console_logging: true,
             use_json_format: false,
             enable_performance_tracking: true,
+            max_log_file_bytes: DEFAULT_MAX_LOG_FILE_BYTES,
+            max_log_files: DEFAULT_MAX_LOG_FILES,
         }
     }
 }
@@ -47,6 +73,8 @@ impl DebugConfig {
             enable_console_logging: true,
             use_json_format: false,
             enable_performance_tracking: true,
+            max_log_file_bytes: DEFAULT_MAX_LOG_FILE_BYTES,
+            max_log_files: DEFAULT_MAX_LOG_FILES,
         }
     }
 
@@ -59,6 +87,8 @@ impl DebugConfig {
             enable_console_logging: false,
             use_json_format: true,
             enable_performance_tracking: false,
+            max_log_file_bytes: DEFAULT_MAX_LOG_FILE_BYTES,
+            max_log_files: DEFAULT_MAX_LOG_FILES,
         }
     }
 
@@ -71,6 +101,8 @@ impl DebugConfig {
             enable_console_logging: true,
             use_json_format: false,
             enable_performance_tracking: false,
+            max_log_file_bytes: DEFAULT_MAX_LOG_FILE_BYTES,
+            max_log_files: DEFAULT_MAX_LOG_FILES,
         }
     }
 }
@@ -89,15 +121,15 @@ pub fn init_logging(config: &DebugConfig) -> Result<(), Box<dyn std::error::Erro
 
     // シンプルな設定でサブスクライバーを初期化
     if config.enable_file_logging {
-        let file_appender = RollingFileAppender::new(
-            Rotation::DAILY,
-            &config.log_directory,
-            "splatoon3-ghost-drawer.log",
-        );
+        let file_appender = SizeRotatingFile::open(
+            Path::new(&config.log_directory).join(LOG_FILE_NAME),
+            config.max_log_file_bytes,
+            config.max_log_files,
+        )?;
 
         tracing_subscriber::fmt()
             .with_env_filter(env_filter)
-            .with_writer(file_appender)
+            .with_writer(Mutex::new(file_appender))
             .json()
             .init();
     } else {
@@ -116,6 +148,151 @@ pub fn init_logging(config: &DebugConfig) -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
+/// ログフィルターの指定が不正な場合などのエラー
+#[derive(Debug, thiserror::Error)]
+pub enum LogLevelError {
+    #[error("Invalid log filter '{directives}': {message}")]
+    Invalid { directives: String, message: String },
+    #[error("Failed to reload the log filter: {0}")]
+    Reload(String),
+}
+
+/// 実行中に変更できるログフィルター
+///
+/// `RUST_LOG` と同じ書式（`debug` や `info,splatoon3_ghost_drawer::infrastructure=trace`）で指定する。
+#[derive(Clone)]
+pub struct LogLevelControl {
+    handle: reload::Handle<EnvFilter, Registry>,
+    current: Arc<Mutex<String>>,
+}
+
+impl LogLevelControl {
+    /// 再読み込みできるフィルターの層を作る（サブスクライバーの最初の層として登録する）
+    pub fn new(
+        directives: &str,
+    ) -> Result<(reload::Layer<EnvFilter, Registry>, Self), LogLevelError> {
+        let (layer, handle) = reload::Layer::new(parse_filter(directives)?);
+        Ok((
+            layer,
+            Self {
+                handle,
+                current: Arc::new(Mutex::new(directives.to_string())),
+            },
+        ))
+    }
+
+    /// 現在のフィルター
+    pub fn current(&self) -> String {
+        self.current
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// フィルターを置き換える（不正な指定の場合は変更しない）
+    pub fn set(&self, directives: &str) -> Result<(), LogLevelError> {
+        let filter = parse_filter(directives)?;
+        let mut current = self.current.lock().unwrap_or_else(|e| e.into_inner());
+        self.handle
+            .reload(filter)
+            .map_err(|e| LogLevelError::Reload(e.to_string()))?;
+        *current = directives.to_string();
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for LogLevelControl {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogLevelControl")
+            .field("current", &self.current())
+            .finish()
+    }
+}
+
+fn parse_filter(directives: &str) -> Result<EnvFilter, LogLevelError> {
+    let invalid = |message: String| LogLevelError::Invalid {
+        directives: directives.to_string(),
+        message,
+    };
+    if directives.trim().is_empty() {
+        return Err(invalid("filter is empty".to_string()));
+    }
+    EnvFilter::builder()
+        .parse(directives)
+        .map_err(|e| invalid(e.to_string()))
+}
+
+/// 一定のサイズを超えたら `.1`、`.2`… と古い順にずらして書き直すログファイル
+///
+/// `max_files` を超えた古いファイルは削除する。
+pub struct SizeRotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl SizeRotatingFile {
+    pub fn open(path: impl Into<PathBuf>, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let path = path.into();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            written,
+            max_bytes: max_bytes.max(1),
+            max_files,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files == 0 {
+            fs::remove_file(&self.path)?;
+        } else {
+            let _ = fs::remove_file(self.rotated_path(self.max_files));
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for SizeRotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // 1行（1イベント）は分割しないので、書き込む前に超えるかどうかで判断する
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        LOG_BYTES_WRITTEN.fetch_add(written as u64, Ordering::Relaxed);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
 /// パフォーマンス測定用のマクロ
 #[macro_export]
 macro_rules! measure_time {
@@ -270,3 +447,51 @@ pub mod test_helpers {
         assert_eq!(result, 42);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_level_control_rejects_invalid_filters() {
+        let (_layer, control) = LogLevelControl::new(DEFAULT_LOG_FILTER).unwrap();
+        assert_eq!(control.current(), DEFAULT_LOG_FILTER);
+
+        control
+            .set("debug,splatoon3_ghost_drawer::infrastructure::hardware=trace")
+            .unwrap();
+        assert_eq!(
+            control.current(),
+            "debug,splatoon3_ghost_drawer::infrastructure::hardware=trace"
+        );
+
+        for invalid in ["", "  ", "info,foo=verbose"] {
+            assert!(
+                matches!(control.set(invalid), Err(LogLevelError::Invalid { .. })),
+                "{invalid:?}"
+            );
+        }
+        assert!(control.current().starts_with("debug"));
+    }
+
+    #[test]
+    fn test_size_rotating_file_keeps_limited_history() {
+        let dir = std::env::temp_dir().join(format!("log-rotation-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LOG_FILE_NAME);
+
+        let mut file = SizeRotatingFile::open(&path, 10, 2).unwrap();
+        for line in ["line-1\n", "line-2\n", "line-3\n", "line-4\n"] {
+            file.write_all(line.as_bytes()).unwrap();
+        }
+        file.flush().unwrap();
+
+        let read = |path: PathBuf| fs::read_to_string(path).unwrap();
+        assert_eq!(read(path.clone()), "line-4\n");
+        assert_eq!(read(file.rotated_path(1)), "line-3\n");
+        assert_eq!(read(file.rotated_path(2)), "line-2\n");
+        assert!(!file.rotated_path(3).exists());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,162 @@
+//! 2つのキャンバスの比較
+//!
+//! 描画済みのアートワークを少し修正した場合に、変わったドットだけを描き直すために使う。
+//! 描画済みかどうかは問わず、不透明なドットがあるかどうかだけで比較する。
+
+use crate::domain::artwork::entities::{Canvas, CanvasError};
+use crate::domain::shared::value_objects::Coordinates;
+
+/// 2つのキャンバスの差分（座標はいずれも行優先の昇順）
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CanvasDiff {
+    /// 比較元（`self`）だけにあるドット
+    pub only_in_base: Vec<Coordinates>,
+    /// 比較先（`other`）だけにあるドット
+    pub only_in_target: Vec<Coordinates>,
+    /// 両方にあるドット
+    pub in_both: Vec<Coordinates>,
+}
+
+impl CanvasDiff {
+    /// 2つのキャンバスのドットが同じか
+    pub fn is_identical(&self) -> bool {
+        self.only_in_base.is_empty() && self.only_in_target.is_empty()
+    }
+}
+
+impl Canvas {
+    /// `other` と比較し、それぞれにだけあるドットと両方にあるドットを求める
+    ///
+    /// キャンバスのサイズが異なる場合はエラー
+    pub fn diff(&self, other: &Canvas) -> Result<CanvasDiff, CanvasError> {
+        if (self.width, self.height) != (other.width, other.height) {
+            return Err(CanvasError::SizeMismatch {
+                expected: (self.width, self.height),
+                actual: (other.width, other.height),
+            });
+        }
+
+        let mut diff = CanvasDiff::default();
+        for (coord, dot) in &self.dots {
+            if !dot.is_visible() {
+                continue;
+            }
+            if other.dots.get(coord).is_some_and(|dot| dot.is_visible()) {
+                diff.in_both.push(*coord);
+            } else {
+                diff.only_in_base.push(*coord);
+            }
+        }
+        diff.only_in_target = other
+            .dots
+            .iter()
+            .filter(|(coord, dot)| {
+                dot.is_visible() && !self.dots.get(coord).is_some_and(|dot| dot.is_visible())
+            })
+            .map(|(coord, _)| *coord)
+            .collect();
+
+        for coordinates in [
+            &mut diff.only_in_base,
+            &mut diff.only_in_target,
+            &mut diff.in_both,
+        ] {
+            coordinates.sort_unstable_by_key(|coord| (coord.y, coord.x));
+        }
+        Ok(diff)
+    }
+
+    /// `base` になく、このキャンバスにだけあるドットを残したキャンバスを作成
+    ///
+    /// 描画済みの状態はこのキャンバスのものを引き継ぐ
+    pub fn without_dots_in(&self, base: &Canvas) -> Result<Canvas, CanvasError> {
+        let diff = base.diff(self)?;
+        let mut canvas = Canvas::with_background(self.width, self.height, self.background_color);
+        canvas.dots = diff
+            .only_in_target
+            .iter()
+            .filter_map(|coord| self.dots.get(coord).map(|dot| (*coord, dot.clone())))
+            .collect();
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::artwork::entities::Dot;
+
+    fn canvas_with(dots: &[(u16, u16)]) -> Canvas {
+        let mut canvas = Canvas::new(10, 5);
+        for &(x, y) in dots {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        canvas
+    }
+
+    fn coords(dots: &[(u16, u16)]) -> Vec<Coordinates> {
+        dots.iter().map(|&(x, y)| Coordinates::new(x, y)).collect()
+    }
+
+    #[test]
+    fn test_identical_canvases() {
+        let base = canvas_with(&[(0, 0), (3, 1), (9, 4)]);
+        let mut target = base.clone();
+        // 描画済みかどうかは比較に影響しない
+        target
+            .get_dot_mut(&Coordinates::new(3, 1))
+            .unwrap()
+            .mark_as_painted();
+
+        let diff = base.diff(&target).unwrap();
+        assert!(diff.is_identical());
+        assert_eq!(diff.in_both, coords(&[(0, 0), (3, 1), (9, 4)]));
+        assert!(target.without_dots_in(&base).unwrap().dots.is_empty());
+    }
+
+    #[test]
+    fn test_disjoint_canvases() {
+        let base = canvas_with(&[(1, 0), (0, 1)]);
+        let target = canvas_with(&[(2, 2), (5, 0)]);
+
+        let diff = base.diff(&target).unwrap();
+        assert_eq!(diff.only_in_base, coords(&[(1, 0), (0, 1)]));
+        assert_eq!(diff.only_in_target, coords(&[(5, 0), (2, 2)]));
+        assert!(diff.in_both.is_empty());
+        assert!(!diff.is_identical());
+    }
+
+    #[test]
+    fn test_partially_overlapping_canvases() {
+        let mut base = canvas_with(&[(0, 0), (1, 0), (2, 0)]);
+        // 透明なドットは無いものとして扱う
+        base.set_dot(Coordinates::new(4, 4), Dot::transparent())
+            .unwrap();
+        let target = canvas_with(&[(1, 0), (2, 0), (3, 0), (4, 4)]);
+
+        let diff = base.diff(&target).unwrap();
+        assert_eq!(diff.only_in_base, coords(&[(0, 0)]));
+        assert_eq!(diff.only_in_target, coords(&[(3, 0), (4, 4)]));
+        assert_eq!(diff.in_both, coords(&[(1, 0), (2, 0)]));
+
+        let changed = target.without_dots_in(&base).unwrap();
+        let mut painted: Vec<_> = changed.dots.keys().copied().collect();
+        painted.sort_unstable_by_key(|coord| (coord.y, coord.x));
+        assert_eq!(painted, coords(&[(3, 0), (4, 4)]));
+    }
+
+    #[test]
+    fn test_size_mismatch_is_rejected() {
+        let base = Canvas::new(10, 5);
+        let target = Canvas::new(10, 6);
+        assert!(matches!(
+            base.diff(&target),
+            Err(CanvasError::SizeMismatch {
+                expected: (10, 5),
+                actual: (10, 6)
+            })
+        ));
+    }
+}
@@ -0,0 +1,299 @@
+//! キャンバスの行優先のランレングス符号化
+//!
+//! ドットを1つずつ `{x, y, color}` で送ると、塗りつぶしの多いキャンバスでは数MBになり、
+//! Piでの生成もブラウザでの解析も遅い。同じ行で連続する同じ色・レイヤーのドットを
+//! `[start_x, length, color]`（レイヤーが0以外なら `[start_x, length, color, layer]`）の
+//! 1つの区間にまとめる。描画の進捗・日時・不透明度は含めない（見えないドットは出力しない）。
+
+use crate::domain::artwork::entities::{Canvas, Dot};
+use crate::domain::shared::value_objects::{Color, Coordinates};
+use serde::de::{self, SeqAccess, Visitor};
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thiserror::Error;
+
+/// 1行の中で同じ色・レイヤーが連続する区間
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanvasRun {
+    pub start_x: u16,
+    pub length: u16,
+    pub color: Color,
+    pub layer: u8,
+}
+
+impl Serialize for CanvasRun {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let len = if self.layer == 0 { 3 } else { 4 };
+        let mut seq = serializer.serialize_seq(Some(len))?;
+        seq.serialize_element(&self.start_x)?;
+        seq.serialize_element(&self.length)?;
+        seq.serialize_element(&self.color.to_hex())?;
+        if self.layer != 0 {
+            seq.serialize_element(&self.layer)?;
+        }
+        seq.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for CanvasRun {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RunVisitor;
+
+        impl<'de> Visitor<'de> for RunVisitor {
+            type Value = CanvasRun;
+
+            fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+                formatter.write_str("[start_x, length, color] or [start_x, length, color, layer]")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<CanvasRun, A::Error> {
+                let start_x = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let length = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let color: String = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(2, &self))?;
+                let color = Color::from_hex(&color).map_err(de::Error::custom)?;
+                let layer = seq.next_element()?.unwrap_or(0);
+                if seq.next_element::<de::IgnoredAny>()?.is_some() {
+                    return Err(de::Error::invalid_length(5, &self));
+                }
+                Ok(CanvasRun {
+                    start_x,
+                    length,
+                    color,
+                    layer,
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(RunVisitor)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum CanvasRleError {
+    #[error("{rows} rows exceed the canvas height {height}")]
+    TooManyRows { rows: usize, height: u16 },
+    #[error("Run at x={start_x} in row {row} is empty")]
+    EmptyRun { row: usize, start_x: u16 },
+    #[error("Run at x={start_x} (length {length}) in row {row} exceeds the canvas width {width}")]
+    OutOfBounds {
+        row: usize,
+        start_x: u16,
+        length: u16,
+        width: u16,
+    },
+    #[error("Run at x={start_x} in row {row} overlaps or precedes the previous run")]
+    Overlapping { row: usize, start_x: u16 },
+}
+
+impl Canvas {
+    /// 見えるドットを行ごとの区間に符号化する（末尾の空の行は省く）
+    pub fn encode_rle(&self) -> Vec<Vec<CanvasRun>> {
+        let mut dots: Vec<(&Coordinates, &Dot)> = self
+            .dots
+            .iter()
+            .filter(|(_, dot)| dot.is_visible())
+            .collect();
+        dots.sort_by_key(|(coordinates, _)| (coordinates.y, coordinates.x));
+
+        let mut rows: Vec<Vec<CanvasRun>> = Vec::new();
+        for (coordinates, dot) in dots {
+            let y = coordinates.y as usize;
+            if rows.len() <= y {
+                rows.resize_with(y + 1, Vec::new);
+            }
+            let row = &mut rows[y];
+            match row.last_mut() {
+                Some(run)
+                    if run.start_x + run.length == coordinates.x
+                        && run.color == dot.color
+                        && run.layer == dot.layer =>
+                {
+                    run.length += 1;
+                }
+                _ => row.push(CanvasRun {
+                    start_x: coordinates.x,
+                    length: 1,
+                    color: dot.color,
+                    layer: dot.layer,
+                }),
+            }
+        }
+        rows
+    }
+
+    /// `encode_rle` の区間から `width` x `height` のキャンバスを作る
+    ///
+    /// 区間は各行でx座標の昇順に並び、重ならず、キャンバスに収まっていなければならない。
+    pub fn decode_rle(
+        width: u16,
+        height: u16,
+        rows: &[Vec<CanvasRun>],
+    ) -> Result<Canvas, CanvasRleError> {
+        if rows.len() > height as usize {
+            return Err(CanvasRleError::TooManyRows {
+                rows: rows.len(),
+                height,
+            });
+        }
+        let mut canvas = Canvas::new(width, height);
+        for (y, runs) in rows.iter().enumerate() {
+            let mut next_x = 0u32;
+            for run in runs {
+                if run.length == 0 {
+                    return Err(CanvasRleError::EmptyRun {
+                        row: y,
+                        start_x: run.start_x,
+                    });
+                }
+                let end = run.start_x as u32 + run.length as u32;
+                if end > width as u32 {
+                    return Err(CanvasRleError::OutOfBounds {
+                        row: y,
+                        start_x: run.start_x,
+                        length: run.length,
+                        width,
+                    });
+                }
+                if (run.start_x as u32) < next_x {
+                    return Err(CanvasRleError::Overlapping {
+                        row: y,
+                        start_x: run.start_x,
+                    });
+                }
+                next_x = end;
+                for x in run.start_x..run.start_x + run.length {
+                    canvas.dots.insert(
+                        Coordinates::new(x, y as u16),
+                        Dot::with_layer(run.color, 255, run.layer),
+                    );
+                }
+            }
+        }
+        Ok(canvas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// テスト用の再現可能な疑似乱数（xorshift）
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % bound
+        }
+    }
+
+    /// 比較に使うドットの内容（座標順）
+    fn content(canvas: &Canvas) -> Vec<(u16, u16, Color, u8)> {
+        let mut dots: Vec<_> = canvas
+            .dots
+            .iter()
+            .filter(|(_, dot)| dot.is_visible())
+            .map(|(c, dot)| (c.y, c.x, dot.color, dot.layer))
+            .collect();
+        dots.sort_by_key(|&(y, x, _, _)| (y, x));
+        dots
+    }
+
+    #[test]
+    fn test_random_canvases_round_trip() {
+        let palette = [
+            Color::black(),
+            Color::white(),
+            Color::new(255, 0, 0, 255),
+            Color::new(0, 0, 255, 128),
+        ];
+        for seed in 1..=200u64 {
+            let mut random = Random(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15));
+            let width = 1 + random.next(64) as u16;
+            let height = 1 + random.next(32) as u16;
+            // 疎なものから全面に近いものまで密度を変える
+            let density = random.next(101);
+            let mut canvas = Canvas::new(width, height);
+            for y in 0..height {
+                for x in 0..width {
+                    if random.next(100) < density {
+                        let color = palette[random.next(palette.len() as u64) as usize];
+                        let layer = if random.next(4) == 0 { 1 } else { 0 };
+                        canvas
+                            .set_dot(Coordinates::new(x, y), Dot::with_layer(color, 255, layer))
+                            .unwrap();
+                    }
+                }
+            }
+
+            let rows = canvas.encode_rle();
+            let json = serde_json::to_string(&rows).unwrap();
+            let parsed: Vec<Vec<CanvasRun>> = serde_json::from_str(&json).unwrap();
+            assert_eq!(parsed, rows, "seed {seed}");
+            let decoded = Canvas::decode_rle(width, height, &parsed).unwrap();
+            assert_eq!(content(&decoded), content(&canvas), "seed {seed}");
+            assert_eq!(decoded.encode_rle(), rows, "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn test_runs_merge_same_color_and_layer_and_skip_invisible_dots() {
+        let mut canvas = Canvas::new(10, 3);
+        for x in 2..6 {
+            canvas
+                .set_dot(Coordinates::new(x, 1), Dot::black())
+                .unwrap();
+        }
+        canvas
+            .set_dot(
+                Coordinates::new(6, 1),
+                Dot::with_layer(Color::black(), 255, 2),
+            )
+            .unwrap();
+        canvas
+            .set_dot(Coordinates::new(0, 0), Dot::transparent())
+            .unwrap();
+
+        let rows = canvas.encode_rle();
+        assert_eq!(
+            serde_json::to_string(&rows).unwrap(),
+            r##"[[],[[2,4,"#000000"],[6,1,"#000000",2]]]"##
+        );
+    }
+
+    #[test]
+    fn test_invalid_runs_are_rejected() {
+        let run = |start_x, length| CanvasRun {
+            start_x,
+            length,
+            color: Color::black(),
+            layer: 0,
+        };
+        assert_eq!(
+            Canvas::decode_rle(4, 1, &[vec![], vec![]]).unwrap_err(),
+            CanvasRleError::TooManyRows { rows: 2, height: 1 }
+        );
+        assert!(matches!(
+            Canvas::decode_rle(4, 1, &[vec![run(3, 2)]]),
+            Err(CanvasRleError::OutOfBounds { .. })
+        ));
+        assert!(matches!(
+            Canvas::decode_rle(4, 1, &[vec![run(0, 0)]]),
+            Err(CanvasRleError::EmptyRun { .. })
+        ));
+        assert!(matches!(
+            Canvas::decode_rle(4, 1, &[vec![run(0, 2), run(1, 1)]]),
+            Err(CanvasRleError::Overlapping { .. })
+        ));
+        assert!(serde_json::from_str::<CanvasRun>(r#"[0, 1, "nope"]"#).is_err());
+        assert!(serde_json::from_str::<CanvasRun>(r##"[0, 1, "#000000", 0, 9]"##).is_err());
+    }
+}
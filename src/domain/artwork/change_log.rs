@@ -0,0 +1,415 @@
+//! アートワークの変更履歴
+//!
+//! バージョンを上げる操作ごとに、何がどう変わったかを1件ずつアートワークと一緒に保存する。
+//! 記録とドメインイベントは同じ `ArtworkChange` から作るため、どちらか一方だけが残ることはない。
+//! 保存するのは直近の `MAX_CHANGE_LOG_ENTRIES` 件まで。
+
+use crate::domain::artwork::entities::{Artwork, ArtworkMetadata, Canvas};
+use crate::domain::artwork::history::HistoryDirection;
+use crate::domain::artwork::value_objects::{CanvasTransform, Polyline};
+use crate::domain::events::ArtworkEvent;
+use crate::domain::painting::value_objects::PaintingPreferences;
+use crate::domain::shared::events::EventMetadata;
+use crate::domain::shared::value_objects::Timestamp;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// アートワークごとに保存する変更履歴の件数（古いものから捨てる）
+pub const MAX_CHANGE_LOG_ENTRIES: usize = 100;
+
+/// 変更の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    /// 作成（アップロード・生成・インポート）
+    Created,
+    /// 別のアートワークの複製として作成
+    Duplicated,
+    /// キャンバスへの変形の適用
+    Transformed,
+    /// ドットの編集
+    DotsEdited,
+    /// 編集の取り消し
+    Undo,
+    /// 編集のやり直し
+    Redo,
+    /// 名前・説明・作者・タグの更新
+    MetadataUpdated,
+    /// 描画設定の更新
+    PreferencesUpdated,
+    /// 線画の置き換え
+    VectorPathsUpdated,
+    /// 描画できたドットの記録
+    Painted,
+    /// 描画の進捗のリセット
+    ProgressReset,
+}
+
+/// 変更前後で見えるドットの増減（描画済みかどうかは問わない）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DotDelta {
+    pub added: usize,
+    pub removed: usize,
+    /// 両方にあり、色・不透明度・レイヤーのいずれかが変わったドット
+    pub changed: usize,
+}
+
+impl DotDelta {
+    /// 変更前後のキャンバスを座標ごとに比べる
+    pub fn between(before: &Canvas, after: &Canvas) -> Self {
+        let mut delta = Self::default();
+        for (coordinates, dot) in before.dots.iter().filter(|(_, dot)| dot.is_visible()) {
+            match after.dots.get(coordinates).filter(|dot| dot.is_visible()) {
+                Some(other)
+                    if (other.color, other.opacity, other.layer)
+                        != (dot.color, dot.opacity, dot.layer) =>
+                {
+                    delta.changed += 1
+                }
+                Some(_) => {}
+                None => delta.removed += 1,
+            }
+        }
+        delta.added = after
+            .dots
+            .iter()
+            .filter(|(coordinates, dot)| {
+                dot.is_visible()
+                    && !before
+                        .dots
+                        .get(coordinates)
+                        .is_some_and(|dot| dot.is_visible())
+            })
+            .count();
+        delta
+    }
+}
+
+/// 変更履歴の1件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChangeLogEntry {
+    /// 変更後のバージョン
+    pub version: u32,
+    pub occurred_at: Timestamp,
+    /// 変更した経路（イベントの `source` と同じ値）
+    pub source: String,
+    pub kind: ChangeKind,
+    /// 変更内容の短い説明（例: `transformed: flip_horizontal`）
+    pub summary: String,
+    pub dots: DotDelta,
+}
+
+/// 1回の変更の内容（変更履歴とドメインイベントの両方の元になる）
+#[derive(Debug, Clone)]
+pub struct ArtworkChange {
+    kind: ChangeKind,
+    summary: String,
+    dots: DotDelta,
+    /// メタデータの変更前の内容（メタデータ更新イベントに使う）
+    previous_metadata: Option<ArtworkMetadata>,
+}
+
+impl ArtworkChange {
+    fn new(kind: ChangeKind, summary: String, dots: DotDelta) -> Self {
+        Self {
+            kind,
+            summary,
+            dots,
+            previous_metadata: None,
+        }
+    }
+
+    pub fn dots(&self) -> DotDelta {
+        self.dots
+    }
+
+    /// 作成（キャンバスのドットはすべて追加として数える）
+    pub fn created(original_format: &str, canvas: &Canvas) -> Self {
+        Self::new(
+            ChangeKind::Created,
+            format!(
+                "created: {} {}x{}",
+                original_format, canvas.width, canvas.height
+            ),
+            DotDelta::between(&Canvas::new(canvas.width, canvas.height), canvas),
+        )
+    }
+
+    /// `source` を複製し、`transforms` を適用して `copy` を作成した
+    pub fn duplicated(source: &Artwork, copy: &Canvas, transforms: &[CanvasTransform]) -> Self {
+        let mut summary = format!("duplicated from {}", source.id);
+        if !transforms.is_empty() {
+            summary.push_str(&format!(
+                "; transformed: {}",
+                describe_transforms(transforms)
+            ));
+        }
+        Self::new(
+            ChangeKind::Duplicated,
+            summary,
+            DotDelta::between(&source.canvas, copy),
+        )
+    }
+
+    /// キャンバスに変形を順に適用した
+    pub fn transformed(before: &Canvas, after: &Canvas, transforms: &[CanvasTransform]) -> Self {
+        Self::new(
+            ChangeKind::Transformed,
+            format!("transformed: {}", describe_transforms(transforms)),
+            DotDelta::between(before, after),
+        )
+    }
+
+    /// ドットを編集した（`edited` は差分に含まれていたドット数）
+    pub fn dots_edited(before: &Canvas, after: &Canvas, edited: u64) -> Self {
+        Self::new(
+            ChangeKind::DotsEdited,
+            format!("edited {edited} dots"),
+            DotDelta::between(before, after),
+        )
+    }
+
+    /// 取り消し・やり直しで内容を戻した
+    pub fn history_step(direction: HistoryDirection, before: &Canvas, after: &Canvas) -> Self {
+        let kind = match direction {
+            HistoryDirection::Undo => ChangeKind::Undo,
+            HistoryDirection::Redo => ChangeKind::Redo,
+        };
+        let dots = DotDelta::between(before, after);
+        let restored = dots.added + dots.removed + dots.changed;
+        let summary = match direction {
+            HistoryDirection::Undo => format!("undo: restored {restored} dots"),
+            HistoryDirection::Redo => format!("redo: restored {restored} dots"),
+        };
+        Self::new(kind, summary, dots)
+    }
+
+    /// メタデータを `old` から `new` に更新した
+    pub fn metadata_updated(old: ArtworkMetadata, new: &ArtworkMetadata) -> Self {
+        let mut parts = Vec::new();
+        if old.name != new.name {
+            parts.push(format!("renamed: '{}' -> '{}'", old.name, new.name));
+        }
+        let fields: Vec<&str> = [
+            ("description", old.description != new.description),
+            ("author", old.author != new.author),
+            ("tags", old.tags != new.tags),
+        ]
+        .into_iter()
+        .filter_map(|(field, changed)| changed.then_some(field))
+        .collect();
+        if !fields.is_empty() {
+            parts.push(format!("updated: {}", fields.join(", ")));
+        }
+        Self {
+            previous_metadata: Some(old),
+            ..Self::new(
+                ChangeKind::MetadataUpdated,
+                parts.join("; "),
+                DotDelta::default(),
+            )
+        }
+    }
+
+    /// 描画設定を置き換えた
+    pub fn preferences_updated(preferences: Option<&PaintingPreferences>) -> Self {
+        let summary = match preferences {
+            Some(_) => "painting preferences updated",
+            None => "painting preferences cleared",
+        };
+        Self::new(
+            ChangeKind::PreferencesUpdated,
+            summary.to_string(),
+            DotDelta::default(),
+        )
+    }
+
+    /// 線画を置き換えた
+    pub fn vector_paths_updated(vector_paths: &[Polyline]) -> Self {
+        let summary = if vector_paths.is_empty() {
+            "vector paths cleared".to_string()
+        } else {
+            format!("vector paths replaced: {} strokes", vector_paths.len())
+        };
+        Self::new(ChangeKind::VectorPathsUpdated, summary, DotDelta::default())
+    }
+
+    /// `marked` 個のドットを描画済みにした
+    pub fn painted(marked: usize) -> Self {
+        Self::new(
+            ChangeKind::Painted,
+            format!("painted {marked} dots"),
+            DotDelta::default(),
+        )
+    }
+
+    /// 描画の進捗を消した（`previous_completion_ratio` は消す前の完成度）
+    pub fn progress_reset(previous_completion_ratio: f64) -> Self {
+        Self::new(
+            ChangeKind::ProgressReset,
+            format!(
+                "painting progress reset (was {:.1}% complete)",
+                previous_completion_ratio * 100.0
+            ),
+            DotDelta::default(),
+        )
+    }
+}
+
+fn describe_transforms(transforms: &[CanvasTransform]) -> String {
+    transforms
+        .iter()
+        .map(|transform| match transform {
+            CanvasTransform::FlipHorizontal => "flip_horizontal".to_string(),
+            CanvasTransform::FlipVertical => "flip_vertical".to_string(),
+            CanvasTransform::Threshold { value } => format!("threshold({value})"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Artwork {
+    /// 変更を適用した後に呼び、変更履歴に追記して対応するドメインイベントを返す
+    ///
+    /// バージョンと日時は、変更を適用した後のアートワークの値を記録する。
+    /// 描画の進捗など、対応するイベントが無い変更は `None`。
+    pub fn record_change(
+        &mut self,
+        change: ArtworkChange,
+        event_metadata: EventMetadata,
+    ) -> Option<ArtworkEvent> {
+        self.change_log.push(ChangeLogEntry {
+            version: self.version,
+            occurred_at: self.updated_at,
+            source: event_metadata.source.clone(),
+            kind: change.kind,
+            summary: change.summary,
+            dots: change.dots,
+        });
+        let overflow = self.change_log.len().saturating_sub(MAX_CHANGE_LOG_ENTRIES);
+        self.change_log.drain(..overflow);
+
+        match change.kind {
+            ChangeKind::Created | ChangeKind::Duplicated => Some(ArtworkEvent::artwork_created(
+                self.id.clone(),
+                self.metadata.clone(),
+                self.original_format.clone(),
+                &self.canvas,
+                self.version,
+                event_metadata,
+            )),
+            ChangeKind::Transformed
+            | ChangeKind::DotsEdited
+            | ChangeKind::Undo
+            | ChangeKind::Redo => Some(ArtworkEvent::canvas_updated(
+                self.id.clone(),
+                &self.canvas,
+                self.version,
+                event_metadata,
+            )),
+            ChangeKind::MetadataUpdated => change.previous_metadata.map(|old_metadata| {
+                ArtworkEvent::metadata_updated(
+                    self.id.clone(),
+                    old_metadata,
+                    self.metadata.clone(),
+                    self.version,
+                    event_metadata,
+                )
+            }),
+            ChangeKind::PreferencesUpdated
+            | ChangeKind::VectorPathsUpdated
+            | ChangeKind::Painted
+            | ChangeKind::ProgressReset => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::artwork::entities::Dot;
+    use crate::domain::shared::value_objects::{Color, Coordinates};
+
+    fn canvas(dots: &[(u16, u16, Color)]) -> Canvas {
+        let mut canvas = Canvas::new(4, 4);
+        for &(x, y, color) in dots {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::new(color, 255))
+                .unwrap();
+        }
+        canvas
+    }
+
+    #[test]
+    fn test_dot_delta_counts_added_removed_and_changed_dots() {
+        let before = canvas(&[
+            (0, 0, Color::black()),
+            (1, 0, Color::black()),
+            (2, 0, Color::black()),
+        ]);
+        let after = canvas(&[
+            (0, 0, Color::black()),
+            (1, 0, Color::white()),
+            (3, 3, Color::black()),
+        ]);
+        assert_eq!(
+            DotDelta::between(&before, &after),
+            DotDelta {
+                added: 1,
+                removed: 1,
+                changed: 1
+            }
+        );
+    }
+
+    #[test]
+    fn test_record_change_keeps_the_latest_entries_and_builds_the_event() {
+        let mut artwork = Artwork::new(
+            ArtworkMetadata::new("log".to_string()),
+            "png".to_string(),
+            canvas(&[(0, 0, Color::black())]),
+        );
+        let created = artwork.record_change(
+            ArtworkChange::created("png", &artwork.canvas.clone()),
+            EventMetadata::new("api".to_string()),
+        );
+        assert!(matches!(
+            created,
+            Some(ArtworkEvent::ArtworkCreated { version: 1, .. })
+        ));
+        assert_eq!(artwork.change_log[0].dots.added, 1);
+
+        for _ in 0..MAX_CHANGE_LOG_ENTRIES {
+            artwork.reset_painting_state();
+            let event = artwork.record_change(
+                ArtworkChange::progress_reset(0.0),
+                EventMetadata::new("api".to_string()),
+            );
+            assert!(event.is_none());
+        }
+        assert_eq!(artwork.change_log.len(), MAX_CHANGE_LOG_ENTRIES);
+        assert_eq!(artwork.change_log[0].version, 2);
+        assert_eq!(
+            artwork.change_log.last().unwrap().version,
+            MAX_CHANGE_LOG_ENTRIES as u32 + 1
+        );
+
+        let old = artwork.metadata.clone();
+        let mut renamed = old.clone();
+        renamed.name = "renamed".to_string();
+        artwork.update_metadata(renamed);
+        let event = artwork.record_change(
+            ArtworkChange::metadata_updated(old, &artwork.metadata.clone()),
+            EventMetadata::new("api".to_string()),
+        );
+        assert!(matches!(
+            event,
+            Some(ArtworkEvent::ArtworkMetadataUpdated { old_metadata, new_metadata, .. })
+                if old_metadata.name == "log" && new_metadata.name == "renamed"
+        ));
+        assert_eq!(
+            artwork.change_log.last().unwrap().summary,
+            "renamed: 'log' -> 'renamed'"
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! ピクセルエディタからの一括ドット差分
+//!
+//! 塗りつぶしなどで数万ドットを一度に変更する場合に、JSONより小さく速く扱えるバイナリ形式。
+//! 1レコード7バイトのリトルエンディアンで、レコードを隙間なく並べる。
+//!
+//! | オフセット | サイズ | 内容 |
+//! |---|---|---|
+//! | 0 | 2 | x（u16） |
+//! | 2 | 2 | y（u16） |
+//! | 4 | 1 | 操作（`0` = clear、`1` = set） |
+//! | 5 | 2 | ラン長（u16、1以上）。(x, y) から右方向に同じ操作を続けるドット数 |
+
+use crate::domain::artwork::entities::{Canvas, Dot};
+use crate::domain::shared::value_objects::Coordinates;
+
+/// 1レコードのバイト数
+pub const DOT_DIFF_RECORD_SIZE: usize = 7;
+
+/// ドットに対する操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DotOp {
+    /// ドットを削除
+    Clear,
+    /// 黒ドットを置く
+    Set,
+}
+
+/// 同じ行で連続するドットへの同じ操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DotRun {
+    pub x: u16,
+    pub y: u16,
+    pub op: DotOp,
+    pub length: u16,
+}
+
+impl DotRun {
+    pub fn single(x: u16, y: u16, op: DotOp) -> Self {
+        Self {
+            x,
+            y,
+            op,
+            length: 1,
+        }
+    }
+}
+
+/// ドット差分のエラー
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DotDiffError {
+    #[error("Payload length {0} is not a multiple of {DOT_DIFF_RECORD_SIZE} bytes")]
+    InvalidLength(usize),
+    #[error("Record {index} has unknown operation {op}")]
+    UnknownOperation { index: usize, op: u8 },
+    #[error("Record {index} has an empty run")]
+    EmptyRun { index: usize },
+    #[error("Record {index} ({x}, {y}) x{length} is outside the {width}x{height} canvas")]
+    OutOfBounds {
+        index: usize,
+        x: u16,
+        y: u16,
+        length: u16,
+        width: u16,
+        height: u16,
+    },
+    #[error("Payload changes {changes} dots, more than the canvas capacity of {capacity}")]
+    TooLarge { changes: u64, capacity: u64 },
+}
+
+/// デコード済みのドット差分
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DotDiff {
+    pub runs: Vec<DotRun>,
+}
+
+impl DotDiff {
+    pub fn new(runs: Vec<DotRun>) -> Self {
+        Self { runs }
+    }
+
+    /// バイナリ形式からデコード（座標の範囲はキャンバスに適用するときに検証する）
+    pub fn decode(payload: &[u8]) -> Result<Self, DotDiffError> {
+        if !payload.len().is_multiple_of(DOT_DIFF_RECORD_SIZE) {
+            return Err(DotDiffError::InvalidLength(payload.len()));
+        }
+        let runs = payload
+            .chunks_exact(DOT_DIFF_RECORD_SIZE)
+            .enumerate()
+            .map(|(index, record)| {
+                let op = match record[4] {
+                    0 => DotOp::Clear,
+                    1 => DotOp::Set,
+                    op => return Err(DotDiffError::UnknownOperation { index, op }),
+                };
+                let length = u16::from_le_bytes([record[5], record[6]]);
+                if length == 0 {
+                    return Err(DotDiffError::EmptyRun { index });
+                }
+                Ok(DotRun {
+                    x: u16::from_le_bytes([record[0], record[1]]),
+                    y: u16::from_le_bytes([record[2], record[3]]),
+                    op,
+                    length,
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { runs })
+    }
+
+    /// バイナリ形式にエンコード
+    pub fn encode(&self) -> Vec<u8> {
+        let mut payload = Vec::with_capacity(self.runs.len() * DOT_DIFF_RECORD_SIZE);
+        for run in &self.runs {
+            payload.extend_from_slice(&run.x.to_le_bytes());
+            payload.extend_from_slice(&run.y.to_le_bytes());
+            payload.push(match run.op {
+                DotOp::Clear => 0,
+                DotOp::Set => 1,
+            });
+            payload.extend_from_slice(&run.length.to_le_bytes());
+        }
+        payload
+    }
+
+    /// 変更するドット数の合計（ラン長の合計）
+    pub fn changes(&self) -> u64 {
+        self.runs.iter().map(|run| run.length as u64).sum()
+    }
+}
+
+impl Canvas {
+    /// ドット差分を適用し、変更したドット数を返す
+    ///
+    /// すべてのレコードを検証してから適用するため、エラーの場合キャンバスは変更されない。
+    pub fn apply_dot_diff(&mut self, diff: &DotDiff) -> Result<u64, DotDiffError> {
+        let capacity = self.width as u64 * self.height as u64;
+        let changes = diff.changes();
+        if changes > capacity {
+            return Err(DotDiffError::TooLarge { changes, capacity });
+        }
+        if let Some((index, run)) = diff.runs.iter().enumerate().find(|(_, run)| {
+            run.y >= self.height || run.x as u32 + run.length as u32 > self.width as u32
+        }) {
+            return Err(DotDiffError::OutOfBounds {
+                index,
+                x: run.x,
+                y: run.y,
+                length: run.length,
+                width: self.width,
+                height: self.height,
+            });
+        }
+
+        for run in &diff.runs {
+            for x in run.x..run.x + run.length {
+                let coordinates = Coordinates::new(x, run.y);
+                match run.op {
+                    DotOp::Set => {
+                        self.dots.insert(coordinates, Dot::black());
+                    }
+                    DotOp::Clear => {
+                        self.dots.remove(&coordinates);
+                    }
+                }
+            }
+        }
+        Ok(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_little_endian_records() {
+        let payload = [0x2C, 0x01, 0x05, 0x00, 0x01, 0x03, 0x00];
+        let diff = DotDiff::decode(&payload).unwrap();
+        assert_eq!(
+            diff.runs,
+            [DotRun {
+                x: 300,
+                y: 5,
+                op: DotOp::Set,
+                length: 3
+            }]
+        );
+        assert_eq!(diff.encode(), payload);
+
+        assert_eq!(
+            DotDiff::decode(&payload[..6]),
+            Err(DotDiffError::InvalidLength(6))
+        );
+        assert_eq!(
+            DotDiff::decode(&[0, 0, 0, 0, 2, 1, 0]),
+            Err(DotDiffError::UnknownOperation { index: 0, op: 2 })
+        );
+        assert_eq!(
+            DotDiff::decode(&[0, 0, 0, 0, 1, 0, 0]),
+            Err(DotDiffError::EmptyRun { index: 0 })
+        );
+    }
+
+    #[test]
+    fn test_apply_sets_and_clears_runs() {
+        let mut canvas = Canvas::new(4, 2);
+        let diff = DotDiff::new(vec![
+            DotRun {
+                x: 0,
+                y: 0,
+                op: DotOp::Set,
+                length: 4,
+            },
+            DotRun::single(1, 0, DotOp::Clear),
+            DotRun::single(3, 1, DotOp::Set),
+        ]);
+
+        assert_eq!(canvas.apply_dot_diff(&diff), Ok(6));
+        let mut dots: Vec<_> = canvas.dots.keys().map(|c| (c.x, c.y)).collect();
+        dots.sort();
+        assert_eq!(dots, [(0, 0), (2, 0), (3, 0), (3, 1)]);
+    }
+
+    #[test]
+    fn test_apply_rejects_invalid_diff_without_changes() {
+        let mut canvas = Canvas::new(4, 2);
+        let out_of_bounds = DotDiff::new(vec![
+            DotRun::single(0, 0, DotOp::Set),
+            DotRun {
+                x: 2,
+                y: 1,
+                op: DotOp::Set,
+                length: 3,
+            },
+        ]);
+        assert!(matches!(
+            canvas.apply_dot_diff(&out_of_bounds),
+            Err(DotDiffError::OutOfBounds { index: 1, .. })
+        ));
+        assert!(canvas.dots.is_empty());
+
+        let too_large = DotDiff::new(vec![DotRun::single(0, 0, DotOp::Set); 9]);
+        assert_eq!(
+            canvas.apply_dot_diff(&too_large),
+            Err(DotDiffError::TooLarge {
+                changes: 9,
+                capacity: 8
+            })
+        );
+    }
+}
@@ -2,12 +2,17 @@
 //!
 //! 画像データの管理、変換、検証に関するエンティティを定義
 
+use crate::domain::artwork::change_log::ChangeLogEntry;
+use crate::domain::artwork::value_objects::{CanvasTransform, Polyline};
+use crate::domain::painting::value_objects::PaintingPreferences;
 use crate::domain::shared::value_objects::{Color, Coordinates, Timestamp};
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
 use tracing::{debug, error, info, instrument, warn};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 /// アートワークID
@@ -68,8 +73,26 @@ impl From<ArtworkId> for Uuid {
     }
 }
 
+/// 1つのアートワークに付けられるタグの最大数
+pub const MAX_TAGS: usize = 16;
+/// タグの最大文字数
+pub const MAX_TAG_LENGTH: usize = 32;
+
+/// メタデータの検証エラー
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum MetadataError {
+    #[error("Name must not be empty")]
+    EmptyName,
+    #[error("Tags must not be empty")]
+    EmptyTag,
+    #[error("Tag '{0}' is longer than {MAX_TAG_LENGTH} characters")]
+    TagTooLong(String),
+    #[error("At most {MAX_TAGS} tags are allowed")]
+    TooManyTags,
+}
+
 /// アートワークのメタデータ
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ArtworkMetadata {
     pub name: String,
     pub description: Option<String>,
@@ -108,6 +131,17 @@ impl ArtworkMetadata {
         self
     }
 
+    /// アップロードされた元ファイルの名前・サイズ・SHA-256を記録する
+    pub fn with_source_file(mut self, original_filename: Option<String>, bytes: &[u8]) -> Self {
+        self.original_filename = original_filename;
+        self.file_size = bytes.len() as u64;
+        self.checksum = Sha256::digest(bytes)
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect();
+        self
+    }
+
     pub fn add_tag(&mut self, tag: String) {
         if !self.tags.contains(&tag) {
             self.tags.push(tag);
@@ -121,6 +155,43 @@ impl ArtworkMetadata {
     pub fn has_tag(&self, tag: &str) -> bool {
         self.tags.contains(&tag.to_string())
     }
+
+    /// タグの前後の空白を取り除き、長さを検証する
+    pub fn normalize_tag(tag: &str) -> Result<String, MetadataError> {
+        let tag = tag.trim();
+        if tag.is_empty() {
+            return Err(MetadataError::EmptyTag);
+        }
+        if tag.chars().count() > MAX_TAG_LENGTH {
+            return Err(MetadataError::TagTooLong(tag.to_string()));
+        }
+        Ok(tag.to_string())
+    }
+
+    /// タグを置き換える（重複は1つにまとめる）
+    ///
+    /// 検証に失敗した場合は元のタグを変更しない
+    pub fn set_tags<S: AsRef<str>>(&mut self, tags: &[S]) -> Result<(), MetadataError> {
+        let mut metadata = Self::new(String::new());
+        metadata.add_tags(tags)?;
+        self.tags = metadata.tags;
+        Ok(())
+    }
+
+    /// タグを追加する（既にあるタグは無視する）
+    ///
+    /// 検証に失敗した場合は元のタグを変更しない
+    pub fn add_tags<S: AsRef<str>>(&mut self, tags: &[S]) -> Result<(), MetadataError> {
+        let mut updated = self.clone();
+        for tag in tags {
+            updated.add_tag(Self::normalize_tag(tag.as_ref())?);
+        }
+        if updated.tags.len() > MAX_TAGS {
+            return Err(MetadataError::TooManyTags);
+        }
+        self.tags = updated.tags;
+        Ok(())
+    }
 }
 
 /// アートワークエンティティ
@@ -135,6 +206,28 @@ pub struct Artwork {
     pub created_at: Timestamp,
     pub updated_at: Timestamp,
     pub version: u32,
+    /// このアートワークに合う描画設定（描画リクエストで省略した項目に使う）
+    #[serde(default)]
+    pub painting_preferences: Option<PaintingPreferences>,
+    /// スティック描画で描く線画（空ならドット描画だけに使う）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vector_paths: Vec<Polyline>,
+    /// アニメーション画像のフレームから作成した場合の組と位置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork_set: Option<ArtworkSetMembership>,
+    /// 直近の変更の記録（古い順、`Artwork::record_change` で追記する）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub change_log: Vec<ChangeLogEntry>,
+}
+
+/// アニメーション画像のフレームごとに作成したアートワークの組での位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct ArtworkSetMembership {
+    pub set_id: String,
+    /// 何番目のフレームか（1始まり）
+    pub frame: u32,
+    /// 組のフレーム数
+    pub frames: u32,
 }
 
 impl Artwork {
@@ -151,6 +244,10 @@ impl Artwork {
             created_at: now,
             updated_at: now,
             version: 1,
+            painting_preferences: None,
+            vector_paths: Vec::new(),
+            artwork_set: None,
+            change_log: Vec::new(),
         };
 
         info!(
@@ -182,6 +279,10 @@ impl Artwork {
             created_at: now,
             updated_at: now,
             version: 1,
+            painting_preferences: None,
+            vector_paths: Vec::new(),
+            artwork_set: None,
+            change_log: Vec::new(),
         }
     }
 
@@ -226,6 +327,20 @@ impl Artwork {
         );
     }
 
+    /// 描画設定を置き換える（空の設定は消去として扱う）
+    pub fn set_painting_preferences(&mut self, preferences: Option<PaintingPreferences>) {
+        self.painting_preferences = preferences.filter(|preferences| !preferences.is_empty());
+        self.updated_at = Timestamp::now();
+        self.version += 1;
+    }
+
+    /// 線画を置き換える（空にすると消去される。座標の検証は呼び出し側で行う）
+    pub fn set_vector_paths(&mut self, vector_paths: Vec<Polyline>) {
+        self.vector_paths = vector_paths;
+        self.updated_at = Timestamp::now();
+        self.version += 1;
+    }
+
     /// アートワークの総ドット数を取得
     pub fn total_dots(&self) -> usize {
         self.canvas.dots.len()
@@ -255,6 +370,29 @@ impl Artwork {
         painted as f64 / total as f64
     }
 
+    /// メモリ上に保持した場合の推定使用量（バイト）
+    pub fn estimated_memory_bytes(&self) -> usize {
+        let metadata = &self.metadata;
+        let text_bytes = self.original_format.len()
+            + metadata.name.len()
+            + metadata.description.as_ref().map_or(0, String::len)
+            + metadata.tags.iter().map(String::len).sum::<usize>()
+            + metadata.author.as_ref().map_or(0, String::len)
+            + metadata.original_filename.as_ref().map_or(0, String::len)
+            + metadata.checksum.len();
+        let change_log_bytes = self
+            .change_log
+            .iter()
+            .map(|entry| {
+                std::mem::size_of::<ChangeLogEntry>() + entry.source.len() + entry.summary.len()
+            })
+            .sum::<usize>();
+        std::mem::size_of::<Self>() - std::mem::size_of::<Canvas>()
+            + text_bytes
+            + change_log_bytes
+            + self.canvas.estimated_memory_bytes()
+    }
+
     /// アートワークの推定描画時間を計算（秒）
     pub fn estimated_painting_time(&self, dots_per_second: f64) -> u64 {
         let drawable = self.drawable_dots();
@@ -305,6 +443,45 @@ impl Artwork {
         }
     }
 
+    /// 新しいIDで独立したコピーを作成（描画状態はリセット、バージョンは1）
+    #[instrument(skip(self), fields(artwork_id = %self.id))]
+    pub fn duplicate(&self, name: Option<String>) -> Self {
+        let mut metadata = self.metadata.clone();
+        metadata.name = name.unwrap_or_else(|| format!("{} (copy)", self.metadata.name));
+
+        let mut copy = Self::new(metadata, self.original_format.clone(), self.canvas.clone());
+        copy.painting_preferences = self.painting_preferences;
+        copy.vector_paths = self.vector_paths.clone();
+        for dot in copy.canvas.dots.values_mut() {
+            dot.reset_paint_status();
+        }
+
+        info!(
+            source_id = %self.id,
+            copy_id = %copy.id,
+            "アートワークを複製しました"
+        );
+        copy
+    }
+
+    /// 描画できたドットを描画済みにする（キャンバスにない座標は無視し、描画済みにした数を返す）
+    pub fn mark_dots_painted(&mut self, coordinates: &[Coordinates]) -> usize {
+        let mut marked = 0;
+        for coordinates in coordinates {
+            if let Some(dot) = self.canvas.dots.get_mut(coordinates)
+                && dot.is_drawable()
+            {
+                dot.mark_as_painted();
+                marked += 1;
+            }
+        }
+        if marked > 0 {
+            self.updated_at = Timestamp::now();
+            self.version += 1;
+        }
+        marked
+    }
+
     /// アートワークをリセット（全ドットの描画状態をクリア）
     pub fn reset_painting_state(&mut self) {
         for dot in self.canvas.dots.values_mut() {
@@ -370,7 +547,7 @@ impl Artwork {
 }
 
 /// アートワークの統計情報
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct ArtworkStatistics {
     pub total_dots: usize,
     pub drawable_dots: usize,
@@ -394,6 +571,9 @@ pub enum ArtworkValidationError {
     DotOutOfBounds(Coordinates),
 }
 
+/// `HashMap` の1要素あたりの管理領域（制御バイトと、負荷率7/8で空いているバケットの分）の見積もり
+const DOT_ENTRY_OVERHEAD_BYTES: usize = 8;
+
 /// キャンバスエンティティ
 ///
 /// 320x120の描画領域を表現
@@ -455,6 +635,27 @@ impl Canvas {
         self.dots.remove(coordinates)
     }
 
+    /// メモリ上の推定使用量（バイト）
+    ///
+    /// TODO: 大きく密なキャンバスは、描画対象のビットセットと色のパレットで表現すれば1ドットあたり数ビットで済む。
+    /// 表現を変えた場合も、使用量の計上（`ArtworkMemoryBudget`）はこの見積もりを直すだけでよい。
+    pub fn estimated_memory_bytes(&self) -> usize {
+        std::mem::size_of::<Self>()
+            + self.dots.len()
+                * (std::mem::size_of::<(Coordinates, Dot)>() + DOT_ENTRY_OVERHEAD_BYTES)
+    }
+
+    /// 全ドットの作成・描画日時を捨てる（日時を捨てたドット数を返す）
+    ///
+    /// 日時は集計にしか使わないため、保存や書き出しの前に捨てて大きさを抑える。
+    pub fn compact_dots(&mut self) -> usize {
+        self.dots
+            .values_mut()
+            .map(Dot::compact)
+            .filter(|&compacted| compacted)
+            .count()
+    }
+
     /// キャンバスをクリア
     pub fn clear(&mut self) {
         self.dots.clear();
@@ -518,6 +719,127 @@ impl Canvas {
             .collect()
     }
 
+    /// 不透明なドットを囲む最小の矩形（左上と右下の座標、いずれも含む）
+    ///
+    /// 描画済みかどうかは問わない。不透明なドットがない場合は `None`
+    pub fn bounding_box(&self) -> Option<(Coordinates, Coordinates)> {
+        let mut drawable = self
+            .dots
+            .iter()
+            .filter(|(_, dot)| dot.is_visible())
+            .map(|(coord, _)| *coord);
+        let first = drawable.next()?;
+        Some(drawable.fold((first, first), |(min, max), coord| {
+            (
+                Coordinates::new(min.x.min(coord.x), min.y.min(coord.y)),
+                Coordinates::new(max.x.max(coord.x), max.y.max(coord.y)),
+            )
+        }))
+    }
+
+    /// `tile_size` 四方のタイルごとの描画可能なドット数（行ごと、右端・下端のタイルは小さくなる）
+    pub fn tile_dot_counts(&self, tile_size: u16) -> Vec<Vec<usize>> {
+        let tile_size = tile_size.max(1);
+        let columns = self.width.div_ceil(tile_size) as usize;
+        let rows = self.height.div_ceil(tile_size) as usize;
+        let mut tiles = vec![vec![0; columns]; rows];
+        for (coord, dot) in &self.dots {
+            if dot.is_drawable() && self.is_valid_coordinate(coord) {
+                tiles[(coord.y / tile_size) as usize][(coord.x / tile_size) as usize] += 1;
+            }
+        }
+        tiles
+    }
+
+    /// 周囲8方向に描画可能なドットが無い、孤立した描画可能なドットの数
+    ///
+    /// 孤立したドットは前後の移動が長くなり、位置ずれの影響を受けやすい
+    pub fn isolated_dots(&self) -> usize {
+        let has_drawable_neighbor = |coord: &Coordinates| {
+            (-1..=1i16).any(|dy| {
+                (-1..=1i16).any(|dx| {
+                    let (Some(x), Some(y)) = (
+                        coord.x.checked_add_signed(dx),
+                        coord.y.checked_add_signed(dy),
+                    ) else {
+                        return false;
+                    };
+                    (dx, dy) != (0, 0)
+                        && self
+                            .dots
+                            .get(&Coordinates::new(x, y))
+                            .is_some_and(Dot::is_drawable)
+                })
+            })
+        };
+        self.dots
+            .iter()
+            .filter(|(coord, dot)| dot.is_drawable() && !has_drawable_neighbor(coord))
+            .count()
+    }
+
+    /// 指定領域（左上と右下の座標を含む）を切り出した新しいキャンバスを作成
+    ///
+    /// 切り出したドットの座標は領域の左上を原点とする座標に変換される
+    pub fn crop(
+        &self,
+        top_left: Coordinates,
+        bottom_right: Coordinates,
+    ) -> Result<Canvas, CanvasError> {
+        if bottom_right.x < top_left.x || bottom_right.y < top_left.y {
+            return Err(CanvasError::InvalidSize);
+        }
+        if !self.is_valid_coordinate(&bottom_right) {
+            return Err(CanvasError::OutOfBounds(bottom_right));
+        }
+
+        let mut cropped = Canvas::with_background(
+            bottom_right.x - top_left.x + 1,
+            bottom_right.y - top_left.y + 1,
+            self.background_color,
+        );
+        cropped.dots = self
+            .get_region(top_left, bottom_right)
+            .into_iter()
+            .map(|(coord, dot)| {
+                (
+                    Coordinates::new(coord.x - top_left.x, coord.y - top_left.y),
+                    dot.clone(),
+                )
+            })
+            .collect();
+        Ok(cropped)
+    }
+
+    /// 周囲の空白を取り除いたキャンバスを作成
+    ///
+    /// 不透明なドットがない場合はそのまま複製する
+    pub fn trimmed(&self) -> Canvas {
+        match self.bounding_box() {
+            Some((top_left, bottom_right)) => self
+                .crop(top_left, bottom_right)
+                .expect("bounding box is always within the canvas"),
+            None => self.clone(),
+        }
+    }
+
+    /// 指定サイズのキャンバスの中央に配置した新しいキャンバスを作成
+    ///
+    /// 余白が奇数の場合、余った1ドットは右側・下側に付く
+    pub fn centered_on(&self, width: u16, height: u16) -> Result<Canvas, CanvasError> {
+        if width == 0 || height == 0 {
+            return Err(CanvasError::InvalidSize);
+        }
+        if self.width > width || self.height > height {
+            return Err(CanvasError::InvalidSize);
+        }
+
+        let offset = Coordinates::new((width - self.width) / 2, (height - self.height) / 2);
+        let mut centered = Canvas::with_background(width, height, self.background_color);
+        centered.merge(self, offset)?;
+        Ok(centered)
+    }
+
     /// キャンバスの密度を計算
     pub fn density(&self) -> f64 {
         let total_pixels = (self.width as f64) * (self.height as f64);
@@ -527,6 +849,70 @@ impl Canvas {
         self.dots.len() as f64 / total_pixels
     }
 
+    /// 不透明なドットがキャンバスの半分より多いか
+    ///
+    /// 明るい絵柄を暗い背景に描いた画像（白抜きのロゴなど）を変換するとこうなる
+    pub fn is_mostly_filled(&self) -> bool {
+        let visible = self.dots.values().filter(|dot| dot.is_visible()).count();
+        visible * 2 > self.width as usize * self.height as usize
+    }
+
+    /// 背景が暗い色か（明るいドットを描く構図）
+    pub fn has_dark_background(&self) -> bool {
+        self.background_color.to_grayscale() < 128
+    }
+
+    /// 描くドットと背景を入れ替えたキャンバスを作成
+    ///
+    /// 不透明なドットのない座標に元の背景色のドットを置き、背景色は元の背景色の反転色にする。
+    /// 描画済みの状態は引き継がない
+    pub fn inverted(&self) -> Canvas {
+        let mut inverted =
+            Canvas::with_background(self.width, self.height, self.background_color.invert());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let coord = Coordinates::new(x, y);
+                if !self.dots.get(&coord).is_some_and(|dot| dot.is_visible()) {
+                    inverted
+                        .dots
+                        .insert(coord, Dot::new(self.background_color, 255));
+                }
+            }
+        }
+        inverted
+    }
+
+    /// 変形を適用
+    pub fn apply_transform(&mut self, transform: CanvasTransform) {
+        let (width, height) = (self.width, self.height);
+        match transform {
+            CanvasTransform::FlipHorizontal => {
+                self.dots = self
+                    .dots
+                    .drain()
+                    .map(|(coord, dot)| (Coordinates::new(width - 1 - coord.x, coord.y), dot))
+                    .collect();
+            }
+            CanvasTransform::FlipVertical => {
+                self.dots = self
+                    .dots
+                    .drain()
+                    .map(|(coord, dot)| (Coordinates::new(coord.x, height - 1 - coord.y), dot))
+                    .collect();
+            }
+            CanvasTransform::Threshold { value } => {
+                for dot in self.dots.values_mut() {
+                    if dot.color.to_binary(value) {
+                        dot.set_opacity(0);
+                    } else {
+                        dot.set_color(Color::black());
+                        dot.set_opacity(255);
+                    }
+                }
+            }
+        }
+    }
+
     /// キャンバスを別のキャンバスとマージ
     pub fn merge(&mut self, other: &Canvas, offset: Coordinates) -> Result<(), CanvasError> {
         for (coord, dot) in &other.dots {
@@ -551,6 +937,15 @@ pub enum CanvasError {
     OutOfBounds(Coordinates),
     #[error("Invalid canvas size")]
     InvalidSize,
+    #[error("Character {0:?} is not supported by the built-in font")]
+    UnsupportedCharacter(char),
+    #[error("Pattern needs {width}x{height} dots and does not fit on the canvas")]
+    PatternTooLarge { width: u16, height: u16 },
+    #[error("Canvas sizes differ: {}x{} and {}x{}", expected.0, expected.1, actual.0, actual.1)]
+    SizeMismatch {
+        expected: (u16, u16),
+        actual: (u16, u16),
+    },
 }
 
 /// ドットエンティティ
@@ -561,7 +956,11 @@ pub struct Dot {
     pub color: Color,
     pub opacity: u8,
     pub is_painted: bool,
-    pub created_at: Timestamp,
+    /// 作成日時（コンパクト化したドットは `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<Timestamp>,
+    /// 描画日時（未描画またはコンパクト化したドットは `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub painted_at: Option<Timestamp>,
     pub layer: u8,
 }
@@ -573,7 +972,7 @@ impl Dot {
             color,
             opacity,
             is_painted: false,
-            created_at: Timestamp::now(),
+            created_at: Some(Timestamp::now()),
             painted_at: None,
             layer: 0,
         }
@@ -585,7 +984,7 @@ impl Dot {
             color,
             opacity,
             is_painted: false,
-            created_at: Timestamp::now(),
+            created_at: Some(Timestamp::now()),
             painted_at: None,
             layer,
         }
@@ -633,16 +1032,24 @@ impl Dot {
         self.color.to_binary(threshold)
     }
 
-    /// ドットの年齢を取得（作成からの経過時間、ミリ秒）
-    pub fn age_millis(&self) -> u64 {
-        self.created_at.elapsed_millis()
+    /// ドットの年齢を取得（作成からの経過時間、ミリ秒。コンパクト化したドットは `None`）
+    pub fn age_millis(&self) -> Option<u64> {
+        self.created_at.map(|t| t.elapsed_millis())
     }
 
-    /// ドットが描画されてからの経過時間を取得（ミリ秒）
+    /// ドットが描画されてからの経過時間を取得（ミリ秒。コンパクト化したドットは `None`）
     pub fn painted_age_millis(&self) -> Option<u64> {
         self.painted_at.map(|t| t.elapsed_millis())
     }
 
+    /// 作成・描画日時を捨てる（描画済みかどうか・色・レイヤーは残す。日時があった場合は `true`）
+    pub fn compact(&mut self) -> bool {
+        let had_timestamps = self.created_at.is_some() || self.painted_at.is_some();
+        self.created_at = None;
+        self.painted_at = None;
+        had_timestamps
+    }
+
     /// ドットの色を変更
     pub fn set_color(&mut self, color: Color) {
         self.color = color;
@@ -667,6 +1074,19 @@ impl Dot {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_metadata_records_source_file() {
+        let metadata = ArtworkMetadata::new("test".to_string())
+            .with_source_file(Some("ika.png".to_string()), b"abc");
+
+        assert_eq!(metadata.original_filename.as_deref(), Some("ika.png"));
+        assert_eq!(metadata.file_size, 3);
+        assert_eq!(
+            metadata.checksum,
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
     #[test]
     fn test_artwork_id() {
         let id1 = ArtworkId::generate();
@@ -682,6 +1102,51 @@ mod tests {
         assert_eq!(id1, id_from_str);
     }
 
+    #[test]
+    fn test_artwork_duplicate_is_independent() {
+        let mut canvas = Canvas::new(4, 2);
+        canvas
+            .set_dot(Coordinates::new(0, 0), Dot::black())
+            .unwrap();
+        let mut original = Artwork::new(
+            ArtworkMetadata::new("Original".to_string()),
+            "png".to_string(),
+            canvas,
+        );
+        original
+            .canvas
+            .get_dot_mut(&Coordinates::new(0, 0))
+            .unwrap()
+            .mark_as_painted();
+        original.update_metadata(original.metadata.clone());
+        original.set_painting_preferences(Some(PaintingPreferences {
+            wait_ms: Some(10),
+            ..PaintingPreferences::default()
+        }));
+
+        let mut copy = original.duplicate(None);
+        assert_ne!(copy.id, original.id);
+        assert_eq!(copy.metadata.name, "Original (copy)");
+        assert_eq!(copy.version, 1);
+        assert_eq!(copy.drawable_dots(), 1);
+        assert_eq!(copy.painting_preferences, original.painting_preferences);
+
+        // 空の設定は消去として扱う
+        original.set_painting_preferences(Some(PaintingPreferences::default()));
+        assert_eq!(original.painting_preferences, None);
+
+        copy.canvas.apply_transform(CanvasTransform::FlipHorizontal);
+        assert!(copy.canvas.get_dot(&Coordinates::new(3, 0)).is_some());
+        assert!(original.canvas.get_dot(&Coordinates::new(3, 0)).is_none());
+        assert!(
+            original
+                .canvas
+                .get_dot(&Coordinates::new(0, 0))
+                .unwrap()
+                .is_painted
+        );
+    }
+
     #[test]
     fn test_artwork_creation() {
         let metadata = ArtworkMetadata::new("Test Artwork".to_string())
@@ -734,6 +1199,48 @@ mod tests {
         assert!(dot.painted_at.is_none());
     }
 
+    #[test]
+    fn test_compacted_dots_keep_paint_state_without_timestamps() {
+        let mut canvas = Canvas::new(2, 1);
+        canvas
+            .set_dot(
+                Coordinates::new(0, 0),
+                Dot::with_layer(Color::black(), 255, 2),
+            )
+            .unwrap();
+        canvas
+            .set_dot(Coordinates::new(1, 0), Dot::black())
+            .unwrap();
+        canvas
+            .get_dot_mut(&Coordinates::new(0, 0))
+            .unwrap()
+            .mark_as_painted();
+        let painted = canvas.get_dot(&Coordinates::new(0, 0)).unwrap();
+        assert!(painted.age_millis().is_some());
+        assert!(painted.painted_age_millis().is_some());
+
+        assert_eq!(canvas.compact_dots(), 2);
+        assert_eq!(canvas.compact_dots(), 0);
+        let painted = canvas.get_dot(&Coordinates::new(0, 0)).unwrap();
+        assert!(painted.is_painted);
+        assert_eq!(painted.layer, 2);
+        assert_eq!(painted.age_millis(), None);
+        assert_eq!(painted.painted_age_millis(), None);
+        assert_eq!(canvas.drawable_dots().len(), 1);
+
+        // 日時を持たない形式でも、日時を持つ従来の形式でも読み込める
+        let json = serde_json::to_string(painted).unwrap();
+        assert!(!json.contains("created_at"), "{json}");
+        assert_eq!(&serde_json::from_str::<Dot>(&json).unwrap(), painted);
+        let legacy = serde_json::to_string(&Dot::black()).unwrap();
+        assert!(
+            serde_json::from_str::<Dot>(&legacy)
+                .unwrap()
+                .created_at
+                .is_some()
+        );
+    }
+
     #[test]
     fn test_artwork_statistics() {
         let metadata = ArtworkMetadata::new("Test".to_string());
@@ -760,6 +1267,27 @@ mod tests {
         assert_eq!(stats.completion_ratio, 0.0);
     }
 
+    #[test]
+    fn test_canvas_tile_counts_and_isolated_dots() {
+        let mut canvas = Canvas::new(20, 10);
+        // (0,0)-(1,1) は斜めに隣接、(19,9) と (10,0) は孤立
+        for (x, y) in [(0, 0), (1, 1), (19, 9), (10, 0)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        canvas
+            .set_dot(Coordinates::new(11, 0), Dot::transparent())
+            .unwrap();
+
+        assert_eq!(canvas.tile_dot_counts(16), vec![vec![3, 1]]);
+        assert_eq!(
+            canvas.tile_dot_counts(8),
+            vec![vec![2, 1, 0], vec![0, 0, 1]]
+        );
+        assert_eq!(canvas.isolated_dots(), 2);
+    }
+
     #[test]
     fn test_canvas_merge() {
         let mut canvas1 = Canvas::new(10, 10);
@@ -778,4 +1306,119 @@ mod tests {
         assert_eq!(canvas1.dots.len(), 2);
         assert!(canvas1.get_dot(&Coordinates::new(2, 2)).is_some());
     }
+
+    #[test]
+    fn test_trim_dots_in_one_corner() {
+        let mut canvas = Canvas::new(320, 120);
+        canvas
+            .set_dot(Coordinates::new(300, 100), Dot::black())
+            .unwrap();
+        canvas
+            .set_dot(Coordinates::new(310, 119), Dot::black())
+            .unwrap();
+        // 透明なドットは範囲の計算に含めない
+        canvas
+            .set_dot(Coordinates::new(0, 0), Dot::transparent())
+            .unwrap();
+
+        assert_eq!(
+            canvas.bounding_box(),
+            Some((Coordinates::new(300, 100), Coordinates::new(310, 119)))
+        );
+
+        let trimmed = canvas.trimmed();
+        assert_eq!((trimmed.width, trimmed.height), (11, 20));
+        assert_eq!(trimmed.dots.len(), 2);
+        assert!(trimmed.get_dot(&Coordinates::new(0, 0)).is_some());
+        assert!(trimmed.get_dot(&Coordinates::new(10, 19)).is_some());
+
+        assert!(Canvas::new(10, 10).bounding_box().is_none());
+        assert!(
+            canvas
+                .crop(Coordinates::new(5, 5), Coordinates::new(320, 5))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_centered_on_with_odd_margins() {
+        // 11x20 を 320x120 に配置: 余白は横309・縦100
+        let mut canvas = Canvas::new(11, 20);
+        canvas
+            .set_dot(Coordinates::new(0, 0), Dot::black())
+            .unwrap();
+        canvas
+            .set_dot(Coordinates::new(10, 19), Dot::black())
+            .unwrap();
+
+        let centered = canvas.centered_on(320, 120).unwrap();
+        assert_eq!((centered.width, centered.height), (320, 120));
+        assert!(centered.get_dot(&Coordinates::new(154, 50)).is_some());
+        assert!(centered.get_dot(&Coordinates::new(164, 69)).is_some());
+        // 左154・右155、上50・下50
+        let (top_left, bottom_right) = centered.bounding_box().unwrap();
+        assert_eq!(top_left.x, 154);
+        assert_eq!(320 - 1 - bottom_right.x, 155);
+        assert_eq!(top_left.y, 50);
+        assert_eq!(120 - 1 - bottom_right.y, 50);
+
+        assert!(canvas.centered_on(10, 120).is_err());
+    }
+
+    #[test]
+    fn test_inverted_swaps_dots_and_background() {
+        // 4x2 のうち白抜きの2ドット以外が黒
+        let mut canvas = Canvas::new(4, 2);
+        for (x, y) in [(0, 0), (1, 0), (2, 0), (3, 0), (0, 1), (3, 1)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        assert!(canvas.is_mostly_filled());
+        assert!(!canvas.has_dark_background());
+
+        let inverted = canvas.inverted();
+        assert_eq!(inverted.background_color, Color::black());
+        assert!(inverted.has_dark_background());
+        assert!(!inverted.is_mostly_filled());
+        let mut drawable: Vec<_> = inverted
+            .drawable_dots()
+            .into_iter()
+            .map(|(coord, dot)| {
+                assert_eq!(dot.color, Color::white());
+                *coord
+            })
+            .collect();
+        drawable.sort_unstable_by_key(|coord| (coord.y, coord.x));
+        assert_eq!(
+            drawable,
+            vec![Coordinates::new(1, 1), Coordinates::new(2, 1)]
+        );
+    }
+
+    #[test]
+    fn test_metadata_tags_are_trimmed_deduplicated_and_limited() {
+        let mut metadata = ArtworkMetadata::new("test".to_string());
+        metadata.set_tags(&[" squid ", "octo", "squid"]).unwrap();
+        assert_eq!(metadata.tags, vec!["squid", "octo"]);
+
+        metadata.add_tags(&["octo", "ink"]).unwrap();
+        assert_eq!(metadata.tags, vec!["squid", "octo", "ink"]);
+
+        // 失敗した場合は変更しない
+        assert_eq!(
+            metadata.add_tags(&["ok", "  "]),
+            Err(MetadataError::EmptyTag)
+        );
+        assert!(matches!(
+            metadata.set_tags(&["x".repeat(MAX_TAG_LENGTH + 1)]),
+            Err(MetadataError::TagTooLong(_))
+        ));
+        let too_many: Vec<String> = (0..=MAX_TAGS).map(|i| format!("tag{i}")).collect();
+        assert_eq!(
+            metadata.set_tags(&too_many),
+            Err(MetadataError::TooManyTags)
+        );
+        assert_eq!(metadata.tags, vec!["squid", "octo", "ink"]);
+    }
 }
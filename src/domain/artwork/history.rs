@@ -0,0 +1,425 @@
+//! キャンバス編集の取り消し履歴
+//!
+//! 編集ごとにキャンバス全体を保存するとメモリが足りなくなるため、変更されたドットの前後の内容だけを
+//! 差分として記録する。描画済みフラグ（`is_painted`）は記録しないので、取り消しても描画の進捗は戻らない。
+//!
+//! 履歴はすべてのアートワークで1つのバイト数の上限を共有し、超えた分は最も古い記録から捨てる。
+
+use crate::domain::artwork::entities::{ArtworkId, Canvas, Dot};
+use crate::domain::shared::value_objects::{Color, Coordinates};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// 履歴全体のメモリ使用量の上限の既定値
+pub const DEFAULT_HISTORY_BUDGET_BYTES: usize = 8 * 1024 * 1024;
+/// アートワークごとに取り消せる編集の数
+pub const MAX_HISTORY_STEPS: usize = 20;
+
+/// 取り消し・やり直しの方向
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HistoryDirection {
+    Undo,
+    Redo,
+}
+
+impl HistoryDirection {
+    pub fn opposite(self) -> Self {
+        match self {
+            Self::Undo => Self::Redo,
+            Self::Redo => Self::Undo,
+        }
+    }
+}
+
+/// アートワークに残っている取り消し・やり直しの数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HistoryDepth {
+    pub undo: usize,
+    pub redo: usize,
+}
+
+/// 取り消し履歴（複製してもすべてのハンドルで同じ履歴を共有する）
+#[derive(Debug, Clone)]
+pub struct CanvasHistory {
+    store: Arc<Mutex<HistoryStore>>,
+}
+
+/// 描画の進捗を除いたドットの内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DotContent {
+    color: Color,
+    opacity: u8,
+    layer: u8,
+}
+
+impl From<&Dot> for DotContent {
+    fn from(dot: &Dot) -> Self {
+        Self {
+            color: dot.color,
+            opacity: dot.opacity,
+            layer: dot.layer,
+        }
+    }
+}
+
+/// 1ドットの変更前後の内容（`None` はドットなし）
+#[derive(Debug, Clone)]
+struct DotChange {
+    coordinates: Coordinates,
+    before: Option<DotContent>,
+    after: Option<DotContent>,
+}
+
+/// 1回の編集の記録
+#[derive(Debug)]
+struct HistoryEntry {
+    /// 記録した順番（古い記録から捨てるために使う）
+    seq: u64,
+    changes: Vec<DotChange>,
+}
+
+impl HistoryEntry {
+    fn bytes(&self) -> usize {
+        std::mem::size_of::<Self>() + self.changes.capacity() * std::mem::size_of::<DotChange>()
+    }
+}
+
+#[derive(Debug, Default)]
+struct ArtworkHistory {
+    /// 取り消せる編集（末尾が最新）
+    undo: VecDeque<HistoryEntry>,
+    /// やり直せる編集（末尾が次にやり直す編集）
+    redo: VecDeque<HistoryEntry>,
+}
+
+#[derive(Debug)]
+struct HistoryStore {
+    budget_bytes: usize,
+    max_steps: usize,
+    used_bytes: usize,
+    next_seq: u64,
+    artworks: HashMap<ArtworkId, ArtworkHistory>,
+}
+
+impl Default for CanvasHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_BUDGET_BYTES)
+    }
+}
+
+impl CanvasHistory {
+    /// 履歴全体で `budget_bytes` までメモリを使う
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            store: Arc::new(Mutex::new(HistoryStore {
+                budget_bytes,
+                max_steps: MAX_HISTORY_STEPS,
+                used_bytes: 0,
+                next_seq: 0,
+                artworks: HashMap::new(),
+            })),
+        }
+    }
+
+    pub fn with_max_steps(self, max_steps: usize) -> Self {
+        self.lock().max_steps = max_steps;
+        self
+    }
+
+    /// `before` から `after` への編集を記録する（内容が変わっていなければ何もしない）
+    ///
+    /// 新しい編集を記録するとやり直しの履歴は消える。
+    /// 1回の編集だけで上限を超える場合は記録できないため、それより前の履歴もたどれなくなり消える。
+    pub fn record(&self, id: &ArtworkId, before: &Canvas, after: &Canvas) {
+        let changes = diff_contents(before, after);
+        if changes.is_empty() {
+            return;
+        }
+
+        let mut store = self.lock();
+        let seq = store.next_seq;
+        store.next_seq += 1;
+        let entry = HistoryEntry { seq, changes };
+        let entry_bytes = entry.bytes();
+        let budget_bytes = store.budget_bytes;
+        let max_steps = store.max_steps;
+
+        let history = store.artworks.entry(id.clone()).or_default();
+        let mut freed: usize = history.redo.drain(..).map(|entry| entry.bytes()).sum();
+        if entry_bytes > budget_bytes {
+            freed += history
+                .undo
+                .drain(..)
+                .map(|entry| entry.bytes())
+                .sum::<usize>();
+            store.used_bytes -= freed;
+            store.artworks.remove(id);
+            return;
+        }
+        history.undo.push_back(entry);
+        while history.undo.len() > max_steps {
+            if let Some(oldest) = history.undo.pop_front() {
+                freed += oldest.bytes();
+            }
+        }
+        store.used_bytes = store.used_bytes + entry_bytes - freed;
+        store.evict_over_budget();
+    }
+
+    /// 最新の編集を取り消して `canvas` を戻す（変更したドット数を返す。履歴がなければ `None`）
+    pub fn undo(&self, id: &ArtworkId, canvas: &mut Canvas) -> Option<u64> {
+        self.step(id, HistoryDirection::Undo, canvas)
+    }
+
+    /// 取り消した編集をやり直す（変更したドット数を返す。履歴がなければ `None`）
+    pub fn redo(&self, id: &ArtworkId, canvas: &mut Canvas) -> Option<u64> {
+        self.step(id, HistoryDirection::Redo, canvas)
+    }
+
+    /// 指定した方向に1つ進め、記録を反対側の履歴へ移す
+    ///
+    /// 既存のドットは色・不透明度・レイヤーだけを書き換え、描画済みフラグはそのまま残す。
+    pub fn step(
+        &self,
+        id: &ArtworkId,
+        direction: HistoryDirection,
+        canvas: &mut Canvas,
+    ) -> Option<u64> {
+        let mut store = self.lock();
+        let history = store.artworks.get_mut(id)?;
+        let (from, to) = match direction {
+            HistoryDirection::Undo => (&mut history.undo, &mut history.redo),
+            HistoryDirection::Redo => (&mut history.redo, &mut history.undo),
+        };
+        let entry = from.pop_back()?;
+        for change in &entry.changes {
+            let content = match direction {
+                HistoryDirection::Undo => change.before,
+                HistoryDirection::Redo => change.after,
+            };
+            restore_dot(canvas, change.coordinates, content);
+        }
+        let changed_dots = entry.changes.len() as u64;
+        to.push_back(entry);
+        Some(changed_dots)
+    }
+
+    pub fn depth(&self, id: &ArtworkId) -> HistoryDepth {
+        self.lock()
+            .artworks
+            .get(id)
+            .map(|history| HistoryDepth {
+                undo: history.undo.len(),
+                redo: history.redo.len(),
+            })
+            .unwrap_or_default()
+    }
+
+    /// アートワークの履歴を捨てる（削除時）
+    pub fn remove(&self, id: &ArtworkId) {
+        let mut store = self.lock();
+        if let Some(history) = store.artworks.remove(id) {
+            let freed: usize = history
+                .undo
+                .iter()
+                .chain(&history.redo)
+                .map(HistoryEntry::bytes)
+                .sum();
+            store.used_bytes -= freed;
+        }
+    }
+
+    /// 履歴が使っているメモリの見積もり（バイト）
+    pub fn used_bytes(&self) -> usize {
+        self.lock().used_bytes
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HistoryStore> {
+        self.store.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl HistoryStore {
+    /// 上限に収まるまで、全アートワークの中で最も古い記録を捨てる
+    ///
+    /// 捨てるのは取り消し履歴の先頭か、やり直し履歴の一番先の記録なので、残った履歴は連続したままになる。
+    fn evict_over_budget(&mut self) {
+        while self.used_bytes > self.budget_bytes {
+            let oldest = self
+                .artworks
+                .iter()
+                .flat_map(|(id, history)| {
+                    let undo = history.undo.front().map(|entry| (entry.seq, id, true));
+                    let redo = history.redo.front().map(|entry| (entry.seq, id, false));
+                    undo.into_iter().chain(redo)
+                })
+                .min_by_key(|&(seq, _, _)| seq)
+                .map(|(_, id, undo)| (id.clone(), undo));
+            let Some((id, undo)) = oldest else {
+                break;
+            };
+
+            let history = self.artworks.get_mut(&id).expect("artwork history");
+            let entry = if undo {
+                history.undo.pop_front()
+            } else {
+                history.redo.pop_front()
+            };
+            self.used_bytes -= entry.map_or(0, |entry| entry.bytes());
+            if history.undo.is_empty() && history.redo.is_empty() {
+                self.artworks.remove(&id);
+            }
+        }
+    }
+}
+
+/// 描画済みフラグを除いて内容が異なるドットを集める
+fn diff_contents(before: &Canvas, after: &Canvas) -> Vec<DotChange> {
+    let mut changes: Vec<DotChange> = before
+        .dots
+        .iter()
+        .filter_map(|(coordinates, dot)| {
+            let before = Some(DotContent::from(dot));
+            let after = after.dots.get(coordinates).map(DotContent::from);
+            (before != after).then_some(DotChange {
+                coordinates: *coordinates,
+                before,
+                after,
+            })
+        })
+        .collect();
+    changes.extend(
+        after
+            .dots
+            .iter()
+            .filter(|(coordinates, _)| !before.dots.contains_key(coordinates))
+            .map(|(coordinates, dot)| DotChange {
+                coordinates: *coordinates,
+                before: None,
+                after: Some(DotContent::from(dot)),
+            }),
+    );
+    changes.shrink_to_fit();
+    changes
+}
+
+fn restore_dot(canvas: &mut Canvas, coordinates: Coordinates, content: Option<DotContent>) {
+    match (content, canvas.dots.get_mut(&coordinates)) {
+        (Some(content), Some(dot)) => {
+            dot.color = content.color;
+            dot.opacity = content.opacity;
+            dot.layer = content.layer;
+        }
+        (Some(content), None) => {
+            canvas.dots.insert(
+                coordinates,
+                Dot::with_layer(content.color, content.opacity, content.layer),
+            );
+        }
+        (None, _) => {
+            canvas.dots.remove(&coordinates);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canvas_with(dots: &[(u16, u16)]) -> Canvas {
+        let mut canvas = Canvas::new(32, 32);
+        for &(x, y) in dots {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        canvas
+    }
+
+    #[test]
+    fn test_undo_redo_restores_contents_but_keeps_painted_flags() {
+        let history = CanvasHistory::default();
+        let id = ArtworkId::generate();
+        let before = canvas_with(&[(0, 0), (1, 0)]);
+        let after = canvas_with(&[(1, 0), (2, 0)]);
+        history.record(&id, &before, &after);
+
+        // 編集後に (1, 0) を描画した
+        let mut canvas = after.clone();
+        canvas
+            .dots
+            .get_mut(&Coordinates::new(1, 0))
+            .unwrap()
+            .mark_as_painted();
+
+        assert_eq!(history.undo(&id, &mut canvas), Some(2));
+        assert!(canvas.dots.contains_key(&Coordinates::new(0, 0)));
+        assert!(!canvas.dots.contains_key(&Coordinates::new(2, 0)));
+        assert!(canvas.dots[&Coordinates::new(1, 0)].is_painted);
+        assert_eq!(history.depth(&id), HistoryDepth { undo: 0, redo: 1 });
+        assert_eq!(history.undo(&id, &mut canvas), None);
+
+        assert_eq!(history.redo(&id, &mut canvas), Some(2));
+        assert!(!canvas.dots.contains_key(&Coordinates::new(0, 0)));
+        assert!(canvas.dots.contains_key(&Coordinates::new(2, 0)));
+        assert!(canvas.dots[&Coordinates::new(1, 0)].is_painted);
+
+        // 新しい編集でやり直しの履歴は消える
+        history.undo(&id, &mut canvas);
+        history.record(&id, &canvas.clone(), &canvas_with(&[(5, 5)]));
+        assert_eq!(history.depth(&id), HistoryDepth { undo: 1, redo: 0 });
+
+        // 描画済みフラグだけの違いは記録しない
+        let mut painted = canvas_with(&[(5, 5)]);
+        painted
+            .dots
+            .get_mut(&Coordinates::new(5, 5))
+            .unwrap()
+            .mark_as_painted();
+        history.record(&id, &canvas_with(&[(5, 5)]), &painted);
+        assert_eq!(history.depth(&id).undo, 1);
+
+        history.remove(&id);
+        assert_eq!(history.depth(&id), HistoryDepth::default());
+        assert_eq!(history.used_bytes(), 0);
+    }
+
+    #[test]
+    fn test_budget_evicts_oldest_entries_across_artworks() {
+        let first = ArtworkId::generate();
+        let second = ArtworkId::generate();
+        let empty = Canvas::new(32, 32);
+        let row = |y: u16| canvas_with(&(0..32).map(|x| (x, y)).collect::<Vec<_>>());
+
+        // 1回の編集（32ドット）の大きさを測ってから、3回分の上限で作り直す
+        let probe = CanvasHistory::default();
+        probe.record(&first, &empty, &row(0));
+        let entry_bytes = probe.used_bytes();
+        let history = CanvasHistory::new(entry_bytes * 3);
+
+        history.record(&first, &empty, &row(0));
+        history.record(&second, &empty, &row(1));
+        history.record(&first, &empty, &row(2));
+        history.record(&second, &empty, &row(3));
+        assert!(history.used_bytes() <= entry_bytes * 3);
+        assert_eq!(history.depth(&first).undo, 1);
+        assert_eq!(history.depth(&second).undo, 2);
+
+        // 上限を超える1回の編集は記録せず、それ以前の履歴も捨てる
+        let full = canvas_with(
+            &(0..32)
+                .flat_map(|y| (0..32).map(move |x| (x, y)))
+                .collect::<Vec<_>>(),
+        );
+        history.record(&second, &empty, &full);
+        assert_eq!(history.depth(&second), HistoryDepth::default());
+        assert_eq!(history.used_bytes(), entry_bytes);
+
+        // 編集の数の上限
+        let history = CanvasHistory::default().with_max_steps(2);
+        for y in 0..4 {
+            history.record(&first, &row(y), &row(y + 1));
+        }
+        assert_eq!(history.depth(&first).undo, 2);
+    }
+}
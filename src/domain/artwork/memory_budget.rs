@@ -0,0 +1,183 @@
+//! 保存中のアートワークのメモリ使用量の計上
+//!
+//! キャンバスのドットは1つずつ `HashMap` の要素として保持するため、1000x1000 のアートワークを
+//! 何枚も作成するとRaspberry Pi Zeroではメモリが足りなくなり、描画中にOOM killerで停止してしまう。
+//! アートワークごとの推定使用量（`Artwork::estimated_memory_bytes`）を計上し、上限を超える作成や編集を拒否する。
+//!
+//! キャンバスの表現を変えた場合も、推定使用量の計算を直すだけで計上の仕組みはそのまま使える。
+
+use crate::domain::artwork::entities::{Artwork, ArtworkId};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// 全アートワークのメモリ使用量の上限の既定値
+pub const DEFAULT_ARTWORK_MEMORY_BUDGET_BYTES: usize = 256 * 1024 * 1024;
+
+/// 上限を超えるため計上できなかった
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error(
+    "Artwork memory budget exceeded: {} in use of {}, this artwork needs {}",
+    format_bytes(*.used_bytes),
+    format_bytes(*.budget_bytes),
+    format_bytes(*.requested_bytes)
+)]
+pub struct MemoryBudgetExceeded {
+    /// 計上済みの使用量（置き換えるアートワークの分を含む）
+    pub used_bytes: usize,
+    pub budget_bytes: usize,
+    /// 計上しようとしたアートワークの推定使用量
+    pub requested_bytes: usize,
+}
+
+/// アートワークのメモリ使用量の計上（複製してもすべてのハンドルで同じ計上を共有する）
+#[derive(Debug, Clone)]
+pub struct ArtworkMemoryBudget {
+    store: Arc<Mutex<BudgetStore>>,
+}
+
+#[derive(Debug)]
+struct BudgetStore {
+    budget_bytes: usize,
+    used_bytes: usize,
+    artworks: HashMap<ArtworkId, usize>,
+}
+
+impl Default for ArtworkMemoryBudget {
+    fn default() -> Self {
+        Self::new(DEFAULT_ARTWORK_MEMORY_BUDGET_BYTES)
+    }
+}
+
+impl ArtworkMemoryBudget {
+    /// 全アートワークで `budget_bytes` までメモリを使う
+    pub fn new(budget_bytes: usize) -> Self {
+        Self {
+            store: Arc::new(Mutex::new(BudgetStore {
+                budget_bytes,
+                used_bytes: 0,
+                artworks: HashMap::new(),
+            })),
+        }
+    }
+
+    pub fn budget_bytes(&self) -> usize {
+        self.lock().budget_bytes
+    }
+
+    /// 計上済みの使用量
+    pub fn used_bytes(&self) -> usize {
+        self.lock().used_bytes
+    }
+
+    /// アートワークの使用量を計上する（計上済みのアートワークは新しい使用量に置き換える）
+    ///
+    /// 置き換えた結果が上限を超える場合は何も変えずにエラーを返す。使用量が減る編集は常に受け付ける。
+    pub fn reserve(&self, artwork: &Artwork) -> Result<(), MemoryBudgetExceeded> {
+        let requested_bytes = artwork.estimated_memory_bytes();
+        let mut store = self.lock();
+        let previous = store.artworks.get(&artwork.id).copied().unwrap_or(0);
+        let used_after = store.used_bytes - previous + requested_bytes;
+        if requested_bytes > previous && used_after > store.budget_bytes {
+            return Err(MemoryBudgetExceeded {
+                used_bytes: store.used_bytes,
+                budget_bytes: store.budget_bytes,
+                requested_bytes,
+            });
+        }
+        store.used_bytes = used_after;
+        store.artworks.insert(artwork.id.clone(), requested_bytes);
+        Ok(())
+    }
+
+    /// 保存済みのアートワークを上限に関係なく計上する（起動時の読み込み用）
+    pub fn account(&self, artwork: &Artwork) {
+        let bytes = artwork.estimated_memory_bytes();
+        let mut store = self.lock();
+        let previous = store
+            .artworks
+            .insert(artwork.id.clone(), bytes)
+            .unwrap_or(0);
+        store.used_bytes = store.used_bytes - previous + bytes;
+    }
+
+    /// 削除したアートワークの計上を取り消す
+    pub fn release(&self, id: &ArtworkId) {
+        let mut store = self.lock();
+        if let Some(bytes) = store.artworks.remove(id) {
+            store.used_bytes -= bytes;
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BudgetStore> {
+        self.store.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// バイト数をMiB単位で表示する
+fn format_bytes(bytes: usize) -> String {
+    format!("{:.1} MiB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::artwork::entities::{ArtworkMetadata, Canvas, Dot};
+    use crate::domain::shared::value_objects::Coordinates;
+
+    fn artwork_with_dots(dots: u16) -> Artwork {
+        let mut canvas = Canvas::new(dots.max(1), 1);
+        for x in 0..dots {
+            canvas
+                .set_dot(Coordinates::new(x, 0), Dot::black())
+                .unwrap();
+        }
+        Artwork::new(
+            ArtworkMetadata::new("budget".to_string()),
+            "api".to_string(),
+            canvas,
+        )
+    }
+
+    #[test]
+    fn test_budget_rejects_growth_beyond_limit_and_releases_on_delete() {
+        let small = artwork_with_dots(10);
+        let large = artwork_with_dots(100);
+        let budget = ArtworkMemoryBudget::new(
+            small.estimated_memory_bytes() + large.estimated_memory_bytes(),
+        );
+
+        budget.reserve(&small).unwrap();
+        budget.reserve(&large).unwrap();
+        assert_eq!(budget.used_bytes(), budget.budget_bytes());
+
+        // 計上済みのアートワークを大きくする編集は拒否し、使用量は変えない
+        let mut grown = large.clone();
+        grown.canvas.width = 101;
+        grown
+            .canvas
+            .set_dot(Coordinates::new(100, 0), Dot::black())
+            .unwrap();
+        let error = budget.reserve(&grown).unwrap_err();
+        assert_eq!(error.used_bytes, budget.budget_bytes());
+        assert_eq!(error.requested_bytes, grown.estimated_memory_bytes());
+        assert!(error.to_string().contains("MiB in use"), "{error}");
+
+        // 小さくする編集と削除は常に受け付け、空いた分だけ再び作成できる
+        let mut shrunk = large.clone();
+        shrunk
+            .canvas
+            .dots
+            .retain(|coordinates, _| coordinates.x < 50);
+        budget.reserve(&shrunk).unwrap();
+        budget.release(&small.id);
+        budget.release(&small.id);
+        assert_eq!(budget.used_bytes(), shrunk.estimated_memory_bytes());
+        budget.reserve(&small).unwrap();
+
+        // 起動時に読み込んだアートワークは上限を超えても計上する
+        let loaded = ArtworkMemoryBudget::new(0);
+        loaded.account(&small);
+        loaded.account(&small);
+        assert_eq!(loaded.used_bytes(), small.estimated_memory_bytes());
+    }
+}
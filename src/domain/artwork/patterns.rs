@@ -0,0 +1,263 @@
+//! ハードウェア確認用のテストパターン
+//!
+//! 画像を用意しなくても描画の位置ずれや抜けを確認できるよう、単純な図形を黒ドットで生成する。
+
+use crate::domain::artwork::entities::{Canvas, CanvasError, Dot};
+use crate::domain::shared::value_objects::Coordinates;
+
+/// 3x5ドットのビットマップフォントの1文字の幅
+const GLYPH_WIDTH: u16 = 3;
+/// 3x5ドットのビットマップフォントの1文字の高さ
+const GLYPH_HEIGHT: u16 = 5;
+
+/// 文字の各行（上から順、3ビットの上位が左端）
+fn glyph(c: char) -> Option<[u8; 5]> {
+    let rows = match c.to_ascii_uppercase() {
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b110, 0b001, 0b010, 0b100, 0b111],
+        '3' => [0b110, 0b001, 0b010, 0b001, 0b110],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b110, 0b001, 0b110],
+        '6' => [0b011, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b010, 0b010, 0b010],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b110],
+        ' ' => [0b000; 5],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '?' => [0b110, 0b001, 0b010, 0b000, 0b010],
+        _ => return None,
+    };
+    Some(rows)
+}
+
+impl Canvas {
+    /// 条件を満たす座標すべてに黒ドットを置いたキャンバスを作成
+    fn filled_where(
+        width: u16,
+        height: u16,
+        mut is_dot: impl FnMut(u16, u16) -> bool,
+    ) -> Result<Canvas, CanvasError> {
+        if width == 0 || height == 0 {
+            return Err(CanvasError::InvalidSize);
+        }
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                if is_dot(x, y) {
+                    canvas.dots.insert(Coordinates::new(x, y), Dot::black());
+                }
+            }
+        }
+        Ok(canvas)
+    }
+
+    /// 市松模様（左上のマスが黒、`cell_size` ドット四方のマス）
+    pub fn checkerboard(width: u16, height: u16, cell_size: u16) -> Result<Canvas, CanvasError> {
+        if cell_size == 0 {
+            return Err(CanvasError::InvalidSize);
+        }
+        Self::filled_where(width, height, |x, y| {
+            (x / cell_size + y / cell_size).is_multiple_of(2)
+        })
+    }
+
+    /// キャンバスの外周に沿った `thickness` ドット幅の枠
+    pub fn border(width: u16, height: u16, thickness: u16) -> Result<Canvas, CanvasError> {
+        if thickness == 0 {
+            return Err(CanvasError::InvalidSize);
+        }
+        Self::filled_where(width, height, |x, y| {
+            x < thickness
+                || y < thickness
+                || x >= width.saturating_sub(thickness)
+                || y >= height.saturating_sub(thickness)
+        })
+    }
+
+    /// 中央で交わる縦横1ドットの線（幅・高さが偶数の場合は中央の左・上の列を使う）
+    pub fn crosshair(width: u16, height: u16) -> Result<Canvas, CanvasError> {
+        let (center_x, center_y) = ((width.max(1) - 1) / 2, (height.max(1) - 1) / 2);
+        Self::filled_where(width, height, |x, y| x == center_x || y == center_y)
+    }
+
+    /// 左上から右下への途切れない斜線（長い辺の各位置に1ドット）
+    pub fn diagonal(width: u16, height: u16) -> Result<Canvas, CanvasError> {
+        let mut canvas = Self::filled_where(width, height, |_, _| false)?;
+        let steps = width.max(height) as u32 - 1;
+        for i in 0..=steps {
+            // 短い辺の座標は四捨五入で求める
+            let scale = |length: u16| {
+                (i * (length as u32 - 1) * 2 + steps)
+                    .checked_div(steps * 2)
+                    .unwrap_or(0) as u16
+            };
+            canvas
+                .dots
+                .insert(Coordinates::new(scale(width), scale(height)), Dot::black());
+        }
+        Ok(canvas)
+    }
+
+    /// 3x5ドットの内蔵フォントで描いた文字列をキャンバスの中央に配置
+    ///
+    /// 文字の間は `scale` ドット空ける。英数字と ` -.:!?` に対応し、小文字は大文字として描く。
+    pub fn text(width: u16, height: u16, text: &str, scale: u16) -> Result<Canvas, CanvasError> {
+        if scale == 0 || text.is_empty() {
+            return Err(CanvasError::InvalidSize);
+        }
+        let glyphs = text
+            .chars()
+            .map(|c| glyph(c).ok_or(CanvasError::UnsupportedCharacter(c)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let text_width = (glyphs.len() as u32 * (GLYPH_WIDTH as u32 + 1) - 1) * scale as u32;
+        let text_height = GLYPH_HEIGHT as u32 * scale as u32;
+        if text_width > width as u32 || text_height > height as u32 {
+            return Err(CanvasError::PatternTooLarge {
+                width: text_width.min(u16::MAX as u32) as u16,
+                height: text_height.min(u16::MAX as u32) as u16,
+            });
+        }
+
+        let text_canvas = Self::filled_where(text_width as u16, text_height as u16, |x, y| {
+            let (column, row) = (x / scale, y / scale);
+            let (index, glyph_x) = (column / (GLYPH_WIDTH + 1), column % (GLYPH_WIDTH + 1));
+            glyph_x < GLYPH_WIDTH && glyphs[index as usize][row as usize] & (0b100 >> glyph_x) != 0
+        })?;
+        text_canvas.centered_on(width, height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 行ごとに `#` と `.` で表したドット配置
+    fn render(canvas: &Canvas) -> Vec<String> {
+        (0..canvas.height)
+            .map(|y| {
+                (0..canvas.width)
+                    .map(|x| match canvas.get_dot(&Coordinates::new(x, y)) {
+                        Some(_) => '#',
+                        None => '.',
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_checkerboard() {
+        assert_eq!(
+            render(&Canvas::checkerboard(5, 3, 1).unwrap()),
+            ["#.#.#", ".#.#.", "#.#.#"]
+        );
+        assert_eq!(
+            render(&Canvas::checkerboard(5, 4, 2).unwrap()),
+            ["##..#", "##..#", "..##.", "..##."]
+        );
+        assert!(Canvas::checkerboard(5, 4, 0).is_err());
+    }
+
+    #[test]
+    fn test_border() {
+        assert_eq!(
+            render(&Canvas::border(5, 4, 1).unwrap()),
+            ["#####", "#...#", "#...#", "#####"]
+        );
+        assert_eq!(
+            render(&Canvas::border(6, 5, 2).unwrap()),
+            ["######", "######", "##..##", "######", "######"]
+        );
+    }
+
+    #[test]
+    fn test_crosshair() {
+        assert_eq!(
+            render(&Canvas::crosshair(5, 3).unwrap()),
+            ["..#..", "#####", "..#.."]
+        );
+        assert_eq!(
+            render(&Canvas::crosshair(4, 4).unwrap()),
+            [".#..", "####", ".#..", ".#.."]
+        );
+    }
+
+    #[test]
+    fn test_diagonal() {
+        assert_eq!(
+            render(&Canvas::diagonal(3, 3).unwrap()),
+            ["#..", ".#.", "..#"]
+        );
+        assert_eq!(
+            render(&Canvas::diagonal(5, 3).unwrap()),
+            ["#....", ".##..", "...##"]
+        );
+        assert_eq!(render(&Canvas::diagonal(1, 3).unwrap()), ["#", "#", "#"]);
+        assert_eq!(render(&Canvas::diagonal(1, 1).unwrap()), ["#"]);
+    }
+
+    #[test]
+    fn test_text_is_centered() {
+        assert_eq!(
+            render(&Canvas::text(9, 7, "Hi", 1).unwrap()),
+            [
+                ".........",
+                ".#.#.###.",
+                ".#.#..#..",
+                ".###..#..",
+                ".#.#..#..",
+                ".#.#.###.",
+                ".........",
+            ]
+        );
+        assert_eq!(
+            render(&Canvas::text(6, 10, "-", 2).unwrap()),
+            [
+                "......", "......", "......", "......", "######", "######", "......", "......",
+                "......", "......",
+            ]
+        );
+        assert!(matches!(
+            Canvas::text(20, 5, "T~", 1),
+            Err(CanvasError::UnsupportedCharacter('~'))
+        ));
+        assert!(matches!(
+            Canvas::text(6, 5, "TEST", 1),
+            Err(CanvasError::PatternTooLarge {
+                width: 15,
+                height: 5
+            })
+        ));
+    }
+}
@@ -67,6 +67,10 @@ pub struct ArtworkQuery {
     pub tags: Option<Vec<String>>,
     /// 作者による検索
     pub author: Option<String>,
+    /// 元ファイルのSHA-256による検索
+    pub checksum: Option<String>,
+    /// アニメーション画像から作成した組による検索
+    pub set_id: Option<String>,
     /// 作成日時範囲
     pub created_after: Option<Timestamp>,
     pub created_before: Option<Timestamp>,
@@ -135,6 +139,22 @@ impl ArtworkQuery {
         }
     }
 
+    /// 元ファイルのチェックサムで検索するクエリ
+    pub fn by_checksum(checksum: String) -> Self {
+        Self {
+            checksum: Some(checksum),
+            ..Default::default()
+        }
+    }
+
+    /// アニメーション画像から作成した組のアートワークを検索するクエリ
+    pub fn by_set_id(set_id: String) -> Self {
+        Self {
+            set_id: Some(set_id),
+            ..Default::default()
+        }
+    }
+
     /// 最近作成されたアートワークを検索するクエリ
     pub fn recent(limit: usize) -> Self {
         Self {
@@ -194,6 +214,127 @@ impl ArtworkQuery {
 
         Ok(())
     }
+
+    /// アートワークが絞り込み条件をすべて満たすか
+    ///
+    /// 名前は大文字・小文字を区別しない部分一致、タグは指定したすべてを持つものに一致する。
+    pub fn matches(&self, artwork: &Artwork) -> bool {
+        let metadata = &artwork.metadata;
+        let created = artwork.created_at.epoch_millis;
+        let updated = artwork.updated_at.epoch_millis;
+
+        self.ids
+            .as_ref()
+            .is_none_or(|ids| ids.contains(&artwork.id))
+            && self
+                .name_contains
+                .as_ref()
+                .is_none_or(|name| metadata.name.to_lowercase().contains(&name.to_lowercase()))
+            && self
+                .tags
+                .as_ref()
+                .is_none_or(|tags| tags.iter().all(|tag| metadata.has_tag(tag)))
+            && self
+                .author
+                .as_ref()
+                .is_none_or(|author| metadata.author.as_ref() == Some(author))
+            && self
+                .checksum
+                .as_ref()
+                .is_none_or(|checksum| metadata.checksum == *checksum)
+            && self.set_id.as_ref().is_none_or(|set_id| {
+                artwork
+                    .artwork_set
+                    .as_ref()
+                    .is_some_and(|set| set.set_id == *set_id)
+            })
+            && self
+                .created_after
+                .is_none_or(|after| created > after.epoch_millis)
+            && self
+                .created_before
+                .is_none_or(|before| created < before.epoch_millis)
+            && self
+                .updated_after
+                .is_none_or(|after| updated > after.epoch_millis)
+            && self
+                .updated_before
+                .is_none_or(|before| updated < before.epoch_millis)
+            && self
+                .format
+                .as_ref()
+                .is_none_or(|format| artwork.original_format == *format)
+            && self.has_computed_range_match(artwork)
+    }
+
+    /// 完成度・複雑度の範囲条件（キャンバスから計算する値）を満たすか
+    pub fn has_computed_range_match(&self, artwork: &Artwork) -> bool {
+        let in_range = |value: f64, min: Option<f64>, max: Option<f64>| {
+            min.is_none_or(|min| value >= min) && max.is_none_or(|max| value <= max)
+        };
+        (self.min_completion.is_none() && self.max_completion.is_none()
+            || in_range(
+                artwork.completion_ratio(),
+                self.min_completion,
+                self.max_completion,
+            ))
+            && (self.min_complexity.is_none() && self.max_complexity.is_none()
+                || in_range(
+                    artwork.complexity_score(),
+                    self.min_complexity,
+                    self.max_complexity,
+                ))
+    }
+
+    /// キャンバスから計算する値での絞り込み・並べ替えを含むか
+    pub fn needs_computed_fields(&self) -> bool {
+        self.min_completion.is_some()
+            || self.max_completion.is_some()
+            || self.min_complexity.is_some()
+            || self.max_complexity.is_some()
+            || matches!(
+                self.sort_by,
+                Some(SortField::CompletionRatio | SortField::ComplexityScore)
+            )
+    }
+
+    /// ソート設定に従って並べ替える（未指定なら作成日時の昇順、同時刻はID順）
+    pub fn sort(&self, artworks: &mut [Artwork]) {
+        let field = self.sort_by.unwrap_or(SortField::CreatedAt);
+        artworks.sort_by(|a, b| {
+            let ordering = match field {
+                SortField::Name => a.metadata.name.cmp(&b.metadata.name),
+                SortField::CreatedAt => a.created_at.cmp(&b.created_at),
+                SortField::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+                SortField::CompletionRatio => a.completion_ratio().total_cmp(&b.completion_ratio()),
+                SortField::ComplexityScore => a.complexity_score().total_cmp(&b.complexity_score()),
+                SortField::TotalDots => a.total_dots().cmp(&b.total_dots()),
+                SortField::FileSize => a.metadata.file_size.cmp(&b.metadata.file_size),
+            };
+            let ordering = ordering.then_with(|| a.id.as_uuid().cmp(&b.id.as_uuid()));
+            match self.sort_order {
+                Some(SortOrder::Descending) => ordering.reverse(),
+                _ => ordering,
+            }
+        });
+    }
+
+    /// 絞り込み・並べ替え・ページネーションをメモリ上で適用する
+    pub fn apply(&self, artworks: impl IntoIterator<Item = Artwork>) -> SearchResult {
+        let mut matching: Vec<Artwork> = artworks
+            .into_iter()
+            .filter(|artwork| self.matches(artwork))
+            .collect();
+        self.sort(&mut matching);
+
+        let total_count = matching.len();
+        let offset = self.offset.unwrap_or(0).min(total_count);
+        let end = self.limit.map_or(total_count, |limit| {
+            offset.saturating_add(limit).min(total_count)
+        });
+        let page: Vec<Artwork> = matching.drain(offset..end).collect();
+        SearchResult::new(page, total_count, end < total_count, 0)
+    }
 }
 
 /// ソートフィールド
@@ -257,6 +398,14 @@ impl SearchResult {
     }
 }
 
+/// ゴミ箱に移したアートワーク
+#[derive(Debug, Clone)]
+pub struct TrashedArtwork {
+    pub artwork: Artwork,
+    /// ゴミ箱に移した日時
+    pub deleted_at: Timestamp,
+}
+
 /// バッチ操作結果
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BatchResult {
@@ -308,6 +457,8 @@ impl Default for BatchResult {
 }
 
 /// アートワークリポジトリトレイト
+///
+/// ゴミ箱に移したアートワークは、取得・検索・件数に含めない（`find_trashed` と `delete` だけが扱う）。
 #[async_trait]
 pub trait ArtworkRepository: Send + Sync {
     /// アートワークを保存
@@ -316,9 +467,22 @@ pub trait ArtworkRepository: Send + Sync {
     /// アートワークを取得
     async fn find_by_id(&self, id: &ArtworkId) -> Result<Option<Artwork>, RepositoryError>;
 
-    /// アートワークを削除
+    /// アートワークを削除（ゴミ箱に移したアートワークも完全に削除する）
     async fn delete(&self, id: &ArtworkId) -> Result<(), RepositoryError>;
 
+    /// アートワークをゴミ箱に移す
+    async fn move_to_trash(
+        &self,
+        id: &ArtworkId,
+        deleted_at: Timestamp,
+    ) -> Result<(), RepositoryError>;
+
+    /// ゴミ箱のアートワークを元に戻す
+    async fn restore_from_trash(&self, id: &ArtworkId) -> Result<(), RepositoryError>;
+
+    /// ゴミ箱のアートワークを、ゴミ箱に移した日時の古い順に取得
+    async fn find_trashed(&self) -> Result<Vec<TrashedArtwork>, RepositoryError>;
+
     /// アートワークを検索
     async fn search(&self, query: &ArtworkQuery) -> Result<SearchResult, RepositoryError>;
 
@@ -530,6 +694,47 @@ mod tests {
         assert!(query.validate().is_err());
     }
 
+    #[test]
+    fn test_artwork_query_apply_filters_sorts_and_paginates() {
+        let artwork = |name: &str, tags: &[&str], created: u64| {
+            let mut metadata = ArtworkMetadata::new(name.to_string());
+            metadata.set_tags(tags).unwrap();
+            let mut artwork = Artwork::new(metadata, "png".to_string(), Canvas::new(4, 4));
+            artwork.created_at = Timestamp::from_millis(created);
+            artwork
+        };
+        let artworks = vec![
+            artwork("Squid Logo", &["logo", "squid"], 3),
+            artwork("Octo", &["octo"], 1),
+            artwork("squid kid", &["squid"], 2),
+        ];
+
+        let mut query = ArtworkQuery::by_name_contains("SQUID".to_string());
+        let result = query.apply(artworks.clone());
+        let names: Vec<_> = result
+            .artworks
+            .iter()
+            .map(|a| a.metadata.name.as_str())
+            .collect();
+        assert_eq!(names, ["squid kid", "Squid Logo"]);
+
+        query.tags = Some(vec!["squid".to_string(), "logo".to_string()]);
+        assert_eq!(query.apply(artworks.clone()).len(), 1);
+
+        let page = ArtworkQuery::new()
+            .with_sort(SortField::CreatedAt, SortOrder::Descending)
+            .with_pagination(2, 1)
+            .apply(artworks);
+        let names: Vec<_> = page
+            .artworks
+            .iter()
+            .map(|a| a.metadata.name.as_str())
+            .collect();
+        assert_eq!(names, ["squid kid", "Octo"]);
+        assert_eq!(page.total_count, 3);
+        assert!(!page.has_more);
+    }
+
     #[test]
     fn test_batch_result() {
         let mut result = BatchResult::new();
@@ -1,5 +1,10 @@
-use crate::domain::artwork::value_objects::{ColorReduction, ImageAdjustments};
-use crate::domain::shared::value_objects::Color;
+use crate::domain::artwork::entities::{Canvas, Dot};
+use crate::domain::artwork::value_objects::{
+    ColorReduction, ConversionError, ConversionParameters, EdgeDetection, ImageAdjustments,
+    Placement, Resolution, RgbaImage, Transparency,
+};
+use crate::domain::shared::value_objects::{Color, Coordinates};
+use std::time::Instant;
 
 /// 画像処理サービス
 pub struct ImageProcessingService;
@@ -115,7 +120,13 @@ impl ImageProcessingService {
     }
 
     /// 色削減の適用
-    pub fn apply_color_reduction(pixel: &Color, reduction: &ColorReduction) -> Color {
+    ///
+    /// 点描は位置によって結果が変わるため、ピクセルの座標も渡す
+    pub fn apply_color_reduction(
+        pixel: &Color,
+        reduction: &ColorReduction,
+        coordinates: Coordinates,
+    ) -> Color {
         match reduction {
             ColorReduction::Grayscale => {
                 let gray = pixel.to_grayscale();
@@ -138,6 +149,13 @@ impl ImageProcessingService {
 
                 Color::new(r, g, b, pixel.a)
             }
+            ColorReduction::Stipple { matrix } => {
+                if pixel.to_grayscale() < matrix.threshold_at(coordinates) {
+                    Color::black()
+                } else {
+                    Color::white()
+                }
+            }
         }
     }
 
@@ -193,4 +211,763 @@ impl ImageProcessingService {
         sorted.sort_unstable();
         sorted[sorted.len() / 2]
     }
+
+    /// RGBAの画素列（行順）を2値化し、黒と判定した画素を黒いドットにしたキャンバスを作成
+    ///
+    /// 不透明度が `transparency` の下限未満の画素は描画せず、半透明の画素はモードに従って背景に重ねる
+    pub fn threshold_rgba(
+        width: u16,
+        height: u16,
+        rgba: &[u8],
+        adjustments: &ImageAdjustments,
+        transparency: &Transparency,
+    ) -> Canvas {
+        let image = RgbaImage::new(width as u32, height as u32, rgba.to_vec())
+            .map_pixels(|_, _, pixel| transparency.composite(&pixel));
+        let adjustments = ImageAdjustments {
+            adaptive_threshold: false,
+            ..adjustments.clone()
+        };
+        ConversionPipeline::dots(&ConversionPipeline::binarize(
+            &image,
+            &adjustments,
+            transparency,
+            None,
+            false,
+        ))
+    }
+
+    /// RGBAの画素列（行順）から輪郭だけを黒いドットにしたキャンバスを作成
+    ///
+    /// 画像調整を適用した濃淡にSobelフィルタを掛け、勾配が `edge.threshold` 以上で周囲より暗い画素
+    /// （輪郭の内側）だけを残す。不透明度が `transparency` の下限未満の画素は白い背景とみなし、
+    /// 半透明の画素はモードに従って背景に重ねてから画像調整を適用する。
+    pub fn outline_rgba(
+        width: u16,
+        height: u16,
+        rgba: &[u8],
+        adjustments: &ImageAdjustments,
+        transparency: &Transparency,
+        edge: &EdgeDetection,
+    ) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        let (width, height) = (width as usize, height as usize);
+        if width == 0 || height == 0 {
+            return canvas;
+        }
+        let mut gray: Vec<u8> = rgba
+            .chunks_exact(4)
+            .take(width * height)
+            .map(|pixel| {
+                let color = Color::new(pixel[0], pixel[1], pixel[2], pixel[3]);
+                if transparency.skips(&color) {
+                    255
+                } else {
+                    Self::apply_adjustments(&transparency.composite(&color), adjustments)
+                        .to_grayscale()
+                }
+            })
+            .collect();
+        gray.resize(width * height, 255);
+
+        // 画像の外は端の画素が続いているとみなす
+        let at = |x: usize, y: usize, dx: usize, dy: usize| {
+            let x = (x + dx).saturating_sub(1).min(width - 1);
+            let y = (y + dy).saturating_sub(1).min(height - 1);
+            gray[y * width + x]
+        };
+        let mut edges = vec![false; width * height];
+        for y in 0..height {
+            for x in 0..width {
+                let mut window = [[0u8; 3]; 3];
+                let mut sum = 0u32;
+                for (dy, row) in window.iter_mut().enumerate() {
+                    for (dx, value) in row.iter_mut().enumerate() {
+                        *value = at(x, y, dx, dy);
+                        sum += *value as u32;
+                    }
+                }
+                edges[y * width + x] = Self::sobel_edge_magnitude(&window) >= edge.threshold
+                    && (window[1][1] as u32) * 9 < sum;
+            }
+        }
+        if edge.thinning {
+            thin(&mut edges, width, height);
+        }
+
+        for (index, _) in edges.iter().enumerate().filter(|(_, edge)| **edge) {
+            let coordinates = Coordinates::new((index % width) as u16, (index / width) as u16);
+            // 輪郭は下限以上の不透明度の画素にしかできないため、ドットには元の画素の不透明度を使う
+            let alpha = rgba.get(index * 4 + 3).copied().unwrap_or(255);
+            canvas
+                .dots
+                .insert(coordinates, Dot::new(Color::black(), alpha));
+        }
+        canvas
+    }
+}
+
+/// 画像からドットへの変換の段階（この順に適用する）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConversionStage {
+    /// キャンバスの解像度に収まるよう縮小した元画像
+    Original,
+    /// 露出・コントラスト・ガンマなどの画像調整の後
+    Adjusted,
+    /// 閾値・点描で白黒の2値にした後
+    Binarized,
+    /// 黒い画素を描画するドットにしたキャンバス
+    Dots,
+}
+
+impl ConversionStage {
+    pub const ALL: [ConversionStage; 4] = [
+        ConversionStage::Original,
+        ConversionStage::Adjusted,
+        ConversionStage::Binarized,
+        ConversionStage::Dots,
+    ];
+
+    /// APIで使う段階の名前
+    pub fn name(self) -> &'static str {
+        match self {
+            ConversionStage::Original => "original",
+            ConversionStage::Adjusted => "adjusted",
+            ConversionStage::Binarized => "binarized",
+            ConversionStage::Dots => "dots",
+        }
+    }
+}
+
+/// 変換パイプラインの各段階の結果
+#[derive(Debug, Clone)]
+pub struct ConversionStages {
+    pub original: RgbaImage,
+    pub adjusted: RgbaImage,
+    pub binarized: RgbaImage,
+    pub canvas: Canvas,
+}
+
+/// 画像をドットのキャンバスに変換するパイプライン
+///
+/// 各段階は単独でも呼べるようにしてあり、`run` は `ConversionStage::ALL` の順に適用する。
+pub struct ConversionPipeline;
+
+impl ConversionPipeline {
+    /// 全段階を順に適用する
+    ///
+    /// 各段階の前に `deadline` を過ぎていれば、その段階の名前を付けて打ち切る
+    pub fn run(
+        image: &RgbaImage,
+        params: &ConversionParameters,
+        deadline: Instant,
+    ) -> Result<ConversionStages, ConversionError> {
+        let check = |stage: ConversionStage| {
+            if Instant::now() >= deadline {
+                Err(ConversionError::TimedOut(stage.name()))
+            } else {
+                Ok(())
+            }
+        };
+        check(ConversionStage::Original)?;
+        let original = Self::fit(
+            image,
+            &params.target_resolution,
+            params.preserve_aspect_ratio,
+        );
+        check(ConversionStage::Adjusted)?;
+        let adjusted = Self::adjust(&original, &params.adjustments, &params.transparency);
+        check(ConversionStage::Binarized)?;
+        let binarized = Self::binarize(
+            &adjusted,
+            &params.adjustments,
+            &params.transparency,
+            params.color_reduction.as_ref(),
+            params.invert_background.unwrap_or(false),
+        );
+        check(ConversionStage::Dots)?;
+        let canvas = Self::dots(&binarized);
+        Ok(ConversionStages {
+            original,
+            adjusted,
+            binarized,
+            canvas,
+        })
+    }
+
+    /// キャンバスの解像度に収まるよう、画素の平均で縮小する（拡大はしない）
+    ///
+    /// `preserve_aspect_ratio` でなければ、はみ出す辺だけを解像度に合わせて縮める
+    pub fn fit(image: &RgbaImage, target: &Resolution, preserve_aspect_ratio: bool) -> RgbaImage {
+        let source = Resolution {
+            width: image.width,
+            height: image.height,
+        };
+        let size = if source.total_pixels() == 0 || source.fits_in(target) {
+            source
+        } else if preserve_aspect_ratio {
+            source.scale_to_fit(target.width, target.height)
+        } else {
+            Resolution {
+                width: source.width.min(target.width),
+                height: source.height.min(target.height),
+            }
+        };
+        if size.width == image.width && size.height == image.height {
+            return image.clone();
+        }
+
+        let mut pixels = Vec::with_capacity(size.total_pixels() as usize * 4);
+        for y in 0..size.height {
+            for x in 0..size.width {
+                pixels.extend_from_slice(&resampled_pixel(image, &size, x, y));
+            }
+        }
+        RgbaImage::new(size.width, size.height, pixels)
+    }
+
+    /// `placement` の大きさに拡大・縮小した画像を `target` の大きさのキャンバスに置く
+    ///
+    /// 画像の無い余白は描画しない透明な白にする。はみ出した部分は拡大・縮小の計算もしない。
+    pub fn place(image: &RgbaImage, target: &Resolution, placement: &Placement) -> RgbaImage {
+        let size = Resolution {
+            width: placement.width,
+            height: placement.height,
+        };
+        let mut pixels = Vec::with_capacity(target.total_pixels() as usize * 4);
+        for y in 0..target.height {
+            let sy = y as i64 - placement.offset_y as i64;
+            for x in 0..target.width {
+                let sx = x as i64 - placement.offset_x as i64;
+                if image.width == 0
+                    || image.height == 0
+                    || !(0..size.width as i64).contains(&sx)
+                    || !(0..size.height as i64).contains(&sy)
+                {
+                    pixels.extend_from_slice(&[255, 255, 255, 0]);
+                } else {
+                    pixels.extend_from_slice(&resampled_pixel(image, &size, sx as u32, sy as u32));
+                }
+            }
+        }
+        RgbaImage::new(target.width, target.height, pixels)
+    }
+
+    /// 半透明の画素を背景に重ねてから、露出・コントラスト・ガンマなどの画像調整を適用する
+    ///
+    /// 不透明度は変えず、描画しない画素の判定とドットの不透明度に使えるよう残す
+    pub fn adjust(
+        image: &RgbaImage,
+        adjustments: &ImageAdjustments,
+        transparency: &Transparency,
+    ) -> RgbaImage {
+        image.map_pixels(|_, _, pixel| {
+            ImageProcessingService::apply_adjustments(&transparency.composite(&pixel), adjustments)
+        })
+    }
+
+    /// 白黒の2値にする
+    ///
+    /// `reduction` が点描・2値化ならそれに従い、それ以外は閾値（`adaptive_threshold` なら周囲の
+    /// 平均との比較）で分ける。`invert` では白黒を入れ替え、明るい部分をドットにする。
+    /// 不透明度が `transparency` の下限未満の画素は、反転しても描画しない透明な白にする。
+    /// 黒い画素は元の不透明度を残す。
+    pub fn binarize(
+        image: &RgbaImage,
+        adjustments: &ImageAdjustments,
+        transparency: &Transparency,
+        reduction: Option<&ColorReduction>,
+        invert: bool,
+    ) -> RgbaImage {
+        let local_averages = (reduction.is_none() && adjustments.adaptive_threshold)
+            .then(|| local_averages(image, adjustments.adaptive_block_size));
+        image.map_pixels(|x, y, pixel| {
+            if transparency.skips(&pixel) {
+                return Color::new(255, 255, 255, 0);
+            }
+            let binary = match (reduction, &local_averages) {
+                (Some(reduction), _) => {
+                    let coordinates = Coordinates::new(x as u16, y as u16);
+                    let reduced = ImageProcessingService::apply_color_reduction(
+                        &pixel,
+                        reduction,
+                        coordinates,
+                    );
+                    match reduction {
+                        ColorReduction::Binary(_) | ColorReduction::Stipple { .. } => reduced,
+                        ColorReduction::Grayscale | ColorReduction::Palette(_) => {
+                            ImageProcessingService::apply_threshold(&reduced, adjustments)
+                        }
+                    }
+                }
+                (None, Some(averages)) => ImageProcessingService::apply_adaptive_threshold(
+                    &pixel,
+                    averages[(y * image.width + x) as usize],
+                    adjustments,
+                ),
+                (None, None) => ImageProcessingService::apply_threshold(&pixel, adjustments),
+            };
+            match (binary == Color::black(), invert) {
+                (true, false) | (false, true) => Color::new(0, 0, 0, pixel.a),
+                _ => Color::white(),
+            }
+        })
+    }
+
+    /// 2値画像の黒い画素を、その不透明度を持つ黒いドットにしたキャンバスを作る
+    pub fn dots(image: &RgbaImage) -> Canvas {
+        let mut canvas = Canvas::new(image.width as u16, image.height as u16);
+        for y in 0..image.height {
+            for x in 0..image.width {
+                let pixel = image.pixel(x, y);
+                let coordinates = Coordinates::new(x as u16, y as u16);
+                if pixel.a > 0
+                    && (pixel.r, pixel.g, pixel.b) == (0, 0, 0)
+                    && canvas.is_valid_coordinate(&coordinates)
+                {
+                    canvas
+                        .dots
+                        .insert(coordinates, Dot::new(Color::black(), pixel.a));
+                }
+            }
+        }
+        canvas
+    }
+}
+
+/// `image` を `size` に拡大・縮小したときの `(x, y)` の画素（元の画素の平均、拡大では最も近い画素）
+fn resampled_pixel(image: &RgbaImage, size: &Resolution, x: u32, y: u32) -> [u8; 4] {
+    let scale = |position: u32, source: u32, scaled: u32| {
+        (position as u64 * source as u64 / scaled as u64) as u32
+    };
+    let top = scale(y, image.height, size.height);
+    let bottom = scale(y + 1, image.height, size.height).max(top + 1);
+    let left = scale(x, image.width, size.width);
+    let right = scale(x + 1, image.width, size.width).max(left + 1);
+    // 透明な画素の色が混ざらないよう、色は不透明度で重み付けする
+    let mut sums = [0u64; 4];
+    for sy in top..bottom {
+        for sx in left..right {
+            let pixel = image.pixel(sx, sy);
+            let alpha = pixel.a as u64;
+            sums[0] += pixel.r as u64 * alpha;
+            sums[1] += pixel.g as u64 * alpha;
+            sums[2] += pixel.b as u64 * alpha;
+            sums[3] += alpha;
+        }
+    }
+    let count = ((bottom - top) * (right - left)) as u64;
+    let color = |sum: u64| sum.checked_div(sums[3]).unwrap_or(0) as u8;
+    [
+        color(sums[0]),
+        color(sums[1]),
+        color(sums[2]),
+        (sums[3] / count) as u8,
+    ]
+}
+
+/// 各画素を中心とした `block_size` 四方の濃淡の平均（画像の外は含めない）
+fn local_averages(image: &RgbaImage, block_size: u16) -> Vec<u8> {
+    let (width, height) = (image.width as usize, image.height as usize);
+    // 累積和で矩形の合計を求める
+    let mut integral = vec![0u64; (width + 1) * (height + 1)];
+    for y in 0..height {
+        let mut row = 0u64;
+        for x in 0..width {
+            row += image.pixel(x as u32, y as u32).to_grayscale() as u64;
+            integral[(y + 1) * (width + 1) + x + 1] = integral[y * (width + 1) + x + 1] + row;
+        }
+    }
+    let radius = block_size as usize / 2;
+    let mut averages = Vec::with_capacity(width * height);
+    for y in 0..height {
+        let (top, bottom) = (y.saturating_sub(radius), (y + radius + 1).min(height));
+        for x in 0..width {
+            let (left, right) = (x.saturating_sub(radius), (x + radius + 1).min(width));
+            let sum = integral[bottom * (width + 1) + right] + integral[top * (width + 1) + left]
+                - integral[top * (width + 1) + right]
+                - integral[bottom * (width + 1) + left];
+            averages.push((sum / ((bottom - top) * (right - left)) as u64) as u8);
+        }
+    }
+    averages
+}
+
+/// Zhang-Suen法で線を1画素幅まで細くする（消せる画素が無くなるまで繰り返す）
+///
+/// 線の端と、消すと線が途切れる画素は残す。1画素幅の角は階段状とみなして消えることがある。
+fn thin(pixels: &mut [bool], width: usize, height: usize) {
+    fn is_set(pixels: &[bool], width: usize, height: usize, x: isize, y: isize) -> bool {
+        x >= 0
+            && y >= 0
+            && (x as usize) < width
+            && (y as usize) < height
+            && pixels[y as usize * width + x as usize]
+    }
+
+    loop {
+        let mut changed = false;
+        for step in 0..2 {
+            let mut removable = Vec::new();
+            for y in 0..height {
+                for x in 0..width {
+                    if !pixels[y * width + x] {
+                        continue;
+                    }
+                    let (x, y) = (x as isize, y as isize);
+                    // 真上から時計回りの8近傍
+                    let n = [
+                        (0, -1),
+                        (1, -1),
+                        (1, 0),
+                        (1, 1),
+                        (0, 1),
+                        (-1, 1),
+                        (-1, 0),
+                        (-1, -1),
+                    ]
+                    .map(|(dx, dy)| is_set(pixels, width, height, x + dx, y + dy));
+                    let neighbors = n.iter().filter(|&&set| set).count();
+                    let transitions = (0..8).filter(|&i| !n[i] && n[(i + 1) % 8]).count();
+                    let (first, second) = if step == 0 {
+                        (n[0] && n[2] && n[4], n[2] && n[4] && n[6])
+                    } else {
+                        (n[0] && n[2] && n[6], n[0] && n[4] && n[6])
+                    };
+                    if (2..=6).contains(&neighbors) && transitions == 1 && !first && !second {
+                        removable.push(y as usize * width + x as usize);
+                    }
+                }
+            }
+            changed |= !removable.is_empty();
+            for index in removable {
+                pixels[index] = false;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::artwork::value_objects::OrderedMatrixSize;
+
+    fn stipple(gray: u8, matrix: OrderedMatrixSize) -> Vec<Vec<bool>> {
+        let pixel = Color::new(gray, gray, gray, 255);
+        let size = matrix.size();
+        (0..size)
+            .map(|y| {
+                (0..size)
+                    .map(|x| {
+                        ImageProcessingService::apply_color_reduction(
+                            &pixel,
+                            &ColorReduction::Stipple { matrix },
+                            Coordinates::new(x, y),
+                        ) == Color::black()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_stipple_half_grey_is_checkerboard() {
+        for matrix in [OrderedMatrixSize::TwoByTwo, OrderedMatrixSize::FourByFour] {
+            let cells = stipple(128, matrix);
+            for (y, row) in cells.iter().enumerate() {
+                for (x, &black) in row.iter().enumerate() {
+                    assert_eq!(black, (x + y) % 2 == 1, "{matrix:?} at ({x}, {y})");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_stipple_density_follows_luminance() {
+        let count = |gray| {
+            stipple(gray, OrderedMatrixSize::FourByFour)
+                .iter()
+                .flatten()
+                .filter(|&&black| black)
+                .count()
+        };
+        assert_eq!(count(0), 16);
+        assert_eq!(count(64), 12);
+        assert_eq!(count(192), 4);
+        assert_eq!(count(255), 0);
+    }
+
+    /// 透明な背景に、`(5, 5)` から10x10の黒い正方形を置いた20x20の画像
+    fn filled_square() -> Vec<u8> {
+        let mut rgba = vec![0; 20 * 20 * 4];
+        for y in 5..15 {
+            for x in 5..15 {
+                rgba[(y * 20 + x) * 4..][..4].copy_from_slice(&[0, 0, 0, 255]);
+            }
+        }
+        rgba
+    }
+
+    fn on_square_perimeter(c: &Coordinates) -> bool {
+        (5..15).contains(&c.x)
+            && (5..15).contains(&c.y)
+            && (c.x == 5 || c.x == 14 || c.y == 5 || c.y == 14)
+    }
+
+    #[test]
+    fn test_outline_of_filled_square_is_its_perimeter() {
+        let rgba = filled_square();
+        let adjustments = ImageAdjustments::default();
+        let filled = ImageProcessingService::threshold_rgba(
+            20,
+            20,
+            &rgba,
+            &adjustments,
+            &Transparency::default(),
+        );
+        assert_eq!(filled.dots.len(), 100);
+
+        let outline = ImageProcessingService::outline_rgba(
+            20,
+            20,
+            &rgba,
+            &adjustments,
+            &Transparency::default(),
+            &EdgeDetection::default(),
+        );
+        assert_eq!(outline.dots.len(), 36);
+        assert!(outline.dots.keys().all(on_square_perimeter));
+
+        // 細線化しても周囲からはみ出さない（1画素幅の角は消えてもよい）
+        let thinned = ImageProcessingService::outline_rgba(
+            20,
+            20,
+            &rgba,
+            &adjustments,
+            &Transparency::default(),
+            &EdgeDetection {
+                thinning: true,
+                ..EdgeDetection::default()
+            },
+        );
+        assert!(
+            (32..=36).contains(&thinned.dots.len()),
+            "{}",
+            thinned.dots.len()
+        );
+        assert!(thinned.dots.keys().all(on_square_perimeter));
+    }
+
+    #[test]
+    fn test_outline_ignores_gradients_below_the_threshold() {
+        // 灰色（200）の正方形は白との差が小さい
+        let rgba: Vec<u8> = filled_square()
+            .chunks_exact(4)
+            .flat_map(|pixel| {
+                if pixel[3] == 255 {
+                    [200, 200, 200, 255]
+                } else {
+                    [255, 255, 255, 255]
+                }
+            })
+            .collect();
+        let adjustments = ImageAdjustments::default();
+        let outline = |threshold| {
+            ImageProcessingService::outline_rgba(
+                20,
+                20,
+                &rgba,
+                &adjustments,
+                &Transparency::default(),
+                &EdgeDetection {
+                    threshold,
+                    thinning: false,
+                },
+            )
+            .dots
+            .len()
+        };
+        assert_eq!(outline(255), 0);
+        assert_eq!(outline(64), 36);
+    }
+
+    #[test]
+    fn test_thinning_reduces_thick_lines_to_one_dot() {
+        let (width, height) = (16, 7);
+        let mut pixels = vec![false; width * height];
+        for y in 2..5 {
+            for x in 1..15 {
+                pixels[y * width + x] = true;
+            }
+        }
+        thin(&mut pixels, width, height);
+
+        for x in 0..width {
+            let column = (0..height).filter(|&y| pixels[y * width + x]).count();
+            assert!(column <= 1, "column {x} has {column} dots");
+        }
+        assert!(pixels.iter().filter(|&&set| set).count() >= 10);
+    }
+
+    #[test]
+    fn test_threshold_rgba_keeps_dark_opaque_pixels() {
+        // 黒・白・半透明の黒・濃い灰色
+        let rgba = [
+            0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 100, 60, 60, 60, 255,
+        ];
+        let canvas = ImageProcessingService::threshold_rgba(
+            2,
+            2,
+            &rgba,
+            &ImageAdjustments::default(),
+            &Transparency::default(),
+        );
+
+        assert_eq!((canvas.width, canvas.height), (2, 2));
+        let mut dots: Vec<_> = canvas.dots.keys().copied().collect();
+        dots.sort_by_key(|c| (c.y, c.x));
+        assert_eq!(dots, vec![Coordinates::new(0, 0), Coordinates::new(1, 1)]);
+    }
+
+    /// 左半分が黒、右半分が白の画像
+    fn half_black(width: u32, height: u32) -> RgbaImage {
+        let mut pixels = Vec::new();
+        for _ in 0..height {
+            for x in 0..width {
+                let value = if x < width / 2 { 0 } else { 255 };
+                pixels.extend_from_slice(&[value, value, value, 255]);
+            }
+        }
+        RgbaImage::new(width, height, pixels)
+    }
+
+    #[test]
+    fn test_fit_stage_downscales_by_averaging_and_never_upscales() {
+        let target = Resolution::splatoon3_standard();
+        let fitted = ConversionPipeline::fit(&half_black(640, 480), &target, true);
+        assert_eq!((fitted.width, fitted.height), (160, 120));
+        assert_eq!(fitted.pixel(0, 0), Color::black());
+        assert_eq!(fitted.pixel(159, 119), Color::white());
+
+        // 縦横の比を保たない場合は、はみ出す辺だけを縮める
+        let stretched = ConversionPipeline::fit(&half_black(640, 100), &target, false);
+        assert_eq!((stretched.width, stretched.height), (320, 100));
+
+        let small = half_black(10, 4);
+        assert_eq!(ConversionPipeline::fit(&small, &target, true), small);
+
+        // 透明な画素の色は平均に混ぜない
+        let red_and_clear = RgbaImage::new(2, 1, vec![255, 0, 0, 255, 0, 0, 0, 0]);
+        let one = Resolution::new(1, 1).unwrap();
+        assert_eq!(
+            ConversionPipeline::fit(&red_and_clear, &one, true).pixel(0, 0),
+            Color::new(255, 0, 0, 127)
+        );
+    }
+
+    #[test]
+    fn test_adjust_stage_is_identity_with_default_adjustments() {
+        let image = RgbaImage::new(2, 1, vec![10, 100, 200, 255, 50, 50, 50, 80]);
+        assert_eq!(
+            ConversionPipeline::adjust(
+                &image,
+                &ImageAdjustments::default(),
+                &Transparency::default()
+            ),
+            image
+        );
+
+        let brighter = ImageAdjustments {
+            exposure: 1.0,
+            ..Default::default()
+        };
+        let adjusted = ConversionPipeline::adjust(&image, &brighter, &Transparency::default());
+        assert_eq!(adjusted.pixel(0, 0), Color::new(20, 200, 255, 255));
+        assert_eq!(adjusted.pixel(1, 0).a, 80);
+    }
+
+    #[test]
+    fn test_binarize_stage_supports_threshold_stipple_adaptive_and_invert() {
+        let gray = RgbaImage::new(4, 4, [128, 128, 128, 255].repeat(16));
+        let adjustments = ImageAdjustments::default();
+        let black_count = |image: &RgbaImage| {
+            image
+                .pixels
+                .chunks_exact(4)
+                .filter(|pixel| pixel[0] == 0)
+                .count()
+        };
+
+        let thresholded = ConversionPipeline::binarize(
+            &gray,
+            &adjustments,
+            &Transparency::default(),
+            None,
+            false,
+        );
+        assert_eq!(black_count(&thresholded), 0);
+        let inverted =
+            ConversionPipeline::binarize(&gray, &adjustments, &Transparency::default(), None, true);
+        assert_eq!(black_count(&inverted), 16);
+
+        // 中間の灰色は4x4の点描で半分が黒になる
+        let stipple = ColorReduction::Stipple {
+            matrix: OrderedMatrixSize::FourByFour,
+        };
+        let stippled = ConversionPipeline::binarize(
+            &gray,
+            &adjustments,
+            &Transparency::default(),
+            Some(&stipple),
+            false,
+        );
+        assert_eq!(black_count(&stippled), 8);
+
+        // 適応的2値化は周囲より暗い画素だけを黒にする
+        let mut pixels = [200, 200, 200, 255].repeat(9);
+        pixels[16..20].copy_from_slice(&[150, 150, 150, 255]);
+        let adaptive = ImageAdjustments {
+            adaptive_threshold: true,
+            adaptive_block_size: 3,
+            adaptive_constant: -5,
+            ..Default::default()
+        };
+        let binarized = ConversionPipeline::binarize(
+            &RgbaImage::new(3, 3, pixels),
+            &adaptive,
+            &Transparency::default(),
+            None,
+            false,
+        );
+        assert_eq!(black_count(&binarized), 1);
+        assert_eq!(binarized.pixel(1, 1), Color::black());
+    }
+
+    #[test]
+    fn test_pipeline_runs_every_stage_and_stops_after_the_deadline() {
+        let params = ConversionParameters::new(
+            crate::domain::artwork::value_objects::ImageFormat::Png,
+            Resolution::splatoon3_standard(),
+        );
+        let image = half_black(640, 240);
+        let stages = ConversionPipeline::run(
+            &image,
+            &params,
+            Instant::now() + std::time::Duration::from_secs(60),
+        )
+        .unwrap();
+        assert_eq!((stages.original.width, stages.original.height), (320, 120));
+        assert_eq!(stages.adjusted, stages.original);
+        assert_eq!(stages.binarized, stages.original);
+        assert_eq!(stages.canvas.dots.len(), 160 * 120);
+
+        let timed_out = ConversionPipeline::run(&image, &params, Instant::now());
+        assert!(matches!(
+            timed_out,
+            Err(ConversionError::TimedOut("original"))
+        ));
+    }
 }
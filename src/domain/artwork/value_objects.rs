@@ -2,9 +2,11 @@
 //!
 //! 画像形式、解像度、変換パラメータなどの値オブジェクトを定義
 
+use crate::domain::shared::value_objects::{Color, Coordinates};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
+use utoipa::ToSchema;
 
 /// 画像フォーマットを表す値オブジェクト
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -308,6 +310,70 @@ impl Resolution {
         self.total_pixels() as f64 / other.total_pixels() as f64
     }
 
+    /// 指定された最小サイズを覆うようにスケール（はみ出す辺は呼び出し側で切り取る）
+    pub fn scale_to_cover(&self, min_width: u32, min_height: u32) -> Self {
+        let width_ratio = min_width as f64 / self.width as f64;
+        let height_ratio = min_height as f64 / self.height as f64;
+        let scale = width_ratio.max(height_ratio);
+
+        let new_width = (self.width as f64 * scale).round() as u32;
+        let new_height = (self.height as f64 * scale).round() as u32;
+
+        Self {
+            width: new_width.max(min_width),
+            height: new_height.max(min_height),
+        }
+    }
+
+    /// `target` のキャンバスに `mode` で合わせたときの、画像を置く位置と大きさ
+    ///
+    /// `fit` は収まる大きさまで縮小して（拡大はしない）中央に置き、余白を残す。
+    /// `fill` はキャンバスを覆う大きさに拡大・縮小し、はみ出した部分を `gravity` の側を残して切り取る。
+    /// `stretch` は縦横比を無視してキャンバスと同じ大きさにする。
+    pub fn place_in(&self, target: &Resolution, mode: ScalingMode, gravity: Gravity) -> Placement {
+        match mode {
+            ScalingMode::Fit => {
+                let size = if self.fits_in(target) {
+                    *self
+                } else {
+                    self.scale_to_fit(target.width, target.height)
+                };
+                let padding = size.pad_to(target);
+                Placement {
+                    offset_x: padding.left as i32,
+                    offset_y: padding.top as i32,
+                    width: size.width,
+                    height: size.height,
+                }
+            }
+            ScalingMode::Fill => {
+                let size = self.scale_to_cover(target.width, target.height);
+                let excess_x = (size.width - target.width) as i32;
+                let excess_y = (size.height - target.height) as i32;
+                Placement {
+                    offset_x: match gravity {
+                        Gravity::Left => 0,
+                        Gravity::Right => -excess_x,
+                        _ => -excess_x / 2,
+                    },
+                    offset_y: match gravity {
+                        Gravity::Top => 0,
+                        Gravity::Bottom => -excess_y,
+                        _ => -excess_y / 2,
+                    },
+                    width: size.width,
+                    height: size.height,
+                }
+            }
+            ScalingMode::Stretch => Placement {
+                offset_x: 0,
+                offset_y: 0,
+                width: target.width,
+                height: target.height,
+            },
+        }
+    }
+
     /// 解像度をパディングして指定サイズに合わせる
     pub fn pad_to(&self, target: &Resolution) -> PaddingInfo {
         let x_padding = if target.width > self.width {
@@ -440,6 +506,125 @@ impl PaddingInfo {
     }
 }
 
+/// 縦横比がキャンバスと違う画像をキャンバスに合わせる方法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScalingMode {
+    /// 全体が収まるよう縮小し、余白を残す（拡大はしない）
+    #[default]
+    Fit,
+    /// キャンバスを覆うよう拡大・縮小し、はみ出した部分を切り取る
+    Fill,
+    /// 縦横比を無視してキャンバスの大きさにする
+    Stretch,
+}
+
+/// `fill` で切り取るときに残す側
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Gravity {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+/// キャンバス上で画像を置いた位置と、拡大・縮小後の大きさ
+///
+/// `fill` では位置が負になり、キャンバスの外にはみ出した部分は切り取られる。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Placement {
+    pub offset_x: i32,
+    pub offset_y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// 変換済みキャンバスに適用する変形
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CanvasTransform {
+    /// 左右反転
+    FlipHorizontal,
+    /// 上下反転
+    FlipVertical,
+    /// 指定値以上の明るさのドットを透明にし、残りを黒で描画
+    Threshold { value: u8 },
+}
+
+/// 線画の1本の線（スティック描画では、ペンを押したまま座標の順に辿る）
+///
+/// 座標はアートワークのキャンバス上の位置。1点だけの線はその位置に点を打つ。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct Polyline {
+    pub points: Vec<Coordinates>,
+}
+
+/// 1つのアートワークに保存できる線画の座標の合計の上限
+pub const MAX_VECTOR_POINTS: usize = 10_000;
+
+impl Polyline {
+    pub fn new(points: Vec<Coordinates>) -> Self {
+        Self { points }
+    }
+
+    /// 線の長さ（ピクセル）
+    pub fn length(&self) -> f64 {
+        self.points
+            .windows(2)
+            .map(|segment| segment[0].distance_to(&segment[1]))
+            .sum()
+    }
+
+    /// 線画がキャンバスに収まり、上限を超えないか検証する
+    pub fn validate_all(
+        paths: &[Polyline],
+        width: u16,
+        height: u16,
+    ) -> Result<(), VectorPathError> {
+        let total: usize = paths.iter().map(|path| path.points.len()).sum();
+        if total > MAX_VECTOR_POINTS {
+            return Err(VectorPathError::TooManyPoints(total));
+        }
+        for (index, path) in paths.iter().enumerate() {
+            if path.points.is_empty() {
+                return Err(VectorPathError::EmptyPath(index));
+            }
+            if let Some(point) = path
+                .points
+                .iter()
+                .find(|point| !point.is_within_bounds(width, height))
+            {
+                return Err(VectorPathError::OutOfBounds {
+                    index,
+                    point: *point,
+                    width,
+                    height,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 線画の検証エラー
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum VectorPathError {
+    #[error("Vector path {0} has no points")]
+    EmptyPath(usize),
+    #[error("Point {point} of vector path {index} is outside the {width}x{height} canvas")]
+    OutOfBounds {
+        index: usize,
+        point: Coordinates,
+        width: u16,
+        height: u16,
+    },
+    #[error("Vector paths have {0} points in total (at most {MAX_VECTOR_POINTS} are allowed)")]
+    TooManyPoints(usize),
+}
+
 /// 画像変換パラメータ
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversionParameters {
@@ -450,12 +635,17 @@ pub struct ConversionParameters {
     pub background_color: Option<crate::domain::shared::value_objects::Color>,
     pub dithering: bool,
     pub color_reduction: Option<ColorReduction>,
+    /// 暗い部分を背景とし、明るい部分を描画する（`None` の場合は多い方を背景とみなす）
+    pub invert_background: Option<bool>,
     // 画像調整パラメータ
     pub adjustments: ImageAdjustments,
+    /// 透明・半透明の画素の扱い
+    pub transparency: Transparency,
 }
 
-/// 画像調整パラメータ
+/// 画像調整パラメータ（省略した項目は既定値）
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ImageAdjustments {
     /// 露出補正 (-2.0 ~ +2.0, 0.0 = 変更なし)
     pub exposure: f32,
@@ -597,6 +787,87 @@ impl ImageAdjustments {
     }
 }
 
+/// 輪郭だけを描く変換の設定
+///
+/// 塗りつぶした2値化の代わりに、濃淡の変化が大きい画素の暗い側だけをドットにする。
+/// 線画のスケッチとして描くと、塗りつぶしよりドット数が大幅に少なくなる。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EdgeDetection {
+    /// 輪郭とみなすSobelフィルタの勾配の大きさ（0 ~ 255）
+    pub threshold: u8,
+    /// 輪郭を1ドット幅まで細線化するか
+    pub thinning: bool,
+}
+
+impl Default for EdgeDetection {
+    fn default() -> Self {
+        Self {
+            threshold: 128,
+            thinning: false,
+        }
+    }
+}
+
+/// 不透明度が下限以上の半透明の画素の色の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransparencyMode {
+    /// 元の色のまま使う
+    #[default]
+    Skip,
+    /// 白い背景に重ねた色にする
+    CompositeWhite,
+    /// 黒い背景に重ねた色にする
+    CompositeBlack,
+}
+
+/// 透明・半透明の画素の扱い
+///
+/// 不透明度が `alpha_cutoff` 未満の画素は、色やモードに関わらず描画しない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Transparency {
+    pub mode: TransparencyMode,
+    /// 描画しうる画素の不透明度の下限（1 ~ 255）
+    pub alpha_cutoff: u8,
+}
+
+impl Default for Transparency {
+    fn default() -> Self {
+        Self {
+            mode: TransparencyMode::Skip,
+            alpha_cutoff: 128,
+        }
+    }
+}
+
+impl Transparency {
+    /// 下限が0の場合は、完全に透明な画素まで描画してしまうためエラーにする
+    pub fn new(mode: TransparencyMode, alpha_cutoff: u8) -> Result<Self, ConversionError> {
+        if alpha_cutoff == 0 {
+            return Err(ConversionError::InvalidAlphaCutoff);
+        }
+        Ok(Self { mode, alpha_cutoff })
+    }
+
+    /// 描画しない画素か
+    pub fn skips(&self, pixel: &Color) -> bool {
+        pixel.a < self.alpha_cutoff
+    }
+
+    /// 半透明の画素をモードの背景に重ねた色（不透明度は元の値のまま残す）
+    pub fn composite(&self, pixel: &Color) -> Color {
+        let background = match self.mode {
+            TransparencyMode::Skip => return *pixel,
+            TransparencyMode::CompositeWhite => 255u32,
+            TransparencyMode::CompositeBlack => 0,
+        };
+        let alpha = pixel.a as u32;
+        let blend =
+            |channel: u8| ((channel as u32 * alpha + background * (255 - alpha) + 127) / 255) as u8;
+        Color::new(blend(pixel.r), blend(pixel.g), blend(pixel.b), pixel.a)
+    }
+}
+
 impl ConversionParameters {
     /// 新しい変換パラメータを作成
     pub fn new(target_format: ImageFormat, target_resolution: Resolution) -> Self {
@@ -608,7 +879,9 @@ impl ConversionParameters {
             background_color: None,
             dithering: false,
             color_reduction: None,
+            invert_background: None,
             adjustments: ImageAdjustments::default(),
+            transparency: Transparency::default(),
         }
     }
 
@@ -622,7 +895,9 @@ impl ConversionParameters {
             background_color: Some(crate::domain::shared::value_objects::Color::white()),
             dithering: true,
             color_reduction: Some(ColorReduction::Palette(16)),
+            invert_background: None,
             adjustments: ImageAdjustments::splatoon3_recommended(),
+            transparency: Transparency::default(),
         }
     }
 
@@ -659,6 +934,18 @@ impl ConversionParameters {
         self
     }
 
+    /// 背景の反転を設定
+    pub fn with_invert_background(mut self, invert: bool) -> Self {
+        self.invert_background = Some(invert);
+        self
+    }
+
+    /// 透明・半透明の画素の扱いを設定
+    pub fn with_transparency(mut self, transparency: Transparency) -> Self {
+        self.transparency = transparency;
+        self
+    }
+
     /// 画像調整を設定
     pub fn with_adjustments(mut self, adjustments: ImageAdjustments) -> Self {
         self.adjustments = adjustments;
@@ -746,6 +1033,86 @@ pub enum ColorReduction {
     Grayscale,
     /// 2値化（白黒）
     Binary(u8), // 閾値
+    /// 組織的ディザリングによる点描（黒ドットの密度で中間調を表現）
+    Stipple { matrix: OrderedMatrixSize },
+}
+
+/// 組織的ディザリングに使うBayer行列のサイズ
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OrderedMatrixSize {
+    /// 2x2（5階調）
+    TwoByTwo,
+    /// 4x4（17階調）
+    FourByFour,
+}
+
+impl OrderedMatrixSize {
+    const BAYER_2X2: [[u8; 2]; 2] = [[0, 2], [3, 1]];
+    const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+    /// 行列の一辺のセル数
+    pub fn size(&self) -> u16 {
+        match self {
+            OrderedMatrixSize::TwoByTwo => 2,
+            OrderedMatrixSize::FourByFour => 4,
+        }
+    }
+
+    /// 指定座標の閾値（グレースケール値がこれ未満なら黒ドット）
+    ///
+    /// 各セルの閾値は `(行列値 + 0.5) * 256 / セル数` で、階調が均等に分かれる
+    pub fn threshold_at(&self, coordinates: Coordinates) -> u8 {
+        let (cell, cells) = match self {
+            OrderedMatrixSize::TwoByTwo => (
+                Self::BAYER_2X2[(coordinates.y % 2) as usize][(coordinates.x % 2) as usize],
+                4u16,
+            ),
+            OrderedMatrixSize::FourByFour => (
+                Self::BAYER_4X4[(coordinates.y % 4) as usize][(coordinates.x % 4) as usize],
+                16u16,
+            ),
+        };
+        ((cell as u16 * 256 + 128) / cells) as u8
+    }
+}
+
+/// RGBAの画素列（行順）を持つ画像
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RgbaImage {
+    /// 画素列の長さが幅と高さに合わなければ、足りない分を透明で埋める（余分は捨てる）
+    pub fn new(width: u32, height: u32, mut pixels: Vec<u8>) -> Self {
+        pixels.resize(width as usize * height as usize * 4, 0);
+        Self {
+            width,
+            height,
+            pixels,
+        }
+    }
+
+    /// 指定座標の画素
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        let pixel = &self.pixels[index..index + 4];
+        Color::new(pixel[0], pixel[1], pixel[2], pixel[3])
+    }
+
+    /// 画素ごとに色を変換した画像を作る
+    pub fn map_pixels(&self, mut convert: impl FnMut(u32, u32, Color) -> Color) -> Self {
+        let mut pixels = Vec::with_capacity(self.pixels.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let color = convert(x, y, self.pixel(x, y));
+                pixels.extend_from_slice(&[color.r, color.g, color.b, color.a]);
+            }
+        }
+        Self::new(self.width, self.height, pixels)
+    }
 }
 
 /// 変換エラー
@@ -759,12 +1126,44 @@ pub enum ConversionError {
     InvalidColorCount,
     #[error("Invalid adjustments: {0}")]
     InvalidAdjustments(String),
+    #[error("alpha_cutoff must be between 1 and 255")]
+    InvalidAlphaCutoff,
+    #[error("Conversion did not finish in time (stopped before the {0} stage)")]
+    TimedOut(&'static str),
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_vector_paths_validation() {
+        let path = |points: &[(u16, u16)]| {
+            Polyline::new(
+                points
+                    .iter()
+                    .map(|&(x, y)| Coordinates::new(x, y))
+                    .collect(),
+            )
+        };
+        let paths = vec![path(&[(0, 0), (3, 4), (3, 0)]), path(&[(9, 9)])];
+        assert_eq!(paths[0].length(), 9.0);
+        assert_eq!(Polyline::validate_all(&paths, 10, 10), Ok(()));
+        assert!(matches!(
+            Polyline::validate_all(&paths, 9, 10),
+            Err(VectorPathError::OutOfBounds { index: 1, .. })
+        ));
+        assert_eq!(
+            Polyline::validate_all(&[path(&[])], 10, 10),
+            Err(VectorPathError::EmptyPath(0))
+        );
+        let long = Polyline::new(vec![Coordinates::origin(); MAX_VECTOR_POINTS + 1]);
+        assert!(matches!(
+            Polyline::validate_all(&[long], 10, 10),
+            Err(VectorPathError::TooManyPoints(_))
+        ));
+    }
+
     #[test]
     fn test_image_format() {
         assert_eq!(ImageFormat::from_extension("png"), Some(ImageFormat::Png));
@@ -798,6 +1197,55 @@ mod tests {
         assert!(fitted.height <= 600);
     }
 
+    #[test]
+    fn test_placement_on_canvas() {
+        let canvas = Resolution::splatoon3_standard();
+        let portrait = Resolution::new(100, 400).unwrap();
+        let placement = |resolution: Resolution, mode, gravity| {
+            let placement = resolution.place_in(&canvas, mode, gravity);
+            (
+                placement.offset_x,
+                placement.offset_y,
+                placement.width,
+                placement.height,
+            )
+        };
+
+        assert_eq!(
+            placement(portrait, ScalingMode::Fit, Gravity::Center),
+            (145, 0, 30, 120)
+        );
+        assert_eq!(
+            placement(portrait, ScalingMode::Fill, Gravity::Center),
+            (0, -580, 320, 1280)
+        );
+        assert_eq!(
+            placement(portrait, ScalingMode::Fill, Gravity::Bottom),
+            (0, -1160, 320, 1280)
+        );
+        assert_eq!(
+            placement(portrait, ScalingMode::Stretch, Gravity::Top),
+            (0, 0, 320, 120)
+        );
+        // 収まる画像は拡大せず中央に置く
+        assert_eq!(
+            placement(
+                Resolution::new(4, 1).unwrap(),
+                ScalingMode::Fit,
+                Gravity::Left
+            ),
+            (158, 59, 4, 1)
+        );
+        assert_eq!(
+            placement(
+                Resolution::new(800, 50).unwrap(),
+                ScalingMode::Fill,
+                Gravity::Right
+            ),
+            (-1600, 0, 1920, 120)
+        );
+    }
+
     #[test]
     fn test_resolution_parsing() {
         let res = "1920x1080".parse::<Resolution>().unwrap();
@@ -1,5 +1,7 @@
 use super::ControllerCommand;
 use crate::domain::hardware::errors::HardwareError;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
 
 /// コントローラーエミュレーターのトレイト
 pub trait ControllerEmulator: Send + Sync {
@@ -12,6 +14,37 @@ pub trait ControllerEmulator: Send + Sync {
     /// コントローラーコマンドを実行
     fn execute_command(&self, command: &ControllerCommand) -> Result<(), HardwareError>;
 
+    /// `cancel` が立ったら途中で打ち切れるようにコントローラーコマンドを実行
+    ///
+    /// 打ち切った場合はすべての入力を離した状態を送ってから `Ok(())` を返す。
+    /// 中断に対応しない実装では最後まで `execute_command` を実行する。
+    fn execute_command_cancellable(
+        &self,
+        command: &ControllerCommand,
+        cancel: &AtomicBool,
+    ) -> Result<(), HardwareError> {
+        let _ = cancel;
+        self.execute_command(command)
+    }
+
+    /// 組み立て済みのレポートをそのまま1回書き込み、書き込んだバイト数を返す（プロトコルの調査用）
+    ///
+    /// コントローラーの入力状態は変えないため、次のコマンドで通常のレポートに戻る。
+    fn write_raw_report(&self, report: &[u8]) -> Result<usize, HardwareError>;
+
     /// エミュレーターをシャットダウン
     fn shutdown(&self) -> Result<(), HardwareError>;
+
+    /// ホストがレポートを受け取らなくなってからの経過時間
+    ///
+    /// 直近のレポート送信が続けて失敗している間（Switchのスリープなど）は `Some`、
+    /// 送信できていれば `None`。検出に対応しない実装では常に `None`。
+    fn host_unresponsive_for(&self) -> Option<Duration> {
+        None
+    }
+
+    /// 最後に送信したHIDレポート（`HidReport::to_bytes` の形式、未送信や非対応の場合は `None`）
+    fn last_report(&self) -> Option<[u8; 8]> {
+        None
+    }
 }
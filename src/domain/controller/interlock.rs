@@ -0,0 +1,130 @@
+//! コントローラー出力の安全装置
+//!
+//! ガジェットは常にSwitchにつながっているため、対戦中にWebUIで描画やキャリブレーションを
+//! 誤って押すと入力が送られてしまう。明示的にアームした間だけ出力を許可する。
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// アームの状態（複製してもすべてのハンドルで同じ状態を共有する）
+#[derive(Debug, Clone, Default)]
+pub struct ControllerInterlock {
+    arming: Arc<Mutex<Option<Arming>>>,
+}
+
+#[derive(Debug, Clone)]
+struct Arming {
+    armed_by: String,
+    expires_at: Option<Instant>,
+}
+
+/// ある時点でのアームの状態
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InterlockState {
+    Disarmed,
+    Armed {
+        /// アームした操作者（認証が有効ならトークンの識別子）
+        armed_by: String,
+        /// 自動で解除されるまでの残り時間（`None` なら解除されるまで有効）
+        remaining: Option<Duration>,
+    },
+}
+
+impl ControllerInterlock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 出力を許可する（`timeout` を過ぎると自動で解除される）
+    pub fn arm(&self, armed_by: impl Into<String>, timeout: Option<Duration>) {
+        *self.lock() = Some(Arming {
+            armed_by: armed_by.into(),
+            expires_at: timeout.map(|timeout| Instant::now() + timeout),
+        });
+    }
+
+    /// 出力を禁止する（アームされていた場合は `true`）
+    pub fn disarm(&self) -> bool {
+        let was_armed = self.is_armed();
+        *self.lock() = None;
+        was_armed
+    }
+
+    pub fn state(&self) -> InterlockState {
+        self.state_at(Instant::now())
+    }
+
+    pub fn is_armed(&self) -> bool {
+        self.state() != InterlockState::Disarmed
+    }
+
+    /// 期限切れのアームは解除済みとして扱う
+    ///
+    /// 期限は出力を始めるときに確認するもので、実行中の描画は期限が来ても止めない。
+    fn state_at(&self, now: Instant) -> InterlockState {
+        let mut arming = self.lock();
+        match arming.as_ref() {
+            Some(Arming {
+                expires_at: Some(expires_at),
+                ..
+            }) if *expires_at <= now => {
+                *arming = None;
+                InterlockState::Disarmed
+            }
+            Some(Arming {
+                armed_by,
+                expires_at,
+            }) => InterlockState::Armed {
+                armed_by: armed_by.clone(),
+                remaining: expires_at.map(|expires_at| expires_at - now),
+            },
+            None => InterlockState::Disarmed,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Option<Arming>> {
+        self.arming.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arm_expires_after_timeout() {
+        let interlock = ControllerInterlock::new();
+        assert_eq!(interlock.state(), InterlockState::Disarmed);
+
+        interlock.arm("token 1a2b3c4d", Some(Duration::from_secs(60)));
+        let now = Instant::now();
+        match interlock.state_at(now) {
+            InterlockState::Armed {
+                armed_by,
+                remaining: Some(remaining),
+            } => {
+                assert_eq!(armed_by, "token 1a2b3c4d");
+                assert!(remaining <= Duration::from_secs(60));
+            }
+            state => panic!("unexpected state: {state:?}"),
+        }
+
+        assert_eq!(
+            interlock.state_at(now + Duration::from_secs(61)),
+            InterlockState::Disarmed
+        );
+        // 一度期限切れになったら時刻が戻っても解除されたまま
+        assert!(!interlock.is_armed());
+    }
+
+    #[test]
+    fn test_disarm_is_shared_between_clones() {
+        let interlock = ControllerInterlock::new();
+        let handle = interlock.clone();
+        interlock.arm("cli", None);
+        assert!(handle.is_armed());
+        assert!(handle.disarm());
+        assert!(!interlock.is_armed());
+        assert!(!interlock.disarm());
+    }
+}
@@ -1,7 +1,13 @@
+use super::ControllerError;
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// コントローラーのボタン
+///
+/// APIでは `api_name` の小文字の名前で表す（名前は安定したAPIとして変更しない）。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Button {
     value: u16,
 }
@@ -22,6 +28,24 @@ impl Button {
     pub const HOME: Button = Button { value: 0x1000 };
     pub const CAPTURE: Button = Button { value: 0x2000 };
 
+    /// 名前を持つすべてのボタン（`GET /api/controller/capabilities` の順）
+    pub const ALL: [Button; 14] = [
+        Self::A,
+        Self::B,
+        Self::X,
+        Self::Y,
+        Self::L,
+        Self::R,
+        Self::ZL,
+        Self::ZR,
+        Self::PLUS,
+        Self::MINUS,
+        Self::HOME,
+        Self::CAPTURE,
+        Self::L_STICK,
+        Self::R_STICK,
+    ];
+
     pub fn new(value: u16) -> Self {
         Self { value }
     }
@@ -29,9 +53,72 @@ impl Button {
     pub fn value(&self) -> u16 {
         self.value
     }
+
+    /// ボタン名（`A`、`ZL`、`PLUS`、`L_STICK` など、大文字小文字は区別しない）から取得
+    pub fn from_name(name: &str) -> Option<Self> {
+        let button = match name.to_ascii_uppercase().replace('-', "_").as_str() {
+            "Y" => Self::Y,
+            "B" => Self::B,
+            "A" => Self::A,
+            "X" => Self::X,
+            "L" => Self::L,
+            "R" => Self::R,
+            "ZL" => Self::ZL,
+            "ZR" => Self::ZR,
+            "MINUS" => Self::MINUS,
+            "PLUS" => Self::PLUS,
+            "L_STICK" | "LSTICK" => Self::L_STICK,
+            "R_STICK" | "RSTICK" => Self::R_STICK,
+            "HOME" => Self::HOME,
+            "CAPTURE" => Self::CAPTURE,
+            _ => return None,
+        };
+        Some(button)
+    }
+
+    /// APIで使う名前（複数のボタンを組み合わせた値などは `None`）
+    pub fn api_name(&self) -> Option<&'static str> {
+        let name = match *self {
+            Self::A => "a",
+            Self::B => "b",
+            Self::X => "x",
+            Self::Y => "y",
+            Self::L => "l",
+            Self::R => "r",
+            Self::ZL => "zl",
+            Self::ZR => "zr",
+            Self::PLUS => "plus",
+            Self::MINUS => "minus",
+            Self::HOME => "home",
+            Self::CAPTURE => "capture",
+            Self::L_STICK => "l_stick",
+            Self::R_STICK => "r_stick",
+            _ => return None,
+        };
+        Some(name)
+    }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+impl fmt::Display for Button {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.api_name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "0x{:04x}", self.value),
+        }
+    }
+}
+
+impl FromStr for Button {
+    type Err = ControllerError;
+
+    /// `from_name` と同じく大文字小文字を区別しない
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s)
+            .ok_or_else(|| ControllerError::InvalidCommand(format!("Unknown button '{s}'")))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct DPad {
     value: u8,
 }
@@ -47,6 +134,19 @@ impl DPad {
     pub const LEFT: DPad = DPad { value: 0x06 };
     pub const UP_LEFT: DPad = DPad { value: 0x07 };
 
+    /// すべての方向とニュートラル（`GET /api/controller/capabilities` の順）
+    pub const ALL: [DPad; 9] = [
+        Self::UP,
+        Self::DOWN,
+        Self::LEFT,
+        Self::RIGHT,
+        Self::UP_LEFT,
+        Self::UP_RIGHT,
+        Self::DOWN_LEFT,
+        Self::DOWN_RIGHT,
+        Self::NEUTRAL,
+    ];
+
     pub fn new(value: u8) -> Self {
         Self { value }
     }
@@ -54,8 +154,146 @@ impl DPad {
     pub fn value(&self) -> u8 {
         self.value
     }
+
+    /// 方向名（`UP`、`DOWN_LEFT`、`NEUTRAL` など、大文字小文字は区別しない）から取得
+    pub fn from_name(name: &str) -> Option<Self> {
+        let dpad = match name.to_ascii_uppercase().replace('-', "_").as_str() {
+            "NEUTRAL" => Self::NEUTRAL,
+            "UP" => Self::UP,
+            "UP_RIGHT" => Self::UP_RIGHT,
+            "RIGHT" => Self::RIGHT,
+            "DOWN_RIGHT" => Self::DOWN_RIGHT,
+            "DOWN" => Self::DOWN,
+            "DOWN_LEFT" => Self::DOWN_LEFT,
+            "LEFT" => Self::LEFT,
+            "UP_LEFT" => Self::UP_LEFT,
+            _ => return None,
+        };
+        Some(dpad)
+    }
+
+    /// 方向名（`from_name` の逆。不明な値は `UNKNOWN`）
+    pub fn name(&self) -> &'static str {
+        match *self {
+            Self::NEUTRAL => "NEUTRAL",
+            Self::UP => "UP",
+            Self::UP_RIGHT => "UP_RIGHT",
+            Self::RIGHT => "RIGHT",
+            Self::DOWN_RIGHT => "DOWN_RIGHT",
+            Self::DOWN => "DOWN",
+            Self::DOWN_LEFT => "DOWN_LEFT",
+            Self::LEFT => "LEFT",
+            Self::UP_LEFT => "UP_LEFT",
+            _ => "UNKNOWN",
+        }
+    }
+
+    /// APIで使う名前（範囲外の値は `None`）
+    pub fn api_name(&self) -> Option<&'static str> {
+        let name = match *self {
+            Self::UP => "up",
+            Self::DOWN => "down",
+            Self::LEFT => "left",
+            Self::RIGHT => "right",
+            Self::UP_LEFT => "up_left",
+            Self::UP_RIGHT => "up_right",
+            Self::DOWN_LEFT => "down_left",
+            Self::DOWN_RIGHT => "down_right",
+            Self::NEUTRAL => "neutral",
+            _ => return None,
+        };
+        Some(name)
+    }
+
+    /// 座標の変化量の符号 (dx, dy) から方向を取得（画面座標なのでyは下向きが正。(0, 0) はニュートラル）
+    pub fn from_offset(dx: i32, dy: i32) -> Self {
+        match (dx.signum(), dy.signum()) {
+            (0, -1) => Self::UP,
+            (1, -1) => Self::UP_RIGHT,
+            (1, 0) => Self::RIGHT,
+            (1, 1) => Self::DOWN_RIGHT,
+            (0, 1) => Self::DOWN,
+            (-1, 1) => Self::DOWN_LEFT,
+            (-1, 0) => Self::LEFT,
+            (-1, -1) => Self::UP_LEFT,
+            _ => Self::NEUTRAL,
+        }
+    }
+
+    /// 1マス移動した際の座標の変化量 (dx, dy)（ニュートラルは (0, 0)）
+    pub fn offset(&self) -> (i16, i16) {
+        match *self {
+            Self::UP => (0, -1),
+            Self::UP_RIGHT => (1, -1),
+            Self::RIGHT => (1, 0),
+            Self::DOWN_RIGHT => (1, 1),
+            Self::DOWN => (0, 1),
+            Self::DOWN_LEFT => (-1, 1),
+            Self::LEFT => (-1, 0),
+            Self::UP_LEFT => (-1, -1),
+            _ => (0, 0),
+        }
+    }
+}
+
+impl fmt::Display for DPad {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.api_name() {
+            Some(name) => f.write_str(name),
+            None => write!(f, "0x{:02x}", self.value),
+        }
+    }
+}
+
+impl FromStr for DPad {
+    type Err = ControllerError;
+
+    /// `from_name` と同じく大文字小文字を区別しない
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_name(s).ok_or_else(|| {
+            ControllerError::InvalidCommand(format!("Unknown D-pad direction '{s}'"))
+        })
+    }
 }
 
+/// `api_name` の名前で読み書きするシリアライズとOpenAPIのスキーマ（名前の無い値はシリアライズできない）
+macro_rules! named_input_serde {
+    ($type:ty, $what:literal) => {
+        impl Serialize for $type {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                match self.api_name() {
+                    Some(name) => serializer.serialize_str(name),
+                    None => Err(serde::ser::Error::custom(format!(
+                        concat!($what, " {} has no API name"),
+                        self
+                    ))),
+                }
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $type {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let name = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+                name.parse().map_err(serde::de::Error::custom)
+            }
+        }
+
+        impl utoipa::PartialSchema for $type {
+            fn schema() -> utoipa::openapi::RefOr<utoipa::openapi::schema::Schema> {
+                utoipa::openapi::ObjectBuilder::new()
+                    .schema_type(utoipa::openapi::schema::Type::String)
+                    .enum_values(Some(Self::ALL.iter().filter_map(|value| value.api_name())))
+                    .into()
+            }
+        }
+
+        impl ToSchema for $type {}
+    };
+}
+
+named_input_serde!(Button, "Button");
+named_input_serde!(DPad, "D-pad value");
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct StickPosition {
     pub x: u8,
@@ -86,6 +324,22 @@ impl StickPosition {
     pub fn is_centered(&self) -> bool {
         *self == Self::CENTER
     }
+
+    /// 十字キーの方向と同じ向きに最大まで倒した位置（ニュートラルは中央）
+    pub fn from_dpad(dpad: DPad) -> Self {
+        let (x, y) = match dpad {
+            DPad::UP => (128, Self::MIN),
+            DPad::UP_RIGHT => (Self::MAX, Self::MIN),
+            DPad::RIGHT => (Self::MAX, 128),
+            DPad::DOWN_RIGHT => (Self::MAX, Self::MAX),
+            DPad::DOWN => (128, Self::MAX),
+            DPad::DOWN_LEFT => (Self::MIN, Self::MAX),
+            DPad::LEFT => (Self::MIN, 128),
+            DPad::UP_LEFT => (Self::MIN, Self::MIN),
+            _ => return Self::CENTER,
+        };
+        Self { x, y }
+    }
 }
 
 impl Default for StickPosition {
@@ -166,6 +420,11 @@ impl ButtonState {
     pub fn raw_value(&self) -> u16 {
         self.pressed
     }
+
+    /// レポートのボタンビットから作成
+    pub fn from_raw(pressed: u16) -> Self {
+        Self { pressed }
+    }
 }
 
 impl Default for ButtonState {
@@ -204,16 +463,17 @@ impl HidReport {
         }
     }
 
+    /// HIDガジェットに書き込む8バイトのレポート（Pokken Controller形式、`from_bytes` と同じ配置）
     pub fn to_bytes(&self) -> [u8; 8] {
         let button_bytes = self.buttons.raw_value().to_le_bytes();
         [
             button_bytes[0],
             button_bytes[1],
+            self.dpad.value(),
             self.left_stick.x,
             self.left_stick.y,
             self.right_stick.x,
             self.right_stick.y,
-            self.dpad.value(),
             0x00, // Vendor specific
         ]
     }
@@ -327,12 +587,8 @@ impl HidReport {
             return Err("Invalid HID report size".to_string());
         }
 
-        let buttons = ButtonState {
-            pressed: u16::from_le_bytes([bytes[0], bytes[1]]),
-        };
-
         Ok(Self {
-            buttons,
+            buttons: ButtonState::from_raw(u16::from_le_bytes([bytes[0], bytes[1]])),
             dpad: DPad::new(bytes[2]),
             left_stick: StickPosition::new(bytes[3], bytes[4]),
             right_stick: StickPosition::new(bytes[5], bytes[6]),
@@ -471,3 +727,127 @@ impl ControllerAction {
         }
     }
 }
+
+/// 手動操作の入力の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ManualInputKind {
+    Button,
+    Dpad,
+    /// 左スティック
+    Stick,
+}
+
+/// 手動操作（カーソル位置合わせなど）で送る単一の入力
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManualInput {
+    Button(Button),
+    DPad(DPad),
+    LeftStick(StickPosition),
+}
+
+impl ManualInput {
+    /// 1回の入力で押し続けられる最大時間（ミリ秒）
+    pub const MAX_DURATION_MS: u32 = 3000;
+    /// 入力後にニュートラルを送り続ける時間（ミリ秒）
+    pub const RELEASE_MS: u32 = 50;
+
+    /// 種類と名前から入力を作成（スティックは十字キーと同じ方向名で指定）
+    pub fn parse(kind: ManualInputKind, value: &str) -> Result<Self, ControllerError> {
+        let input = match kind {
+            ManualInputKind::Button => Button::from_name(value).map(Self::Button),
+            ManualInputKind::Dpad => DPad::from_name(value)
+                .filter(|dpad| *dpad != DPad::NEUTRAL)
+                .map(Self::DPad),
+            ManualInputKind::Stick => DPad::from_name(value)
+                .filter(|dpad| *dpad != DPad::NEUTRAL)
+                .map(|dpad| Self::LeftStick(StickPosition::from_dpad(dpad))),
+        };
+        input.ok_or_else(|| {
+            ControllerError::InvalidCommand(format!("Unknown {kind:?} input '{value}'"))
+        })
+    }
+
+    /// 押し続ける時間を1〜`MAX_DURATION_MS` ミリ秒に丸める
+    pub fn clamp_duration(duration_ms: u32) -> u32 {
+        duration_ms.clamp(1, Self::MAX_DURATION_MS)
+    }
+
+    /// 入力と、その解除（ボタンを離す・ニュートラルに戻す）のアクション
+    pub fn to_actions(&self, duration_ms: u32) -> [ControllerAction; 2] {
+        let duration_ms = Self::clamp_duration(duration_ms);
+        match *self {
+            Self::Button(button) => [
+                ControllerAction::press_button(button, duration_ms),
+                ControllerAction::release_button(button, Self::RELEASE_MS),
+            ],
+            Self::DPad(dpad) => [
+                ControllerAction::set_dpad(dpad, duration_ms),
+                ControllerAction::set_dpad(DPad::NEUTRAL, Self::RELEASE_MS),
+            ],
+            Self::LeftStick(position) => [
+                ControllerAction::move_left_stick(position, duration_ms),
+                ControllerAction::move_left_stick(StickPosition::CENTER, Self::RELEASE_MS),
+            ],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_button_names_round_trip() {
+        let names = [
+            "a", "b", "x", "y", "l", "r", "zl", "zr", "plus", "minus", "home", "capture",
+            "l_stick", "r_stick",
+        ];
+        assert_eq!(Button::ALL.len(), names.len());
+        for (button, name) in Button::ALL.into_iter().zip(names) {
+            let json = serde_json::to_string(&button).unwrap();
+            assert_eq!(json, format!("\"{name}\""));
+            assert_eq!(serde_json::from_str::<Button>(&json).unwrap(), button);
+            assert_eq!(button.to_string(), name);
+            assert_eq!(name.parse::<Button>().unwrap(), button);
+        }
+    }
+
+    #[test]
+    fn test_dpad_names_round_trip() {
+        let names = [
+            "up",
+            "down",
+            "left",
+            "right",
+            "up_left",
+            "up_right",
+            "down_left",
+            "down_right",
+            "neutral",
+        ];
+        assert_eq!(DPad::ALL.len(), names.len());
+        for (dpad, name) in DPad::ALL.into_iter().zip(names) {
+            let json = serde_json::to_string(&dpad).unwrap();
+            assert_eq!(json, format!("\"{name}\""));
+            assert_eq!(serde_json::from_str::<DPad>(&json).unwrap(), dpad);
+            assert_eq!(dpad.to_string(), name);
+            assert_eq!(name.parse::<DPad>().unwrap(), dpad);
+        }
+    }
+
+    #[test]
+    fn test_parsing_accepts_legacy_names_and_rejects_unknown() {
+        assert_eq!("ZL".parse::<Button>().unwrap(), Button::ZL);
+        assert_eq!("lstick".parse::<Button>().unwrap(), Button::L_STICK);
+        assert_eq!("DOWN-LEFT".parse::<DPad>().unwrap(), DPad::DOWN_LEFT);
+        assert!("turbo".parse::<Button>().is_err());
+        assert!(serde_json::from_str::<DPad>("\"sideways\"").is_err());
+        assert!(serde_json::from_str::<Button>("{\"value\":4}").is_err());
+
+        let combined = Button::new(Button::A.value | Button::B.value);
+        assert_eq!(combined.to_string(), "0x0006");
+        assert!(serde_json::to_string(&combined).is_err());
+        assert_eq!(DPad::new(0x0c).to_string(), "0x0c");
+    }
+}
@@ -4,6 +4,7 @@
 
 use crate::domain::artwork::entities::{ArtworkId, ArtworkMetadata, Canvas};
 use crate::domain::shared::events::{DomainEvent, EventId, EventMetadata};
+use crate::domain::shared::i18n::{Locale, Message, MessageKey};
 use crate::domain::shared::value_objects::{Coordinates, Timestamp};
 use serde::{Deserialize, Serialize};
 
@@ -49,6 +50,8 @@ pub enum ArtworkEvent {
         event_id: EventId,
         artwork_id: ArtworkId,
         artwork_name: String,
+        /// 完全に削除したか（偽ならゴミ箱に移しただけで元に戻せる）
+        permanent: bool,
         occurred_at: Timestamp,
         version: u32,
         event_metadata: EventMetadata,
@@ -200,10 +203,11 @@ impl ArtworkEvent {
         }
     }
 
-    /// アートワーク削除イベントを作成
+    /// アートワーク削除イベントを作成（`permanent` が偽ならゴミ箱への移動）
     pub fn artwork_deleted(
         artwork_id: ArtworkId,
         artwork_name: String,
+        permanent: bool,
         version: u32,
         event_metadata: EventMetadata,
     ) -> Self {
@@ -211,6 +215,7 @@ impl ArtworkEvent {
             event_id: EventId::generate(),
             artwork_id,
             artwork_name,
+            permanent,
             occurred_at: Timestamp::now(),
             version,
             event_metadata,
@@ -433,88 +438,81 @@ impl ArtworkEvent {
         }
     }
 
-    /// イベントのサマリーメッセージを取得
-    pub fn summary(&self) -> String {
+    /// イベントのサマリーメッセージ（言語を決める前の文言）
+    pub fn message(&self) -> Message {
+        let percent = |ratio: f64| format!("{:.1}", ratio * 100.0);
         match self {
             Self::ArtworkCreated { metadata, .. } => {
-                format!("アートワーク「{}」が作成されました", metadata.name)
+                Message::new(MessageKey::ArtworkCreated).with_arg("name", &metadata.name)
             }
             Self::ArtworkMetadataUpdated { new_metadata, .. } => {
-                format!(
-                    "アートワーク「{}」のメタデータが更新されました",
-                    new_metadata.name
-                )
+                Message::new(MessageKey::ArtworkMetadataUpdated)
+                    .with_arg("name", &new_metadata.name)
             }
             Self::ArtworkCanvasUpdated { drawable_dots, .. } => {
-                format!("キャンバスが更新されました（描画可能ドット: {drawable_dots}個）")
-            }
-            Self::ArtworkDeleted { artwork_name, .. } => {
-                format!("アートワーク「{artwork_name}」が削除されました")
+                Message::new(MessageKey::ArtworkCanvasUpdated).with_arg("dots", drawable_dots)
             }
+            Self::ArtworkDeleted {
+                artwork_name,
+                permanent,
+                ..
+            } => Message::new(if *permanent {
+                MessageKey::ArtworkDeleted
+            } else {
+                MessageKey::ArtworkTrashed
+            })
+            .with_arg("name", artwork_name),
             Self::PaintingStarted {
                 total_dots_to_paint,
                 ..
-            } => {
-                format!("描画を開始しました（{total_dots_to_paint}個のドット）")
-            }
+            } => Message::new(MessageKey::PaintingStarted).with_arg("dots", total_dots_to_paint),
             Self::DotPainted {
                 coordinates,
                 sequence_number,
                 ..
-            } => {
-                format!("ドット #{sequence_number} を座標 {coordinates} に描画しました")
-            }
+            } => Message::new(MessageKey::DotPainted)
+                .with_arg("sequence", sequence_number)
+                .with_arg("coordinates", coordinates),
             Self::PaintingPaused {
                 completion_ratio, ..
-            } => {
-                format!(
-                    "描画を一時停止しました（進捗: {:.1}%）",
-                    completion_ratio * 100.0
-                )
-            }
+            } => Message::new(MessageKey::PaintingPaused)
+                .with_arg("progress", percent(*completion_ratio)),
             Self::PaintingResumed { remaining_dots, .. } => {
-                format!("描画を再開しました（残り: {remaining_dots}個）")
+                Message::new(MessageKey::PaintingResumed).with_arg("remaining", remaining_dots)
             }
             Self::PaintingCompleted {
                 total_dots_painted,
                 painting_duration_seconds,
                 ..
-            } => {
-                format!(
-                    "描画が完了しました（{total_dots_painted}個のドット、{painting_duration_seconds}秒）"
-                )
-            }
+            } => Message::new(MessageKey::PaintingCompleted)
+                .with_arg("dots", total_dots_painted)
+                .with_arg("seconds", painting_duration_seconds),
             Self::PaintingCancelled {
                 completion_ratio,
                 reason,
                 ..
-            } => {
-                format!(
-                    "描画がキャンセルされました（進捗: {:.1}%、理由: {}）",
-                    completion_ratio * 100.0,
-                    reason
-                )
-            }
+            } => Message::new(MessageKey::PaintingCancelled)
+                .with_arg("progress", percent(*completion_ratio))
+                .with_arg("reason", reason),
             Self::PaintingErrorOccurred {
                 error_message,
                 retry_count,
                 ..
-            } => {
-                format!(
-                    "描画エラーが発生しました（リトライ: {retry_count}回、エラー: {error_message}）"
-                )
-            }
+            } => Message::new(MessageKey::PaintingErrorOccurred)
+                .with_arg("retries", retry_count)
+                .with_arg("error", error_message),
             Self::ArtworkReset {
                 previous_completion_ratio,
                 ..
-            } => {
-                format!(
-                    "アートワークがリセットされました（以前の進捗: {:.1}%）",
-                    previous_completion_ratio * 100.0
-                )
-            }
+            } => Message::new(MessageKey::ArtworkReset)
+                .with_arg("progress", percent(*previous_completion_ratio)),
         }
     }
+
+    /// イベントのサマリーメッセージを `locale` で取得
+    pub fn summary(&self, locale: Locale) -> String {
+        self.message().render(locale)
+    }
 }
 
 impl DomainEvent for ArtworkEvent {
@@ -697,6 +695,29 @@ mod tests {
         assert!(event.should_notify_user());
     }
 
+    #[test]
+    fn test_artwork_deleted_event_distinguishes_trash() {
+        let deleted = |permanent| {
+            ArtworkEvent::artwork_deleted(
+                ArtworkId::generate(),
+                "Squid".to_string(),
+                permanent,
+                1,
+                EventMetadata::new("test".to_string()),
+            )
+        };
+        assert_eq!(
+            deleted(false).summary(Locale::En),
+            "Artwork \"Squid\" was moved to the trash"
+        );
+        assert_eq!(
+            deleted(true).summary(Locale::En),
+            "Artwork \"Squid\" was deleted"
+        );
+        let json = deleted(false).as_json().unwrap();
+        assert!(json.contains("\"permanent\":false"));
+    }
+
     #[test]
     fn test_dot_painted_event() {
         let artwork_id = ArtworkId::generate();
@@ -734,9 +755,12 @@ mod tests {
         assert_eq!(event.severity(), EventSeverity::Error);
         assert!(event.should_notify_user());
 
-        let summary = event.summary();
+        let summary = event.summary(Locale::Ja);
         assert!(summary.contains("描画エラーが発生しました"));
         assert!(summary.contains("リトライ: 3回"));
+        let summary = event.summary(Locale::En);
+        assert!(summary.contains("A painting error occurred"));
+        assert!(summary.contains("retries: 3"));
     }
 
     #[test]
@@ -1,5 +1,8 @@
 use thiserror::Error;
 
+/// 送信先のエンドポイントが停止している（ESHUTDOWN）。Switchがガジェットを有効にしていない、またはスリープ中
+const ESHUTDOWN: i32 = 108;
+
 #[derive(Error, Debug)]
 pub enum HardwareError {
     #[error("Board not supported for USB OTG: {0}")]
@@ -20,8 +23,10 @@ pub enum HardwareError {
     #[error("Systemd service operation failed: {0}")]
     SystemdServiceFailed(String),
 
-    #[error("Permission denied. Root privileges required")]
-    PermissionDenied,
+    #[error(
+        "{context}: permission denied. Run with sudo, or run 'sudo splatoon3-ghost-drawer fix-permissions' for the HID device"
+    )]
+    PermissionDenied { context: String },
 
     #[error("System command failed: {0}")]
     SystemCommandFailed(String),
@@ -35,9 +40,40 @@ pub enum HardwareError {
     #[error("Device not connected")]
     NotConnected,
 
+    #[error("Host is not accepting reports (the Switch may be asleep)")]
+    HostUnresponsive,
+
+    /// ホストがエンドポイントを有効にしていない（ESHUTDOWN / ENOTCONN）
+    #[error(
+        "{context}: the Switch is not accepting input. Wake it up and open the Home screen, or reconnect the USB cable"
+    )]
+    HostNotReady { context: String },
+
+    /// USB接続が切れた（EPIPE）
+    #[error(
+        "{context}: the USB connection to the Switch was broken. Reconnect the cable or run 'sudo splatoon3-ghost-drawer fix-connection'"
+    )]
+    Disconnected { context: String },
+
+    /// ホストがレポートを取りに来ない（EAGAIN）
+    #[error(
+        "{context}: the Switch is not polling the controller (it may be asleep). Wake it up to continue"
+    )]
+    WouldBlock { context: String },
+
+    /// 分類できない入出力エラー
+    #[error("{context}: {source}. Run 'sudo splatoon3-ghost-drawer diagnose' for details")]
+    Other {
+        context: String,
+        source: std::io::Error,
+    },
+
     #[error("Device not initialized")]
     NotInitialized,
 
+    #[error("Controller output is locked; arm the controller first")]
+    NotArmed,
+
     #[error("Invalid parameter: {0}")]
     InvalidParameter(String),
 
@@ -55,4 +91,87 @@ impl HardwareError {
             HardwareError::KernelModuleNotLoaded(_) | HardwareError::SystemdServiceFailed(_)
         )
     }
+
+    /// HIDデバイスの入出力エラーを分類する（`context` には何をしていたかを書く）
+    pub fn classify_io(err: std::io::Error, context: &str) -> HardwareError {
+        let context = context.to_string();
+        match err.kind() {
+            std::io::ErrorKind::PermissionDenied => HardwareError::PermissionDenied { context },
+            std::io::ErrorKind::WouldBlock => HardwareError::WouldBlock { context },
+            std::io::ErrorKind::BrokenPipe => HardwareError::Disconnected { context },
+            std::io::ErrorKind::NotConnected => HardwareError::HostNotReady { context },
+            _ if err.raw_os_error() == Some(ESHUTDOWN) => HardwareError::HostNotReady { context },
+            _ => HardwareError::Other {
+                context,
+                source: err,
+            },
+        }
+    }
+
+    /// ホストがレポートを受け取っていないことを示すエラーか（Switchの復帰を待てば再開できる）
+    pub fn is_host_unresponsive(&self) -> bool {
+        matches!(
+            self,
+            HardwareError::HostUnresponsive
+                | HardwareError::HostNotReady { .. }
+                | HardwareError::Disconnected { .. }
+                | HardwareError::WouldBlock { .. }
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn classify(code: i32) -> HardwareError {
+        HardwareError::classify_io(
+            std::io::Error::from_raw_os_error(code),
+            "Writing HID report",
+        )
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_classify_io_maps_raw_os_errors() {
+        // ESHUTDOWN / ENOTCONN / EPIPE / EAGAIN / EACCES
+        assert!(matches!(classify(108), HardwareError::HostNotReady { .. }));
+        assert!(matches!(classify(107), HardwareError::HostNotReady { .. }));
+        assert!(matches!(classify(32), HardwareError::Disconnected { .. }));
+        assert!(matches!(classify(11), HardwareError::WouldBlock { .. }));
+        assert!(matches!(
+            classify(13),
+            HardwareError::PermissionDenied { .. }
+        ));
+        // ENOENT などはそのまま元のエラーを残す
+        let other = classify(2);
+        assert!(matches!(
+            &other,
+            HardwareError::Other { source, .. } if source.raw_os_error() == Some(2)
+        ));
+
+        for code in [108, 107, 32, 11] {
+            assert!(classify(code).is_host_unresponsive(), "{code}");
+        }
+        assert!(!classify(13).is_host_unresponsive());
+        assert!(!other.is_host_unresponsive());
+    }
+
+    #[test]
+    fn test_classified_errors_include_context_and_hint() {
+        let message = HardwareError::classify_io(
+            std::io::Error::from(std::io::ErrorKind::BrokenPipe),
+            "Writing HID report",
+        )
+        .to_string();
+        assert!(message.starts_with("Writing HID report: "), "{message}");
+        assert!(message.contains("fix-connection"), "{message}");
+
+        let message = HardwareError::classify_io(
+            std::io::Error::from(std::io::ErrorKind::PermissionDenied),
+            "Opening /dev/hidg0",
+        )
+        .to_string();
+        assert!(message.contains("sudo"), "{message}");
+    }
 }
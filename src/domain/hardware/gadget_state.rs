@@ -0,0 +1,102 @@
+//! configfsに反映されているガジェットの設定の読み出し結果
+//!
+//! Switchが認識しないときに、ソースコードではなく実際にカーネルへ書き込まれた値と
+//! 見比べられるよう、読めた値をそのまま返す。読めなかった項目は `None` にする。
+
+use base64::Engine;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+/// 現在のガジェットの設定
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, ToSchema)]
+pub struct GadgetState {
+    /// configfsにガジェットのディレクトリがあるか
+    pub configured: bool,
+    /// ガジェットのconfigfsディレクトリ
+    pub gadget_path: String,
+    pub id_vendor: Option<String>,
+    pub id_product: Option<String>,
+    pub bcd_device: Option<String>,
+    pub bcd_usb: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+    pub serial_number: Option<String>,
+    /// `configs/c.1` の構成名
+    pub configuration: Option<String>,
+    /// `configs/c.1/MaxPower`（mA）
+    pub max_power_ma: Option<u32>,
+    /// HID function の `report_length`
+    pub report_length: Option<u32>,
+    pub report_descriptor: Option<ReportDescriptorDump>,
+    /// バインドしているUDC（未バインドなら `None`）
+    pub udc: Option<String>,
+    /// `/sys/class/udc/<udc>/state`（`configured` ならホストが認識済み）
+    pub udc_state: Option<String>,
+    /// `/dev/hidg*` のデバイスファイル
+    pub device_nodes: Vec<HidDeviceNode>,
+}
+
+impl GadgetState {
+    /// ガジェットのディレクトリがない場合の結果
+    pub fn unconfigured(gadget_path: impl Into<String>) -> Self {
+        Self {
+            gadget_path: gadget_path.into(),
+            ..Self::default()
+        }
+    }
+}
+
+/// カーネルから読み戻したレポートディスクリプタ
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct ReportDescriptorDump {
+    pub length: usize,
+    /// 小文字の16進数（区切りなし）
+    pub hex: String,
+    pub base64: String,
+    /// このプログラムが書き込むディスクリプタと一致するか
+    pub matches_expected: bool,
+}
+
+impl ReportDescriptorDump {
+    pub fn new(bytes: &[u8], expected: &[u8]) -> Self {
+        Self {
+            length: bytes.len(),
+            hex: bytes.iter().map(|byte| format!("{byte:02x}")).collect(),
+            base64: base64::engine::general_purpose::STANDARD.encode(bytes),
+            matches_expected: bytes == expected,
+        }
+    }
+}
+
+/// HIDデバイスファイルの状態
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct HidDeviceNode {
+    pub path: String,
+    pub char_device: bool,
+    /// メジャー・マイナー番号（例: `236:0`）
+    pub device_number: Option<String>,
+    /// パーミッション（例: `0660`）
+    pub mode: Option<String>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_descriptor_dump() {
+        let dump = ReportDescriptorDump::new(&[0x05, 0x01, 0xc0], &[0x05, 0x01, 0xc0]);
+        assert_eq!(dump.length, 3);
+        assert_eq!(dump.hex, "0501c0");
+        assert_eq!(dump.base64, "BQHA");
+        assert!(dump.matches_expected);
+        assert!(!ReportDescriptorDump::new(&[0x05, 0x01], &[0x05, 0x01, 0xc0]).matches_expected);
+
+        let state =
+            serde_json::to_value(GadgetState::unconfigured("/sys/kernel/config/x")).unwrap();
+        assert_eq!(state["configured"], false);
+        assert!(state["report_descriptor"].is_null());
+    }
+}
@@ -0,0 +1,108 @@
+//! HIDレポートの送信の計測
+//!
+//! コントローラーは送信のたびに `ReportMetricsSink` へ結果を通知する。
+//! 集計の方法と公開先（Web APIなど）はコントローラー側では知らない。
+
+use super::HardwareError;
+use serde::Serialize;
+use std::fmt;
+use std::time::Instant;
+use utoipa::ToSchema;
+
+/// レポートの書き込みエラーの分類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportErrorClass {
+    /// ホストがレポートを取りに来ない（EAGAIN、スリープなど）
+    WouldBlock,
+    /// ホストがエンドポイントを有効にしていない（ESHUTDOWN / ENOTCONN）
+    HostNotReady,
+    /// USB接続が切れた（EPIPE）
+    Disconnected,
+    /// HIDデバイスへの書き込み権限がない
+    PermissionDenied,
+    /// デバイスが見つからない・初期化されていない
+    DeviceMissing,
+    /// その他の入出力エラー
+    Other,
+}
+
+impl ReportErrorClass {
+    pub const ALL: [ReportErrorClass; 6] = [
+        ReportErrorClass::WouldBlock,
+        ReportErrorClass::HostNotReady,
+        ReportErrorClass::Disconnected,
+        ReportErrorClass::PermissionDenied,
+        ReportErrorClass::DeviceMissing,
+        ReportErrorClass::Other,
+    ];
+
+    pub fn of(error: &HardwareError) -> Self {
+        match error {
+            HardwareError::WouldBlock { .. } | HardwareError::HostUnresponsive => Self::WouldBlock,
+            HardwareError::HostNotReady { .. } | HardwareError::NotConnected => Self::HostNotReady,
+            HardwareError::Disconnected { .. } => Self::Disconnected,
+            HardwareError::PermissionDenied { .. } => Self::PermissionDenied,
+            HardwareError::NotInitialized | HardwareError::DeviceNotFound(_) => Self::DeviceMissing,
+            _ => Self::Other,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::WouldBlock => "would_block",
+            Self::HostNotReady => "host_not_ready",
+            Self::Disconnected => "disconnected",
+            Self::PermissionDenied => "permission_denied",
+            Self::DeviceMissing => "device_missing",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl fmt::Display for ReportErrorClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// HIDレポートの送信結果の通知先
+///
+/// 送信のたびに呼ばれるため、実装はロックを長く持たずにすぐ戻ること。
+pub trait ReportMetricsSink: Send + Sync {
+    /// `bytes` バイトのレポートを `at` に書き込めた
+    fn report_sent(&self, bytes: usize, at: Instant);
+    /// レポートを書き込めなかった
+    fn report_failed(&self, class: ReportErrorClass);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_classes() {
+        let class = |error: HardwareError| ReportErrorClass::of(&error);
+        let context = || "Writing HID report".to_string();
+        assert_eq!(
+            class(HardwareError::WouldBlock { context: context() }),
+            ReportErrorClass::WouldBlock
+        );
+        assert_eq!(
+            class(HardwareError::Disconnected { context: context() }),
+            ReportErrorClass::Disconnected
+        );
+        assert_eq!(
+            class(HardwareError::NotInitialized),
+            ReportErrorClass::DeviceMissing
+        );
+        assert_eq!(
+            class(HardwareError::Unknown("?".to_string())),
+            ReportErrorClass::Other
+        );
+        assert_eq!(
+            serde_json::to_value(ReportErrorClass::HostNotReady).unwrap(),
+            "host_not_ready"
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use super::{Board, HardwareError, SystemdService, UsbGadget};
+use super::{Board, GadgetState, GadgetVerification, HardwareError, SystemdService, UsbGadget};
 use crate::domain::setup::repositories::SetupError;
 use async_trait::async_trait;
 
@@ -34,4 +34,8 @@ pub trait UsbGadgetManager: Send + Sync {
     fn configure_as_pro_controller(&self) -> Result<(), SetupError>;
     fn is_gadget_configured(&self) -> Result<bool, SetupError>;
     fn reconnect_gadget(&self) -> Result<(), SetupError>;
+    /// バインド済みのガジェットがカーネルに正しく反映されているかを確認
+    fn verify_gadget(&self) -> Result<GadgetVerification, SetupError>;
+    /// configfsとsysfsから現在のガジェットの設定を読む（未構成でもエラーにしない）
+    fn read_gadget_state(&self) -> Result<GadgetState, SetupError>;
 }
@@ -0,0 +1,233 @@
+//! ガジェットのバインド後の検証
+//!
+//! レポートディスクリプタの書き込みが途中で切れていたり `report_length` が誤っていたりすると、
+//! Switchはレポートを黙って無視する。カーネルに反映された値を読み戻して確認するための判定をまとめる。
+
+use std::fmt;
+
+/// 検証項目の判定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Passed,
+    /// 異常ではないが対応が必要な場合がある（Switch未接続など）
+    Warning,
+    Failed,
+}
+
+impl CheckStatus {
+    pub fn icon(&self) -> &'static str {
+        match self {
+            CheckStatus::Passed => "✅",
+            CheckStatus::Warning => "⚠️",
+            CheckStatus::Failed => "❌",
+        }
+    }
+}
+
+/// 1つの検証項目の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GadgetCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+impl GadgetCheck {
+    fn new(name: &'static str, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+        }
+    }
+
+    /// レポートディスクリプタを書き込んだ内容とバイト単位で比較
+    pub fn report_descriptor(expected: &[u8], actual: Option<&[u8]>) -> Self {
+        const NAME: &str = "Report descriptor";
+        let Some(actual) = actual else {
+            return Self::new(NAME, CheckStatus::Failed, "cannot read report_desc");
+        };
+        if actual == expected {
+            return Self::new(
+                NAME,
+                CheckStatus::Passed,
+                format!("{} bytes match", expected.len()),
+            );
+        }
+        let first_difference = expected
+            .iter()
+            .zip(actual)
+            .position(|(expected, actual)| expected != actual)
+            .unwrap_or(expected.len().min(actual.len()));
+        Self::new(
+            NAME,
+            CheckStatus::Failed,
+            format!(
+                "MISMATCH: wrote {} bytes, kernel has {} bytes (first difference at byte {})",
+                expected.len(),
+                actual.len(),
+                first_difference
+            ),
+        )
+    }
+
+    /// `report_length` が期待値かどうか
+    pub fn report_length(expected: usize, actual: Option<&str>) -> Self {
+        const NAME: &str = "Report length";
+        match actual.map(str::trim) {
+            None => Self::new(NAME, CheckStatus::Failed, "cannot read report_length"),
+            Some(actual) if actual.parse() == Ok(expected) => {
+                Self::new(NAME, CheckStatus::Passed, format!("{expected} bytes"))
+            }
+            Some(actual) => Self::new(
+                NAME,
+                CheckStatus::Failed,
+                format!("expected {expected} bytes, kernel has '{actual}'"),
+            ),
+        }
+    }
+
+    /// HID functionの `dev`（`major:minor`）とデバイスファイルの番号が一致するかどうか
+    pub fn device_node(device_path: &str, dev: Option<&str>, node: Option<(u32, u32)>) -> Self {
+        const NAME: &str = "Device node";
+        let Some(dev) = dev else {
+            return Self::new(NAME, CheckStatus::Failed, "cannot read dev");
+        };
+        let Some((major, minor)) = parse_device_number(dev) else {
+            return Self::new(
+                NAME,
+                CheckStatus::Failed,
+                format!("invalid dev '{}'", dev.trim()),
+            );
+        };
+        match node {
+            Some(node) if node == (major, minor) => Self::new(
+                NAME,
+                CheckStatus::Passed,
+                format!("{device_path} is {major}:{minor}"),
+            ),
+            Some((node_major, node_minor)) => Self::new(
+                NAME,
+                CheckStatus::Failed,
+                format!(
+                    "{device_path} is {node_major}:{node_minor}, but the HID function is {major}:{minor}"
+                ),
+            ),
+            None => Self::new(
+                NAME,
+                CheckStatus::Failed,
+                format!(
+                    "{device_path} is missing or not a character device (expected {major}:{minor})"
+                ),
+            ),
+        }
+    }
+
+    /// UDCの `state` が `configured`（Switchが認識済み）かどうか
+    pub fn udc_state(state: Option<&str>) -> Self {
+        const NAME: &str = "UDC state";
+        match state.map(str::trim) {
+            None => Self::new(NAME, CheckStatus::Failed, "cannot read UDC state"),
+            Some("configured") => Self::new(NAME, CheckStatus::Passed, "configured"),
+            Some(state) => Self::new(
+                NAME,
+                CheckStatus::Warning,
+                format!("{state} (connect the Switch and check again)"),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for GadgetCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}: {}", self.status.icon(), self.name, self.detail)
+    }
+}
+
+/// バインド後の検証結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GadgetVerification {
+    pub checks: Vec<GadgetCheck>,
+}
+
+impl GadgetVerification {
+    /// 失敗した項目がないかどうか（警告は含めない）
+    pub fn is_ok(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &GadgetCheck> {
+        self.checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Failed)
+    }
+}
+
+/// `major:minor` 形式のデバイス番号を解析
+pub fn parse_device_number(dev: &str) -> Option<(u32, u32)> {
+    let (major, minor) = dev.trim().split_once(':')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_descriptor_mismatch_reports_first_difference() {
+        let expected = [0x05, 0x01, 0x09, 0x05];
+
+        let check = GadgetCheck::report_descriptor(&expected, Some(&expected));
+        assert_eq!(check.status, CheckStatus::Passed);
+
+        let check = GadgetCheck::report_descriptor(&expected, Some(&[0x05, 0x01]));
+        assert_eq!(check.status, CheckStatus::Failed);
+        assert!(check.detail.contains("wrote 4 bytes, kernel has 2 bytes"));
+        assert!(check.detail.contains("byte 2"));
+
+        let check = GadgetCheck::report_descriptor(&expected, Some(&[0x05, 0x02, 0x09, 0x05]));
+        assert!(check.detail.contains("byte 1"));
+
+        let check = GadgetCheck::report_descriptor(&expected, None);
+        assert_eq!(check.status, CheckStatus::Failed);
+    }
+
+    #[test]
+    fn test_report_length_and_udc_state() {
+        assert_eq!(
+            GadgetCheck::report_length(8, Some("8\n")).status,
+            CheckStatus::Passed
+        );
+        assert_eq!(
+            GadgetCheck::report_length(8, Some("64\n")).status,
+            CheckStatus::Failed
+        );
+        assert_eq!(
+            GadgetCheck::udc_state(Some("configured\n")).status,
+            CheckStatus::Passed
+        );
+        assert_eq!(
+            GadgetCheck::udc_state(Some("not attached\n")).status,
+            CheckStatus::Warning
+        );
+    }
+
+    #[test]
+    fn test_device_node_must_match_function_dev() {
+        assert_eq!(parse_device_number("236:0\n"), Some((236, 0)));
+        assert_eq!(parse_device_number("236"), None);
+
+        let check = GadgetCheck::device_node("/dev/hidg0", Some("236:0\n"), Some((236, 0)));
+        assert_eq!(check.status, CheckStatus::Passed);
+        let check = GadgetCheck::device_node("/dev/hidg0", Some("236:0\n"), Some((237, 0)));
+        assert_eq!(check.status, CheckStatus::Failed);
+        let check = GadgetCheck::device_node("/dev/hidg0", Some("236:0\n"), None);
+        assert_eq!(check.status, CheckStatus::Failed);
+
+        let verification = GadgetVerification {
+            checks: vec![check, GadgetCheck::udc_state(Some("not attached"))],
+        };
+        assert!(!verification.is_ok());
+        assert_eq!(verification.failures().count(), 1);
+    }
+}
@@ -0,0 +1,457 @@
+use crate::domain::artwork::entities::ArtworkId;
+use crate::domain::painting::value_objects::{
+    DrawingPath, DrawingStrategy, PaintTiming, StopLimit,
+};
+use crate::domain::shared::value_objects::{Coordinates, Timestamp};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Instant;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+/// 描画の終了理由
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RunOutcome {
+    /// 全ドットを描画した
+    Completed,
+    /// 最後まで進んだが、やり直しても描画できずにスキップしたドットがある
+    CompletedWithErrors { skipped: usize },
+    /// ユーザーが停止した
+    Stopped,
+    /// `stop_after` の上限に達して、ドットの区切りで停止した
+    LimitReached { limit: StopLimit },
+    /// 描画前の確認で、印が正しい位置に描かれていないと回答された
+    PreflightDeclined,
+    /// 描画前の確認の回答が時間内に無かった
+    PreflightTimedOut,
+    /// コントローラーのエラーなどで中断した
+    Error { message: String },
+}
+
+impl RunOutcome {
+    /// 終了理由の名前（シリアライズ時の `kind` タグと同じ）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RunOutcome::Completed => "completed",
+            RunOutcome::CompletedWithErrors { .. } => "completed_with_errors",
+            RunOutcome::Stopped => "stopped",
+            RunOutcome::LimitReached { .. } => "limit_reached",
+            RunOutcome::PreflightDeclined => "preflight_declined",
+            RunOutcome::PreflightTimedOut => "preflight_timed_out",
+            RunOutcome::Error { .. } => "error",
+        }
+    }
+}
+
+/// 1回の描画実行の記録
+///
+/// タイミングは開始時の設定値。描画中に変更された場合も開始時の値を記録する。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PaintingRun {
+    pub id: String,
+    pub artwork_id: ArtworkId,
+    pub artwork_version: u32,
+    pub strategy: DrawingStrategy,
+    pub timing: PaintTiming,
+    pub repeats: u32,
+    pub started_at: Timestamp,
+    pub finished_at: Option<Timestamp>,
+    /// 描画対象のドット数
+    pub dots_attempted: usize,
+    /// 描画した経路のハッシュ（`DrawingPath::path_hash`）
+    pub path_hash: String,
+    /// 実際に描画したドット数
+    pub dots_painted: usize,
+    /// 実行中は `None`
+    pub outcome: Option<RunOutcome>,
+    /// ドットごとの結果の集計（実行中と、記録がない過去の実行は `None`）
+    #[serde(default)]
+    pub report: Option<CompletionReport>,
+}
+
+impl PaintingRun {
+    /// 描画開始時の記録を作成
+    pub fn start(
+        artwork_id: ArtworkId,
+        artwork_version: u32,
+        strategy: DrawingStrategy,
+        timing: PaintTiming,
+        repeats: u32,
+        path: &DrawingPath,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4().to_string(),
+            artwork_id,
+            artwork_version,
+            strategy,
+            timing,
+            repeats,
+            started_at: Timestamp::now(),
+            finished_at: None,
+            dots_attempted: path.coordinates.len(),
+            path_hash: path.path_hash(),
+            dots_painted: 0,
+            outcome: None,
+            report: None,
+        }
+    }
+
+    /// 終了時刻と結果を記録
+    pub fn finish(&mut self, dots_painted: usize, outcome: RunOutcome) {
+        self.finished_at = Some(Timestamp::now());
+        self.dots_painted = dots_painted;
+        self.outcome = Some(outcome);
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.outcome.is_some()
+    }
+
+    /// 実行時間（ミリ秒）。実行中は `None`
+    pub fn duration_millis(&self) -> Option<u64> {
+        self.finished_at.map(|finished_at| {
+            finished_at
+                .epoch_millis
+                .saturating_sub(self.started_at.epoch_millis)
+        })
+    }
+
+    /// 1分あたりの描画ドット数の平均。実行中または実行時間が0の場合は0
+    pub fn dots_per_minute(&self) -> f64 {
+        match self.duration_millis() {
+            Some(duration) if duration > 0 => self.dots_painted as f64 * 60_000.0 / duration as f64,
+            _ => 0.0,
+        }
+    }
+}
+
+/// 描画結果の報告に含める、スキップしたドットの最大数
+pub const MAX_REPORTED_SKIPPED_DOTS: usize = 50;
+
+/// 1ドットの描画結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DotOutcome {
+    /// 1回目で描画できた
+    Painted,
+    /// やり直して描画できた（`attempts` は描画できた回を含む試行回数）
+    Retried { attempts: u32 },
+    /// やり直しても描画できなかった
+    Skipped { attempts: u32, error: String },
+}
+
+/// 描画できずにスキップしたドット
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SkippedDot {
+    pub x: u16,
+    pub y: u16,
+    /// 最後の試行のエラー
+    pub error: String,
+}
+
+/// 描画終了時のドットごとの結果の集計
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct CompletionReport {
+    /// 描画対象のドット数
+    pub total_dots: usize,
+    /// 描画できたドット数（やり直して描画できたドットを含む）
+    pub succeeded: usize,
+    /// やり直して描画できたドット数
+    pub retried: usize,
+    /// スキップしたドット数
+    pub skipped: usize,
+    /// スキップしたドット（先頭から最大 `MAX_REPORTED_SKIPPED_DOTS` 件）
+    pub skipped_dots: Vec<SkippedDot>,
+    /// 全ドットのやり直しの合計回数
+    pub total_retries: u64,
+    /// 描画開始からの経過時間（ミリ秒）
+    pub duration_ms: u64,
+    /// `duration_ms` のうち一時停止して待機していた時間（ミリ秒）
+    #[serde(default)]
+    pub paused_ms: u64,
+    /// `stop_after` の上限に達して停止した場合の上限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit_reached: Option<StopLimit>,
+    /// 描画前の確認で中止した場合の理由
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preflight_rejected: Option<PreflightRejection>,
+    /// 描画対象の最初の行から続けて全ドットを描き終えた最後の行（アートワークの座標）
+    ///
+    /// 次の描画の `from_row` にこの値 + 1 を指定すると、残りの行から描き直せる
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_completed_row: Option<u16>,
+}
+
+/// 描画前の確認で描画を中止した理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PreflightRejection {
+    /// 印が正しい位置に描かれていないと回答された
+    Declined,
+    /// 時間内に回答が無かった
+    TimedOut,
+}
+
+impl CompletionReport {
+    /// 描画対象のすべてのドットについて結果が出たか（停止・中断した場合は `false`）
+    pub fn is_complete(&self) -> bool {
+        self.succeeded + self.skipped >= self.total_dots
+    }
+
+    /// 報告から描画の終了理由を決める（エラーで中断した場合は呼び出し側で `Error` にする）
+    pub fn outcome(&self) -> RunOutcome {
+        match (self.is_complete(), self.skipped) {
+            (false, _) => match (self.preflight_rejected, self.limit_reached) {
+                (Some(PreflightRejection::Declined), _) => RunOutcome::PreflightDeclined,
+                (Some(PreflightRejection::TimedOut), _) => RunOutcome::PreflightTimedOut,
+                (None, Some(limit)) => RunOutcome::LimitReached { limit },
+                (None, None) => RunOutcome::Stopped,
+            },
+            (true, 0) => RunOutcome::Completed,
+            (true, skipped) => RunOutcome::CompletedWithErrors { skipped },
+        }
+    }
+
+    /// 残りのドットを描き終えるまでの推定時間（秒、それまでの速さから計算。まだ1ドットも終えていなければ `None`）
+    pub fn eta_seconds(&self) -> Option<f64> {
+        let finished = self.succeeded + self.skipped;
+        (finished > 0).then(|| {
+            let remaining = self.total_dots.saturating_sub(finished);
+            self.duration_ms as f64 / 1000.0 / finished as f64 * remaining as f64
+        })
+    }
+
+    /// ログ用の1行の要約
+    pub fn summary(&self) -> String {
+        format!(
+            "{}/{} dots painted, {} skipped, {} retries ({} dots retried), {:.1}s",
+            self.succeeded,
+            self.total_dots,
+            self.skipped,
+            self.total_retries,
+            self.retried,
+            self.duration_ms as f64 / 1000.0
+        )
+    }
+}
+
+/// 描画中のドットごとの結果を記録する
+#[derive(Debug, Clone)]
+pub struct CompletionTracker {
+    total_dots: usize,
+    started_at: Instant,
+    finished_at: Option<Instant>,
+    painted: Vec<Coordinates>,
+    retried: usize,
+    skipped: usize,
+    skipped_dots: Vec<SkippedDot>,
+    total_retries: u64,
+    limit_reached: Option<StopLimit>,
+    preflight_rejected: Option<PreflightRejection>,
+    paused: std::time::Duration,
+    /// アートワークの行ごとの、まだ描画できていないドット数
+    rows_remaining: BTreeMap<u16, usize>,
+}
+
+impl CompletionTracker {
+    pub fn new(total_dots: usize) -> Self {
+        Self {
+            total_dots,
+            started_at: Instant::now(),
+            finished_at: None,
+            painted: Vec::with_capacity(total_dots),
+            retried: 0,
+            skipped: 0,
+            skipped_dots: Vec::new(),
+            total_retries: 0,
+            limit_reached: None,
+            preflight_rejected: None,
+            paused: std::time::Duration::ZERO,
+            rows_remaining: BTreeMap::new(),
+        }
+    }
+
+    /// 行ごとのドット数（`DrawingPath::row_dot_counts`）を渡して、描き終えた行を追跡する
+    pub fn with_rows(mut self, rows: BTreeMap<u16, usize>) -> Self {
+        self.rows_remaining = rows;
+        self
+    }
+
+    pub fn record(&mut self, coordinates: Coordinates, outcome: DotOutcome) {
+        if !matches!(outcome, DotOutcome::Skipped { .. })
+            && let Some(remaining) = self.rows_remaining.get_mut(&coordinates.y)
+        {
+            *remaining = remaining.saturating_sub(1);
+        }
+        match outcome {
+            DotOutcome::Painted => self.painted.push(coordinates),
+            DotOutcome::Retried { attempts } => {
+                self.painted.push(coordinates);
+                self.retried += 1;
+                self.total_retries += attempts.saturating_sub(1) as u64;
+            }
+            DotOutcome::Skipped { attempts, error } => {
+                self.skipped += 1;
+                self.total_retries += attempts.saturating_sub(1) as u64;
+                if self.skipped_dots.len() < MAX_REPORTED_SKIPPED_DOTS {
+                    self.skipped_dots.push(SkippedDot {
+                        x: coordinates.x,
+                        y: coordinates.y,
+                        error,
+                    });
+                }
+            }
+        }
+    }
+
+    /// 描画できたドットの座標（描画した順）
+    pub fn painted_dots(&self) -> &[Coordinates] {
+        &self.painted
+    }
+
+    /// 描画開始からの経過時間（`finish` の後は変わらない）
+    pub fn elapsed(&self) -> std::time::Duration {
+        self.finished_at
+            .unwrap_or_else(Instant::now)
+            .duration_since(self.started_at)
+    }
+
+    /// 一時停止して待機していた時間を加える
+    pub fn add_paused(&mut self, paused: std::time::Duration) {
+        self.paused += paused;
+    }
+
+    /// `stop_after` の上限に達して停止することを記録する
+    pub fn stop_at_limit(&mut self, limit: StopLimit) {
+        self.limit_reached = Some(limit);
+    }
+
+    /// 描画前の確認で中止することを記録する
+    pub fn reject_preflight(&mut self, reason: PreflightRejection) {
+        self.preflight_rejected = Some(reason);
+    }
+
+    /// 最初の行から続けて全ドットを描き終えた最後の行
+    fn last_completed_row(&self) -> Option<u16> {
+        self.rows_remaining
+            .iter()
+            .take_while(|(_, remaining)| **remaining == 0)
+            .last()
+            .map(|(row, _)| *row)
+    }
+
+    /// 経過時間を確定する（2回目以降は何もしない）
+    pub fn finish(&mut self) {
+        self.finished_at.get_or_insert_with(Instant::now);
+    }
+
+    /// 現時点の集計（`finish` の後は経過時間が変わらない）
+    pub fn report(&self) -> CompletionReport {
+        CompletionReport {
+            total_dots: self.total_dots,
+            succeeded: self.painted.len(),
+            retried: self.retried,
+            skipped: self.skipped,
+            skipped_dots: self.skipped_dots.clone(),
+            total_retries: self.total_retries,
+            duration_ms: self.elapsed().as_millis() as u64,
+            paused_ms: self.paused.as_millis() as u64,
+            limit_reached: self.limit_reached,
+            preflight_rejected: self.preflight_rejected,
+            last_completed_row: self.last_completed_row(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_painting_run_lifecycle() {
+        let mut run = PaintingRun::start(
+            ArtworkId::generate(),
+            3,
+            DrawingStrategy::ZigZag,
+            PaintTiming::new(100, 60, 40),
+            2,
+            &DrawingPath::new((0..120).map(|x| Coordinates::new(x, 0)).collect()),
+        );
+        assert!(!run.is_finished());
+        assert_eq!(run.dots_attempted, 120);
+        assert_eq!(run.dots_per_minute(), 0.0);
+
+        run.finish(90, RunOutcome::Stopped);
+        // 30秒で90ドット
+        run.finished_at = Some(run.started_at.add_millis(30_000));
+
+        assert!(run.is_finished());
+        assert_eq!(run.duration_millis(), Some(30_000));
+        assert_eq!(run.dots_per_minute(), 180.0);
+    }
+
+    #[test]
+    fn test_completion_tracker_counts_retries_and_caps_skipped_dots() {
+        let mut tracker = CompletionTracker::new(MAX_REPORTED_SKIPPED_DOTS + 3);
+        tracker.record(Coordinates::new(0, 0), DotOutcome::Painted);
+        tracker.record(Coordinates::new(1, 0), DotOutcome::Retried { attempts: 3 });
+        assert_eq!(tracker.report().outcome(), RunOutcome::Stopped);
+
+        for x in 0..=MAX_REPORTED_SKIPPED_DOTS as u16 {
+            tracker.record(
+                Coordinates::new(x, 1),
+                DotOutcome::Skipped {
+                    attempts: 2,
+                    error: "write failed".to_string(),
+                },
+            );
+        }
+
+        let report = tracker.report();
+        assert!(report.is_complete());
+        assert_eq!(report.succeeded, 2);
+        assert_eq!(report.retried, 1);
+        assert_eq!(report.skipped, MAX_REPORTED_SKIPPED_DOTS + 1);
+        assert_eq!(report.skipped_dots.len(), MAX_REPORTED_SKIPPED_DOTS);
+        assert_eq!(report.skipped_dots[0].error, "write failed");
+        assert_eq!(
+            report.total_retries,
+            2 + MAX_REPORTED_SKIPPED_DOTS as u64 + 1
+        );
+        assert_eq!(
+            report.outcome(),
+            RunOutcome::CompletedWithErrors {
+                skipped: MAX_REPORTED_SKIPPED_DOTS + 1
+            }
+        );
+        assert_eq!(
+            tracker.painted_dots(),
+            &[Coordinates::new(0, 0), Coordinates::new(1, 0)]
+        );
+    }
+
+    #[test]
+    fn test_completion_tracker_reports_last_row_completed_from_the_start() {
+        let rows = BTreeMap::from([(3, 2), (4, 1), (6, 1)]);
+        let mut tracker = CompletionTracker::new(4).with_rows(rows);
+        assert_eq!(tracker.report().last_completed_row, None);
+
+        tracker.record(Coordinates::new(0, 3), DotOutcome::Painted);
+        tracker.record(Coordinates::new(0, 4), DotOutcome::Painted);
+        // 3行目が残っている間は、後の行を描き終えても数えない
+        assert_eq!(tracker.report().last_completed_row, None);
+
+        tracker.record(Coordinates::new(1, 3), DotOutcome::Retried { attempts: 2 });
+        assert_eq!(tracker.report().last_completed_row, Some(4));
+
+        // スキップしたドットの行は描き終えていない
+        tracker.record(
+            Coordinates::new(0, 6),
+            DotOutcome::Skipped {
+                attempts: 3,
+                error: "write failed".to_string(),
+            },
+        );
+        assert_eq!(tracker.report().last_completed_row, Some(4));
+    }
+}
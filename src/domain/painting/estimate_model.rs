@@ -0,0 +1,185 @@
+//! 実測に合わせて描画の見積もり時間を補正するモデル
+//!
+//! 見積もりは設定したタイミングだけから計算するため、Switchが取りこぼした入力のやり直しや
+//! ニュートラルクリアの分だけ実際の所要時間とずれる。描画を終えるたびに実測と見積もりの比を
+//! タイミング（押下・離す・待機時間）ごとに指数移動平均で記録し、以降の見積もりに掛ける。
+
+use super::value_objects::PaintTiming;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// 補正係数の下限（見積もりより大幅に速く終わった記録で見積もりを縮めすぎない）
+pub const MIN_ESTIMATE_CORRECTION: f64 = 0.8;
+/// 補正係数の上限（停止・中断が混ざった記録で見積もりを膨らませすぎない）
+pub const MAX_ESTIMATE_CORRECTION: f64 = 3.0;
+/// 指数移動平均で新しい記録に掛ける重み
+const SMOOTHING: f64 = 0.3;
+
+/// タイミングごとの補正係数
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EstimateBucket {
+    pub timing: PaintTiming,
+    /// 見積もりに掛ける係数（実測 / 見積もりの指数移動平均）
+    pub factor: f64,
+    /// 記録した描画の数
+    pub runs: u32,
+    /// 最後に記録した日時（RFC 3339）
+    pub updated_at: String,
+}
+
+/// 描画の見積もり時間の補正モデル
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EstimateModel {
+    /// タイミングごとの補正係数（記録した順）
+    pub buckets: Vec<EstimateBucket>,
+}
+
+impl EstimateModel {
+    /// タイミングの補正係数（記録が無ければ1.0、保存された値も `MIN`〜`MAX_ESTIMATE_CORRECTION` に収める）
+    pub fn correction(&self, timing: &PaintTiming) -> f64 {
+        self.buckets
+            .iter()
+            .find(|bucket| bucket.timing == *timing)
+            .map_or(1.0, |bucket| clamp_correction(bucket.factor))
+    }
+
+    /// 描画1回分の見積もりと実測の所要時間を記録し、更新後の係数を返す（どちらかが0なら記録しない）
+    pub fn record(
+        &mut self,
+        timing: PaintTiming,
+        estimated_ms: u64,
+        actual_ms: u64,
+        recorded_at: String,
+    ) -> Option<f64> {
+        if estimated_ms == 0 || actual_ms == 0 {
+            return None;
+        }
+        let ratio = clamp_correction(actual_ms as f64 / estimated_ms as f64);
+        let factor = match self
+            .buckets
+            .iter_mut()
+            .find(|bucket| bucket.timing == timing)
+        {
+            Some(bucket) => {
+                bucket.factor = clamp_correction(
+                    clamp_correction(bucket.factor) * (1.0 - SMOOTHING) + ratio * SMOOTHING,
+                );
+                bucket.runs = bucket.runs.saturating_add(1);
+                bucket.updated_at = recorded_at;
+                bucket.factor
+            }
+            // 最初の記録はそのまま係数にする
+            None => {
+                self.buckets.push(EstimateBucket {
+                    timing,
+                    factor: ratio,
+                    runs: 1,
+                    updated_at: recorded_at,
+                });
+                ratio
+            }
+        };
+        Some(factor)
+    }
+}
+
+fn clamp_correction(factor: f64) -> f64 {
+    if factor.is_finite() {
+        factor.clamp(MIN_ESTIMATE_CORRECTION, MAX_ESTIMATE_CORRECTION)
+    } else {
+        1.0
+    }
+}
+
+/// 補正前の見積もりと、それに掛ける補正係数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CorrectedEstimate {
+    /// タイミングだけから計算した見積もり（ミリ秒）
+    pub raw_ms: u64,
+    pub correction: f64,
+}
+
+impl CorrectedEstimate {
+    pub fn new(raw_ms: u64, correction: f64) -> Self {
+        Self { raw_ms, correction }
+    }
+
+    /// 補正後の見積もり（ミリ秒）
+    pub fn corrected_ms(&self) -> u64 {
+        (self.raw_ms as f64 * self.correction).round() as u64
+    }
+
+    /// `total` 件のうち `done` 件を終えた時点の残りの見積もり
+    pub fn remaining(&self, done: usize, total: usize) -> Self {
+        let remaining = total.saturating_sub(done) as f64 / total.max(1) as f64;
+        Self::new(
+            (self.raw_ms as f64 * remaining).round() as u64,
+            self.correction,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded_at() -> String {
+        "2026-01-01T00:00:00Z".to_string()
+    }
+
+    #[test]
+    fn test_record_seeds_and_smooths_the_factor_per_timing() {
+        let timing = PaintTiming::new(100, 60, 40);
+        let mut model = EstimateModel::default();
+        assert_eq!(model.correction(&timing), 1.0);
+
+        assert_eq!(model.record(timing, 1000, 1500, recorded_at()), Some(1.5));
+        assert_eq!(model.correction(&timing), 1.5);
+
+        // 1.5 * 0.7 + 1.0 * 0.3
+        let factor = model.record(timing, 1000, 1000, recorded_at()).unwrap();
+        assert!((factor - 1.35).abs() < 1e-9, "{factor}");
+        assert_eq!(model.buckets[0].runs, 2);
+
+        // 別のタイミングには影響しない
+        assert_eq!(model.correction(&PaintTiming::new(50, 50, 0)), 1.0);
+        assert_eq!(model.record(timing, 0, 1000, recorded_at()), None);
+        assert_eq!(model.buckets[0].runs, 2);
+    }
+
+    #[test]
+    fn test_corrections_stay_within_the_sane_range() {
+        let timing = PaintTiming::default();
+        let mut model = EstimateModel::default();
+        assert_eq!(
+            model.record(timing, 1000, 60_000, recorded_at()),
+            Some(MAX_ESTIMATE_CORRECTION)
+        );
+        // 極端に速い記録は下限の値として平均する
+        for _ in 0..20 {
+            model.record(timing, 1000, 1, recorded_at());
+        }
+        let correction = model.correction(&timing);
+        assert!(
+            (MIN_ESTIMATE_CORRECTION..MIN_ESTIMATE_CORRECTION + 0.01).contains(&correction),
+            "{correction}"
+        );
+
+        // 手で書き換えられた値も範囲に収める
+        model.buckets[0].factor = 10.0;
+        assert_eq!(model.correction(&timing), MAX_ESTIMATE_CORRECTION);
+        model.buckets[0].factor = f64::NAN;
+        assert_eq!(model.correction(&timing), 1.0);
+    }
+
+    #[test]
+    fn test_corrected_estimate_scales_the_remaining_time() {
+        let estimate = CorrectedEstimate::new(10_000, 1.25);
+        assert_eq!(estimate.corrected_ms(), 12_500);
+
+        let remaining = estimate.remaining(3, 4);
+        assert_eq!(remaining.raw_ms, 2_500);
+        assert_eq!(remaining.corrected_ms(), 3_125);
+        assert_eq!(estimate.remaining(5, 4).raw_ms, 0);
+    }
+}
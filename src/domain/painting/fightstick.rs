@@ -0,0 +1,362 @@
+//! Switch-Fightstick形式のコマンド列への書き出し
+//!
+//! AVR/Teensy向けのSwitch-Fightstick系ファームウェア（`joystick.c`）は、
+//! `{ 入力, フレーム数 }` を並べた `step[]` 配列を先頭から順に再生する。
+//! 実機での描画と同じ入力（初期化手順と描画パス）をこの形式に変換し、既存のハードウェアで描けるようにする。
+//!
+//! ## 入力名
+//! - ボタン: `A` `B` `X` `Y` `L` `R` `ZL` `ZR` `PLUS` `MINUS` `HOME` `CAPTURE` `LCLICK` `RCLICK`
+//! - 左スティック: `UP` `DOWN` `LEFT` `RIGHT`（斜めは2方向を同じ長さずつ順に倒す）
+//! - 十字キー: `DPAD_UP` `DPAD_DOWN` `DPAD_LEFT` `DPAD_RIGHT`（`HAT_*` を設定する分岐をファームウェアに追加する）
+//! - 何も入力しない: `NOTHING`
+//!
+//! ## ミリ秒からフレーム数への変換
+//! フレーム数は `ceil(ms / frame_ms)` で、常に切り上げる。切り捨てると短い押下が0フレームになり、
+//! ドットが抜けたりカーソルが動かなかったりするため。
+//! - ボタン・スティック・十字キーの入力は0ミリ秒でも1フレーム押す
+//! - 続けて何も入力しない区間（離す・待機・ニュートラル）は合計してから切り上げ、0フレームなら省略する
+//! - 1要素のフレーム数は `uint16_t` のため、65535フレームを超える入力は同じ入力の要素に分割する
+
+use crate::domain::controller::{ActionType, Button, ControllerAction, DPad, StickPosition};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// 1フレームの既定の長さ（ミリ秒、USBのレポート間隔）
+pub const DEFAULT_FIGHTSTICK_FRAME_MS: u32 = 8;
+/// 何も入力しない要素の入力名
+pub const FIGHTSTICK_NOTHING: &str = "NOTHING";
+
+/// 書き出す形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FightstickFormat {
+    /// `joystick.c` にそのまま貼り付けられる `step[]` 配列
+    #[default]
+    C,
+    /// `button,frames` のCSV（変換ツール向けの中間形式）
+    Csv,
+}
+
+impl FightstickFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::C => "c",
+            Self::Csv => "csv",
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::C => "text/x-c; charset=utf-8",
+            Self::Csv => "text/csv; charset=utf-8",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FightstickError {
+    #[error("Frame length must be at least 1ms")]
+    InvalidFrameLength,
+    #[error("Switch-Fightstick scripts cannot express {0}")]
+    UnsupportedInput(String),
+}
+
+/// `step[]` 配列の1要素
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FightstickStep {
+    pub input: &'static str,
+    pub frames: u16,
+}
+
+/// Switch-Fightstick形式のコマンド列
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FightstickScript {
+    pub frame_ms: u32,
+    pub steps: Vec<FightstickStep>,
+}
+
+/// ミリ秒をフレーム数に変換する（切り上げ）
+pub fn ms_to_frames(duration_ms: u64, frame_ms: u32) -> u64 {
+    duration_ms.div_ceil(frame_ms as u64)
+}
+
+impl FightstickScript {
+    /// コントローラーの入力列を変換する
+    pub fn from_actions<'a>(
+        actions: impl IntoIterator<Item = &'a ControllerAction>,
+        frame_ms: u32,
+    ) -> Result<Self, FightstickError> {
+        if frame_ms == 0 {
+            return Err(FightstickError::InvalidFrameLength);
+        }
+
+        let mut script = Self {
+            frame_ms,
+            steps: Vec::new(),
+        };
+        let mut idle_ms = 0u64;
+        for action in actions {
+            let inputs = fightstick_inputs(action)?;
+            if inputs.is_empty() {
+                idle_ms += action.duration_ms as u64;
+                continue;
+            }
+            script.push(FIGHTSTICK_NOTHING, ms_to_frames(idle_ms, frame_ms));
+            idle_ms = 0;
+            for input in inputs {
+                script.push(
+                    input,
+                    ms_to_frames(action.duration_ms as u64, frame_ms).max(1),
+                );
+            }
+        }
+        script.push(FIGHTSTICK_NOTHING, ms_to_frames(idle_ms, frame_ms));
+        Ok(script)
+    }
+
+    fn push(&mut self, input: &'static str, mut frames: u64) {
+        while frames > 0 {
+            let chunk = frames.min(u16::MAX as u64);
+            self.steps.push(FightstickStep {
+                input,
+                frames: chunk as u16,
+            });
+            frames -= chunk;
+        }
+    }
+
+    /// 全体の長さ（フレーム）
+    pub fn total_frames(&self) -> u64 {
+        self.steps.iter().map(|step| step.frames as u64).sum()
+    }
+
+    /// 指定した形式の文字列にする（`title` はC形式の先頭のコメントに使う）
+    pub fn render(&self, format: FightstickFormat, title: &str) -> String {
+        match format {
+            FightstickFormat::C => {
+                let mut output = format!(
+                    "// {title}\n// 1 frame = {}ms (durations are rounded up), {} steps, {} frames\nstatic const command step[] = {{\n",
+                    self.frame_ms,
+                    self.steps.len(),
+                    self.total_frames()
+                );
+                for step in &self.steps {
+                    output.push_str(&format!("\t{{ {}, {} }},\n", step.input, step.frames));
+                }
+                output.push_str("};\n");
+                output
+            }
+            FightstickFormat::Csv => {
+                let mut output = String::from("button,frames\n");
+                for step in &self.steps {
+                    output.push_str(&format!("{},{}\n", step.input, step.frames));
+                }
+                output
+            }
+        }
+    }
+}
+
+/// 1つの入力を要素の入力名に変換する（何も入力しない場合は空）
+fn fightstick_inputs(action: &ControllerAction) -> Result<Vec<&'static str>, FightstickError> {
+    let inputs = match action.action_type {
+        ActionType::PressButton(button) => vec![button_name(button)?],
+        ActionType::ReleaseButton(_) | ActionType::Wait => Vec::new(),
+        ActionType::SetDPad(dpad) => match dpad {
+            DPad::NEUTRAL => Vec::new(),
+            DPad::UP => vec!["DPAD_UP"],
+            DPad::DOWN => vec!["DPAD_DOWN"],
+            DPad::LEFT => vec!["DPAD_LEFT"],
+            DPad::RIGHT => vec!["DPAD_RIGHT"],
+            _ => {
+                return Err(FightstickError::UnsupportedInput(format!(
+                    "diagonal D-pad input {}",
+                    dpad.name()
+                )));
+            }
+        },
+        ActionType::MoveLeftStick(position) => stick_inputs(position)?,
+        ActionType::MoveRightStick(_) => {
+            return Err(FightstickError::UnsupportedInput(
+                "right stick input".to_string(),
+            ));
+        }
+        ActionType::SetReport(_) => {
+            return Err(FightstickError::UnsupportedInput(
+                "raw HID reports".to_string(),
+            ));
+        }
+    };
+    Ok(inputs)
+}
+
+fn stick_inputs(position: StickPosition) -> Result<Vec<&'static str>, FightstickError> {
+    let inputs = if position.is_centered() {
+        Vec::new()
+    } else if position == StickPosition::from_dpad(DPad::UP) {
+        vec!["UP"]
+    } else if position == StickPosition::from_dpad(DPad::DOWN) {
+        vec!["DOWN"]
+    } else if position == StickPosition::from_dpad(DPad::LEFT) {
+        vec!["LEFT"]
+    } else if position == StickPosition::from_dpad(DPad::RIGHT) {
+        vec!["RIGHT"]
+    } else if position == StickPosition::from_dpad(DPad::UP_LEFT) {
+        vec!["UP", "LEFT"]
+    } else if position == StickPosition::from_dpad(DPad::UP_RIGHT) {
+        vec!["UP", "RIGHT"]
+    } else if position == StickPosition::from_dpad(DPad::DOWN_LEFT) {
+        vec!["DOWN", "LEFT"]
+    } else if position == StickPosition::from_dpad(DPad::DOWN_RIGHT) {
+        vec!["DOWN", "RIGHT"]
+    } else {
+        return Err(FightstickError::UnsupportedInput(format!(
+            "partial stick position ({}, {})",
+            position.x, position.y
+        )));
+    };
+    Ok(inputs)
+}
+
+fn button_name(button: Button) -> Result<&'static str, FightstickError> {
+    let name = match button {
+        Button::A => "A",
+        Button::B => "B",
+        Button::X => "X",
+        Button::Y => "Y",
+        Button::L => "L",
+        Button::R => "R",
+        Button::ZL => "ZL",
+        Button::ZR => "ZR",
+        Button::PLUS => "PLUS",
+        Button::MINUS => "MINUS",
+        Button::HOME => "HOME",
+        Button::CAPTURE => "CAPTURE",
+        Button::L_STICK => "LCLICK",
+        Button::R_STICK => "RCLICK",
+        _ => {
+            return Err(FightstickError::UnsupportedInput(format!(
+                "button combination {button:?}"
+            )));
+        }
+    };
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::artwork::entities::{Canvas, Dot};
+    use crate::domain::painting::{
+        ArtworkToCommandConverter, DrawingCanvasConfig, DrawingStrategy, PaintTiming, RunOptions,
+    };
+    use crate::domain::shared::value_objects::Coordinates;
+
+    #[test]
+    fn test_ms_to_frames_always_rounds_up() {
+        assert_eq!(ms_to_frames(0, 8), 0);
+        assert_eq!(ms_to_frames(1, 8), 1);
+        assert_eq!(ms_to_frames(8, 8), 1);
+        assert_eq!(ms_to_frames(9, 8), 2);
+        assert_eq!(ms_to_frames(100, 8), 13);
+    }
+
+    #[test]
+    fn test_three_dot_artwork_script() {
+        let mut canvas = Canvas::new(4, 2);
+        for (x, y) in [(0, 0), (2, 0), (2, 1)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        let config = DrawingCanvasConfig::new(PaintTiming::new(100, 60, 40), RunOptions::default());
+        let converter = ArtworkToCommandConverter::new(config, DrawingStrategy::RasterScan);
+        let path = converter.create_drawing_path(&canvas);
+        let commands = converter.painting_commands(&path).unwrap();
+        let script = FightstickScript::from_actions(
+            commands.iter().flat_map(|command| &command.sequence),
+            8,
+        )
+        .unwrap();
+
+        let mut expected = vec!["button,frames"];
+        // Lを5回押してペンを小にする（押下300ms、離して200ms + 800ms）
+        for _ in 0..4 {
+            expected.extend(["L,38", "NOTHING,125"]);
+        }
+        // 最後の待機500msは続けて何も入力しない区間にまとめる
+        expected.extend(["L,38", "NOTHING,188"]);
+        // 左上に5秒ずつ倒し、戻して100ms + 500ms、最初のドットの前のニュートラル20ms
+        expected.extend(["UP,625", "LEFT,625", "NOTHING,78"]);
+        // (0, 0)
+        expected.extend(["A,13", "NOTHING,13"]);
+        // (2, 0)（移動の後は十字キーがニュートラルに戻っているため、クリアは最初のドットだけ）
+        expected.extend(["DPAD_RIGHT,13", "NOTHING,13", "DPAD_RIGHT,13", "NOTHING,13"]);
+        expected.extend(["A,13", "NOTHING,13"]);
+        // (2, 1)
+        expected.extend(["DPAD_DOWN,13", "NOTHING,13", "A,13", "NOTHING,13"]);
+        assert_eq!(
+            script
+                .render(FightstickFormat::Csv, "")
+                .lines()
+                .collect::<Vec<_>>(),
+            expected
+        );
+
+        let c = script.render(FightstickFormat::C, "squid (3 dots)");
+        let lines: Vec<_> = c.lines().collect();
+        assert_eq!(lines[0], "// squid (3 dots)");
+        assert_eq!(
+            lines[1],
+            format!(
+                "// 1 frame = 8ms (durations are rounded up), {} steps, {} frames",
+                expected.len() - 1,
+                script.total_frames()
+            )
+        );
+        assert_eq!(lines[2], "static const command step[] = {");
+        assert_eq!(lines[3], "\t{ L, 38 },");
+        assert_eq!(lines[lines.len() - 2], "\t{ NOTHING, 13 },");
+        assert_eq!(lines[lines.len() - 1], "};");
+    }
+
+    #[test]
+    fn test_long_inputs_are_split_and_zero_length_presses_kept() {
+        let actions = [
+            ControllerAction::press_button(Button::A, 0),
+            ControllerAction::wait(0),
+            ControllerAction::wait(u16::MAX as u32 + 10),
+        ];
+        let script = FightstickScript::from_actions(&actions, 1).unwrap();
+        assert_eq!(
+            script.steps,
+            vec![
+                FightstickStep {
+                    input: "A",
+                    frames: 1
+                },
+                FightstickStep {
+                    input: FIGHTSTICK_NOTHING,
+                    frames: u16::MAX
+                },
+                FightstickStep {
+                    input: FIGHTSTICK_NOTHING,
+                    frames: 10
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsupported_inputs_are_rejected() {
+        assert_eq!(
+            FightstickScript::from_actions(&[ControllerAction::wait(10)], 0),
+            Err(FightstickError::InvalidFrameLength)
+        );
+        assert!(matches!(
+            FightstickScript::from_actions(&[ControllerAction::set_dpad(DPad::UP_LEFT, 100)], 8),
+            Err(FightstickError::UnsupportedInput(_))
+        ));
+    }
+}
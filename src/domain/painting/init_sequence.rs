@@ -0,0 +1,407 @@
+//! 描画前の初期化手順
+//!
+//! ペンサイズの初期化やカーソルの左上への移動は、描画する画面（Splatoon3の投稿エディタなど）によって
+//! 必要な操作が異なるため、ボタンの連打・スティックの保持・待機を並べたデータとして扱う。
+//! 実機での描画と `ArtworkToCommandConverter` のコマンド生成は、同じ手順から入力を作る。
+
+use crate::domain::controller::{Button, ControllerAction, ControllerCommand, DPad, StickPosition};
+use crate::domain::painting::value_objects::DrawingMode;
+use crate::domain::shared::i18n::{Locale, Message, MessageKey};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// 1つの手順に含められる最大の手順数
+pub const MAX_INIT_STEPS: usize = 32;
+/// 手順全体で送れる最大の入力数（押す・離す・待機をそれぞれ1つと数える）
+pub const MAX_INIT_ACTIONS: usize = 200;
+/// 手順全体の最大の所要時間（ミリ秒）
+pub const MAX_INIT_DURATION_MS: u64 = 60_000;
+
+/// ボタンを押している時間（ミリ秒）
+const TAP_PRESS_MS: u32 = 300;
+/// ボタンを離してから次の入力までの時間（ミリ秒）
+const TAP_RELEASE_MS: u32 = 200;
+/// スティックを戻した後にニュートラルを送り続ける時間（ミリ秒）
+const STICK_RELEASE_MS: u32 = 100;
+
+/// 組み込みの初期化手順
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InitPreset {
+    /// Splatoon3の投稿エディタ（描画モードのペンを選び、左スティックで左上へ移動）
+    #[default]
+    Splatoon3PostEditor,
+    /// 何もしない（ペンとカーソルを手動で合わせた場合）
+    None,
+}
+
+/// 初期化の1手順
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum InitStep {
+    /// ボタンを `count` 回押す（1回ごとに `delay_ms` 待つ）
+    TapButton {
+        /// ボタン名（`A`、`L`、`ZL` など）
+        button: String,
+        count: u32,
+        delay_ms: u32,
+    },
+    /// 左スティックを方向名（`UP_LEFT` など）の向きに `duration_ms` 倒してから戻す
+    HoldStick { direction: String, duration_ms: u32 },
+    /// 何も入力せずに待つ
+    Wait { duration_ms: u32 },
+}
+
+/// 描画前に送る初期化の手順
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct InitSequence {
+    pub steps: Vec<InitStep>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum InitSequenceError {
+    #[error("Initialization sequence has {steps} steps (max {max})")]
+    TooManySteps { steps: usize, max: usize },
+    #[error("Unknown button '{0}' in initialization sequence")]
+    UnknownButton(String),
+    #[error("Unknown stick direction '{0}' in initialization sequence")]
+    UnknownDirection(String),
+    #[error("Step {index} taps a button 0 times")]
+    EmptyTap { index: usize },
+    #[error("Initialization sequence sends {actions} inputs (max {max})")]
+    TooManyActions { actions: usize, max: usize },
+    #[error("Initialization sequence takes {duration_ms}ms (max {max_ms}ms)")]
+    TooLong { duration_ms: u64, max_ms: u64 },
+}
+
+impl Default for InitSequence {
+    fn default() -> Self {
+        Self::preset(InitPreset::default())
+    }
+}
+
+impl From<InitPreset> for InitSequence {
+    fn from(preset: InitPreset) -> Self {
+        Self::preset(preset)
+    }
+}
+
+impl InitStep {
+    /// 手順を入力に展開する
+    pub fn to_actions(&self) -> Result<Vec<ControllerAction>, InitSequenceError> {
+        let actions = match self {
+            Self::TapButton {
+                button,
+                count,
+                delay_ms,
+            } => {
+                let button = Button::from_name(button)
+                    .ok_or_else(|| InitSequenceError::UnknownButton(button.clone()))?;
+                (0..*count)
+                    .flat_map(|_| {
+                        [
+                            ControllerAction::press_button(button, TAP_PRESS_MS),
+                            ControllerAction::release_button(button, TAP_RELEASE_MS),
+                            ControllerAction::wait(*delay_ms),
+                        ]
+                    })
+                    .collect()
+            }
+            Self::HoldStick {
+                direction,
+                duration_ms,
+            } => {
+                let dpad = DPad::from_name(direction)
+                    .filter(|dpad| *dpad != DPad::NEUTRAL)
+                    .ok_or_else(|| InitSequenceError::UnknownDirection(direction.clone()))?;
+                vec![
+                    ControllerAction::move_left_stick(StickPosition::from_dpad(dpad), *duration_ms),
+                    ControllerAction::move_left_stick(StickPosition::CENTER, STICK_RELEASE_MS),
+                ]
+            }
+            Self::Wait { duration_ms } => vec![ControllerAction::wait(*duration_ms)],
+        };
+        Ok(actions)
+    }
+
+    /// 進捗表示用の説明（言語を決める前の文言）
+    pub fn message(&self) -> Message {
+        match self {
+            Self::TapButton { button, count, .. } => Message::new(MessageKey::InitTapButton)
+                .with_arg("button", button)
+                .with_arg("count", count),
+            Self::HoldStick { direction, .. } => {
+                Message::new(MessageKey::InitHoldStick).with_arg("direction", direction)
+            }
+            Self::Wait { duration_ms } => {
+                Message::new(MessageKey::InitWait).with_arg("ms", duration_ms)
+            }
+        }
+    }
+
+    /// 進捗表示用の説明
+    pub fn describe(&self) -> String {
+        self.message().render(Locale::default())
+    }
+}
+
+impl InitSequence {
+    pub fn new(steps: Vec<InitStep>) -> Self {
+        Self { steps }
+    }
+
+    /// ピクセルペンで描く場合のプリセットの手順
+    pub fn preset(preset: InitPreset) -> Self {
+        Self::preset_for(preset, DrawingMode::default())
+    }
+
+    /// 描画モードに合わせたプリセットの手順
+    pub fn preset_for(preset: InitPreset, mode: DrawingMode) -> Self {
+        match preset {
+            InitPreset::Splatoon3PostEditor => Self::splatoon3_post_editor_for(mode),
+            InitPreset::None => Self::new(Vec::new()),
+        }
+    }
+
+    /// Splatoon3の投稿エディタでピクセルペンを使う手順
+    pub fn splatoon3_post_editor() -> Self {
+        Self::splatoon3_post_editor_for(DrawingMode::PixelPen)
+    }
+
+    /// Splatoon3の投稿エディタ用の手順
+    ///
+    /// ペンサイズは 小 → 中 → 大 → 小 と切り替わるため、Lを5回押せば取りこぼしがあっても小になる。
+    /// 描画モードの選択手順（`DrawingMode::selection_steps`）でペンを合わせた後、
+    /// 左スティックを5秒間左上に倒してカーソルを原点に合わせる。
+    pub fn splatoon3_post_editor_for(mode: DrawingMode) -> Self {
+        let mut steps = mode.selection_steps();
+        steps.extend([
+            InitStep::Wait { duration_ms: 500 },
+            InitStep::HoldStick {
+                direction: "UP_LEFT".to_string(),
+                duration_ms: 5000,
+            },
+            InitStep::Wait { duration_ms: 500 },
+        ]);
+        Self::new(steps)
+    }
+
+    /// 描画モードの選択手順で始まるか（プリセットから作った手順ではペンの選択を繰り返さない）
+    pub fn selects_drawing_mode(&self, mode: DrawingMode) -> bool {
+        self.steps.starts_with(&mode.selection_steps())
+    }
+
+    /// 手順ごとのコマンド（実機では手順の間で停止要求を確認する）
+    pub fn step_commands(&self) -> Result<Vec<ControllerCommand>, InitSequenceError> {
+        self.validate()?;
+        self.steps
+            .iter()
+            .enumerate()
+            .map(|(index, step)| {
+                let command = ControllerCommand::new(format!("Initialize Step {}", index + 1))
+                    .with_description(step.describe());
+                Ok(step
+                    .to_actions()?
+                    .into_iter()
+                    .fold(command, ControllerCommand::add_action))
+            })
+            .collect()
+    }
+
+    /// すべての手順を1つにまとめたコマンド
+    pub fn to_command(&self) -> Result<ControllerCommand, InitSequenceError> {
+        let command = ControllerCommand::new("Initialize").with_description("描画前の初期化");
+        Ok(self
+            .step_commands()?
+            .into_iter()
+            .flat_map(|step| step.sequence)
+            .fold(command, ControllerCommand::add_action))
+    }
+
+    /// 手順の数・入力の数・所要時間の上限と、ボタン名・方向名を確認する
+    pub fn validate(&self) -> Result<(), InitSequenceError> {
+        if self.steps.len() > MAX_INIT_STEPS {
+            return Err(InitSequenceError::TooManySteps {
+                steps: self.steps.len(),
+                max: MAX_INIT_STEPS,
+            });
+        }
+
+        let mut actions = 0usize;
+        let mut duration_ms = 0u64;
+        for (index, step) in self.steps.iter().enumerate() {
+            if let InitStep::TapButton { count: 0, .. } = step {
+                return Err(InitSequenceError::EmptyTap { index });
+            }
+            // 連打の回数が極端な場合でも展開する前に上限で止める
+            let step_actions = match step {
+                InitStep::TapButton { count, .. } => *count as usize * 3,
+                InitStep::HoldStick { .. } => 2,
+                InitStep::Wait { .. } => 1,
+            };
+            actions += step_actions;
+            if actions > MAX_INIT_ACTIONS {
+                return Err(InitSequenceError::TooManyActions {
+                    actions,
+                    max: MAX_INIT_ACTIONS,
+                });
+            }
+            duration_ms += step
+                .to_actions()?
+                .iter()
+                .map(|action| action.duration_ms as u64)
+                .sum::<u64>();
+        }
+
+        if duration_ms > MAX_INIT_DURATION_MS {
+            return Err(InitSequenceError::TooLong {
+                duration_ms,
+                max_ms: MAX_INIT_DURATION_MS,
+            });
+        }
+        Ok(())
+    }
+
+    /// 手順全体の所要時間（ミリ秒、名前が不正な手順は数えない）
+    pub fn duration_ms(&self) -> u64 {
+        self.steps
+            .iter()
+            .filter_map(|step| step.to_actions().ok())
+            .flatten()
+            .map(|action| action.duration_ms as u64)
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::controller::ActionType;
+
+    #[test]
+    fn test_post_editor_preset_taps_l_and_holds_stick_to_top_left() {
+        let sequence = InitSequence::default();
+        assert_eq!(
+            sequence,
+            InitSequence::preset(InitPreset::Splatoon3PostEditor)
+        );
+        sequence.validate().unwrap();
+        assert_eq!(
+            sequence.duration_ms(),
+            5 * (300 + 200 + 800) + 500 + 5100 + 500
+        );
+
+        let command = sequence.to_command().unwrap();
+        let l_presses = command
+            .sequence
+            .iter()
+            .filter(|action| action.action_type == ActionType::PressButton(Button::L))
+            .count();
+        assert_eq!(l_presses, 5);
+        assert!(command.sequence.iter().any(|action| {
+            action.action_type == ActionType::MoveLeftStick(StickPosition::new(0, 0))
+                && action.duration_ms == 5000
+        }));
+        assert_eq!(sequence.step_commands().unwrap().len(), 4);
+
+        assert!(sequence.selects_drawing_mode(DrawingMode::PixelPen));
+        assert!(!sequence.selects_drawing_mode(DrawingMode::Brush));
+
+        assert!(
+            InitSequence::preset(InitPreset::None)
+                .to_command()
+                .unwrap()
+                .sequence
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_post_editor_preset_selects_pen_for_drawing_mode() {
+        let taps = |mode: DrawingMode| -> Vec<Button> {
+            InitSequence::preset_for(InitPreset::Splatoon3PostEditor, mode)
+                .to_command()
+                .unwrap()
+                .sequence
+                .into_iter()
+                .filter_map(|action| match action.action_type {
+                    ActionType::PressButton(button) => Some(button),
+                    _ => None,
+                })
+                .collect()
+        };
+        let l5 = [Button::L; 5];
+
+        assert_eq!(taps(DrawingMode::PixelPen), l5);
+        assert_eq!(taps(DrawingMode::Eraser), l5);
+        assert_eq!(taps(DrawingMode::Brush), [&l5[..], &[Button::R]].concat());
+        assert_eq!(
+            taps(DrawingMode::ThickBrush),
+            [&l5[..], &[Button::R, Button::R]].concat()
+        );
+        for mode in DrawingMode::ALL {
+            let sequence = InitSequence::preset_for(InitPreset::Splatoon3PostEditor, mode);
+            sequence.validate().unwrap();
+            assert!(sequence.selects_drawing_mode(mode));
+            assert!(
+                InitSequence::preset_for(InitPreset::None, mode)
+                    .steps
+                    .is_empty()
+            );
+        }
+    }
+
+    #[test]
+    fn test_custom_sequence_limits() {
+        let tap = |button: &str, count: u32| InitStep::TapButton {
+            button: button.to_string(),
+            count,
+            delay_ms: 100,
+        };
+
+        assert_eq!(
+            InitSequence::new(vec![tap("Q", 1)]).validate(),
+            Err(InitSequenceError::UnknownButton("Q".to_string()))
+        );
+        assert_eq!(
+            InitSequence::new(vec![InitStep::HoldStick {
+                direction: "NEUTRAL".to_string(),
+                duration_ms: 100,
+            }])
+            .validate(),
+            Err(InitSequenceError::UnknownDirection("NEUTRAL".to_string()))
+        );
+        assert_eq!(
+            InitSequence::new(vec![tap("A", 0)]).validate(),
+            Err(InitSequenceError::EmptyTap { index: 0 })
+        );
+        assert!(matches!(
+            InitSequence::new(vec![tap("A", u32::MAX)]).validate(),
+            Err(InitSequenceError::TooManyActions { .. })
+        ));
+        assert!(matches!(
+            InitSequence::new(vec![
+                InitStep::Wait { duration_ms: 100 };
+                MAX_INIT_STEPS + 1
+            ])
+            .validate(),
+            Err(InitSequenceError::TooManySteps { .. })
+        ));
+        assert!(matches!(
+            InitSequence::new(vec![InitStep::Wait {
+                duration_ms: u32::MAX
+            }])
+            .validate(),
+            Err(InitSequenceError::TooLong { .. })
+        ));
+
+        // JSONでは `type` で手順の種類を指定する
+        let sequence: InitSequence = serde_json::from_str(
+            r#"{"steps":[{"type":"tap_button","button":"r","count":2,"delay_ms":300},{"type":"wait","duration_ms":1000}]}"#,
+        )
+        .unwrap();
+        sequence.validate().unwrap();
+        assert_eq!(sequence.to_command().unwrap().sequence.len(), 7);
+    }
+}
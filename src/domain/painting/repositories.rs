@@ -0,0 +1,19 @@
+use crate::domain::artwork::entities::ArtworkId;
+use crate::domain::painting::entities::PaintingRun;
+
+/// 描画実行記録のリポジトリ
+///
+/// 描画スレッドの終了処理（パニック時を含む）から呼ばれるため同期APIとする
+pub trait PaintingRunRepository: Send + Sync {
+    /// 記録を保存（同じIDの記録は上書き）
+    fn save(&self, run: &PaintingRun);
+
+    /// アートワークの記録を新しい順に取得
+    fn find_by_artwork(&self, artwork_id: &ArtworkId) -> Vec<PaintingRun>;
+
+    /// 全アートワークの記録を新しい順に最大 `limit` 件取得
+    fn recent(&self, limit: usize) -> Vec<PaintingRun>;
+
+    /// すべての記録を削除し、削除した件数を返す
+    fn clear(&self) -> usize;
+}
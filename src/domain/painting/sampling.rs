@@ -0,0 +1,142 @@
+//! 大きなアートワークの見積もり用の間引き
+//!
+//! 全戦略の経路を全ドットで計算すると遅いボードではリクエストがタイムアウトするため、
+//! 横帯（数行ずつ）単位で間引いたキャンバスで見積もり、ドット数の比で拡大する。
+//! 帯の中の並びはそのまま残すので、経路の局所的な形（行の往復や近傍探索の距離）は元と変わらない。
+
+use crate::domain::artwork::entities::Canvas;
+use crate::domain::shared::value_objects::Coordinates;
+
+/// 間引きの単位となる横帯の高さ（行数）
+pub const SAMPLE_BAND_HEIGHT: u16 = 4;
+/// 見積もりに使う最大ドット数の既定値
+pub const DEFAULT_SAMPLE_DOTS: usize = 5000;
+/// 見積もりに使う最大ドット数の下限（これより少ないと誤差が大きくなる）
+pub const MIN_SAMPLE_DOTS: usize = 1000;
+
+/// 見積もり用に間引いたキャンバス
+#[derive(Debug, Clone)]
+pub struct CanvasSample {
+    /// 選んだ横帯を上から詰めて並べたキャンバス
+    pub canvas: Canvas,
+    /// サンプルに含まれる描画対象のドット数
+    pub sample_dots: usize,
+    /// 元のキャンバスの描画対象のドット数
+    pub total_dots: usize,
+}
+
+impl CanvasSample {
+    /// サンプルの結果を元のキャンバスに換算する倍率
+    pub fn scale(&self) -> f64 {
+        self.total_dots as f64 / self.sample_dots as f64
+    }
+
+    /// 回数をサンプルから元のキャンバスに換算する
+    pub fn scale_count(&self, count: u64) -> u64 {
+        (count as f64 * self.scale()).round() as u64
+    }
+}
+
+/// 描画対象のドットが `max_dots` を超える場合に、横帯を等間隔に選んで間引く
+///
+/// 結果は決定的で、同じキャンバスからは常に同じサンプルを返す。
+/// `max_dots` 以下のキャンバスや、1つの帯だけで `max_dots` を超える場合は `None`（全ドットで計算する）。
+pub fn sample_row_bands(canvas: &Canvas, max_dots: usize) -> Option<CanvasSample> {
+    let max_dots = max_dots.max(MIN_SAMPLE_DOTS);
+    let band_count = canvas.height.div_ceil(SAMPLE_BAND_HEIGHT) as usize;
+    let mut band_dots = vec![0usize; band_count];
+    for (coord, dot) in &canvas.dots {
+        if dot.is_drawable() {
+            band_dots[(coord.y / SAMPLE_BAND_HEIGHT) as usize] += 1;
+        }
+    }
+    let total_dots: usize = band_dots.iter().sum();
+    if total_dots <= max_dots {
+        return None;
+    }
+
+    // 間隔を広げながら、上限に収まる中で最も多くのドットを含む帯の組を選ぶ
+    let (stride, offset, sample_dots) = (total_dots.div_ceil(max_dots).max(2)..=band_count)
+        .find_map(|stride| {
+            (0..stride)
+                .map(|offset| {
+                    let dots: usize = band_dots.iter().skip(offset).step_by(stride).sum();
+                    (stride, offset, dots)
+                })
+                .filter(|&(_, _, dots)| dots > 0 && dots <= max_dots)
+                .max_by_key(|&(_, offset, dots)| (dots, std::cmp::Reverse(offset)))
+        })?;
+
+    let selected = band_count.saturating_sub(offset).div_ceil(stride);
+    let mut sample = Canvas::with_background(
+        canvas.width,
+        (selected as u16) * SAMPLE_BAND_HEIGHT,
+        canvas.background_color,
+    );
+    for (coord, dot) in &canvas.dots {
+        let band = (coord.y / SAMPLE_BAND_HEIGHT) as usize;
+        if !dot.is_drawable() || band < offset || !(band - offset).is_multiple_of(stride) {
+            continue;
+        }
+        let y =
+            ((band - offset) / stride) as u16 * SAMPLE_BAND_HEIGHT + coord.y % SAMPLE_BAND_HEIGHT;
+        sample
+            .dots
+            .insert(Coordinates::new(coord.x, y), dot.clone());
+    }
+
+    Some(CanvasSample {
+        canvas: sample,
+        sample_dots,
+        total_dots,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::artwork::entities::Dot;
+
+    fn filled_canvas(width: u16, height: u16) -> Canvas {
+        let mut canvas = Canvas::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                canvas
+                    .set_dot(Coordinates::new(x, y), Dot::black())
+                    .unwrap();
+            }
+        }
+        canvas
+    }
+
+    #[test]
+    fn test_small_canvas_is_not_sampled() {
+        let canvas = filled_canvas(20, 20);
+        assert!(sample_row_bands(&canvas, 5000).is_none());
+    }
+
+    #[test]
+    fn test_bands_are_selected_evenly_and_packed() {
+        // 320x120の全面（38,400ドット）は4行の帯30本。8本ごとに選ぶと上限に収まるのは3本（3,840ドット）
+        let canvas = filled_canvas(320, 120);
+        let sample = sample_row_bands(&canvas, 5000).unwrap();
+        assert_eq!(sample.total_dots, 38_400);
+        assert_eq!(sample.sample_dots, 3_840);
+        assert_eq!(sample.canvas.dots.len(), 3_840);
+        assert_eq!(sample.canvas.height, 12);
+        assert!(sample.canvas.dots.keys().all(|coord| coord.y < 12));
+        assert_eq!(sample.scale(), 10.0);
+        assert_eq!(sample.scale_count(7), 70);
+
+        // 同じキャンバスからは同じサンプルになる
+        let again = sample_row_bands(&canvas, 5000).unwrap();
+        assert_eq!(again.canvas.dots.len(), sample.canvas.dots.len());
+        assert!(
+            again
+                .canvas
+                .dots
+                .keys()
+                .all(|coord| sample.canvas.dots.contains_key(coord))
+        );
+    }
+}
@@ -0,0 +1,569 @@
+//! 他のツールの描画スクリプトの読み込み
+//!
+//! Switch-Fightstick の `step[]` 配列（C・CSV）と NXBT のマクロを入力の列に変換し、
+//! ゲーム内キャンバスの左上からカーソルを動かして、描かれるドットの座標を再現する。
+//!
+//! ## 再現する入力
+//! - 十字キー（`DPAD_UP` など）: 押すたびに1マス移動する（押し続けても1マス、キャンバスの端で止まる）
+//! - `A`: カーソルの位置にドットを描く（押したまま十字キーで移動すると、ゲームと同じく移動先にも描く）
+//! - 何も入力しない（`NOTHING`、NXBTの時間だけの行）: すべての入力を離す
+//!
+//! 同じ入力が続く要素は1回の押下として扱う。スティック（移動量がわからない）と
+//! A以外のボタン、不明な入力名は再現せず、入力名ごとに回数と最初の行を警告として返す。
+
+use crate::domain::artwork::entities::{Canvas, Dot};
+use crate::domain::controller::{Button, DPad};
+use crate::domain::shared::value_objects::{CoordinateSpace, Coordinates};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use thiserror::Error;
+use utoipa::ToSchema;
+
+/// 再現するゲーム内キャンバスの大きさ
+const SCRIPT_CANVAS_WIDTH: u16 = 320;
+const SCRIPT_CANVAS_HEIGHT: u16 = 120;
+
+/// `LOOP` を展開した後の入力の最大数（キャンバス全体を描くスクリプトにも十分な量）
+pub const MAX_SCRIPT_STEPS: usize = 500_000;
+
+/// 読み込むスクリプトの形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScriptFormat {
+    /// Switch-Fightstick の `{ 入力, フレーム数 }` の配列、または `button,frames` のCSV
+    Fightstick,
+    /// NXBT のマクロ（`A 0.1s` のように、同時に押す入力と長さを1行に書く）
+    Nxbt,
+}
+
+impl ScriptFormat {
+    /// `{` を含めばFightstickのC、最初の行が `,` を含めばFightstickのCSV、それ以外はNXBTとみなす
+    pub fn detect(script: &str) -> Self {
+        let first_line = script
+            .lines()
+            .map(|line| strip_comment(line, "//").trim())
+            .find(|line| !line.is_empty());
+        if script.contains('{') || first_line.is_some_and(|line| line.contains(',')) {
+            Self::Fightstick
+        } else {
+            Self::Nxbt
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Fightstick => "fightstick",
+            Self::Nxbt => "nxbt",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ScriptImportError {
+    #[error("Line {line}: {reason}")]
+    InvalidLine { line: usize, reason: String },
+    #[error("The script contains no inputs")]
+    Empty,
+}
+
+/// 再現しなかった入力の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IgnoredInput {
+    /// スティック（カーソルの移動量がわからない）
+    Stick,
+    /// A以外のボタン
+    Button,
+    /// 不明な入力名
+    Unknown,
+}
+
+/// 再現しなかった入力（入力名ごとにまとめる）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptImportWarning {
+    /// スクリプトの表記のままの入力名
+    pub input: String,
+    pub kind: IgnoredInput,
+    /// 押した回数
+    pub occurrences: usize,
+    /// 最初に現れた行（1始まり）
+    pub first_line: usize,
+}
+
+impl fmt::Display for ScriptImportWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let what = match self.kind {
+            IgnoredInput::Stick => "stick input",
+            IgnoredInput::Button => "button",
+            IgnoredInput::Unknown => "unknown input",
+        };
+        write!(
+            f,
+            "Line {}: {} '{}' was ignored ({} time(s)); the reconstructed dots may be misplaced",
+            self.first_line, what, self.input, self.occurrences
+        )
+    }
+}
+
+/// スクリプトを再生した結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScriptReplay {
+    /// 描かれるドット（ゲーム内キャンバスの座標、最初に描いた順、重複なし）
+    pub dots: Vec<Coordinates>,
+    /// Aを押した回数（同じ位置に描き直した分も含み、押したままの移動で描いた分は含まない）
+    pub presses: usize,
+    /// 十字キーで移動した回数（キャンバスの端で動かなかった分も含む）
+    pub moves: usize,
+    pub warnings: Vec<ScriptImportWarning>,
+}
+
+impl ScriptReplay {
+    /// 描かれるドットを黒で置いた、ゲーム内キャンバスと同じ大きさのキャンバス
+    pub fn to_canvas(&self) -> Canvas {
+        let mut canvas = Canvas::new(SCRIPT_CANVAS_WIDTH, SCRIPT_CANVAS_HEIGHT);
+        for &dot in &self.dots {
+            let coordinates = CoordinateSpace::GameCanvas.convert(
+                dot,
+                CoordinateSpace::Editor,
+                SCRIPT_CANVAS_WIDTH,
+                SCRIPT_CANVAS_HEIGHT,
+            );
+            canvas
+                .set_dot(coordinates, Dot::black())
+                .expect("the cursor stays within the canvas");
+        }
+        canvas
+    }
+
+    fn ignore(&mut self, input: &str, kind: IgnoredInput, line: usize) {
+        match self.warnings.iter_mut().find(|w| w.input == input) {
+            Some(warning) => warning.occurrences += 1,
+            None => self.warnings.push(ScriptImportWarning {
+                input: input.to_string(),
+                kind,
+                occurrences: 1,
+                first_line: line,
+            }),
+        }
+    }
+}
+
+/// 同時に押す入力と、その行
+#[derive(Debug)]
+struct ScriptStep {
+    line: usize,
+    inputs: Vec<String>,
+}
+
+/// スクリプトを読み込み、カーソルを左上から動かして描かれるドットを求める
+pub fn replay_script(
+    script: &str,
+    format: ScriptFormat,
+) -> Result<ScriptReplay, ScriptImportError> {
+    let steps = match format {
+        ScriptFormat::Fightstick => parse_fightstick(script)?,
+        ScriptFormat::Nxbt => parse_nxbt(script)?,
+    };
+    if steps.is_empty() {
+        return Err(ScriptImportError::Empty);
+    }
+
+    let mut replay = ScriptReplay {
+        dots: Vec::new(),
+        presses: 0,
+        moves: 0,
+        warnings: Vec::new(),
+    };
+    let mut painted = std::collections::HashSet::new();
+    let mut cursor = (0i32, 0i32);
+    let mut held: Vec<String> = Vec::new();
+    for step in steps {
+        let pressed: Vec<&String> = step
+            .inputs
+            .iter()
+            .filter(|input| !held.contains(input))
+            .collect();
+        // 同時に押した場合は移動してから描く
+        let mut paint = false;
+        let mut moved = false;
+        for input in pressed {
+            match classify(input) {
+                Ok(Action::Move(dpad)) => {
+                    let (dx, dy) = dpad.offset();
+                    cursor = (
+                        (cursor.0 + i32::from(dx)).clamp(0, i32::from(SCRIPT_CANVAS_WIDTH) - 1),
+                        (cursor.1 + i32::from(dy)).clamp(0, i32::from(SCRIPT_CANVAS_HEIGHT) - 1),
+                    );
+                    replay.moves += 1;
+                    moved = true;
+                }
+                Ok(Action::Paint) => {
+                    replay.presses += 1;
+                    paint = true;
+                }
+                Ok(Action::Nothing) => {}
+                Err(kind) => replay.ignore(input, kind, step.line),
+            }
+        }
+        let holding_a = step
+            .inputs
+            .iter()
+            .any(|input| matches!(classify(input), Ok(Action::Paint)));
+        if paint || (moved && holding_a) {
+            let dot = Coordinates::new(cursor.0 as u16, cursor.1 as u16);
+            if painted.insert(dot) {
+                replay.dots.push(dot);
+            }
+        }
+        held = step.inputs;
+    }
+    Ok(replay)
+}
+
+/// 入力で起きること
+enum Action {
+    Move(DPad),
+    Paint,
+    Nothing,
+}
+
+fn classify(input: &str) -> Result<Action, IgnoredInput> {
+    let name = input.to_ascii_uppercase();
+    if name == "NOTHING" {
+        return Ok(Action::Nothing);
+    }
+    if let Some(direction) = name.strip_prefix("DPAD_") {
+        return match DPad::from_name(direction) {
+            Some(DPad::NEUTRAL) => Ok(Action::Nothing),
+            Some(dpad) => Ok(Action::Move(dpad)),
+            None => Err(IgnoredInput::Unknown),
+        };
+    }
+    // NXBTのスティック（`L_STICK@+000+100`）は中央なら何もしない
+    if let Some((_, position)) = name.split_once('@') {
+        return match position {
+            "+000+000" | "-000+000" | "+000-000" | "-000-000" => Ok(Action::Nothing),
+            _ => Err(IgnoredInput::Stick),
+        };
+    }
+    match name.as_str() {
+        "UP" | "DOWN" | "LEFT" | "RIGHT" | "THROW" => Err(IgnoredInput::Stick),
+        "A" => Ok(Action::Paint),
+        "L_STICK_PRESS" | "R_STICK_PRESS" | "LCLICK" | "RCLICK" | "TRIGGERS" => {
+            Err(IgnoredInput::Button)
+        }
+        _ if Button::from_name(&name).is_some() => Err(IgnoredInput::Button),
+        _ => Err(IgnoredInput::Unknown),
+    }
+}
+
+/// `marker` 以降を取り除く
+fn strip_comment<'a>(line: &'a str, marker: &str) -> &'a str {
+    line.split_once(marker).map_or(line, |(code, _)| code)
+}
+
+/// `{ A, 5 }` の並び（C）か `A,5` の行（CSV）を読む（0フレームの要素は再生されないため除く）
+fn parse_fightstick(script: &str) -> Result<Vec<ScriptStep>, ScriptImportError> {
+    let is_c = script.contains('{');
+    let mut steps = Vec::new();
+    for (index, line) in script.lines().enumerate() {
+        let line_number = index + 1;
+        let code = strip_comment(line, "//");
+        let entries: Vec<&str> = if is_c {
+            code.split('{')
+                .skip(1)
+                .filter_map(|rest| rest.split_once('}').map(|(entry, _)| entry))
+                .collect()
+        } else if code.trim().is_empty() || code.trim().eq_ignore_ascii_case("button,frames") {
+            Vec::new()
+        } else {
+            vec![code]
+        };
+        for entry in entries {
+            let invalid = |reason: String| ScriptImportError::InvalidLine {
+                line: line_number,
+                reason,
+            };
+            let (inputs, frames) = entry
+                .split_once(',')
+                .ok_or_else(|| invalid(format!("expected 'input, frames' but got '{entry}'")))?;
+            let frames: u16 = frames
+                .trim()
+                .parse()
+                .map_err(|_| invalid(format!("invalid frame count '{}'", frames.trim())))?;
+            if frames == 0 {
+                continue;
+            }
+            if steps.len() >= MAX_SCRIPT_STEPS {
+                return Err(invalid(format!(
+                    "the script has more than {MAX_SCRIPT_STEPS} inputs"
+                )));
+            }
+            steps.push(ScriptStep {
+                line: line_number,
+                inputs: split_inputs(inputs.split('|')),
+            });
+        }
+    }
+    Ok(steps)
+}
+
+/// NXBTのマクロを読む（`LOOP n` は字下げした後続の行を繰り返す）
+fn parse_nxbt(script: &str) -> Result<Vec<ScriptStep>, ScriptImportError> {
+    let lines: Vec<(usize, usize, &str)> = script
+        .lines()
+        .enumerate()
+        .filter_map(|(index, line)| {
+            let code = strip_comment(line, "#").trim_end();
+            let content = code.trim_start();
+            (!content.is_empty()).then_some((index + 1, code.len() - content.len(), content))
+        })
+        .collect();
+    let mut steps = Vec::new();
+    parse_nxbt_block(&lines, &mut steps)?;
+    Ok(steps)
+}
+
+fn parse_nxbt_block(
+    lines: &[(usize, usize, &str)],
+    steps: &mut Vec<ScriptStep>,
+) -> Result<(), ScriptImportError> {
+    let mut index = 0;
+    while let Some(&(line, indent, content)) = lines.get(index) {
+        let invalid = |reason: String| ScriptImportError::InvalidLine { line, reason };
+        index += 1;
+        let tokens: Vec<&str> = content.split_whitespace().collect();
+        if tokens[0].eq_ignore_ascii_case("LOOP") {
+            let count: usize = tokens
+                .get(1)
+                .and_then(|count| count.parse().ok())
+                .ok_or_else(|| invalid(format!("invalid loop '{content}'")))?;
+            let body_len = lines[index..]
+                .iter()
+                .take_while(|(_, body_indent, _)| *body_indent > indent)
+                .count();
+            let mut body = Vec::new();
+            parse_nxbt_block(&lines[index..index + body_len], &mut body)?;
+            // 入れ子のループは内側で上限を確かめ済みのため、ここでは展開後の合計だけを確かめる
+            let total = body
+                .len()
+                .checked_mul(count)
+                .and_then(|expanded| expanded.checked_add(steps.len()))
+                .filter(|total| *total <= MAX_SCRIPT_STEPS)
+                .ok_or_else(|| {
+                    invalid(format!(
+                        "the loop expands to more than {MAX_SCRIPT_STEPS} inputs"
+                    ))
+                })?;
+            if !body.is_empty() {
+                steps.reserve(total - steps.len());
+                for _ in 0..count {
+                    steps.extend(body.iter().map(|step| ScriptStep {
+                        line: step.line,
+                        inputs: step.inputs.clone(),
+                    }));
+                }
+            }
+            index += body_len;
+            continue;
+        }
+
+        let (duration, inputs) = tokens.split_last().expect("the line is not empty");
+        let seconds: f64 = duration
+            .strip_suffix(['s', 'S'])
+            .and_then(|seconds| seconds.parse().ok())
+            .filter(|seconds: &f64| seconds.is_finite() && *seconds >= 0.0)
+            .ok_or_else(|| {
+                invalid(format!(
+                    "expected a duration like '0.1s' but got '{duration}'"
+                ))
+            })?;
+        if seconds == 0.0 {
+            continue;
+        }
+        if steps.len() >= MAX_SCRIPT_STEPS {
+            return Err(invalid(format!(
+                "the script has more than {MAX_SCRIPT_STEPS} inputs"
+            )));
+        }
+        steps.push(ScriptStep {
+            line,
+            inputs: split_inputs(inputs.iter().copied()),
+        });
+    }
+    Ok(())
+}
+
+/// 入力名を整え、`NOTHING` と空の入力名を除く
+fn split_inputs<'a>(inputs: impl Iterator<Item = &'a str>) -> Vec<String> {
+    inputs
+        .map(str::trim)
+        .filter(|input| !input.is_empty() && !input.eq_ignore_ascii_case("NOTHING"))
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dots(replay: &ScriptReplay) -> Vec<(u16, u16)> {
+        replay.dots.iter().map(|dot| (dot.x, dot.y)).collect()
+    }
+
+    #[test]
+    fn test_three_dot_fightstick_script_is_reconstructed() {
+        // (0, 0)、右に2つ進んで (2, 0)、下に1つ進んで (2, 1)
+        let script = "// squid (3 dots)
+static const command step[] = {
+\t{ UP, 625 }, { LEFT, 625 },
+\t{ NOTHING, 78 },
+\t{ A, 13 },
+\t{ NOTHING, 13 },
+\t{ DPAD_RIGHT, 13 },
+\t{ NOTHING, 13 },
+\t{ DPAD_RIGHT, 13 },
+\t{ NOTHING, 13 },
+\t{ A, 13 },
+\t{ NOTHING, 13 },
+\t{ DPAD_DOWN, 13 },
+\t{ NOTHING, 0 },
+\t{ NOTHING, 13 },
+\t{ A, 13 },
+\t{ NOTHING, 13 },
+};
+";
+        assert_eq!(ScriptFormat::detect(script), ScriptFormat::Fightstick);
+        let replay = replay_script(script, ScriptFormat::Fightstick).unwrap();
+        assert_eq!(dots(&replay), vec![(0, 0), (2, 0), (2, 1)]);
+        assert_eq!((replay.presses, replay.moves), (3, 3));
+        assert_eq!(
+            replay.warnings,
+            vec![
+                ScriptImportWarning {
+                    input: "UP".to_string(),
+                    kind: IgnoredInput::Stick,
+                    occurrences: 1,
+                    first_line: 3,
+                },
+                ScriptImportWarning {
+                    input: "LEFT".to_string(),
+                    kind: IgnoredInput::Stick,
+                    occurrences: 1,
+                    first_line: 3,
+                },
+            ]
+        );
+
+        let canvas = replay.to_canvas();
+        assert_eq!((canvas.width, canvas.height), (320, 120));
+        let mut drawn: Vec<_> = canvas
+            .drawable_dots()
+            .into_iter()
+            .map(|(coordinates, _)| (coordinates.x, coordinates.y))
+            .collect();
+        drawn.sort_by_key(|&(x, y)| (y, x));
+        assert_eq!(drawn, vec![(0, 0), (2, 0), (2, 1)]);
+    }
+
+    #[test]
+    fn test_held_inputs_count_once_and_the_cursor_stops_at_the_edge() {
+        // 分割された長い押下は1回、端を越える移動は無視、同じ位置の描き直しはドットを増やさない。
+        // Aを押したままの移動は移動先にも描く
+        let script = "button,frames
+DPAD_LEFT,65535
+DPAD_LEFT,10
+A,1
+A,5
+NOTHING,1
+A,1
+DPAD_DOWN|A,2
+B,1
+THROW,1
+";
+        assert_eq!(ScriptFormat::detect(script), ScriptFormat::Fightstick);
+        let replay = replay_script(script, ScriptFormat::Fightstick).unwrap();
+        assert_eq!(dots(&replay), vec![(0, 0), (0, 1)]);
+        assert_eq!((replay.presses, replay.moves), (2, 2));
+        let kinds: Vec<_> = replay
+            .warnings
+            .iter()
+            .map(|w| (w.input.as_str(), w.kind, w.first_line))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("B", IgnoredInput::Button, 9),
+                ("THROW", IgnoredInput::Stick, 10)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_nxbt_macro_with_loops() {
+        let script = "# 横に3つ描き、その右下に1つ
+L_STICK@+000+000 0.1s
+LOOP 3
+    A 0.1s
+    0.1s
+    DPAD_RIGHT 0.1s
+    0.1s
+DPAD_DOWN 0.1s
+0.0s
+L_STICK@-100+000 1.0s
+ZR 0.1s
+A 0.1s
+";
+        assert_eq!(ScriptFormat::detect(script), ScriptFormat::Nxbt);
+        let replay = replay_script(script, ScriptFormat::Nxbt).unwrap();
+        assert_eq!(dots(&replay), vec![(0, 0), (1, 0), (2, 0), (3, 1)]);
+        let inputs: Vec<_> = replay
+            .warnings
+            .iter()
+            .map(|w| (w.input.as_str(), w.kind))
+            .collect();
+        assert_eq!(
+            inputs,
+            vec![
+                ("L_STICK@-100+000", IgnoredInput::Stick),
+                ("ZR", IgnoredInput::Button)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_malformed_scripts_are_rejected_with_the_line() {
+        assert_eq!(
+            replay_script("{ A, x },", ScriptFormat::Fightstick),
+            Err(ScriptImportError::InvalidLine {
+                line: 1,
+                reason: "invalid frame count 'x'".to_string()
+            })
+        );
+        assert!(matches!(
+            replay_script("A\nA 1", ScriptFormat::Nxbt),
+            Err(ScriptImportError::InvalidLine { line: 1, .. })
+        ));
+        assert_eq!(
+            replay_script("button,frames\n", ScriptFormat::Fightstick),
+            Err(ScriptImportError::Empty)
+        );
+    }
+
+    #[test]
+    fn test_nxbt_loops_are_bounded() {
+        for script in [
+            "LOOP 18446744073709551615\n    A 0.1s\n",
+            "LOOP 1000\n    LOOP 1000\n        A 0.1s\n        0.1s\n",
+        ] {
+            let result = replay_script(script, ScriptFormat::Nxbt);
+            assert!(
+                matches!(result, Err(ScriptImportError::InvalidLine { line: 1, .. })),
+                "{script:?}: {result:?}"
+            );
+        }
+        // 空のループは回数が大きくても展開しない
+        let script = "LOOP 18446744073709551615\n    0.0s\nA 0.1s\n";
+        let replay = replay_script(script, ScriptFormat::Nxbt).unwrap();
+        assert_eq!(dots(&replay), vec![(0, 0)]);
+    }
+}
@@ -1,20 +1,101 @@
-use crate::domain::artwork::entities::{Artwork, Canvas};
+use crate::domain::artwork::entities::{Artwork, Canvas, Dot};
 use crate::domain::controller::{Button, ControllerAction, ControllerCommand, DPad};
+use crate::domain::painting::init_sequence::{InitSequence, InitSequenceError};
 use crate::domain::painting::value_objects::{
-    CursorDirection, DrawingCanvasConfig, DrawingPath, DrawingStrategy,
+    AdaptiveTimingSettings, CalibrationLayout, CalibrationLayoutError, CalibrationPattern,
+    CalibrationPlan, CancellationToken, CanvasRegion, DrawingCanvasConfig, DrawingPath,
+    DrawingStrategy, LayerEstimate, PaintTiming, PathLayer, RunEstimate, RunOptions,
+    TwoOptSettings, TwoOptStats, TwoOptStopReason,
 };
-use crate::domain::shared::value_objects::Coordinates;
-use tracing::info;
+use crate::domain::shared::value_objects::{CoordinateSpace, Coordinates};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// 描画パスの計算が取り消された（途中までの経路は返さない）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("Drawing path computation was cancelled")]
+pub struct PathCancelled;
+
+/// 最近傍探索で取り消しを確認する間隔（ドット数）
+const CANCEL_CHECK_INTERVAL: usize = 256;
 
 /// アートワークをコントローラーコマンドに変換するサービス
 pub struct ArtworkToCommandConverter {
     config: DrawingCanvasConfig,
     strategy: DrawingStrategy,
+    region: Option<CanvasRegion>,
+    from_row: Option<u16>,
+    seed: Option<u64>,
+    two_opt: TwoOptSettings,
 }
 
 impl ArtworkToCommandConverter {
     pub fn new(config: DrawingCanvasConfig, strategy: DrawingStrategy) -> Self {
-        Self { config, strategy }
+        Self {
+            config,
+            strategy,
+            region: None,
+            from_row: None,
+            seed: None,
+            two_opt: TwoOptSettings::default(),
+        }
+    }
+
+    /// `GreedyTwoOpt` の2-opt最適化の打ち切り条件を指定する
+    pub fn with_two_opt_settings(mut self, settings: TwoOptSettings) -> Self {
+        self.two_opt = settings;
+        self
+    }
+
+    /// 乱択を使う描画戦略のシード値を指定する（現在の戦略はすべて決定的で、結果に影響しない）
+    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    /// 見積もりとコマンド生成に使う設定
+    pub fn config(&self) -> &DrawingCanvasConfig {
+        &self.config
+    }
+
+    /// 描画対象を指定した矩形領域内のドットに限定する
+    pub fn with_region(mut self, region: CanvasRegion) -> Self {
+        self.region = Some(region);
+        self
+    }
+
+    /// 描画対象をアートワークの指定した行以降（`y >= from_row`）のドットに限定する
+    ///
+    /// 領域を指定した場合は、領域内のドットをさらに絞り込む
+    pub fn with_from_row(mut self, from_row: u16) -> Self {
+        self.from_row = Some(from_row);
+        self
+    }
+
+    /// 領域内で `from_row` より上の行にある未描画のドット（アートワークの座標）
+    ///
+    /// 描画済みとみなして入力を送らないドットで、`from_row` を指定しない場合は空
+    pub fn dots_before_from_row(&self, canvas: &Canvas) -> Vec<Coordinates> {
+        let Some(from_row) = self.from_row else {
+            return Vec::new();
+        };
+        let mut dots: Vec<Coordinates> = canvas
+            .dots
+            .iter()
+            .filter(|(coord, dot)| coord.y < from_row && self.is_in_region(coord, dot))
+            .map(|(coord, _)| *coord)
+            .collect();
+        dots.sort_unstable_by_key(|coord| (coord.y, coord.x));
+        dots
+    }
+
+    /// 描画可能で、指定した領域内にあるドットか
+    fn is_in_region(&self, coord: &Coordinates, dot: &Dot) -> bool {
+        dot.is_drawable() && self.region.is_none_or(|region| region.contains(coord))
     }
 
     /// アートワークをコントローラーコマンドのシーケンスに変換
@@ -25,7 +106,7 @@ impl ArtworkToCommandConverter {
         commands.push(self.create_initialization_command());
 
         // 2. 描画モード選択コマンド
-        commands.push(self.create_select_drawing_mode_command());
+        commands.extend(self.create_select_drawing_mode_command());
 
         // 3. 描画パスを生成
         let drawing_path = self.create_drawing_path(&artwork.canvas);
@@ -44,234 +125,274 @@ impl ArtworkToCommandConverter {
         commands
     }
 
-    /// 初期化コマンドを作成
+    /// 実機での描画と同じ入力のコマンド（初期化手順と描画パス）
+    ///
+    /// 描画モードの選択と完了後の操作は含まない
+    pub fn painting_commands(
+        &self,
+        path: &DrawingPath,
+    ) -> Result<Vec<ControllerCommand>, InitSequenceError> {
+        let mut commands = vec![self.config.init_sequence.to_command()?];
+        commands.extend(self.create_drawing_commands(path));
+        Ok(commands)
+    }
+
+    /// 初期化コマンドを作成（実機での描画と同じ `init_sequence` から作る）
     fn create_initialization_command(&self) -> ControllerCommand {
-        let mut command =
-            ControllerCommand::new("Initialize").with_description("コントローラーを初期化");
-
-        // Switchのメニュー表示待機
-        command = command
-            .add_action(ControllerAction::wait(2000))
-            .add_action(ControllerAction::set_dpad(DPad::NEUTRAL, 100));
-
-        // カーソルを左上に移動（初期位置へ）
-        // 左上に完全に移動するため、画面サイズ以上の移動を実行
-        // よりゆっくりとした動作で移動
-        for _ in 0..150 {
-            command = command
-                .add_action(ControllerAction::set_dpad(DPad::UP_LEFT, 50))
-                .add_action(ControllerAction::set_dpad(DPad::NEUTRAL, 50));
-        }
-        command = command.add_action(ControllerAction::set_dpad(DPad::NEUTRAL, 500));
-
-        command
-    }
-
-    /// 描画モード選択コマンドを作成
-    fn create_select_drawing_mode_command(&self) -> ControllerCommand {
-        let mut command =
-            ControllerCommand::new("Select Drawing Mode").with_description("描画モードを選択");
-
-        let mode_button = self.config.drawing_mode.select_button();
-
-        // ペン選択前の待機時間を追加
-        command = command.add_action(ControllerAction::wait(500));
-
-        // Lボタンの場合は最低2回押す（ピクセルペンを確実に選択）
-        if mode_button == Button::L {
-            // ピクセルペンに切り替えるために2回押す
-            for i in 0..2 {
-                command = command
-                    .add_action(ControllerAction::press_button(Button::L, 200))
-                    .add_action(ControllerAction::release_button(Button::L, 300));
-                if i < 1 {
-                    command = command.add_action(ControllerAction::wait(500));
-                }
+        self.config.init_sequence.to_command().unwrap_or_else(|e| {
+            warn!("初期化手順が不正なため省略します: {}", e);
+            ControllerCommand::new("Initialize").with_description("描画前の初期化")
+        })
+    }
+
+    /// 描画モード選択コマンドを作成（`DrawingMode::selection_steps` から作る）
+    ///
+    /// 初期化手順がすでに同じ選択手順で始まる場合は、ペンの選択を繰り返さない
+    fn create_select_drawing_mode_command(&self) -> Option<ControllerCommand> {
+        let mode = self.config.drawing_mode;
+        if self.config.init_sequence.selects_drawing_mode(mode) {
+            return None;
+        }
+        let command = ControllerCommand::new("Select Drawing Mode")
+            .with_description(format!("描画モード（{mode}）を選択"));
+        let selection = InitSequence::new(mode.selection_steps())
+            .to_command()
+            .ok()?;
+        Some(
+            selection
+                .sequence
+                .into_iter()
+                .fold(command, ControllerCommand::add_action),
+        )
+    }
+
+    /// 描画パスを生成
+    ///
+    /// ドットはレイヤー番号の昇順に描画し、描画戦略はレイヤーごとに適用する。
+    /// キャンバスのドットはHashMapで保持されているため、実行ごとに同じ経路になるよう
+    /// 座標の (y, x) 順に並べてから描画戦略に渡す。
+    /// 全レイヤーの座標は1本の配列に (layer, y, x) 順で並べ、各レイヤーの区間をその場で並べ替える。
+    /// `origin` を指定した場合、パスの座標は（領域で絞り込んだ後に）ゲーム内キャンバスの座標に平行移動する。
+    /// アートワークの座標（エディターの座標系）はゲーム内キャンバスの座標系に変換してから並べる。
+    pub fn create_drawing_path(&self, canvas: &Canvas) -> DrawingPath {
+        match self.create_cancellable_drawing_path(canvas, &CancellationToken::default()) {
+            Ok(path) => path,
+            Err(PathCancelled) => unreachable!("the token is never cancelled"),
+        }
+    }
+
+    /// `create_drawing_path` と同じ描画パスを、`cancel` で取り消せるように生成する
+    ///
+    /// レイヤーの開始時、最近傍探索は一定のドット数ごと、2-optは反復ごと（と反復中の一定間隔ごと）に確認し、
+    /// 取り消されていれば途中までの経路を捨てて `PathCancelled` を返す。
+    pub fn create_cancellable_drawing_path(
+        &self,
+        canvas: &Canvas,
+        cancel: &CancellationToken,
+    ) -> Result<DrawingPath, PathCancelled> {
+        debug_assert!(
+            CoordinateSpace::GameCanvas.agrees_with_dpad(),
+            "D-pad directions do not match the {} axes",
+            CoordinateSpace::GameCanvas
+        );
+        let options = &self.config.options;
+        let to_game = |coord: Coordinates| {
+            CoordinateSpace::Editor.convert(
+                coord,
+                CoordinateSpace::GameCanvas,
+                canvas.width,
+                canvas.height,
+            )
+        };
+        let mut dots: Vec<(u8, Coordinates)> = canvas
+            .dots
+            .iter()
+            .filter(|(coord, dot)| {
+                self.is_in_region(coord, dot) && self.from_row.is_none_or(|row| coord.y >= row)
+            })
+            .map(|(coord, dot)| (dot.layer, options.canvas_coordinates(to_game(*coord))))
+            .collect();
+        dots.sort_unstable_by_key(|(layer, coord)| (*layer, coord.y, coord.x));
+
+        let mut coordinates = Vec::with_capacity(dots.len());
+        let mut layers: Vec<PathLayer> = Vec::new();
+        for (layer, coord) in dots {
+            match layers.last_mut() {
+                Some(segment) if segment.layer == layer => segment.len += 1,
+                _ => layers.push(PathLayer {
+                    layer,
+                    start: coordinates.len(),
+                    len: 1,
+                }),
             }
-        } else {
-            command = command
-                .add_action(ControllerAction::press_button(mode_button, 200))
-                .add_action(ControllerAction::release_button(mode_button, 300));
+            coordinates.push(coord);
         }
 
-        command = command.add_action(ControllerAction::wait(1000));
+        // 2-optの時間の上限は全レイヤーで共有する
+        let deadline = Instant::now() + self.two_opt.time_budget;
+        let mut grid = BucketGrid::default();
+        let mut two_opt: Option<TwoOptStats> = None;
+        for segment in &layers {
+            if cancel.is_cancelled() {
+                return Err(PathCancelled);
+            }
+            let stats = self.order_dots(
+                &mut coordinates[segment.start..segment.start + segment.len],
+                &mut grid,
+                deadline,
+                cancel,
+            )?;
+            two_opt = match (two_opt, stats) {
+                (Some(total), Some(stats)) => Some(total.merge(stats)),
+                (total, stats) => total.or(stats),
+            };
+        }
+        if let Some(stats) = &two_opt {
+            info!(
+                "2-opt optimization: {} iterations in {}ms, distance {} -> {} (window {}, stopped: {:?})",
+                stats.iterations,
+                stats.elapsed_ms,
+                stats.distance_before,
+                stats.distance_after,
+                stats.window,
+                stats.stop_reason
+            );
+        }
 
-        command
+        // 行ごとの進み具合を追えるよう、各ドットのアートワークの行を記録する
+        let rows = coordinates
+            .iter()
+            .map(|coord| {
+                options.artwork_coordinates(*coord).map_or(coord.y, |c| {
+                    CoordinateSpace::GameCanvas
+                        .convert(c, CoordinateSpace::Editor, canvas.width, canvas.height)
+                        .y
+                })
+            })
+            .collect();
+        let mut path = DrawingPath::with_layers(coordinates, layers);
+        path.two_opt = two_opt;
+        path.rows = rows;
+        path.estimated_time_ms =
+            simulate_run(&path, &self.config.timing, &self.config.options).total_ms;
+        Ok(path)
     }
 
-    /// 描画パスを生成
-    pub fn create_drawing_path(&self, canvas: &Canvas) -> DrawingPath {
-        let drawable_dots = canvas.drawable_dots();
-        let coordinates: Vec<Coordinates> = match self.strategy {
+    /// 描画戦略に従って (y, x) 順に並んだドットをその場で並べ替える
+    ///
+    /// `GreedyTwoOpt` の場合は2-opt最適化の結果を返す。
+    fn order_dots(
+        &self,
+        coords: &mut [Coordinates],
+        grid: &mut BucketGrid,
+        deadline: Instant,
+        cancel: &CancellationToken,
+    ) -> Result<Option<TwoOptStats>, PathCancelled> {
+        match self.strategy {
             DrawingStrategy::RasterScan => {
-                // 左から右、上から下
-                let mut coords: Vec<Coordinates> =
-                    drawable_dots.into_iter().map(|(coord, _)| *coord).collect();
-                coords.sort_by_key(|c| (c.y, c.x));
-                coords
+                // 左から右、上から下（入力の順序そのまま）
             }
             DrawingStrategy::ZigZag => {
-                // ジグザグパターン
-                let mut coords: Vec<Coordinates> =
-                    drawable_dots.into_iter().map(|(coord, _)| *coord).collect();
-                coords.sort_by_key(|c| (c.y, c.x));
-
-                // 偶数行は逆順にする
-                let mut result = Vec::new();
-                let mut current_y = 0;
-                let mut row = Vec::new();
-
-                for coord in coords {
-                    if coord.y != current_y {
-                        if current_y % 2 == 1 {
-                            row.reverse();
-                        }
-                        result.extend(row);
-                        row = Vec::new();
-                        current_y = coord.y;
+                // 奇数行は逆順にする
+                for row in coords.chunk_by_mut(|a, b| a.y == b.y) {
+                    if row[0].y % 2 == 1 {
+                        row.reverse();
                     }
-                    row.push(coord);
                 }
-
-                if current_y % 2 == 1 {
-                    row.reverse();
-                }
-                result.extend(row);
-                result
             }
             DrawingStrategy::NearestNeighbor => {
                 // 最近傍探索（簡易版）
-                self.nearest_neighbor_path(drawable_dots)
+                self.nearest_neighbor_path(coords, grid, cancel)?;
             }
             DrawingStrategy::GreedyTwoOpt => {
                 // Greedy + 2-opt最適化
-                let path = self.nearest_neighbor_path(drawable_dots);
-                self.two_opt_optimize(path)
+                self.nearest_neighbor_path(coords, grid, cancel)?;
+                let stats = self.two_opt_optimize(coords, deadline, cancel);
+                if stats.stop_reason == TwoOptStopReason::Cancelled {
+                    return Err(PathCancelled);
+                }
+                return Ok(Some(stats));
             }
             DrawingStrategy::Spiral => {
                 // スパイラルパターン（未実装、ラスタースキャンにフォールバック）
-                let mut coords: Vec<Coordinates> =
-                    drawable_dots.into_iter().map(|(coord, _)| *coord).collect();
-                coords.sort_by_key(|c| (c.y, c.x));
-                coords
             }
-        };
-
-        let mut path = DrawingPath::new(coordinates);
-        path.calculate_estimated_time(&self.config);
-        path
+        }
+        Ok(None)
     }
 
     /// 最近傍探索でパスを生成（グリッド最適化版）
+    ///
+    /// `coords` の座標をグリッドへ移し、訪問順に `coords` へ書き戻す。
     fn nearest_neighbor_path(
         &self,
-        drawable_dots: Vec<(&Coordinates, &crate::domain::artwork::entities::Dot)>,
-    ) -> Vec<Coordinates> {
-        if drawable_dots.is_empty() {
-            return Vec::new();
+        coords: &mut [Coordinates],
+        grid: &mut BucketGrid,
+        cancel: &CancellationToken,
+    ) -> Result<(), PathCancelled> {
+        if coords.is_empty() {
+            return Ok(());
         }
 
-        let total_dots = drawable_dots.len();
-        let mut path = Vec::with_capacity(total_dots);
-
-        // グリッドサイズ（バケットサイズ）
-        // 320x120のキャンバスに対して10x10のグリッドを作成
-        const GRID_SIZE: i16 = 10;
-        const GRID_COLS: usize = (320 / GRID_SIZE as usize) + 1;
-        const GRID_ROWS: usize = (120 / GRID_SIZE as usize) + 1;
-
-        // グリッドの初期化
-        let mut grid: Vec<Vec<Vec<Coordinates>>> = vec![vec![Vec::new(); GRID_COLS]; GRID_ROWS];
-
         // 全点をグリッドに配置
-        for (coord, _) in drawable_dots {
-            let col = (coord.x as usize) / (GRID_SIZE as usize);
-            let row = (coord.y as usize) / (GRID_SIZE as usize);
-            if row < GRID_ROWS && col < GRID_COLS {
-                grid[row][col].push(*coord);
-            }
-        }
+        grid.fill(coords);
 
         // 最初の点（左上）を探す
         // グリッドの左上から順に探して最初に見つかった点を使用
-        let mut current = Coordinates::new(0, 0);
-        let mut found_start = false;
-
-        'start_search: for row in grid.iter_mut().take(GRID_ROWS) {
-            for bucket in row.iter_mut().take(GRID_COLS) {
-                if !bucket.is_empty() {
-                    // バケット内で最も左上の点を探す
-                    let mut min_idx = 0;
-                    let mut min_x = u16::MAX;
-                    let mut min_y = u16::MAX;
-
-                    for (i, p) in bucket.iter().enumerate() {
-                        if p.x < min_x || (p.x == min_x && p.y < min_y) {
-                            min_x = p.x;
-                            min_y = p.y;
-                            min_idx = i;
-                        }
-                    }
-
-                    // 開始点として設定し、リストから削除
-                    current = bucket.swap_remove(min_idx);
-                    path.push(current);
-                    found_start = true;
-                    break 'start_search;
-                }
-            }
-        }
-
-        if !found_start {
-            return Vec::new();
-        }
+        let Some(start_bucket) = (0..grid.rows * grid.cols).find(|&b| grid.lens[b] > 0) else {
+            return Ok(());
+        };
+        // バケット内で最も左上の点を探す
+        let min_idx = grid
+            .bucket(start_bucket)
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, p)| (p.x, p.y))
+            .map_or(0, |(i, _)| i);
+        let mut current = grid.remove(start_bucket, min_idx);
+        coords[0] = current;
 
         // 残りの点を探索
-        for _ in 1..total_dots {
-            let current_col = (current.x as usize) / (GRID_SIZE as usize);
-            let current_row = (current.y as usize) / (GRID_SIZE as usize);
+        for (index, slot) in coords.iter_mut().enumerate().skip(1) {
+            if index % CANCEL_CHECK_INTERVAL == 0 && cancel.is_cancelled() {
+                return Err(PathCancelled);
+            }
+            let current_col = current.x as usize / BucketGrid::CELL_SIZE;
+            let current_row = current.y as usize / BucketGrid::CELL_SIZE;
 
-            let mut nearest_dist = u32::MAX;
-            let mut nearest_point = Coordinates::new(0, 0);
-            let mut found_bucket_row = 0;
-            let mut found_bucket_col = 0;
-            let mut found_idx = 0;
-            let mut found = false;
+            let mut nearest: Option<(u32, Coordinates, usize, usize)> = None;
 
             // 近隣のバケットから探索範囲を広げていく
             // 半径0（自身のバケット）から開始
-            let max_radius = std::cmp::max(GRID_ROWS, GRID_COLS);
+            let max_radius = grid.rows.max(grid.cols);
 
-            'search: for radius in 0..=max_radius {
+            for radius in 0..=max_radius {
                 // 探索範囲のバケットをチェック
-                let r_min = (current_row as isize - radius as isize).max(0) as usize;
-                let r_max =
-                    (current_row as isize + radius as isize).min(GRID_ROWS as isize - 1) as usize;
-                let c_min = (current_col as isize - radius as isize).max(0) as usize;
-                let c_max =
-                    (current_col as isize + radius as isize).min(GRID_COLS as isize - 1) as usize;
-
-                let mut found_in_radius = false;
+                let r_min = current_row.saturating_sub(radius);
+                let r_max = (current_row + radius).min(grid.rows - 1);
+                let c_min = current_col.saturating_sub(radius);
+                let c_max = (current_col + radius).min(grid.cols - 1);
 
-                for (r, row) in grid.iter().enumerate().take(r_max + 1).skip(r_min) {
-                    for (c, _) in row.iter().enumerate().take(c_max + 1).skip(c_min) {
+                for r in r_min..=r_max {
+                    for c in c_min..=c_max {
                         // 半径のエッジにあるバケットのみをチェック（内側は既にチェック済み）
                         // ただしradius=0の場合はチェックする
                         let is_edge =
                             radius == 0 || r == r_min || r == r_max || c == c_min || c == c_max;
+                        if !is_edge {
+                            continue;
+                        }
 
-                        if is_edge && !grid[r][c].is_empty() {
-                            for (i, p) in grid[r][c].iter().enumerate() {
-                                let dist = current.manhattan_distance_to(p);
-                                if dist < nearest_dist {
-                                    nearest_dist = dist;
-                                    nearest_point = *p;
-                                    found_bucket_row = r;
-                                    found_bucket_col = c;
-                                    found_idx = i;
-                                    found = true;
-                                    found_in_radius = true;
-                                }
+                        let bucket = r * grid.cols + c;
+                        for (i, p) in grid.bucket(bucket).iter().enumerate() {
+                            let dist = current.manhattan_distance_to(p);
+                            // 同じ距離の場合は (y, x) の小さい点を選ぶ
+                            let closer = nearest.is_none_or(|(nearest_dist, nearest_point, ..)| {
+                                dist < nearest_dist
+                                    || (dist == nearest_dist
+                                        && (p.y, p.x) < (nearest_point.y, nearest_point.x))
+                            });
+                            if closer {
+                                nearest = Some((dist, *p, bucket, i));
                             }
                         }
                     }
@@ -280,48 +401,83 @@ impl ArtworkToCommandConverter {
                 // この半径で見つかり、かつ次の半径の最小距離よりも近ければ確定
                 // （マンハッタン距離なので、グリッド境界までの距離を考慮する必要があるが、
                 //  簡易的に「見つかったら終了」とする。厳密な最近傍でなくても十分）
-                if found_in_radius {
-                    break 'search;
+                if nearest.is_some() {
+                    break;
                 }
             }
 
-            if found {
-                // 見つかった点を削除してパスに追加
-                grid[found_bucket_row][found_bucket_col].swap_remove(found_idx);
-                current = nearest_point;
-                path.push(current);
-            } else {
-                break; // 点が見つからない（通常ありえない）
-            }
+            let Some((_, _, bucket, idx)) = nearest else {
+                unreachable!("grid holds exactly the unvisited dots");
+            };
+            // 見つかった点を削除してパスに追加
+            current = grid.remove(bucket, idx);
+            *slot = current;
         }
-
-        path
+        Ok(())
     }
 
     /// 2-optアルゴリズムによるパスの最適化
-    fn two_opt_optimize(&self, mut path: Vec<Coordinates>) -> Vec<Coordinates> {
+    ///
+    /// 近傍の点だけを入れ替え候補にしてO(N*K)に抑える（Kは `two_opt_window` で決める）。
+    /// 入れ替えがなくなるか、1反復の短縮率が下限を下回るか、`deadline` を過ぎるか、
+    /// `cancel` が取り消されたら打ち切る。
+    /// 反復の途中で打ち切っても、それまでの入れ替えはすべて有効な経路のまま残る。
+    fn two_opt_optimize(
+        &self,
+        path: &mut [Coordinates],
+        deadline: Instant,
+        cancel: &CancellationToken,
+    ) -> TwoOptStats {
+        let started = Instant::now();
         let n = path.len();
+        let distance_before = path_distance(path);
+        let window = two_opt_window(path);
+        let mut stats = TwoOptStats {
+            iterations: 0,
+            elapsed_ms: 0,
+            distance_before,
+            distance_after: distance_before,
+            window,
+            stop_reason: TwoOptStopReason::Converged,
+        };
         if n < 4 {
-            return path;
+            return stats;
         }
 
-        let mut improved = true;
-        let mut iterations = 0;
-        // 無限ループ防止と処理時間制限のための最大反復回数
-        const MAX_ITERATIONS: usize = 50;
+        // 時刻の確認は一定間隔ごとに行う（毎回だと比較より重い）
+        const DEADLINE_CHECK_INTERVAL: usize = 256;
 
-        // 探索ウィンドウサイズ（近傍のみを探索して計算量を削減）
-        // 全点対全点だとO(N^2)で38400点の場合に数分かかるため、
-        // 前後500点程度に制限してO(N*K)にする
-        const WINDOW_SIZE: usize = 500;
-
-        while improved && iterations < MAX_ITERATIONS {
-            improved = false;
-            iterations += 1;
+        let mut distance = distance_before;
+        'iterations: loop {
+            if cancel.is_cancelled() {
+                stats.stop_reason = TwoOptStopReason::Cancelled;
+                break;
+            }
+            if Instant::now() >= deadline {
+                stats.stop_reason = TwoOptStopReason::TimeBudget;
+                break;
+            }
+            stats.iterations += 1;
+            let mut gain: u64 = 0;
 
             for i in 0..n - 2 {
+                if i % DEADLINE_CHECK_INTERVAL == 0 && i > 0 {
+                    let stop_reason = if cancel.is_cancelled() {
+                        Some(TwoOptStopReason::Cancelled)
+                    } else if Instant::now() >= deadline {
+                        Some(TwoOptStopReason::TimeBudget)
+                    } else {
+                        None
+                    };
+                    if let Some(stop_reason) = stop_reason {
+                        distance -= gain;
+                        stats.stop_reason = stop_reason;
+                        break 'iterations;
+                    }
+                }
+
                 // jはi+2から開始し、ウィンドウサイズまたは配列末尾まで
-                let end_j = std::cmp::min(i + WINDOW_SIZE, n - 1);
+                let end_j = std::cmp::min(i + window, n - 1);
 
                 for j in i + 2..end_j {
                     let p1 = path[i];
@@ -339,24 +495,38 @@ impl ArtworkToCommandConverter {
                     if new_dist < current_dist {
                         // セグメント[i+1..=j]を反転
                         path[i + 1..=j].reverse();
-                        improved = true;
+                        gain += (current_dist - new_dist) as u64;
                     }
                 }
             }
-        }
 
-        info!(
-            "2-opt optimization finished after {} iterations",
-            iterations
-        );
+            if gain == 0 {
+                stats.stop_reason = TwoOptStopReason::Converged;
+                break;
+            }
+            let improvement = gain as f64 / distance as f64;
+            distance -= gain;
+            if improvement < self.two_opt.min_improvement {
+                stats.stop_reason = TwoOptStopReason::MarginalGain;
+                break;
+            }
+        }
 
-        path
+        stats.distance_after = distance;
+        stats.elapsed_ms = started.elapsed().as_millis() as u64;
+        stats
     }
 
     /// 描画コマンドを生成
+    ///
+    /// 実機での描画と同じく、移動 → ニュートラルクリア → Aボタン×`repeats` の順に入力する
+    /// （消しゴムの場合はAの代わりにBを押す）。
     fn create_drawing_commands(&self, path: &DrawingPath) -> Vec<ControllerCommand> {
         let mut commands = Vec::new();
-        let mut current_pos = Coordinates::new(0, 0); // 開始位置
+        let mut current_pos = Coordinates::origin(); // 開始位置
+        let timing = self.config.timing;
+        let dot_button = self.config.drawing_mode.dot_button();
+        let mut dot_index = 0u64;
 
         // バッチサイズ（1コマンドあたりのドット数）
         const BATCH_SIZE: usize = 100;
@@ -365,6 +535,15 @@ impl ArtworkToCommandConverter {
             let mut command = ControllerCommand::new(format!("Draw Batch {}", batch_idx + 1))
                 .with_description(format!("{}個のドットを描画", chunk.len()));
 
+            if batch_idx == 0
+                && let Some(entry_point) = self.config.options.entry_point
+            {
+                for action in self.create_move_actions(&current_pos, &entry_point) {
+                    command = command.add_action(action);
+                }
+                current_pos = entry_point;
+            }
+
             for target in chunk {
                 // 現在位置から目標位置への移動コマンドを追加
                 let move_actions = self.create_move_actions(&current_pos, target);
@@ -372,13 +551,25 @@ impl ArtworkToCommandConverter {
                     command = command.add_action(action);
                 }
 
-                // ドットを描画
-                command = command
-                    .add_action(ControllerAction::press_button(
-                        Button::A,
-                        self.config.dot_draw_delay_ms,
-                    ))
-                    .add_action(ControllerAction::release_button(Button::A, 50));
+                // ドットを描画（移動のタップで十字キーはニュートラルに戻っているため、クリアは設定の間隔だけ）
+                if self.config.options.neutral_clear.forced_at(dot_index) {
+                    command = command.add_action(ControllerAction::set_dpad(
+                        DPad::NEUTRAL,
+                        NEUTRAL_CLEAR_MS as u32,
+                    ));
+                }
+                dot_index += 1;
+                for _ in 0..self.config.options.repeats.max(1) {
+                    command = command
+                        .add_action(ControllerAction::press_button(dot_button, timing.press_ms))
+                        .add_action(ControllerAction::release_button(
+                            dot_button,
+                            timing.release_ms,
+                        ));
+                    if timing.wait_ms > 0 {
+                        command = command.add_action(ControllerAction::wait(timing.wait_ms));
+                    }
+                }
 
                 current_pos = *target;
             }
@@ -389,56 +580,19 @@ impl ArtworkToCommandConverter {
         commands
     }
 
-    /// 2点間の移動アクションを生成
+    /// 2点間の移動アクションを生成（1マスごとに押下 → ニュートラル → 待機）
     fn create_move_actions(&self, from: &Coordinates, to: &Coordinates) -> Vec<ControllerAction> {
+        let timing = self.config.timing;
         let mut actions = Vec::new();
-        let mut current = *from;
-
-        // X方向の移動
-        while current.x != to.x {
-            let direction = if to.x > current.x {
-                CursorDirection::Right
-            } else {
-                CursorDirection::Left
-            };
-
-            actions.push(ControllerAction::set_dpad(
-                direction.to_dpad(),
-                self.config.cursor_speed_ms,
-            ));
 
-            if to.x > current.x {
-                current.x += 1;
-            } else {
-                current.x -= 1;
+        for step in from.steps_to(to, self.config.options.diagonal_moves) {
+            actions.push(ControllerAction::set_dpad(step, timing.press_ms));
+            actions.push(ControllerAction::set_dpad(DPad::NEUTRAL, timing.release_ms));
+            if timing.wait_ms > 0 {
+                actions.push(ControllerAction::wait(timing.wait_ms));
             }
         }
 
-        // Y方向の移動
-        while current.y != to.y {
-            let direction = if to.y > current.y {
-                CursorDirection::Down
-            } else {
-                CursorDirection::Up
-            };
-
-            actions.push(ControllerAction::set_dpad(
-                direction.to_dpad(),
-                self.config.cursor_speed_ms,
-            ));
-
-            if to.y > current.y {
-                current.y += 1;
-            } else {
-                current.y -= 1;
-            }
-        }
-
-        // 移動後はニュートラルに戻す
-        if !actions.is_empty() {
-            actions.push(ControllerAction::set_dpad(DPad::NEUTRAL, 50));
-        }
-
         actions
     }
 
@@ -452,20 +606,395 @@ impl ArtworkToCommandConverter {
     }
 }
 
+/// 座標列の総移動距離（マンハッタン距離）
+fn path_distance(path: &[Coordinates]) -> u64 {
+    path.windows(2)
+        .map(|pair| pair[0].manhattan_distance_to(&pair[1]) as u64)
+        .sum()
+}
+
+/// 2-optで入れ替え候補にする前後の点数
+///
+/// ドットが敷き詰められたキャンバスでは近い点が経路上でも近くにあるため前後 `BASE` 点で十分だが、
+/// 疎なアートワークでは同じ範囲の空間に含まれる点が少ないので、密度に反比例して広げる。
+/// 点が少なければ全域を探索する。
+fn two_opt_window(path: &[Coordinates]) -> usize {
+    /// 全ドットが描画対象のキャンバスでの探索ウィンドウ
+    const BASE: usize = 500;
+    /// これ以下の点数なら全域を探索する
+    const FULL_RANGE_DOTS: usize = 2000;
+
+    let n = path.len();
+    if n <= FULL_RANGE_DOTS {
+        return n;
+    }
+    let (min_x, min_y, max_x, max_y) = path.iter().fold(
+        (u16::MAX, u16::MAX, 0, 0),
+        |(min_x, min_y, max_x, max_y), c| {
+            (
+                min_x.min(c.x),
+                min_y.min(c.y),
+                max_x.max(c.x),
+                max_y.max(c.y),
+            )
+        },
+    );
+    let area = (max_x - min_x) as usize + 1;
+    let area = area * ((max_y - min_y) as usize + 1);
+    // density = n / area なので BASE / density = BASE * area / n
+    (BASE * area).div_ceil(n).clamp(BASE, n)
+}
+
+/// 最近傍探索用のバケットグリッド
+///
+/// 全バケットの座標を1本の配列に詰め、各バケットは `starts[b]..starts[b] + lens[b]` の区間を使う。
+/// 取り出した座標は区間末尾と入れ替えて長さを縮めるため、探索中に確保は発生しない。
+/// レイヤー間で使い回すと前回確保した領域を再利用する。
+#[derive(Debug, Default)]
+struct BucketGrid {
+    cols: usize,
+    rows: usize,
+    starts: Vec<usize>,
+    lens: Vec<usize>,
+    items: Vec<Coordinates>,
+}
+
+impl BucketGrid {
+    /// 1バケットの一辺（ドット）
+    const CELL_SIZE: usize = 10;
+
+    /// 座標を振り分ける（グリッドの大きさは座標の最大値に合わせる）
+    fn fill(&mut self, coords: &[Coordinates]) {
+        let (max_x, max_y) = coords
+            .iter()
+            .fold((0, 0), |(x, y), c| (x.max(c.x), y.max(c.y)));
+        self.cols = max_x as usize / Self::CELL_SIZE + 1;
+        self.rows = max_y as usize / Self::CELL_SIZE + 1;
+        let buckets = self.rows * self.cols;
+
+        self.lens.clear();
+        self.lens.resize(buckets, 0);
+        for coord in coords {
+            let bucket = self.bucket_of(coord);
+            self.lens[bucket] += 1;
+        }
+
+        self.starts.clear();
+        let mut offset = 0;
+        for len in &mut self.lens {
+            self.starts.push(offset);
+            offset += *len;
+            *len = 0;
+        }
+
+        // 入力順（(y, x) 順）のまま各バケットに詰める
+        self.items.clear();
+        self.items.resize(coords.len(), Coordinates::new(0, 0));
+        for coord in coords {
+            let bucket = self.bucket_of(coord);
+            self.items[self.starts[bucket] + self.lens[bucket]] = *coord;
+            self.lens[bucket] += 1;
+        }
+    }
+
+    fn bucket_of(&self, coord: &Coordinates) -> usize {
+        (coord.y as usize / Self::CELL_SIZE) * self.cols + coord.x as usize / Self::CELL_SIZE
+    }
+
+    fn bucket(&self, bucket: usize) -> &[Coordinates] {
+        let start = self.starts[bucket];
+        &self.items[start..start + self.lens[bucket]]
+    }
+
+    /// バケット内の `index` 番目の座標を取り出す
+    fn remove(&mut self, bucket: usize, index: usize) -> Coordinates {
+        let start = self.starts[bucket];
+        let last = start + self.lens[bucket] - 1;
+        self.items.swap(start + index, last);
+        self.lens[bucket] -= 1;
+        self.items[last]
+    }
+}
+
+/// 十字キー入力の連続回数ごとに挿入する、ドリフト防止の待機時間
+pub const DRIFT_PAUSE_EVERY_DPAD_OPS: u64 = 15;
+/// ドリフト防止の待機時間（ミリ秒）
+pub const DRIFT_PAUSE_MS: u64 = 100;
+/// 移動方向が切り替わる際の待機時間（ミリ秒）
+pub const DIRECTION_CHANGE_DELAY_MS: u64 = 50;
+/// 描画前のニュートラルクリアに掛かる時間（押下＋解放、ミリ秒）
+pub const NEUTRAL_CLEAR_MS: u64 = 20;
+/// 左スティックで左上へ戻る再ホーミングに掛かる時間（移動＋中立＋待機、ミリ秒）
+pub const REHOME_MS: u64 = 5000 + 100 + 500;
+
+/// 書き込み遅延の指数移動平均に使う平滑化係数
+const ADAPTIVE_EWMA_ALPHA: f64 = 0.2;
+
+/// 書き込み遅延を観測してドット間の待機時間を調整する
+///
+/// 平均遅延が閾値を超えている間は待機時間を倍率分ずつ延ばし、
+/// 回復すると下限との差を1/4ずつ縮めて徐々に戻す。
+#[derive(Debug, Clone)]
+pub struct AdaptiveTimingController {
+    settings: AdaptiveTimingSettings,
+    latency_ewma_ms: Option<f64>,
+    wait_ms: u32,
+}
+
+impl AdaptiveTimingController {
+    pub fn new(settings: AdaptiveTimingSettings, initial_wait_ms: u32) -> Self {
+        Self {
+            settings,
+            latency_ewma_ms: None,
+            wait_ms: initial_wait_ms.clamp(settings.min_wait_ms, settings.max_wait_ms),
+        }
+    }
+
+    /// コマンド1回分の遅延（想定時間を超過した分）を記録し、次の待機時間を返す
+    ///
+    /// `configured_wait_ms` はユーザー設定の待機時間で、下限と同様にこれより短くはしない。
+    pub fn observe(&mut self, latency: Duration, configured_wait_ms: u32) -> u32 {
+        let latency_ms = latency.as_secs_f64() * 1000.0;
+        let ewma = match self.latency_ewma_ms {
+            Some(previous) => previous + ADAPTIVE_EWMA_ALPHA * (latency_ms - previous),
+            None => latency_ms,
+        };
+        self.latency_ewma_ms = Some(ewma);
+
+        let floor = self.settings.min_wait_ms.max(configured_wait_ms);
+        let ceiling = self.settings.max_wait_ms.max(floor);
+
+        let next = if ewma > self.settings.latency_threshold_ms as f64 {
+            let scaled = self.wait_ms.max(1) as u64 * self.settings.scale_percent as u64 / 100;
+            scaled.max(self.wait_ms as u64 + 1) as u32
+        } else {
+            let excess = self.wait_ms.saturating_sub(floor);
+            self.wait_ms - excess.div_ceil(4)
+        };
+        self.wait_ms = next.clamp(floor, ceiling);
+        self.wait_ms
+    }
+
+    /// 現在の待機時間（ミリ秒）
+    pub fn wait_ms(&self) -> u32 {
+        self.wait_ms
+    }
+
+    /// 書き込み遅延の平均（ミリ秒、未観測なら0）
+    pub fn latency_ewma_ms(&self) -> f64 {
+        self.latency_ewma_ms.unwrap_or(0.0)
+    }
+}
+
+/// 描画パスを実機と同じ手順でシミュレーションし、操作回数と所要時間を見積もる
+///
+/// 原点(0, 0)から開始し、`entry_point` があればそこへ移動してからパスを辿る。
+/// 各ドットでは移動 → ニュートラルクリア（`neutral_clear` の設定で省略）→ Aボタン×`repeats` の順に入力する。
+pub fn simulate_run(path: &DrawingPath, timing: &PaintTiming, options: &RunOptions) -> RunEstimate {
+    simulate_layers(path, timing, options)
+        .iter()
+        .fold(RunEstimate::default(), |total, layer| RunEstimate {
+            dpad_ops: total.dpad_ops + layer.estimate.dpad_ops,
+            a_presses: total.a_presses + layer.estimate.a_presses,
+            neutral_clears: total.neutral_clears + layer.estimate.neutral_clears,
+            total_ms: total.total_ms + layer.estimate.total_ms,
+        })
+}
+
+/// `simulate_run` の見積もりをレイヤーごとに分けて返す
+///
+/// 2番目以降のレイヤーは開始前に左上へ再ホーミングするため、原点から移動を始める。
+pub fn simulate_layers(
+    path: &DrawingPath,
+    timing: &PaintTiming,
+    options: &RunOptions,
+) -> Vec<LayerEstimate> {
+    let mut current = Coordinates::origin();
+    // ドリフト防止の待機は描画全体の累計入力回数で判定される
+    let mut total_dpad_ops = 0u64;
+    let mut dot_index = 0u64;
+    let mut layers = Vec::new();
+
+    let mut simulate_move = |estimate: &mut RunEstimate, from: Coordinates, to: Coordinates| {
+        let mut previous: Option<DPad> = None;
+        for step in from.steps_to(&to, options.diagonal_moves) {
+            if previous.is_some_and(|p| p != step) {
+                estimate.total_ms += DIRECTION_CHANGE_DELAY_MS;
+            }
+            estimate.total_ms += timing.tap_ms();
+            estimate.dpad_ops += 1;
+            total_dpad_ops += 1;
+            if total_dpad_ops.is_multiple_of(DRIFT_PAUSE_EVERY_DPAD_OPS) {
+                estimate.total_ms += DRIFT_PAUSE_MS;
+            }
+            previous = Some(step);
+        }
+    };
+
+    for (index, (layer, coordinates)) in path.layer_segments().into_iter().enumerate() {
+        let mut estimate = RunEstimate::default();
+
+        if index == 0 {
+            if let Some(entry_point) = options.entry_point {
+                simulate_move(&mut estimate, current, entry_point);
+                current = entry_point;
+            }
+        } else {
+            estimate.total_ms += REHOME_MS;
+            current = Coordinates::origin();
+        }
+
+        for target in coordinates {
+            simulate_move(&mut estimate, current, *target);
+            current = *target;
+
+            // 実機では十字キーの状態が分からないときもクリアするが、エラーの無い描画では間隔の分だけになる
+            if options.neutral_clear.forced_at(dot_index) {
+                estimate.neutral_clears += 1;
+                estimate.total_ms += NEUTRAL_CLEAR_MS;
+            }
+            dot_index += 1;
+
+            estimate.a_presses += options.repeats as u64;
+            estimate.total_ms += options.repeats as u64 * timing.tap_ms();
+        }
+
+        layers.push(LayerEstimate {
+            layer,
+            dots: coordinates.len(),
+            estimate,
+        });
+    }
+
+    layers
+}
+
+/// キャリブレーションパターンのドット配置を計算
+///
+/// `Border` 以外はパターンをキャンバス中央に配置し、各行（列）を往復しながら描画する。
+/// `Horizontal`・`Vertical` の i 行目は「(i+1)px描画 + (i+1)px空白」を進行方向に繰り返す。
+/// `Border` はキャンバスの外周を左上から時計回りに `spacing` 間隔で辿る。
+pub fn calibration_plan(
+    layout: &CalibrationLayout,
+    canvas_width: u16,
+    canvas_height: u16,
+) -> Result<CalibrationPlan, CalibrationLayoutError> {
+    if layout.spacing == 0 || canvas_width == 0 || canvas_height == 0 {
+        return Err(CalibrationLayoutError::EmptySize);
+    }
+    if layout.pattern != CalibrationPattern::Border && (layout.rows == 0 || layout.width == 0) {
+        return Err(CalibrationLayoutError::EmptySize);
+    }
+
+    let spacing = layout.spacing as u32;
+    let rows = layout.rows as u32;
+    let length = layout.width as u32;
+    let span = rows.saturating_sub(1) * spacing + 1;
+    let columns = length.saturating_sub(1) / spacing + 1;
+
+    let (width, height) = match layout.pattern {
+        CalibrationPattern::Horizontal => (length, span),
+        CalibrationPattern::Vertical => (span, length),
+        CalibrationPattern::Grid => ((columns - 1) * spacing + 1, span),
+        CalibrationPattern::Border => (canvas_width as u32, canvas_height as u32),
+    };
+    if width > canvas_width as u32 || height > canvas_height as u32 {
+        return Err(CalibrationLayoutError::OutOfBounds {
+            width,
+            height,
+            canvas_width,
+            canvas_height,
+        });
+    }
+
+    // 行 `row` の進行方向 `position` 番目の相対座標（奇数行は逆向き）
+    let stripe = |row: u32, position: u32| -> (u32, u32) {
+        let along = if row.is_multiple_of(2) {
+            position
+        } else {
+            length - 1 - position
+        };
+        (along, row * spacing)
+    };
+
+    let mut relative = Vec::new();
+    match layout.pattern {
+        CalibrationPattern::Horizontal | CalibrationPattern::Vertical => {
+            for row in 0..rows {
+                let segment = row + 1;
+                for position in (0..length).filter(|p| (p / segment) % 2 == 0) {
+                    let (along, across) = stripe(row, position);
+                    relative.push(if layout.pattern == CalibrationPattern::Horizontal {
+                        (along, across)
+                    } else {
+                        (across, along)
+                    });
+                }
+            }
+        }
+        CalibrationPattern::Grid => {
+            for row in 0..rows {
+                for column in 0..columns {
+                    let column = if row.is_multiple_of(2) {
+                        column
+                    } else {
+                        columns - 1 - column
+                    };
+                    relative.push((column * spacing, row * spacing));
+                }
+            }
+        }
+        CalibrationPattern::Border => {
+            let (right, bottom) = (width - 1, height - 1);
+            let perimeter = (2 * (right + bottom)).max(1);
+            for t in (0..perimeter).step_by(spacing as usize) {
+                relative.push(if t < right {
+                    (t, 0)
+                } else if t < right + bottom {
+                    (right, t - right)
+                } else if t < 2 * right + bottom {
+                    (right - (t - right - bottom), bottom)
+                } else {
+                    (0, bottom - (t - 2 * right - bottom))
+                });
+            }
+        }
+    }
+
+    let origin = Coordinates::new(
+        ((canvas_width as u32 - width) / 2) as u16,
+        ((canvas_height as u32 - height) / 2) as u16,
+    );
+    let dots = relative
+        .into_iter()
+        .map(|(x, y)| Coordinates::new(origin.x + x as u16, origin.y + y as u16))
+        .collect();
+
+    Ok(CalibrationPlan {
+        origin,
+        width: width as u16,
+        height: height as u16,
+        dots,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::painting::value_objects::DrawingMode;
+    use crate::domain::controller::ActionType;
+    use crate::domain::painting::init_sequence::InitPreset;
+    use crate::domain::painting::value_objects::{DrawingMode, NeutralClearSettings};
+    use crate::domain::shared::value_objects::Color;
 
     #[test]
     fn test_two_opt_optimize_removes_crossing() {
         let config = DrawingCanvasConfig {
             width: 100,
             height: 100,
-            cursor_speed_ms: 10,
-            dot_draw_delay_ms: 10,
-            line_wrap_delay_ms: 10,
+            timing: PaintTiming::new(10, 10, 10),
+            options: RunOptions::default(),
             drawing_mode: DrawingMode::PixelPen,
+            init_sequence: InitSequence::default(),
         };
         let strategy = DrawingStrategy::GreedyTwoOpt;
         let converter = ArtworkToCommandConverter::new(config, strategy);
@@ -479,7 +1008,12 @@ mod tests {
             Coordinates::new(10, 0),
         ];
 
-        let optimized = converter.two_opt_optimize(path.clone());
+        let mut optimized = path.clone();
+        let stats = converter.two_opt_optimize(
+            &mut optimized,
+            Instant::now() + Duration::from_secs(1),
+            &CancellationToken::default(),
+        );
 
         // Calculate distances
         let original_dist: u32 = path
@@ -499,6 +1033,10 @@ mod tests {
             optimized_dist < original_dist,
             "Optimized path should be shorter"
         );
+        assert_eq!(stats.distance_before, original_dist as u64);
+        assert_eq!(stats.distance_after, optimized_dist as u64);
+        assert_eq!(stats.window, path.len());
+        assert_eq!(stats.stop_reason, TwoOptStopReason::Converged);
         assert_eq!(
             optimized.len(),
             path.len(),
@@ -512,4 +1050,727 @@ mod tests {
         // If i=0, p1=path[0]. We swap path[i+1..=j]. So path[0] is never moved.
         assert_eq!(optimized[0], path[0], "Start point should be preserved");
     }
+
+    #[test]
+    fn test_create_drawing_path_respects_region_bounds() {
+        let mut canvas = Canvas::new(20, 20);
+        for (x, y) in [(2, 2), (5, 5), (6, 5), (5, 6), (10, 10)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+
+        // 左上(2,2)を含み、右下(6,6)を含まない
+        let region = CanvasRegion::new(2, 2, 4, 4);
+        let converter = ArtworkToCommandConverter::new(
+            DrawingCanvasConfig::default(),
+            DrawingStrategy::RasterScan,
+        )
+        .with_region(region);
+        let path = converter.create_drawing_path(&canvas);
+
+        assert_eq!(
+            path.coordinates,
+            vec![Coordinates::new(2, 2), Coordinates::new(5, 5)]
+        );
+    }
+
+    #[test]
+    fn test_create_drawing_path_from_row_combines_with_region_and_records_rows() {
+        let mut canvas = Canvas::new(20, 20);
+        for (x, y) in [(2, 2), (3, 3), (5, 4), (2, 5), (10, 5), (4, 6)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        let config = DrawingCanvasConfig {
+            options: RunOptions {
+                origin: Some(Coordinates::new(100, 50)),
+                ..RunOptions::default()
+            },
+            ..DrawingCanvasConfig::default()
+        };
+
+        // 領域 (2,2)-(5,5) の中で、4行目以降だけを描く
+        let converter = ArtworkToCommandConverter::new(config, DrawingStrategy::ZigZag)
+            .with_region(CanvasRegion::new(2, 2, 4, 4))
+            .with_from_row(4);
+        let path = converter.create_drawing_path(&canvas);
+        assert_eq!(
+            path.coordinates,
+            vec![Coordinates::new(105, 54), Coordinates::new(102, 55)]
+        );
+        // 行はゲーム内キャンバスではなくアートワークの座標で記録する
+        assert_eq!(path.rows, vec![4, 5]);
+        assert_eq!(
+            path.row_dot_counts().into_iter().collect::<Vec<_>>(),
+            vec![(4, 1), (5, 1)]
+        );
+        assert_eq!(
+            converter.dots_before_from_row(&canvas),
+            vec![Coordinates::new(2, 2), Coordinates::new(3, 3)]
+        );
+    }
+
+    #[test]
+    fn test_create_drawing_path_places_artwork_at_origin() {
+        let mut canvas = Canvas::new(20, 20);
+        for (x, y) in [(2, 2), (5, 5), (10, 10)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        let config = |origin| DrawingCanvasConfig {
+            options: RunOptions {
+                origin,
+                ..RunOptions::default()
+            },
+            ..DrawingCanvasConfig::default()
+        };
+
+        // 領域はアートワークの座標で絞り込み、その後で平行移動する
+        let origin = Coordinates::new(300, 100);
+        let path =
+            ArtworkToCommandConverter::new(config(Some(origin)), DrawingStrategy::RasterScan)
+                .with_region(CanvasRegion::new(2, 2, 4, 4))
+                .create_drawing_path(&canvas);
+        assert_eq!(
+            path.coordinates,
+            vec![Coordinates::new(302, 102), Coordinates::new(305, 105)]
+        );
+
+        // 左上からの移動が長くなる分だけ見積もりが延びる
+        let placed =
+            ArtworkToCommandConverter::new(config(Some(origin)), DrawingStrategy::RasterScan)
+                .create_drawing_path(&canvas);
+        let unplaced = ArtworkToCommandConverter::new(config(None), DrawingStrategy::RasterScan)
+            .create_drawing_path(&canvas);
+        assert_eq!(placed.total_distance, unplaced.total_distance);
+        assert!(placed.estimated_time_ms > unplaced.estimated_time_ms);
+        let timing = PaintTiming::default();
+        let extra_ops = simulate_run(&placed, &timing, &config(Some(origin)).options).dpad_ops
+            - simulate_run(&unplaced, &timing, &RunOptions::default()).dpad_ops;
+        assert_eq!(extra_ops, 400);
+    }
+
+    /// 上下・左右が非対称な「F」の字（5x3）を左上に置いたキャンバス
+    fn f_glyph_canvas() -> Canvas {
+        let mut canvas = Canvas::new(8, 8);
+        for (y, row) in ["###", "#..", "##.", "#..", "#.."].iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                if cell == '#' {
+                    canvas
+                        .set_dot(Coordinates::new(x as u16, y as u16), Dot::black())
+                        .unwrap();
+                }
+            }
+        }
+        canvas
+    }
+
+    #[test]
+    fn test_asymmetric_glyph_starts_at_top_left_without_mirroring() {
+        let canvas = f_glyph_canvas();
+        let top_row = [
+            Coordinates::new(0, 0),
+            Coordinates::new(1, 0),
+            Coordinates::new(2, 0),
+        ];
+        for strategy in [
+            DrawingStrategy::RasterScan,
+            DrawingStrategy::ZigZag,
+            DrawingStrategy::NearestNeighbor,
+            DrawingStrategy::GreedyTwoOpt,
+        ] {
+            let path = ArtworkToCommandConverter::new(DrawingCanvasConfig::default(), strategy)
+                .create_drawing_path(&canvas);
+            assert_eq!(path.coordinates.len(), 8, "{strategy:?}");
+            assert_eq!(path.coordinates[..3], top_row, "{strategy:?}");
+            assert_eq!(path.rows[..3], [0, 0, 0], "{strategy:?}");
+        }
+
+        // 横棒を左から描くため、原点で1つ描いた後の最初の移動は右
+        let artwork = Artwork::new(
+            crate::domain::artwork::entities::ArtworkMetadata::new("F".to_string()),
+            "png".to_string(),
+            canvas.clone(),
+        );
+        let commands = ArtworkToCommandConverter::new(
+            DrawingCanvasConfig::default(),
+            DrawingStrategy::RasterScan,
+        )
+        .convert(&artwork);
+        let first_move = commands
+            .iter()
+            .filter(|command| command.name.starts_with("Draw Batch"))
+            .flat_map(|command| &command.sequence)
+            .find_map(|action| match action.action_type {
+                ActionType::SetDPad(dpad) if dpad != DPad::NEUTRAL => Some(dpad),
+                _ => None,
+            });
+        assert_eq!(first_move, Some(DPad::RIGHT));
+
+        // 配置先を指定しても向きは変わらない
+        let config = DrawingCanvasConfig {
+            options: RunOptions {
+                origin: Some(Coordinates::new(100, 50)),
+                ..RunOptions::default()
+            },
+            ..DrawingCanvasConfig::default()
+        };
+        let path = ArtworkToCommandConverter::new(config, DrawingStrategy::RasterScan)
+            .create_drawing_path(&canvas);
+        assert_eq!(
+            path.coordinates[..4],
+            [
+                Coordinates::new(100, 50),
+                Coordinates::new(101, 50),
+                Coordinates::new(102, 50),
+                Coordinates::new(100, 51),
+            ]
+        );
+        assert_eq!(path.rows[..4], [0, 0, 0, 1]);
+    }
+
+    #[test]
+    fn test_select_drawing_mode_command_follows_mode() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas
+            .set_dot(Coordinates::new(1, 1), Dot::black())
+            .unwrap();
+        let artwork = Artwork::new(
+            crate::domain::artwork::entities::ArtworkMetadata::new("mode".to_string()),
+            "png".to_string(),
+            canvas,
+        );
+        let presses = |command: &ControllerCommand| -> Vec<Button> {
+            command
+                .sequence
+                .iter()
+                .filter_map(|action| match action.action_type {
+                    ActionType::PressButton(button) => Some(button),
+                    _ => None,
+                })
+                .collect()
+        };
+        let convert = |mode: DrawingMode, preset: InitPreset| {
+            let config = DrawingCanvasConfig {
+                drawing_mode: mode,
+                init_sequence: InitSequence::preset_for(preset, mode),
+                ..DrawingCanvasConfig::default()
+            };
+            ArtworkToCommandConverter::new(config, DrawingStrategy::RasterScan).convert(&artwork)
+        };
+
+        let l5 = [Button::L; 5];
+        for (mode, selection, dot_button) in [
+            (DrawingMode::PixelPen, l5.to_vec(), Button::A),
+            (
+                DrawingMode::Brush,
+                [&l5[..], &[Button::R]].concat(),
+                Button::A,
+            ),
+            (
+                DrawingMode::ThickBrush,
+                [&l5[..], &[Button::R, Button::R]].concat(),
+                Button::A,
+            ),
+            (DrawingMode::Eraser, l5.to_vec(), Button::B),
+        ] {
+            // 初期化手順がない場合は、描画モードの選択コマンドでペンを選ぶ
+            let commands = convert(mode, InitPreset::None);
+            let select = commands
+                .iter()
+                .find(|command| command.name == "Select Drawing Mode")
+                .unwrap();
+            assert_eq!(presses(select), selection, "{mode}");
+            let draw = commands
+                .iter()
+                .find(|command| command.name.starts_with("Draw Batch"))
+                .unwrap();
+            assert_eq!(presses(draw), [dot_button], "{mode}");
+
+            // プリセットの初期化手順がペンを選ぶ場合は繰り返さない
+            let commands = convert(mode, InitPreset::Splatoon3PostEditor);
+            assert!(
+                commands
+                    .iter()
+                    .all(|command| command.name != "Select Drawing Mode")
+            );
+            assert_eq!(presses(&commands[0]), selection, "{mode}");
+        }
+    }
+
+    #[test]
+    fn test_estimate_and_commands_use_config_timing() {
+        let mut canvas = Canvas::new(20, 10);
+        for (x, y) in [(3, 1), (8, 4), (2, 6)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        let config = DrawingCanvasConfig::new(
+            PaintTiming::new(30, 20, 10),
+            RunOptions {
+                repeats: 2,
+                ..RunOptions::default()
+            },
+        );
+        let converter = ArtworkToCommandConverter::new(config.clone(), DrawingStrategy::ZigZag);
+
+        let path = converter.create_drawing_path(&canvas);
+        let estimate = simulate_run(&path, &config.timing, &config.options);
+        assert_eq!(path.estimated_time_ms, estimate.total_ms);
+
+        let artwork = Artwork::new(
+            crate::domain::artwork::entities::ArtworkMetadata::new("timing".to_string()),
+            "png".to_string(),
+            canvas,
+        );
+        let commands = converter.convert(&artwork);
+        let draw_actions: Vec<_> = commands
+            .iter()
+            .filter(|command| command.name.starts_with("Draw Batch"))
+            .flat_map(|command| &command.sequence)
+            .collect();
+
+        let mut moves = 0u64;
+        let mut presses = 0u64;
+        for action in draw_actions {
+            match action.action_type {
+                ActionType::SetDPad(dpad) if dpad != DPad::NEUTRAL => {
+                    moves += 1;
+                    assert_eq!(action.duration_ms, config.timing.press_ms);
+                }
+                ActionType::PressButton(Button::A) => {
+                    presses += 1;
+                    assert_eq!(action.duration_ms, config.timing.press_ms);
+                }
+                ActionType::ReleaseButton(Button::A) => {
+                    assert_eq!(action.duration_ms, config.timing.release_ms);
+                }
+                ActionType::Wait => assert_eq!(action.duration_ms, config.timing.wait_ms),
+                _ => {}
+            }
+        }
+        assert_eq!(moves, estimate.dpad_ops);
+        assert_eq!(presses, estimate.a_presses);
+    }
+
+    #[test]
+    fn test_two_opt_stops_at_time_budget_and_reports_stats() {
+        // 疎な格子は全域を探索し、十分な時間があれば収束まで続ける
+        let mut canvas = Canvas::new(320, 180);
+        for y in (0..180).step_by(6) {
+            for x in (0..320).step_by(6) {
+                if (x * 7 + y * 3) % 5 != 0 {
+                    canvas
+                        .set_dot(Coordinates::new(x, y), Dot::black())
+                        .unwrap();
+                }
+            }
+        }
+        let dots = canvas.dots.len();
+        let converter = |settings: TwoOptSettings| {
+            ArtworkToCommandConverter::new(
+                DrawingCanvasConfig::default(),
+                DrawingStrategy::GreedyTwoOpt,
+            )
+            .with_two_opt_settings(settings)
+        };
+
+        let path = converter(TwoOptSettings::default()).create_drawing_path(&canvas);
+        let stats = path.two_opt.unwrap();
+        assert_eq!(stats.window, dots);
+        assert!(stats.iterations >= 1);
+        assert!(stats.distance_after <= stats.distance_before);
+        assert_ne!(stats.stop_reason, TwoOptStopReason::TimeBudget);
+
+        let path = converter(TwoOptSettings::default().with_time_budget_ms(0))
+            .create_drawing_path(&canvas);
+        let stats = path.two_opt.unwrap();
+        assert_eq!(stats.stop_reason, TwoOptStopReason::TimeBudget);
+        assert_eq!(stats.iterations, 0);
+        assert_eq!(stats.distance_after, stats.distance_before);
+        assert_eq!(path.coordinates.len(), dots);
+
+        let path = ArtworkToCommandConverter::new(
+            DrawingCanvasConfig::default(),
+            DrawingStrategy::NearestNeighbor,
+        )
+        .create_drawing_path(&canvas);
+        assert!(path.two_opt.is_none());
+    }
+
+    #[test]
+    fn test_cancelled_path_computation_returns_no_partial_path() {
+        let mut canvas = Canvas::new(40, 20);
+        for x in 0..40 {
+            canvas
+                .set_dot(Coordinates::new(x, x % 20), Dot::black())
+                .unwrap();
+        }
+        let cancel = CancellationToken::default();
+        cancel.cancel();
+        for strategy in [
+            DrawingStrategy::NearestNeighbor,
+            DrawingStrategy::GreedyTwoOpt,
+        ] {
+            let converter =
+                ArtworkToCommandConverter::new(DrawingCanvasConfig::default(), strategy);
+            assert_eq!(
+                converter.create_cancellable_drawing_path(&canvas, &cancel),
+                Err(PathCancelled)
+            );
+        }
+
+        // 取り消し済みなら2-optは1反復も始めない
+        let converter = ArtworkToCommandConverter::new(
+            DrawingCanvasConfig::default(),
+            DrawingStrategy::GreedyTwoOpt,
+        );
+        let mut path: Vec<_> = (0..40).map(|x| Coordinates::new(x, x % 20)).collect();
+        let original = path.clone();
+        let stats =
+            converter.two_opt_optimize(&mut path, Instant::now() + Duration::from_secs(1), &cancel);
+        assert_eq!(stats.stop_reason, TwoOptStopReason::Cancelled);
+        assert_eq!(stats.iterations, 0);
+        assert_eq!(path, original);
+    }
+
+    #[test]
+    fn test_two_opt_observes_cancellation_during_a_long_run() {
+        // 並びを崩した密な格子は、時間の上限と短縮率の下限が無ければ長く反復し続ける
+        let mut path: Vec<_> = (0..120)
+            .flat_map(|y| (0..320).map(move |x| Coordinates::new(x, y)))
+            .collect();
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        for i in (1..path.len()).rev() {
+            seed = seed
+                .wrapping_mul(6_364_136_223_846_793_005)
+                .wrapping_add(1_442_695_040_888_963_407);
+            path.swap(i, (seed >> 33) as usize % (i + 1));
+        }
+        let converter = ArtworkToCommandConverter::new(
+            DrawingCanvasConfig::default(),
+            DrawingStrategy::GreedyTwoOpt,
+        )
+        .with_two_opt_settings(TwoOptSettings {
+            time_budget: Duration::from_secs(60),
+            min_improvement: 0.0,
+        });
+
+        let cancel = CancellationToken::default();
+        let worker = {
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                converter.two_opt_optimize(
+                    &mut path,
+                    Instant::now() + Duration::from_secs(60),
+                    &cancel,
+                )
+            })
+        };
+        std::thread::sleep(Duration::from_millis(50));
+        cancel.cancel();
+        let cancelled_at = Instant::now();
+        let stats = worker.join().unwrap();
+        assert_eq!(stats.stop_reason, TwoOptStopReason::Cancelled);
+        assert!(stats.iterations >= 1);
+        // 反復の途中でも一定の間隔で確認するため、取り消しから間もなく終わる
+        assert!(
+            cancelled_at.elapsed() < Duration::from_secs(2),
+            "{:?}",
+            cancelled_at.elapsed()
+        );
+    }
+
+    #[test]
+    fn test_two_opt_window_scales_with_density() {
+        let grid = |step: usize| -> Vec<Coordinates> {
+            (0..180)
+                .step_by(step)
+                .flat_map(|y| (0..320).step_by(step).map(move |x| Coordinates::new(x, y)))
+                .collect()
+        };
+        // 全ドットが対象なら基準の500点、密度1/4なら約4倍、少なければ全域
+        assert_eq!(two_opt_window(&grid(1)), 500);
+        let sparse = grid(2);
+        assert!((1900..=2100).contains(&two_opt_window(&sparse)));
+        assert_eq!(two_opt_window(&grid(8)), grid(8).len());
+    }
+
+    #[test]
+    fn test_create_drawing_path_is_deterministic() {
+        // 等距離の候補が多い格子状の配置（キャンバスを作り直すたびにHashMapの順序が変わる）
+        let build_canvas = || {
+            let mut canvas = Canvas::new(60, 40);
+            for y in (0..40).step_by(2) {
+                for x in (0..60).step_by(2) {
+                    if (x * 7 + y * 3) % 5 != 0 {
+                        canvas
+                            .set_dot(Coordinates::new(x, y), Dot::black())
+                            .unwrap();
+                    }
+                }
+            }
+            canvas
+        };
+
+        for strategy in [
+            DrawingStrategy::NearestNeighbor,
+            DrawingStrategy::GreedyTwoOpt,
+        ] {
+            let converter =
+                ArtworkToCommandConverter::new(DrawingCanvasConfig::default(), strategy);
+            let expected = converter.create_drawing_path(&build_canvas());
+            for _ in 0..50 {
+                let path = converter.create_drawing_path(&build_canvas());
+                assert_eq!(path.coordinates, expected.coordinates, "{strategy:?}");
+                assert_eq!(path.path_hash(), expected.path_hash());
+            }
+        }
+    }
+
+    #[test]
+    fn test_create_drawing_path_orders_layers_ascending() {
+        let mut canvas = Canvas::new(10, 10);
+        canvas
+            .set_dot(
+                Coordinates::new(1, 1),
+                Dot::with_layer(Color::black(), 255, 2),
+            )
+            .unwrap();
+        canvas
+            .set_dot(
+                Coordinates::new(3, 0),
+                Dot::with_layer(Color::black(), 255, 0),
+            )
+            .unwrap();
+        canvas
+            .set_dot(
+                Coordinates::new(0, 0),
+                Dot::with_layer(Color::black(), 255, 2),
+            )
+            .unwrap();
+
+        let converter = ArtworkToCommandConverter::new(
+            DrawingCanvasConfig::default(),
+            DrawingStrategy::RasterScan,
+        );
+        let path = converter.create_drawing_path(&canvas);
+
+        let segments = path.layer_segments();
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0], (0, &[Coordinates::new(3, 0)][..]));
+        assert_eq!(
+            segments[1],
+            (2, &[Coordinates::new(0, 0), Coordinates::new(1, 1)][..])
+        );
+    }
+
+    #[test]
+    fn test_adaptive_timing_stays_within_bounds() {
+        let settings = AdaptiveTimingSettings::new(40, 100);
+        let mut controller = AdaptiveTimingController::new(settings, 40);
+
+        // 遅延が続くと上限まで延びる
+        for _ in 0..10 {
+            controller.observe(Duration::from_millis(30), 40);
+        }
+        assert_eq!(controller.wait_ms(), 100);
+
+        // 回復すると（平均が閾値を下回ってから）徐々に戻り、下限を下回らない
+        let recovery = (0..30)
+            .map(|_| controller.observe(Duration::ZERO, 40))
+            .collect::<Vec<_>>();
+        assert!(recovery.windows(2).all(|w| w[1] <= w[0]));
+        assert!(recovery.iter().any(|&wait| wait > 40 && wait < 100));
+        assert_eq!(controller.wait_ms(), 40);
+
+        // ユーザー設定の待機時間が下限より大きければそれが下限になる
+        assert_eq!(controller.observe(Duration::ZERO, 60), 60);
+    }
+
+    #[test]
+    fn test_simulate_layers_rehomes_between_layers() {
+        let path = DrawingPath::from_layers(vec![
+            (0, vec![Coordinates::new(3, 0)]),
+            (1, vec![Coordinates::new(0, 2)]),
+        ]);
+        let timing = PaintTiming::new(10, 10, 0);
+        let options = RunOptions::default();
+
+        let layers = simulate_layers(&path, &timing, &options);
+
+        assert_eq!(layers.len(), 2);
+        assert_eq!(layers[0].estimate.dpad_ops, 3);
+        // 再ホーミング後は原点から移動する（十字キーはニュートラルのままなのでクリアは省く）
+        assert_eq!(layers[1].estimate.dpad_ops, 2);
+        assert_eq!(layers[1].estimate.neutral_clears, 0);
+        assert_eq!(layers[1].estimate.total_ms, REHOME_MS + 2 * 20 + 20);
+        assert_eq!(simulate_run(&path, &timing, &options).dpad_ops, 5);
+    }
+
+    #[test]
+    fn test_canvas_region_parse_and_validate() {
+        let region: CanvasRegion = "200, 0, 120, 120".parse().unwrap();
+        assert_eq!(region, CanvasRegion::new(200, 0, 120, 120));
+        assert!(region.validate_within(320, 120).is_ok());
+        assert!(region.validate_within(319, 120).is_err());
+        assert!(
+            CanvasRegion::new(0, 0, 0, 5)
+                .validate_within(320, 120)
+                .is_err()
+        );
+        assert!("1,2,3".parse::<CanvasRegion>().is_err());
+    }
+
+    #[test]
+    fn test_simulate_run_counts_operations() {
+        let path = DrawingPath::new(vec![Coordinates::new(2, 1), Coordinates::new(2, 3)]);
+        let timing = PaintTiming::new(10, 10, 0);
+        let options = RunOptions {
+            repeats: 2,
+            neutral_clear: NeutralClearSettings::always(),
+            ..Default::default()
+        };
+
+        let estimate = simulate_run(&path, &timing, &options);
+
+        assert_eq!(estimate.dpad_ops, 5);
+        assert_eq!(estimate.a_presses, 4);
+        assert_eq!(estimate.neutral_clears, 2);
+        // 移動5回 + A4回 (各20ms) + 方向転換1回 + ニュートラルクリア2回
+        assert_eq!(
+            estimate.total_ms,
+            9 * 20 + DIRECTION_CHANGE_DELAY_MS + 2 * NEUTRAL_CLEAR_MS
+        );
+
+        // 既定では最初のドットと一定の間隔のドットだけクリアする
+        let skipped = simulate_run(
+            &path,
+            &timing,
+            &RunOptions {
+                repeats: 2,
+                ..Default::default()
+            },
+        );
+        assert_eq!(skipped.neutral_clears, 1);
+        assert_eq!(skipped.total_ms, estimate.total_ms - NEUTRAL_CLEAR_MS);
+        let forced = NeutralClearSettings {
+            skip_redundant: true,
+            force_every_dots: 1,
+        };
+        assert!(forced.forced_at(1));
+        assert!(!NeutralClearSettings::default().forced_at(49));
+        assert!(NeutralClearSettings::default().forced_at(50));
+    }
+
+    #[test]
+    fn test_calibration_plan_default_matches_original_pattern() {
+        let plan = calibration_plan(&CalibrationLayout::default(), 320, 180).unwrap();
+
+        // 従来どおり (150, 85) から 20x9 の範囲に5行描画する
+        assert_eq!(plan.origin, Coordinates::new(150, 85));
+        assert_eq!((plan.width, plan.height), (20, 9));
+        assert_eq!(plan.dots.len(), 10 + 10 + 11 + 12 + 10);
+        assert_eq!(plan.dots[0], Coordinates::new(150, 85));
+        assert_eq!(plan.dots[1], Coordinates::new(152, 85));
+        // 2行目は右端から左向きに「2px描画 + 2px空白」
+        assert_eq!(plan.dots[10], Coordinates::new(169, 87));
+        assert_eq!(plan.dots[11], Coordinates::new(168, 87));
+        assert_eq!(plan.dots[12], Coordinates::new(165, 87));
+        assert!(plan.dots.iter().all(|dot| dot.y % 2 == 1));
+    }
+
+    #[test]
+    fn test_calibration_plan_vertical_transposes_horizontal() {
+        let horizontal = calibration_plan(&CalibrationLayout::default(), 320, 180).unwrap();
+        let layout = CalibrationLayout {
+            pattern: CalibrationPattern::Vertical,
+            ..CalibrationLayout::default()
+        };
+        let vertical = calibration_plan(&layout, 320, 180).unwrap();
+
+        assert_eq!((vertical.width, vertical.height), (9, 20));
+        let transposed: Vec<_> = horizontal
+            .dots
+            .iter()
+            .map(|dot| {
+                Coordinates::new(
+                    dot.y - horizontal.origin.y + vertical.origin.x,
+                    dot.x - horizontal.origin.x + vertical.origin.y,
+                )
+            })
+            .collect();
+        assert_eq!(vertical.dots, transposed);
+    }
+
+    #[test]
+    fn test_calibration_plan_grid_and_border() {
+        let grid = CalibrationLayout {
+            pattern: CalibrationPattern::Grid,
+            rows: 3,
+            width: 21,
+            spacing: 10,
+        };
+        let plan = calibration_plan(&grid, 320, 180).unwrap();
+        assert_eq!((plan.width, plan.height), (21, 21));
+        assert_eq!(plan.dots.len(), 9);
+        assert_eq!(
+            plan.dots[2],
+            Coordinates::new(plan.origin.x + 20, plan.origin.y)
+        );
+        assert_eq!(
+            plan.dots[3],
+            Coordinates::new(plan.origin.x + 20, plan.origin.y + 10)
+        );
+
+        let border = CalibrationLayout {
+            pattern: CalibrationPattern::Border,
+            rows: 0,
+            width: 0,
+            spacing: 1,
+        };
+        let plan = calibration_plan(&border, 4, 3).unwrap();
+        assert_eq!(plan.origin, Coordinates::origin());
+        let expected: Vec<_> = [
+            (0, 0),
+            (1, 0),
+            (2, 0),
+            (3, 0),
+            (3, 1),
+            (3, 2),
+            (2, 2),
+            (1, 2),
+            (0, 2),
+            (0, 1),
+        ]
+        .into_iter()
+        .map(|(x, y)| Coordinates::new(x, y))
+        .collect();
+        assert_eq!(plan.dots, expected);
+    }
+
+    #[test]
+    fn test_calibration_plan_rejects_invalid_layout() {
+        let empty = CalibrationLayout {
+            spacing: 0,
+            ..CalibrationLayout::default()
+        };
+        assert_eq!(
+            calibration_plan(&empty, 320, 180),
+            Err(CalibrationLayoutError::EmptySize)
+        );
+
+        let too_wide = CalibrationLayout {
+            width: 321,
+            ..CalibrationLayout::default()
+        };
+        assert!(matches!(
+            calibration_plan(&too_wide, 320, 180),
+            Err(CalibrationLayoutError::OutOfBounds { width: 321, .. })
+        ));
+    }
 }
@@ -1,47 +1,116 @@
-use crate::domain::controller::{Button, DPad};
+use crate::domain::controller::Button;
+use crate::domain::painting::init_sequence::{InitSequence, InitStep};
 use crate::domain::shared::value_objects::Coordinates;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
 
 /// Splatoon3の描画モード
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+///
+/// ペンの太さはLを5回押して小に合わせてから、Rで1段階ずつ太くして選ぶ。
+/// 消しゴムは小のペンのまま、Aの代わりにBを押してドットを消す。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum DrawingMode {
-    /// ピクセルペン（ドット単位）
+    /// ピクセルペン（ドット単位、ペンサイズ小）
+    #[default]
     PixelPen,
-    /// 通常ペン
-    NormalPen,
-    /// 太いペン
-    ThickPen,
-    /// 消しゴム
+    /// ブラシ（ペンサイズ中）
+    Brush,
+    /// 太いブラシ（ペンサイズ大）
+    ThickBrush,
+    /// 消しゴム（ペンサイズ小でBを押す）
     Eraser,
 }
 
 impl DrawingMode {
-    /// 描画モードを選択するためのボタン
-    pub fn select_button(&self) -> Button {
+    pub const ALL: [DrawingMode; 4] = [
+        DrawingMode::PixelPen,
+        DrawingMode::Brush,
+        DrawingMode::ThickBrush,
+        DrawingMode::Eraser,
+    ];
+
+    /// ペンサイズを小から太くする段階数
+    fn size_steps(&self) -> u32 {
+        match self {
+            DrawingMode::PixelPen | DrawingMode::Eraser => 0,
+            DrawingMode::Brush => 1,
+            DrawingMode::ThickBrush => 2,
+        }
+    }
+
+    /// 描画モードを選択する手順（初期化手順と `Select Drawing Mode` コマンドの両方に使う）
+    pub fn selection_steps(&self) -> Vec<InitStep> {
+        let mut steps = vec![InitStep::TapButton {
+            button: "L".to_string(),
+            count: 5,
+            delay_ms: 800,
+        }];
+        let size_steps = self.size_steps();
+        if size_steps > 0 {
+            steps.push(InitStep::TapButton {
+                button: "R".to_string(),
+                count: size_steps,
+                delay_ms: 800,
+            });
+        }
+        steps
+    }
+
+    /// 1ドットごとに押すボタン（消しゴムはB）
+    pub fn dot_button(&self) -> Button {
         match self {
-            DrawingMode::PixelPen => Button::L,
-            DrawingMode::NormalPen => Button::R,
-            DrawingMode::ThickPen => Button::ZL,
-            DrawingMode::Eraser => Button::ZR,
+            DrawingMode::Eraser => Button::B,
+            _ => Button::A,
         }
     }
 }
 
+impl fmt::Display for DrawingMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            DrawingMode::PixelPen => "pixel_pen",
+            DrawingMode::Brush => "brush",
+            DrawingMode::ThickBrush => "thick_brush",
+            DrawingMode::Eraser => "eraser",
+        };
+        f.write_str(name)
+    }
+}
+
 /// 描画キャンバスの設定
+///
+/// 経路の見積もりと実際の描画は、同じ `timing` と `options` を使う。
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DrawingCanvasConfig {
     /// キャンバスの幅（ピクセル）
     pub width: u16,
     /// キャンバスの高さ（ピクセル）
     pub height: u16,
-    /// カーソルの移動速度（ミリ秒/ピクセル）
-    pub cursor_speed_ms: u32,
-    /// ドット描画の待機時間（ミリ秒）
-    pub dot_draw_delay_ms: u32,
-    /// 行の折り返し時の追加待機時間（ミリ秒）
-    pub line_wrap_delay_ms: u32,
-    /// 描画モード
+    /// 1入力あたりのタイミング
+    pub timing: PaintTiming,
+    /// 描画実行の動作オプション
+    pub options: RunOptions,
+    /// 描画モード（ドットごとに押すボタンを決める）
+    #[serde(default)]
     pub drawing_mode: DrawingMode,
+    /// 描画前の初期化手順
+    #[serde(default)]
+    pub init_sequence: InitSequence,
+}
+
+impl DrawingCanvasConfig {
+    /// 指定したタイミングと動作オプションで描画する設定を作成
+    pub fn new(timing: PaintTiming, options: RunOptions) -> Self {
+        Self {
+            timing,
+            options,
+            ..Self::default()
+        }
+    }
 }
 
 impl Default for DrawingCanvasConfig {
@@ -49,57 +118,10 @@ impl Default for DrawingCanvasConfig {
         Self {
             width: 320,
             height: 120,
-            cursor_speed_ms: 100,    // 1ピクセル移動に100ms
-            dot_draw_delay_ms: 100,  // ドット描画に100ms
-            line_wrap_delay_ms: 200, // 行折り返しに追加200ms
+            timing: PaintTiming::default(),
+            options: RunOptions::default(),
             drawing_mode: DrawingMode::PixelPen,
-        }
-    }
-}
-
-/// カーソルの移動方向
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub enum CursorDirection {
-    Up,
-    Down,
-    Left,
-    Right,
-    UpLeft,
-    UpRight,
-    DownLeft,
-    DownRight,
-}
-
-impl CursorDirection {
-    /// 方向をDPadの値に変換
-    pub fn to_dpad(&self) -> DPad {
-        match self {
-            CursorDirection::Up => DPad::UP,
-            CursorDirection::Down => DPad::DOWN,
-            CursorDirection::Left => DPad::LEFT,
-            CursorDirection::Right => DPad::RIGHT,
-            CursorDirection::UpLeft => DPad::UP_LEFT,
-            CursorDirection::UpRight => DPad::UP_RIGHT,
-            CursorDirection::DownLeft => DPad::DOWN_LEFT,
-            CursorDirection::DownRight => DPad::DOWN_RIGHT,
-        }
-    }
-
-    /// 2つの座標間の方向を計算
-    pub fn from_coordinates(from: &Coordinates, to: &Coordinates) -> Option<Self> {
-        let dx = to.x as i32 - from.x as i32;
-        let dy = to.y as i32 - from.y as i32;
-
-        match (dx.signum(), dy.signum()) {
-            (0, -1) => Some(CursorDirection::Up),
-            (0, 1) => Some(CursorDirection::Down),
-            (-1, 0) => Some(CursorDirection::Left),
-            (1, 0) => Some(CursorDirection::Right),
-            (-1, -1) => Some(CursorDirection::UpLeft),
-            (1, -1) => Some(CursorDirection::UpRight),
-            (-1, 1) => Some(CursorDirection::DownLeft),
-            (1, 1) => Some(CursorDirection::DownRight),
-            _ => None,
+            init_sequence: InitSequence::default(),
         }
     }
 }
@@ -111,8 +133,27 @@ pub struct DrawingPath {
     pub coordinates: Vec<Coordinates>,
     /// 総移動距離
     pub total_distance: u32,
-    /// 推定所要時間（ミリ秒）
-    pub estimated_time_ms: u32,
+    /// 推定所要時間（ミリ秒、`simulate_run` による見積もり）
+    pub estimated_time_ms: u64,
+    /// レイヤーごとの区間（空の場合は全体がレイヤー0）
+    #[serde(default)]
+    pub layers: Vec<PathLayer>,
+    /// 2-opt最適化の結果（`GreedyTwoOpt` 以外は `None`）
+    #[serde(default)]
+    pub two_opt: Option<TwoOptStats>,
+    /// `coordinates` の各ドットが属するアートワークの行（空の場合は行を記録していない）
+    #[serde(default)]
+    pub rows: Vec<u16>,
+}
+
+/// 描画パス内の1レイヤー分の区間
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PathLayer {
+    pub layer: u8,
+    /// `coordinates` 内の開始位置
+    pub start: usize,
+    /// 区間のドット数
+    pub len: usize,
 }
 
 impl DrawingPath {
@@ -122,7 +163,79 @@ impl DrawingPath {
             coordinates,
             total_distance,
             estimated_time_ms: 0,
+            layers: Vec::new(),
+            two_opt: None,
+            rows: Vec::new(),
+        }
+    }
+
+    /// レイヤー順に並んだ区間からパスを作成
+    pub fn from_layers(layers: Vec<(u8, Vec<Coordinates>)>) -> Self {
+        let mut coordinates = Vec::new();
+        let mut segments = Vec::with_capacity(layers.len());
+        for (layer, layer_coordinates) in layers {
+            segments.push(PathLayer {
+                layer,
+                start: coordinates.len(),
+                len: layer_coordinates.len(),
+            });
+            coordinates.extend(layer_coordinates);
+        }
+
+        Self::with_layers(coordinates, segments)
+    }
+
+    /// 描画順に並んだ座標列と、その中のレイヤー区間からパスを作成
+    pub fn with_layers(coordinates: Vec<Coordinates>, layers: Vec<PathLayer>) -> Self {
+        let mut path = Self::new(coordinates);
+        path.layers = layers;
+        path
+    }
+
+    /// レイヤー番号とその区間の座標を描画順に返す
+    pub fn layer_segments(&self) -> Vec<(u8, &[Coordinates])> {
+        if self.layers.is_empty() {
+            return vec![(0, self.coordinates.as_slice())];
         }
+        self.layers
+            .iter()
+            .map(|segment| {
+                (
+                    segment.layer,
+                    &self.coordinates[segment.start..segment.start + segment.len],
+                )
+            })
+            .collect()
+    }
+
+    /// アートワークの行ごとのドット数（行を記録していないパスは空）
+    pub fn row_dot_counts(&self) -> BTreeMap<u16, usize> {
+        let mut counts = BTreeMap::new();
+        for row in &self.rows {
+            *counts.entry(*row).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// 連続する2点間の移動のうち最も長いもの（マンハッタン距離、無駄な移動の目安）
+    pub fn longest_move(&self) -> u32 {
+        self.coordinates
+            .windows(2)
+            .map(|pair| pair[0].manhattan_distance_to(&pair[1]))
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 座標列のハッシュ（XXH3、16桁の16進数）
+    ///
+    /// 同じ順序で同じ座標を描画するパスは同じ値になるため、描画実行と経路の対応付けに使う
+    pub fn path_hash(&self) -> String {
+        let bytes: Vec<u8> = self
+            .coordinates
+            .iter()
+            .flat_map(|c| c.x.to_le_bytes().into_iter().chain(c.y.to_le_bytes()))
+            .collect();
+        format!("{:016x}", xxhash_rust::xxh3::xxh3_64(&bytes))
     }
 
     fn calculate_total_distance(coordinates: &[Coordinates]) -> u32 {
@@ -135,40 +248,105 @@ impl DrawingPath {
             .map(|pair| pair[0].manhattan_distance_to(&pair[1]))
             .sum()
     }
+}
+
+/// 2-opt最適化の打ち切り条件
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwoOptSettings {
+    /// 全レイヤーの最適化に使う時間の上限
+    pub time_budget: std::time::Duration,
+    /// 1反復で短縮できた距離の割合（反復前の総距離に対する比）がこれを下回ったら打ち切る
+    pub min_improvement: f64,
+}
+
+impl TwoOptSettings {
+    /// 時間の上限の既定値（ミリ秒）
+    pub const DEFAULT_TIME_BUDGET_MS: u64 = 2000;
+    /// 時間の上限として指定できる最大値（ミリ秒）
+    pub const MAX_TIME_BUDGET_MS: u64 = 30_000;
+    /// 反復ごとの短縮率の下限の既定値（0.1%）
+    pub const DEFAULT_MIN_IMPROVEMENT: f64 = 0.001;
+
+    /// 時間の上限を指定する（`MAX_TIME_BUDGET_MS` で丸める）
+    pub fn with_time_budget_ms(mut self, time_budget_ms: u64) -> Self {
+        self.time_budget =
+            std::time::Duration::from_millis(time_budget_ms.min(Self::MAX_TIME_BUDGET_MS));
+        self
+    }
+}
 
-    /// 設定に基づいて推定時間を計算
-    pub fn calculate_estimated_time(&mut self, config: &DrawingCanvasConfig) {
-        if self.coordinates.is_empty() {
-            self.estimated_time_ms = 0;
-            return;
+impl Default for TwoOptSettings {
+    fn default() -> Self {
+        Self {
+            time_budget: std::time::Duration::from_millis(Self::DEFAULT_TIME_BUDGET_MS),
+            min_improvement: Self::DEFAULT_MIN_IMPROVEMENT,
         }
+    }
+}
 
-        let mut time_ms = 0u32;
-        let mut prev_coord: Option<&Coordinates> = None;
+/// 2-opt最適化を打ち切った理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TwoOptStopReason {
+    /// 短縮できる入れ替えがなくなった
+    Converged,
+    /// 1反復の短縮率が下限を下回った
+    MarginalGain,
+    /// 時間の上限に達した
+    TimeBudget,
+    /// 計算が取り消された
+    Cancelled,
+}
 
-        for coord in &self.coordinates {
-            if let Some(prev) = prev_coord {
-                // 移動時間を計算
-                let distance = prev.manhattan_distance_to(coord);
-                time_ms += distance * config.cursor_speed_ms;
+/// 描画パスの計算の取り消し（複製してもすべてのハンドルで同じ状態を共有する）
+///
+/// 計算する側は一定の間隔で確認し、取り消されていれば途中の結果を捨てて終わる。
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(std::sync::Arc<std::sync::atomic::AtomicBool>);
 
-                // 行が変わった場合は追加の待機時間
-                if prev.y != coord.y {
-                    time_ms += config.line_wrap_delay_ms;
-                }
-            }
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
 
-            // ドット描画時間
-            time_ms += config.dot_draw_delay_ms;
-            prev_coord = Some(coord);
-        }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
 
-        self.estimated_time_ms = time_ms;
+/// 2-opt最適化の結果（複数レイヤーの場合は合計）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct TwoOptStats {
+    /// 実行した反復回数
+    pub iterations: u32,
+    /// 最適化に掛かった時間（ミリ秒）
+    pub elapsed_ms: u64,
+    /// 最適化前の総移動距離（レイヤー内の移動のみ）
+    pub distance_before: u64,
+    /// 最適化後の総移動距離（レイヤー内の移動のみ）
+    pub distance_after: u64,
+    /// 探索ウィンドウの大きさ（前後の点数。レイヤーごとに異なる場合は最大値）
+    pub window: usize,
+    /// 最後に打ち切ったレイヤーの理由
+    pub stop_reason: TwoOptStopReason,
+}
+
+impl TwoOptStats {
+    /// 別のレイヤーの結果を合算する
+    pub fn merge(self, other: Self) -> Self {
+        Self {
+            iterations: self.iterations + other.iterations,
+            elapsed_ms: self.elapsed_ms + other.elapsed_ms,
+            distance_before: self.distance_before + other.distance_before,
+            distance_after: self.distance_after + other.distance_after,
+            window: self.window.max(other.window),
+            stop_reason: other.stop_reason,
+        }
     }
 }
 
 /// 描画戦略
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 pub enum DrawingStrategy {
     /// 左から右、上から下へのラスタースキャン
     RasterScan,
@@ -181,3 +359,646 @@ pub enum DrawingStrategy {
     /// スパイラル（渦巻き）パターン
     Spiral,
 }
+
+impl DrawingStrategy {
+    pub const ALL: [DrawingStrategy; 5] = [
+        DrawingStrategy::RasterScan,
+        DrawingStrategy::ZigZag,
+        DrawingStrategy::NearestNeighbor,
+        DrawingStrategy::GreedyTwoOpt,
+        DrawingStrategy::Spiral,
+    ];
+}
+
+/// キャンバス上の矩形領域
+///
+/// 左上の座標を含み、右下の座標（`x + width`, `y + height`）は含まない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub struct CanvasRegion {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl CanvasRegion {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// 領域の左上座標
+    pub fn top_left(&self) -> Coordinates {
+        Coordinates::new(self.x, self.y)
+    }
+
+    /// 座標が領域内にあるか判定
+    pub fn contains(&self, coordinates: &Coordinates) -> bool {
+        let x = coordinates.x as u32;
+        let y = coordinates.y as u32;
+        x >= self.x as u32
+            && y >= self.y as u32
+            && x < self.x as u32 + self.width as u32
+            && y < self.y as u32 + self.height as u32
+    }
+
+    /// 領域がキャンバス内に収まっているか検証
+    pub fn validate_within(
+        &self,
+        canvas_width: u16,
+        canvas_height: u16,
+    ) -> Result<(), CanvasRegionError> {
+        if self.width == 0 || self.height == 0 {
+            return Err(CanvasRegionError::EmptySize);
+        }
+        if self.x as u32 + self.width as u32 > canvas_width as u32
+            || self.y as u32 + self.height as u32 > canvas_height as u32
+        {
+            return Err(CanvasRegionError::OutOfBounds {
+                region: *self,
+                canvas_width,
+                canvas_height,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CanvasRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "({}, {}) {}x{}", self.x, self.y, self.width, self.height)
+    }
+}
+
+impl FromStr for CanvasRegion {
+    type Err = CanvasRegionError;
+
+    /// `x,y,width,height` 形式の文字列から作成
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split(',').map(str::trim).collect();
+        if parts.len() != 4 {
+            return Err(CanvasRegionError::InvalidFormat(s.to_string()));
+        }
+
+        let mut values = [0u16; 4];
+        for (value, part) in values.iter_mut().zip(&parts) {
+            *value = part
+                .parse::<u16>()
+                .map_err(|_| CanvasRegionError::InvalidFormat(s.to_string()))?;
+        }
+
+        Ok(Self::new(values[0], values[1], values[2], values[3]))
+    }
+}
+
+/// 描画領域のエラー
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CanvasRegionError {
+    #[error("Invalid region format '{0}'. Expected x,y,width,height")]
+    InvalidFormat(String),
+    #[error("Region width and height must be greater than 0")]
+    EmptySize,
+    #[error("Region {region} exceeds canvas bounds {canvas_width}x{canvas_height}")]
+    OutOfBounds {
+        region: CanvasRegion,
+        canvas_width: u16,
+        canvas_height: u16,
+    },
+}
+
+/// 速度キャリブレーションで描画するテストパターン
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CalibrationPattern {
+    /// 横方向の行（行ごとに描画・空白の幅を1pxずつ増やす）
+    #[default]
+    Horizontal,
+    /// 縦方向の列（`Horizontal` を転置したもの、縦移動のずれを確認する）
+    Vertical,
+    /// 一定間隔の格子状の点
+    Grid,
+    /// キャンバスの外周
+    Border,
+}
+
+/// キャリブレーションパターンの形状
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CalibrationLayout {
+    pub pattern: CalibrationPattern,
+    /// 行数（`Vertical` では列数、`Border` では未使用）
+    pub rows: u16,
+    /// 1行の長さ（ピクセル、`Border` では未使用）
+    pub width: u16,
+    /// 行間・点の間隔（ピクセル）
+    pub spacing: u16,
+}
+
+impl Default for CalibrationLayout {
+    fn default() -> Self {
+        Self {
+            pattern: CalibrationPattern::Horizontal,
+            rows: 5,
+            width: 20,
+            spacing: 2,
+        }
+    }
+}
+
+/// キャリブレーションで描画するドットの配置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalibrationPlan {
+    /// パターン左上のキャンバス座標（描画開始時のカーソル位置）
+    pub origin: Coordinates,
+    /// パターンの幅（ピクセル）
+    pub width: u16,
+    /// パターンの高さ（ピクセル）
+    pub height: u16,
+    /// 描画順に並べたドットのキャンバス座標
+    pub dots: Vec<Coordinates>,
+}
+
+/// キャリブレーションパターンのエラー
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CalibrationLayoutError {
+    #[error("Calibration rows, width and spacing must be greater than 0")]
+    EmptySize,
+    #[error(
+        "Calibration pattern {width}x{height} exceeds canvas bounds {canvas_width}x{canvas_height}"
+    )]
+    OutOfBounds {
+        width: u32,
+        height: u32,
+        canvas_width: u16,
+        canvas_height: u16,
+    },
+}
+
+/// 1入力あたりのタイミング（ミリ秒）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PaintTiming {
+    /// ボタン・十字キーを押している時間
+    pub press_ms: u32,
+    /// ニュートラル状態を保持する時間
+    pub release_ms: u32,
+    /// 入力間の追加待機時間
+    pub wait_ms: u32,
+}
+
+impl PaintTiming {
+    pub fn new(press_ms: u32, release_ms: u32, wait_ms: u32) -> Self {
+        Self {
+            press_ms,
+            release_ms,
+            wait_ms,
+        }
+    }
+
+    /// 1回のタップに掛かる時間
+    pub fn tap_ms(&self) -> u64 {
+        self.press_ms as u64 + self.release_ms as u64 + self.wait_ms as u64
+    }
+}
+
+impl Default for PaintTiming {
+    fn default() -> Self {
+        Self::new(100, 60, 40)
+    }
+}
+
+/// アートワークごとに保存する描画設定（省略した項目はサーバーの既定値を使う）
+///
+/// 描画リクエストで指定した値が最優先で、次にこの設定、最後にサーバーの既定値を使う。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PaintingPreferences {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub press_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wait_ms: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub strategy: Option<DrawingStrategy>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagonal_moves: Option<bool>,
+    /// アートワークの左上を置くゲーム内キャンバスの位置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub origin: Option<Coordinates>,
+    /// 描画モード
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drawing_mode: Option<DrawingMode>,
+}
+
+impl PaintingPreferences {
+    /// 何も設定されていないか
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// 描画実行の動作オプション
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunOptions {
+    /// 1ドットあたりのAボタン押下回数
+    pub repeats: u32,
+    /// 斜め方向の十字キー入力で移動するか
+    pub diagonal_moves: bool,
+    /// 描画開始前に移動する地点（領域描画時の左上など）
+    pub entry_point: Option<Coordinates>,
+    /// アートワークの左上を置くゲーム内キャンバスの位置（`None` は左上）
+    #[serde(default)]
+    pub origin: Option<Coordinates>,
+    /// 書き込み遅延に応じた待機時間の自動調整（無効ならNone）
+    pub adaptive: Option<AdaptiveTimingSettings>,
+    /// 一時停止の受け付け方
+    pub pause: PauseSettings,
+    /// 一時的な送信エラーの場合に1ドットの描画を試す最大回数（超えたらスキップする）
+    #[serde(default = "default_max_dot_attempts")]
+    pub max_dot_attempts: u32,
+    /// 描画を途中で切り上げる上限（無ければ最後まで描画する）
+    #[serde(default)]
+    pub stop_after: Option<StopLimits>,
+    /// 描画前のニュートラルクリアを省略する条件
+    #[serde(default)]
+    pub neutral_clear: NeutralClearSettings,
+    /// 描画前に確認用の印を描き、ユーザーの確認を待つ（無ければすぐに描画する）
+    #[serde(default)]
+    pub preflight: Option<PreflightSettings>,
+}
+
+impl RunOptions {
+    /// アートワークの座標をゲーム内キャンバスの座標に変換する
+    pub fn canvas_coordinates(&self, coordinates: Coordinates) -> Coordinates {
+        self.origin
+            .map_or(coordinates, |origin| coordinates.offset_by(origin))
+    }
+
+    /// ゲーム内キャンバスの座標をアートワークの座標に戻す（アートワークより左上なら `None`）
+    pub fn artwork_coordinates(&self, position: Coordinates) -> Option<Coordinates> {
+        match self.origin {
+            Some(origin) => position.relative_to(origin),
+            None => Some(position),
+        }
+    }
+}
+
+/// 1ドットの描画を試す既定の最大回数
+pub const DEFAULT_MAX_DOT_ATTEMPTS: u32 = 3;
+
+fn default_max_dot_attempts() -> u32 {
+    DEFAULT_MAX_DOT_ATTEMPTS
+}
+
+impl Default for RunOptions {
+    fn default() -> Self {
+        Self {
+            repeats: 1,
+            diagonal_moves: false,
+            entry_point: None,
+            origin: None,
+            adaptive: None,
+            pause: PauseSettings::default(),
+            max_dot_attempts: DEFAULT_MAX_DOT_ATTEMPTS,
+            stop_after: None,
+            neutral_clear: NeutralClearSettings::default(),
+            preflight: None,
+        }
+    }
+}
+
+/// 描画前の確認を待つ既定の時間（秒）
+pub const DEFAULT_PREFLIGHT_TIMEOUT_SECS: u32 = 120;
+/// 描画前の確認を待つ時間の上限（秒、本体の自動スリープより短くする）
+pub const MAX_PREFLIGHT_TIMEOUT_SECS: u32 = 1800;
+
+/// 描画前の確認（プリフライト）の設定
+///
+/// キャンバスがズームされていたりカーソルがキャンバスの外にあったりすると、そのまま何時間も
+/// 誤った入力を送ってしまう。本番の描画の前に左上隅へ小さな印を描き、正しい位置に描かれたかを確認する。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PreflightSettings {
+    /// 確認を待つ時間（秒、過ぎたら描画を中止する）
+    pub timeout_secs: u32,
+}
+
+impl Default for PreflightSettings {
+    fn default() -> Self {
+        Self {
+            timeout_secs: DEFAULT_PREFLIGHT_TIMEOUT_SECS,
+        }
+    }
+}
+
+impl PreflightSettings {
+    /// 確認用の印（ゲーム内キャンバスの左上隅のL字の3ドット、描く順）
+    pub fn mark() -> [Coordinates; 3] {
+        [
+            Coordinates::new(0, 0),
+            Coordinates::new(0, 1),
+            Coordinates::new(1, 1),
+        ]
+    }
+}
+
+/// 省略していても描画前のニュートラルクリアを必ず送る既定の間隔（ドット数）
+pub const DEFAULT_FORCED_NEUTRAL_CLEAR_EVERY: u32 = 50;
+
+/// 描画前のニュートラルクリアの省略
+///
+/// 移動のタップは最後に十字キーをニュートラルに戻すため、ほとんどのドットではクリアが重複する。
+/// 十字キーの状態が分からない場合（最初のドット、送信エラーの後など）は省略しない。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NeutralClearSettings {
+    /// 十字キーがニュートラルと分かっているドットではクリアを送らない（`false` なら毎ドット送る）
+    pub skip_redundant: bool,
+    /// 省略する場合も、このドット数ごとに必ずクリアを送る（0なら最初のドットだけ）
+    pub force_every_dots: u32,
+}
+
+impl NeutralClearSettings {
+    /// 毎ドットクリアを送る（省略しない）設定
+    pub fn always() -> Self {
+        Self {
+            skip_redundant: false,
+            ..Self::default()
+        }
+    }
+
+    /// `dot_index` 番目（描画全体で0始まり）のドットで、十字キーの状態に関わらずクリアを送るか
+    pub fn forced_at(&self, dot_index: u64) -> bool {
+        !self.skip_redundant
+            || dot_index == 0
+            || (self.force_every_dots > 0 && dot_index.is_multiple_of(self.force_every_dots as u64))
+    }
+}
+
+impl Default for NeutralClearSettings {
+    fn default() -> Self {
+        Self {
+            skip_redundant: true,
+            force_every_dots: DEFAULT_FORCED_NEUTRAL_CLEAR_EVERY,
+        }
+    }
+}
+
+/// 描画を途中で切り上げる条件（いずれかに達したら、ドットの区切りで停止する）
+///
+/// `completion_ratio` はアートワーク全体の完成度で、前回までに描画済みのドットも含めて数える。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct StopAfter {
+    /// この描画で描くドット数の上限
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dots: Option<usize>,
+    /// 描画開始（初期化手順を含む）からの経過時間の上限（分）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minutes: Option<u32>,
+    /// アートワークの完成度（0より大きく1以下）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub completion_ratio: Option<f32>,
+}
+
+impl StopAfter {
+    /// 描画開始時の進捗で、ドット数と経過時間の上限に変換する
+    ///
+    /// `painted_before` は描画済みのドット数、`total_dots` はアートワークの総ドット数。
+    pub fn resolve(
+        &self,
+        painted_before: usize,
+        total_dots: usize,
+    ) -> Result<StopLimits, StopAfterError> {
+        if self.dots.is_none() && self.minutes.is_none() && self.completion_ratio.is_none() {
+            return Err(StopAfterError::NoLimit);
+        }
+        if self.dots == Some(0) {
+            return Err(StopAfterError::ZeroDots);
+        }
+        if self.minutes == Some(0) {
+            return Err(StopAfterError::ZeroMinutes);
+        }
+        let completion_dots = match self.completion_ratio {
+            Some(ratio) if !(ratio > 0.0 && ratio <= 1.0) => {
+                return Err(StopAfterError::InvalidRatio(ratio));
+            }
+            Some(ratio) => {
+                let target = (ratio as f64 * total_dots as f64).ceil() as usize;
+                match target.saturating_sub(painted_before) {
+                    0 => return Err(StopAfterError::RatioAlreadyReached(ratio)),
+                    remaining => Some(remaining),
+                }
+            }
+            None => None,
+        };
+        Ok(StopLimits {
+            dots: self.dots,
+            minutes: self.minutes,
+            completion_dots,
+        })
+    }
+}
+
+/// 描画を切り上げる条件のエラー
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum StopAfterError {
+    #[error("stop_after needs at least one of dots, minutes or completion_ratio")]
+    NoLimit,
+    #[error("stop_after.dots must be greater than 0")]
+    ZeroDots,
+    #[error("stop_after.minutes must be greater than 0")]
+    ZeroMinutes,
+    #[error("stop_after.completion_ratio must be greater than 0 and at most 1 (got {0})")]
+    InvalidRatio(f32),
+    #[error("The artwork has already reached completion_ratio {0}")]
+    RatioAlreadyReached(f32),
+}
+
+/// 描画を切り上げる上限（`StopAfter` を描画開始時の進捗で解決したもの）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct StopLimits {
+    /// この描画で描くドット数の上限
+    pub dots: Option<usize>,
+    /// 経過時間の上限（分）
+    pub minutes: Option<u32>,
+    /// 指定した完成度に達するまでに、この描画で描くドット数
+    pub completion_dots: Option<usize>,
+}
+
+/// 描画を切り上げた上限
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StopLimit {
+    Dots,
+    Minutes,
+    CompletionRatio,
+}
+
+impl StopLimits {
+    /// この描画で `painted` ドットを描き、`elapsed` 経過した時点で達した上限
+    pub fn reached(&self, painted: usize, elapsed: std::time::Duration) -> Option<StopLimit> {
+        if self.dots.is_some_and(|dots| painted >= dots) {
+            Some(StopLimit::Dots)
+        } else if self.completion_dots.is_some_and(|dots| painted >= dots) {
+            Some(StopLimit::CompletionRatio)
+        } else if self
+            .minutes
+            .is_some_and(|minutes| elapsed.as_secs() >= minutes as u64 * 60)
+        {
+            Some(StopLimit::Minutes)
+        } else {
+            None
+        }
+    }
+
+    /// 上限までに描けるドット数（ドット数の上限が無ければ `None`）
+    pub fn remaining_dots(&self, painted: usize) -> Option<usize> {
+        [self.dots, self.completion_dots]
+            .into_iter()
+            .flatten()
+            .min()
+            .map(|dots| dots.saturating_sub(painted))
+    }
+
+    /// 上限までの残り時間（秒、時間の上限が無ければ `None`）
+    pub fn remaining_seconds(&self, elapsed: std::time::Duration) -> Option<u64> {
+        self.minutes
+            .map(|minutes| (minutes as u64 * 60).saturating_sub(elapsed.as_secs()))
+    }
+}
+
+/// 一時停止要求を受け付けるタイミング
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum PauseMode {
+    /// カーソル移動中でも十字キー1回分の入力ごとに停止する
+    #[default]
+    Immediate,
+    /// ドットの描画が終わり、ニュートラルに戻した後でのみ停止する
+    Safe,
+}
+
+/// Switchがレポートを受け取らなくなってから自動で一時停止するまでの既定の猶予（ミリ秒）
+pub const DEFAULT_HOST_GRACE_MS: u32 = 3000;
+
+/// 一時停止と再開の動作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PauseSettings {
+    pub mode: PauseMode,
+    /// `Safe` の再開時に左上へ戻り、停止中のカーソルのずれをリセットする
+    pub rehome_on_resume: bool,
+    /// レポートの送信失敗（Switchのスリープなど）がこの時間続いたら自動で一時停止する
+    pub host_grace_ms: u32,
+    /// 自動一時停止の後、Switchが応答を再開したらユーザー操作なしで再開する
+    pub auto_resume: bool,
+    /// 一時停止中にこの時間入力がなければ、本体がスリープしないよう無害な入力を送る（ミリ秒、`None` で送らない）
+    pub keepalive_idle_ms: Option<u32>,
+}
+
+impl Default for PauseSettings {
+    fn default() -> Self {
+        Self {
+            mode: PauseMode::default(),
+            rehome_on_resume: false,
+            host_grace_ms: DEFAULT_HOST_GRACE_MS,
+            auto_resume: false,
+            keepalive_idle_ms: None,
+        }
+    }
+}
+
+/// 自動スリープの既定の閾値（分、Switchの携帯モードの既定値）
+pub const DEFAULT_MAX_UNINTERRUPTED_MINUTES: u32 = 60;
+
+/// 本体の自動スリープで描画が中断されないかの確認
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SleepGuardSettings {
+    /// 見積もり時間がこれを超えると警告する（分、0なら確認しない）
+    pub max_uninterrupted_minutes: u32,
+    /// 超える場合は `acknowledge_sleep_risk` を指定しない描画リクエストを拒否する
+    pub strict: bool,
+}
+
+impl Default for SleepGuardSettings {
+    fn default() -> Self {
+        Self {
+            max_uninterrupted_minutes: DEFAULT_MAX_UNINTERRUPTED_MINUTES,
+            strict: false,
+        }
+    }
+}
+
+impl SleepGuardSettings {
+    /// 見積もり時間が閾値を超える場合の警告
+    pub fn warning(&self, estimated_ms: u64) -> Option<String> {
+        let limit_ms = self.max_uninterrupted_minutes as u64 * 60_000;
+        (limit_ms > 0 && estimated_ms > limit_ms).then(|| {
+            format!(
+                "estimated {} exceeds configured auto-sleep threshold ({})",
+                format_hours_minutes(estimated_ms),
+                format_hours_minutes(limit_ms)
+            )
+        })
+    }
+}
+
+/// 所要時間を `2h13m` の形式にする（分未満は切り上げ）
+pub fn format_hours_minutes(duration_ms: u64) -> String {
+    let minutes = duration_ms.div_ceil(60_000);
+    match (minutes / 60, minutes % 60) {
+        (0, minutes) => format!("{minutes}m"),
+        (hours, minutes) => format!("{hours}h{minutes:02}m"),
+    }
+}
+
+/// 書き込み遅延に応じてドット間の待機時間を調整する設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AdaptiveTimingSettings {
+    /// 待機時間の下限（これより短くはしない）
+    pub min_wait_ms: u32,
+    /// 待機時間の上限
+    pub max_wait_ms: u32,
+    /// 遅延が閾値を超えた際に待機時間へ掛ける倍率（%）
+    pub scale_percent: u32,
+    /// 平均書き込み遅延がこれを超えると待機時間を延ばす（ミリ秒）
+    pub latency_threshold_ms: u32,
+}
+
+impl AdaptiveTimingSettings {
+    /// 待機時間の下限・上限として指定できる最大値（ミリ秒）
+    pub const MAX_WAIT_MS: u32 = 60_000;
+
+    /// 既定の倍率と閾値で、`min_wait_ms` 〜 `max_wait_ms` の範囲を調整する設定を作成
+    pub fn new(min_wait_ms: u32, max_wait_ms: u32) -> Self {
+        Self {
+            min_wait_ms,
+            max_wait_ms: max_wait_ms.max(min_wait_ms),
+            scale_percent: 150,
+            latency_threshold_ms: 8,
+        }
+    }
+}
+
+/// 描画実行のシミュレーション結果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunEstimate {
+    /// 十字キーによる移動回数
+    pub dpad_ops: u64,
+    /// Aボタンの押下回数
+    pub a_presses: u64,
+    /// 描画前のニュートラルクリア回数
+    pub neutral_clears: u64,
+    /// 推定所要時間（ミリ秒、初期化シーケンスを除く）
+    pub total_ms: u64,
+}
+
+/// レイヤー単位のシミュレーション結果
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LayerEstimate {
+    pub layer: u8,
+    /// レイヤー内のドット数
+    pub dots: usize,
+    /// レイヤー開始時の再ホーミングを含む見積もり
+    pub estimate: RunEstimate,
+}
@@ -0,0 +1,288 @@
+//! 線画（ポリライン）をスティック操作で描くコマンドへの変換
+//!
+//! ドット描画のように十字キーで1マスずつ動かさず、ペンのボタンを押したまま左スティックを倒して線を引く。
+//! カーソルの位置はスティックを倒した時間と、あらかじめ測った移動速度からの推定でしかないため、
+//! 線が長くなるほど誤差が積み重なる。線ごとにキャンバスの左上へ押し当てて位置を合わせ直す。
+
+use crate::domain::artwork::value_objects::Polyline;
+use crate::domain::controller::{Button, ControllerAction, ControllerCommand, StickPosition};
+use crate::domain::shared::value_objects::Coordinates;
+use thiserror::Error;
+
+/// 測った移動速度に対して見込む誤差の割合
+const RATE_TOLERANCE: f64 = 0.05;
+/// スティックの傾きを8ビットで表すことによる向きの誤差（移動距離に対する割合）
+const DIRECTION_TOLERANCE: f64 = 1.0 / 127.5;
+/// HIDレポートの送信間隔（スティックを倒す時間はこの単位でずれる）
+const REPORT_INTERVAL_MS: f64 = 8.0;
+/// 左上へ押し当てるときに、キャンバスの対角線を移動する時間に足す余裕の割合
+const REHOME_MARGIN: f64 = 1.25;
+/// スティックを倒す強さの下限（これより弱いとSwitch側の遊びで動かないことがある）
+pub const MIN_STICK_TILT: f64 = 0.1;
+
+/// スティック描画の設定
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VectorSettings {
+    /// スティックを最大まで倒したときのカーソルの移動速度（ピクセル/秒、実機で測った値）
+    pub px_per_sec: f64,
+    /// スティックを倒す強さ（`MIN_STICK_TILT`〜1.0、速度は強さに比例するとみなす）
+    pub tilt: f64,
+    /// 線を引く間押し続けるボタン（消しゴムならB）
+    pub pen_button: Button,
+    /// 線の始点でボタンを押してから動き始めるまでの時間（ミリ秒）
+    pub press_ms: u32,
+    /// 線の終点でボタンを離した後の待ち時間（ミリ秒）
+    pub release_ms: u32,
+    /// 線画の左上を置くゲーム内キャンバスの位置
+    pub origin: Coordinates,
+    /// 左上へ押し当てるときに移動するキャンバスの大きさ
+    pub canvas_width: u16,
+    pub canvas_height: u16,
+}
+
+/// スティック描画の設定の検証エラー
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum VectorSettingsError {
+    #[error("px_per_sec must be a positive number (got {0})")]
+    InvalidRate(f64),
+    #[error("tilt must be between {MIN_STICK_TILT} and 1.0 (got {0})")]
+    InvalidTilt(f64),
+}
+
+impl VectorSettings {
+    pub fn validate(&self) -> Result<(), VectorSettingsError> {
+        if !(self.px_per_sec.is_finite() && self.px_per_sec > 0.0) {
+            return Err(VectorSettingsError::InvalidRate(self.px_per_sec));
+        }
+        if !(MIN_STICK_TILT..=1.0).contains(&self.tilt) {
+            return Err(VectorSettingsError::InvalidTilt(self.tilt));
+        }
+        Ok(())
+    }
+
+    /// 線を引くときの移動速度（ピクセル/ミリ秒）
+    fn draw_px_per_ms(&self) -> f64 {
+        self.px_per_sec * self.tilt / 1000.0
+    }
+
+    /// `distance` ピクセル移動したときの推定位置のずれ（ピクセル）
+    fn deviation(&self, distance: f64) -> f64 {
+        distance * (RATE_TOLERANCE + DIRECTION_TOLERANCE)
+            + self.draw_px_per_ms() * REPORT_INTERVAL_MS
+    }
+}
+
+/// 線画を描くコマンドと見積もり
+#[derive(Debug, Clone)]
+pub struct VectorPlan {
+    /// 実行順のコマンド（線ごとに「左上へ戻る」「始点へ移動」「線を引く」の3つ）
+    pub commands: Vec<ControllerCommand>,
+    pub strokes: usize,
+    /// 線を引く長さの合計（ピクセル、移動は含まない）
+    pub length_px: f64,
+    pub total_ms: u64,
+    /// 推定位置と実際のカーソル位置のずれの見込みの最大値（ピクセル）
+    pub max_deviation_px: f64,
+}
+
+/// 線画をスティック操作のコマンドに変換する
+///
+/// 各線の前にスティックを左上へ倒し続けてカーソルをキャンバスの角に押し当て、そこから始点まで
+/// ボタンを押さずに移動する。誤差は角からの移動と線の長さに応じて見積もる。
+pub fn vector_plan(
+    paths: &[Polyline],
+    settings: &VectorSettings,
+) -> Result<VectorPlan, VectorSettingsError> {
+    settings.validate()?;
+    let offset = |point: &Coordinates| {
+        (
+            (point.x + settings.origin.x) as f64,
+            (point.y + settings.origin.y) as f64,
+        )
+    };
+    let diagonal = (settings.canvas_width as f64).hypot(settings.canvas_height as f64);
+    let rehome_ms = (diagonal / (settings.px_per_sec / 1000.0) * REHOME_MARGIN).ceil() as u32;
+
+    let mut commands = Vec::with_capacity(paths.len() * 3);
+    let mut length_px = 0.0;
+    let mut max_deviation_px: f64 = 0.0;
+    for (index, path) in paths.iter().enumerate() {
+        let Some(start) = path.points.first() else {
+            continue;
+        };
+        let stroke = index + 1;
+        commands.push(
+            ControllerCommand::new(format!("Rehome before stroke {stroke}"))
+                .with_description("スティックでカーソルをキャンバスの左上に押し当てる")
+                .add_action(ControllerAction::move_left_stick(
+                    StickPosition::new(StickPosition::MIN, StickPosition::MIN),
+                    rehome_ms,
+                )),
+        );
+
+        let start = offset(start);
+        let mut travel = ControllerCommand::new(format!("Travel to stroke {stroke}"));
+        let mut deviation = push_segment(&mut travel, (0.0, 0.0), start, settings);
+        commands.push(travel);
+
+        let mut draw = ControllerCommand::new(format!("Stroke {stroke}")).add_action(
+            ControllerAction::press_button(settings.pen_button, settings.press_ms),
+        );
+        let mut from = start;
+        for point in &path.points[1..] {
+            let to = offset(point);
+            deviation += push_segment(&mut draw, from, to, settings);
+            length_px += (to.0 - from.0).hypot(to.1 - from.1);
+            from = to;
+        }
+        commands.push(draw.add_action(ControllerAction::release_button(
+            settings.pen_button,
+            settings.release_ms,
+        )));
+        max_deviation_px = max_deviation_px.max(deviation);
+    }
+
+    let total_ms = commands
+        .iter()
+        .map(|command| command.total_duration_ms() as u64)
+        .sum();
+    Ok(VectorPlan {
+        strokes: commands.len() / 3,
+        commands,
+        length_px,
+        total_ms,
+        max_deviation_px,
+    })
+}
+
+/// `from` から `to` へスティックで移動するアクションを追加し、そのずれの見込みを返す
+fn push_segment(
+    command: &mut ControllerCommand,
+    from: (f64, f64),
+    to: (f64, f64),
+    settings: &VectorSettings,
+) -> f64 {
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+    let distance = dx.hypot(dy);
+    if distance == 0.0 {
+        return 0.0;
+    }
+    let duration_ms = (distance / settings.draw_px_per_ms()).round().max(1.0) as u32;
+    // 動かさない軸が中央からずれないよう、中央（128）を基準に丸める
+    let axis =
+        |component: f64| (128.0 + component / distance * settings.tilt * 127.0).round() as u8;
+    let position = StickPosition::new(axis(dx), axis(dy));
+    command
+        .sequence
+        .push(ControllerAction::move_left_stick(position, duration_ms));
+    settings.deviation(distance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::controller::ActionType;
+
+    fn settings() -> VectorSettings {
+        VectorSettings {
+            px_per_sec: 100.0,
+            tilt: 0.5,
+            pen_button: Button::A,
+            press_ms: 50,
+            release_ms: 50,
+            origin: Coordinates::new(10, 0),
+            canvas_width: 320,
+            canvas_height: 180,
+        }
+    }
+
+    fn path(points: &[(u16, u16)]) -> Polyline {
+        Polyline::new(
+            points
+                .iter()
+                .map(|&(x, y)| Coordinates::new(x, y))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_strokes_hold_the_pen_while_moving_the_stick() {
+        let plan = vector_plan(
+            &[path(&[(0, 0), (50, 0), (50, 25)]), path(&[(5, 5)])],
+            &settings(),
+        )
+        .unwrap();
+
+        assert_eq!(plan.strokes, 2);
+        assert_eq!(plan.commands.len(), 6);
+        assert_eq!(plan.length_px, 75.0);
+        let stroke = &plan.commands[2];
+        let actions: Vec<&ActionType> = stroke.sequence.iter().map(|a| &a.action_type).collect();
+        assert_eq!(actions.first(), Some(&&ActionType::PressButton(Button::A)));
+        assert_eq!(actions.last(), Some(&&ActionType::ReleaseButton(Button::A)));
+        // 50ピクセルを半分の傾き（50ピクセル/秒）で右へ、25ピクセルを下へ
+        assert_eq!(stroke.sequence[1].duration_ms, 1000);
+        assert_eq!(
+            stroke.sequence[1].action_type,
+            ActionType::MoveLeftStick(StickPosition::new(192, 128))
+        );
+        assert_eq!(stroke.sequence[2].duration_ms, 500);
+        assert_eq!(
+            stroke.sequence[2].action_type,
+            ActionType::MoveLeftStick(StickPosition::new(128, 192))
+        );
+
+        // 移動ではボタンを押さず、1点だけの線は押して離すだけ
+        assert!(
+            plan.commands[1]
+                .sequence
+                .iter()
+                .all(|action| matches!(action.action_type, ActionType::MoveLeftStick(_)))
+        );
+        assert_eq!(plan.commands[5].sequence.len(), 2);
+        assert_eq!(
+            plan.total_ms,
+            plan.commands
+                .iter()
+                .map(|command| command.total_duration_ms() as u64)
+                .sum::<u64>()
+        );
+    }
+
+    #[test]
+    fn test_deviation_grows_with_stroke_length() {
+        let short = vector_plan(&[path(&[(0, 0), (10, 0)])], &settings()).unwrap();
+        let long = vector_plan(
+            &[path(&[(0, 0), (10, 0)]), path(&[(0, 0), (200, 100)])],
+            &settings(),
+        )
+        .unwrap();
+
+        assert!(short.max_deviation_px > 0.0);
+        assert!(long.max_deviation_px > short.max_deviation_px);
+        // 線ごとに位置を合わせ直すため、短い線だけなら誤差は積み重ならない
+        let repeated = vector_plan(&vec![path(&[(0, 0), (10, 0)]); 5], &settings()).unwrap();
+        assert_eq!(repeated.max_deviation_px, short.max_deviation_px);
+    }
+
+    #[test]
+    fn test_invalid_settings() {
+        let paths = [path(&[(0, 0)])];
+        let rate = VectorSettings {
+            px_per_sec: 0.0,
+            ..settings()
+        };
+        assert_eq!(
+            vector_plan(&paths, &rate).unwrap_err(),
+            VectorSettingsError::InvalidRate(0.0)
+        );
+        let tilt = VectorSettings {
+            tilt: 1.5,
+            ..settings()
+        };
+        assert!(matches!(
+            vector_plan(&paths, &tilt),
+            Err(VectorSettingsError::InvalidTilt(_))
+        ));
+    }
+}
@@ -1,4 +1,7 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use utoipa::ToSchema;
 
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum BoardModel {
@@ -17,6 +20,16 @@ impl BoardModel {
         }
     }
 
+    /// このボードでSwitchと接続できるUDC名（優先順）
+    pub fn expected_udc_names(&self) -> &'static [&'static str] {
+        match self {
+            BoardModel::OrangePiZero2W => &["musb-hdrc.4.auto"],
+            BoardModel::RaspberryPiZero => &["20980000.usb"],
+            BoardModel::RaspberryPiZero2W => &["3f980000.usb", "fe980000.usb"],
+            BoardModel::Unknown(_) => &[],
+        }
+    }
+
     pub fn requires_config_txt(&self) -> bool {
         matches!(
             self,
@@ -41,3 +54,173 @@ pub struct SystemSetupStatus {
     pub systemd_service_enabled: bool,
     pub usb_gadget_configured: bool,
 }
+
+/// 接続修正ウィザードの手順（実行順）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FixConnectionStep {
+    UnbindUdc,
+    ReloadModules,
+    RebuildGadget,
+    RebindUdc,
+    WaitForHidDevice,
+    TestWrite,
+}
+
+impl FixConnectionStep {
+    pub const ALL: [FixConnectionStep; 6] = [
+        FixConnectionStep::UnbindUdc,
+        FixConnectionStep::ReloadModules,
+        FixConnectionStep::RebuildGadget,
+        FixConnectionStep::RebindUdc,
+        FixConnectionStep::WaitForHidDevice,
+        FixConnectionStep::TestWrite,
+    ];
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            FixConnectionStep::UnbindUdc => "Unbinding UDC",
+            FixConnectionStep::ReloadModules => "Loading kernel modules",
+            FixConnectionStep::RebuildGadget => "Rebuilding USB Gadget",
+            FixConnectionStep::RebindUdc => "Binding UDC",
+            FixConnectionStep::WaitForHidDevice => "Waiting for HID device",
+            FixConnectionStep::TestWrite => "Testing HID write",
+        }
+    }
+}
+
+/// 各手順の実行結果（失敗時は対処方法を `hints` に含める）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct FixConnectionStepResult {
+    pub step: FixConnectionStep,
+    pub success: bool,
+    pub message: String,
+    pub hints: Vec<String>,
+}
+
+impl FixConnectionStepResult {
+    pub fn succeeded(step: FixConnectionStep, message: impl Into<String>) -> Self {
+        Self {
+            step,
+            success: true,
+            message: message.into(),
+            hints: Vec::new(),
+        }
+    }
+
+    pub fn failed(step: FixConnectionStep, message: impl Into<String>) -> Self {
+        Self {
+            step,
+            success: false,
+            message: message.into(),
+            hints: Vec::new(),
+        }
+    }
+
+    pub fn with_hint(mut self, hint: impl Into<String>) -> Self {
+        self.hints.push(hint.into());
+        self
+    }
+}
+
+/// 接続修正ウィザードの終了理由
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum FixConnectionOutcome {
+    Completed,
+    Failed { step: FixConnectionStep },
+    Aborted,
+}
+
+/// 監査ログに記録するガジェットの状態を変える操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GadgetOperation {
+    /// 初回セットアップ（ブート設定とサービスの作成）
+    Setup,
+    /// configfs へのガジェットの構成
+    Configure,
+    /// ガジェットの切断と再バインド
+    Reconnect,
+    /// ガジェットの設定の削除
+    Cleanup,
+    /// セットアップした設定すべての削除
+    CleanupSystem,
+    /// 接続修正の手順
+    FixConnection,
+    /// HIDデバイスの権限の修正
+    FixPermissions,
+}
+
+impl fmt::Display for GadgetOperation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            GadgetOperation::Setup => "setup",
+            GadgetOperation::Configure => "configure",
+            GadgetOperation::Reconnect => "reconnect",
+            GadgetOperation::Cleanup => "cleanup",
+            GadgetOperation::CleanupSystem => "cleanup_system",
+            GadgetOperation::FixConnection => "fix_connection",
+            GadgetOperation::FixPermissions => "fix_permissions",
+        })
+    }
+}
+
+/// 操作を実行した経路
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditInitiator {
+    Cli,
+    Web,
+    /// 起動時の `splatoon3-gadget.service`
+    Systemd,
+}
+
+impl fmt::Display for AuditInitiator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AuditInitiator::Cli => "cli",
+            AuditInitiator::Web => "web",
+            AuditInitiator::Systemd => "systemd",
+        })
+    }
+}
+
+/// 監査ログの1件
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct GadgetAuditEntry {
+    /// 記録した時刻（RFC 3339）
+    pub timestamp: String,
+    pub operation: GadgetOperation,
+    pub initiator: AuditInitiator,
+    /// 操作の引数（UDC名など、無ければ空）
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub parameters: BTreeMap<String, String>,
+    pub success: bool,
+    /// 失敗した場合のエラー
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl GadgetAuditEntry {
+    /// 現在時刻で操作の結果を記録する
+    pub fn new<E: fmt::Display>(
+        operation: GadgetOperation,
+        initiator: AuditInitiator,
+        result: &Result<(), E>,
+    ) -> Self {
+        Self {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            operation,
+            initiator,
+            parameters: BTreeMap::new(),
+            success: result.is_ok(),
+            error: result.as_ref().err().map(ToString::to_string),
+        }
+    }
+
+    pub fn with_parameter(mut self, key: &str, value: impl Into<String>) -> Self {
+        self.parameters.insert(key.to_string(), value.into());
+        self
+    }
+}
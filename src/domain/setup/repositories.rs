@@ -1,4 +1,7 @@
-use super::entities::{BoardModel, SystemSetupStatus};
+use super::entities::{
+    BoardModel, FixConnectionStep, FixConnectionStepResult, GadgetAuditEntry, SystemSetupStatus,
+};
+use std::fmt;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -20,6 +23,38 @@ pub enum SetupError {
 
     #[error("Unknown error: {0}")]
     Unknown(String),
+
+    /// セーフモードのため実行しなかった（成功したふりをすると後続の手順が誤った前提で進む操作）
+    #[error("Blocked by safe mode: {0}")]
+    SafeModeBlocked(String),
+
+    /// `/sys/class/udc` からガジェットをバインドするUDCを得られなかった
+    #[error("UDC not available ({reason}): {detail}")]
+    UdcUnavailable {
+        reason: UdcUnavailableReason,
+        detail: String,
+    },
+}
+
+/// UDCを得られなかった理由（起動時のログと監査ログに残す）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UdcUnavailableReason {
+    /// 待っても `/sys/class/udc` が現れない（USB OTGが無効、またはドライバーが読み込まれていない）
+    DirectoryMissing,
+    /// 待っても `/sys/class/udc` にUDCが現れない
+    DirectoryEmpty,
+    /// `/sys/class/udc` を読む権限が無い
+    PermissionDenied,
+}
+
+impl fmt::Display for UdcUnavailableReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            UdcUnavailableReason::DirectoryMissing => "udc_directory_missing",
+            UdcUnavailableReason::DirectoryEmpty => "udc_directory_empty",
+            UdcUnavailableReason::PermissionDenied => "udc_permission_denied",
+        })
+    }
 }
 
 pub trait BoardDetector: Send + Sync {
@@ -38,11 +73,27 @@ pub trait SystemdServiceManager: Send + Sync {
     fn is_service_enabled(&self) -> Result<bool, SetupError>;
     fn create_web_service(&self) -> Result<(), SetupError>;
     fn enable_web_service(&self) -> Result<(), SetupError>;
+    /// インストール済みのユニットファイルに足りない順序付けの指定（ファイルごとに1行）
+    fn missing_unit_ordering(&self) -> Vec<String>;
     fn disable_and_remove_services(&self) -> Result<(), SetupError>;
     fn setup_application_files(&self) -> Result<(), SetupError>;
     fn cleanup_application_files(&self) -> Result<(), SetupError>;
 }
 
+/// 接続修正ウィザードの各手順を実機に対して実行する
+pub trait ConnectionRepairer: Send + Sync {
+    fn run_step(&self, step: FixConnectionStep) -> FixConnectionStepResult;
+}
+
+/// ガジェットの状態を変えた操作を追記する監査ログ
+///
+/// 記録の失敗で元の操作を失敗させないよう、`record` はエラーを返さない（実装側でログに出す）。
+pub trait GadgetAuditLog: Send + Sync {
+    fn record(&self, entry: GadgetAuditEntry);
+    /// 新しい順に最大 `limit` 件を、古いものから並べて返す
+    fn recent(&self, limit: usize) -> Result<Vec<GadgetAuditEntry>, SetupError>;
+}
+
 pub trait SystemSetupRepository: Send + Sync {
     fn get_setup_status(&self) -> Result<SystemSetupStatus, SetupError>;
 }
@@ -0,0 +1,564 @@
+//! 利用者に見せる文言の英語・日本語の翻訳
+//!
+//! 文言はキー（`MessageKey`）と引数の組（`Message`）で持ち、表示する直前に言語（`Locale`）を
+//! 指定して組み立てる。単一のバイナリで配布できるよう、翻訳は外部ファイルではなくこのファイルに持つ。
+//! テンプレートの `{name}` は同じ名前の引数で置き換える。
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// 文言の言語
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Locale {
+    /// 日本語（サーバーの設定を省略した場合の既定値）
+    #[default]
+    Ja,
+    En,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::Ja, Locale::En];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ja => "ja",
+            Self::En => "en",
+        }
+    }
+
+    /// `Accept-Language` の値から、対応している言語のうち最も優先度の高いものを選ぶ
+    ///
+    /// 対応している言語が含まれない場合（`*` だけの場合も）は `None`。
+    pub fn from_accept_language(value: &str) -> Option<Locale> {
+        let mut best: Option<(f32, Locale)> = None;
+        for item in value.split(',') {
+            let mut parts = item.split(';');
+            let Ok(locale) = parts.next().unwrap_or_default().trim().parse::<Locale>() else {
+                continue;
+            };
+            let quality = parts
+                .find_map(|param| param.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse::<f32>().ok())
+                .unwrap_or(0.0);
+            // 同じ優先度なら先に書かれた言語を選ぶ
+            if quality > 0.0 && best.is_none_or(|(best_quality, _)| quality > best_quality) {
+                best = Some((quality, locale));
+            }
+        }
+        best.map(|(_, locale)| locale)
+    }
+}
+
+impl fmt::Display for Locale {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Locale {
+    type Err = String;
+
+    /// `ja`・`ja-JP`・`en-US` のような言語タグを受け付ける（大文字・小文字は区別しない）
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let language = s.split(['-', '_']).next().unwrap_or_default();
+        if language.eq_ignore_ascii_case("ja") {
+            Ok(Self::Ja)
+        } else if language.eq_ignore_ascii_case("en") {
+            Ok(Self::En)
+        } else {
+            Err(format!("Unsupported locale '{s}' (expected ja or en)"))
+        }
+    }
+}
+
+/// 文言のキーと、言語ごとのテンプレートを定義する
+macro_rules! message_catalog {
+    ($($(#[$doc:meta])* $key:ident { en: $en:literal, ja: $ja:literal })*) => {
+        /// 翻訳できる文言
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+        #[serde(rename_all = "snake_case")]
+        pub enum MessageKey {
+            $($(#[$doc])* $key,)*
+        }
+
+        impl MessageKey {
+            pub const ALL: &'static [MessageKey] = &[$(MessageKey::$key,)*];
+
+            /// `locale` のテンプレート
+            pub fn template(self, locale: Locale) -> &'static str {
+                match (self, locale) {
+                    $(
+                        (MessageKey::$key, Locale::En) => $en,
+                        (MessageKey::$key, Locale::Ja) => $ja,
+                    )*
+                }
+            }
+        }
+    };
+}
+
+message_catalog! {
+    // ドメインイベント（`ArtworkEvent::summary`）
+    ArtworkCreated {
+        en: "Artwork \"{name}\" was created",
+        ja: "アートワーク「{name}」が作成されました"
+    }
+    ArtworkMetadataUpdated {
+        en: "Metadata of artwork \"{name}\" was updated",
+        ja: "アートワーク「{name}」のメタデータが更新されました"
+    }
+    ArtworkCanvasUpdated {
+        en: "Canvas was updated ({dots} drawable dots)",
+        ja: "キャンバスが更新されました（描画可能ドット: {dots}個）"
+    }
+    ArtworkDeleted {
+        en: "Artwork \"{name}\" was deleted",
+        ja: "アートワーク「{name}」が削除されました"
+    }
+    ArtworkTrashed {
+        en: "Artwork \"{name}\" was moved to the trash",
+        ja: "アートワーク「{name}」がゴミ箱に移されました"
+    }
+    PaintingStarted {
+        en: "Painting started ({dots} dots)",
+        ja: "描画を開始しました（{dots}個のドット）"
+    }
+    DotPainted {
+        en: "Painted dot #{sequence} at {coordinates}",
+        ja: "ドット #{sequence} を座標 {coordinates} に描画しました"
+    }
+    PaintingPaused {
+        en: "Painting paused ({progress}% complete)",
+        ja: "描画を一時停止しました（進捗: {progress}%）"
+    }
+    PaintingResumed {
+        en: "Painting resumed ({remaining} dots left)",
+        ja: "描画を再開しました（残り: {remaining}個）"
+    }
+    PaintingCompleted {
+        en: "Painting completed ({dots} dots in {seconds} seconds)",
+        ja: "描画が完了しました（{dots}個のドット、{seconds}秒）"
+    }
+    PaintingCancelled {
+        en: "Painting was cancelled ({progress}% complete, reason: {reason})",
+        ja: "描画がキャンセルされました（進捗: {progress}%、理由: {reason}）"
+    }
+    PaintingErrorOccurred {
+        en: "A painting error occurred (retries: {retries}, error: {error})",
+        ja: "描画エラーが発生しました（リトライ: {retries}回、エラー: {error}）"
+    }
+    ArtworkReset {
+        en: "Artwork was reset (previously {progress}% complete)",
+        ja: "アートワークがリセットされました（以前の進捗: {progress}%）"
+    }
+
+    // APIのエラー
+    ArtworkNotFound {
+        en: "Artwork {id} not found",
+        ja: "アートワーク {id} が見つかりません"
+    }
+    ArtworkNotInTrash {
+        en: "Artwork {id} is not in the trash",
+        ja: "アートワーク {id} はゴミ箱にありません"
+    }
+    PaintingAlreadyRunning {
+        en: "Painting run {generation} is still running; stop it first",
+        ja: "描画 {generation} が実行中です。先に停止してください"
+    }
+    StrictSimulation {
+        en: "Painting and calibration are disabled in strict simulation mode",
+        ja: "厳格シミュレーションモードでは描画とキャリブレーションを実行できません"
+    }
+    ControllerLocked {
+        en: "Controller output is locked. Arm it with POST /api/controller/arm first",
+        ja: "コントローラーの出力がロックされています。先に POST /api/controller/arm でアームしてください"
+    }
+    ArtworkBusy {
+        en: "Cannot start {requested} of artwork {id}: {held_by} is in progress",
+        ja: "アートワーク {id} は{held_by}の実行中のため、{requested}を開始できません"
+    }
+    NothingToUndo {
+        en: "Nothing to undo",
+        ja: "取り消せる編集がありません"
+    }
+    NothingToRedo {
+        en: "Nothing to redo",
+        ja: "やり直せる編集がありません"
+    }
+    InvalidAccessToken {
+        en: "Invalid access token",
+        ja: "アクセストークンが正しくありません"
+    }
+    ManualInputWhilePainting {
+        en: "Cannot send manual input while painting or calibration is active",
+        ja: "描画・キャリブレーションの実行中は手動入力を送れません"
+    }
+    ManualInputWhileFixingConnection {
+        en: "Cannot send manual input while the connection fix is running",
+        ja: "接続修正の実行中は手動入力を送れません"
+    }
+    ResetWhilePainting {
+        en: "Cannot reset data while painting is active",
+        ja: "描画中はデータを初期化できません"
+    }
+    ReconnectWhilePainting {
+        en: "Cannot reconnect the gadget while painting is active",
+        ja: "描画中はガジェットを再接続できません"
+    }
+    GalleryDisabled {
+        en: "Gallery mode is disabled",
+        ja: "ギャラリーモードは無効です"
+    }
+
+    // アートワークごとの操作（`ArtworkBusy` の引数）
+    OperationPainting {
+        en: "painting",
+        ja: "描画"
+    }
+    OperationVectorPainting {
+        en: "vector painting",
+        ja: "線画の描画"
+    }
+    OperationPathComputation {
+        en: "drawing path computation",
+        ja: "描画パスの計算"
+    }
+    OperationStrategyComparison {
+        en: "strategy comparison",
+        ja: "描画戦略の比較"
+    }
+    OperationAnalysis {
+        en: "artwork analysis",
+        ja: "アートワークの解析"
+    }
+    OperationCanvasEdit {
+        en: "canvas edit",
+        ja: "キャンバスの編集"
+    }
+    OperationVectorPathUpdate {
+        en: "vector path update",
+        ja: "線画の置き換え"
+    }
+    OperationCompaction {
+        en: "compaction",
+        ja: "コンパクト化"
+    }
+    OperationUndo {
+        en: "undo",
+        ja: "取り消し"
+    }
+    OperationRedo {
+        en: "redo",
+        ja: "やり直し"
+    }
+    OperationDeletion {
+        en: "deletion",
+        ja: "削除"
+    }
+    OperationDataReset {
+        en: "data reset",
+        ja: "データの初期化"
+    }
+
+    // 進捗チャンネル（WebSocket）で送る状態
+    CalibrationCompleted {
+        en: "Calibration test completed",
+        ja: "キャリブレーションテストが完了しました"
+    }
+    CalibrationFailed {
+        en: "Calibration test failed: {error}",
+        ja: "キャリブレーションテストが失敗しました: {error}"
+    }
+    CalibrationCancelled {
+        en: "Calibration test was interrupted",
+        ja: "キャリブレーションテストが中断されました"
+    }
+    CalibrationCleanupPending {
+        en: "Erasing the calibration pattern in {seconds} seconds",
+        ja: "{seconds}秒後にキャリブレーションパターンを消します"
+    }
+    CalibrationCleanupCompleted {
+        en: "Erased {dots} dots of the calibration pattern",
+        ja: "キャリブレーションパターンを{dots}ドット消しました"
+    }
+    CalibrationCleanupFailed {
+        en: "Could not erase the calibration pattern: {error}",
+        ja: "キャリブレーションパターンを消せませんでした: {error}"
+    }
+    MoveTestCompleted {
+        en: "{test} completed",
+        ja: "{test}が完了しました"
+    }
+    MoveTestFailed {
+        en: "{test} failed",
+        ja: "{test}が失敗しました"
+    }
+    PaintMoveTest {
+        en: "Paint move test",
+        ja: "描画移動テスト"
+    }
+    GapMoveTest {
+        en: "Gap move test",
+        ja: "空白移動テスト"
+    }
+    StatusDrawingPreflightMark {
+        en: "Drawing the confirmation mark",
+        ja: "確認用の印を描いています"
+    }
+    StatusMovingToRegion {
+        en: "Moving to the top-left corner of the region",
+        ja: "描画領域の左上へ移動中"
+    }
+    StatusStartingPainting {
+        en: "Starting to paint",
+        ja: "描画を開始します"
+    }
+    StatusRehomingBeforeLayer {
+        en: "Moving to the top-left corner before layer {layer}",
+        ja: "レイヤー{layer}の描画前に左上へ移動中"
+    }
+    InitTapButton {
+        en: "Pressing the {button} button {count} times",
+        ja: "{button}ボタンを{count}回押しています"
+    }
+    InitHoldStick {
+        en: "Tilting the left stick {direction}",
+        ja: "左スティックを{direction}に倒しています"
+    }
+    InitWait {
+        en: "Waiting {ms}ms",
+        ja: "{ms}ms待機しています"
+    }
+}
+
+/// 文言の引数（別の文言のキーは、同じ言語で組み立ててから埋め込む）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum MessageArg {
+    Text(String),
+    Key { key: MessageKey },
+}
+
+/// 言語を決める前の文言
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Message {
+    pub key: MessageKey,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub args: BTreeMap<String, MessageArg>,
+}
+
+impl Message {
+    pub fn new(key: MessageKey) -> Self {
+        Self {
+            key,
+            args: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_arg(mut self, name: &str, value: impl fmt::Display) -> Self {
+        self.args
+            .insert(name.to_string(), MessageArg::Text(value.to_string()));
+        self
+    }
+
+    /// 別の文言（操作名など）を引数にする
+    pub fn with_key_arg(mut self, name: &str, key: MessageKey) -> Self {
+        self.args.insert(name.to_string(), MessageArg::Key { key });
+        self
+    }
+
+    /// `locale` で文言を組み立てる（引数の無いプレースホルダーはそのまま残す）
+    pub fn render(&self, locale: Locale) -> String {
+        let template = self.key.template(locale);
+        let mut rendered = String::with_capacity(template.len());
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            let placeholder = &rest[start..];
+            let Some(end) = placeholder.find('}') else {
+                break;
+            };
+            match self.args.get(&placeholder[1..end]) {
+                Some(MessageArg::Text(text)) => rendered.push_str(text),
+                Some(MessageArg::Key { key }) => rendered.push_str(key.template(locale)),
+                None => rendered.push_str(&placeholder[..=end]),
+            }
+            rest = &placeholder[end + 1..];
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+
+    /// JSONオブジェクトの `field` に `locale` の文言を入れ、`{field}_key` と `{field}_args` に
+    /// 別の言語で組み立て直すためのキーと引数を入れる
+    pub fn insert_into(&self, value: &mut serde_json::Value, field: &str, locale: Locale) {
+        let Some(object) = value.as_object_mut() else {
+            return;
+        };
+        object.insert(field.to_string(), self.render(locale).into());
+        object.insert(
+            format!("{field}_key"),
+            serde_json::to_value(self.key).unwrap_or_default(),
+        );
+        object.insert(
+            format!("{field}_args"),
+            serde_json::to_value(&self.args).unwrap_or_default(),
+        );
+    }
+}
+
+/// `Message::insert_into` で文言を入れるフィールド
+pub const LOCALIZED_FIELDS: [&str; 2] = ["message", "status_message"];
+
+/// `Message::insert_into` で文言を入れたJSONを `locale` で組み立て直す（対象が無ければ `None`）
+pub fn relocalize_json(text: &str, locale: Locale) -> Option<String> {
+    // 進捗はドットごとに送られるため、文言の無いメッセージは解析せずに素通りさせる
+    if !text.contains("_key\"") {
+        return None;
+    }
+    let mut value: serde_json::Value = serde_json::from_str(text).ok()?;
+    let mut changed = false;
+    for field in LOCALIZED_FIELDS {
+        let object = value.as_object()?;
+        let Some(key) = object.get(&format!("{field}_key")) else {
+            continue;
+        };
+        let Ok(key) = serde_json::from_value::<MessageKey>(key.clone()) else {
+            continue;
+        };
+        let args = object
+            .get(&format!("{field}_args"))
+            .and_then(|args| serde_json::from_value(args.clone()).ok())
+            .unwrap_or_default();
+        let rendered = Message { key, args }.render(locale);
+        value[field] = rendered.into();
+        changed = true;
+    }
+    changed.then(|| value.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// テンプレートの `{name}` の名前
+    fn placeholders(template: &str) -> BTreeSet<&str> {
+        let mut names = BTreeSet::new();
+        let mut rest = template;
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .unwrap_or_else(|| panic!("unclosed placeholder in {template:?}"));
+            names.insert(&rest[start + 1..start + end]);
+            rest = &rest[start + end + 1..];
+        }
+        names
+    }
+
+    #[test]
+    fn test_every_key_renders_in_every_locale() {
+        for &key in MessageKey::ALL {
+            let names = placeholders(key.template(Locale::En));
+            assert_eq!(
+                placeholders(key.template(Locale::Ja)),
+                names,
+                "{key:?} must use the same placeholders in every locale"
+            );
+            let message = names.iter().fold(Message::new(key), |message, name| {
+                message.with_arg(name, format!("<{name}>"))
+            });
+            for locale in Locale::ALL {
+                let rendered = message.render(locale);
+                assert!(!rendered.is_empty(), "{key:?} in {locale}");
+                assert!(
+                    !rendered.contains(['{', '}']),
+                    "{key:?} in {locale} left a placeholder: {rendered}"
+                );
+                for name in &names {
+                    assert!(
+                        rendered.contains(&format!("<{name}>")),
+                        "{key:?} in {locale}"
+                    );
+                }
+            }
+            assert_ne!(
+                key.template(Locale::En),
+                key.template(Locale::Ja),
+                "{key:?} is not translated"
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_embeds_key_args_and_keeps_missing_placeholders() {
+        let message = Message::new(MessageKey::ArtworkBusy)
+            .with_arg("id", "abc")
+            .with_key_arg("requested", MessageKey::OperationDeletion)
+            .with_key_arg("held_by", MessageKey::OperationPainting);
+        assert_eq!(
+            message.render(Locale::En),
+            "Cannot start deletion of artwork abc: painting is in progress"
+        );
+        assert_eq!(
+            message.render(Locale::Ja),
+            "アートワーク abc は描画の実行中のため、削除を開始できません"
+        );
+        assert_eq!(
+            Message::new(MessageKey::ArtworkNotFound).render(Locale::En),
+            "Artwork {id} not found"
+        );
+    }
+
+    #[test]
+    fn test_accept_language_picks_the_preferred_supported_locale() {
+        for (header, expected) in [
+            ("ja", Some(Locale::Ja)),
+            ("en-US,en;q=0.9", Some(Locale::En)),
+            ("fr-FR, en;q=0.5, ja;q=0.8", Some(Locale::Ja)),
+            ("JA-jp;q=0.2, EN;q=0.3", Some(Locale::En)),
+            ("en;q=0, ja;q=0.1", Some(Locale::Ja)),
+            ("de, *;q=0.5", None),
+            ("", None),
+        ] {
+            assert_eq!(Locale::from_accept_language(header), expected, "{header}");
+        }
+        assert!("fr".parse::<Locale>().is_err());
+    }
+
+    #[test]
+    fn test_progress_messages_can_be_relocalized() {
+        let mut value = serde_json::json!({ "type": "calibration_cleanup_complete", "erased": 3 });
+        Message::new(MessageKey::CalibrationCleanupCompleted)
+            .with_arg("dots", 3)
+            .insert_into(&mut value, "message", Locale::Ja);
+        assert_eq!(
+            value["message"],
+            "キャリブレーションパターンを3ドット消しました"
+        );
+
+        let english = relocalize_json(&value.to_string(), Locale::En).unwrap();
+        let english: serde_json::Value = serde_json::from_str(&english).unwrap();
+        assert_eq!(
+            english["message"],
+            "Erased 3 dots of the calibration pattern"
+        );
+        assert_eq!(english["erased"], 3);
+
+        let mut status = serde_json::json!({ "type": "progress" });
+        Message::new(MessageKey::StatusRehomingBeforeLayer)
+            .with_arg("layer", 2)
+            .insert_into(&mut status, "status_message", Locale::Ja);
+        let english = relocalize_json(&status.to_string(), Locale::En).unwrap();
+        assert!(english.contains("before layer 2"));
+
+        assert_eq!(
+            relocalize_json(r#"{"type":"progress","current":1}"#, Locale::En),
+            None
+        );
+    }
+}
@@ -2,9 +2,11 @@
 //!
 //! 複数の集約で使用される共通の値オブジェクトを定義
 
+use crate::domain::controller::DPad;
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
+use utoipa::ToSchema;
 
 /// エンティティの基本トレイト
 pub trait Entity {
@@ -16,7 +18,7 @@ pub trait Entity {
 }
 
 /// 2次元座標を表す値オブジェクト
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
 pub struct Coordinates {
     pub x: u16,
     pub y: u16,
@@ -52,6 +54,52 @@ impl Coordinates {
         (dx as u32) + (dy as u32)
     }
 
+    /// 他の座標までの変化量 (dx, dy)
+    pub fn delta_to(&self, other: &Coordinates) -> (i32, i32) {
+        (
+            other.x as i32 - self.x as i32,
+            other.y as i32 - self.y as i32,
+        )
+    }
+
+    /// 他の座標へ向かう次の1マスの十字キーの方向（斜めを含む。同じ座標なら `None`）
+    pub fn step_toward(&self, other: &Coordinates) -> Option<DPad> {
+        let (dx, dy) = self.delta_to(other);
+        if (dx, dy) == (0, 0) {
+            None
+        } else {
+            Some(DPad::from_offset(dx, dy))
+        }
+    }
+
+    /// 他の座標までカーソル移動する際の1マスごとの十字キーの方向
+    ///
+    /// `allow_diagonal` が有効な場合は斜め移動を先に行い、残りを縦横で移動する。
+    /// 無効な場合はX方向、Y方向の順に移動する。
+    pub fn steps_to(
+        &self,
+        other: &Coordinates,
+        allow_diagonal: bool,
+    ) -> impl Iterator<Item = DPad> + use<> {
+        let (dx, dy) = self.delta_to(other);
+        let (remaining_x, remaining_y) = (dx.unsigned_abs() as usize, dy.unsigned_abs() as usize);
+        let diagonal_count = if allow_diagonal {
+            remaining_x.min(remaining_y)
+        } else {
+            0
+        };
+
+        std::iter::repeat_n(DPad::from_offset(dx, dy), diagonal_count)
+            .chain(std::iter::repeat_n(
+                DPad::from_offset(dx, 0),
+                remaining_x - diagonal_count,
+            ))
+            .chain(std::iter::repeat_n(
+                DPad::from_offset(0, dy),
+                remaining_y - diagonal_count,
+            ))
+    }
+
     /// 座標を指定された方向に移動
     pub fn move_by(&self, dx: i16, dy: i16) -> Option<Coordinates> {
         let new_x = (self.x as i32) + (dx as i32);
@@ -64,6 +112,22 @@ impl Coordinates {
         }
     }
 
+    /// `origin` を左上に置いた場合の座標（u16の範囲を超える場合は飽和する）
+    pub fn offset_by(&self, origin: Coordinates) -> Coordinates {
+        Coordinates::new(
+            self.x.saturating_add(origin.x),
+            self.y.saturating_add(origin.y),
+        )
+    }
+
+    /// `offset_by` の逆変換（`origin` より左または上の場合は `None`）
+    pub fn relative_to(&self, origin: Coordinates) -> Option<Coordinates> {
+        Some(Coordinates::new(
+            self.x.checked_sub(origin.x)?,
+            self.y.checked_sub(origin.y)?,
+        ))
+    }
+
     /// 座標の配列から境界ボックスを計算
     pub fn bounding_box(coords: &[Coordinates]) -> Option<(Coordinates, Coordinates)> {
         if coords.is_empty() {
@@ -112,6 +176,86 @@ impl FromStr for Coordinates {
     }
 }
 
+/// 座標系（軸の向きと原点）
+///
+/// 座標を扱う場所ごとに、どの座標系の値かを明示するために使う。
+/// どちらの座標系も原点 (0, 0) は左上で、xは右、yは下に向かって増える
+/// （十字キーの下・スティックを下に倒す（`y` = 255）とyが増える）。
+/// 現在は同じ向きのため変換は恒等だが、回転・反転を加える場合もこの変換を通すことで、
+/// 上下・左右が反転したまま描画する誤りを防ぐ。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CoordinateSpace {
+    /// Web UIのエディター・アップロードした画像・アートワークのキャンバス
+    Editor,
+    /// ゲーム内キャンバス（描画パスとカーソル位置）
+    GameCanvas,
+}
+
+impl CoordinateSpace {
+    /// xが右に向かって増えるか
+    pub fn x_grows_rightward(self) -> bool {
+        match self {
+            CoordinateSpace::Editor | CoordinateSpace::GameCanvas => true,
+        }
+    }
+
+    /// yが下に向かって増えるか
+    pub fn y_grows_downward(self) -> bool {
+        match self {
+            CoordinateSpace::Editor | CoordinateSpace::GameCanvas => true,
+        }
+    }
+
+    /// 十字キーで1マス移動した変化量（`DPad::offset`）が、この座標系の軸の向きと一致するか
+    pub fn agrees_with_dpad(self) -> bool {
+        (DPad::RIGHT.offset().0 > 0) == self.x_grows_rightward()
+            && (DPad::DOWN.offset().1 > 0) == self.y_grows_downward()
+    }
+
+    /// `width` x `height` の範囲の座標を `target` の座標系に変換する（範囲外の座標は飽和する）
+    pub fn convert(
+        self,
+        coordinates: Coordinates,
+        target: CoordinateSpace,
+        width: u16,
+        height: u16,
+    ) -> Coordinates {
+        let flip = |value: u16, size: u16| size.saturating_sub(1).saturating_sub(value);
+        Coordinates::new(
+            if self.x_grows_rightward() == target.x_grows_rightward() {
+                coordinates.x
+            } else {
+                flip(coordinates.x, width)
+            },
+            if self.y_grows_downward() == target.y_grows_downward() {
+                coordinates.y
+            } else {
+                flip(coordinates.y, height)
+            },
+        )
+    }
+}
+
+impl fmt::Display for CoordinateSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            CoordinateSpace::Editor => "editor",
+            CoordinateSpace::GameCanvas => "game canvas",
+        };
+        let x = if self.x_grows_rightward() {
+            "right"
+        } else {
+            "left"
+        };
+        let y = if self.y_grows_downward() {
+            "down"
+        } else {
+            "up"
+        };
+        write!(f, "{name} (x grows {x}, y grows {y})")
+    }
+}
+
 /// 色の値を表す値オブジェクト
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Color {
@@ -473,6 +617,38 @@ impl FromStr for Timestamp {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_coordinate_spaces_share_top_left_origin_and_match_inputs() {
+        for space in [CoordinateSpace::Editor, CoordinateSpace::GameCanvas] {
+            assert!(
+                space.x_grows_rightward() && space.y_grows_downward(),
+                "{space}"
+            );
+            assert!(space.agrees_with_dpad(), "{space}");
+        }
+        // スティックも下に倒すとyが増える向き
+        assert_eq!(
+            crate::domain::controller::StickPosition::from_dpad(DPad::DOWN).y,
+            u8::MAX
+        );
+
+        let top_left = Coordinates::origin();
+        let bottom_right = Coordinates::new(319, 119);
+        for coordinates in [top_left, bottom_right, Coordinates::new(12, 34)] {
+            let game =
+                CoordinateSpace::Editor.convert(coordinates, CoordinateSpace::GameCanvas, 320, 120);
+            assert_eq!(game, coordinates);
+            assert_eq!(
+                CoordinateSpace::GameCanvas.convert(game, CoordinateSpace::Editor, 320, 120),
+                coordinates
+            );
+        }
+        assert_eq!(
+            CoordinateSpace::GameCanvas.to_string(),
+            "game canvas (x grows right, y grows down)"
+        );
+    }
+
     #[test]
     fn test_coordinates() {
         let coord = Coordinates::new(10, 20);
@@ -488,6 +664,95 @@ mod tests {
         assert_eq!("(10, 20)".parse::<Coordinates>().unwrap(), coord);
     }
 
+    #[test]
+    fn test_steps_to_zero_distance_and_straight_lines() {
+        let center = Coordinates::new(5, 5);
+        assert_eq!(center.delta_to(&center), (0, 0));
+        assert_eq!(center.step_toward(&center), None);
+        for allow_diagonal in [false, true] {
+            assert_eq!(center.steps_to(&center, allow_diagonal).count(), 0);
+        }
+
+        for (target, dpad) in [
+            (Coordinates::new(5, 2), DPad::UP),
+            (Coordinates::new(5, 8), DPad::DOWN),
+            (Coordinates::new(2, 5), DPad::LEFT),
+            (Coordinates::new(8, 5), DPad::RIGHT),
+        ] {
+            assert_eq!(center.step_toward(&target), Some(dpad));
+            for allow_diagonal in [false, true] {
+                assert_eq!(
+                    center.steps_to(&target, allow_diagonal).collect::<Vec<_>>(),
+                    vec![dpad; 3]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_steps_to_diagonal_and_straight() {
+        let from = Coordinates::new(5, 5);
+        let to = Coordinates::new(8, 3);
+        assert_eq!(from.delta_to(&to), (3, -2));
+        assert_eq!(from.step_toward(&to), Some(DPad::UP_RIGHT));
+
+        assert_eq!(
+            from.steps_to(&to, false).collect::<Vec<_>>(),
+            vec![DPad::RIGHT, DPad::RIGHT, DPad::RIGHT, DPad::UP, DPad::UP]
+        );
+        assert_eq!(
+            from.steps_to(&to, true).collect::<Vec<_>>(),
+            vec![DPad::UP_RIGHT, DPad::UP_RIGHT, DPad::RIGHT]
+        );
+
+        for (target, dpad) in [
+            (Coordinates::new(3, 3), DPad::UP_LEFT),
+            (Coordinates::new(7, 3), DPad::UP_RIGHT),
+            (Coordinates::new(3, 7), DPad::DOWN_LEFT),
+            (Coordinates::new(7, 7), DPad::DOWN_RIGHT),
+        ] {
+            assert_eq!(from.step_toward(&target), Some(dpad));
+            assert_eq!(
+                from.steps_to(&target, true).collect::<Vec<_>>(),
+                vec![dpad; 2]
+            );
+        }
+    }
+
+    #[test]
+    fn test_steps_to_reaches_every_target() {
+        let points: Vec<Coordinates> = (0..6)
+            .flat_map(|y| (0..6).map(move |x| Coordinates::new(x, y)))
+            .collect();
+        for from in &points {
+            for to in &points {
+                let (dx, dy) = from.delta_to(to);
+                for allow_diagonal in [false, true] {
+                    let steps: Vec<DPad> = from.steps_to(to, allow_diagonal).collect();
+                    let expected_len = if allow_diagonal {
+                        dx.abs().max(dy.abs())
+                    } else {
+                        dx.abs() + dy.abs()
+                    };
+                    assert_eq!(steps.len(), expected_len as usize, "{from} -> {to}");
+
+                    let mut position = *from;
+                    for step in &steps {
+                        assert_ne!(*step, DPad::NEUTRAL);
+                        let (step_x, step_y) = step.offset();
+                        position = position.move_by(step_x, step_y).unwrap();
+                    }
+                    assert_eq!(position, *to, "{from} -> {to}");
+
+                    // 斜め移動ありでは最初の1マスが `step_toward` と一致する
+                    if allow_diagonal {
+                        assert_eq!(steps.first().copied(), from.step_toward(to));
+                    }
+                }
+            }
+        }
+    }
+
     #[test]
     fn test_color() {
         let color = Color::from_rgb(128, 128, 128);
@@ -0,0 +1,476 @@
+//! アニメーション画像（GIF・APNG・WebP）のフレームの読み出しと、静止画のデコード
+//!
+//! フレームは1枚ずつデコードして合成用のバッファに重ね、合成した画像を呼び出し側に渡してから次へ進む。
+//! 全フレームを同時にメモリへ展開しないため、使用量は画像1枚分の数倍に収まる。
+
+use crate::domain::artwork::value_objects::{ImageFormat, RgbaImage};
+use std::io::Cursor;
+use thiserror::Error;
+
+/// 分割できるフレーム数の上限
+pub const MAX_ANIMATION_FRAMES: usize = 50;
+
+#[derive(Debug, Error)]
+pub enum AnimationError {
+    #[error("The animation has more than {max} frames")]
+    TooManyFrames { max: usize },
+    #[error("The animation is {width}x{height}, larger than {max}x{max} pixels")]
+    TooLarge { width: u32, height: u32, max: u32 },
+    #[error("Failed to decode the {format} animation: {message}")]
+    Decode {
+        format: ImageFormat,
+        message: String,
+    },
+    #[error("Unsupported image format: only PNG, GIF and WebP can be decoded")]
+    UnsupportedFormat,
+}
+
+/// 合成済みの1フレーム（RGBA、行順）
+pub struct AnimationFrame<'a> {
+    /// 何番目のフレームか（0始まり）
+    pub index: usize,
+    pub width: u16,
+    pub height: u16,
+    pub rgba: &'a [u8],
+}
+
+/// アニメーション画像の形式を判定する（アニメーションでない、または対応していない形式なら `None`）
+///
+/// GIFはフレームを読むまで枚数が分からないため、GIFであれば1枚だけでも `Some` を返す。
+pub fn detect_animation(bytes: &[u8]) -> Option<ImageFormat> {
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        Some(ImageFormat::Gif)
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png::Decoder::new(Cursor::new(bytes))
+            .read_info()
+            .ok()
+            .and_then(|reader| reader.info().animation_control)
+            .filter(|control| control.num_frames > 1)
+            .map(|_| ImageFormat::Png)
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        image_webp::WebPDecoder::new(Cursor::new(bytes))
+            .ok()
+            .filter(|decoder| decoder.is_animated() && decoder.num_frames() > 1)
+            .map(|_| ImageFormat::Webp)
+    } else {
+        None
+    }
+}
+
+/// フレームを先頭から順に合成して `convert` に渡し、変換結果をフレーム順に返す
+///
+/// 1枚しかフレームがなければ `Ok(None)`。フレーム数が `max_frames` を超える場合は、
+/// 枚数の分かる形式ではデコードを始める前に、GIFでは超えた時点でエラーにする。
+pub fn decode_frames<T>(
+    bytes: &[u8],
+    max_frames: usize,
+    max_side: u32,
+    mut convert: impl FnMut(AnimationFrame<'_>) -> T,
+) -> Result<Option<Vec<T>>, AnimationError> {
+    let Some(format) = detect_animation(bytes) else {
+        return Ok(None);
+    };
+    let decode_error = |error: &dyn std::fmt::Display| AnimationError::Decode {
+        format,
+        message: error.to_string(),
+    };
+    let mut frames = Vec::new();
+    let mut emit = |canvas: &FrameBuffer| {
+        if frames.len() >= max_frames {
+            return Err(AnimationError::TooManyFrames { max: max_frames });
+        }
+        frames.push(convert(AnimationFrame {
+            index: frames.len(),
+            width: canvas.width as u16,
+            height: canvas.height as u16,
+            rgba: &canvas.pixels,
+        }));
+        Ok(())
+    };
+
+    match format {
+        ImageFormat::Gif => {
+            let mut options = gif::DecodeOptions::new();
+            options.set_color_output(gif::ColorOutput::RGBA);
+            let mut decoder = options
+                .read_info(Cursor::new(bytes))
+                .map_err(|e| decode_error(&e))?;
+            let mut canvas =
+                FrameBuffer::new(decoder.width() as u32, decoder.height() as u32, max_side)?;
+            while let Some(frame) = decoder.read_next_frame().map_err(|e| decode_error(&e))? {
+                let region = Region {
+                    x: frame.left as u32,
+                    y: frame.top as u32,
+                    width: frame.width as u32,
+                    height: frame.height as u32,
+                };
+                let previous =
+                    (frame.dispose == gif::DisposalMethod::Previous).then(|| canvas.pixels.clone());
+                canvas.draw(region, &frame.buffer, 4, true);
+                emit(&canvas)?;
+                match frame.dispose {
+                    gif::DisposalMethod::Background => canvas.clear(region),
+                    gif::DisposalMethod::Previous => {
+                        canvas.pixels = previous.unwrap_or_default();
+                    }
+                    gif::DisposalMethod::Any | gif::DisposalMethod::Keep => {}
+                }
+            }
+        }
+        ImageFormat::Png => {
+            let mut decoder = png::Decoder::new(Cursor::new(bytes));
+            decoder.set_transformations(png::Transformations::normalize_to_color8());
+            let mut reader = decoder.read_info().map_err(|e| decode_error(&e))?;
+            let info = reader.info();
+            let total = info
+                .animation_control
+                .map_or(1, |control| control.num_frames) as usize;
+            if total > max_frames {
+                return Err(AnimationError::TooManyFrames { max: max_frames });
+            }
+            let mut canvas = FrameBuffer::new(info.width, info.height, max_side)?;
+            let mut buffer = vec![0; reader.output_buffer_size()];
+            let mut decoded = 0;
+            while decoded < total {
+                let output = reader
+                    .next_frame(&mut buffer)
+                    .map_err(|e| decode_error(&e))?;
+                // 先頭の画像がアニメーションに含まれない場合は読み飛ばす
+                let Some(control) = reader.info().frame_control else {
+                    continue;
+                };
+                decoded += 1;
+                let region = Region {
+                    x: control.x_offset,
+                    y: control.y_offset,
+                    width: control.width,
+                    height: control.height,
+                };
+                let channels = output.color_type.samples();
+                let rgba = &buffer[..output.buffer_size()];
+                let dispose = if decoded == 1 && control.dispose_op == png::DisposeOp::Previous {
+                    // 最初のフレームの「前の状態に戻す」は背景で消すのと同じ
+                    png::DisposeOp::Background
+                } else {
+                    control.dispose_op
+                };
+                let previous = (dispose == png::DisposeOp::Previous).then(|| canvas.pixels.clone());
+                let blend = control.blend_op == png::BlendOp::Over;
+                canvas.draw(region, rgba, channels, blend);
+                emit(&canvas)?;
+                match dispose {
+                    png::DisposeOp::Background => canvas.clear(region),
+                    png::DisposeOp::Previous => canvas.pixels = previous.unwrap_or_default(),
+                    png::DisposeOp::None => {}
+                }
+            }
+        }
+        ImageFormat::Webp => {
+            let mut decoder =
+                image_webp::WebPDecoder::new(Cursor::new(bytes)).map_err(|e| decode_error(&e))?;
+            let total = decoder.num_frames() as usize;
+            if total > max_frames {
+                return Err(AnimationError::TooManyFrames { max: max_frames });
+            }
+            let (width, height) = decoder.dimensions();
+            let mut canvas = FrameBuffer::new(width, height, max_side)?;
+            let channels = if decoder.has_alpha() { 4 } else { 3 };
+            // デコーダーが前のフレームと合成した画像を返す
+            let mut buffer = vec![0; width as usize * height as usize * channels];
+            let whole = Region {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            };
+            for _ in 0..total {
+                decoder
+                    .read_frame(&mut buffer)
+                    .map_err(|e| decode_error(&e))?;
+                canvas.draw(whole, &buffer, channels, false);
+                emit(&canvas)?;
+            }
+        }
+        _ => return Ok(None),
+    }
+
+    Ok((frames.len() > 1).then_some(frames))
+}
+
+/// PNG・GIF・WebPの画像をデコードする（アニメーションは最初のフレーム）
+///
+/// 幅・高さが `max_side` を超える画像は、画素を展開する前にエラーにする。
+pub fn decode_image(bytes: &[u8], max_side: u32) -> Result<RgbaImage, AnimationError> {
+    let format = if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        ImageFormat::Gif
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        ImageFormat::Png
+    } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+        ImageFormat::Webp
+    } else {
+        return Err(AnimationError::UnsupportedFormat);
+    };
+    let decode_error = |error: &dyn std::fmt::Display| AnimationError::Decode {
+        format,
+        message: error.to_string(),
+    };
+
+    let canvas = match format {
+        ImageFormat::Gif => {
+            let mut options = gif::DecodeOptions::new();
+            options.set_color_output(gif::ColorOutput::RGBA);
+            let mut decoder = options
+                .read_info(Cursor::new(bytes))
+                .map_err(|e| decode_error(&e))?;
+            let mut canvas =
+                FrameBuffer::new(decoder.width() as u32, decoder.height() as u32, max_side)?;
+            let frame = decoder
+                .read_next_frame()
+                .map_err(|e| decode_error(&e))?
+                .ok_or_else(|| decode_error(&"the image has no frames"))?;
+            let region = Region {
+                x: frame.left as u32,
+                y: frame.top as u32,
+                width: frame.width as u32,
+                height: frame.height as u32,
+            };
+            canvas.draw(region, &frame.buffer, 4, true);
+            canvas
+        }
+        ImageFormat::Png => {
+            let mut decoder = png::Decoder::new(Cursor::new(bytes));
+            decoder.set_transformations(png::Transformations::normalize_to_color8());
+            let mut reader = decoder.read_info().map_err(|e| decode_error(&e))?;
+            let (width, height) = (reader.info().width, reader.info().height);
+            let mut canvas = FrameBuffer::new(width, height, max_side)?;
+            let mut buffer = vec![0; reader.output_buffer_size()];
+            let output = reader
+                .next_frame(&mut buffer)
+                .map_err(|e| decode_error(&e))?;
+            let whole = Region {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            };
+            canvas.draw(
+                whole,
+                &buffer[..output.buffer_size()],
+                output.color_type.samples(),
+                false,
+            );
+            canvas
+        }
+        _ => {
+            let mut decoder =
+                image_webp::WebPDecoder::new(Cursor::new(bytes)).map_err(|e| decode_error(&e))?;
+            let (width, height) = decoder.dimensions();
+            let mut canvas = FrameBuffer::new(width, height, max_side)?;
+            let channels = if decoder.has_alpha() { 4 } else { 3 };
+            let mut buffer = vec![0; width as usize * height as usize * channels];
+            decoder
+                .read_image(&mut buffer)
+                .map_err(|e| decode_error(&e))?;
+            let whole = Region {
+                x: 0,
+                y: 0,
+                width,
+                height,
+            };
+            canvas.draw(whole, &buffer, channels, false);
+            canvas
+        }
+    };
+    Ok(RgbaImage::new(canvas.width, canvas.height, canvas.pixels))
+}
+
+/// フレーム内の矩形
+#[derive(Debug, Clone, Copy)]
+struct Region {
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+}
+
+/// 合成用のRGBAバッファ（透明で始める）
+struct FrameBuffer {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl FrameBuffer {
+    fn new(width: u32, height: u32, max_side: u32) -> Result<Self, AnimationError> {
+        if width == 0 || height == 0 || width > max_side || height > max_side {
+            return Err(AnimationError::TooLarge {
+                width,
+                height,
+                max: max_side,
+            });
+        }
+        Ok(Self {
+            width,
+            height,
+            pixels: vec![0; width as usize * height as usize * 4],
+        })
+    }
+
+    /// `channels` チャンネル（1〜4）の画素列を矩形に描く
+    ///
+    /// `blend` では透明度に応じて下の画素と混ぜ、それ以外は上書きする。はみ出した部分は捨てる。
+    fn draw(&mut self, region: Region, pixels: &[u8], channels: usize, blend: bool) {
+        for row in 0..region.height {
+            let y = region.y + row;
+            if y >= self.height {
+                break;
+            }
+            for column in 0..region.width {
+                let x = region.x + column;
+                if x >= self.width {
+                    break;
+                }
+                let source = (row as usize * region.width as usize + column as usize) * channels;
+                let Some(sample) = pixels.get(source..source + channels) else {
+                    return;
+                };
+                let src = match channels {
+                    1 => [sample[0], sample[0], sample[0], 255],
+                    2 => [sample[0], sample[0], sample[0], sample[1]],
+                    3 => [sample[0], sample[1], sample[2], 255],
+                    _ => [sample[0], sample[1], sample[2], sample[3]],
+                };
+                let target = (y as usize * self.width as usize + x as usize) * 4;
+                let dst = &mut self.pixels[target..target + 4];
+                if !blend || src[3] == 255 {
+                    dst.copy_from_slice(&src);
+                } else if src[3] > 0 {
+                    let alpha = src[3] as u32;
+                    let under = dst[3] as u32 * (255 - alpha) / 255;
+                    let out_alpha = alpha + under;
+                    for channel in 0..3 {
+                        dst[channel] = ((src[channel] as u32 * alpha + dst[channel] as u32 * under)
+                            / out_alpha) as u8;
+                    }
+                    dst[3] = out_alpha as u8;
+                }
+            }
+        }
+    }
+
+    /// 矩形を透明に戻す
+    fn clear(&mut self, region: Region) {
+        let transparent = vec![0; region.width as usize * region.height as usize * 4];
+        self.draw(region, &transparent, 4, false);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 2x1のGIFを、各フレームで1画素ずつ黒く塗るアニメーションとして作る
+    fn gif(frames: usize, dispose: gif::DisposalMethod) -> Vec<u8> {
+        let palette = [255, 255, 255, 0, 0, 0];
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut bytes, 2, 1, &palette).unwrap();
+            for index in 0..frames {
+                let frame = gif::Frame {
+                    left: (index % 2) as u16,
+                    width: 1,
+                    height: 1,
+                    dispose,
+                    buffer: std::borrow::Cow::Owned(vec![1]),
+                    ..Default::default()
+                };
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+        bytes
+    }
+
+    fn black_pixels(frame: AnimationFrame<'_>) -> Vec<usize> {
+        frame
+            .rgba
+            .chunks_exact(4)
+            .enumerate()
+            .filter(|(_, pixel)| pixel[3] == 255 && pixel[0] == 0)
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    #[test]
+    fn test_gif_frames_are_composited_in_order() {
+        let kept = decode_frames(&gif(3, gif::DisposalMethod::Keep), 50, 1000, black_pixels)
+            .unwrap()
+            .unwrap();
+        assert_eq!(kept, vec![vec![0], vec![0, 1], vec![0, 1]]);
+
+        // 背景で消す場合は、前のフレームの画素が残らない
+        let cleared = decode_frames(
+            &gif(3, gif::DisposalMethod::Background),
+            50,
+            1000,
+            black_pixels,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(cleared, vec![vec![0], vec![1], vec![0]]);
+    }
+
+    #[test]
+    fn test_frame_limits() {
+        assert!(matches!(
+            decode_frames(&gif(4, gif::DisposalMethod::Keep), 3, 1000, |_| ()),
+            Err(AnimationError::TooManyFrames { max: 3 })
+        ));
+        assert!(matches!(
+            decode_frames(&gif(2, gif::DisposalMethod::Keep), 50, 1, |_| ()),
+            Err(AnimationError::TooLarge { width: 2, .. })
+        ));
+        // 1枚だけのGIFやアニメーションでない画像は分割しない
+        assert!(
+            decode_frames(&gif(1, gif::DisposalMethod::Keep), 50, 1000, |_| ())
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            decode_frames(b"not an image", 50, 1000, |_| ())
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(detect_animation(b"plain text"), None);
+    }
+
+    #[test]
+    fn test_decode_image_reads_stills_and_the_first_animation_frame() {
+        // 2x1のグレースケールPNG（黒・白）
+        let mut png_bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut png_bytes, 2, 1);
+            encoder.set_color(png::ColorType::Grayscale);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[0, 255]).unwrap();
+        }
+        let image = decode_image(&png_bytes, 1000).unwrap();
+        assert_eq!((image.width, image.height), (2, 1));
+        assert_eq!(image.pixels, vec![0, 0, 0, 255, 255, 255, 255, 255]);
+
+        let first = decode_image(&gif(3, gif::DisposalMethod::Keep), 1000).unwrap();
+        assert_eq!(&first.pixels[..4], &[0, 0, 0, 255]);
+        assert_eq!(
+            first.pixels[7], 0,
+            "the second pixel is drawn by a later frame"
+        );
+
+        assert!(matches!(
+            decode_image(&png_bytes, 1),
+            Err(AnimationError::TooLarge { width: 2, .. })
+        ));
+        assert!(matches!(
+            decode_image(b"not an image", 1000),
+            Err(AnimationError::UnsupportedFormat)
+        ));
+    }
+}
@@ -0,0 +1,65 @@
+//! データディレクトリの作成と、アプリケーションのデータディレクトリであることを示す目印
+//!
+//! `reset-data` は目印のあるディレクトリしか初期化しないため、`--data-dir` の指定を誤っても
+//! ホームディレクトリなど、アプリケーションが作っていないディレクトリの中身は消えない。
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// アプリケーションが作ったデータディレクトリに置くファイルの名前
+pub const DATA_DIR_MARKER: &str = ".splatoon3-ghost-drawer";
+
+const MARKER_CONTENTS: &str = "This directory holds splatoon3-ghost-drawer data.\n\
+     `splatoon3-ghost-drawer reset-data` only deletes directories that contain this file.\n";
+
+/// データディレクトリを作成する
+///
+/// 新しく作った場合と空だった場合だけ目印を置き、既にファイルのあるディレクトリには置かない。
+pub fn create_data_dir(path: &Path) -> io::Result<()> {
+    if is_marked(path) {
+        return Ok(());
+    }
+    let unused = match fs::read_dir(path) {
+        Ok(mut entries) => entries.next().is_none(),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => true,
+        Err(e) => return Err(e),
+    };
+    fs::create_dir_all(path)?;
+    if unused {
+        fs::write(path.join(DATA_DIR_MARKER), MARKER_CONTENTS)?;
+    }
+    Ok(())
+}
+
+/// 目印のあるデータディレクトリかどうか（目印へのシンボリックリンクは認めない）
+pub fn is_marked(path: &Path) -> bool {
+    fs::symlink_metadata(path.join(DATA_DIR_MARKER)).is_ok_and(|metadata| metadata.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_new_or_empty_directories_are_marked() {
+        let root = std::env::temp_dir().join(format!("data-dir-{}", uuid::Uuid::new_v4()));
+
+        let created = root.join("created");
+        create_data_dir(&created).unwrap();
+        assert!(is_marked(&created));
+
+        let empty = root.join("empty");
+        fs::create_dir_all(&empty).unwrap();
+        create_data_dir(&empty).unwrap();
+        assert!(is_marked(&empty));
+
+        let used = root.join("used");
+        fs::create_dir_all(&used).unwrap();
+        fs::write(used.join("notes.txt"), "mine").unwrap();
+        create_data_dir(&used).unwrap();
+        assert!(!is_marked(&used));
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
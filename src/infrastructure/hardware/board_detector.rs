@@ -1,6 +1,5 @@
 use crate::domain::hardware::{Board, BoardModel, BoardRepository, HardwareError};
 use async_trait::async_trait;
-use std::os::unix::process::ExitStatusExt;
 use std::path::Path;
 use tokio::fs;
 use tokio::process::Command;
@@ -41,21 +40,12 @@ impl LinuxBoardDetector {
     }
 
     async fn check_module_loaded(&self, module_name: &str) -> bool {
-        let output =
-            Command::new("lsmod")
-                .output()
-                .await
-                .unwrap_or_else(|_| std::process::Output {
-                    status: std::process::ExitStatus::from_raw(1),
-                    stdout: Vec::new(),
-                    stderr: Vec::new(),
-                });
-
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            stdout.lines().any(|line| line.starts_with(module_name))
-        } else {
-            false
+        match Command::new("lsmod").output().await {
+            Ok(output) if output.status.success() => {
+                let stdout = String::from_utf8_lossy(&output.stdout);
+                stdout.lines().any(|line| line.starts_with(module_name))
+            }
+            _ => false,
         }
     }
 
@@ -146,9 +136,10 @@ impl BoardRepository for LinuxBoardDetector {
         })?;
 
         // Check if running with sufficient privileges
-        let euid = unsafe { libc::geteuid() };
-        if euid != 0 {
-            return Err(HardwareError::PermissionDenied);
+        if !crate::infrastructure::platform::is_root() {
+            return Err(HardwareError::PermissionDenied {
+                context: format!("Updating {config_path}"),
+            });
         }
 
         // Read current config
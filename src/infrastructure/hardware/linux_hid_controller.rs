@@ -1,17 +1,79 @@
-use crate::domain::controller::{ActionType, Button, ControllerCommand, ControllerEmulator, DPad};
+use crate::domain::controller::{
+    ActionType, Button, ButtonState, ControllerCommand, ControllerEmulator, DPad, HidReport,
+    StickPosition,
+};
 use crate::domain::hardware::errors::HardwareError;
-use std::fs::OpenOptions;
+use crate::domain::hardware::metrics::{ReportErrorClass, ReportMetricsSink};
+use crate::infrastructure::hardware::linux_usb_gadget_manager::GADGET_NAME;
 use std::io::Write;
 use std::path::Path;
-use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
 use std::thread;
-use std::time::Duration;
-use tracing::{debug, error, info, warn};
+use std::time::{Duration, Instant};
+use tracing::{debug, error, info, trace, warn};
+
+/// 入力中にレポートを送り続ける間隔（125Hz）
+const REPORT_INTERVAL: Duration = Duration::from_millis(8);
+/// ホストが前のレポートを受け取るまで書き込みを再試行する時間
+///
+/// これを超えて書き込めない場合はホストが応答していない（スリープなど）とみなす。
+const REPORT_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+/// 書き込みを再試行する間隔
+const REPORT_RETRY_INTERVAL: Duration = Duration::from_millis(1);
+/// レポートの送信数をまとめてログに出す間隔
+///
+/// 個々のレポートは `trace` レベルでしか出さないため、送信できているかはこの集計で確認する。
+const REPORT_SUMMARY_INTERVAL: Duration = Duration::from_secs(10);
 
 /// Linux HIDデバイスを使用したコントローラーエミュレーター
 pub struct LinuxHidController {
+    /// ガジェットのconfigfsディレクトリ
+    gadget_path: String,
+    /// 使用するHIDデバイスファイル（`None` なら `/dev/hidg0`〜`/dev/hidg3` から探す）
+    device_override: Option<String>,
     device_path: Mutex<Option<String>>,
     current_state: Mutex<ProControllerState>,
+    last_report: Mutex<Option<[u8; 8]>>,
+    /// レポートの送信に続けて失敗し始めた時刻（送信できれば `None`）
+    unresponsive_since: Mutex<Option<Instant>>,
+    report_stats: Mutex<ReportStats>,
+    /// 送信結果の通知先（未設定の場合は集計しない）
+    metrics: Option<Arc<dyn ReportMetricsSink>>,
+}
+
+/// 集計期間中のレポートの送信数
+#[derive(Debug)]
+struct ReportStats {
+    window_start: Instant,
+    sent: u64,
+    errors: u64,
+}
+
+impl ReportStats {
+    fn new(now: Instant) -> Self {
+        Self {
+            window_start: now,
+            sent: 0,
+            errors: 0,
+        }
+    }
+
+    /// 送信結果を数え、集計期間が過ぎていれば `(送信数, エラー数, 期間)` を返して数え直す
+    fn record(&mut self, sent: bool, now: Instant) -> Option<(u64, u64, Duration)> {
+        if sent {
+            self.sent += 1;
+        } else {
+            self.errors += 1;
+        }
+        let elapsed = now.saturating_duration_since(self.window_start);
+        if elapsed < REPORT_SUMMARY_INTERVAL {
+            return None;
+        }
+        let summary = (self.sent, self.errors, elapsed);
+        *self = Self::new(now);
+        Some(summary)
+    }
 }
 
 #[derive(Clone, Copy, Debug)]
@@ -36,13 +98,59 @@ impl Default for ProControllerState {
     }
 }
 
+impl ProControllerState {
+    /// HAT（ビット16-19）とボタンを分けてレポートに変換
+    fn to_hid_report(self) -> HidReport {
+        HidReport {
+            buttons: ButtonState::from_raw((self.buttons & 0xFFFF) as u16),
+            dpad: DPad::new(((self.buttons >> 16) & 0x0F) as u8),
+            left_stick: StickPosition::new(self.left_stick_x, self.left_stick_y),
+            right_stick: StickPosition::new(self.right_stick_x, self.right_stick_y),
+        }
+    }
+
+    /// `to_hid_report` の逆変換（`ActionType::SetReport` で状態を丸ごと置き換える）
+    fn from_hid_report(report: &HidReport) -> Self {
+        Self {
+            buttons: report.buttons.raw_value() as u32 | (report.dpad.value() as u32) << 16,
+            left_stick_x: report.left_stick.x,
+            left_stick_y: report.left_stick.y,
+            right_stick_x: report.right_stick.x,
+            right_stick_y: report.right_stick.y,
+        }
+    }
+}
+
 impl LinuxHidController {
     pub fn new() -> Self {
         Self {
+            gadget_path: format!("/sys/kernel/config/usb_gadget/{GADGET_NAME}"),
+            device_override: None,
             device_path: Mutex::new(None),
             current_state: Mutex::new(ProControllerState::default()),
+            last_report: Mutex::new(None),
+            unresponsive_since: Mutex::new(None),
+            report_stats: Mutex::new(ReportStats::new(Instant::now())),
+            metrics: None,
         }
     }
+
+    /// レポートの送信結果を `metrics` に通知する
+    pub fn with_metrics(mut self, metrics: Arc<dyn ReportMetricsSink>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// 別名で作ったガジェット（ループバックテストなど）を使う
+    pub fn with_gadget(
+        mut self,
+        gadget_path: impl Into<String>,
+        device_path: impl Into<String>,
+    ) -> Self {
+        self.gadget_path = gadget_path.into();
+        self.device_override = Some(device_path.into());
+        self
+    }
 }
 
 impl Default for LinuxHidController {
@@ -54,6 +162,16 @@ impl Default for LinuxHidController {
 impl LinuxHidController {
     /// HIDデバイスパスを検索
     fn find_hid_device(&self) -> Result<String, HardwareError> {
+        if let Some(path) = &self.device_override {
+            if Path::new(path).exists() {
+                info!("Using HID device at: {}", path);
+                return Ok(path.clone());
+            }
+            return Err(HardwareError::DeviceNotFound(format!(
+                "HID gadget device {path} not found"
+            )));
+        }
+
         let hid_paths = ["/dev/hidg0", "/dev/hidg1", "/dev/hidg2", "/dev/hidg3"];
 
         for path in &hid_paths {
@@ -69,103 +187,155 @@ impl LinuxHidController {
     }
 
     /// 現在の状態をHIDレポートとして送信
+    /// 現在の入力を保持したまま、指定時間レポートを送り続ける
+    ///
+    /// 途中で `cancel` が立った場合はニュートラルのレポートを1回送って `false` を返す。
+    fn hold(&self, duration_ms: u32, cancel: &AtomicBool) -> Result<bool, HardwareError> {
+        let start_time = std::time::Instant::now();
+        let duration = Duration::from_millis(duration_ms as u64);
+        while start_time.elapsed() < duration {
+            if cancel.load(Ordering::SeqCst) {
+                self.cancel_to_neutral()?;
+                return Ok(false);
+            }
+            self.send_report()?;
+            thread::sleep(REPORT_INTERVAL);
+        }
+        Ok(true)
+    }
+
+    /// レポートを送らずに待機する（`cancel` の扱いは `hold` と同じ）
+    fn wait(&self, duration_ms: u32, cancel: &AtomicBool) -> Result<bool, HardwareError> {
+        let deadline = std::time::Instant::now() + Duration::from_millis(duration_ms as u64);
+        loop {
+            if cancel.load(Ordering::SeqCst) {
+                self.cancel_to_neutral()?;
+                return Ok(false);
+            }
+            let remaining = deadline.saturating_duration_since(std::time::Instant::now());
+            if remaining.is_zero() {
+                return Ok(true);
+            }
+            thread::sleep(remaining.min(REPORT_INTERVAL));
+        }
+    }
+
+    /// 中断時にすべての入力を離したレポートを送る
+    fn cancel_to_neutral(&self) -> Result<(), HardwareError> {
+        debug!("Controller command cancelled; sending neutral report");
+        *self.current_state.lock().unwrap() = ProControllerState::default();
+        self.send_report()
+    }
+
+    /// ホストがレポートを受け取ったかどうかを記録する
+    fn record_host_response(&self, accepted: bool) {
+        let mut since = self.unresponsive_since.lock().unwrap();
+        if accepted {
+            if let Some(started) = since.take() {
+                info!(
+                    "Host is accepting reports again after {:?}",
+                    started.elapsed()
+                );
+            }
+        } else if since.is_none() {
+            warn!("Host stopped accepting reports (the Switch may be asleep)");
+            *since = Some(Instant::now());
+        }
+    }
+
     fn send_report(&self) -> Result<(), HardwareError> {
+        // Pokken Controller Report (8 bytes)
+        let report = self
+            .current_state
+            .lock()
+            .unwrap()
+            .to_hid_report()
+            .to_bytes();
+        self.send_bytes(&report)?;
+        trace!(
+            "HID Report: Btn={:04X} HAT={:02X} L=({},{}) R=({},{}) Raw=[{:02X},{:02X},{:02X},{:02X},{:02X},{:02X},{:02X},{:02X}]",
+            (report[1] as u16) << 8 | report[0] as u16,
+            report[2],
+            report[3],
+            report[4],
+            report[5],
+            report[6],
+            report[0],
+            report[1],
+            report[2],
+            report[3],
+            report[4],
+            report[5],
+            report[6],
+            report[7]
+        );
+        Ok(())
+    }
+
+    /// レポートを書き込み、送信結果を集計する
+    fn send_bytes(&self, report: &[u8]) -> Result<usize, HardwareError> {
+        let result = self.write_report(report);
+        if let Some(metrics) = &self.metrics {
+            match &result {
+                Ok(bytes) => metrics.report_sent(*bytes, Instant::now()),
+                Err(error) => metrics.report_failed(ReportErrorClass::of(error)),
+            }
+        }
+        let summary = self
+            .report_stats
+            .lock()
+            .unwrap()
+            .record(result.is_ok(), Instant::now());
+        if let Some((sent, errors, elapsed)) = summary {
+            info!(
+                "Sent {} HID reports in the last {:.0}s, {} errors",
+                sent,
+                elapsed.as_secs_f64(),
+                errors
+            );
+        }
+        result
+    }
+
+    /// レポートを書き込み、書き込んだバイト数を返す
+    fn write_report(&self, report: &[u8]) -> Result<usize, HardwareError> {
         let device_path = self.device_path.lock().unwrap();
         if let Some(path) = device_path.as_ref() {
-            let state = self.current_state.lock().unwrap();
-
-            // Pokken Controller Report (8 bytes)
-            let mut report = [0u8; 8];
-
-            // Byte 0-1: Buttons (Little Endian)
-            report[0] = (state.buttons & 0xFF) as u8;
-            report[1] = ((state.buttons >> 8) & 0xFF) as u8;
-
-            // Byte 2: HAT (Lower 4 bits)
-            // HAT is stored in bits 16-19 of state.buttons in our internal representation for DPad
-            // But here we need to extract it or recalculate it.
-            // Actually, let's look at how dpad_to_bits stores it.
-            // It stores it as 0x00000 to 0x80000.
-            // Let's assume state.buttons bits 16-19 hold the HAT value if we change dpad_to_bits.
-            // For now, let's decode the HAT from the upper bits of state.buttons if we use that storage,
-            // OR better, let's just use a separate field for HAT in ProControllerState if we want to be clean.
-            // However, to minimize changes, let's look at dpad_to_bits below.
-            // It currently returns 0x00000 etc.
-            // Let's change dpad_to_bits to return the 4-bit value and store it in a specific place,
-            // or just extract it here.
-            // Wait, the current implementation of dpad_to_bits returns a bitmask for Pro Controller?
-            // No, Pro Controller HAT is 0-7.
-            // The current dpad_to_bits returns 0x00000, 0x10000... which looks like it's trying to map to specific bits?
-            // Actually, looking at the previous code:
-            // 0x00 => 0x00000 (UP)
-            // ...
-            // _ => 0x80000 (NEUTRAL)
-            // This suggests the HAT value was being stored in the 3rd byte (bits 16-23) of the buttons field?
-            // In the previous send_report:
-            // report[5] = ((state.buttons >> 16) & 0xFF) as u8;
-            // So yes, it was stored in byte 5.
-            // For Pokken, HAT is in Byte 2.
-            // Let's extract it.
-            let hat_value = (state.buttons >> 16) & 0x0F;
-            report[2] = hat_value as u8;
-
-            // Byte 3: LX
-            report[3] = state.left_stick_x;
-            // Byte 4: LY
-            report[4] = state.left_stick_y;
-            // Byte 5: RX
-            report[5] = state.right_stick_x;
-            // Byte 6: RY
-            report[6] = state.right_stick_y;
-            // Byte 7: Vendor
-            report[7] = 0x00;
-
-            // HIDデバイスに書き込み（エラーハンドリング改善）
-            match OpenOptions::new().write(true).open(path) {
-                Ok(mut file) => {
-                    match file.write_all(&report) {
-                        Ok(_) => {
-                            info!(
-                                "HID Report: Btn={:04X} HAT={:02X} L=({},{}) R=({},{}) Raw=[{:02X},{:02X},{:02X},{:02X},{:02X},{:02X},{:02X},{:02X}]",
-                                (report[1] as u16) << 8 | report[0] as u16,
-                                report[2],
-                                report[3],
-                                report[4],
-                                report[5],
-                                report[6],
-                                report[0],
-                                report[1],
-                                report[2],
-                                report[3],
-                                report[4],
-                                report[5],
-                                report[6],
-                                report[7]
-                            );
-                            Ok(())
-                        }
-                        Err(e) => {
-                            if e.kind() == std::io::ErrorKind::BrokenPipe
-                                || e.raw_os_error() == Some(108)
-                            // ESHUTDOWN
-                            {
-                                warn!("HID device disconnected: {}", e);
-                                Err(HardwareError::NotConnected)
-                            } else {
-                                error!("Failed to write HID report: {}", e);
-                                Err(HardwareError::IoError(e))
-                            }
-                        }
-                    }
-                }
+            // ホストが応答しないときに書き込みが止まったままにならないよう、
+            // ノンブロッキングで開いて `REPORT_WRITE_TIMEOUT` の間だけ再試行する
+            let mut file = match crate::infrastructure::platform::open_nonblocking_write(path) {
+                Ok(file) => file,
                 Err(e) => {
-                    if e.kind() == std::io::ErrorKind::PermissionDenied {
-                        error!("Permission denied accessing HID device: {}", path);
-                        Err(HardwareError::PermissionDenied)
-                    } else {
-                        error!("Failed to open HID device {}: {}", path, e);
-                        Err(HardwareError::IoError(e))
+                    let error =
+                        HardwareError::classify_io(e, &format!("Opening HID device {path}"));
+                    error!("{}", error);
+                    return Err(error);
+                }
+            };
+            let deadline = Instant::now() + REPORT_WRITE_TIMEOUT;
+            loop {
+                match file.write_all(report) {
+                    Ok(_) => {
+                        self.record_host_response(true);
+                        if let Ok(report) = report.try_into() {
+                            *self.last_report.lock().unwrap() = Some(report);
+                        }
+                        return Ok(report.len());
                     }
+                    Err(e) => match HardwareError::classify_io(e, "Writing HID report") {
+                        HardwareError::WouldBlock { .. } if Instant::now() < deadline => {
+                            thread::sleep(REPORT_RETRY_INTERVAL);
+                        }
+                        error if error.is_host_unresponsive() => {
+                            debug!("HID report was not accepted by the host: {}", error);
+                            self.record_host_response(false);
+                            return Err(error);
+                        }
+                        error => {
+                            error!("{}", error);
+                            return Err(error);
+                        }
+                    },
                 }
             }
         } else {
@@ -246,8 +416,7 @@ impl ControllerEmulator for LinuxHidController {
         info!("Initializing Linux HID controller...");
 
         // USB Gadgetが設定されているか確認
-        let gadget_path = Path::new("/sys/kernel/config/usb_gadget/nintendo_controller");
-        if !gadget_path.exists() {
+        if !Path::new(&self.gadget_path).exists() {
             error!("USB Gadget not configured. Run 'sudo splatoon3-ghost-drawer setup' first.");
             return Err(HardwareError::GadgetConfigurationFailed(
                 "USB Gadget not configured".to_string(),
@@ -260,11 +429,10 @@ impl ControllerEmulator for LinuxHidController {
 
         // デバイスの権限を確認
         if let Err(e) = std::fs::metadata(&device_path) {
-            error!("Cannot access HID device {}: {}", device_path, e);
-            if e.kind() == std::io::ErrorKind::PermissionDenied {
-                return Err(HardwareError::PermissionDenied);
-            }
-            return Err(HardwareError::IoError(e));
+            let error =
+                HardwareError::classify_io(e, &format!("Accessing HID device {device_path}"));
+            error!("{}", error);
+            return Err(error);
         }
 
         // デバイスパスを保存
@@ -281,7 +449,12 @@ impl ControllerEmulator for LinuxHidController {
 
                 // エラーの種類に応じて詳細情報を提供
                 match &e {
-                    HardwareError::NotConnected => {
+                    HardwareError::Disconnected { .. } => {
+                        error!("Broken pipe when writing to HID device");
+                        println!("\n❌ HID device connection was broken.");
+                        println!("   The USB connection may have been interrupted.");
+                    }
+                    e if matches!(e, HardwareError::NotConnected) || e.is_host_unresponsive() => {
                         error!("HID device appears to be disconnected. This can happen if:");
                         error!("1. Nintendo Switch is not ready to receive input");
                         error!("2. USB cable is not properly connected");
@@ -292,18 +465,11 @@ impl ControllerEmulator for LinuxHidController {
                         println!("   2. Reconnect the USB cable");
                         println!("   3. Run 'sudo systemctl restart splatoon3-gadget.service'");
                     }
-                    HardwareError::PermissionDenied => {
+                    HardwareError::PermissionDenied { .. } => {
                         error!("Permission denied accessing HID device");
                         println!("\n❌ Permission denied accessing HID device.");
                         println!("   This command must be run with sudo.");
                     }
-                    HardwareError::IoError(io_err)
-                        if io_err.kind() == std::io::ErrorKind::BrokenPipe =>
-                    {
-                        error!("Broken pipe when writing to HID device");
-                        println!("\n❌ HID device connection was broken.");
-                        println!("   The USB connection may have been interrupted.");
-                    }
                     _ => {
                         error!("Unexpected error during initialization");
                     }
@@ -325,16 +491,17 @@ impl ControllerEmulator for LinuxHidController {
             }
 
             // USB Gadgetの状態を確認
-            let gadget_path = Path::new("/sys/kernel/config/usb_gadget/nintendo_controller/UDC");
-            if !gadget_path.exists() {
+            let gadget_path = format!("{}/UDC", self.gadget_path);
+            if !Path::new(&gadget_path).exists() {
                 warn!("USB Gadget UDC path does not exist");
                 return Ok(false);
             }
 
             // UDCの状態確認（権限エラーはエラーとして扱う＝厳格なチェック）
-            let udc_content = std::fs::read_to_string(gadget_path).map_err(|e| {
-                error!("Failed to read UDC status: {}", e);
-                HardwareError::IoError(e)
+            let udc_content = std::fs::read_to_string(&gadget_path).map_err(|e| {
+                let error = HardwareError::classify_io(e, "Reading UDC status");
+                error!("{}", error);
+                error
             })?;
 
             let is_connected = !udc_content.trim().is_empty();
@@ -347,12 +514,7 @@ impl ControllerEmulator for LinuxHidController {
 
             // 実際にHIDデバイスに書き込めるかテスト（接続状態の確認）
             // O_NONBLOCKを使用して、ブロッキングを防ぎつつ厳格にチェックする
-            use std::os::unix::fs::OpenOptionsExt;
-            match OpenOptions::new()
-                .write(true)
-                .custom_flags(libc::O_NONBLOCK) // ノンブロッキングモード
-                .open(path)
-            {
+            match crate::infrastructure::platform::open_nonblocking_write(path) {
                 Ok(mut file) => {
                     // NEUTRAL状態のレポートを送信してテスト
                     let test_report = [0x00, 0x00, 0x08, 0x80, 0x80, 0x80, 0x80, 0x00];
@@ -361,32 +523,37 @@ impl ControllerEmulator for LinuxHidController {
                             debug!(
                                 "HID device is writable and connected (Non-blocking check passed)"
                             );
+                            self.record_host_response(true);
                             Ok(true)
                         }
-                        Err(e) => {
-                            if e.kind() == std::io::ErrorKind::WouldBlock {
+                        Err(e) => match HardwareError::classify_io(e, "Testing HID device") {
+                            HardwareError::WouldBlock { .. } => {
                                 // ブロックされる＝ホストがポーリングしていない可能性があるが、
                                 // デバイスファイルが開けている以上、物理的には接続されているとみなす。
                                 // ここでfalseを返すと、ポーリング間隔のタイミング次第で「未接続」と判定されてしまう。
                                 debug!(
                                     "HID device write would block (Buffer full or Host not polling). Assuming connected."
                                 );
+                                self.record_host_response(false);
                                 Ok(true)
-                            } else if e.kind() == std::io::ErrorKind::BrokenPipe
-                                || e.raw_os_error() == Some(108)
-                            // ESHUTDOWN
-                            {
-                                warn!("HID device not ready: {}", e);
+                            }
+                            error if error.is_host_unresponsive() => {
+                                warn!("HID device not ready: {}", error);
+                                self.record_host_response(false);
                                 Ok(false)
-                            } else {
-                                error!("Failed to test HID device: {}", e);
-                                Err(HardwareError::IoError(e))
                             }
-                        }
+                            error => {
+                                error!("{}", error);
+                                Err(error)
+                            }
+                        },
                     }
                 }
                 Err(e) => {
-                    error!("Cannot open HID device for testing: {}", e);
+                    error!(
+                        "{}",
+                        HardwareError::classify_io(e, &format!("Opening HID device {path}"))
+                    );
                     Ok(false)
                 }
             }
@@ -397,12 +564,20 @@ impl ControllerEmulator for LinuxHidController {
     }
 
     fn execute_command(&self, command: &ControllerCommand) -> Result<(), HardwareError> {
+        self.execute_command_cancellable(command, &AtomicBool::new(false))
+    }
+
+    fn execute_command_cancellable(
+        &self,
+        command: &ControllerCommand,
+        cancel: &AtomicBool,
+    ) -> Result<(), HardwareError> {
         debug!("Executing controller command: {}", command.name);
 
         for action in &command.sequence {
             match &action.action_type {
                 ActionType::PressButton(button) => {
-                    info!(
+                    debug!(
                         "PressButton: {:?}, bits: 0x{:04X}",
                         button,
                         Self::button_to_bits(button)
@@ -413,22 +588,17 @@ impl ControllerEmulator for LinuxHidController {
                     state.buttons &= 0xFFF0FFFF; // D-padビット（16-19）をクリア
                     state.buttons |= (DPad::NEUTRAL.value() as u32) << 16; // NEUTRAL状態を設定
                     state.buttons |= Self::button_to_bits(button);
-                    info!("State buttons after press: 0x{:08X}", state.buttons);
+                    debug!("State buttons after press: 0x{:08X}", state.buttons);
                     // スティックの値は変更しない（現在の値を維持）
                     // これにより、意図しないスティック入力を防ぐ
                     drop(state);
                     // 押下中は継続的にレポートを送信（8ms間隔 = 125Hz）
-                    let start_time = std::time::Instant::now();
-                    let duration = Duration::from_millis(action.duration_ms as u64);
-                    let report_interval = Duration::from_millis(8);
-
-                    while start_time.elapsed() < duration {
-                        self.send_report()?;
-                        thread::sleep(report_interval);
+                    if !self.hold(action.duration_ms, cancel)? {
+                        return Ok(());
                     }
                 }
                 ActionType::ReleaseButton(button) => {
-                    info!(
+                    debug!(
                         "ReleaseButton: {:?}, bits: 0x{:04X}",
                         button,
                         Self::button_to_bits(button)
@@ -438,21 +608,16 @@ impl ControllerEmulator for LinuxHidController {
                     // D-padビットもクリアしてNEUTRALに設定
                     state.buttons &= 0xFFF0FFFF; // D-padビット（16-19）をクリア
                     state.buttons |= (DPad::NEUTRAL.value() as u32) << 16; // NEUTRAL状態を設定
-                    info!("State buttons after release: 0x{:08X}", state.buttons);
+                    debug!("State buttons after release: 0x{:08X}", state.buttons);
                     // スティックの値は変更しない（現在の値を維持）
                     drop(state);
                     // リリース中も継続的にレポートを送信（8ms間隔 = 125Hz）
-                    let start_time = std::time::Instant::now();
-                    let duration = Duration::from_millis(action.duration_ms as u64);
-                    let report_interval = Duration::from_millis(8);
-
-                    while start_time.elapsed() < duration {
-                        self.send_report()?;
-                        thread::sleep(report_interval);
+                    if !self.hold(action.duration_ms, cancel)? {
+                        return Ok(());
                     }
                 }
                 ActionType::SetDPad(dpad) => {
-                    info!(
+                    debug!(
                         "SetDPad: {:?}, bits: 0x{:08X}",
                         dpad,
                         Self::dpad_to_bits(dpad)
@@ -461,18 +626,13 @@ impl ControllerEmulator for LinuxHidController {
                     // DPadビットをクリアしてから設定
                     state.buttons &= 0xFFF0FFFF;
                     state.buttons |= Self::dpad_to_bits(dpad);
-                    info!("State buttons after DPad: 0x{:08X}", state.buttons);
+                    debug!("State buttons after DPad: 0x{:08X}", state.buttons);
                     // スティックの値は変更しない（現在の値を維持）
                     // これにより、D-pad使用時にスティックからの意図しない入力を防ぐ
                     drop(state);
                     // DPad入力中も継続的にレポートを送信（8ms間隔 = 125Hz）
-                    let start_time = std::time::Instant::now();
-                    let duration = Duration::from_millis(action.duration_ms as u64);
-                    let report_interval = Duration::from_millis(8);
-
-                    while start_time.elapsed() < duration {
-                        self.send_report()?;
-                        thread::sleep(report_interval);
+                    if !self.hold(action.duration_ms, cancel)? {
+                        return Ok(());
                     }
                 }
                 ActionType::MoveLeftStick(position) => {
@@ -481,13 +641,8 @@ impl ControllerEmulator for LinuxHidController {
                     state.left_stick_y = position.y;
                     drop(state);
                     // 左スティック入力中も継続的にレポートを送信（8ms間隔 = 125Hz）
-                    let start_time = std::time::Instant::now();
-                    let duration = Duration::from_millis(action.duration_ms as u64);
-                    let report_interval = Duration::from_millis(8);
-
-                    while start_time.elapsed() < duration {
-                        self.send_report()?;
-                        thread::sleep(report_interval);
+                    if !self.hold(action.duration_ms, cancel)? {
+                        return Ok(());
                     }
                     // スティック移動後、自動的に中央に戻す
                     // CENTER (128, 128) でない場合のみリセット
@@ -499,7 +654,7 @@ impl ControllerEmulator for LinuxHidController {
                         // ニュートラル状態を確実に送信
                         for _ in 0..5 {
                             self.send_report()?;
-                            thread::sleep(report_interval);
+                            thread::sleep(REPORT_INTERVAL);
                         }
                     }
                 }
@@ -509,13 +664,23 @@ impl ControllerEmulator for LinuxHidController {
                     state.right_stick_y = position.y;
                     drop(state);
                     self.send_report()?;
-                    thread::sleep(Duration::from_millis(action.duration_ms as u64));
+                    if !self.wait(action.duration_ms, cancel)? {
+                        return Ok(());
+                    }
                 }
                 ActionType::Wait => {
-                    thread::sleep(Duration::from_millis(action.duration_ms as u64));
+                    if !self.wait(action.duration_ms, cancel)? {
+                        return Ok(());
+                    }
                 }
-                ActionType::SetReport(_) => {
-                    // Not implemented for this use case
+                ActionType::SetReport(report) => {
+                    debug!("SetReport: {:02X?}", report.to_bytes());
+                    *self.current_state.lock().unwrap() =
+                        ProControllerState::from_hid_report(report);
+                    // 指定した入力を保持したまま継続的にレポートを送信（8ms間隔 = 125Hz）
+                    if !self.hold(action.duration_ms, cancel)? {
+                        return Ok(());
+                    }
                 }
             }
         }
@@ -523,6 +688,11 @@ impl ControllerEmulator for LinuxHidController {
         Ok(())
     }
 
+    fn write_raw_report(&self, report: &[u8]) -> Result<usize, HardwareError> {
+        debug!("Writing raw HID report: {:02X?}", report);
+        self.send_bytes(report)
+    }
+
     fn shutdown(&self) -> Result<(), HardwareError> {
         info!("Shutting down Linux HID controller...");
 
@@ -532,8 +702,61 @@ impl ControllerEmulator for LinuxHidController {
 
         // デバイスパスをクリア
         *self.device_path.lock().unwrap() = None;
+        *self.last_report.lock().unwrap() = None;
 
         info!("Linux HID controller shut down successfully");
         Ok(())
     }
+
+    fn host_unresponsive_for(&self) -> Option<Duration> {
+        self.unresponsive_since
+            .lock()
+            .unwrap()
+            .map(|since| since.elapsed())
+    }
+
+    fn last_report(&self) -> Option<[u8; 8]> {
+        *self.last_report.lock().unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_report_state_round_trips_through_hid_report() {
+        let report =
+            HidReport::from_bytes(&[0x05, 0x10, 0x02, 0x00, 0xFF, 0x40, 0xC0, 0x00]).unwrap();
+        let state = ProControllerState::from_hid_report(&report);
+        assert_eq!(state.to_hid_report(), report);
+        assert_eq!(
+            ProControllerState::from_hid_report(&HidReport::new()).to_hid_report(),
+            ProControllerState::default().to_hid_report()
+        );
+    }
+
+    #[test]
+    fn test_report_stats_summarize_once_per_interval() {
+        let start = Instant::now();
+        let mut stats = ReportStats::new(start);
+        for i in 0..1249 {
+            let now = start + REPORT_INTERVAL * i;
+            assert_eq!(stats.record(true, now), None);
+        }
+        assert_eq!(
+            stats.record(false, start + Duration::from_millis(9_999)),
+            None
+        );
+
+        let end = start + REPORT_SUMMARY_INTERVAL;
+        assert_eq!(
+            stats.record(true, end),
+            Some((1250, 1, REPORT_SUMMARY_INTERVAL))
+        );
+        // 次の集計期間は0から数える
+        assert_eq!(stats.record(true, end + Duration::from_secs(1)), None);
+        assert_eq!(stats.sent, 1);
+        assert_eq!(stats.errors, 0);
+    }
 }
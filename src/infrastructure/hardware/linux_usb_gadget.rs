@@ -217,9 +217,9 @@ impl UsbGadgetRepository for LinuxUsbGadgetManager {
         // Link function to configuration
         let symlink_path = format!("{config_path}/hid.usb0");
         if !Path::new(&symlink_path).exists() {
-            std::os::unix::fs::symlink(&function_path, &symlink_path).map_err(|e| {
-                HardwareError::FileOperationFailed(format!("Failed to create symlink: {e}"))
-            })?;
+            crate::infrastructure::platform::symlink(&function_path, &symlink_path).map_err(
+                |e| HardwareError::FileOperationFailed(format!("Failed to create symlink: {e}")),
+            )?;
         }
 
         info!("USB gadget configured");
@@ -1,26 +1,538 @@
+use crate::domain::hardware::gadget_state::{GadgetState, HidDeviceNode, ReportDescriptorDump};
 use crate::domain::hardware::repositories::UsbGadgetManager;
-use crate::domain::setup::repositories::SetupError;
+use crate::domain::hardware::verification::{GadgetCheck, GadgetVerification};
+use crate::domain::setup::entities::{
+    AuditInitiator, BoardModel, GadgetAuditEntry, GadgetOperation,
+};
+use crate::domain::setup::repositories::{
+    BoardDetector, GadgetAuditLog, SetupError, UdcUnavailableReason,
+};
+use crate::infrastructure::hardware::orange_pi_udc::OrangePiUdcPreparer;
+use std::fmt;
 use std::fs;
-use std::io::Write;
+use std::io::{self, Write};
 use std::path::Path;
 use std::process::Command;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tracing::{debug, error, info, warn};
 
-const GADGET_PATH: &str = "/sys/kernel/config/usb_gadget/nintendo_controller";
+/// configfsのガジェットを置くディレクトリ
+const CONFIGFS_GADGETS_PATH: &str = "/sys/kernel/config/usb_gadget";
+/// 本番のガジェット名
+pub const GADGET_NAME: &str = "nintendo_controller";
 const VID: &str = "0x0f0d"; // HORI CO., LTD.
 const PID: &str = "0x0092"; // Pokken Tournament DX Pro Pad
 
-pub struct LinuxUsbGadgetManager;
+/// ゲームパッドのHIDデバイスファイル
+pub const HID_DEVICE_PATH: &str = "/dev/hidg0";
+/// 入力レポートのバイト数
+pub const REPORT_LENGTH: usize = 8;
+/// バインド後、UDCが `configured` になるのを待つ時間（Switch未接続なら待ちきって警告にする）
+const UDC_CONFIGURED_TIMEOUT: Duration = Duration::from_secs(5);
+/// HIDデバイスファイルの作成を確認する間隔
+const DEVICE_NODE_POLL_INTERVAL: Duration = Duration::from_millis(100);
+const UDC_STATE_POLL_INTERVAL: Duration = Duration::from_millis(200);
+/// UDCを列挙するディレクトリ
+const UDC_CLASS_PATH: &str = "/sys/class/udc";
+/// 起動直後にモジュールの初期化を待ち、UDCが現れるまで待つ時間の既定値
+const UDC_WAIT_TIMEOUT: Duration = Duration::from_secs(30);
+/// UDCが現れたかを確認する間隔
+const UDC_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Pokken Tournament DX Pro Pad のHIDレポートディスクリプタ
+pub const PRO_CONTROLLER_REPORT_DESCRIPTOR: &[u8] = &[
+    0x05, 0x01, // Usage Page (Generic Desktop Ctrls)
+    0x09, 0x05, // Usage (Game Pad)
+    0xA1, 0x01, // Collection (Application)
+    0x15, 0x00, //   Logical Minimum (0)
+    0x25, 0x01, //   Logical Maximum (1)
+    0x35, 0x00, //   Physical Minimum (0)
+    0x45, 0x01, //   Physical Maximum (1)
+    0x75, 0x01, //   Report Size (1)
+    0x95, 0x10, //   Report Count (16)
+    0x05, 0x09, //   Usage Page (Button)
+    0x19, 0x01, //   Usage Minimum (0x01)
+    0x29, 0x10, //   Usage Maximum (0x10)
+    0x81, 0x02, //   Input (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position)
+    0x05, 0x01, //   Usage Page (Generic Desktop Ctrls)
+    0x25, 0x07, //   Logical Maximum (7)
+    0x46, 0x3B, 0x01, //   Physical Maximum (315)
+    0x75, 0x04, //   Report Size (4)
+    0x95, 0x01, //   Report Count (1)
+    0x65, 0x14, //   Unit (System: English Rotation, Length: Centimeter)
+    0x09, 0x39, //   Usage (Hat Switch)
+    0x81, 0x42, //   Input (Data,Var,Abs,No Wrap,Linear,Preferred State,Null State)
+    0x65, 0x00, //   Unit (None)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x01, //   Input (Const,Array,Abs,No Wrap,Linear,Preferred State,No Null Position)
+    0x26, 0xFF, 0x00, //   Logical Maximum (255)
+    0x46, 0xFF, 0x00, //   Physical Maximum (255)
+    0x09, 0x30, //   Usage (X)
+    0x09, 0x31, //   Usage (Y)
+    0x09, 0x32, //   Usage (Z)
+    0x09, 0x35, //   Usage (Rz)
+    0x75, 0x08, //   Report Size (8)
+    0x95, 0x04, //   Report Count (4)
+    0x81, 0x02, //   Input (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position)
+    0x06, 0x00, 0xFF, //   Usage Page (Vendor Defined 0xFF00)
+    0x09, 0x20, //   Usage (0x20)
+    0x95, 0x01, //   Report Count (1)
+    0x81, 0x02, //   Input (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position)
+    0x0A, 0x21, 0x26, //   Usage (0x2621)
+    0x95, 0x08, //   Report Count (8)
+    0x91,
+    0x02, //   Output (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position,Non-volatile)
+    0xC0, // End Collection
+];
+
+/// ガジェットの設定ファイル（`udc = <UDC名>` で使用するUDCを固定できる）
+pub const GADGET_CONFIG_FILE: &str = "/etc/splatoon3-ghost-drawer/gadget.conf";
+
+/// UDCを選んだ理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UdcSelectionReason {
+    /// `--udc` または設定ファイルで固定されている
+    Pinned,
+    /// 検出したボードで動作確認済みのUDC
+    BoardDefault(BoardModel),
+    /// 既知のUDCがないため、名前順で最初のもの
+    FirstAvailable,
+}
+
+impl fmt::Display for UdcSelectionReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UdcSelectionReason::Pinned => write!(f, "pinned by configuration"),
+            UdcSelectionReason::BoardDefault(board) => write!(f, "known-good UDC for {board:?}"),
+            UdcSelectionReason::FirstAvailable => write!(f, "first available UDC by name"),
+        }
+    }
+}
+
+/// 選択したUDC
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UdcSelection {
+    pub name: String,
+    pub reason: UdcSelectionReason,
+}
+
+/// `/sys/class/udc` の候補から、ガジェットをバインドするUDCを決定する
+///
+/// 候補は名前順に評価するため、ディレクトリの列挙順に左右されない。
+/// 優先順位は「固定されたUDC」→「ボードの既知のUDC」→「テスト用（dummy_hcd）以外で名前順の先頭」。
+/// 固定されたUDCが存在しない場合は、別のUDCにバインドせずエラーにする。
+pub fn select_udc(
+    candidates: &[String],
+    pinned: Option<&str>,
+    board: Option<&BoardModel>,
+) -> Result<UdcSelection, SetupError> {
+    let mut candidates = candidates.to_vec();
+    candidates.sort();
+    let available = |name: &str| candidates.iter().any(|candidate| candidate == name);
+
+    let selection = if let Some(pinned) = pinned {
+        if !available(pinned) {
+            return Err(SetupError::Unknown(format!(
+                "Pinned UDC '{pinned}' not found (available: {})",
+                candidates.join(", ")
+            )));
+        }
+        UdcSelection {
+            name: pinned.to_string(),
+            reason: UdcSelectionReason::Pinned,
+        }
+    } else if let Some((board, name)) = board.and_then(|board| {
+        board
+            .expected_udc_names()
+            .iter()
+            .find(|name| available(name))
+            .map(|name| (board, name))
+    }) {
+        UdcSelection {
+            name: name.to_string(),
+            reason: UdcSelectionReason::BoardDefault(board.clone()),
+        }
+    } else if let Some(name) = candidates.iter().find(|name| !is_dummy_udc(name)) {
+        UdcSelection {
+            name: name.clone(),
+            reason: UdcSelectionReason::FirstAvailable,
+        }
+    } else if candidates.is_empty() {
+        return Err(SetupError::Unknown("No UDC found".to_string()));
+    } else {
+        return Err(SetupError::Unknown(format!(
+            "Only test UDCs found ({}); pin one with --udc to use it",
+            candidates.join(", ")
+        )));
+    };
+
+    for candidate in &candidates {
+        if *candidate == selection.name {
+            info!(
+                "UDC candidate {}: selected ({})",
+                candidate, selection.reason
+            );
+        } else {
+            info!(
+                "UDC candidate {}: rejected ({})",
+                candidate,
+                rejection_reason(candidate, &selection)
+            );
+        }
+    }
+
+    Ok(selection)
+}
+
+fn rejection_reason(candidate: &str, selection: &UdcSelection) -> String {
+    match &selection.reason {
+        UdcSelectionReason::Pinned => format!("{} is pinned", selection.name),
+        _ if is_dummy_udc(candidate) => "dummy_hcd test controller".to_string(),
+        UdcSelectionReason::BoardDefault(board)
+            if board.expected_udc_names().contains(&candidate) =>
+        {
+            format!("lower priority than {} for {board:?}", selection.name)
+        }
+        UdcSelectionReason::BoardDefault(board) => format!("not a known UDC for {board:?}"),
+        UdcSelectionReason::FirstAvailable => format!("sorted after {}", selection.name),
+    }
+}
+
+fn is_dummy_udc(name: &str) -> bool {
+    name.starts_with("dummy_udc")
+}
+
+/// 設定ファイルの内容から固定されたUDC名を取り出す
+fn parse_pinned_udc(content: &str) -> Option<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .find(|(key, _)| key.trim() == "udc")
+        .map(|(_, value)| value.trim().trim_matches('"').to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// 設定ファイルで固定されたUDC名を読み込む
+pub fn read_pinned_udc() -> Option<String> {
+    fs::read_to_string(GADGET_CONFIG_FILE)
+        .ok()
+        .and_then(|content| parse_pinned_udc(&content))
+}
+
+/// UDCの `state` が `configured` になるまで待ち、最後に読んだ値を返す
+fn wait_for_udc_configured(udc: &str, timeout: Duration) -> Option<String> {
+    let state_path = format!("/sys/class/udc/{udc}/state");
+    let deadline = Instant::now() + timeout;
+    loop {
+        let state = fs::read_to_string(&state_path).ok();
+        if state.as_deref().map(str::trim) == Some("configured") || Instant::now() >= deadline {
+            return state;
+        }
+        std::thread::sleep(UDC_STATE_POLL_INTERVAL);
+    }
+}
+
+/// UDCのディレクトリの中の名前を列挙する
+fn read_udc_candidates(dir: &Path) -> io::Result<Vec<String>> {
+    let mut candidates = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name().to_string_lossy().to_string();
+        if !name.is_empty() {
+            candidates.push(name);
+        }
+    }
+    Ok(candidates)
+}
+
+/// `dir` にUDCが現れるまで `interval` ごとに最大 `timeout` 待ち、UDCの候補を返す
+///
+/// 起動直後はカーネルモジュールの初期化が終わっておらず、ディレクトリが無いか空のことがある。
+/// 読む権限が無い場合は待っても変わらないため、すぐに失敗する。
+fn wait_for_udc(
+    dir: &Path,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<Vec<String>, SetupError> {
+    let started = Instant::now();
+    loop {
+        let (reason, detail) = match read_udc_candidates(dir) {
+            Ok(candidates) if !candidates.is_empty() => {
+                if !started.elapsed().is_zero() {
+                    info!(
+                        "UDC appeared in {} after {:.1}s",
+                        dir.display(),
+                        started.elapsed().as_secs_f64()
+                    );
+                }
+                return Ok(candidates);
+            }
+            Ok(_) => (
+                UdcUnavailableReason::DirectoryEmpty,
+                format!("{} has no UDC", dir.display()),
+            ),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => (
+                UdcUnavailableReason::DirectoryMissing,
+                format!("{} does not exist", dir.display()),
+            ),
+            Err(e) if e.kind() == io::ErrorKind::PermissionDenied => {
+                return Err(SetupError::UdcUnavailable {
+                    reason: UdcUnavailableReason::PermissionDenied,
+                    detail: format!("cannot read {}: {e}", dir.display()),
+                });
+            }
+            Err(e) => {
+                return Err(SetupError::Unknown(format!(
+                    "Failed to read UDC directory: {e}"
+                )));
+            }
+        };
+
+        let waited = started.elapsed();
+        if waited >= timeout {
+            return Err(SetupError::UdcUnavailable {
+                reason,
+                detail: format!("{detail} after waiting {:.1}s", waited.as_secs_f64()),
+            });
+        }
+        info!(
+            "Waiting for UDC: {} ({:.1}s of {:.1}s)",
+            detail,
+            waited.as_secs_f64(),
+            timeout.as_secs_f64()
+        );
+        std::thread::sleep(interval.min(timeout - waited));
+    }
+}
+
+/// デバイスファイルが文字デバイスとして現れるまで最大 `timeout` 待ち、デバイス番号を返す
+///
+/// udevがデバイスファイルを作るのはガジェットをUDCにバインドした後なので、直後には無いことがある。
+fn wait_for_device_node(path: &str, timeout: Duration) -> Option<(u32, u32)> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let node = fs::metadata(path)
+            .ok()
+            .filter(crate::infrastructure::platform::is_char_device)
+            .and_then(|metadata| crate::infrastructure::platform::device_number(&metadata));
+        if node.is_some() || Instant::now() >= deadline {
+            return node;
+        }
+        std::thread::sleep(DEVICE_NODE_POLL_INTERVAL);
+    }
+}
+
+/// configfsのガジェットのディレクトリと `device_pattern` に一致するデバイスファイルから状態を読む
+///
+/// `udc_class_path` は `/sys/class/udc`。読めないファイルは `None` として扱う。
+fn read_gadget_state_at(
+    gadget_path: &Path,
+    udc_class_path: &Path,
+    device_pattern: &str,
+) -> GadgetState {
+    if !gadget_path.is_dir() {
+        return GadgetState::unconfigured(gadget_path.display().to_string());
+    }
+    let read = |relative: &str| {
+        fs::read_to_string(gadget_path.join(relative))
+            .ok()
+            .map(|content| content.trim().to_string())
+            .filter(|content| !content.is_empty())
+    };
+    let number = |relative: &str| read(relative).and_then(|value| value.parse().ok());
+    let hid_function = "functions/hid.usb0";
+    let udc = read("UDC");
+    let udc_state = udc.as_ref().and_then(|udc| {
+        fs::read_to_string(udc_class_path.join(udc).join("state"))
+            .ok()
+            .map(|state| state.trim().to_string())
+    });
+
+    GadgetState {
+        configured: true,
+        gadget_path: gadget_path.display().to_string(),
+        id_vendor: read("idVendor"),
+        id_product: read("idProduct"),
+        bcd_device: read("bcdDevice"),
+        bcd_usb: read("bcdUSB"),
+        manufacturer: read("strings/0x409/manufacturer"),
+        product: read("strings/0x409/product"),
+        serial_number: read("strings/0x409/serialnumber"),
+        configuration: read("configs/c.1/strings/0x409/configuration"),
+        max_power_ma: number("configs/c.1/MaxPower"),
+        report_length: number(&format!("{hid_function}/report_length")),
+        report_descriptor: fs::read(gadget_path.join(hid_function).join("report_desc"))
+            .ok()
+            .map(|bytes| ReportDescriptorDump::new(&bytes, PRO_CONTROLLER_REPORT_DESCRIPTOR)),
+        udc,
+        udc_state,
+        device_nodes: glob::glob(device_pattern)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|path| hid_device_node(&path))
+            .collect(),
+    }
+}
+
+fn hid_device_node(path: &Path) -> HidDeviceNode {
+    use crate::infrastructure::platform;
+    let metadata = fs::metadata(path).ok();
+    let owner = metadata.as_ref().and_then(platform::owner);
+    HidDeviceNode {
+        path: path.display().to_string(),
+        char_device: metadata.as_ref().is_some_and(platform::is_char_device),
+        device_number: metadata
+            .as_ref()
+            .filter(|metadata| platform::is_char_device(metadata))
+            .and_then(platform::device_number)
+            .map(|(major, minor)| format!("{major}:{minor}")),
+        mode: metadata
+            .as_ref()
+            .and_then(platform::permission_bits)
+            .map(|mode| format!("{mode:04o}")),
+        uid: owner.map(|(uid, _)| uid),
+        gid: owner.map(|(_, gid)| gid),
+    }
+}
+
+pub struct LinuxUsbGadgetManager {
+    board_detector: Option<Arc<dyn BoardDetector>>,
+    udc_override: Option<String>,
+    /// ガジェットのconfigfsディレクトリ
+    gadget_path: String,
+    /// ガジェットのHIDデバイスファイル
+    hid_device_path: String,
+    /// 検証時にHIDデバイスファイルが現れるまで待つ時間
+    device_node_timeout: Duration,
+    /// `/sys/class/udc` にUDCが現れるまで待つ時間
+    udc_wait: Duration,
+    /// 構成・再接続を記録する監査ログ
+    audit_log: Option<Arc<dyn GadgetAuditLog>>,
+    audit_initiator: AuditInitiator,
+}
 
 impl Default for LinuxUsbGadgetManager {
     fn default() -> Self {
-        Self
+        Self {
+            board_detector: None,
+            udc_override: None,
+            gadget_path: format!("{CONFIGFS_GADGETS_PATH}/{GADGET_NAME}"),
+            hid_device_path: HID_DEVICE_PATH.to_string(),
+            device_node_timeout: Duration::ZERO,
+            udc_wait: UDC_WAIT_TIMEOUT,
+            audit_log: None,
+            audit_initiator: AuditInitiator::Cli,
+        }
     }
 }
 
 impl LinuxUsbGadgetManager {
     pub fn new() -> Self {
-        Self
+        Self::default()
+    }
+
+    /// configfsのガジェット名を変更（既定は `nintendo_controller`）
+    ///
+    /// ループバックテストで本番のガジェットと別の名前で作るために使う。
+    pub fn with_gadget_name(mut self, name: &str) -> Self {
+        self.gadget_path = format!("{CONFIGFS_GADGETS_PATH}/{name}");
+        self
+    }
+
+    /// ガジェットのHIDデバイスファイルを変更（既定は `/dev/hidg0`）
+    pub fn with_hid_device_path(mut self, path: impl Into<String>) -> Self {
+        self.hid_device_path = path.into();
+        self
+    }
+
+    /// 検証時にHIDデバイスファイルが現れるまで待つ時間（既定は待たない）
+    ///
+    /// 起動時にsystemdから構成する場合、デバイスファイルができてから成功として終了するために使う。
+    pub fn with_device_node_timeout(mut self, timeout: Duration) -> Self {
+        self.device_node_timeout = timeout;
+        self
+    }
+
+    /// `/sys/class/udc` にUDCが現れるまで待つ時間（既定は30秒）
+    ///
+    /// 起動直後はUDCのドライバーの初期化が終わる前にガジェットを構成し始めることがあるため、その間待つ。
+    pub fn with_udc_wait(mut self, timeout: Duration) -> Self {
+        self.udc_wait = timeout;
+        self
+    }
+
+    pub fn gadget_path(&self) -> &str {
+        &self.gadget_path
+    }
+
+    /// HID function のconfigfsディレクトリ
+    pub fn hid_function_path(&self) -> String {
+        format!("{}/functions/hid.usb0", self.gadget_path)
+    }
+
+    /// ガジェットをUDCから切り離し、configfsのディレクトリを削除する（存在しなければ何もしない）
+    pub fn remove_gadget(&self) {
+        let gadget_path = self.gadget_path.as_str();
+        if !Path::new(gadget_path).exists() {
+            return;
+        }
+
+        // Unbind UDC if bound
+        let udc_path = format!("{gadget_path}/UDC");
+        if Path::new(&udc_path).exists() {
+            let _ = fs::write(&udc_path, "");
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+
+        // Remove symlinks from configs
+        let config_path = format!("{gadget_path}/configs/c.1/hid.usb0");
+        if Path::new(&config_path).exists() {
+            let _ = fs::remove_file(&config_path);
+        }
+
+        // Remove directories in reverse order
+        let dirs_to_remove = vec![
+            format!("{gadget_path}/configs/c.1/strings/0x409"),
+            format!("{gadget_path}/configs/c.1"),
+            format!("{gadget_path}/configs"),
+            self.hid_function_path(),
+            format!("{gadget_path}/functions"),
+            format!("{gadget_path}/strings/0x409"),
+            format!("{gadget_path}/strings"),
+            gadget_path.to_string(),
+        ];
+
+        for dir in dirs_to_remove {
+            if Path::new(&dir).exists() {
+                let _ = fs::remove_dir(&dir);
+            }
+        }
+    }
+
+    /// UDCの選択に使うボード検出器を設定
+    pub fn with_board_detector(mut self, board_detector: Arc<dyn BoardDetector>) -> Self {
+        self.board_detector = Some(board_detector);
+        self
+    }
+
+    /// 構成・再接続を監査ログに記録する（`initiator` は操作した経路）
+    pub fn with_audit_log(
+        mut self,
+        audit_log: Arc<dyn GadgetAuditLog>,
+        initiator: AuditInitiator,
+    ) -> Self {
+        self.audit_log = Some(audit_log);
+        self.audit_initiator = initiator;
+        self
+    }
+
+    /// 設定ファイルより優先して使用するUDCを指定
+    pub fn with_udc(mut self, udc: Option<String>) -> Self {
+        self.udc_override = udc;
+        self
     }
 
     fn write_file(&self, path: &str, content: &str) -> Result<(), SetupError> {
@@ -61,11 +573,28 @@ impl LinuxUsbGadgetManager {
     }
 
     fn get_udc_name(&self) -> Result<String, SetupError> {
-        let udc_dir = "/sys/class/udc";
+        let board =
+            self.board_detector
+                .as_ref()
+                .and_then(|detector| match detector.detect_board() {
+                    Ok(board) => Some(board),
+                    Err(e) => {
+                        warn!(
+                            "Board detection failed, ignoring board-specific UDCs: {}",
+                            e
+                        );
+                        None
+                    }
+                });
+
+        // Orange Pi Zero 2W のmusbはホストモードで起動するとUDCが現れないため、先にペリフェラルモードにする
+        if board == Some(BoardModel::OrangePiZero2W) {
+            OrangePiUdcPreparer::new().prepare()?;
+        }
 
         // First check if the directory exists
-        if !Path::new(udc_dir).exists() {
-            error!("UDC directory does not exist: {}", udc_dir);
+        if !Path::new(UDC_CLASS_PATH).exists() {
+            warn!("UDC directory does not exist yet: {}", UDC_CLASS_PATH);
 
             // Try to load the necessary modules for Raspberry Pi Zero 2W
             info!("Attempting to load USB gadget modules...");
@@ -94,44 +623,36 @@ impl LinuxUsbGadgetManager {
                     info!("dwc2 overlay available");
                 }
             }
-
-            // Wait a bit for modules to initialize
-            std::thread::sleep(std::time::Duration::from_millis(1000));
-
-            // Check again after loading modules
-            if !Path::new(udc_dir).exists() {
-                error!("UDC directory still not found. This may indicate:");
-                error!("1. USB OTG is not enabled in device tree");
-                error!("2. The musb driver is not compatible with your kernel");
-                error!("3. Hardware does not support USB OTG");
-                return Err(SetupError::Unknown(
-                    "UDC directory not found after loading modules".to_string(),
-                ));
-            }
         }
 
-        let entries = fs::read_dir(udc_dir)
-            .map_err(|e| SetupError::Unknown(format!("Failed to read UDC directory: {e}")))?;
-
-        for entry in entries {
-            let entry =
-                entry.map_err(|e| SetupError::Unknown(format!("Failed to read UDC entry: {e}")))?;
-
-            let name = entry.file_name().to_string_lossy().to_string();
-            if !name.is_empty() {
-                info!("Found UDC: {}", name);
-
-                // For Raspberry Pi Zero 2W, the UDC is typically dwc2 based
-                if name.contains("dwc2") || name.contains("fe980000.usb") {
-                    info!("Using Raspberry Pi Zero 2W UDC: {}", name);
-                    return Ok(name);
+        // Wait for the modules to initialize and register a UDC
+        let candidates = wait_for_udc(Path::new(UDC_CLASS_PATH), self.udc_wait, UDC_POLL_INTERVAL)
+            .inspect_err(|e| {
+                if let SetupError::UdcUnavailable {
+                    reason: UdcUnavailableReason::DirectoryMissing,
+                    ..
+                } = e
+                {
+                    error!("UDC directory still not found. This may indicate:");
+                    error!("1. USB OTG is not enabled in device tree");
+                    error!("2. The musb driver is not compatible with your kernel");
+                    error!("3. Hardware does not support USB OTG");
                 }
+            })?;
 
-                return Ok(name);
+        let pinned = match &self.udc_override {
+            Some(udc) => {
+                info!("UDC pinned by --udc: {}", udc);
+                Some(udc.clone())
             }
-        }
+            None => read_pinned_udc().inspect(|udc| {
+                info!("UDC pinned by {}: {}", GADGET_CONFIG_FILE, udc);
+            }),
+        };
 
-        Err(SetupError::Unknown("No UDC found".to_string()))
+        let selection = select_udc(&candidates, pinned.as_deref(), board.as_ref())?;
+        info!("Using UDC: {} ({})", selection.name, selection.reason);
+        Ok(selection.name)
     }
 
     fn configure_hid_permissions(&self) -> Result<(), SetupError> {
@@ -198,8 +719,24 @@ impl LinuxUsbGadgetManager {
     }
 }
 
-impl UsbGadgetManager for LinuxUsbGadgetManager {
-    fn configure_as_pro_controller(&self) -> Result<(), SetupError> {
+impl LinuxUsbGadgetManager {
+    /// 監査ログに記録する（UDCを指定した場合はその名前も残す）
+    fn audit(&self, operation: GadgetOperation, result: &Result<(), SetupError>) {
+        let Some(audit_log) = &self.audit_log else {
+            return;
+        };
+        let mut entry = GadgetAuditEntry::new(operation, self.audit_initiator, result)
+            .with_parameter("gadget_path", self.gadget_path.clone());
+        if let Some(udc) = &self.udc_override {
+            entry = entry.with_parameter("udc", udc.clone());
+        }
+        if let Err(SetupError::UdcUnavailable { reason, .. }) = result {
+            entry = entry.with_parameter("udc_unavailable", reason.to_string());
+        }
+        audit_log.record(entry);
+    }
+
+    fn configure_pro_controller(&self) -> Result<(), SetupError> {
         info!("Configuring USB Gadget as Nintendo Switch Pro Controller...");
 
         // Load kernel modules
@@ -224,68 +761,38 @@ impl UsbGadgetManager for LinuxUsbGadgetManager {
         }
 
         // If gadget already exists, try to clean it up first
-        if Path::new(GADGET_PATH).exists() {
+        if Path::new(&self.gadget_path).exists() {
             info!("Cleaning up existing gadget configuration...");
-
-            // Unbind UDC if bound
-            let udc_path = format!("{GADGET_PATH}/UDC");
-            if Path::new(&udc_path).exists() {
-                let _ = fs::write(&udc_path, "");
-                std::thread::sleep(std::time::Duration::from_millis(500));
-            }
-
-            // Remove symlinks from configs
-            let config_path = format!("{GADGET_PATH}/configs/c.1/hid.usb0");
-            if Path::new(&config_path).exists() {
-                let _ = fs::remove_file(&config_path);
-            }
-
-            // Remove directories in reverse order
-            let dirs_to_remove = vec![
-                format!("{}/configs/c.1/strings/0x409", GADGET_PATH),
-                format!("{}/configs/c.1", GADGET_PATH),
-                format!("{}/configs", GADGET_PATH),
-                format!("{}/functions/hid.usb0", GADGET_PATH),
-                format!("{}/functions", GADGET_PATH),
-                format!("{}/strings/0x409", GADGET_PATH),
-                format!("{}/strings", GADGET_PATH),
-                GADGET_PATH.to_string(),
-            ];
-
-            for dir in dirs_to_remove {
-                if Path::new(&dir).exists() {
-                    let _ = fs::remove_dir(&dir);
-                }
-            }
-
+            self.remove_gadget();
             std::thread::sleep(std::time::Duration::from_millis(500));
         }
 
         // Create gadget directory
-        self.create_directory(GADGET_PATH)?;
+        let gadget_path = self.gadget_path.as_str();
+        self.create_directory(gadget_path)?;
 
         // Set vendor and product IDs
-        self.write_file(&format!("{GADGET_PATH}/idVendor"), VID)?;
-        self.write_file(&format!("{GADGET_PATH}/idProduct"), PID)?;
+        self.write_file(&format!("{gadget_path}/idVendor"), VID)?;
+        self.write_file(&format!("{gadget_path}/idProduct"), PID)?;
 
         // Set USB version
-        self.write_file(&format!("{GADGET_PATH}/bcdUSB"), "0x0200")?; // USB 2.0
-        self.write_file(&format!("{GADGET_PATH}/bcdDevice"), "0x0100")?;
+        self.write_file(&format!("{gadget_path}/bcdUSB"), "0x0200")?; // USB 2.0
+        self.write_file(&format!("{gadget_path}/bcdDevice"), "0x0100")?;
 
         // Set device class
-        self.write_file(&format!("{GADGET_PATH}/bDeviceClass"), "0x00")?;
-        self.write_file(&format!("{GADGET_PATH}/bDeviceSubClass"), "0x00")?;
-        self.write_file(&format!("{GADGET_PATH}/bDeviceProtocol"), "0x00")?;
+        self.write_file(&format!("{gadget_path}/bDeviceClass"), "0x00")?;
+        self.write_file(&format!("{gadget_path}/bDeviceSubClass"), "0x00")?;
+        self.write_file(&format!("{gadget_path}/bDeviceProtocol"), "0x00")?;
 
         // Set strings
-        let strings_dir = format!("{GADGET_PATH}/strings/0x409");
+        let strings_dir = format!("{gadget_path}/strings/0x409");
         self.create_directory(&strings_dir)?;
         self.write_file(&format!("{strings_dir}/serialnumber"), "000000000001")?;
         self.write_file(&format!("{strings_dir}/manufacturer"), "Nintendo")?;
         self.write_file(&format!("{strings_dir}/product"), "Pro Controller")?;
 
         // Create configuration
-        let config_dir = format!("{GADGET_PATH}/configs/c.1");
+        let config_dir = format!("{gadget_path}/configs/c.1");
         self.create_directory(&config_dir)?;
         self.write_file(&format!("{config_dir}/MaxPower"), "500")?;
 
@@ -297,11 +804,14 @@ impl UsbGadgetManager for LinuxUsbGadgetManager {
         )?;
 
         // Create HID function
-        let hid_dir = format!("{GADGET_PATH}/functions/hid.usb0");
+        let hid_dir = self.hid_function_path();
         self.create_directory(&hid_dir)?;
         self.write_file(&format!("{hid_dir}/protocol"), "0")?;
         self.write_file(&format!("{hid_dir}/subclass"), "0")?;
-        self.write_file(&format!("{hid_dir}/report_length"), "8")?;
+        self.write_file(
+            &format!("{hid_dir}/report_length"),
+            &REPORT_LENGTH.to_string(),
+        )?;
 
         // Write HID report descriptor for Nintendo Pro Controller
         // This is the actual descriptor used by the Pro Controller
@@ -309,60 +819,12 @@ impl UsbGadgetManager for LinuxUsbGadgetManager {
         // ... (inside configure_as_pro_controller)
 
         // Set USB version
-        self.write_file(&format!("{GADGET_PATH}/bcdUSB"), "0x0200")?; // USB 2.0
-        self.write_file(&format!("{GADGET_PATH}/bcdDevice"), "0x0100")?;
+        self.write_file(&format!("{gadget_path}/bcdUSB"), "0x0200")?; // USB 2.0
+        self.write_file(&format!("{gadget_path}/bcdDevice"), "0x0100")?;
 
         // ...
 
         // Write HID report descriptor for Pokken Tournament DX Pro Pad
-        let report_desc = vec![
-            0x05, 0x01, // Usage Page (Generic Desktop Ctrls)
-            0x09, 0x05, // Usage (Game Pad)
-            0xA1, 0x01, // Collection (Application)
-            0x15, 0x00, //   Logical Minimum (0)
-            0x25, 0x01, //   Logical Maximum (1)
-            0x35, 0x00, //   Physical Minimum (0)
-            0x45, 0x01, //   Physical Maximum (1)
-            0x75, 0x01, //   Report Size (1)
-            0x95, 0x10, //   Report Count (16)
-            0x05, 0x09, //   Usage Page (Button)
-            0x19, 0x01, //   Usage Minimum (0x01)
-            0x29, 0x10, //   Usage Maximum (0x10)
-            0x81,
-            0x02, //   Input (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position)
-            0x05, 0x01, //   Usage Page (Generic Desktop Ctrls)
-            0x25, 0x07, //   Logical Maximum (7)
-            0x46, 0x3B, 0x01, //   Physical Maximum (315)
-            0x75, 0x04, //   Report Size (4)
-            0x95, 0x01, //   Report Count (1)
-            0x65, 0x14, //   Unit (System: English Rotation, Length: Centimeter)
-            0x09, 0x39, //   Usage (Hat Switch)
-            0x81, 0x42, //   Input (Data,Var,Abs,No Wrap,Linear,Preferred State,Null State)
-            0x65, 0x00, //   Unit (None)
-            0x95, 0x01, //   Report Count (1)
-            0x81,
-            0x01, //   Input (Const,Array,Abs,No Wrap,Linear,Preferred State,No Null Position)
-            0x26, 0xFF, 0x00, //   Logical Maximum (255)
-            0x46, 0xFF, 0x00, //   Physical Maximum (255)
-            0x09, 0x30, //   Usage (X)
-            0x09, 0x31, //   Usage (Y)
-            0x09, 0x32, //   Usage (Z)
-            0x09, 0x35, //   Usage (Rz)
-            0x75, 0x08, //   Report Size (8)
-            0x95, 0x04, //   Report Count (4)
-            0x81,
-            0x02, //   Input (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position)
-            0x06, 0x00, 0xFF, //   Usage Page (Vendor Defined 0xFF00)
-            0x09, 0x20, //   Usage (0x20)
-            0x95, 0x01, //   Report Count (1)
-            0x81,
-            0x02, //   Input (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position)
-            0x0A, 0x21, 0x26, //   Usage (0x2621)
-            0x95, 0x08, //   Report Count (8)
-            0x91,
-            0x02, //   Output (Data,Var,Abs,No Wrap,Linear,Preferred State,No Null Position,Non-volatile)
-            0xC0, // End Collection
-        ];
 
         let report_desc_path = format!("{hid_dir}/report_desc");
         let mut file = fs::OpenOptions::new()
@@ -374,17 +836,18 @@ impl UsbGadgetManager for LinuxUsbGadgetManager {
                 SetupError::FileSystemError(e)
             })?;
 
-        file.write_all(&report_desc).map_err(|e| {
-            error!("Failed to write report descriptor: {}", e);
-            SetupError::FileSystemError(e)
-        })?;
+        file.write_all(PRO_CONTROLLER_REPORT_DESCRIPTOR)
+            .map_err(|e| {
+                error!("Failed to write report descriptor: {}", e);
+                SetupError::FileSystemError(e)
+            })?;
 
         info!("Wrote HID report descriptor");
 
         // Link function to configuration
         let function_link = format!("{config_dir}/hid.usb0");
         if !Path::new(&function_link).exists() {
-            std::os::unix::fs::symlink(&hid_dir, &function_link).map_err(|e| {
+            crate::infrastructure::platform::symlink(&hid_dir, &function_link).map_err(|e| {
                 error!("Failed to create symlink: {}", e);
                 SetupError::FileSystemError(e)
             })?;
@@ -393,39 +856,41 @@ impl UsbGadgetManager for LinuxUsbGadgetManager {
 
         // Enable the gadget
         let udc_name = self.get_udc_name()?;
-        self.write_file(&format!("{GADGET_PATH}/UDC"), &udc_name)?;
+        self.write_file(&format!("{gadget_path}/UDC"), &udc_name)?;
 
         // Wait for HID device to be created
         std::thread::sleep(std::time::Duration::from_millis(1000));
 
-        // Robustly ensure /dev/hidg0 exists and is a character device
-        let hidg0_path = Path::new("/dev/hidg0");
-        let needs_recreation = if !hidg0_path.exists() {
+        // Robustly ensure the HID device exists and is a character device
+        let device_path = Path::new(&self.hid_device_path);
+        let needs_recreation = if !device_path.exists() {
             true
         } else {
             // Check if it is a character device
-            match fs::metadata(hidg0_path) {
-                Ok(metadata) => {
-                    use std::os::unix::fs::FileTypeExt;
-                    !metadata.file_type().is_char_device()
-                }
+            match fs::metadata(device_path) {
+                Ok(metadata) => !crate::infrastructure::platform::is_char_device(&metadata),
                 Err(_) => true,
             }
         };
 
         if needs_recreation {
-            warn!("/dev/hidg0 missing or not a character device. Attempting manual creation...");
+            warn!(
+                "{} missing or not a character device. Attempting manual creation...",
+                self.hid_device_path
+            );
 
             // Clean up if it exists (as directory or file)
-            if hidg0_path.exists() {
-                if hidg0_path.is_dir() {
-                    if let Err(e) = fs::remove_dir_all(hidg0_path) {
-                        error!("Failed to remove directory /dev/hidg0: {}", e);
+            if device_path.exists() {
+                if device_path.is_dir() {
+                    if let Err(e) = fs::remove_dir_all(device_path) {
+                        error!("Failed to remove directory {}: {}", self.hid_device_path, e);
                         // Try with command as fallback
-                        let _ = Command::new("rm").args(["-rf", "/dev/hidg0"]).output();
+                        let _ = Command::new("rm")
+                            .args(["-rf", &self.hid_device_path])
+                            .output();
                     }
                 } else {
-                    let _ = fs::remove_file("/dev/hidg0");
+                    let _ = fs::remove_file(&self.hid_device_path);
                 }
             }
 
@@ -449,18 +914,18 @@ impl UsbGadgetManager for LinuxUsbGadgetManager {
             };
 
             info!(
-                "Creating /dev/hidg0 with major {} and minor {}",
-                major, minor
+                "Creating {} with major {} and minor {}",
+                self.hid_device_path, major, minor
             );
 
             let output = Command::new("mknod")
-                .args(["/dev/hidg0", "c", &major, &minor])
+                .args([self.hid_device_path.as_str(), "c", &major, &minor])
                 .output()
                 .map_err(|e| SetupError::Unknown(format!("Failed to run mknod: {e}")))?;
 
             if !output.status.success() {
                 let stderr = String::from_utf8_lossy(&output.stderr);
-                error!("Failed to create /dev/hidg0: {}", stderr);
+                error!("Failed to create {}: {}", self.hid_device_path, stderr);
             }
         }
 
@@ -472,27 +937,11 @@ impl UsbGadgetManager for LinuxUsbGadgetManager {
         Ok(())
     }
 
-    fn is_gadget_configured(&self) -> Result<bool, SetupError> {
-        // Check if gadget path exists
-        if !Path::new(GADGET_PATH).exists() {
-            return Ok(false);
-        }
-
-        // Check if UDC is set (gadget is active)
-        let udc_path = format!("{GADGET_PATH}/UDC");
-        if !Path::new(&udc_path).exists() {
-            return Ok(false);
-        }
-
-        let udc_content = fs::read_to_string(&udc_path)?;
-        Ok(!udc_content.trim().is_empty())
-    }
-
-    fn reconnect_gadget(&self) -> Result<(), SetupError> {
+    fn reconnect(&self) -> Result<(), SetupError> {
         info!("Reconnecting USB Gadget...");
 
         // Get the current UDC name
-        let udc_path = format!("{GADGET_PATH}/UDC");
+        let udc_path = format!("{}/UDC", self.gadget_path);
         let udc_name = if Path::new(&udc_path).exists() {
             fs::read_to_string(&udc_path)
                 .ok()
@@ -542,3 +991,239 @@ impl UsbGadgetManager for LinuxUsbGadgetManager {
         Ok(())
     }
 }
+
+impl UsbGadgetManager for LinuxUsbGadgetManager {
+    fn configure_as_pro_controller(&self) -> Result<(), SetupError> {
+        let result = self.configure_pro_controller();
+        self.audit(GadgetOperation::Configure, &result);
+        result
+    }
+
+    fn is_gadget_configured(&self) -> Result<bool, SetupError> {
+        // Check if gadget path exists
+        if !Path::new(&self.gadget_path).exists() {
+            return Ok(false);
+        }
+
+        // Check if UDC is set (gadget is active)
+        let udc_path = format!("{}/UDC", self.gadget_path);
+        if !Path::new(&udc_path).exists() {
+            return Ok(false);
+        }
+
+        let udc_content = fs::read_to_string(&udc_path)?;
+        Ok(!udc_content.trim().is_empty())
+    }
+
+    fn reconnect_gadget(&self) -> Result<(), SetupError> {
+        let result = self.reconnect();
+        self.audit(GadgetOperation::Reconnect, &result);
+        result
+    }
+
+    fn verify_gadget(&self) -> Result<GadgetVerification, SetupError> {
+        let udc = fs::read_to_string(format!("{}/UDC", self.gadget_path))
+            .ok()
+            .map(|udc| udc.trim().to_string())
+            .filter(|udc| !udc.is_empty())
+            .ok_or_else(|| SetupError::Unknown("USB Gadget is not bound to a UDC".to_string()))?;
+
+        let hid_function_path = self.hid_function_path();
+        let report_desc = fs::read(format!("{hid_function_path}/report_desc")).ok();
+        let report_length = fs::read_to_string(format!("{hid_function_path}/report_length")).ok();
+        let dev = fs::read_to_string(format!("{hid_function_path}/dev")).ok();
+        let node = wait_for_device_node(&self.hid_device_path, self.device_node_timeout);
+        let udc_state = wait_for_udc_configured(&udc, UDC_CONFIGURED_TIMEOUT);
+
+        let verification = GadgetVerification {
+            checks: vec![
+                GadgetCheck::report_descriptor(
+                    PRO_CONTROLLER_REPORT_DESCRIPTOR,
+                    report_desc.as_deref(),
+                ),
+                GadgetCheck::report_length(REPORT_LENGTH, report_length.as_deref()),
+                GadgetCheck::device_node(&self.hid_device_path, dev.as_deref(), node),
+                GadgetCheck::udc_state(udc_state.as_deref()),
+            ],
+        };
+        for check in &verification.checks {
+            info!("Gadget verification: {}", check);
+        }
+        Ok(verification)
+    }
+
+    fn read_gadget_state(&self) -> Result<GadgetState, SetupError> {
+        Ok(read_gadget_state_at(
+            Path::new(&self.gadget_path),
+            Path::new("/sys/class/udc"),
+            "/dev/hidg*",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_select_udc_prefers_pinned_then_board_default() {
+        let candidates = names(&["dummy_udc.0", "fe980000.usb", "3f980000.usb"]);
+
+        let selection = select_udc(&candidates, Some("dummy_udc.0"), None).unwrap();
+        assert_eq!(selection.reason, UdcSelectionReason::Pinned);
+        assert_eq!(selection.name, "dummy_udc.0");
+        assert!(select_udc(&candidates, Some("musb-hdrc.4.auto"), None).is_err());
+
+        let board = BoardModel::RaspberryPiZero2W;
+        let selection = select_udc(&candidates, None, Some(&board)).unwrap();
+        assert_eq!(selection.name, "3f980000.usb");
+        assert_eq!(selection.reason, UdcSelectionReason::BoardDefault(board));
+    }
+
+    #[test]
+    fn test_select_udc_fallback_is_sorted_and_skips_dummy() {
+        let candidates = names(&["zz.usb", "dummy_udc.0", "aa.usb"]);
+        let board = BoardModel::OrangePiZero2W;
+
+        let selection = select_udc(&candidates, None, Some(&board)).unwrap();
+        assert_eq!(selection.name, "aa.usb");
+        assert_eq!(selection.reason, UdcSelectionReason::FirstAvailable);
+
+        assert!(select_udc(&names(&["dummy_udc.0"]), None, None).is_err());
+        assert!(select_udc(&[], None, None).is_err());
+    }
+
+    #[test]
+    fn test_gadget_name_changes_configfs_paths() {
+        let manager = LinuxUsbGadgetManager::new();
+        assert_eq!(
+            manager.hid_function_path(),
+            "/sys/kernel/config/usb_gadget/nintendo_controller/functions/hid.usb0"
+        );
+
+        let manager = manager.with_gadget_name("ghost_drawer_loopback");
+        assert_eq!(
+            manager.gadget_path(),
+            "/sys/kernel/config/usb_gadget/ghost_drawer_loopback"
+        );
+        assert_eq!(
+            manager.hid_function_path(),
+            "/sys/kernel/config/usb_gadget/ghost_drawer_loopback/functions/hid.usb0"
+        );
+    }
+
+    #[test]
+    fn test_parse_pinned_udc() {
+        assert_eq!(
+            parse_pinned_udc("# comment\nudc = \"musb-hdrc.4.auto\"\n"),
+            Some("musb-hdrc.4.auto".to_string())
+        );
+        assert_eq!(parse_pinned_udc("# udc = fe980000.usb\n"), None);
+        assert_eq!(parse_pinned_udc("udc =\n"), None);
+    }
+
+    #[test]
+    fn test_read_gadget_state_from_configfs() {
+        let root = std::env::temp_dir().join(format!("gadget-state-{}", uuid::Uuid::new_v4()));
+        let gadget = root.join("nintendo_controller");
+        let udc_class = root.join("udc");
+        assert!(!read_gadget_state_at(&gadget, &udc_class, "").configured);
+
+        let write = |relative: &str, content: &[u8]| {
+            let path = gadget.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("idVendor", b"0x0f0d\n");
+        write("idProduct", b"0x0092\n");
+        write("strings/0x409/product", b"POKKEN CONTROLLER\n");
+        write("configs/c.1/MaxPower", b"500\n");
+        write("functions/hid.usb0/report_length", b"8\n");
+        write(
+            "functions/hid.usb0/report_desc",
+            PRO_CONTROLLER_REPORT_DESCRIPTOR,
+        );
+        write("UDC", b"3f980000.usb\n");
+        fs::create_dir_all(udc_class.join("3f980000.usb")).unwrap();
+        fs::write(udc_class.join("3f980000.usb/state"), "configured\n").unwrap();
+        let node = root.join("hidg0");
+        fs::write(&node, "").unwrap();
+
+        let pattern = format!("{}/hidg*", root.display());
+        let state = read_gadget_state_at(&gadget, &udc_class, &pattern);
+        assert!(state.configured);
+        assert_eq!(state.id_vendor.as_deref(), Some(VID));
+        assert_eq!(state.product.as_deref(), Some("POKKEN CONTROLLER"));
+        assert_eq!(state.serial_number, None);
+        assert_eq!(state.max_power_ma, Some(500));
+        assert_eq!(state.report_length, Some(REPORT_LENGTH as u32));
+        let descriptor = state.report_descriptor.unwrap();
+        assert!(descriptor.matches_expected);
+        assert_eq!(descriptor.length, PRO_CONTROLLER_REPORT_DESCRIPTOR.len());
+        assert_eq!(state.udc.as_deref(), Some("3f980000.usb"));
+        assert_eq!(state.udc_state.as_deref(), Some("configured"));
+        assert_eq!(state.device_nodes.len(), 1);
+        // 通常のファイルはキャラクターデバイスではない
+        assert!(!state.device_nodes[0].char_device);
+        assert_eq!(state.device_nodes[0].device_number, None);
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_wait_for_device_node_gives_up_after_the_timeout() {
+        assert!(wait_for_device_node("/dev/null", Duration::ZERO).is_some());
+
+        let started = Instant::now();
+        let missing = std::env::temp_dir().join(format!("hidg-{}", uuid::Uuid::new_v4()));
+        let timeout = Duration::from_millis(300);
+        assert_eq!(
+            wait_for_device_node(missing.to_str().unwrap(), timeout),
+            None
+        );
+        assert!(started.elapsed() >= timeout);
+    }
+
+    #[test]
+    fn test_wait_for_udc_polls_until_a_udc_appears() {
+        let dir = std::env::temp_dir().join(format!("udc-{}", uuid::Uuid::new_v4()));
+        let interval = Duration::from_millis(20);
+
+        let missing = wait_for_udc(&dir, Duration::from_millis(100), interval).unwrap_err();
+        assert!(matches!(
+            missing,
+            SetupError::UdcUnavailable {
+                reason: UdcUnavailableReason::DirectoryMissing,
+                ..
+            }
+        ));
+        fs::create_dir(&dir).unwrap();
+        let empty = wait_for_udc(&dir, Duration::from_millis(100), interval).unwrap_err();
+        assert!(matches!(
+            empty,
+            SetupError::UdcUnavailable {
+                reason: UdcUnavailableReason::DirectoryEmpty,
+                ..
+            }
+        ));
+        fs::remove_dir(&dir).unwrap();
+
+        // 起動時のようにディレクトリとUDCが遅れて現れる
+        let creator = {
+            let dir = dir.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(100));
+                fs::create_dir(&dir).unwrap();
+                std::thread::sleep(Duration::from_millis(100));
+                fs::write(dir.join("3f980000.usb"), "").unwrap();
+            })
+        };
+        let candidates = wait_for_udc(&dir, Duration::from_secs(10), interval).unwrap();
+        creator.join().unwrap();
+        assert_eq!(candidates, ["3f980000.usb"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
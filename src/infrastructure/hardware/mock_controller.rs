@@ -1,10 +1,51 @@
-use crate::domain::controller::{ControllerCommand, ControllerEmulator};
+use crate::domain::controller::{
+    ActionType, Button, ControllerCommand, ControllerEmulator, DPad, ProController,
+};
 use crate::domain::hardware::errors::HardwareError;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::{debug, info};
 
-pub struct MockController;
+/// 待機中に中断を確認する間隔（実機のレポート送信間隔と同じ）
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(8);
+
+/// MockControllerが記録した描画操作の回数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RecordedOperations {
+    /// 十字キーの移動入力（ニュートラル以外）
+    pub dpad_ops: u64,
+    /// Aボタンの押下
+    pub a_presses: u64,
+    /// 描画前のニュートラル入力
+    pub neutral_clears: u64,
+}
+
+pub struct MockController {
+    simulate_delays: bool,
+    recorded: Mutex<RecordedOperations>,
+    /// 実行されたコマンドの記録（`with_command_log` で有効にした場合のみ）
+    command_log: Option<Mutex<Vec<ControllerCommand>>>,
+    /// 実機なら送信していたレポートを作るためのコントローラー状態
+    state: Mutex<ProController>,
+    last_report: Mutex<Option<[u8; 8]>>,
+    /// 障害注入: この数のコマンドを実行した後にホストが応答しなくなる（テスト用）
+    host_sleeps_after: Mutex<Option<usize>>,
+    executed_commands: Mutex<usize>,
+    /// ホストが応答しなくなった時刻（応答していれば `None`）
+    host_asleep_since: Mutex<Option<Instant>>,
+    /// 障害注入: 名前が一致するコマンドの何回目（1から数える）を失敗させるか（テスト用）
+    command_failures: Mutex<Option<CommandFailures>>,
+    /// 障害注入: Switchとの接続が切れている（テスト用）
+    disconnected: AtomicBool,
+}
+
+struct CommandFailures {
+    name_prefix: String,
+    fail_at: Vec<usize>,
+    seen: usize,
+}
 
 impl Default for MockController {
     fn default() -> Self {
@@ -14,7 +55,132 @@ impl Default for MockController {
 
 impl MockController {
     pub fn new() -> Self {
-        Self
+        Self {
+            simulate_delays: true,
+            recorded: Mutex::new(RecordedOperations::default()),
+            command_log: None,
+            state: Mutex::new(ProController::new("mock")),
+            last_report: Mutex::new(None),
+            host_sleeps_after: Mutex::new(None),
+            executed_commands: Mutex::new(0),
+            host_asleep_since: Mutex::new(None),
+            command_failures: Mutex::new(None),
+            disconnected: AtomicBool::new(false),
+        }
+    }
+
+    /// アクションの待ち時間を省略する（テスト用）
+    pub fn without_delays(mut self) -> Self {
+        self.simulate_delays = false;
+        self
+    }
+
+    /// 実行されたコマンドを順に記録する（テスト用）
+    pub fn with_command_log(mut self) -> Self {
+        self.command_log = Some(Mutex::new(Vec::new()));
+        self
+    }
+
+    /// 指定した数のコマンドを実行した後、Switchがスリープしたようにレポートを受け取らなくする
+    /// （障害注入、テスト用）
+    pub fn with_host_sleep_after(self, commands: usize) -> Self {
+        *self.host_sleeps_after.lock().unwrap() = Some(commands);
+        self
+    }
+
+    /// 名前が `name_prefix` で始まるコマンドのうち、`fail_at` 回目の実行を送信エラーにする
+    /// （障害注入、テスト用）
+    ///
+    /// 失敗したコマンドは実行されなかったものとして、操作の回数にも数えない。
+    pub fn with_command_failures(self, name_prefix: &str, fail_at: &[usize]) -> Self {
+        *self.command_failures.lock().unwrap() = Some(CommandFailures {
+            name_prefix: name_prefix.to_string(),
+            fail_at: fail_at.to_vec(),
+            seen: 0,
+        });
+        self
+    }
+
+    /// 障害注入で失敗させるコマンドか
+    fn injected_failure(&self, command: &ControllerCommand) -> Option<HardwareError> {
+        let mut failures = self.command_failures.lock().unwrap();
+        let failures = failures.as_mut()?;
+        if !command.name.starts_with(&failures.name_prefix) {
+            return None;
+        }
+        failures.seen += 1;
+        failures.fail_at.contains(&failures.seen).then(|| {
+            HardwareError::IoError(std::io::Error::other(format!(
+                "injected failure of '{}'",
+                command.name
+            )))
+        })
+    }
+
+    /// Switchとの接続が切れた・戻ったことにする（`is_connected` の結果が変わる、テスト用）
+    pub fn set_connected(&self, connected: bool) {
+        self.disconnected.store(!connected, Ordering::SeqCst);
+    }
+
+    /// スリープさせたホストを復帰させる（テスト用）
+    pub fn wake_host(&self) {
+        *self.host_sleeps_after.lock().unwrap() = None;
+        *self.host_asleep_since.lock().unwrap() = None;
+    }
+
+    /// ホストがレポートを受け取らない状態か（受け取らない場合はその開始時刻を記録する）
+    fn host_asleep(&self) -> bool {
+        let mut asleep_since = self.host_asleep_since.lock().unwrap();
+        if asleep_since.is_some() {
+            return true;
+        }
+        let executed = *self.executed_commands.lock().unwrap();
+        if self
+            .host_sleeps_after
+            .lock()
+            .unwrap()
+            .is_some_and(|after| executed >= after)
+        {
+            *asleep_since = Some(Instant::now());
+            return true;
+        }
+        false
+    }
+
+    /// これまでに実行された操作の回数
+    pub fn recorded_operations(&self) -> RecordedOperations {
+        *self.recorded.lock().unwrap()
+    }
+
+    /// これまでに実行されたコマンド（記録が無効なら空）
+    pub fn recorded_commands(&self) -> Vec<ControllerCommand> {
+        self.command_log
+            .as_ref()
+            .map(|log| log.lock().unwrap().clone())
+            .unwrap_or_default()
+    }
+
+    /// 状態を更新し、実機なら送信していたレポートとして記録する
+    fn send_state(&self, update: impl FnOnce(&mut ProController)) {
+        let mut state = self.state.lock().unwrap();
+        update(&mut state);
+        *self.last_report.lock().unwrap() = Some(state.get_report_bytes());
+    }
+
+    fn record(&self, command: &ControllerCommand) {
+        let mut recorded = self.recorded.lock().unwrap();
+        for (index, action) in command.sequence.iter().enumerate() {
+            match &action.action_type {
+                ActionType::SetDPad(dpad) if *dpad != DPad::NEUTRAL => recorded.dpad_ops += 1,
+                // コマンド先頭のニュートラルは移動の解除ではなく明示的なクリア
+                ActionType::SetDPad(_) if index == 0 => recorded.neutral_clears += 1,
+                ActionType::PressButton(button) if *button == Button::A => recorded.a_presses += 1,
+                _ => {}
+            }
+        }
+        if let Some(log) = &self.command_log {
+            log.lock().unwrap().push(command.clone());
+        }
     }
 }
 
@@ -25,20 +191,82 @@ impl ControllerEmulator for MockController {
     }
 
     fn is_connected(&self) -> Result<bool, HardwareError> {
-        Ok(true)
+        Ok(!self.disconnected.load(Ordering::SeqCst))
     }
 
     fn execute_command(&self, command: &ControllerCommand) -> Result<(), HardwareError> {
+        self.execute_command_cancellable(command, &AtomicBool::new(false))
+    }
+
+    fn execute_command_cancellable(
+        &self,
+        command: &ControllerCommand,
+        cancel: &AtomicBool,
+    ) -> Result<(), HardwareError> {
         debug!("Mock executing command: {}", command.name);
+        if self.host_asleep() {
+            return Err(HardwareError::HostUnresponsive);
+        }
+        if let Some(error) = self.injected_failure(command) {
+            return Err(error);
+        }
+        *self.executed_commands.lock().unwrap() += 1;
+        self.record(command);
+        if !self.simulate_delays {
+            let mut state = self.state.lock().unwrap();
+            for action in &command.sequence {
+                state.apply_action(action);
+            }
+            if !command.sequence.is_empty() {
+                *self.last_report.lock().unwrap() = Some(state.get_report_bytes());
+            }
+            return Ok(());
+        }
+
         for action in &command.sequence {
+            self.send_state(|state| state.apply_action(action));
             // Simulate action duration
-            thread::sleep(Duration::from_millis(action.duration_ms as u64));
+            let deadline = Instant::now() + Duration::from_millis(action.duration_ms as u64);
+            while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+                if cancel.load(Ordering::SeqCst) {
+                    self.send_state(ProController::reset_state);
+                    return Ok(());
+                }
+                if remaining.is_zero() {
+                    break;
+                }
+                thread::sleep(remaining.min(CANCEL_POLL_INTERVAL));
+            }
         }
         Ok(())
     }
 
+    fn write_raw_report(&self, report: &[u8]) -> Result<usize, HardwareError> {
+        debug!("Mock writing raw report: {:02X?}", report);
+        if self.host_asleep() {
+            return Err(HardwareError::HostUnresponsive);
+        }
+        if let Ok(report) = report.try_into() {
+            *self.last_report.lock().unwrap() = Some(report);
+        }
+        Ok(report.len())
+    }
+
     fn shutdown(&self) -> Result<(), HardwareError> {
         info!("Shutting down Mock Controller");
+        // 実機と同じく、すべての入力を離したレポートを送る
+        self.send_state(ProController::reset_state);
         Ok(())
     }
+
+    fn host_unresponsive_for(&self) -> Option<Duration> {
+        self.host_asleep_since
+            .lock()
+            .unwrap()
+            .map(|since| since.elapsed())
+    }
+
+    fn last_report(&self) -> Option<[u8; 8]> {
+        *self.last_report.lock().unwrap()
+    }
 }
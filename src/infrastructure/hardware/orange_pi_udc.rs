@@ -0,0 +1,386 @@
+//! Orange Pi Zero 2W のUDCの準備
+//!
+//! Orange Pi Zero 2W のmusbコントローラーはホストモードで起動することがあり、その間は
+//! `/sys/class/udc` にUDCが現れず、ガジェットのバインドが「No UDC found」で失敗する。
+//! 必要なカーネルモジュールを読み込み、musbの `mode` に `peripheral` を書き込んでから、
+//! 間隔を延ばしながらUDCが現れるのを待つ。`mode` の場所はカーネルによってデバイス名
+//! （`musb-hdrc.4.auto` など）が変わるため、`devices/platform` の下から探す。
+
+use crate::domain::setup::repositories::SetupError;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// musbのUDCに必要なカーネルモジュール（読み込み済みなら `/sys/module` に現れる）
+const MUSB_MODULES: [&str; 2] = ["sunxi", "musb_hdrc"];
+/// UDCが現れるまで待つ間隔（確認するたびに延ばす）
+const UDC_RETRY_DELAYS: [Duration; 4] = [
+    Duration::from_millis(250),
+    Duration::from_millis(500),
+    Duration::from_secs(1),
+    Duration::from_secs(2),
+];
+/// `devices/platform` からmusbのデバイスを探す深さ（`soc/5100000.usb/musb-hdrc.4.auto` が3段目）
+const MODE_SEARCH_DEPTH: usize = 4;
+
+/// カーネルモジュールを読み込む関数（失敗した場合はエラーの内容を返す）
+pub type ModuleLoader = Arc<dyn Fn(&str) -> Result<(), String> + Send + Sync>;
+
+/// musbの動作モードのファイル
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MusbModeFile {
+    pub path: PathBuf,
+    /// 読み取った動作モード（読めなければ `None`）
+    pub mode: Option<String>,
+}
+
+impl MusbModeFile {
+    /// ガジェットとして接続できるモードか
+    pub fn is_peripheral(&self) -> bool {
+        matches!(self.mode.as_deref(), Some("peripheral" | "b_peripheral"))
+    }
+}
+
+/// UDCを準備した結果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UdcPreparation {
+    /// 見つかったUDC（名前順）
+    pub udcs: Vec<String>,
+    /// 読み込んだカーネルモジュール
+    pub loaded_modules: Vec<String>,
+    /// ペリフェラルモードに切り替えた `mode` ファイル
+    pub forced_peripheral: Vec<PathBuf>,
+}
+
+/// Orange Pi Zero 2W のmusbをペリフェラルモードにして、UDCが現れるまで待つ
+#[derive(Clone)]
+pub struct OrangePiUdcPreparer {
+    /// sysfsのマウント先（テストでは一時ディレクトリ）
+    sys_root: PathBuf,
+    retry_delays: Vec<Duration>,
+    module_loader: ModuleLoader,
+}
+
+impl Default for OrangePiUdcPreparer {
+    fn default() -> Self {
+        Self {
+            sys_root: PathBuf::from("/sys"),
+            retry_delays: UDC_RETRY_DELAYS.to_vec(),
+            module_loader: Arc::new(modprobe),
+        }
+    }
+}
+
+impl OrangePiUdcPreparer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sysfsのマウント先を変更（既定は `/sys`）
+    pub fn with_sys_root(mut self, sys_root: impl Into<PathBuf>) -> Self {
+        self.sys_root = sys_root.into();
+        self
+    }
+
+    /// UDCが現れるまで待つ間隔を変更
+    pub fn with_retry_delays(mut self, retry_delays: Vec<Duration>) -> Self {
+        self.retry_delays = retry_delays;
+        self
+    }
+
+    /// カーネルモジュールの読み込み方法を変更（既定は `modprobe`）
+    pub fn with_module_loader(mut self, module_loader: ModuleLoader) -> Self {
+        self.module_loader = module_loader;
+        self
+    }
+
+    /// `class/udc` にあるUDC（名前順）
+    pub fn udcs(&self) -> Vec<String> {
+        let mut udcs: Vec<String> = fs::read_dir(self.sys_root.join("class/udc"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .collect();
+        udcs.sort();
+        udcs
+    }
+
+    /// `devices/platform` の下にあるmusbの `mode` ファイル（パスの順）
+    ///
+    /// シンボリックリンクはたどらない（sysfsのリンクは循環している）。
+    pub fn mode_files(&self) -> Vec<MusbModeFile> {
+        let mut paths = Vec::new();
+        find_mode_files(
+            &self.sys_root.join("devices/platform"),
+            MODE_SEARCH_DEPTH,
+            &mut paths,
+        );
+        paths.sort();
+        paths
+            .into_iter()
+            .map(|path| MusbModeFile {
+                mode: fs::read_to_string(&path)
+                    .ok()
+                    .map(|mode| mode.trim().to_string()),
+                path,
+            })
+            .collect()
+    }
+
+    /// 読み込まれていないmusbのカーネルモジュール
+    pub fn missing_modules(&self) -> Vec<&'static str> {
+        MUSB_MODULES
+            .into_iter()
+            .filter(|module| !self.sys_root.join("module").join(module).exists())
+            .collect()
+    }
+
+    /// ペリフェラルモードでない `mode` に `peripheral` を書き込み、切り替えたファイルを返す
+    ///
+    /// `mode` が1つも見つからない場合と、書き込めなかった場合はエラーにする。
+    pub fn force_peripheral(&self) -> Result<Vec<PathBuf>, SetupError> {
+        let mode_files = self.mode_files();
+        if mode_files.is_empty() {
+            return Err(SetupError::Unknown(format!(
+                "No musb mode file found under {}: USB OTG may not be enabled in the device tree or the musb driver is not loaded",
+                self.sys_root.join("devices/platform").display()
+            )));
+        }
+        let mut switched = Vec::new();
+        for mode_file in mode_files.into_iter().filter(|file| !file.is_peripheral()) {
+            warn!(
+                "musb is in {} mode; forcing peripheral mode via {}",
+                mode_file.mode.as_deref().unwrap_or("an unknown"),
+                mode_file.path.display()
+            );
+            fs::write(&mode_file.path, "peripheral").map_err(|e| {
+                SetupError::Unknown(format!(
+                    "Failed to force peripheral mode via {}: {e}",
+                    mode_file.path.display()
+                ))
+            })?;
+            switched.push(mode_file.path);
+        }
+        Ok(switched)
+    }
+
+    /// UDCが無ければモジュールの読み込みとペリフェラルモードへの切り替えを行い、UDCが現れるまで待つ
+    pub fn prepare(&self) -> Result<UdcPreparation, SetupError> {
+        let udcs = self.udcs();
+        if !udcs.is_empty() {
+            return Ok(UdcPreparation {
+                udcs,
+                ..UdcPreparation::default()
+            });
+        }
+
+        info!("No UDC found on Orange Pi Zero 2W; preparing the musb controller");
+        let mut preparation = UdcPreparation::default();
+        for module in self.missing_modules() {
+            match (self.module_loader)(module) {
+                Ok(()) => preparation.loaded_modules.push(module.to_string()),
+                Err(e) => warn!("Failed to load module {}: {}", module, e),
+            }
+        }
+
+        // モジュールを読み込んだ直後は `mode` がまだ無いことがあるため、待つたびに切り替えを試す
+        let mut last_error = None;
+        for delay in &self.retry_delays {
+            match self.force_peripheral() {
+                Ok(switched) => {
+                    preparation.forced_peripheral.extend(switched);
+                    last_error = None;
+                }
+                Err(e) => last_error = Some(e.to_string()),
+            }
+            std::thread::sleep(*delay);
+            preparation.udcs = self.udcs();
+            if !preparation.udcs.is_empty() {
+                info!(
+                    "UDC {} appeared after preparing the musb controller",
+                    preparation.udcs.join(", ")
+                );
+                return Ok(preparation);
+            }
+            debug!("No UDC yet after waiting {:?}", delay);
+        }
+
+        Err(SetupError::Unknown(format!(
+            "No UDC found on Orange Pi Zero 2W after forcing the musb controller into peripheral mode{}. \
+             Check that 'overlays=usb-otg' is in /boot/orangepiEnv.txt, that the Switch is connected to the USB-C OTG port, and reboot",
+            last_error.map(|e| format!(" ({e})")).unwrap_or_default()
+        )))
+    }
+}
+
+/// `dir` の下から `musb-hdrc*/mode` を探す
+fn find_mode_files(dir: &Path, depth: usize, found: &mut Vec<PathBuf>) {
+    if depth == 0 {
+        return;
+    }
+    for entry in fs::read_dir(dir).into_iter().flatten().flatten() {
+        if !entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+            continue;
+        }
+        let path = entry.path();
+        let mode = path.join("mode");
+        if entry.file_name().to_string_lossy().starts_with("musb-hdrc") && mode.is_file() {
+            found.push(mode);
+        } else {
+            find_mode_files(&path, depth - 1, found);
+        }
+    }
+}
+
+fn modprobe(module: &str) -> Result<(), String> {
+    let output = Command::new("modprobe")
+        .arg(module)
+        .output()
+        .map_err(|e| format!("Failed to run modprobe: {e}"))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// テスト用のsysfsのディレクトリ
+    struct FakeSysfs {
+        root: PathBuf,
+    }
+
+    impl FakeSysfs {
+        fn new() -> Self {
+            let root = std::env::temp_dir().join(format!("fake-sysfs-{}", uuid::Uuid::new_v4()));
+            fs::create_dir_all(root.join("class/udc")).unwrap();
+            Self { root }
+        }
+
+        fn write(&self, relative: &str, content: &str) -> PathBuf {
+            let path = self.root.join(relative);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+            path
+        }
+
+        fn add_udc(&self, name: &str) {
+            fs::create_dir_all(self.root.join("class/udc").join(name)).unwrap();
+        }
+
+        fn preparer(&self) -> OrangePiUdcPreparer {
+            OrangePiUdcPreparer::new()
+                .with_sys_root(&self.root)
+                .with_retry_delays(vec![Duration::from_millis(1); 3])
+                .with_module_loader(Arc::new(|module| Err(format!("{module} not available"))))
+        }
+    }
+
+    impl Drop for FakeSysfs {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.root);
+        }
+    }
+
+    #[test]
+    fn test_mode_files_are_found_regardless_of_the_device_name() {
+        let sysfs = FakeSysfs::new();
+        let soc = sysfs.write(
+            "devices/platform/soc/5100000.usb/musb-hdrc.2.auto/mode",
+            "host\n",
+        );
+        let platform = sysfs.write("devices/platform/musb-hdrc.4.auto/mode", "b_peripheral\n");
+        // 深すぎる場所とmusb以外のデバイスは対象にしない
+        sysfs.write("devices/platform/a/b/c/d/musb-hdrc.9.auto/mode", "host\n");
+        sysfs.write("devices/platform/soc/5200000.usb/usb1/mode", "host\n");
+
+        let preparer = sysfs.preparer();
+        let mode_files = preparer.mode_files();
+        assert_eq!(
+            mode_files,
+            vec![
+                MusbModeFile {
+                    path: platform,
+                    mode: Some("b_peripheral".to_string()),
+                },
+                MusbModeFile {
+                    path: soc.clone(),
+                    mode: Some("host".to_string()),
+                },
+            ]
+        );
+        assert!(mode_files[0].is_peripheral());
+        assert!(!mode_files[1].is_peripheral());
+
+        assert_eq!(preparer.force_peripheral().unwrap(), vec![soc.clone()]);
+        assert_eq!(fs::read_to_string(&soc).unwrap(), "peripheral");
+        // 既にペリフェラルモードなら書き込まない
+        assert!(preparer.force_peripheral().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_mode_file_is_an_error() {
+        let sysfs = FakeSysfs::new();
+        let error = sysfs.preparer().force_peripheral().unwrap_err();
+        assert!(error.to_string().contains("No musb mode file"), "{error}");
+    }
+
+    #[test]
+    fn test_prepare_loads_missing_modules_and_waits_for_the_udc() {
+        let sysfs = FakeSysfs::new();
+        sysfs.write("module/sunxi/refcnt", "1\n");
+        let mode = sysfs.write(
+            "devices/platform/soc/5100000.usb/musb-hdrc.4.auto/mode",
+            "host\n",
+        );
+
+        // モジュールを読み込むとUDCが現れる
+        let loaded = Arc::new(Mutex::new(Vec::new()));
+        let loader_log = loaded.clone();
+        let udc_class = sysfs.root.join("class/udc/musb-hdrc.4.auto");
+        let preparer = sysfs
+            .preparer()
+            .with_module_loader(Arc::new(move |module: &str| {
+                loader_log.lock().unwrap().push(module.to_string());
+                fs::create_dir_all(&udc_class).unwrap();
+                Ok(())
+            }));
+
+        let preparation = preparer.prepare().unwrap();
+        assert_eq!(*loaded.lock().unwrap(), vec!["musb_hdrc".to_string()]);
+        assert_eq!(preparation.loaded_modules, vec!["musb_hdrc".to_string()]);
+        assert_eq!(preparation.forced_peripheral, vec![mode.clone()]);
+        assert_eq!(preparation.udcs, vec!["musb-hdrc.4.auto".to_string()]);
+        assert_eq!(fs::read_to_string(&mode).unwrap(), "peripheral");
+
+        // UDCがあれば何もしない
+        fs::write(&mode, "host").unwrap();
+        let preparation = preparer.prepare().unwrap();
+        assert!(preparation.forced_peripheral.is_empty());
+        assert_eq!(fs::read_to_string(&mode).unwrap(), "host");
+    }
+
+    #[test]
+    fn test_prepare_gives_up_with_a_board_specific_error() {
+        let sysfs = FakeSysfs::new();
+        let error = sysfs.preparer().prepare().unwrap_err().to_string();
+        assert!(error.contains("Orange Pi Zero 2W"), "{error}");
+        assert!(error.contains("overlays=usb-otg"), "{error}");
+        assert!(error.contains("No musb mode file"), "{error}");
+
+        // ペリフェラルモードにしてもUDCが現れない場合
+        sysfs.write("devices/platform/musb-hdrc.4.auto/mode", "host\n");
+        let error = sysfs.preparer().prepare().unwrap_err().to_string();
+        assert!(!error.contains("No musb mode file"), "{error}");
+        sysfs.add_udc("musb-hdrc.4.auto");
+        assert!(sysfs.preparer().prepare().is_ok());
+    }
+}
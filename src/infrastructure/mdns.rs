@@ -0,0 +1,145 @@
+//! mDNS（DNS-SD）によるWeb UIの告知
+//!
+//! DHCPでIPアドレスが変わってもLAN内の端末から `http://splatoon3-drawer.local:8080` で開けるよう、
+//! ホスト名と `_http._tcp`（HTTPS時は `_https._tcp`）のサービスを告知する。
+//! アドレスはネットワークインターフェースの変化に合わせて自動で更新される。
+
+use mdns_sd::{ServiceDaemon, ServiceInfo};
+use std::time::Duration;
+use thiserror::Error;
+use tracing::{debug, warn};
+
+/// 告知するホスト名の既定値（`.local` を除く）
+pub const DEFAULT_MDNS_HOSTNAME: &str = "splatoon3-drawer";
+/// DNS-SDのサービスのインスタンス名
+const SERVICE_INSTANCE_NAME: &str = "Splatoon3 Ghost Drawer";
+/// 告知の取り消しを待つ最大時間（終了を遅らせすぎないように短くする）
+const UNREGISTER_TIMEOUT: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Error)]
+pub enum MdnsError {
+    #[error("Invalid mDNS hostname '{0}': use 1-63 letters, digits or hyphens (without .local)")]
+    InvalidHostname(String),
+    #[error("Failed to start the mDNS responder: {0}")]
+    Daemon(String),
+    #[error("Failed to register the mDNS service: {0}")]
+    Register(String),
+}
+
+/// 実行中のmDNSの告知（破棄時に取り消す）
+pub struct MdnsAdvertisement {
+    daemon: ServiceDaemon,
+    fullname: String,
+    hostname: String,
+    port: u16,
+    scheme: &'static str,
+}
+
+impl MdnsAdvertisement {
+    /// `hostname.local` と、`port` で待ち受けているWeb UIのサービスを告知する
+    ///
+    /// TXTレコードにはクレートのバージョンとWeb UIのパスを含める。
+    pub fn start(hostname: &str, port: u16, tls: bool) -> Result<Self, MdnsError> {
+        validate_hostname(hostname)?;
+        let (scheme, service_type) = if tls {
+            ("https", "_https._tcp.local.")
+        } else {
+            ("http", "_http._tcp.local.")
+        };
+
+        let daemon = ServiceDaemon::new().map_err(|e| MdnsError::Daemon(e.to_string()))?;
+        let properties = [("version", env!("CARGO_PKG_VERSION")), ("path", "/")];
+        let service = ServiceInfo::new(
+            service_type,
+            SERVICE_INSTANCE_NAME,
+            &format!("{hostname}.local."),
+            (),
+            port,
+            &properties[..],
+        )
+        .map_err(|e| MdnsError::Register(e.to_string()))?
+        .enable_addr_auto();
+        let fullname = service.get_fullname().to_string();
+        if let Err(e) = daemon.register(service) {
+            let _ = daemon.shutdown();
+            return Err(MdnsError::Register(e.to_string()));
+        }
+
+        Ok(Self {
+            daemon,
+            fullname,
+            hostname: format!("{hostname}.local"),
+            port,
+            scheme,
+        })
+    }
+
+    /// 告知しているホスト名（`splatoon3-drawer.local` など）
+    pub fn hostname(&self) -> &str {
+        &self.hostname
+    }
+
+    /// 告知しているWeb UIのURL
+    pub fn url(&self) -> String {
+        format!("{}://{}:{}", self.scheme, self.hostname, self.port)
+    }
+}
+
+impl Drop for MdnsAdvertisement {
+    fn drop(&mut self) {
+        // 取り消しを送ってから止めると、他の端末のキャッシュからすぐに消える
+        match self.daemon.unregister(&self.fullname) {
+            Ok(status) => {
+                if let Err(e) = status.recv_timeout(UNREGISTER_TIMEOUT) {
+                    debug!("mDNS unregister did not complete: {}", e);
+                }
+            }
+            Err(e) => warn!("Failed to unregister mDNS service: {}", e),
+        }
+        if let Err(e) = self.daemon.shutdown() {
+            debug!("Failed to stop the mDNS responder: {}", e);
+        }
+    }
+}
+
+/// ホスト名が1つのDNSラベルとして使えるか確認する
+pub fn validate_hostname(hostname: &str) -> Result<(), MdnsError> {
+    let valid = (1..=63).contains(&hostname.len())
+        && hostname
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-')
+        && !hostname.starts_with('-')
+        && !hostname.ends_with('-');
+    if valid {
+        Ok(())
+    } else {
+        Err(MdnsError::InvalidHostname(hostname.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_hostname() {
+        validate_hostname(DEFAULT_MDNS_HOSTNAME).unwrap();
+        validate_hostname("drawer2").unwrap();
+        for invalid in [
+            "",
+            "drawer.local",
+            "-drawer",
+            "drawer-",
+            "ドロワー",
+            &"a".repeat(64),
+        ] {
+            assert!(
+                matches!(
+                    validate_hostname(invalid),
+                    Err(MdnsError::InvalidHostname(_))
+                ),
+                "{invalid}"
+            );
+        }
+    }
+}
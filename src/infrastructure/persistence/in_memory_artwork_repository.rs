@@ -0,0 +1,126 @@
+use crate::domain::artwork::entities::{Artwork, ArtworkId};
+use crate::domain::artwork::repositories::{
+    ArtworkQuery, ArtworkRepository, RepositoryError, RepositoryHealth, SearchResult,
+    TrashedArtwork,
+};
+use crate::domain::shared::value_objects::Timestamp;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use tokio::sync::RwLock;
+
+/// メモリ上にアートワークを保持するリポジトリ（テストやシミュレーション向け）
+#[derive(Default)]
+pub struct InMemoryArtworkRepository {
+    artworks: RwLock<HashMap<ArtworkId, Artwork>>,
+    trash: RwLock<HashMap<ArtworkId, TrashedArtwork>>,
+}
+
+impl InMemoryArtworkRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl ArtworkRepository for InMemoryArtworkRepository {
+    async fn save(&self, artwork: &Artwork) -> Result<(), RepositoryError> {
+        // ゴミ箱のアートワークはゴミ箱に入れたまま更新する
+        if let Some(trashed) = self.trash.write().await.get_mut(&artwork.id) {
+            trashed.artwork = artwork.clone();
+            return Ok(());
+        }
+        self.artworks
+            .write()
+            .await
+            .insert(artwork.id.clone(), artwork.clone());
+        Ok(())
+    }
+
+    async fn find_by_id(&self, id: &ArtworkId) -> Result<Option<Artwork>, RepositoryError> {
+        Ok(self.artworks.read().await.get(id).cloned())
+    }
+
+    async fn delete(&self, id: &ArtworkId) -> Result<(), RepositoryError> {
+        let removed = self.artworks.write().await.remove(id).is_some()
+            || self.trash.write().await.remove(id).is_some();
+        if removed {
+            Ok(())
+        } else {
+            Err(RepositoryError::NotFound { id: id.clone() })
+        }
+    }
+
+    async fn move_to_trash(
+        &self,
+        id: &ArtworkId,
+        deleted_at: Timestamp,
+    ) -> Result<(), RepositoryError> {
+        let artwork = self
+            .artworks
+            .write()
+            .await
+            .remove(id)
+            .ok_or_else(|| RepositoryError::NotFound { id: id.clone() })?;
+        self.trash.write().await.insert(
+            id.clone(),
+            TrashedArtwork {
+                artwork,
+                deleted_at,
+            },
+        );
+        Ok(())
+    }
+
+    async fn restore_from_trash(&self, id: &ArtworkId) -> Result<(), RepositoryError> {
+        let trashed = self
+            .trash
+            .write()
+            .await
+            .remove(id)
+            .ok_or_else(|| RepositoryError::NotFound { id: id.clone() })?;
+        self.artworks
+            .write()
+            .await
+            .insert(id.clone(), trashed.artwork);
+        Ok(())
+    }
+
+    async fn find_trashed(&self) -> Result<Vec<TrashedArtwork>, RepositoryError> {
+        let mut trashed: Vec<TrashedArtwork> = self.trash.read().await.values().cloned().collect();
+        trashed.sort_by_key(|trashed| (trashed.deleted_at, trashed.artwork.id.as_str()));
+        Ok(trashed)
+    }
+
+    async fn search(&self, query: &ArtworkQuery) -> Result<SearchResult, RepositoryError> {
+        query.validate()?;
+        let started = std::time::Instant::now();
+        let artworks = self.artworks.read().await;
+        let mut result = query.apply(
+            artworks
+                .values()
+                .filter(|artwork| query.matches(artwork))
+                .cloned(),
+        );
+        result.query_time_ms = started.elapsed().as_millis() as u64;
+        Ok(result)
+    }
+
+    async fn count(&self) -> Result<usize, RepositoryError> {
+        Ok(self.artworks.read().await.len())
+    }
+
+    async fn health_check(&self) -> Result<RepositoryHealth, RepositoryError> {
+        Ok(RepositoryHealth {
+            total_artworks: self.artworks.read().await.len(),
+            ..RepositoryHealth::healthy()
+        })
+    }
+
+    async fn initialize(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+
+    async fn cleanup(&self) -> Result<(), RepositoryError> {
+        Ok(())
+    }
+}
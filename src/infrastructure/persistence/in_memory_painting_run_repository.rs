@@ -0,0 +1,52 @@
+use crate::domain::artwork::entities::ArtworkId;
+use crate::domain::painting::entities::PaintingRun;
+use crate::domain::painting::repositories::PaintingRunRepository;
+use std::sync::Mutex;
+
+/// メモリ上に描画実行記録を保持するリポジトリ
+#[derive(Default)]
+pub struct InMemoryPaintingRunRepository {
+    runs: Mutex<Vec<PaintingRun>>,
+}
+
+impl InMemoryPaintingRunRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn newest_first(&self, filter: impl Fn(&PaintingRun) -> bool) -> Vec<PaintingRun> {
+        let runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+        runs.iter()
+            .rev()
+            .filter(|run| filter(run))
+            .cloned()
+            .collect()
+    }
+}
+
+impl PaintingRunRepository for InMemoryPaintingRunRepository {
+    fn save(&self, run: &PaintingRun) {
+        let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+        match runs.iter_mut().find(|existing| existing.id == run.id) {
+            Some(existing) => *existing = run.clone(),
+            None => runs.push(run.clone()),
+        }
+    }
+
+    fn find_by_artwork(&self, artwork_id: &ArtworkId) -> Vec<PaintingRun> {
+        self.newest_first(|run| run.artwork_id == *artwork_id)
+    }
+
+    fn recent(&self, limit: usize) -> Vec<PaintingRun> {
+        let mut runs = self.newest_first(|_| true);
+        runs.truncate(limit);
+        runs
+    }
+
+    fn clear(&self) -> usize {
+        let mut runs = self.runs.lock().unwrap_or_else(|e| e.into_inner());
+        let removed = runs.len();
+        runs.clear();
+        removed
+    }
+}
@@ -0,0 +1,790 @@
+use super::sqlite_database::{SqliteDatabase, repository_error};
+use crate::domain::artwork::entities::{
+    Artwork, ArtworkId, ArtworkMetadata, ArtworkSetMembership, Canvas, Dot,
+};
+use crate::domain::artwork::repositories::{
+    ArtworkQuery, ArtworkRepository, RepositoryError, RepositoryHealth, SearchResult, SortField,
+    SortOrder, TrashedArtwork,
+};
+use crate::domain::shared::value_objects::{Color, Coordinates, Timestamp};
+use async_trait::async_trait;
+use flate2::Compression;
+use flate2::read::ZlibDecoder;
+use flate2::write::ZlibEncoder;
+use rusqlite::types::Value;
+use rusqlite::{Connection, OptionalExtension, Row, params, params_from_iter};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+
+/// SQLiteにアートワークを保存するリポジトリ
+///
+/// 名前・タグ・作者・チェックサムは列として保存し、検索条件はSQLで絞り込む。
+/// キャンバスはJSONをzlibで圧縮したBLOBとして保存する。
+/// ゴミ箱に移したアートワークは行を残して `deleted_at` を設定し、通常の取得・検索からは除く。
+/// ドットごとの作成・描画日時は集計にしか使わないため、既定では捨ててから保存する。
+pub struct SqliteArtworkRepository {
+    database: SqliteDatabase,
+    keep_dot_timestamps: bool,
+}
+
+impl SqliteArtworkRepository {
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self {
+            database,
+            keep_dot_timestamps: false,
+        }
+    }
+
+    /// ドットごとの作成・描画日時も保存する
+    pub fn with_dot_timestamps(mut self) -> Self {
+        self.keep_dot_timestamps = true;
+        self
+    }
+}
+
+/// 保存用のキャンバス表現（座標をキーにしたマップはJSONにできないため配列にする）
+#[derive(Serialize, Deserialize)]
+struct StoredCanvas {
+    width: u16,
+    height: u16,
+    background_color: Color,
+    dots: Vec<(Coordinates, Dot)>,
+}
+
+/// キャンバスを保存用に圧縮する（`keep_dot_timestamps` が偽ならドットの日時を捨てる）
+fn encode_canvas(canvas: &Canvas, keep_dot_timestamps: bool) -> Result<Vec<u8>, RepositoryError> {
+    let mut dots: Vec<(Coordinates, Dot)> = canvas
+        .dots
+        .iter()
+        .map(|(coordinates, dot)| {
+            let mut dot = dot.clone();
+            if !keep_dot_timestamps {
+                dot.compact();
+            }
+            (*coordinates, dot)
+        })
+        .collect();
+    dots.sort_by_key(|(coordinates, _)| (coordinates.y, coordinates.x));
+    let stored = StoredCanvas {
+        width: canvas.width,
+        height: canvas.height,
+        background_color: canvas.background_color,
+        dots,
+    };
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    serde_json::to_writer(&mut encoder, &stored).map_err(serialization_error)?;
+    encoder.flush().map_err(serialization_error)?;
+    encoder.finish().map_err(serialization_error)
+}
+
+fn decode_canvas(blob: &[u8]) -> Result<Canvas, RepositoryError> {
+    let mut json = Vec::new();
+    ZlibDecoder::new(blob)
+        .read_to_end(&mut json)
+        .map_err(serialization_error)?;
+    let stored: StoredCanvas = serde_json::from_slice(&json).map_err(serialization_error)?;
+    Ok(Canvas {
+        width: stored.width,
+        height: stored.height,
+        dots: stored.dots.into_iter().collect(),
+        background_color: stored.background_color,
+    })
+}
+
+fn serialization_error(error: impl std::fmt::Display) -> RepositoryError {
+    RepositoryError::SerializationError {
+        message: error.to_string(),
+    }
+}
+
+const SELECT_ARTWORK: &str = "SELECT id, name, description, author, original_filename, file_size, \
+     checksum, original_format, canvas, created_at, updated_at, version, painting_preferences, \
+     vector_paths, set_id, set_frame, set_frames, change_log, deleted_at FROM artworks";
+
+fn read_artwork(connection: &Connection, row: &Row<'_>) -> Result<Artwork, RepositoryError> {
+    let id: String = row.get(0).map_err(repository_error)?;
+    let blob: Vec<u8> = row.get(8).map_err(repository_error)?;
+    let tags = connection
+        .prepare_cached("SELECT tag FROM artwork_tags WHERE artwork_id = ?1 ORDER BY position")
+        .and_then(|mut statement| {
+            statement
+                .query_map([&id], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()
+        })
+        .map_err(repository_error)?;
+
+    let read = |row: &Row<'_>| -> rusqlite::Result<(ArtworkMetadata, String, i64, i64, u32)> {
+        Ok((
+            ArtworkMetadata {
+                name: row.get(1)?,
+                description: row.get(2)?,
+                tags,
+                author: row.get(3)?,
+                original_filename: row.get(4)?,
+                file_size: row.get::<_, i64>(5)? as u64,
+                checksum: row.get(6)?,
+            },
+            row.get(7)?,
+            row.get(9)?,
+            row.get(10)?,
+            row.get(11)?,
+        ))
+    };
+    let (metadata, original_format, created_at, updated_at, version) =
+        read(row).map_err(repository_error)?;
+    let painting_preferences = row
+        .get::<_, Option<String>>(12)
+        .map_err(repository_error)?
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(serialization_error)?;
+    let vector_paths = row
+        .get::<_, Option<String>>(13)
+        .map_err(repository_error)?
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(serialization_error)?
+        .unwrap_or_default();
+    let artwork_set = row
+        .get::<_, Option<String>>(14)
+        .map_err(repository_error)?
+        .map(|set_id| {
+            Ok(ArtworkSetMembership {
+                set_id,
+                frame: row.get(15)?,
+                frames: row.get(16)?,
+            })
+        })
+        .transpose()
+        .map_err(repository_error)?;
+    let change_log = row
+        .get::<_, Option<String>>(17)
+        .map_err(repository_error)?
+        .map(|json| serde_json::from_str(&json))
+        .transpose()
+        .map_err(serialization_error)?
+        .unwrap_or_default();
+
+    Ok(Artwork {
+        id: ArtworkId::parse(&id)
+            .map_err(|message| RepositoryError::SerializationError { message })?,
+        metadata,
+        original_format,
+        canvas: decode_canvas(&blob)?,
+        created_at: Timestamp::from_millis(created_at as u64),
+        updated_at: Timestamp::from_millis(updated_at as u64),
+        version,
+        painting_preferences,
+        vector_paths,
+        artwork_set,
+        change_log,
+    })
+}
+
+fn query_artworks(
+    connection: &Connection,
+    sql: &str,
+    values: &[Value],
+) -> Result<Vec<Artwork>, RepositoryError> {
+    let mut statement = connection.prepare(sql).map_err(repository_error)?;
+    let mut rows = statement
+        .query(params_from_iter(values))
+        .map_err(repository_error)?;
+    let mut artworks = Vec::new();
+    while let Some(row) = rows.next().map_err(repository_error)? {
+        artworks.push(read_artwork(connection, row)?);
+    }
+    Ok(artworks)
+}
+
+/// 列に保存している条件を `WHERE` 句にする
+fn where_clause(query: &ArtworkQuery) -> (String, Vec<Value>) {
+    let mut conditions = vec!["deleted_at IS NULL".to_string()];
+    let mut values = Vec::new();
+
+    if let Some(ids) = &query.ids {
+        let placeholders = vec!["?"; ids.len()].join(", ");
+        conditions.push(format!("id IN ({placeholders})"));
+        values.extend(ids.iter().map(|id| Value::Text(id.as_str())));
+    }
+    if let Some(name) = &query.name_contains {
+        // 大文字・小文字を区別しない部分一致（`%` や `_` はそのまま文字として扱う）
+        conditions.push("instr(lower(name), lower(?)) > 0".to_string());
+        values.push(Value::Text(name.clone()));
+    }
+    for tag in query.tags.iter().flatten() {
+        conditions.push(
+            "EXISTS (SELECT 1 FROM artwork_tags t WHERE t.artwork_id = artworks.id AND t.tag = ?)"
+                .to_string(),
+        );
+        values.push(Value::Text(tag.clone()));
+    }
+    let mut equals = |column: &str, value: &Option<String>| {
+        if let Some(value) = value {
+            conditions.push(format!("{column} = ?"));
+            values.push(Value::Text(value.clone()));
+        }
+    };
+    equals("author", &query.author);
+    equals("checksum", &query.checksum);
+    equals("set_id", &query.set_id);
+    equals("original_format", &query.format);
+    for (column, operator, timestamp) in [
+        ("created_at", ">", query.created_after),
+        ("created_at", "<", query.created_before),
+        ("updated_at", ">", query.updated_after),
+        ("updated_at", "<", query.updated_before),
+    ] {
+        if let Some(timestamp) = timestamp {
+            conditions.push(format!("{column} {operator} ?"));
+            values.push(Value::Integer(timestamp.epoch_millis as i64));
+        }
+    }
+
+    (format!(" WHERE {}", conditions.join(" AND ")), values)
+}
+
+/// 並べ替えの列（キャンバスから計算する値は `None`）
+fn sort_column(field: SortField) -> Option<&'static str> {
+    match field {
+        SortField::Name => Some("name"),
+        SortField::CreatedAt => Some("created_at"),
+        SortField::UpdatedAt => Some("updated_at"),
+        SortField::TotalDots => Some("total_dots"),
+        SortField::FileSize => Some("file_size"),
+        SortField::CompletionRatio | SortField::ComplexityScore => None,
+    }
+}
+
+fn search_artworks(
+    connection: &Connection,
+    query: &ArtworkQuery,
+) -> Result<SearchResult, RepositoryError> {
+    let (where_sql, values) = where_clause(query);
+
+    // 完成度・複雑度はキャンバスから計算するため、列の条件で絞り込んだ後にメモリ上で処理する
+    if query.needs_computed_fields() {
+        let sql = format!("{SELECT_ARTWORK}{where_sql}");
+        return Ok(query.apply(query_artworks(connection, &sql, &values)?));
+    }
+
+    let total_count: usize = connection
+        .query_row(
+            &format!("SELECT COUNT(*) FROM artworks{where_sql}"),
+            params_from_iter(&values),
+            |row| row.get::<_, i64>(0),
+        )
+        .map_err(repository_error)? as usize;
+
+    let column = query.sort_by.and_then(sort_column).unwrap_or("created_at");
+    let direction = match query.sort_order {
+        Some(SortOrder::Descending) => "DESC",
+        _ => "ASC",
+    };
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.map_or(-1, |limit| limit as i64);
+    let sql = format!(
+        "{SELECT_ARTWORK}{where_sql} ORDER BY {column} {direction}, id {direction} LIMIT {limit} OFFSET {offset}"
+    );
+    let artworks = query_artworks(connection, &sql, &values)?;
+    let has_more = offset + artworks.len() < total_count;
+    Ok(SearchResult::new(artworks, total_count, has_more, 0))
+}
+
+fn save_artwork(
+    connection: &mut Connection,
+    artwork: &Artwork,
+    canvas: &[u8],
+    painting_preferences: Option<String>,
+    vector_paths: Option<String>,
+    change_log: Option<String>,
+) -> rusqlite::Result<()> {
+    let id = artwork.id.as_str();
+    let metadata = &artwork.metadata;
+    let transaction = connection.transaction()?;
+    transaction.execute(
+        "INSERT INTO artworks (id, name, description, author, original_filename, file_size, \
+         checksum, original_format, canvas_width, canvas_height, total_dots, canvas, created_at, \
+         updated_at, version, painting_preferences, vector_paths, set_id, set_frame, set_frames, \
+         change_log) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, \
+         ?19, ?20, ?21) \
+         ON CONFLICT (id) DO UPDATE SET name = excluded.name, description = excluded.description, \
+         author = excluded.author, original_filename = excluded.original_filename, \
+         file_size = excluded.file_size, checksum = excluded.checksum, \
+         original_format = excluded.original_format, canvas_width = excluded.canvas_width, \
+         canvas_height = excluded.canvas_height, total_dots = excluded.total_dots, \
+         canvas = excluded.canvas, created_at = excluded.created_at, \
+         updated_at = excluded.updated_at, version = excluded.version, \
+         painting_preferences = excluded.painting_preferences, \
+         vector_paths = excluded.vector_paths, set_id = excluded.set_id, \
+         set_frame = excluded.set_frame, set_frames = excluded.set_frames, \
+         change_log = excluded.change_log",
+        params![
+            id,
+            metadata.name,
+            metadata.description,
+            metadata.author,
+            metadata.original_filename,
+            metadata.file_size as i64,
+            metadata.checksum,
+            artwork.original_format,
+            artwork.canvas.width,
+            artwork.canvas.height,
+            artwork.total_dots() as i64,
+            canvas,
+            artwork.created_at.epoch_millis as i64,
+            artwork.updated_at.epoch_millis as i64,
+            artwork.version,
+            painting_preferences,
+            vector_paths,
+            artwork.artwork_set.as_ref().map(|set| &set.set_id),
+            artwork.artwork_set.as_ref().map(|set| set.frame),
+            artwork.artwork_set.as_ref().map(|set| set.frames),
+            change_log,
+        ],
+    )?;
+    transaction.execute("DELETE FROM artwork_tags WHERE artwork_id = ?1", [&id])?;
+    {
+        let mut insert = transaction.prepare_cached(
+            "INSERT INTO artwork_tags (artwork_id, position, tag) VALUES (?1, ?2, ?3)",
+        )?;
+        for (position, tag) in metadata.tags.iter().enumerate() {
+            insert.execute(params![id, position as i64, tag])?;
+        }
+    }
+    transaction.commit()
+}
+
+#[async_trait]
+impl ArtworkRepository for SqliteArtworkRepository {
+    async fn save(&self, artwork: &Artwork) -> Result<(), RepositoryError> {
+        let artwork = artwork.clone();
+        let keep_dot_timestamps = self.keep_dot_timestamps;
+        self.database
+            .run(move |connection| {
+                let canvas = encode_canvas(&artwork.canvas, keep_dot_timestamps)?;
+                let painting_preferences = artwork
+                    .painting_preferences
+                    .map(|preferences| serde_json::to_string(&preferences))
+                    .transpose()
+                    .map_err(serialization_error)?;
+                let vector_paths = (!artwork.vector_paths.is_empty())
+                    .then(|| serde_json::to_string(&artwork.vector_paths))
+                    .transpose()
+                    .map_err(serialization_error)?;
+                let change_log = (!artwork.change_log.is_empty())
+                    .then(|| serde_json::to_string(&artwork.change_log))
+                    .transpose()
+                    .map_err(serialization_error)?;
+                save_artwork(
+                    connection,
+                    &artwork,
+                    &canvas,
+                    painting_preferences,
+                    vector_paths,
+                    change_log,
+                )
+                .map_err(repository_error)
+            })
+            .await
+    }
+
+    async fn find_by_id(&self, id: &ArtworkId) -> Result<Option<Artwork>, RepositoryError> {
+        let id = id.as_str();
+        self.database
+            .run(move |connection| {
+                let sql = format!("{SELECT_ARTWORK} WHERE id = ?1 AND deleted_at IS NULL");
+                let mut statement = connection.prepare(&sql).map_err(repository_error)?;
+                let mut rows = statement.query([&id]).map_err(repository_error)?;
+                match rows.next().map_err(repository_error)? {
+                    Some(row) => read_artwork(connection, row).map(Some),
+                    None => Ok(None),
+                }
+            })
+            .await
+    }
+
+    async fn delete(&self, id: &ArtworkId) -> Result<(), RepositoryError> {
+        let id = id.clone();
+        self.database
+            .run(move |connection| {
+                let deleted = connection
+                    .execute("DELETE FROM artworks WHERE id = ?1", [id.as_str()])
+                    .map_err(repository_error)?;
+                if deleted == 0 {
+                    return Err(RepositoryError::NotFound { id });
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn move_to_trash(
+        &self,
+        id: &ArtworkId,
+        deleted_at: Timestamp,
+    ) -> Result<(), RepositoryError> {
+        let id = id.clone();
+        self.database
+            .run(move |connection| {
+                let trashed = connection
+                    .execute(
+                        "UPDATE artworks SET deleted_at = ?2 WHERE id = ?1 AND deleted_at IS NULL",
+                        params![id.as_str(), deleted_at.epoch_millis as i64],
+                    )
+                    .map_err(repository_error)?;
+                if trashed == 0 {
+                    return Err(RepositoryError::NotFound { id });
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn restore_from_trash(&self, id: &ArtworkId) -> Result<(), RepositoryError> {
+        let id = id.clone();
+        self.database
+            .run(move |connection| {
+                let restored = connection
+                    .execute(
+                        "UPDATE artworks SET deleted_at = NULL \
+                         WHERE id = ?1 AND deleted_at IS NOT NULL",
+                        [id.as_str()],
+                    )
+                    .map_err(repository_error)?;
+                if restored == 0 {
+                    return Err(RepositoryError::NotFound { id });
+                }
+                Ok(())
+            })
+            .await
+    }
+
+    async fn find_trashed(&self) -> Result<Vec<TrashedArtwork>, RepositoryError> {
+        self.database
+            .run(|connection| {
+                let sql = format!(
+                    "{SELECT_ARTWORK} WHERE deleted_at IS NOT NULL ORDER BY deleted_at, id"
+                );
+                let mut statement = connection.prepare(&sql).map_err(repository_error)?;
+                let mut rows = statement.query([]).map_err(repository_error)?;
+                let mut trashed = Vec::new();
+                while let Some(row) = rows.next().map_err(repository_error)? {
+                    let deleted_at: i64 = row.get(18).map_err(repository_error)?;
+                    trashed.push(TrashedArtwork {
+                        artwork: read_artwork(connection, row)?,
+                        deleted_at: Timestamp::from_millis(deleted_at as u64),
+                    });
+                }
+                Ok(trashed)
+            })
+            .await
+    }
+
+    async fn search(&self, query: &ArtworkQuery) -> Result<SearchResult, RepositoryError> {
+        query.validate()?;
+        let query = query.clone();
+        let started = std::time::Instant::now();
+        let mut result = self
+            .database
+            .run(move |connection| search_artworks(connection, &query))
+            .await?;
+        result.query_time_ms = started.elapsed().as_millis() as u64;
+        Ok(result)
+    }
+
+    async fn exists(&self, id: &ArtworkId) -> Result<bool, RepositoryError> {
+        let id = id.as_str();
+        self.database
+            .run(move |connection| {
+                connection
+                    .query_row(
+                        "SELECT 1 FROM artworks WHERE id = ?1 AND deleted_at IS NULL",
+                        [&id],
+                        |_| Ok(()),
+                    )
+                    .optional()
+                    .map(|found| found.is_some())
+                    .map_err(repository_error)
+            })
+            .await
+    }
+
+    async fn count(&self) -> Result<usize, RepositoryError> {
+        self.database
+            .run(|connection| count_artworks(connection).map_err(repository_error))
+            .await
+    }
+
+    async fn health_check(&self) -> Result<RepositoryHealth, RepositoryError> {
+        self.database
+            .run(|connection| {
+                let total_artworks = count_artworks(connection).map_err(repository_error)?;
+                let page_count: i64 = connection
+                    .pragma_query_value(None, "page_count", |row| row.get(0))
+                    .map_err(repository_error)?;
+                let page_size: i64 = connection
+                    .pragma_query_value(None, "page_size", |row| row.get(0))
+                    .map_err(repository_error)?;
+                Ok(RepositoryHealth {
+                    total_artworks,
+                    storage_usage_bytes: (page_count * page_size) as u64,
+                    ..RepositoryHealth::healthy()
+                })
+            })
+            .await
+    }
+
+    async fn initialize(&self) -> Result<(), RepositoryError> {
+        // スキーマは `SqliteDatabase::open` で作成済み
+        Ok(())
+    }
+
+    async fn cleanup(&self) -> Result<(), RepositoryError> {
+        self.database
+            .run(|connection| {
+                connection
+                    .execute_batch("PRAGMA optimize;")
+                    .map_err(repository_error)
+            })
+            .await
+    }
+}
+
+fn count_artworks(connection: &Connection) -> rusqlite::Result<usize> {
+    connection
+        .query_row(
+            "SELECT COUNT(*) FROM artworks WHERE deleted_at IS NULL",
+            [],
+            |row| row.get::<_, i64>(0),
+        )
+        .map(|count| count as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::artwork::change_log::ArtworkChange;
+    use crate::domain::artwork::value_objects::Polyline;
+    use crate::domain::painting::{DrawingStrategy, PaintingPreferences};
+    use crate::domain::shared::events::EventMetadata;
+
+    fn artwork(name: &str, tags: &[&str]) -> Artwork {
+        let mut metadata = ArtworkMetadata::new(name.to_string());
+        metadata.set_tags(tags).unwrap();
+        let mut canvas = Canvas::new(8, 4);
+        canvas
+            .set_dot(
+                Coordinates::new(1, 2),
+                Dot::with_layer(Color::black(), 255, 1),
+            )
+            .unwrap();
+        canvas
+            .set_dot(Coordinates::new(7, 0), Dot::new(Color::white(), 128))
+            .unwrap();
+        Artwork::new(metadata, "png".to_string(), canvas)
+    }
+
+    #[tokio::test]
+    async fn test_compacted_canvas_round_trips_and_shrinks() {
+        let database = SqliteDatabase::open_in_memory().unwrap();
+        let mut original = artwork("Painted", &[]);
+        original
+            .canvas
+            .get_dot_mut(&Coordinates::new(1, 2))
+            .unwrap()
+            .mark_as_painted();
+
+        let compacted = encode_canvas(&original.canvas, false).unwrap();
+        let full = encode_canvas(&original.canvas, true).unwrap();
+        assert!(compacted.len() < full.len());
+
+        let repository = SqliteArtworkRepository::new(database.clone());
+        repository.save(&original).await.unwrap();
+        let loaded = repository.find_by_id(&original.id).await.unwrap().unwrap();
+        let dot = loaded.canvas.get_dot(&Coordinates::new(1, 2)).unwrap();
+        assert!(dot.is_painted);
+        assert_eq!(dot.layer, 1);
+        assert_eq!(dot.painted_age_millis(), None);
+        assert_eq!(loaded.canvas.drawable_dots().len(), 1);
+
+        // 日時を残す設定ではそのまま保存する
+        let repository = SqliteArtworkRepository::new(database).with_dot_timestamps();
+        repository.save(&original).await.unwrap();
+        let loaded = repository.find_by_id(&original.id).await.unwrap().unwrap();
+        assert_eq!(loaded.canvas.dots, original.canvas.dots);
+    }
+
+    #[tokio::test]
+    async fn test_save_find_and_delete_round_trip() {
+        let repository = SqliteArtworkRepository::new(SqliteDatabase::open_in_memory().unwrap());
+        let mut original = artwork("Squid", &["logo", "squid"]);
+        original.metadata.author = Some("Agent 3".to_string());
+        original.metadata.checksum = "abc123".to_string();
+        repository.save(&original).await.unwrap();
+
+        let loaded = repository.find_by_id(&original.id).await.unwrap().unwrap();
+        assert_eq!(loaded.metadata, original.metadata);
+        // ドットの日時は捨てて保存する
+        let mut compacted = original.canvas.clone();
+        compacted.compact_dots();
+        assert_eq!(loaded.canvas.dots, compacted.dots);
+        assert_eq!(loaded.canvas.width, 8);
+        assert_eq!(loaded.created_at, original.created_at);
+        assert_eq!(loaded.version, original.version);
+        assert_eq!(loaded.painting_preferences, None);
+        assert!(loaded.vector_paths.is_empty());
+        assert!(loaded.change_log.is_empty());
+
+        // 保存し直すとタグも置き換わる
+        let mut updated = loaded;
+        updated.metadata.set_tags(&["octo"]).unwrap();
+        updated.set_painting_preferences(Some(PaintingPreferences {
+            wait_ms: Some(20),
+            strategy: Some(DrawingStrategy::ZigZag),
+            origin: Some(Coordinates::new(4, 2)),
+            ..PaintingPreferences::default()
+        }));
+        updated.set_vector_paths(vec![Polyline::new(vec![
+            Coordinates::new(1, 1),
+            Coordinates::new(6, 3),
+        ])]);
+        updated.record_change(
+            ArtworkChange::vector_paths_updated(&updated.vector_paths.clone()),
+            EventMetadata::new("api".to_string()),
+        );
+        repository.save(&updated).await.unwrap();
+        assert_eq!(repository.count().await.unwrap(), 1);
+        let reloaded = repository.find_by_id(&original.id).await.unwrap().unwrap();
+        assert_eq!(reloaded.painting_preferences, updated.painting_preferences);
+        assert_eq!(reloaded.vector_paths, updated.vector_paths);
+        assert_eq!(reloaded.change_log, updated.change_log);
+        assert!(
+            repository
+                .find_by_tags(&["squid".to_string()])
+                .await
+                .unwrap()
+                .is_empty()
+        );
+
+        repository.delete(&original.id).await.unwrap();
+        assert!(repository.find_by_id(&original.id).await.unwrap().is_none());
+        assert!(matches!(
+            repository.delete(&original.id).await,
+            Err(RepositoryError::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_search_matches_in_memory_filters() {
+        let repository = SqliteArtworkRepository::new(SqliteDatabase::open_in_memory().unwrap());
+        let mut artworks = vec![
+            artwork("Squid Logo", &["logo", "squid"]),
+            artwork("Octo 100%", &["octo"]),
+            artwork("squid kid", &["squid"]),
+        ];
+        artworks[1].artwork_set = Some(ArtworkSetMembership {
+            set_id: "set-1".to_string(),
+            frame: 2,
+            frames: 3,
+        });
+        for (index, artwork) in artworks.iter_mut().enumerate() {
+            artwork.created_at = Timestamp::from_millis(1_000 + index as u64);
+            repository.save(artwork).await.unwrap();
+        }
+
+        let queries = [
+            ArtworkQuery::new(),
+            ArtworkQuery::by_name_contains("SQUID".to_string()),
+            ArtworkQuery::by_name_contains("100%".to_string()),
+            ArtworkQuery::by_tags(vec!["squid".to_string(), "logo".to_string()]),
+            ArtworkQuery::by_set_id("set-1".to_string()),
+            ArtworkQuery::new()
+                .with_sort(SortField::Name, SortOrder::Descending)
+                .with_pagination(2, 1),
+            ArtworkQuery {
+                min_completion: Some(0.0),
+                ..ArtworkQuery::by_tags(vec!["squid".to_string()])
+            },
+        ];
+        for query in queries {
+            let expected: Vec<_> = query
+                .apply(artworks.clone())
+                .artworks
+                .iter()
+                .map(|artwork| artwork.id.clone())
+                .collect();
+            let result = repository.search(&query).await.unwrap();
+            let actual: Vec<_> = result
+                .artworks
+                .iter()
+                .map(|artwork| artwork.id.clone())
+                .collect();
+            assert_eq!(actual, expected, "{query:?}");
+        }
+
+        let members = repository
+            .search(&ArtworkQuery::by_set_id("set-1".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(members.artworks.len(), 1);
+        assert_eq!(members.artworks[0].artwork_set, artworks[1].artwork_set);
+    }
+
+    #[tokio::test]
+    async fn test_trashed_artworks_are_hidden_and_survive_reopening() {
+        let dir = std::env::temp_dir().join(format!("ghost-drawer-db-{}", uuid::Uuid::new_v4()));
+        let repository = SqliteArtworkRepository::new(SqliteDatabase::open(&dir).unwrap());
+        let kept = artwork("Squid", &["squid"]);
+        let trashed = artwork("Octo", &["squid"]);
+        repository.save(&kept).await.unwrap();
+        repository.save(&trashed).await.unwrap();
+        repository
+            .move_to_trash(&trashed.id, Timestamp::from_millis(5_000))
+            .await
+            .unwrap();
+        drop(repository);
+
+        let repository = SqliteArtworkRepository::new(SqliteDatabase::open(&dir).unwrap());
+        assert!(repository.find_by_id(&trashed.id).await.unwrap().is_none());
+        assert!(!repository.exists(&trashed.id).await.unwrap());
+        assert_eq!(repository.count().await.unwrap(), 1);
+        let result = repository
+            .search(&ArtworkQuery::by_tags(vec!["squid".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(result.total_count, 1);
+        assert_eq!(result.artworks[0].id, kept.id);
+        let trash = repository.find_trashed().await.unwrap();
+        assert_eq!(trash.len(), 1);
+        assert_eq!(trash[0].artwork.id, trashed.id);
+        assert_eq!(trash[0].deleted_at, Timestamp::from_millis(5_000));
+        assert!(matches!(
+            repository
+                .move_to_trash(&trashed.id, Timestamp::now())
+                .await,
+            Err(RepositoryError::NotFound { .. })
+        ));
+
+        repository.restore_from_trash(&trashed.id).await.unwrap();
+        assert!(repository.find_by_id(&trashed.id).await.unwrap().is_some());
+        assert!(repository.find_trashed().await.unwrap().is_empty());
+        assert!(matches!(
+            repository.restore_from_trash(&trashed.id).await,
+            Err(RepositoryError::NotFound { .. })
+        ));
+
+        // ゴミ箱のアートワークも完全に削除できる
+        repository
+            .move_to_trash(&trashed.id, Timestamp::now())
+            .await
+            .unwrap();
+        repository.delete(&trashed.id).await.unwrap();
+        assert!(repository.find_trashed().await.unwrap().is_empty());
+
+        drop(repository);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,242 @@
+//! アートワークと描画履歴を保存するSQLiteデータベース
+//!
+//! 接続は1つだけ開き、リポジトリ間で共有する。スキーマは起動時に `PRAGMA user_version`
+//! を見て未適用のマイグレーションを順に適用する。
+
+use crate::domain::artwork::repositories::RepositoryError;
+use crate::infrastructure::data_dir::create_data_dir;
+use rusqlite::Connection;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use thiserror::Error;
+use tracing::info;
+
+/// データディレクトリ内のデータベースファイル名
+const DATABASE_FILE: &str = "ghost-drawer.db";
+
+/// スキーマのマイグレーション（`user_version` が配列の位置より小さいものを適用する）
+const MIGRATIONS: &[&str] = &[
+    // 1: アートワーク・タグ・描画履歴
+    "CREATE TABLE artworks (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        description TEXT,
+        author TEXT,
+        original_filename TEXT,
+        file_size INTEGER NOT NULL,
+        checksum TEXT NOT NULL,
+        original_format TEXT NOT NULL,
+        canvas_width INTEGER NOT NULL,
+        canvas_height INTEGER NOT NULL,
+        total_dots INTEGER NOT NULL,
+        canvas BLOB NOT NULL,
+        created_at INTEGER NOT NULL,
+        updated_at INTEGER NOT NULL,
+        version INTEGER NOT NULL
+    );
+    CREATE INDEX idx_artworks_created_at ON artworks (created_at);
+    CREATE INDEX idx_artworks_checksum ON artworks (checksum);
+    CREATE TABLE artwork_tags (
+        artwork_id TEXT NOT NULL REFERENCES artworks (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (artwork_id, position)
+    );
+    CREATE INDEX idx_artwork_tags_tag ON artwork_tags (tag);
+    CREATE TABLE painting_runs (
+        id TEXT PRIMARY KEY,
+        artwork_id TEXT NOT NULL,
+        started_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX idx_painting_runs_artwork ON painting_runs (artwork_id, started_at);",
+    // 2: アートワークごとの描画設定（JSON）
+    "ALTER TABLE artworks ADD COLUMN painting_preferences TEXT;",
+    // 3: スティック描画の線画（JSON）
+    "ALTER TABLE artworks ADD COLUMN vector_paths TEXT;",
+    // 4: アニメーション画像のフレームから作成したアートワークの組
+    "ALTER TABLE artworks ADD COLUMN set_id TEXT;
+    ALTER TABLE artworks ADD COLUMN set_frame INTEGER;
+    ALTER TABLE artworks ADD COLUMN set_frames INTEGER;
+    CREATE INDEX artworks_set_id ON artworks (set_id);",
+    // 5: アートワークの変更履歴（JSON）
+    "ALTER TABLE artworks ADD COLUMN change_log TEXT;",
+    // 6: ゴミ箱に移した日時（NULLなら通常のアートワーク）
+    "ALTER TABLE artworks ADD COLUMN deleted_at INTEGER;",
+];
+
+#[derive(Debug, Error)]
+pub enum DatabaseError {
+    #[error("Failed to create data directory {path}: {source}")]
+    CreateDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to open database {path}: {source}")]
+    Open {
+        path: PathBuf,
+        source: rusqlite::Error,
+    },
+    #[error("Failed to migrate database schema to version {version}: {source}")]
+    Migrate {
+        version: usize,
+        source: rusqlite::Error,
+    },
+}
+
+/// リポジトリ間で共有するSQLiteの接続
+#[derive(Clone)]
+pub struct SqliteDatabase {
+    connection: Arc<Mutex<Connection>>,
+}
+
+impl SqliteDatabase {
+    /// データディレクトリ内のデータベースファイルのパス
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join(DATABASE_FILE)
+    }
+
+    /// データディレクトリ内でデータベースが使うファイル名（WALのジャーナルを含む）
+    pub fn file_names() -> [String; 3] {
+        [
+            DATABASE_FILE.to_string(),
+            format!("{DATABASE_FILE}-wal"),
+            format!("{DATABASE_FILE}-shm"),
+        ]
+    }
+
+    /// データディレクトリのデータベースを開き、スキーマを最新にする
+    pub fn open(data_dir: &Path) -> Result<Self, DatabaseError> {
+        create_data_dir(data_dir).map_err(|source| DatabaseError::CreateDirectory {
+            path: data_dir.to_path_buf(),
+            source,
+        })?;
+        let path = Self::path(data_dir);
+        let connection = Connection::open(&path)
+            .and_then(|connection| {
+                connection.pragma_update(None, "journal_mode", "WAL")?;
+                Ok(connection)
+            })
+            .map_err(|source| DatabaseError::Open {
+                path: path.clone(),
+                source,
+            })?;
+        let database = Self::from_connection(connection, &path)?;
+        info!("Opened database: {}", path.display());
+        Ok(database)
+    }
+
+    /// メモリ上のデータベースを開く（テスト用）
+    pub fn open_in_memory() -> Result<Self, DatabaseError> {
+        let path = PathBuf::from(":memory:");
+        let connection = Connection::open_in_memory().map_err(|source| DatabaseError::Open {
+            path: path.clone(),
+            source,
+        })?;
+        Self::from_connection(connection, &path)
+    }
+
+    fn from_connection(mut connection: Connection, path: &Path) -> Result<Self, DatabaseError> {
+        connection
+            .pragma_update(None, "foreign_keys", true)
+            .map_err(|source| DatabaseError::Open {
+                path: path.to_path_buf(),
+                source,
+            })?;
+        migrate(&mut connection)?;
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    /// 適用済みのスキーマのバージョン
+    pub fn schema_version(&self) -> usize {
+        self.with_connection(|connection| schema_version(connection))
+            .unwrap_or_default()
+    }
+
+    /// 接続を排他的に使う（描画スレッドなど同期処理から呼ぶ）
+    pub(crate) fn with_connection<R>(
+        &self,
+        operation: impl FnOnce(&mut Connection) -> rusqlite::Result<R>,
+    ) -> rusqlite::Result<R> {
+        let mut connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
+        operation(&mut connection)
+    }
+
+    /// 非同期処理から接続を使う（ファイルI/Oでワーカースレッドを止めないよう別スレッドで実行する）
+    pub(crate) async fn run<R: Send + 'static>(
+        &self,
+        operation: impl FnOnce(&mut Connection) -> Result<R, RepositoryError> + Send + 'static,
+    ) -> Result<R, RepositoryError> {
+        let database = self.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = database
+                .connection
+                .lock()
+                .unwrap_or_else(|e| e.into_inner());
+            operation(&mut connection)
+        })
+        .await
+        .map_err(|e| RepositoryError::Internal {
+            message: format!("Database task failed: {e}"),
+        })?
+    }
+}
+
+/// SQLiteのエラーをリポジトリのエラーに変換
+pub(crate) fn repository_error(error: rusqlite::Error) -> RepositoryError {
+    match error {
+        rusqlite::Error::SqliteFailure(code, _)
+            if code.code == rusqlite::ErrorCode::DatabaseBusy
+                || code.code == rusqlite::ErrorCode::DatabaseLocked =>
+        {
+            RepositoryError::ConcurrentModification
+        }
+        error => RepositoryError::Internal {
+            message: error.to_string(),
+        },
+    }
+}
+
+fn schema_version(connection: &Connection) -> rusqlite::Result<usize> {
+    connection.pragma_query_value(None, "user_version", |row| row.get(0))
+}
+
+fn migrate(connection: &mut Connection) -> Result<(), DatabaseError> {
+    let current = schema_version(connection)
+        .map_err(|source| DatabaseError::Migrate { version: 0, source })?;
+
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(current) {
+        let version = index + 1;
+        let apply = |connection: &mut Connection| {
+            let transaction = connection.transaction()?;
+            transaction.execute_batch(sql)?;
+            transaction.pragma_update(None, "user_version", version)?;
+            transaction.commit()
+        };
+        apply(connection).map_err(|source| DatabaseError::Migrate { version, source })?;
+        info!("Migrated database schema to version {}", version);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_open_migrates_once() {
+        let dir = std::env::temp_dir().join(format!("ghost-drawer-db-{}", uuid::Uuid::new_v4()));
+
+        let database = SqliteDatabase::open(&dir).unwrap();
+        assert_eq!(database.schema_version(), MIGRATIONS.len());
+        drop(database);
+
+        // 既に最新のスキーマなら何も適用しない
+        let reopened = SqliteDatabase::open(&dir).unwrap();
+        assert_eq!(reopened.schema_version(), MIGRATIONS.len());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,159 @@
+use super::sqlite_database::SqliteDatabase;
+use crate::domain::artwork::entities::ArtworkId;
+use crate::domain::painting::entities::PaintingRun;
+use crate::domain::painting::repositories::PaintingRunRepository;
+use rusqlite::{Connection, params};
+use tracing::error;
+
+/// SQLiteに描画実行記録を保存するリポジトリ
+///
+/// 記録はJSONで保存し、アートワークIDと開始日時だけを検索用の列にする。
+pub struct SqlitePaintingRunRepository {
+    database: SqliteDatabase,
+}
+
+impl SqlitePaintingRunRepository {
+    pub fn new(database: SqliteDatabase) -> Self {
+        Self { database }
+    }
+
+    fn newest_first(
+        &self,
+        operation: &str,
+        query: impl FnOnce(&mut Connection) -> rusqlite::Result<Vec<String>>,
+    ) -> Vec<PaintingRun> {
+        // 履歴の読み込みに失敗しても描画は続けられるよう、エラーはログに残して空にする
+        match self.database.with_connection(query) {
+            Ok(rows) => rows
+                .iter()
+                .filter_map(|data| match serde_json::from_str(data) {
+                    Ok(run) => Some(run),
+                    Err(e) => {
+                        error!("Skipping unreadable painting run: {}", e);
+                        None
+                    }
+                })
+                .collect(),
+            Err(e) => {
+                error!("Failed to {}: {}", operation, e);
+                Vec::new()
+            }
+        }
+    }
+}
+
+fn collect_data(
+    connection: &Connection,
+    sql: &str,
+    params: impl rusqlite::Params,
+) -> rusqlite::Result<Vec<String>> {
+    let mut statement = connection.prepare_cached(sql)?;
+    statement.query_map(params, |row| row.get(0))?.collect()
+}
+
+impl PaintingRunRepository for SqlitePaintingRunRepository {
+    fn save(&self, run: &PaintingRun) {
+        let data = match serde_json::to_string(run) {
+            Ok(data) => data,
+            Err(e) => {
+                error!("Failed to serialize painting run {}: {}", run.id, e);
+                return;
+            }
+        };
+        let result = self.database.with_connection(|connection| {
+            connection.execute(
+                "INSERT INTO painting_runs (id, artwork_id, started_at, data) \
+                 VALUES (?1, ?2, ?3, ?4) \
+                 ON CONFLICT (id) DO UPDATE SET data = excluded.data",
+                params![
+                    run.id,
+                    run.artwork_id.as_str(),
+                    run.started_at.epoch_millis as i64,
+                    data
+                ],
+            )
+        });
+        if let Err(e) = result {
+            error!("Failed to save painting run {}: {}", run.id, e);
+        }
+    }
+
+    fn find_by_artwork(&self, artwork_id: &ArtworkId) -> Vec<PaintingRun> {
+        self.newest_first("load painting runs", |connection| {
+            collect_data(
+                connection,
+                "SELECT data FROM painting_runs WHERE artwork_id = ?1 \
+                 ORDER BY started_at DESC, rowid DESC",
+                [artwork_id.as_str()],
+            )
+        })
+    }
+
+    fn recent(&self, limit: usize) -> Vec<PaintingRun> {
+        self.newest_first("load recent painting runs", |connection| {
+            collect_data(
+                connection,
+                "SELECT data FROM painting_runs ORDER BY started_at DESC, rowid DESC LIMIT ?1",
+                [limit as i64],
+            )
+        })
+    }
+
+    fn clear(&self) -> usize {
+        match self
+            .database
+            .with_connection(|connection| connection.execute("DELETE FROM painting_runs", []))
+        {
+            Ok(removed) => removed,
+            Err(e) => {
+                error!("Failed to clear painting runs: {}", e);
+                0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::painting::entities::RunOutcome;
+    use crate::domain::painting::value_objects::{DrawingPath, DrawingStrategy, PaintTiming};
+    use crate::domain::shared::value_objects::Coordinates;
+
+    #[test]
+    fn test_runs_are_updated_and_listed_newest_first() {
+        let repository =
+            SqlitePaintingRunRepository::new(SqliteDatabase::open_in_memory().unwrap());
+        let artwork_id = ArtworkId::generate();
+        let path = DrawingPath::new(vec![Coordinates::new(1, 1)]);
+        let start = |artwork_id: &ArtworkId| {
+            PaintingRun::start(
+                artwork_id.clone(),
+                1,
+                DrawingStrategy::ZigZag,
+                PaintTiming::default(),
+                1,
+                &path,
+            )
+        };
+
+        let mut first = start(&artwork_id);
+        repository.save(&first);
+        let second = start(&artwork_id);
+        repository.save(&second);
+        repository.save(&start(&ArtworkId::generate()));
+
+        first.finish(1, RunOutcome::Completed);
+        repository.save(&first);
+
+        let runs = repository.find_by_artwork(&artwork_id);
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].id, second.id);
+        assert_eq!(runs[1], first);
+        assert_eq!(repository.recent(2).len(), 2);
+        assert_eq!(repository.recent(10).len(), 3);
+
+        assert_eq!(repository.clear(), 3);
+        assert!(repository.recent(10).is_empty());
+    }
+}
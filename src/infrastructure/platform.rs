@@ -0,0 +1,137 @@
+//! OS固有機能の薄いラッパー
+//!
+//! 実機（Linux）ではそのままOSの機能を呼び出し、それ以外の環境では
+//! 開発・シミュレーション用にビルドが通るよう安全な代替動作を行う。
+
+use std::fs::{File, Metadata, OpenOptions};
+use std::io;
+use std::path::Path;
+
+/// 実効ユーザーがrootかどうか
+pub fn is_root() -> bool {
+    #[cfg(unix)]
+    {
+        nix::unistd::Uid::effective().is_root()
+    }
+    #[cfg(not(unix))]
+    {
+        false
+    }
+}
+
+/// シンボリックリンクを作成
+pub fn symlink(original: impl AsRef<Path>, link: impl AsRef<Path>) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(original, link)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (original, link);
+        Err(unsupported("symlink"))
+    }
+}
+
+/// キャラクターデバイスかどうか
+pub fn is_char_device(metadata: &Metadata) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        metadata.file_type().is_char_device()
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        false
+    }
+}
+
+/// デバイスファイルのメジャー・マイナー番号。取得できない環境では `None`
+pub fn device_number(metadata: &Metadata) -> Option<(u32, u32)> {
+    #[cfg(target_os = "linux")]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let rdev = metadata.rdev();
+        Some((libc::major(rdev), libc::minor(rdev)))
+    }
+    #[cfg(not(target_os = "linux"))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// パーミッションのビット（例: 0o664）。取得できない環境では `None`
+pub fn permission_bits(metadata: &Metadata) -> Option<u32> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        Some(metadata.permissions().mode() & 0o777)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// ファイルの所有者のUIDとGID。取得できない環境では `None`
+pub fn owner(metadata: &Metadata) -> Option<(u32, u32)> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        Some((metadata.uid(), metadata.gid()))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// ファイルの所有者を変更
+pub fn chown(path: impl AsRef<Path>, uid: u32, gid: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::chown(path, Some(uid), Some(gid))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, uid, gid);
+        Err(unsupported("chown"))
+    }
+}
+
+/// ノンブロッキングモードで書き込み用に開く
+pub fn open_nonblocking_write(path: impl AsRef<Path>) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_NONBLOCK);
+    }
+    options.open(path)
+}
+
+/// 作成時のパーミッションを指定して書き込み用に開く（既存の内容は破棄）
+pub fn create_with_mode(path: impl AsRef<Path>, mode: u32) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode);
+    }
+    #[cfg(not(unix))]
+    let _ = mode;
+    options.open(path)
+}
+
+#[cfg(not(unix))]
+fn unsupported(operation: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::Unsupported,
+        format!("{operation} is not supported on this platform"),
+    )
+}
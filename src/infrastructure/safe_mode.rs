@@ -0,0 +1,232 @@
+//! セーフモード（`--safe-mode`）でシステムのパスへの書き込みを止めるラッパー
+//!
+//! USBガジェット・ブート設定・systemdのユニット・接続修正・HIDデバイスへの操作を包み、
+//! 状態を読むだけの操作は包んだ実装に任せ、書き込む操作は実行する代わりに内容をログに出して成功を返す。
+//! 成功したふりをすると後続の手順が誤った前提で進む操作（ガジェットの構成・HIDへの書き込みの確認）は
+//! `SetupError::SafeModeBlocked` で失敗させる。データディレクトリの中への書き込みは止めない。
+
+use crate::domain::controller::{ControllerCommand, ControllerEmulator};
+use crate::domain::hardware::errors::HardwareError;
+use crate::domain::hardware::repositories::UsbGadgetManager;
+use crate::domain::hardware::{GadgetState, GadgetVerification};
+use crate::domain::setup::entities::{BoardModel, FixConnectionStep, FixConnectionStepResult};
+use crate::domain::setup::repositories::{
+    BootConfigurator, ConnectionRepairer, SetupError, SystemdServiceManager,
+};
+use crate::infrastructure::hardware::mock_controller::MockController;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// セーフモードで置き換えるHIDデバイス
+const HID_DEVICE_PATH: &str = "/dev/hidg0";
+
+/// 実行しなかった操作をログに出す
+fn skipped(operation: &str) {
+    info!("Safe mode: skipped {}", operation);
+}
+
+/// 実行せずに失敗させる操作のエラー
+fn blocked(operation: &str) -> SetupError {
+    warn!("Safe mode: blocked {}", operation);
+    SetupError::SafeModeBlocked(operation.to_string())
+}
+
+/// 書き込む操作を止め、読む操作だけを `inner` に任せるラッパー
+///
+/// `UsbGadgetManager`・`BootConfigurator`・`SystemdServiceManager`・`ConnectionRepairer` を実装する。
+pub struct SafeMode<T: ?Sized> {
+    inner: Arc<T>,
+}
+
+impl<T: ?Sized> SafeMode<T> {
+    pub fn new(inner: Arc<T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: UsbGadgetManager + ?Sized> UsbGadgetManager for SafeMode<T> {
+    fn configure_as_pro_controller(&self) -> Result<(), SetupError> {
+        // 構成したふりをすると、続く確認が実際のconfigfsを読んで食い違う
+        Err(blocked(
+            "configuring the USB gadget in /sys/kernel/config/usb_gadget",
+        ))
+    }
+
+    fn is_gadget_configured(&self) -> Result<bool, SetupError> {
+        self.inner.is_gadget_configured()
+    }
+
+    fn reconnect_gadget(&self) -> Result<(), SetupError> {
+        skipped("unbinding and rebinding the USB gadget's UDC");
+        Ok(())
+    }
+
+    fn verify_gadget(&self) -> Result<GadgetVerification, SetupError> {
+        self.inner.verify_gadget()
+    }
+
+    fn read_gadget_state(&self) -> Result<GadgetState, SetupError> {
+        self.inner.read_gadget_state()
+    }
+}
+
+impl<T: BootConfigurator + ?Sized> BootConfigurator for SafeMode<T> {
+    fn configure_boot_for_otg(&self, board: &BoardModel) -> Result<(), SetupError> {
+        skipped(&format!(
+            "enabling USB OTG in the boot configuration for {board:?}"
+        ));
+        Ok(())
+    }
+
+    fn is_boot_configured(&self, board: &BoardModel) -> Result<bool, SetupError> {
+        self.inner.is_boot_configured(board)
+    }
+
+    fn remove_boot_configuration(&self, board: &BoardModel) -> Result<(), SetupError> {
+        skipped(&format!(
+            "removing USB OTG from the boot configuration for {board:?}"
+        ));
+        Ok(())
+    }
+}
+
+impl<T: SystemdServiceManager + ?Sized> SystemdServiceManager for SafeMode<T> {
+    fn create_gadget_service(&self) -> Result<(), SetupError> {
+        skipped("writing the gadget systemd unit to /etc/systemd/system");
+        Ok(())
+    }
+
+    fn enable_gadget_service(&self) -> Result<(), SetupError> {
+        skipped("enabling the gadget systemd service");
+        Ok(())
+    }
+
+    fn is_service_enabled(&self) -> Result<bool, SetupError> {
+        self.inner.is_service_enabled()
+    }
+
+    fn create_web_service(&self) -> Result<(), SetupError> {
+        skipped("writing the web systemd unit to /etc/systemd/system");
+        Ok(())
+    }
+
+    fn enable_web_service(&self) -> Result<(), SetupError> {
+        skipped("enabling the web systemd service");
+        Ok(())
+    }
+
+    fn missing_unit_ordering(&self) -> Vec<String> {
+        self.inner.missing_unit_ordering()
+    }
+
+    fn disable_and_remove_services(&self) -> Result<(), SetupError> {
+        skipped("disabling and removing the systemd services");
+        Ok(())
+    }
+
+    fn setup_application_files(&self) -> Result<(), SetupError> {
+        skipped("installing the application files");
+        Ok(())
+    }
+
+    fn cleanup_application_files(&self) -> Result<(), SetupError> {
+        skipped("removing the application files");
+        Ok(())
+    }
+}
+
+impl<T: ConnectionRepairer + ?Sized> ConnectionRepairer for SafeMode<T> {
+    fn run_step(&self, step: FixConnectionStep) -> FixConnectionStepResult {
+        match step {
+            // デバイスノードを待つだけなので実際に確認する
+            FixConnectionStep::WaitForHidDevice => self.inner.run_step(step),
+            // 書き込めたことにすると接続が直ったと誤って伝える
+            FixConnectionStep::TestWrite => FixConnectionStepResult::failed(
+                step,
+                blocked(&format!("writing a test report to {HID_DEVICE_PATH}")).to_string(),
+            )
+            .with_hint("Restart without --safe-mode to test the HID device"),
+            _ => {
+                skipped(step.description());
+                FixConnectionStepResult::succeeded(
+                    step,
+                    format!("Skipped in safe mode: {}", step.description()),
+                )
+            }
+        }
+    }
+}
+
+/// HIDデバイスを開かず、送るはずだったコマンドをログに出すコントローラー
+///
+/// 描画の進み方が実機と変わらないよう、入力の時間は `MockController` で再現する。
+pub struct SafeModeController {
+    stand_in: MockController,
+}
+
+impl SafeModeController {
+    pub fn new() -> Self {
+        Self {
+            stand_in: MockController::new(),
+        }
+    }
+}
+
+impl Default for SafeModeController {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ControllerEmulator for SafeModeController {
+    fn initialize(&self) -> Result<(), HardwareError> {
+        skipped(&format!("opening {HID_DEVICE_PATH}"));
+        self.stand_in.initialize()
+    }
+
+    fn is_connected(&self) -> Result<bool, HardwareError> {
+        self.stand_in.is_connected()
+    }
+
+    fn execute_command(&self, command: &ControllerCommand) -> Result<(), HardwareError> {
+        debug!(
+            "Safe mode: not writing '{}' to {}",
+            command.name, HID_DEVICE_PATH
+        );
+        self.stand_in.execute_command(command)
+    }
+
+    fn execute_command_cancellable(
+        &self,
+        command: &ControllerCommand,
+        cancel: &AtomicBool,
+    ) -> Result<(), HardwareError> {
+        debug!(
+            "Safe mode: not writing '{}' to {}",
+            command.name, HID_DEVICE_PATH
+        );
+        self.stand_in.execute_command_cancellable(command, cancel)
+    }
+
+    fn write_raw_report(&self, report: &[u8]) -> Result<usize, HardwareError> {
+        debug!(
+            "Safe mode: not writing raw report {:02X?} to {}",
+            report, HID_DEVICE_PATH
+        );
+        self.stand_in.write_raw_report(report)
+    }
+
+    fn shutdown(&self) -> Result<(), HardwareError> {
+        self.stand_in.shutdown()
+    }
+
+    fn host_unresponsive_for(&self) -> Option<Duration> {
+        self.stand_in.host_unresponsive_for()
+    }
+
+    fn last_report(&self) -> Option<[u8; 8]> {
+        self.stand_in.last_report()
+    }
+}
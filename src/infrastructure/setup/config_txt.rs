@@ -0,0 +1,395 @@
+//! Raspberry Pi の config.txt をセクション単位で編集する
+//!
+//! 行は元の改行コード（`\n` / `\r\n`）を付けたまま保持し、書き戻すときは
+//! 編集したブロック以外を1バイトも変えない。
+
+use std::fmt;
+
+/// 追加するブロックの目印のコメント
+const GADGET_COMMENT: &str = "# Splatoon3 Ghost Drawer USB Gadget Configuration";
+/// USBガジェットモードを有効にするオーバーレイ
+const GADGET_OVERLAY: &str = "dtoverlay=dwc2";
+/// Pi Zero系のボードに適用される条件付きセクション
+const PI_ZERO_FILTERS: [&str; 4] = ["all", "pi0", "pi0w", "pi02"];
+
+/// セクション（`[all]` などの見出しから次の見出しまで）
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ConfigSection {
+    /// 見出しの行（最初の見出しより前の部分は `None`）
+    header: Option<String>,
+    /// 見出しに続く行（コメント・空行・改行コードを含む）
+    lines: Vec<String>,
+}
+
+impl ConfigSection {
+    /// `[pi02]` なら `pi02`（小文字）
+    fn name(&self) -> Option<String> {
+        self.header
+            .as_deref()
+            .and_then(section_name)
+            .map(|name| name.to_ascii_lowercase())
+    }
+
+    /// すべてのボードに適用されるか（最初の見出しより前の部分と `[all]`）
+    fn is_unconditional(&self) -> bool {
+        self.name().is_none_or(|name| name == "all")
+    }
+
+    /// 末尾の空行を除いた行数（ブロックを追加する位置）
+    fn content_end(&self) -> usize {
+        self.lines
+            .iter()
+            .rposition(|line| !content(line).trim().is_empty())
+            .map_or(0, |index| index + 1)
+    }
+}
+
+/// 後のセクションでUSBをホストモードに戻している設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostModeOverride {
+    /// セクション名（`pi02` など）
+    pub section: String,
+    /// 該当する行（改行コードを除く）
+    pub line: String,
+}
+
+impl fmt::Display for HostModeOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}", self.section, self.line)
+    }
+}
+
+/// セクションに分けた config.txt
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigTxt {
+    sections: Vec<ConfigSection>,
+    /// 追加する行の改行コード（ファイルの最初の行に合わせる）
+    line_ending: &'static str,
+}
+
+impl ConfigTxt {
+    pub fn parse(text: &str) -> Self {
+        let line_ending = match text.find('\n') {
+            Some(index) if text[..index].ends_with('\r') => "\r\n",
+            _ => "\n",
+        };
+        let mut sections = vec![ConfigSection {
+            header: None,
+            lines: Vec::new(),
+        }];
+        for line in text.split_inclusive('\n') {
+            if section_name(line).is_some() {
+                sections.push(ConfigSection {
+                    header: Some(line.to_string()),
+                    lines: Vec::new(),
+                });
+            } else if let Some(section) = sections.last_mut() {
+                section.lines.push(line.to_string());
+            }
+        }
+        Self {
+            sections,
+            line_ending,
+        }
+    }
+
+    /// すべてのボードに適用されるセクションに `dtoverlay=dwc2` があるか
+    pub fn has_gadget_overlay(&self) -> bool {
+        self.sections
+            .iter()
+            .filter(|section| section.is_unconditional())
+            .any(|section| section.lines.iter().any(|line| is_gadget_overlay(line)))
+    }
+
+    /// 最後の `[all]` セクションの末尾に `dtoverlay=dwc2` のブロックを追加し、変更した場合は `true` を返す
+    ///
+    /// 既に設定済みなら何もしない。`[all]` が無い場合はファイルの末尾に作る
+    /// （見出しが1つも無いファイルでは末尾にそのまま追加する）。
+    pub fn ensure_gadget_overlay(&mut self) -> bool {
+        if self.has_gadget_overlay() {
+            return false;
+        }
+        let has_headers = self.sections.len() > 1;
+        let target = match self
+            .sections
+            .iter()
+            .rposition(|section| section.name().as_deref() == Some("all"))
+        {
+            Some(index) => index,
+            None if !has_headers => 0,
+            None => {
+                self.terminate_last_line();
+                if !self.ends_with_blank_line() {
+                    let line_ending = self.line_ending.to_string();
+                    if let Some(section) = self.sections.last_mut() {
+                        section.lines.push(line_ending);
+                    }
+                }
+                self.sections.push(ConfigSection {
+                    header: Some(format!("[all]{}", self.line_ending)),
+                    lines: Vec::new(),
+                });
+                self.sections.len() - 1
+            }
+        };
+
+        let line_ending = self.line_ending;
+        let section = &mut self.sections[target];
+        let index = section.content_end();
+        if index == section.lines.len() {
+            // ファイルの最後の行が改行で終わっていない場合
+            if let Some(last) = section.lines.last_mut().or(section.header.as_mut())
+                && !last.ends_with('\n')
+            {
+                last.push_str(line_ending);
+            }
+        }
+        let mut block = Vec::new();
+        if index > 0 {
+            block.push(line_ending.to_string());
+        }
+        block.push(format!("{GADGET_COMMENT}{line_ending}"));
+        block.push(format!("{GADGET_OVERLAY}{line_ending}"));
+        section.lines.splice(index..index, block);
+        true
+    }
+
+    /// `ensure_gadget_overlay` で追加したブロックと、すべてのボードに適用される `dtoverlay=dwc2` を取り除く
+    pub fn remove_gadget_overlay(&mut self) -> bool {
+        let mut modified = false;
+        for section in self
+            .sections
+            .iter_mut()
+            .filter(|section| section.is_unconditional())
+        {
+            let mut index = 0;
+            while index < section.lines.len() {
+                let line = content(&section.lines[index]).trim();
+                if line == GADGET_COMMENT {
+                    section.lines.remove(index);
+                    // 追加したときに挟んだ空行（直前が空行で、その前が設定やコメントの場合）
+                    let added_blank = index >= 2
+                        && content(&section.lines[index - 1]).trim().is_empty()
+                        && !content(&section.lines[index - 2]).trim().is_empty();
+                    if added_blank {
+                        section.lines.remove(index - 1);
+                        index -= 1;
+                    }
+                    modified = true;
+                } else if is_gadget_overlay(&section.lines[index]) {
+                    section.lines.remove(index);
+                    modified = true;
+                } else {
+                    index += 1;
+                }
+            }
+        }
+        modified
+    }
+
+    /// `dtoverlay=dwc2` より後で、Pi Zero系に適用されるセクションがホストモードに戻している設定
+    ///
+    /// config.txt は後に書かれた設定が優先されるため、これらがあるとガジェットモードにならない。
+    pub fn host_mode_overrides(&self) -> Vec<HostModeOverride> {
+        let mut after_gadget_overlay = false;
+        let mut overrides = Vec::new();
+        for section in &self.sections {
+            let name = section.name().unwrap_or_else(|| "all".to_string());
+            let applies = PI_ZERO_FILTERS.contains(&name.as_str());
+            for line in &section.lines {
+                if section.is_unconditional() && is_gadget_overlay(line) {
+                    after_gadget_overlay = true;
+                } else if after_gadget_overlay && applies && forces_host_mode(line) {
+                    overrides.push(HostModeOverride {
+                        section: name.clone(),
+                        line: content(line).trim().to_string(),
+                    });
+                }
+            }
+        }
+        overrides
+    }
+
+    fn ends_with_blank_line(&self) -> bool {
+        let last_line = self
+            .sections
+            .iter()
+            .rev()
+            .find_map(|section| section.lines.last().or(section.header.as_ref()));
+        last_line.is_none_or(|line| content(line).trim().is_empty())
+    }
+
+    /// 改行で終わっていない最後の行に改行を付ける
+    fn terminate_last_line(&mut self) {
+        let line_ending = self.line_ending;
+        let last_line = self
+            .sections
+            .iter_mut()
+            .rev()
+            .find_map(|section| section.lines.last_mut().or(section.header.as_mut()));
+        if let Some(line) = last_line
+            && !line.ends_with('\n')
+        {
+            line.push_str(line_ending);
+        }
+    }
+}
+
+impl fmt::Display for ConfigTxt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for section in &self.sections {
+            if let Some(header) = &section.header {
+                f.write_str(header)?;
+            }
+            for line in &section.lines {
+                f.write_str(line)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 改行コードを除いた行
+fn content(line: &str) -> &str {
+    line.trim_end_matches(['\n', '\r'])
+}
+
+/// 見出しの行（`[pi02]` など）ならセクション名
+fn section_name(line: &str) -> Option<&str> {
+    content(line)
+        .trim()
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+}
+
+fn is_gadget_overlay(line: &str) -> bool {
+    content(line).trim() == GADGET_OVERLAY
+}
+
+/// USBをホストモードにする設定（`dtoverlay=dwc2,dr_mode=host` と `otg_mode=1`）
+fn forces_host_mode(line: &str) -> bool {
+    let line = content(line).trim();
+    if let Some(overlay) = line.strip_prefix("dtoverlay=") {
+        let mut params = overlay.split(',').map(str::trim);
+        return params.next() == Some("dwc2") && params.any(|param| param == "dr_mode=host");
+    }
+    line.replace(' ', "") == "otg_mode=1"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BOOKWORM: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/config_txt/bookworm.txt"
+    ));
+    const UBUNTU_SERVER: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/config_txt/ubuntu_server.txt"
+    ));
+    const PI02_HOST_MODE: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/config_txt/pi02_host_mode.txt"
+    ));
+
+    /// 追加して書き戻した内容（追加後にもう一度追加しても変わらず、取り除くと元に戻ることも確認する）
+    fn configure(original: &str) -> (String, Vec<HostModeOverride>) {
+        let mut config = ConfigTxt::parse(original);
+        assert_eq!(config.to_string(), original);
+        assert!(!config.has_gadget_overlay());
+        assert!(config.ensure_gadget_overlay());
+        let configured = config.to_string();
+
+        let mut reparsed = ConfigTxt::parse(&configured);
+        assert!(reparsed.has_gadget_overlay());
+        assert!(!reparsed.ensure_gadget_overlay());
+        assert_eq!(reparsed.to_string(), configured);
+
+        assert!(reparsed.remove_gadget_overlay());
+        assert_eq!(reparsed.to_string(), original);
+        (configured, config.host_mode_overrides())
+    }
+
+    #[test]
+    fn test_bookworm_appends_block_to_trailing_all_section() {
+        let (configured, overrides) = configure(BOOKWORM);
+        assert_eq!(
+            configured,
+            format!("{BOOKWORM}{GADGET_COMMENT}\n{GADGET_OVERLAY}\n")
+        );
+        // [cm4] と [cm5] のホストモードは Pi Zero には適用されない
+        assert!(overrides.is_empty());
+    }
+
+    #[test]
+    fn test_ubuntu_server_keeps_blank_line_groups_around_user_settings() {
+        let (configured, overrides) = configure(UBUNTU_SERVER);
+        assert_eq!(
+            configured,
+            UBUNTU_SERVER.replace(
+                "gpu_mem=16\n",
+                &format!("gpu_mem=16\n\n{GADGET_COMMENT}\n{GADGET_OVERLAY}\n")
+            )
+        );
+        assert!(configured.contains("arm_64bit=1\n\n\n# My own settings\n"));
+        assert!(overrides.is_empty());
+    }
+
+    #[test]
+    fn test_later_pi_zero_section_overriding_dwc2_is_reported() {
+        let (configured, overrides) = configure(PI02_HOST_MODE);
+        assert_eq!(
+            configured,
+            PI02_HOST_MODE.replace(
+                "enable_uart=1\n",
+                &format!("enable_uart=1\n\n{GADGET_COMMENT}\n{GADGET_OVERLAY}\n")
+            )
+        );
+        assert_eq!(
+            overrides,
+            [HostModeOverride {
+                section: "pi02".to_string(),
+                line: "dtoverlay=dwc2,dr_mode=host".to_string(),
+            }]
+        );
+        assert_eq!(
+            overrides[0].to_string(),
+            "[pi02] dtoverlay=dwc2,dr_mode=host"
+        );
+    }
+
+    #[test]
+    fn test_crlf_and_missing_trailing_newline_are_preserved() {
+        let mut config = ConfigTxt::parse("dtparam=audio=on\r\narm_64bit=1");
+        assert!(config.ensure_gadget_overlay());
+        assert_eq!(
+            config.to_string(),
+            format!(
+                "dtparam=audio=on\r\narm_64bit=1\r\n\r\n{GADGET_COMMENT}\r\n{GADGET_OVERLAY}\r\n"
+            )
+        );
+        assert!(config.remove_gadget_overlay());
+        assert_eq!(config.to_string(), "dtparam=audio=on\r\narm_64bit=1\r\n");
+    }
+
+    #[test]
+    fn test_all_section_is_created_when_only_conditional_sections_exist() {
+        // 作成した [all] の見出しは取り除いても残る
+        let mut config = ConfigTxt::parse("[pi4]\narm_boost=1\n");
+        assert!(config.ensure_gadget_overlay());
+        assert_eq!(
+            config.to_string(),
+            format!("[pi4]\narm_boost=1\n\n[all]\n{GADGET_COMMENT}\n{GADGET_OVERLAY}\n")
+        );
+
+        // 元の設定より前にあるホストモードや、Pi Zero に適用されないセクションは報告しない
+        let config = ConfigTxt::parse(
+            "[pi02]\notg_mode=1\n[all]\ndtoverlay=dwc2\n[pi4]\notg_mode=1\n[PI0]\notg_mode = 1\n",
+        );
+        assert!(config.has_gadget_overlay());
+        let overrides = config.host_mode_overrides();
+        assert_eq!(overrides.len(), 1);
+        assert_eq!(overrides[0].section, "pi0");
+    }
+}
@@ -0,0 +1,174 @@
+use crate::debug::SizeRotatingFile;
+use crate::domain::setup::entities::GadgetAuditEntry;
+use crate::domain::setup::repositories::{GadgetAuditLog, SetupError};
+use crate::infrastructure::data_dir::create_data_dir;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing::{debug, warn};
+
+/// データディレクトリの中の監査ログのファイル名
+pub const AUDIT_LOG_FILE: &str = "audit.log";
+/// ファイルを `.1`、`.2` にずらすサイズ
+const MAX_BYTES: u64 = 1024 * 1024;
+/// 残す古いファイルの数
+const MAX_FILES: usize = 3;
+
+/// 1行に1件のJSONで追記する監査ログ（一定のサイズでローテーションする）
+///
+/// ファイルは最初に記録するときに開くため、読み取りだけなら書き込み権限は要らない。
+pub struct JsonlGadgetAuditLog {
+    path: PathBuf,
+    file: Mutex<Option<SizeRotatingFile>>,
+}
+
+impl JsonlGadgetAuditLog {
+    /// `data_dir` の中の `audit.log` に記録する
+    pub fn new(data_dir: impl AsRef<Path>) -> Self {
+        Self {
+            path: data_dir.as_ref().join(AUDIT_LOG_FILE),
+            file: Mutex::new(None),
+        }
+    }
+
+    fn append(&self, entry: &GadgetAuditEntry) -> io::Result<()> {
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        if file.is_none() {
+            if let Some(dir) = self.path.parent() {
+                create_data_dir(dir)?;
+            }
+            *file = Some(SizeRotatingFile::open(&self.path, MAX_BYTES, MAX_FILES)?);
+        }
+        let file = file.as_mut().expect("audit log file was just opened");
+        file.write_all(line.as_bytes())?;
+        file.flush()
+    }
+
+    /// 古いファイルから順に並べたログのファイル
+    fn files_oldest_first(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = (1..=MAX_FILES)
+            .rev()
+            .map(|index| {
+                let mut name = self.path.as_os_str().to_owned();
+                name.push(format!(".{index}"));
+                PathBuf::from(name)
+            })
+            .collect();
+        files.push(self.path.clone());
+        files
+    }
+}
+
+impl GadgetAuditLog for JsonlGadgetAuditLog {
+    fn record(&self, entry: GadgetAuditEntry) {
+        if let Err(e) = self.append(&entry) {
+            warn!(
+                "Failed to write the audit log {}: {} ({} by {})",
+                self.path.display(),
+                e,
+                entry.operation,
+                entry.initiator
+            );
+        }
+    }
+
+    fn recent(&self, limit: usize) -> Result<Vec<GadgetAuditEntry>, SetupError> {
+        let mut entries = VecDeque::with_capacity(limit.min(1024));
+        if limit == 0 {
+            return Ok(Vec::new());
+        }
+        for path in self.files_oldest_first() {
+            let content = match fs::read_to_string(&path) {
+                Ok(content) => content,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                // 書き込み中に途切れた行などは読み飛ばす
+                match serde_json::from_str(line) {
+                    Ok(entry) => {
+                        if entries.len() == limit {
+                            entries.pop_front();
+                        }
+                        entries.push_back(entry);
+                    }
+                    Err(e) => debug!(
+                        "Skipping malformed audit log line in {}: {}",
+                        path.display(),
+                        e
+                    ),
+                }
+            }
+        }
+        Ok(entries.into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::setup::entities::{AuditInitiator, GadgetOperation};
+
+    fn entry(index: usize) -> GadgetAuditEntry {
+        GadgetAuditEntry::new(
+            GadgetOperation::Reconnect,
+            AuditInitiator::Web,
+            &Ok::<(), String>(()),
+        )
+        .with_parameter("index", index.to_string())
+    }
+
+    #[test]
+    fn test_recent_returns_latest_entries_across_rotated_files() {
+        let dir = std::env::temp_dir().join(format!("audit-log-{}", uuid::Uuid::new_v4()));
+        let log = JsonlGadgetAuditLog::new(&dir);
+        assert!(log.recent(10).unwrap().is_empty());
+
+        log.record(entry(0));
+        log.record(
+            GadgetAuditEntry::new(
+                GadgetOperation::Configure,
+                AuditInitiator::Systemd,
+                &Err::<(), _>("No UDC found"),
+            )
+            .with_parameter("udc", "3f980000.usb"),
+        );
+        let entries = log.recent(10).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].success);
+        assert_eq!(entries[1].error.as_deref(), Some("No UDC found"));
+        assert_eq!(entries[1].parameters["udc"], "3f980000.usb");
+
+        // ローテーションした古いファイルと、途切れた行を含めて読む
+        fs::rename(dir.join(AUDIT_LOG_FILE), dir.join("audit.log.1")).unwrap();
+        let log = JsonlGadgetAuditLog::new(&dir);
+        log.record(entry(2));
+        fs::OpenOptions::new()
+            .append(true)
+            .open(dir.join(AUDIT_LOG_FILE))
+            .unwrap()
+            .write_all(b"{\"timestamp\":\"2026")
+            .unwrap();
+        let entries = log.recent(2).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].operation, GadgetOperation::Configure);
+        assert_eq!(entries[1].parameters["index"], "2");
+        assert_eq!(log.recent(10).unwrap().len(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_write_failure_does_not_panic() {
+        // ディレクトリを作れない場所でも記録は失敗するだけで続けられる
+        let file = std::env::temp_dir().join(format!("audit-log-{}", uuid::Uuid::new_v4()));
+        fs::write(&file, "").unwrap();
+        let log = JsonlGadgetAuditLog::new(file.join("data"));
+        log.record(entry(0));
+        log.record(entry(1));
+        fs::remove_file(&file).unwrap();
+    }
+}
@@ -1,77 +1,164 @@
+use super::config_txt::ConfigTxt;
 use crate::domain::setup::entities::BoardModel;
 use crate::domain::setup::repositories::{BootConfigurator, SetupError};
 use std::fs;
 use std::io::Write;
-use std::path::Path;
-use tracing::info;
-
-pub struct LinuxBootConfigurator;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
+
+/// Armbian系のブート環境ファイル（Orange Piのビルドは orangepiEnv.txt を使う）
+const ARMBIAN_ENV_FILES: [&str; 2] = ["orangepiEnv.txt", "armbianEnv.txt"];
+/// Raspberry Pi の config.txt（Bookworm以降は firmware/ の下）
+const CONFIG_TXT_FILES: [&str; 2] = ["firmware/config.txt", "config.txt"];
+/// イメージ付属のUSB OTGオーバーレイ（`overlays=` で指定する名前）
+const BUILTIN_OTG_OVERLAY: &str = "usb-otg";
+/// 付属のオーバーレイがない場合に配置するユーザーオーバーレイ（`user_overlays=` で指定する名前）
+const USER_OTG_OVERLAY: &str = "splatoon3-usb-otg";
+/// `overlays/splatoon3-usb-otg.dts` をコンパイルしたオーバーレイ
+const USER_OTG_OVERLAY_DTBO: &[u8] = include_bytes!(concat!(
+    env!("CARGO_MANIFEST_DIR"),
+    "/overlays/splatoon3-usb-otg.dtbo"
+));
+
+pub struct LinuxBootConfigurator {
+    /// Armbian系のブートファイルを置くディレクトリ
+    boot_dir: PathBuf,
+}
 
 impl Default for LinuxBootConfigurator {
     fn default() -> Self {
-        Self
+        Self {
+            boot_dir: PathBuf::from("/boot"),
+        }
     }
 }
 
+/// USB OTGを有効にするオーバーレイの読み込み方法
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum OtgOverlay {
+    /// イメージ付属のオーバーレイを `overlays=` で読み込む
+    Builtin(PathBuf),
+    /// 同梱のオーバーレイを `overlay-user/` に配置して `user_overlays=` で読み込む
+    User(PathBuf),
+}
+
 impl LinuxBootConfigurator {
     pub fn new() -> Self {
-        Self
+        Self::default()
     }
 
-    fn configure_armbian_env(&self, board: &BoardModel) -> Result<(), SetupError> {
-        // Orange Pi Zero 2W uses orangepiEnv.txt, other boards might use armbianEnv.txt
-        let env_files = vec!["/boot/orangepiEnv.txt", "/boot/armbianEnv.txt"];
-        let mut env_file_path = None;
-
-        for file in &env_files {
-            if Path::new(file).exists() {
-                env_file_path = Some(*file);
-                break;
-            }
+    /// Armbian系のブートファイルと config.txt を探すディレクトリを変更する（既定は `/boot`）
+    pub fn with_boot_dir(mut self, boot_dir: impl Into<PathBuf>) -> Self {
+        self.boot_dir = boot_dir.into();
+        self
+    }
+
+    fn armbian_env_file(&self) -> Option<PathBuf> {
+        ARMBIAN_ENV_FILES
+            .iter()
+            .map(|file| self.boot_dir.join(file))
+            .find(|path| path.exists())
+    }
+
+    fn config_txt_file(&self) -> Option<PathBuf> {
+        CONFIG_TXT_FILES
+            .iter()
+            .map(|file| self.boot_dir.join(file))
+            .find(|path| path.exists())
+    }
+
+    fn user_otg_overlay_path(&self) -> PathBuf {
+        self.boot_dir
+            .join("overlay-user")
+            .join(format!("{USER_OTG_OVERLAY}.dtbo"))
+    }
+
+    /// イメージ付属の usb-otg オーバーレイのファイル（存在しなければ `None`）
+    ///
+    /// U-Bootは `overlays=` の各名前を `dtb/allwinner/overlay/<overlay_prefix>-<名前>.dtbo` として読み込む。
+    fn builtin_otg_overlay(&self, lines: &[String], board: &BoardModel) -> Option<PathBuf> {
+        let default_file = board.otg_device_tree_overlay()?.to_string();
+        let overlay_dir = self.boot_dir.join("dtb/allwinner/overlay");
+        env_value(lines, "overlay_prefix")
+            .map(|prefix| format!("{prefix}-{BUILTIN_OTG_OVERLAY}"))
+            .into_iter()
+            .chain([default_file])
+            .map(|name| overlay_dir.join(format!("{name}.dtbo")))
+            .find(|path| path.is_file())
+    }
+
+    /// 付属のオーバーレイがあればそれを使い、なければ同梱のオーバーレイを使う
+    fn select_otg_overlay(&self, lines: &[String], board: &BoardModel) -> OtgOverlay {
+        match self.builtin_otg_overlay(lines, board) {
+            Some(path) => OtgOverlay::Builtin(path),
+            None => OtgOverlay::User(self.user_otg_overlay_path()),
         }
+    }
 
-        let env_file = env_file_path.ok_or_else(|| {
+    fn configure_armbian_env(&self, board: &BoardModel) -> Result<(), SetupError> {
+        let env_file = self.armbian_env_file().ok_or_else(|| {
             SetupError::BootConfigurationFailed(
                 "Neither orangepiEnv.txt nor armbianEnv.txt found".to_string(),
             )
         })?;
 
-        info!("Using boot environment file: {}", env_file);
+        info!("Using boot environment file: {}", env_file.display());
 
         // Read existing configuration
-        let content = fs::read_to_string(env_file)?;
+        let content = fs::read_to_string(&env_file)?;
         let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
 
-        // Check and update overlays
-        // For Orange Pi Zero 2W, we use "usb-otg" instead of the full overlay name
-        let overlay_to_add = if matches!(board, BoardModel::OrangePiZero2W) {
-            "usb-otg"
-        } else if let Some(overlay) = board.otg_device_tree_overlay() {
-            overlay
-        } else {
-            return Ok(()); // No overlay needed
-        };
-
-        let mut found = false;
-        for line in &mut lines {
-            if line.starts_with("overlays=") {
-                let existing_overlays = line.split('=').nth(1).unwrap_or("");
-                if !existing_overlays.contains(overlay_to_add) {
-                    *line = format!("overlays={} {}", existing_overlays.trim(), overlay_to_add)
-                        .trim()
-                        .to_string();
+        match self.select_otg_overlay(&lines, board) {
+            OtgOverlay::Builtin(dtbo) => {
+                info!(
+                    "Found built-in overlay {}; enabling it with overlays={}",
+                    dtbo.display(),
+                    BUILTIN_OTG_OVERLAY
+                );
+                if add_env_list_entry(&mut lines, "overlays", BUILTIN_OTG_OVERLAY) {
                     info!(
-                        "Updated overlays in {} (added {})",
-                        env_file, overlay_to_add
+                        "Added {} to overlays in {}",
+                        BUILTIN_OTG_OVERLAY,
+                        env_file.display()
+                    );
+                }
+                // 以前の実行で配置したユーザーオーバーレイは不要になる
+                if remove_env_list_entry(&mut lines, "user_overlays", USER_OTG_OVERLAY) {
+                    info!(
+                        "Removed {} from user_overlays in {}",
+                        USER_OTG_OVERLAY,
+                        env_file.display()
+                    );
+                }
+                self.remove_user_otg_overlay_file()?;
+            }
+            OtgOverlay::User(dtbo) => {
+                warn!(
+                    "Built-in {} overlay not found under {}; installing bundled overlay to {}",
+                    BUILTIN_OTG_OVERLAY,
+                    self.boot_dir.join("dtb/allwinner/overlay").display(),
+                    dtbo.display()
+                );
+                if let Some(dir) = dtbo.parent() {
+                    fs::create_dir_all(dir)?;
+                }
+                fs::write(&dtbo, USER_OTG_OVERLAY_DTBO)?;
+                // 存在しないオーバーレイの指定は何もしないので外しておく
+                if remove_env_list_entry(&mut lines, "overlays", BUILTIN_OTG_OVERLAY) {
+                    info!(
+                        "Removed missing overlay {} from overlays in {}",
+                        BUILTIN_OTG_OVERLAY,
+                        env_file.display()
+                    );
+                }
+                if add_env_list_entry(&mut lines, "user_overlays", USER_OTG_OVERLAY) {
+                    info!(
+                        "Added {} to user_overlays in {}",
+                        USER_OTG_OVERLAY,
+                        env_file.display()
                     );
                 }
-                found = true;
-                break;
             }
-        }
-        if !found {
-            lines.push(format!("overlays={overlay_to_add}"));
-            info!("Added overlays={} to {}", overlay_to_add, env_file);
         }
 
         // Add USB OTG mode parameter for Orange Pi Zero 2W
@@ -90,20 +177,64 @@ impl LinuxBootConfigurator {
             }
             if !found_dr_mode {
                 lines.push("param_dwc2_dr_mode=otg".to_string());
-                info!("Added param_dwc2_dr_mode=otg to {}", env_file);
+                info!("Added param_dwc2_dr_mode=otg to {}", env_file.display());
             }
         }
 
-        // Write back the configuration
-        let mut file = fs::OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .open(env_file)?;
+        write_env_file(&env_file, &lines)
+    }
+
+    fn is_armbian_env_configured(&self, board: &BoardModel) -> Result<bool, SetupError> {
+        let Some(env_file) = self.armbian_env_file() else {
+            return Ok(false);
+        };
+        let content = fs::read_to_string(env_file)?;
+        let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+
+        let builtin = env_list_contains(&lines, "overlays", BUILTIN_OTG_OVERLAY)
+            && self.builtin_otg_overlay(&lines, board).is_some();
+        let user = env_list_contains(&lines, "user_overlays", USER_OTG_OVERLAY)
+            && self.user_otg_overlay_path().is_file();
+        Ok(builtin || user)
+    }
+
+    fn remove_armbian_env_configuration(&self) -> Result<(), SetupError> {
+        for env_file in ARMBIAN_ENV_FILES
+            .iter()
+            .map(|file| self.boot_dir.join(file))
+        {
+            if !env_file.exists() {
+                continue;
+            }
 
-        for line in &lines {
-            writeln!(file, "{line}")?;
+            let content = fs::read_to_string(&env_file)?;
+            let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+
+            let mut modified = remove_env_list_entry(&mut lines, "overlays", BUILTIN_OTG_OVERLAY);
+            modified |= remove_env_list_entry(&mut lines, "user_overlays", USER_OTG_OVERLAY);
+
+            // Also remove param_dwc2_dr_mode line
+            let before = lines.len();
+            lines.retain(|line| !line.starts_with("param_dwc2_dr_mode="));
+            modified |= lines.len() != before;
+
+            if modified {
+                // Remove empty lines
+                lines.retain(|line| !line.is_empty());
+                write_env_file(&env_file, &lines)?;
+                info!("Removed USB OTG configuration from {}", env_file.display());
+            }
         }
 
+        self.remove_user_otg_overlay_file()
+    }
+
+    fn remove_user_otg_overlay_file(&self) -> Result<(), SetupError> {
+        let dtbo = self.user_otg_overlay_path();
+        if dtbo.exists() {
+            fs::remove_file(&dtbo)?;
+            info!("Removed user overlay {}", dtbo.display());
+        }
         Ok(())
     }
 
@@ -127,99 +258,64 @@ impl LinuxBootConfigurator {
     }
 
     fn configure_config_txt(&self) -> Result<(), SetupError> {
-        // Check both possible locations for config.txt
-        let config_files = vec!["/boot/firmware/config.txt", "/boot/config.txt"];
-        let mut config_path = None;
-
-        for file in &config_files {
-            if Path::new(file).exists() {
-                config_path = Some(*file);
-                break;
-            }
-        }
-
-        let config_file = config_path.ok_or_else(|| {
-            SetupError::BootConfigurationFailed(
-                "config.txt not found in /boot or /boot/firmware".to_string(),
-            )
+        let config_file = self.config_txt_file().ok_or_else(|| {
+            SetupError::BootConfigurationFailed(format!(
+                "config.txt not found in {} or {}",
+                self.boot_dir.display(),
+                self.boot_dir.join("firmware").display()
+            ))
         })?;
 
-        info!("Configuring {} for USB OTG", config_file);
+        info!("Configuring {} for USB OTG", config_file.display());
 
         // Create backup before modification
-        self.create_config_backup(config_file)?;
-
-        // Read and parse existing configuration
-        let content = fs::read_to_string(config_file)?;
-        let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
+        self.create_config_backup(&config_file)?;
+
+        // 編集したブロック以外（コメント・空行・改行コード）はそのまま書き戻す
+        let mut config = ConfigTxt::parse(&fs::read_to_string(&config_file)?);
+        let modified = config.ensure_gadget_overlay();
+        for host_mode in config.host_mode_overrides() {
+            warn!(
+                "{} switches USB back to host mode after dtoverlay=dwc2: {}",
+                config_file.display(),
+                host_mode
+            );
+        }
 
-        // Check if configuration is already correct
-        if self.is_config_already_correct(&lines)? {
+        if !modified {
             info!("Configuration is already correct, no changes needed");
             return Ok(());
         }
 
-        // Remove any conflicting configurations first
-        self.remove_conflicting_config(&mut lines)?;
-
-        // Add our configuration in the [all] section
-        self.add_gadget_config(&mut lines)?;
-
-        // Write back the modified configuration
-        fs::write(config_file, lines.join("\n"))?;
-        info!("Updated {} with USB gadget configuration", config_file);
+        fs::write(&config_file, config.to_string())?;
+        info!(
+            "Added USB gadget configuration to [all] section of {}",
+            config_file.display()
+        );
 
         Ok(())
     }
 
-    fn create_config_backup(&self, config_file: &str) -> Result<(), SetupError> {
-        let backup_file = format!("{config_file}.splatoon3-backup");
+    fn create_config_backup(&self, config_file: &Path) -> Result<(), SetupError> {
+        let backup_file = config_backup_path(config_file);
 
         // Only create backup if it doesn't exist
-        if !Path::new(&backup_file).exists() {
+        if !backup_file.exists() {
             fs::copy(config_file, &backup_file)?;
-            info!("Created backup at {}", backup_file);
+            info!("Created backup at {}", backup_file.display());
         }
 
         Ok(())
     }
 
-    fn is_config_already_correct(&self, lines: &[String]) -> Result<bool, SetupError> {
-        let mut in_all_section = false;
-        let mut found_dwc2 = false;
-        let mut found_our_comment = false;
-
-        for line in lines {
-            let trimmed = line.trim();
-
-            if trimmed == "[all]" {
-                in_all_section = true;
-            } else if trimmed.starts_with('[') && trimmed.ends_with(']') {
-                in_all_section = false;
-            } else if in_all_section {
-                if trimmed == "dtoverlay=dwc2" {
-                    found_dwc2 = true;
-                } else if trimmed == "# Splatoon3 Ghost Drawer USB Gadget Configuration" {
-                    found_our_comment = true;
-                }
-            }
-        }
-
-        Ok(found_dwc2 && found_our_comment)
-    }
-
     fn restore_config_backup(&self) -> Result<(), SetupError> {
-        let config_files = vec!["/boot/firmware/config.txt", "/boot/config.txt"];
-
-        for config_file in config_files {
-            if Path::new(config_file).exists() {
-                let backup_file = format!("{config_file}.splatoon3-backup");
+        if let Some(config_file) = self.config_txt_file() {
+            let backup_file = config_backup_path(&config_file);
 
-                if Path::new(&backup_file).exists() {
-                    fs::copy(&backup_file, config_file)?;
-                    info!("Restored {} from backup", config_file);
-                    return Ok(());
-                }
+            if backup_file.exists() {
+                fs::copy(&backup_file, &config_file)?;
+                info!("Restored {} from backup", config_file.display());
+                return Ok(());
             }
         }
 
@@ -228,133 +324,6 @@ impl LinuxBootConfigurator {
         ))
     }
 
-    fn remove_conflicting_config(&self, lines: &mut Vec<String>) -> Result<(), SetupError> {
-        let mut i = 0;
-        let mut in_cm4_section = false;
-        let mut in_cm5_section = false;
-
-        while i < lines.len() {
-            let line = lines[i].trim().to_string();
-
-            // Track which section we're in
-            if line == "[cm4]" {
-                in_cm4_section = true;
-                in_cm5_section = false;
-            } else if line == "[cm5]" {
-                in_cm4_section = false;
-                in_cm5_section = true;
-            } else if line == "[all]" || (line.starts_with('[') && line.ends_with(']')) {
-                in_cm4_section = false;
-                in_cm5_section = false;
-            }
-
-            // Remove conflicting dwc2 configurations
-            if line.contains("dtoverlay=dwc2") {
-                if in_cm5_section && line.contains("dr_mode=host") {
-                    // Keep CM5 host mode but add a comment
-                    let commented = format!("# {}", lines[i]);
-                    info!("Commented out conflicting CM5 host mode: {}", line);
-                    lines[i] = commented;
-                } else if !in_cm4_section && !in_cm5_section {
-                    // Remove any standalone dwc2 overlays outside sections
-                    info!("Removed conflicting dtoverlay: {}", line);
-                    lines.remove(i);
-                    continue; // Don't increment i since we removed a line
-                }
-            }
-
-            // Remove duplicate comments and empty lines from previous runs
-            if line.contains("Enable USB OTG mode")
-                || line.contains("Enable USB gadget mode")
-                || line.contains("Splatoon3 Ghost Drawer USB Gadget Configuration")
-            {
-                lines.remove(i);
-                continue;
-            }
-
-            i += 1;
-        }
-
-        Ok(())
-    }
-
-    fn add_gadget_config(&self, lines: &mut Vec<String>) -> Result<(), SetupError> {
-        // Find the [all] section or add it
-        let mut all_section_index = None;
-
-        for (i, line) in lines.iter().enumerate() {
-            if line.trim() == "[all]" {
-                all_section_index = Some(i);
-                break;
-            }
-        }
-
-        let insert_index = if let Some(index) = all_section_index {
-            // Find the end of the [all] section
-            let mut end_index = lines.len();
-            for (i, line) in lines.iter().enumerate().skip(index + 1) {
-                if line.trim().starts_with('[') && line.trim().ends_with(']') {
-                    end_index = i;
-                    break;
-                }
-            }
-            end_index
-        } else {
-            // Add [all] section at the end
-            lines.push("".to_string());
-            lines.push("[all]".to_string());
-            lines.len()
-        };
-
-        // Check if our configuration already exists in the [all] section
-        let mut has_gadget_config = false;
-        if let Some(all_idx) = all_section_index {
-            for i in (all_idx + 1)..insert_index {
-                if i < lines.len() && lines[i].trim() == "dtoverlay=dwc2" {
-                    has_gadget_config = true;
-                    break;
-                }
-            }
-        }
-
-        if !has_gadget_config {
-            // Remove any trailing empty lines before adding our config
-            while insert_index > 0
-                && lines
-                    .get(insert_index - 1)
-                    .is_some_and(|l| l.trim().is_empty())
-            {
-                lines.remove(insert_index - 1);
-            }
-
-            let final_insert_index = if let Some(all_idx) = all_section_index {
-                // Find the actual end of [all] section after cleanup
-                let mut end_idx = lines.len();
-                for (i, line) in lines.iter().enumerate().skip(all_idx + 1) {
-                    if line.trim().starts_with('[') && line.trim().ends_with(']') {
-                        end_idx = i;
-                        break;
-                    }
-                }
-                end_idx
-            } else {
-                lines.len()
-            };
-
-            lines.insert(final_insert_index, "".to_string());
-            lines.insert(
-                final_insert_index + 1,
-                "# Splatoon3 Ghost Drawer USB Gadget Configuration".to_string(),
-            );
-            lines.insert(final_insert_index + 2, "dtoverlay=dwc2".to_string());
-            info!("Added USB gadget configuration to [all] section");
-        } else {
-            info!("USB gadget configuration already exists in [all] section");
-        }
-
-        Ok(())
-    }
-
     fn configure_kernel_modules(&self) -> Result<(), SetupError> {
         info!("Configuring kernel modules for USB gadget");
 
@@ -468,36 +437,11 @@ impl LinuxBootConfigurator {
     }
 
     fn check_config_txt_configuration(&self) -> Result<bool, SetupError> {
-        let config_files = vec!["/boot/firmware/config.txt", "/boot/config.txt"];
-
-        for config_file in config_files {
-            if !Path::new(config_file).exists() {
-                continue;
-            }
-
-            let content = fs::read_to_string(config_file)?;
-            let lines: Vec<&str> = content.lines().collect();
-
-            let mut in_all_section = false;
-            let mut found_gadget_config = false;
-
-            for line in lines {
-                let trimmed = line.trim();
-
-                if trimmed == "[all]" {
-                    in_all_section = true;
-                } else if trimmed.starts_with('[') && trimmed.ends_with(']') {
-                    in_all_section = false;
-                } else if in_all_section && trimmed == "dtoverlay=dwc2" {
-                    found_gadget_config = true;
-                    break;
-                }
-            }
-
-            return Ok(found_gadget_config);
-        }
-
-        Ok(false)
+        let Some(config_file) = self.config_txt_file() else {
+            return Ok(false);
+        };
+        let config = ConfigTxt::parse(&fs::read_to_string(config_file)?);
+        Ok(config.has_gadget_overlay())
     }
 
     fn check_modules_configuration(&self) -> Result<bool, SetupError> {
@@ -551,20 +495,7 @@ impl BootConfigurator for LinuxBootConfigurator {
 
     fn is_boot_configured(&self, board: &BoardModel) -> Result<bool, SetupError> {
         match board {
-            BoardModel::OrangePiZero2W => {
-                // Check both possible env files
-                let env_files = vec!["/boot/orangepiEnv.txt", "/boot/armbianEnv.txt"];
-
-                for env_file in env_files {
-                    if Path::new(env_file).exists() {
-                        let content = fs::read_to_string(env_file)?;
-                        // Check for "usb-otg" which is what we actually add
-                        return Ok(content.contains("usb-otg"));
-                    }
-                }
-
-                Ok(false)
-            }
+            BoardModel::OrangePiZero2W => self.is_armbian_env_configured(board),
             BoardModel::RaspberryPiZero | BoardModel::RaspberryPiZero2W => {
                 // Check comprehensive configuration
                 self.check_raspberry_pi_configuration()
@@ -576,102 +507,22 @@ impl BootConfigurator for LinuxBootConfigurator {
     fn remove_boot_configuration(&self, board: &BoardModel) -> Result<(), SetupError> {
         info!("Removing boot configuration for board: {:?}", board);
 
-        // Try to restore from backup first
-        if let Ok(()) = self.restore_config_backup() {
-            info!("Successfully restored configuration from backup");
-        } else {
-            info!("No backup found, manually removing configuration");
-        }
-
         match board {
-            BoardModel::OrangePiZero2W => {
-                let env_files = vec!["/boot/orangepiEnv.txt", "/boot/armbianEnv.txt"];
-
-                for env_file in env_files {
-                    if !Path::new(env_file).exists() {
-                        continue;
-                    }
-
-                    let content = fs::read_to_string(env_file)?;
-                    let mut lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-                    let mut modified = false;
-
-                    // Remove "usb-otg" overlay
-                    for line in &mut lines {
-                        if line.starts_with("overlays=") && line.contains("usb-otg") {
-                            // Remove the overlay from the line
-                            let overlays: Vec<&str> = line[9..]
-                                .split(' ')
-                                .filter(|s| !s.contains("usb-otg"))
-                                .collect();
-
-                            if overlays.is_empty() {
-                                *line = String::new();
-                            } else {
-                                *line = format!("overlays={}", overlays.join(" "));
-                            }
-                            modified = true;
-                            break;
-                        }
-                    }
-
-                    // Also remove param_dwc2_dr_mode line
-                    lines.retain(|line| !line.starts_with("param_dwc2_dr_mode="));
-                    if lines.len() != content.lines().count() {
-                        modified = true;
-                    }
-
-                    if modified {
-                        // Remove empty lines
-                        lines.retain(|line| !line.is_empty());
-
-                        let mut file = fs::OpenOptions::new()
-                            .write(true)
-                            .truncate(true)
-                            .open(env_file)?;
-
-                        for line in &lines {
-                            writeln!(file, "{line}")?;
-                        }
-                        info!("Removed USB OTG configuration from {}", env_file);
-                    }
-                }
-
-                Ok(())
-            }
+            BoardModel::OrangePiZero2W => self.remove_armbian_env_configuration(),
             BoardModel::RaspberryPiZero | BoardModel::RaspberryPiZero2W => {
-                // Check both possible locations for config.txt
-                let config_files = vec!["/boot/firmware/config.txt", "/boot/config.txt"];
-
-                for config_file in config_files {
-                    if !Path::new(config_file).exists() {
-                        continue;
-                    }
+                // Try to restore from backup first
+                if let Ok(()) = self.restore_config_backup() {
+                    info!("Successfully restored configuration from backup");
+                } else {
+                    info!("No backup found, manually removing configuration");
+                }
 
-                    let content = fs::read_to_string(config_file)?;
-                    let lines: Vec<String> = content.lines().map(|s| s.to_string()).collect();
-                    let mut new_lines = Vec::new();
-                    let mut skip_next = false;
-
-                    for line in lines {
-                        if skip_next && line.trim().is_empty() {
-                            skip_next = false;
-                            continue;
-                        }
-                        skip_next = false;
-
-                        if line.trim() == "dtoverlay=dwc2" {
-                            skip_next = true;
-                            continue;
-                        }
-                        if line.contains("Enable USB OTG mode for gadget") {
-                            continue;
-                        }
-                        new_lines.push(line);
+                if let Some(config_file) = self.config_txt_file() {
+                    let mut config = ConfigTxt::parse(&fs::read_to_string(&config_file)?);
+                    if config.remove_gadget_overlay() {
+                        fs::write(&config_file, config.to_string())?;
+                        info!("Removed dtoverlay=dwc2 from {}", config_file.display());
                     }
-
-                    fs::write(config_file, new_lines.join("\n"))?;
-                    info!("Removed dtoverlay=dwc2 from {}", config_file);
                 }
 
                 // Remove dwc2 from /etc/modules
@@ -705,3 +556,178 @@ impl BootConfigurator for LinuxBootConfigurator {
         }
     }
 }
+
+/// 最初に編集する前の config.txt の保存先
+fn config_backup_path(config_file: &Path) -> PathBuf {
+    let mut backup = config_file.as_os_str().to_owned();
+    backup.push(".splatoon3-backup");
+    PathBuf::from(backup)
+}
+
+/// `key=value` 形式の行から値を取り出す
+fn env_value<'a>(lines: &'a [String], key: &str) -> Option<&'a str> {
+    lines.iter().find_map(|line| {
+        line.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix('='))
+            .map(str::trim)
+    })
+}
+
+/// 空白区切りのリスト（`overlays=` など）に `entry` が含まれるか
+fn env_list_contains(lines: &[String], key: &str, entry: &str) -> bool {
+    env_value(lines, key).is_some_and(|value| value.split_whitespace().any(|e| e == entry))
+}
+
+/// 空白区切りのリストに `entry` を追加し、変更した場合は `true` を返す
+fn add_env_list_entry(lines: &mut Vec<String>, key: &str, entry: &str) -> bool {
+    if env_list_contains(lines, key, entry) {
+        return false;
+    }
+    let prefix = format!("{key}=");
+    match lines.iter_mut().find(|line| line.starts_with(&prefix)) {
+        Some(line) => {
+            let value = line[prefix.len()..].trim();
+            *line = if value.is_empty() {
+                format!("{prefix}{entry}")
+            } else {
+                format!("{prefix}{value} {entry}")
+            };
+        }
+        None => lines.push(format!("{key}={entry}")),
+    }
+    true
+}
+
+/// 空白区切りのリストから `entry` を取り除き（空になれば行ごと削除）、変更した場合は `true` を返す
+fn remove_env_list_entry(lines: &mut Vec<String>, key: &str, entry: &str) -> bool {
+    if !env_list_contains(lines, key, entry) {
+        return false;
+    }
+    let prefix = format!("{key}=");
+    lines.retain_mut(|line| {
+        let Some(value) = line.strip_prefix(&prefix) else {
+            return true;
+        };
+        let remaining: Vec<&str> = value.split_whitespace().filter(|e| *e != entry).collect();
+        if remaining.is_empty() {
+            return false;
+        }
+        *line = format!("{prefix}{}", remaining.join(" "));
+        true
+    });
+    true
+}
+
+fn write_env_file(env_file: &Path, lines: &[String]) -> Result<(), SetupError> {
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(env_file)?;
+    for line in lines {
+        writeln!(file, "{line}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_boot_dir(env: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("ghost-drawer-boot-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("orangepiEnv.txt"), env).unwrap();
+        dir
+    }
+
+    fn read_env(dir: &Path) -> String {
+        fs::read_to_string(dir.join("orangepiEnv.txt")).unwrap()
+    }
+
+    #[test]
+    fn test_builtin_overlay_is_used_when_dtbo_exists() {
+        let dir = temp_boot_dir("verbosity=1\noverlay_prefix=sun50i-h616\noverlays=spi0\n");
+        let overlay_dir = dir.join("dtb/allwinner/overlay");
+        fs::create_dir_all(&overlay_dir).unwrap();
+        fs::write(overlay_dir.join("sun50i-h616-usb-otg.dtbo"), b"").unwrap();
+        let configurator = LinuxBootConfigurator::new().with_boot_dir(&dir);
+        let board = BoardModel::OrangePiZero2W;
+
+        assert!(!configurator.is_boot_configured(&board).unwrap());
+        configurator.configure_boot_for_otg(&board).unwrap();
+        assert_eq!(
+            read_env(&dir),
+            "verbosity=1\noverlay_prefix=sun50i-h616\noverlays=spi0 usb-otg\nparam_dwc2_dr_mode=otg\n"
+        );
+        assert!(!dir.join("overlay-user").exists());
+        assert!(configurator.is_boot_configured(&board).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_bundled_user_overlay_is_installed_when_builtin_is_missing() {
+        // usb-otg が指定されていても、dtboがなければ設定済みとみなさない
+        let dir = temp_boot_dir("overlay_prefix=sun50i-h616\noverlays=usb-otg\n");
+        let configurator = LinuxBootConfigurator::new().with_boot_dir(&dir);
+        let board = BoardModel::OrangePiZero2W;
+        assert!(!configurator.is_boot_configured(&board).unwrap());
+
+        configurator.configure_boot_for_otg(&board).unwrap();
+        assert_eq!(
+            read_env(&dir),
+            "overlay_prefix=sun50i-h616\nuser_overlays=splatoon3-usb-otg\nparam_dwc2_dr_mode=otg\n"
+        );
+        let dtbo = fs::read(dir.join("overlay-user/splatoon3-usb-otg.dtbo")).unwrap();
+        assert_eq!(dtbo, USER_OTG_OVERLAY_DTBO);
+        assert_eq!(dtbo[..4], [0xd0, 0x0d, 0xfe, 0xed]);
+        assert!(configurator.is_boot_configured(&board).unwrap());
+
+        configurator.remove_boot_configuration(&board).unwrap();
+        assert_eq!(read_env(&dir), "overlay_prefix=sun50i-h616\n");
+        assert!(!dir.join("overlay-user/splatoon3-usb-otg.dtbo").exists());
+        assert!(!configurator.is_boot_configured(&board).unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_env_list_entries_match_whole_names() {
+        let mut lines = vec!["user_overlays=splatoon3-usb-otg".to_string()];
+        assert!(!env_list_contains(&lines, "overlays", "usb-otg"));
+        assert!(!env_list_contains(&lines, "user_overlays", "usb-otg"));
+
+        assert!(add_env_list_entry(&mut lines, "overlays", "usb-otg"));
+        assert!(!add_env_list_entry(&mut lines, "overlays", "usb-otg"));
+        assert!(remove_env_list_entry(
+            &mut lines,
+            "user_overlays",
+            "splatoon3-usb-otg"
+        ));
+        assert_eq!(lines, ["overlays=usb-otg"]);
+    }
+
+    #[test]
+    fn test_config_txt_is_edited_in_place_with_backup() {
+        let dir = std::env::temp_dir().join(format!("ghost-drawer-boot-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(dir.join("firmware")).unwrap();
+        let config_file = dir.join("firmware/config.txt");
+        let original = "dtparam=audio=on\n\n[cm5]\ndtoverlay=dwc2,dr_mode=host\n\n[all]\n";
+        fs::write(&config_file, original).unwrap();
+        let configurator = LinuxBootConfigurator::new().with_boot_dir(&dir);
+
+        assert!(!configurator.check_config_txt_configuration().unwrap());
+        configurator.configure_config_txt().unwrap();
+        let configured = fs::read_to_string(&config_file).unwrap();
+        assert!(configured.starts_with(original));
+        assert!(configurator.check_config_txt_configuration().unwrap());
+        assert_eq!(
+            fs::read_to_string(dir.join("firmware/config.txt.splatoon3-backup")).unwrap(),
+            original
+        );
+
+        configurator.configure_config_txt().unwrap();
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), configured);
+        configurator.restore_config_backup().unwrap();
+        assert_eq!(fs::read_to_string(&config_file).unwrap(), original);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
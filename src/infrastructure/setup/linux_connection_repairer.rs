@@ -0,0 +1,230 @@
+use crate::domain::hardware::errors::HardwareError;
+use crate::domain::hardware::repositories::UsbGadgetManager;
+use crate::domain::setup::entities::{FixConnectionStep, FixConnectionStepResult};
+use crate::domain::setup::repositories::ConnectionRepairer;
+use crate::infrastructure::hardware::orange_pi_udc::OrangePiUdcPreparer;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+const UDC_PATH: &str = "/sys/kernel/config/usb_gadget/nintendo_controller/UDC";
+const HID_DEVICE_PATH: &str = "/dev/hidg0";
+const HID_DEVICE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 接続修正の各手順をLinuxのsysfs/configfsに対して実行する（主にOrange Pi Zero 2W向け）
+pub struct LinuxConnectionRepairer {
+    gadget_manager: Arc<dyn UsbGadgetManager>,
+}
+
+impl LinuxConnectionRepairer {
+    pub fn new(gadget_manager: Arc<dyn UsbGadgetManager>) -> Self {
+        Self { gadget_manager }
+    }
+
+    fn unbind_udc(&self) -> FixConnectionStepResult {
+        let step = FixConnectionStep::UnbindUdc;
+
+        match fs::read_to_string(UDC_PATH) {
+            Err(_) => FixConnectionStepResult::succeeded(step, "USB Gadget not configured yet"),
+            Ok(udc) if udc.trim().is_empty() => {
+                FixConnectionStepResult::succeeded(step, "UDC already unbound")
+            }
+            Ok(udc) => match fs::write(UDC_PATH, "") {
+                Ok(()) => {
+                    thread::sleep(Duration::from_millis(500));
+                    FixConnectionStepResult::succeeded(step, format!("UDC {} unbound", udc.trim()))
+                }
+                Err(e) => {
+                    FixConnectionStepResult::failed(step, format!("Failed to unbind UDC: {e}"))
+                        .with_hint("Run this command with root privileges")
+                }
+            },
+        }
+    }
+
+    fn reload_modules(&self) -> FixConnectionStepResult {
+        let step = FixConnectionStep::ReloadModules;
+
+        // (モジュール名, 必須かどうか)
+        let modules = [
+            ("sunxi", false),
+            ("musb_hdrc", false),
+            ("usb_f_hid", true),
+            ("libcomposite", true),
+        ];
+
+        let mut loaded = Vec::new();
+        let mut missing_required = Vec::new();
+        for (module, required) in modules {
+            let output = match Command::new("modprobe").arg(module).output() {
+                Ok(output) => output,
+                Err(e) => {
+                    return FixConnectionStepResult::failed(
+                        step,
+                        format!("Failed to run modprobe: {e}"),
+                    );
+                }
+            };
+
+            // モジュールが既にロードされている場合も成功扱い
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            if output.status.success() || stderr.is_empty() {
+                loaded.push(module);
+            } else {
+                debug!("modprobe {} failed: {}", module, stderr.trim());
+                if required {
+                    missing_required.push(module);
+                }
+            }
+        }
+
+        let mut result = if missing_required.is_empty() {
+            FixConnectionStepResult::succeeded(step, format!("Loaded: {}", loaded.join(", ")))
+        } else {
+            FixConnectionStepResult::failed(
+                step,
+                format!(
+                    "Required modules unavailable: {}",
+                    missing_required.join(", ")
+                ),
+            )
+        };
+        result.hints = check_and_fix_otg_mode();
+        result
+    }
+
+    fn rebuild_gadget(&self) -> FixConnectionStepResult {
+        let step = FixConnectionStep::RebuildGadget;
+
+        match self.gadget_manager.configure_as_pro_controller() {
+            Ok(()) => FixConnectionStepResult::succeeded(step, "USB Gadget configured"),
+            Err(e) => {
+                FixConnectionStepResult::failed(step, format!("Failed to configure gadget: {e}"))
+                    .with_hint("Run: sudo splatoon3-ghost-drawer diagnose")
+                    .with_hint("Check dmesg: sudo dmesg | grep -E '(musb|gadget|hid)'")
+            }
+        }
+    }
+
+    fn rebind_udc(&self) -> FixConnectionStepResult {
+        let step = FixConnectionStep::RebindUdc;
+
+        if !matches!(self.gadget_manager.is_gadget_configured(), Ok(true))
+            && let Err(e) = self.gadget_manager.reconnect_gadget()
+        {
+            return FixConnectionStepResult::failed(step, format!("Failed to bind UDC: {e}"))
+                .with_hint("Run: sudo splatoon3-ghost-drawer diagnose")
+                .with_hint("Try rebooting your device");
+        }
+
+        match fs::read_to_string(UDC_PATH) {
+            Ok(udc) if !udc.trim().is_empty() => {
+                FixConnectionStepResult::succeeded(step, format!("UDC bound to: {}", udc.trim()))
+            }
+            _ => FixConnectionStepResult::failed(step, "UDC not bound")
+                .with_hint("Run: sudo splatoon3-ghost-drawer diagnose")
+                .with_hint("Try rebooting your device"),
+        }
+    }
+
+    fn wait_for_hid_device(&self) -> FixConnectionStepResult {
+        let step = FixConnectionStep::WaitForHidDevice;
+
+        let started = Instant::now();
+        while started.elapsed() < HID_DEVICE_TIMEOUT {
+            if Path::new(HID_DEVICE_PATH).exists() {
+                return FixConnectionStepResult::succeeded(
+                    step,
+                    format!("HID device {HID_DEVICE_PATH} exists"),
+                );
+            }
+            thread::sleep(Duration::from_millis(100));
+        }
+
+        FixConnectionStepResult::failed(step, "HID device not found")
+            .with_hint("Check dmesg: sudo dmesg | grep -E '(musb|gadget|hid)'")
+            .with_hint("Try rebooting your device")
+    }
+
+    fn test_write(&self) -> FixConnectionStepResult {
+        let step = FixConnectionStep::TestWrite;
+
+        let mut file = match fs::OpenOptions::new().write(true).open(HID_DEVICE_PATH) {
+            Ok(file) => file,
+            Err(e) => {
+                let error = HardwareError::classify_io(e, "Cannot open HID device");
+                return FixConnectionStepResult::failed(step, error.to_string())
+                    .with_hint("Run: sudo splatoon3-ghost-drawer fix-permissions");
+            }
+        };
+
+        match file.write_all(&[0u8; 64]) {
+            Ok(()) => FixConnectionStepResult::succeeded(step, "HID device is writable"),
+            Err(e) => match HardwareError::classify_io(e, "HID device write test failed") {
+                error if error.is_host_unresponsive() => {
+                    FixConnectionStepResult::failed(step, error.to_string())
+                        .with_hint("Ensure Nintendo Switch is on the Home screen")
+                        .with_hint("Connect your device to Nintendo Switch via USB-C")
+                }
+                error @ HardwareError::PermissionDenied { .. } => {
+                    FixConnectionStepResult::failed(step, error.to_string())
+                        .with_hint("Run: sudo splatoon3-ghost-drawer fix-permissions")
+                }
+                error => FixConnectionStepResult::failed(step, error.to_string())
+                    .with_hint("Run: sudo splatoon3-ghost-drawer diagnose")
+                    .with_hint("Try rebooting your device"),
+            },
+        }
+    }
+}
+
+impl ConnectionRepairer for LinuxConnectionRepairer {
+    fn run_step(&self, step: FixConnectionStep) -> FixConnectionStepResult {
+        match step {
+            FixConnectionStep::UnbindUdc => self.unbind_udc(),
+            FixConnectionStep::ReloadModules => self.reload_modules(),
+            FixConnectionStep::RebuildGadget => self.rebuild_gadget(),
+            FixConnectionStep::RebindUdc => self.rebind_udc(),
+            FixConnectionStep::WaitForHidDevice => self.wait_for_hid_device(),
+            FixConnectionStep::TestWrite => self.test_write(),
+        }
+    }
+}
+
+/// USB OTGをペリフェラルモードにし、解決できない問題の対処方法を返す
+fn check_and_fix_otg_mode() -> Vec<String> {
+    let mut hints = Vec::new();
+
+    // デバイス名がカーネルによって変わるため、UDCの準備と同じ方法で `mode` を探す
+    let preparer = OrangePiUdcPreparer::new();
+    if preparer.mode_files().is_empty() {
+        hints.push(
+            "No USB OTG mode file found: USB OTG may not be enabled in Device Tree, \
+             the musb driver may not be loaded, or a different USB controller is used"
+                .to_string(),
+        );
+    } else {
+        match preparer.force_peripheral() {
+            Ok(switched) if !switched.is_empty() => thread::sleep(Duration::from_millis(500)),
+            Ok(_) => {}
+            Err(e) => {
+                warn!("{}", e);
+                hints.push("You may need to enable USB OTG in Device Tree".to_string());
+            }
+        }
+    }
+
+    let env_file = "/boot/orangepiEnv.txt";
+    if let Ok(content) = fs::read_to_string(env_file)
+        && !content.contains("usb-otg")
+    {
+        hints.push(format!("Add 'overlays=usb-otg' to {env_file}"));
+    }
+
+    hints
+}
@@ -6,9 +6,108 @@ use std::process::Command;
 use tracing::{debug, info};
 
 const GADGET_SERVICE_NAME: &str = "splatoon3-gadget";
-const GADGET_SERVICE_FILE: &str = "/etc/systemd/system/splatoon3-gadget.service";
+pub const GADGET_SERVICE_FILE: &str = "/etc/systemd/system/splatoon3-gadget.service";
 const WEB_SERVICE_NAME: &str = "splatoon3-ghost-drawer";
-const WEB_SERVICE_FILE: &str = "/etc/systemd/system/splatoon3-ghost-drawer.service";
+pub const WEB_SERVICE_FILE: &str = "/etc/systemd/system/splatoon3-ghost-drawer.service";
+const INSTALLED_BINARY_PATH: &str = "/opt/splatoon3-ghost-drawer/splatoon3-ghost-drawer";
+
+/// ガジェットの構成後に `/dev/hidg0` が現れるまで待つ時間（`TimeoutStartSec` より短くする）
+const GADGET_DEVICE_TIMEOUT_SECS: u64 = 20;
+/// 起動直後に `/sys/class/udc` にUDCが現れるまで待つ時間（`/dev/hidg0` を待つ時間と合わせて `TimeoutStartSec` より短くする）
+const GADGET_UDC_WAIT_SECS: u64 = 30;
+
+/// ガジェットのサービスに必要な順序付けの指定（キーと、その値に含まれるべき項目）
+///
+/// Webサービスより先に終わり、終了後も起動済みとして扱われることで、`Requires=` が満たされる。
+pub const GADGET_UNIT_ORDERING: [(&str, &str); 3] = [
+    ("Before", "splatoon3-ghost-drawer.service"),
+    ("Type", "oneshot"),
+    ("RemainAfterExit", "yes"),
+];
+
+/// Webサービスに必要な順序付けの指定
+///
+/// ガジェットのサービスの完了と、`/dev/hidg0` のデバイスユニットを待ってから起動する。
+pub const WEB_UNIT_ORDERING: [(&str, &str); 4] = [
+    ("After", "splatoon3-gadget.service"),
+    ("Requires", "splatoon3-gadget.service"),
+    ("After", "dev-hidg0.device"),
+    ("Wants", "dev-hidg0.device"),
+];
+
+/// ユニットファイルに無い指定を `Key=value` の形で返す
+///
+/// `After=a.service b.service` のように1行に複数並んだ値や、同じキーの複数行も探す。
+pub fn missing_unit_directives(unit: &str, required: &[(&str, &str)]) -> Vec<String> {
+    required
+        .iter()
+        .filter(|(key, value)| {
+            !unit.lines().any(|line| {
+                line.trim()
+                    .split_once('=')
+                    .is_some_and(|(line_key, values)| {
+                        line_key.trim() == *key && values.split_whitespace().any(|v| v == *value)
+                    })
+            })
+        })
+        .map(|(key, value)| format!("{key}={value}"))
+        .collect()
+}
+
+/// ガジェットを構成するサービスのユニットファイル
+fn gadget_unit(binary_path: &str) -> String {
+    format!(
+        r#"[Unit]
+Description=Splatoon3 Ghost Drawer USB Gadget Configuration
+After=network.target
+Before=splatoon3-ghost-drawer.service
+# Before=basic.target
+# DefaultDependencies=no
+
+[Service]
+Type=oneshot
+RemainAfterExit=yes
+# UDCが現れるまで待って構成し、/dev/hidg0 が作られるまで待ってから成功として終了する
+ExecStart={binary_path} _internal_configure_gadget --wait-udc-secs {GADGET_UDC_WAIT_SECS} --device-timeout-secs {GADGET_DEVICE_TIMEOUT_SECS}
+ExecStop=/bin/sh -c 'echo "" > /sys/kernel/config/usb_gadget/nintendo_controller/UDC || true'
+StandardOutput=journal
+StandardError=journal
+TimeoutStartSec=60s
+
+[Install]
+WantedBy=multi-user.target
+"#
+    )
+}
+
+/// WebUIのサービスのユニットファイル
+fn web_unit(binary_path: &str) -> String {
+    format!(
+        r#"[Unit]
+Description=Splatoon3 Ghost Drawer Web Service
+After=network-online.target splatoon3-gadget.service dev-hidg0.device
+Wants=network-online.target dev-hidg0.device
+Requires=splatoon3-gadget.service
+
+[Service]
+Type=simple
+ExecStart={binary_path} run
+Restart=on-failure
+RestartSec=10
+User=splatoon3
+Group=splatoon3
+Environment="RUST_LOG=info"
+StandardOutput=journal
+StandardError=journal
+TimeoutStartSec=60s
+# Grant access to HID devices
+SupplementaryGroups=input
+
+[Install]
+WantedBy=multi-user.target
+"#
+    )
+}
 
 pub struct LinuxSystemdManager;
 
@@ -92,8 +191,9 @@ impl LinuxSystemdManager {
         // Create udev rule for HID device permissions
         let udev_rule_content = r#"# Splatoon3 Ghost Drawer HID Device Permissions
 # Give splatoon3 user access to HID gadget devices
+# TAG+="systemd" creates dev-hidg0.device, which the web service waits for
 SUBSYSTEM=="hidg", GROUP="splatoon3", MODE="0664"
-KERNEL=="hidg*", GROUP="splatoon3", MODE="0664"
+KERNEL=="hidg*", TAG+="systemd", GROUP="splatoon3", MODE="0664"
 
 # Also ensure input group access
 SUBSYSTEM=="input", GROUP="input", MODE="0664"
@@ -146,28 +246,7 @@ impl SystemdServiceManager for LinuxSystemdManager {
         info!("Creating systemd service file...");
 
         // Use the installed binary path
-        let installed_binary_path = "/opt/splatoon3-ghost-drawer/splatoon3-ghost-drawer";
-
-        let service_content = format!(
-            r#"[Unit]
-Description=Splatoon3 Ghost Drawer USB Gadget Configuration
-After=network.target
-# Before=basic.target
-# DefaultDependencies=no
-
-[Service]
-Type=oneshot
-RemainAfterExit=yes
-ExecStart={installed_binary_path} _internal_configure_gadget
-ExecStop=/bin/sh -c 'echo "" > /sys/kernel/config/usb_gadget/nintendo_controller/UDC || true'
-StandardOutput=journal
-StandardError=journal
-TimeoutStartSec=30s
-
-[Install]
-WantedBy=multi-user.target
-"#
-        );
+        let service_content = gadget_unit(INSTALLED_BINARY_PATH);
 
         // Write service file
         let mut file = fs::OpenOptions::new()
@@ -254,33 +333,7 @@ WantedBy=multi-user.target
         self.setup_hid_device_permissions()?;
 
         // Use the installed binary path
-        let installed_binary_path = "/opt/splatoon3-ghost-drawer/splatoon3-ghost-drawer";
-
-        let service_content = format!(
-            r#"[Unit]
-Description=Splatoon3 Ghost Drawer Web Service
-After=network-online.target splatoon3-gadget.service
-Wants=network-online.target
-Requires=splatoon3-gadget.service
-
-[Service]
-Type=simple
-ExecStart={installed_binary_path} run
-Restart=on-failure
-RestartSec=10
-User=splatoon3
-Group=splatoon3
-Environment="RUST_LOG=info"
-StandardOutput=journal
-StandardError=journal
-TimeoutStartSec=60s
-# Grant access to HID devices
-SupplementaryGroups=input
-
-[Install]
-WantedBy=multi-user.target
-"#
-        );
+        let service_content = web_unit(INSTALLED_BINARY_PATH);
 
         // Write service file
         let mut file = fs::OpenOptions::new()
@@ -343,6 +396,20 @@ WantedBy=multi-user.target
         Ok(())
     }
 
+    fn missing_unit_ordering(&self) -> Vec<String> {
+        [
+            (GADGET_SERVICE_FILE, &GADGET_UNIT_ORDERING[..]),
+            (WEB_SERVICE_FILE, &WEB_UNIT_ORDERING[..]),
+        ]
+        .into_iter()
+        .filter_map(|(path, required)| {
+            let unit = fs::read_to_string(path).ok()?;
+            let missing = missing_unit_directives(&unit, required);
+            (!missing.is_empty()).then(|| format!("{path}: {}", missing.join(", ")))
+        })
+        .collect()
+    }
+
     fn disable_and_remove_services(&self) -> Result<(), SetupError> {
         info!("Disabling and removing systemd services...");
 
@@ -450,3 +517,34 @@ WantedBy=multi-user.target
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_templates_order_the_web_service_after_the_gadget() {
+        let gadget = gadget_unit(INSTALLED_BINARY_PATH);
+        let web = web_unit(INSTALLED_BINARY_PATH);
+        assert!(missing_unit_directives(&gadget, &GADGET_UNIT_ORDERING).is_empty());
+        assert!(missing_unit_directives(&web, &WEB_UNIT_ORDERING).is_empty());
+        assert!(
+            gadget
+                .contains("_internal_configure_gadget --wait-udc-secs 30 --device-timeout-secs 20")
+        );
+
+        // 以前のテンプレートはデバイスユニットを待たず、ガジェット側にも順序が無い
+        let old_web = "[Unit]\nAfter=network-online.target splatoon3-gadget.service\n\
+                       Wants=network-online.target\nRequires=splatoon3-gadget.service\n";
+        assert_eq!(
+            missing_unit_directives(old_web, &WEB_UNIT_ORDERING),
+            ["After=dev-hidg0.device", "Wants=dev-hidg0.device"]
+        );
+        let old_gadget = "[Unit]\nAfter=network.target\n# Before=basic.target\n\
+                          [Service]\nType=oneshot\nRemainAfterExit=yes\n";
+        assert_eq!(
+            missing_unit_directives(old_gadget, &GADGET_UNIT_ORDERING),
+            ["Before=splatoon3-ghost-drawer.service"]
+        );
+    }
+}
@@ -0,0 +1,529 @@
+//! ハードウェアに依存しない自己診断（`splatoon3-ghost-drawer doctor`）
+//!
+//! 失敗がシステムの設定とアプリのどちらにあるかを切り分けられるよう、MockControllerだけを使って
+//! 画像の変換・描画パスの生成・描画・Web APIまでを順に確かめる。Switch・HIDデバイス・データディレクトリには触れない。
+//! 出力はCIとの比較やissueへの貼り付けに使うため、ポート・パス・IDなど実行ごとに変わる値を含めない
+//! （所要時間の列だけは `--no-timing` で省ける）。段階が失敗した場合、残りの段階は実行せずに飛ばす。
+
+use crate::domain::artwork::entities::{Artwork, ArtworkMetadata, Canvas};
+use crate::domain::artwork::services::ImageProcessingService;
+use crate::domain::artwork::value_objects::{ImageAdjustments, Transparency};
+use crate::domain::controller::ControllerEmulator;
+use crate::domain::painting::{
+    ArtworkToCommandConverter, DrawingCanvasConfig, DrawingStrategy, simulate_run,
+};
+use crate::domain::shared::value_objects::Coordinates;
+use crate::infrastructure::hardware::mock_controller::MockController;
+use crate::interfaces::web::dto::{ErrorResponse, PaintingStatus};
+use crate::interfaces::web::server::{CreateArtworkRequest, serve_self_test};
+use serde_json::{Value, json};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// 段階の名前（実行する順）
+pub const DOCTOR_STAGES: [&str; 9] = [
+    "conversion",
+    "path generation",
+    "mock paint",
+    "web server",
+    "list artworks",
+    "create artwork",
+    "artwork path",
+    "strategy comparison",
+    "paint (preview)",
+];
+
+/// 自己診断に使う、上下・左右が非対称な「F」の字（3x5）
+const TEST_GLYPH: [&str; 5] = ["###", "#..", "##.", "#..", "#.."];
+/// 字の1画素を描く大きさ（画素）
+const TEST_GLYPH_SCALE: u16 = 2;
+/// 字の周りの余白（画素）
+const TEST_IMAGE_MARGIN: u16 = 2;
+/// 描画の終了を待つ上限
+const PAINT_TIMEOUT: Duration = Duration::from_secs(30);
+/// 描画の状態を確かめる間隔
+const PAINT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+/// APIへの1回の要求の待ち時間
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// 段階の結果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StageOutcome {
+    /// 確かめた内容
+    Passed(String),
+    /// 失敗の理由
+    Failed(String),
+    /// 前の段階が失敗したため実行しなかった
+    Skipped,
+}
+
+#[derive(Debug, Clone)]
+pub struct DoctorStage {
+    pub name: &'static str,
+    pub outcome: StageOutcome,
+    pub elapsed: Duration,
+}
+
+/// 自己診断の結果
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub stages: Vec<DoctorStage>,
+}
+
+impl DoctorReport {
+    /// 最初に失敗した段階
+    pub fn failed_stage(&self) -> Option<&DoctorStage> {
+        self.stages
+            .iter()
+            .find(|stage| matches!(stage.outcome, StageOutcome::Failed(_)))
+    }
+
+    pub fn passed(&self) -> bool {
+        self.failed_stage().is_none()
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        if self.passed() { 0 } else { 1 }
+    }
+
+    /// 端末に表示する結果の表（`timing` が無効なら所要時間の列を省く）
+    pub fn table_lines(&self, timing: bool) -> Vec<String> {
+        let width = DOCTOR_STAGES
+            .iter()
+            .map(|name| name.len())
+            .max()
+            .unwrap_or(0);
+        let mut lines = vec![if timing {
+            format!(
+                "{:<width$}  {:<6}  {:>8}  Details",
+                "Stage", "Result", "Time"
+            )
+        } else {
+            format!("{:<width$}  {:<6}  Details", "Stage", "Result")
+        }];
+        for stage in &self.stages {
+            let (result, details) = match &stage.outcome {
+                StageOutcome::Passed(details) => ("PASS", details.as_str()),
+                StageOutcome::Failed(reason) => ("FAIL", reason.as_str()),
+                StageOutcome::Skipped => ("SKIP", "not run after an earlier failure"),
+            };
+            lines.push(if timing && stage.outcome != StageOutcome::Skipped {
+                let time = format!("{}ms", stage.elapsed.as_millis());
+                format!("{:<width$}  {result:<6}  {time:>8}  {details}", stage.name)
+            } else if timing {
+                format!("{:<width$}  {result:<6}  {:>8}  {details}", stage.name, "-")
+            } else {
+                format!("{:<width$}  {result:<6}  {details}", stage.name)
+            });
+        }
+        lines.push(match self.failed_stage() {
+            Some(stage) => format!("Self-test failed at stage '{}'", stage.name),
+            None => format!("All {} stages passed", self.stages.len()),
+        });
+        lines
+    }
+
+    /// 段階を実行して結果と所要時間を記録する
+    async fn run<T>(
+        &mut self,
+        stage: impl Future<Output = Result<(T, String), String>>,
+    ) -> Option<T> {
+        let name = DOCTOR_STAGES[self.stages.len()];
+        let started = Instant::now();
+        let (value, outcome) = match stage.await {
+            Ok((value, details)) => (Some(value), StageOutcome::Passed(details)),
+            Err(reason) => (None, StageOutcome::Failed(reason)),
+        };
+        self.stages.push(DoctorStage {
+            name,
+            outcome,
+            elapsed: started.elapsed(),
+        });
+        value
+    }
+
+    /// 残りの段階を飛ばしたことにする
+    fn skip_rest(mut self) -> Self {
+        for name in &DOCTOR_STAGES[self.stages.len()..] {
+            self.stages.push(DoctorStage {
+                name,
+                outcome: StageOutcome::Skipped,
+                elapsed: Duration::ZERO,
+            });
+        }
+        self
+    }
+}
+
+/// すべての段階を順に実行する
+pub async fn run_doctor() -> DoctorReport {
+    let mut report = DoctorReport::default();
+    let Some(artwork) = report.run(async { convert_test_image() }).await else {
+        return report.skip_rest();
+    };
+    let Some(()) = report.run(async { generate_paths(&artwork.canvas) }).await else {
+        return report.skip_rest();
+    };
+    let Some(()) = report.run(async { paint_with_mock(&artwork) }).await else {
+        return report.skip_rest();
+    };
+    let Some((api, server)) = report.run(start_web_server()).await else {
+        return report.skip_rest();
+    };
+    let dots = artwork.canvas.dots.len();
+    let completed = async {
+        report.run(api.list_artworks()).await?;
+        let id = report.run(api.create_artwork(&artwork)).await?;
+        report.run(api.artwork_path(&id, dots)).await?;
+        report.run(api.compare_strategies(&id)).await?;
+        report.run(api.paint_preview(&id, dots)).await
+    }
+    .await;
+    server.abort();
+    match completed {
+        Some(()) => report,
+        None => report.skip_rest(),
+    }
+}
+
+/// 字を描いたRGBAの画像
+fn test_image() -> (u16, u16, Vec<u8>) {
+    let width = 3 * TEST_GLYPH_SCALE + 2 * TEST_IMAGE_MARGIN;
+    let height = 5 * TEST_GLYPH_SCALE + 2 * TEST_IMAGE_MARGIN;
+    let mut rgba = Vec::with_capacity(width as usize * height as usize * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let cell = |value: u16| {
+                value
+                    .checked_sub(TEST_IMAGE_MARGIN)
+                    .map(|value| (value / TEST_GLYPH_SCALE) as usize)
+            };
+            let ink = cell(x).zip(cell(y)).is_some_and(|(column, row)| {
+                TEST_GLYPH
+                    .get(row)
+                    .and_then(|line| line.as_bytes().get(column))
+                    == Some(&b'#')
+            });
+            let value = if ink { 0 } else { 255 };
+            rgba.extend_from_slice(&[value, value, value, 255]);
+        }
+    }
+    (width, height, rgba)
+}
+
+fn convert_test_image() -> Result<(Artwork, String), String> {
+    let (width, height, rgba) = test_image();
+    let canvas = ImageProcessingService::threshold_rgba(
+        width,
+        height,
+        &rgba,
+        &ImageAdjustments::default(),
+        &Transparency::default(),
+    );
+    let expected = TEST_GLYPH
+        .iter()
+        .flat_map(|row| row.bytes())
+        .filter(|&cell| cell == b'#')
+        .count()
+        * (TEST_GLYPH_SCALE * TEST_GLYPH_SCALE) as usize;
+    if canvas.dots.len() != expected {
+        return Err(format!(
+            "expected {expected} dots from the {width}x{height} test image, got {}",
+            canvas.dots.len()
+        ));
+    }
+    let origin = Coordinates::new(TEST_IMAGE_MARGIN, TEST_IMAGE_MARGIN);
+    if !canvas.dots.contains_key(&origin) {
+        return Err(format!("the top-left dot of the glyph {origin} is missing"));
+    }
+    let details = format!("{expected} dots from a {width}x{height} image");
+    let artwork = Artwork::new(
+        ArtworkMetadata::new("Doctor self-test".to_string()),
+        "png".to_string(),
+        canvas,
+    );
+    Ok((artwork, details))
+}
+
+fn generate_paths(canvas: &Canvas) -> Result<((), String), String> {
+    let expected: HashSet<Coordinates> = canvas.dots.keys().copied().collect();
+    let first_dot = expected
+        .iter()
+        .min_by_key(|coordinates| (coordinates.y, coordinates.x))
+        .copied();
+    for strategy in DrawingStrategy::ALL {
+        let path = ArtworkToCommandConverter::new(DrawingCanvasConfig::default(), strategy)
+            .create_drawing_path(canvas);
+        let visited: HashSet<Coordinates> = path.coordinates.iter().copied().collect();
+        if path.coordinates.len() != expected.len() || visited != expected {
+            return Err(format!(
+                "{strategy:?} visits {} of {} dots",
+                visited.intersection(&expected).count(),
+                expected.len()
+            ));
+        }
+        // 左上から描き始める戦略は、上下・左右が反転していれば別のドットから始まる
+        if matches!(
+            strategy,
+            DrawingStrategy::RasterScan | DrawingStrategy::ZigZag
+        ) && path.coordinates.first().copied() != first_dot
+        {
+            return Err(format!(
+                "{strategy:?} starts at {:?} instead of the top-left dot",
+                path.coordinates.first()
+            ));
+        }
+    }
+    Ok(((), format!("{} strategies", DrawingStrategy::ALL.len())))
+}
+
+fn paint_with_mock(artwork: &Artwork) -> Result<((), String), String> {
+    let config = DrawingCanvasConfig::default();
+    let converter = ArtworkToCommandConverter::new(config.clone(), DrawingStrategy::RasterScan);
+    let path = converter.create_drawing_path(&artwork.canvas);
+    let expected = simulate_run(&path, &config.timing, &config.options);
+    let controller = MockController::new().without_delays();
+    controller
+        .initialize()
+        .map_err(|e| format!("failed to initialize the mock controller: {e}"))?;
+    let drawing = converter
+        .convert(artwork)
+        .into_iter()
+        .filter(|command| command.name.starts_with("Draw Batch"));
+    for command in drawing {
+        controller
+            .execute_command(&command)
+            .map_err(|e| format!("command '{}' failed: {e}", command.name))?;
+    }
+    let recorded = controller.recorded_operations();
+    if (recorded.a_presses, recorded.dpad_ops) != (expected.a_presses, expected.dpad_ops) {
+        return Err(format!(
+            "sent {} presses and {} moves, expected {} and {}",
+            recorded.a_presses, recorded.dpad_ops, expected.a_presses, expected.dpad_ops
+        ));
+    }
+    Ok((
+        (),
+        format!(
+            "{} presses, {} moves",
+            recorded.a_presses, recorded.dpad_ops
+        ),
+    ))
+}
+
+/// 空いているポートでシミュレーションのサーバーを起動する
+async fn start_web_server() -> Result<((SelfTestApi, tokio::task::JoinHandle<()>), String), String>
+{
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .map_err(|e| format!("cannot listen on 127.0.0.1: {e}"))?;
+    let address = listener
+        .local_addr()
+        .map_err(|e| format!("cannot read the listening address: {e}"))?;
+    let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new().without_delays());
+    let server = tokio::spawn(async move {
+        if let Err(e) = serve_self_test(listener, controller).await {
+            tracing::error!("Self-test server stopped: {}", e);
+        }
+    });
+    let api = SelfTestApi {
+        base_url: format!("http://{address}"),
+        http: reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default(),
+    };
+    if let Err(reason) = api.request("GET", "/api/painting/status", None).await {
+        server.abort();
+        return Err(reason);
+    }
+    Ok(((api, server), "listening on an ephemeral port".to_string()))
+}
+
+/// 自己診断のサーバーのAPI
+struct SelfTestApi {
+    base_url: String,
+    http: reqwest::Client,
+}
+
+impl SelfTestApi {
+    /// 2xxの応答の本文を読む（失敗の理由にはポートを含めない）
+    async fn request(
+        &self,
+        method: &str,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, String> {
+        let url = format!("{}{path}", self.base_url);
+        let request = match method {
+            "POST" => self
+                .http
+                .post(&url)
+                .json(&body.unwrap_or_else(|| json!({}))),
+            _ => self.http.get(&url),
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("{method} {path} failed: {}", e.without_url()))?;
+        let status = response.status();
+        let text = response
+            .text()
+            .await
+            .map_err(|e| format!("{method} {path}: {}", e.without_url()))?;
+        if !status.is_success() {
+            let message = serde_json::from_str::<ErrorResponse>(&text)
+                .map(|error| error.message)
+                .unwrap_or(text);
+            return Err(format!(
+                "{method} {path} returned {}: {message}",
+                status.as_u16()
+            ));
+        }
+        serde_json::from_str(&text).map_err(|e| format!("{method} {path}: invalid JSON: {e}"))
+    }
+
+    async fn list_artworks(&self) -> Result<((), String), String> {
+        let artworks = self.request("GET", "/api/artworks", None).await?;
+        let count = artworks.as_array().map_or(0, Vec::len);
+        Ok(((), format!("{count} artworks")))
+    }
+
+    async fn create_artwork(&self, artwork: &Artwork) -> Result<(String, String), String> {
+        let request =
+            CreateArtworkRequest::from_canvas(artwork.metadata.name.clone(), &artwork.canvas);
+        let body = serde_json::to_value(request).map_err(|e| e.to_string())?;
+        let created = self.request("POST", "/api/artworks", Some(body)).await?;
+        let id = created["id"]
+            .as_str()
+            .ok_or("POST /api/artworks returned no id")?;
+        let dots = created["artwork"]["total_dots"].as_u64().unwrap_or(0);
+        if dots != artwork.canvas.dots.len() as u64 {
+            return Err(format!(
+                "created artwork has {dots} dots, expected {}",
+                artwork.canvas.dots.len()
+            ));
+        }
+        Ok((id.to_string(), format!("{dots} dots")))
+    }
+
+    async fn artwork_path(&self, id: &str, dots: usize) -> Result<((), String), String> {
+        let response = self
+            .request("GET", &format!("/api/artworks/{id}/path"), None)
+            .await?;
+        let length = response["path"].as_array().map_or(0, Vec::len);
+        if length != dots {
+            return Err(format!("path has {length} dots, expected {dots}"));
+        }
+        Ok(((), format!("{length} dots")))
+    }
+
+    async fn compare_strategies(&self, id: &str) -> Result<((), String), String> {
+        let response = self
+            .request("GET", &format!("/api/artworks/{id}/strategies"), None)
+            .await?;
+        let compared = response["strategies"].as_array().map_or(0, Vec::len);
+        if compared == 0 {
+            return Err("no strategies were compared".to_string());
+        }
+        Ok(((), format!("{compared} strategies")))
+    }
+
+    /// プレビュー指定で描画し、すべてのドットを描き終えるまで待つ
+    async fn paint_preview(&self, id: &str, dots: usize) -> Result<((), String), String> {
+        self.request("POST", "/api/controller/arm", None).await?;
+        self.request(
+            "POST",
+            &format!("/api/artworks/{id}/paint"),
+            Some(json!({ "preview": true })),
+        )
+        .await?;
+        let started = Instant::now();
+        loop {
+            let status: PaintingStatus =
+                serde_json::from_value(self.request("GET", "/api/painting/status", None).await?)
+                    .map_err(|e| format!("GET /api/painting/status: {e}"))?;
+            if let Some(run) = status.last_run.filter(|_| !status.active) {
+                let outcome = run.outcome.as_ref().map_or("unknown", |o| o.kind());
+                if outcome != "completed" || run.dots_painted != dots {
+                    return Err(format!(
+                        "painting ended as {outcome} with {} of {dots} dots",
+                        run.dots_painted
+                    ));
+                }
+                return Ok(((), format!("{dots} dots painted")));
+            }
+            if started.elapsed() > PAINT_TIMEOUT {
+                return Err(format!(
+                    "painting did not finish within {}s",
+                    PAINT_TIMEOUT.as_secs()
+                ));
+            }
+            tokio::time::sleep(PAINT_POLL_INTERVAL).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_doctor_passes_every_stage() {
+        let report = run_doctor().await;
+        let lines = report.table_lines(false);
+        assert!(report.passed(), "{}", lines.join("\n"));
+        assert_eq!(report.exit_code(), 0);
+        assert_eq!(report.stages.len(), DOCTOR_STAGES.len());
+        assert_eq!(
+            lines[1..4],
+            [
+                "conversion           PASS    32 dots from a 10x14 image",
+                "path generation      PASS    5 strategies",
+                "mock paint           PASS    32 presses, 56 moves",
+            ]
+        );
+        assert_eq!(lines.last().unwrap(), "All 9 stages passed");
+        // 2回目も同じ表になる
+        assert_eq!(run_doctor().await.table_lines(false), lines);
+    }
+
+    #[test]
+    fn test_failure_names_the_stage_and_skips_the_rest() {
+        let mut report = DoctorReport::default();
+        report.stages.push(DoctorStage {
+            name: DOCTOR_STAGES[0],
+            outcome: StageOutcome::Passed("ok".to_string()),
+            elapsed: Duration::from_millis(3),
+        });
+        report.stages.push(DoctorStage {
+            name: DOCTOR_STAGES[1],
+            outcome: StageOutcome::Failed("ZigZag visits 3 of 4 dots".to_string()),
+            elapsed: Duration::from_millis(12),
+        });
+        let report = report.skip_rest();
+        assert_eq!(report.exit_code(), 1);
+        assert_eq!(report.stages.len(), DOCTOR_STAGES.len());
+        assert!(
+            report.stages[2..]
+                .iter()
+                .all(|stage| stage.outcome == StageOutcome::Skipped)
+        );
+
+        let lines = report.table_lines(true);
+        assert_eq!(lines[0], "Stage                Result      Time  Details");
+        assert_eq!(
+            lines[2],
+            "path generation      FAIL        12ms  ZigZag visits 3 of 4 dots"
+        );
+        assert_eq!(
+            lines[3],
+            "mock paint           SKIP           -  not run after an earlier failure"
+        );
+        assert_eq!(
+            lines.last().unwrap(),
+            "Self-test failed at stage 'path generation'"
+        );
+    }
+}
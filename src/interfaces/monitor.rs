@@ -0,0 +1,838 @@
+//! 端末で描画の進捗を見る読み取り専用のモニター（`splatoon3-ghost-drawer monitor`）
+//!
+//! 起動中のサーバーの `GET /api/painting/status` を定期的に取得し、`/ws/logs` の進捗と警告を受け取って表示する。
+//! コントローラーには何も送らないため、サーバーと同時に動かしてもデバイスを取り合わない。
+//! サーバーが再起動した場合は、つながるまで取得と接続をやり直す。
+
+use crate::interfaces::web::dto::{GalleryThumbnail, PaintingStatus};
+use futures_util::StreamExt;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::symbols::Marker;
+use ratatui::text::Line;
+use ratatui::widgets::canvas::{Canvas, Points};
+use ratatui::widgets::{Block, Gauge, Paragraph, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use tracing::debug;
+
+/// `GET /api/painting/status` を取得する間隔
+const STATUS_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// サーバーへの1回の要求の待ち時間
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// 進捗のWebSocketが切れてから接続し直すまでの待ち時間
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+/// 画面を描き直し、キー入力を確認する間隔
+const REDRAW_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 描画の速さ（ドット/分）を求める直近の期間
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// 表示する直近の警告の件数
+const MAX_WARNINGS: usize = 5;
+
+/// キャンバスを描く画面の大きさの下限（これより小さい端末では文字だけで表示する）
+const MIN_DASHBOARD_WIDTH: u16 = 50;
+const MIN_DASHBOARD_HEIGHT: u16 = 16;
+
+/// モニターの接続先
+#[derive(Debug, Clone)]
+pub struct MonitorSettings {
+    pub host: String,
+    pub port: u16,
+}
+
+impl MonitorSettings {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+        }
+    }
+
+    /// URLに入れるホスト名（IPv6アドレスは角括弧で囲む）
+    fn authority(&self) -> String {
+        if self.host.contains(':') && !self.host.starts_with('[') {
+            format!("[{}]:{}", self.host, self.port)
+        } else {
+            format!("{}:{}", self.host, self.port)
+        }
+    }
+
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.authority())
+    }
+
+    fn status_url(&self) -> String {
+        format!("{}/api/painting/status?thumbnail=true", self.base_url())
+    }
+
+    fn websocket_url(&self) -> String {
+        format!("ws://{}/ws/logs", self.authority())
+    }
+}
+
+/// 取得処理からモニターの画面へ送る出来事
+#[derive(Debug)]
+enum MonitorEvent {
+    /// `GET /api/painting/status` の結果（失敗した場合はエラーの内容）
+    Status(Result<Box<PaintingStatus>, String>),
+    /// 描画中のアートワークの名前
+    ArtworkName { id: String, name: String },
+    /// 進捗のWebSocketにつながった
+    StreamConnected,
+    /// 進捗のWebSocketが切れた（接続し直す）
+    StreamDisconnected(String),
+    /// 進捗のWebSocketのメッセージ
+    StreamMessage(String),
+}
+
+/// サーバーとの接続状態
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ServerLink {
+    Connecting,
+    Online,
+    /// 取得に失敗した（次の取得でやり直す）
+    Offline(String),
+}
+
+/// モニターに表示する状態（取得した結果と受け取ったメッセージから組み立てる）
+#[derive(Debug)]
+pub struct MonitorView {
+    server_url: String,
+    server: ServerLink,
+    stream_connected: bool,
+    /// Switchとの接続（`connection_state` を受け取るまでは不明）
+    switch_connected: Option<bool>,
+    status: Option<PaintingStatus>,
+    /// 名前を取得したアートワークのID・名前
+    artwork: Option<(String, String)>,
+    /// 名前の取得を始めたアートワークのID
+    artwork_requested: Option<String>,
+    /// 進捗のメッセージの状態の文言（初期化中など）
+    status_message: Option<String>,
+    /// 描画済みのドット数の履歴（描画の世代ごと）
+    rate_samples: VecDeque<(Instant, usize)>,
+    rate_generation: Option<u64>,
+    warnings: VecDeque<String>,
+}
+
+impl MonitorView {
+    pub fn new(server_url: impl Into<String>) -> Self {
+        Self {
+            server_url: server_url.into(),
+            server: ServerLink::Connecting,
+            stream_connected: false,
+            switch_connected: None,
+            status: None,
+            artwork: None,
+            artwork_requested: None,
+            status_message: None,
+            rate_samples: VecDeque::new(),
+            rate_generation: None,
+            warnings: VecDeque::new(),
+        }
+    }
+
+    fn apply(&mut self, event: MonitorEvent, now: Instant) {
+        match event {
+            MonitorEvent::Status(Ok(status)) => self.apply_status(*status, now),
+            MonitorEvent::Status(Err(error)) => self.server = ServerLink::Offline(error),
+            MonitorEvent::ArtworkName { id, name } => self.artwork = Some((id, name)),
+            MonitorEvent::StreamConnected => self.stream_connected = true,
+            MonitorEvent::StreamDisconnected(error) => {
+                debug!("Progress stream disconnected: {}", error);
+                self.stream_connected = false;
+                // 再接続までに変わったかもしれないため、次の `connection_state` を待つ
+                self.switch_connected = None;
+            }
+            MonitorEvent::StreamMessage(text) => self.apply_message(&text),
+        }
+    }
+
+    /// 取得した描画の状態を反映し、描画の速さの履歴に加える
+    pub fn apply_status(&mut self, status: PaintingStatus, now: Instant) {
+        self.server = ServerLink::Online;
+        if status.generation != self.rate_generation {
+            self.rate_samples.clear();
+            self.rate_generation = status.generation;
+            self.status_message = None;
+        }
+        if status.active {
+            self.rate_samples.push_back((now, status.painted));
+            while let Some((at, _)) = self.rate_samples.front()
+                && now.duration_since(*at) > RATE_WINDOW
+            {
+                self.rate_samples.pop_front();
+            }
+        }
+        self.status = Some(status);
+    }
+
+    /// 進捗のWebSocketのメッセージを反映する（表示しない種類は無視する）
+    pub fn apply_message(&mut self, text: &str) {
+        let Ok(message) = serde_json::from_str::<Value>(text) else {
+            return;
+        };
+        match message["type"].as_str() {
+            Some("log") => {
+                let level = message["level"].as_str().unwrap_or_default();
+                if matches!(level, "WARN" | "ERROR") {
+                    let time = message["timestamp"]
+                        .as_str()
+                        .and_then(|timestamp| chrono::DateTime::parse_from_rfc3339(timestamp).ok())
+                        .map(|timestamp| {
+                            timestamp
+                                .with_timezone(&chrono::Local)
+                                .format("%H:%M:%S")
+                                .to_string()
+                        })
+                        .unwrap_or_default();
+                    self.push_warning(format!(
+                        "{time} {level} {}",
+                        message["message"].as_str().unwrap_or_default()
+                    ));
+                }
+            }
+            Some("connection_state") => {
+                self.switch_connected = message["state"].as_str().map(|state| state == "connected");
+            }
+            Some("progress") => {
+                if let Some(status_message) = message["status_message"].as_str() {
+                    self.status_message = Some(status_message.to_string());
+                } else if message["current"].is_u64() {
+                    self.status_message = None;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn push_warning(&mut self, warning: String) {
+        if self.warnings.len() == MAX_WARNINGS {
+            self.warnings.pop_front();
+        }
+        self.warnings.push_back(warning);
+    }
+
+    /// 名前をまだ取得していない描画中のアートワークのID（取得を始めたものとして記録する）
+    fn take_artwork_to_fetch(&mut self) -> Option<String> {
+        let id = self.status.as_ref()?.artwork_id.clone()?;
+        if self.artwork_requested.as_ref() == Some(&id) {
+            return None;
+        }
+        self.artwork_requested = Some(id.clone());
+        Some(id)
+    }
+
+    /// 直近1分間の描画の速さ（ドット/分、1秒以上の履歴が無ければ `None`）
+    pub fn dots_per_minute(&self) -> Option<f64> {
+        let (first_at, first) = self.rate_samples.front()?;
+        let (last_at, last) = self.rate_samples.back()?;
+        let elapsed = last_at.duration_since(*first_at);
+        (elapsed >= Duration::from_secs(1))
+            .then(|| last.saturating_sub(*first) as f64 * 60.0 / elapsed.as_secs_f64())
+    }
+
+    fn artwork_name(&self) -> Option<&str> {
+        let id = self.status.as_ref()?.artwork_id.as_ref()?;
+        self.artwork
+            .as_ref()
+            .filter(|(artwork_id, _)| artwork_id == id)
+            .map(|(_, name)| name.as_str())
+    }
+
+    fn server_line(&self) -> String {
+        let link = match &self.server {
+            ServerLink::Connecting => "connecting...".to_string(),
+            ServerLink::Online if self.stream_connected => "online".to_string(),
+            ServerLink::Online => "online (progress stream reconnecting...)".to_string(),
+            ServerLink::Offline(error) => format!("unreachable, retrying ({error})"),
+        };
+        format!("Server  {}  {link}", self.server_url)
+    }
+
+    fn switch_line(&self) -> String {
+        let state = match self.switch_connected {
+            Some(true) => "connected",
+            Some(false) => "disconnected",
+            None => "unknown",
+        };
+        format!("Switch  {state}")
+    }
+
+    fn state_line(&self) -> String {
+        let Some(status) = &self.status else {
+            return "State   -".to_string();
+        };
+        let state = match status {
+            PaintingStatus { active: false, .. } => match &status.scheduled {
+                Some(scheduled) => format!("idle (painting scheduled for {})", scheduled.start_at),
+                None => "idle".to_string(),
+            },
+            PaintingStatus { paused: true, .. } => "paused".to_string(),
+            PaintingStatus {
+                preflight: Some(preflight),
+                ..
+            } if preflight.state == "awaiting_confirmation" => {
+                "waiting for the preflight mark to be confirmed".to_string()
+            }
+            _ => "painting".to_string(),
+        };
+        let artwork = match (self.artwork_name(), &status.artwork_id) {
+            (Some(name), _) => format!("  {name}"),
+            (None, Some(id)) => format!("  {id}"),
+            (None, None) => String::new(),
+        };
+        match &self.status_message {
+            Some(message) if status.active => format!("State   {state}{artwork}  ({message})"),
+            _ => format!("State   {state}{artwork}"),
+        }
+    }
+
+    /// 描画済み・全体のドット数
+    fn progress(&self) -> Option<(usize, usize)> {
+        let status = self.status.as_ref().filter(|status| status.active)?;
+        Some((status.painted, status.total_dots?))
+    }
+
+    fn progress_line(&self) -> String {
+        match self.progress() {
+            Some((painted, total)) if total > 0 => format!(
+                "{painted}/{total} dots ({:.1}%)",
+                painted as f64 * 100.0 / total as f64
+            ),
+            Some((painted, _)) => format!("{painted} dots"),
+            None => "-".to_string(),
+        }
+    }
+
+    fn stats_line(&self) -> String {
+        let status = self.status.as_ref().filter(|status| status.active);
+        let eta = status
+            .and_then(|status| status.eta_seconds)
+            .map_or("-".to_string(), format_duration);
+        let rate = self
+            .dots_per_minute()
+            .map_or("-".to_string(), |rate| format!("{rate:.1}"));
+        let timing =
+            status
+                .and_then(|status| status.config.as_ref())
+                .map_or("-".to_string(), |config| {
+                    format!(
+                        "{}+{}+{}ms",
+                        config.press_ms, config.release_ms, config.wait_ms
+                    )
+                });
+        format!("ETA {eta}  |  {rate} dots/min  |  timing {timing}")
+    }
+
+    fn last_run_line(&self) -> Option<String> {
+        let run = self.status.as_ref()?.last_run.as_ref()?;
+        let outcome = run
+            .outcome
+            .as_ref()
+            .map_or("running", |outcome| outcome.kind());
+        Some(format!(
+            "Last run  {outcome}, {}/{} dots",
+            run.dots_painted, run.dots_attempted
+        ))
+    }
+
+    /// 文字だけで表示する場合の各行（小さい端末用）
+    pub fn summary_lines(&self) -> Vec<String> {
+        let mut lines = vec![
+            self.server_line(),
+            self.switch_line(),
+            self.state_line(),
+            format!("Progress  {}", self.progress_line()),
+            self.stats_line(),
+        ];
+        lines.extend(self.last_run_line());
+        lines.extend(self.warnings.iter().rev().take(2).cloned());
+        lines.push("q: quit".to_string());
+        lines
+    }
+
+    fn thumbnail(&self) -> Option<&GalleryThumbnail> {
+        self.status
+            .as_ref()
+            .filter(|status| status.active)?
+            .thumbnail
+            .as_ref()
+    }
+}
+
+/// 秒数を `1h 02m`・`12m 03s`・`45s` の形にする
+pub(crate) fn format_duration(seconds: f64) -> String {
+    let seconds = seconds.max(0.0).round() as u64;
+    match (seconds / 3600, seconds % 3600 / 60, seconds % 60) {
+        (0, 0, s) => format!("{s}s"),
+        (0, m, s) => format!("{m}m {s:02}s"),
+        (h, m, _) => format!("{h}h {m:02}m"),
+    }
+}
+
+/// モニターの画面を描く（端末が小さい場合は文字だけにする）
+pub fn draw(frame: &mut Frame, view: &MonitorView) {
+    let area = frame.area();
+    if area.width < MIN_DASHBOARD_WIDTH || area.height < MIN_DASHBOARD_HEIGHT {
+        let lines: Vec<Line> = view.summary_lines().into_iter().map(Line::from).collect();
+        frame.render_widget(Paragraph::new(lines).wrap(Wrap { trim: true }), area);
+        return;
+    }
+
+    let warnings_height = view.warnings.len().max(1) as u16 + 2;
+    let [header, canvas, gauge, stats, warnings, help] = Layout::vertical([
+        Constraint::Length(3),
+        Constraint::Min(4),
+        Constraint::Length(1),
+        Constraint::Length(1),
+        Constraint::Length(warnings_height),
+        Constraint::Length(1),
+    ])
+    .areas(area);
+
+    let header_lines = vec![
+        Line::from(view.server_line()).style(match view.server {
+            ServerLink::Online => Style::default(),
+            _ => Style::default().fg(Color::Yellow),
+        }),
+        Line::from(view.switch_line()).style(match view.switch_connected {
+            Some(false) => Style::default().fg(Color::Red),
+            _ => Style::default(),
+        }),
+        Line::from(view.state_line()).bold(),
+    ];
+    frame.render_widget(Paragraph::new(header_lines), header);
+
+    draw_canvas(frame, view, canvas);
+
+    let ratio = match view.progress() {
+        Some((painted, total)) if total > 0 => (painted as f64 / total as f64).min(1.0),
+        _ => 0.0,
+    };
+    frame.render_widget(
+        Gauge::default()
+            .gauge_style(Style::default().fg(Color::Green))
+            .ratio(ratio)
+            .label(view.progress_line()),
+        gauge,
+    );
+    frame.render_widget(Paragraph::new(view.stats_line()), stats);
+
+    let warning_lines: Vec<Line> = if view.warnings.is_empty() {
+        vec![Line::from("none").dark_gray()]
+    } else {
+        view.warnings
+            .iter()
+            .map(|warning| Line::from(warning.as_str()).yellow())
+            .collect()
+    };
+    frame.render_widget(
+        Paragraph::new(warning_lines).block(Block::bordered().title("Recent warnings")),
+        warnings,
+    );
+    frame.render_widget(
+        Paragraph::new(
+            view.last_run_line()
+                .map_or("q: quit".to_string(), |line| format!("{line}  |  q: quit")),
+        )
+        .dark_gray(),
+        help,
+    );
+}
+
+/// 描画中のアートワークを点字で描く（描画済みは緑、未描画は灰色、原点の左上は黄色の印）
+fn draw_canvas(frame: &mut Frame, view: &MonitorView, area: Rect) {
+    let block = Block::bordered().title("Canvas (origin: top-left)");
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+    let Some(thumbnail) = view.thumbnail().filter(|t| t.width > 0 && t.height > 0) else {
+        let message = match view.status.as_ref() {
+            Some(status) if status.active => "No artwork is being painted",
+            _ => "No active painting",
+        };
+        frame.render_widget(Paragraph::new(message).dark_gray(), inner);
+        return;
+    };
+
+    // 点字1文字は横2×縦4セルなので、セルが正方形に見えるよう縦横の比を保って縮める
+    let (width, height) = (thumbnail.width as f64, thumbnail.height as f64);
+    let scale = (inner.width as f64 * 2.0 / width)
+        .min(inner.height as f64 * 4.0 / height)
+        .min(1.0);
+    let columns = ((width * scale / 2.0).ceil() as u16).clamp(1, inner.width);
+    let rows = ((height * scale / 4.0).ceil() as u16).clamp(1, inner.height);
+    let target = Rect::new(
+        inner.x + (inner.width - columns) / 2,
+        inner.y + (inner.height - rows) / 2,
+        columns,
+        rows,
+    );
+
+    let mut painted = Vec::new();
+    let mut unpainted = Vec::new();
+    for (y, row) in thumbnail.rows.iter().enumerate() {
+        // キャンバスの座標は下が0なので、上の行ほど大きくする
+        let y = height - 1.0 - y as f64;
+        for (x, cell) in row.chars().enumerate() {
+            match cell {
+                '#' => painted.push((x as f64, y)),
+                'o' => unpainted.push((x as f64, y)),
+                _ => {}
+            }
+        }
+    }
+    let canvas = Canvas::default()
+        .marker(Marker::Braille)
+        .x_bounds([0.0, (width - 1.0).max(1.0)])
+        .y_bounds([0.0, (height - 1.0).max(1.0)])
+        .paint(|context| {
+            context.draw(&Points {
+                coords: &unpainted,
+                color: Color::DarkGray,
+            });
+            context.draw(&Points {
+                coords: &painted,
+                color: Color::Green,
+            });
+            // 上下・左右が反転していないことを確かめられるよう、原点（左上）に印を付ける
+            context.draw(&Points {
+                coords: &[(0.0, height - 1.0)],
+                color: Color::Yellow,
+            });
+        });
+    frame.render_widget(canvas, target);
+}
+
+/// 描画の状態を定期的に取得する（取得に失敗してもやめない）
+async fn poll_status(
+    client: reqwest::Client,
+    settings: MonitorSettings,
+    events: mpsc::UnboundedSender<MonitorEvent>,
+) {
+    let url = settings.status_url();
+    let mut ticker = tokio::time::interval(STATUS_POLL_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    loop {
+        ticker.tick().await;
+        let result = fetch_status(&client, &url).await;
+        if events.send(MonitorEvent::Status(result)).is_err() {
+            return;
+        }
+    }
+}
+
+async fn fetch_status(client: &reqwest::Client, url: &str) -> Result<Box<PaintingStatus>, String> {
+    let response = client.get(url).send().await.map_err(|e| e.to_string())?;
+    if !response.status().is_success() {
+        return Err(format!("HTTP {}", response.status()));
+    }
+    response
+        .json::<Box<PaintingStatus>>()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// アートワークの名前を取得する（取得できなければ何も送らず、IDを表示したままにする）
+async fn fetch_artwork_name(
+    client: reqwest::Client,
+    settings: MonitorSettings,
+    id: String,
+    events: mpsc::UnboundedSender<MonitorEvent>,
+) {
+    let url = format!("{}/api/artworks/{id}", settings.base_url());
+    let name = match client.get(&url).send().await {
+        Ok(response) if response.status().is_success() => response
+            .json::<Value>()
+            .await
+            .ok()
+            .and_then(|artwork| artwork["name"].as_str().map(str::to_string)),
+        Ok(response) => {
+            debug!("Failed to fetch artwork {}: HTTP {}", id, response.status());
+            None
+        }
+        Err(e) => {
+            debug!("Failed to fetch artwork {}: {}", id, e);
+            None
+        }
+    };
+    if let Some(name) = name {
+        let _ = events.send(MonitorEvent::ArtworkName { id, name });
+    }
+}
+
+/// 進捗のWebSocketを受信し続ける（切れたら待ってから接続し直す）
+async fn stream_progress(url: String, events: mpsc::UnboundedSender<MonitorEvent>) {
+    loop {
+        let reason = match tokio_tungstenite::connect_async(&url).await {
+            Ok((mut socket, _)) => {
+                if events.send(MonitorEvent::StreamConnected).is_err() {
+                    return;
+                }
+                loop {
+                    match socket.next().await {
+                        Some(Ok(WsMessage::Text(text))) => {
+                            if events
+                                .send(MonitorEvent::StreamMessage(text.to_string()))
+                                .is_err()
+                            {
+                                return;
+                            }
+                        }
+                        Some(Ok(WsMessage::Close(_))) | None => break "closed".to_string(),
+                        Some(Ok(_)) => {}
+                        Some(Err(e)) => break e.to_string(),
+                    }
+                }
+            }
+            Err(e) => e.to_string(),
+        };
+        if events
+            .send(MonitorEvent::StreamDisconnected(reason))
+            .is_err()
+        {
+            return;
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+/// 終了するキー（q・Esc・Ctrl+C）か
+fn is_quit_key(code: KeyCode, modifiers: KeyModifiers) -> bool {
+    matches!(code, KeyCode::Char('q') | KeyCode::Esc)
+        || (code == KeyCode::Char('c') && modifiers.contains(KeyModifiers::CONTROL))
+}
+
+/// モニターを表示し、終了キーが押されるまで更新し続ける
+///
+/// 終了時（パニックした場合を含む）は端末を元の状態に戻す。
+pub async fn run_monitor(settings: MonitorSettings) -> std::io::Result<()> {
+    let client = reqwest::Client::builder()
+        .timeout(REQUEST_TIMEOUT)
+        .build()
+        .map_err(std::io::Error::other)?;
+    let (events, mut receiver) = mpsc::unbounded_channel();
+    let poller = tokio::spawn(poll_status(
+        client.clone(),
+        settings.clone(),
+        events.clone(),
+    ));
+    let stream = tokio::spawn(stream_progress(settings.websocket_url(), events.clone()));
+
+    let mut terminal = ratatui::init();
+    let result = monitor_loop(&mut terminal, &mut receiver, &client, &settings, &events).await;
+    ratatui::restore();
+    poller.abort();
+    stream.abort();
+    result
+}
+
+async fn monitor_loop(
+    terminal: &mut DefaultTerminal,
+    receiver: &mut mpsc::UnboundedReceiver<MonitorEvent>,
+    client: &reqwest::Client,
+    settings: &MonitorSettings,
+    events: &mpsc::UnboundedSender<MonitorEvent>,
+) -> std::io::Result<()> {
+    let mut view = MonitorView::new(settings.base_url());
+    let mut ticker = tokio::time::interval(REDRAW_INTERVAL);
+    loop {
+        tokio::select! {
+            Some(event) = receiver.recv() => {
+                view.apply(event, Instant::now());
+                if let Some(id) = view.take_artwork_to_fetch() {
+                    tokio::spawn(fetch_artwork_name(
+                        client.clone(),
+                        settings.clone(),
+                        id,
+                        events.clone(),
+                    ));
+                }
+                continue;
+            }
+            _ = ticker.tick() => {}
+        }
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+                && is_quit_key(key.code, key.modifiers)
+            {
+                return Ok(());
+            }
+        }
+        terminal.draw(|frame| draw(frame, &view))?;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::Terminal;
+    use ratatui::backend::TestBackend;
+
+    fn status(value: serde_json::Value) -> PaintingStatus {
+        let mut status = serde_json::json!({
+            "active": true,
+            "paused": false,
+            "painted": 0,
+            "config": null,
+            "last_run": null,
+            "generation": 1,
+            "scheduled": null,
+            "stop_after": null,
+            "preflight": null,
+            "artwork_id": "artwork-1",
+            "total_dots": 200,
+            "eta_seconds": 754.0,
+            "thumbnail": { "width": 4, "height": 2, "scale": 1, "rows": ["#o..", "..o#"] }
+        });
+        status
+            .as_object_mut()
+            .unwrap()
+            .extend(value.as_object().unwrap().clone());
+        serde_json::from_value(status).unwrap()
+    }
+
+    fn render(view: &MonitorView, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| draw(frame, view)).unwrap();
+        terminal
+            .backend()
+            .buffer()
+            .content()
+            .chunks(width as usize)
+            .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_monitor_settings_build_urls() {
+        let settings = MonitorSettings::new("192.168.1.20", 8080);
+        assert_eq!(settings.base_url(), "http://192.168.1.20:8080");
+        assert_eq!(settings.websocket_url(), "ws://192.168.1.20:8080/ws/logs");
+        assert_eq!(
+            MonitorSettings::new("::1", 3000).status_url(),
+            "http://[::1]:3000/api/painting/status?thumbnail=true"
+        );
+    }
+
+    #[test]
+    fn test_monitor_view_tracks_rate_and_resets_for_a_new_run() {
+        let mut view = MonitorView::new("http://localhost:8080");
+        let start = Instant::now();
+        view.apply_status(status(serde_json::json!({ "painted": 10 })), start);
+        assert_eq!(view.dots_per_minute(), None);
+        view.apply_status(
+            status(serde_json::json!({ "painted": 40 })),
+            start + Duration::from_secs(30),
+        );
+        assert_eq!(view.dots_per_minute(), Some(60.0));
+        assert_eq!(view.progress_line(), "40/200 dots (20.0%)");
+        assert!(
+            view.stats_line()
+                .starts_with("ETA 12m 34s  |  60.0 dots/min")
+        );
+
+        // 1分より前の履歴は使わない
+        view.apply_status(
+            status(serde_json::json!({ "painted": 160 })),
+            start + Duration::from_secs(90),
+        );
+        assert_eq!(view.dots_per_minute(), Some(120.0));
+
+        // 次の描画では履歴をやり直す
+        view.apply_status(
+            status(serde_json::json!({ "painted": 1, "generation": 2 })),
+            start + Duration::from_secs(91),
+        );
+        assert_eq!(view.dots_per_minute(), None);
+    }
+
+    #[test]
+    fn test_monitor_view_collects_warnings_and_connection_state() {
+        let mut view = MonitorView::new("http://localhost:8080");
+        view.apply_message(r#"{"type":"connection_state","state":"disconnected","changed":true}"#);
+        assert_eq!(view.switch_connected, Some(false));
+        view.apply_message(r#"{"type":"log","level":"INFO","message":"Painting started"}"#);
+        for i in 0..=MAX_WARNINGS {
+            view.apply_message(&format!(
+                r#"{{"type":"log","level":"WARN","message":"Retrying dot {i}"}}"#
+            ));
+        }
+        assert_eq!(view.warnings.len(), MAX_WARNINGS);
+        assert!(view.warnings[0].ends_with("WARN Retrying dot 1"));
+        view.apply_message(r#"{"type":"progress","status_message":"初期化中"}"#);
+        assert_eq!(view.status_message.as_deref(), Some("初期化中"));
+        view.apply_message("not json");
+
+        view.apply(
+            MonitorEvent::StreamDisconnected("closed".to_string()),
+            Instant::now(),
+        );
+        assert_eq!(view.switch_connected, None);
+    }
+
+    #[test]
+    fn test_monitor_view_fetches_each_artwork_name_once() {
+        let mut view = MonitorView::new("http://localhost:8080");
+        view.apply_status(status(serde_json::json!({})), Instant::now());
+        assert_eq!(view.take_artwork_to_fetch().as_deref(), Some("artwork-1"));
+        assert_eq!(view.take_artwork_to_fetch(), None);
+        assert!(view.state_line().contains("artwork-1"));
+
+        view.apply(
+            MonitorEvent::ArtworkName {
+                id: "artwork-1".to_string(),
+                name: "Inkling".to_string(),
+            },
+            Instant::now(),
+        );
+        assert_eq!(view.state_line(), "State   painting  Inkling");
+    }
+
+    #[test]
+    fn test_monitor_draws_dashboard_and_degrades_on_small_terminals() {
+        let mut view = MonitorView::new("http://localhost:8080");
+        view.apply_status(status(serde_json::json!({ "painted": 50 })), Instant::now());
+
+        let dashboard = render(&view, 80, 24);
+        assert!(dashboard.contains("Canvas"), "{dashboard}");
+        assert!(dashboard.contains("50/200 dots (25.0%)"), "{dashboard}");
+        assert!(dashboard.contains("Recent warnings"), "{dashboard}");
+        // 点字の文字でキャンバスを描く
+        assert!(
+            dashboard
+                .chars()
+                .any(|c| ('\u{2801}'..='\u{28ff}').contains(&c)),
+            "{dashboard}"
+        );
+
+        let small = render(&view, 40, 10);
+        assert!(!small.contains("Canvas"), "{small}");
+        assert!(small.contains("Progress  50/200"), "{small}");
+
+        view.apply(
+            MonitorEvent::Status(Err("connection refused".to_string())),
+            Instant::now(),
+        );
+        assert!(view.server_line().contains("unreachable, retrying"));
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(45.4), "45s");
+        assert_eq!(format_duration(754.0), "12m 34s");
+        assert_eq!(format_duration(3725.0), "1h 02m");
+    }
+}
@@ -0,0 +1,373 @@
+//! 起動中のサーバーの描画を端末から確認・操作するコマンド（`splatoon3-ghost-drawer painting`）
+//!
+//! Web UIから開始した描画を、SSHなどからサービスごと止めずに一時停止・停止できるようにする。
+//! 応答はサーバーと同じDTOで読むため、APIの形が変わればコマンドもコンパイル時に追従する。
+//! 終了コードで描画に影響したかを返す（描画中でなければ1、サーバーに届かなければ2）。
+
+use crate::interfaces::monitor::format_duration;
+use crate::interfaces::web::dto::{ErrorResponse, PaintingSignalResponse, PaintingStatus};
+use serde_json::Value;
+use std::time::Duration;
+use thiserror::Error;
+
+/// サーバーへの1回の要求の待ち時間（停止・一時停止は描画スレッドの確認を最大2秒待つ）
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 描画に影響しなかった場合の終了コード（`status` では描画中でない場合）
+pub const EXIT_UNAFFECTED: i32 = 1;
+/// サーバーに届かない・要求が拒否された場合の終了コード
+pub const EXIT_FAILED: i32 = 2;
+
+/// 描画への操作
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaintingAction {
+    Status,
+    Pause,
+    Resume,
+    Stop,
+}
+
+#[derive(Debug, Error)]
+pub enum PaintingClientError {
+    #[error("Failed to reach {url}: {source}")]
+    Unreachable { url: String, source: reqwest::Error },
+    #[error("Server returned {status}: {message}")]
+    Rejected { status: u16, message: String },
+    #[error("Unexpected response from {url}: {source}")]
+    InvalidResponse { url: String, source: reqwest::Error },
+}
+
+/// 操作の結果
+#[derive(Debug)]
+pub enum PaintingCommandOutcome {
+    Status {
+        status: Box<PaintingStatus>,
+        /// 描画中のアートワークの名前（取得できなければ `None`）
+        artwork_name: Option<String>,
+    },
+    /// 停止・一時停止・再開の結果（描画中でない、または既にその状態なら `success: false`）
+    Signal(PaintingSignalResponse),
+}
+
+impl PaintingCommandOutcome {
+    /// 描画中の描画に影響したか（`status` では描画中か）
+    pub fn affected(&self) -> bool {
+        match self {
+            PaintingCommandOutcome::Status { status, .. } => status.active,
+            PaintingCommandOutcome::Signal(response) => response.success,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        if self.affected() { 0 } else { EXIT_UNAFFECTED }
+    }
+
+    /// 端末に表示する文言
+    pub fn summary_lines(&self) -> Vec<String> {
+        match self {
+            PaintingCommandOutcome::Status {
+                status,
+                artwork_name,
+            } => status_lines(status, artwork_name.as_deref()),
+            PaintingCommandOutcome::Signal(response) => {
+                let mut line = response.message.clone();
+                if let Some(generation) = response.generation {
+                    line.push_str(&format!(" (run {generation})"));
+                }
+                if response.success && !response.acknowledged {
+                    line.push_str("; not acknowledged yet, it takes effect at the next dot");
+                }
+                vec![line]
+            }
+        }
+    }
+}
+
+/// 描画の状態を表示する行
+fn status_lines(status: &PaintingStatus, artwork_name: Option<&str>) -> Vec<String> {
+    let mut lines = Vec::new();
+    if status.active {
+        let state = if status.paused { "paused" } else { "running" };
+        lines.push(match status.generation {
+            Some(generation) => format!("Painting: {state} (run {generation})"),
+            None => format!("Painting: {state}"),
+        });
+        if let Some(artwork_id) = &status.artwork_id {
+            lines.push(match artwork_name {
+                Some(name) => format!("Artwork:  {name} ({artwork_id})"),
+                None => format!("Artwork:  {artwork_id}"),
+            });
+        }
+        lines.push(match status.total_dots {
+            Some(total) if total > 0 => format!(
+                "Progress: {} / {} dots ({:.1}%)",
+                status.painted,
+                total,
+                status.painted as f64 * 100.0 / total as f64
+            ),
+            _ => format!("Progress: {} dots", status.painted),
+        });
+        lines.push(format!(
+            "ETA:      {}",
+            status.eta_seconds.map_or("-".to_string(), format_duration)
+        ));
+        if let Some(preflight) = &status.preflight {
+            lines.push(format!("Preflight: {}", preflight.state));
+        }
+    } else {
+        lines.push("Painting: idle (no active painting)".to_string());
+    }
+    if let Some(scheduled) = &status.scheduled {
+        lines.push(format!(
+            "Scheduled: {} at {} (in {})",
+            scheduled.artwork_id,
+            scheduled.start_at,
+            format_duration(scheduled.seconds_remaining)
+        ));
+    }
+    if let Some(run) = &status.last_run {
+        lines.push(format!(
+            "Last run: {} {}, {} / {} dots",
+            run.artwork_id,
+            run.outcome
+                .as_ref()
+                .map_or("unknown", |outcome| outcome.kind()),
+            run.dots_painted,
+            run.dots_attempted
+        ));
+    }
+    lines
+}
+
+/// 起動中のサーバーの描画APIのクライアント
+#[derive(Debug, Clone)]
+pub struct PaintingClient {
+    base_url: String,
+    /// 変更系APIに付けるアクセストークン（`--no-auth` のサーバーでは不要）
+    token: Option<String>,
+    http: reqwest::Client,
+}
+
+impl PaintingClient {
+    /// `base_url` は `http://127.0.0.1:8080` の形
+    pub fn new(base_url: impl Into<String>, token: Option<String>) -> Self {
+        Self {
+            base_url: base_url.into(),
+            token,
+            http: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    pub async fn run(
+        &self,
+        action: PaintingAction,
+    ) -> Result<PaintingCommandOutcome, PaintingClientError> {
+        match action {
+            PaintingAction::Status => {
+                let status = self.status().await?;
+                let artwork_name = match &status.artwork_id {
+                    Some(id) => self.artwork_name(id).await,
+                    None => None,
+                };
+                Ok(PaintingCommandOutcome::Status {
+                    status: Box::new(status),
+                    artwork_name,
+                })
+            }
+            PaintingAction::Pause => self.set_paused(true).await,
+            PaintingAction::Resume => self.set_paused(false).await,
+            PaintingAction::Stop => {
+                let response = self.post_signal("/api/painting/stop", &[]).await?;
+                Ok(PaintingCommandOutcome::Signal(response))
+            }
+        }
+    }
+
+    pub async fn status(&self) -> Result<PaintingStatus, PaintingClientError> {
+        let url = format!("{}/api/painting/status", self.base_url);
+        let response = self.http.get(&url).send().await;
+        read_json(&url, response).await
+    }
+
+    /// アートワークの名前（取得できなくても状態の表示は続ける）
+    async fn artwork_name(&self, id: &str) -> Option<String> {
+        let url = format!("{}/api/artworks/{id}", self.base_url);
+        let artwork: Value = read_json(&url, self.http.get(&url).send().await)
+            .await
+            .ok()?;
+        artwork["name"].as_str().map(str::to_string)
+    }
+
+    /// 状態を確かめてから、実行中の描画を世代番号で指定して一時停止・再開する
+    ///
+    /// 切り替え（`paused` の省略）は使わないため、既にその状態なら何も送らずに `success: false` を返す。
+    async fn set_paused(
+        &self,
+        paused: bool,
+    ) -> Result<PaintingCommandOutcome, PaintingClientError> {
+        let status = self.status().await?;
+        let unaffected = |message: &str| {
+            PaintingCommandOutcome::Signal(PaintingSignalResponse {
+                success: false,
+                message: message.to_string(),
+                generation: status.generation,
+                acknowledged: false,
+                paused: status.active.then_some(status.paused),
+            })
+        };
+        if !status.active {
+            return Ok(unaffected("No active painting found"));
+        }
+        if status.paused == paused {
+            return Ok(unaffected(if paused {
+                "Painting is already paused"
+            } else {
+                "Painting is not paused"
+            }));
+        }
+
+        let mut query = vec![("paused", paused.to_string())];
+        if let Some(generation) = status.generation {
+            query.push(("generation", generation.to_string()));
+        }
+        match self.post_signal("/api/painting/pause", &query).await {
+            // 状態を確かめた後に描画が終わった
+            Err(PaintingClientError::Rejected { status: 410, .. }) => {
+                Ok(unaffected("No active painting found"))
+            }
+            result => result.map(PaintingCommandOutcome::Signal),
+        }
+    }
+
+    async fn post_signal(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<PaintingSignalResponse, PaintingClientError> {
+        let url = format!("{}{path}", self.base_url);
+        let mut request = self.http.post(&url).query(query);
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        read_json(&url, request.send().await).await
+    }
+}
+
+/// 成功した応答の本文を読み、失敗した応答はサーバーのエラーの内容にする
+async fn read_json<T: serde::de::DeserializeOwned>(
+    url: &str,
+    response: Result<reqwest::Response, reqwest::Error>,
+) -> Result<T, PaintingClientError> {
+    let response = response.map_err(|source| PaintingClientError::Unreachable {
+        url: url.to_string(),
+        source,
+    })?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        let message = serde_json::from_str::<ErrorResponse>(&body)
+            .map(|error| error.message)
+            .unwrap_or(body);
+        return Err(PaintingClientError::Rejected {
+            status: status.as_u16(),
+            message,
+        });
+    }
+    response
+        .json()
+        .await
+        .map_err(|source| PaintingClientError::InvalidResponse {
+            url: url.to_string(),
+            source,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn idle_status() -> PaintingStatus {
+        serde_json::from_value(serde_json::json!({
+            "active": false,
+            "paused": false,
+            "painted": 0,
+            "config": null,
+            "last_run": null,
+            "generation": null,
+            "scheduled": null,
+            "stop_after": null,
+            "preflight": null,
+            "artwork_id": null,
+            "total_dots": null,
+            "eta_seconds": null
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_status_lines_show_artwork_progress_and_eta() {
+        let status = PaintingStatus {
+            active: true,
+            paused: true,
+            painted: 120,
+            generation: Some(3),
+            artwork_id: Some("abc".to_string()),
+            total_dots: Some(4800),
+            eta_seconds: Some(754.0),
+            ..idle_status()
+        };
+        let outcome = PaintingCommandOutcome::Status {
+            status: Box::new(status),
+            artwork_name: Some("Squid".to_string()),
+        };
+        assert_eq!(
+            outcome.summary_lines(),
+            vec![
+                "Painting: paused (run 3)",
+                "Artwork:  Squid (abc)",
+                "Progress: 120 / 4800 dots (2.5%)",
+                "ETA:      12m 34s",
+            ]
+        );
+        assert_eq!(outcome.exit_code(), 0);
+
+        let idle = PaintingCommandOutcome::Status {
+            status: Box::new(idle_status()),
+            artwork_name: None,
+        };
+        assert_eq!(
+            idle.summary_lines(),
+            vec!["Painting: idle (no active painting)"]
+        );
+        assert_eq!(idle.exit_code(), EXIT_UNAFFECTED);
+    }
+
+    #[test]
+    fn test_signal_exit_code_reflects_whether_the_run_was_affected() {
+        let stopped = PaintingCommandOutcome::Signal(PaintingSignalResponse {
+            success: true,
+            message: "Stop requested".to_string(),
+            generation: Some(2),
+            acknowledged: false,
+            paused: None,
+        });
+        assert_eq!(stopped.exit_code(), 0);
+        assert_eq!(
+            stopped.summary_lines(),
+            vec!["Stop requested (run 2); not acknowledged yet, it takes effect at the next dot"]
+        );
+
+        let idle = PaintingCommandOutcome::Signal(PaintingSignalResponse {
+            success: false,
+            message: "No active painting found".to_string(),
+            generation: None,
+            acknowledged: false,
+            paused: None,
+        });
+        assert_eq!(idle.exit_code(), EXIT_UNAFFECTED);
+        assert_eq!(idle.summary_lines(), vec!["No active painting found"]);
+    }
+}
@@ -0,0 +1,298 @@
+//! アートワークごとの操作の排他
+//!
+//! 描画・パスの計算は読み込んだ時点のキャンバスで進むため、その間にキャンバスを書き換えたり
+//! アートワークを削除したりすると、実行中の処理が既に存在しない内容を扱うことになる。
+//! 読み取りの操作は同時にいくつでも実行できるが、キャンバスの変更と削除は他の操作が無い場合だけ
+//! 受け付ける。どちらも待たずに409を返し、実行中の操作を伝える。
+//!
+//! 名前・タグ・描画設定の変更はキャンバスを読む処理に影響しないため対象にしない。
+
+use super::error_response::ErrorResponse;
+use crate::domain::artwork::entities::ArtworkId;
+use crate::domain::shared::i18n::{Locale, Message, MessageKey};
+use axum::http::StatusCode;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// アートワークで実行中の操作
+#[derive(Debug, Default)]
+struct LockEntry {
+    /// 読み取りの操作（取得した順、識別番号と内容）
+    readers: Vec<(u64, MessageKey)>,
+    /// 変更・削除の操作
+    writer: Option<MessageKey>,
+}
+
+impl LockEntry {
+    fn is_empty(&self) -> bool {
+        self.readers.is_empty() && self.writer.is_none()
+    }
+
+    /// 実行中の操作の内容（変更・削除を優先する）
+    fn held_by(&self) -> Option<MessageKey> {
+        self.writer
+            .or_else(|| self.readers.first().map(|(_, operation)| *operation))
+    }
+}
+
+/// 操作を開始できなかった理由
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArtworkLockConflict {
+    pub id: String,
+    /// 開始しようとした操作
+    pub requested: MessageKey,
+    /// 実行中の操作
+    pub held_by: MessageKey,
+}
+
+impl ArtworkLockConflict {
+    pub fn message(&self) -> Message {
+        Message::new(MessageKey::ArtworkBusy)
+            .with_arg("id", &self.id)
+            .with_key_arg("requested", self.requested)
+            .with_key_arg("held_by", self.held_by)
+    }
+}
+
+impl fmt::Display for ArtworkLockConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message().render(Locale::En))
+    }
+}
+
+impl std::error::Error for ArtworkLockConflict {}
+
+impl From<ArtworkLockConflict> for ErrorResponse {
+    fn from(conflict: ArtworkLockConflict) -> Self {
+        ErrorResponse::localized(StatusCode::CONFLICT, conflict.message())
+    }
+}
+
+/// アートワークIDごとの操作の登録先（複製してもすべてのハンドルで同じ登録先を共有する）
+///
+/// 登録は操作の終了（ガードの破棄）で取り消し、操作が残っていないアートワークの項目は消すため、
+/// 削除したアートワークの項目は残らない。
+#[derive(Debug, Clone, Default)]
+pub struct ArtworkLocks {
+    entries: Arc<Mutex<HashMap<String, LockEntry>>>,
+    next_token: Arc<AtomicU64>,
+}
+
+impl ArtworkLocks {
+    fn lock(&self) -> MutexGuard<'_, HashMap<String, LockEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 大文字のUUIDなど、同じアートワークを指す別の表記を同じ項目にまとめる
+    fn key(id: &str) -> String {
+        ArtworkId::parse(id).map_or_else(|_| id.to_string(), |id| id.as_str())
+    }
+
+    /// キャンバスを読む操作を登録する（変更・削除の実行中は409）
+    pub fn read(
+        &self,
+        id: &str,
+        operation: MessageKey,
+    ) -> Result<ArtworkReadGuard, ArtworkLockConflict> {
+        let key = Self::key(id);
+        let mut entries = self.lock();
+        let entry = entries.entry(key.clone()).or_default();
+        if let Some(writer) = entry.writer {
+            return Err(ArtworkLockConflict {
+                id: key,
+                requested: operation,
+                held_by: writer,
+            });
+        }
+        let token = self.next_token.fetch_add(1, Ordering::Relaxed);
+        entry.readers.push((token, operation));
+        Ok(ArtworkReadGuard {
+            locks: self.clone(),
+            key,
+            token,
+        })
+    }
+
+    /// キャンバスの変更・削除を登録する（他の操作の実行中は409）
+    pub fn write(
+        &self,
+        id: &str,
+        operation: MessageKey,
+    ) -> Result<ArtworkWriteGuard, ArtworkLockConflict> {
+        let key = Self::key(id);
+        let mut entries = self.lock();
+        let entry = entries.entry(key.clone()).or_default();
+        if let Some(held_by) = entry.held_by() {
+            return Err(ArtworkLockConflict {
+                held_by,
+                id: key,
+                requested: operation,
+            });
+        }
+        entry.writer = Some(operation);
+        Ok(ArtworkWriteGuard {
+            locks: self.clone(),
+            key,
+        })
+    }
+
+    /// アートワークで実行中の操作（変更・削除、読み取りの順）
+    #[cfg(test)]
+    pub fn operations(&self, id: &str) -> Vec<MessageKey> {
+        self.lock()
+            .get(&Self::key(id))
+            .map(|entry| {
+                entry
+                    .writer
+                    .into_iter()
+                    .chain(entry.readers.iter().map(|(_, operation)| *operation))
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// 実行中の操作が無いか
+    #[cfg(test)]
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    fn release(&self, key: &str, release: impl FnOnce(&mut LockEntry)) {
+        let mut entries = self.lock();
+        if let Some(entry) = entries.get_mut(key) {
+            release(entry);
+            if entry.is_empty() {
+                entries.remove(key);
+            }
+        }
+    }
+}
+
+/// 読み取りの操作の登録（破棄すると取り消す）
+#[derive(Debug)]
+pub struct ArtworkReadGuard {
+    locks: ArtworkLocks,
+    key: String,
+    token: u64,
+}
+
+impl Drop for ArtworkReadGuard {
+    fn drop(&mut self) {
+        let token = self.token;
+        self.locks.release(&self.key, |entry| {
+            entry.readers.retain(|(reader, _)| *reader != token);
+        });
+    }
+}
+
+/// 変更・削除の操作の登録（破棄すると取り消す）
+#[derive(Debug)]
+pub struct ArtworkWriteGuard {
+    locks: ArtworkLocks,
+    key: String,
+}
+
+impl Drop for ArtworkWriteGuard {
+    fn drop(&mut self) {
+        self.locks.release(&self.key, |entry| entry.writer = None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use MessageKey::*;
+
+    #[test]
+    fn test_readers_share_and_writers_are_exclusive() {
+        let locks = ArtworkLocks::default();
+        let id = ArtworkId::generate().as_str();
+
+        let path = locks.read(&id, OperationPathComputation).unwrap();
+        let painting = locks.read(&id.to_uppercase(), OperationPainting).unwrap();
+        assert_eq!(
+            locks.operations(&id),
+            [OperationPathComputation, OperationPainting]
+        );
+
+        let conflict = locks.write(&id, OperationDeletion).unwrap_err();
+        assert_eq!(conflict.held_by, OperationPathComputation);
+        assert_eq!(conflict.requested, OperationDeletion);
+        assert_eq!(
+            conflict.to_string(),
+            format!(
+                "Cannot start deletion of artwork {id}: drawing path computation is in progress"
+            )
+        );
+        drop(path);
+        let conflict = locks.write(&id, OperationCanvasEdit).unwrap_err();
+        assert_eq!(conflict.held_by, OperationPainting);
+        drop(painting);
+
+        let edit = locks.write(&id, OperationCanvasEdit).unwrap();
+        assert_eq!(
+            locks.read(&id, OperationPainting).unwrap_err().held_by,
+            OperationCanvasEdit
+        );
+        assert_eq!(
+            locks.write(&id, OperationDeletion).unwrap_err().held_by,
+            OperationCanvasEdit
+        );
+        // 別のアートワークには影響しない
+        let other = locks
+            .write(&ArtworkId::generate().as_str(), OperationDeletion)
+            .unwrap();
+        drop(other);
+        drop(edit);
+        assert!(locks.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_operations_leave_no_entries_behind() {
+        use std::sync::atomic::AtomicIsize;
+
+        let locks = ArtworkLocks::default();
+        let ids: Vec<String> = (0..4).map(|_| ArtworkId::generate().as_str()).collect();
+        // アートワークごとの実行中の読み取りの数（変更中は -1）
+        let holders: Arc<Vec<AtomicIsize>> =
+            Arc::new((0..ids.len()).map(|_| AtomicIsize::new(0)).collect());
+
+        let mut tasks = Vec::new();
+        for task in 0..32 {
+            let locks = locks.clone();
+            let holders = holders.clone();
+            let index = task % ids.len();
+            let id = ids[index].clone();
+            tasks.push(tokio::spawn(async move {
+                let holder = &holders[index];
+                let mut granted = 0;
+                for round in 0..50 {
+                    if (task + round) % 3 == 0 {
+                        if let Ok(guard) = locks.write(&id, OperationCanvasEdit) {
+                            assert_eq!(holder.swap(-1, Ordering::SeqCst), 0);
+                            granted += 1;
+                            tokio::task::yield_now().await;
+                            holder.store(0, Ordering::SeqCst);
+                            drop(guard);
+                        }
+                    } else if let Ok(guard) = locks.read(&id, OperationPathComputation) {
+                        assert!(holder.fetch_add(1, Ordering::SeqCst) >= 0);
+                        granted += 1;
+                        tokio::task::yield_now().await;
+                        holder.fetch_sub(1, Ordering::SeqCst);
+                        drop(guard);
+                    }
+                }
+                granted
+            }));
+        }
+        let mut granted = 0;
+        for task in tasks {
+            granted += task.await.unwrap();
+        }
+        assert!(granted > 0);
+        assert!(locks.is_empty(), "released operations must not be kept");
+    }
+}
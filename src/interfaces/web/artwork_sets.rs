@@ -0,0 +1,607 @@
+//! アニメーション画像をフレームごとに分割して作成したアートワークの組のAPIハンドラー
+//!
+//! 組は各アートワークに記録した `set_id` で表し、組そのものは保存しない。
+
+use super::artworks::{
+    ArtworkResponse, ArtworkSummary, EdgeDetectionSummary, discard_artwork, fit_canvas,
+    remove_artwork,
+};
+use super::dto::ApiResponse;
+use super::error_response::ErrorResponse;
+use super::state::ArtworkState;
+use crate::domain::artwork::entities::{Artwork, ArtworkMetadata, ArtworkSetMembership};
+use crate::domain::artwork::repositories::ArtworkQuery;
+use crate::domain::artwork::services::{ConversionPipeline, ImageProcessingService};
+use crate::domain::artwork::value_objects::{
+    EdgeDetection, Gravity, ImageAdjustments, Resolution, RgbaImage, ScalingMode, Transparency,
+};
+use crate::domain::shared::events::EventMetadata;
+use crate::domain::shared::i18n::MessageKey;
+use crate::infrastructure::animation::{
+    AnimationError, MAX_ANIMATION_FRAMES, decode_frames, detect_animation,
+};
+use axum::{
+    Json,
+    extract::{Path, State},
+    http::StatusCode,
+};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// フレームの幅・高さの上限（`POST /api/artworks` のキャンバスと同じ）
+const MAX_FRAME_SIDE: u32 = 1000;
+
+/// 組のアートワーク
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArtworkSetResponse {
+    pub set_id: String,
+    /// 分割したときのフレーム数
+    pub frames: u32,
+    /// 残っているアートワーク（フレーム順、個別に削除したフレームは含まない）
+    pub artworks: Vec<ArtworkSummary>,
+}
+
+/// 組のアートワークをフレーム順に取得する（1つもなければ404）
+async fn set_members(state: &ArtworkState, set_id: &str) -> Result<Vec<Artwork>, ErrorResponse> {
+    let mut artworks = state
+        .artworks
+        .search(&ArtworkQuery::by_set_id(set_id.to_string()))
+        .await?
+        .artworks;
+    if artworks.is_empty() {
+        return Err(ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            format!("Artwork set {set_id} not found"),
+        ));
+    }
+    artworks.sort_by_key(|artwork| artwork.artwork_set.as_ref().map(|set| set.frame));
+    Ok(artworks)
+}
+
+/// 組のアートワークのID（フレーム順）
+pub(crate) async fn set_member_ids(
+    state: &ArtworkState,
+    set_id: &str,
+) -> Result<Vec<String>, ErrorResponse> {
+    Ok(set_members(state, set_id)
+        .await?
+        .iter()
+        .map(|artwork| artwork.id.as_str())
+        .collect())
+}
+
+/// List the artworks created from the frames of one animated image
+#[utoipa::path(
+    get, path = "/api/artwork-sets/{set_id}", tag = "artworks",
+    params(("set_id" = String, Path, description = "組のID")),
+    responses(
+        (status = 200, body = ArtworkSetResponse),
+        (status = 404, description = "組のアートワークが残っていない", body = ErrorResponse)
+    )
+)]
+pub async fn get_artwork_set(
+    State(state): State<Arc<ArtworkState>>,
+    Path(set_id): Path<String>,
+) -> Result<Json<ArtworkSetResponse>, ErrorResponse> {
+    let artworks = set_members(&state, &set_id).await?;
+    Ok(Json(ArtworkSetResponse {
+        frames: artworks
+            .iter()
+            .find_map(|artwork| artwork.artwork_set.as_ref().map(|set| set.frames))
+            .unwrap_or_default(),
+        artworks: artworks.iter().map(ArtworkSummary::from).collect(),
+        set_id,
+    }))
+}
+
+/// Delete every artwork of a set
+#[utoipa::path(
+    delete, path = "/api/artwork-sets/{set_id}", tag = "artworks",
+    params(("set_id" = String, Path, description = "組のID")),
+    responses(
+        (status = 200, body = ApiResponse),
+        (status = 404, description = "組のアートワークが残っていない", body = ErrorResponse),
+        (status = 409, description = "組のアートワークを描画中、またはパスの計算中", body = ErrorResponse)
+    )
+)]
+pub async fn delete_artwork_set(
+    State(state): State<Arc<ArtworkState>>,
+    Path(set_id): Path<String>,
+) -> Result<Json<ApiResponse>, ErrorResponse> {
+    let artworks = set_members(&state, &set_id).await?;
+    // 1つでも描画中・パスの計算中なら、どれも削除しない
+    let _locks = artworks
+        .iter()
+        .map(|artwork| {
+            state
+                .artwork_locks
+                .write(&artwork.id.as_str(), MessageKey::OperationDeletion)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for artwork in &artworks {
+        discard_artwork(&state, &artwork.id).await?;
+    }
+    info!(
+        "Artwork set {} deleted ({} artworks)",
+        set_id,
+        artworks.len()
+    );
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Deleted {} artworks of set {set_id}", artworks.len()),
+    }))
+}
+
+/// アニメーション画像をフレームごとのアートワークにして保存する
+///
+/// アニメーションでなければ何も作らずに `None` を返す。フレームは1枚ずつデコードして2値化し、
+/// 通常のアップロードと同じく空白の除去・中央配置を適用する。名前は「`name` [frame 3/12]」にする。
+/// `edge_detection` を指定すると輪郭だけをドットにし、塗りつぶした場合とのドット数を応答に含める。
+/// 透明・半透明の画素は `transparency` に従って扱う。
+#[allow(clippy::too_many_arguments)]
+pub(crate) async fn upload_frames(
+    state: &ArtworkState,
+    metadata: ArtworkMetadata,
+    image_data: Vec<u8>,
+    auto_trim: bool,
+    center_on_canvas: bool,
+    edge_detection: Option<EdgeDetection>,
+    transparency: Transparency,
+    (scaling_mode, gravity): (ScalingMode, Gravity),
+) -> Result<Option<ArtworkResponse>, ErrorResponse> {
+    let decoded = tokio::task::spawn_blocking(move || {
+        let format = detect_animation(&image_data);
+        let adjustments = ImageAdjustments::default();
+        let target = Resolution::splatoon3_standard();
+        let frames = decode_frames(&image_data, MAX_ANIMATION_FRAMES, MAX_FRAME_SIDE, |frame| {
+            // 変換の前に、画像をキャンバスの大きさに合わせて置く
+            let source = Resolution {
+                width: frame.width as u32,
+                height: frame.height as u32,
+            };
+            let placement = source.place_in(&target, scaling_mode, gravity);
+            let placed = ConversionPipeline::place(
+                &RgbaImage::new(source.width, source.height, frame.rgba.to_vec()),
+                &target,
+                &placement,
+            );
+            let (width, height) = (target.width as u16, target.height as u16);
+            let filled = ImageProcessingService::threshold_rgba(
+                width,
+                height,
+                &placed.pixels,
+                &adjustments,
+                &transparency,
+            );
+            let filled_dots = filled.dots.len();
+            let canvas = match &edge_detection {
+                Some(edge) => ImageProcessingService::outline_rgba(
+                    width,
+                    height,
+                    &placed.pixels,
+                    &adjustments,
+                    &transparency,
+                    edge,
+                ),
+                None => filled,
+            };
+            fit_canvas(canvas, auto_trim, center_on_canvas)
+                .map(|canvas| (canvas, filled_dots, placement))
+        });
+        format.zip(frames.transpose())
+    })
+    .await
+    .map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Decoding the animation failed: {e}"),
+        )
+    })?;
+    let Some((format, frames)) = decoded else {
+        return Ok(None);
+    };
+    let canvases = frames
+        .map_err(|e| {
+            warn!("Could not split the animation: {}", e);
+            let status = match e {
+                AnimationError::Decode { .. } | AnimationError::UnsupportedFormat => {
+                    StatusCode::BAD_REQUEST
+                }
+                AnimationError::TooManyFrames { .. } | AnimationError::TooLarge { .. } => {
+                    StatusCode::UNPROCESSABLE_ENTITY
+                }
+            };
+            ErrorResponse::new(status, e.to_string())
+        })?
+        .into_iter()
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let edge_summary = edge_detection.map(|_| {
+        EdgeDetectionSummary::new(
+            canvases
+                .iter()
+                .map(|(canvas, _, _)| canvas.dots.len())
+                .sum(),
+            canvases.iter().map(|(_, filled_dots, _)| filled_dots).sum(),
+        )
+    });
+    let placement = canvases.first().map(|(_, _, placement)| *placement);
+
+    let set_id = uuid::Uuid::new_v4().to_string();
+    let frames = canvases.len() as u32;
+    let mut created = Vec::with_capacity(canvases.len());
+    for (index, (canvas, _, _)) in canvases.into_iter().enumerate() {
+        let frame = index as u32 + 1;
+        let mut frame_metadata = metadata.clone();
+        frame_metadata.name = format!("{} [frame {frame}/{frames}]", metadata.name);
+        let mut artwork = Artwork::new(frame_metadata, format.extension().to_string(), canvas);
+        artwork.artwork_set = Some(ArtworkSetMembership {
+            set_id: set_id.clone(),
+            frame,
+            frames,
+        });
+        let id = artwork.id.clone();
+        if let Err(e) = state
+            .insert_artwork(artwork, EventMetadata::new("upload".to_string()))
+            .await
+        {
+            // 途中までの組は残さない
+            for id in &created {
+                let _ = remove_artwork(state, id).await;
+            }
+            return Err(e);
+        }
+        created.push(id);
+    }
+    info!(
+        "Split {} animation '{}' into {} artworks (set {})",
+        format, metadata.name, frames, set_id
+    );
+
+    let artwork_ids: Vec<String> = created.iter().map(|id| id.as_str()).collect();
+    Ok(Some(ArtworkResponse {
+        id: artwork_ids[0].clone(),
+        message: format!(
+            "Image '{}' was split into {frames} frame artworks",
+            metadata.name
+        ),
+        artwork: None,
+        estimated_painting_seconds: None,
+        duplicate: false,
+        warnings: Vec::new(),
+        duplicates_resolved: None,
+        set_id: Some(set_id),
+        artwork_ids,
+        edge_detection: edge_summary,
+        placement,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+    use crate::interfaces::web::test_support::TestClient;
+    use axum::body::Body;
+    use axum::http::{Request, header};
+
+    /// 4x1のGIFで、フレームごとに左から1画素ずつ黒く塗り足していくアニメーション
+    fn animated_gif(frames: usize) -> Vec<u8> {
+        let palette = [255, 255, 255, 0, 0, 0];
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut bytes, 4, 1, &palette).unwrap();
+            for index in 0..frames {
+                let frame = gif::Frame {
+                    left: (index % 4) as u16,
+                    width: 1,
+                    height: 1,
+                    dispose: gif::DisposalMethod::Keep,
+                    buffer: std::borrow::Cow::Owned(vec![1]),
+                    ..Default::default()
+                };
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+        bytes
+    }
+
+    /// 12x12の中央に8x8の黒い正方形を描いたGIFを、2フレーム続けたアニメーション
+    fn square_gif() -> Vec<u8> {
+        let palette = [255, 255, 255, 0, 0, 0];
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut bytes, 12, 12, &palette).unwrap();
+            let square: Vec<u8> = (0..144)
+                .map(|index| {
+                    u8::from((2..10).contains(&(index % 12)) && (2..10).contains(&(index / 12)))
+                })
+                .collect();
+            for _ in 0..2 {
+                let frame = gif::Frame {
+                    width: 12,
+                    height: 12,
+                    buffer: std::borrow::Cow::Owned(square.clone()),
+                    ..Default::default()
+                };
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+        bytes
+    }
+
+    /// 半分を黒く塗ったGIFを2フレーム続けたアニメーション（縦長なら上半分、横長なら左半分）
+    fn half_black_gif(width: u16, height: u16) -> Vec<u8> {
+        let palette = [255, 255, 255, 0, 0, 0];
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = gif::Encoder::new(&mut bytes, width, height, &palette).unwrap();
+            let pixels: Vec<u8> = (0..height)
+                .flat_map(|y| {
+                    (0..width).map(move |x| {
+                        u8::from(if height > width {
+                            y < height / 2
+                        } else {
+                            x < width / 2
+                        })
+                    })
+                })
+                .collect();
+            for _ in 0..2 {
+                let frame = gif::Frame {
+                    width,
+                    height,
+                    buffer: std::borrow::Cow::Owned(pixels.clone()),
+                    ..Default::default()
+                };
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+        bytes
+    }
+
+    fn upload(path: &str, name: &str, file: &[u8]) -> Request<Body> {
+        let mut body = format!(
+            "--b\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\n{name}\r\n\
+             --b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"anim.gif\"\r\n\
+             Content-Type: image/gif\r\n\r\n"
+        )
+        .into_bytes();
+        body.extend_from_slice(file);
+        body.extend_from_slice(b"\r\n--b--\r\n");
+        Request::builder()
+            .method("POST")
+            .uri(path)
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=b")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_animated_upload_is_split_into_an_artwork_set() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let client = TestClient::new(state.clone());
+        let path = "/api/artworks/upload?split_frames=true";
+
+        let response = client.send(upload(path, "anim", &animated_gif(3))).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let created = response.json();
+        let set_id = created["set_id"].as_str().unwrap().to_string();
+        let ids = created["artwork_ids"].as_array().unwrap().clone();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(created["id"], ids[0]);
+
+        let response = client.get(&format!("/api/artwork-sets/{set_id}")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let set = response.json();
+        assert_eq!(set["frames"], 3);
+        let artworks = set["artworks"].as_array().unwrap();
+        let names: Vec<_> = artworks.iter().map(|a| a["name"].clone()).collect();
+        assert_eq!(
+            names,
+            ["anim [frame 1/3]", "anim [frame 2/3]", "anim [frame 3/3]"]
+        );
+        // 前のフレームに重ねて描いた状態が各フレームになる
+        let dots: Vec<_> = artworks
+            .iter()
+            .map(|a| a["drawable_dots"].clone())
+            .collect();
+        assert_eq!(dots, [1, 2, 3]);
+        assert_eq!(artworks[1]["artwork_set"]["frame"], 2);
+        assert_eq!(artworks[0]["format"], "gif");
+
+        // 同じファイルは組ごと既存のものを返す
+        let duplicate = client
+            .send(upload(path, "again", &animated_gif(3)))
+            .await
+            .json();
+        assert_eq!(duplicate["duplicate"], true);
+        assert_eq!(duplicate["set_id"], set_id.as_str());
+        assert_eq!(duplicate["artwork_ids"].as_array().unwrap(), &ids);
+
+        // アニメーションでなければ通常どおり1つ作る
+        let still = client.send(upload(path, "still", b"abc")).await.json();
+        assert!(still.get("set_id").is_none());
+        assert_eq!(state.artworks.count().await.unwrap(), 4);
+
+        let response = client.delete(&format!("/api/artwork-sets/{set_id}")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(state.artworks.count().await.unwrap(), 1);
+        let response = client.get(&format!("/api/artwork-sets/{set_id}")).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        let response = client.delete(&format!("/api/artwork-sets/{set_id}")).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_edge_detect_keeps_only_outlines_and_reports_the_reduction() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let client = TestClient::new(state.clone());
+
+        let response = client
+            .send(upload(
+                "/api/artworks/upload?split_frames=true&edge_detect=true&allow_duplicate=true",
+                "outline",
+                &square_gif(),
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let created = response.json();
+        // 8x8の正方形は塗りつぶすと64ドット、輪郭は28ドット（2フレーム分）
+        assert_eq!(created["edge_detection"]["dots"], 56);
+        assert_eq!(created["edge_detection"]["filled_dots"], 128);
+        assert_eq!(created["edge_detection"]["reduction_percent"], 56.3);
+        let id = created["artwork_ids"][1].as_str().unwrap();
+        let artwork = state.find_artwork(id).await.unwrap().unwrap();
+        assert_eq!(artwork.canvas.dots.len(), 28);
+
+        // 塗りつぶしの変換では比較を返さない
+        let filled = client
+            .send(upload(
+                "/api/artworks/upload?split_frames=true&allow_duplicate=true",
+                "filled",
+                &square_gif(),
+            ))
+            .await
+            .json();
+        assert!(filled.get("edge_detection").is_none());
+
+        // 静止画はサーバーで変換しない
+        let response = client
+            .send(upload(
+                "/api/artworks/upload?split_frames=true&edge_detect=true",
+                "still",
+                b"abc",
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(response.message().contains("edge_detect"));
+    }
+
+    #[tokio::test]
+    async fn test_scaling_mode_places_frames_on_the_canvas() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let client = TestClient::new(state.clone());
+        let portrait = half_black_gif(100, 400);
+        let panorama = half_black_gif(800, 50);
+
+        // (画像, クエリ, 置いた位置と大きさ, ドットを囲む矩形（左上と右下、いずれも含む）)
+        let cases = [
+            (&portrait, "", [145, 0, 30, 120], [145, 0, 174, 59]),
+            (
+                &portrait,
+                "&scaling_mode=fill",
+                [0, -580, 320, 1280],
+                [0, 0, 319, 59],
+            ),
+            (
+                &portrait,
+                "&scaling_mode=fill&gravity=top",
+                [0, 0, 320, 1280],
+                [0, 0, 319, 119],
+            ),
+            (
+                &portrait,
+                "&scaling_mode=stretch",
+                [0, 0, 320, 120],
+                [0, 0, 319, 59],
+            ),
+            (
+                &panorama,
+                "&scaling_mode=fit",
+                [0, 50, 320, 20],
+                [0, 50, 159, 69],
+            ),
+            (
+                &panorama,
+                "&scaling_mode=fill",
+                [-800, 0, 1920, 120],
+                [0, 0, 159, 119],
+            ),
+            (
+                &panorama,
+                "&scaling_mode=fill&gravity=left",
+                [0, 0, 1920, 120],
+                [0, 0, 319, 119],
+            ),
+            (
+                &panorama,
+                "&scaling_mode=stretch",
+                [0, 0, 320, 120],
+                [0, 0, 159, 119],
+            ),
+        ];
+        for (image, query, placement, bounds) in cases {
+            let path =
+                format!("/api/artworks/upload?split_frames=true&allow_duplicate=true{query}");
+            let response = client.send(upload(&path, "scaled", image)).await;
+            assert_eq!(
+                response.status,
+                StatusCode::OK,
+                "{query}: {}",
+                response.text()
+            );
+            let created = response.json();
+            assert_eq!(
+                created["placement"],
+                serde_json::json!({
+                    "offset_x": placement[0],
+                    "offset_y": placement[1],
+                    "width": placement[2],
+                    "height": placement[3],
+                }),
+                "{query}"
+            );
+            for id in created["artwork_ids"].as_array().unwrap() {
+                let artwork = state
+                    .find_artwork(id.as_str().unwrap())
+                    .await
+                    .unwrap()
+                    .unwrap();
+                assert_eq!((artwork.canvas.width, artwork.canvas.height), (320, 120));
+                let (min, max) = artwork.canvas.bounding_box().unwrap();
+                assert_eq!([min.x, min.y, max.x, max.y], bounds, "{query}");
+            }
+        }
+
+        let response = client
+            .send(upload(
+                "/api/artworks/upload?split_frames=true&allow_duplicate=true&scaling_mode=zoom",
+                "scaled",
+                &portrait,
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_too_many_frames_are_rejected_without_creating_artworks() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let client = TestClient::new(state.clone());
+
+        let response = client
+            .send(upload(
+                "/api/artworks/upload?split_frames=true",
+                "long",
+                &animated_gif(MAX_ANIMATION_FRAMES + 1),
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(
+            response.message().contains("50 frames"),
+            "{}",
+            response.text()
+        );
+        assert_eq!(state.artworks.count().await.unwrap(), 0);
+    }
+}
@@ -0,0 +1,4817 @@
+//! アートワークの作成・編集・取得のAPIハンドラー
+
+use super::artwork_locks::ArtworkWriteGuard;
+use super::artwork_sets::{set_member_ids, upload_frames};
+use super::dto::{
+    ApiResponse, EstimateAccuracy, LayerStats, StrategyComparisonResponse, StrategyStats,
+};
+use super::error_response::ErrorResponse;
+use super::etag::{ETag, conditional_json};
+use super::painting::{PaintRequest, drawing_config, parse_origin, parse_region, validate_origin};
+use super::path_computations::PathComputationKind;
+use super::state::ArtworkState;
+use crate::domain::artwork::canvas_rle::CanvasRun;
+use crate::domain::artwork::change_log::{ArtworkChange, ChangeKind, ChangeLogEntry};
+use crate::domain::artwork::dot_diff::{DotDiff, DotDiffError};
+use crate::domain::artwork::entities::{
+    Artwork, ArtworkId, ArtworkMetadata, ArtworkSetMembership, ArtworkStatistics, Canvas,
+    CanvasError, Dot, MetadataError,
+};
+use crate::domain::artwork::history::HistoryDirection;
+use crate::domain::artwork::repositories::{
+    ArtworkQuery, RepositoryError, SortField, SortOrder, TrashedArtwork,
+};
+use crate::domain::artwork::services::ImageProcessingService;
+use crate::domain::artwork::value_objects::{
+    CanvasTransform, ColorReduction, EdgeDetection, Gravity, OrderedMatrixSize, Placement,
+    Polyline, ScalingMode, Transparency, TransparencyMode,
+};
+use crate::domain::events::ArtworkEvent;
+use crate::domain::painting::{
+    ArtworkToCommandConverter, CancellationToken, CanvasRegion, DEFAULT_FIGHTSTICK_FRAME_MS,
+    DEFAULT_SAMPLE_DOTS, DrawingCanvasConfig, DrawingMode, DrawingStrategy, FightstickFormat,
+    FightstickScript, InitPreset, InitSequence, PaintTiming, PaintingPreferences, PathCancelled,
+    RunOptions, ScriptFormat, TwoOptSettings, TwoOptStats, replay_script, sample_row_bands,
+    simulate_layers, simulate_run,
+};
+use crate::domain::shared::events::EventMetadata;
+use crate::domain::shared::i18n::{Message, MessageKey};
+use crate::domain::shared::value_objects::{Color, Coordinates, Timestamp};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::{Multipart, Path, Query, State},
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::Arc;
+use tracing::{debug, error, info, warn};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ArtworkSummary {
+    pub id: String,
+    pub name: String,
+    pub format: String,
+    pub canvas_size: String,
+    pub total_dots: usize,
+    pub drawable_dots: usize,
+    pub completion_ratio: f32,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub description: Option<String>,
+    pub tags: Vec<String>,
+    pub author: Option<String>,
+    /// アップロード時のファイル名
+    pub original_filename: Option<String>,
+    /// アップロードされたファイルのサイズ（バイト、アップロード以外で作成した場合は0）
+    pub file_size: u64,
+    /// アップロードされたファイルのSHA-256（16進数）
+    pub checksum: Option<String>,
+    /// 背景色（`#RRGGBB`）。暗い場合は明るいドットを描く
+    pub background_color: String,
+    /// メモリ上に保持した場合の推定使用量（バイト、保存数の上限の計上に使う）
+    #[serde(default)]
+    pub estimated_memory_bytes: usize,
+    /// このアートワークに保存した描画設定
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub painting_preferences: Option<PaintingPreferences>,
+    /// スティック描画で描く線画
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub vector_paths: Vec<Polyline>,
+    /// アニメーション画像のフレームから作成した場合の組と位置
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub artwork_set: Option<ArtworkSetMembership>,
+}
+
+impl From<&Artwork> for ArtworkSummary {
+    fn from(artwork: &Artwork) -> Self {
+        Self {
+            id: artwork.id.as_str().to_string(),
+            name: artwork.metadata.name.clone(),
+            format: artwork.original_format.clone(),
+            canvas_size: format!("{}x{}", artwork.canvas.width, artwork.canvas.height),
+            total_dots: artwork.total_dots(),
+            drawable_dots: artwork.drawable_dots(),
+            completion_ratio: artwork.completion_ratio() as f32,
+            created_at: artwork.created_at.epoch_millis as i64,
+            updated_at: artwork.updated_at.epoch_millis as i64,
+            description: artwork.metadata.description.clone(),
+            tags: artwork.metadata.tags.clone(),
+            author: artwork.metadata.author.clone(),
+            original_filename: artwork.metadata.original_filename.clone(),
+            file_size: artwork.metadata.file_size,
+            checksum: non_empty(&artwork.metadata.checksum),
+            background_color: artwork.canvas.background_color.to_hex(),
+            estimated_memory_bytes: artwork.estimated_memory_bytes(),
+            painting_preferences: artwork.painting_preferences,
+            vector_paths: artwork.vector_paths.clone(),
+            artwork_set: artwork.artwork_set.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CreateArtworkRequest {
+    pub name: String,
+    pub width: u16,
+    pub height: u16,
+    /// `rows` を指定した場合は空にする
+    #[serde(default)]
+    pub dots: Vec<DotData>,
+    /// `dots` の代わりに送る、行優先のランレングス符号化したドット（`?format=compact` の出力）
+    ///
+    /// 各行は `[start_x, length, color]`（レイヤーが0以外なら末尾に `layer`）の区間の配列で、
+    /// 区間はx座標の昇順に重ならないよう並べる。末尾の空の行は省略できる。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[schema(value_type = Vec<Vec<Vec<Object>>>)]
+    pub rows: Vec<Vec<CanvasRun>>,
+    /// 周囲の空白を取り除く（キャンバスサイズは描画範囲に縮む）
+    #[serde(default)]
+    pub auto_trim: bool,
+    /// 空白を取り除いた上で `width` x `height` の中央に配置する
+    #[serde(default)]
+    pub center_on_canvas: bool,
+    /// 点描では各ドットの色を明るさとして扱い、黒と判定されたドットだけを描画する
+    #[serde(default)]
+    pub tone_mode: ToneMode,
+    /// 送られたドットを背景とみなし、残りの座標を描画する（暗い背景に明るい絵柄）
+    ///
+    /// 省略時はドットがキャンバスの半分より多い場合に反転する
+    #[serde(default)]
+    pub invert_background: Option<bool>,
+    pub description: Option<String>,
+    /// 前後の空白は取り除かれ、重複は1つにまとめられる
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub author: Option<String>,
+    /// アートワークと一緒に保存する描画設定（`GET /api/artworks/{id}` の値をそのまま渡せる）
+    #[serde(default)]
+    pub painting_preferences: Option<PaintingPreferences>,
+    /// スティック描画（`POST /api/artworks/{id}/paint-vector`）で描く線画
+    ///
+    /// 指定した場合は `dots` を空にでき、`auto_trim`・`center_on_canvas` とは同時に指定できない
+    #[serde(default)]
+    pub vector_paths: Vec<Polyline>,
+    /// エクスポートで `include_history=true` を指定した場合の変更履歴（作成時には使わない）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<ChangeLogEntryResponse>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DotData {
+    pub x: u16,
+    pub y: u16,
+    pub color: String,
+    /// 描画パス番号（小さい順に描画）
+    #[serde(default)]
+    pub layer: u8,
+}
+
+impl CreateArtworkRequest {
+    /// キャンバスのドットをそのまま送る作成リクエスト
+    pub fn from_canvas(name: String, canvas: &Canvas) -> Self {
+        let mut dots: Vec<DotData> = canvas
+            .dots
+            .iter()
+            .map(|(coordinates, dot)| DotData {
+                x: coordinates.x,
+                y: coordinates.y,
+                color: dot.color.to_hex(),
+                layer: dot.layer,
+            })
+            .collect();
+        dots.sort_by_key(|dot| (dot.y, dot.x));
+        Self {
+            dots,
+            ..Self::empty(name, canvas)
+        }
+    }
+
+    /// キャンバスのドットをランレングス符号化して送る作成リクエスト
+    pub fn from_canvas_rle(name: String, canvas: &Canvas) -> Self {
+        Self {
+            rows: canvas.encode_rle(),
+            ..Self::empty(name, canvas)
+        }
+    }
+
+    /// 保存済みのアートワークを作り直せる作成リクエスト（`GET /api/artworks/{id}/export`）
+    pub fn from_artwork(artwork: &Artwork, format: CanvasWireFormat) -> Self {
+        let name = artwork.metadata.name.clone();
+        let request = match format {
+            CanvasWireFormat::Dots => Self::from_canvas(name, &artwork.canvas),
+            CanvasWireFormat::Compact => Self::from_canvas_rle(name, &artwork.canvas),
+        };
+        Self {
+            description: artwork.metadata.description.clone(),
+            tags: artwork.metadata.tags.clone(),
+            author: artwork.metadata.author.clone(),
+            painting_preferences: artwork.painting_preferences,
+            vector_paths: artwork.vector_paths.clone(),
+            ..request
+        }
+    }
+
+    fn empty(name: String, canvas: &Canvas) -> Self {
+        Self {
+            name,
+            width: canvas.width,
+            height: canvas.height,
+            dots: Vec::new(),
+            rows: Vec::new(),
+            auto_trim: false,
+            center_on_canvas: false,
+            tone_mode: ToneMode::Binary,
+            invert_background: Some(false),
+            description: None,
+            tags: Vec::new(),
+            author: None,
+            painting_preferences: None,
+            vector_paths: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+}
+
+/// 圧縮したキャンバス表現を `Accept` ヘッダーで要求する場合のメディアタイプ
+pub const COMPACT_CANVAS_MEDIA_TYPE: &str = "application/vnd.ghost-drawer.canvas-rle+json";
+
+/// キャンバスのドットの表現
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CanvasWireFormat {
+    /// ドットごとの `{x, y, color, layer}`
+    #[default]
+    Dots,
+    /// 行優先のランレングス符号化（`rows`）
+    Compact,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct CanvasFormatQuery {
+    /// ドットの表現（省略時は `Accept` が圧縮表現のメディアタイプなら `compact`、それ以外は `dots`）
+    pub format: Option<CanvasWireFormat>,
+}
+
+impl CanvasFormatQuery {
+    fn resolve(&self, headers: &HeaderMap) -> CanvasWireFormat {
+        self.format.unwrap_or_else(|| {
+            let compact = headers
+                .get_all(header::ACCEPT)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .flat_map(|value| value.split(','))
+                .any(|media| {
+                    media
+                        .split(';')
+                        .next()
+                        .is_some_and(|media| media.trim() == COMPACT_CANVAS_MEDIA_TYPE)
+                });
+            if compact {
+                CanvasWireFormat::Compact
+            } else {
+                CanvasWireFormat::Dots
+            }
+        })
+    }
+}
+
+/// ランレングス符号化したキャンバス
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CompactCanvas {
+    pub width: u16,
+    pub height: u16,
+    pub background_color: String,
+    /// 行ごとの `[start_x, length, color]`（レイヤーが0以外なら末尾に `layer`）の区間
+    #[schema(value_type = Vec<Vec<Vec<Object>>>)]
+    pub rows: Vec<Vec<CanvasRun>>,
+}
+
+impl From<&Canvas> for CompactCanvas {
+    fn from(canvas: &Canvas) -> Self {
+        Self {
+            width: canvas.width,
+            height: canvas.height,
+            background_color: canvas.background_color.to_hex(),
+            rows: canvas.encode_rle(),
+        }
+    }
+}
+
+/// `?format=compact` を指定した場合のアートワーク（概要とキャンバスの中身）
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArtworkDetailResponse {
+    #[serde(flatten)]
+    pub artwork: ArtworkSummary,
+    pub canvas: CompactCanvas,
+}
+
+/// 生成するテストパターン
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TestPattern {
+    /// 市松模様
+    Checkerboard,
+    /// 外周の枠
+    Border,
+    /// 中央の十字線
+    Crosshair,
+    /// 左上から右下への斜線
+    Diagonal,
+    /// 内蔵の3x5フォントで描いた文字列
+    Text,
+}
+
+impl TestPattern {
+    fn name(self) -> &'static str {
+        match self {
+            TestPattern::Checkerboard => "checkerboard",
+            TestPattern::Border => "border",
+            TestPattern::Crosshair => "crosshair",
+            TestPattern::Diagonal => "diagonal",
+            TestPattern::Text => "text",
+        }
+    }
+}
+
+fn default_pattern_width() -> u16 {
+    320
+}
+
+fn default_pattern_height() -> u16 {
+    120
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GenerateArtworkRequest {
+    pub pattern: TestPattern,
+    #[serde(default = "default_pattern_width")]
+    pub width: u16,
+    #[serde(default = "default_pattern_height")]
+    pub height: u16,
+    /// `checkerboard` の1マスの大きさ（既定は1）
+    pub cell_size: Option<u16>,
+    /// `border` の枠の太さ（既定は1）
+    pub thickness: Option<u16>,
+    /// `text` で描く文字列（英数字と ` -.:!?`）
+    pub text: Option<String>,
+    /// `text` の拡大率（既定は1）
+    pub scale: Option<u16>,
+    /// 省略時は `test-<pattern>`
+    pub name: Option<String>,
+}
+
+impl GenerateArtworkRequest {
+    pub fn new(pattern: TestPattern) -> Self {
+        Self {
+            pattern,
+            width: default_pattern_width(),
+            height: default_pattern_height(),
+            cell_size: None,
+            thickness: None,
+            text: None,
+            scale: None,
+            name: None,
+        }
+    }
+
+    /// アートワーク名
+    pub fn artwork_name(&self) -> String {
+        self.name
+            .as_deref()
+            .and_then(non_empty)
+            .unwrap_or_else(|| format!("test-{}", self.pattern.name()))
+    }
+
+    /// パターンを描いたキャンバスを生成する
+    pub fn generate_canvas(&self) -> Result<Canvas, String> {
+        if self.width > 1000 || self.height > 1000 {
+            return Err("Width and height must not exceed 1000 pixels".to_string());
+        }
+        let canvas = match self.pattern {
+            TestPattern::Checkerboard => {
+                Canvas::checkerboard(self.width, self.height, self.cell_size.unwrap_or(1))
+            }
+            TestPattern::Border => {
+                Canvas::border(self.width, self.height, self.thickness.unwrap_or(1))
+            }
+            TestPattern::Crosshair => Canvas::crosshair(self.width, self.height),
+            TestPattern::Diagonal => Canvas::diagonal(self.width, self.height),
+            TestPattern::Text => {
+                let text = self
+                    .text
+                    .as_deref()
+                    .filter(|text| !text.is_empty())
+                    .ok_or("The text pattern requires `text`")?;
+                Canvas::text(self.width, self.height, text, self.scale.unwrap_or(1))
+            }
+        };
+        canvas.map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ArtworkResponse {
+    pub id: String,
+    pub message: String,
+    pub artwork: Option<ArtworkSummary>,
+    /// 既定のタイミングで描画した場合の見積もり時間（秒）
+    pub estimated_painting_seconds: Option<f64>,
+    /// 同じ内容のファイルが既にアップロードされており、新規作成せず既存のIDを返した
+    pub duplicate: bool,
+    /// 描画前に確認が必要な点（背景の塗りつぶしが必要など）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// `on_duplicate` で解決した重複ドットの数（解決を指定した場合のみ）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duplicates_resolved: Option<usize>,
+    /// アニメーション画像をフレームごとに分割した場合の組のID（`id` は最初のフレーム）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub set_id: Option<String>,
+    /// 分割して作成したアートワークのID（フレーム順）
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub artwork_ids: Vec<String>,
+    /// `edge_detect` で輪郭だけに変換した場合の、塗りつぶしとのドット数の比較
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub edge_detection: Option<EdgeDetectionSummary>,
+    /// サーバーで変換した場合の、`scaling_mode` で画像をキャンバスに置いた位置と大きさ（全フレーム共通）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub placement: Option<Placement>,
+}
+
+/// 輪郭だけに変換したドット数と、同じ画像を塗りつぶして2値化した場合のドット数
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+pub struct EdgeDetectionSummary {
+    /// 輪郭のドット数（フレームに分割した場合は全フレームの合計）
+    pub dots: usize,
+    /// 塗りつぶした場合のドット数
+    pub filled_dots: usize,
+    /// 塗りつぶしと比べて減ったドットの割合（%）
+    pub reduction_percent: f64,
+}
+
+impl EdgeDetectionSummary {
+    pub fn new(dots: usize, filled_dots: usize) -> Self {
+        let reduction_percent = if filled_dots == 0 {
+            0.0
+        } else {
+            (filled_dots.saturating_sub(dots) as f64 * 1000.0 / filled_dots as f64).round() / 10.0
+        };
+        Self {
+            dots,
+            filled_dots,
+            reduction_percent,
+        }
+    }
+}
+
+/// 階調の表現方法
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneMode {
+    /// 送られたドットをそのまま描画する
+    #[default]
+    Binary,
+    /// ドットの明るさを2x2のBayer行列で点描に変換する
+    Stipple2,
+    /// ドットの明るさを4x4のBayer行列で点描に変換する
+    Stipple4,
+}
+
+impl ToneMode {
+    pub(crate) fn color_reduction(self) -> Option<ColorReduction> {
+        match self {
+            ToneMode::Binary => None,
+            ToneMode::Stipple2 => Some(ColorReduction::Stipple {
+                matrix: OrderedMatrixSize::TwoByTwo,
+            }),
+            ToneMode::Stipple4 => Some(ColorReduction::Stipple {
+                matrix: OrderedMatrixSize::FourByFour,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct DuplicateArtworkRequest {
+    /// 省略時は「元の名前 (copy)」
+    pub name: Option<String>,
+    /// 複製後のキャンバスに順番に適用する変形
+    #[serde(default)]
+    pub transforms: Vec<CanvasTransform>,
+}
+
+/// メタデータの部分更新（省略した項目は変更しない）
+#[derive(Debug, Default, Deserialize, ToSchema)]
+pub struct UpdateMetadataRequest {
+    /// 前後の空白は取り除かれる（空にはできない）
+    pub name: Option<String>,
+    /// 空文字列で説明を消去する
+    pub description: Option<String>,
+    /// 空文字列で作者を消去する
+    pub author: Option<String>,
+    /// タグをすべて置き換える
+    pub tags: Option<Vec<String>>,
+    /// 追加するタグ（既にあるタグは無視）
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    /// 削除するタグ
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+}
+
+impl UpdateMetadataRequest {
+    /// 置き換え → 削除 → 追加の順にタグを更新する
+    fn apply_to(&self, metadata: &mut ArtworkMetadata) -> Result<(), MetadataError> {
+        if let Some(name) = &self.name {
+            metadata.name = non_empty(name).ok_or(MetadataError::EmptyName)?;
+        }
+        if let Some(description) = &self.description {
+            metadata.description = non_empty(description);
+        }
+        if let Some(author) = &self.author {
+            metadata.author = non_empty(author);
+        }
+        if let Some(tags) = &self.tags {
+            metadata.set_tags(tags)?;
+        }
+        for tag in &self.remove_tags {
+            metadata.remove_tag(tag.trim());
+        }
+        metadata.add_tags(&self.add_tags)
+    }
+}
+
+/// 前後の空白を取り除き、空なら未設定とする
+fn non_empty(value: &str) -> Option<String> {
+    let value = value.trim();
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListArtworksQuery {
+    /// このタグを持つアートワークだけを返す
+    pub tag: Option<String>,
+    /// 名前にこの文字列を含むアートワークだけを返す（大文字・小文字を区別しない）
+    pub name: Option<String>,
+}
+
+impl ListArtworksQuery {
+    /// 保存先で絞り込む検索条件（作成日時の古い順）
+    fn to_query(&self) -> ArtworkQuery {
+        let non_blank = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+                .map(str::to_string)
+        };
+        ArtworkQuery {
+            name_contains: non_blank(&self.name),
+            tags: non_blank(&self.tag).map(|tag| vec![tag]),
+            ..ArtworkQuery::new()
+        }
+        .with_sort(SortField::CreatedAt, SortOrder::Ascending)
+    }
+}
+
+/// 同じ座標のドットが複数送られた場合の扱い
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateDotPolicy {
+    /// 重複した座標を列挙して422で拒否する
+    #[default]
+    Reject,
+    /// 後に送られたドットを採用する
+    LastWins,
+    /// 先に送られたドットを採用する
+    FirstWins,
+}
+
+/// 422の応答に列挙する重複した座標の上限
+const MAX_LISTED_DUPLICATES: usize = 20;
+
+/// 重複をまとめたドット
+pub struct ResolvedDots<'a> {
+    /// 採用したドット（座標が最初に現れた順）
+    pub dots: Vec<&'a DotData>,
+    /// 重複として捨てたドット数
+    pub discarded: usize,
+}
+
+impl DuplicateDotPolicy {
+    /// 座標ごとに1つのドットにまとめる
+    ///
+    /// `Reject` で重複があれば、重複した座標を昇順で返す。
+    pub fn resolve(self, dots: &[DotData]) -> Result<ResolvedDots<'_>, Vec<(u16, u16)>> {
+        let mut positions: HashMap<(u16, u16), usize> = HashMap::with_capacity(dots.len());
+        let mut resolved: Vec<&DotData> = Vec::with_capacity(dots.len());
+        let mut duplicated = BTreeSet::new();
+        for dot in dots {
+            match positions.entry((dot.x, dot.y)) {
+                Entry::Vacant(entry) => {
+                    entry.insert(resolved.len());
+                    resolved.push(dot);
+                }
+                Entry::Occupied(entry) => {
+                    duplicated.insert((dot.x, dot.y));
+                    if self == Self::LastWins {
+                        resolved[*entry.get()] = dot;
+                    }
+                }
+            }
+        }
+
+        if self == Self::Reject && !duplicated.is_empty() {
+            return Err(duplicated.into_iter().collect());
+        }
+        Ok(ResolvedDots {
+            discarded: dots.len() - resolved.len(),
+            dots: resolved,
+        })
+    }
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CreateArtworkQuery {
+    /// 同じ座標のドットが複数ある場合の扱い（既定は `reject`）
+    #[serde(default)]
+    pub on_duplicate: DuplicateDotPolicy,
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct UploadArtworkQuery {
+    /// 同じ内容のファイルがアップロード済みでも新しいアートワークを作成する
+    #[serde(default)]
+    pub allow_duplicate: bool,
+    /// アニメーション画像（GIF・APNG・WebP）をフレームごとのアートワークに分割する
+    ///
+    /// アニメーションでない画像は通常どおり1つのアートワークになる
+    #[serde(default)]
+    pub split_frames: bool,
+    /// 塗りつぶす代わりに輪郭だけをドットにする（サーバーで変換する `split_frames` のアニメーションのみ）
+    #[serde(default)]
+    pub edge_detect: bool,
+    /// 輪郭とみなす勾配の大きさ（0〜255、既定128）
+    pub edge_threshold: Option<u8>,
+    /// 輪郭を1ドット幅まで細線化する
+    #[serde(default)]
+    pub edge_thinning: bool,
+    /// 半透明の画素の扱い（サーバーで変換する `split_frames` のアニメーションのみ、既定は `skip`）
+    #[serde(default)]
+    pub transparency_mode: TransparencyMode,
+    /// 描画しうる画素の不透明度の下限（1〜255、既定128）。これ未満の画素は色に関係なく描画しない
+    pub alpha_cutoff: Option<u8>,
+    /// 縦横比が320x120と違う画像をキャンバスに合わせる方法（サーバーで変換する `split_frames` のアニメーションのみ、既定は `fit`）
+    #[serde(default)]
+    pub scaling_mode: ScalingMode,
+    /// `fill` で切り取るときに残す側（既定は `center`）
+    #[serde(default)]
+    pub gravity: Gravity,
+}
+
+impl UploadArtworkQuery {
+    /// 輪郭だけに変換する設定（`edge_detect` を指定しなければ `None`）
+    fn edge_detection(&self) -> Option<EdgeDetection> {
+        self.edge_detect.then(|| EdgeDetection {
+            threshold: self
+                .edge_threshold
+                .unwrap_or(EdgeDetection::default().threshold),
+            thinning: self.edge_thinning,
+        })
+    }
+
+    /// 透明・半透明の画素の扱い
+    fn transparency(&self) -> Result<Transparency, ErrorResponse> {
+        Transparency::new(
+            self.transparency_mode,
+            self.alpha_cutoff
+                .unwrap_or(Transparency::default().alpha_cutoff),
+        )
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct GetPathRequest {
+    pub strategy: Option<DrawingStrategy>,
+    /// `x,y,width,height` 形式の描画領域
+    pub region: Option<String>,
+    /// `x,y` 形式の、アートワークの左上を置くゲーム内キャンバスの位置（座標列はゲーム内の座標になる）
+    pub origin: Option<String>,
+    /// 乱択を使う描画戦略のシード値
+    pub seed: Option<u64>,
+    /// 2-opt最適化の時間の上限（ミリ秒、省略時はサーバーの設定、最大30000）
+    pub two_opt_budget_ms: Option<u64>,
+}
+
+/// 戦略比較の見積もり条件（描画開始時と同じオプション）
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct StrategyComparisonRequest {
+    pub press_ms: Option<u32>,
+    pub release_ms: Option<u32>,
+    pub wait_ms: Option<u32>,
+    pub repeats: Option<u32>,
+    pub diagonal_moves: Option<bool>,
+    /// `x,y` 形式の、アートワークの左上を置くゲーム内キャンバスの位置（左上からの移動も見積もりに含める）
+    pub origin: Option<String>,
+    /// 2-opt最適化の時間の上限（ミリ秒、省略時はサーバーの設定、最大30000）
+    pub two_opt_budget_ms: Option<u64>,
+    /// `fast` にすると大きなアートワークを間引いて見積もる（既定は `exact`）
+    pub mode: Option<StrategyComparisonMode>,
+    /// `fast` で見積もりに使う最大ドット数（既定値5000、下限1000）。これ以下のアートワークは全ドットで計算する
+    pub sample_dots: Option<usize>,
+}
+
+/// 戦略比較の計算方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StrategyComparisonMode {
+    /// 全ドットで経路を計算する
+    Exact,
+    /// 描画対象のドットが多い場合は間引いたサンプルから見積もる
+    Fast,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PathResponse {
+    pub path: Vec<Coordinates>,
+    pub estimated_time_sec: f64,
+    /// 座標列のハッシュ（描画実行の記録と同じ値なら同じ経路）
+    pub path_hash: String,
+    pub stats: PathStats,
+}
+
+/// 描画パスの統計
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PathStats {
+    /// 総移動距離（マンハッタン距離）
+    pub total_distance: u32,
+    /// 2-opt最適化の結果（`GreedyTwoOpt` のみ）
+    pub two_opt: Option<TwoOptStats>,
+}
+
+/// 解析でドット数を数えるタイルの一辺（ドット）
+pub const ANALYSIS_TILE_SIZE: u16 = 16;
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct ArtworkAnalysisQuery {
+    /// 描画パスを求める描画戦略（省略時はアートワークの描画設定、無ければ `GreedyTwoOpt`）
+    pub strategy: Option<DrawingStrategy>,
+}
+
+/// 描画前の確認に使うアートワークの解析結果
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ArtworkAnalysisResponse {
+    pub id: String,
+    /// 解析したアートワークのバージョン
+    pub version: u32,
+    pub statistics: ArtworkStatistics,
+    /// `tiles` の1タイルの一辺（ドット）
+    pub tile_size: u16,
+    /// タイルごとの描画可能なドット数（上の行から順に、各行は左から。ヒートマップ用）
+    pub tiles: Vec<Vec<usize>>,
+    /// 周囲8方向に描画可能なドットが無い、位置ずれの影響を受けやすいドットの数
+    pub isolated_dots: usize,
+    /// 不透明なドットを囲む最小の領域（ドットが無ければ `null`）
+    pub bounding_box: Option<CanvasRegion>,
+    /// 選択中の描画戦略での描画パス
+    pub path: AnalysisPathSummary,
+}
+
+/// 解析した描画パスの概要
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct AnalysisPathSummary {
+    pub strategy: DrawingStrategy,
+    /// 描画するドット数（描画済みのドットを除く）
+    pub dots: usize,
+    /// 総移動距離（マンハッタン距離）
+    pub total_distance: u32,
+    /// 描画せずに移動する区間のうち最も長いもの（マンハッタン距離、無駄な移動の目安）
+    pub longest_move: u32,
+    /// 初期化シーケンスを除いた推定所要時間（秒）
+    pub estimated_time_seconds: f64,
+    pub path_hash: String,
+    /// 2-opt最適化の結果（`GreedyTwoOpt` のみ）
+    pub two_opt: Option<TwoOptStats>,
+}
+
+/// アートワークの解析結果のキャッシュ（アートワークごとに最新の1件だけ保持する）
+///
+/// バージョンと描画戦略が一致する間だけ使い、アートワークを変更すると次の取得で計算し直す。
+#[derive(Debug, Clone, Default)]
+pub struct ArtworkAnalysisCache {
+    entries: Arc<std::sync::Mutex<HashMap<String, ArtworkAnalysisResponse>>>,
+}
+
+impl ArtworkAnalysisCache {
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, ArtworkAnalysisResponse>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn get(
+        &self,
+        id: &str,
+        version: u32,
+        strategy: DrawingStrategy,
+    ) -> Option<ArtworkAnalysisResponse> {
+        self.lock()
+            .get(id)
+            .filter(|analysis| analysis.version == version && analysis.path.strategy == strategy)
+            .cloned()
+    }
+
+    fn insert(&self, analysis: ArtworkAnalysisResponse) {
+        self.lock().insert(analysis.id.clone(), analysis);
+    }
+
+    fn remove(&self, id: &str) {
+        self.lock().remove(id);
+    }
+}
+
+/// List all artworks
+#[utoipa::path(
+    get, path = "/api/artworks", tag = "artworks",
+    params(ListArtworksQuery),
+    responses(
+        (status = 200, description = "アートワーク一覧（作成日時の古い順）", body = [ArtworkSummary]),
+        (status = 304, description = "`If-None-Match` が一致"),
+        (status = 500, description = "保存先の読み込みに失敗", body = ErrorResponse)
+    )
+)]
+pub async fn list_artworks(
+    State(state): State<Arc<ArtworkState>>,
+    Query(query): Query<ListArtworksQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ErrorResponse> {
+    let matching = state.artworks.search(&query.to_query()).await?.artworks;
+
+    Ok(conditional_json(
+        &headers,
+        ETag::for_artworks(&matching),
+        || {
+            matching
+                .iter()
+                .map(ArtworkSummary::from)
+                .collect::<Vec<_>>()
+        },
+    ))
+}
+
+/// Create a new artwork
+#[utoipa::path(
+    post, path = "/api/artworks", tag = "artworks",
+    request_body = CreateArtworkRequest,
+    params(CreateArtworkQuery),
+    responses(
+        (status = 200, description = "作成したアートワーク", body = ArtworkResponse),
+        (status = 422, description = "リクエストが不正（`on_duplicate` を省略した場合の座標の重複を含む）", body = ErrorResponse),
+        (status = 507, description = "アートワークのメモリ使用量の上限を超える", body = ErrorResponse)
+    )
+)]
+pub async fn create_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Query(query): Query<CreateArtworkQuery>,
+    request: Result<Json<CreateArtworkRequest>, axum::extract::rejection::JsonRejection>,
+) -> Result<Json<ArtworkResponse>, impl IntoResponse> {
+    // Handle JSON parsing errors
+    let Json(request) = match request {
+        Ok(json) => json,
+        Err(e) => {
+            warn!("JSON parsing error: {:?}", e);
+            return Err(ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Invalid JSON: {e}"),
+            ));
+        }
+    };
+
+    info!("Creating artwork: {}", request.name);
+    info!("Dimensions: {}x{}", request.width, request.height);
+    info!(
+        "Number of dots: {} ({} compact rows)",
+        request.dots.len(),
+        request.rows.len()
+    );
+
+    // Validate dimensions
+    if request.width == 0 || request.height == 0 {
+        warn!("Invalid dimensions: {}x{}", request.width, request.height);
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Width and height must be greater than 0",
+        ));
+    }
+
+    if request.width > 1000 || request.height > 1000 {
+        warn!("Dimensions too large: {}x{}", request.width, request.height);
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Width and height must not exceed 1000 pixels",
+        ));
+    }
+
+    // Validate dots
+    if !request.dots.is_empty() && !request.rows.is_empty() {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "dots and rows cannot be combined",
+        ));
+    }
+    if request.dots.is_empty() && request.rows.is_empty() && request.vector_paths.is_empty() {
+        warn!("No dots provided");
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "At least one dot is required",
+        ));
+    }
+
+    // 線画の座標はキャンバスを切り詰めても動かないため、組み合わせを認めない
+    if !request.vector_paths.is_empty() {
+        if request.auto_trim || request.center_on_canvas {
+            return Err(ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "vector_paths cannot be combined with auto_trim or center_on_canvas",
+            ));
+        }
+        Polyline::validate_all(&request.vector_paths, request.width, request.height)
+            .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    }
+
+    let mut metadata = ArtworkMetadata::new(request.name.clone());
+    metadata.description = request.description.as_deref().and_then(non_empty);
+    metadata.author = request.author.as_deref().and_then(non_empty);
+    metadata
+        .set_tags(&request.tags)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    // Create canvas from dots
+    let tone_reduction = request.tone_mode.color_reduction();
+    let (mut canvas, discarded) = if request.rows.is_empty() {
+        canvas_from_dots(&request, query.on_duplicate, tone_reduction.as_ref())?
+    } else {
+        // 区間は重ならないため重複の解決は不要
+        let mut canvas = Canvas::decode_rle(request.width, request.height, &request.rows)
+            .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+        if let Some(reduction) = &tone_reduction {
+            canvas.dots.retain(|coordinates, dot| {
+                ImageProcessingService::apply_color_reduction(&dot.color, reduction, *coordinates)
+                    == Color::black()
+            });
+            for dot in canvas.dots.values_mut() {
+                dot.color = Color::black();
+            }
+        }
+        (canvas, 0)
+    };
+
+    // 暗い背景に明るい絵柄の画像は、少ない方の明るいドットを描く
+    let invert = request
+        .invert_background
+        .unwrap_or_else(|| canvas.is_mostly_filled());
+    if invert {
+        info!("Inverting background: painting the light areas on a dark background");
+        canvas = canvas.inverted();
+    }
+
+    let canvas = fit_canvas(canvas, request.auto_trim, request.center_on_canvas)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let warnings = canvas_warnings(&canvas);
+
+    if let Some(origin) = request.painting_preferences.and_then(|p| p.origin) {
+        validate_origin(origin, &canvas)?;
+    }
+
+    // Create artwork
+    let mut artwork = Artwork::new(metadata, "api".to_string(), canvas);
+    artwork.painting_preferences = request
+        .painting_preferences
+        .filter(|preferences| !preferences.is_empty());
+    artwork.vector_paths = request.vector_paths;
+    let artwork_id = artwork.id.as_str().to_string();
+    let summary = ArtworkSummary::from(&artwork);
+    let estimated_painting_seconds = estimate_painting_seconds(&artwork.canvas);
+
+    // Store artwork
+    state
+        .insert_artwork(artwork, EventMetadata::new("api".to_string()))
+        .await?;
+
+    info!(
+        "Artwork created with ID: {} ({} drawable dots, ~{:.0}s to paint)",
+        artwork_id, summary.drawable_dots, estimated_painting_seconds
+    );
+
+    Ok(Json(ArtworkResponse {
+        id: artwork_id,
+        message: format!("Artwork '{}' created successfully", request.name),
+        artwork: Some(summary),
+        estimated_painting_seconds: Some(estimated_painting_seconds),
+        duplicate: false,
+        warnings,
+        duplicates_resolved: (query.on_duplicate != DuplicateDotPolicy::Reject)
+            .then_some(discarded),
+        set_id: None,
+        artwork_ids: Vec::new(),
+        edge_detection: None,
+        placement: None,
+    }))
+}
+
+/// 作成リクエストの `dots` からキャンバスを作り、解決した重複ドットの数と一緒に返す
+fn canvas_from_dots(
+    request: &CreateArtworkRequest,
+    on_duplicate: DuplicateDotPolicy,
+    tone_reduction: Option<&ColorReduction>,
+) -> Result<(Canvas, usize), ErrorResponse> {
+    let mut canvas = Canvas::new(request.width, request.height);
+
+    // Validate dot coordinates
+    for (index, dot_data) in request.dots.iter().enumerate() {
+        if dot_data.x >= request.width || dot_data.y >= request.height {
+            warn!(
+                "Dot {} has invalid coordinates: ({}, {})",
+                index, dot_data.x, dot_data.y
+            );
+            return Err(ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Dot at index {index} has coordinates outside canvas bounds"),
+            ));
+        }
+    }
+
+    // 同じ座標のドットは上書きされて総数が合わなくなるため、方針に従ってまとめる
+    let ResolvedDots { dots, discarded } = on_duplicate
+        .resolve(&request.dots)
+        .map_err(|duplicated| {
+            warn!("{} duplicated dot coordinates", duplicated.len());
+            let mut listed = duplicated
+                .iter()
+                .take(MAX_LISTED_DUPLICATES)
+                .map(|(x, y)| format!("({x}, {y})"))
+                .collect::<Vec<_>>()
+                .join(", ");
+            if duplicated.len() > MAX_LISTED_DUPLICATES {
+                listed.push_str(&format!(
+                    " and {} more",
+                    duplicated.len() - MAX_LISTED_DUPLICATES
+                ));
+            }
+            ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "Duplicate dot coordinates: {listed} (pass on_duplicate=last_wins or first_wins to resolve)"
+                ),
+            )
+        })?;
+    if discarded > 0 {
+        info!("Resolved {} duplicate dots ({:?})", discarded, on_duplicate);
+    }
+
+    // Add dots to canvas
+    for dot_data in dots {
+        let mut color = parse_color(&dot_data.color).unwrap_or(Color::new(0, 0, 0, 255));
+        let coordinates = Coordinates::new(dot_data.x, dot_data.y);
+        if let Some(reduction) = tone_reduction {
+            color = ImageProcessingService::apply_color_reduction(&color, reduction, coordinates);
+            if color != Color::black() {
+                continue;
+            }
+        }
+        let dot = Dot::with_layer(color, 255, dot_data.layer);
+        if let Err(e) = canvas.set_dot(coordinates, dot) {
+            warn!(
+                "Failed to set dot at ({}, {}): {:?}",
+                dot_data.x, dot_data.y, e
+            );
+        }
+    }
+
+    Ok((canvas, discarded))
+}
+
+/// Generate a test pattern artwork for checking the hardware without an image
+#[utoipa::path(
+    post, path = "/api/artworks/generate", tag = "artworks",
+    request_body = GenerateArtworkRequest,
+    responses(
+        (status = 200, description = "作成したアートワーク", body = ArtworkResponse),
+        (status = 422, description = "パターンのパラメーターが不正", body = ErrorResponse),
+        (status = 507, description = "アートワークのメモリ使用量の上限を超える", body = ErrorResponse)
+    )
+)]
+pub async fn generate_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    request: Result<Json<GenerateArtworkRequest>, axum::extract::rejection::JsonRejection>,
+) -> Result<Json<ArtworkResponse>, ErrorResponse> {
+    let Json(request) = request.map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Invalid JSON: {e}"),
+        )
+    })?;
+    let canvas = request
+        .generate_canvas()
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+
+    let name = request.artwork_name();
+    let metadata = ArtworkMetadata::new(name.clone()).with_tags(vec!["test-pattern".to_string()]);
+    let artwork = Artwork::new(metadata, "generated".to_string(), canvas);
+    let artwork_id = artwork.id.as_str();
+    let summary = ArtworkSummary::from(&artwork);
+    let estimated_painting_seconds = estimate_painting_seconds(&artwork.canvas);
+
+    state
+        .insert_artwork(artwork, EventMetadata::new("api".to_string()))
+        .await?;
+    info!(
+        "Generated {} test pattern {} ({} drawable dots, ~{:.0}s to paint)",
+        request.pattern.name(),
+        artwork_id,
+        summary.drawable_dots,
+        estimated_painting_seconds
+    );
+
+    Ok(Json(ArtworkResponse {
+        id: artwork_id,
+        message: format!("Artwork '{name}' generated successfully"),
+        artwork: Some(summary),
+        estimated_painting_seconds: Some(estimated_painting_seconds),
+        duplicate: false,
+        warnings: Vec::new(),
+        duplicates_resolved: None,
+        set_id: None,
+        artwork_ids: Vec::new(),
+        edge_detection: None,
+        placement: None,
+    }))
+}
+
+/// 他のツールの描画スクリプトの読み込み
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ImportScriptRequest {
+    /// スクリプトの本文
+    pub script: String,
+    /// 省略時は本文から判定する（`{` を含むか最初の行が `,` を含めば `fightstick`、それ以外は `nxbt`）
+    pub format: Option<ScriptFormat>,
+    /// 省略時は `imported-<format>`
+    pub name: Option<String>,
+}
+
+/// Import a Switch-Fightstick / NXBT drawing script as an artwork
+///
+/// ゲーム内キャンバスの左上から十字キーとAボタンの入力をたどり、描かれるドットでアートワークを作る。
+/// 再現しなかった入力（スティック・A以外のボタン・不明な入力名）は入力名ごとに `warnings` に入る。
+#[utoipa::path(
+    post, path = "/api/artworks/import-script", tag = "artworks",
+    request_body = ImportScriptRequest,
+    responses(
+        (status = 200, description = "作成したアートワーク", body = ArtworkResponse),
+        (status = 422, description = "スクリプトの形式が不正、またはドットを1つも描かない", body = ErrorResponse),
+        (status = 507, description = "アートワークのメモリ使用量の上限を超える", body = ErrorResponse)
+    )
+)]
+pub async fn import_script(
+    State(state): State<Arc<ArtworkState>>,
+    request: Result<Json<ImportScriptRequest>, axum::extract::rejection::JsonRejection>,
+) -> Result<Json<ArtworkResponse>, ErrorResponse> {
+    let Json(request) = request.map_err(|e| {
+        ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("Invalid JSON: {e}"),
+        )
+    })?;
+    let format = request
+        .format
+        .unwrap_or_else(|| ScriptFormat::detect(&request.script));
+    // `LOOP` の展開は大きなスクリプトでは時間がかかるため、非同期のワーカーを塞がない
+    let script = request.script;
+    let replay = tokio::task::spawn_blocking(move || replay_script(&script, format))
+        .await
+        .map_err(|e| {
+            error!("Script import task failed: {}", e);
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to import the script",
+            )
+        })?
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    if replay.dots.is_empty() {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "The script does not paint any dots",
+        ));
+    }
+
+    let name = request
+        .name
+        .as_deref()
+        .and_then(non_empty)
+        .unwrap_or_else(|| format!("imported-{}", format.name()));
+    let metadata =
+        ArtworkMetadata::new(name.clone()).with_tags(vec!["imported-script".to_string()]);
+    let artwork = Artwork::new(metadata, format.name().to_string(), replay.to_canvas());
+    let artwork_id = artwork.id.as_str();
+    let summary = ArtworkSummary::from(&artwork);
+    let estimated_painting_seconds = estimate_painting_seconds(&artwork.canvas);
+
+    state
+        .insert_artwork(artwork, EventMetadata::new("api".to_string()))
+        .await?;
+    info!(
+        "Imported {} script as artwork {} ({} dots, {} ignored inputs)",
+        format.name(),
+        artwork_id,
+        replay.dots.len(),
+        replay.warnings.len()
+    );
+
+    Ok(Json(ArtworkResponse {
+        id: artwork_id,
+        message: format!("Artwork '{name}' imported successfully"),
+        artwork: Some(summary),
+        estimated_painting_seconds: Some(estimated_painting_seconds),
+        duplicate: false,
+        warnings: replay.warnings.iter().map(ToString::to_string).collect(),
+        duplicates_resolved: None,
+        set_id: None,
+        artwork_ids: Vec::new(),
+        edge_detection: None,
+        placement: None,
+    }))
+}
+
+/// 既定のタイミングで描画した場合の所要時間（秒）を見積もる
+///
+/// ドット数が多くても高速に求められるよう、ジグザグ順の経路で計算する
+pub(crate) fn estimate_painting_seconds(canvas: &Canvas) -> f64 {
+    let drawing_path =
+        ArtworkToCommandConverter::new(DrawingCanvasConfig::default(), DrawingStrategy::ZigZag)
+            .create_drawing_path(canvas);
+    drawing_path.estimated_time_ms as f64 / 1000.0
+}
+
+/// 空白の除去と中央配置のオプションをキャンバスに適用する
+///
+/// 中央配置は空白を除去した結果を元のキャンバスサイズの中央に置くため、`auto_trim` を含意する
+/// 描画を始める前に確認してほしい点を列挙する
+fn canvas_warnings(canvas: &Canvas) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if canvas.has_dark_background() {
+        warnings.push(format!(
+            "The artwork has a dark background ({}): the in-game post editor starts with a white canvas, so fill it with the background color and switch the pen color before painting",
+            canvas.background_color.to_hex()
+        ));
+    }
+    if canvas.is_mostly_filled() {
+        warnings.push(format!(
+            "More than half of the canvas ({} dots) will be painted; for a light-on-dark image set invert_background to paint only the light areas",
+            canvas.drawable_dots().len()
+        ));
+    }
+    warnings
+}
+
+pub(crate) fn fit_canvas(
+    canvas: Canvas,
+    auto_trim: bool,
+    center_on_canvas: bool,
+) -> Result<Canvas, CanvasError> {
+    if center_on_canvas {
+        canvas.trimmed().centered_on(canvas.width, canvas.height)
+    } else if auto_trim {
+        Ok(canvas.trimmed())
+    } else {
+        Ok(canvas)
+    }
+}
+
+/// Update an artwork's name, description, author and tags
+#[utoipa::path(
+    patch, path = "/api/artworks/{id}/metadata", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    request_body = UpdateMetadataRequest,
+    responses(
+        (status = 200, description = "更新後のアートワーク", body = ArtworkSummary),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 422, description = "名前が空、またはタグが不正", body = ErrorResponse),
+        (status = 507, description = "アートワークのメモリ使用量の上限を超える", body = ErrorResponse)
+    )
+)]
+pub async fn update_artwork_metadata(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateMetadataRequest>,
+) -> Result<Json<ArtworkSummary>, ErrorResponse> {
+    let _edit = state.artwork_edits.lock().await;
+    let mut artwork = state.artwork_or_not_found(&id).await?;
+
+    let mut metadata = artwork.metadata.clone();
+    request
+        .apply_to(&mut metadata)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    // 変更がなければバージョンを上げずに返す
+    if metadata != artwork.metadata {
+        let change = ArtworkChange::metadata_updated(artwork.metadata.clone(), &metadata);
+        artwork.update_metadata(metadata);
+        let event = artwork.record_change(change, EventMetadata::new("api".to_string()));
+        state.save_edited_artwork(&artwork).await?;
+        state.publish_change(event).await;
+    }
+
+    Ok(Json(ArtworkSummary::from(&artwork)))
+}
+
+/// Replace the painting preferences saved with an artwork
+///
+/// 描画リクエストで省略した押下時間・待機時間・戦略・斜め移動・配置位置に使われる。
+/// 保存済みの設定はすべて置き換わり、空のオブジェクトを送ると消去される。
+#[utoipa::path(
+    patch, path = "/api/artworks/{id}/preferences", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    request_body = PaintingPreferences,
+    responses(
+        (status = 200, description = "更新後のアートワーク", body = ArtworkSummary),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 422, description = "配置したアートワークがゲーム内キャンバスに収まらない", body = ErrorResponse)
+    )
+)]
+pub async fn update_painting_preferences(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Json(preferences): Json<PaintingPreferences>,
+) -> Result<Json<ArtworkSummary>, ErrorResponse> {
+    let _edit = state.artwork_edits.lock().await;
+    let mut artwork = state.artwork_or_not_found(&id).await?;
+    if let Some(origin) = preferences.origin {
+        validate_origin(origin, &artwork.canvas)?;
+    }
+
+    let preferences = Some(preferences).filter(|preferences| !preferences.is_empty());
+    if preferences != artwork.painting_preferences {
+        let change = ArtworkChange::preferences_updated(preferences.as_ref());
+        artwork.set_painting_preferences(preferences);
+        artwork.record_change(change, EventMetadata::new("api".to_string()));
+        state.save_edited_artwork(&artwork).await?;
+        info!(
+            "Painting preferences of artwork {} updated: {:?}",
+            id, artwork.painting_preferences
+        );
+    }
+
+    Ok(Json(ArtworkSummary::from(&artwork)))
+}
+
+/// 線画の置き換え
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UpdateVectorPathsRequest {
+    /// 空にすると線画を消去する
+    pub vector_paths: Vec<Polyline>,
+}
+
+/// Replace the line art painted by `POST /api/artworks/{id}/paint-vector`
+///
+/// 座標はアートワークのキャンバス上の位置で、ドットとは別に保存される。
+#[utoipa::path(
+    put, path = "/api/artworks/{id}/vector-paths", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    request_body = UpdateVectorPathsRequest,
+    responses(
+        (status = 200, description = "更新後のアートワーク", body = ArtworkSummary),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "アートワークを描画中、またはパスの計算中", body = ErrorResponse),
+        (status = 422, description = "座標がキャンバスの外にある、空の線がある、座標が多すぎる", body = ErrorResponse)
+    )
+)]
+pub async fn update_vector_paths(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Json(request): Json<UpdateVectorPathsRequest>,
+) -> Result<Json<ArtworkSummary>, ErrorResponse> {
+    let _lock = state
+        .artwork_locks
+        .write(&id, MessageKey::OperationVectorPathUpdate)?;
+    let _edit = state.artwork_edits.lock().await;
+    let mut artwork = state.artwork_or_not_found(&id).await?;
+    Polyline::validate_all(
+        &request.vector_paths,
+        artwork.canvas.width,
+        artwork.canvas.height,
+    )
+    .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    if request.vector_paths != artwork.vector_paths {
+        let change = ArtworkChange::vector_paths_updated(&request.vector_paths);
+        artwork.set_vector_paths(request.vector_paths);
+        artwork.record_change(change, EventMetadata::new("api".to_string()));
+        state.save_edited_artwork(&artwork).await?;
+        info!(
+            "Vector paths of artwork {} updated ({} strokes)",
+            id,
+            artwork.vector_paths.len()
+        );
+    }
+
+    Ok(Json(ArtworkSummary::from(&artwork)))
+}
+
+/// ドットの日時を捨てた結果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CompactArtworkResponse {
+    pub id: String,
+    /// 日時を捨てたドット数（既にコンパクトな場合は0）
+    pub compacted_dots: usize,
+    /// ドットをJSONにした大きさ（バイト、圧縮前）
+    pub bytes_before: usize,
+    pub bytes_after: usize,
+}
+
+/// ドットをJSONにした大きさ（保存時の圧縮前の大きさの目安）
+fn serialized_dots_bytes(canvas: &Canvas) -> usize {
+    serde_json::to_vec(&canvas.dots.iter().collect::<Vec<_>>()).map_or(0, |json| json.len())
+}
+
+/// Drop per-dot timestamps of an artwork to shrink its stored size
+///
+/// 描画済みかどうか・座標・色・レイヤーは変わらない。日時は集計にしか使わないため、
+/// SQLiteに保存する場合は `--keep-dot-timestamps` を指定しない限り保存時にも捨てられる。
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/compact", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    responses(
+        (status = 200, description = "前後の大きさ", body = CompactArtworkResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "アートワークを描画中、またはパスの計算中", body = ErrorResponse)
+    )
+)]
+pub async fn compact_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+) -> Result<Json<CompactArtworkResponse>, ErrorResponse> {
+    let _lock = state
+        .artwork_locks
+        .write(&id, MessageKey::OperationCompaction)?;
+    let _edit = state.artwork_edits.lock().await;
+    let mut artwork = state.artwork_or_not_found(&id).await?;
+
+    let bytes_before = serialized_dots_bytes(&artwork.canvas);
+    let compacted_dots = artwork.canvas.compact_dots();
+    let bytes_after = serialized_dots_bytes(&artwork.canvas);
+    if compacted_dots > 0 {
+        state.save_edited_artwork(&artwork).await?;
+    }
+    info!(
+        "Compacted {} dots of artwork {}: {} -> {} bytes",
+        compacted_dots, id, bytes_before, bytes_after
+    );
+
+    Ok(Json(CompactArtworkResponse {
+        id,
+        compacted_dots,
+        bytes_before,
+        bytes_after,
+    }))
+}
+
+/// キャンバスへの変形の適用
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransformArtworkRequest {
+    /// 順番に適用する変形（1つ以上）
+    pub transforms: Vec<CanvasTransform>,
+}
+
+/// 変形の適用結果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransformArtworkResponse {
+    pub id: String,
+    /// 適用後のバージョン（変形の数によらず1つ上がる）
+    pub version: u32,
+    pub dots_added: usize,
+    pub dots_removed: usize,
+    /// 色・不透明度・レイヤーが変わったドット数
+    pub dots_changed: usize,
+    /// 適用後の描画対象ドット数
+    pub drawable_dots: usize,
+}
+
+/// Apply canvas transforms to an artwork in place
+///
+/// `POST /api/artworks/{id}/duplicate` の `transforms` と同じ変形を、複製せずに適用する。
+/// ドットの編集と同じく `POST /api/artworks/{id}/undo` で取り消せる。
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/transform", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    request_body = TransformArtworkRequest,
+    responses(
+        (status = 200, description = "適用結果", body = TransformArtworkResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "アートワークを描画中、またはパスの計算中", body = ErrorResponse),
+        (status = 422, description = "変形が指定されていない", body = ErrorResponse),
+        (status = 507, description = "アートワークのメモリ使用量の上限を超える", body = ErrorResponse)
+    )
+)]
+pub async fn transform_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Json(request): Json<TransformArtworkRequest>,
+) -> Result<Json<TransformArtworkResponse>, ErrorResponse> {
+    if request.transforms.is_empty() {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "At least one transform is required",
+        ));
+    }
+
+    let _lock = state
+        .artwork_locks
+        .write(&id, MessageKey::OperationCanvasEdit)?;
+    let _edit = state.artwork_edits.lock().await;
+    let mut artwork = state.artwork_or_not_found(&id).await?;
+    let previous = artwork.canvas.clone();
+    let mut canvas = previous.clone();
+    for transform in &request.transforms {
+        canvas.apply_transform(*transform);
+    }
+    let change = ArtworkChange::transformed(&previous, &canvas, &request.transforms);
+    let dots = change.dots();
+    artwork.update_canvas(canvas);
+    let event = artwork.record_change(change, EventMetadata::new("api".to_string()));
+    state.save_edited_artwork(&artwork).await?;
+    state
+        .canvas_history
+        .record(&artwork.id, &previous, &artwork.canvas);
+    state.publish_change(event).await;
+
+    Ok(Json(TransformArtworkResponse {
+        id: artwork.id.as_str().to_string(),
+        version: artwork.version,
+        dots_added: dots.added,
+        dots_removed: dots.removed,
+        dots_changed: dots.changed,
+        drawable_dots: artwork.drawable_dots(),
+    }))
+}
+
+/// 一括ドット差分の適用結果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BulkDotsResponse {
+    pub id: String,
+    /// 適用後のバージョン（1回の差分につき1つ上がる）
+    pub version: u32,
+    /// 差分に含まれていた変更ドット数（ラン長の合計）
+    pub changed_dots: u64,
+    /// 適用後の描画対象ドット数
+    pub drawable_dots: usize,
+}
+
+fn dot_diff_status(error: &DotDiffError) -> StatusCode {
+    match error {
+        DotDiffError::InvalidLength(_)
+        | DotDiffError::UnknownOperation { .. }
+        | DotDiffError::EmptyRun { .. } => StatusCode::BAD_REQUEST,
+        DotDiffError::OutOfBounds { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+        DotDiffError::TooLarge { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+    }
+}
+
+/// Apply a binary dot diff from the pixel editor in one atomic update
+///
+/// 本文は7バイトのレコード（x: u16 LE、y: u16 LE、操作: u8 で `0` = clear / `1` = set、
+/// ラン長: u16 LE）を並べたもの。ラン長は (x, y) から右方向に同じ操作を続けるドット数。
+/// すべてのレコードを検証してから適用するため、エラーの場合は何も変更されない。
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/dots:bulk", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    request_body(
+        content = Vec<u8>, content_type = "application/octet-stream",
+        description = "7バイトのレコード（x: u16 LE, y: u16 LE, op: u8, run: u16 LE）の並び"
+    ),
+    responses(
+        (status = 200, description = "適用結果", body = BulkDotsResponse),
+        (status = 400, description = "レコードの形式が不正", body = ErrorResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "アートワークを描画中、またはパスの計算中", body = ErrorResponse),
+        (status = 413, description = "変更ドット数がキャンバスの容量を超えている", body = ErrorResponse),
+        (status = 422, description = "キャンバスの範囲外のレコードがある", body = ErrorResponse),
+        (status = 507, description = "アートワークのメモリ使用量の上限を超える", body = ErrorResponse)
+    )
+)]
+pub async fn apply_dot_diff(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    body: Bytes,
+) -> Result<Json<BulkDotsResponse>, ErrorResponse> {
+    let diff = DotDiff::decode(&body)
+        .map_err(|e| ErrorResponse::new(dot_diff_status(&e), e.to_string()))?;
+
+    let _lock = state
+        .artwork_locks
+        .write(&id, MessageKey::OperationCanvasEdit)?;
+    let _edit = state.artwork_edits.lock().await;
+    let mut artwork = state.artwork_or_not_found(&id).await?;
+    let mut canvas = artwork.canvas.clone();
+    let changed_dots = canvas
+        .apply_dot_diff(&diff)
+        .map_err(|e| ErrorResponse::new(dot_diff_status(&e), e.to_string()))?;
+    let previous = artwork.canvas.clone();
+    let change = ArtworkChange::dots_edited(&previous, &canvas, changed_dots);
+    artwork.update_canvas(canvas);
+    let event = artwork.record_change(change, EventMetadata::new("api".to_string()));
+    state.save_edited_artwork(&artwork).await?;
+    state
+        .canvas_history
+        .record(&artwork.id, &previous, &artwork.canvas);
+    state.publish_change(event).await;
+
+    Ok(Json(BulkDotsResponse {
+        id: artwork.id.as_str().to_string(),
+        version: artwork.version,
+        changed_dots,
+        drawable_dots: artwork.drawable_dots(),
+    }))
+}
+
+/// 取り消し・やり直しの結果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CanvasHistoryResponse {
+    pub id: String,
+    /// 適用後のバージョン（取り消し・やり直しでも1つ上がる）
+    pub version: u32,
+    /// 内容を戻したドット数
+    pub changed_dots: u64,
+    /// 適用後の描画対象ドット数
+    pub drawable_dots: usize,
+    /// 残っている取り消しの数
+    pub undo_steps: usize,
+    /// 残っているやり直しの数
+    pub redo_steps: usize,
+}
+
+/// Undo the most recent canvas edit of an artwork
+///
+/// 描画済みのフラグは戻さないため、取り消しても描画の進捗はそのまま残る。
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/undo", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    responses(
+        (status = 200, description = "取り消し後の状態", body = CanvasHistoryResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "取り消せる編集がない、またはアートワークを描画中・パスの計算中", body = ErrorResponse)
+    )
+)]
+pub async fn undo_artwork_edit(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+) -> Result<Json<CanvasHistoryResponse>, ErrorResponse> {
+    step_canvas_history(&state, &id, HistoryDirection::Undo).await
+}
+
+/// Redo the most recently undone canvas edit of an artwork
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/redo", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    responses(
+        (status = 200, description = "やり直し後の状態", body = CanvasHistoryResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "やり直せる編集がない、またはアートワークを描画中・パスの計算中", body = ErrorResponse)
+    )
+)]
+pub async fn redo_artwork_edit(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+) -> Result<Json<CanvasHistoryResponse>, ErrorResponse> {
+    step_canvas_history(&state, &id, HistoryDirection::Redo).await
+}
+
+async fn step_canvas_history(
+    state: &ArtworkState,
+    id: &str,
+    direction: HistoryDirection,
+) -> Result<Json<CanvasHistoryResponse>, ErrorResponse> {
+    let operation = match direction {
+        HistoryDirection::Undo => MessageKey::OperationUndo,
+        HistoryDirection::Redo => MessageKey::OperationRedo,
+    };
+    let _lock = state.artwork_locks.write(id, operation)?;
+    let _edit = state.artwork_edits.lock().await;
+    let mut artwork = state.artwork_or_not_found(id).await?;
+    let mut canvas = artwork.canvas.clone();
+    let Some(changed_dots) = state
+        .canvas_history
+        .step(&artwork.id, direction, &mut canvas)
+    else {
+        let key = match direction {
+            HistoryDirection::Undo => MessageKey::NothingToUndo,
+            HistoryDirection::Redo => MessageKey::NothingToRedo,
+        };
+        return Err(ErrorResponse::localized(
+            StatusCode::CONFLICT,
+            Message::new(key),
+        ));
+    };
+    let change = ArtworkChange::history_step(direction, &artwork.canvas, &canvas);
+    artwork.update_canvas(canvas);
+    let event = artwork.record_change(change, EventMetadata::new("api".to_string()));
+    if let Err(e) = state.artworks.save(&artwork).await {
+        // 保存できなかった場合は履歴の位置を元に戻す
+        let mut discarded = artwork.canvas.clone();
+        state
+            .canvas_history
+            .step(&artwork.id, direction.opposite(), &mut discarded);
+        return Err(e.into());
+    }
+    // 取り消しは以前の内容に戻すだけなので、上限を超えても受け付ける
+    state.memory_budget.account(&artwork);
+    state.publish_change(event).await;
+
+    let depth = state.canvas_history.depth(&artwork.id);
+    Ok(Json(CanvasHistoryResponse {
+        id: artwork.id.as_str().to_string(),
+        version: artwork.version,
+        changed_dots,
+        drawable_dots: artwork.drawable_dots(),
+        undo_steps: depth.undo,
+        redo_steps: depth.redo,
+    }))
+}
+
+/// Get a specific artwork
+///
+/// `?format=compact` または `Accept: application/vnd.ghost-drawer.canvas-rle+json` を指定すると、
+/// 概要に加えてランレングス符号化したキャンバスの中身（`canvas`）を返す。
+#[utoipa::path(
+    get, path = "/api/artworks/{id}", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID"), CanvasFormatQuery),
+    responses(
+        (status = 200, description = "アートワーク（`format=compact` の場合は `ArtworkDetailResponse`）", body = ArtworkSummary),
+        (status = 304, description = "`If-None-Match` が一致"),
+        (status = 404, description = "アートワークが存在しない")
+    )
+)]
+pub async fn get_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Query(query): Query<CanvasFormatQuery>,
+    headers: HeaderMap,
+) -> Result<Response, StatusCode> {
+    let Some(artwork) = state.find_artwork(&id).await.map_err(|e| e.status())? else {
+        return Err(StatusCode::NOT_FOUND);
+    };
+    let etag = ETag::for_artwork(&artwork);
+    let mut response = match query.resolve(&headers) {
+        CanvasWireFormat::Dots => {
+            conditional_json(&headers, etag, || ArtworkSummary::from(&artwork))
+        }
+        CanvasWireFormat::Compact => conditional_json(&headers, etag.variant("compact"), || {
+            ArtworkDetailResponse {
+                artwork: ArtworkSummary::from(&artwork),
+                canvas: CompactCanvas::from(&artwork.canvas),
+            }
+        }),
+    };
+    response
+        .headers_mut()
+        .insert(header::VARY, header::HeaderValue::from_static("Accept"));
+    Ok(response)
+}
+
+/// Export an artwork as a create request
+///
+/// `POST /api/artworks` にそのまま送ると同じ内容のアートワークを作れるJSONを返す。
+/// ドットは `?format=compact`（または `Accept: application/vnd.ghost-drawer.canvas-rle+json`）で
+/// 行優先のランレングス符号化（`rows`）、それ以外は `dots` で表す。描画の進捗は含めない。
+/// `?include_history=true` で変更履歴を `history` に含める。
+#[utoipa::path(
+    get, path = "/api/artworks/{id}/export", tag = "artworks",
+    params(
+        ("id" = String, Path, description = "アートワークID"),
+        CanvasFormatQuery,
+        ExportArtworkQuery
+    ),
+    responses(
+        (status = 200, description = "作成リクエスト", body = CreateArtworkRequest),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse)
+    )
+)]
+pub async fn export_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Query(query): Query<CanvasFormatQuery>,
+    Query(export): Query<ExportArtworkQuery>,
+    headers: HeaderMap,
+) -> Result<Response, ErrorResponse> {
+    let artwork = state.artwork_or_not_found(&id).await?;
+    let format = query.resolve(&headers);
+    let mut request = CreateArtworkRequest::from_artwork(&artwork, format);
+    if export.include_history {
+        request.history = artwork.change_log.iter().map(Into::into).collect();
+    }
+    info!(
+        "Exported artwork {} ({:?}, {} dots, {} compact rows)",
+        id,
+        format,
+        request.dots.len(),
+        request.rows.len()
+    );
+
+    Ok((
+        [
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{id}.json\""),
+            ),
+            (header::VARY, "Accept".to_string()),
+        ],
+        Json(request),
+    )
+        .into_response())
+}
+
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct ExportArtworkQuery {
+    /// 変更履歴を `history` に含める
+    #[serde(default)]
+    pub include_history: bool,
+}
+
+/// 変更履歴の1件
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ChangeLogEntryResponse {
+    /// 変更後のバージョン
+    pub version: u32,
+    pub occurred_at: i64,
+    /// 変更した経路（`api`・`upload`・`painting` など）
+    pub source: String,
+    pub kind: ChangeKind,
+    /// 変更内容の短い説明（例: `transformed: flip_horizontal`）
+    pub summary: String,
+    pub dots_added: usize,
+    pub dots_removed: usize,
+    /// 色・不透明度・レイヤーが変わったドット数
+    pub dots_changed: usize,
+}
+
+impl From<&ChangeLogEntry> for ChangeLogEntryResponse {
+    fn from(entry: &ChangeLogEntry) -> Self {
+        Self {
+            version: entry.version,
+            occurred_at: entry.occurred_at.epoch_millis as i64,
+            source: entry.source.clone(),
+            kind: entry.kind,
+            summary: entry.summary.clone(),
+            dots_added: entry.dots.added,
+            dots_removed: entry.dots.removed,
+            dots_changed: entry.dots.changed,
+        }
+    }
+}
+
+/// 取り消したパスの計算
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CancelComputationsResponse {
+    pub id: String,
+    /// 取り消した実行中の計算（描画パス・解析・戦略の比較）の数
+    pub cancelled: usize,
+}
+
+/// アートワークの変更履歴
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ArtworkHistoryResponse {
+    pub id: String,
+    /// 現在のバージョン
+    pub version: u32,
+    /// 古い順（直近100件まで）
+    pub entries: Vec<ChangeLogEntryResponse>,
+}
+
+/// Get the change log of an artwork
+///
+/// バージョンを上げた操作ごとに、種類・内容の説明・ドットの増減を記録している。
+/// この機能より前に作成・変更したアートワークは、それ以降の変更だけが記録される。
+#[utoipa::path(
+    get, path = "/api/artworks/{id}/history", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    responses(
+        (status = 200, description = "変更履歴", body = ArtworkHistoryResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse)
+    )
+)]
+pub async fn get_artwork_history(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ArtworkHistoryResponse>, ErrorResponse> {
+    let artwork = state.artwork_or_not_found(&id).await?;
+    Ok(Json(ArtworkHistoryResponse {
+        id: artwork.id.as_str().to_string(),
+        version: artwork.version,
+        entries: artwork.change_log.iter().map(Into::into).collect(),
+    }))
+}
+
+/// 差分の応答に含める座標の最大数（件数は常に全体を返す）
+const MAX_DIFF_COORDINATES: usize = 1000;
+
+/// 差分に含まれるドット
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DiffDots {
+    pub count: usize,
+    /// 行優先の昇順（最大1000件）
+    pub coordinates: Vec<Coordinates>,
+    /// `coordinates` が上限で切り詰められたか
+    pub truncated: bool,
+}
+
+impl From<Vec<Coordinates>> for DiffDots {
+    fn from(mut coordinates: Vec<Coordinates>) -> Self {
+        let count = coordinates.len();
+        coordinates.truncate(MAX_DIFF_COORDINATES);
+        Self {
+            count,
+            coordinates,
+            truncated: count > MAX_DIFF_COORDINATES,
+        }
+    }
+}
+
+/// 2つのアートワークのキャンバスの差分
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ArtworkDiffResponse {
+    pub a: String,
+    pub b: String,
+    pub width: u16,
+    pub height: u16,
+    pub only_in_a: DiffDots,
+    pub only_in_b: DiffDots,
+    pub in_both: DiffDots,
+}
+
+/// Compare the canvases of two artworks of the same size
+///
+/// 描画済みかどうかは問わず、不透明なドットがあるかどうかで比較する。
+#[utoipa::path(
+    get, path = "/api/artworks/{a}/diff/{b}", tag = "artworks",
+    params(
+        ("a" = String, Path, description = "比較元のアートワークID"),
+        ("b" = String, Path, description = "比較先のアートワークID")
+    ),
+    responses(
+        (status = 200, description = "差分", body = ArtworkDiffResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 422, description = "キャンバスのサイズが異なる", body = ErrorResponse)
+    )
+)]
+pub async fn get_artwork_diff(
+    State(state): State<Arc<ArtworkState>>,
+    Path((a, b)): Path<(String, String)>,
+) -> Result<Json<ArtworkDiffResponse>, ErrorResponse> {
+    let artwork_a = state.artwork_or_not_found(&a).await?;
+    let artwork_b = state.artwork_or_not_found(&b).await?;
+    let diff = artwork_a
+        .canvas
+        .diff(&artwork_b.canvas)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    Ok(Json(ArtworkDiffResponse {
+        a,
+        b,
+        width: artwork_a.canvas.width,
+        height: artwork_a.canvas.height,
+        only_in_a: diff.only_in_base.into(),
+        only_in_b: diff.only_in_target.into(),
+        in_both: diff.in_both.into(),
+    }))
+}
+
+/// Duplicate an artwork with a new ID, optionally applying transforms to the copy
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/duplicate", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    request_body(content = Option<DuplicateArtworkRequest>, description = "省略可"),
+    responses(
+        (status = 200, description = "複製したアートワーク", body = ArtworkResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 507, description = "アートワークのメモリ使用量の上限を超える", body = ErrorResponse)
+    )
+)]
+pub async fn duplicate_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    request: Option<Json<DuplicateArtworkRequest>>,
+) -> Result<Json<ArtworkResponse>, ErrorResponse> {
+    let request = request.map(|Json(request)| request).unwrap_or_default();
+
+    let source = state.artwork_or_not_found(&id).await?;
+    let mut copy = source.duplicate(request.name);
+
+    for transform in &request.transforms {
+        copy.canvas.apply_transform(*transform);
+    }
+    let change = ArtworkChange::duplicated(&source, &copy.canvas, &request.transforms);
+
+    let copy_id = copy.id.as_str().to_string();
+    let summary = ArtworkSummary::from(&copy);
+    let warnings = canvas_warnings(&copy.canvas);
+    let event_metadata = EventMetadata::new("api".to_string())
+        .add_property("duplicated_from".to_string(), id.clone());
+    state
+        .insert_changed_artwork(copy, change, event_metadata)
+        .await?;
+
+    info!("Artwork {} duplicated as {}", id, copy_id);
+
+    Ok(Json(ArtworkResponse {
+        id: copy_id,
+        message: format!("Artwork '{}' duplicated successfully", summary.name),
+        artwork: Some(summary),
+        estimated_painting_seconds: None,
+        duplicate: false,
+        warnings,
+        duplicates_resolved: None,
+        set_id: None,
+        artwork_ids: Vec::new(),
+        edge_detection: None,
+        placement: None,
+    }))
+}
+
+/// ゴミ箱のアートワークを完全に削除するまでの既定の期間
+pub const DEFAULT_TRASH_RETENTION: std::time::Duration =
+    std::time::Duration::from_secs(7 * 24 * 60 * 60);
+
+/// アートワークの削除の指定
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteArtworkRequest {
+    /// ゴミ箱に移さずに完全に削除する（ゴミ箱のアートワークも削除できる）
+    #[serde(default)]
+    pub permanent: bool,
+}
+
+/// Delete an artwork
+///
+/// 既定ではゴミ箱に移し、`POST /api/artworks/{id}/restore` で元に戻せる。ゴミ箱のアートワークは
+/// 一覧・取得・描画の対象にならないが、メモリ使用量の計上には残る。`permanent=true` で完全に削除する。
+/// 描画中・キャンバスの変更中のアートワークは削除できない。実行中のパスの計算は取り消す。
+#[utoipa::path(
+    delete, path = "/api/artworks/{id}", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID"), DeleteArtworkRequest),
+    responses(
+        (status = 200, body = ApiResponse),
+        (status = 404, description = "アートワークが存在しない（`permanent=true` ではゴミ箱にも無い）", body = ErrorResponse),
+        (status = 409, description = "アートワークを描画中、またはキャンバスを変更中", body = ErrorResponse)
+    )
+)]
+pub async fn delete_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Query(request): Query<DeleteArtworkRequest>,
+) -> Result<Json<ApiResponse>, ErrorResponse> {
+    let not_found = || {
+        ErrorResponse::localized(
+            StatusCode::NOT_FOUND,
+            Message::new(MessageKey::ArtworkNotFound).with_arg("id", &id),
+        )
+    };
+    let artwork_id = ArtworkId::parse(&id).map_err(|_| not_found())?;
+
+    let _lock = lock_for_deletion(&state, &artwork_id).await?;
+    let artwork = match state.artworks.find_by_id(&artwork_id).await? {
+        Some(artwork) => artwork,
+        None if request.permanent => find_trashed(&state, &artwork_id)
+            .await?
+            .map(|trashed| trashed.artwork)
+            .ok_or_else(not_found)?,
+        None => return Err(not_found()),
+    };
+    let message = if request.permanent {
+        discard_artwork(&state, &artwork_id).await?;
+        info!("Artwork {} deleted permanently", id);
+        "Artwork deleted permanently"
+    } else {
+        state
+            .artworks
+            .move_to_trash(&artwork_id, Timestamp::now())
+            .await?;
+        state.analyses.remove(&artwork_id.as_str());
+        info!("Artwork {} moved to the trash", id);
+        "Artwork moved to the trash"
+    };
+    state
+        .publish_change(Some(ArtworkEvent::artwork_deleted(
+            artwork.id,
+            artwork.metadata.name,
+            request.permanent,
+            artwork.version,
+            EventMetadata::new("api".to_string()),
+        )))
+        .await;
+    Ok(Json(ApiResponse {
+        success: true,
+        message: message.to_string(),
+    }))
+}
+
+/// 削除で取り消したパスの計算が終わるのを待つ間隔と回数
+const CANCELLED_COMPUTATION_POLL: std::time::Duration = std::time::Duration::from_millis(50);
+const CANCELLED_COMPUTATION_POLLS: u32 = 40;
+
+/// 削除の登録を取る（描画中・キャンバスの変更中なら409）
+///
+/// 実行中のパスの計算は取り消し、計算が登録を外すまで少しだけ待つ。
+async fn lock_for_deletion(
+    state: &ArtworkState,
+    id: &ArtworkId,
+) -> Result<ArtworkWriteGuard, ErrorResponse> {
+    let cancelled = state.path_computations.cancel_artwork(&id.as_str());
+    let mut polls = 0;
+    let lock = loop {
+        match state
+            .artwork_locks
+            .write(&id.as_str(), MessageKey::OperationDeletion)
+        {
+            Ok(lock) => break lock,
+            Err(conflict)
+                if cancelled > 0
+                    && polls < CANCELLED_COMPUTATION_POLLS
+                    && matches!(
+                        conflict.held_by,
+                        MessageKey::OperationPathComputation
+                            | MessageKey::OperationAnalysis
+                            | MessageKey::OperationStrategyComparison
+                    ) =>
+            {
+                polls += 1;
+                tokio::time::sleep(CANCELLED_COMPUTATION_POLL).await;
+            }
+            Err(conflict) => return Err(conflict.into()),
+        }
+    };
+    if cancelled > 0 {
+        info!(
+            "Cancelled {} path computation(s) of deleted artwork {}",
+            cancelled, id
+        );
+    }
+    Ok(lock)
+}
+
+/// アートワークを完全に削除する（描画中・キャンバスの変更中なら409）
+pub(crate) async fn remove_artwork(
+    state: &ArtworkState,
+    id: &ArtworkId,
+) -> Result<(), ErrorResponse> {
+    let _lock = lock_for_deletion(state, id).await?;
+    discard_artwork(state, id).await?;
+    Ok(())
+}
+
+/// ゴミ箱からアートワークを探す
+async fn find_trashed(
+    state: &ArtworkState,
+    id: &ArtworkId,
+) -> Result<Option<TrashedArtwork>, RepositoryError> {
+    Ok(state
+        .artworks
+        .find_trashed()
+        .await?
+        .into_iter()
+        .find(|trashed| trashed.artwork.id == *id))
+}
+
+/// ゴミ箱のアートワーク
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrashedArtworkSummary {
+    #[serde(flatten)]
+    pub artwork: ArtworkSummary,
+    /// ゴミ箱に移した日時（Unix時間のミリ秒）
+    pub deleted_at: i64,
+    /// 保持期間が過ぎ、完全に削除される対象になる日時（Unix時間のミリ秒）
+    pub purge_after: i64,
+}
+
+/// ゴミ箱の一覧
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TrashResponse {
+    /// ゴミ箱に移した日時の古い順
+    pub artworks: Vec<TrashedArtworkSummary>,
+    /// ゴミ箱に残す日数
+    pub retention_days: u64,
+}
+
+/// List artworks in the trash
+#[utoipa::path(
+    get, path = "/api/artworks/trash", tag = "artworks",
+    responses((status = 200, description = "ゴミ箱のアートワーク", body = TrashResponse))
+)]
+pub async fn list_trash(
+    State(state): State<Arc<ArtworkState>>,
+) -> Result<Json<TrashResponse>, ErrorResponse> {
+    let retention_ms = state.trash_retention.as_millis() as i64;
+    let artworks = state
+        .artworks
+        .find_trashed()
+        .await?
+        .iter()
+        .map(|trashed| TrashedArtworkSummary {
+            artwork: ArtworkSummary::from(&trashed.artwork),
+            deleted_at: trashed.deleted_at.epoch_millis as i64,
+            purge_after: trashed.deleted_at.epoch_millis as i64 + retention_ms,
+        })
+        .collect();
+    Ok(Json(TrashResponse {
+        artworks,
+        retention_days: state.trash_retention.as_secs() / (24 * 60 * 60),
+    }))
+}
+
+/// Restore an artwork from the trash
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/restore", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    responses(
+        (status = 200, description = "元に戻したアートワーク", body = ArtworkSummary),
+        (status = 404, description = "アートワークがゴミ箱に無い", body = ErrorResponse)
+    )
+)]
+pub async fn restore_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+) -> Result<Json<ArtworkSummary>, ErrorResponse> {
+    let not_in_trash = || {
+        ErrorResponse::localized(
+            StatusCode::NOT_FOUND,
+            Message::new(MessageKey::ArtworkNotInTrash).with_arg("id", &id),
+        )
+    };
+    let artwork_id = ArtworkId::parse(&id).map_err(|_| not_in_trash())?;
+    match state.artworks.restore_from_trash(&artwork_id).await {
+        Err(RepositoryError::NotFound { .. }) => return Err(not_in_trash()),
+        result => result?,
+    }
+    let artwork = state.artwork_or_not_found(&id).await?;
+    info!("Artwork {} restored from the trash", id);
+    Ok(Json(ArtworkSummary::from(&artwork)))
+}
+
+/// 完全に削除したゴミ箱のアートワーク
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PurgeTrashResponse {
+    /// 完全に削除したアートワークのID
+    pub purged: Vec<String>,
+}
+
+/// Permanently delete artworks kept in the trash longer than the retention period
+///
+/// 保持期間はサーバーの `--trash-retention-days`（既定7日）。起動時にも同じように削除する。
+#[utoipa::path(
+    post, path = "/api/artworks/trash/purge", tag = "artworks",
+    responses((status = 200, description = "完全に削除したアートワーク", body = PurgeTrashResponse))
+)]
+pub async fn purge_trash(
+    State(state): State<Arc<ArtworkState>>,
+) -> Result<Json<PurgeTrashResponse>, ErrorResponse> {
+    let purged = purge_expired_trash(&state).await?;
+    Ok(Json(PurgeTrashResponse {
+        purged: purged.iter().map(|id| id.as_str()).collect(),
+    }))
+}
+
+/// 保持期間を過ぎたゴミ箱のアートワークを完全に削除し、削除したIDを返す
+pub(crate) async fn purge_expired_trash(
+    state: &ArtworkState,
+) -> Result<Vec<ArtworkId>, ErrorResponse> {
+    let cutoff = Timestamp::now()
+        .epoch_millis
+        .saturating_sub(state.trash_retention.as_millis() as u64);
+    let mut purged = Vec::new();
+    for trashed in state.artworks.find_trashed().await? {
+        if trashed.deleted_at.epoch_millis > cutoff {
+            continue;
+        }
+        let artwork = trashed.artwork;
+        let _lock = state
+            .artwork_locks
+            .write(&artwork.id.as_str(), MessageKey::OperationDeletion)?;
+        discard_artwork(state, &artwork.id).await?;
+        info!(
+            "Artwork {} purged from the trash (deleted at {} ms)",
+            artwork.id, trashed.deleted_at.epoch_millis
+        );
+        state
+            .publish_change(Some(ArtworkEvent::artwork_deleted(
+                artwork.id.clone(),
+                artwork.metadata.name,
+                true,
+                artwork.version,
+                EventMetadata::new("trash".to_string()),
+            )))
+            .await;
+        purged.push(artwork.id);
+    }
+    Ok(purged)
+}
+
+/// アートワークを完全に削除し、編集履歴・メモリ使用量の計上・解析結果も破棄する
+///
+/// 呼び出し側で `artwork_locks` の削除の登録を済ませておく。
+pub(crate) async fn discard_artwork(
+    state: &ArtworkState,
+    id: &ArtworkId,
+) -> Result<(), RepositoryError> {
+    state.artworks.delete(id).await?;
+    state.canvas_history.remove(id);
+    state.memory_budget.release(id);
+    state.analyses.remove(&id.as_str());
+    Ok(())
+}
+
+/// Get drawing path for an artwork
+#[utoipa::path(
+    get, path = "/api/artworks/{id}/path", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID"), GetPathRequest),
+    responses(
+        (status = 200, description = "描画順の座標列", body = PathResponse),
+        (status = 400, description = "描画領域・配置位置の形式が不正", body = ErrorResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "アートワークのキャンバスを変更中・削除中、または計算が取り消された", body = ErrorResponse),
+        (status = 422, description = "描画領域がキャンバス外、または配置したアートワークがゲーム内キャンバスに収まらない", body = ErrorResponse)
+    )
+)]
+pub async fn get_artwork_path(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Query(params): Query<GetPathRequest>,
+) -> Result<Json<PathResponse>, ErrorResponse> {
+    let _lock = state
+        .artwork_locks
+        .read(&id, MessageKey::OperationPathComputation)?;
+    match state.find_artwork(&id).await? {
+        Some(artwork) => {
+            let region = params
+                .region
+                .as_deref()
+                .map(|value| parse_region(value, &artwork.canvas))
+                .transpose()?;
+            let origin = params
+                .origin
+                .as_deref()
+                .map(|value| parse_origin(value, &artwork.canvas))
+                .transpose()?;
+
+            let strategy = params.strategy.unwrap_or(DrawingStrategy::GreedyTwoOpt);
+            let mut config = DrawingCanvasConfig::default();
+            config.options.origin = origin;
+            let mut converter = ArtworkToCommandConverter::new(config, strategy)
+                .with_seed(params.seed)
+                .with_two_opt_settings(state.two_opt_settings(params.two_opt_budget_ms));
+            if let Some(region) = region {
+                converter = converter.with_region(region);
+            }
+            let computation = state
+                .path_computations
+                .start(&id, PathComputationKind::Path(strategy));
+            let cancel = computation.token().clone();
+            let drawing_path = tokio::task::spawn_blocking(move || {
+                converter.create_cancellable_drawing_path(&artwork.canvas, &cancel)
+            })
+            .await
+            .map_err(|e| {
+                error!("Path computation task failed: {}", e);
+                ErrorResponse::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to compute drawing path",
+                )
+            })??;
+            let path_hash = drawing_path.path_hash();
+
+            Ok(Json(PathResponse {
+                estimated_time_sec: drawing_path.estimated_time_ms as f64 / 1000.0,
+                path_hash,
+                stats: PathStats {
+                    total_distance: drawing_path.total_distance,
+                    two_opt: drawing_path.two_opt,
+                },
+                path: drawing_path.coordinates,
+            }))
+        }
+        None => Err(ErrorResponse::localized(
+            StatusCode::NOT_FOUND,
+            Message::new(MessageKey::ArtworkNotFound).with_arg("id", &id),
+        )),
+    }
+}
+
+/// Analyze an artwork before painting it
+///
+/// アートワークのバージョンと描画戦略が同じ間は、前回の結果を返す。
+/// 取り消された計算の結果は保存しない。
+#[utoipa::path(
+    get, path = "/api/artworks/{id}/analysis", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID"), ArtworkAnalysisQuery),
+    responses(
+        (status = 200, description = "統計・タイルごとのドット数・描画パスの概要", body = ArtworkAnalysisResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "アートワークのキャンバスを変更中・削除中、または計算が取り消された", body = ErrorResponse),
+        (status = 422, description = "保存された描画設定の配置位置がゲーム内キャンバスに収まらない", body = ErrorResponse)
+    )
+)]
+pub async fn get_artwork_analysis(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Query(query): Query<ArtworkAnalysisQuery>,
+) -> Result<Json<ArtworkAnalysisResponse>, ErrorResponse> {
+    let _lock = state
+        .artwork_locks
+        .read(&id, MessageKey::OperationAnalysis)?;
+    let artwork = state.artwork_or_not_found(&id).await?;
+    // 描画を開始する場合と同じく、リクエスト > アートワークの描画設定 > 既定値の順に決める
+    let request = PaintRequest {
+        strategy: query.strategy,
+        ..PaintRequest::default()
+    }
+    .with_preferences(artwork.painting_preferences.as_ref());
+    let strategy = request.strategy.unwrap_or(DrawingStrategy::GreedyTwoOpt);
+    if let Some(analysis) = state.analyses.get(&id, artwork.version, strategy) {
+        debug!(
+            "Using cached analysis of artwork {} v{}",
+            id, artwork.version
+        );
+        return Ok(Json(analysis));
+    }
+
+    let config = drawing_config(
+        &request,
+        &artwork.canvas,
+        state.pause,
+        state.init_preset,
+        state.preflight,
+    )?;
+    let two_opt = state.two_opt;
+    let computation = state
+        .path_computations
+        .start(&id, PathComputationKind::Analysis(strategy));
+    let cancel = computation.token().clone();
+    let analysis = tokio::task::spawn_blocking(move || {
+        analyze_artwork(&artwork, config, strategy, two_opt, &cancel)
+    })
+    .await
+    .map_err(|e| {
+        error!("Artwork analysis task failed: {}", e);
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to analyze artwork",
+        )
+    })??;
+    state.analyses.insert(analysis.clone());
+    Ok(Json(analysis))
+}
+
+/// アートワークの統計と、`strategy` で描画する場合のパスを求める
+fn analyze_artwork(
+    artwork: &Artwork,
+    config: DrawingCanvasConfig,
+    strategy: DrawingStrategy,
+    two_opt: TwoOptSettings,
+    cancel: &CancellationToken,
+) -> Result<ArtworkAnalysisResponse, PathCancelled> {
+    let canvas = &artwork.canvas;
+    let drawing_path = ArtworkToCommandConverter::new(config, strategy)
+        .with_two_opt_settings(two_opt)
+        .create_cancellable_drawing_path(canvas, cancel)?;
+    Ok(ArtworkAnalysisResponse {
+        id: artwork.id.as_str(),
+        version: artwork.version,
+        statistics: artwork.statistics(),
+        tile_size: ANALYSIS_TILE_SIZE,
+        tiles: canvas.tile_dot_counts(ANALYSIS_TILE_SIZE),
+        isolated_dots: canvas.isolated_dots(),
+        bounding_box: canvas.bounding_box().map(|(top_left, bottom_right)| {
+            CanvasRegion::new(
+                top_left.x,
+                top_left.y,
+                bottom_right.x - top_left.x + 1,
+                bottom_right.y - top_left.y + 1,
+            )
+        }),
+        path: AnalysisPathSummary {
+            strategy,
+            dots: drawing_path.coordinates.len(),
+            total_distance: drawing_path.total_distance,
+            longest_move: drawing_path.longest_move(),
+            estimated_time_seconds: drawing_path.estimated_time_ms as f64 / 1000.0,
+            path_hash: drawing_path.path_hash(),
+            two_opt: drawing_path.two_opt,
+        },
+    })
+}
+
+/// Get stats for all drawing strategies
+#[utoipa::path(
+    get, path = "/api/artworks/{id}/strategies", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID"), StrategyComparisonRequest),
+    responses(
+        (status = 200, description = "戦略ごとの見積もり", body = StrategyComparisonResponse),
+        (status = 400, description = "配置位置の形式が不正", body = ErrorResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "アートワークのキャンバスを変更中・削除中、または計算が取り消された", body = ErrorResponse),
+        (status = 422, description = "配置したアートワークがゲーム内キャンバスに収まらない", body = ErrorResponse)
+    )
+)]
+pub async fn get_artwork_strategies(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Query(params): Query<StrategyComparisonRequest>,
+) -> Result<Json<StrategyComparisonResponse>, ErrorResponse> {
+    // 計算が終わるまでキャンバスの変更と削除を受け付けない
+    let _lock = state
+        .artwork_locks
+        .read(&id, MessageKey::OperationStrategyComparison)?;
+    match state.find_artwork(&id).await? {
+        Some(artwork_clone) => {
+            let origin = params
+                .origin
+                .as_deref()
+                .map(|value| parse_origin(value, &artwork_clone.canvas))
+                .transpose()?;
+            let defaults = PaintTiming::default();
+            let config = DrawingCanvasConfig::new(
+                PaintTiming::new(
+                    params.press_ms.unwrap_or(defaults.press_ms),
+                    params.release_ms.unwrap_or(defaults.release_ms),
+                    params.wait_ms.unwrap_or(defaults.wait_ms),
+                ),
+                RunOptions {
+                    repeats: params.repeats.unwrap_or(1).max(1),
+                    diagonal_moves: params.diagonal_moves.unwrap_or(false),
+                    origin,
+                    ..RunOptions::default()
+                },
+            );
+            let two_opt = state.two_opt_settings(params.two_opt_budget_ms);
+
+            let max_sample_dots = (params.mode == Some(StrategyComparisonMode::Fast))
+                .then(|| params.sample_dots.unwrap_or(DEFAULT_SAMPLE_DOTS));
+
+            // Calculate strategies in a blocking thread to avoid blocking the async runtime
+            let computation = state
+                .path_computations
+                .start(&id, PathComputationKind::Strategies);
+            let cancel = computation.token().clone();
+            let comparison = tokio::task::spawn_blocking(move || {
+                compare_strategies(
+                    &artwork_clone.canvas,
+                    &config,
+                    two_opt,
+                    max_sample_dots,
+                    &cancel,
+                )
+            })
+            .await
+            .map_err(|e| {
+                error!("Strategy calculation task failed: {}", e);
+                ErrorResponse::new(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Failed to compare strategies",
+                )
+            })??;
+
+            Ok(Json(comparison))
+        }
+        None => Err(ErrorResponse::localized(
+            StatusCode::NOT_FOUND,
+            Message::new(MessageKey::ArtworkNotFound).with_arg("id", &id),
+        )),
+    }
+}
+
+/// Cancel running path computations of an artwork
+///
+/// 描画パス・解析・戦略の比較の実行中の計算を取り消す。取り消された計算のリクエストは409で終わり、
+/// 途中の結果は保存しない。
+#[utoipa::path(
+    delete, path = "/api/artworks/{id}/strategies/compute", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID")),
+    responses(
+        (status = 200, description = "取り消した計算の数", body = CancelComputationsResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse)
+    )
+)]
+pub async fn cancel_artwork_computations(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+) -> Result<Json<CancelComputationsResponse>, ErrorResponse> {
+    let artwork = state.artwork_or_not_found(&id).await?;
+    let id = artwork.id.as_str();
+    let cancelled = state.path_computations.cancel_artwork(&id);
+    info!(
+        "Cancelled {} path computation(s) of artwork {}",
+        cancelled, id
+    );
+    Ok(Json(CancelComputationsResponse { id, cancelled }))
+}
+
+/// 全戦略の見積もりを計算する
+///
+/// `max_sample_dots` を指定すると、描画対象のドットがそれを超える場合は横帯単位で間引いたキャンバスで計算し、
+/// 回数と時間をドット数の比で換算する。
+/// `cancel` が取り消されたら残りの戦略は計算しない。
+fn compare_strategies(
+    canvas: &Canvas,
+    config: &DrawingCanvasConfig,
+    two_opt: TwoOptSettings,
+    max_sample_dots: Option<usize>,
+    cancel: &CancellationToken,
+) -> Result<StrategyComparisonResponse, PathCancelled> {
+    let sample = max_sample_dots.and_then(|max_dots| sample_row_bands(canvas, max_dots));
+    let scale_count = |count: u64| {
+        sample
+            .as_ref()
+            .map_or(count, |sample| sample.scale_count(count)) as usize
+    };
+    let scale_ms = |ms: u64| sample.as_ref().map_or(ms as f64, |s| ms as f64 * s.scale()) / 1000.0;
+    let target = sample.as_ref().map_or(canvas, |sample| &sample.canvas);
+
+    let strategies = [
+        DrawingStrategy::GreedyTwoOpt,
+        DrawingStrategy::NearestNeighbor,
+        DrawingStrategy::ZigZag,
+        DrawingStrategy::RasterScan,
+    ]
+    .into_iter()
+    .map(|strategy| {
+        let converter =
+            ArtworkToCommandConverter::new(config.clone(), strategy).with_two_opt_settings(two_opt);
+        let drawing_path = converter.create_cancellable_drawing_path(target, cancel)?;
+        let estimate = simulate_run(&drawing_path, &config.timing, &config.options);
+        let layers = simulate_layers(&drawing_path, &config.timing, &config.options)
+            .into_iter()
+            .map(|layer| LayerStats {
+                layer: layer.layer,
+                dots: scale_count(layer.dots as u64),
+                dpad_operations: scale_count(layer.estimate.dpad_ops),
+                a_button_presses: scale_count(layer.estimate.a_presses),
+                neutral_clears: scale_count(layer.estimate.neutral_clears),
+                estimated_time_seconds: scale_ms(layer.estimate.total_ms),
+            })
+            .collect();
+
+        Ok(StrategyStats {
+            strategy,
+            dpad_operations: scale_count(estimate.dpad_ops),
+            a_button_presses: scale_count(estimate.a_presses),
+            neutral_clears: scale_count(estimate.neutral_clears),
+            estimated_time_seconds: scale_ms(estimate.total_ms),
+            layers,
+            two_opt: drawing_path.two_opt,
+        })
+    })
+    .collect::<Result<_, PathCancelled>>()?;
+
+    Ok(match sample {
+        Some(sample) => StrategyComparisonResponse {
+            strategies,
+            approximate: true,
+            sample_dots: sample.sample_dots,
+            total_dots: sample.total_dots,
+            accuracy: Some(EstimateAccuracy::row_bands()),
+        },
+        None => {
+            let total_dots = canvas.dots.values().filter(|dot| dot.is_drawable()).count();
+            StrategyComparisonResponse {
+                strategies,
+                approximate: false,
+                sample_dots: total_dots,
+                total_dots,
+                accuracy: None,
+            }
+        }
+    })
+}
+
+/// Switch-Fightstick向けの書き出し条件（省略時は描画開始時の既定値）
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct FightstickExportRequest {
+    pub strategy: Option<DrawingStrategy>,
+    pub press_ms: Option<u32>,
+    pub release_ms: Option<u32>,
+    pub wait_ms: Option<u32>,
+    pub repeats: Option<u32>,
+    /// 1フレームの長さ（ミリ秒、既定8）。ミリ秒は常に切り上げてフレーム数にする
+    pub frame_ms: Option<u32>,
+    pub init_preset: Option<InitPreset>,
+    /// 描画モード（省略時は `pixel_pen`、初期化手順のペンの選択とドットごとのボタンが変わる）
+    pub drawing_mode: Option<DrawingMode>,
+    /// `c`（`joystick.c` の `step[]` 配列、既定）または `csv`
+    pub format: Option<FightstickFormat>,
+}
+
+/// Export the drawing inputs as a Switch-Fightstick command script
+///
+/// 初期化手順（ペンサイズのL連打と左上への移動）と描画パスの入力を、
+/// AVR/Teensy向けのSwitch-Fightstick系ファームウェアの `{ 入力, フレーム数 }` 配列に変換する。
+/// 十字キーの斜め移動は使わない。
+#[utoipa::path(
+    get, path = "/api/artworks/{id}/export/fightstick", tag = "artworks",
+    params(("id" = String, Path, description = "アートワークID"), FightstickExportRequest),
+    responses(
+        (status = 200, description = "Switch-Fightstick形式のコマンド列", content_type = "text/plain", body = String),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 422, description = "フレームの長さが不正", body = ErrorResponse)
+    )
+)]
+pub async fn export_fightstick(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Query(params): Query<FightstickExportRequest>,
+) -> Result<Response, ErrorResponse> {
+    let artwork = state.artwork_or_not_found(&id).await?;
+
+    let defaults = PaintTiming::default();
+    let mut config = DrawingCanvasConfig::new(
+        PaintTiming::new(
+            params.press_ms.unwrap_or(defaults.press_ms),
+            params.release_ms.unwrap_or(defaults.release_ms),
+            params.wait_ms.unwrap_or(defaults.wait_ms),
+        ),
+        RunOptions {
+            repeats: params.repeats.unwrap_or(1).max(1),
+            ..RunOptions::default()
+        },
+    );
+    config.drawing_mode = params.drawing_mode.unwrap_or_default();
+    config.init_sequence =
+        InitSequence::preset_for(params.init_preset.unwrap_or_default(), config.drawing_mode);
+    let strategy = params.strategy.unwrap_or(DrawingStrategy::GreedyTwoOpt);
+    let converter = ArtworkToCommandConverter::new(config, strategy)
+        .with_two_opt_settings(state.two_opt_settings(None));
+    let drawing_path = converter.create_drawing_path(&artwork.canvas);
+    let commands = converter
+        .painting_commands(&drawing_path)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let script = FightstickScript::from_actions(
+        commands.iter().flat_map(|command| &command.sequence),
+        params.frame_ms.unwrap_or(DEFAULT_FIGHTSTICK_FRAME_MS),
+    )
+    .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    let format = params.format.unwrap_or_default();
+    let title = format!(
+        "{} ({} dots, strategy {:?}) exported by splatoon3-ghost-drawer",
+        artwork.metadata.name,
+        drawing_path.coordinates.len(),
+        strategy
+    );
+    info!(
+        "Exported artwork {} as a Switch-Fightstick script ({} steps, {} frames)",
+        id,
+        script.steps.len(),
+        script.total_frames()
+    );
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!(
+                    "attachment; filename=\"{id}-fightstick.{}\"",
+                    format.extension()
+                ),
+            ),
+        ],
+        script.render(format, &title),
+    )
+        .into_response())
+}
+
+/// Upload artwork image
+#[utoipa::path(
+    post, path = "/api/artworks/upload", tag = "artworks",
+    request_body(
+        content_type = "multipart/form-data",
+        description = "`name`、画像ファイル `file`、任意の `auto_trim` / `center_on_canvas`（`true` / `1` / `on`）、`description`、`author`、`tags`（複数指定またはカンマ区切り）"
+    ),
+    params(UploadArtworkQuery),
+    responses(
+        (status = 200, description = "作成したアートワーク（同じ内容のファイルがアップロード済みの場合は既存のアートワークで `duplicate: true`、フレームに分割した場合は `set_id` と `artwork_ids`）", body = ArtworkResponse),
+        (status = 400, description = "画像、または `scaling_mode` / `gravity` が不正", body = ErrorResponse),
+        (status = 422, description = "タグが不正、空白の除去・中央配置に失敗、アニメーションのフレーム数・大きさが上限を超える、静止画に `edge_detect` を指定、または `alpha_cutoff` が0", body = ErrorResponse),
+        (status = 507, description = "アートワークのメモリ使用量の上限を超える", body = ErrorResponse)
+    )
+)]
+pub async fn upload_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Query(query): Query<UploadArtworkQuery>,
+    mut multipart: Multipart,
+) -> Result<Json<ArtworkResponse>, ErrorResponse> {
+    let mut name = String::new();
+    let mut image_data = Vec::new();
+    let mut original_filename = None;
+    let mut auto_trim = false;
+    let mut center_on_canvas = false;
+    let mut description = String::new();
+    let mut author = String::new();
+    let mut tags = Vec::new();
+
+    // Process multipart form
+    while let Some(field) = multipart.next_field().await.unwrap() {
+        let field_name = field.name().unwrap_or("").to_string();
+
+        match field_name.as_str() {
+            "name" => {
+                name = field.text().await.unwrap_or_default();
+            }
+            "file" => {
+                original_filename = field.file_name().and_then(non_empty);
+                image_data = field.bytes().await.unwrap_or_default().to_vec();
+            }
+            "auto_trim" => {
+                auto_trim = is_truthy(&field.text().await.unwrap_or_default());
+            }
+            "center_on_canvas" => {
+                center_on_canvas = is_truthy(&field.text().await.unwrap_or_default());
+            }
+            "description" => {
+                description = field.text().await.unwrap_or_default();
+            }
+            "author" => {
+                author = field.text().await.unwrap_or_default();
+            }
+            // 複数指定またはカンマ区切り
+            "tags" => {
+                let text = field.text().await.unwrap_or_default();
+                tags.extend(
+                    text.split(',')
+                        .filter(|tag| !tag.trim().is_empty())
+                        .map(str::to_string),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    if name.is_empty() || image_data.is_empty() {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "Both name and file are required",
+        ));
+    }
+
+    info!("Uploading artwork: {} ({} bytes)", name, image_data.len());
+
+    let mut metadata =
+        ArtworkMetadata::new(name.clone()).with_source_file(original_filename, &image_data);
+    if !query.allow_duplicate
+        && let Some(existing) = state.find_by_checksum(&metadata.checksum).await?
+    {
+        info!(
+            "Upload of '{}' matches existing artwork {} (checksum {})",
+            name, existing.id, metadata.checksum
+        );
+        // 分割した組の一部なら、組のアートワークをまとめて返す
+        let set_id = existing.artwork_set.as_ref().map(|set| set.set_id.clone());
+        let artwork_ids = match &set_id {
+            Some(set_id) => set_member_ids(&state, set_id).await?,
+            None => Vec::new(),
+        };
+        return Ok(Json(ArtworkResponse {
+            id: existing.id.as_str().to_string(),
+            message: format!(
+                "Image '{name}' was already uploaded as '{}'",
+                existing.metadata.name
+            ),
+            artwork: Some(ArtworkSummary::from(&existing)),
+            estimated_painting_seconds: None,
+            duplicate: true,
+            warnings: Vec::new(),
+            duplicates_resolved: None,
+            set_id,
+            artwork_ids,
+            edge_detection: None,
+            placement: None,
+        }));
+    }
+
+    metadata.description = non_empty(&description);
+    metadata.author = non_empty(&author);
+    metadata
+        .set_tags(&tags)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    let edge_detection = query.edge_detection();
+    let transparency = query.transparency()?;
+    if query.split_frames
+        && let Some(response) = upload_frames(
+            &state,
+            metadata.clone(),
+            image_data,
+            auto_trim,
+            center_on_canvas,
+            edge_detection,
+            transparency,
+            (query.scaling_mode, query.gravity),
+        )
+        .await?
+    {
+        return Ok(Json(response));
+    }
+    // 静止画はブラウザで2値化したドットを送るため、サーバーでは変換しない
+    if edge_detection.is_some() {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "edge_detect is only supported for animations uploaded with split_frames=true",
+        ));
+    }
+
+    // Create simple canvas (TODO: implement actual image processing)
+    let canvas = fit_canvas(Canvas::new(320, 180), auto_trim, center_on_canvas)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    // Create artwork
+    let artwork = Artwork::new(metadata, "png".to_string(), canvas);
+    let artwork_id = artwork.id.as_str().to_string();
+
+    // Store artwork
+    state
+        .insert_artwork(artwork, EventMetadata::new("upload".to_string()))
+        .await?;
+
+    Ok(Json(ArtworkResponse {
+        id: artwork_id,
+        message: format!("Image '{name}' uploaded successfully"),
+        artwork: None,
+        estimated_painting_seconds: None,
+        duplicate: false,
+        warnings: Vec::new(),
+        duplicates_resolved: None,
+        set_id: None,
+        artwork_ids: Vec::new(),
+        edge_detection: None,
+        placement: None,
+    }))
+}
+
+/// フォームの真偽値（`true` / `1` / `on`）を解釈する
+fn is_truthy(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "true" | "1" | "on"
+    )
+}
+
+// Helper function to parse color from string
+fn parse_color(color_str: &str) -> Option<Color> {
+    if color_str.starts_with('#') && color_str.len() == 7 {
+        let r = u8::from_str_radix(&color_str[1..3], 16).ok()?;
+        let g = u8::from_str_radix(&color_str[3..5], 16).ok()?;
+        let b = u8::from_str_radix(&color_str[5..7], 16).ok()?;
+        Some(Color::new(r, g, b, 255))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::{TestClient, artwork_state_with};
+    use super::*;
+    use crate::domain::artwork::memory_budget::ArtworkMemoryBudget;
+    use crate::domain::events::ArtworkEvent;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    /// 保存済みのアートワークを更新してバージョンを上げる
+    async fn bump_version(state: &ArtworkState, id: &str) {
+        let mut artwork = state.find_artwork(id).await.unwrap().unwrap();
+        artwork.reset_painting_state();
+        state.artworks.save(&artwork).await.unwrap();
+    }
+
+    fn revalidate_headers(response: &Response) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            axum::http::header::IF_NONE_MATCH,
+            response.headers()[axum::http::header::ETAG].clone(),
+        );
+        headers
+    }
+
+    #[tokio::test]
+    async fn test_get_artwork_honors_if_none_match() {
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("etag".to_string()),
+            "api".to_string(),
+            Canvas::new(4, 4),
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+
+        let first = get_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Query(CanvasFormatQuery::default()),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(first.headers()["cache-control"], "no-cache");
+
+        let headers = revalidate_headers(&first);
+        let second = get_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Query(CanvasFormatQuery::default()),
+            headers.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+
+        // バージョンが上がるとETagが変わり、本体が再送される
+        bump_version(&state, &id).await;
+        let third = get_artwork(
+            State(state.clone()),
+            Path(id),
+            Query(CanvasFormatQuery::default()),
+            headers,
+        )
+        .await
+        .unwrap();
+        assert_eq!(third.status(), StatusCode::OK);
+        assert_ne!(third.headers()["etag"], first.headers()["etag"]);
+    }
+
+    #[tokio::test]
+    async fn test_list_artworks_etag_tracks_versions() {
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("list".to_string()),
+            "api".to_string(),
+            Canvas::new(4, 4),
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+
+        let first = list_artworks(
+            State(state.clone()),
+            Query(ListArtworksQuery {
+                tag: None,
+                name: None,
+            }),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        let headers = revalidate_headers(&first);
+        let second = list_artworks(
+            State(state.clone()),
+            Query(ListArtworksQuery {
+                tag: None,
+                name: None,
+            }),
+            headers.clone(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(second.status(), StatusCode::NOT_MODIFIED);
+
+        bump_version(&state, &id).await;
+        let third = list_artworks(
+            State(state),
+            Query(ListArtworksQuery {
+                tag: None,
+                name: None,
+            }),
+            headers,
+        )
+        .await
+        .unwrap();
+        assert_eq!(third.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_create_artwork_centers_trimmed_dots() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let dot = |x, y| DotData {
+            x,
+            y,
+            color: "#000000".to_string(),
+            layer: 0,
+        };
+        let request = CreateArtworkRequest {
+            name: "corner".to_string(),
+            width: 320,
+            height: 120,
+            dots: vec![dot(0, 0), dot(2, 1)],
+            auto_trim: false,
+            center_on_canvas: true,
+            tone_mode: ToneMode::Binary,
+            invert_background: None,
+            description: None,
+            tags: Vec::new(),
+            author: None,
+            painting_preferences: None,
+            vector_paths: Vec::new(),
+            history: Vec::new(),
+            rows: Vec::new(),
+        };
+
+        let Ok(Json(response)) = create_artwork(
+            State(state.clone()),
+            Query(CreateArtworkQuery::default()),
+            Ok(Json(request)),
+        )
+        .await
+        else {
+            panic!("create_artwork failed");
+        };
+        assert_eq!(response.artwork.unwrap().canvas_size, "320x120");
+
+        // 3x2 の描画範囲が中央（左158・右159、上59・下59）に移動する
+        let Json(path) = get_artwork_path(
+            State(state),
+            Path(response.id),
+            Query(GetPathRequest {
+                strategy: Some(DrawingStrategy::RasterScan),
+                region: None,
+                origin: None,
+                seed: None,
+                two_opt_budget_ms: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            path.path,
+            vec![Coordinates::new(158, 59), Coordinates::new(160, 60)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_artwork_metadata_edit_and_tag_filter() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let request = CreateArtworkRequest {
+            name: "squid".to_string(),
+            width: 4,
+            height: 4,
+            dots: vec![DotData {
+                x: 1,
+                y: 1,
+                color: "#000000".to_string(),
+                layer: 0,
+            }],
+            auto_trim: false,
+            center_on_canvas: false,
+            tone_mode: ToneMode::Binary,
+            invert_background: None,
+            description: Some("  ".to_string()),
+            tags: vec![" ink ".to_string(), "ink".to_string()],
+            author: Some(" Agent 3 ".to_string()),
+            painting_preferences: None,
+            vector_paths: Vec::new(),
+            history: Vec::new(),
+            rows: Vec::new(),
+        };
+        let Ok(Json(created)) = create_artwork(
+            State(state.clone()),
+            Query(CreateArtworkQuery::default()),
+            Ok(Json(request)),
+        )
+        .await
+        else {
+            panic!("create_artwork failed");
+        };
+        let summary = created.artwork.unwrap();
+        assert_eq!(summary.tags, vec!["ink"]);
+        assert_eq!(summary.author.as_deref(), Some("Agent 3"));
+        assert_eq!(summary.description, None);
+
+        let Json(updated) = update_artwork_metadata(
+            State(state.clone()),
+            Path(created.id.clone()),
+            Json(UpdateMetadataRequest {
+                description: Some("Inkopolis".to_string()),
+                add_tags: vec!["splat".to_string(), " splat".to_string()],
+                remove_tags: vec!["ink".to_string()],
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.tags, vec!["splat"]);
+        assert_eq!(updated.description.as_deref(), Some("Inkopolis"));
+
+        let invalid = update_artwork_metadata(
+            State(state.clone()),
+            Path(created.id.clone()),
+            Json(UpdateMetadataRequest {
+                add_tags: vec![" ".to_string()],
+                ..Default::default()
+            }),
+        )
+        .await;
+        assert_eq!(
+            invalid.unwrap_err().status_code,
+            StatusCode::UNPROCESSABLE_ENTITY.as_u16()
+        );
+
+        // 検証に失敗した更新はイベントを記録しない
+        let events = state.events.read().await;
+        assert!(matches!(
+            events.last(),
+            Some(ArtworkEvent::ArtworkMetadataUpdated { version: 2, .. })
+        ));
+        drop(events);
+
+        let listed = |tag: &str| {
+            let state = state.clone();
+            let tag = tag.to_string();
+            async move {
+                let response = list_artworks(
+                    State(state),
+                    Query(ListArtworksQuery {
+                        tag: Some(tag),
+                        name: None,
+                    }),
+                    HeaderMap::new(),
+                )
+                .await
+                .unwrap();
+                let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+                    .await
+                    .unwrap();
+                serde_json::from_slice::<Vec<serde_json::Value>>(&body)
+                    .unwrap()
+                    .len()
+            }
+        };
+        assert_eq!(listed("splat").await, 1);
+        assert_eq!(listed("ink").await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_import_script_creates_artwork_with_warnings() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let client = TestClient::new(state.clone());
+        let script = "button,frames\nUP,625\nA,13\nNOTHING,13\nDPAD_RIGHT,13\nNOTHING,13\nA,13\nNOTHING,13\nDPAD_DOWN,13\nA,13\n";
+        let response = client
+            .post(
+                "/api/artworks/import-script",
+                serde_json::json!({ "script": script, "name": "squid" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let body = response.json();
+        assert_eq!(body["warnings"].as_array().unwrap().len(), 1);
+        assert!(body["warnings"][0].as_str().unwrap().contains("'UP'"));
+        let artwork = state
+            .find_artwork(body["id"].as_str().unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(artwork.metadata.name, "squid");
+        assert_eq!(artwork.original_format, "fightstick");
+        let mut dots: Vec<_> = artwork
+            .canvas
+            .drawable_dots()
+            .into_iter()
+            .map(|(coordinates, _)| (coordinates.x, coordinates.y))
+            .collect();
+        dots.sort_unstable();
+        assert_eq!(dots, vec![(0, 0), (1, 0), (1, 1)]);
+
+        // 形式が不正な行と、ドットを描かないスクリプトは422
+        for script in ["{ A, many },", "DPAD_RIGHT 0.1s\n0.1s\n"] {
+            let response = client
+                .post(
+                    "/api/artworks/import-script",
+                    serde_json::json!({ "script": script }),
+                )
+                .await;
+            assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        }
+        assert_eq!(state.artworks.count().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_generate_artwork_stores_test_pattern() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let request = GenerateArtworkRequest {
+            width: 10,
+            height: 6,
+            ..GenerateArtworkRequest::new(TestPattern::Border)
+        };
+
+        let Json(response) = generate_artwork(State(state.clone()), Ok(Json(request)))
+            .await
+            .unwrap();
+        let artwork = state.find_artwork(&response.id).await.unwrap().unwrap();
+        assert_eq!(artwork.metadata.name, "test-border");
+        assert!(artwork.metadata.has_tag("test-pattern"));
+        assert_eq!(artwork.drawable_dots(), 2 * 10 + 2 * 4);
+
+        // 文字列パターンは文字列が必須
+        let error = generate_artwork(
+            State(state),
+            Ok(Json(GenerateArtworkRequest::new(TestPattern::Text))),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_export_fightstick_script() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let Json(response) = generate_artwork(
+            State(state.clone()),
+            Ok(Json(GenerateArtworkRequest {
+                width: 10,
+                height: 6,
+                ..GenerateArtworkRequest::new(TestPattern::Border)
+            })),
+        )
+        .await
+        .unwrap();
+        let request = |format, frame_ms| FightstickExportRequest {
+            strategy: Some(DrawingStrategy::ZigZag),
+            press_ms: None,
+            release_ms: None,
+            wait_ms: None,
+            repeats: None,
+            frame_ms,
+            init_preset: None,
+            drawing_mode: None,
+            format,
+        };
+
+        let export = export_fightstick(
+            State(state.clone()),
+            Path(response.id.clone()),
+            Query(request(Some(FightstickFormat::Csv), None)),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            export.headers()[header::CONTENT_DISPOSITION],
+            format!("attachment; filename=\"{}-fightstick.csv\"", response.id)
+        );
+        let body = axum::body::to_bytes(export.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<_> = body.lines().collect();
+        assert_eq!(lines[..2], ["button,frames", "L,38"]);
+        // 外周の28ドットをすべてAで描く
+        assert_eq!(
+            lines.iter().filter(|line| line.starts_with("A,")).count(),
+            28
+        );
+
+        let error = export_fightstick(
+            State(state),
+            Path(response.id),
+            Query(request(None, Some(0))),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_bulk_dot_diff_applies_atomically_with_one_version() {
+        use crate::domain::artwork::dot_diff::{DotOp, DotRun};
+
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("flood".to_string()),
+            "api".to_string(),
+            Canvas::new(250, 200),
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+
+        // 塗りつぶし相当の5万ドット（1ドットずつのレコード）
+        let diff = DotDiff::new(
+            (0..200)
+                .flat_map(|y| (0..250).map(move |x| DotRun::single(x, y, DotOp::Set)))
+                .collect(),
+        );
+        let started = std::time::Instant::now();
+        let Json(response) = apply_dot_diff(
+            State(state.clone()),
+            Path(id.clone()),
+            Bytes::from(diff.encode()),
+        )
+        .await
+        .unwrap();
+        assert!(
+            started.elapsed() < std::time::Duration::from_millis(500),
+            "50k changes took {:?}",
+            started.elapsed()
+        );
+        assert_eq!(response.changed_dots, 50_000);
+        assert_eq!(response.drawable_dots, 50_000);
+        assert_eq!(response.version, 2);
+
+        // 1件でも範囲外なら何も適用しない
+        let invalid = DotDiff::new(vec![
+            DotRun::single(0, 0, DotOp::Clear),
+            DotRun::single(250, 0, DotOp::Clear),
+        ]);
+        let error = apply_dot_diff(
+            State(state.clone()),
+            Path(id.clone()),
+            Bytes::from(invalid.encode()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // 容量を超える差分と壊れたレコードは拒否する
+        let too_large = Bytes::from([diff.encode(), invalid.encode()].concat());
+        let error = apply_dot_diff(State(state.clone()), Path(id.clone()), too_large)
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let error = apply_dot_diff(
+            State(state.clone()),
+            Path(id.clone()),
+            Bytes::from_static(&[0, 0, 0]),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+        let artwork = state.find_artwork(&id).await.unwrap().unwrap();
+        assert_eq!(artwork.version, 2);
+        assert_eq!(artwork.drawable_dots(), 50_000);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_path_computations_are_cancelled_on_request_and_on_delete() {
+        // 時間の上限と短縮率の下限が無い2-optは、取り消されるまで計算を続ける
+        let mut canvas = Canvas::new(320, 120);
+        for y in 0..120u16 {
+            for x in 0..320u16 {
+                if (u32::from(x) * 7919 + u32::from(y) * 104_729) % 3 == 0 {
+                    canvas
+                        .set_dot(Coordinates::new(x, y), Dot::black())
+                        .unwrap();
+                }
+            }
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("large".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let state = ArtworkState::new(Arc::new(MockController::new().without_delays()))
+            .with_two_opt_settings(TwoOptSettings {
+                time_budget: std::time::Duration::from_millis(TwoOptSettings::MAX_TIME_BUDGET_MS),
+                min_improvement: 0.0,
+            });
+        state.artworks.save(&artwork).await.unwrap();
+        let state = Arc::new(state);
+        let client = TestClient::new(state.clone());
+        let wait_until_running = || async {
+            while state.path_computations.len() == 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            }
+        };
+
+        let comparison = tokio::spawn({
+            let client = TestClient::new(state.clone());
+            let url = format!("/api/artworks/{id}/strategies");
+            async move { client.get(&url).await }
+        });
+        wait_until_running().await;
+        let response = client
+            .delete(&format!("/api/artworks/{id}/strategies/compute"))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["cancelled"], 1);
+        let comparison = tokio::time::timeout(std::time::Duration::from_secs(5), comparison)
+            .await
+            .expect("the comparison did not observe the cancellation")
+            .unwrap();
+        assert_eq!(comparison.status, StatusCode::CONFLICT);
+        assert_eq!(state.path_computations.len(), 0);
+
+        // 削除は実行中の解析を取り消して待ち、取り消した解析の結果は保存しない
+        let analysis = tokio::spawn({
+            let client = TestClient::new(state.clone());
+            let url = format!("/api/artworks/{id}/analysis");
+            async move { client.get(&url).await }
+        });
+        wait_until_running().await;
+        let response = client.delete(&format!("/api/artworks/{id}")).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        assert_eq!(analysis.await.unwrap().status, StatusCode::CONFLICT);
+        assert!(
+            state
+                .analyses
+                .get(&id, artwork.version, DrawingStrategy::GreedyTwoOpt)
+                .is_none()
+        );
+        assert!(state.find_artwork(&id).await.unwrap().is_none());
+        assert!(state.artwork_locks.is_empty());
+
+        let response = client
+            .delete(&format!("/api/artworks/{id}/strategies/compute"))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_history_records_each_change_with_its_version() {
+        use crate::domain::artwork::dot_diff::{DotOp, DotRun};
+        use axum::body::Body;
+        use axum::http::Request;
+
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let client = TestClient::new(state.clone());
+        let created = client
+            .post(
+                "/api/artworks",
+                serde_json::json!({
+                    "name": "history",
+                    "width": 8,
+                    "height": 4,
+                    "dots": [
+                        {"x": 0, "y": 0, "color": "#000000"},
+                        {"x": 1, "y": 0, "color": "#000000"}
+                    ]
+                }),
+            )
+            .await;
+        assert_eq!(created.status, StatusCode::OK, "{}", created.text());
+        let id = created.json()["id"].as_str().unwrap().to_string();
+
+        let transformed = client
+            .post(
+                &format!("/api/artworks/{id}/transform"),
+                serde_json::json!({"transforms": [{"type": "flip_horizontal"}]}),
+            )
+            .await
+            .json();
+        assert_eq!(transformed["version"], 2);
+        assert_eq!(transformed["dots_added"], 2);
+        assert_eq!(transformed["dots_removed"], 2);
+
+        let diff = DotDiff::new(vec![DotRun {
+            x: 0,
+            y: 1,
+            op: DotOp::Set,
+            length: 3,
+        }]);
+        let edited = client
+            .send(
+                Request::post(format!("/api/artworks/{id}/dots:bulk"))
+                    .header(header::CONTENT_TYPE, "application/octet-stream")
+                    .body(Body::from(diff.encode()))
+                    .unwrap(),
+            )
+            .await
+            .json();
+        assert_eq!(edited["version"], 3);
+
+        let renamed = client
+            .patch(
+                &format!("/api/artworks/{id}/metadata"),
+                serde_json::json!({"name": " renamed "}),
+            )
+            .await
+            .json();
+        assert_eq!(renamed["name"], "renamed");
+        let empty_name = client
+            .patch(
+                &format!("/api/artworks/{id}/metadata"),
+                serde_json::json!({"name": " "}),
+            )
+            .await;
+        assert_eq!(empty_name.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let history = client
+            .get(&format!("/api/artworks/{id}/history"))
+            .await
+            .json();
+        assert_eq!(history["version"], 4);
+        let entries = history["entries"].as_array().unwrap();
+        let kinds: Vec<(u64, &str)> = entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry["version"].as_u64().unwrap(),
+                    entry["kind"].as_str().unwrap(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (1, "created"),
+                (2, "transformed"),
+                (3, "dots_edited"),
+                (4, "metadata_updated")
+            ]
+        );
+        assert_eq!(entries[0]["dots_added"], 2);
+        assert_eq!(entries[1]["summary"], "transformed: flip_horizontal");
+        assert_eq!(entries[2]["summary"], "edited 3 dots");
+        assert_eq!(entries[2]["dots_added"], 3);
+        assert_eq!(entries[3]["summary"], "renamed: 'history' -> 'renamed'");
+        assert!(entries.iter().all(|entry| entry["source"] == "api"));
+
+        // 同じ変更からドメインイベントも作られる
+        let versions: Vec<u32> = state
+            .events
+            .read()
+            .await
+            .iter()
+            .filter_map(|event| match event {
+                ArtworkEvent::ArtworkCreated { version, .. }
+                | ArtworkEvent::ArtworkCanvasUpdated { version, .. }
+                | ArtworkEvent::ArtworkMetadataUpdated { version, .. } => Some(*version),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(versions, [1, 2, 3, 4]);
+
+        // エクスポートには指定した場合だけ含める
+        let exported = client
+            .get(&format!("/api/artworks/{id}/export"))
+            .await
+            .json();
+        assert!(exported.get("history").is_none());
+        let exported = client
+            .get(&format!("/api/artworks/{id}/export?include_history=true"))
+            .await
+            .json();
+        assert_eq!(exported["history"].as_array().unwrap().len(), 4);
+        assert_eq!(exported["history"][3]["version"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_undo_redo_canvas_edits_keep_painting_progress() {
+        use crate::domain::artwork::dot_diff::{DotOp, DotRun};
+
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("undo".to_string()),
+            "api".to_string(),
+            Canvas::new(16, 16),
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+        let edit = |runs: Vec<DotRun>| Bytes::from(DotDiff::new(runs).encode());
+
+        let error = undo_artwork_edit(State(state.clone()), Path(id.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+
+        for y in 0..2 {
+            let Json(response) = apply_dot_diff(
+                State(state.clone()),
+                Path(id.clone()),
+                edit(vec![DotRun {
+                    x: 0,
+                    y,
+                    op: DotOp::Set,
+                    length: 4,
+                }]),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.changed_dots, 4);
+        }
+
+        // 1行目を描画してから2回目の編集を取り消す
+        let mut painted = state.find_artwork(&id).await.unwrap().unwrap();
+        painted
+            .canvas
+            .get_dot_mut(&Coordinates::new(0, 0))
+            .unwrap()
+            .mark_as_painted();
+        state.artworks.save(&painted).await.unwrap();
+
+        let Json(response) = undo_artwork_edit(State(state.clone()), Path(id.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.changed_dots, 4);
+        assert_eq!(response.version, painted.version + 1);
+        assert_eq!((response.undo_steps, response.redo_steps), (1, 1));
+        let artwork = state.find_artwork(&id).await.unwrap().unwrap();
+        assert_eq!(artwork.total_dots(), 4);
+        assert!(artwork.canvas.dots[&Coordinates::new(0, 0)].is_painted);
+        assert!(matches!(
+            state.events.read().await.last(),
+            Some(ArtworkEvent::ArtworkCanvasUpdated { version, .. }) if *version == response.version
+        ));
+
+        let Json(response) = redo_artwork_edit(State(state.clone()), Path(id.clone()))
+            .await
+            .unwrap();
+        assert_eq!((response.undo_steps, response.redo_steps), (2, 0));
+        assert_eq!(response.drawable_dots, 7);
+        let error = redo_artwork_edit(State(state.clone()), Path(id.clone()))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+
+        // 完全に削除すると履歴も消える
+        let Json(deleted) = delete_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Query(DeleteArtworkRequest { permanent: true }),
+        )
+        .await
+        .unwrap();
+        assert!(deleted.success);
+        assert_eq!(state.canvas_history.used_bytes(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_create_artwork_stipples_grey_area() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let dots = (0..4)
+            .flat_map(|y| (0..4).map(move |x| (x, y)))
+            .map(|(x, y)| DotData {
+                x,
+                y,
+                color: "#808080".to_string(),
+                layer: 0,
+            })
+            .collect();
+        let request = CreateArtworkRequest {
+            name: "grey".to_string(),
+            width: 4,
+            height: 4,
+            dots,
+            auto_trim: false,
+            center_on_canvas: false,
+            tone_mode: ToneMode::Stipple2,
+            invert_background: None,
+            description: None,
+            tags: Vec::new(),
+            author: None,
+            painting_preferences: None,
+            vector_paths: Vec::new(),
+            history: Vec::new(),
+            rows: Vec::new(),
+        };
+
+        let Ok(Json(response)) = create_artwork(
+            State(state.clone()),
+            Query(CreateArtworkQuery::default()),
+            Ok(Json(request)),
+        )
+        .await
+        else {
+            panic!("create_artwork failed");
+        };
+        assert_eq!(response.artwork.unwrap().drawable_dots, 8);
+        assert!(response.estimated_painting_seconds.unwrap() > 0.0);
+
+        // 50%グレーは市松模様になる
+        let artwork = state.find_artwork(&response.id).await.unwrap().unwrap();
+        let canvas = &artwork.canvas;
+        for (coord, _) in canvas.drawable_dots() {
+            assert_eq!((coord.x + coord.y) % 2, 1, "unexpected dot at {coord}");
+        }
+    }
+
+    fn duplicated_dots_request() -> CreateArtworkRequest {
+        let dot = |x, y, color: &str, layer| DotData {
+            x,
+            y,
+            color: color.to_string(),
+            layer,
+        };
+        CreateArtworkRequest {
+            name: "duplicated".to_string(),
+            width: 4,
+            height: 4,
+            // (1, 1) は3回、(2, 0) は2回送られている
+            dots: vec![
+                dot(1, 1, "#000000", 0),
+                dot(2, 0, "#000000", 0),
+                dot(1, 1, "#ff0000", 1),
+                dot(3, 3, "#000000", 0),
+                dot(2, 0, "#00ff00", 2),
+                dot(1, 1, "#0000ff", 3),
+            ],
+            auto_trim: false,
+            center_on_canvas: false,
+            tone_mode: ToneMode::Binary,
+            invert_background: Some(false),
+            description: None,
+            tags: Vec::new(),
+            author: None,
+            painting_preferences: None,
+            vector_paths: Vec::new(),
+            history: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_artwork_rejects_duplicate_dots_by_default() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+
+        let response = match create_artwork(
+            State(state.clone()),
+            Query(CreateArtworkQuery::default()),
+            Ok(Json(duplicated_dots_request())),
+        )
+        .await
+        {
+            Ok(_) => panic!("duplicate dots were accepted"),
+            Err(e) => e.into_response(),
+        };
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("(1, 1), (2, 0)"), "{body}");
+        assert!(!body.contains("(3, 3)"), "{body}");
+        assert!(
+            state
+                .artworks
+                .search(&Default::default())
+                .await
+                .unwrap()
+                .artworks
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_create_artwork_resolves_duplicate_dots_deterministically() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let black = Color::new(0, 0, 0, 255);
+
+        for (policy, expected) in [
+            (
+                DuplicateDotPolicy::LastWins,
+                [
+                    (Color::new(0, 0, 255, 255), 3),
+                    (Color::new(0, 255, 0, 255), 2),
+                ],
+            ),
+            (DuplicateDotPolicy::FirstWins, [(black, 0), (black, 0)]),
+        ] {
+            let Ok(Json(response)) = create_artwork(
+                State(state.clone()),
+                Query(CreateArtworkQuery {
+                    on_duplicate: policy,
+                }),
+                Ok(Json(duplicated_dots_request())),
+            )
+            .await
+            else {
+                panic!("create_artwork failed for {policy:?}");
+            };
+            assert_eq!(response.duplicates_resolved, Some(3), "{policy:?}");
+
+            let artwork = state.find_artwork(&response.id).await.unwrap().unwrap();
+            let canvas = &artwork.canvas;
+            assert_eq!(canvas.dots.len(), 3, "{policy:?}");
+            for (coordinates, (color, layer)) in [Coordinates::new(1, 1), Coordinates::new(2, 0)]
+                .into_iter()
+                .zip(expected)
+            {
+                let dot = &canvas.dots[&coordinates];
+                assert_eq!(
+                    (dot.color, dot.layer),
+                    (color, layer),
+                    "{policy:?} at {coordinates}"
+                );
+            }
+            assert_eq!(canvas.dots[&Coordinates::new(3, 3)].color, black);
+        }
+
+        // 重複が無ければ解決したドット数は0
+        let mut request = duplicated_dots_request();
+        request.dots.truncate(2);
+        let Ok(Json(response)) = create_artwork(
+            State(state),
+            Query(CreateArtworkQuery {
+                on_duplicate: DuplicateDotPolicy::LastWins,
+            }),
+            Ok(Json(request)),
+        )
+        .await
+        else {
+            panic!("create_artwork failed");
+        };
+        assert_eq!(response.duplicates_resolved, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_create_artwork_is_refused_beyond_memory_budget() {
+        let request = || {
+            let mut request = duplicated_dots_request();
+            request.dots.truncate(2);
+            request
+        };
+        let create = |state: Arc<ArtworkState>| async move {
+            create_artwork(
+                State(state),
+                Query(CreateArtworkQuery::default()),
+                Ok(Json(request())),
+            )
+            .await
+        };
+
+        // 1枚分の推定使用量を測り、1.5枚分の上限で作り直す
+        let probe = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let Ok(Json(created)) = create(probe.clone()).await else {
+            panic!("create_artwork failed");
+        };
+        let per_artwork = probe.memory_budget.used_bytes();
+        assert!(per_artwork > 0);
+        let summary =
+            ArtworkSummary::from(&probe.find_artwork(&created.id).await.unwrap().unwrap());
+        assert_eq!(summary.estimated_memory_bytes, per_artwork);
+
+        let state = Arc::new(
+            ArtworkState::new(Arc::new(MockController::new().without_delays()))
+                .with_memory_budget(ArtworkMemoryBudget::new(per_artwork * 3 / 2)),
+        );
+        let Ok(Json(first)) = create(state.clone()).await else {
+            panic!("first artwork was refused");
+        };
+        let response = match create(state.clone()).await {
+            Ok(_) => panic!("artwork beyond the memory budget was accepted"),
+            Err(e) => e.into_response(),
+        };
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let body = String::from_utf8_lossy(&body);
+        assert!(body.contains("MiB in use"), "{body}");
+        assert_eq!(state.memory_budget.used_bytes(), per_artwork);
+
+        // ゴミ箱に移しても計上は残り、完全に削除すると取り消されて再び作成できる
+        let Json(deleted) = delete_artwork(
+            State(state.clone()),
+            Path(first.id.clone()),
+            Query(DeleteArtworkRequest { permanent: false }),
+        )
+        .await
+        .unwrap();
+        assert!(deleted.success);
+        assert_eq!(state.memory_budget.used_bytes(), per_artwork);
+        let Json(deleted) = delete_artwork(
+            State(state.clone()),
+            Path(first.id),
+            Query(DeleteArtworkRequest { permanent: true }),
+        )
+        .await
+        .unwrap();
+        assert!(deleted.success);
+        assert_eq!(state.memory_budget.used_bytes(), 0);
+        assert!(create(state).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_create_artwork_inverts_light_on_dark_image() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        // 6x2 の黒地に白抜きの2ドット
+        let dots = (0..2)
+            .flat_map(|y| (0..6).map(move |x| (x, y)))
+            .filter(|&(x, y)| (x, y) != (2, 1) && (x, y) != (4, 1))
+            .map(|(x, y)| DotData {
+                x,
+                y,
+                color: "#000000".to_string(),
+                layer: 0,
+            })
+            .collect();
+        let request = CreateArtworkRequest {
+            name: "white logo".to_string(),
+            width: 6,
+            height: 2,
+            dots,
+            auto_trim: false,
+            center_on_canvas: false,
+            tone_mode: ToneMode::Binary,
+            invert_background: None,
+            description: None,
+            tags: Vec::new(),
+            author: None,
+            painting_preferences: None,
+            vector_paths: Vec::new(),
+            history: Vec::new(),
+            rows: Vec::new(),
+        };
+
+        let Ok(Json(response)) = create_artwork(
+            State(state.clone()),
+            Query(CreateArtworkQuery::default()),
+            Ok(Json(request)),
+        )
+        .await
+        else {
+            panic!("create_artwork failed");
+        };
+        let summary = response.artwork.unwrap();
+        assert_eq!(summary.drawable_dots, 2);
+        assert_eq!(summary.background_color, "#000000");
+        assert_eq!(response.warnings.len(), 1);
+        assert!(response.warnings[0].contains("dark background"));
+
+        // 経路は白抜きのドットだけを通る
+        let Json(path) = get_artwork_path(
+            State(state),
+            Path(response.id),
+            Query(GetPathRequest {
+                strategy: Some(DrawingStrategy::RasterScan),
+                region: None,
+                origin: None,
+                seed: None,
+                two_opt_budget_ms: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            path.path,
+            vec![Coordinates::new(2, 1), Coordinates::new(4, 1)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_compact_artwork_drops_dot_timestamps_and_reports_sizes() {
+        let mut canvas = Canvas::new(100, 10);
+        for x in 0..100 {
+            canvas
+                .set_dot(Coordinates::new(x, 3), Dot::black())
+                .unwrap();
+        }
+        let mut artwork = Artwork::new(
+            ArtworkMetadata::new("compact".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        artwork.mark_dots_painted(&[Coordinates::new(0, 3), Coordinates::new(1, 3)]);
+        let id = artwork.id.as_str().to_string();
+        let state = artwork_state_with(artwork).await;
+
+        let Json(response) = compact_artwork(State(state.clone()), Path(id.clone()))
+            .await
+            .unwrap();
+        assert_eq!(response.compacted_dots, 100);
+        assert!(
+            response.bytes_after * 4 < response.bytes_before * 3,
+            "{} -> {}",
+            response.bytes_before,
+            response.bytes_after
+        );
+
+        let stored = state.find_artwork(&id).await.unwrap().unwrap();
+        assert_eq!(stored.canvas.painted_dots().len(), 2);
+        assert_eq!(stored.drawable_dots(), 98);
+        assert!(
+            stored
+                .canvas
+                .dots
+                .values()
+                .all(|dot| dot.age_millis().is_none())
+        );
+
+        let Json(again) = compact_artwork(State(state.clone()), Path(id.clone()))
+            .await
+            .unwrap();
+        assert_eq!(again.compacted_dots, 0);
+        assert_eq!(again.bytes_before, again.bytes_after);
+        assert_eq!(
+            compact_artwork(State(state), Path("missing".to_string()))
+                .await
+                .unwrap_err()
+                .status(),
+            StatusCode::NOT_FOUND
+        );
+    }
+
+    /// 見積もりの精度を確認する代表的なキャンバス（320x120）
+    fn representative_canvases() -> Vec<(&'static str, Canvas)> {
+        let mut random: u64 = 0x2545_F491_4F6C_DD1D;
+        let mut next = move || {
+            random ^= random << 13;
+            random ^= random >> 7;
+            random ^= random << 17;
+            random
+        };
+        let build = |predicate: &mut dyn FnMut(u16, u16) -> bool| {
+            let mut canvas = Canvas::new(320, 120);
+            for y in 0..120 {
+                for x in 0..320 {
+                    if predicate(x, y) {
+                        canvas
+                            .set_dot(Coordinates::new(x, y), Dot::black())
+                            .unwrap();
+                    }
+                }
+            }
+            canvas
+        };
+
+        vec![
+            // 塗りつぶした円
+            (
+                "disc",
+                build(&mut |x, y| {
+                    let (dx, dy) = (x as i32 - 160, y as i32 - 60);
+                    dx * dx + dy * dy <= 55 * 55
+                }),
+            ),
+            // 写真をディザリングしたような、右に行くほど濃くなる点描
+            (
+                "dithered gradient",
+                build(&mut |x, _| next() % 320 < x as u64),
+            ),
+            // 文字のような細い線の集まり
+            (
+                "strokes",
+                build(&mut |x, y| (x % 8 < 2 && y % 40 < 30) || (y % 40 == 0 && x % 24 < 16)),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_fast_strategy_comparison_matches_exact_ranking() {
+        let config = DrawingCanvasConfig::default();
+        let two_opt = TwoOptSettings::default();
+        let ranking = |comparison: &StrategyComparisonResponse| {
+            let mut strategies: Vec<_> = comparison
+                .strategies
+                .iter()
+                .map(|stats| (stats.estimated_time_seconds, stats.strategy))
+                .collect();
+            strategies.sort_by(|a, b| a.0.total_cmp(&b.0));
+            strategies
+                .into_iter()
+                .map(|(_, strategy)| strategy)
+                .collect::<Vec<_>>()
+        };
+
+        for (name, canvas) in representative_canvases() {
+            let never = CancellationToken::default();
+            let exact = compare_strategies(&canvas, &config, two_opt, None, &never).unwrap();
+            let fast =
+                compare_strategies(&canvas, &config, two_opt, Some(DEFAULT_SAMPLE_DOTS), &never)
+                    .unwrap();
+            assert!(!exact.approximate && exact.accuracy.is_none());
+            assert!(fast.approximate, "{name}");
+            assert_eq!(fast.total_dots, exact.total_dots);
+            assert!(fast.sample_dots <= DEFAULT_SAMPLE_DOTS);
+            for (fast, exact) in fast.strategies.iter().zip(&exact.strategies) {
+                let error = (fast.estimated_time_seconds / exact.estimated_time_seconds - 1.0)
+                    .abs()
+                    * 100.0;
+                assert!(
+                    error <= EstimateAccuracy::ROW_BANDS_TIME_ERROR_PERCENT,
+                    "{name} {:?}: {error:.1}%",
+                    fast.strategy
+                );
+            }
+            assert_eq!(ranking(&fast), ranking(&exact), "{name}");
+        }
+
+        // 閾値以下のアートワークは fast でも全ドットで計算する
+        let (_, small) = &representative_canvases()[0];
+        let fast = compare_strategies(
+            small,
+            &config,
+            two_opt,
+            Some(20_000),
+            &CancellationToken::default(),
+        )
+        .unwrap();
+        assert!(!fast.approximate);
+        assert_eq!(fast.sample_dots, fast.total_dots);
+    }
+
+    #[tokio::test]
+    async fn test_artwork_analysis_reports_tiles_isolated_dots_and_path() {
+        let mut canvas = Canvas::new(40, 20);
+        for (x, y) in [(0, 0), (1, 0), (2, 1), (35, 18)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        let mut artwork = Artwork::new(
+            ArtworkMetadata::new("analysis".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        artwork.set_painting_preferences(Some(PaintingPreferences {
+            strategy: Some(DrawingStrategy::ZigZag),
+            ..PaintingPreferences::default()
+        }));
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+
+        let Json(analysis) = get_artwork_analysis(
+            State(state.clone()),
+            Path(id.clone()),
+            Query(ArtworkAnalysisQuery::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(analysis.statistics.drawable_dots, 4);
+        assert_eq!(analysis.tile_size, ANALYSIS_TILE_SIZE);
+        assert_eq!(analysis.tiles, vec![vec![3, 0, 0], vec![0, 0, 1]]);
+        assert_eq!(analysis.isolated_dots, 1);
+        assert_eq!(analysis.bounding_box, Some(CanvasRegion::new(0, 0, 36, 19)));
+        // アートワークの描画設定の戦略を使う
+        assert_eq!(analysis.path.strategy, DrawingStrategy::ZigZag);
+        assert_eq!(analysis.path.dots, 4);
+        assert!(analysis.path.longest_move >= 33 + 17);
+        assert!(analysis.path.estimated_time_seconds > 0.0);
+        assert!(
+            state
+                .analyses
+                .get(&id, analysis.version, DrawingStrategy::ZigZag)
+                .is_some()
+        );
+
+        // 戦略を指定すると計算し直し、アートワークを変更するとバージョンの新しい結果になる
+        let Json(greedy) = get_artwork_analysis(
+            State(state.clone()),
+            Path(id.clone()),
+            Query(ArtworkAnalysisQuery {
+                strategy: Some(DrawingStrategy::NearestNeighbor),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(greedy.path.strategy, DrawingStrategy::NearestNeighbor);
+        bump_version(&state, &id).await;
+        let Json(updated) = get_artwork_analysis(
+            State(state.clone()),
+            Path(id.clone()),
+            Query(ArtworkAnalysisQuery::default()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(updated.version, analysis.version + 1);
+
+        let error = get_artwork_analysis(
+            State(state.clone()),
+            Path("missing".to_string()),
+            Query(ArtworkAnalysisQuery::default()),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::NOT_FOUND);
+    }
+
+    /// 黒いドットを `dots` の位置に置いたアートワークを保存したルーター
+    async fn client_with_dots(
+        width: u16,
+        height: u16,
+        dots: &[(u16, u16)],
+    ) -> (TestClient, String) {
+        let mut artwork = Artwork::new(
+            ArtworkMetadata::new("routes".to_string()),
+            "api".to_string(),
+            Canvas::new(width, height),
+        );
+        for &(x, y) in dots {
+            artwork
+                .canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        let id = artwork.id.as_str();
+        (TestClient::new(artwork_state_with(artwork).await), id)
+    }
+
+    #[tokio::test]
+    async fn test_artwork_collection_routes() {
+        let (client, id) = client_with_dots(4, 4, &[(1, 1)]).await;
+
+        let response = client.get("/api/artworks").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json().as_array().unwrap().len(), 1);
+
+        let response = client
+            .post(
+                "/api/artworks",
+                serde_json::json!({
+                    "name": "created",
+                    "width": 4,
+                    "height": 4,
+                    "dots": [{ "x": 0, "y": 0, "color": "#000000" }],
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["artwork"]["name"], "created");
+
+        let response = client
+            .post(
+                "/api/artworks",
+                serde_json::json!({
+                    "name": "duplicated",
+                    "width": 4,
+                    "height": 4,
+                    "dots": [
+                        { "x": 0, "y": 0, "color": "#000000" },
+                        { "x": 0, "y": 0, "color": "#000000" },
+                    ],
+                }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = client
+            .post(
+                "/api/artworks/generate",
+                serde_json::json!({ "pattern": "border", "width": 8, "height": 8 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client
+            .post(
+                "/api/artworks/generate",
+                serde_json::json!({ "pattern": "spiral" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = client.get(&format!("/api/artworks/{id}")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["id"], id.as_str());
+        let response = client.get("/api/artworks/missing").await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        let response = client
+            .post(
+                &format!("/api/artworks/{id}/duplicate"),
+                serde_json::json!({}),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client.post_empty("/api/artworks/missing/duplicate").await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        let response = client.delete(&format!("/api/artworks/{id}")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client.delete(&format!("/api/artworks/{id}")).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_artwork_stays_in_the_trash_until_restored_or_purged() {
+        let trashed_state = |retention| async move {
+            let state = Arc::new(
+                ArtworkState::new(Arc::new(MockController::new().without_delays()))
+                    .with_trash_retention(retention),
+            );
+            let artwork = Artwork::new(
+                ArtworkMetadata::new("squid".to_string()),
+                "api".to_string(),
+                Canvas::new(4, 4),
+            );
+            let id = artwork.id.as_str();
+            state
+                .insert_artwork(artwork, EventMetadata::new("api".to_string()))
+                .await
+                .unwrap();
+            let response = TestClient::new(state.clone())
+                .delete(&format!("/api/artworks/{id}"))
+                .await;
+            assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+            assert_eq!(response.json()["message"], "Artwork moved to the trash");
+            (state, id)
+        };
+        let (state, id) = trashed_state(DEFAULT_TRASH_RETENTION).await;
+        let client = TestClient::new(state.clone());
+        let in_use = state.memory_budget.used_bytes();
+        assert!(in_use > 0);
+        assert!(matches!(
+            state.events.read().await.last(),
+            Some(ArtworkEvent::ArtworkDeleted {
+                permanent: false,
+                ..
+            })
+        ));
+
+        // 一覧・取得・描画の対象にならない
+        assert_eq!(
+            client.get(&format!("/api/artworks/{id}")).await.status,
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            client.get("/api/artworks").await.json(),
+            serde_json::json!([])
+        );
+        state.interlock.arm("test", None);
+        let response = client
+            .post(&format!("/api/artworks/{id}/paint"), serde_json::json!({}))
+            .await;
+        assert_eq!(
+            response.status,
+            StatusCode::NOT_FOUND,
+            "{}",
+            response.text()
+        );
+        assert_eq!(
+            client.delete(&format!("/api/artworks/{id}")).await.status,
+            StatusCode::NOT_FOUND
+        );
+
+        let trash = client.get("/api/artworks/trash").await.json();
+        assert_eq!(trash["retention_days"], 7);
+        assert_eq!(trash["artworks"][0]["id"], id.as_str());
+        assert_eq!(trash["artworks"][0]["name"], "squid");
+        let deleted_at = trash["artworks"][0]["deleted_at"].as_i64().unwrap();
+        assert_eq!(
+            trash["artworks"][0]["purge_after"].as_i64().unwrap() - deleted_at,
+            DEFAULT_TRASH_RETENTION.as_millis() as i64
+        );
+        // 保持期間内なので消えない
+        let response = client.post_empty("/api/artworks/trash/purge").await;
+        assert_eq!(response.json()["purged"], serde_json::json!([]));
+
+        let response = client
+            .post_empty(&format!("/api/artworks/{id}/restore"))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        assert_eq!(response.json()["id"], id.as_str());
+        assert_eq!(
+            client.get(&format!("/api/artworks/{id}")).await.status,
+            StatusCode::OK
+        );
+        let response = client
+            .post_empty(&format!("/api/artworks/{id}/restore"))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert_eq!(
+            response.message(),
+            format!("アートワーク {id} はゴミ箱にありません")
+        );
+
+        // 完全な削除はゴミ箱のアートワークにも使え、メモリの計上を取り消す
+        client.delete(&format!("/api/artworks/{id}")).await;
+        let response = client
+            .delete(&format!("/api/artworks/{id}?permanent=true"))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        assert_eq!(response.json()["message"], "Artwork deleted permanently");
+        assert!(matches!(
+            state.events.read().await.last(),
+            Some(ArtworkEvent::ArtworkDeleted {
+                permanent: true,
+                ..
+            })
+        ));
+        assert_eq!(state.memory_budget.used_bytes(), 0);
+        assert!(state.artworks.find_trashed().await.unwrap().is_empty());
+        assert!(state.artwork_locks.is_empty());
+
+        // 保持期間を過ぎたものは完全に削除する
+        let (state, id) = trashed_state(std::time::Duration::ZERO).await;
+        let response = TestClient::new(state.clone())
+            .post_empty("/api/artworks/trash/purge")
+            .await;
+        assert_eq!(response.json()["purged"], serde_json::json!([id]));
+        assert_eq!(state.memory_budget.used_bytes(), 0);
+        assert!(state.artworks.find_trashed().await.unwrap().is_empty());
+    }
+
+    /// `name` と（あれば）`file` を送るマルチパートのアップロード
+    fn upload_request(file: Option<&str>) -> axum::http::Request<axum::body::Body> {
+        let boundary = "test-boundary";
+        let mut body = format!(
+            "--{boundary}\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\nuploaded\r\n"
+        );
+        if let Some(file) = file {
+            body.push_str(&format!(
+                "--{boundary}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"image.png\"\r\n\
+                 Content-Type: image/png\r\n\r\n{file}\r\n"
+            ));
+        }
+        body.push_str(&format!("--{boundary}--\r\n"));
+        axum::http::Request::post("/api/artworks/upload")
+            .header(
+                axum::http::header::CONTENT_TYPE,
+                format!("multipart/form-data; boundary={boundary}"),
+            )
+            .body(axum::body::Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_artwork_upload_route() {
+        let client = TestClient::new(Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        ))));
+
+        let response = client.send(upload_request(Some("image bytes"))).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["duplicate"], false);
+
+        let response = client.send(upload_request(None)).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_artwork_edit_routes() {
+        use crate::domain::artwork::dot_diff::{DotOp, DotRun};
+
+        let (client, id) = client_with_dots(4, 4, &[(1, 1)]).await;
+
+        let response = client
+            .patch(
+                &format!("/api/artworks/{id}/metadata"),
+                serde_json::json!({ "description": "edited" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["description"], "edited");
+        let response = client
+            .patch("/api/artworks/missing/metadata", serde_json::json!({}))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        let response = client
+            .patch(
+                &format!("/api/artworks/{id}/preferences"),
+                serde_json::json!({}),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client
+            .patch("/api/artworks/missing/preferences", serde_json::json!({}))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        let response = client
+            .post_empty(&format!("/api/artworks/{id}/compact"))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client.post_empty("/api/artworks/missing/compact").await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        // 何も編集していなければ取り消せない
+        let response = client.post_empty(&format!("/api/artworks/{id}/undo")).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+
+        let diff = DotDiff::new(vec![DotRun::single(2, 2, DotOp::Set)]);
+        let request = axum::http::Request::post(format!("/api/artworks/{id}/dots:bulk"))
+            .body(axum::body::Body::from(diff.encode()))
+            .unwrap();
+        let response = client.send(request).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let request = axum::http::Request::post(format!("/api/artworks/{id}/dots:bulk"))
+            .body(axum::body::Body::from("garbage"))
+            .unwrap();
+        let response = client.send(request).await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+        let response = client.post_empty(&format!("/api/artworks/{id}/undo")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["redo_steps"], 1);
+        let response = client.post_empty(&format!("/api/artworks/{id}/redo")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client.post_empty(&format!("/api/artworks/{id}/redo")).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_artwork_cannot_be_deleted_while_its_path_is_computed() {
+        use crate::domain::artwork::dot_diff::{DotOp, DotRun};
+
+        let mut artwork = Artwork::new(
+            ArtworkMetadata::new("busy".to_string()),
+            "api".to_string(),
+            Canvas::new(320, 120),
+        );
+        for y in 0..120 {
+            for x in 0..320 {
+                if (x * 7 + y * 13) % 5 != 0 {
+                    artwork
+                        .canvas
+                        .set_dot(Coordinates::new(x, y), Dot::black())
+                        .unwrap();
+                }
+            }
+        }
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+        let client = TestClient::new(state.clone());
+
+        // 取り消せる計算を登録する前の比較と同じく、ロックだけを持つ
+        // （登録後は削除が計算を取り消す: test_path_computations_are_cancelled_on_request_and_on_delete）
+        let comparison = state
+            .artwork_locks
+            .read(&id, MessageKey::OperationStrategyComparison)
+            .unwrap();
+
+        let response = client.delete(&format!("/api/artworks/{id}")).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(
+            response.message(),
+            format!("アートワーク {id} は描画戦略の比較の実行中のため、削除を開始できません")
+        );
+        // Accept-Language で英語を選べる
+        let request = axum::http::Request::delete(format!("/api/artworks/{id}"))
+            .header(
+                axum::http::header::ACCEPT_LANGUAGE,
+                "en-US,en;q=0.9,ja;q=0.8",
+            )
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let response = client.send(request).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert_eq!(
+            response.message(),
+            format!("Cannot start deletion of artwork {id}: strategy comparison is in progress")
+        );
+        let diff = DotDiff::new(vec![DotRun::single(0, 0, DotOp::Clear)]);
+        let request = axum::http::Request::post(format!("/api/artworks/{id}/dots:bulk"))
+            .body(axum::body::Body::from(diff.encode()))
+            .unwrap();
+        assert_eq!(client.send(request).await.status, StatusCode::CONFLICT);
+
+        drop(comparison);
+        let response = client.get(&format!("/api/artworks/{id}/strategies")).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        assert!(state.artwork_locks.is_empty());
+
+        let response = client.delete(&format!("/api/artworks/{id}")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client.get(&format!("/api/artworks/{id}/strategies")).await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        assert!(
+            state.artwork_locks.is_empty(),
+            "deleted artworks must not keep entries"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_artwork_planning_routes() {
+        let (client, id) = client_with_dots(4, 4, &[(0, 0), (3, 3)]).await;
+
+        let response = client.get(&format!("/api/artworks/{id}/path")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["path"].as_array().unwrap().len(), 2);
+        let response = client
+            .get(&format!("/api/artworks/{id}/path?region=nonsense"))
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+        let response = client.get(&format!("/api/artworks/{id}/strategies")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client
+            .get(&format!("/api/artworks/{id}/strategies?origin=nonsense"))
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+
+        let response = client.get(&format!("/api/artworks/{id}/analysis")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client.get("/api/artworks/missing/analysis").await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        let response = client
+            .get(&format!("/api/artworks/{id}/export/fightstick"))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(!response.body.is_empty());
+        let response = client
+            .get(&format!("/api/artworks/{id}/export/fightstick?frame_ms=0"))
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = client.get(&format!("/api/artworks/{id}/diff/{id}")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["in_both"]["count"], 2);
+        // サイズの違うキャンバスは比較できない
+        let response = client
+            .post(
+                "/api/artworks",
+                serde_json::json!({
+                    "name": "larger",
+                    "width": 8,
+                    "height": 8,
+                    "dots": [{ "x": 0, "y": 0, "color": "#000000" }],
+                }),
+            )
+            .await;
+        let other_id = response.json()["id"].as_str().unwrap().to_string();
+        let response = client
+            .get(&format!("/api/artworks/{id}/diff/{other_id}"))
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_compact_canvas_format_round_trips_through_export_and_create() {
+        // 320x120のほぼ全面を塗りつぶし、斜めに穴を空けたキャンバス
+        let dots: Vec<(u16, u16)> = (0..120)
+            .flat_map(|y| (0..320).map(move |x| (x, y)))
+            .filter(|&(x, y)| (x * 7 + y * 3) % 11 != 0)
+            .collect();
+        let (client, id) = client_with_dots(320, 120, &dots).await;
+
+        let response = client.get(&format!("/api/artworks/{id}")).await;
+        assert!(response.json().get("canvas").is_none());
+        let plain_etag = response.headers[header::ETAG].clone();
+        let response = client
+            .get(&format!("/api/artworks/{id}?format=compact"))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_ne!(response.headers[header::ETAG], plain_etag);
+        let body = response.json();
+        assert_eq!(body["id"], id);
+        assert_eq!(body["canvas"]["rows"].as_array().unwrap().len(), 120);
+        let request = axum::http::Request::builder()
+            .uri(format!("/api/artworks/{id}"))
+            .header(header::ACCEPT, COMPACT_CANVAS_MEDIA_TYPE)
+            .body(axum::body::Body::empty())
+            .unwrap();
+        assert_eq!(client.send(request).await.json(), body);
+
+        let dots_export = client
+            .get(&format!("/api/artworks/{id}/export?format=dots"))
+            .await;
+        let compact_export = client
+            .get(&format!("/api/artworks/{id}/export?format=compact"))
+            .await;
+        assert_eq!(compact_export.status, StatusCode::OK);
+        assert_eq!(
+            dots_export.json()["dots"].as_array().unwrap().len(),
+            dots.len()
+        );
+        assert!(
+            compact_export.body.len() * 4 < dots_export.body.len(),
+            "compact {} bytes vs dots {} bytes",
+            compact_export.body.len(),
+            dots_export.body.len()
+        );
+
+        // 書き出した作成リクエストは、どちらの表現でも同じキャンバスになる
+        for export in [&dots_export, &compact_export] {
+            let response = client.post("/api/artworks", export.json()).await;
+            assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+            assert_eq!(response.json()["artwork"]["total_dots"], dots.len());
+            let created = response.json()["id"].as_str().unwrap().to_string();
+            let diff = client
+                .get(&format!("/api/artworks/{id}/diff/{created}"))
+                .await
+                .json();
+            assert_eq!(diff["in_both"]["count"], dots.len());
+            assert_eq!(diff["only_in_b"]["count"], 0);
+        }
+
+        let mut both = compact_export.json();
+        both["dots"] = serde_json::json!([{ "x": 0, "y": 0, "color": "#000000" }]);
+        let response = client.post("/api/artworks", both).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        let overlapping = serde_json::json!({
+            "name": "overlapping",
+            "width": 4,
+            "height": 1,
+            "rows": [[[0, 2, "#000000"], [1, 2, "#000000"]]]
+        });
+        let response = client.post("/api/artworks", overlapping).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(response.message().contains("overlaps"));
+    }
+}
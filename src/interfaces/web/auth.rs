@@ -0,0 +1,211 @@
+use axum::http::{HeaderMap, header};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::info;
+
+/// ログイン後に発行するセッションCookieの名前
+pub const SESSION_COOKIE: &str = "ghost_drawer_session";
+/// セッションCookieの有効期間（30日）
+pub const SESSION_MAX_AGE_SECS: u64 = 30 * 24 * 60 * 60;
+
+/// 変更系APIに必要なアクセストークン
+#[derive(Clone, PartialEq, Eq)]
+pub struct AuthToken(String);
+
+/// リクエストがトークンを示した方法
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Credential {
+    /// `Authorization: Bearer` ヘッダー
+    Bearer,
+    /// ログイン後のセッションCookie
+    Session,
+}
+
+#[derive(Debug, Error)]
+pub enum AuthError {
+    #[error("Failed to create data directory {path}: {source}")]
+    CreateDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to read access token {path}: {source}")]
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to write access token {path}: {source}")]
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Access token file {0} is empty")]
+    Empty(PathBuf),
+}
+
+impl AuthToken {
+    pub fn new(token: impl Into<String>) -> Self {
+        Self(token.into())
+    }
+
+    /// OSの暗号論的乱数から256ビットのトークンを生成（16進数64文字）
+    pub fn generate() -> Self {
+        let mut bytes = [0u8; 32];
+        getrandom::fill(&mut bytes).expect("the OS random number generator is unavailable");
+        Self(bytes.iter().map(|b| format!("{b:02x}")).collect())
+    }
+
+    /// データディレクトリ内のトークンファイルのパス
+    pub fn path(data_dir: &Path) -> PathBuf {
+        data_dir.join("auth-token")
+    }
+
+    /// 保存済みのトークンを読み込む（未生成なら `None`）
+    pub fn load(data_dir: &Path) -> Result<Option<Self>, AuthError> {
+        let path = Self::path(data_dir);
+        match std::fs::read_to_string(&path) {
+            Ok(contents) => {
+                let token = contents.trim();
+                if token.is_empty() {
+                    return Err(AuthError::Empty(path));
+                }
+                Ok(Some(Self(token.to_string())))
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(source) => Err(AuthError::Read { path, source }),
+        }
+    }
+
+    /// 保存済みのトークンを読み込み、無ければ生成して保存する
+    pub fn load_or_create(data_dir: &Path) -> Result<Self, AuthError> {
+        if let Some(token) = Self::load(data_dir)? {
+            return Ok(token);
+        }
+
+        crate::infrastructure::data_dir::create_data_dir(data_dir).map_err(|source| {
+            AuthError::CreateDirectory {
+                path: data_dir.to_path_buf(),
+                source,
+            }
+        })?;
+
+        let token = Self::generate();
+        let path = Self::path(data_dir);
+        {
+            use std::io::Write;
+            crate::infrastructure::platform::create_with_mode(&path, 0o600)
+                .and_then(|mut file| writeln!(file, "{}", token.0))
+                .map_err(|source| AuthError::Write {
+                    path: path.clone(),
+                    source,
+                })?;
+        }
+        info!("Generated access token: {}", path.display());
+        Ok(token)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// ログイン後のCookieに入れる値（トークンそのものは保存させない）
+    pub fn session_value(&self) -> String {
+        let digest = Sha256::new()
+            .chain_update(b"session:")
+            .chain_update(self.0.as_bytes())
+            .finalize();
+        digest.iter().map(|b| format!("{b:02x}")).collect()
+    }
+
+    /// `Authorization: Bearer` のトークンを検証
+    pub fn verify(&self, candidate: &str) -> bool {
+        constant_time_eq(self.0.as_bytes(), candidate.as_bytes())
+    }
+
+    /// セッションCookieの値を検証
+    pub fn verify_session(&self, candidate: &str) -> bool {
+        constant_time_eq(self.session_value().as_bytes(), candidate.as_bytes())
+    }
+
+    /// `Authorization: Bearer` ヘッダーかセッションCookieでトークンを検証
+    pub fn authenticate(&self, headers: &HeaderMap) -> Option<Credential> {
+        let bearer = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .is_some_and(|candidate| self.verify(candidate.trim()));
+        if bearer {
+            return Some(Credential::Bearer);
+        }
+
+        headers
+            .get_all(header::COOKIE)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(';'))
+            .filter_map(|pair| pair.trim().split_once('='))
+            .any(|(name, value)| name == SESSION_COOKIE && self.verify_session(value))
+            .then_some(Credential::Session)
+    }
+
+    /// ログなどに表示するトークンの識別子（トークンそのものは推測できない）
+    pub fn fingerprint(&self) -> String {
+        let digest = Sha256::new()
+            .chain_update(b"fingerprint:")
+            .chain_update(self.0.as_bytes())
+            .finalize();
+        digest[..4].iter().map(|b| format!("{b:02x}")).collect()
+    }
+}
+
+// ログにトークンが出力されないようにする
+impl fmt::Debug for AuthToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("AuthToken(..)")
+    }
+}
+
+/// 比較にかかる時間から一致した長さを推測されないように比較する
+fn constant_time_eq(expected: &[u8], candidate: &[u8]) -> bool {
+    expected.len() == candidate.len()
+        && expected
+            .iter()
+            .zip(candidate)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_or_create_persists_token() {
+        let dir = std::env::temp_dir().join(format!("ghost-drawer-auth-{}", uuid::Uuid::new_v4()));
+        assert!(AuthToken::load(&dir).unwrap().is_none());
+
+        let created = AuthToken::load_or_create(&dir).unwrap();
+        assert_eq!(created.as_str().len(), 64);
+        assert!(created.as_str().bytes().all(|b| b.is_ascii_hexdigit()));
+        assert_ne!(AuthToken::generate(), created);
+        assert_eq!(AuthToken::load_or_create(&dir).unwrap(), created);
+        assert_eq!(AuthToken::load(&dir).unwrap(), Some(created));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_verify_token_and_session() {
+        let token = AuthToken::new("secret");
+        assert!(token.verify("secret"));
+        assert!(!token.verify("secreT"));
+        assert!(!token.verify("secret2"));
+        assert!(token.verify_session(&token.session_value()));
+        assert!(!token.verify_session("secret"));
+        assert_ne!(
+            token.session_value(),
+            AuthToken::new("other").session_value()
+        );
+    }
+}
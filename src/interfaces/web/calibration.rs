@@ -0,0 +1,535 @@
+//! 速度キャリブレーションと移動テストのAPIハンドラー
+
+use super::dto::ApiResponse;
+use super::error_response::ErrorResponse;
+use super::models::{
+    CalibrationCleanupRequest, CalibrationCleanupResponse, CalibrationRequest,
+    CalibrationStartResponse, MAX_CLEANUP_DELAY_SECS,
+};
+use super::state::{ArtworkState, CalibrationSession, release_active_painting};
+use crate::application::controller_io::run_controller_io;
+use crate::application::progress::send_progress;
+use crate::application::use_cases::{
+    CALIBRATION_CANVAS_HEIGHT, CALIBRATION_CANVAS_WIDTH, CalibrationTrace, MoveTestKind,
+    PaintingControl, perform_calibration_cleanup, perform_move_test, perform_speed_calibration,
+};
+use crate::domain::hardware::errors::HardwareError;
+use crate::domain::painting::{InitSequence, PaintTiming, calibration_plan};
+use crate::domain::shared::i18n::{Message, MessageKey};
+use axum::{Json, extract::State, http::StatusCode};
+use chrono::Utc;
+use serde_json::json;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tracing::{error, info};
+
+/// 自動の後片付けを待つ間に停止要求を確認する間隔
+const CLEANUP_DELAY_POLL: std::time::Duration = std::time::Duration::from_millis(100);
+
+/// 速度キャリブレーションテストを開始するAPIハンドラー
+#[utoipa::path(
+    post, path = "/api/calibration/start", tag = "calibration",
+    request_body = CalibrationRequest,
+    responses(
+        (status = 200, body = CalibrationStartResponse),
+        (status = 409, description = "厳格シミュレーション中", body = ErrorResponse),
+        (status = 423, description = "コントローラーがアームされていない", body = ErrorResponse),
+        (status = 422, description = "パターンがキャンバスに収まらない、`cleanup_delay_secs` が上限を超える", body = ErrorResponse)
+    )
+)]
+pub async fn start_calibration(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<CalibrationRequest>,
+) -> Result<Json<CalibrationStartResponse>, ErrorResponse> {
+    state.ensure_controller_allowed()?;
+    let plan = calibration_plan(
+        &request.layout(),
+        CALIBRATION_CANVAS_WIDTH,
+        CALIBRATION_CANVAS_HEIGHT,
+    )
+    .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    if request.cleanup_delay_secs > MAX_CLEANUP_DELAY_SECS {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("cleanup_delay_secs must be at most {MAX_CLEANUP_DELAY_SECS}"),
+        ));
+    }
+    info!(
+        "Starting speed calibration test with params: press={}ms, release={}ms, wait={}ms, skip_init={}, pattern={:?}",
+        request.press_ms,
+        request.release_ms,
+        request.wait_ms,
+        request.skip_initialization,
+        request.pattern
+    );
+
+    let controller = state.controller.clone();
+    let press_ms = request.press_ms;
+    let release_ms = request.release_ms;
+    let wait_ms = request.wait_ms;
+    let init_sequence =
+        (!request.skip_initialization).then(|| InitSequence::preset(state.init_preset));
+
+    // Setup control signals
+    let control = PaintingControl::new(1, press_ms, release_ms, wait_ms);
+    let stop_signal = control.stop_signal.clone();
+
+    // Store active painting control
+    state.begin_painting(&control).await?;
+
+    // 描いたドットを後片付けできるよう、前のキャリブレーションの記録と置き換える
+    let session = CalibrationSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        trace: Arc::new(CalibrationTrace::new()),
+        timing: PaintTiming::new(press_ms, release_ms, wait_ms),
+    };
+    *state.calibration.write().await = Some(session.clone());
+
+    let active_painting_store = state.active_painting.clone();
+    let task_plan = plan.clone();
+    let trace = session.trace.clone();
+    let auto_cleanup = request
+        .auto_cleanup
+        .then(|| std::time::Duration::from_secs(request.cleanup_delay_secs.into()));
+
+    // Spawn calibration task
+    tokio::spawn(async move {
+        let result = run_controller_io(move || {
+            perform_speed_calibration(
+                controller.clone(),
+                stop_signal.clone(),
+                press_ms,
+                release_ms,
+                wait_ms,
+                init_sequence.as_ref(),
+                &task_plan,
+                &trace,
+            )?;
+            if let Some(delay) = auto_cleanup
+                && wait_unless_stopped(&stop_signal, delay)
+            {
+                let result = perform_calibration_cleanup(
+                    controller,
+                    stop_signal,
+                    &trace,
+                    press_ms,
+                    release_ms,
+                    wait_ms,
+                );
+                send_cleanup_complete(&result);
+                result?;
+            }
+            Ok::<(), HardwareError>(())
+        })
+        .await;
+
+        // Clear active painting when done
+        release_active_painting(&active_painting_store, &control).await;
+
+        // Send completion status through PROGRESS_CHANNEL for frontend notification
+        match result {
+            Ok(Ok(_)) => {
+                info!("Calibration completed successfully");
+                // Send calibration completion event
+                send_progress(
+                    json!({
+                        "type": "calibration_complete",
+                        "timestamp": Utc::now().to_rfc3339(),
+                        "status": "success"
+                    }),
+                    "message",
+                    &Message::new(MessageKey::CalibrationCompleted),
+                );
+            }
+            Ok(Err(e)) => {
+                error!("Calibration failed with hardware error: {}", e);
+                // Send calibration failure event
+                send_progress(
+                    json!({
+                        "type": "calibration_complete",
+                        "timestamp": Utc::now().to_rfc3339(),
+                        "status": "error"
+                    }),
+                    "message",
+                    &Message::new(MessageKey::CalibrationFailed).with_arg("error", e),
+                );
+            }
+            Err(e) => {
+                error!("Calibration task panicked or was cancelled: {}", e);
+                // Send calibration cancellation event
+                send_progress(
+                    json!({
+                        "type": "calibration_complete",
+                        "timestamp": Utc::now().to_rfc3339(),
+                        "status": "cancelled"
+                    }),
+                    "message",
+                    &Message::new(MessageKey::CalibrationCancelled),
+                );
+            }
+        }
+    });
+
+    Ok(Json(CalibrationStartResponse::new(
+        session.id,
+        request.pattern,
+        plan,
+    )))
+}
+
+/// 自動の後片付けの前に、結果を撮影するための時間だけ待つ（停止要求があれば `false`）
+fn wait_unless_stopped(stop_signal: &AtomicBool, delay: std::time::Duration) -> bool {
+    if stop_signal.load(Ordering::SeqCst) {
+        return false;
+    }
+    info!("Erasing the calibration pattern in {:?}", delay);
+    send_progress(
+        json!({
+            "type": "calibration_cleanup_pending",
+            "timestamp": Utc::now().to_rfc3339(),
+            "delay_secs": delay.as_secs()
+        }),
+        "message",
+        &Message::new(MessageKey::CalibrationCleanupPending).with_arg("seconds", delay.as_secs()),
+    );
+    let deadline = std::time::Instant::now() + delay;
+    while std::time::Instant::now() < deadline {
+        if stop_signal.load(Ordering::SeqCst) {
+            return false;
+        }
+        std::thread::sleep(CLEANUP_DELAY_POLL.min(deadline - std::time::Instant::now()));
+    }
+    !stop_signal.load(Ordering::SeqCst)
+}
+
+/// 後片付けの結果を進捗チャンネルへ送る
+fn send_cleanup_complete(result: &Result<usize, HardwareError>) {
+    let (value, message) = match result {
+        Ok(erased) => (
+            json!({
+                "type": "calibration_cleanup_complete",
+                "timestamp": Utc::now().to_rfc3339(),
+                "status": "success",
+                "erased": erased
+            }),
+            Message::new(MessageKey::CalibrationCleanupCompleted).with_arg("dots", erased),
+        ),
+        Err(e) => {
+            error!("Calibration cleanup failed: {}", e);
+            (
+                json!({
+                    "type": "calibration_cleanup_complete",
+                    "timestamp": Utc::now().to_rfc3339(),
+                    "status": "error"
+                }),
+                Message::new(MessageKey::CalibrationCleanupFailed).with_arg("error", e),
+            )
+        }
+    };
+    send_progress(value, "message", &message);
+}
+
+/// Erase the dots drawn by a speed calibration
+///
+/// キャリブレーションで記録した座標を、描いた順と逆に辿ってBボタンで消す。
+/// 停止した場合は消していないドットが記録に残るため、もう一度呼べば続きから消せる。
+#[utoipa::path(
+    post, path = "/api/calibration/cleanup", tag = "calibration",
+    request_body = CalibrationCleanupRequest,
+    responses(
+        (status = 200, body = CalibrationCleanupResponse),
+        (status = 404, description = "セッションが存在しない（サーバーの再起動後など）", body = ErrorResponse),
+        (status = 409, description = "カーソル位置が分からない、または描画・キャリブレーションの実行中", body = ErrorResponse),
+        (status = 423, description = "コントローラーがアームされていない", body = ErrorResponse)
+    )
+)]
+pub async fn cleanup_calibration(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<CalibrationCleanupRequest>,
+) -> Result<Json<CalibrationCleanupResponse>, ErrorResponse> {
+    state.ensure_controller_allowed()?;
+    let session = state
+        .calibration
+        .read()
+        .await
+        .clone()
+        .filter(|session| session.id == request.session_id)
+        .ok_or_else(|| {
+            ErrorResponse::new(
+                StatusCode::NOT_FOUND,
+                format!(
+                    "Calibration session {} not found (only the latest calibration since the server started can be cleaned up)",
+                    request.session_id
+                ),
+            )
+        })?;
+
+    let press_ms = request.press_ms.unwrap_or(session.timing.press_ms);
+    let release_ms = request.release_ms.unwrap_or(session.timing.release_ms);
+    let wait_ms = request.wait_ms.unwrap_or(session.timing.wait_ms);
+    let control = PaintingControl::new(1, press_ms, release_ms, wait_ms);
+    state.begin_painting(&control).await?;
+
+    // カーソルが他の操作で動いていたら、どこを消すか分からない
+    if session.trace.cursor().is_none() {
+        release_active_painting(&state.active_painting, &control).await;
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "The cursor position is unknown because the calibration was interrupted before reaching its origin or the controller was used since; erase the pattern manually",
+        ));
+    }
+    let dots = session.trace.drawn_dots().len();
+    info!("Cleaning up calibration {} ({} dots)", session.id, dots);
+
+    let controller = state.controller.clone();
+    let stop_signal = control.stop_signal.clone();
+    let active_painting_store = state.active_painting.clone();
+    let trace = session.trace.clone();
+    tokio::spawn(async move {
+        let result = run_controller_io(move || {
+            perform_calibration_cleanup(
+                controller,
+                stop_signal,
+                &trace,
+                press_ms,
+                release_ms,
+                wait_ms,
+            )
+        })
+        .await
+        .unwrap_or_else(|e| Err(HardwareError::Unknown(e.to_string())));
+        release_active_painting(&active_painting_store, &control).await;
+        send_cleanup_complete(&result);
+    });
+
+    Ok(Json(CalibrationCleanupResponse {
+        success: true,
+        message: "Calibration cleanup started".to_string(),
+        session_id: session.id,
+        dots,
+    }))
+}
+
+/// 描画移動テストを開始するAPIハンドラー
+#[utoipa::path(
+    post, path = "/api/calibration/test/paint-move", tag = "calibration",
+    request_body = CalibrationRequest,
+    responses(
+        (status = 200, body = ApiResponse),
+        (status = 409, description = "厳格シミュレーション中", body = ErrorResponse),
+        (status = 423, description = "コントローラーがアームされていない", body = ErrorResponse)
+    )
+)]
+pub async fn start_paint_move_test(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<CalibrationRequest>,
+) -> Result<Json<ApiResponse>, ErrorResponse> {
+    start_move_test(state, request, MoveTestKind::PaintMove).await
+}
+
+/// 空白移動テストを開始するAPIハンドラー
+#[utoipa::path(
+    post, path = "/api/calibration/test/gap-move", tag = "calibration",
+    request_body = CalibrationRequest,
+    responses(
+        (status = 200, body = ApiResponse),
+        (status = 409, description = "厳格シミュレーション中", body = ErrorResponse),
+        (status = 423, description = "コントローラーがアームされていない", body = ErrorResponse)
+    )
+)]
+pub async fn start_gap_move_test(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<CalibrationRequest>,
+) -> Result<Json<ApiResponse>, ErrorResponse> {
+    start_move_test(state, request, MoveTestKind::GapMove).await
+}
+
+/// 移動テストを開始し、終了したら結果を進捗チャンネルへ送る
+async fn start_move_test(
+    state: Arc<ArtworkState>,
+    request: CalibrationRequest,
+    kind: MoveTestKind,
+) -> Result<Json<ApiResponse>, ErrorResponse> {
+    state.ensure_controller_allowed()?;
+    info!("Starting {} test", kind.name().to_lowercase());
+
+    let controller = state.controller.clone();
+    let press_ms = request.press_ms;
+    let release_ms = request.release_ms;
+    let wait_ms = request.wait_ms;
+
+    let control = PaintingControl::new(1, press_ms, release_ms, wait_ms);
+    let stop_signal = control.stop_signal.clone();
+
+    state.begin_painting(&control).await?;
+    state.forget_calibration_cursor().await;
+
+    let active_painting_store = state.active_painting.clone();
+
+    tokio::spawn(async move {
+        let result = run_controller_io(move || {
+            perform_move_test(controller, stop_signal, kind, press_ms, release_ms, wait_ms)
+        })
+        .await;
+
+        release_active_painting(&active_painting_store, &control).await;
+
+        let label = match kind {
+            MoveTestKind::PaintMove => MessageKey::PaintMoveTest,
+            MoveTestKind::GapMove => MessageKey::GapMoveTest,
+        };
+        let (status, key) = match result {
+            Ok(Ok(_)) => ("success", MessageKey::MoveTestCompleted),
+            _ => ("error", MessageKey::MoveTestFailed),
+        };
+        send_progress(
+            json!({
+                "type": "calibration_complete",
+                "timestamp": Utc::now().to_rfc3339(),
+                "status": status
+            }),
+            "message",
+            &Message::new(key).with_key_arg("test", label),
+        );
+    });
+
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("{} test started", kind.name()),
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::state::ControllerMode;
+    use super::super::test_support::TestClient;
+    use super::*;
+    use crate::domain::controller::{ActionType, Button};
+    use crate::infrastructure::hardware::mock_controller::MockController;
+    use axum::response::IntoResponse;
+
+    #[tokio::test]
+    async fn test_strict_simulation_rejects_calibration() {
+        let state = ArtworkState::new(Arc::new(MockController::new().without_delays()))
+            .with_controller_mode(ControllerMode::Simulated { strict: true });
+
+        let result =
+            start_calibration(State(Arc::new(state)), Json(CalibrationRequest::default())).await;
+
+        let response = result.unwrap_err().into_response();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+    }
+
+    #[tokio::test]
+    async fn test_calibration_rejects_pattern_outside_canvas() {
+        let state = ArtworkState::new(Arc::new(MockController::new().without_delays()));
+        state.interlock.arm("test", None);
+        let request = CalibrationRequest {
+            pattern: crate::domain::painting::CalibrationPattern::Vertical,
+            width: 200,
+            ..Default::default()
+        };
+
+        let result = start_calibration(State(Arc::new(state)), Json(request)).await;
+
+        let response = result.unwrap_err().into_response();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_calibration_routes_require_armed_controller() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let client = TestClient::new(state.clone());
+        let request = serde_json::json!({ "press_ms": 1, "release_ms": 1, "wait_ms": 1 });
+        let paths = [
+            "/api/calibration/start",
+            "/api/calibration/test/paint-move",
+            "/api/calibration/test/gap-move",
+        ];
+
+        for path in paths {
+            let response = client.post(path, request.clone()).await;
+            assert_eq!(response.status, StatusCode::LOCKED, "{path}");
+        }
+
+        state.interlock.arm("test", None);
+        for path in paths {
+            let response = client.post(path, request.clone()).await;
+            assert_eq!(
+                response.status,
+                StatusCode::OK,
+                "{path}: {}",
+                response.text()
+            );
+            assert_eq!(response.json()["success"], true, "{path}");
+            assert!(
+                state
+                    .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                    .await
+            );
+        }
+        let response = client
+            .post(
+                "/api/calibration/start",
+                serde_json::json!({ "press_ms": 1 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_cleanup_erases_the_latest_calibration() {
+        let controller = Arc::new(MockController::new().without_delays().with_command_log());
+        let state = Arc::new(ArtworkState::new(controller.clone()));
+        state.interlock.arm("test", None);
+        let client = TestClient::new(state.clone());
+        let timing = serde_json::json!({ "press_ms": 1, "release_ms": 1, "wait_ms": 1 });
+
+        let response = client
+            .post(
+                "/api/calibration/cleanup",
+                serde_json::json!({ "session_id": "unknown" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        let mut request = timing.clone();
+        request["skip_initialization"] = true.into();
+        request["cleanup_delay_secs"] = (MAX_CLEANUP_DELAY_SECS + 1).into();
+        let response = client.post("/api/calibration/start", request.clone()).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        request["cleanup_delay_secs"] = 0.into();
+        let response = client.post("/api/calibration/start", request).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let session_id = response.json()["session_id"].as_str().unwrap().to_string();
+        let start = std::time::Duration::from_secs(10);
+        assert!(state.wait_for_painting_to_finish(start).await);
+        let drawn = controller.recorded_operations().a_presses;
+        assert!(drawn > 0);
+
+        let mut request = timing.clone();
+        request["session_id"] = session_id.clone().into();
+        let response = client
+            .post("/api/calibration/cleanup", request.clone())
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        assert_eq!(response.json()["dots"], drawn);
+        assert!(state.wait_for_painting_to_finish(start).await);
+        let erased = controller
+            .recorded_commands()
+            .into_iter()
+            .flat_map(|command| command.sequence)
+            .filter(|action| action.action_type == ActionType::PressButton(Button::B))
+            .count();
+        assert_eq!(erased as u64, drawn);
+
+        // 移動テストでカーソルが動いたら、どこを消すか分からない
+        client.post("/api/calibration/test/gap-move", timing).await;
+        assert!(state.wait_for_painting_to_finish(start).await);
+        let response = client.post("/api/calibration/cleanup", request).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+    }
+}
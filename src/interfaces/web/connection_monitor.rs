@@ -0,0 +1,165 @@
+//! Switchとの接続状態の監視
+//!
+//! 一定間隔で `ControllerEmulator::is_connected` を確認し、`connection_state` メッセージを
+//! 進捗チャンネルへ送る。WebUIはこれを受けて接続状態のバッジを更新する。
+//!
+//! 実機の `is_connected` はテスト用のレポートを書き込むため、描画・キャリブレーション・
+//! 接続修正・手動入力の間は確認を見送り、デバイスへの書き込みが競合しないようにする。
+
+use super::log_streamer::PROGRESS_CHANNEL;
+use super::state::ArtworkState;
+use crate::application::controller_io::run_controller_io;
+use crate::domain::controller::ControllerEmulator;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// 接続状態を確認する間隔の既定値
+pub const DEFAULT_CONNECTION_MONITOR_INTERVAL: Duration = Duration::from_secs(5);
+
+/// 接続状態の監視の設定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionMonitorSettings {
+    pub enabled: bool,
+    /// 接続状態を確認する間隔
+    pub interval: Duration,
+}
+
+impl Default for ConnectionMonitorSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            interval: DEFAULT_CONNECTION_MONITOR_INTERVAL,
+        }
+    }
+}
+
+impl ConnectionMonitorSettings {
+    pub fn disabled() -> Self {
+        Self {
+            enabled: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+}
+
+/// 接続状態の監視をバックグラウンドで開始する（無効な場合は何もしない）
+pub fn spawn_connection_monitor(
+    state: Arc<ArtworkState>,
+    settings: ConnectionMonitorSettings,
+) -> Option<tokio::task::JoinHandle<()>> {
+    if !settings.enabled {
+        info!("Connection monitor is disabled");
+        return None;
+    }
+    info!(
+        "Monitoring the connection to the Switch every {} ms",
+        settings.interval.as_millis()
+    );
+    Some(tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(settings.interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut last_state = None;
+        loop {
+            ticker.tick().await;
+            let Some(connected) = poll_connection(&state).await else {
+                continue;
+            };
+            let changed = last_state != Some(connected);
+            last_state = Some(connected);
+            let _ = PROGRESS_CHANNEL.send(connection_state_message(connected, changed));
+        }
+    }))
+}
+
+/// デバイスを使う操作が無ければ接続状態を確認する（見送った場合は `None`）
+pub(crate) async fn poll_connection(state: &ArtworkState) -> Option<bool> {
+    // 確認中に描画が始まらないよう、描画のロックを確認が終わるまで保持する
+    let active_painting = state.active_painting.read().await;
+    if active_painting.is_some() {
+        debug!("Skipping connection check while painting is active");
+        return None;
+    }
+    if state.connection_fix.read().await.is_some() {
+        debug!("Skipping connection check while the connection is being fixed");
+        return None;
+    }
+    let Ok(_input_guard) = state.controller_input.try_lock() else {
+        debug!("Skipping connection check while manual input is being sent");
+        return None;
+    };
+    Some(probe_connection(state.controller.clone()).await)
+}
+
+/// 接続を確認する（確認できなかった場合は未接続として扱う）
+pub(crate) async fn probe_connection(controller: Arc<dyn ControllerEmulator>) -> bool {
+    match run_controller_io(move || controller.is_connected()).await {
+        Ok(Ok(connected)) => connected,
+        Ok(Err(e)) => {
+            debug!("Connection check failed: {}", e);
+            false
+        }
+        Err(e) => {
+            debug!("Connection check panicked: {}", e);
+            false
+        }
+    }
+}
+
+/// `connection_state` メッセージ（`changed` は前回の確認から状態が変わったか）
+fn connection_state_message(connected: bool, changed: bool) -> String {
+    json!({
+        "type": "connection_state",
+        "state": if connected { "connected" } else { "disconnected" },
+        "changed": changed,
+        "timestamp": chrono::Utc::now().to_rfc3339(),
+    })
+    .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::use_cases::PaintingControl;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+    use crate::interfaces::web::state::ConnectionFixSession;
+    use std::sync::atomic::AtomicBool;
+
+    #[tokio::test]
+    async fn test_poll_connection_pauses_while_the_device_is_in_use() {
+        let state = ArtworkState::new(Arc::new(MockController::new()));
+        assert_eq!(poll_connection(&state).await, Some(true));
+
+        *state.active_painting.write().await = Some(PaintingControl::new(1, 100, 100, 100));
+        assert_eq!(poll_connection(&state).await, None);
+        *state.active_painting.write().await = None;
+
+        *state.connection_fix.write().await = Some(ConnectionFixSession {
+            id: "fix".to_string(),
+            abort_signal: Arc::new(AtomicBool::new(false)),
+        });
+        assert_eq!(poll_connection(&state).await, None);
+        *state.connection_fix.write().await = None;
+
+        let input_guard = state.controller_input.lock().await;
+        assert_eq!(poll_connection(&state).await, None);
+        drop(input_guard);
+        assert_eq!(poll_connection(&state).await, Some(true));
+    }
+
+    #[test]
+    fn test_connection_state_message() {
+        let message: serde_json::Value =
+            serde_json::from_str(&connection_state_message(false, true)).unwrap();
+        assert_eq!(message["type"], "connection_state");
+        assert_eq!(message["state"], "disconnected");
+        assert_eq!(message["changed"], true);
+        assert!(message["timestamp"].is_string());
+    }
+}
@@ -0,0 +1,267 @@
+//! 手動入力と出力の安全装置（アーム・解除）のAPIハンドラー
+
+use super::auth::Credential;
+use super::error_response::ErrorResponse;
+use super::models::{
+    ArmControllerRequest, ControllerCapabilities, ControllerInputRequest, ControllerInputResponse,
+    ControllerStatus, StickRange,
+};
+use super::state::ArtworkState;
+use crate::application::controller_io::run_controller_io;
+use crate::application::use_cases::{SendControllerInputUseCase, format_report};
+use crate::domain::controller::{
+    Button, DPad, InterlockState, ManualInput, ManualInputKind, StickPosition,
+};
+use crate::domain::shared::i18n::{Message, MessageKey};
+use axum::{
+    Json,
+    body::Bytes,
+    extract::State,
+    http::{HeaderMap, StatusCode},
+};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Send a single controller input for manual control
+///
+/// 入力後はボタンを離す・ニュートラルに戻すところまで実行する。
+/// 連続したリクエストは到着順に1件ずつ送信し、デバイス上で混ざらないようにする。
+#[utoipa::path(
+    post, path = "/api/controller/input", tag = "controller",
+    request_body = ControllerInputRequest,
+    responses(
+        (status = 200, body = ControllerInputResponse),
+        (status = 409, description = "描画・キャリブレーション・接続修正の実行中、または厳格シミュレーション中", body = ErrorResponse),
+        (status = 423, description = "コントローラーがアームされていない", body = ErrorResponse),
+        (status = 422, description = "ボタン名・方向名が不正", body = ErrorResponse),
+        (status = 500, description = "コントローラーへの送信に失敗", body = ErrorResponse)
+    )
+)]
+pub async fn send_controller_input(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<ControllerInputRequest>,
+) -> Result<Json<ControllerInputResponse>, ErrorResponse> {
+    state.ensure_controller_allowed()?;
+    let input = ManualInput::parse(request.kind, &request.value)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    let _input_guard = state.controller_input.lock().await;
+    if state.active_painting.read().await.is_some() {
+        return Err(ErrorResponse::localized(
+            StatusCode::CONFLICT,
+            Message::new(MessageKey::ManualInputWhilePainting),
+        ));
+    }
+    if state.connection_fix.read().await.is_some() {
+        return Err(ErrorResponse::localized(
+            StatusCode::CONFLICT,
+            Message::new(MessageKey::ManualInputWhileFixingConnection),
+        ));
+    }
+
+    state.forget_calibration_cursor().await;
+    let use_case = SendControllerInputUseCase::new(state.controller.clone());
+    let duration_ms = request.duration_ms;
+    let report = run_controller_io(move || use_case.execute(input, duration_ms))
+        .await
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| {
+            error!("Manual input failed: {}", e);
+            ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+
+    Ok(Json(ControllerInputResponse {
+        duration_ms: report.duration_ms,
+        reports: report.reports.iter().map(format_report).collect(),
+    }))
+}
+
+/// List the button names, D-pad values and stick range accepted by the API
+///
+/// 名前は安定したAPIとして扱い、UIはこの一覧からボタンを生成する。
+#[utoipa::path(
+    get, path = "/api/controller/capabilities", tag = "controller",
+    responses((status = 200, body = ControllerCapabilities))
+)]
+pub async fn get_controller_capabilities() -> Json<ControllerCapabilities> {
+    Json(ControllerCapabilities {
+        buttons: Button::ALL.to_vec(),
+        dpad: DPad::ALL.to_vec(),
+        stick: StickRange {
+            min: StickPosition::MIN,
+            max: StickPosition::MAX,
+            center: StickPosition::CENTER.x,
+        },
+        input_kinds: vec![
+            ManualInputKind::Button,
+            ManualInputKind::Dpad,
+            ManualInputKind::Stick,
+        ],
+        max_duration_ms: ManualInput::MAX_DURATION_MS,
+    })
+}
+
+/// Get the controller output interlock state
+#[utoipa::path(
+    get, path = "/api/controller/status", tag = "controller",
+    responses((status = 200, body = ControllerStatus))
+)]
+pub async fn get_controller_status(
+    State(state): State<Arc<ArtworkState>>,
+) -> Json<ControllerStatus> {
+    Json(controller_status(&state).await)
+}
+
+/// Arm the controller so painting, calibration and manual input can send output
+///
+/// `auto_disarm_ms` を指定すると、その時間が過ぎた後は新たな出力を拒否する（実行中の描画は止めない）。
+#[utoipa::path(
+    post, path = "/api/controller/arm", tag = "controller",
+    request_body(content = Option<ArmControllerRequest>, description = "省略すると解除するまで有効"),
+    responses(
+        (status = 200, body = ControllerStatus),
+        (status = 400, description = "本文がJSONとして不正", body = ErrorResponse),
+        (status = 422, description = "`auto_disarm_ms` が0", body = ErrorResponse)
+    )
+)]
+pub async fn arm_controller(
+    State(state): State<Arc<ArtworkState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ControllerStatus>, ErrorResponse> {
+    // 本文は省略できる（`Content-Type: application/json` で空の場合も含む）
+    let request: ArmControllerRequest = if body.trim_ascii().is_empty() {
+        ArmControllerRequest::default()
+    } else {
+        serde_json::from_slice(&body)
+            .map_err(|e| ErrorResponse::new(StatusCode::BAD_REQUEST, e.to_string()))?
+    };
+    if request.auto_disarm_ms == Some(0) {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "auto_disarm_ms must be greater than 0",
+        ));
+    }
+
+    let armed_by = match state.auth_token() {
+        Some(token) => match token.authenticate(&headers) {
+            Some(Credential::Bearer) => format!("token {}", token.fingerprint()),
+            Some(Credential::Session) => format!("session {}", token.fingerprint()),
+            None => "unknown".to_string(),
+        },
+        None => "anonymous".to_string(),
+    };
+    info!(
+        "Controller armed by {} (auto-disarm: {:?} ms)",
+        armed_by, request.auto_disarm_ms
+    );
+    state
+        .interlock
+        .arm(armed_by, request.auto_disarm_ms.map(Duration::from_millis));
+    Ok(Json(controller_status(&state).await))
+}
+
+/// Disarm the controller, stopping any active painting or calibration
+#[utoipa::path(
+    post, path = "/api/controller/disarm", tag = "controller",
+    responses((status = 200, body = ControllerStatus))
+)]
+pub async fn disarm_controller(State(state): State<Arc<ArtworkState>>) -> Json<ControllerStatus> {
+    state.interlock.disarm();
+    // 描画中の解除は停止ボタンと同じ扱い
+    if state.stop_active_painting().await {
+        info!("Controller disarmed; stop signal sent to active painting");
+    } else {
+        info!("Controller disarmed");
+    }
+    Json(controller_status(&state).await)
+}
+
+async fn controller_status(state: &ArtworkState) -> ControllerStatus {
+    let painting_active = state.active_painting.read().await.is_some();
+    match state.interlock.state() {
+        InterlockState::Armed {
+            armed_by,
+            remaining,
+        } => ControllerStatus {
+            armed: true,
+            armed_by: Some(armed_by),
+            auto_disarm_in_ms: remaining.map(|remaining| remaining.as_millis() as u64),
+            painting_active,
+        },
+        InterlockState::Disarmed => ControllerStatus {
+            armed: false,
+            armed_by: None,
+            auto_disarm_in_ms: None,
+            painting_active,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::TestClient;
+    use super::*;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    #[tokio::test]
+    async fn test_controller_routes_arm_send_input_and_disarm() {
+        let client = TestClient::new(Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        ))));
+        let input = serde_json::json!({ "type": "button", "value": "A", "duration_ms": 10 });
+
+        let response = client.get("/api/controller/status").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["armed"], false);
+        let response = client.post("/api/controller/input", input.clone()).await;
+        assert_eq!(response.status, StatusCode::LOCKED);
+
+        let response = client
+            .post(
+                "/api/controller/arm",
+                serde_json::json!({ "auto_disarm_ms": 0 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        let response = client.post_empty("/api/controller/arm").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["armed"], true);
+        assert_eq!(response.json()["armed_by"], "anonymous");
+
+        let response = client.post("/api/controller/input", input).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(!response.json()["reports"].as_array().unwrap().is_empty());
+        let response = client
+            .post(
+                "/api/controller/input",
+                serde_json::json!({ "type": "button", "value": "TURBO" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = client.post_empty("/api/controller/disarm").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["armed"], false);
+    }
+    #[tokio::test]
+    async fn test_controller_capabilities_lists_api_names() {
+        let client = TestClient::new(Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        ))));
+
+        let response = client.get("/api/controller/capabilities").await;
+        assert_eq!(response.status, StatusCode::OK);
+        let body = response.json();
+        assert_eq!(body["buttons"].as_array().unwrap().len(), Button::ALL.len());
+        assert_eq!(body["buttons"][6], "zl");
+        assert_eq!(body["dpad"][4], "up_left");
+        assert_eq!(body["dpad"][8], "neutral");
+        assert_eq!(
+            body["stick"],
+            serde_json::json!({ "min": 0, "max": 255, "center": 128 })
+        );
+        assert_eq!(body["max_duration_ms"], 3000);
+    }
+}
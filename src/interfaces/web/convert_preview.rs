@@ -0,0 +1,511 @@
+//! 画像をアートワークにせず、変換の各段階の結果を確かめるプレビューのAPIハンドラー
+//!
+//! 変換は `ConversionPipeline` の段階ごとに縮小したPNGにして返す。認証無しでも重い処理を
+//! 繰り返させないよう、アップロードの大きさ・デコード後の画素数・処理時間に上限を設け、
+//! 同時に実行するのは1件だけにする。
+
+use super::artworks::{ToneMode, estimate_painting_seconds};
+use super::error_response::ErrorResponse;
+use super::state::ArtworkState;
+use crate::domain::artwork::entities::Canvas;
+use crate::domain::artwork::services::{ConversionPipeline, ConversionStage, ConversionStages};
+use crate::domain::artwork::value_objects::{
+    ConversionError, ConversionParameters, ImageAdjustments, ImageFormat, Resolution, RgbaImage,
+    Transparency, TransparencyMode,
+};
+use crate::domain::shared::value_objects::Color;
+use crate::infrastructure::animation::{AnimationError, decode_image};
+use axum::{
+    Json,
+    extract::{Multipart, State},
+    http::StatusCode,
+};
+use base64::Engine;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// アップロードできる画像ファイルの大きさの上限
+pub const MAX_PREVIEW_UPLOAD_BYTES: usize = 4 * 1024 * 1024;
+/// デコードする画像の幅・高さの上限
+const MAX_PREVIEW_SIDE: u32 = 2000;
+/// デコードから全段階の変換までにかけられる時間
+const PREVIEW_TIME_LIMIT: Duration = Duration::from_secs(5);
+
+/// 変換の1段階の結果
+#[derive(Debug, Serialize, ToSchema)]
+pub struct StagePreview {
+    /// 段階の名前（`original` / `adjusted` / `binarized` / `dots`）
+    pub stage: String,
+    pub width: u32,
+    pub height: u32,
+    /// Base64でエンコードしたPNG
+    pub png_base64: String,
+}
+
+/// 変換プレビューの結果
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ConversionPreviewResponse {
+    /// 変換を適用する順の各段階（`dots` はキャンバスの解像度で、左上が原点）
+    pub stages: Vec<StagePreview>,
+    /// 描画するドット数
+    pub drawable_dots: usize,
+    /// 既定のタイミングで描画した場合の所要時間（秒）
+    pub estimated_painting_seconds: f64,
+}
+
+/// Preview how an image is converted into dots
+///
+/// アートワークは作成しない。各段階の画像はキャンバスの解像度（320x120）に収まるよう縮小する。
+#[utoipa::path(
+    post, path = "/api/convert/preview", tag = "artworks",
+    request_body(
+        content_type = "multipart/form-data",
+        description = "画像ファイル `file`（PNG・GIF・WebP、4MBまで）、任意の `adjustments`（画像調整のJSON、省略した項目は既定値）、`tone_mode`（`binary` / `stipple2` / `stipple4`）、`invert_background`（`true` / `1` / `on`）、`transparency_mode`（`skip` / `composite_white` / `composite_black`）、`alpha_cutoff`（1〜255、既定128）"
+    ),
+    responses(
+        (status = 200, body = ConversionPreviewResponse),
+        (status = 400, description = "ファイルが無い、画像・調整のJSON・`transparency_mode`・`alpha_cutoff` が不正", body = ErrorResponse),
+        (status = 413, description = "ファイルが4MBを超える", body = ErrorResponse),
+        (status = 415, description = "PNG・GIF・WebP以外の画像", body = ErrorResponse),
+        (status = 422, description = "画像調整の値が範囲外、`alpha_cutoff` が0、画像が2000x2000を超える、または変換が5秒以内に終わらない", body = ErrorResponse),
+        (status = 429, description = "別のプレビューを変換中", body = ErrorResponse)
+    )
+)]
+pub async fn preview_conversion(
+    State(state): State<Arc<ArtworkState>>,
+    mut multipart: Multipart,
+) -> Result<Json<ConversionPreviewResponse>, ErrorResponse> {
+    let mut image_data = Vec::new();
+    let mut params = ConversionParameters::new(ImageFormat::Png, Resolution::splatoon3_standard());
+    let mut transparency_mode = TransparencyMode::default();
+    let mut alpha_cutoff = Transparency::default().alpha_cutoff;
+
+    while let Some(field) = multipart
+        .next_field()
+        .await
+        .map_err(|e| ErrorResponse::new(e.status(), e.body_text()))?
+    {
+        let field_name = field.name().unwrap_or("").to_string();
+        let value = field
+            .bytes()
+            .await
+            .map_err(|e| ErrorResponse::new(e.status(), e.body_text()))?;
+        match field_name.as_str() {
+            "file" => image_data = value.to_vec(),
+            "adjustments" => {
+                params.adjustments =
+                    serde_json::from_slice::<ImageAdjustments>(&value).map_err(|e| {
+                        ErrorResponse::new(
+                            StatusCode::BAD_REQUEST,
+                            format!("Invalid adjustments: {e}"),
+                        )
+                    })?;
+            }
+            "tone_mode" => {
+                let text = String::from_utf8_lossy(&value).trim().to_string();
+                let tone_mode: ToneMode = serde_json::from_value(serde_json::Value::String(text))
+                    .map_err(|e| {
+                    ErrorResponse::new(StatusCode::BAD_REQUEST, format!("Invalid tone_mode: {e}"))
+                })?;
+                params.color_reduction = tone_mode.color_reduction();
+            }
+            "invert_background" => {
+                let text = String::from_utf8_lossy(&value).trim().to_ascii_lowercase();
+                params.invert_background = Some(matches!(text.as_str(), "true" | "1" | "on"));
+            }
+            "transparency_mode" => {
+                let text = String::from_utf8_lossy(&value).trim().to_string();
+                transparency_mode = serde_json::from_value(serde_json::Value::String(text))
+                    .map_err(|e| {
+                        ErrorResponse::new(
+                            StatusCode::BAD_REQUEST,
+                            format!("Invalid transparency_mode: {e}"),
+                        )
+                    })?;
+            }
+            "alpha_cutoff" => {
+                alpha_cutoff = String::from_utf8_lossy(&value)
+                    .trim()
+                    .parse()
+                    .map_err(|e| {
+                        ErrorResponse::new(
+                            StatusCode::BAD_REQUEST,
+                            format!("Invalid alpha_cutoff: {e}"),
+                        )
+                    })?;
+            }
+            _ => {}
+        }
+    }
+
+    if image_data.is_empty() {
+        return Err(ErrorResponse::new(
+            StatusCode::BAD_REQUEST,
+            "An image file is required",
+        ));
+    }
+    params.transparency = Transparency::new(transparency_mode, alpha_cutoff)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    params
+        .validate()
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    // 実行中のプレビューがあれば待たずに断り、変換が終わるまで許可を手放さない
+    let permit = state
+        .conversion_previews
+        .clone()
+        .try_acquire_owned()
+        .map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "Another conversion preview is running; try again shortly",
+            )
+        })?;
+
+    info!("Previewing the conversion of {} bytes", image_data.len());
+    let deadline = Instant::now() + PREVIEW_TIME_LIMIT;
+    let task = tokio::task::spawn_blocking(move || {
+        let _permit = permit;
+        render_preview(&image_data, &params, deadline)
+    });
+    // 段階の合間で打ち切れない場合に備え、応答は時間の上限を少し過ぎたら返す
+    let result = tokio::time::timeout(PREVIEW_TIME_LIMIT + Duration::from_secs(1), task)
+        .await
+        .map_err(|_| {
+            ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                ConversionError::TimedOut("dots").to_string(),
+            )
+        })?
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Conversion preview failed: {e}"),
+            )
+        })?;
+    result
+        .map(Json)
+        .inspect_err(|e| warn!("Conversion preview failed: {}", e.message))
+}
+
+/// 画像をデコードして全段階を変換し、各段階をPNGにする
+fn render_preview(
+    image_data: &[u8],
+    params: &ConversionParameters,
+    deadline: Instant,
+) -> Result<ConversionPreviewResponse, ErrorResponse> {
+    let image = decode_image(image_data, MAX_PREVIEW_SIDE).map_err(|e| {
+        let status = match e {
+            AnimationError::UnsupportedFormat => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AnimationError::TooLarge { .. } | AnimationError::TooManyFrames { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            AnimationError::Decode { .. } => StatusCode::BAD_REQUEST,
+        };
+        ErrorResponse::new(status, e.to_string())
+    })?;
+    let ConversionStages {
+        original,
+        adjusted,
+        binarized,
+        canvas,
+    } = ConversionPipeline::run(&image, params, deadline)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let dots = render_dots(&canvas, &params.target_resolution);
+
+    let stages = ConversionStage::ALL
+        .into_iter()
+        .zip([&original, &adjusted, &binarized, &dots])
+        .map(|(stage, image)| {
+            Ok(StagePreview {
+                stage: stage.name().to_string(),
+                width: image.width,
+                height: image.height,
+                png_base64: encode_png(image)?,
+            })
+        })
+        .collect::<Result<Vec<_>, png::EncodingError>>()
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to encode the preview: {e}"),
+            )
+        })?;
+    Ok(ConversionPreviewResponse {
+        stages,
+        drawable_dots: canvas.drawable_dots().len(),
+        estimated_painting_seconds: estimate_painting_seconds(&canvas),
+    })
+}
+
+/// ドットをキャンバスの解像度の白い画像に黒で描く
+fn render_dots(canvas: &Canvas, resolution: &Resolution) -> RgbaImage {
+    let white = Color::white();
+    let mut image = RgbaImage::new(
+        resolution.width,
+        resolution.height,
+        [white.r, white.g, white.b, white.a].repeat(resolution.total_pixels() as usize),
+    );
+    for (coordinates, _) in canvas.drawable_dots() {
+        let (x, y) = (coordinates.x as u32, coordinates.y as u32);
+        if x < image.width && y < image.height {
+            let index = (y as usize * image.width as usize + x as usize) * 4;
+            image.pixels[index..index + 3].fill(0);
+        }
+    }
+    image
+}
+
+fn encode_png(image: &RgbaImage) -> Result<String, png::EncodingError> {
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut bytes, image.width, image.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&image.pixels)?;
+    }
+    Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+    use crate::interfaces::web::test_support::TestClient;
+    use axum::body::Body;
+    use axum::http::{Request, header};
+
+    /// 左半分が黒、右半分が白の640x240のPNG
+    fn half_black_png() -> Vec<u8> {
+        let (width, height) = (640, 240);
+        let row: Vec<u8> = (0..width)
+            .map(|x| if x < width / 2 { 0 } else { 255 })
+            .collect();
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, width, height);
+            encoder.set_color(png::ColorType::Grayscale);
+            let mut writer = encoder.write_header().unwrap();
+            writer
+                .write_image_data(&row.repeat(height as usize))
+                .unwrap();
+        }
+        bytes
+    }
+
+    /// 20x20のPNG。外周から4画素は完全に透明（色は黒）、その内側の3画素幅は不透明度が
+    /// 64・160・224と上がる灰色（100）の縁、中央の6x6は不透明な黒
+    fn layered_png() -> Vec<u8> {
+        let mut pixels = Vec::new();
+        for y in 0..20u32 {
+            for x in 0..20u32 {
+                let pixel = match layer(x, y) {
+                    0..=3 => [0, 0, 0, 0],
+                    4 => [100, 100, 100, 64],
+                    5 => [100, 100, 100, 160],
+                    6 => [100, 100, 100, 224],
+                    _ => [0, 0, 0, 255],
+                };
+                pixels.extend_from_slice(&pixel);
+            }
+        }
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, 20, 20);
+            encoder.set_color(png::ColorType::Rgba);
+            encoder.set_depth(png::BitDepth::Eight);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&pixels).unwrap();
+        }
+        bytes
+    }
+
+    /// 外周からの距離（`layered_png` の層）
+    fn layer(x: u32, y: u32) -> u32 {
+        x.min(y).min(19 - x).min(19 - y)
+    }
+
+    /// 層ごとのドット数（透明な外周・不透明度64・160・224の縁・不透明な中央）
+    fn dots_per_region(transparency: Transparency) -> [usize; 5] {
+        let image = decode_image(&layered_png(), MAX_PREVIEW_SIDE).unwrap();
+        let params = ConversionParameters::new(ImageFormat::Png, Resolution::splatoon3_standard())
+            .with_transparency(transparency);
+        let stages =
+            ConversionPipeline::run(&image, &params, Instant::now() + PREVIEW_TIME_LIMIT).unwrap();
+        let mut counts = [0; 5];
+        for (coordinates, dot) in stages.canvas.drawable_dots() {
+            let region = match layer(coordinates.x as u32, coordinates.y as u32) {
+                0..=3 => 0,
+                layer @ 4..=6 => layer as usize - 3,
+                _ => 4,
+            };
+            let expected_opacity = [0, 64, 160, 224, 255][region];
+            assert_eq!(dot.opacity, expected_opacity, "{coordinates:?}");
+            counts[region] += 1;
+        }
+        counts
+    }
+
+    #[test]
+    fn test_transparent_border_gradient_edge_and_opaque_core() {
+        let transparency = |mode, cutoff| Transparency::new(mode, cutoff).unwrap();
+        // 外周と下限未満の縁は色に関わらず描画せず、下限以上の灰色の縁はそのまま黒になる
+        assert_eq!(dots_per_region(Transparency::default()), [0, 0, 36, 28, 36]);
+        // 白に重ねると薄い縁は明るくなって消える
+        assert_eq!(
+            dots_per_region(transparency(TransparencyMode::CompositeWhite, 128)),
+            [0, 0, 0, 28, 36]
+        );
+        // 下限を下げれば黒に重ねた薄い縁も描画する
+        assert_eq!(
+            dots_per_region(transparency(TransparencyMode::CompositeBlack, 64)),
+            [0, 44, 36, 28, 36]
+        );
+        assert_eq!(
+            dots_per_region(transparency(TransparencyMode::Skip, 255)),
+            [0, 0, 0, 0, 36]
+        );
+        assert!(Transparency::new(TransparencyMode::Skip, 0).is_err());
+    }
+
+    fn preview(file: &[u8], fields: &[(&str, &str)]) -> Request<Body> {
+        let mut body = Vec::new();
+        for (name, value) in fields {
+            body.extend_from_slice(
+                format!(
+                    "--b\r\nContent-Disposition: form-data; name=\"{name}\"\r\n\r\n{value}\r\n"
+                )
+                .as_bytes(),
+            );
+        }
+        body.extend_from_slice(
+            b"--b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"image.png\"\r\n\
+              Content-Type: image/png\r\n\r\n",
+        );
+        body.extend_from_slice(file);
+        body.extend_from_slice(b"\r\n--b--\r\n");
+        Request::builder()
+            .method("POST")
+            .uri("/api/convert/preview")
+            .header(header::CONTENT_TYPE, "multipart/form-data; boundary=b")
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    fn state() -> Arc<ArtworkState> {
+        Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )))
+    }
+
+    #[tokio::test]
+    async fn test_preview_returns_every_stage_without_creating_an_artwork() {
+        let state = state();
+        let client = TestClient::new(state.clone());
+
+        let response = client.send(preview(&half_black_png(), &[])).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let body = response.json();
+        let stages = body["stages"].as_array().unwrap();
+        let names: Vec<_> = stages.iter().map(|stage| stage["stage"].clone()).collect();
+        assert_eq!(names, ["original", "adjusted", "binarized", "dots"]);
+        for stage in stages {
+            assert_eq!(
+                (&stage["width"], &stage["height"]),
+                (&320.into(), &120.into())
+            );
+            let png = base64::engine::general_purpose::STANDARD
+                .decode(stage["png_base64"].as_str().unwrap())
+                .unwrap();
+            assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        }
+        assert_eq!(body["drawable_dots"], 160 * 120);
+        assert!(body["estimated_painting_seconds"].as_f64().unwrap() > 0.0);
+
+        let artworks = client.get("/api/artworks").await.json();
+        assert_eq!(artworks.as_array().unwrap().len(), 0);
+
+        // 反転すると白かった右半分をドットにする
+        let response = client
+            .send(preview(
+                &half_black_png(),
+                &[
+                    ("invert_background", "true"),
+                    ("adjustments", r#"{"threshold": 10}"#),
+                ],
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        assert_eq!(response.json()["drawable_dots"], 160 * 120);
+    }
+
+    #[tokio::test]
+    async fn test_preview_applies_the_transparency_fields() {
+        let client = TestClient::new(state());
+        let png = layered_png();
+
+        let drawable_dots = |fields: &'static [(&'static str, &'static str)]| {
+            let request = preview(&png, fields);
+            let client = &client;
+            async move {
+                let response = client.send(request).await;
+                assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+                response.json()["drawable_dots"].clone()
+            }
+        };
+        assert_eq!(drawable_dots(&[]).await, 100);
+        assert_eq!(
+            drawable_dots(&[("transparency_mode", "composite_white")]).await,
+            64
+        );
+        assert_eq!(
+            drawable_dots(&[
+                ("transparency_mode", "composite_black"),
+                ("alpha_cutoff", "64")
+            ])
+            .await,
+            144
+        );
+
+        let response = client.send(preview(&png, &[("alpha_cutoff", "0")])).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        let response = client
+            .send(preview(&png, &[("transparency_mode", "opaque")]))
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_preview_rejects_invalid_input_and_concurrent_requests() {
+        let state = state();
+        let client = TestClient::new(state.clone());
+        let image = half_black_png();
+
+        let response = client
+            .send(preview(&image, &[("adjustments", r#"{"gamma": 20.0}"#)]))
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        let response = client
+            .send(preview(&image, &[("tone_mode", "halftone")]))
+            .await;
+        assert_eq!(response.status, StatusCode::BAD_REQUEST);
+        let response = client.send(preview(b"plain text", &[])).await;
+        assert_eq!(response.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        let response = client
+            .send(preview(&vec![0; MAX_PREVIEW_UPLOAD_BYTES + 1], &[]))
+            .await;
+        assert_eq!(response.status, StatusCode::PAYLOAD_TOO_LARGE);
+
+        let _running = state
+            .conversion_previews
+            .clone()
+            .try_acquire_owned()
+            .unwrap();
+        let response = client.send(preview(&image, &[])).await;
+        assert_eq!(response.status, StatusCode::TOO_MANY_REQUESTS);
+    }
+}
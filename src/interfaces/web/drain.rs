@@ -0,0 +1,120 @@
+//! `run --drain` で、描画の予定が無くなったらサーバーを止めて終了するための待機
+//!
+//! 描画の実行中・予約中と、認証を通った変更系APIへのリクエストを「作業」とみなし、
+//! 最後の作業から猶予時間が過ぎたら終了する。猶予中に新しい作業が来ればカウントダウンをやり直す。
+
+use super::state::ArtworkState;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::info;
+
+/// 描画の状態を確認する間隔
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// カウントダウンの残りをログに出す間隔（残りが5秒以下になったら毎秒）
+const COUNTDOWN_LOG_INTERVAL_SECS: u64 = 10;
+
+/// 最後に作業があった時刻
+#[derive(Debug, Clone)]
+pub struct ActivityClock(Arc<Mutex<Instant>>);
+
+impl Default for ActivityClock {
+    fn default() -> Self {
+        Self(Arc::new(Mutex::new(Instant::now())))
+    }
+}
+
+impl ActivityClock {
+    /// 今、作業があったことを記録する
+    pub fn touch(&self) {
+        *self.0.lock().unwrap_or_else(|e| e.into_inner()) = Instant::now();
+    }
+
+    /// 最後の作業からの経過時間
+    pub fn idle_for(&self) -> Duration {
+        self.0.lock().unwrap_or_else(|e| e.into_inner()).elapsed()
+    }
+}
+
+/// 描画の実行中・予約中でない状態が `grace` 続くまで待つ
+///
+/// 描画中や予約がある間は、どれだけ待っても戻らない。
+pub(super) async fn wait_until_drained(state: &ArtworkState, grace: Duration) {
+    let mut logged_remaining: Option<u64> = None;
+    let mut previous_idle = Duration::ZERO;
+    loop {
+        let busy = state.active_painting.read().await.is_some()
+            || state.scheduled_painting.read().await.is_some();
+        if busy {
+            state.activity.touch();
+        }
+        let idle = state.activity.idle_for();
+        if logged_remaining.is_some() && (busy || idle < previous_idle) {
+            info!("New work arrived; drain countdown cancelled");
+            logged_remaining = None;
+        }
+        previous_idle = idle;
+
+        if !busy {
+            let Some(remaining) = grace.checked_sub(idle).filter(|left| !left.is_zero()) else {
+                info!("No painting is active or scheduled; draining the server");
+                return;
+            };
+            let remaining_secs = remaining.as_secs_f64().ceil() as u64;
+            match logged_remaining {
+                None => info!(
+                    "No painting is active or scheduled; exiting in {}s unless new work arrives",
+                    remaining_secs
+                ),
+                Some(logged)
+                    if logged != remaining_secs
+                        && (remaining_secs <= 5
+                            || remaining_secs.is_multiple_of(COUNTDOWN_LOG_INTERVAL_SECS)) =>
+                {
+                    info!("Exiting in {}s", remaining_secs)
+                }
+                Some(_) => {
+                    tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+                    continue;
+                }
+            }
+            logged_remaining = Some(remaining_secs);
+        }
+        tokio::time::sleep(DRAIN_POLL_INTERVAL).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application::use_cases::PaintingControl;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    #[tokio::test]
+    async fn test_drain_waits_for_painting_and_restarts_on_activity() {
+        let state = Arc::new(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )));
+        let grace = Duration::from_millis(300);
+
+        // 描画中は猶予時間を過ぎても終了しない
+        let control = PaintingControl::new(1, 0, 0, 0);
+        state.begin_painting(&control).await.unwrap();
+        let drained = tokio::spawn({
+            let state = state.clone();
+            async move { wait_until_drained(&state, grace).await }
+        });
+        tokio::time::sleep(grace * 3).await;
+        assert!(!drained.is_finished());
+
+        *state.active_painting.write().await = None;
+        let released = Instant::now();
+        // 猶予中の作業でカウントダウンをやり直す
+        tokio::time::sleep(grace / 2).await;
+        state.activity.touch();
+        let touched = Instant::now();
+        drained.await.unwrap();
+        assert!(released.elapsed() >= grace + grace / 2);
+        assert!(touched.elapsed() >= grace);
+    }
+}
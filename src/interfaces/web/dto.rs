@@ -1,15 +1,454 @@
-use crate::domain::painting::value_objects::DrawingStrategy;
+use crate::application::use_cases::run_painting::PreflightPhase;
+use crate::domain::artwork::entities::Canvas;
+use crate::domain::painting::entities::{
+    CompletionReport, PaintingRun, PreflightRejection, RunOutcome,
+};
+use crate::domain::painting::estimate_model::EstimateBucket;
+use crate::domain::painting::value_objects::{
+    DrawingCanvasConfig, DrawingStrategy, PauseMode, PreflightSettings, StopLimits, TwoOptStats,
+};
+use crate::domain::shared::value_objects::Coordinates;
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize, Deserialize)]
+pub use super::error_response::ErrorResponse;
+
+/// 成否とメッセージだけを返す操作の結果
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiResponse {
+    pub success: bool,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct StrategyStats {
     pub strategy: DrawingStrategy,
     pub dpad_operations: usize,
     pub a_button_presses: usize,
+    pub neutral_clears: usize,
+    pub estimated_time_seconds: f64,
+    /// レイヤーごとの内訳（描画順）
+    pub layers: Vec<LayerStats>,
+    /// 2-opt最適化の結果（`GreedyTwoOpt` のみ）
+    pub two_opt: Option<TwoOptStats>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct LayerStats {
+    pub layer: u8,
+    pub dots: usize,
+    pub dpad_operations: usize,
+    pub a_button_presses: usize,
+    pub neutral_clears: usize,
     pub estimated_time_seconds: f64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct StrategyComparisonResponse {
     pub strategies: Vec<StrategyStats>,
+    /// 間引いたサンプルから見積もったか（`true` なら回数と時間は推定値）
+    pub approximate: bool,
+    /// 見積もりに使ったドット数（全ドットで計算した場合は `total_dots` と同じ）
+    pub sample_dots: usize,
+    /// 描画対象のドット数
+    pub total_dots: usize,
+    /// 推定値の誤差の目安（`approximate` の場合のみ）
+    pub accuracy: Option<EstimateAccuracy>,
+}
+
+/// 間引いた見積もりの誤差の目安
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct EstimateAccuracy {
+    /// 間引き方（`row_bands`: 4行の横帯を等間隔に選び、ドット数の比で換算）
+    pub method: String,
+    /// 代表的なアートワークで確認した所要時間の誤差の目安（±%）
+    pub typical_time_error_percent: f64,
+    pub note: String,
+}
+
+impl EstimateAccuracy {
+    /// 所要時間の誤差の目安（`compare_strategies` のテストで確認している上限）
+    pub const ROW_BANDS_TIME_ERROR_PERCENT: f64 = 10.0;
+
+    pub fn row_bands() -> Self {
+        Self {
+            method: "row_bands".to_string(),
+            typical_time_error_percent: Self::ROW_BANDS_TIME_ERROR_PERCENT,
+            note: "Counts and times are extrapolated from evenly spaced 4-row bands. \
+                   Strategy rankings are preserved, but totals can be off when dots are \
+                   concentrated in a few rows, and two_opt stats describe the sample only."
+                .to_string(),
+        }
+    }
+}
+
+/// 描画実行の記録
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PaintingRunResponse {
+    pub id: String,
+    pub artwork_id: String,
+    pub artwork_version: u32,
+    pub strategy: DrawingStrategy,
+    pub press_ms: u32,
+    pub release_ms: u32,
+    pub wait_ms: u32,
+    pub repeats: u32,
+    /// 開始時刻（エポックミリ秒）
+    pub started_at: i64,
+    /// 終了時刻（エポックミリ秒）。実行中は `null`
+    pub finished_at: Option<i64>,
+    pub dots_attempted: usize,
+    pub dots_painted: usize,
+    /// 描画した経路のハッシュ（`GET /api/artworks/{id}/path` の `path_hash` と比較できる）
+    pub path_hash: String,
+    /// 実行中は `null`
+    pub outcome: Option<RunOutcome>,
+    pub dots_per_minute: f64,
+    /// ドットごとの結果の集計（実行中と、集計を記録する前の実行は `null`）
+    pub report: Option<CompletionReport>,
+}
+
+impl From<&PaintingRun> for PaintingRunResponse {
+    fn from(run: &PaintingRun) -> Self {
+        Self {
+            id: run.id.clone(),
+            artwork_id: run.artwork_id.as_str(),
+            artwork_version: run.artwork_version,
+            strategy: run.strategy,
+            press_ms: run.timing.press_ms,
+            release_ms: run.timing.release_ms,
+            wait_ms: run.timing.wait_ms,
+            repeats: run.repeats,
+            started_at: run.started_at.epoch_millis as i64,
+            finished_at: run
+                .finished_at
+                .map(|finished_at| finished_at.epoch_millis as i64),
+            dots_attempted: run.dots_attempted,
+            dots_painted: run.dots_painted,
+            path_hash: run.path_hash.clone(),
+            outcome: run.outcome.clone(),
+            dots_per_minute: run.dots_per_minute(),
+            report: run.report.clone(),
+        }
+    }
+}
+
+/// 見積もりと描画の両方に使われる設定
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PaintingConfigResponse {
+    pub canvas_width: u16,
+    pub canvas_height: u16,
+    pub press_ms: u32,
+    pub release_ms: u32,
+    pub wait_ms: u32,
+    pub repeats: u32,
+    pub diagonal_moves: bool,
+    /// 描画開始前に移動する地点（領域描画時のみ）
+    pub entry_point: Option<Coordinates>,
+    /// アートワークの左上を置いたゲーム内キャンバスの位置（指定時のみ）
+    pub origin: Option<Coordinates>,
+    /// 待機時間の自動調整の下限（無効なら `null`）
+    pub adaptive_min_wait_ms: Option<u32>,
+    /// 待機時間の自動調整の上限（無効なら `null`）
+    pub adaptive_max_wait_ms: Option<u32>,
+    pub pause_mode: PauseMode,
+    /// `safe` の再開時に左上へ戻るか
+    pub rehome_on_resume: bool,
+    /// Switchが応答しなくなってから自動で一時停止するまでの猶予（ミリ秒）
+    pub host_grace_ms: u32,
+    /// 自動一時停止の後、Switchの復帰時に自動で再開するか
+    pub auto_resume: bool,
+    /// 一時停止中にスリープ防止の入力を送るまでの時間（ミリ秒、送らない場合は `null`）
+    pub keepalive_idle_ms: Option<u32>,
+    /// 一時的な送信エラーの場合に1ドットの描画を試す最大回数
+    pub max_dot_attempts: u32,
+    /// 十字キーがニュートラルと分かっているドットで描画前のクリアを省くか
+    pub skip_redundant_neutral_clears: bool,
+    /// 省略する場合もクリアを必ず送る間隔（ドット数、0なら最初のドットだけ）
+    pub neutral_clear_every: u32,
+}
+
+impl From<&DrawingCanvasConfig> for PaintingConfigResponse {
+    fn from(config: &DrawingCanvasConfig) -> Self {
+        Self {
+            canvas_width: config.width,
+            canvas_height: config.height,
+            press_ms: config.timing.press_ms,
+            release_ms: config.timing.release_ms,
+            wait_ms: config.timing.wait_ms,
+            repeats: config.options.repeats,
+            diagonal_moves: config.options.diagonal_moves,
+            entry_point: config.options.entry_point,
+            origin: config.options.origin,
+            adaptive_min_wait_ms: config.options.adaptive.map(|a| a.min_wait_ms),
+            adaptive_max_wait_ms: config.options.adaptive.map(|a| a.max_wait_ms),
+            pause_mode: config.options.pause.mode,
+            rehome_on_resume: config.options.pause.rehome_on_resume,
+            host_grace_ms: config.options.pause.host_grace_ms,
+            auto_resume: config.options.pause.auto_resume,
+            keepalive_idle_ms: config.options.pause.keepalive_idle_ms,
+            max_dot_attempts: config.options.max_dot_attempts,
+            skip_redundant_neutral_clears: config.options.neutral_clear.skip_redundant,
+            neutral_clear_every: config.options.neutral_clear.force_every_dots,
+        }
+    }
+}
+
+/// 描画開始時のレスポンス
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PaintStartResponse {
+    pub success: bool,
+    pub message: String,
+    /// 初期化シーケンスを除いた推定所要時間（秒）
+    pub estimated_time_seconds: f64,
+    /// `estimated_time_seconds` に過去の描画の実測から学習した補正係数を掛けた推定所要時間（秒）
+    pub corrected_estimated_time_seconds: f64,
+    /// 掛けた補正係数（このタイミングの記録が無い場合やシミュレーションでは1.0）
+    pub estimate_correction: f64,
+    /// 描画に使う設定（見積もりと同じ値）
+    pub config: PaintingConfigResponse,
+    /// 描画前に確認が必要な点（見積もり時間が自動スリープの閾値を超えるなど）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// 開始した描画の世代番号（停止・一時停止の `generation` に指定する）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generation: Option<u64>,
+    /// `start_at` を指定した場合の予約（描画はまだ開始していない）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scheduled: Option<ScheduledPaintingStatus>,
+    /// `from_row` より上の行にあり、描き終えたものとして入力を送らずに描画済みにしたドット数
+    #[serde(default)]
+    pub already_complete_dots: usize,
+    /// `trace` を指定した場合の、描画の終了後にタイミングの記録をCSVで取得するURL
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub trace_url: Option<String>,
+}
+
+/// 描画の実測から学習した見積もり時間の補正係数
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EstimateModelResponse {
+    /// 補正係数を学習して見積もりに掛けているか（シミュレーションでは `false`）
+    pub enabled: bool,
+    /// 補正係数の下限
+    pub min_correction: f64,
+    /// 補正係数の上限
+    pub max_correction: f64,
+    /// タイミングごとの補正係数
+    pub buckets: Vec<EstimateBucket>,
+}
+
+/// スティック描画の開始時のレスポンス
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VectorPaintStartResponse {
+    pub success: bool,
+    pub message: String,
+    /// 描く線の数
+    pub strokes: usize,
+    /// 線を引く長さの合計（ピクセル、線の間の移動は含まない）
+    pub length_px: f64,
+    /// 初期化シーケンスを除いた推定所要時間（秒）
+    pub estimated_time_seconds: f64,
+    /// 推定位置と実際のカーソル位置のずれの見込み（ピクセル、線の終点で最大になる）
+    pub max_deviation_px: f64,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// 開始した描画の世代番号（停止の `generation` に指定する）
+    pub generation: u64,
+}
+
+/// 開始を待っている描画の予約
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScheduledPaintingStatus {
+    pub id: String,
+    pub artwork_id: String,
+    /// 差分描画の元のアートワークのID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_artwork_id: Option<String>,
+    /// 開始する時刻（RFC 3339）
+    pub start_at: String,
+    /// 開始までの残り秒数
+    pub seconds_remaining: f64,
+}
+
+/// 実行中の描画の状態
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PaintingStatus {
+    /// 描画またはキャリブレーションを実行中か
+    pub active: bool,
+    pub paused: bool,
+    /// 描画済みのドット数
+    pub painted: usize,
+    /// 現在の設定（実行中に変更したタイミング・繰り返し回数を反映、描画中以外は `null`）
+    pub config: Option<PaintingConfigResponse>,
+    /// 直前に終了した描画の記録（次の描画を開始するまで返す）
+    pub last_run: Option<PaintingRunResponse>,
+    /// 実行中の描画の世代番号（描画中以外は `null`）
+    pub generation: Option<u64>,
+    /// 開始を待っている描画の予約（無ければ `null`）
+    pub scheduled: Option<ScheduledPaintingStatus>,
+    /// 実行中の描画を切り上げる上限と、上限までの残り（`stop_after` を指定していなければ `null`）
+    pub stop_after: Option<StopAfterStatus>,
+    /// 描画前の確認の状態（確認しない描画や描画中以外は `null`）
+    pub preflight: Option<PreflightStatus>,
+    /// 描画中のアートワークのID（キャリブレーションなど、アートワークを描いていなければ `null`）
+    pub artwork_id: Option<String>,
+    /// この描画で描くドット数（描画中以外は `null`）
+    pub total_dots: Option<usize>,
+    /// 描画が終わるまでの推定時間（秒、それまでの速さから計算。描画中以外は `null`）
+    pub eta_seconds: Option<f64>,
+    /// `?thumbnail=true` の場合の、描画中のアートワークの縮小図
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thumbnail: Option<GalleryThumbnail>,
+}
+
+/// 描画前の確認の状態
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PreflightStatus {
+    /// `drawing_mark`・`awaiting_confirmation`・`confirmed`・`declined`・`timed_out`
+    pub state: String,
+    /// 回答の期限（RFC3339、回答待ちの間のみ）
+    pub deadline: Option<String>,
+    /// 期限までの秒数（回答待ちの間のみ）
+    pub seconds_remaining: Option<u64>,
+    /// 確認用の印のドット（ゲーム内キャンバスの座標）
+    pub mark: Vec<Coordinates>,
+}
+
+impl PreflightStatus {
+    pub fn new(phase: PreflightPhase, now: DateTime<Utc>) -> Self {
+        let (state, deadline) = match phase {
+            PreflightPhase::DrawingMark => ("drawing_mark", None),
+            PreflightPhase::AwaitingConfirmation { deadline } => {
+                ("awaiting_confirmation", Some(deadline))
+            }
+            PreflightPhase::Confirmed => ("confirmed", None),
+            PreflightPhase::Rejected(PreflightRejection::Declined) => ("declined", None),
+            PreflightPhase::Rejected(PreflightRejection::TimedOut) => ("timed_out", None),
+        };
+        Self {
+            state: state.to_string(),
+            deadline: deadline.map(|deadline| deadline.to_rfc3339()),
+            seconds_remaining: deadline
+                .map(|deadline| (deadline - now).num_seconds().max(0) as u64),
+            mark: PreflightSettings::mark().to_vec(),
+        }
+    }
+}
+
+/// `stop_after` の上限と残り
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct StopAfterStatus {
+    pub limits: StopLimits,
+    /// 上限に達するまでに描けるドット数（ドット数・完成度の上限が無ければ `null`）
+    pub remaining_dots: Option<usize>,
+    /// 上限に達するまでの秒数（時間の上限が無ければ `null`）
+    pub remaining_seconds: Option<u64>,
+}
+
+impl StopAfterStatus {
+    /// この描画で `painted` ドットを描き、`elapsed` 経過した時点の残り
+    pub fn new(limits: StopLimits, painted: usize, elapsed: std::time::Duration) -> Self {
+        Self {
+            limits,
+            remaining_dots: limits.remaining_dots(painted),
+            remaining_seconds: limits.remaining_seconds(elapsed),
+        }
+    }
+}
+
+/// 停止・一時停止のレスポンス
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct PaintingSignalResponse {
+    /// 実行中の描画に指示が届いたか（描画中でなければ `false`）
+    pub success: bool,
+    pub message: String,
+    /// 指示を送った描画の世代番号
+    pub generation: Option<u64>,
+    /// 描画スレッドが指示を受け取ったことを確認できたか
+    ///
+    /// 停止では描画スレッドの終了、一時停止・再開では待機の開始・終了を確認する。
+    /// `false` でも指示は届いており、ドットの区切りなどで後から反映される。
+    pub acknowledged: bool,
+    /// 変更後の一時停止の状態（停止では `null`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused: Option<bool>,
+}
+
+/// ギャラリーで公開する描画の進捗（操作に使える情報は含めない）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GalleryState {
+    /// 描画中か（描画中でなければ直前に描画したアートワークを返す）
+    pub active: bool,
+    pub paused: bool,
+    pub artwork_name: Option<String>,
+    pub thumbnail: Option<GalleryThumbnail>,
+    /// アートワーク全体の描画済みの割合（0.0〜1.0、描画中のドットも含む）
+    pub completion_ratio: f32,
+    /// 現在のカーソル位置（描画中以外は `null`）
+    pub cursor: Option<Coordinates>,
+    /// 描画が終わるまでの推定時間（秒、描画中にそれまでの速さから計算）
+    pub eta_seconds: Option<f64>,
+    /// 最近終了した描画（新しい順）
+    pub recent_completions: Vec<GalleryCompletion>,
+}
+
+/// 縮小したキャンバスの描画状況
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct GalleryThumbnail {
+    pub width: u16,
+    pub height: u16,
+    /// 1セルにまとめたキャンバスのドット数（縦横それぞれ）
+    pub scale: u16,
+    /// 上から順の各行。`.` は描くドットが無い、`o` は未描画、`#` は描画済みのセル
+    pub rows: Vec<String>,
+}
+
+/// サムネイルの幅の上限（セル）
+const GALLERY_THUMBNAIL_MAX_WIDTH: u16 = 160;
+
+impl From<&Canvas> for GalleryThumbnail {
+    fn from(canvas: &Canvas) -> Self {
+        let scale = canvas.width.div_ceil(GALLERY_THUMBNAIL_MAX_WIDTH).max(1);
+        let width = canvas.width.div_ceil(scale);
+        let height = canvas.height.div_ceil(scale);
+        // セルごとに（描くドットの数, 描画済みの数）を数え、未描画が残るセルは `o` にする
+        let mut cells = vec![(0u32, 0u32); width as usize * height as usize];
+        for (coords, dot) in canvas.dots.iter().filter(|(_, dot)| dot.opacity > 0) {
+            let cell = &mut cells
+                [(coords.y / scale) as usize * width as usize + (coords.x / scale) as usize];
+            cell.0 += 1;
+            cell.1 += dot.is_painted as u32;
+        }
+        let rows = cells
+            .chunks(width as usize)
+            .map(|row| {
+                row.iter()
+                    .map(|&(dots, painted)| match dots {
+                        0 => '.',
+                        _ if painted == dots => '#',
+                        _ => 'o',
+                    })
+                    .collect()
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            scale,
+            rows,
+        }
+    }
+}
+
+/// ギャラリーで公開する終了した描画
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct GalleryCompletion {
+    /// 削除されたアートワークは `null`
+    pub artwork_name: Option<String>,
+    /// 終了理由（`completed`、`completed_with_errors`、`stopped`、`limit_reached`、`error`。エラーの内容は含めない）
+    pub outcome: String,
+    pub dots_painted: usize,
+    /// 終了時刻（エポックミリ秒）
+    pub finished_at: i64,
 }
@@ -1,4 +1,9 @@
 use rust_embed::Embed;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::OnceLock;
+use thiserror::Error;
 
 /// WebUIの静的アセットを埋め込む
 #[derive(Embed)]
@@ -7,10 +12,155 @@ use rust_embed::Embed;
 #[include = "**/*"]
 pub struct WebAssets;
 
+/// WebUIのアセットの提供元
+///
+/// 外部ディレクトリを指定すると、そこにあるファイルを優先し、ないファイルは埋め込みアセットを使う。
+/// フロントエンドの修正をRustの再ビルドなしで確認するための開発向けの機能。
+#[derive(Debug, Clone, Default)]
+pub struct WebAssetSource {
+    dir: Option<PathBuf>,
+}
+
+/// 読み込んだアセット
+#[derive(Debug, Clone)]
+pub struct Asset {
+    pub data: Cow<'static, [u8]>,
+    /// 外部ディレクトリから読み込んだか（キャッシュさせない）
+    pub overridden: bool,
+}
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum AssetPathError {
+    #[error("Path '{0}' is outside the assets directory")]
+    OutsideAssetsDir(String),
+}
+
+impl WebAssetSource {
+    /// 埋め込みアセットのみを使う
+    pub fn embedded() -> Self {
+        Self::default()
+    }
+
+    /// `dir` のファイルで埋め込みアセットを上書きする
+    pub fn with_dir(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: Some(dir.into()),
+        }
+    }
+
+    pub fn dir(&self) -> Option<&Path> {
+        self.dir.as_deref()
+    }
+
+    /// `path`（先頭の `/` を除いたURLパス）のアセットを読み込む
+    ///
+    /// 外部ディレクトリの外を指すパス（`..` やディレクトリ外へのシンボリックリンク）はエラーにする。
+    pub fn load(&self, path: &str) -> Result<Option<Asset>, AssetPathError> {
+        if let Some(dir) = &self.dir
+            && let Some(data) = read_from_dir(dir, path)?
+        {
+            return Ok(Some(Asset {
+                data: Cow::Owned(data),
+                overridden: true,
+            }));
+        }
+        Ok(WebAssets::get(path).map(|file| Asset {
+            data: file.data,
+            overridden: false,
+        }))
+    }
+
+    /// 実際に配信されるアセット全体のフィンガープリント
+    ///
+    /// フロントエンドはこの値の変化でバックエンドの更新を検知し、再読み込みを促す。
+    pub fn fingerprint(&self) -> String {
+        let Some(dir) = &self.dir else {
+            return embedded_fingerprint().clone();
+        };
+        let mut files = embedded_file_hashes();
+        let mut overrides = Vec::new();
+        collect_files(dir, dir, &mut overrides);
+        for (path, file) in overrides {
+            if let Ok(data) = std::fs::read(file) {
+                files.insert(path, xxhash_rust::xxh3::xxh3_64(&data));
+            }
+        }
+        fingerprint_of(&files)
+    }
+}
+
+/// 外部ディレクトリからファイルを読む（存在しなければ `None`）
+fn read_from_dir(dir: &Path, path: &str) -> Result<Option<Vec<u8>>, AssetPathError> {
+    let outside = || AssetPathError::OutsideAssetsDir(path.to_string());
+    if !Path::new(path)
+        .components()
+        .all(|component| matches!(component, Component::Normal(_)))
+    {
+        return Err(outside());
+    }
+    let (Ok(root), Ok(file)) = (dir.canonicalize(), dir.join(path).canonicalize()) else {
+        return Ok(None);
+    };
+    if !file.starts_with(&root) {
+        return Err(outside());
+    }
+    if !file.is_file() {
+        return Ok(None);
+    }
+    Ok(std::fs::read(file).ok())
+}
+
+/// `dir` 以下のファイルを `root` からの相対パス（`/` 区切り）とともに集める
+fn collect_files(root: &Path, dir: &Path, files: &mut Vec<(String, PathBuf)>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_files(root, &path, files);
+        } else if let Ok(relative) = path.strip_prefix(root) {
+            let components: Vec<_> = relative
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy())
+                .collect();
+            files.push((components.join("/"), path));
+        }
+    }
+}
+
+fn embedded_file_hashes() -> BTreeMap<String, u64> {
+    WebAssets::iter()
+        .filter_map(|path| {
+            let file = WebAssets::get(&path)?;
+            Some((path.into_owned(), xxhash_rust::xxh3::xxh3_64(&file.data)))
+        })
+        .collect()
+}
+
+fn embedded_fingerprint() -> &'static String {
+    static FINGERPRINT: OnceLock<String> = OnceLock::new();
+    FINGERPRINT.get_or_init(|| fingerprint_of(&embedded_file_hashes()))
+}
+
+fn fingerprint_of(files: &BTreeMap<String, u64>) -> String {
+    let mut bytes = Vec::new();
+    for (path, hash) in files {
+        bytes.extend_from_slice(path.as_bytes());
+        bytes.push(0);
+        bytes.extend_from_slice(&hash.to_le_bytes());
+    }
+    format!("{:016x}", xxhash_rust::xxh3::xxh3_64(&bytes))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn temp_assets_dir() -> PathBuf {
+        std::env::temp_dir().join(format!("ghost-drawer-assets-{}", uuid::Uuid::new_v4()))
+    }
+
     #[test]
     fn test_embedded_assets_available() {
         // index.htmlが埋め込まれていることを確認
@@ -25,4 +175,49 @@ mod tests {
         let js = WebAssets::get("js/app.js");
         assert!(js.is_some());
     }
+
+    #[test]
+    fn test_assets_dir_overrides_and_falls_back_to_embedded() {
+        let dir = temp_assets_dir();
+        std::fs::create_dir_all(dir.join("css")).unwrap();
+        std::fs::write(dir.join("css/style.css"), "body {}").unwrap();
+
+        let embedded = WebAssetSource::embedded();
+        let source = WebAssetSource::with_dir(&dir);
+
+        let css = source.load("css/style.css").unwrap().unwrap();
+        assert!(css.overridden);
+        assert_eq!(css.data.as_ref(), b"body {}");
+
+        let js = source.load("js/app.js").unwrap().unwrap();
+        assert!(!js.overridden);
+        assert!(source.load("missing.txt").unwrap().is_none());
+
+        assert_ne!(source.fingerprint(), embedded.fingerprint());
+        assert_eq!(embedded.fingerprint(), embedded.fingerprint());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_assets_dir_rejects_paths_outside_the_directory() {
+        let parent = temp_assets_dir();
+        let dir = parent.join("web");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(parent.join("secret.txt"), "secret").unwrap();
+        let source = WebAssetSource::with_dir(&dir);
+
+        for path in ["../secret.txt", "css/../../secret.txt", "/etc/passwd"] {
+            assert_eq!(
+                source.load(path).unwrap_err(),
+                AssetPathError::OutsideAssetsDir(path.to_string())
+            );
+        }
+
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(parent.join("secret.txt"), dir.join("link.txt")).unwrap();
+            assert!(source.load("link.txt").is_err());
+        }
+        std::fs::remove_dir_all(&parent).unwrap();
+    }
 }
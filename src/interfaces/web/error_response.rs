@@ -1,15 +1,27 @@
+use super::state::ArtworkState;
+use crate::domain::artwork::repositories::RepositoryError;
+use crate::domain::shared::i18n::{Locale, Message};
 use axum::{
     Json,
-    http::StatusCode,
+    extract::{Request, State},
+    http::{HeaderMap, StatusCode, header},
+    middleware::Next,
     response::{IntoResponse, Response},
 };
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tracing::error;
+use utoipa::ToSchema;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ErrorResponse {
     pub error: String,
+    /// エラーの内容（翻訳のある文言は `Accept-Language` かサーバーの既定の言語）
     pub message: String,
     pub status_code: u16,
+    /// 翻訳できる文言（応答を返す直前に `localize_errors` が言語を決めて組み立てる）
+    #[serde(skip)]
+    localized: Option<Message>,
 }
 
 impl ErrorResponse {
@@ -21,15 +33,79 @@ impl ErrorResponse {
                 .to_string(),
             message: message.into(),
             status_code: status_code.as_u16(),
+            localized: None,
         }
     }
+
+    /// 翻訳できる文言のエラー
+    pub fn localized(status_code: StatusCode, message: Message) -> Self {
+        Self {
+            localized: Some(message.clone()),
+            ..Self::new(status_code, message.render(Locale::default()))
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+}
+
+impl From<RepositoryError> for ErrorResponse {
+    fn from(error: RepositoryError) -> Self {
+        let status_code = match &error {
+            RepositoryError::NotFound { .. } => StatusCode::NOT_FOUND,
+            RepositoryError::AlreadyExists { .. } | RepositoryError::ConcurrentModification => {
+                StatusCode::CONFLICT
+            }
+            RepositoryError::ValidationError { .. } | RepositoryError::InvalidQuery { .. } => {
+                StatusCode::UNPROCESSABLE_ENTITY
+            }
+            _ => {
+                error!("Artwork storage error: {}", error);
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+        };
+        Self::new(status_code, error.to_string())
+    }
 }
 
 impl IntoResponse for ErrorResponse {
-    fn into_response(self) -> Response {
-        let status_code =
-            StatusCode::from_u16(self.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+    fn into_response(mut self) -> Response {
+        let localized = self.localized.take();
+        let mut response = (self.status(), Json(self)).into_response();
+        if let Some(message) = localized {
+            response.extensions_mut().insert(message);
+        }
+        response
+    }
+}
 
-        (status_code, Json(self)).into_response()
+/// リクエストの文言の言語（`Accept-Language` で選ばれなければ `fallback`）
+pub fn request_locale(headers: &HeaderMap, fallback: Locale) -> Locale {
+    headers
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(Locale::from_accept_language)
+        .unwrap_or(fallback)
+}
+
+/// 翻訳できる文言のエラーを、リクエストの言語で組み立て直すミドルウェア
+pub async fn localize_errors(
+    State(state): State<Arc<ArtworkState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let locale = request_locale(request.headers(), state.locale);
+    let response = next.run(request).await;
+    let Some(message) = response.extensions().get::<Message>().cloned() else {
+        return response;
+    };
+    let status = response.status();
+    let mut localized = ErrorResponse::new(status, message.render(locale)).into_response();
+    for (name, value) in response.headers() {
+        if name != header::CONTENT_LENGTH {
+            localized.headers_mut().insert(name, value.clone());
+        }
     }
+    localized
 }
@@ -0,0 +1,99 @@
+use crate::domain::artwork::entities::Artwork;
+use axum::{
+    Json,
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+/// 条件付きリクエスト用のエンティティタグ
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag(String);
+
+impl ETag {
+    /// アートワーク単体のETag（IDとバージョンから生成）
+    pub fn for_artwork(artwork: &Artwork) -> Self {
+        Self(format!("\"{}-v{}\"", artwork.id, artwork.version))
+    }
+
+    /// アートワーク一覧のETag（全IDとバージョンのハッシュ）
+    pub fn for_artworks<'a>(artworks: impl IntoIterator<Item = &'a Artwork>) -> Self {
+        let mut entries: Vec<(String, u32)> = artworks
+            .into_iter()
+            .map(|artwork| (artwork.id.as_str(), artwork.version))
+            .collect();
+        entries.sort();
+
+        let mut hasher = DefaultHasher::new();
+        entries.hash(&mut hasher);
+        Self(format!("\"list-{:016x}\"", hasher.finish()))
+    }
+
+    /// 同じリソースの別の表現のETag（`"id-v3"` → `"id-v3-compact"`）
+    pub fn variant(self, name: &str) -> Self {
+        Self(format!("{}-{name}\"", self.0.trim_end_matches('"')))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// `If-None-Match` ヘッダーがこのETagに一致するか（弱い比較）
+    pub fn matches(&self, headers: &HeaderMap) -> bool {
+        headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(str::trim)
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == self.0)
+    }
+}
+
+/// ETagが一致すれば304を、そうでなければJSON本体を返す
+///
+/// どちらの場合も `Cache-Control: no-cache` を付け、クライアントに毎回再検証させる。
+/// 本体の生成は一致しなかった場合のみ行う。
+pub fn conditional_json<T: Serialize>(
+    headers: &HeaderMap,
+    etag: ETag,
+    body: impl FnOnce() -> T,
+) -> Response {
+    let mut response = if etag.matches(headers) {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        Json(body()).into_response()
+    };
+
+    let response_headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(etag.as_str()) {
+        response_headers.insert(header::ETAG, value);
+    }
+    response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers_with(value: &'static str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static(value));
+        headers
+    }
+
+    #[test]
+    fn test_etag_matching() {
+        let etag = ETag("\"abc-v1\"".to_string());
+
+        assert!(etag.matches(&headers_with("\"abc-v1\"")));
+        assert!(etag.matches(&headers_with("W/\"abc-v1\"")));
+        assert!(etag.matches(&headers_with("\"other\", \"abc-v1\"")));
+        assert!(etag.matches(&headers_with("*")));
+        assert!(!etag.matches(&headers_with("\"abc-v2\"")));
+        assert!(!etag.matches(&HeaderMap::new()));
+    }
+}
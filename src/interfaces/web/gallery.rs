@@ -0,0 +1,244 @@
+//! 配信の視聴者向けに、描画の進捗だけを公開する読み取り専用のギャラリー
+//!
+//! 認証無しで見られるため、公開する項目はここで選んだものに限る（操作やログ、エラーの内容は含めない）。
+
+use super::dto::{GalleryCompletion, GalleryState, GalleryThumbnail};
+use super::error_response::ErrorResponse;
+use super::state::ArtworkState;
+use crate::domain::shared::i18n::{Message, MessageKey};
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// ギャラリーに表示する最近終了した描画の件数
+const RECENT_COMPLETIONS: usize = 5;
+
+/// ギャラリーのWebSocketに進捗を送る間隔（この間の進捗は最新の1件にまとめる）
+pub const GALLERY_PROGRESS_INTERVAL: Duration = Duration::from_millis(500);
+
+/// ギャラリーモードの有効・無効（再起動せずに切り替えられる）
+#[derive(Debug, Clone)]
+pub struct GalleryMode {
+    enabled: Arc<AtomicBool>,
+}
+
+impl GalleryMode {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: Arc::new(AtomicBool::new(enabled)),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::SeqCst)
+    }
+
+    /// 有効・無効を切り替え、変更前の状態を返す
+    pub fn set(&self, enabled: bool) -> bool {
+        self.enabled.swap(enabled, Ordering::SeqCst)
+    }
+
+    /// 無効にされていれば404を返す（ギャラリーの存在自体を知らせない）
+    pub fn ensure_enabled(&self) -> Result<(), ErrorResponse> {
+        if self.is_enabled() {
+            Ok(())
+        } else {
+            Err(ErrorResponse::localized(
+                axum::http::StatusCode::NOT_FOUND,
+                Message::new(MessageKey::GalleryDisabled),
+            ))
+        }
+    }
+}
+
+impl Default for GalleryMode {
+    fn default() -> Self {
+        Self::new(true)
+    }
+}
+
+/// 進捗チャンネルのメッセージをギャラリーに送るかどうか
+#[derive(Debug, PartialEq)]
+pub enum GalleryUpdate {
+    /// カーソルの移動（間引いて最新のものだけを送る）
+    Progress(String),
+    /// 一時停止や終了などの出来事（すぐに送る）
+    Event(String),
+}
+
+/// 進捗チャンネルのメッセージから、ギャラリーに公開する項目だけを取り出す
+///
+/// 統計やキャリブレーション、接続修正などのメッセージは `None`。
+pub fn gallery_update(message: &str) -> Option<GalleryUpdate> {
+    let message: Value = serde_json::from_str(message).ok()?;
+    match message["type"].as_str()? {
+        // 状態の文字列だけのメッセージ（初期化中など）は座標を持たない
+        "progress" if message["current"].is_u64() => Some(GalleryUpdate::Progress(
+            json!({
+                "type": "progress",
+                "current": message["current"],
+                "total": message["total"],
+                "x": message["x"],
+                "y": message["y"]
+            })
+            .to_string(),
+        )),
+        "paused_at" => Some(GalleryUpdate::Event(
+            json!({
+                "type": "paused",
+                "x": message["x"],
+                "y": message["y"]
+            })
+            .to_string(),
+        )),
+        "layer_complete" => Some(GalleryUpdate::Event(
+            json!({
+                "type": "layer_complete",
+                "layer_index": message["layer_index"],
+                "layer_count": message["layer_count"]
+            })
+            .to_string(),
+        )),
+        "completion_report" => Some(GalleryUpdate::Event(
+            json!({
+                "type": "completion",
+                "outcome": message["outcome"]["kind"],
+                "succeeded": message["report"]["succeeded"],
+                "total_dots": message["report"]["total_dots"]
+            })
+            .to_string(),
+        )),
+        _ => None,
+    }
+}
+
+/// 実行中（無ければ直前）の描画の公開用の状態を作成する
+pub async fn gallery_state(state: &ArtworkState) -> Result<GalleryState, ErrorResponse> {
+    let recent_runs = state.runs.recent(RECENT_COMPLETIONS + 1);
+    let mut recent_completions = Vec::new();
+    for run in recent_runs.iter().filter(|run| run.is_finished()) {
+        let Some(outcome) = &run.outcome else {
+            continue;
+        };
+        recent_completions.push(GalleryCompletion {
+            artwork_name: state
+                .find_artwork(&run.artwork_id.as_str())
+                .await?
+                .map(|artwork| artwork.metadata.name),
+            outcome: outcome.kind().to_string(),
+            dots_painted: run.dots_painted,
+            finished_at: run.finished_at.map_or(0, |at| at.epoch_millis as i64),
+        });
+    }
+    recent_completions.truncate(RECENT_COMPLETIONS);
+
+    // キャリブレーションなど、アートワークを描いていない実行は公開しない
+    let active = state
+        .active_painting
+        .read()
+        .await
+        .clone()
+        .and_then(|control| {
+            let artwork_id = control.event_log.as_ref()?.artwork_id.clone();
+            Some((control, artwork_id))
+        });
+    let Some((control, artwork_id)) = active else {
+        let artwork = match recent_runs.first() {
+            Some(run) => state.find_artwork(&run.artwork_id.as_str()).await?,
+            None => None,
+        };
+        return Ok(GalleryState {
+            active: false,
+            paused: false,
+            artwork_name: artwork.as_ref().map(|a| a.metadata.name.clone()),
+            thumbnail: artwork.as_ref().map(|a| GalleryThumbnail::from(&a.canvas)),
+            completion_ratio: artwork
+                .as_ref()
+                .map_or(0.0, |a| a.completion_ratio() as f32),
+            cursor: None,
+            eta_seconds: None,
+            recent_completions,
+        });
+    };
+
+    let artwork = state.artwork_in_progress(&control, &artwork_id).await?;
+    let eta_seconds = control
+        .completion
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .report()
+        .eta_seconds();
+
+    Ok(GalleryState {
+        active: true,
+        paused: control.pause_signal.load(Ordering::SeqCst),
+        artwork_name: artwork.as_ref().map(|a| a.metadata.name.clone()),
+        thumbnail: artwork.as_ref().map(|a| GalleryThumbnail::from(&a.canvas)),
+        completion_ratio: artwork
+            .as_ref()
+            .map_or(0.0, |a| a.completion_ratio() as f32),
+        // サムネイルはアートワークの座標なので、配置位置の分を戻す
+        cursor: control.cursor().and_then(|cursor| match &control.config {
+            Some(config) => config.options.artwork_coordinates(cursor),
+            None => Some(cursor),
+        }),
+        eta_seconds,
+        recent_completions,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gallery_update_exposes_only_public_fields() {
+        let progress = r#"{"type":"progress","current":3,"total":10,"x":4,"y":2,"dpad_operations":7,"a_button_presses":6,"is_paint":true}"#;
+        assert_eq!(
+            gallery_update(progress),
+            Some(GalleryUpdate::Progress(
+                r#"{"current":3,"total":10,"type":"progress","x":4,"y":2}"#.to_string()
+            ))
+        );
+
+        let report = json!({
+            "type": "completion_report",
+            "outcome": { "kind": "error", "message": "/dev/hidg0: Broken pipe" },
+            "report": { "succeeded": 8, "total_dots": 10, "skipped_dots": [{ "x": 1, "y": 1, "error": "EPIPE" }] }
+        })
+        .to_string();
+        let Some(GalleryUpdate::Event(event)) = gallery_update(&report) else {
+            panic!("completion report was not forwarded");
+        };
+        assert!(
+            !event.contains("Broken pipe") && !event.contains("EPIPE"),
+            "{event}"
+        );
+        assert!(event.contains(r#""outcome":"error""#), "{event}");
+
+        for private in [
+            r#"{"type":"progress","status_message":"初期化中"}"#,
+            r#"{"type":"stats","press_ms":100}"#,
+            r#"{"type":"calibration_complete","status":"success"}"#,
+            r#"{"type":"fix_connection","step":"reload_gadget"}"#,
+            "not json",
+        ] {
+            assert_eq!(gallery_update(private), None, "{private}");
+        }
+    }
+
+    #[test]
+    fn test_gallery_mode_toggles_without_restart() {
+        let mode = GalleryMode::default();
+        let shared = mode.clone();
+        assert!(mode.ensure_enabled().is_ok());
+        assert!(shared.set(false));
+        assert_eq!(
+            mode.ensure_enabled().unwrap_err().status(),
+            axum::http::StatusCode::NOT_FOUND
+        );
+        assert!(!mode.set(true));
+        assert!(shared.is_enabled());
+    }
+}
@@ -1,30 +1,125 @@
-use super::artwork_handlers::ArtworkState;
-use super::log_streamer::stream_logs;
-use super::models::{HardwareDetails, HardwareStatus, SystemInfo};
+use super::artworks::discard_artwork;
+use super::auth::{AuthToken, SESSION_COOKIE, SESSION_MAX_AGE_SECS};
+use super::connection_monitor::probe_connection;
+use super::dto::{ApiResponse, GalleryState};
+use super::error_response::{ErrorResponse, request_locale};
+use super::gallery::gallery_state;
+use super::log_streamer::{PROGRESS_CHANNEL, stream_gallery, stream_logs};
+use super::models::{
+    AuditLogQuery, AuditLogResponse, FixConnectionStartResponse, GalleryModeRequest,
+    GalleryModeResponse, HardwareDetails, HardwareStatus, LogLevelRequest, LogLevelResponse,
+    LoginRequest, MAX_AUDIT_LIMIT, MAX_RECONNECT_TIMEOUT_MS, ReconnectGadgetQuery,
+    ReconnectGadgetResponse, ResetDataRequest, ResetDataResponse, SystemInfo, VersionInfo,
+    WebhookTestResponse,
+};
+use super::scheduled_painting::cancel_schedule;
+use super::state::{ArtworkState, ConnectionFixSession, ControllerMode, release_active_painting};
+use super::webhooks::WebhookPayload;
+use crate::application::controller_io::run_controller_io;
+use crate::application::metrics::MetricsReport;
+use crate::application::use_cases::{
+    BenchmarkReport, FixConnectionEvent, FixConnectionUseCase, PaintingControl,
+    RESET_DATA_CONFIRMATION,
+};
+use crate::domain::artwork::entities::ArtworkId;
+use crate::domain::controller::ControllerEmulator;
+use crate::domain::events::ArtworkEvent;
+use crate::domain::hardware::GadgetState;
+use crate::domain::setup::entities::{AuditInitiator, FixConnectionOutcome, FixConnectionStep};
+use crate::domain::shared::events::EventMetadata;
+use crate::domain::shared::i18n::{Message, MessageKey};
 use axum::{
     Json,
-    extract::{State, ws::WebSocketUpgrade},
-    response::Response,
+    extract::{
+        Query, State,
+        ws::{WebSocketUpgrade, rejection::WebSocketUpgradeRejection},
+    },
+    http::{HeaderMap, StatusCode, header},
+    response::{IntoResponse, Response},
 };
+use serde_json::json;
 use std::path::Path;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{error, info, warn};
 
 /// Get system information
-pub async fn get_system_info() -> Json<SystemInfo> {
+#[utoipa::path(
+    get, path = "/api/system/info", tag = "system",
+    responses((status = 200, body = SystemInfo))
+)]
+pub async fn get_system_info(State(state): State<Arc<ArtworkState>>) -> Json<SystemInfo> {
+    let (simulation, strict_simulation) = match state.controller_mode {
+        ControllerMode::Hardware => (false, false),
+        ControllerMode::Simulated { strict } => (true, strict),
+    };
+
+    let benchmark = match &state.benchmark {
+        Some(benchmark) => {
+            let benchmark = benchmark.clone();
+            tokio::task::spawn_blocking(move || benchmark.latest())
+                .await
+                .ok()
+                .and_then(|latest| {
+                    latest
+                        .inspect_err(|e| warn!("Ignoring the latest benchmark: {}", e))
+                        .ok()
+                })
+                .flatten()
+        }
+        None => None,
+    };
+
     Json(SystemInfo {
         version: env!("CARGO_PKG_VERSION").to_string(),
         rust_version: "1.85.0".to_string(), // Since CARGO_PKG_RUST_VERSION is not available
         os: std::env::consts::OS.to_string(),
         arch: std::env::consts::ARCH.to_string(),
         uptime_seconds: get_system_uptime(),
+        simulation,
+        strict_simulation,
+        safe_mode: state.safe_mode,
+        benchmark,
+    })
+}
+
+/// Get backend and web UI version
+#[utoipa::path(
+    get, path = "/api/version", tag = "system",
+    responses((status = 200, body = VersionInfo))
+)]
+pub async fn get_version(State(state): State<Arc<ArtworkState>>) -> Json<VersionInfo> {
+    // 外部ディレクトリを使う場合はファイルを読むのでブロッキングスレッドで計算する
+    let assets = state.assets.clone();
+    let assets_overridden = assets.dir().is_some();
+    let assets_fingerprint = tokio::task::spawn_blocking(move || assets.fingerprint())
+        .await
+        .unwrap_or_else(|_| "unknown".to_string());
+
+    Json(VersionInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_hash: env!("GIT_HASH").to_string(),
+        build_timestamp: env!("BUILD_TIMESTAMP").to_string(),
+        assets_fingerprint,
+        assets_overridden,
     })
 }
 
 /// Get hardware status
+#[utoipa::path(
+    get, path = "/api/hardware/status", tag = "system",
+    responses((status = 200, body = HardwareStatus))
+)]
 pub async fn get_hardware_status(State(state): State<Arc<ArtworkState>>) -> Json<HardwareStatus> {
     // Use the controller abstraction to check connection status
     // This allows MockController to report "connected" even if physical hardware is missing
-    let nintendo_switch_connected = state.controller.is_connected().unwrap_or(false);
+    let controller = state.controller.clone();
+    let nintendo_switch_connected = run_controller_io(move || controller.is_connected())
+        .await
+        .ok()
+        .and_then(Result::ok)
+        .unwrap_or(false);
 
     let usb_otg_available = check_usb_otg_availability();
     let hid_device_available = check_hid_device_availability();
@@ -38,9 +133,778 @@ pub async fn get_hardware_status(State(state): State<Arc<ArtworkState>>) -> Json
     })
 }
 
+/// Start the connection fix wizard
+///
+/// 手順は1つずつバックグラウンドで実行され、開始・結果が `fix_connection` メッセージで通知される。
+#[utoipa::path(
+    post, path = "/api/system/fix-connection/start", tag = "system",
+    responses(
+        (status = 202, body = FixConnectionStartResponse),
+        (status = 409, description = "描画中または接続修正の実行中", body = ErrorResponse),
+        (status = 503, description = "この環境では接続修正を利用できない", body = ErrorResponse)
+    )
+)]
+pub async fn start_fix_connection(
+    State(state): State<Arc<ArtworkState>>,
+) -> Result<(StatusCode, Json<FixConnectionStartResponse>), ErrorResponse> {
+    let Some(repairer) = state.connection_repairer.clone() else {
+        return Err(ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Connection fix is not available in this environment",
+        ));
+    };
+
+    // 描画中の確認とセッション登録の間に描画が始まらないよう、先に描画のロックを取る
+    let active_painting = state.active_painting.read().await;
+    if active_painting.is_some() {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "Cannot fix the connection while painting is active",
+        ));
+    }
+
+    let mut connection_fix = state.connection_fix.write().await;
+    if connection_fix.is_some() {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "Connection fix is already running",
+        ));
+    }
+
+    let session = ConnectionFixSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        abort_signal: Arc::new(AtomicBool::new(false)),
+    };
+    *connection_fix = Some(session.clone());
+    drop(connection_fix);
+    drop(active_painting);
+
+    info!("Starting connection fix session {}", session.id);
+    let session_id = session.id.clone();
+    let connection_fix = state.connection_fix.clone();
+    let audit_log = state.audit_log.clone();
+    tokio::spawn(async move {
+        let mut use_case = FixConnectionUseCase::new(repairer);
+        if let Some(audit_log) = audit_log {
+            use_case = use_case.with_audit_log(audit_log, AuditInitiator::Web);
+        }
+        let worker_session = session.clone();
+        let result = run_controller_io(move || {
+            use_case.run(&worker_session.abort_signal, |event| {
+                send_fix_connection_event(&worker_session.id, &event)
+            })
+        })
+        .await;
+
+        let outcome = match result {
+            Ok(report) => report.outcome,
+            Err(e) => {
+                error!("Connection fix session {} panicked: {}", session.id, e);
+                FixConnectionOutcome::Aborted
+            }
+        };
+        info!(
+            "Connection fix session {} finished: {:?}",
+            session.id, outcome
+        );
+        *connection_fix.write().await = None;
+        let _ = PROGRESS_CHANNEL.send(
+            json!({
+                "type": "fix_connection",
+                "event": "finished",
+                "session_id": session.id,
+                "outcome": outcome,
+            })
+            .to_string(),
+        );
+    });
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(FixConnectionStartResponse {
+            session_id,
+            steps: FixConnectionStep::ALL.to_vec(),
+        }),
+    ))
+}
+
+/// Abort the running connection fix wizard
+///
+/// 実行中の手順が終わった時点で中断する。
+#[utoipa::path(
+    post, path = "/api/system/fix-connection/abort", tag = "system",
+    responses(
+        (status = 200, description = "実行中でなければ `success: false`", body = ApiResponse)
+    )
+)]
+pub async fn abort_fix_connection(State(state): State<Arc<ArtworkState>>) -> Json<ApiResponse> {
+    match state.connection_fix.read().await.as_ref() {
+        Some(session) => {
+            session.abort_signal.store(true, Ordering::SeqCst);
+            info!("Abort requested for connection fix session {}", session.id);
+            Json(ApiResponse {
+                success: true,
+                message: "Connection fix will stop after the current step".to_string(),
+            })
+        }
+        None => Json(ApiResponse {
+            success: false,
+            message: "No connection fix is running".to_string(),
+        }),
+    }
+}
+
+/// 再接続後に接続を確認する間隔
+const RECONNECT_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Reconnect the USB gadget and wait until the Switch recognizes the controller
+///
+/// ガジェットを切断して再バインドした後、`timeout_ms` まで接続を確認する。
+/// 待ち時間内に接続できなかった場合も200で `connected: false` を返す。
+#[utoipa::path(
+    post, path = "/api/system/reconnect-gadget", tag = "system",
+    params(ReconnectGadgetQuery),
+    responses(
+        (status = 200, body = ReconnectGadgetResponse),
+        (status = 409, description = "描画中、または接続修正・再接続の実行中", body = ErrorResponse),
+        (status = 422, description = "`timeout_ms` が範囲外", body = ErrorResponse),
+        (status = 500, description = "ガジェットの再接続に失敗", body = ErrorResponse),
+        (status = 503, description = "この環境では再接続を利用できない", body = ErrorResponse)
+    )
+)]
+pub async fn reconnect_gadget(
+    State(state): State<Arc<ArtworkState>>,
+    Query(query): Query<ReconnectGadgetQuery>,
+) -> Result<Json<ReconnectGadgetResponse>, ErrorResponse> {
+    let Some(gadget_manager) = state.gadget_manager.clone() else {
+        return Err(ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Gadget reconnect is not available in this environment",
+        ));
+    };
+    if !(1..=MAX_RECONNECT_TIMEOUT_MS).contains(&query.timeout_ms) {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("timeout_ms must be between 1 and {MAX_RECONNECT_TIMEOUT_MS}"),
+        ));
+    }
+
+    // 接続修正と同じセッションとして登録し、手動入力・接続の監視・接続修正と重ならないようにする
+    let active_painting = state.active_painting.read().await;
+    if active_painting.is_some() {
+        return Err(ErrorResponse::localized(
+            StatusCode::CONFLICT,
+            Message::new(MessageKey::ReconnectWhilePainting),
+        ));
+    }
+    let mut connection_fix = state.connection_fix.write().await;
+    if connection_fix.is_some() {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "Connection fix or gadget reconnect is already running",
+        ));
+    }
+    let session = ConnectionFixSession {
+        id: uuid::Uuid::new_v4().to_string(),
+        abort_signal: Arc::new(AtomicBool::new(false)),
+    };
+    *connection_fix = Some(session.clone());
+    drop(connection_fix);
+    drop(active_painting);
+
+    info!("Reconnecting the USB gadget (session {})", session.id);
+    let controller = state.controller.clone();
+    let connection_fix = state.connection_fix.clone();
+    let timeout = Duration::from_millis(query.timeout_ms);
+    // クライアントが切断してもセッションの登録を必ず解除するよう、別タスクで実行する
+    let task = tokio::spawn(async move {
+        let started = tokio::time::Instant::now();
+        let reconnected = run_controller_io(move || gadget_manager.reconnect_gadget()).await;
+        let reconnect_ms = started.elapsed().as_millis() as u64;
+        let result = match reconnected {
+            Ok(Ok(())) => {
+                let waiting = tokio::time::Instant::now();
+                let connected = wait_until_connected(controller, timeout).await;
+                Ok(ReconnectGadgetResponse {
+                    connected,
+                    reconnect_ms,
+                    wait_ms: waiting.elapsed().as_millis() as u64,
+                })
+            }
+            Ok(Err(e)) => Err(e.to_string()),
+            Err(e) => Err(e.to_string()),
+        };
+        *connection_fix.write().await = None;
+        result
+    });
+
+    let result = task
+        .await
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    match result {
+        Ok(response) => {
+            info!(
+                "Gadget reconnect finished: connected={} (reconnect {} ms, wait {} ms)",
+                response.connected, response.reconnect_ms, response.wait_ms
+            );
+            Ok(Json(response))
+        }
+        Err(message) => {
+            error!("Gadget reconnect failed: {}", message);
+            Err(ErrorResponse::new(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                format!("Failed to reconnect the USB gadget: {message}"),
+            ))
+        }
+    }
+}
+
+/// List the latest gadget state changes from the audit log
+///
+/// 起動時のサービス・CLI・Webからのガジェットの構成・再接続・クリーンアップ・接続修正などを、古いものから順に返す。
+#[utoipa::path(
+    get, path = "/api/system/audit", tag = "system",
+    params(AuditLogQuery),
+    responses(
+        (status = 200, body = AuditLogResponse),
+        (status = 422, description = "`limit` が範囲外", body = ErrorResponse),
+        (status = 500, description = "監査ログを読み取れない", body = ErrorResponse),
+        (status = 503, description = "監査ログが設定されていない", body = ErrorResponse)
+    )
+)]
+pub async fn get_audit_log(
+    State(state): State<Arc<ArtworkState>>,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<AuditLogResponse>, ErrorResponse> {
+    let Some(audit_log) = state.audit_log.clone() else {
+        return Err(ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The audit log is not available in this environment",
+        ));
+    };
+    if !(1..=MAX_AUDIT_LIMIT).contains(&query.limit) {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("limit must be between 1 and {MAX_AUDIT_LIMIT}"),
+        ));
+    }
+    let entries = tokio::task::spawn_blocking(move || audit_log.recent(query.limit))
+        .await
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(AuditLogResponse { entries }))
+}
+
+/// Get the USB gadget attributes currently applied in configfs
+///
+/// VID/PID・文字列・構成・`report_length`・レポートディスクリプタ（16進数とBase64）・UDCの状態・
+/// `/dev/hidg*` のパーミッションを、カーネルに反映されている値のまま返す。
+/// ガジェットが未構成の場合はエラーにせず `configured: false` を返す。
+#[utoipa::path(
+    get, path = "/api/system/gadget", tag = "system",
+    responses(
+        (status = 200, body = GadgetState),
+        (status = 500, description = "ガジェットの状態を読み取れない", body = ErrorResponse),
+        (status = 503, description = "シミュレーションモードなどでガジェットを扱わない", body = ErrorResponse)
+    )
+)]
+pub async fn get_gadget_state(
+    State(state): State<Arc<ArtworkState>>,
+) -> Result<Json<GadgetState>, ErrorResponse> {
+    let Some(gadget_manager) = state.gadget_manager.clone() else {
+        return Err(ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "The USB gadget is not available in this environment",
+        ));
+    };
+    let gadget_state = tokio::task::spawn_blocking(move || gadget_manager.read_gadget_state())
+        .await
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(gadget_state))
+}
+
+/// Reset all application data
+///
+/// アートワーク・描画履歴・描画の予約・イベントログなどのメモリ上の状態を消してから、
+/// データディレクトリ（監査ログ・アクセストークン・証明書など）の中身を削除する。
+/// 開いたままのデータベースはファイルを残して中身だけを削除する。
+/// 削除後は `tls/` などのディレクトリを作り直し、アクセストークン（以降のリクエストには新しい
+/// トークンが必要）と自己署名証明書をその場で生成し直す。
+/// /boot・configfs・systemdのユニットには触れない。
+#[utoipa::path(
+    post, path = "/api/system/reset-data", tag = "system",
+    request_body = ResetDataRequest,
+    responses(
+        (status = 200, body = ResetDataResponse),
+        (status = 409, description = "描画中、またはアートワークのパスの計算中", body = ErrorResponse),
+        (status = 422, description = "`confirm` が一致しない", body = ErrorResponse),
+        (status = 500, description = "データディレクトリを初期化できない（目印が無いなど。この場合は何も消さない）、データの削除、またはトークン・証明書の生成に失敗", body = ErrorResponse),
+        (status = 503, description = "データディレクトリが設定されていない", body = ErrorResponse)
+    )
+)]
+pub async fn reset_data(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<ResetDataRequest>,
+) -> Result<Json<ResetDataResponse>, ErrorResponse> {
+    let Some(data_reset) = state.data_reset.clone() else {
+        return Err(ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Data reset is not available in this environment",
+        ));
+    };
+    if request.confirm != RESET_DATA_CONFIRMATION {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("confirm must be \"{RESET_DATA_CONFIRMATION}\""),
+        ));
+    }
+
+    // 初期化が終わるまで描画とアートワークの編集を始めさせない
+    let active_painting = state.active_painting.read().await;
+    if active_painting.is_some() {
+        return Err(ErrorResponse::localized(
+            StatusCode::CONFLICT,
+            Message::new(MessageKey::ResetWhilePainting),
+        ));
+    }
+    let _artwork_edits = state.artwork_edits.lock().await;
+
+    // 目印の無いディレクトリなど、初期化できないデータディレクトリなら何も消さない
+    let check = data_reset.clone();
+    tokio::task::spawn_blocking(move || check.entries())
+        .await
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| {
+            error!("Data reset refused: {}", e);
+            ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    // ゴミ箱のアートワークも消す
+    let mut artworks = state.artworks.find_all().await?;
+    artworks.extend(
+        state
+            .artworks
+            .find_trashed()
+            .await?
+            .into_iter()
+            .map(|trashed| trashed.artwork),
+    );
+    // パスの計算中のアートワークがあれば何も消さない
+    let _locks = artworks
+        .iter()
+        .map(|artwork| {
+            state
+                .artwork_locks
+                .write(&artwork.id.as_str(), MessageKey::OperationDataReset)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let cancelled_schedule = cancel_schedule(&state).await;
+    for artwork in &artworks {
+        discard_artwork(&state, &artwork.id).await?;
+    }
+    *state.calibration.write().await = None;
+    state.events.clear().await;
+    let runs = state.runs.clone();
+    let estimate_model = state.estimate_model.clone();
+    let regenerate_token = state.auth_token.is_some();
+    let (painting_runs_removed, files, auth_token) = tokio::task::spawn_blocking(move || {
+        let painting_runs_removed = runs.clear();
+        let files = data_reset.execute().map_err(|e| e.to_string())?;
+        // 補正モデルのファイルは消えたため、読み込んだ係数も捨てる
+        estimate_model.forget();
+        // 消したトークンを使い続けないよう、新しいトークンを生成して保存する
+        let auth_token = regenerate_token
+            .then(|| AuthToken::load_or_create(data_reset.data_dir()))
+            .transpose()
+            .map_err(|e| e.to_string())?;
+        Ok::<_, String>((painting_runs_removed, files, auth_token))
+    })
+    .await
+    .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    .map_err(|e| {
+        error!("Data reset failed: {}", e);
+        ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e)
+    })?;
+    let auth_token_regenerated = auth_token.is_some();
+    if let Some(token) = auth_token {
+        warn!(
+            "Regenerated the access token after the data reset: {}",
+            token.fingerprint()
+        );
+        state.replace_auth_token(token);
+    }
+    // 自己署名証明書も消えたため、作り直して読み込み直す
+    if let Some(tls) = &state.self_signed_tls
+        && let Err(e) = tls.reload().await
+    {
+        error!("Failed to regenerate the self-signed certificate: {}", e);
+        return Err(ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            e.to_string(),
+        ));
+    }
+    drop(active_painting);
+
+    warn!(
+        "Reset application data: {} artworks, {} painting runs and {} files ({} bytes) removed from {}",
+        artworks.len(),
+        painting_runs_removed,
+        files.removed.len(),
+        files.bytes_removed,
+        files.data_dir
+    );
+    Ok(Json(ResetDataResponse {
+        artworks_removed: artworks.len(),
+        painting_runs_removed,
+        cancelled_schedule,
+        files,
+        auth_token_regenerated,
+    }))
+}
+
+/// Measure the painting speed this hardware can sustain
+///
+/// HIDデバイス（シミュレーションではtmpfsのファイル）へのレポートの書き込み、パスの生成中の
+/// 8ms間隔の送信ループの揺らぎ、1万ドットのパスの生成速度を最大15秒で測り、結果を
+/// データディレクトリに保存する。実行中は描画・キャリブレーションを開始できない。
+#[utoipa::path(
+    post, path = "/api/system/benchmark", tag = "system",
+    responses(
+        (status = 200, body = BenchmarkReport),
+        (status = 409, description = "描画中または接続修正の実行中", body = ErrorResponse),
+        (status = 500, description = "HIDデバイスを開けない、または結果を保存できない", body = ErrorResponse),
+        (status = 503, description = "この環境ではベンチマークを利用できない", body = ErrorResponse)
+    )
+)]
+pub async fn run_benchmark(
+    State(state): State<Arc<ArtworkState>>,
+) -> Result<Json<BenchmarkReport>, ErrorResponse> {
+    let Some(benchmark) = state.benchmark.clone() else {
+        return Err(ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Benchmark is not available in this environment",
+        ));
+    };
+    if state.connection_fix.read().await.is_some() {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            "Cannot run the benchmark while the connection fix is running",
+        ));
+    }
+    // 測定中に描画・キャリブレーションがデバイスへ書き込まないよう、実行中として登録する
+    let control = PaintingControl::new(1, 0, 0, 0);
+    state.begin_painting(&control).await?;
+    info!("Starting the painting-speed benchmark");
+    let result = tokio::task::spawn_blocking(move || benchmark.execute()).await;
+    release_active_painting(&state.active_painting, &control).await;
+
+    result
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map(Json)
+        .map_err(|e| {
+            error!("Benchmark failed: {}", e);
+            ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })
+}
+
+/// Get HID report and log write metrics
+///
+/// 起動からの累計と、直近の描画の分（描画の開始時に0に戻る）を返す。
+#[utoipa::path(
+    get, path = "/api/metrics", tag = "system",
+    responses((status = 200, body = MetricsReport))
+)]
+pub async fn get_metrics(State(state): State<Arc<ArtworkState>>) -> Json<MetricsReport> {
+    Json(state.metrics.report())
+}
+
+/// Get the metrics in the Prometheus text format
+#[utoipa::path(
+    get, path = "/metrics", tag = "system",
+    responses(
+        (status = 200, description = "Prometheusのテキスト形式", content_type = "text/plain", body = String)
+    )
+)]
+pub async fn get_prometheus_metrics(State(state): State<Arc<ArtworkState>>) -> Response {
+    (
+        [(
+            header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.report().to_prometheus(),
+    )
+        .into_response()
+}
+
+/// 接続を確認できるまで最大 `timeout` 待つ（確認できれば `true`）
+async fn wait_until_connected(controller: Arc<dyn ControllerEmulator>, timeout: Duration) -> bool {
+    let deadline = tokio::time::Instant::now() + timeout;
+    loop {
+        if probe_connection(controller.clone()).await {
+            return true;
+        }
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        tokio::time::sleep(RECONNECT_POLL_INTERVAL.min(deadline - now)).await;
+    }
+}
+
+fn send_fix_connection_event(session_id: &str, event: &FixConnectionEvent) {
+    let total = FixConnectionStep::ALL.len();
+    let message = match event {
+        FixConnectionEvent::StepStarted { index, step } => json!({
+            "type": "fix_connection",
+            "event": "step_started",
+            "session_id": session_id,
+            "index": index,
+            "total": total,
+            "step": step,
+            "description": step.description(),
+        }),
+        FixConnectionEvent::StepFinished { index, result } => json!({
+            "type": "fix_connection",
+            "event": "step_result",
+            "session_id": session_id,
+            "index": index,
+            "total": total,
+            "result": result,
+        }),
+    };
+    let _ = PROGRESS_CHANNEL.send(message.to_string());
+}
+
+/// Log in to the web UI with the access token
+///
+/// 成功するとセッションCookieを発行し、以降の変更系APIは `Authorization` ヘッダー無しで呼べる。
+#[utoipa::path(
+    post, path = "/api/auth/login", tag = "system",
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "認証が無効な場合はCookieを発行しない", body = ApiResponse),
+        (status = 401, description = "トークンが一致しない", body = ErrorResponse)
+    )
+)]
+pub async fn login(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<LoginRequest>,
+) -> Result<Response, ErrorResponse> {
+    let Some(token) = state.auth_token() else {
+        return Ok(Json(ApiResponse {
+            success: true,
+            message: "Authentication is disabled".to_string(),
+        })
+        .into_response());
+    };
+
+    if !token.verify(request.token.trim()) {
+        return Err(ErrorResponse::localized(
+            StatusCode::UNAUTHORIZED,
+            Message::new(MessageKey::InvalidAccessToken),
+        ));
+    }
+
+    info!("Web UI logged in");
+    let cookie = format!(
+        "{SESSION_COOKIE}={}; Path=/; HttpOnly; SameSite=Strict; Max-Age={SESSION_MAX_AGE_SECS}",
+        token.session_value()
+    );
+    Ok((
+        [(header::SET_COOKIE, cookie)],
+        Json(ApiResponse {
+            success: true,
+            message: "Logged in".to_string(),
+        }),
+    )
+        .into_response())
+}
+
+/// Change the log filter of the running server
+///
+/// サーバーを再起動せずに、不具合の調査中だけ `debug` や `trace` のログを出せる。
+#[utoipa::path(
+    put, path = "/api/system/log-level", tag = "system",
+    request_body = LogLevelRequest,
+    responses(
+        (status = 200, body = LogLevelResponse),
+        (status = 422, description = "フィルターの書式が不正", body = ErrorResponse),
+        (status = 503, description = "この起動方法ではログフィルターを変更できない", body = ErrorResponse)
+    )
+)]
+pub async fn set_log_level(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<LogLevelRequest>,
+) -> Result<Json<LogLevelResponse>, ErrorResponse> {
+    let Some(control) = &state.log_level else {
+        return Err(ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Log level cannot be changed in this environment",
+        ));
+    };
+
+    let level = request.level.trim();
+    let previous = control.current();
+    control.set(level).map_err(|e| {
+        warn!("Rejected log level change: {}", e);
+        ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string())
+    })?;
+    // 変更後のフィルターで `info` が出なくなっても変更の記録は残す
+    warn!("Log level changed from '{}' to '{}'", previous, level);
+    Ok(Json(LogLevelResponse {
+        level: level.to_string(),
+        previous,
+    }))
+}
+
+/// Send a sample event to the configured webhooks
+///
+/// `painting_completed` のサンプルを `test: true` で送信し、URLごとの結果を返す。
+/// 送信先のURLはホスト名だけを返す。
+#[utoipa::path(
+    post, path = "/api/settings/webhooks/test", tag = "system",
+    responses(
+        (status = 200, body = WebhookTestResponse),
+        (status = 503, description = "Webhookが設定されていない", body = ErrorResponse)
+    )
+)]
+pub async fn test_webhooks(
+    State(state): State<Arc<ArtworkState>>,
+) -> Result<Json<WebhookTestResponse>, ErrorResponse> {
+    let Some(webhooks) = &state.webhooks else {
+        return Err(ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "No webhooks are configured; start the server with --webhook-url",
+        ));
+    };
+
+    let event = ArtworkEvent::painting_completed(
+        ArtworkId::generate(),
+        1024,
+        3600,
+        1,
+        EventMetadata::new("webhook_test".to_string()),
+    );
+    let payload =
+        WebhookPayload::from_event(&event, Some("Webhook test".to_string()), state.locale)
+            .into_test();
+    let deliveries = webhooks.deliver(&payload).await;
+    info!(
+        "Sent test webhook: {} of {} delivered",
+        deliveries
+            .iter()
+            .filter(|delivery| delivery.delivered)
+            .count(),
+        deliveries.len()
+    );
+    Ok(Json(WebhookTestResponse { deliveries }))
+}
+
 /// WebSocket handler for log streaming
-pub async fn websocket_handler(ws: WebSocketUpgrade) -> Response {
-    ws.on_upgrade(stream_logs)
+///
+/// 進捗の文言は `Accept-Language` の言語（無ければサーバーの既定の言語）で送る。
+pub async fn websocket_handler(
+    State(state): State<Arc<ArtworkState>>,
+    headers: HeaderMap,
+    ws: WebSocketUpgrade,
+) -> Response {
+    let locale = request_locale(&headers, state.locale);
+    ws.on_upgrade(move |socket| stream_logs(socket, locale))
+}
+
+/// Get whether the public gallery is enabled
+#[utoipa::path(
+    get, path = "/api/system/gallery", tag = "system",
+    responses((status = 200, body = GalleryModeResponse))
+)]
+pub async fn get_gallery_mode(State(state): State<Arc<ArtworkState>>) -> Json<GalleryModeResponse> {
+    let enabled = state.gallery.is_enabled();
+    Json(GalleryModeResponse {
+        enabled,
+        previous: enabled,
+    })
+}
+
+/// Enable or disable the public gallery without restarting
+///
+/// 無効にすると、ギャラリーのページとAPIは404を返し、接続中の視聴者は切断される。
+#[utoipa::path(
+    put, path = "/api/system/gallery", tag = "system",
+    request_body = GalleryModeRequest,
+    responses((status = 200, body = GalleryModeResponse))
+)]
+pub async fn set_gallery_mode(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<GalleryModeRequest>,
+) -> Json<GalleryModeResponse> {
+    let previous = state.gallery.set(request.enabled);
+    if previous != request.enabled {
+        info!(
+            "Gallery mode {}",
+            if request.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+    }
+    Json(GalleryModeResponse {
+        enabled: request.enabled,
+        previous,
+    })
+}
+
+/// Get the painting progress shown in the public gallery
+///
+/// 認証無しで取得でき、描画を操作する情報は含まない。
+#[utoipa::path(
+    get, path = "/api/gallery/state", tag = "gallery",
+    responses(
+        (status = 200, body = GalleryState),
+        (status = 404, description = "ギャラリーモードが無効", body = ErrorResponse)
+    )
+)]
+pub async fn get_gallery_state(
+    State(state): State<Arc<ArtworkState>>,
+) -> Result<Json<GalleryState>, ErrorResponse> {
+    state.gallery.ensure_enabled()?;
+    Ok(Json(gallery_state(&state).await?))
+}
+
+/// Serve the public gallery page
+pub async fn gallery_page(State(state): State<Arc<ArtworkState>>) -> Response {
+    if let Err(e) = state.gallery.ensure_enabled() {
+        return e.into_response();
+    }
+    let assets = state.assets.clone();
+    match tokio::task::spawn_blocking(move || assets.load("gallery.html")).await {
+        Ok(Ok(Some(asset))) => (
+            [(header::CONTENT_TYPE, "text/html; charset=utf-8")],
+            asset.data.into_owned(),
+        )
+            .into_response(),
+        _ => ErrorResponse::new(StatusCode::NOT_FOUND, "Gallery page is not available")
+            .into_response(),
+    }
+}
+
+/// WebSocket handler for the read-only gallery progress
+///
+/// 無効な場合はWebSocketのリクエストかどうかに関わらず404を返す。
+pub async fn gallery_websocket_handler(
+    State(state): State<Arc<ArtworkState>>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Response {
+    if let Err(e) = state.gallery.ensure_enabled() {
+        return e.into_response();
+    }
+    match ws {
+        Ok(ws) => {
+            let gallery = state.gallery.clone();
+            ws.on_upgrade(move |socket| stream_gallery(socket, gallery))
+        }
+        Err(rejection) => rejection.into_response(),
+    }
 }
 
 // Helper functions
@@ -143,3 +1007,462 @@ fn get_hardware_details() -> HardwareDetails {
 
     details
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::TestClient;
+    use super::*;
+    use crate::debug::LogLevelControl;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    fn client_with(state: ArtworkState) -> TestClient {
+        TestClient::new(Arc::new(state))
+    }
+
+    fn client() -> TestClient {
+        client_with(ArtworkState::new(Arc::new(
+            MockController::new().without_delays(),
+        )))
+    }
+
+    fn state() -> ArtworkState {
+        ArtworkState::new(Arc::new(MockController::new().without_delays()))
+    }
+
+    #[tokio::test]
+    async fn test_system_info_version_and_hardware_status_routes() {
+        let client = client();
+
+        let response = client.get("/api/system/info").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.json()["version"].is_string());
+
+        let response = client.get("/api/version").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["version"], env!("CARGO_PKG_VERSION"));
+
+        let response = client.get("/api/hardware/status").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.json()["details"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_login_route_sets_session_cookie_for_valid_token() {
+        let client = client_with(state().with_auth_token(AuthToken::new("secret")));
+
+        let response = client
+            .post("/api/auth/login", serde_json::json!({ "token": "secret" }))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let cookie = response.headers[header::SET_COOKIE].to_str().unwrap();
+        assert!(
+            cookie.starts_with(&format!("{SESSION_COOKIE}=")),
+            "{cookie}"
+        );
+
+        let response = client
+            .post("/api/auth/login", serde_json::json!({ "token": "wrong" }))
+            .await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert!(response.headers.get(header::SET_COOKIE).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_log_level_route_changes_filter_and_rejects_invalid_levels() {
+        let response = client()
+            .put(
+                "/api/system/log-level",
+                serde_json::json!({ "level": "debug" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let (_layer, control) = LogLevelControl::new("info").unwrap();
+        let client = client_with(state().with_log_level_control(control.clone()));
+        let response = client
+            .put(
+                "/api/system/log-level",
+                serde_json::json!({ "level": "debug" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["previous"], "info");
+        assert_eq!(control.current(), "debug");
+
+        let response = client
+            .put("/api/system/log-level", serde_json::json!({ "level": "[" }))
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(control.current(), "debug");
+    }
+
+    #[tokio::test]
+    async fn test_connection_routes_are_unavailable_without_backends() {
+        let client = client();
+
+        let response = client.post_empty("/api/system/fix-connection/start").await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+
+        // 実行中のウィザードが無ければ何もしない
+        let response = client.post_empty("/api/system/fix-connection/abort").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["success"], false);
+
+        let response = client.post_empty("/api/system/reconnect-gadget").await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_metrics_routes_report_json_and_prometheus_text() {
+        use crate::domain::hardware::{ReportErrorClass, ReportMetricsSink};
+
+        let state = state();
+        state.metrics.report_sent(8, std::time::Instant::now());
+        state.metrics.report_failed(ReportErrorClass::Disconnected);
+        state.metrics.start_run();
+        state.metrics.report_sent(8, std::time::Instant::now());
+        let client = client_with(state);
+
+        let response = client.get("/api/metrics").await;
+        assert_eq!(response.status, StatusCode::OK);
+        let body = response.json();
+        assert_eq!(body["process"]["reports_sent"], 2);
+        assert_eq!(body["process"]["bytes_sent"], 16);
+        assert_eq!(body["process"]["write_errors"]["disconnected"], 1);
+        assert_eq!(body["run"]["metrics"]["reports_sent"], 1);
+        assert_eq!(body["run"]["metrics"]["write_errors"]["disconnected"], 0);
+
+        let response = client.get("/metrics").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(
+            response.headers[header::CONTENT_TYPE]
+                .to_str()
+                .unwrap()
+                .starts_with("text/plain")
+        );
+        let text = String::from_utf8(response.body.to_vec()).unwrap();
+        assert!(text.contains("\nghost_drawer_hid_reports_sent_total 2\n"));
+        assert!(text.contains("\nghost_drawer_run_hid_reports_sent 1\n"));
+    }
+
+    #[tokio::test]
+    async fn test_audit_route_returns_latest_entries() {
+        use crate::domain::setup::entities::{GadgetAuditEntry, GadgetOperation};
+        use crate::domain::setup::repositories::GadgetAuditLog;
+        use crate::infrastructure::setup::JsonlGadgetAuditLog;
+
+        let response = client().get("/api/system/audit").await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let dir = std::env::temp_dir().join(format!("audit-route-{}", uuid::Uuid::new_v4()));
+        let audit_log = Arc::new(JsonlGadgetAuditLog::new(&dir));
+        for operation in [GadgetOperation::Configure, GadgetOperation::Reconnect] {
+            audit_log.record(GadgetAuditEntry::new(
+                operation,
+                AuditInitiator::Systemd,
+                &Ok::<(), String>(()),
+            ));
+        }
+        let client = client_with(state().with_audit_log(audit_log));
+
+        let response = client.get("/api/system/audit?limit=1").await;
+        assert_eq!(response.status, StatusCode::OK);
+        let entries = response.json()["entries"].clone();
+        assert_eq!(entries.as_array().unwrap().len(), 1);
+        assert_eq!(entries[0]["operation"], "reconnect");
+        assert_eq!(entries[0]["initiator"], "systemd");
+        assert_eq!(entries[0]["success"], true);
+        let response = client.get("/api/system/audit").await;
+        assert_eq!(response.json()["entries"].as_array().unwrap().len(), 2);
+        let response = client.get("/api/system/audit?limit=0").await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reset_data_route_clears_artworks_runs_and_data_files() {
+        use crate::application::use_cases::{PaintingControl, ResetDataUseCase};
+        use crate::domain::painting::entities::PaintingRun;
+        use crate::domain::painting::value_objects::{DrawingPath, DrawingStrategy, PaintTiming};
+
+        let confirm = serde_json::json!({ "confirm": RESET_DATA_CONFIRMATION });
+        let response = client()
+            .post("/api/system/reset-data", confirm.clone())
+            .await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let dir = std::env::temp_dir().join(format!("reset-data-route-{}", uuid::Uuid::new_v4()));
+        crate::infrastructure::data_dir::create_data_dir(&dir).unwrap();
+        std::fs::create_dir_all(dir.join("tls")).unwrap();
+        std::fs::write(dir.join("audit.log"), "{}\n").unwrap();
+        std::fs::write(dir.join("tls/cert.pem"), "cert").unwrap();
+        let state = Arc::new(state().with_data_reset(ResetDataUseCase::new(&dir)));
+        let client = TestClient::new(state.clone());
+        let response = client
+            .post(
+                "/api/artworks/generate",
+                serde_json::json!({ "pattern": "border", "width": 8, "height": 8 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let artwork = state.artworks.find_all().await.unwrap().remove(0);
+        state.runs.save(&PaintingRun::start(
+            artwork.id.clone(),
+            artwork.version,
+            DrawingStrategy::RasterScan,
+            PaintTiming::default(),
+            1,
+            &DrawingPath::new(Vec::new()),
+        ));
+        // ゴミ箱のアートワークも消す
+        let response = client
+            .post(
+                "/api/artworks/generate",
+                serde_json::json!({ "pattern": "border", "width": 8, "height": 8 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let trashed = state
+            .artworks
+            .find_all()
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|other| other.id != artwork.id)
+            .unwrap();
+        let response = client
+            .delete(&format!("/api/artworks/{}", trashed.id))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+
+        let response = client
+            .post(
+                "/api/system/reset-data",
+                serde_json::json!({ "confirm": "yes" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        *state.active_painting.write().await = Some(PaintingControl::new(1, 100, 100, 100));
+        let response = client.post("/api/system/reset-data", confirm.clone()).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        *state.active_painting.write().await = None;
+        assert_eq!(state.artworks.count().await.unwrap(), 1);
+
+        let response = client.post("/api/system/reset-data", confirm).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let body = response.json();
+        assert_eq!(body["artworks_removed"], 2);
+        assert_eq!(body["painting_runs_removed"], 1);
+        assert_eq!(body["files"]["removed"].as_array().unwrap().len(), 3);
+        assert_eq!(body["auth_token_regenerated"], false);
+        assert_eq!(state.artworks.count().await.unwrap(), 0);
+        assert!(state.artworks.find_trashed().await.unwrap().is_empty());
+        assert!(state.runs.recent(10).is_empty());
+        assert_eq!(state.memory_budget.used_bytes(), 0);
+        assert!(dir.join("tls").is_dir());
+        assert!(!dir.join("audit.log").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reset_data_route_leaves_state_untouched_for_unmarked_directories() {
+        use crate::application::use_cases::ResetDataUseCase;
+        use crate::domain::painting::entities::PaintingRun;
+        use crate::domain::painting::value_objects::{DrawingPath, DrawingStrategy, PaintTiming};
+
+        let dir = std::env::temp_dir().join(format!("reset-data-home-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(".bashrc"), "export PATH").unwrap();
+        let state = Arc::new(state().with_data_reset(ResetDataUseCase::new(&dir)));
+        let client = TestClient::new(state.clone());
+        let response = client
+            .post(
+                "/api/artworks/generate",
+                serde_json::json!({ "pattern": "border", "width": 8, "height": 8 }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let artwork = state.artworks.find_all().await.unwrap().remove(0);
+        state.runs.save(&PaintingRun::start(
+            artwork.id.clone(),
+            artwork.version,
+            DrawingStrategy::RasterScan,
+            PaintTiming::default(),
+            1,
+            &DrawingPath::new(Vec::new()),
+        ));
+
+        let response = client
+            .post(
+                "/api/system/reset-data",
+                serde_json::json!({ "confirm": RESET_DATA_CONFIRMATION }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(
+            response.message().contains("marker"),
+            "{}",
+            response.message()
+        );
+        assert_eq!(state.artworks.count().await.unwrap(), 1);
+        assert_eq!(state.runs.recent(10).len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(dir.join(".bashrc")).unwrap(),
+            "export PATH"
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reset_data_route_regenerates_the_access_token() {
+        use crate::application::use_cases::ResetDataUseCase;
+        use axum::body::Body;
+        use axum::http::Request;
+
+        let dir = std::env::temp_dir().join(format!("reset-data-token-{}", uuid::Uuid::new_v4()));
+        let old = AuthToken::load_or_create(&dir).unwrap();
+        let state = Arc::new(
+            state()
+                .with_auth_token(old.clone())
+                .with_data_reset(ResetDataUseCase::new(&dir)),
+        );
+        let client = TestClient::new(state.clone());
+        let reset = |token: &AuthToken| {
+            Request::builder()
+                .method("POST")
+                .uri("/api/system/reset-data")
+                .header(header::AUTHORIZATION, format!("Bearer {}", token.as_str()))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(
+                    serde_json::json!({ "confirm": RESET_DATA_CONFIRMATION }).to_string(),
+                ))
+                .unwrap()
+        };
+
+        let response = client.send(reset(&old)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["auth_token_regenerated"], true);
+        let new = state.auth_token().unwrap();
+        assert!(new != old, "the deleted token must not stay valid");
+        assert!(AuthToken::load(&dir).unwrap() == Some(new.clone()));
+
+        let response = client.send(reset(&old)).await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        let response = client.send(reset(&new)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_route_refuses_painting_and_is_reported_in_system_info() {
+        use crate::application::use_cases::{BenchmarkUseCase, PaintingControl};
+        use crate::domain::painting::TwoOptSettings;
+
+        let response = client().post_empty("/api/system/benchmark").await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let dir = std::env::temp_dir().join(format!("benchmark-route-{}", uuid::Uuid::new_v4()));
+        let benchmark = BenchmarkUseCase::new(&dir)
+            .simulated()
+            .with_two_opt_settings(TwoOptSettings::default().with_time_budget_ms(100));
+        let state = Arc::new(state().with_benchmark(benchmark));
+        let client = TestClient::new(state.clone());
+        assert!(client.get("/api/system/info").await.json()["benchmark"].is_null());
+
+        *state.active_painting.write().await = Some(PaintingControl::new(1, 100, 100, 100));
+        let response = client.post_empty("/api/system/benchmark").await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        *state.active_painting.write().await = None;
+
+        let running = tokio::spawn({
+            let client = TestClient::new(state.clone());
+            async move { client.post_empty("/api/system/benchmark").await }
+        });
+        while state.active_painting.read().await.is_none() {
+            assert!(!running.is_finished(), "the benchmark never registered");
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+        // 測定中は描画・キャリブレーションを開始できない
+        let painting = PaintingControl::new(1, 100, 100, 100);
+        let refused = state.begin_painting(&painting).await.unwrap_err();
+        assert_eq!(refused.status(), StatusCode::CONFLICT);
+
+        let response = running.await.unwrap();
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let report = response.json();
+        assert_eq!(report["simulated"], true);
+        assert_eq!(report["path_generation"]["dots"], 10_000);
+        assert!(state.active_painting.read().await.is_none());
+
+        let info = client.get("/api/system/info").await.json();
+        assert_eq!(info["benchmark"], report);
+        assert!(
+            info["benchmark"]["suggested_timing"]["press_ms"]
+                .as_u64()
+                .unwrap()
+                >= 16
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_webhook_test_route_requires_configured_webhooks() {
+        let response = client().post_empty("/api/settings/webhooks/test").await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.message().contains("--webhook-url"));
+    }
+
+    #[tokio::test]
+    async fn test_gallery_routes_follow_gallery_mode() {
+        let client = client();
+
+        let response = client.get("/api/system/gallery").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["enabled"], true);
+        let response = client.get("/api/gallery/state").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["active"], false);
+        let response = client.get("/gallery").await;
+        assert_eq!(response.status, StatusCode::OK);
+
+        let response = client
+            .put(
+                "/api/system/gallery",
+                serde_json::json!({ "enabled": false }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(
+            response.json(),
+            serde_json::json!({ "enabled": false, "previous": true })
+        );
+        for path in ["/api/gallery/state", "/gallery", "/ws/gallery"] {
+            assert_eq!(
+                client.get(path).await.status,
+                StatusCode::NOT_FOUND,
+                "{path}"
+            );
+        }
+
+        let response = client
+            .put(
+                "/api/system/gallery",
+                serde_json::json!({ "enabled": "yes" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_websocket_routes_reject_plain_requests() {
+        let client = client();
+        for path in ["/ws/logs", "/ws/gallery"] {
+            let response = client.get(path).await;
+            assert!(
+                response.status.is_client_error(),
+                "{path}: {}",
+                response.status
+            );
+        }
+    }
+}
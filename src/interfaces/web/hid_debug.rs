@@ -0,0 +1,336 @@
+//! プロトコルの調査用に組み立て済みのHIDレポートを送るデバッグ用API
+//!
+//! `run --debug` で起動した場合だけ受け付ける。送信中は手動入力のロックを取り、実行中の描画として
+//! 登録するため、描画・キャリブレーション・手動入力と混ざらない。停止・解除で残りの送信を打ち切る。
+
+use super::error_response::ErrorResponse;
+use super::state::{ArtworkState, release_active_painting};
+use crate::application::controller_io::run_controller_io;
+use crate::application::use_cases::PaintingControl;
+use crate::domain::controller::ControllerEmulator;
+use crate::domain::hardware::ReportErrorClass;
+use crate::domain::shared::i18n::{Message, MessageKey};
+use crate::infrastructure::hardware::linux_usb_gadget_manager::REPORT_LENGTH;
+use axum::{Json, extract::State, http::StatusCode};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{info, warn};
+use utoipa::ToSchema;
+
+/// 1回の要求で送れるレポートの数
+const MAX_REPEAT: u32 = 1000;
+/// レポートの送信間隔の上限（ミリ秒）
+const MAX_INTERVAL_MS: u32 = 1000;
+
+/// 組み立て済みのHIDレポートの送信要求
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct HidReportInjectionRequest {
+    /// 送るレポート（16進数、空白は無視する。例: `"0000088080808000"`）
+    pub bytes: String,
+    /// 送る回数（1〜1000）
+    #[serde(default = "default_repeat")]
+    pub repeat: u32,
+    /// 送信間隔（ミリ秒、最大1000）
+    #[serde(default = "default_interval_ms")]
+    pub interval_ms: u32,
+}
+
+fn default_repeat() -> u32 {
+    1
+}
+
+fn default_interval_ms() -> u32 {
+    8
+}
+
+/// HIDレポートの送信結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, ToSchema)]
+pub struct HidReportInjectionResponse {
+    /// 書き込めたレポートの数
+    pub written: u32,
+    /// 1回に書き込んだバイト数
+    pub report_length: usize,
+    /// 停止・解除で残りの送信を打ち切ったか
+    pub stopped: bool,
+    /// 書き込みに失敗した場合のエラーの分類（失敗した時点で送信をやめる）
+    pub error: Option<ReportErrorClass>,
+    /// 書き込みに失敗した場合のエラーメッセージ
+    pub error_message: Option<String>,
+}
+
+/// Write a hand-crafted HID report to the Switch (debug mode only)
+///
+/// `bytes` の長さはHID functionの `report_length`（ガジェットを扱わない環境では8）と一致する必要がある。
+/// コントローラーの入力状態は変えないため、最後に送ったレポートは次の入力で上書きされるまで有効なまま残る。
+#[utoipa::path(
+    post, path = "/api/debug/hid-report", tag = "controller",
+    request_body = HidReportInjectionRequest,
+    responses(
+        (status = 200, description = "書き込みに失敗した場合も、それまでに書き込めた数とエラーの分類を返す", body = HidReportInjectionResponse),
+        (status = 409, description = "描画・キャリブレーション・接続修正の実行中、または厳格シミュレーション中", body = ErrorResponse),
+        (status = 422, description = "16進数・長さ・回数・間隔が不正", body = ErrorResponse),
+        (status = 423, description = "コントローラーがアームされていない", body = ErrorResponse),
+        (status = 503, description = "`--debug` を指定せずに起動した", body = ErrorResponse)
+    )
+)]
+pub async fn inject_hid_report(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<HidReportInjectionRequest>,
+) -> Result<Json<HidReportInjectionResponse>, ErrorResponse> {
+    if !state.debug {
+        return Err(ErrorResponse::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "Debug endpoints are disabled. Restart with --debug to enable them",
+        ));
+    }
+    let report = parse_hex_report(&request.bytes)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e))?;
+    if !(1..=MAX_REPEAT).contains(&request.repeat) {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("repeat must be between 1 and {MAX_REPEAT}"),
+        ));
+    }
+    if request.interval_ms > MAX_INTERVAL_MS {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("interval_ms must be at most {MAX_INTERVAL_MS}"),
+        ));
+    }
+    let report_length = configured_report_length(&state).await;
+    if report.len() != report_length {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "The report is {} bytes but the HID function's report_length is {report_length}",
+                report.len()
+            ),
+        ));
+    }
+
+    state.ensure_controller_allowed()?;
+    let input_guard = state.controller_input.clone().lock_owned().await;
+    if state.connection_fix.read().await.is_some() {
+        return Err(ErrorResponse::localized(
+            StatusCode::CONFLICT,
+            Message::new(MessageKey::ManualInputWhileFixingConnection),
+        ));
+    }
+    let control = PaintingControl::new(1, 0, 0, request.interval_ms);
+    state.begin_painting(&control).await?;
+    state.forget_calibration_cursor().await;
+    info!(
+        "Injecting raw HID report {:02X?} {} time(s) every {} ms",
+        report, request.repeat, request.interval_ms
+    );
+
+    // 切断されても登録とロックを残さないよう、送信から登録の解除までを別タスクで行う
+    let controller = state.controller.clone();
+    let active_painting = state.active_painting.clone();
+    let interval = Duration::from_millis(request.interval_ms.into());
+    let task = tokio::spawn(async move {
+        let stop_signal = control.stop_signal.clone();
+        let result = run_controller_io(move || {
+            write_reports(
+                controller.as_ref(),
+                &report,
+                request.repeat,
+                interval,
+                &stop_signal,
+            )
+        })
+        .await;
+        release_active_painting(&active_painting, &control).await;
+        drop(input_guard);
+        result
+    });
+    let response = task
+        .await
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(response))
+}
+
+/// 空白を除いた16進数の文字列をバイト列にする
+fn parse_hex_report(text: &str) -> Result<Vec<u8>, String> {
+    let digits: Vec<u8> = text
+        .bytes()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect();
+    if digits.is_empty() {
+        return Err("bytes must not be empty".to_string());
+    }
+    if let Some(&invalid) = digits.iter().find(|byte| !byte.is_ascii_hexdigit()) {
+        return Err(format!(
+            "bytes must be hexadecimal, found '{}'",
+            char::from(invalid)
+        ));
+    }
+    if !digits.len().is_multiple_of(2) {
+        return Err("bytes must have an even number of hex digits".to_string());
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| {
+            let pair = std::str::from_utf8(pair).expect("hex digits are ASCII");
+            u8::from_str_radix(pair, 16).expect("validated hex digits")
+        })
+        .collect())
+}
+
+/// ガジェットの `report_length`（ガジェットを扱わない・読めない場合は既定の長さ）
+async fn configured_report_length(state: &ArtworkState) -> usize {
+    let Some(gadget_manager) = state.gadget_manager.clone() else {
+        return REPORT_LENGTH;
+    };
+    tokio::task::spawn_blocking(move || gadget_manager.read_gadget_state())
+        .await
+        .ok()
+        .and_then(Result::ok)
+        .and_then(|gadget| gadget.report_length)
+        .map_or(REPORT_LENGTH, |length| length as usize)
+}
+
+/// レポートを `repeat` 回書き込む（失敗した時点と `stop_signal` が立った時点で打ち切る）
+fn write_reports(
+    controller: &dyn ControllerEmulator,
+    report: &[u8],
+    repeat: u32,
+    interval: Duration,
+    stop_signal: &AtomicBool,
+) -> HidReportInjectionResponse {
+    let mut response = HidReportInjectionResponse {
+        written: 0,
+        report_length: report.len(),
+        stopped: false,
+        error: None,
+        error_message: None,
+    };
+    for index in 0..repeat {
+        if index > 0 {
+            std::thread::sleep(interval);
+        }
+        if stop_signal.load(Ordering::SeqCst) {
+            response.stopped = true;
+            break;
+        }
+        match controller.write_raw_report(report) {
+            Ok(_) => response.written += 1,
+            Err(e) => {
+                warn!(
+                    "Raw HID report {} of {} was not written: {}",
+                    index + 1,
+                    repeat,
+                    e
+                );
+                response.error = Some(ReportErrorClass::of(&e));
+                response.error_message = Some(e.to_string());
+                break;
+            }
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::TestClient;
+    use super::*;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    #[test]
+    fn test_parse_hex_report_ignores_whitespace_and_rejects_bad_digits() {
+        assert_eq!(
+            parse_hex_report("00 04 08 80\n80 80 80 00"),
+            Ok(vec![0x00, 0x04, 0x08, 0x80, 0x80, 0x80, 0x80, 0x00])
+        );
+        assert_eq!(parse_hex_report("aB"), Ok(vec![0xAB]));
+        assert!(parse_hex_report("").is_err());
+        assert!(parse_hex_report("+f").is_err());
+        assert!(parse_hex_report("0x00").is_err());
+        assert!(parse_hex_report("000").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_hid_report_injection_requires_debug_mode_and_an_armed_controller() {
+        let press_a =
+            serde_json::json!({ "bytes": "0400088080808000", "repeat": 3, "interval_ms": 0 });
+        let mock = Arc::new(MockController::new().without_delays());
+        let client = TestClient::new(Arc::new(ArtworkState::new(mock.clone())));
+        let response = client.post("/api/debug/hid-report", press_a.clone()).await;
+        assert_eq!(response.status, StatusCode::SERVICE_UNAVAILABLE);
+
+        let state = Arc::new(ArtworkState::new(mock.clone()).with_debug_endpoints());
+        let client = TestClient::new(state.clone());
+        let response = client.post("/api/debug/hid-report", press_a.clone()).await;
+        assert_eq!(response.status, StatusCode::LOCKED);
+
+        state.interlock.arm("test", None);
+        for (body, message) in [
+            (
+                serde_json::json!({ "bytes": "04000880808080" }),
+                "The report is 7 bytes but the HID function's report_length is 8",
+            ),
+            (
+                serde_json::json!({ "bytes": "zz00088080808000" }),
+                "bytes must be hexadecimal, found 'z'",
+            ),
+            (
+                serde_json::json!({ "bytes": "0400088080808000", "repeat": 0 }),
+                "repeat must be between 1 and 1000",
+            ),
+        ] {
+            let response = client.post("/api/debug/hid-report", body).await;
+            assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+            assert_eq!(response.message(), message);
+        }
+
+        let response = client.post("/api/debug/hid-report", press_a.clone()).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        assert_eq!(
+            response.json(),
+            serde_json::json!({
+                "written": 3,
+                "report_length": 8,
+                "stopped": false,
+                "error": null,
+                "error_message": null
+            })
+        );
+        assert_eq!(
+            mock.last_report(),
+            Some([0x04, 0x00, 0x08, 0x80, 0x80, 0x80, 0x80, 0x00])
+        );
+        // 送信が終われば描画として登録されたままにならない
+        state.ensure_no_active_painting().await.unwrap();
+
+        // 描画の実行中は送らない
+        let control = PaintingControl::new(1, 0, 0, 0);
+        state.begin_painting(&control).await.unwrap();
+        let response = client.post("/api/debug/hid-report", press_a).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        release_active_painting(&state.active_painting, &control).await;
+    }
+
+    #[test]
+    fn test_write_reports_stops_at_the_first_failure_or_stop_signal() {
+        let mock = MockController::new()
+            .without_delays()
+            .with_host_sleep_after(0);
+        let response = write_reports(&mock, &[0; 8], 5, Duration::ZERO, &AtomicBool::new(false));
+        assert_eq!(response.written, 0);
+        assert_eq!(response.error, Some(ReportErrorClass::WouldBlock));
+        assert!(response.error_message.is_some());
+
+        let response = write_reports(
+            &MockController::new(),
+            &[0; 8],
+            5,
+            Duration::ZERO,
+            &AtomicBool::new(true),
+        );
+        assert_eq!((response.written, response.stopped), (0, true));
+    }
+}
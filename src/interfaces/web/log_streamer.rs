@@ -1,3 +1,5 @@
+use super::gallery::{GALLERY_PROGRESS_INTERVAL, GalleryMode, GalleryUpdate, gallery_update};
+use crate::domain::shared::i18n::{Locale, relocalize_json};
 use axum::extract::ws::{Message, WebSocket};
 use chrono::Utc;
 use serde_json::json;
@@ -10,13 +12,10 @@ lazy_static::lazy_static! {
         let (tx, _) = broadcast::channel(100);
         tx
     };
-
-    pub static ref PROGRESS_CHANNEL: broadcast::Sender<String> = {
-        let (tx, _) = broadcast::channel(100);
-        tx
-    };
 }
 
+pub use crate::application::progress::PROGRESS_CHANNEL;
+
 /// Custom tracing subscriber layer to capture logs
 pub struct LogCaptureLayer;
 
@@ -77,7 +76,9 @@ impl tracing::field::Visit for JsonVisitor {
 }
 
 /// Stream logs to WebSocket connection
-pub async fn stream_logs(mut socket: WebSocket) {
+///
+/// 進捗の文言は接続の言語（`locale`）で組み立て直して送る。
+pub async fn stream_logs(mut socket: WebSocket, locale: Locale) {
     info!("Starting log streaming");
 
     // Subscribe to channels
@@ -125,6 +126,7 @@ pub async fn stream_logs(mut socket: WebSocket) {
             result = progress_rx.recv() => {
                 match result {
                     Ok(msg) => {
+                        let msg = relocalize_json(&msg, locale).unwrap_or(msg);
                         if socket.send(Message::Text(msg.into())).await.is_err() {
                             break;
                         }
@@ -156,3 +158,53 @@ pub async fn stream_logs(mut socket: WebSocket) {
 
     info!("Log streaming ended");
 }
+
+/// ギャラリーの視聴者に、公開する進捗だけを間引いて送る
+///
+/// ログは送らず、クライアントからのメッセージは無視する。ギャラリーモードが無効にされたら切断する。
+pub async fn stream_gallery(mut socket: WebSocket, gallery: GalleryMode) {
+    let mut progress_rx = PROGRESS_CHANNEL.subscribe();
+    let mut ticker = tokio::time::interval(GALLERY_PROGRESS_INTERVAL);
+    ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+    // 送信を待っている最新のカーソル移動
+    let mut pending: Option<String> = None;
+
+    loop {
+        let outgoing = tokio::select! {
+            result = progress_rx.recv() => match result {
+                Ok(msg) => match gallery_update(&msg) {
+                    Some(GalleryUpdate::Progress(progress)) => {
+                        pending = Some(progress);
+                        continue;
+                    }
+                    // 出来事より前のカーソル移動を先に送り、順序を保つ
+                    Some(GalleryUpdate::Event(event)) => {
+                        pending.take().into_iter().chain([event]).collect()
+                    }
+                    None => continue,
+                },
+                Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                Err(broadcast::error::RecvError::Closed) => break,
+            },
+
+            _ = ticker.tick() => {
+                if !gallery.is_enabled() {
+                    let _ = socket.send(Message::Close(None)).await;
+                    break;
+                }
+                pending.take().into_iter().collect::<Vec<_>>()
+            }
+
+            msg = socket.recv() => match msg {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                _ => continue,
+            },
+        };
+
+        for msg in outgoing {
+            if socket.send(Message::Text(msg.into())).await.is_err() {
+                return;
+            }
+        }
+    }
+}
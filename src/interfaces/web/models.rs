@@ -1,15 +1,44 @@
+use super::dto::ScheduledPaintingStatus;
+use crate::application::use_cases::{BenchmarkReport, DataResetReport};
+use crate::domain::controller::{Button, DPad, ManualInputKind};
+use crate::domain::painting::{CalibrationLayout, CalibrationPattern, CalibrationPlan};
+use crate::domain::setup::entities::{FixConnectionStep, GadgetAuditEntry};
+use crate::domain::shared::value_objects::Coordinates;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct SystemInfo {
     pub version: String,
     pub rust_version: String,
     pub os: String,
     pub arch: String,
     pub uptime_seconds: u64,
+    /// 実機ではなくMockControllerで動作しているか
+    pub simulation: bool,
+    /// シミュレーション中に描画・キャリブレーションを拒否するか
+    pub strict_simulation: bool,
+    /// セーフモードでシステムのパス（configfs・ブート設定・systemd・HIDデバイス）に書き込まないか
+    pub safe_mode: bool,
+    /// 直近のベンチマークの結果（`suggested_timing` が押下・離す・待機時間の下限の目安）
+    pub benchmark: Option<BenchmarkReport>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// バックエンドとWebUIのバージョン（フロントエンドが更新を検知して再読み込みを促すため）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VersionInfo {
+    /// クレートのバージョン
+    pub version: String,
+    /// ビルド元のコミット（短縮ハッシュ。不明な場合は `unknown`）
+    pub git_hash: String,
+    pub build_timestamp: String,
+    /// 配信中のWebUIアセット全体のフィンガープリント
+    pub assets_fingerprint: String,
+    /// `--assets-dir` で外部ディレクトリのアセットを配信しているか
+    pub assets_overridden: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct HardwareStatus {
     pub nintendo_switch_connected: bool,
     pub usb_otg_available: bool,
@@ -18,7 +47,7 @@ pub struct HardwareStatus {
     pub details: HardwareDetails,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct HardwareDetails {
     pub board_model: Option<String>,
     pub usb_gadget_configured: bool,
@@ -26,29 +55,351 @@ pub struct HardwareDetails {
     pub kernel_modules_loaded: Vec<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CalibrationRequest {
     pub press_ms: u32,
     pub release_ms: u32,
     pub wait_ms: u32,
     #[serde(default)]
     pub skip_initialization: bool,
+    /// 速度キャリブレーションで描画するパターン（既定: `horizontal`）
+    #[serde(default)]
+    pub pattern: CalibrationPattern,
+    /// 行数（`vertical` では列数）
+    #[serde(default = "default_calibration_rows")]
+    pub rows: u16,
+    /// 1行の長さ（ピクセル）
+    #[serde(default = "default_calibration_width")]
+    pub width: u16,
+    /// 行間・点の間隔（ピクセル）
+    #[serde(default = "default_calibration_spacing")]
+    pub spacing: u16,
+    /// 描き終えてから `cleanup_delay_secs` 秒後に、描いたドットを自動で消す
+    #[serde(default)]
+    pub auto_cleanup: bool,
+    /// 自動で消すまでの待ち時間（秒、結果を撮影するための時間。既定10秒、最大600秒）
+    #[serde(default = "default_cleanup_delay_secs")]
+    pub cleanup_delay_secs: u32,
+}
+
+/// `auto_cleanup` で消すまでの待ち時間の上限（秒）
+pub const MAX_CLEANUP_DELAY_SECS: u32 = 600;
+
+fn default_cleanup_delay_secs() -> u32 {
+    10
+}
+
+fn default_calibration_rows() -> u16 {
+    CalibrationLayout::default().rows
+}
+
+fn default_calibration_width() -> u16 {
+    CalibrationLayout::default().width
+}
+
+fn default_calibration_spacing() -> u16 {
+    CalibrationLayout::default().spacing
+}
+
+impl CalibrationRequest {
+    /// 速度キャリブレーションのパターン形状
+    pub fn layout(&self) -> CalibrationLayout {
+        CalibrationLayout {
+            pattern: self.pattern,
+            rows: self.rows,
+            width: self.width,
+            spacing: self.spacing,
+        }
+    }
 }
 
 impl Default for CalibrationRequest {
     fn default() -> Self {
+        let layout = CalibrationLayout::default();
         Self {
             press_ms: 50,
             release_ms: 30,
             wait_ms: 20,
             skip_initialization: false,
+            pattern: layout.pattern,
+            rows: layout.rows,
+            width: layout.width,
+            spacing: layout.spacing,
+            auto_cleanup: false,
+            cleanup_delay_secs: default_cleanup_delay_secs(),
+        }
+    }
+}
+
+/// 速度キャリブレーション開始時のレスポンス
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CalibrationStartResponse {
+    pub success: bool,
+    pub message: String,
+    /// 後片付け（`POST /api/calibration/cleanup`）に指定するセッションID
+    pub session_id: String,
+    pub pattern: CalibrationPattern,
+    /// パターン左上のキャンバス座標（`skip_initialization` 時は現在のカーソル位置に相当）
+    pub origin: Coordinates,
+    pub width: u16,
+    pub height: u16,
+    /// 描画順に並べた期待されるドットのキャンバス座標
+    pub expected_dots: Vec<Coordinates>,
+}
+
+impl CalibrationStartResponse {
+    pub fn new(session_id: String, pattern: CalibrationPattern, plan: CalibrationPlan) -> Self {
+        Self {
+            success: true,
+            message: "Speed calibration test started".to_string(),
+            session_id,
+            pattern,
+            origin: plan.origin,
+            width: plan.width,
+            height: plan.height,
+            expected_dots: plan.dots,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// キャリブレーションで描いたドットを消す
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CalibrationCleanupRequest {
+    /// キャリブレーション開始時のレスポンスの `session_id`
+    pub session_id: String,
+    /// 省略時はキャリブレーションと同じ速度
+    pub press_ms: Option<u32>,
+    pub release_ms: Option<u32>,
+    pub wait_ms: Option<u32>,
+}
+
+/// 後片付けの開始時のレスポンス
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CalibrationCleanupResponse {
+    pub success: bool,
+    pub message: String,
+    pub session_id: String,
+    /// 消すドットの数
+    pub dots: usize,
+}
+
+/// Web UIからのログイン（成功するとセッションCookieを発行する）
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoginRequest {
+    /// `run` 起動時・`info` で表示されるアクセストークン
+    pub token: String,
+}
+
+/// ログフィルターの変更
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LogLevelRequest {
+    /// `RUST_LOG` と同じ書式（`debug`、`info,splatoon3_ghost_drawer::infrastructure::hardware=trace` など）
+    pub level: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LogLevelResponse {
+    pub level: String,
+    /// 変更前のフィルター
+    pub previous: String,
+}
+
+/// ギャラリーモードの切り替え
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GalleryModeRequest {
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct GalleryModeResponse {
+    pub enabled: bool,
+    /// 変更前の状態（取得時は `enabled` と同じ）
+    pub previous: bool,
+}
+
+/// Webhookのテスト送信の結果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookTestResponse {
+    /// 設定したURLごとの送信結果（設定の順）
+    pub deliveries: Vec<WebhookDeliveryStatus>,
+}
+
+/// 1つのWebhookへの送信結果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct WebhookDeliveryStatus {
+    /// 送信先のホスト名（URLのパスやクエリは含まない）
+    pub url: String,
+    pub delivered: bool,
+    /// リトライを含めた送信回数
+    pub attempts: u32,
+    /// 最後の応答のHTTPステータス（接続できなかった場合は `None`）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct UpdateTimingRequest {
     pub press_ms: u32,
     pub release_ms: u32,
     pub wait_ms: u32,
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FixConnectionStartResponse {
+    pub session_id: String,
+    /// 実行する手順（進捗はWebSocketの `fix_connection` メッセージで通知）
+    pub steps: Vec<FixConnectionStep>,
+}
+
+/// ガジェット再接続後に接続を待つ時間の既定値（ミリ秒）
+pub const DEFAULT_RECONNECT_TIMEOUT_MS: u64 = 10_000;
+/// ガジェット再接続後に接続を待つ時間の上限（ミリ秒）
+pub const MAX_RECONNECT_TIMEOUT_MS: u64 = 60_000;
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReconnectGadgetQuery {
+    /// 再接続後に接続を待つ最大時間（ミリ秒、1〜60000、既定は10000）
+    #[serde(default = "default_reconnect_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for ReconnectGadgetQuery {
+    fn default() -> Self {
+        Self {
+            timeout_ms: DEFAULT_RECONNECT_TIMEOUT_MS,
+        }
+    }
+}
+
+fn default_reconnect_timeout_ms() -> u64 {
+    DEFAULT_RECONNECT_TIMEOUT_MS
+}
+
+/// 監査ログから返す件数の既定値
+pub const DEFAULT_AUDIT_LIMIT: usize = 50;
+/// 監査ログから返す件数の上限
+pub const MAX_AUDIT_LIMIT: usize = 1000;
+
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogQuery {
+    /// 新しいものから返す件数（1〜1000、既定は50）
+    #[serde(default = "default_audit_limit")]
+    pub limit: usize,
+}
+
+fn default_audit_limit() -> usize {
+    DEFAULT_AUDIT_LIMIT
+}
+
+/// ガジェットの状態を変えた操作の記録
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct AuditLogResponse {
+    /// 古いものから順に並べた記録
+    pub entries: Vec<GadgetAuditEntry>,
+}
+
+/// ガジェット再接続の結果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ReconnectGadgetResponse {
+    /// 待ち時間内にSwitchとの接続を確認できたか
+    pub connected: bool,
+    /// ガジェットの切断から再バインドまでにかかった時間（ミリ秒）
+    pub reconnect_ms: u64,
+    /// 再バインド後に接続を待った時間（ミリ秒）
+    pub wait_ms: u64,
+}
+
+/// データディレクトリの初期化の要求
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResetDataRequest {
+    /// 誤操作を防ぐための確認（`"reset-all-data"` のみ受け付ける）
+    pub confirm: String,
+}
+
+/// データディレクトリの初期化の結果
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ResetDataResponse {
+    /// 削除したアートワークの数
+    pub artworks_removed: usize,
+    /// 削除した描画履歴の数
+    pub painting_runs_removed: usize,
+    /// 取り消した描画の予約（予約が無ければ `None`）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cancelled_schedule: Option<ScheduledPaintingStatus>,
+    /// データディレクトリから削除したファイル
+    pub files: DataResetReport,
+    /// アクセストークンを作り直したか（以降は新しいトークンが必要。認証が無効なら `false`）
+    pub auth_token_regenerated: bool,
+}
+
+/// 手動操作で送る単一の入力
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ControllerInputRequest {
+    #[serde(rename = "type")]
+    pub kind: ManualInputKind,
+    /// ボタン名（`a`、`zl` など）または方向名（`up`、`down_left` など、大文字小文字は区別しない）
+    pub value: String,
+    /// 押し続ける時間（ミリ秒、最大3000）
+    #[serde(default = "default_input_duration_ms")]
+    pub duration_ms: u32,
+}
+
+fn default_input_duration_ms() -> u32 {
+    100
+}
+
+/// コントローラー出力をアームする要求
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ArmControllerRequest {
+    /// 自動で解除するまでの時間（ミリ秒、省略すると解除するまで有効）
+    #[serde(default)]
+    pub auto_disarm_ms: Option<u64>,
+}
+
+/// コントローラー出力の安全装置の状態
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ControllerStatus {
+    /// 描画・キャリブレーション・手動入力を実行できるか
+    pub armed: bool,
+    /// アームした操作者（認証が有効ならトークンの識別子、無効なら `anonymous`）
+    pub armed_by: Option<String>,
+    /// 自動で解除されるまでの残り時間（ミリ秒）
+    pub auto_disarm_in_ms: Option<u64>,
+    /// 描画・キャリブレーションの実行中か
+    pub painting_active: bool,
+}
+
+/// 手動操作で使えるボタン名・方向名とスティックの範囲
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ControllerCapabilities {
+    /// ボタン名（`type: "button"` の `value` に使う）
+    pub buttons: Vec<Button>,
+    /// 十字キーの値（`neutral` は手動入力では使えない）
+    pub dpad: Vec<DPad>,
+    /// スティックの軸の範囲
+    pub stick: StickRange,
+    /// 手動入力の種類
+    pub input_kinds: Vec<ManualInputKind>,
+    /// 1回の入力で押し続けられる最大時間（ミリ秒）
+    pub max_duration_ms: u32,
+}
+
+/// スティックの各軸が取る値の範囲
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct StickRange {
+    pub min: u8,
+    pub max: u8,
+    pub center: u8,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ControllerInputResponse {
+    /// 上限で丸めた後の入力時間（ミリ秒）
+    pub duration_ms: u32,
+    /// 入力時と解除時に送信したHIDレポート（`04 00 08 80 80 80 80 00` 形式）
+    pub reports: Vec<String>,
+}
@@ -0,0 +1,523 @@
+use super::artwork_sets::ArtworkSetResponse;
+use super::artworks::{
+    AnalysisPathSummary, ArtworkAnalysisResponse, ArtworkDetailResponse, ArtworkDiffResponse,
+    ArtworkHistoryResponse, ArtworkResponse, ArtworkSummary, BulkDotsResponse,
+    CancelComputationsResponse, CanvasHistoryResponse, CanvasWireFormat, ChangeLogEntryResponse,
+    CompactArtworkResponse, CompactCanvas, CreateArtworkRequest, DiffDots, DotData,
+    DuplicateArtworkRequest, DuplicateDotPolicy, EdgeDetectionSummary, GenerateArtworkRequest,
+    ImportScriptRequest, PathResponse, PathStats, PurgeTrashResponse, StrategyComparisonMode,
+    TestPattern, ToneMode, TransformArtworkRequest, TransformArtworkResponse, TrashResponse,
+    TrashedArtworkSummary, UpdateMetadataRequest, UpdateVectorPathsRequest,
+};
+use super::convert_preview::{ConversionPreviewResponse, StagePreview};
+use super::dto::{
+    ApiResponse, EstimateAccuracy, EstimateModelResponse, GalleryCompletion, GalleryState,
+    GalleryThumbnail, LayerStats, PaintStartResponse, PaintingConfigResponse, PaintingRunResponse,
+    PaintingSignalResponse, PaintingStatus, PreflightStatus, ScheduledPaintingStatus,
+    StopAfterStatus, StrategyComparisonResponse, StrategyStats, VectorPaintStartResponse,
+};
+use super::error_response::ErrorResponse;
+use super::hid_debug::{HidReportInjectionRequest, HidReportInjectionResponse};
+use super::models::{
+    ArmControllerRequest, AuditLogResponse, CalibrationCleanupRequest, CalibrationCleanupResponse,
+    CalibrationRequest, CalibrationStartResponse, ControllerCapabilities, ControllerInputRequest,
+    ControllerInputResponse, ControllerStatus, FixConnectionStartResponse, GalleryModeRequest,
+    GalleryModeResponse, HardwareDetails, HardwareStatus, LogLevelRequest, LogLevelResponse,
+    LoginRequest, ReconnectGadgetResponse, ResetDataRequest, ResetDataResponse, StickRange,
+    SystemInfo, UpdateTimingRequest, VersionInfo, WebhookDeliveryStatus, WebhookTestResponse,
+};
+use super::painting::{
+    ConfirmPreflightRequest, PaintDiffRequest, PaintRequest, UpdateRepeatsRequest,
+    VectorPaintRequest,
+};
+use crate::application::metrics::{MetricsReport, MetricsSnapshot, RunMetrics};
+use crate::application::use_cases::{
+    BenchmarkReport, DataEntryKind, DataResetReport, LatencyPercentiles, PathGenerationBenchmark,
+    RemovedDataEntry, ReportLoopBenchmark, ReportWriteBenchmark,
+};
+use crate::domain::artwork::change_log::ChangeKind;
+use crate::domain::artwork::entities::{ArtworkSetMembership, ArtworkStatistics};
+use crate::domain::artwork::value_objects::{
+    CanvasTransform, Gravity, Placement, Polyline, ScalingMode, TransparencyMode,
+};
+use crate::domain::controller::{Button, DPad, ManualInputKind};
+use crate::domain::hardware::{GadgetState, HidDeviceNode, ReportDescriptorDump, ReportErrorClass};
+use crate::domain::painting::{
+    CalibrationPattern, CanvasRegion, CompletionReport, DrawingMode, DrawingStrategy,
+    EstimateBucket, FightstickFormat, InitPreset, InitSequence, InitStep, PaintTiming,
+    PaintingPreferences, PauseMode, PreflightRejection, RunOutcome, ScriptFormat, SkippedDot,
+    StopAfter, StopLimit, StopLimits, TwoOptStats, TwoOptStopReason,
+};
+use crate::domain::setup::entities::{
+    AuditInitiator, FixConnectionOutcome, FixConnectionStep, FixConnectionStepResult,
+    GadgetAuditEntry, GadgetOperation,
+};
+use crate::domain::shared::value_objects::Coordinates;
+use utoipa::OpenApi;
+use utoipa_swagger_ui::SwaggerUi;
+
+/// OpenAPI仕様の公開パス
+pub const OPENAPI_JSON_PATH: &str = "/api/openapi.json";
+/// Swagger UIの公開パス
+pub const SWAGGER_UI_PATH: &str = "/api/docs";
+
+/// Web APIのOpenAPI仕様
+///
+/// ハンドラーの `#[utoipa::path]` とDTOの `ToSchema` から生成する。
+/// エンドポイントを追加したらここにも登録すること（テストで未登録を検出する）。
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Splatoon3 Ghost Drawer API"),
+    paths(
+        super::handlers::get_system_info,
+        super::handlers::get_version,
+        super::handlers::get_hardware_status,
+        super::handlers::start_fix_connection,
+        super::handlers::abort_fix_connection,
+        super::handlers::reconnect_gadget,
+        super::handlers::get_audit_log,
+        super::handlers::get_gadget_state,
+        super::handlers::reset_data,
+        super::handlers::run_benchmark,
+        super::handlers::get_metrics,
+        super::handlers::get_prometheus_metrics,
+        super::controller::send_controller_input,
+        super::controller::get_controller_status,
+        super::controller::get_controller_capabilities,
+        super::controller::arm_controller,
+        super::controller::disarm_controller,
+        super::hid_debug::inject_hid_report,
+        super::handlers::login,
+        super::handlers::set_log_level,
+        super::handlers::test_webhooks,
+        super::handlers::get_gallery_mode,
+        super::handlers::set_gallery_mode,
+        super::handlers::get_gallery_state,
+        super::artworks::list_artworks,
+        super::artworks::create_artwork,
+        super::artworks::upload_artwork,
+        super::artwork_sets::get_artwork_set,
+        super::artwork_sets::delete_artwork_set,
+        super::artworks::generate_artwork,
+        super::artworks::import_script,
+        super::convert_preview::preview_conversion,
+        super::artworks::get_artwork,
+        super::artworks::delete_artwork,
+        super::artworks::list_trash,
+        super::artworks::purge_trash,
+        super::artworks::restore_artwork,
+        super::artworks::duplicate_artwork,
+        super::artworks::transform_artwork,
+        super::artworks::get_artwork_diff,
+        super::artworks::update_artwork_metadata,
+        super::artworks::update_painting_preferences,
+        super::artworks::update_vector_paths,
+        super::artworks::compact_artwork,
+        super::artworks::apply_dot_diff,
+        super::artworks::undo_artwork_edit,
+        super::artworks::redo_artwork_edit,
+        super::artworks::get_artwork_path,
+        super::artworks::get_artwork_strategies,
+        super::artworks::cancel_artwork_computations,
+        super::artworks::get_artwork_analysis,
+        super::artworks::export_artwork,
+        super::artworks::export_fightstick,
+        super::artworks::get_artwork_history,
+        super::painting::list_artwork_runs,
+        super::painting::list_painting_runs,
+        super::painting::get_painting_trace,
+        super::painting::paint_artwork,
+        super::painting::paint_artwork_diff,
+        super::painting::paint_artwork_vector,
+        super::painting::get_painting_status,
+        super::painting::stop_painting,
+        super::painting::pause_painting,
+        super::painting::confirm_preflight,
+        super::painting::cancel_scheduled_painting,
+        super::painting::get_estimate_model,
+        super::painting::reset_estimate_model,
+        super::painting::update_painting_repeats,
+        super::painting::update_painting_timing,
+        super::calibration::start_calibration,
+        super::calibration::cleanup_calibration,
+        super::calibration::start_paint_move_test,
+        super::calibration::start_gap_move_test,
+    ),
+    components(schemas(
+        AnalysisPathSummary,
+        ApiResponse,
+        ArmControllerRequest,
+        ArtworkAnalysisResponse,
+        ArtworkDetailResponse,
+        ArtworkDiffResponse,
+        ArtworkHistoryResponse,
+        ArtworkResponse,
+        ArtworkSetMembership,
+        ArtworkSetResponse,
+        ArtworkStatistics,
+        ArtworkSummary,
+        AuditInitiator,
+        AuditLogResponse,
+        BulkDotsResponse,
+        Button,
+        CalibrationCleanupRequest,
+        CalibrationCleanupResponse,
+        BenchmarkReport,
+        CalibrationPattern,
+        CalibrationRequest,
+        CalibrationStartResponse,
+        CancelComputationsResponse,
+        CanvasHistoryResponse,
+        CanvasRegion,
+        CanvasTransform,
+        CanvasWireFormat,
+        ChangeKind,
+        ChangeLogEntryResponse,
+        CompactArtworkResponse,
+        CompactCanvas,
+        CompletionReport,
+        ConfirmPreflightRequest,
+        ControllerCapabilities,
+        ControllerInputRequest,
+        ControllerInputResponse,
+        ControllerStatus,
+        ConversionPreviewResponse,
+        Coordinates,
+        CreateArtworkRequest,
+        DPad,
+        DataEntryKind,
+        DataResetReport,
+        DiffDots,
+        DotData,
+        DrawingMode,
+        DrawingStrategy,
+        DuplicateArtworkRequest,
+        DuplicateDotPolicy,
+        EdgeDetectionSummary,
+        ErrorResponse,
+        EstimateAccuracy,
+        EstimateBucket,
+        EstimateModelResponse,
+        FightstickFormat,
+        FixConnectionOutcome,
+        FixConnectionStartResponse,
+        FixConnectionStep,
+        FixConnectionStepResult,
+        GadgetAuditEntry,
+        GadgetOperation,
+        GadgetState,
+        GalleryCompletion,
+        GalleryModeRequest,
+        GalleryModeResponse,
+        GalleryState,
+        GalleryThumbnail,
+        GenerateArtworkRequest,
+        Gravity,
+        HardwareDetails,
+        HardwareStatus,
+        HidDeviceNode,
+        HidReportInjectionRequest,
+        HidReportInjectionResponse,
+        ImportScriptRequest,
+        InitPreset,
+        InitSequence,
+        InitStep,
+        LatencyPercentiles,
+        LayerStats,
+        LogLevelRequest,
+        LogLevelResponse,
+        LoginRequest,
+        ManualInputKind,
+        MetricsReport,
+        MetricsSnapshot,
+        PaintDiffRequest,
+        PaintRequest,
+        PaintStartResponse,
+        PaintTiming,
+        PaintingConfigResponse,
+        PaintingPreferences,
+        PaintingRunResponse,
+        PaintingSignalResponse,
+        PaintingStatus,
+        PathGenerationBenchmark,
+        PathResponse,
+        PathStats,
+        PauseMode,
+        Placement,
+        Polyline,
+        PreflightRejection,
+        PreflightStatus,
+        PurgeTrashResponse,
+        ReconnectGadgetResponse,
+        RemovedDataEntry,
+        ReportDescriptorDump,
+        ReportErrorClass,
+        ReportLoopBenchmark,
+        ReportWriteBenchmark,
+        ResetDataRequest,
+        ResetDataResponse,
+        RunMetrics,
+        RunOutcome,
+        ScalingMode,
+        ScheduledPaintingStatus,
+        ScriptFormat,
+        SkippedDot,
+        StagePreview,
+        StopAfter,
+        StopAfterStatus,
+        StopLimit,
+        StickRange,
+        StopLimits,
+        StrategyComparisonMode,
+        StrategyComparisonResponse,
+        StrategyStats,
+        SystemInfo,
+        TestPattern,
+        ToneMode,
+        TransformArtworkRequest,
+        TransformArtworkResponse,
+        TransparencyMode,
+        TrashResponse,
+        TrashedArtworkSummary,
+        TwoOptStats,
+        TwoOptStopReason,
+        UpdateMetadataRequest,
+        UpdateRepeatsRequest,
+        UpdateTimingRequest,
+        UpdateVectorPathsRequest,
+        VectorPaintRequest,
+        VectorPaintStartResponse,
+        VersionInfo,
+        WebhookDeliveryStatus,
+        WebhookTestResponse,
+    )),
+    tags(
+        (name = "artworks", description = "アートワークの管理と描画パスの見積もり"),
+        (name = "painting", description = "描画の開始と実行中の制御"),
+        (name = "calibration", description = "速度キャリブレーションと移動テスト"),
+        (name = "controller", description = "コントローラーの手動操作"),
+        (name = "system", description = "システムとハードウェアの状態"),
+        (name = "gallery", description = "認証無しで公開する読み取り専用の進捗"),
+    )
+)]
+pub struct ApiDoc;
+
+/// 仕様JSONとSwagger UI（埋め込みアセット）を配信するルーター
+pub fn swagger_ui() -> SwaggerUi {
+    SwaggerUi::new(SWAGGER_UI_PATH).url(OPENAPI_JSON_PATH, ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use std::collections::BTreeSet;
+
+    fn spec() -> Value {
+        serde_json::to_value(ApiDoc::openapi()).unwrap()
+    }
+
+    fn collect_refs(value: &Value, refs: &mut BTreeSet<String>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    refs.insert(reference.clone());
+                }
+                map.values().for_each(|v| collect_refs(v, refs));
+            }
+            Value::Array(items) => items.iter().for_each(|v| collect_refs(v, refs)),
+            _ => {}
+        }
+    }
+
+    #[test]
+    fn test_spec_documents_all_api_routes() {
+        let spec = spec();
+        let documented: BTreeSet<&str> = spec["paths"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+
+        // router.rs の build_router に登録されているAPI（/api/health と WebSocket を除く）
+        let routed = BTreeSet::from([
+            "/api/system/info",
+            "/api/version",
+            "/api/hardware/status",
+            "/api/system/fix-connection/start",
+            "/api/system/fix-connection/abort",
+            "/api/system/reconnect-gadget",
+            "/api/system/audit",
+            "/api/system/gadget",
+            "/api/system/reset-data",
+            "/api/system/benchmark",
+            "/api/metrics",
+            "/metrics",
+            "/api/settings/webhooks/test",
+            "/api/artworks",
+            "/api/artworks/upload",
+            "/api/artwork-sets/{set_id}",
+            "/api/artworks/generate",
+            "/api/artworks/import-script",
+            "/api/convert/preview",
+            "/api/artworks/{id}",
+            "/api/artworks/trash",
+            "/api/artworks/trash/purge",
+            "/api/artworks/{id}/restore",
+            "/api/artworks/{id}/metadata",
+            "/api/artworks/{id}/preferences",
+            "/api/artworks/{id}/vector-paths",
+            "/api/artworks/{id}/compact",
+            "/api/artworks/{id}/dots:bulk",
+            "/api/artworks/{id}/undo",
+            "/api/artworks/{id}/redo",
+            "/api/artworks/{id}/duplicate",
+            "/api/artworks/{id}/transform",
+            "/api/artworks/{a}/diff/{b}",
+            "/api/artworks/{id}/path",
+            "/api/artworks/{id}/strategies",
+            "/api/artworks/{id}/strategies/compute",
+            "/api/artworks/{id}/analysis",
+            "/api/artworks/{id}/export",
+            "/api/artworks/{id}/export/fightstick",
+            "/api/artworks/{id}/history",
+            "/api/artworks/{id}/runs",
+            "/api/painting/runs",
+            "/api/painting/runs/{run_id}/trace.csv",
+            "/api/painting/status",
+            "/api/artworks/{id}/paint",
+            "/api/artworks/{id}/paint-diff",
+            "/api/artworks/{id}/paint-vector",
+            "/api/painting/repeats",
+            "/api/painting/timing",
+            "/api/painting/scheduled",
+            "/api/painting/estimate-model",
+            "/api/painting/stop",
+            "/api/painting/pause",
+            "/api/painting/confirm-preflight",
+            "/api/calibration/start",
+            "/api/calibration/cleanup",
+            "/api/calibration/test/paint-move",
+            "/api/calibration/test/gap-move",
+            "/api/controller/input",
+            "/api/controller/status",
+            "/api/controller/capabilities",
+            "/api/controller/arm",
+            "/api/controller/disarm",
+            "/api/debug/hid-report",
+            "/api/auth/login",
+            "/api/system/log-level",
+            "/api/system/gallery",
+            "/api/gallery/state",
+        ]);
+        assert_eq!(documented, routed);
+    }
+
+    #[test]
+    fn test_artwork_analysis_schema_keeps_field_names() {
+        // WebUIの描画前チェックリストが参照する項目
+        let spec = spec();
+        let schemas = &spec["components"]["schemas"];
+        let required = |name: &str| -> BTreeSet<String> {
+            schemas[name]["required"]
+                .as_array()
+                .unwrap_or_else(|| panic!("{name} has no required fields"))
+                .iter()
+                .map(|v| v.as_str().unwrap().to_string())
+                .collect()
+        };
+        let expected = |fields: &[&str]| fields.iter().map(|f| f.to_string()).collect();
+
+        assert_eq!(
+            required("ArtworkAnalysisResponse"),
+            expected(&[
+                "id",
+                "version",
+                "statistics",
+                "tile_size",
+                "tiles",
+                "isolated_dots",
+                "path"
+            ])
+        );
+        assert!(
+            schemas["ArtworkAnalysisResponse"]["properties"]
+                .get("bounding_box")
+                .is_some()
+        );
+        assert_eq!(
+            required("AnalysisPathSummary"),
+            expected(&[
+                "strategy",
+                "dots",
+                "total_distance",
+                "longest_move",
+                "estimated_time_seconds",
+                "path_hash"
+            ])
+        );
+        assert_eq!(
+            required("ArtworkStatistics"),
+            expected(&[
+                "total_dots",
+                "drawable_dots",
+                "painted_dots",
+                "unique_colors",
+                "completion_ratio",
+                "complexity_score",
+                "canvas_size"
+            ])
+        );
+    }
+
+    #[test]
+    fn test_spec_references_resolve() {
+        let spec = spec();
+        let mut refs = BTreeSet::new();
+        collect_refs(&spec, &mut refs);
+        assert!(!refs.is_empty());
+
+        for reference in refs {
+            let name = reference
+                .strip_prefix("#/components/schemas/")
+                .unwrap_or_else(|| panic!("unexpected reference {reference}"));
+            assert!(
+                spec["components"]["schemas"].get(name).is_some(),
+                "{reference} is not defined in components"
+            );
+        }
+    }
+
+    #[test]
+    fn test_enums_use_serde_names() {
+        let spec = spec();
+        let schemas = &spec["components"]["schemas"];
+
+        let strategies: Vec<&str> = schemas["DrawingStrategy"]["enum"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|v| v.as_str().unwrap())
+            .collect();
+        for strategy in strategies.iter() {
+            let parsed: DrawingStrategy =
+                serde_json::from_value(Value::String(strategy.to_string())).unwrap();
+            assert_eq!(serde_json::to_value(parsed).unwrap(), *strategy);
+        }
+        assert_eq!(strategies.len(), 5);
+
+        assert_eq!(
+            schemas["ToneMode"]["enum"],
+            serde_json::json!(["binary", "stipple2", "stipple4"])
+        );
+        assert_eq!(
+            schemas["DuplicateDotPolicy"]["enum"],
+            serde_json::json!(["reject", "last_wins", "first_wins"])
+        );
+
+        let transforms = serde_json::to_string(&schemas["CanvasTransform"]).unwrap();
+        assert!(transforms.contains("flip_horizontal"));
+        assert!(transforms.contains("threshold"));
+    }
+}
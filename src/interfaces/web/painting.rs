@@ -0,0 +1,3335 @@
+//! 描画の開始・停止・一時停止と描画履歴のAPIハンドラー
+//!
+//! 描画そのものは `application::use_cases::run_painting` が専用スレッドで実行する。
+
+use super::dto::{
+    ApiResponse, EstimateModelResponse, GalleryThumbnail, PaintStartResponse,
+    PaintingConfigResponse, PaintingRunResponse, PaintingSignalResponse, PaintingStatus,
+    PreflightStatus, StopAfterStatus, VectorPaintStartResponse,
+};
+use super::error_response::ErrorResponse;
+use super::log_streamer::PROGRESS_CHANNEL;
+use super::models::UpdateTimingRequest;
+use super::scheduled_painting::{
+    ScheduledPainting, cancel_schedule, parse_start_at, schedule_painting,
+};
+use super::state::{ArtworkState, release_active_painting};
+use crate::application::controller_io::run_controller_io;
+use crate::application::estimate_model::EstimateModelStore;
+use crate::application::painting_trace::PaintingTrace;
+use crate::application::use_cases::{
+    MAX_DOT_ATTEMPTS_LIMIT, PaintingControl, PaintingEventLog, PaintingRunGuard, perform_painting,
+    perform_vector_painting,
+};
+use crate::domain::artwork::change_log::ArtworkChange;
+use crate::domain::artwork::entities::{Artwork, Canvas};
+use crate::domain::events::ArtworkEvent;
+use crate::domain::painting::{
+    AdaptiveTimingSettings, ArtworkToCommandConverter, CanvasRegion, CompletionReport,
+    DEFAULT_FORCED_NEUTRAL_CLEAR_EVERY, DEFAULT_MAX_DOT_ATTEMPTS, DEFAULT_PREFLIGHT_TIMEOUT_SECS,
+    DrawingCanvasConfig, DrawingMode, DrawingStrategy, InitPreset, InitSequence,
+    MAX_ESTIMATE_CORRECTION, MAX_PREFLIGHT_TIMEOUT_SECS, MIN_ESTIMATE_CORRECTION,
+    NeutralClearSettings, PaintTiming, PaintingPreferences, PaintingRun, PauseMode, PauseSettings,
+    PreflightSettings, RunOptions, StopAfter, VectorSettings, simulate_run, vector_plan,
+};
+use crate::domain::shared::events::EventMetadata;
+use crate::domain::shared::i18n::MessageKey;
+use crate::domain::shared::value_objects::Coordinates;
+use axum::{
+    Json,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{StatusCode, header},
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use tracing::{error, info, warn};
+use utoipa::{IntoParams, ToSchema};
+
+/// 停止・一時停止を描画スレッドが受け取ったか確認するまでの待ち時間
+const SIGNAL_ACK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(2);
+
+#[derive(Debug, Clone, Default, Deserialize, ToSchema)]
+pub struct PaintRequest {
+    pub press_ms: Option<u32>,
+    pub release_ms: Option<u32>,
+    pub wait_ms: Option<u32>,
+    pub preview: Option<bool>,
+    pub strategy: Option<DrawingStrategy>,
+    pub repeats: Option<u32>,
+    pub region: Option<CanvasRegion>,
+    /// この行（アートワークの座標）以降のドットだけを描く。上の行のドットは入力を送らずに描画済みにする
+    ///
+    /// `region` と同時に指定した場合は、領域内のドットをさらに絞り込む
+    pub from_row: Option<u16>,
+    /// アートワークの左上を置くゲーム内キャンバスの位置（省略時は左上、`region` はアートワークの座標で指定する）
+    pub origin: Option<Coordinates>,
+    pub diagonal_moves: Option<bool>,
+    /// 書き込み遅延に応じてドット間の待機時間を自動調整する
+    pub adaptive: Option<bool>,
+    /// 自動調整時の待機時間の下限（省略時は `wait_ms`、最大60000）
+    pub adaptive_min_wait_ms: Option<u32>,
+    /// 自動調整時の待機時間の上限（最大60000）
+    pub adaptive_max_wait_ms: Option<u32>,
+    /// 遅延超過時の待機時間の倍率（%）
+    pub adaptive_scale_percent: Option<u32>,
+    /// 待機時間を延ばし始める平均遅延（ミリ秒）
+    pub adaptive_latency_threshold_ms: Option<u32>,
+    /// 乱択を使う描画戦略のシード値
+    pub seed: Option<u64>,
+    /// 一時停止を受け付けるタイミング（省略時はサーバーの設定）
+    pub pause_mode: Option<PauseMode>,
+    /// `safe` の再開時に左上へ戻ってから描画を続ける（省略時はサーバーの設定）
+    pub rehome_on_resume: Option<bool>,
+    /// Switchがレポートを受け取らない状態がこの時間続いたら自動で一時停止する（省略時はサーバーの設定）
+    pub host_grace_ms: Option<u32>,
+    /// 自動一時停止の後、Switchが復帰したら自動で再開する（省略時はサーバーの設定）
+    pub auto_resume: Option<bool>,
+    /// 描画前の初期化手順のプリセット（省略時はサーバーの設定）
+    pub init_preset: Option<InitPreset>,
+    /// プリセットの代わりに使う初期化手順（`init_preset` とは同時に指定できない）
+    pub init_sequence: Option<InitSequence>,
+    /// 描画モード（省略時は `pixel_pen`）。プリセットの初期化手順はこのモードのペンを選ぶ。
+    /// `init_sequence` を指定した場合はペンの選択を手順に含めること
+    pub drawing_mode: Option<DrawingMode>,
+    /// 描画済みの記録を消して、全ドットを描き直す（省略時は描画済みのドットを飛ばす）
+    pub reset_progress: Option<bool>,
+    /// 一時的な送信エラーの場合に1ドットの描画を試す最大回数（省略時は3、最大10）
+    pub max_dot_attempts: Option<u32>,
+    /// 一時停止中にこの時間入力がなければスリープ防止の入力を送る（ミリ秒、0で送らない。省略時はサーバーの設定）
+    pub keepalive_idle_ms: Option<u32>,
+    /// 見積もり時間が自動スリープの閾値を超えても描画を開始する（サーバーが厳格な設定の場合に必要）
+    pub acknowledge_sleep_risk: Option<bool>,
+    /// 描画を開始する時刻（RFC 3339、24時間以内。省略時はすぐに開始する）
+    pub start_at: Option<String>,
+    /// 描画を途中で切り上げる条件（ドット数・経過時間・完成度のいずれかに達したら停止する）
+    pub stop_after: Option<StopAfter>,
+    /// 十字キーがニュートラルと分かっているドットでは描画前のクリアを省く（省略時は `true`、デバッグ用に `false` で毎ドット送る）
+    pub skip_redundant_neutral_clears: Option<bool>,
+    /// 省略する場合も、このドット数ごとにクリアを必ず送る（省略時は50、0なら最初のドットだけ）
+    pub neutral_clear_every: Option<u32>,
+    /// 描画前に左上隅へ確認用の印を描き、`POST /api/painting/confirm-preflight` での確認を待つ（省略時はサーバーの設定）
+    pub preflight: Option<bool>,
+    /// 描画前の確認を待つ時間（秒、省略時は120、最大1800）。過ぎたら描画を中止する
+    pub preflight_timeout_secs: Option<u32>,
+    /// ドットごとの予定と実際のタイミングを記録し、描画の終了後に
+    /// `GET /api/painting/runs/{run_id}/trace.csv` で取得できるようにする（省略時は記録しない）
+    pub trace: Option<bool>,
+}
+
+impl PaintRequest {
+    /// 省略された項目をアートワークの描画設定で補う（リクエスト > アートワーク > サーバーの既定値）
+    pub(crate) fn with_preferences(&self, preferences: Option<&PaintingPreferences>) -> Self {
+        let mut request = self.clone();
+        if let Some(preferences) = preferences {
+            request.press_ms = request.press_ms.or(preferences.press_ms);
+            request.release_ms = request.release_ms.or(preferences.release_ms);
+            request.wait_ms = request.wait_ms.or(preferences.wait_ms);
+            request.strategy = request.strategy.or(preferences.strategy);
+            request.diagonal_moves = request.diagonal_moves.or(preferences.diagonal_moves);
+            request.origin = request.origin.or(preferences.origin);
+            request.drawing_mode = request.drawing_mode.or(preferences.drawing_mode);
+        }
+        request
+    }
+}
+
+/// 元のアートワークとの差分だけを描く描画リクエスト
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct PaintDiffRequest {
+    /// 描画済みの元のアートワークのID（キャンバスのサイズが同じであること）
+    pub base_artwork_id: String,
+    #[serde(flatten)]
+    pub paint: PaintRequest,
+}
+
+/// 線画をスティック操作で描く描画リクエスト
+#[derive(Debug, Clone, Deserialize, ToSchema)]
+pub struct VectorPaintRequest {
+    /// スティックを最大まで倒したときのカーソルの移動速度（ピクセル/秒、実機で測った値）
+    pub px_per_sec: f64,
+    /// スティックを倒す強さ（0.1〜1.0、省略時は1.0）。弱くすると遅くなる分、ずれが小さくなる
+    pub tilt: Option<f64>,
+    /// 線の始点でペンを押してから動き始めるまでの時間（省略時は `press_ms` の既定値）
+    pub press_ms: Option<u32>,
+    /// 線の終点でペンを離した後の待ち時間（省略時は `release_ms` の既定値）
+    pub release_ms: Option<u32>,
+    /// アートワークの左上を置くゲーム内キャンバスの位置（省略時はアートワークの描画設定、なければ左上）
+    pub origin: Option<Coordinates>,
+    /// 描画モード（省略時はアートワークの描画設定、なければ `pixel_pen`）。消しゴムではBを押したまま動かす
+    pub drawing_mode: Option<DrawingMode>,
+    /// 描画前の初期化手順のプリセット（省略時はサーバーの設定）
+    pub init_preset: Option<InitPreset>,
+    /// 初期化手順を飛ばす（ペンは選択済みとみなす。位置は線ごとに左上で合わせ直す）
+    #[serde(default)]
+    pub skip_initialization: bool,
+    /// 見積もり時間が自動スリープの閾値を超えても描画を開始する（サーバーが厳格な設定の場合に必要）
+    pub acknowledge_sleep_risk: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdateRepeatsRequest {
+    pub repeats: u32,
+}
+
+/// 描画履歴の取得件数
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PaintingRunsQuery {
+    /// 最大件数（既定値50、上限500）
+    pub limit: Option<usize>,
+}
+
+/// 描画履歴の取得件数の既定値と上限
+const DEFAULT_RUNS_LIMIT: usize = 50;
+const MAX_RUNS_LIMIT: usize = 500;
+
+/// List painting runs for an artwork, newest first
+#[utoipa::path(
+    get, path = "/api/artworks/{id}/runs", tag = "painting",
+    params(("id" = String, Path, description = "アートワークID")),
+    responses(
+        (status = 200, description = "新しい順の描画履歴", body = [PaintingRunResponse]),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse)
+    )
+)]
+pub async fn list_artwork_runs(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+) -> Result<Json<Vec<PaintingRunResponse>>, ErrorResponse> {
+    let artwork = state.artwork_or_not_found(&id).await?;
+
+    Ok(Json(
+        state
+            .runs
+            .find_by_artwork(&artwork.id)
+            .iter()
+            .map(PaintingRunResponse::from)
+            .collect(),
+    ))
+}
+
+/// List recent painting runs across all artworks, newest first
+#[utoipa::path(
+    get, path = "/api/painting/runs", tag = "painting",
+    params(PaintingRunsQuery),
+    responses(
+        (status = 200, description = "新しい順の描画履歴", body = [PaintingRunResponse])
+    )
+)]
+pub async fn list_painting_runs(
+    State(state): State<Arc<ArtworkState>>,
+    Query(query): Query<PaintingRunsQuery>,
+) -> Json<Vec<PaintingRunResponse>> {
+    let limit = query
+        .limit
+        .unwrap_or(DEFAULT_RUNS_LIMIT)
+        .min(MAX_RUNS_LIMIT);
+
+    Json(
+        state
+            .runs
+            .recent(limit)
+            .iter()
+            .map(PaintingRunResponse::from)
+            .collect(),
+    )
+}
+
+/// 記録のCSVを送る塊の大きさ
+const TRACE_CSV_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Download the per-dot timing trace of a finished painting run as CSV
+///
+/// 列は `application::painting_trace::TRACE_CSV_HEADER` の通り。保持するのは直近の数件の描画だけで、
+/// サーバーを再起動すると消える。
+#[utoipa::path(
+    get, path = "/api/painting/runs/{run_id}/trace.csv", tag = "painting",
+    params(("run_id" = String, Path, description = "描画の実行ID")),
+    responses(
+        (status = 200, description = "ドットごとのタイミングの記録", content_type = "text/csv", body = String),
+        (status = 404, description = "この描画のタイミングを記録していない（`trace` を指定していないか、古くて破棄した）", body = ErrorResponse),
+        (status = 409, description = "描画がまだ終わっていない", body = ErrorResponse)
+    )
+)]
+pub async fn get_painting_trace(
+    State(state): State<Arc<ArtworkState>>,
+    Path(run_id): Path<String>,
+) -> Result<Response, ErrorResponse> {
+    let trace = state.painting_traces.get(&run_id).ok_or_else(|| {
+        ErrorResponse::new(
+            StatusCode::NOT_FOUND,
+            format!("No timing trace was recorded for painting run {run_id}"),
+        )
+    })?;
+    if !trace
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_finished()
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            format!("Painting run {run_id} has not finished yet"),
+        ));
+    }
+
+    // 一時ファイルに書き出した分も含めて、読んだ分から順に送る
+    let (sender, receiver) = tokio::sync::mpsc::channel::<Vec<u8>>(4);
+    tokio::task::spawn_blocking(move || {
+        let trace = trace.lock().unwrap_or_else(|e| e.into_inner());
+        trace.write_csv_chunks(TRACE_CSV_CHUNK_BYTES, |chunk| {
+            sender.blocking_send(chunk).is_ok()
+        });
+    });
+    let chunks = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        let chunk = receiver.recv().await?;
+        Some((Ok::<_, std::convert::Infallible>(chunk), receiver))
+    });
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"trace-{run_id}.csv\""),
+            ),
+        ],
+        Body::from_stream(chunks),
+    )
+        .into_response())
+}
+
+/// 停止・一時停止の対象
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct StopPaintingQuery {
+    /// 対象の描画の世代番号（省略時は実行中の描画）
+    pub generation: Option<u64>,
+}
+
+/// 一時停止・再開の指定
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct PausePaintingQuery {
+    /// 対象の描画の世代番号（省略時は実行中の描画）
+    pub generation: Option<u64>,
+    /// `true` で一時停止、`false` で再開（省略時は切り替え）
+    pub paused: Option<bool>,
+}
+
+/// 描画前の確認への回答
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct ConfirmPreflightRequest {
+    /// `true` で印の位置が正しいとして描画を続け、`false` で描画を中止する
+    pub confirmed: bool,
+    /// 対象の描画の世代番号（省略時は実行中の描画）
+    pub generation: Option<u64>,
+}
+
+/// 停止・一時停止の対象になる実行中の描画を取り出す
+///
+/// 描画中でなければ `Ok(None)`。`generation` を指定した描画が既に終わっていれば410、
+/// まだ開始していない世代なら409を返す。
+async fn targeted_painting(
+    state: &ArtworkState,
+    generation: Option<u64>,
+) -> Result<Option<PaintingControl>, ErrorResponse> {
+    let active = state.active_painting.read().await.clone();
+    match (active, generation) {
+        // 同時に実行できる描画は1つだけなので、古い世代は終了している
+        (Some(control), Some(generation)) if generation < control.generation => {
+            Err(run_ended(generation))
+        }
+        (Some(control), Some(generation)) if generation > control.generation => {
+            Err(ErrorResponse::new(
+                StatusCode::CONFLICT,
+                format!(
+                    "Painting run {generation} is not the active run (active: {})",
+                    control.generation
+                ),
+            ))
+        }
+        (None, Some(generation)) => Err(run_ended(generation)),
+        (active, _) => Ok(active),
+    }
+}
+
+fn run_ended(generation: u64) -> ErrorResponse {
+    ErrorResponse::new(
+        StatusCode::GONE,
+        format!("Painting run {generation} has already ended"),
+    )
+}
+
+fn no_active_painting() -> Json<PaintingSignalResponse> {
+    Json(PaintingSignalResponse {
+        success: false,
+        message: "No active painting found".to_string(),
+        generation: None,
+        acknowledged: false,
+        paused: None,
+    })
+}
+
+/// Stop current painting
+///
+/// 描画スレッドが停止を受け取って終了するまで少し待ち、`acknowledged` で結果を返す。
+#[utoipa::path(
+    post, path = "/api/painting/stop", tag = "painting",
+    params(StopPaintingQuery),
+    responses(
+        (status = 200, description = "描画中でなければ `success: false`", body = PaintingSignalResponse),
+        (status = 409, description = "`generation` の描画はまだ開始していない", body = ErrorResponse),
+        (status = 410, description = "`generation` の描画は既に終了", body = ErrorResponse)
+    )
+)]
+pub async fn stop_painting(
+    State(state): State<Arc<ArtworkState>>,
+    Query(query): Query<StopPaintingQuery>,
+) -> Result<Json<PaintingSignalResponse>, ErrorResponse> {
+    let Some(control) = targeted_painting(&state, query.generation).await? else {
+        return Ok(no_active_painting());
+    };
+    if !control.request_stop() {
+        return Err(run_ended(control.generation));
+    }
+    info!("Stop signal sent to painting run {}", control.generation);
+
+    let acknowledged = control.wait_for_stop(SIGNAL_ACK_TIMEOUT).await;
+    if !acknowledged {
+        warn!(
+            "Painting run {} has not stopped yet after {:?}",
+            control.generation, SIGNAL_ACK_TIMEOUT
+        );
+    }
+    Ok(Json(PaintingSignalResponse {
+        success: true,
+        message: if acknowledged {
+            "Painting stopped".to_string()
+        } else {
+            "Stop requested".to_string()
+        },
+        generation: Some(control.generation),
+        acknowledged,
+        paused: None,
+    }))
+}
+
+/// Pause/Resume current painting
+///
+/// 描画スレッドが待機を開始（再開では終了）するまで少し待ち、`acknowledged` で結果を返す。
+#[utoipa::path(
+    post, path = "/api/painting/pause", tag = "painting",
+    params(PausePaintingQuery),
+    responses(
+        (status = 200, description = "描画中でなければ `success: false`", body = PaintingSignalResponse),
+        (status = 409, description = "`generation` の描画はまだ開始していない", body = ErrorResponse),
+        (status = 410, description = "`generation` の描画は既に終了", body = ErrorResponse)
+    )
+)]
+pub async fn pause_painting(
+    State(state): State<Arc<ArtworkState>>,
+    Query(query): Query<PausePaintingQuery>,
+) -> Result<Json<PaintingSignalResponse>, ErrorResponse> {
+    let Some(control) = targeted_painting(&state, query.generation).await? else {
+        return Ok(no_active_painting());
+    };
+    let Some(paused) = control.request_pause(query.paused) else {
+        return Err(run_ended(control.generation));
+    };
+    let status = if paused { "paused" } else { "resumed" };
+    info!("Painting run {} {}", control.generation, status);
+
+    let acknowledged = control.wait_for_pause(paused, SIGNAL_ACK_TIMEOUT).await;
+    Ok(Json(PaintingSignalResponse {
+        success: true,
+        message: format!("Painting {}", status),
+        generation: Some(control.generation),
+        acknowledged,
+        paused: Some(paused),
+    }))
+}
+
+/// Answer the preflight check of the current painting
+///
+/// 描画スレッドが回答を受け取るまで少し待ち、`acknowledged` で結果を返す。
+#[utoipa::path(
+    post, path = "/api/painting/confirm-preflight", tag = "painting",
+    request_body = ConfirmPreflightRequest,
+    responses(
+        (status = 200, description = "描画中でなければ `success: false`", body = PaintingSignalResponse),
+        (status = 409, description = "確認を待っていない（確認しない描画・回答済み・期限切れ）", body = ErrorResponse),
+        (status = 410, description = "`generation` の描画は既に終了", body = ErrorResponse)
+    )
+)]
+pub async fn confirm_preflight(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<ConfirmPreflightRequest>,
+) -> Result<Json<PaintingSignalResponse>, ErrorResponse> {
+    let Some(control) = targeted_painting(&state, request.generation).await? else {
+        return Ok(no_active_painting());
+    };
+    if !control.answer_preflight(request.confirmed) {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            format!(
+                "Painting run {} is not waiting for a preflight confirmation",
+                control.generation
+            ),
+        ));
+    }
+    let answer = if request.confirmed {
+        "confirmed"
+    } else {
+        "declined"
+    };
+    info!(
+        "Preflight of painting run {} {}",
+        control.generation, answer
+    );
+
+    let acknowledged = control.wait_for_preflight_answer(SIGNAL_ACK_TIMEOUT).await;
+    Ok(Json(PaintingSignalResponse {
+        success: true,
+        message: format!("Preflight {}", answer),
+        generation: Some(control.generation),
+        acknowledged,
+        paused: None,
+    }))
+}
+
+/// `GET /api/painting/status` のオプション
+#[derive(Debug, Default, Deserialize, IntoParams)]
+pub struct PaintingStatusQuery {
+    /// 描画中のアートワークの縮小図（`thumbnail`）を含める
+    pub thumbnail: Option<bool>,
+}
+
+/// Get the state and effective settings of the current painting
+#[utoipa::path(
+    get, path = "/api/painting/status", tag = "painting",
+    params(PaintingStatusQuery),
+    responses((status = 200, body = PaintingStatus))
+)]
+pub async fn get_painting_status(
+    State(state): State<Arc<ArtworkState>>,
+    Query(query): Query<PaintingStatusQuery>,
+) -> Result<Json<PaintingStatus>, ErrorResponse> {
+    let active_painting = state.active_painting.read().await;
+    // 次の描画を開始すると最新の記録は実行中になるため、結果は返さなくなる
+    let last_run = state
+        .runs
+        .recent(1)
+        .first()
+        .filter(|run| run.is_finished())
+        .map(PaintingRunResponse::from);
+    let scheduled = state
+        .scheduled_painting
+        .read()
+        .await
+        .as_ref()
+        .map(ScheduledPainting::status);
+
+    Ok(Json(match active_painting.as_ref() {
+        Some(control) => {
+            let artwork_id = control.event_log.as_ref().map(|log| log.artwork_id.clone());
+            let report = control
+                .completion
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .report();
+            let thumbnail = match &artwork_id {
+                Some(artwork_id) if query.thumbnail.unwrap_or(false) => state
+                    .artwork_in_progress(control, artwork_id)
+                    .await?
+                    .map(|artwork| GalleryThumbnail::from(&artwork.canvas)),
+                _ => None,
+            };
+            PaintingStatus {
+                active: true,
+                paused: control.pause_signal.load(Ordering::SeqCst),
+                painted: control.painted.load(Ordering::SeqCst),
+                config: control
+                    .effective_config()
+                    .as_ref()
+                    .map(PaintingConfigResponse::from),
+                last_run,
+                generation: Some(control.generation),
+                scheduled,
+                stop_after: stop_after_status(control),
+                preflight: control
+                    .preflight_phase()
+                    .map(|phase| PreflightStatus::new(phase, Utc::now())),
+                artwork_id: artwork_id.map(|id| id.as_str()),
+                total_dots: Some(report.total_dots),
+                eta_seconds: report.eta_seconds(),
+                thumbnail,
+            }
+        }
+        None => PaintingStatus {
+            active: false,
+            paused: false,
+            painted: 0,
+            config: None,
+            last_run,
+            generation: None,
+            scheduled,
+            stop_after: None,
+            preflight: None,
+            artwork_id: None,
+            total_dots: None,
+            eta_seconds: None,
+            thumbnail: None,
+        },
+    }))
+}
+
+/// 実行中の描画の `stop_after` の上限と、これまでの進捗から見た残り
+fn stop_after_status(control: &PaintingControl) -> Option<StopAfterStatus> {
+    let limits = control.config.as_ref()?.options.stop_after?;
+    let completion = control.completion.lock().unwrap_or_else(|e| e.into_inner());
+    Some(StopAfterStatus::new(
+        limits,
+        completion.painted_dots().len(),
+        completion.elapsed(),
+    ))
+}
+
+/// Update repeats for current painting
+#[utoipa::path(
+    post, path = "/api/painting/repeats", tag = "painting",
+    request_body = UpdateRepeatsRequest,
+    responses(
+        (status = 200, description = "描画中でなければ `success: false`", body = ApiResponse)
+    )
+)]
+pub async fn update_painting_repeats(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<UpdateRepeatsRequest>,
+) -> Result<Json<ApiResponse>, StatusCode> {
+    let active_painting = state.active_painting.read().await;
+
+    if let Some(control) = active_painting.as_ref() {
+        let repeats = request.repeats.max(1);
+        control.repeats.store(repeats, Ordering::SeqCst);
+
+        info!("Updated painting repeats to {}", repeats);
+
+        Ok(Json(ApiResponse {
+            success: true,
+            message: format!("Repeats updated to {}", repeats),
+        }))
+    } else {
+        Ok(Json(ApiResponse {
+            success: false,
+            message: "No active painting found".to_string(),
+        }))
+    }
+}
+
+/// Update timing for current painting
+#[utoipa::path(
+    post, path = "/api/painting/timing", tag = "painting",
+    request_body = UpdateTimingRequest,
+    responses(
+        (status = 200, description = "描画中でなければ `success: false`", body = ApiResponse)
+    )
+)]
+pub async fn update_painting_timing(
+    State(state): State<Arc<ArtworkState>>,
+    Json(request): Json<UpdateTimingRequest>,
+) -> Result<Json<ApiResponse>, StatusCode> {
+    let active_painting = state.active_painting.read().await;
+
+    if let Some(control) = active_painting.as_ref() {
+        control
+            .press_ms
+            .store(request.press_ms as u64, Ordering::SeqCst);
+        control
+            .release_ms
+            .store(request.release_ms as u64, Ordering::SeqCst);
+        control
+            .wait_ms
+            .store(request.wait_ms as u64, Ordering::SeqCst);
+
+        info!(
+            "Updated painting timing to press={}ms, release={}ms, wait={}ms",
+            request.press_ms, request.release_ms, request.wait_ms
+        );
+
+        Ok(Json(ApiResponse {
+            success: true,
+            message: format!(
+                "Timing updated to {}/{}/{} ms",
+                request.press_ms, request.release_ms, request.wait_ms
+            ),
+        }))
+    } else {
+        Ok(Json(ApiResponse {
+            success: false,
+            message: "No active painting found".to_string(),
+        }))
+    }
+}
+
+/// Paint an artwork
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/paint", tag = "painting",
+    params(("id" = String, Path, description = "アートワークID")),
+    request_body = PaintRequest,
+    responses(
+        (status = 200, description = "描画に使う設定と見積もり", body = PaintStartResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "厳格シミュレーション中、またはアートワークのキャンバスを変更中", body = ErrorResponse),
+        (status = 423, description = "コントローラーがアームされていない", body = ErrorResponse),
+        (status = 422, description = "描画領域・`from_row`・自動調整の範囲が不正", body = ErrorResponse)
+    )
+)]
+pub async fn paint_artwork(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Json(request): Json<PaintRequest>,
+) -> Result<Json<PaintStartResponse>, ErrorResponse> {
+    state.ensure_controller_allowed()?;
+    let artwork = state.artwork_or_not_found(&id).await?;
+    if let Some(start_at) = request.start_at.as_deref() {
+        let start_at = parse_start_at(start_at)?;
+        return schedule_paint_request(state, &id, &artwork, None, start_at, request).await;
+    }
+    start_painting(state, &id, artwork, None, &request).await
+}
+
+/// Paint only the dots of an artwork that are not in an already painted base artwork
+///
+/// 元のアートワークにだけあるドットは消さずに残る（消しゴムでの描画には未対応）。
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/paint-diff", tag = "painting",
+    params(("id" = String, Path, description = "描画するアートワークID")),
+    request_body = PaintDiffRequest,
+    responses(
+        (status = 200, description = "描画に使う設定と見積もり", body = PaintStartResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "厳格シミュレーション中、またはアートワークのキャンバスを変更中", body = ErrorResponse),
+        (status = 423, description = "コントローラーがアームされていない", body = ErrorResponse),
+        (status = 422, description = "キャンバスのサイズが異なる、または描画領域・自動調整の範囲が不正", body = ErrorResponse)
+    )
+)]
+pub async fn paint_artwork_diff(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Json(request): Json<PaintDiffRequest>,
+) -> Result<Json<PaintStartResponse>, ErrorResponse> {
+    state.ensure_controller_allowed()?;
+    let artwork = state.artwork_or_not_found(&id).await?;
+    let base = state.artwork_or_not_found(&request.base_artwork_id).await?;
+    let diff = base.canvas.diff(&artwork.canvas).map_err(|e| {
+        warn!(
+            "Cannot paint artwork {} over {}: {}",
+            id, request.base_artwork_id, e
+        );
+        ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string())
+    })?;
+    if !diff.only_in_base.is_empty() {
+        warn!(
+            "{} dots only in base artwork {} are left as they are (erasing is not supported)",
+            diff.only_in_base.len(),
+            request.base_artwork_id
+        );
+    }
+    if let Some(start_at) = request.paint.start_at.as_deref() {
+        let start_at = parse_start_at(start_at)?;
+        let base_artwork_id = Some(request.base_artwork_id);
+        return schedule_paint_request(
+            state,
+            &id,
+            &artwork,
+            base_artwork_id,
+            start_at,
+            request.paint,
+        )
+        .await;
+    }
+    start_painting(state, &id, artwork, Some(&base.canvas), &request.paint).await
+}
+
+/// Paint the line art of an artwork as continuous strokes with the left stick
+///
+/// ペンを押したままスティックを倒して線を引くため、ドット描画より速いが位置は推定でしかない。
+/// 署名や輪郭などの大まかな線向けで、応答の `max_deviation_px` がずれの見込み。
+/// 描画済みの記録・一時停止・予約には対応しない。
+#[utoipa::path(
+    post, path = "/api/artworks/{id}/paint-vector", tag = "painting",
+    params(("id" = String, Path, description = "アートワークID")),
+    request_body = VectorPaintRequest,
+    responses(
+        (status = 200, description = "描く線の数と見積もり", body = VectorPaintStartResponse),
+        (status = 404, description = "アートワークが存在しない", body = ErrorResponse),
+        (status = 409, description = "厳格シミュレーション中、描画・キャリブレーションの実行中、またはアートワークを変更中", body = ErrorResponse),
+        (status = 423, description = "コントローラーがアームされていない", body = ErrorResponse),
+        (status = 422, description = "線画が無い、速度・傾きが不正、配置がゲーム内キャンバスに収まらない", body = ErrorResponse)
+    )
+)]
+pub async fn paint_artwork_vector(
+    State(state): State<Arc<ArtworkState>>,
+    Path(id): Path<String>,
+    Json(request): Json<VectorPaintRequest>,
+) -> Result<Json<VectorPaintStartResponse>, ErrorResponse> {
+    state.ensure_controller_allowed()?;
+    let artwork = state.artwork_or_not_found(&id).await?;
+    if artwork.vector_paths.is_empty() {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "Artwork has no vector paths (set them with PUT /api/artworks/{id}/vector-paths)",
+        ));
+    }
+    let preferences = artwork.painting_preferences.unwrap_or_default();
+    let origin = request
+        .origin
+        .or(preferences.origin)
+        .unwrap_or_else(Coordinates::origin);
+    validate_origin(origin, &artwork.canvas)?;
+    let drawing_mode = request
+        .drawing_mode
+        .or(preferences.drawing_mode)
+        .unwrap_or_default();
+    let default_timing = PaintTiming::default();
+    let game_canvas = DrawingCanvasConfig::default();
+    let settings = VectorSettings {
+        px_per_sec: request.px_per_sec,
+        tilt: request.tilt.unwrap_or(1.0),
+        pen_button: drawing_mode.dot_button(),
+        press_ms: request.press_ms.unwrap_or(default_timing.press_ms),
+        release_ms: request.release_ms.unwrap_or(default_timing.release_ms),
+        origin,
+        canvas_width: game_canvas.width,
+        canvas_height: game_canvas.height,
+    };
+    let plan = vector_plan(&artwork.vector_paths, &settings)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    let init_sequence = (!request.skip_initialization).then(|| {
+        InitSequence::preset_for(
+            request.init_preset.unwrap_or(state.init_preset),
+            drawing_mode,
+        )
+    });
+
+    let mut warnings = Vec::new();
+    let run_ms = plan.total_ms
+        + init_sequence
+            .as_ref()
+            .map_or(0, |sequence| sequence.duration_ms());
+    if let Some(warning) = state.sleep_guard.warning(run_ms) {
+        if state.sleep_guard.strict && !request.acknowledge_sleep_risk.unwrap_or(false) {
+            warn!(
+                "Refusing to paint vector paths of artwork {}: {}",
+                id, warning
+            );
+            return Err(ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "{warning}; disable auto-sleep on the Switch and set acknowledge_sleep_risk to paint anyway"
+                ),
+            ));
+        }
+        warnings.push(warning);
+    }
+    info!(
+        "Starting vector painting for artwork {} ({} strokes, {:.0}px/s at tilt {}, origin {}, mode {})",
+        id, plan.strokes, settings.px_per_sec, settings.tilt, origin, drawing_mode
+    );
+
+    let lock = state
+        .artwork_locks
+        .read(&id, MessageKey::OperationVectorPainting)?;
+    let control = PaintingControl::new(1, settings.press_ms, settings.release_ms, 0);
+    state.begin_painting(&control).await?;
+    state.forget_calibration_cursor().await;
+    state.metrics.start_run();
+
+    let controller = state.controller.clone();
+    let stop_signal = control.stop_signal.clone();
+    let active_painting_store = state.active_painting.clone();
+    let generation = control.generation;
+    let task_plan = plan.clone();
+    tokio::spawn(async move {
+        let result = run_controller_io(move || {
+            perform_vector_painting(controller, stop_signal, init_sequence.as_ref(), &task_plan)
+        })
+        .await;
+        control.mark_finished();
+        drop(lock);
+        release_active_painting(&active_painting_store, &control).await;
+
+        let message = match result {
+            Ok(Ok(strokes)) => serde_json::json!({
+                "type": "vector_complete",
+                "status": "success",
+                "strokes": strokes
+            }),
+            Ok(Err(e)) => {
+                error!("Vector painting failed with hardware error: {}", e);
+                serde_json::json!({
+                    "type": "vector_complete",
+                    "status": "error",
+                    "message": e.to_string()
+                })
+            }
+            Err(e) => {
+                error!("Vector painting task panicked or was cancelled: {}", e);
+                serde_json::json!({ "type": "vector_complete", "status": "cancelled" })
+            }
+        };
+        let _ = PROGRESS_CHANNEL.send(message.to_string());
+    });
+
+    let estimated_time_seconds = plan.total_ms as f64 / 1000.0;
+    Ok(Json(VectorPaintStartResponse {
+        success: true,
+        message: format!(
+            "Vector painting started (estimated time: {estimated_time_seconds:.1} seconds, deviation up to ~{:.1}px)",
+            plan.max_deviation_px
+        ),
+        strokes: plan.strokes,
+        length_px: plan.length_px,
+        estimated_time_seconds,
+        max_deviation_px: plan.max_deviation_px,
+        warnings,
+        generation,
+    }))
+}
+
+/// 開始時刻を指定した描画リクエストを予約する
+///
+/// 開始時にも同じ検証を行うが、設定の誤りは予約の時点で返す。見積もりは開始時に行う。
+async fn schedule_paint_request(
+    state: Arc<ArtworkState>,
+    id: &str,
+    artwork: &Artwork,
+    base_artwork_id: Option<String>,
+    start_at: DateTime<Utc>,
+    request: PaintRequest,
+) -> Result<Json<PaintStartResponse>, ErrorResponse> {
+    let config = drawing_config(
+        &request.with_preferences(artwork.painting_preferences.as_ref()),
+        &artwork.canvas,
+        state.pause,
+        state.init_preset,
+        state.preflight,
+    )
+    .inspect_err(|e| {
+        warn!("Invalid paint request for artwork {}: {}", id, e.message);
+    })?;
+    let scheduled =
+        schedule_painting(state, id.to_string(), base_artwork_id, start_at, request).await?;
+    Ok(Json(PaintStartResponse {
+        success: true,
+        message: format!(
+            "Painting scheduled for {} ({:.0} seconds from now)",
+            scheduled.start_at, scheduled.seconds_remaining
+        ),
+        estimated_time_seconds: 0.0,
+        corrected_estimated_time_seconds: 0.0,
+        estimate_correction: 1.0,
+        config: PaintingConfigResponse::from(&config),
+        warnings: Vec::new(),
+        generation: None,
+        scheduled: Some(scheduled),
+        already_complete_dots: 0,
+        trace_url: None,
+    }))
+}
+
+/// Cancel the painting scheduled with `start_at`
+#[utoipa::path(
+    delete, path = "/api/painting/scheduled", tag = "painting",
+    responses(
+        (status = 200, description = "予約が無ければ `success: false`", body = ApiResponse)
+    )
+)]
+pub async fn cancel_scheduled_painting(
+    State(state): State<Arc<ArtworkState>>,
+) -> Json<ApiResponse> {
+    Json(match cancel_schedule(&state).await {
+        Some(scheduled) => ApiResponse {
+            success: true,
+            message: format!(
+                "Cancelled painting of artwork {} scheduled for {}",
+                scheduled.artwork_id, scheduled.start_at
+            ),
+        },
+        None => ApiResponse {
+            success: false,
+            message: "No scheduled painting found".to_string(),
+        },
+    })
+}
+
+/// Get the estimate correction factors learned from completed runs
+#[utoipa::path(
+    get, path = "/api/painting/estimate-model", tag = "painting",
+    responses(
+        (status = 200, description = "タイミングごとの補正係数", body = EstimateModelResponse)
+    )
+)]
+pub async fn get_estimate_model(
+    State(state): State<Arc<ArtworkState>>,
+) -> Json<EstimateModelResponse> {
+    Json(EstimateModelResponse {
+        enabled: state.estimate_model.is_enabled(),
+        min_correction: MIN_ESTIMATE_CORRECTION,
+        max_correction: MAX_ESTIMATE_CORRECTION,
+        buckets: state.estimate_model.model().buckets,
+    })
+}
+
+/// Discard the learned estimate correction factors
+#[utoipa::path(
+    delete, path = "/api/painting/estimate-model", tag = "painting",
+    responses(
+        (status = 200, description = "補正係数を削除した", body = ApiResponse),
+        (status = 500, description = "保存先のファイルを削除できない", body = ErrorResponse)
+    )
+)]
+pub async fn reset_estimate_model(
+    State(state): State<Arc<ArtworkState>>,
+) -> Result<Json<ApiResponse>, ErrorResponse> {
+    let estimate_model = state.estimate_model.clone();
+    let removed = estimate_model.model().buckets.len();
+    tokio::task::spawn_blocking(move || estimate_model.reset())
+        .await
+        .map_err(|e| ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| {
+            error!("Failed to reset the estimate model: {}", e);
+            ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())
+        })?;
+    info!("Reset the estimate model ({} timings removed)", removed);
+    Ok(Json(ApiResponse {
+        success: true,
+        message: format!("Removed the estimate corrections of {removed} timings"),
+    }))
+}
+
+/// 描画を開始し、終了まで別タスクで進める
+///
+/// `base` を指定した場合は、そのキャンバスに無いドットだけを描く。
+pub(crate) async fn start_painting(
+    state: Arc<ArtworkState>,
+    id: &str,
+    mut artwork: Artwork,
+    base: Option<&Canvas>,
+    request: &PaintRequest,
+) -> Result<Json<PaintStartResponse>, ErrorResponse> {
+    // 描画が終わるまでキャンバスの変更と削除を受け付けない
+    let lock = state
+        .artwork_locks
+        .read(id, MessageKey::OperationPainting)?;
+    let request = &request.with_preferences(artwork.painting_preferences.as_ref());
+    let config = drawing_config(
+        request,
+        &artwork.canvas,
+        state.pause,
+        state.init_preset,
+        state.preflight,
+    )
+    .inspect_err(|e| {
+        warn!("Invalid paint request for artwork {}: {}", id, e.message);
+    })?;
+    // 描画済みの記録は、描画を開始すると決まってから保存する
+    let reset_progress =
+        request.reset_progress.unwrap_or(false) && !artwork.canvas.painted_dots().is_empty();
+    if reset_progress {
+        artwork.reset_painting_state();
+    }
+    let preview = request.preview.unwrap_or(false);
+    let strategy = request.strategy.unwrap_or(DrawingStrategy::GreedyTwoOpt);
+    let region = request.region;
+    let from_row = request.from_row;
+    let timing = config.timing;
+
+    info!(
+        "Starting painting for artwork {} (timing: {}+{}+{}ms/px, preview: {}, strategy: {:?}, repeats: {}, region: {:?}, from_row: {:?}, diagonal_moves: {})",
+        id,
+        timing.press_ms,
+        timing.release_ms,
+        timing.wait_ms,
+        preview,
+        strategy,
+        config.options.repeats,
+        region,
+        from_row,
+        config.options.diagonal_moves
+    );
+
+    // Generate the drawing path once so the estimate matches what is painted
+    let canvas = match base {
+        Some(base) => {
+            let canvas = artwork
+                .canvas
+                .without_dots_in(base)
+                .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+            info!(
+                "Painting only the {} dots that are not in the base artwork",
+                canvas.dots.len()
+            );
+            canvas
+        }
+        None => artwork.canvas.clone(),
+    };
+    let seed = request.seed;
+    let two_opt = state.two_opt;
+    let converter_config = config.clone();
+    let (drawing_path, already_complete) = tokio::task::spawn_blocking(move || {
+        let mut converter = ArtworkToCommandConverter::new(converter_config, strategy)
+            .with_seed(seed)
+            .with_two_opt_settings(two_opt);
+        if let Some(region) = region {
+            converter = converter.with_region(region);
+        }
+        if let Some(from_row) = from_row {
+            converter = converter.with_from_row(from_row);
+        }
+        (
+            converter.create_drawing_path(&canvas),
+            converter.dots_before_from_row(&canvas),
+        )
+    })
+    .await
+    .map_err(|e| {
+        error!("Path generation task failed: {}", e);
+        ErrorResponse::new(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "Failed to generate drawing path",
+        )
+    })?;
+
+    if drawing_path.coordinates.is_empty() {
+        let message = match (&region, from_row) {
+            (Some(region), Some(from_row)) => {
+                format!("No drawable dots inside region {region} from row {from_row}")
+            }
+            (Some(region), None) => format!("No drawable dots inside region {region}"),
+            (None, Some(from_row)) => format!("No drawable dots from row {from_row}"),
+            (None, None) if base.is_some() => {
+                "No unpainted dots differ from the base artwork".to_string()
+            }
+            (None, None) if !artwork.canvas.painted_dots().is_empty() => {
+                "All dots are already painted (set reset_progress to paint again)".to_string()
+            }
+            (None, None) => "Artwork has no drawable dots".to_string(),
+        };
+        info!("Skipping painting for artwork {}: {}", id, message);
+        return Ok(Json(PaintStartResponse {
+            success: false,
+            message,
+            estimated_time_seconds: 0.0,
+            corrected_estimated_time_seconds: 0.0,
+            estimate_correction: 1.0,
+            config: PaintingConfigResponse::from(&config),
+            warnings: Vec::new(),
+            generation: None,
+            scheduled: None,
+            already_complete_dots: 0,
+            trace_url: None,
+        }));
+    }
+
+    let estimate = simulate_run(&drawing_path, &config.timing, &config.options);
+    let corrected = state
+        .estimate_model
+        .estimate(&config.timing, estimate.total_ms);
+    info!(
+        "Run estimate: {} dpad ops, {} A presses, {} neutral clears, {:.1}s ({:.1}s with the {:.2}x correction)",
+        estimate.dpad_ops,
+        estimate.a_presses,
+        estimate.neutral_clears,
+        estimate.total_ms as f64 / 1000.0,
+        corrected.corrected_ms() as f64 / 1000.0,
+        corrected.correction
+    );
+
+    // 初期化手順を含めて、本体の自動スリープまでに終わるかを確認する
+    let mut warnings = Vec::new();
+    let run_ms = corrected.corrected_ms() + config.init_sequence.duration_ms();
+    if let Some(warning) = state.sleep_guard.warning(run_ms) {
+        if state.sleep_guard.strict && !request.acknowledge_sleep_risk.unwrap_or(false) {
+            warn!("Refusing to paint artwork {}: {}", id, warning);
+            return Err(ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "{warning}; disable auto-sleep on the Switch and set acknowledge_sleep_risk to paint anyway"
+                ),
+            ));
+        }
+        warn!("Artwork {}: {}", id, warning);
+        warnings.push(warning);
+    }
+
+    // 描画済みの状態を消す前に、実行中の描画が無いことを確認する
+    state.ensure_no_active_painting().await?;
+    if reset_progress {
+        let _edit = state.artwork_edits.lock().await;
+        artwork = state.artwork_or_not_found(id).await?;
+        let previous_completion_ratio = artwork.completion_ratio();
+        artwork.reset_painting_state();
+        artwork.record_change(
+            ArtworkChange::progress_reset(previous_completion_ratio),
+            EventMetadata::new("api".to_string()),
+        );
+        state.artworks.save(&artwork).await?;
+        info!("Reset painting progress of artwork {}", id);
+    }
+    // `from_row` より上の行は描き終えているものとして、入力を送らずに描画済みにする
+    if !already_complete.is_empty() {
+        info!(
+            "Marking {} dots above row {:?} of artwork {} as already painted",
+            already_complete.len(),
+            from_row,
+            id
+        );
+        state
+            .mark_dots_painted(&artwork.id, &already_complete)
+            .await?;
+        artwork = state.artwork_or_not_found(id).await?;
+    }
+
+    let controller = state.controller.clone();
+
+    // Setup control signals
+    let mut control = PaintingControl::from_config(&config)
+        .with_event_log(PaintingEventLog {
+            artwork_id: artwork.id.clone(),
+            version: artwork.version,
+            events: state.events.clone(),
+        })
+        .with_estimate(corrected);
+    let trace = request.trace.unwrap_or(false).then(|| {
+        Arc::new(std::sync::Mutex::new(PaintingTrace::new(
+            drawing_path.coordinates.len(),
+        )))
+    });
+    if let Some(trace) = &trace {
+        control = control.with_trace(trace.clone());
+    }
+
+    // Record the run before starting so it is visible while painting
+    let run = PaintingRun::start(
+        artwork.id.clone(),
+        artwork.version,
+        strategy,
+        timing,
+        config.options.repeats,
+        &drawing_path,
+    );
+    info!("Drawing path hash: {}", run.path_hash);
+    state.begin_painting(&control).await?;
+    state.forget_calibration_cursor().await;
+    state.metrics.start_run();
+    state.runs.save(&run);
+    let trace_url = trace.map(|trace| {
+        state.painting_traces.insert(&run.id, trace);
+        format!("/api/painting/runs/{}/trace.csv", run.id)
+    });
+    state
+        .events
+        .push(ArtworkEvent::painting_started(
+            artwork.id.clone(),
+            run.dots_attempted,
+            estimate.total_ms / 1000,
+            artwork.version,
+            EventMetadata::new("painting".to_string()),
+        ))
+        .await;
+    let runs = state.runs.clone();
+
+    let active_painting_store = state.active_painting.clone();
+    let response_config = PaintingConfigResponse::from(&config);
+    let completion = control.completion.clone();
+    let generation = control.generation;
+    let state = state.clone();
+    let artwork_id = artwork.id.clone();
+    let estimate_model = state.estimate_model.clone();
+    let estimated_ms = estimate.total_ms + config.init_sequence.duration_ms();
+
+    // Spawn painting task
+    tokio::spawn(async move {
+        // Run blocking controller operations in a blocking thread
+        let task_control = control.clone();
+        let result = run_controller_io(move || {
+            let mut guard =
+                PaintingRunGuard::new(controller.clone(), task_control.clone(), runs, run);
+            let result = perform_painting(controller, drawing_path, &config, task_control.clone());
+            guard.finish(&result);
+            if let Ok(report) = &result {
+                learn_estimate(
+                    &estimate_model,
+                    &task_control,
+                    &config,
+                    estimated_ms,
+                    report,
+                );
+            }
+            result
+        })
+        .await;
+        // 以降はコントローラーに何も送らないため、ここで停止要求を受領済みにする
+        control.mark_finished();
+
+        // 停止・エラーの場合も、描画できたドットだけを描画済みにする
+        let painted_dots = completion
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .painted_dots()
+            .to_vec();
+        if let Err(e) = state.mark_dots_painted(&artwork_id, &painted_dots).await {
+            error!(
+                "Failed to record painted dots for artwork {}: {}",
+                artwork_id, e
+            );
+        }
+        drop(lock);
+
+        // Clear active painting when done
+        release_active_painting(&active_painting_store, &control).await;
+
+        match result {
+            Ok(Ok(report)) => info!("Painting finished: {}", report.summary()),
+            Ok(Err(e)) => error!("Painting failed with hardware error: {}", e),
+            Err(e) => error!("Painting task panicked or was cancelled: {}", e),
+        }
+    });
+
+    let estimated_time_seconds = estimate.total_ms as f64 / 1000.0;
+    let corrected_estimated_time_seconds = corrected.corrected_ms() as f64 / 1000.0;
+    let mut message = if corrected.correction == 1.0 {
+        format!("Painting started (estimated time: {estimated_time_seconds:.1} seconds)")
+    } else {
+        format!(
+            "Painting started (estimated time: {estimated_time_seconds:.1} seconds, {corrected_estimated_time_seconds:.1} seconds with the {:.2}x correction learned from past runs)",
+            corrected.correction
+        )
+    };
+    if let Some(from_row) = from_row
+        && !already_complete.is_empty()
+    {
+        message.push_str(&format!(
+            "; skipped {} already-complete dots above row {from_row}",
+            already_complete.len()
+        ));
+    }
+    Ok(Json(PaintStartResponse {
+        success: true,
+        message,
+        estimated_time_seconds,
+        corrected_estimated_time_seconds,
+        estimate_correction: corrected.correction,
+        config: response_config,
+        warnings,
+        generation: Some(generation),
+        scheduled: None,
+        already_complete_dots: already_complete.len(),
+        trace_url,
+    }))
+}
+
+/// 描画を終えた実測の所要時間を、見積もりの補正モデルに記録する
+///
+/// 最後まで描かなかった描画、確認用の印の回答を待った描画、実行中にタイミングを変えた描画は
+/// 見積もりと比べられないため記録しない。一時停止していた時間は実測から除く。
+fn learn_estimate(
+    estimate_model: &EstimateModelStore,
+    control: &PaintingControl,
+    config: &DrawingCanvasConfig,
+    estimated_ms: u64,
+    report: &CompletionReport,
+) {
+    let timing_changed = control
+        .effective_config()
+        .is_some_and(|effective| effective.timing != config.timing);
+    if !report.is_complete() || config.options.preflight.is_some() || timing_changed {
+        return;
+    }
+    let actual_ms = report.duration_ms.saturating_sub(report.paused_ms);
+    if let Err(e) = estimate_model.record(config.timing, estimated_ms, actual_ms) {
+        warn!("{}", e);
+    }
+}
+
+/// 描画リクエストから、見積もりと描画の両方に使う設定を作成する
+///
+/// 省略された値は `PaintTiming` と `RunOptions` の既定値、一時停止はサーバーの設定を使う
+pub(crate) fn drawing_config(
+    request: &PaintRequest,
+    canvas: &Canvas,
+    pause: PauseSettings,
+    init_preset: InitPreset,
+    preflight: bool,
+) -> Result<DrawingCanvasConfig, ErrorResponse> {
+    if let Some(region) = &request.region {
+        region
+            .validate_within(canvas.width, canvas.height)
+            .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    }
+    if let Some(from_row) = request.from_row
+        && from_row >= canvas.height
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "from_row ({from_row}) must be less than the canvas height ({})",
+                canvas.height
+            ),
+        ));
+    }
+    if let Some(origin) = request.origin {
+        validate_origin(origin, canvas)?;
+    }
+    if let Some(timeout_secs) = request.preflight_timeout_secs
+        && !(1..=MAX_PREFLIGHT_TIMEOUT_SECS).contains(&timeout_secs)
+    {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("preflight_timeout_secs must be between 1 and {MAX_PREFLIGHT_TIMEOUT_SECS}"),
+        ));
+    }
+    // 描画済みの記録を消す場合は、完成度を0から数える
+    let stop_after = request
+        .stop_after
+        .map(|stop_after| {
+            let painted_before = match request.reset_progress {
+                Some(true) => 0,
+                _ => canvas.painted_dots().len(),
+            };
+            stop_after.resolve(painted_before, canvas.dots.len())
+        })
+        .transpose()
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+
+    let defaults = PaintTiming::default();
+    let timing = PaintTiming::new(
+        request.press_ms.unwrap_or(defaults.press_ms),
+        request.release_ms.unwrap_or(defaults.release_ms),
+        request.wait_ms.unwrap_or(defaults.wait_ms),
+    );
+    let adaptive = if request.adaptive.unwrap_or(false) {
+        let min_wait_ms = request.adaptive_min_wait_ms.unwrap_or(timing.wait_ms);
+        let max_wait_ms = request.adaptive_max_wait_ms.unwrap_or_else(|| {
+            min_wait_ms
+                .saturating_mul(4)
+                .max(min_wait_ms.saturating_add(100))
+                .min(AdaptiveTimingSettings::MAX_WAIT_MS)
+        });
+        for (field, value) in [
+            ("adaptive_min_wait_ms", min_wait_ms),
+            ("adaptive_max_wait_ms", max_wait_ms),
+        ] {
+            if value > AdaptiveTimingSettings::MAX_WAIT_MS {
+                return Err(ErrorResponse::new(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    format!(
+                        "{field} must be at most {}",
+                        AdaptiveTimingSettings::MAX_WAIT_MS
+                    ),
+                ));
+            }
+        }
+        if max_wait_ms < min_wait_ms {
+            return Err(ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!(
+                    "adaptive_max_wait_ms ({max_wait_ms}) must not be less than adaptive_min_wait_ms ({min_wait_ms})"
+                ),
+            ));
+        }
+        let mut settings = AdaptiveTimingSettings::new(min_wait_ms, max_wait_ms);
+        if let Some(scale_percent) = request.adaptive_scale_percent {
+            settings.scale_percent = scale_percent.max(101);
+        }
+        if let Some(threshold) = request.adaptive_latency_threshold_ms {
+            settings.latency_threshold_ms = threshold;
+        }
+        Some(settings)
+    } else {
+        None
+    };
+    let options = RunOptions {
+        repeats: request.repeats.unwrap_or(1).max(1), // Ensure at least 1 repeat
+        diagonal_moves: request.diagonal_moves.unwrap_or(false),
+        // 領域はアートワークの座標なので、移動先はゲーム内キャンバスの座標に直す
+        entry_point: request.region.map(|region| {
+            let top_left = region.top_left();
+            request
+                .origin
+                .map_or(top_left, |origin| top_left.offset_by(origin))
+        }),
+        origin: request.origin,
+        adaptive,
+        pause: PauseSettings {
+            mode: request.pause_mode.unwrap_or(pause.mode),
+            rehome_on_resume: request.rehome_on_resume.unwrap_or(pause.rehome_on_resume),
+            host_grace_ms: request.host_grace_ms.unwrap_or(pause.host_grace_ms),
+            auto_resume: request.auto_resume.unwrap_or(pause.auto_resume),
+            keepalive_idle_ms: match request.keepalive_idle_ms {
+                Some(0) => None,
+                Some(idle_ms) => Some(idle_ms),
+                None => pause.keepalive_idle_ms,
+            },
+        },
+        max_dot_attempts: request
+            .max_dot_attempts
+            .unwrap_or(DEFAULT_MAX_DOT_ATTEMPTS)
+            .clamp(1, MAX_DOT_ATTEMPTS_LIMIT),
+        stop_after,
+        neutral_clear: NeutralClearSettings {
+            skip_redundant: request.skip_redundant_neutral_clears.unwrap_or(true),
+            force_every_dots: request
+                .neutral_clear_every
+                .unwrap_or(DEFAULT_FORCED_NEUTRAL_CLEAR_EVERY),
+        },
+        preflight: request
+            .preflight
+            .unwrap_or(preflight)
+            .then(|| PreflightSettings {
+                timeout_secs: request
+                    .preflight_timeout_secs
+                    .unwrap_or(DEFAULT_PREFLIGHT_TIMEOUT_SECS),
+            }),
+    };
+
+    let drawing_mode = request.drawing_mode.unwrap_or_default();
+    let init_sequence = match (request.init_preset, &request.init_sequence) {
+        (Some(_), Some(_)) => {
+            return Err(ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "Specify either init_preset or init_sequence, not both",
+            ));
+        }
+        (_, Some(sequence)) => {
+            sequence
+                .validate()
+                .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+            sequence.clone()
+        }
+        (preset, None) => InitSequence::preset_for(preset.unwrap_or(init_preset), drawing_mode),
+    };
+
+    Ok(DrawingCanvasConfig {
+        width: canvas.width,
+        height: canvas.height,
+        drawing_mode,
+        init_sequence,
+        ..DrawingCanvasConfig::new(timing, options)
+    })
+}
+
+/// クエリ文字列の描画領域を解析し、キャンバス範囲内か検証する
+pub(crate) fn parse_region(value: &str, canvas: &Canvas) -> Result<CanvasRegion, ErrorResponse> {
+    let region: CanvasRegion =
+        value
+            .parse()
+            .map_err(|e: crate::domain::painting::CanvasRegionError| {
+                ErrorResponse::new(StatusCode::BAD_REQUEST, e.to_string())
+            })?;
+    region
+        .validate_within(canvas.width, canvas.height)
+        .map_err(|e| ErrorResponse::new(StatusCode::UNPROCESSABLE_ENTITY, e.to_string()))?;
+    Ok(region)
+}
+
+/// クエリ文字列の配置位置（`x,y`）を解析し、アートワークがゲーム内キャンバスに収まるか検証する
+pub(crate) fn parse_origin(value: &str, canvas: &Canvas) -> Result<Coordinates, ErrorResponse> {
+    let origin: Coordinates = value
+        .parse()
+        .map_err(|e: String| ErrorResponse::new(StatusCode::BAD_REQUEST, e))?;
+    validate_origin(origin, canvas)?;
+    Ok(origin)
+}
+
+/// `origin` に置いたアートワークがゲーム内キャンバスに収まるか検証する
+pub(crate) fn validate_origin(origin: Coordinates, canvas: &Canvas) -> Result<(), ErrorResponse> {
+    let game_canvas = DrawingCanvasConfig::default();
+    CanvasRegion::new(origin.x, origin.y, canvas.width, canvas.height)
+        .validate_within(game_canvas.width, game_canvas.height)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("Artwork placed at origin {origin} does not fit the game canvas: {e}"),
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::artworks::{
+        CreateArtworkQuery, CreateArtworkRequest, GetPathRequest, StrategyComparisonRequest,
+        create_artwork, duplicate_artwork, get_artwork_diff, get_artwork_path,
+        get_artwork_strategies, update_painting_preferences,
+    };
+    use super::super::test_support::{TestClient, artwork_state_with};
+    use super::*;
+    use crate::application::estimate_model::ESTIMATE_MODEL_FILE;
+    use crate::domain::artwork::dot_diff::{DotDiff, DotOp, DotRun};
+    use crate::domain::artwork::entities::{ArtworkMetadata, Dot};
+    use crate::domain::controller::{
+        ActionType, Button, ControllerCommand, ControllerEmulator, StickPosition,
+    };
+    use crate::domain::hardware::errors::HardwareError;
+    use crate::domain::painting::{
+        CompletionTracker, DotOutcome, DrawingPath, RunOutcome, SleepGuardSettings,
+    };
+    use crate::infrastructure::hardware::mock_controller::MockController;
+    use axum::body::Body;
+    use axum::http::{Request, header};
+    use std::sync::atomic::AtomicBool;
+
+    /// 複数の描画が同時にコントローラーを操作したかを記録する
+    struct ExclusiveController {
+        inner: Arc<MockController>,
+        busy: AtomicBool,
+        overlapped: AtomicBool,
+    }
+
+    impl ControllerEmulator for ExclusiveController {
+        fn initialize(&self) -> Result<(), HardwareError> {
+            self.inner.initialize()
+        }
+
+        fn is_connected(&self) -> Result<bool, HardwareError> {
+            self.inner.is_connected()
+        }
+
+        fn execute_command(&self, command: &ControllerCommand) -> Result<(), HardwareError> {
+            if self.busy.swap(true, Ordering::SeqCst) {
+                self.overlapped.store(true, Ordering::SeqCst);
+            }
+            let result = self.inner.execute_command(command);
+            self.busy.store(false, Ordering::SeqCst);
+            result
+        }
+
+        fn write_raw_report(&self, report: &[u8]) -> Result<usize, HardwareError> {
+            self.inner.write_raw_report(report)
+        }
+
+        fn shutdown(&self) -> Result<(), HardwareError> {
+            self.inner.shutdown()
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_no_run_continues_past_acknowledged_stop() {
+        let mock = Arc::new(MockController::new().with_command_log());
+        let controller = Arc::new(ExclusiveController {
+            inner: mock.clone(),
+            busy: AtomicBool::new(false),
+            overlapped: AtomicBool::new(false),
+        });
+        let state = Arc::new(ArtworkState::new(controller.clone()));
+        state.interlock.arm("test", None);
+        let paint = || {
+            serde_json::from_value::<PaintRequest>(serde_json::json!({
+                "press_ms": 1,
+                "release_ms": 1,
+                "wait_ms": 0,
+                "init_preset": "none",
+                "reset_progress": true
+            }))
+            .unwrap()
+        };
+
+        for round in 0..40u16 {
+            // 描画の長さを変えて、開始直後・描画中・終了間際のそれぞれで停止させる
+            let mut canvas = Canvas::new(16, 2);
+            for x in 0..=(round % 16) {
+                canvas
+                    .set_dot(Coordinates::new(x, round % 2), Dot::black())
+                    .unwrap();
+            }
+            let artwork = Artwork::new(
+                ArtworkMetadata::new(format!("race {round}")),
+                "api".to_string(),
+                canvas,
+            );
+            let id = artwork.id.as_str();
+            state.artworks.save(&artwork).await.unwrap();
+
+            // 同時に開始しても、コントローラーを操作する描画は常に1つだけ
+            let starts: Vec<_> = (0..2)
+                .map(|_| {
+                    tokio::spawn(paint_artwork(
+                        State(state.clone()),
+                        Path(id.clone()),
+                        Json(paint()),
+                    ))
+                })
+                .collect();
+            let mut generation = None;
+            for start in starts {
+                match start.await.unwrap() {
+                    Ok(Json(response)) => generation = generation.max(response.generation),
+                    Err(error) => assert_eq!(error.status(), StatusCode::CONFLICT),
+                }
+            }
+            let generation = generation.expect("neither paint started");
+
+            let pauses: Vec<_> = (0..2)
+                .map(|_| {
+                    tokio::spawn(pause_painting(
+                        State(state.clone()),
+                        Query(PausePaintingQuery {
+                            generation: Some(generation),
+                            paused: None,
+                        }),
+                    ))
+                })
+                .collect();
+            let stop = tokio::spawn(stop_painting(
+                State(state.clone()),
+                Query(StopPaintingQuery {
+                    generation: Some(generation),
+                }),
+            ));
+            for pause in pauses {
+                if let Err(error) = pause.await.unwrap() {
+                    assert_eq!(error.status(), StatusCode::GONE);
+                }
+            }
+            match stop.await.unwrap() {
+                Ok(Json(response)) => {
+                    assert!(response.success);
+                    assert!(
+                        response.acknowledged,
+                        "round {round}: stop not acknowledged"
+                    );
+                    let sent = mock.recorded_commands().len();
+                    tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+                    assert_eq!(
+                        mock.recorded_commands().len(),
+                        sent,
+                        "round {round}: run {generation} kept painting after its stop was acknowledged"
+                    );
+                }
+                Err(error) => assert_eq!(error.status(), StatusCode::GONE),
+            }
+
+            assert!(
+                state
+                    .wait_for_painting_to_finish(std::time::Duration::from_secs(5))
+                    .await
+            );
+        }
+        assert!(!controller.overlapped.load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_gallery_state_shows_live_progress_of_active_painting() {
+        let mut canvas = Canvas::new(4, 2);
+        for x in 0..4 {
+            canvas
+                .set_dot(Coordinates::new(x, 0), Dot::black())
+                .unwrap();
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("live".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let state = artwork_state_with(artwork.clone()).await;
+
+        // 描画中のドットはまだアートワークに記録されていない
+        let control = PaintingControl::new(1, 1, 1, 0).with_event_log(PaintingEventLog {
+            artwork_id: artwork.id.clone(),
+            version: artwork.version,
+            events: state.events.clone(),
+        });
+        {
+            let mut completion = control.completion.lock().unwrap();
+            *completion = CompletionTracker::new(4);
+            completion.record(Coordinates::new(0, 0), DotOutcome::Painted);
+            completion.record(Coordinates::new(1, 0), DotOutcome::Painted);
+        }
+        control.set_cursor(Coordinates::new(1, 0));
+        state.begin_painting(&control).await.unwrap();
+
+        let gallery = crate::interfaces::web::gallery::gallery_state(&state)
+            .await
+            .unwrap();
+        assert!(gallery.active);
+        assert_eq!(gallery.artwork_name.as_deref(), Some("live"));
+        assert_eq!(gallery.completion_ratio, 0.5);
+        assert_eq!(gallery.cursor, Some(Coordinates::new(1, 0)));
+        assert!(gallery.eta_seconds.is_some());
+        let thumbnail = gallery.thumbnail.unwrap();
+        assert_eq!(thumbnail.rows, vec!["##oo", "...."]);
+
+        // 描画の状態も同じ縮小図を、指定した場合だけ返す（`monitor` が使う）
+        let status = |thumbnail| {
+            get_painting_status(
+                State(state.clone()),
+                Query(PaintingStatusQuery {
+                    thumbnail: Some(thumbnail),
+                }),
+            )
+        };
+        let Json(with_thumbnail) = status(true).await.unwrap();
+        assert_eq!(
+            with_thumbnail.artwork_id.as_deref(),
+            Some(artwork.id.as_str().as_str())
+        );
+        assert_eq!(with_thumbnail.total_dots, Some(4));
+        assert!(with_thumbnail.eta_seconds.is_some());
+        assert_eq!(with_thumbnail.thumbnail, Some(thumbnail));
+        assert_eq!(status(false).await.unwrap().thumbnail, None);
+
+        // キャリブレーションなど、アートワークの無い実行は公開しない
+        release_active_painting(&state.active_painting, &control).await;
+        state
+            .begin_painting(&PaintingControl::new(1, 1, 1, 0))
+            .await
+            .unwrap();
+        let gallery = crate::interfaces::web::gallery::gallery_state(&state)
+            .await
+            .unwrap();
+        assert!(!gallery.active);
+        assert_eq!(gallery.cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_painting_run_is_finalized_when_thread_panics() {
+        let controller = Arc::new(MockController::new().without_delays());
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("runs".to_string()),
+            "api".to_string(),
+            Canvas::new(4, 4),
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork.clone()).await;
+        let control = PaintingControl::new(1, 100, 60, 40);
+        let run = PaintingRun::start(
+            artwork.id.clone(),
+            artwork.version,
+            DrawingStrategy::RasterScan,
+            PaintTiming::new(100, 60, 40),
+            1,
+            &DrawingPath::new((0..10).map(|x| Coordinates::new(x, 0)).collect()),
+        );
+        state.runs.save(&run);
+
+        let (guard_controller, runs) = (controller.clone(), state.runs.clone());
+        let result = run_controller_io(move || {
+            let _guard = PaintingRunGuard::new(guard_controller, control.clone(), runs, run);
+            control.painted.store(3, Ordering::SeqCst);
+            panic!("controller thread crashed");
+        })
+        .await;
+        assert!(result.is_err());
+
+        // パニック時もニュートラルに戻し、記録をエラーとして確定する
+        assert_eq!(controller.recorded_operations().neutral_clears, 1);
+        let Json(runs) = list_artwork_runs(State(state.clone()), Path(id))
+            .await
+            .unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].dots_painted, 3);
+        assert!(runs[0].finished_at.is_some());
+        assert!(matches!(runs[0].outcome, Some(RunOutcome::Error { .. })));
+
+        let Json(recent) =
+            list_painting_runs(State(state), Query(PaintingRunsQuery { limit: Some(0) })).await;
+        assert!(recent.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_skipped_dots_are_reported_and_left_unpainted() {
+        let mut canvas = Canvas::new(8, 2);
+        for x in 1..=4 {
+            canvas
+                .set_dot(Coordinates::new(x, 0), Dot::black())
+                .unwrap();
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("skips".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let state = artwork_state_with(artwork.clone()).await;
+
+        // 2つ目のドットは1回失敗してから描画でき、3つ目のドットは3回とも失敗する
+        let mock = Arc::new(
+            MockController::new()
+                .without_delays()
+                .with_command_failures("Paint Dot", &[2, 4, 5, 6]),
+        );
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let drawing_path = DrawingPath::new((1..=4).map(|x| Coordinates::new(x, 0)).collect());
+        let mut config = DrawingCanvasConfig::new(PaintTiming::new(1, 1, 0), RunOptions::default());
+        config.init_sequence = InitSequence::preset(InitPreset::None);
+        let control = PaintingControl::from_config(&config);
+        let run = PaintingRun::start(
+            artwork.id.clone(),
+            artwork.version,
+            DrawingStrategy::RasterScan,
+            config.timing,
+            1,
+            &drawing_path,
+        );
+        state.runs.save(&run);
+
+        let completion = control.completion.clone();
+        let runs = state.runs.clone();
+        let report = run_controller_io(move || {
+            let mut guard = PaintingRunGuard::new(controller.clone(), control.clone(), runs, run);
+            let result = perform_painting(controller, drawing_path, &config, control);
+            guard.finish(&result);
+            result
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+        assert_eq!(report.total_dots, 4);
+        assert_eq!(report.succeeded, 3);
+        assert_eq!(report.retried, 1);
+        assert_eq!(report.skipped, 1);
+        assert_eq!(report.total_retries, 3);
+        assert_eq!((report.skipped_dots[0].x, report.skipped_dots[0].y), (3, 0));
+        assert_eq!(mock.recorded_operations().a_presses, 3);
+
+        // 描画できたドットだけを描画済みにする
+        let painted = completion.lock().unwrap().painted_dots().to_vec();
+        state
+            .mark_dots_painted(&artwork.id, &painted)
+            .await
+            .unwrap();
+        let artwork = state
+            .find_artwork(&artwork.id.as_str())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(!artwork.canvas.dots[&Coordinates::new(3, 0)].is_painted);
+        assert_eq!(artwork.canvas.painted_dots().len(), 3);
+        assert_eq!(artwork.drawable_dots(), 1);
+
+        // 次の描画を開始するまで、状態APIで結果を確認できる
+        let Json(status) = get_painting_status(State(state), Query(PaintingStatusQuery::default()))
+            .await
+            .unwrap();
+        let last_run = status.last_run.unwrap();
+        assert_eq!(
+            last_run.outcome,
+            Some(RunOutcome::CompletedWithErrors { skipped: 1 })
+        );
+        assert_eq!(last_run.dots_painted, 3);
+        assert_eq!(last_run.report, Some(report));
+    }
+
+    #[tokio::test]
+    async fn test_strict_sleep_guard_refuses_long_paint_until_acknowledged() {
+        let mut canvas = Canvas::new(8, 4);
+        for x in 0..8 {
+            canvas
+                .set_dot(Coordinates::new(x, 0), Dot::black())
+                .unwrap();
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("long".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let state = ArtworkState::new(Arc::new(MockController::new().without_delays()))
+            .with_sleep_guard(SleepGuardSettings {
+                max_uninterrupted_minutes: 1,
+                strict: true,
+            });
+        state.artworks.save(&artwork).await.unwrap();
+        let state = Arc::new(state);
+        state.interlock.arm("test", None);
+
+        let paint = |acknowledge: bool| {
+            serde_json::from_value::<PaintRequest>(serde_json::json!({
+                "press_ms": 5000,
+                "release_ms": 5000,
+                "wait_ms": 0,
+                "init_preset": "none",
+                "acknowledge_sleep_risk": acknowledge
+            }))
+            .unwrap()
+        };
+        let error = paint_artwork(State(state.clone()), Path(id.clone()), Json(paint(false)))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(state.active_painting.read().await.is_none());
+
+        let Json(response) =
+            paint_artwork(State(state.clone()), Path(id.clone()), Json(paint(true)))
+                .await
+                .unwrap();
+        assert!(response.success, "{}", response.message);
+        assert_eq!(response.warnings.len(), 1);
+        assert!(
+            response.warnings[0].contains("auto-sleep threshold (1m)"),
+            "{}",
+            response.warnings[0]
+        );
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(5))
+                .await
+        );
+    }
+
+    #[tokio::test]
+    async fn test_paint_at_origin_places_artwork_on_game_canvas() {
+        // 100x80 のアートワークを (220, 40) に置くと、右下がゲーム内キャンバスの右下に揃う
+        let mut canvas = Canvas::new(100, 80);
+        for (x, y) in [(0, 0), (99, 79)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("placed".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+        state.interlock.arm("test", None);
+
+        let path = |origin: &str, region: Option<&str>| {
+            get_artwork_path(
+                State(state.clone()),
+                Path(id.clone()),
+                Query(GetPathRequest {
+                    strategy: Some(DrawingStrategy::RasterScan),
+                    region: region.map(str::to_string),
+                    origin: Some(origin.to_string()),
+                    seed: None,
+                    two_opt_budget_ms: None,
+                }),
+            )
+        };
+        let Json(flush) = path("220,40", None).await.unwrap();
+        assert_eq!(
+            flush.path,
+            vec![Coordinates::new(220, 40), Coordinates::new(319, 119)]
+        );
+        for overflowing in ["221,40", "220,41"] {
+            let error = path(overflowing, None).await.unwrap_err();
+            assert_eq!(
+                error.status(),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "{overflowing}"
+            );
+        }
+        assert_eq!(
+            path("220", None).await.unwrap_err().status(),
+            StatusCode::BAD_REQUEST
+        );
+
+        // 領域はアートワークの座標で指定する
+        let Json(corner) = path("220,40", Some("90,70,10,10")).await.unwrap();
+        assert_eq!(corner.path, vec![Coordinates::new(319, 119)]);
+
+        // 戦略比較も左上からの長い移動を含めて見積もる
+        let strategies = |origin: Option<&str>| {
+            get_artwork_strategies(
+                State(state.clone()),
+                Path(id.clone()),
+                Query(
+                    serde_json::from_value::<StrategyComparisonRequest>(serde_json::json!({
+                        "origin": origin
+                    }))
+                    .unwrap(),
+                ),
+            )
+        };
+        let Json(placed) = strategies(Some("220,40")).await.unwrap();
+        let Json(unplaced) = strategies(None).await.unwrap();
+        for (placed, unplaced) in placed.strategies.iter().zip(&unplaced.strategies) {
+            assert!(
+                placed.estimated_time_seconds > unplaced.estimated_time_seconds,
+                "{:?}",
+                placed.strategy
+            );
+            assert_eq!(placed.dpad_operations, unplaced.dpad_operations + 260);
+        }
+        assert_eq!(
+            strategies(Some("221,40")).await.unwrap_err().status(),
+            StatusCode::UNPROCESSABLE_ENTITY
+        );
+
+        let paint_request = |origin: serde_json::Value| {
+            serde_json::from_value::<PaintRequest>(serde_json::json!({
+                "press_ms": 1,
+                "release_ms": 1,
+                "wait_ms": 0,
+                "init_preset": "none",
+                "strategy": "RasterScan",
+                "origin": origin,
+                "region": { "x": 90, "y": 70, "width": 10, "height": 10 }
+            }))
+            .unwrap()
+        };
+        let error = paint_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Json(paint_request(serde_json::json!({ "x": 221, "y": 40 }))),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let Json(response) = paint_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Json(paint_request(serde_json::json!({ "x": 220, "y": 40 }))),
+        )
+        .await
+        .unwrap();
+        assert!(response.success, "{}", response.message);
+        assert_eq!(response.config.origin, Some(Coordinates::new(220, 40)));
+        assert_eq!(
+            response.config.entry_point,
+            Some(Coordinates::new(310, 110))
+        );
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+
+        // 実行の経路は同じ条件の経路と一致し、描画済みの記録はアートワークの座標で残る
+        assert_eq!(state.runs.recent(1)[0].path_hash, corner.path_hash);
+        let artwork = state.find_artwork(&id).await.unwrap().unwrap();
+        let painted: Vec<Coordinates> = artwork
+            .canvas
+            .painted_dots()
+            .into_iter()
+            .map(|(coord, _)| *coord)
+            .collect();
+        assert_eq!(painted, vec![Coordinates::new(99, 79)]);
+    }
+
+    #[tokio::test]
+    async fn test_painting_records_started_and_finished_events() {
+        let mut canvas = Canvas::new(4, 2);
+        canvas
+            .set_dot(Coordinates::new(1, 1), Dot::black())
+            .unwrap();
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("events".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str().to_string();
+        let state = artwork_state_with(artwork).await;
+        state.interlock.arm("test", None);
+        let mut subscriber = state.events.subscribe();
+
+        let request = serde_json::from_value::<PaintRequest>(serde_json::json!({
+            "press_ms": 1,
+            "release_ms": 1,
+            "wait_ms": 0,
+            "init_preset": "none"
+        }))
+        .unwrap();
+        let Json(response) = paint_artwork(State(state.clone()), Path(id.clone()), Json(request))
+            .await
+            .unwrap();
+        assert!(response.success, "{}", response.message);
+
+        let started = subscriber.recv().await.unwrap();
+        assert!(
+            matches!(
+                started,
+                ArtworkEvent::PaintingStarted {
+                    total_dots_to_paint: 1,
+                    ..
+                }
+            ),
+            "{started:?}"
+        );
+        let finished = tokio::time::timeout(std::time::Duration::from_secs(10), subscriber.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(
+            matches!(
+                finished,
+                ArtworkEvent::PaintingCompleted {
+                    total_dots_painted: 1,
+                    ..
+                }
+            ),
+            "{finished:?}"
+        );
+        assert!(
+            matches!(&finished, ArtworkEvent::PaintingCompleted { artwork_id, .. } if artwork_id.as_str() == id)
+        );
+        assert_eq!(state.events.read().await.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_completed_runs_teach_the_estimate_model() {
+        let mut canvas = Canvas::new(4, 2);
+        for x in 0..3 {
+            canvas
+                .set_dot(Coordinates::new(x, 1), Dot::black())
+                .unwrap();
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("estimate".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str().to_string();
+        let paint_request = || {
+            Json(
+                serde_json::from_value::<PaintRequest>(serde_json::json!({
+                    "press_ms": 2,
+                    "release_ms": 1,
+                    "wait_ms": 0,
+                    "init_preset": "none",
+                    "reset_progress": true
+                }))
+                .unwrap(),
+            )
+        };
+
+        // シミュレーションの既定では学習も補正もしない
+        let simulated = artwork_state_with(artwork.clone()).await;
+        simulated.interlock.arm("test", None);
+        let Json(response) =
+            paint_artwork(State(simulated.clone()), Path(id.clone()), paint_request())
+                .await
+                .unwrap();
+        assert_eq!(response.estimate_correction, 1.0);
+        assert_eq!(
+            response.corrected_estimated_time_seconds,
+            response.estimated_time_seconds
+        );
+        assert!(
+            simulated
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+        let Json(model) = get_estimate_model(State(simulated)).await;
+        assert!(!model.enabled);
+        assert!(model.buckets.is_empty());
+
+        let dir = std::env::temp_dir().join(format!("estimate-route-{}", uuid::Uuid::new_v4()));
+        let state = ArtworkState::new(Arc::new(MockController::new()))
+            .with_estimate_model(EstimateModelStore::open(&dir));
+        state.artworks.save(&artwork).await.unwrap();
+        state.interlock.arm("test", None);
+        let state = Arc::new(state);
+        let Json(first) = paint_artwork(State(state.clone()), Path(id.clone()), paint_request())
+            .await
+            .unwrap();
+        assert_eq!(first.estimate_correction, 1.0);
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+
+        let client = TestClient::new(state.clone());
+        let model = client.get("/api/painting/estimate-model").await.json();
+        assert_eq!(model["enabled"], true);
+        assert_eq!(model["max_correction"], MAX_ESTIMATE_CORRECTION);
+        let buckets = model["buckets"].as_array().unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(
+            buckets[0]["timing"],
+            serde_json::json!({ "press_ms": 2, "release_ms": 1, "wait_ms": 0 })
+        );
+        assert_eq!(buckets[0]["runs"], 1);
+        // JSONを読み直した値は最後の桁がずれることがあるため、係数は応答の型から取る
+        let Json(learned) = get_estimate_model(State(state.clone())).await;
+        let factor = learned.buckets[0].factor;
+        assert_eq!(buckets[0]["factor"].as_f64().unwrap() as f32, factor as f32);
+        assert!((MIN_ESTIMATE_CORRECTION..=MAX_ESTIMATE_CORRECTION).contains(&factor));
+
+        // 次の描画の見積もりには学習した係数を掛ける
+        let Json(second) = paint_artwork(State(state.clone()), Path(id.clone()), paint_request())
+            .await
+            .unwrap();
+        assert!(second.success, "{}", second.message);
+        assert_eq!(second.estimate_correction, factor);
+        assert_eq!(second.estimated_time_seconds, first.estimated_time_seconds);
+        assert!(
+            (second.corrected_estimated_time_seconds - second.estimated_time_seconds * factor)
+                .abs()
+                < 0.01
+        );
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+
+        let response = client.delete("/api/painting/estimate-model").await;
+        assert_eq!(response.status, StatusCode::OK);
+        let model = client.get("/api/painting/estimate-model").await.json();
+        assert_eq!(model["buckets"], serde_json::json!([]));
+        assert!(!dir.join(ESTIMATE_MODEL_FILE).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_paint_from_row_skips_rows_above_and_reports_last_completed_row() {
+        let mut canvas = Canvas::new(4, 4);
+        for (x, y) in [(0, 0), (3, 0), (1, 1), (2, 2), (0, 3), (3, 3)] {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("from-row".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str().to_string();
+        let state = artwork_state_with(artwork).await;
+        state.interlock.arm("test", None);
+
+        let paint_request = |extra: serde_json::Value| {
+            let mut request = serde_json::json!({
+                "press_ms": 1,
+                "release_ms": 1,
+                "wait_ms": 0,
+                "init_preset": "none",
+                "strategy": "ZigZag"
+            });
+            request
+                .as_object_mut()
+                .unwrap()
+                .extend(extra.as_object().unwrap().clone());
+            Json(serde_json::from_value::<PaintRequest>(request).unwrap())
+        };
+
+        let error = paint_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            paint_request(serde_json::json!({ "from_row": 4 })),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // 領域 (1,0)-(3,3) と2行目以降の両方で絞り込む
+        let Json(response) = paint_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            paint_request(serde_json::json!({
+                "from_row": 2,
+                "region": { "x": 1, "y": 0, "width": 3, "height": 4 }
+            })),
+        )
+        .await
+        .unwrap();
+        assert!(response.success, "{}", response.message);
+        assert_eq!(response.already_complete_dots, 2);
+        assert!(response.message.contains("2 already-complete dots"));
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+
+        let run = &state.runs.recent(1)[0];
+        assert_eq!(run.dots_attempted, 2);
+        assert_eq!(run.report.as_ref().unwrap().last_completed_row, Some(3));
+        let artwork = state.find_artwork(&id).await.unwrap().unwrap();
+        let mut painted: Vec<Coordinates> = artwork
+            .canvas
+            .painted_dots()
+            .into_iter()
+            .map(|(coord, _)| *coord)
+            .collect();
+        painted.sort_unstable_by_key(|coord| (coord.y, coord.x));
+        // 領域外の (0,0) と (0,3) は描画済みにしない
+        assert_eq!(
+            painted,
+            vec![
+                Coordinates::new(3, 0),
+                Coordinates::new(1, 1),
+                Coordinates::new(2, 2),
+                Coordinates::new(3, 3)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_painting_preferences_fill_in_omitted_paint_options() {
+        let mut canvas = Canvas::new(4, 2);
+        canvas
+            .set_dot(Coordinates::new(3, 1), Dot::black())
+            .unwrap();
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("preferences".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str().to_string();
+        let state = artwork_state_with(artwork).await;
+        state.interlock.arm("test", None);
+
+        let update = |preferences: serde_json::Value| {
+            update_painting_preferences(
+                State(state.clone()),
+                Path(id.clone()),
+                Json(serde_json::from_value(preferences).unwrap()),
+            )
+        };
+        let error = update(serde_json::json!({ "origin": { "x": 317, "y": 0 } }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let Json(summary) = update(serde_json::json!({
+            "press_ms": 5,
+            "wait_ms": 7,
+            "strategy": "ZigZag",
+            "diagonal_moves": true,
+            "origin": { "x": 10, "y": 20 }
+        }))
+        .await
+        .unwrap();
+        let preferences = summary.painting_preferences.unwrap();
+        assert_eq!(preferences.press_ms, Some(5));
+        assert_eq!(preferences.release_ms, None);
+
+        // リクエスト > アートワーク > サーバーの既定値の順に使う
+        let request = serde_json::from_value::<PaintRequest>(serde_json::json!({
+            "wait_ms": 3,
+            "init_preset": "none"
+        }))
+        .unwrap();
+        let Json(response) = paint_artwork(State(state.clone()), Path(id.clone()), Json(request))
+            .await
+            .unwrap();
+        assert!(response.success, "{}", response.message);
+        assert_eq!(response.config.press_ms, 5);
+        assert_eq!(
+            response.config.release_ms,
+            PaintTiming::default().release_ms
+        );
+        assert_eq!(response.config.wait_ms, 3);
+        assert!(response.config.diagonal_moves);
+        assert_eq!(response.config.origin, Some(Coordinates::new(10, 20)));
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+
+        // 複製と、取得した設定を付けた作成（インポート）でも引き継がれる
+        let Json(copy) = duplicate_artwork(State(state.clone()), Path(id.clone()), None)
+            .await
+            .unwrap();
+        assert_eq!(
+            copy.artwork.unwrap().painting_preferences,
+            Some(preferences)
+        );
+        let stored = state.find_artwork(&id).await.unwrap().unwrap();
+        let mut request = CreateArtworkRequest::from_canvas("imported".to_string(), &stored.canvas);
+        request.painting_preferences = summary.painting_preferences;
+        let Ok(Json(imported)) = create_artwork(
+            State(state.clone()),
+            Query(CreateArtworkQuery::default()),
+            Ok(Json(request)),
+        )
+        .await
+        else {
+            panic!("create_artwork failed");
+        };
+        assert_eq!(
+            imported.artwork.unwrap().painting_preferences,
+            Some(preferences)
+        );
+
+        // 空のオブジェクトで消去する
+        let Json(summary) = update(serde_json::json!({})).await.unwrap();
+        assert_eq!(summary.painting_preferences, None);
+    }
+
+    #[tokio::test]
+    async fn test_paint_vector_draws_strokes_with_the_stick() {
+        let controller = Arc::new(MockController::new().without_delays().with_command_log());
+        let state = Arc::new(ArtworkState::new(controller.clone()));
+        state.interlock.arm("test", None);
+        let client = TestClient::new(state.clone());
+
+        let mut request = serde_json::json!({
+            "name": "signature",
+            "width": 40,
+            "height": 20,
+            "dots": [],
+            "vector_paths": [{ "points": [{ "x": 0, "y": 0 }, { "x": 40, "y": 0 }] }]
+        });
+        let response = client.post("/api/artworks", request.clone()).await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        request["vector_paths"][0]["points"][1]["x"] = 39.into();
+        let response = client.post("/api/artworks", request).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let id = response.json()["artwork"]["id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let paint = serde_json::json!({
+            "px_per_sec": 200.0,
+            "tilt": 0.5,
+            "press_ms": 1,
+            "release_ms": 1,
+            "init_preset": "none"
+        });
+        let response = client
+            .post(&format!("/api/artworks/{id}/paint-vector"), paint.clone())
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let body = response.json();
+        assert_eq!(body["strokes"], 1);
+        assert_eq!(body["length_px"], 39.0);
+        assert!(body["max_deviation_px"].as_f64().unwrap() > 0.0);
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+        let stroke = controller.recorded_commands().pop().unwrap();
+        assert_eq!(stroke.name, "Stroke 1");
+        assert_eq!(
+            stroke.sequence[1].action_type,
+            ActionType::MoveLeftStick(StickPosition::new(192, 128))
+        );
+        // ドット描画の経路は使わないため、描画済みの記録は変わらない
+        let artwork = state.artwork_or_not_found(&id).await.unwrap();
+        assert!(artwork.canvas.painted_dots().is_empty());
+
+        let response = client
+            .put(
+                &format!("/api/artworks/{id}/vector-paths"),
+                serde_json::json!({ "vector_paths": [] }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.json().get("vector_paths").is_none());
+        let response = client
+            .post(&format!("/api/artworks/{id}/paint-vector"), paint)
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_paint_diff_paints_only_dots_missing_from_base() {
+        let canvas_with = |dots: &[(u16, u16)]| {
+            let mut canvas = Canvas::new(8, 4);
+            for &(x, y) in dots {
+                canvas
+                    .set_dot(Coordinates::new(x, y), Dot::black())
+                    .unwrap();
+            }
+            canvas
+        };
+        let base = Artwork::new(
+            ArtworkMetadata::new("v1".to_string()),
+            "api".to_string(),
+            canvas_with(&[(0, 0), (1, 0), (2, 0)]),
+        );
+        let target = Artwork::new(
+            ArtworkMetadata::new("v2".to_string()),
+            "api".to_string(),
+            canvas_with(&[(1, 0), (2, 0), (5, 1), (6, 3)]),
+        );
+        let other_size = Artwork::new(
+            ArtworkMetadata::new("wide".to_string()),
+            "api".to_string(),
+            Canvas::new(9, 4),
+        );
+        let (base_id, target_id) = (base.id.as_str(), target.id.as_str());
+        let state = artwork_state_with(base).await;
+        state.artworks.save(&target).await.unwrap();
+        state.artworks.save(&other_size).await.unwrap();
+        state.interlock.arm("test", None);
+
+        let Json(diff) = get_artwork_diff(
+            State(state.clone()),
+            Path((base_id.clone(), target_id.clone())),
+        )
+        .await
+        .unwrap();
+        assert_eq!(diff.only_in_a.count, 1);
+        assert_eq!(diff.only_in_b.count, 2);
+        assert_eq!(diff.only_in_b.coordinates[0], Coordinates::new(5, 1));
+        assert_eq!(diff.in_both.count, 2);
+        assert!(!diff.in_both.truncated);
+
+        let error = get_artwork_diff(
+            State(state.clone()),
+            Path((base_id.clone(), other_size.id.as_str())),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let paint_diff = |base_artwork_id: String| {
+            serde_json::from_value::<PaintDiffRequest>(serde_json::json!({
+                "base_artwork_id": base_artwork_id,
+                "press_ms": 1,
+                "release_ms": 1,
+                "wait_ms": 0,
+                "init_preset": "none"
+            }))
+            .unwrap()
+        };
+        let error = paint_artwork_diff(
+            State(state.clone()),
+            Path(other_size.id.as_str()),
+            Json(paint_diff(base_id.clone())),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let Json(response) = paint_artwork_diff(
+            State(state.clone()),
+            Path(target_id.clone()),
+            Json(paint_diff(base_id.clone())),
+        )
+        .await
+        .unwrap();
+        assert!(response.success, "{}", response.message);
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(5))
+                .await
+        );
+
+        // 元のアートワークにもあるドットは描かない
+        let target = state.find_artwork(&target_id).await.unwrap().unwrap();
+        let mut painted: Vec<_> = target
+            .canvas
+            .painted_dots()
+            .into_iter()
+            .map(|(coord, _)| *coord)
+            .collect();
+        painted.sort_unstable_by_key(|coord| (coord.y, coord.x));
+        assert_eq!(painted, [Coordinates::new(5, 1), Coordinates::new(6, 3)]);
+    }
+
+    #[test]
+    fn test_adaptive_wait_bounds_are_validated() {
+        let canvas = Canvas::new(8, 8);
+        let config_for = |body: serde_json::Value| {
+            let request: PaintRequest = serde_json::from_value(body).unwrap();
+            drawing_config(
+                &request,
+                &canvas,
+                PauseSettings::default(),
+                InitPreset::None,
+                false,
+            )
+        };
+
+        let adaptive = config_for(serde_json::json!({ "adaptive": true, "wait_ms": 20 }))
+            .unwrap()
+            .options
+            .adaptive
+            .unwrap();
+        assert_eq!((adaptive.min_wait_ms, adaptive.max_wait_ms), (20, 120));
+        // 上限の既定値は下限から計算しても上限を超えない
+        let adaptive = config_for(serde_json::json!({ "adaptive": true, "wait_ms": 50_000 }))
+            .unwrap()
+            .options
+            .adaptive
+            .unwrap();
+        assert_eq!(adaptive.max_wait_ms, AdaptiveTimingSettings::MAX_WAIT_MS);
+
+        for body in [
+            serde_json::json!({ "adaptive": true, "wait_ms": 1_073_741_824u32 }),
+            serde_json::json!({ "adaptive": true, "adaptive_min_wait_ms": u32::MAX }),
+            serde_json::json!({ "adaptive": true, "adaptive_max_wait_ms": 60_001 }),
+        ] {
+            let error = config_for(body).unwrap_err();
+            assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        }
+    }
+
+    #[test]
+    fn test_paint_request_selects_init_sequence() {
+        let canvas = Canvas::new(8, 8);
+        let config_for = |body: serde_json::Value, preset: InitPreset| {
+            let request: PaintRequest = serde_json::from_value(body).unwrap();
+            drawing_config(&request, &canvas, PauseSettings::default(), preset, false)
+        };
+
+        // 指定がなければサーバーの既定の手順を使う
+        let config = config_for(serde_json::json!({}), InitPreset::None).unwrap();
+        assert!(config.init_sequence.steps.is_empty());
+        let config = config_for(
+            serde_json::json!({ "init_preset": "splatoon3_post_editor" }),
+            InitPreset::None,
+        )
+        .unwrap();
+        assert_eq!(config.init_sequence, InitSequence::splatoon3_post_editor());
+        assert_eq!(config.drawing_mode, DrawingMode::PixelPen);
+
+        // 描画モードに合わせてプリセットのペンの選択が変わる
+        let config = config_for(
+            serde_json::json!({ "drawing_mode": "thick_brush" }),
+            InitPreset::Splatoon3PostEditor,
+        )
+        .unwrap();
+        assert_eq!(config.drawing_mode, DrawingMode::ThickBrush);
+        assert_eq!(
+            config.init_sequence,
+            InitSequence::splatoon3_post_editor_for(DrawingMode::ThickBrush)
+        );
+        let preferences = PaintingPreferences {
+            drawing_mode: Some(DrawingMode::Eraser),
+            ..PaintingPreferences::default()
+        };
+        let request = PaintRequest::default().with_preferences(Some(&preferences));
+        assert_eq!(request.drawing_mode, Some(DrawingMode::Eraser));
+        assert!(
+            serde_json::from_value::<PaintRequest>(serde_json::json!({ "drawing_mode": "crayon" }))
+                .is_err()
+        );
+
+        let custom = serde_json::json!({
+            "steps": [{ "type": "tap_button", "button": "R", "count": 2, "delay_ms": 100 }]
+        });
+        let error = config_for(
+            serde_json::json!({ "init_preset": "none", "init_sequence": custom }),
+            InitPreset::default(),
+        )
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let error = config_for(
+            serde_json::json!({
+                "init_sequence": { "steps": [{ "type": "hold_stick", "direction": "SIDEWAYS", "duration_ms": 100 }] }
+            }),
+            InitPreset::default(),
+        )
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        // 独自の手順は手順ごとのコマンドとして描画の前に送られる
+        let config = config_for(
+            serde_json::json!({ "init_sequence": custom, "press_ms": 1, "release_ms": 1, "wait_ms": 0 }),
+            InitPreset::default(),
+        )
+        .unwrap();
+        let mock = Arc::new(MockController::new().without_delays().with_command_log());
+        let controller: Arc<dyn ControllerEmulator> = mock.clone();
+        let control = PaintingControl::from_config(&config);
+        let drawing_path = DrawingPath::new(vec![Coordinates::new(1, 1)]);
+        perform_painting(controller, drawing_path, &config, control).unwrap();
+
+        let commands = mock.recorded_commands();
+        let init_commands: Vec<_> = commands
+            .iter()
+            .filter(|command| command.name.starts_with("Initialize Step"))
+            .collect();
+        assert_eq!(init_commands.len(), 1);
+        let r_presses = init_commands[0]
+            .sequence
+            .iter()
+            .filter(|action| action.action_type == ActionType::PressButton(Button::R))
+            .count();
+        assert_eq!(r_presses, 2);
+        assert!(
+            !commands
+                .iter()
+                .flat_map(|command| &command.sequence)
+                .any(|action| action.action_type == ActionType::PressButton(Button::L))
+        );
+    }
+
+    /// 予約IDの `scheduled` メッセージのうち、`event` が一致するものを待つ
+    async fn wait_for_schedule_event(
+        progress: &mut tokio::sync::broadcast::Receiver<String>,
+        schedule_id: &str,
+        event: &str,
+    ) -> serde_json::Value {
+        tokio::time::timeout(std::time::Duration::from_secs(5), async {
+            loop {
+                let Ok(message) = progress.recv().await else {
+                    continue;
+                };
+                let message: serde_json::Value = serde_json::from_str(&message).unwrap();
+                if message["type"] == "scheduled"
+                    && message["schedule_id"] == schedule_id
+                    && message["event"] == event
+                {
+                    return message;
+                }
+            }
+        })
+        .await
+        .unwrap()
+    }
+
+    fn scheduled_paint_request(start_at: chrono::DateTime<Utc>) -> PaintRequest {
+        serde_json::from_value(serde_json::json!({
+            "press_ms": 1,
+            "release_ms": 1,
+            "wait_ms": 0,
+            "init_preset": "none",
+            "start_at": start_at.to_rfc3339()
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_painting_counts_down_and_starts_at_the_given_time() {
+        use crate::interfaces::web::log_streamer::PROGRESS_CHANNEL;
+
+        let mut canvas = Canvas::new(4, 4);
+        canvas
+            .set_dot(Coordinates::new(1, 1), Dot::black())
+            .unwrap();
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("scheduled".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+        state.interlock.arm("test", None);
+        let mut progress = PROGRESS_CHANNEL.subscribe();
+
+        let past = Utc::now() - chrono::Duration::seconds(1);
+        let error = paint_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Json(scheduled_paint_request(past)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let start_at = Utc::now() + chrono::Duration::milliseconds(1500);
+        let Json(response) = paint_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Json(scheduled_paint_request(start_at)),
+        )
+        .await
+        .unwrap();
+        assert!(response.success, "{}", response.message);
+        assert_eq!(response.generation, None);
+        let scheduled = response.scheduled.unwrap();
+        assert_eq!(scheduled.artwork_id, id);
+        assert!(scheduled.seconds_remaining > 0.0);
+        assert!(state.active_painting.read().await.is_none());
+
+        let Json(status) =
+            get_painting_status(State(state.clone()), Query(PaintingStatusQuery::default()))
+                .await
+                .unwrap();
+        assert!(!status.active);
+        assert_eq!(status.scheduled.unwrap().id, scheduled.id);
+
+        // 予約は1件だけ
+        let error = paint_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Json(scheduled_paint_request(start_at)),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(error.status(), StatusCode::CONFLICT);
+
+        let countdown = wait_for_schedule_event(&mut progress, &scheduled.id, "countdown").await;
+        assert_eq!(countdown["artwork_id"], id);
+        let started = wait_for_schedule_event(&mut progress, &scheduled.id, "started").await;
+        assert!(started["generation"].is_u64());
+        assert!(Utc::now() >= start_at);
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(5))
+                .await
+        );
+        let Json(status) =
+            get_painting_status(State(state.clone()), Query(PaintingStatusQuery::default()))
+                .await
+                .unwrap();
+        assert!(status.scheduled.is_none());
+        assert_eq!(status.last_run.unwrap().artwork_id, id);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_painting_can_be_cancelled_and_aborts_when_disconnected() {
+        use crate::interfaces::web::log_streamer::PROGRESS_CHANNEL;
+
+        let mut canvas = Canvas::new(4, 4);
+        canvas
+            .set_dot(Coordinates::new(1, 1), Dot::black())
+            .unwrap();
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("scheduled".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let mock = Arc::new(MockController::new().without_delays());
+        let state = ArtworkState::new(mock.clone());
+        state.artworks.save(&artwork).await.unwrap();
+        let state = Arc::new(state);
+        state.interlock.arm("test", None);
+        let mut progress = PROGRESS_CHANNEL.subscribe();
+
+        let Json(response) = cancel_scheduled_painting(State(state.clone())).await;
+        assert!(!response.success);
+
+        let start_at = Utc::now() + chrono::Duration::hours(1);
+        let Json(response) = paint_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Json(scheduled_paint_request(start_at)),
+        )
+        .await
+        .unwrap();
+        let scheduled = response.scheduled.unwrap();
+        let Json(response) = cancel_scheduled_painting(State(state.clone())).await;
+        assert!(response.success, "{}", response.message);
+        wait_for_schedule_event(&mut progress, &scheduled.id, "cancelled").await;
+        assert!(state.scheduled_painting.read().await.is_none());
+
+        // 開始の直前に接続を確認し、切れていれば描画を始めない
+        mock.set_connected(false);
+        let start_at = Utc::now() + chrono::Duration::milliseconds(300);
+        let Json(response) = paint_artwork(
+            State(state.clone()),
+            Path(id.clone()),
+            Json(scheduled_paint_request(start_at)),
+        )
+        .await
+        .unwrap();
+        let scheduled = response.scheduled.unwrap();
+        let aborted = wait_for_schedule_event(&mut progress, &scheduled.id, "aborted").await;
+        assert!(
+            aborted["reason"]
+                .as_str()
+                .unwrap()
+                .contains("not connected"),
+            "{aborted}"
+        );
+        assert!(state.active_painting.read().await.is_none());
+        assert!(state.scheduled_painting.read().await.is_none());
+        assert!(state.runs.recent(1).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_painting_routes_control_an_active_run() {
+        let mut canvas = Canvas::new(8, 8);
+        for y in 0..8 {
+            for x in 0..8 {
+                canvas
+                    .set_dot(Coordinates::new(x, y), Dot::black())
+                    .unwrap();
+            }
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("routes".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+        let client = TestClient::new(state.clone());
+        let timing = serde_json::json!({ "press_ms": 20, "release_ms": 20, "wait_ms": 20 });
+
+        // 描画中でなければ何も変えない
+        let response = client.get("/api/painting/status").await;
+        assert_eq!(response.status, StatusCode::OK);
+        for (path, body) in [
+            ("/api/painting/repeats", serde_json::json!({ "repeats": 2 })),
+            ("/api/painting/timing", timing.clone()),
+        ] {
+            let response = client.post(path, body).await;
+            assert_eq!(response.status, StatusCode::OK);
+            assert_eq!(response.json()["success"], false, "{path}");
+        }
+        for path in ["/api/painting/stop", "/api/painting/pause"] {
+            let response = client.post_empty(path).await;
+            assert_eq!(response.status, StatusCode::OK);
+            assert_eq!(response.json()["success"], false, "{path}");
+        }
+        let confirm = serde_json::json!({ "confirmed": true });
+        let response = client
+            .post("/api/painting/confirm-preflight", confirm.clone())
+            .await;
+        assert_eq!(response.json()["success"], false);
+        let response = client
+            .post(
+                "/api/painting/repeats",
+                serde_json::json!({ "repeats": "two" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+
+        // アームされていなければ描画できない
+        let path = format!("/api/artworks/{id}/paint");
+        let response = client.post(&path, timing.clone()).await;
+        assert_eq!(response.status, StatusCode::LOCKED);
+        let response = client
+            .post("/api/artworks/missing/paint", timing.clone())
+            .await;
+        assert_eq!(response.status, StatusCode::LOCKED);
+
+        state.interlock.arm("test", None);
+        let response = client
+            .post("/api/artworks/missing/paint", timing.clone())
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        let response = client.post(&path, timing.clone()).await;
+        assert_eq!(response.status, StatusCode::OK);
+        let generation = response.json()["generation"].as_u64().unwrap();
+
+        let response = client.get("/api/painting/status").await;
+        assert_eq!(response.json()["active"], true, "{}", response.text());
+        let response = client
+            .post("/api/painting/repeats", serde_json::json!({ "repeats": 1 }))
+            .await;
+        assert_eq!(response.json()["success"], true);
+        let response = client.post("/api/painting/timing", timing).await;
+        assert_eq!(response.json()["success"], true);
+        // 確認を求めない描画では回答を受け付けない
+        let response = client
+            .post("/api/painting/confirm-preflight", confirm)
+            .await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        let response = client.get("/api/painting/status").await;
+        assert!(response.json()["preflight"].is_null());
+
+        let response = client
+            .post_empty(&format!(
+                "/api/painting/pause?generation={}",
+                generation + 1
+            ))
+            .await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        let response = client.post_empty("/api/painting/pause?paused=true").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["paused"], true);
+        let response = client
+            .post_empty(&format!("/api/painting/stop?generation={generation}"))
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["success"], true);
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(5))
+                .await
+        );
+
+        let response = client.get(&format!("/api/artworks/{id}/runs")).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json().as_array().unwrap().len(), 1);
+        let response = client.get("/api/artworks/missing/runs").await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        let response = client.get("/api/painting/runs?limit=10").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json().as_array().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_paint_diff_and_schedule_routes() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas
+            .set_dot(Coordinates::new(1, 1), Dot::black())
+            .unwrap();
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("routes".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+        state.interlock.arm("test", None);
+        let client = TestClient::new(state);
+
+        let response = client
+            .post(
+                &format!("/api/artworks/{id}/paint-diff"),
+                serde_json::json!({ "base_artwork_id": "missing" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+
+        let start_at = (Utc::now() + chrono::Duration::minutes(10)).to_rfc3339();
+        let response = client
+            .post(
+                &format!("/api/artworks/{id}/paint-diff"),
+                serde_json::json!({ "base_artwork_id": id, "start_at": start_at }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["scheduled"]["artwork_id"], id.as_str());
+
+        let response = client.delete("/api/painting/scheduled").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["success"], true);
+        let response = client.delete("/api/painting/scheduled").await;
+        assert_eq!(response.json()["success"], false);
+    }
+
+    #[tokio::test]
+    async fn test_traced_run_can_be_downloaded_as_csv_after_it_finishes() {
+        let mut canvas = Canvas::new(4, 2);
+        for x in 0..4 {
+            canvas
+                .set_dot(Coordinates::new(x, 1), Dot::black())
+                .unwrap();
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("traced".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+        state.interlock.arm("test", None);
+        let client = TestClient::new(state.clone());
+        let path = format!("/api/artworks/{id}/paint");
+        let paint = |trace: bool| {
+            serde_json::json!({
+                "press_ms": 5,
+                "release_ms": 5,
+                "wait_ms": 50,
+                "init_preset": "none",
+                "reset_progress": true,
+                "trace": trace,
+            })
+        };
+
+        let response = client.post(&path, paint(true)).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let trace_url = response.json()["trace_url"].as_str().unwrap().to_string();
+        let response = client.get(&trace_url).await;
+        assert_eq!(response.status, StatusCode::CONFLICT);
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+
+        let response = client.get(&trace_url).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        assert_eq!(
+            response.headers[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        let csv = response.text();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some(crate::application::painting_trace::TRACE_CSV_HEADER.trim_end())
+        );
+        let rows: Vec<Vec<&str>> = lines.map(|line| line.split(',').collect()).collect();
+        assert_eq!(rows.len(), 4, "{csv}");
+        for (index, row) in rows.iter().enumerate() {
+            assert_eq!(row[0], index.to_string());
+            assert_eq!(row[2], "1");
+            assert_eq!(&row[3..6], ["5", "5", "50"]);
+            // 実際の待機時間は予定より短くならない
+            assert!(row[9].parse::<u64>().unwrap() >= 50_000, "{csv}");
+            assert_eq!(&row[10..], ["1", "painted"]);
+        }
+
+        // 記録しなかった描画や知らない実行IDは404
+        let response = client.post(&path, paint(false)).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert!(response.json()["trace_url"].is_null());
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+        let run_id = state.runs.recent(1)[0].id.clone();
+        let response = client
+            .get(&format!("/api/painting/runs/{run_id}/trace.csv"))
+            .await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+        let response = client.get("/api/painting/runs/missing/trace.csv").await;
+        assert_eq!(response.status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_paint_stop_after_limits_run_and_reports_headroom() {
+        let mut canvas = Canvas::new(8, 8);
+        for y in 0..8 {
+            for x in 0..8 {
+                canvas
+                    .set_dot(Coordinates::new(x, y), Dot::black())
+                    .unwrap();
+            }
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("limited".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+        state.interlock.arm("test", None);
+        let client = TestClient::new(state.clone());
+        let path = format!("/api/artworks/{id}/paint");
+        let paint = |stop_after: serde_json::Value| {
+            serde_json::json!({
+                "press_ms": 20,
+                "release_ms": 20,
+                "wait_ms": 20,
+                "stop_after": stop_after,
+            })
+        };
+
+        for stop_after in [
+            serde_json::json!({}),
+            serde_json::json!({ "minutes": 0 }),
+            serde_json::json!({ "dots": 0, "minutes": 30 }),
+            serde_json::json!({ "completion_ratio": 1.5 }),
+        ] {
+            let response = client.post(&path, paint(stop_after.clone())).await;
+            assert_eq!(
+                response.status,
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "{stop_after}"
+            );
+        }
+
+        let response = client
+            .post(
+                &path,
+                paint(serde_json::json!({ "dots": 3, "minutes": 30 })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let status = client.get("/api/painting/status").await.json();
+        let stop_after = &status["stop_after"];
+        assert_eq!(stop_after["limits"]["dots"], 3);
+        assert!(stop_after["remaining_dots"].as_u64().unwrap() <= 3);
+        let remaining_seconds = stop_after["remaining_seconds"].as_u64().unwrap();
+        assert!((1790..=1800).contains(&remaining_seconds), "{status}");
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(10))
+                .await
+        );
+
+        let status = client.get("/api/painting/status").await.json();
+        assert_eq!(status["stop_after"], serde_json::Value::Null);
+        let last_run = &status["last_run"];
+        assert_eq!(
+            last_run["outcome"],
+            serde_json::json!({ "kind": "limit_reached", "limit": "dots" })
+        );
+        assert_eq!(last_run["dots_painted"], 3);
+        let artwork = state.find_artwork(&id).await.unwrap().unwrap();
+        assert_eq!(artwork.canvas.painted_dots().len(), 3);
+
+        // 描画済みの3ドットで既に達している完成度は指定できない
+        let response = client
+            .post(
+                &path,
+                paint(serde_json::json!({ "completion_ratio": 0.04 })),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(response.message().contains("already reached"));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_artwork_cannot_be_edited_or_deleted_while_it_is_painted() {
+        let mut canvas = Canvas::new(8, 8);
+        for y in 0..8 {
+            for x in 0..8 {
+                canvas
+                    .set_dot(Coordinates::new(x, y), Dot::black())
+                    .unwrap();
+            }
+        }
+        let artwork = Artwork::new(
+            ArtworkMetadata::new("locked".to_string()),
+            "api".to_string(),
+            canvas,
+        );
+        let id = artwork.id.as_str();
+        let state = artwork_state_with(artwork).await;
+        state.interlock.arm("test", None);
+        let client = TestClient::new(state.clone());
+        let timing = serde_json::json!({ "press_ms": 20, "release_ms": 20, "wait_ms": 20 });
+
+        let response = client
+            .post(&format!("/api/artworks/{id}/paint"), timing)
+            .await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        let response = client.post_empty("/api/painting/pause?paused=true").await;
+        assert_eq!(response.json()["paused"], true);
+        assert_eq!(
+            state.artwork_locks.operations(&id),
+            [MessageKey::OperationPainting]
+        );
+
+        // 描画中のアートワークを別々のタスクから同時に変更・削除しようとする
+        let diff = DotDiff::new(vec![DotRun::single(0, 0, DotOp::Clear)]);
+        let requests = vec![
+            Request::post(format!("/api/artworks/{id}/dots:bulk"))
+                .body(Body::from(diff.encode()))
+                .unwrap(),
+            Request::put(format!("/api/artworks/{id}/vector-paths"))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(r#"{"vector_paths": []}"#))
+                .unwrap(),
+            Request::post(format!("/api/artworks/{id}/compact"))
+                .body(Body::empty())
+                .unwrap(),
+            Request::post(format!("/api/artworks/{id}/undo"))
+                .body(Body::empty())
+                .unwrap(),
+            Request::delete(format!("/api/artworks/{id}"))
+                .body(Body::empty())
+                .unwrap(),
+        ];
+        let tasks: Vec<_> = requests
+            .into_iter()
+            .map(|request| {
+                let client = TestClient::new(state.clone());
+                tokio::spawn(async move { client.send(request).await })
+            })
+            .collect();
+        for task in tasks {
+            let response = task.await.unwrap();
+            assert_eq!(response.status, StatusCode::CONFLICT, "{}", response.text());
+            assert!(response.message().contains("は描画の実行中のため"));
+        }
+        assert!(state.find_artwork(&id).await.unwrap().is_some());
+
+        // 名前の変更とパスの計算は描画と同時に行える
+        let response = client
+            .patch(
+                &format!("/api/artworks/{id}/metadata"),
+                serde_json::json!({ "description": "painting" }),
+            )
+            .await;
+        assert_eq!(response.status, StatusCode::OK);
+        let response = client.get(&format!("/api/artworks/{id}/path")).await;
+        assert_eq!(response.status, StatusCode::OK);
+
+        let response = client.post_empty("/api/painting/stop").await;
+        assert_eq!(response.json()["success"], true);
+        assert!(
+            state
+                .wait_for_painting_to_finish(std::time::Duration::from_secs(5))
+                .await
+        );
+        assert!(state.artwork_locks.is_empty());
+        let response = client.delete(&format!("/api/artworks/{id}")).await;
+        assert_eq!(response.status, StatusCode::OK, "{}", response.text());
+        assert!(state.artwork_locks.is_empty());
+    }
+}
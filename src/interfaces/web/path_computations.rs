@@ -0,0 +1,154 @@
+//! 実行中の描画パスの計算の取り消し
+//!
+//! 描画パス・解析・戦略の比較の計算は、開始時にアートワークと計算の種類ごとに取り消し用のトークンを登録する。
+//! 同じアートワーク・同じ種類の計算を新しく始めると前の計算を取り消し、アートワークの削除と
+//! `DELETE /api/artworks/{id}/strategies/compute` ではそのアートワークの計算をすべて取り消す。
+//! クライアントが応答を待たずに切断した場合も、ハンドラーと一緒にガードが破棄されて計算を取り消す。
+
+use super::error_response::ErrorResponse;
+use crate::domain::artwork::entities::ArtworkId;
+use crate::domain::painting::{CancellationToken, DrawingStrategy, PathCancelled};
+use axum::http::StatusCode;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// 計算の種類（同じ種類の新しい計算が前の計算を置き換える）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PathComputationKind {
+    /// `GET /api/artworks/{id}/path`
+    Path(DrawingStrategy),
+    /// `GET /api/artworks/{id}/analysis`
+    Analysis(DrawingStrategy),
+    /// `GET /api/artworks/{id}/strategies`（全戦略）
+    Strategies,
+}
+
+impl From<PathCancelled> for ErrorResponse {
+    fn from(cancelled: PathCancelled) -> Self {
+        ErrorResponse::new(StatusCode::CONFLICT, cancelled.to_string())
+    }
+}
+
+type ComputationKey = (String, PathComputationKind);
+
+/// 実行中の計算の登録先（複製してもすべてのハンドルで同じ登録先を共有する）
+#[derive(Debug, Clone, Default)]
+pub struct PathComputations {
+    running: Arc<Mutex<HashMap<ComputationKey, (u64, CancellationToken)>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl PathComputations {
+    fn lock(&self) -> MutexGuard<'_, HashMap<ComputationKey, (u64, CancellationToken)>> {
+        self.running.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 大文字のUUIDなど、同じアートワークを指す別の表記を同じ項目にまとめる
+    fn key(id: &str) -> String {
+        ArtworkId::parse(id).map_or_else(|_| id.to_string(), |id| id.as_str())
+    }
+
+    /// 計算を登録する（同じアートワーク・同じ種類の実行中の計算は取り消す）
+    pub fn start(&self, id: &str, kind: PathComputationKind) -> PathComputationGuard {
+        let key = (Self::key(id), kind);
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let token = CancellationToken::default();
+        if let Some((_, superseded)) = self.lock().insert(key.clone(), (id, token.clone())) {
+            superseded.cancel();
+        }
+        PathComputationGuard {
+            computations: self.clone(),
+            key,
+            id,
+            token,
+        }
+    }
+
+    /// アートワークの実行中の計算をすべて取り消し、取り消した数を返す
+    pub fn cancel_artwork(&self, id: &str) -> usize {
+        let artwork = Self::key(id);
+        let mut running = self.lock();
+        let before = running.len();
+        running.retain(|(id, _), (_, token)| {
+            if *id == artwork {
+                token.cancel();
+            }
+            *id != artwork
+        });
+        before - running.len()
+    }
+
+    /// 実行中の計算の数
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+}
+
+/// 計算の登録（破棄すると計算を取り消し、登録も取り消す）
+#[derive(Debug)]
+pub struct PathComputationGuard {
+    computations: PathComputations,
+    key: ComputationKey,
+    id: u64,
+    token: CancellationToken,
+}
+
+impl PathComputationGuard {
+    /// 計算に渡すトークン
+    pub fn token(&self) -> &CancellationToken {
+        &self.token
+    }
+}
+
+impl Drop for PathComputationGuard {
+    fn drop(&mut self) {
+        // 終わった計算の取り消しは何もしないため、切断で途中のまま残った計算だけが止まる
+        self.token.cancel();
+        let mut running = self.computations.lock();
+        // 新しい計算に置き換えられていれば、その登録は残す
+        if running.get(&self.key).is_some_and(|(id, _)| *id == self.id) {
+            running.remove(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "0f8fad5b-d9cb-469f-a165-70867728950e";
+
+    #[test]
+    fn test_new_computation_supersedes_the_same_kind_only() {
+        let computations = PathComputations::default();
+        let first = computations.start(ID, PathComputationKind::Path(DrawingStrategy::ZigZag));
+        let other = computations.start(ID, PathComputationKind::Strategies);
+        let second = computations.start(
+            &ID.to_uppercase(),
+            PathComputationKind::Path(DrawingStrategy::ZigZag),
+        );
+        assert!(first.token().is_cancelled());
+        assert!(!other.token().is_cancelled());
+        assert!(!second.token().is_cancelled());
+
+        // 置き換えられた計算の終了で、新しい計算の登録は消えない
+        drop(first);
+        assert_eq!(computations.len(), 2);
+        assert_eq!(computations.cancel_artwork(ID), 2);
+        assert!(other.token().is_cancelled());
+        assert!(second.token().is_cancelled());
+        assert_eq!(computations.len(), 0);
+    }
+
+    #[test]
+    fn test_dropping_the_guard_cancels_the_computation() {
+        let computations = PathComputations::default();
+        let guard = computations.start(ID, PathComputationKind::Strategies);
+        let token = guard.token().clone();
+        drop(guard);
+        assert!(token.is_cancelled());
+        assert_eq!(computations.len(), 0);
+    }
+}
@@ -0,0 +1,370 @@
+//! APIとWebUIのルーティング
+
+use super::artwork_sets::{delete_artwork_set, get_artwork_set};
+use super::artworks::{
+    apply_dot_diff, cancel_artwork_computations, compact_artwork, create_artwork, delete_artwork,
+    duplicate_artwork, export_artwork, export_fightstick, generate_artwork, get_artwork,
+    get_artwork_analysis, get_artwork_diff, get_artwork_history, get_artwork_path,
+    get_artwork_strategies, import_script, list_artworks, list_trash, purge_trash,
+    redo_artwork_edit, restore_artwork, transform_artwork, undo_artwork_edit,
+    update_artwork_metadata, update_painting_preferences, update_vector_paths, upload_artwork,
+};
+use super::auth::AuthToken;
+use super::calibration::{
+    cleanup_calibration, start_calibration, start_gap_move_test, start_paint_move_test,
+};
+use super::controller::{
+    arm_controller, disarm_controller, get_controller_capabilities, get_controller_status,
+    send_controller_input,
+};
+use super::convert_preview::{MAX_PREVIEW_UPLOAD_BYTES, preview_conversion};
+use super::embedded_assets::WebAssetSource;
+use super::error_response::{ErrorResponse, localize_errors};
+use super::handlers::{
+    abort_fix_connection, gallery_page, gallery_websocket_handler, get_audit_log, get_gadget_state,
+    get_gallery_mode, get_gallery_state, get_hardware_status, get_metrics, get_prometheus_metrics,
+    get_system_info, get_version, login, reconnect_gadget, reset_data, run_benchmark,
+    set_gallery_mode, set_log_level, start_fix_connection, test_webhooks, websocket_handler,
+};
+use super::hid_debug::inject_hid_report;
+use super::openapi::swagger_ui;
+use super::painting::{
+    cancel_scheduled_painting, confirm_preflight, get_estimate_model, get_painting_status,
+    get_painting_trace, list_artwork_runs, list_painting_runs, paint_artwork, paint_artwork_diff,
+    paint_artwork_vector, pause_painting, reset_estimate_model, stop_painting,
+    update_painting_repeats, update_painting_timing,
+};
+use super::state::ArtworkState;
+use axum::{
+    Router,
+    body::Body,
+    extract::{DefaultBodyLimit, Request, State},
+    http::{HeaderMap, Method, StatusCode, Uri, header},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::{delete, get, patch, post, put},
+};
+use std::sync::Arc;
+use tower::ServiceBuilder;
+use tower_http::cors::CorsLayer;
+use tracing::warn;
+
+/// ログインAPIのパス（トークン無しで呼べる）
+const LOGIN_PATH: &str = "/api/auth/login";
+
+/// 認証が必要なリクエストか（`/api/` 配下の変更系メソッド）
+///
+/// 読み取り専用のGETと静的ファイル、WebSocketは認証無しで利用できる。
+/// ギャラリー（`/gallery`、`/api/gallery/state`、`/ws/gallery`）もこれに含まれる。
+fn requires_auth(method: &Method, path: &str) -> bool {
+    path.starts_with("/api/")
+        && path != LOGIN_PATH
+        && !matches!(*method, Method::GET | Method::HEAD | Method::OPTIONS)
+}
+
+/// `Authorization: Bearer` ヘッダーかセッションCookieでトークンを検証
+fn is_authorized(token: &AuthToken, headers: &HeaderMap) -> bool {
+    token.authenticate(headers).is_some()
+}
+
+/// 変更系APIにアクセストークンを要求するミドルウェア
+async fn require_auth(
+    State(state): State<Arc<ArtworkState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if let Some(token) = state.auth_token()
+        && requires_auth(request.method(), request.uri().path())
+        && !is_authorized(&token, request.headers())
+    {
+        warn!(
+            "Rejected unauthenticated request: {} {}",
+            request.method(),
+            request.uri().path()
+        );
+        let mut response = ErrorResponse::new(
+            StatusCode::UNAUTHORIZED,
+            "Access token required. Send `Authorization: Bearer <token>` or log in via /api/auth/login",
+        )
+        .into_response();
+        response.headers_mut().insert(
+            header::WWW_AUTHENTICATE,
+            header::HeaderValue::from_static("Bearer"),
+        );
+        return response;
+    }
+
+    next.run(request).await
+}
+
+/// 認証を通った変更系APIへのリクエストを作業として記録するミドルウェア
+async fn record_activity(
+    State(state): State<Arc<ArtworkState>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if requires_auth(request.method(), request.uri().path()) {
+        state.activity.touch();
+    }
+    next.run(request).await
+}
+
+/// 全エンドポイントを持つアプリケーションルーターを作成
+///
+/// `ArtworkState::auth_token` が設定されていれば変更系APIに認証を要求する。
+/// サーバーの起動とハンドラーのテストは、どちらもこのルーターを使う。
+pub fn build_router(app_state: Arc<ArtworkState>) -> Router {
+    let assets = app_state.assets.clone();
+    Router::new()
+        // API endpoints
+        .route("/api/health", get(|| async { "OK" }))
+        .route(LOGIN_PATH, post(login))
+        .route("/api/system/info", get(get_system_info))
+        .route("/api/version", get(get_version))
+        .route("/api/system/log-level", put(set_log_level))
+        .route("/api/hardware/status", get(get_hardware_status))
+        .route(
+            "/api/system/fix-connection/start",
+            post(start_fix_connection),
+        )
+        .route(
+            "/api/system/fix-connection/abort",
+            post(abort_fix_connection),
+        )
+        .route("/api/system/reconnect-gadget", post(reconnect_gadget))
+        .route("/api/system/audit", get(get_audit_log))
+        .route("/api/system/gadget", get(get_gadget_state))
+        .route("/api/system/reset-data", post(reset_data))
+        .route("/api/system/benchmark", post(run_benchmark))
+        .route("/api/metrics", get(get_metrics))
+        .route("/metrics", get(get_prometheus_metrics))
+        .route("/api/settings/webhooks/test", post(test_webhooks))
+        // Artwork endpoints
+        .route("/api/artworks", get(list_artworks).post(create_artwork))
+        .route("/api/artworks/upload", post(upload_artwork))
+        .route("/api/artworks/generate", post(generate_artwork))
+        .route("/api/artworks/import-script", post(import_script))
+        .route(
+            "/api/convert/preview",
+            post(preview_conversion).layer(DefaultBodyLimit::max(MAX_PREVIEW_UPLOAD_BYTES)),
+        )
+        .route(
+            "/api/artworks/{id}",
+            get(get_artwork).delete(delete_artwork),
+        )
+        .route("/api/artworks/trash", get(list_trash))
+        .route("/api/artworks/trash/purge", post(purge_trash))
+        .route("/api/artworks/{id}/restore", post(restore_artwork))
+        .route(
+            "/api/artwork-sets/{set_id}",
+            get(get_artwork_set).delete(delete_artwork_set),
+        )
+        .route("/api/artworks/{id}/duplicate", post(duplicate_artwork))
+        .route("/api/artworks/{id}/transform", post(transform_artwork))
+        .route("/api/artworks/{a}/diff/{b}", get(get_artwork_diff))
+        .route(
+            "/api/artworks/{id}/metadata",
+            patch(update_artwork_metadata),
+        )
+        .route(
+            "/api/artworks/{id}/preferences",
+            patch(update_painting_preferences),
+        )
+        .route("/api/artworks/{id}/vector-paths", put(update_vector_paths))
+        .route("/api/artworks/{id}/compact", post(compact_artwork))
+        .route("/api/artworks/{id}/dots:bulk", post(apply_dot_diff))
+        .route("/api/artworks/{id}/undo", post(undo_artwork_edit))
+        .route("/api/artworks/{id}/redo", post(redo_artwork_edit))
+        .route("/api/artworks/{id}/path", get(get_artwork_path))
+        .route("/api/artworks/{id}/strategies", get(get_artwork_strategies))
+        .route(
+            "/api/artworks/{id}/strategies/compute",
+            delete(cancel_artwork_computations),
+        )
+        .route("/api/artworks/{id}/analysis", get(get_artwork_analysis))
+        .route("/api/artworks/{id}/export", get(export_artwork))
+        .route(
+            "/api/artworks/{id}/export/fightstick",
+            get(export_fightstick),
+        )
+        .route("/api/artworks/{id}/history", get(get_artwork_history))
+        .route("/api/artworks/{id}/runs", get(list_artwork_runs))
+        .route("/api/painting/runs", get(list_painting_runs))
+        .route(
+            "/api/painting/runs/{run_id}/trace.csv",
+            get(get_painting_trace),
+        )
+        .route("/api/painting/status", get(get_painting_status))
+        .route("/api/painting/repeats", post(update_painting_repeats))
+        .route("/api/painting/timing", post(update_painting_timing))
+        .route("/api/artworks/{id}/paint", post(paint_artwork))
+        .route("/api/artworks/{id}/paint-diff", post(paint_artwork_diff))
+        .route(
+            "/api/artworks/{id}/paint-vector",
+            post(paint_artwork_vector),
+        )
+        .route("/api/painting/stop", post(stop_painting))
+        .route("/api/painting/pause", post(pause_painting))
+        .route("/api/painting/confirm-preflight", post(confirm_preflight))
+        .route("/api/painting/scheduled", delete(cancel_scheduled_painting))
+        .route(
+            "/api/painting/estimate-model",
+            get(get_estimate_model).delete(reset_estimate_model),
+        )
+        .route("/api/calibration/start", post(start_calibration))
+        .route("/api/calibration/cleanup", post(cleanup_calibration))
+        .route(
+            "/api/calibration/test/paint-move",
+            post(start_paint_move_test),
+        )
+        .route("/api/calibration/test/gap-move", post(start_gap_move_test))
+        .route("/api/controller/input", post(send_controller_input))
+        .route("/api/controller/status", get(get_controller_status))
+        .route(
+            "/api/controller/capabilities",
+            get(get_controller_capabilities),
+        )
+        .route("/api/controller/arm", post(arm_controller))
+        .route("/api/controller/disarm", post(disarm_controller))
+        .route("/api/debug/hid-report", post(inject_hid_report))
+        // Read-only public gallery (no token required, 404 while disabled)
+        .route(
+            "/api/system/gallery",
+            get(get_gallery_mode).put(set_gallery_mode),
+        )
+        .route("/gallery", get(gallery_page))
+        .route("/api/gallery/state", get(get_gallery_state))
+        .route("/ws/gallery", get(gallery_websocket_handler))
+        // WebSocket endpoint
+        .route("/ws/logs", get(websocket_handler))
+        // OpenAPI spec and Swagger UI
+        .merge(swagger_ui())
+        // Count authorized mutating requests as activity for `run --drain`
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            record_activity,
+        ))
+        // Require the access token for mutating endpoints
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            require_auth,
+        ))
+        // Render localized error messages in the language of the request
+        .layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            localize_errors,
+        ))
+        // Add state
+        .with_state(app_state)
+        // Add CORS support and body size limit
+        .layer(
+            ServiceBuilder::new()
+                .layer(DefaultBodyLimit::max(10 * 1024 * 1024)) // 10MB limit
+                .layer(CorsLayer::permissive()),
+        )
+        // Serve embedded static files (or the --assets-dir overrides) as fallback
+        .fallback(move |uri: Uri| static_handler(assets.clone(), uri))
+}
+
+/// 埋め込まれた静的ファイルを提供するハンドラ
+async fn static_handler(assets: WebAssetSource, uri: Uri) -> Response {
+    let path = uri.path().trim_start_matches('/');
+
+    // ルートパスの場合はindex.htmlを提供
+    let path = if path.is_empty() || path == "/" {
+        "index.html"
+    } else {
+        path
+    }
+    .to_string();
+
+    // 外部ディレクトリを読む場合があるのでブロッキングスレッドで取得する
+    let loaded = tokio::task::spawn_blocking(move || {
+        // ファイルが見つからない場合はindex.htmlを返す（SPAのため）
+        match assets.load(&path) {
+            Ok(Some(asset)) => Ok(Some((path, asset))),
+            Ok(None) => assets
+                .load("index.html")
+                .map(|asset| asset.map(|asset| ("index.html".to_string(), asset))),
+            Err(e) => Err(e),
+        }
+    })
+    .await;
+
+    match loaded {
+        Ok(Ok(Some((path, asset)))) => {
+            let mime = mime_guess::from_path(&path).first_or_octet_stream();
+            let mut response = Response::builder()
+                .status(StatusCode::OK)
+                .header(header::CONTENT_TYPE, mime.as_ref());
+            if asset.overridden {
+                // 外部ディレクトリのファイルは編集をすぐ反映できるよう毎回取り直させる
+                response = response.header(header::CACHE_CONTROL, "no-cache");
+            }
+            response.body(Body::from(asset.data.into_owned())).unwrap()
+        }
+        Ok(Ok(None)) => Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::from("404 Not Found"))
+            .unwrap(),
+        Ok(Err(e)) => {
+            warn!("Rejected asset request: {}", e);
+            ErrorResponse::new(StatusCode::BAD_REQUEST, e.to_string()).into_response()
+        }
+        Err(e) => {
+            ErrorResponse::new(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::test_support::TestClient;
+    use super::*;
+    use crate::infrastructure::hardware::mock_controller::MockController;
+
+    fn client(auth_token: Option<AuthToken>) -> TestClient {
+        let mut state = ArtworkState::new(Arc::new(MockController::new().without_delays()));
+        if let Some(token) = auth_token {
+            state = state.with_auth_token(token);
+        }
+        TestClient::new(Arc::new(state))
+    }
+
+    #[tokio::test]
+    async fn test_health_and_static_fallback() {
+        let client = client(None);
+
+        let response = client.get("/api/health").await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.text(), "OK");
+
+        // SPAのため、存在しないパスにはindex.htmlを返す
+        let index = client.get("/").await;
+        let fallback = client.get("/artworks/unknown").await;
+        assert_eq!(fallback.status, index.status);
+        assert_eq!(fallback.body, index.body);
+    }
+
+    #[tokio::test]
+    async fn test_mutating_routes_require_token() {
+        let client = client(Some(AuthToken::new("secret")));
+
+        let response = client.post_empty("/api/controller/arm").await;
+        assert_eq!(response.status, StatusCode::UNAUTHORIZED);
+        assert_eq!(response.headers[header::WWW_AUTHENTICATE], "Bearer");
+        assert!(response.message().contains("/api/auth/login"));
+
+        // 読み取りは認証無しで利用できる
+        let response = client.get("/api/controller/status").await;
+        assert_eq!(response.status, StatusCode::OK);
+
+        let request = Request::post("/api/controller/arm")
+            .header(header::AUTHORIZATION, "Bearer secret")
+            .body(Body::empty())
+            .unwrap();
+        let response = client.send(request).await;
+        assert_eq!(response.status, StatusCode::OK);
+        assert_eq!(response.json()["armed"], true);
+    }
+}
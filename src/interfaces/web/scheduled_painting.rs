@@ -0,0 +1,292 @@
+//! 予約した時刻に描画を開始する
+//!
+//! 予約は `ArtworkState` に1件だけ保持し、開始までは `scheduled` メッセージでカウントダウンを
+//! 進捗チャンネルへ送る。待機は一度の長いスリープにせず、毎回壁時計と比較して眠り直すため、
+//! NTPなどで時刻が変わっても予約した時刻に開始する。
+
+use super::connection_monitor::probe_connection;
+use super::dto::ScheduledPaintingStatus;
+use super::error_response::ErrorResponse;
+use super::log_streamer::PROGRESS_CHANNEL;
+use super::painting::{PaintRequest, start_painting};
+use super::state::ArtworkState;
+use axum::http::StatusCode;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::task::AbortHandle;
+use tracing::{info, warn};
+
+/// 予約できる開始時刻の上限（現在時刻からの長さ）
+pub const MAX_SCHEDULE_AHEAD: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// カウントダウンを送り、壁時計と比較し直す間隔
+const COUNTDOWN_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 開始を待っている描画の予約
+pub struct ScheduledPainting {
+    pub id: String,
+    pub artwork_id: String,
+    /// 差分描画の元のアートワークのID（通常の描画では `None`）
+    pub base_artwork_id: Option<String>,
+    pub start_at: DateTime<Utc>,
+    /// 開始時に使う描画リクエスト
+    pub request: PaintRequest,
+    abort: Option<AbortHandle>,
+}
+
+impl ScheduledPainting {
+    pub fn status(&self) -> ScheduledPaintingStatus {
+        ScheduledPaintingStatus {
+            id: self.id.clone(),
+            artwork_id: self.artwork_id.clone(),
+            base_artwork_id: self.base_artwork_id.clone(),
+            start_at: self.start_at.to_rfc3339(),
+            seconds_remaining: seconds_until(self.start_at),
+        }
+    }
+
+    /// 開始を待っているタスクを止める
+    pub fn cancel(&self) {
+        if let Some(abort) = &self.abort {
+            abort.abort();
+        }
+    }
+}
+
+/// `start_at` を解析し、現在より後かつ24時間以内であることを確認する
+pub fn parse_start_at(start_at: &str) -> Result<DateTime<Utc>, ErrorResponse> {
+    let start_at = DateTime::parse_from_rfc3339(start_at)
+        .map_err(|e| {
+            ErrorResponse::new(
+                StatusCode::UNPROCESSABLE_ENTITY,
+                format!("start_at must be an RFC 3339 timestamp: {e}"),
+            )
+        })?
+        .with_timezone(&Utc);
+    let now = Utc::now();
+    if start_at <= now {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!("start_at ({}) must be in the future", start_at.to_rfc3339()),
+        ));
+    }
+    if (start_at - now).to_std().unwrap_or_default() > MAX_SCHEDULE_AHEAD {
+        return Err(ErrorResponse::new(
+            StatusCode::UNPROCESSABLE_ENTITY,
+            format!(
+                "start_at ({}) must be within {} hours",
+                start_at.to_rfc3339(),
+                MAX_SCHEDULE_AHEAD.as_secs() / 3600
+            ),
+        ));
+    }
+    Ok(start_at)
+}
+
+/// 描画を予約し、開始時刻まで待つタスクを起動する（予約済みの場合は409）
+pub async fn schedule_painting(
+    state: Arc<ArtworkState>,
+    artwork_id: String,
+    base_artwork_id: Option<String>,
+    start_at: DateTime<Utc>,
+    request: PaintRequest,
+) -> Result<ScheduledPaintingStatus, ErrorResponse> {
+    // 開始を待つタスクはこのロックを取ってから予約を取り出すため、登録より先には動かない
+    let mut scheduled = state.scheduled_painting.write().await;
+    if let Some(existing) = scheduled.as_ref() {
+        return Err(ErrorResponse::new(
+            StatusCode::CONFLICT,
+            format!(
+                "Painting of artwork {} is already scheduled for {}; cancel it with DELETE /api/painting/scheduled first",
+                existing.artwork_id,
+                existing.start_at.to_rfc3339()
+            ),
+        ));
+    }
+    let mut schedule = ScheduledPainting {
+        id: uuid::Uuid::new_v4().to_string(),
+        artwork_id,
+        base_artwork_id,
+        start_at,
+        request: PaintRequest {
+            start_at: None,
+            ..request
+        },
+        abort: None,
+    };
+    let task = tokio::spawn(wait_and_start(state.clone(), schedule.id.clone(), start_at));
+    schedule.abort = Some(task.abort_handle());
+    info!(
+        "Scheduled painting {} of artwork {} for {}",
+        schedule.id,
+        schedule.artwork_id,
+        start_at.to_rfc3339()
+    );
+    let status = schedule.status();
+    *scheduled = Some(schedule);
+    Ok(status)
+}
+
+/// 予約を取り消す（予約が無ければ `None`）
+pub async fn cancel_schedule(state: &ArtworkState) -> Option<ScheduledPaintingStatus> {
+    let schedule = state.scheduled_painting.write().await.take()?;
+    schedule.cancel();
+    info!(
+        "Cancelled scheduled painting {} of artwork {}",
+        schedule.id, schedule.artwork_id
+    );
+    let status = schedule.status();
+    let _ = PROGRESS_CHANNEL.send(scheduled_message(&status, "cancelled", json!({})));
+    Some(status)
+}
+
+/// 開始時刻まで待ってから描画を開始する
+async fn wait_and_start(state: Arc<ArtworkState>, schedule_id: String, start_at: DateTime<Utc>) {
+    loop {
+        let remaining = (start_at - Utc::now()).to_std().unwrap_or_default();
+        if remaining.is_zero() {
+            break;
+        }
+        if let Some(schedule) = state.scheduled_painting.read().await.as_ref() {
+            let status = schedule.status();
+            let _ = PROGRESS_CHANNEL.send(scheduled_message(&status, "countdown", json!({})));
+        }
+        tokio::time::sleep(remaining.min(COUNTDOWN_INTERVAL)).await;
+    }
+
+    let schedule = {
+        let mut scheduled = state.scheduled_painting.write().await;
+        if scheduled.as_ref().map(|schedule| schedule.id.as_str()) != Some(schedule_id.as_str()) {
+            return;
+        }
+        scheduled.take().expect("schedule checked above")
+    };
+    let status = schedule.status();
+    match start_scheduled(&state, &schedule).await {
+        Ok(generation) => {
+            info!(
+                "Started scheduled painting {} of artwork {} (generation {})",
+                schedule.id, schedule.artwork_id, generation
+            );
+            let _ = PROGRESS_CHANNEL.send(scheduled_message(
+                &status,
+                "started",
+                json!({ "generation": generation }),
+            ));
+        }
+        Err(reason) => {
+            warn!(
+                "Aborted scheduled painting {} of artwork {}: {}",
+                schedule.id, schedule.artwork_id, reason
+            );
+            let _ = PROGRESS_CHANNEL.send(scheduled_message(
+                &status,
+                "aborted",
+                json!({ "reason": reason }),
+            ));
+        }
+    }
+}
+
+/// 接続を確認し直してから予約した描画を開始する（開始できなければ理由を返す）
+async fn start_scheduled(
+    state: &Arc<ArtworkState>,
+    schedule: &ScheduledPainting,
+) -> Result<u64, String> {
+    state.ensure_controller_allowed().map_err(|e| e.message)?;
+    state
+        .ensure_no_active_painting()
+        .await
+        .map_err(|e| e.message)?;
+    {
+        let _input_guard = state.controller_input.lock().await;
+        if !probe_connection(state.controller.clone()).await {
+            return Err("Controller is not connected to the Switch".to_string());
+        }
+    }
+
+    let artwork = state
+        .find_artwork(&schedule.artwork_id)
+        .await
+        .map_err(|e| e.message)?
+        .ok_or_else(|| format!("Artwork {} was deleted", schedule.artwork_id))?;
+    let base = match &schedule.base_artwork_id {
+        Some(base_id) => Some(
+            state
+                .find_artwork(base_id)
+                .await
+                .map_err(|e| e.message)?
+                .ok_or_else(|| format!("Base artwork {base_id} was deleted"))?
+                .canvas,
+        ),
+        None => None,
+    };
+    let response = start_painting(
+        state.clone(),
+        &schedule.artwork_id,
+        artwork,
+        base.as_ref(),
+        &schedule.request,
+    )
+    .await
+    .map_err(|e| e.message)?;
+    match response.generation {
+        Some(generation) if response.success => Ok(generation),
+        _ => Err(response.0.message),
+    }
+}
+
+/// 開始時刻までの残り秒数（過ぎていれば0）
+fn seconds_until(start_at: DateTime<Utc>) -> f64 {
+    (start_at - Utc::now())
+        .to_std()
+        .unwrap_or_default()
+        .as_secs_f64()
+}
+
+/// `scheduled` メッセージ（`event` は countdown / started / aborted / cancelled）
+fn scheduled_message(
+    status: &ScheduledPaintingStatus,
+    event: &str,
+    extra: serde_json::Value,
+) -> String {
+    let mut message = json!({
+        "type": "scheduled",
+        "event": event,
+        "schedule_id": status.id,
+        "artwork_id": status.artwork_id,
+        "start_at": status.start_at,
+        "seconds_remaining": status.seconds_remaining,
+        "timestamp": Utc::now().to_rfc3339(),
+    });
+    if let (Some(message), serde_json::Value::Object(extra)) = (message.as_object_mut(), extra) {
+        message.extend(extra);
+    }
+    message.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_start_at_requires_a_future_time_within_a_day() {
+        let soon = Utc::now() + chrono::Duration::minutes(5);
+        assert_eq!(parse_start_at(&soon.to_rfc3339()).unwrap(), soon);
+
+        for invalid in [
+            "tomorrow".to_string(),
+            (Utc::now() - chrono::Duration::minutes(1)).to_rfc3339(),
+            (Utc::now() + chrono::Duration::hours(25)).to_rfc3339(),
+        ] {
+            let error = parse_start_at(&invalid).unwrap_err();
+            assert_eq!(
+                error.status(),
+                StatusCode::UNPROCESSABLE_ENTITY,
+                "{invalid}"
+            );
+        }
+    }
+}
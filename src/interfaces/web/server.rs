@@ -1,139 +1,1729 @@
-use super::{
-    ArtworkState, create_artwork, delete_artwork, embedded_assets::WebAssets, get_artwork,
-    get_artwork_path, get_artwork_strategies, get_hardware_status, get_system_info, list_artworks,
-    paint_artwork, pause_painting, start_calibration, start_gap_move_test, start_paint_move_test,
-    stop_painting, update_painting_repeats, update_painting_timing, upload_artwork,
-    websocket_handler,
+use super::router::build_router;
+use super::state::{ArtworkState, ControllerMode};
+use crate::application::controller_io::{initialize_with_retry, run_controller_io};
+use crate::application::estimate_model::EstimateModelStore;
+use crate::application::metrics::Metrics;
+use crate::application::use_cases::{BenchmarkUseCase, ResetDataUseCase};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, UdpSocket};
+use std::path::PathBuf;
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{info, warn};
+
+pub use super::artworks::{CreateArtworkRequest, GenerateArtworkRequest, TestPattern};
+use super::artworks::{DEFAULT_TRASH_RETENTION, purge_expired_trash};
+pub use super::auth::{AuthError, AuthToken};
+pub use super::connection_monitor::ConnectionMonitorSettings;
+use super::connection_monitor::spawn_connection_monitor;
+use super::drain::wait_until_drained;
+use super::tls::SelfSignedTls;
+pub use super::tls::TlsSettings;
+use super::webhooks::{WebhookDispatcher, spawn_webhook_dispatcher};
+pub use super::webhooks::{WebhookSettings, parse_webhook_url};
+use crate::debug::LogLevelControl;
+use crate::domain::artwork::memory_budget::{
+    ArtworkMemoryBudget, DEFAULT_ARTWORK_MEMORY_BUDGET_BYTES,
 };
-use axum::{
-    Router,
-    body::Body,
-    extract::DefaultBodyLimit,
-    http::{StatusCode, Uri, header},
-    response::{IntoResponse, Response},
-    routing::{get, post},
+use crate::domain::artwork::repositories::RepositoryError;
+use crate::domain::hardware::UsbGadgetManager;
+pub use crate::domain::painting::{
+    InitPreset, PauseMode, PauseSettings, SleepGuardSettings, TwoOptSettings,
 };
-use std::net::SocketAddr;
-use std::sync::Arc;
-use tokio::net::TcpListener;
-use tower::ServiceBuilder;
-use tower_http::cors::CorsLayer;
-use tracing::info;
+use crate::domain::setup::entities::AuditInitiator;
+use crate::domain::setup::repositories::{ConnectionRepairer, GadgetAuditLog};
+use crate::domain::shared::i18n::Locale;
+use crate::infrastructure::data_dir;
+pub use crate::infrastructure::mdns::DEFAULT_MDNS_HOSTNAME;
+use crate::infrastructure::persistence::sqlite_artwork_repository::SqliteArtworkRepository;
+pub use crate::infrastructure::persistence::sqlite_database::DatabaseError;
+use crate::infrastructure::persistence::sqlite_database::SqliteDatabase;
+use crate::infrastructure::persistence::sqlite_painting_run_repository::SqlitePaintingRunRepository;
+use crate::infrastructure::safe_mode::SafeMode;
+use crate::infrastructure::setup::JsonlGadgetAuditLog;
+
+/// 起動時にコントローラーの初期化を再試行する時間の既定値
+pub const DEFAULT_CONTROLLER_INIT_WINDOW: std::time::Duration = std::time::Duration::from_secs(30);
+
+/// Webサーバーの起動設定
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: Option<TlsSettings>,
+    /// 証明書などのアプリケーションデータを保存するディレクトリ
+    pub data_dir: PathBuf,
+    /// 実機を使わずMockControllerで動作する
+    pub simulate: bool,
+    /// シミュレーション中は描画・キャリブレーションのAPIを拒否する
+    pub strict_simulation: bool,
+    /// ガジェット・ブート設定・systemd・HIDデバイスへの書き込みを止め、実行する代わりにログに出す
+    pub safe_mode: bool,
+    /// 任意のHIDレポートを送る `POST /api/debug/hid-report` などのデバッグ用APIを有効にする
+    pub debug: bool,
+    /// 変更系APIにアクセストークンを要求する（信頼できるネットワークでは無効にできる）
+    pub auth: bool,
+    /// 描画リクエストで省略された場合の一時停止の動作
+    pub pause: PauseSettings,
+    /// 見積もり時間と本体の自動スリープの閾値の比較
+    pub sleep_guard: SleepGuardSettings,
+    /// アートワークと描画履歴の保存先
+    pub storage: StorageBackend,
+    /// 埋め込みアセットより優先して配信するWebUIのディレクトリ（フロントエンド開発用）
+    pub assets_dir: Option<PathBuf>,
+    /// 描画パスの2-opt最適化の打ち切り条件
+    pub two_opt: TwoOptSettings,
+    /// 描画リクエストで省略された場合の初期化手順
+    pub init_preset: InitPreset,
+    /// 描画リクエストで省略された場合も描画前の確認を行う
+    pub preflight: bool,
+    /// `Accept-Language` で言語が選ばれなかった場合の文言の言語
+    pub locale: Locale,
+    /// `0.0.0.0` の代わりに `::` でIPv4とIPv6の両方を待ち受ける（`--host` を省略した場合）
+    pub dual_stack: bool,
+    /// mDNSで告知するホスト名（`.local` を除く、`None` なら告知しない）
+    pub mdns_hostname: Option<String>,
+    /// 実行中にログフィルターを変更するためのハンドル（`None` ならAPIから変更できない）
+    pub log_level: Option<LogLevelControl>,
+    /// 認証無しで進捗を公開するギャラリーを有効にして起動する（実行中にAPIから切り替えられる）
+    pub gallery: bool,
+    /// 保存中のアートワーク全体のメモリ使用量の上限（バイト）
+    pub artwork_memory_budget_bytes: usize,
+    /// ゴミ箱のアートワークを完全に削除するまでの期間
+    pub trash_retention: std::time::Duration,
+    /// Switchとの接続状態を一定間隔で確認し、WebSocketで通知する
+    pub connection_monitor: ConnectionMonitorSettings,
+    /// SQLiteに保存するときにドットごとの作成・描画日時を捨てない
+    pub keep_dot_timestamps: bool,
+    /// 描画の完了などを通知するWebhook（URLが空なら通知しない）
+    pub webhooks: WebhookSettings,
+    /// 描画の実行中・予約中でない状態がこの時間続いたらサーバーを止めて終了する（`None` なら終了しない）
+    pub drain_grace: Option<std::time::Duration>,
+    /// 起動時にコントローラーの初期化を再試行する時間（過ぎたらシミュレーションに切り替える）
+    pub controller_init_window: std::time::Duration,
+}
+
+/// アートワークと描画履歴の保存先
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StorageBackend {
+    /// メモリ上に保持する（再起動で消える。テストやシミュレーション向け）
+    Memory,
+    /// データディレクトリのSQLiteデータベースに保存する
+    #[default]
+    Sqlite,
+}
+
+impl ServerConfig {
+    pub fn new(host: impl Into<String>, port: u16) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            tls: None,
+            data_dir: PathBuf::from(DEFAULT_DATA_DIR),
+            simulate: false,
+            strict_simulation: false,
+            safe_mode: false,
+            debug: false,
+            auth: true,
+            pause: PauseSettings::default(),
+            sleep_guard: SleepGuardSettings::default(),
+            storage: StorageBackend::default(),
+            assets_dir: None,
+            two_opt: TwoOptSettings::default(),
+            init_preset: InitPreset::default(),
+            preflight: false,
+            locale: Locale::default(),
+            dual_stack: false,
+            mdns_hostname: Some(DEFAULT_MDNS_HOSTNAME.to_string()),
+            log_level: None,
+            gallery: true,
+            artwork_memory_budget_bytes: DEFAULT_ARTWORK_MEMORY_BUDGET_BYTES,
+            trash_retention: DEFAULT_TRASH_RETENTION,
+            connection_monitor: ConnectionMonitorSettings::default(),
+            keep_dot_timestamps: false,
+            webhooks: WebhookSettings::default(),
+            drain_grace: None,
+            controller_init_window: DEFAULT_CONTROLLER_INIT_WINDOW,
+        }
+    }
+
+    pub fn with_simulation(mut self, strict: bool) -> Self {
+        self.simulate = true;
+        self.strict_simulation = strict;
+        self
+    }
+
+    pub fn with_safe_mode(mut self) -> Self {
+        self.safe_mode = true;
+        self
+    }
+
+    pub fn with_debug_endpoints(mut self) -> Self {
+        self.debug = true;
+        self
+    }
+
+    pub fn with_tls(mut self, tls: TlsSettings) -> Self {
+        self.tls = Some(tls);
+        self
+    }
+
+    pub fn with_data_dir(mut self, data_dir: impl Into<PathBuf>) -> Self {
+        self.data_dir = data_dir.into();
+        self
+    }
+
+    pub fn without_auth(mut self) -> Self {
+        self.auth = false;
+        self
+    }
+
+    pub fn with_pause_settings(mut self, pause: PauseSettings) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    pub fn with_sleep_guard(mut self, sleep_guard: SleepGuardSettings) -> Self {
+        self.sleep_guard = sleep_guard;
+        self
+    }
+
+    pub fn with_storage(mut self, storage: StorageBackend) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    pub fn with_two_opt_settings(mut self, two_opt: TwoOptSettings) -> Self {
+        self.two_opt = two_opt;
+        self
+    }
+
+    pub fn with_artwork_memory_budget(mut self, budget_bytes: usize) -> Self {
+        self.artwork_memory_budget_bytes = budget_bytes;
+        self
+    }
+
+    pub fn with_trash_retention(mut self, retention: std::time::Duration) -> Self {
+        self.trash_retention = retention;
+        self
+    }
+
+    pub fn with_connection_monitor(
+        mut self,
+        connection_monitor: ConnectionMonitorSettings,
+    ) -> Self {
+        self.connection_monitor = connection_monitor;
+        self
+    }
+
+    pub fn with_init_preset(mut self, init_preset: InitPreset) -> Self {
+        self.init_preset = init_preset;
+        self
+    }
+
+    pub fn with_preflight(mut self) -> Self {
+        self.preflight = true;
+        self
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    pub fn with_webhooks(mut self, webhooks: WebhookSettings) -> Self {
+        self.webhooks = webhooks;
+        self
+    }
+
+    pub fn with_dual_stack(mut self) -> Self {
+        self.dual_stack = true;
+        self
+    }
+
+    pub fn with_mdns_hostname(mut self, hostname: impl Into<String>) -> Self {
+        self.mdns_hostname = Some(hostname.into());
+        self
+    }
+
+    pub fn without_mdns(mut self) -> Self {
+        self.mdns_hostname = None;
+        self
+    }
+
+    pub fn with_dot_timestamps(mut self) -> Self {
+        self.keep_dot_timestamps = true;
+        self
+    }
+
+    pub fn without_gallery(mut self) -> Self {
+        self.gallery = false;
+        self
+    }
+
+    pub fn with_log_level_control(mut self, control: LogLevelControl) -> Self {
+        self.log_level = Some(control);
+        self
+    }
+
+    pub fn with_drain(mut self, grace: std::time::Duration) -> Self {
+        self.drain_grace = Some(grace);
+        self
+    }
+
+    pub fn with_controller_init_window(mut self, window: std::time::Duration) -> Self {
+        self.controller_init_window = window;
+        self
+    }
+
+    pub fn with_assets_dir(mut self, assets_dir: impl Into<PathBuf>) -> Self {
+        self.assets_dir = Some(assets_dir.into());
+        self
+    }
+}
+
+/// アプリケーションデータの既定の保存先
+pub const DEFAULT_DATA_DIR: &str = "/var/lib/splatoon3-ghost-drawer";
+/// 待ち受けるアドレスの既定値（`with_dual_stack` ではIPv6も待ち受ける）
+pub const DEFAULT_HOST: &str = "0.0.0.0";
+
+#[derive(Debug, Error)]
+pub enum ServerStartupError {
+    #[error("Invalid host '{host}': not an IP address and could not be resolved ({reason})")]
+    UnresolvableHost { host: String, reason: String },
+    #[error(
+        "Port {port} is already in use on {host}. Stop the other process or choose another port with --port"
+    )]
+    PortInUse { host: String, port: u16 },
+    #[error("Cannot bind to {addr}: {source}")]
+    Bind {
+        addr: SocketAddr,
+        source: std::io::Error,
+    },
+    #[error(transparent)]
+    Tls(#[from] super::tls::TlsError),
+    #[error(transparent)]
+    Auth(#[from] AuthError),
+    #[error(transparent)]
+    Database(#[from] DatabaseError),
+    #[error("Failed to load stored artworks: {0}")]
+    StoredArtworks(#[from] RepositoryError),
+}
+
+/// ホスト名をIPアドレスとして解釈し、失敗した場合は名前解決を行う
+pub async fn resolve_bind_address(host: &str, port: u16) -> Result<SocketAddr, ServerStartupError> {
+    if let Ok(ip) = host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        return Ok(SocketAddr::new(ip, port));
+    }
+
+    let mut addrs = tokio::net::lookup_host((host, port)).await.map_err(|e| {
+        ServerStartupError::UnresolvableHost {
+            host: host.to_string(),
+            reason: e.to_string(),
+        }
+    })?;
+    addrs
+        .next()
+        .ok_or_else(|| ServerStartupError::UnresolvableHost {
+            host: host.to_string(),
+            reason: "no addresses returned".to_string(),
+        })
+}
+
+/// 待ち受けソケットを作成（ポート使用中は分かりやすいエラーにする）
+fn bind_listener(
+    addr: SocketAddr,
+    host: &str,
+) -> Result<std::net::TcpListener, ServerStartupError> {
+    let listener = std::net::TcpListener::bind(addr).map_err(|source| {
+        if source.kind() == std::io::ErrorKind::AddrInUse {
+            ServerStartupError::PortInUse {
+                host: host.to_string(),
+                port: addr.port(),
+            }
+        } else {
+            ServerStartupError::Bind { addr, source }
+        }
+    })?;
+    listener
+        .set_nonblocking(true)
+        .map_err(|source| ServerStartupError::Bind { addr, source })?;
+    Ok(listener)
+}
+
+/// `::` でIPv4（v4-mapped）とIPv6の両方を待ち受けるソケットを作成
+///
+/// `net.ipv6.bindv6only` の設定によらず、明示的に `IPV6_V6ONLY` を無効にする。
+fn bind_dual_stack_listener(
+    port: u16,
+    host: &str,
+) -> Result<std::net::TcpListener, ServerStartupError> {
+    use socket2::{Domain, Protocol, Socket, Type};
+
+    let addr = SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), port);
+    let bind_error = |source: std::io::Error| {
+        if source.kind() == std::io::ErrorKind::AddrInUse {
+            ServerStartupError::PortInUse {
+                host: host.to_string(),
+                port,
+            }
+        } else {
+            ServerStartupError::Bind { addr, source }
+        }
+    };
+    let socket =
+        Socket::new(Domain::IPV6, Type::STREAM, Some(Protocol::TCP)).map_err(bind_error)?;
+    socket.set_only_v6(false).map_err(bind_error)?;
+    socket.set_reuse_address(true).map_err(bind_error)?;
+    socket.bind(&addr.into()).map_err(bind_error)?;
+    socket.listen(1024).map_err(bind_error)?;
+    socket.set_nonblocking(true).map_err(bind_error)?;
+    Ok(socket.into())
+}
+
+/// LAN内の他端末から到達可能なIPアドレスを推定
+///
+/// UDPソケットの接続先を設定するだけなので、実際にパケットは送信しない
+fn detect_lan_address() -> Option<IpAddr> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    let ip = socket.local_addr().ok()?.ip();
+    (!ip.is_loopback() && !ip.is_unspecified()).then_some(ip)
+}
+
+/// 実際に待ち受けているアドレスから、アクセス用URLの一覧を作成
+fn reachable_urls(bound: SocketAddr, scheme: &str) -> Vec<String> {
+    let format_url = |ip: IpAddr| match ip {
+        IpAddr::V6(v6) => format!("{scheme}://[{v6}]:{}", bound.port()),
+        IpAddr::V4(v4) => format!("{scheme}://{v4}:{}", bound.port()),
+    };
+
+    if bound.ip().is_unspecified() {
+        let mut urls = vec![format!("{scheme}://localhost:{}", bound.port())];
+        if let Some(lan_ip) = detect_lan_address() {
+            urls.push(format_url(lan_ip));
+        }
+        urls
+    } else {
+        vec![format_url(bound.ip())]
+    }
+}
 
-pub async fn create_server(host: String, port: u16) -> anyhow::Result<()> {
+/// ガジェットの再接続と接続修正のAPIを使えるようにする（セーフモードではシステムのパスへの書き込みを止める）
+fn with_gadget_access(
+    app_state: ArtworkState,
+    gadget_manager: Arc<dyn UsbGadgetManager>,
+    connection_repairer: Arc<dyn ConnectionRepairer>,
+    safe_mode: bool,
+) -> ArtworkState {
+    if safe_mode {
+        app_state
+            .with_connection_repairer(Arc::new(SafeMode::new(connection_repairer)))
+            .with_gadget_manager(Arc::new(SafeMode::new(gadget_manager)))
+    } else {
+        app_state
+            .with_connection_repairer(connection_repairer)
+            .with_gadget_manager(gadget_manager)
+    }
+}
+
+pub async fn create_server(config: ServerConfig) -> anyhow::Result<()> {
     info!("Starting Splatoon3 Ghost Drawer web server...");
 
-    // Parse socket address
-    let addr: SocketAddr = format!("{host}:{port}").parse()?;
+    // Validate and resolve the bind address before doing any hardware work
+    let addr = resolve_bind_address(&config.host, config.port).await?;
+    let listener = if config.dual_stack && addr.ip() == IpAddr::V4(Ipv4Addr::UNSPECIFIED) {
+        match bind_dual_stack_listener(addr.port(), &config.host) {
+            Ok(listener) => listener,
+            // ポート使用中以外（IPv6が無効なカーネルなど）はIPv4だけで待ち受ける
+            Err(ServerStartupError::Bind { source, .. }) => {
+                warn!("IPv6 is not available ({}); listening on IPv4 only", source);
+                bind_listener(addr, &config.host)?
+            }
+            Err(e) => return Err(e.into()),
+        }
+    } else {
+        bind_listener(addr, &config.host)?
+    };
+    let bound_addr = listener.local_addr()?;
 
     // Create shared application state
     use crate::domain::controller::ControllerEmulator;
     use crate::infrastructure::hardware::linux_hid_controller::LinuxHidController;
     use crate::infrastructure::hardware::mock_controller::MockController;
 
-    let mut controller: Arc<dyn ControllerEmulator> = Arc::new(LinuxHidController::new());
+    // Initialize controller (blocking device I/O stays off the async workers)
+    let simulation = ControllerMode::Simulated {
+        strict: config.strict_simulation,
+    };
+    let simulate = config.simulate;
+    let safe_mode = config.safe_mode;
+    let init_window = config.controller_init_window;
+    let metrics = Arc::new(Metrics::new());
+    let report_metrics = metrics.clone();
+    let (controller, controller_mode) = run_controller_io(move || {
+        if simulate {
+            info!("Simulation mode: using Mock Controller");
+        } else if safe_mode {
+            // HIDデバイスを開かないため、Switchには何も届かないシミュレーションとして扱う
+            use crate::infrastructure::safe_mode::SafeModeController;
+
+            let controller: Arc<dyn ControllerEmulator> = Arc::new(SafeModeController::new());
+            if let Err(e) = controller.initialize() {
+                tracing::error!("Failed to initialize the safe mode controller: {}", e);
+            }
+            return (controller, simulation);
+        } else {
+            let controller: Arc<dyn ControllerEmulator> =
+                Arc::new(LinuxHidController::new().with_metrics(report_metrics));
+            // 起動直後はガジェットの構成が終わっていないことがあるため、しばらく再試行する
+            match initialize_with_retry(controller.as_ref(), init_window) {
+                Ok(()) => return (controller, ControllerMode::Hardware),
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to initialize Linux HID controller within {:?}: {}",
+                        init_window,
+                        e
+                    );
+                    tracing::warn!("Falling back to Mock Controller for testing/simulation.");
+                }
+            }
+        }
 
-    // Initialize controller
-    if let Err(e) = controller.initialize() {
-        tracing::warn!("Failed to initialize Linux HID controller: {}", e);
-        tracing::warn!("Falling back to Mock Controller for testing/simulation.");
-        controller = Arc::new(MockController::new());
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
         if let Err(e) = controller.initialize() {
             tracing::error!("Failed to initialize Mock Controller: {}", e);
         }
+        (controller, simulation)
+    })
+    .await?;
+    let mut app_state = ArtworkState::new(controller)
+        .with_controller_mode(controller_mode)
+        .with_metrics(metrics)
+        .with_pause_settings(config.pause)
+        .with_sleep_guard(config.sleep_guard)
+        .with_two_opt_settings(config.two_opt)
+        .with_init_preset(config.init_preset)
+        .with_locale(config.locale)
+        .with_memory_budget(ArtworkMemoryBudget::new(config.artwork_memory_budget_bytes))
+        .with_trash_retention(config.trash_retention);
+    if let Some(control) = &config.log_level {
+        app_state = app_state.with_log_level_control(control.clone());
     }
-    let app_state = Arc::new(ArtworkState::new(controller));
-
-    // Create the application router with all endpoints
-    let app = Router::new()
-        // API endpoints
-        .route("/api/health", get(|| async { "OK" }))
-        .route("/api/system/info", get(get_system_info))
-        .route("/api/hardware/status", get(get_hardware_status))
-        // Artwork endpoints
-        .route("/api/artworks", get(list_artworks).post(create_artwork))
-        .route("/api/artworks/upload", post(upload_artwork))
-        .route(
-            "/api/artworks/{id}",
-            get(get_artwork).delete(delete_artwork),
-        )
-        .route("/api/artworks/{id}/path", get(get_artwork_path))
-        .route("/api/artworks/{id}/strategies", get(get_artwork_strategies))
-        .route("/api/painting/repeats", post(update_painting_repeats))
-        .route("/api/painting/timing", post(update_painting_timing))
-        .route("/api/artworks/{id}/paint", post(paint_artwork))
-        .route("/api/painting/stop", post(stop_painting))
-        .route("/api/painting/pause", post(pause_painting))
-        .route("/api/calibration/start", post(start_calibration))
-        .route(
-            "/api/calibration/test/paint-move",
-            post(start_paint_move_test),
-        )
-        .route("/api/calibration/test/gap-move", post(start_gap_move_test))
-        // WebSocket endpoint
-        .route("/ws/logs", get(websocket_handler))
-        // Add state
-        .with_state(app_state)
-        // Add CORS support and body size limit
-        .layer(
-            ServiceBuilder::new()
-                .layer(DefaultBodyLimit::max(10 * 1024 * 1024)) // 10MB limit
-                .layer(CorsLayer::permissive()),
-        )
-        // Serve embedded static files as fallback
-        .fallback(static_handler);
+    if !config.gallery {
+        app_state = app_state.without_gallery();
+    }
+    if config.preflight {
+        app_state = app_state.with_preflight();
+    }
+    if config.data_dir.is_dir() && !data_dir::is_marked(&config.data_dir) {
+        warn!(
+            "{} has no {} marker; reset-data refuses to delete it until the marker is created",
+            config.data_dir.display(),
+            data_dir::DATA_DIR_MARKER
+        );
+    }
+    let mut data_reset = ResetDataUseCase::new(&config.data_dir);
+    match config.storage {
+        StorageBackend::Sqlite => {
+            // 開いたままのデータベースはファイルを消さず、リポジトリから中身を削除する
+            data_reset = data_reset.keeping(SqliteDatabase::file_names());
+            let database = SqliteDatabase::open(&config.data_dir)?;
+            let mut artworks = SqliteArtworkRepository::new(database.clone());
+            if config.keep_dot_timestamps {
+                artworks = artworks.with_dot_timestamps();
+            }
+            app_state = app_state.with_repositories(
+                Arc::new(artworks),
+                Arc::new(SqlitePaintingRunRepository::new(database)),
+            );
+        }
+        StorageBackend::Memory => {
+            warn!("Using in-memory storage; artworks and painting history are lost on restart");
+        }
+    }
+    app_state = app_state.with_data_reset(data_reset);
+    let mut benchmark =
+        BenchmarkUseCase::new(&config.data_dir).with_two_opt_settings(config.two_opt);
+    if config.simulate || config.safe_mode {
+        benchmark = benchmark.simulated();
+    }
+    app_state = app_state.with_benchmark(benchmark);
+    // シミュレーションでは見積もりを補正せず、テストの見積もりを一定に保つ
+    if controller_mode == ControllerMode::Hardware {
+        app_state = app_state.with_estimate_model(EstimateModelStore::open(&config.data_dir));
+    }
+    let stored = app_state.account_stored_artworks().await?;
+    let memory_budget = &app_state.memory_budget;
+    info!(
+        "Accounted {} stored artworks: {} of {} bytes of the artwork memory budget in use",
+        stored,
+        memory_budget.used_bytes(),
+        memory_budget.budget_bytes()
+    );
+    match purge_expired_trash(&app_state).await {
+        Ok(purged) if !purged.is_empty() => info!(
+            "Purged {} artworks kept in the trash longer than the retention period",
+            purged.len()
+        ),
+        Ok(_) => {}
+        Err(e) => warn!("Failed to purge the trash: {}", e.message),
+    }
+    if let Some(assets_dir) = &config.assets_dir {
+        if assets_dir.is_dir() {
+            info!(
+                "Serving web UI from {} (falling back to embedded assets)",
+                assets_dir.display()
+            );
+        } else {
+            warn!(
+                "Assets directory {} does not exist; serving embedded assets",
+                assets_dir.display()
+            );
+        }
+        app_state = app_state.with_assets_dir(assets_dir);
+    }
+    let audit_log: Arc<dyn GadgetAuditLog> = Arc::new(JsonlGadgetAuditLog::new(&config.data_dir));
+    app_state = app_state.with_audit_log(audit_log.clone());
+    if !config.simulate {
+        use crate::infrastructure::hardware::linux_usb_gadget_manager::LinuxUsbGadgetManager;
+        use crate::infrastructure::setup::{LinuxBoardDetector, LinuxConnectionRepairer};
 
-    // Create TCP listener
-    let listener = TcpListener::bind(&addr).await?;
+        let gadget_manager: Arc<dyn UsbGadgetManager> = Arc::new(
+            LinuxUsbGadgetManager::new()
+                .with_board_detector(Arc::new(LinuxBoardDetector::new()))
+                .with_audit_log(audit_log, AuditInitiator::Web),
+        );
+        let connection_repairer = Arc::new(LinuxConnectionRepairer::new(gadget_manager.clone()));
+        app_state = with_gadget_access(
+            app_state,
+            gadget_manager,
+            connection_repairer,
+            config.safe_mode,
+        );
+    }
+    if config.safe_mode {
+        app_state = app_state.with_safe_mode();
+    }
+    if config.debug {
+        warn!("Debug endpoints are enabled; raw HID reports can be sent to the Switch");
+        app_state = app_state.with_debug_endpoints();
+    }
+    let auth_token = if config.auth {
+        let token = AuthToken::load_or_create(&config.data_dir)?;
+        app_state = app_state.with_auth_token(token.clone());
+        Some(token)
+    } else {
+        warn!("Authentication is disabled; anyone on the network can control the Switch");
+        None
+    };
+    if config.webhooks.is_enabled() {
+        match WebhookDispatcher::new(config.webhooks.clone()) {
+            Ok(dispatcher) => app_state = app_state.with_webhooks(dispatcher),
+            Err(e) => warn!(
+                "Webhooks are disabled: failed to create the HTTP client: {}",
+                e
+            ),
+        }
+    }
+    let tls_config = match &config.tls {
+        Some(tls) => {
+            let mut subject_alt_names = vec!["localhost".to_string()];
+            if !bound_addr.ip().is_unspecified() {
+                subject_alt_names.push(bound_addr.ip().to_string());
+            }
+            if let Some(lan_ip) = detect_lan_address() {
+                subject_alt_names.push(lan_ip.to_string());
+            }
+            if config.host.parse::<IpAddr>().is_err() && config.host != "localhost" {
+                subject_alt_names.push(config.host.clone());
+            }
+            if let Some(hostname) = &config.mdns_hostname {
+                subject_alt_names.push(format!("{hostname}.local"));
+            }
+            let tls_config = tls.load(&config.data_dir, &subject_alt_names).await?;
+            if matches!(tls, TlsSettings::SelfSigned) {
+                app_state = app_state.with_self_signed_tls(SelfSignedTls::new(
+                    tls_config.clone(),
+                    &config.data_dir,
+                    &subject_alt_names,
+                ));
+            }
+            Some(tls_config)
+        }
+        None => None,
+    };
+    let app_state = Arc::new(app_state);
+    spawn_connection_monitor(app_state.clone(), config.connection_monitor);
+    spawn_webhook_dispatcher(app_state.clone());
+    let app = build_router(app_state.clone());
+
+    let scheme = if config.tls.is_some() {
+        "https"
+    } else {
+        "http"
+    };
+    let urls = reachable_urls(bound_addr, scheme);
 
+    info!("Listening on {} ({})", bound_addr, scheme);
     println!("🌐 Web server started successfully!");
-    println!("   URL: http://{addr}");
+    if config.safe_mode {
+        println!(
+            "   🛡️  Safe mode: nothing is written to the gadget, boot files, systemd or HID device"
+        );
+    }
+    for url in &urls {
+        info!("Web UI available at {}", url);
+        println!("   URL: {url}");
+    }
+    if let Some(token) = &auth_token {
+        println!("   Access token: {}", token.as_str());
+        println!(
+            "   (stored in {}, required for painting and other changes)",
+            AuthToken::path(&config.data_dir).display()
+        );
+    }
+    if matches!(config.tls, Some(TlsSettings::SelfSigned)) {
+        warn!("Using a self-signed certificate; browsers will ask you to trust it on first visit");
+    }
+    if let Some(grace) = config.drain_grace {
+        println!(
+            "   Exits once no painting is active or scheduled for {}s (--drain)",
+            grace.as_secs()
+        );
+    }
     println!("   Press Ctrl+C to stop");
 
+    // 終了シグナルを受けたら実行中の描画を止め、スティック入力の途中でも打ち切らせる
+    let shutdown = {
+        let app_state = app_state.clone();
+        let drain_grace = config.drain_grace;
+        async move {
+            let drained = async {
+                match drain_grace {
+                    Some(grace) => wait_until_drained(&app_state, grace).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                _ = shutdown_signal() => info!("Shutdown requested"),
+                _ = drained => info!("Painting queue drained; shutting down"),
+            }
+            if app_state.stop_active_painting().await {
+                info!("Stopping active painting before exit...");
+            }
+        }
+    };
+
     // Run the server
-    axum::serve(listener, app)
-        .await
-        .map_err(|e| anyhow::anyhow!("Server error: {}", e))?;
+    match tls_config {
+        Some(tls_config) => {
+            let handle = axum_server::Handle::new();
+            let shutdown_handle = handle.clone();
+            tokio::spawn(async move {
+                shutdown.await;
+                shutdown_handle.graceful_shutdown(Some(SHUTDOWN_GRACE_PERIOD));
+            });
+            axum_server::from_tcp_rustls(listener, tls_config)
+                .handle(handle)
+                .serve(app.into_make_service())
+                .await
+                .map_err(|e| anyhow::anyhow!("Server error: {}", e))?
+        }
+        None => axum::serve(tokio::net::TcpListener::from_std(listener)?, app)
+            .with_graceful_shutdown(shutdown)
+            .await
+            .map_err(|e| anyhow::anyhow!("Server error: {}", e))?,
+    }
 
+    if !app_state
+        .wait_for_painting_to_finish(SHUTDOWN_GRACE_PERIOD)
+        .await
+    {
+        warn!("Painting did not stop within {:?}", SHUTDOWN_GRACE_PERIOD);
+    }
+    info!("Web server stopped");
     Ok(())
 }
 
-/// 埋め込まれた静的ファイルを提供するハンドラ
-async fn static_handler(uri: Uri) -> impl IntoResponse {
-    let path = uri.path().trim_start_matches('/');
+/// 認証・永続化・ハードウェアを使わないシミュレーションのサーバーを `listener` で動かす（`doctor` の自己診断用）
+///
+/// データディレクトリにも触れず、描画の入力はすべて `controller` に送る。
+pub async fn serve_self_test(
+    listener: tokio::net::TcpListener,
+    controller: Arc<dyn crate::domain::controller::ControllerEmulator>,
+) -> std::io::Result<()> {
+    let app_state = ArtworkState::new(controller)
+        .with_controller_mode(ControllerMode::Simulated { strict: false })
+        .without_gallery();
+    axum::serve(listener, build_router(Arc::new(app_state))).await
+}
 
-    // ルートパスの場合はindex.htmlを提供
-    let path = if path.is_empty() || path == "/" {
-        "index.html"
-    } else {
-        path
+/// 終了シグナル後に接続と描画の終了を待つ最大時間
+const SHUTDOWN_GRACE_PERIOD: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Ctrl+C（UnixではSIGTERMも）を受け取るまで待つ
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
     };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
 
-    // ファイルを取得
-    match WebAssets::get(path) {
-        Some(content) => {
-            let mime = mime_guess::from_path(path).first_or_octet_stream();
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
 
-            Response::builder()
-                .status(StatusCode::OK)
-                .header(header::CONTENT_TYPE, mime.as_ref())
-                .body(Body::from(content.data.to_vec()))
-                .unwrap()
+#[cfg(test)]
+mod tests {
+    use super::super::log_streamer::PROGRESS_CHANNEL;
+    use super::*;
+    use crate::application::use_cases::PaintingControl;
+    use crate::domain::controller::ControllerEmulator;
+    use crate::domain::hardware::gadget_state::GadgetState;
+    use crate::domain::hardware::repositories::UsbGadgetManager;
+    use crate::domain::hardware::verification::GadgetVerification;
+    use crate::domain::setup::entities::{BoardModel, FixConnectionStep, FixConnectionStepResult};
+    use crate::domain::setup::repositories::{
+        BoardDetector, BootConfigurator, ConnectionRepairer, SetupError, SystemdServiceManager,
+    };
+    use crate::infrastructure::hardware::mock_controller::MockController;
+    use std::time::{Duration, Instant};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    async fn send_request(addr: SocketAddr, method: &str, path: &str, body: &str) -> String {
+        send_request_with_type(addr, method, path, "application/json", body).await
+    }
+
+    async fn send_request_with_type(
+        addr: SocketAddr,
+        method: &str,
+        path: &str,
+        content_type: &str,
+        body: &str,
+    ) -> String {
+        send_request_with_headers(addr, method, path, content_type, "", body).await
+    }
+
+    /// `headers` は `Name: value\r\n` 形式で追加するヘッダー
+    async fn send_request_with_headers(
+        addr: SocketAddr,
+        method: &str,
+        path: &str,
+        content_type: &str,
+        headers: &str,
+        body: &str,
+    ) -> String {
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "{method} {path} HTTP/1.1\r\nHost: localhost\r\nContent-Type: {content_type}\r\n{headers}Content-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    // 単一スレッドのランタイムで実行し、コントローラー操作がワーカーを塞ぐと即座に検出できるようにする
+    #[tokio::test(flavor = "current_thread")]
+    async fn test_controller_commands_do_not_block_other_requests() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let state = ArtworkState::new(controller);
+        state.interlock.arm("test", None);
+        let app = build_router(Arc::new(state));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let response = send_request(
+            addr,
+            "POST",
+            "/api/calibration/start",
+            r#"{"press_ms":100,"release_ms":100,"wait_ms":100}"#,
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        // キャリブレーション（数秒間のコントローラー操作）の実行中に一覧を取得する
+        for _ in 0..5 {
+            let started = Instant::now();
+            let response = send_request(addr, "GET", "/api/artworks", "").await;
+            assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+            assert!(
+                started.elapsed() < Duration::from_millis(250),
+                "GET /api/artworks took {:?} while the controller was busy",
+                started.elapsed()
+            );
+            tokio::time::sleep(Duration::from_millis(100)).await;
         }
-        None => {
-            // ファイルが見つからない場合はindex.htmlを返す（SPAのため）
-            if let Some(content) = WebAssets::get("index.html") {
-                Response::builder()
-                    .status(StatusCode::OK)
-                    .header(header::CONTENT_TYPE, "text/html")
-                    .body(Body::from(content.data.to_vec()))
-                    .unwrap()
-            } else {
-                Response::builder()
-                    .status(StatusCode::NOT_FOUND)
-                    .body(Body::from("404 Not Found"))
-                    .unwrap()
+
+        send_request(addr, "POST", "/api/painting/stop", "").await;
+    }
+
+    #[tokio::test]
+    async fn test_controller_output_requires_arming_and_disarm_stops_painting() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let token = AuthToken::new("secret");
+        let state = Arc::new(ArtworkState::new(controller).with_auth_token(token.clone()));
+        let app = build_router(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let authorized = |method: &'static str, path: &'static str, body: &'static str| {
+            send_request_with_headers(
+                addr,
+                method,
+                path,
+                "application/json",
+                "Authorization: Bearer secret\r\n",
+                body,
+            )
+        };
+
+        let input = r#"{"type":"button","value":"A"}"#;
+        let calibration = r#"{"press_ms":100,"release_ms":100,"wait_ms":100}"#;
+        let response = authorized("POST", "/api/controller/input", input).await;
+        assert!(response.starts_with("HTTP/1.1 423"), "{response}");
+        let response = authorized("POST", "/api/calibration/start", calibration).await;
+        assert!(response.starts_with("HTTP/1.1 423"), "{response}");
+
+        let response = authorized("POST", "/api/controller/arm", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let response =
+            authorized("POST", "/api/controller/arm", r#"{"auto_disarm_ms":60000}"#).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let response = send_request(addr, "GET", "/api/controller/status", "").await;
+        assert!(response.contains(r#""armed":true"#), "{response}");
+        assert!(
+            response.contains(&format!(r#""armed_by":"token {}""#, token.fingerprint())),
+            "{response}"
+        );
+        assert!(response.contains(r#""auto_disarm_in_ms":"#), "{response}");
+
+        let response = authorized("POST", "/api/controller/input", input).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        // キャリブレーション中に解除すると停止と同じく描画スレッドが終わる
+        let response = authorized("POST", "/api/calibration/start", calibration).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let response = authorized("POST", "/api/controller/disarm", "").await;
+        assert!(response.contains(r#""armed":false"#), "{response}");
+        assert!(
+            state
+                .wait_for_painting_to_finish(Duration::from_secs(5))
+                .await
+        );
+
+        let response = authorized("POST", "/api/controller/input", input).await;
+        assert!(response.starts_with("HTTP/1.1 423"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn test_controller_input_serializes_requests_and_refuses_while_painting() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let state = Arc::new(ArtworkState::new(controller));
+        state.interlock.arm("test", None);
+        let app = build_router(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // 300ms押下 + 50msの解除を2件同時に送ると、順番に実行されるため700ms以上かかる
+        let body = r#"{"type":"dpad","value":"UP","duration_ms":300}"#;
+        let started = Instant::now();
+        let (first, second) = tokio::join!(
+            send_request(addr, "POST", "/api/controller/input", body),
+            send_request(addr, "POST", "/api/controller/input", body),
+        );
+        assert!(first.starts_with("HTTP/1.1 200"), "{first}");
+        assert!(second.starts_with("HTTP/1.1 200"), "{second}");
+        assert!(started.elapsed() >= Duration::from_millis(700));
+        assert!(
+            first.contains(r#""reports":["00 00 00 80 80 80 80 00","00 00 08 80 80 80 80 00"]"#),
+            "{first}"
+        );
+
+        let response = send_request(
+            addr,
+            "POST",
+            "/api/controller/input",
+            r#"{"type":"button","value":"START"}"#,
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 422"), "{response}");
+
+        *state.active_painting.write().await = Some(PaintingControl::new(1, 100, 100, 100));
+        let response = send_request(addr, "POST", "/api/controller/input", body).await;
+        assert!(response.starts_with("HTTP/1.1 409"), "{response}");
+        *state.active_painting.write().await = None;
+    }
+
+    #[tokio::test]
+    async fn test_upload_records_source_file_and_detects_duplicates() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let state = Arc::new(ArtworkState::new(controller));
+        let app = build_router(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let upload = |path: &'static str, name: &'static str| {
+            let body = format!(
+                "--b\r\nContent-Disposition: form-data; name=\"name\"\r\n\r\n{name}\r\n\
+                 --b\r\nContent-Disposition: form-data; name=\"file\"; filename=\"ika.png\"\r\n\
+                 Content-Type: image/png\r\n\r\nabc\r\n--b--\r\n"
+            );
+            async move {
+                let response = send_request_with_type(
+                    addr,
+                    "POST",
+                    path,
+                    "multipart/form-data; boundary=b",
+                    &body,
+                )
+                .await;
+                assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+                let json = &response[response.find("\r\n\r\n").unwrap() + 4..];
+                serde_json::from_str::<serde_json::Value>(json).unwrap()
             }
+        };
+
+        let first = upload("/api/artworks/upload", "first").await;
+        assert_eq!(first["duplicate"], false);
+        let second = upload("/api/artworks/upload", "second").await;
+        assert_eq!(second["duplicate"], true);
+        assert_eq!(second["id"], first["id"]);
+        let forced = upload("/api/artworks/upload?allow_duplicate=true", "forced").await;
+        assert_eq!(forced["duplicate"], false);
+        assert_ne!(forced["id"], first["id"]);
+        assert_eq!(state.artworks.count().await.unwrap(), 2);
+
+        let id = first["id"].as_str().unwrap();
+        let response = send_request(addr, "GET", &format!("/api/artworks/{id}"), "").await;
+        assert!(
+            response.contains(r#""original_filename":"ika.png""#),
+            "{response}"
+        );
+        assert!(response.contains(r#""file_size":3"#), "{response}");
+        assert!(
+            response.contains(
+                r#""checksum":"ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad""#
+            ),
+            "{response}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_assets_dir_overrides_embedded_assets_and_reports_version() {
+        let dir = std::env::temp_dir().join(format!("ghost-drawer-web-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("css")).unwrap();
+        std::fs::write(dir.join("css/style.css"), "body { color: red; }").unwrap();
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let app = build_router(Arc::new(
+            ArtworkState::new(controller).with_assets_dir(&dir),
+        ));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let response = send_request(addr, "GET", "/css/style.css", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("cache-control: no-cache"), "{response}");
+        assert!(response.ends_with("body { color: red; }"), "{response}");
+
+        // ディレクトリにないファイルは埋め込みアセットを使う
+        let response = send_request(addr, "GET", "/js/app.js", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(!response.contains("cache-control"), "{response}");
+
+        let response = send_request(addr, "GET", "/css/../../etc/passwd", "").await;
+        assert!(response.starts_with("HTTP/1.1 400"), "{response}");
+
+        let response = send_request(addr, "GET", "/api/version", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains(&format!(r#""version":"{}""#, env!("CARGO_PKG_VERSION"))));
+        assert!(
+            response.contains(r#""assets_overridden":true"#),
+            "{response}"
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_openapi_spec_and_docs_are_served() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let app = build_router(Arc::new(ArtworkState::new(controller)));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let response = send_request(addr, "GET", "/api/openapi.json", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("\"/api/artworks/{id}/paint\""));
+
+        let response = send_request(addr, "GET", "/api/docs/", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("swagger"));
+    }
+
+    /// 各手順に時間がかかる接続修正（実機に触れない）
+    struct SlowRepairer;
+
+    impl ConnectionRepairer for SlowRepairer {
+        fn run_step(&self, step: FixConnectionStep) -> FixConnectionStepResult {
+            std::thread::sleep(Duration::from_millis(100));
+            FixConnectionStepResult::succeeded(step, "ok")
         }
     }
+
+    #[tokio::test]
+    async fn test_fix_connection_wizard_can_be_aborted_and_refuses_while_painting() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let state = Arc::new(
+            ArtworkState::new(controller).with_connection_repairer(Arc::new(SlowRepairer)),
+        );
+        let app = build_router(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        *state.active_painting.write().await = Some(PaintingControl::new(1, 100, 100, 100));
+        let response = send_request(addr, "POST", "/api/system/fix-connection/start", "").await;
+        assert!(response.starts_with("HTTP/1.1 409"), "{response}");
+        *state.active_painting.write().await = None;
+
+        let mut progress = PROGRESS_CHANNEL.subscribe();
+        let response = send_request(addr, "POST", "/api/system/fix-connection/start", "").await;
+        assert!(response.starts_with("HTTP/1.1 202"), "{response}");
+        let response = send_request(addr, "POST", "/api/system/fix-connection/start", "").await;
+        assert!(response.starts_with("HTTP/1.1 409"), "{response}");
+
+        let response = send_request(addr, "POST", "/api/system/fix-connection/abort", "").await;
+        assert!(response.contains("\"success\":true"), "{response}");
+
+        // 他のテストの進捗メッセージも流れるため、接続修正の終了通知を探す
+        let finished = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let message: serde_json::Value =
+                    serde_json::from_str(&progress.recv().await.unwrap()).unwrap();
+                if message["type"] == "fix_connection" && message["event"] == "finished" {
+                    return message;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(finished["outcome"]["kind"], "aborted");
+        assert!(state.connection_fix.read().await.is_none());
+
+        let response = send_request(addr, "POST", "/api/system/fix-connection/abort", "").await;
+        assert!(response.contains("\"success\":false"), "{response}");
+    }
+
+    /// 再接続の回数だけを数えるガジェット（実機に触れない）
+    #[derive(Default)]
+    struct CountingGadgetManager {
+        reconnects: std::sync::atomic::AtomicUsize,
+    }
+
+    impl UsbGadgetManager for CountingGadgetManager {
+        fn configure_as_pro_controller(&self) -> Result<(), SetupError> {
+            Ok(())
+        }
+
+        fn is_gadget_configured(&self) -> Result<bool, SetupError> {
+            Ok(true)
+        }
+
+        fn reconnect_gadget(&self) -> Result<(), SetupError> {
+            self.reconnects
+                .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn verify_gadget(&self) -> Result<GadgetVerification, SetupError> {
+            Ok(GadgetVerification::default())
+        }
+
+        fn read_gadget_state(&self) -> Result<GadgetState, SetupError> {
+            Ok(GadgetState::unconfigured(
+                "/sys/kernel/config/usb_gadget/test",
+            ))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_gadget_state_reports_unconfigured_gadget() {
+        let serve = |state: ArtworkState| async move {
+            let app = build_router(Arc::new(state));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+            send_request(addr, "GET", "/api/system/gadget", "").await
+        };
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+
+        let response = serve(ArtworkState::new(controller.clone())).await;
+        assert!(response.starts_with("HTTP/1.1 503"), "{response}");
+
+        let state = ArtworkState::new(controller)
+            .with_gadget_manager(Arc::new(CountingGadgetManager::default()));
+        let response = serve(state).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("\"configured\":false"), "{response}");
+        assert!(response.contains("\"device_nodes\":[]"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_gadget_waits_for_connection_and_refuses_while_painting() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let response = {
+            let app = build_router(Arc::new(ArtworkState::new(controller.clone())));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+            send_request(addr, "POST", "/api/system/reconnect-gadget", "").await
+        };
+        assert!(response.starts_with("HTTP/1.1 503"), "{response}");
+
+        let gadget_manager = Arc::new(CountingGadgetManager::default());
+        let state =
+            Arc::new(ArtworkState::new(controller).with_gadget_manager(gadget_manager.clone()));
+        let app = build_router(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        *state.active_painting.write().await = Some(PaintingControl::new(1, 100, 100, 100));
+        let response = send_request(addr, "POST", "/api/system/reconnect-gadget", "").await;
+        assert!(response.starts_with("HTTP/1.1 409"), "{response}");
+        *state.active_painting.write().await = None;
+
+        let response = send_request(
+            addr,
+            "POST",
+            "/api/system/reconnect-gadget?timeout_ms=0",
+            "",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 422"), "{response}");
+        assert_eq!(
+            gadget_manager
+                .reconnects
+                .load(std::sync::atomic::Ordering::SeqCst),
+            0
+        );
+
+        let response = send_request(
+            addr,
+            "POST",
+            "/api/system/reconnect-gadget?timeout_ms=1000",
+            "",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("\"connected\":true"), "{response}");
+        assert!(response.contains("\"reconnect_ms\":"), "{response}");
+        assert_eq!(
+            gadget_manager
+                .reconnects
+                .load(std::sync::atomic::Ordering::SeqCst),
+            1
+        );
+        // 再接続が終わればセッションは解除され、接続修正を開始できる
+        assert!(state.connection_fix.read().await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_mutating_endpoints_require_access_token() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let state =
+            Arc::new(ArtworkState::new(controller).with_auth_token(AuthToken::new("test-token")));
+        let app = build_router(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let post = |path: &'static str, headers: &'static str| {
+            send_request_with_headers(addr, "POST", path, "application/json", headers, "")
+        };
+
+        // 読み取り専用のAPIと静的ファイルは認証無しで利用できる
+        let response = send_request(addr, "GET", "/api/artworks", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let response = send_request(addr, "GET", "/", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        let response = post("/api/painting/stop", "").await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+        assert!(response.contains("www-authenticate: Bearer"), "{response}");
+        let response = send_request(addr, "DELETE", "/api/artworks/missing", "").await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+        let response = post("/api/painting/stop", "Authorization: Bearer wrong\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+
+        let response = post("/api/painting/stop", "Authorization: Bearer test-token\r\n").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        // ログインで発行されたCookieでも認証できる
+        let response = send_request(addr, "POST", "/api/auth/login", r#"{"token":"wrong"}"#).await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+        let response =
+            send_request(addr, "POST", "/api/auth/login", r#"{"token":"test-token"}"#).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let cookie = response
+            .lines()
+            .find_map(|line| line.strip_prefix("set-cookie: "))
+            .and_then(|value| value.split(';').next())
+            .unwrap()
+            .to_string();
+        assert!(!cookie.contains("test-token"));
+
+        let headers = format!("Cookie: theme=dark; {cookie}\r\n");
+        let response = send_request_with_headers(
+            addr,
+            "POST",
+            "/api/painting/stop",
+            "application/json",
+            &headers,
+            "",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn test_gallery_is_public_and_can_be_disabled_at_runtime() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let state =
+            Arc::new(ArtworkState::new(controller).with_auth_token(AuthToken::new("test-token")));
+        let app = build_router(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let set_gallery = |headers: &'static str, enabled: bool| {
+            send_request_with_headers(
+                addr,
+                "PUT",
+                "/api/system/gallery",
+                "application/json",
+                headers,
+                if enabled {
+                    r#"{"enabled":true}"#
+                } else {
+                    r#"{"enabled":false}"#
+                },
+            )
+        };
+
+        // 認証を有効にしていても、ギャラリーはトークン無しで見られる
+        let response = send_request(addr, "GET", "/gallery", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains("js/gallery.js"), "{response}");
+        let response = send_request(addr, "GET", "/api/gallery/state", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains(r#""active":false"#), "{response}");
+
+        // 切り替えにはトークンが必要
+        let response = set_gallery("", false).await;
+        assert!(response.starts_with("HTTP/1.1 401"), "{response}");
+        let response = set_gallery("Authorization: Bearer test-token\r\n", false).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains(r#""previous":true"#), "{response}");
+
+        for path in ["/gallery", "/api/gallery/state", "/ws/gallery"] {
+            let response = send_request(addr, "GET", path, "").await;
+            assert!(response.starts_with("HTTP/1.1 404"), "{path}: {response}");
+        }
+
+        let response = set_gallery("Authorization: Bearer test-token\r\n", true).await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let response = send_request(addr, "GET", "/api/gallery/state", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn test_auth_can_be_disabled() {
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let app = build_router(Arc::new(ArtworkState::new(controller)));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let response = send_request(addr, "POST", "/api/painting/stop", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let response = send_request(addr, "POST", "/api/auth/login", r#"{"token":"any"}"#).await;
+        assert!(
+            response.contains("Authentication is disabled"),
+            "{response}"
+        );
+        assert!(!response.contains("set-cookie"), "{response}");
+        assert!(!ServerConfig::new("127.0.0.1", 0).without_auth().auth);
+    }
+
+    #[tokio::test]
+    async fn test_log_level_can_be_changed_at_runtime() {
+        let serve = |state: ArtworkState| async move {
+            let app = build_router(Arc::new(state));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+            addr
+        };
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+
+        let addr = serve(ArtworkState::new(controller.clone())).await;
+        let response =
+            send_request(addr, "PUT", "/api/system/log-level", r#"{"level":"debug"}"#).await;
+        assert!(response.starts_with("HTTP/1.1 503"), "{response}");
+
+        let (_layer, control) = LogLevelControl::new("info").unwrap();
+        let addr =
+            serve(ArtworkState::new(controller).with_log_level_control(control.clone())).await;
+        let response = send_request(
+            addr,
+            "PUT",
+            "/api/system/log-level",
+            r#"{"level":"info,foo=verbose"}"#,
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 422"), "{response}");
+        assert_eq!(control.current(), "info");
+
+        let response = send_request(
+            addr,
+            "PUT",
+            "/api/system/log-level",
+            r#"{"level":"debug,splatoon3_ghost_drawer::infrastructure::hardware=trace"}"#,
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(response.contains(r#""previous":"info""#), "{response}");
+        assert_eq!(
+            control.current(),
+            "debug,splatoon3_ghost_drawer::infrastructure::hardware=trace"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_webhook_test_endpoint_reports_each_delivery() {
+        let serve = |state: ArtworkState| async move {
+            let app = build_router(Arc::new(state));
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let addr = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+            addr
+        };
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+
+        let addr = serve(ArtworkState::new(controller.clone())).await;
+        let response = send_request(addr, "POST", "/api/settings/webhooks/test", "").await;
+        assert!(response.starts_with("HTTP/1.1 503"), "{response}");
+
+        // 接続を拒否されるURL
+        let dead = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let dead_addr = dead.local_addr().unwrap();
+        drop(dead);
+        let settings = WebhookSettings::new(vec![format!("http://{dead_addr}/hook/secret-token")])
+            .with_retry(2, Duration::from_millis(10));
+        let addr = serve(
+            ArtworkState::new(controller).with_webhooks(WebhookDispatcher::new(settings).unwrap()),
+        )
+        .await;
+        let response = send_request(addr, "POST", "/api/settings/webhooks/test", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let json = &response[response.find("\r\n\r\n").unwrap() + 4..];
+        let body: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(body["deliveries"][0]["url"], "127.0.0.1");
+        assert_eq!(body["deliveries"][0]["delivered"], false);
+        assert_eq!(body["deliveries"][0]["attempts"], 2);
+        assert!(!response.contains("secret-token"), "{response}");
+    }
+
+    #[tokio::test]
+    async fn test_dual_stack_listener_accepts_ipv4_and_ipv6() {
+        let listener = match bind_dual_stack_listener(0, DEFAULT_HOST) {
+            Ok(listener) => listener,
+            // IPv6が無効な環境では確認できない
+            Err(e) => {
+                eprintln!("skipping: {e}");
+                return;
+            }
+        };
+        let listener = tokio::net::TcpListener::from_std(listener).unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(MockController::new());
+        let app = build_router(Arc::new(ArtworkState::new(controller)));
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let v4 = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), port);
+        let response = send_request(v4, "GET", "/api/artworks", "").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        let v6 = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), port);
+        if tokio::net::TcpStream::connect(v6).await.is_ok() {
+            let response = send_request(v6, "GET", "/api/artworks", "").await;
+            assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        }
+    }
+
+    /// システムのパスを開いて書き込む代わりに、開いたはずのパスを記録する（実機に触れない）
+    #[derive(Default)]
+    struct RecordingSystem {
+        opened_for_writing: std::sync::Mutex<Vec<PathBuf>>,
+    }
+
+    impl RecordingSystem {
+        fn open_for_writing(&self, path: &str) -> Result<(), SetupError> {
+            self.opened_for_writing
+                .lock()
+                .unwrap()
+                .push(PathBuf::from(path));
+            Ok(())
+        }
+
+        /// `data_dir` の外で書き込み用に開いたパス
+        fn outside(&self, data_dir: &std::path::Path) -> Vec<PathBuf> {
+            let opened = self.opened_for_writing.lock().unwrap();
+            opened
+                .iter()
+                .filter(|path| !path.starts_with(data_dir))
+                .cloned()
+                .collect()
+        }
+    }
+
+    const GADGET_PATH: &str = "/sys/kernel/config/usb_gadget/nintendo_controller";
+
+    impl UsbGadgetManager for RecordingSystem {
+        fn configure_as_pro_controller(&self) -> Result<(), SetupError> {
+            self.open_for_writing(&format!("{GADGET_PATH}/idVendor"))
+        }
+
+        fn is_gadget_configured(&self) -> Result<bool, SetupError> {
+            Ok(true)
+        }
+
+        fn reconnect_gadget(&self) -> Result<(), SetupError> {
+            self.open_for_writing(&format!("{GADGET_PATH}/UDC"))
+        }
+
+        fn verify_gadget(&self) -> Result<GadgetVerification, SetupError> {
+            Ok(GadgetVerification::default())
+        }
+
+        fn read_gadget_state(&self) -> Result<GadgetState, SetupError> {
+            Ok(GadgetState::unconfigured(GADGET_PATH))
+        }
+    }
+
+    impl ConnectionRepairer for RecordingSystem {
+        fn run_step(&self, step: FixConnectionStep) -> FixConnectionStepResult {
+            let path = match step {
+                FixConnectionStep::UnbindUdc | FixConnectionStep::RebindUdc => {
+                    format!("{GADGET_PATH}/UDC")
+                }
+                FixConnectionStep::ReloadModules => "/sys/module".to_string(),
+                FixConnectionStep::RebuildGadget => format!("{GADGET_PATH}/idVendor"),
+                FixConnectionStep::WaitForHidDevice => {
+                    return FixConnectionStepResult::succeeded(step, "found");
+                }
+                FixConnectionStep::TestWrite => "/dev/hidg0".to_string(),
+            };
+            self.open_for_writing(&path).unwrap();
+            FixConnectionStepResult::succeeded(step, "ok")
+        }
+    }
+
+    impl BoardDetector for RecordingSystem {
+        fn detect_board(&self) -> Result<BoardModel, SetupError> {
+            Ok(BoardModel::OrangePiZero2W)
+        }
+    }
+
+    impl BootConfigurator for RecordingSystem {
+        fn configure_boot_for_otg(&self, _board: &BoardModel) -> Result<(), SetupError> {
+            self.open_for_writing("/boot/orangepiEnv.txt")
+        }
+
+        fn is_boot_configured(&self, _board: &BoardModel) -> Result<bool, SetupError> {
+            Ok(false)
+        }
+
+        fn remove_boot_configuration(&self, _board: &BoardModel) -> Result<(), SetupError> {
+            self.open_for_writing("/boot/orangepiEnv.txt")
+        }
+    }
+
+    impl SystemdServiceManager for RecordingSystem {
+        fn create_gadget_service(&self) -> Result<(), SetupError> {
+            self.open_for_writing("/etc/systemd/system/splatoon3-gadget.service")
+        }
+
+        fn enable_gadget_service(&self) -> Result<(), SetupError> {
+            self.open_for_writing("/etc/systemd/system/multi-user.target.wants")
+        }
+
+        fn is_service_enabled(&self) -> Result<bool, SetupError> {
+            Ok(false)
+        }
+
+        fn create_web_service(&self) -> Result<(), SetupError> {
+            self.open_for_writing("/etc/systemd/system/splatoon3-ghost-drawer.service")
+        }
+
+        fn enable_web_service(&self) -> Result<(), SetupError> {
+            self.open_for_writing("/etc/systemd/system/multi-user.target.wants")
+        }
+
+        fn missing_unit_ordering(&self) -> Vec<String> {
+            Vec::new()
+        }
+
+        fn disable_and_remove_services(&self) -> Result<(), SetupError> {
+            self.open_for_writing("/etc/systemd/system/splatoon3-gadget.service")
+        }
+
+        fn setup_application_files(&self) -> Result<(), SetupError> {
+            self.open_for_writing("/usr/local/bin/splatoon3-ghost-drawer")
+        }
+
+        fn cleanup_application_files(&self) -> Result<(), SetupError> {
+            self.open_for_writing("/usr/local/bin/splatoon3-ghost-drawer")
+        }
+    }
+
+    #[tokio::test]
+    async fn test_safe_mode_writes_nothing_outside_the_data_dir() {
+        use crate::application::use_cases::SetupSystemUseCase;
+        use crate::infrastructure::safe_mode::SafeModeController;
+
+        let data_dir = std::env::temp_dir().join(format!("safe-mode-{}", uuid::Uuid::new_v4()));
+        let system = Arc::new(RecordingSystem::default());
+
+        // セットアップ：ブート設定とユニットファイルはログに出すだけで成功する
+        let setup = SetupSystemUseCase::new(
+            system.clone(),
+            Arc::new(SafeMode::new(system.clone() as Arc<dyn BootConfigurator>)),
+            Arc::new(SafeMode::new(
+                system.clone() as Arc<dyn SystemdServiceManager>
+            )),
+        )
+        .with_safe_mode();
+        setup.execute(true).unwrap();
+        // ガジェットの構成：成功したふりをせずに止める
+        let gadget = SafeMode::new(system.clone() as Arc<dyn UsbGadgetManager>);
+        assert!(matches!(
+            gadget.configure_as_pro_controller(),
+            Err(SetupError::SafeModeBlocked(_))
+        ));
+
+        let controller: Arc<dyn ControllerEmulator> = Arc::new(SafeModeController::new());
+        let state = with_gadget_access(
+            ArtworkState::new(controller)
+                .with_init_preset(InitPreset::None)
+                .with_audit_log(Arc::new(JsonlGadgetAuditLog::new(&data_dir)))
+                .with_safe_mode(),
+            system.clone(),
+            system.clone(),
+            true,
+        );
+        let state = Arc::new(state);
+        state.interlock.arm("test", None);
+        let app = build_router(state.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let response = send_request(addr, "GET", "/api/system/info", "").await;
+        assert!(response.contains(r#""safe_mode":true"#), "{response}");
+
+        let response = send_request(
+            addr,
+            "POST",
+            "/api/system/reconnect-gadget?timeout_ms=1000",
+            "",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+
+        // 接続修正：書き込みの確認は成功したふりをせず、その手順で止まる
+        let mut progress = PROGRESS_CHANNEL.subscribe();
+        let response = send_request(addr, "POST", "/api/system/fix-connection/start", "").await;
+        assert!(response.starts_with("HTTP/1.1 202"), "{response}");
+        let finished = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                let message: serde_json::Value =
+                    serde_json::from_str(&progress.recv().await.unwrap()).unwrap();
+                if message["type"] == "fix_connection" && message["event"] == "finished" {
+                    return message;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(finished["outcome"]["kind"], "failed");
+        assert_eq!(finished["outcome"]["step"], "test_write");
+
+        // 描画：入力はHIDデバイスに書かれず、時間だけ再現される
+        let request = GenerateArtworkRequest {
+            width: 4,
+            height: 2,
+            ..GenerateArtworkRequest::new(TestPattern::Checkerboard)
+        };
+        let response = send_request(
+            addr,
+            "POST",
+            "/api/artworks/generate",
+            &serde_json::to_string(&request).unwrap(),
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let (_, body) = response.split_once("\r\n\r\n").unwrap();
+        let artwork: serde_json::Value = serde_json::from_str(body).unwrap();
+        let id = artwork["id"].as_str().unwrap();
+        let response = send_request(addr, "POST", &format!("/api/artworks/{id}/paint"), "{}").await;
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        assert!(
+            state
+                .wait_for_painting_to_finish(Duration::from_secs(30))
+                .await
+        );
+
+        assert_eq!(system.outside(&data_dir), Vec::<PathBuf>::new());
+
+        // 記録の確認：セーフモードでなければ同じ操作でシステムのパスを開く
+        with_gadget_access(
+            ArtworkState::new(Arc::new(MockController::new())),
+            system.clone(),
+            system.clone(),
+            false,
+        )
+        .gadget_manager
+        .unwrap()
+        .reconnect_gadget()
+        .unwrap();
+        assert_eq!(
+            system.outside(&data_dir),
+            vec![PathBuf::from(format!("{GADGET_PATH}/UDC"))]
+        );
+        let _ = std::fs::remove_dir_all(&data_dir);
+    }
 }
@@ -0,0 +1,614 @@
+//! Webハンドラーが共有する状態
+//!
+//! アートワークの保存先、コントローラー、実行中の描画などを `ArtworkState` にまとめ、
+//! 全てのハンドラーへ `State` として渡す。
+
+use super::artwork_locks::ArtworkLocks;
+use super::artworks::{ArtworkAnalysisCache, DEFAULT_TRASH_RETENTION};
+use super::auth::AuthToken;
+use super::drain::ActivityClock;
+use super::embedded_assets::WebAssetSource;
+use super::error_response::ErrorResponse;
+use super::gallery::GalleryMode;
+use super::path_computations::PathComputations;
+use super::scheduled_painting::ScheduledPainting;
+use super::tls::SelfSignedTls;
+use super::webhooks::WebhookDispatcher;
+use crate::application::estimate_model::EstimateModelStore;
+use crate::application::metrics::Metrics;
+use crate::application::painting_trace::PaintingTraceStore;
+use crate::application::use_cases::{
+    ArtworkEventLog, BenchmarkUseCase, CalibrationTrace, PaintingControl, ResetDataUseCase,
+};
+use crate::debug::LogLevelControl;
+use crate::domain::artwork::change_log::ArtworkChange;
+use crate::domain::artwork::entities::{Artwork, ArtworkId};
+use crate::domain::artwork::history::CanvasHistory;
+use crate::domain::artwork::memory_budget::ArtworkMemoryBudget;
+use crate::domain::artwork::repositories::{
+    ArtworkQuery, ArtworkRepository, RepositoryError, SortField, SortOrder,
+};
+use crate::domain::controller::{ControllerEmulator, ControllerInterlock};
+use crate::domain::events::ArtworkEvent;
+use crate::domain::hardware::repositories::UsbGadgetManager;
+use crate::domain::painting::{
+    InitPreset, PaintTiming, PaintingRunRepository, PauseSettings, SleepGuardSettings,
+    TwoOptSettings,
+};
+use crate::domain::setup::repositories::{ConnectionRepairer, GadgetAuditLog};
+use crate::domain::shared::events::EventMetadata;
+use crate::domain::shared::i18n::{Locale, Message, MessageKey};
+use crate::domain::shared::value_objects::Coordinates;
+use crate::infrastructure::persistence::{
+    in_memory_artwork_repository::InMemoryArtworkRepository,
+    in_memory_painting_run_repository::InMemoryPaintingRunRepository,
+};
+use axum::http::StatusCode;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+fn already_running(running: &PaintingControl) -> ErrorResponse {
+    ErrorResponse::localized(
+        StatusCode::CONFLICT,
+        Message::new(MessageKey::PaintingAlreadyRunning).with_arg("generation", running.generation),
+    )
+}
+
+/// 描画の終了を記録し、実行中の描画として登録されたままなら取り除く
+///
+/// 後から開始された描画を消さないよう、世代番号が一致する場合だけ取り除く。
+pub(crate) async fn release_active_painting(
+    store: &RwLock<Option<PaintingControl>>,
+    control: &PaintingControl,
+) {
+    control.mark_finished();
+    let mut active = store.write().await;
+    if active
+        .as_ref()
+        .is_some_and(|active| active.generation == control.generation)
+    {
+        *active = None;
+    }
+}
+
+#[derive(Clone)]
+pub struct ArtworkState {
+    /// アートワークの保存先
+    pub artworks: Arc<dyn ArtworkRepository>,
+    pub controller: Arc<dyn ControllerEmulator>,
+    pub active_painting: Arc<RwLock<Option<PaintingControl>>>,
+    /// アートワーク集約のドメインイベントログ
+    pub events: ArtworkEventLog,
+    pub controller_mode: ControllerMode,
+    /// セーフモードで起動し、システムのパスへの書き込みを止めているか
+    pub safe_mode: bool,
+    /// デバッグ用API（`POST /api/debug/hid-report`）を受け付けるか
+    pub debug: bool,
+    /// 描画実行の履歴
+    pub runs: Arc<dyn PaintingRunRepository>,
+    /// 接続修正ウィザードの実行先（未設定の場合はウィザードを利用できない）
+    pub connection_repairer: Option<Arc<dyn ConnectionRepairer>>,
+    /// USBガジェットの再接続先（未設定の場合は再接続APIを利用できない）
+    pub gadget_manager: Option<Arc<dyn UsbGadgetManager>>,
+    /// ガジェットの構成・再接続・接続修正を記録する監査ログ（未設定の場合は記録しない）
+    pub audit_log: Option<Arc<dyn GadgetAuditLog>>,
+    /// 実行中の接続修正・ガジェット再接続のセッション
+    pub connection_fix: Arc<RwLock<Option<ConnectionFixSession>>>,
+    /// 手動入力を1件ずつデバイスへ送るためのロック
+    pub controller_input: Arc<tokio::sync::Mutex<()>>,
+    /// アートワークの読み込みから保存までを直列化する書き込みロック
+    pub artwork_edits: Arc<tokio::sync::Mutex<()>>,
+    /// アートワークごとに実行中の描画・パスの計算・キャンバスの変更・削除
+    pub artwork_locks: ArtworkLocks,
+    /// 変更系APIに要求するアクセストークン（`None` なら認証しない。`reset-data` で作り直す）
+    pub auth_token: Option<Arc<std::sync::RwLock<AuthToken>>>,
+    /// データディレクトリの自己署名証明書で提供中のHTTPS（`reset-data` で作り直す）
+    pub self_signed_tls: Option<SelfSignedTls>,
+    /// 描画リクエストで省略された場合の一時停止の動作
+    pub pause: PauseSettings,
+    /// WebUIのアセットの提供元
+    pub assets: WebAssetSource,
+    /// アームされるまでコントローラー出力を拒否する安全装置
+    pub interlock: ControllerInterlock,
+    /// `GreedyTwoOpt` の2-opt最適化の打ち切り条件
+    pub two_opt: TwoOptSettings,
+    /// キャンバス編集の取り消し履歴
+    pub canvas_history: CanvasHistory,
+    /// 描画リクエストで省略された場合の初期化手順
+    pub init_preset: InitPreset,
+    /// 描画リクエストで省略された場合に、描画前の確認用の印を描いて確認を待つか
+    pub preflight: bool,
+    /// 実行中に変更できるログフィルター（未設定の場合はAPIから変更できない）
+    pub log_level: Option<LogLevelControl>,
+    /// 見積もり時間と本体の自動スリープの閾値の比較
+    pub sleep_guard: SleepGuardSettings,
+    /// 認証無しで進捗を公開するギャラリーの有効・無効
+    pub gallery: GalleryMode,
+    /// 保存中のアートワーク（ゴミ箱のアートワークを含む）のメモリ使用量の計上
+    pub memory_budget: ArtworkMemoryBudget,
+    /// ゴミ箱のアートワークを `POST /api/artworks/trash/purge` と起動時に完全に削除するまでの期間
+    pub trash_retention: std::time::Duration,
+    /// 開始時刻を待っている描画の予約
+    pub scheduled_painting: Arc<RwLock<Option<ScheduledPainting>>>,
+    /// 描画前の確認に使う解析結果のキャッシュ
+    pub analyses: ArtworkAnalysisCache,
+    /// 実行中の描画パス・解析・戦略の比較の計算（削除・置き換え・要求で取り消す）
+    pub path_computations: PathComputations,
+    /// イベントの通知先のWebhook（未設定の場合は通知しない）
+    pub webhooks: Option<Arc<WebhookDispatcher>>,
+    /// HIDレポートとログの書き込み量の集計
+    pub metrics: Arc<Metrics>,
+    /// 直近の速度キャリブレーション（描いたドットの後片付けに使う）
+    pub calibration: Arc<RwLock<Option<CalibrationSession>>>,
+    /// データディレクトリの初期化（未設定の場合はAPIから初期化できない）
+    pub data_reset: Option<ResetDataUseCase>,
+    /// 描画速度のベンチマーク（未設定の場合はAPIから実行できない）
+    pub benchmark: Option<BenchmarkUseCase>,
+    /// 描画の実測から学習した見積もりの補正（未設定の場合やシミュレーションでは補正しない）
+    pub estimate_model: EstimateModelStore,
+    /// `Accept-Language` で言語が選ばれなかった場合の文言の言語（ログもこの言語で出力する）
+    pub locale: Locale,
+    /// 変換プレビューを同時に1件だけ実行するための許可
+    pub conversion_previews: Arc<tokio::sync::Semaphore>,
+    /// 最後に変更系APIへのリクエストや描画があった時刻（`run --drain` の終了判定に使う）
+    pub activity: ActivityClock,
+    /// `PaintRequest.trace` で記録した直近の描画のタイミング
+    pub painting_traces: PaintingTraceStore,
+}
+
+/// 描いたドットとカーソル位置を記録している速度キャリブレーション
+#[derive(Clone)]
+pub struct CalibrationSession {
+    pub id: String,
+    pub trace: Arc<CalibrationTrace>,
+    /// キャリブレーションで使った速度（後片付けの既定値）
+    pub timing: PaintTiming,
+}
+
+/// 実行中の接続修正ウィザード
+#[derive(Clone)]
+pub struct ConnectionFixSession {
+    pub id: String,
+    pub abort_signal: Arc<AtomicBool>,
+}
+
+/// 実機のコントローラーを使用しているか、シミュレーションか
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ControllerMode {
+    #[default]
+    Hardware,
+    /// MockControllerで動作（`strict` の場合は描画・キャリブレーションを拒否）
+    Simulated { strict: bool },
+}
+
+impl ArtworkState {
+    pub fn new(controller: Arc<dyn ControllerEmulator>) -> Self {
+        Self {
+            artworks: Arc::new(InMemoryArtworkRepository::new()),
+            controller,
+            active_painting: Arc::new(RwLock::new(None)),
+            events: ArtworkEventLog::default(),
+            controller_mode: ControllerMode::Hardware,
+            safe_mode: false,
+            debug: false,
+            runs: Arc::new(InMemoryPaintingRunRepository::new()),
+            connection_repairer: None,
+            gadget_manager: None,
+            audit_log: None,
+            connection_fix: Arc::new(RwLock::new(None)),
+            controller_input: Arc::new(tokio::sync::Mutex::new(())),
+            artwork_edits: Arc::new(tokio::sync::Mutex::new(())),
+            artwork_locks: ArtworkLocks::default(),
+            auth_token: None,
+            self_signed_tls: None,
+            pause: PauseSettings::default(),
+            assets: WebAssetSource::embedded(),
+            interlock: ControllerInterlock::new(),
+            two_opt: TwoOptSettings::default(),
+            canvas_history: CanvasHistory::default(),
+            init_preset: InitPreset::default(),
+            preflight: false,
+            log_level: None,
+            sleep_guard: SleepGuardSettings::default(),
+            gallery: GalleryMode::default(),
+            memory_budget: ArtworkMemoryBudget::default(),
+            trash_retention: DEFAULT_TRASH_RETENTION,
+            scheduled_painting: Arc::new(RwLock::new(None)),
+            analyses: ArtworkAnalysisCache::default(),
+            path_computations: PathComputations::default(),
+            webhooks: None,
+            metrics: Arc::new(Metrics::new()),
+            calibration: Arc::new(RwLock::new(None)),
+            data_reset: None,
+            benchmark: None,
+            estimate_model: EstimateModelStore::disabled(),
+            locale: Locale::default(),
+            conversion_previews: Arc::new(tokio::sync::Semaphore::new(1)),
+            activity: ActivityClock::default(),
+            painting_traces: PaintingTraceStore::default(),
+        }
+    }
+
+    /// アートワークと描画履歴の保存先を差し替える
+    pub fn with_repositories(
+        mut self,
+        artworks: Arc<dyn ArtworkRepository>,
+        runs: Arc<dyn PaintingRunRepository>,
+    ) -> Self {
+        self.artworks = artworks;
+        self.runs = runs;
+        self
+    }
+
+    pub fn with_connection_repairer(mut self, repairer: Arc<dyn ConnectionRepairer>) -> Self {
+        self.connection_repairer = Some(repairer);
+        self
+    }
+
+    pub fn with_gadget_manager(mut self, gadget_manager: Arc<dyn UsbGadgetManager>) -> Self {
+        self.gadget_manager = Some(gadget_manager);
+        self
+    }
+
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn GadgetAuditLog>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+
+    /// `POST /api/system/reset-data` で初期化するデータディレクトリ
+    pub fn with_data_reset(mut self, data_reset: ResetDataUseCase) -> Self {
+        self.data_reset = Some(data_reset);
+        self
+    }
+
+    pub fn with_benchmark(mut self, benchmark: BenchmarkUseCase) -> Self {
+        self.benchmark = Some(benchmark);
+        self
+    }
+
+    pub fn with_estimate_model(mut self, estimate_model: EstimateModelStore) -> Self {
+        self.estimate_model = estimate_model;
+        self
+    }
+
+    pub fn with_controller_mode(mut self, controller_mode: ControllerMode) -> Self {
+        self.controller_mode = controller_mode;
+        self
+    }
+
+    pub fn with_safe_mode(mut self) -> Self {
+        self.safe_mode = true;
+        self
+    }
+
+    pub fn with_debug_endpoints(mut self) -> Self {
+        self.debug = true;
+        self
+    }
+
+    pub fn with_auth_token(mut self, token: AuthToken) -> Self {
+        self.auth_token = Some(Arc::new(std::sync::RwLock::new(token)));
+        self
+    }
+
+    pub fn with_self_signed_tls(mut self, tls: SelfSignedTls) -> Self {
+        self.self_signed_tls = Some(tls);
+        self
+    }
+
+    /// 現在のアクセストークン（`None` なら認証しない）
+    pub fn auth_token(&self) -> Option<AuthToken> {
+        self.auth_token
+            .as_ref()
+            .map(|token| token.read().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    /// アクセストークンを置き換える（認証が無効なら何もしない）
+    pub fn replace_auth_token(&self, token: AuthToken) {
+        if let Some(current) = &self.auth_token {
+            *current.write().unwrap_or_else(|e| e.into_inner()) = token;
+        }
+    }
+
+    pub fn with_pause_settings(mut self, pause: PauseSettings) -> Self {
+        self.pause = pause;
+        self
+    }
+
+    pub fn with_two_opt_settings(mut self, two_opt: TwoOptSettings) -> Self {
+        self.two_opt = two_opt;
+        self
+    }
+
+    pub fn with_init_preset(mut self, init_preset: InitPreset) -> Self {
+        self.init_preset = init_preset;
+        self
+    }
+
+    pub fn with_locale(mut self, locale: Locale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// 描画リクエストで省略された場合も描画前の確認を行う
+    pub fn with_preflight(mut self) -> Self {
+        self.preflight = true;
+        self
+    }
+
+    pub fn with_log_level_control(mut self, control: LogLevelControl) -> Self {
+        self.log_level = Some(control);
+        self
+    }
+
+    pub fn with_sleep_guard(mut self, sleep_guard: SleepGuardSettings) -> Self {
+        self.sleep_guard = sleep_guard;
+        self
+    }
+
+    pub fn with_memory_budget(mut self, memory_budget: ArtworkMemoryBudget) -> Self {
+        self.memory_budget = memory_budget;
+        self
+    }
+
+    /// ゴミ箱のアートワークを完全に削除するまでの期間（既定は7日）
+    pub fn with_trash_retention(mut self, retention: std::time::Duration) -> Self {
+        self.trash_retention = retention;
+        self
+    }
+
+    /// コントローラーに渡した集計先を共有する
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = metrics;
+        self
+    }
+
+    pub fn with_webhooks(mut self, webhooks: WebhookDispatcher) -> Self {
+        self.webhooks = Some(Arc::new(webhooks));
+        self
+    }
+
+    /// 保存先に既にあるアートワークの使用量を計上する（起動時に1回呼ぶ）
+    ///
+    /// ゴミ箱のアートワークも元に戻せるよう保持しているため計上する。
+    pub async fn account_stored_artworks(&self) -> Result<usize, RepositoryError> {
+        let artworks = self.artworks.find_all().await?;
+        let trashed = self.artworks.find_trashed().await?;
+        for artwork in artworks
+            .iter()
+            .chain(trashed.iter().map(|trashed| &trashed.artwork))
+        {
+            self.memory_budget.account(artwork);
+        }
+        Ok(artworks.len() + trashed.len())
+    }
+
+    /// アートワークの使用量を計上する（上限を超える場合は507）
+    pub(crate) fn reserve_memory(&self, artwork: &Artwork) -> Result<(), ErrorResponse> {
+        self.memory_budget.reserve(artwork).map_err(|e| {
+            warn!("Rejecting artwork {}: {}", artwork.id, e);
+            ErrorResponse::new(StatusCode::INSUFFICIENT_STORAGE, e.to_string())
+        })
+    }
+
+    /// 編集したアートワークを計上し直して保存する（保存できなければ保存先の内容で計上し直す）
+    pub(crate) async fn save_edited_artwork(&self, artwork: &Artwork) -> Result<(), ErrorResponse> {
+        self.reserve_memory(artwork)?;
+        if let Err(e) = self.artworks.save(artwork).await {
+            if let Ok(Some(stored)) = self.artworks.find_by_id(&artwork.id).await {
+                self.memory_budget.account(&stored);
+            }
+            return Err(e.into());
+        }
+        Ok(())
+    }
+
+    /// ギャラリーモードを無効にして起動する（APIから再び有効にできる）
+    pub fn without_gallery(self) -> Self {
+        self.gallery.set(false);
+        self
+    }
+
+    /// リクエストで時間の上限が指定されていればサーバーの設定を上書きする
+    pub(crate) fn two_opt_settings(&self, time_budget_ms: Option<u64>) -> TwoOptSettings {
+        match time_budget_ms {
+            Some(time_budget_ms) => self.two_opt.with_time_budget_ms(time_budget_ms),
+            None => self.two_opt,
+        }
+    }
+
+    /// 埋め込みアセットより `dir` のファイルを優先して配信する
+    pub fn with_assets_dir(mut self, dir: impl Into<std::path::PathBuf>) -> Self {
+        self.assets = WebAssetSource::with_dir(dir);
+        self
+    }
+
+    /// 実行中の描画・キャリブレーションに停止を指示する（実行中でなければ `false`）
+    pub async fn stop_active_painting(&self) -> bool {
+        self.active_painting
+            .read()
+            .await
+            .as_ref()
+            .is_some_and(PaintingControl::request_stop)
+    }
+
+    /// 実行中の描画・キャリブレーションがあれば409を返す
+    pub(crate) async fn ensure_no_active_painting(&self) -> Result<(), ErrorResponse> {
+        match self.active_painting.read().await.as_ref() {
+            Some(running) if !running.is_finished() => Err(already_running(running)),
+            _ => Ok(()),
+        }
+    }
+
+    /// 描画・キャリブレーションを実行中として登録する（他の実行中があれば409）
+    ///
+    /// 確認と登録を同じロックの中で行い、同時に開始された描画が互いを上書きしないようにする。
+    pub(crate) async fn begin_painting(
+        &self,
+        control: &PaintingControl,
+    ) -> Result<(), ErrorResponse> {
+        let mut active = self.active_painting.write().await;
+        if let Some(running) = active.as_ref().filter(|running| !running.is_finished()) {
+            return Err(already_running(running));
+        }
+        *active = Some(control.clone());
+        Ok(())
+    }
+
+    /// キャリブレーションの記録にない操作でカーソルが動くため、後片付けできないようにする
+    pub(crate) async fn forget_calibration_cursor(&self) {
+        if let Some(session) = self.calibration.read().await.as_ref() {
+            session.trace.forget_cursor();
+        }
+    }
+
+    /// 実行中の描画スレッドが終わるまで最大 `timeout` 待つ（終わっていれば `true`）
+    pub async fn wait_for_painting_to_finish(&self, timeout: std::time::Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        while self.active_painting.read().await.is_some() {
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        true
+    }
+
+    /// 厳格なシミュレーションモードやアームされていない間はコントローラーを動かす操作を拒否する
+    pub(crate) fn ensure_controller_allowed(&self) -> Result<(), ErrorResponse> {
+        if self.controller_mode == (ControllerMode::Simulated { strict: true }) {
+            return Err(ErrorResponse::localized(
+                StatusCode::CONFLICT,
+                Message::new(MessageKey::StrictSimulation),
+            ));
+        }
+        if !self.interlock.is_armed() {
+            return Err(ErrorResponse::localized(
+                StatusCode::LOCKED,
+                Message::new(MessageKey::ControllerLocked),
+            ));
+        }
+        Ok(())
+    }
+
+    /// IDのアートワークを取得する（IDの形式が不正な場合も存在しないものとして扱う）
+    pub(crate) async fn find_artwork(&self, id: &str) -> Result<Option<Artwork>, ErrorResponse> {
+        let Ok(id) = ArtworkId::parse(id) else {
+            return Ok(None);
+        };
+        Ok(self.artworks.find_by_id(&id).await?)
+    }
+
+    /// 描画中のアートワークに、アートワークへ記録される前の描画済みのドットを重ねたもの
+    pub(crate) async fn artwork_in_progress(
+        &self,
+        control: &PaintingControl,
+        artwork_id: &ArtworkId,
+    ) -> Result<Option<Artwork>, ErrorResponse> {
+        let painted_dots = control
+            .completion
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .painted_dots()
+            .to_vec();
+        Ok(self
+            .find_artwork(&artwork_id.as_str())
+            .await?
+            .map(|mut artwork| {
+                artwork.mark_dots_painted(&painted_dots);
+                artwork
+            }))
+    }
+
+    /// IDのアートワークを取得し、存在しなければ404にする
+    pub(crate) async fn artwork_or_not_found(&self, id: &str) -> Result<Artwork, ErrorResponse> {
+        self.find_artwork(id).await?.ok_or_else(|| {
+            ErrorResponse::localized(
+                StatusCode::NOT_FOUND,
+                Message::new(MessageKey::ArtworkNotFound).with_arg("id", id),
+            )
+        })
+    }
+
+    /// 描画できたドットをアートワークに記録する（描画中に削除された場合は何もしない）
+    pub(crate) async fn mark_dots_painted(
+        &self,
+        id: &ArtworkId,
+        coordinates: &[Coordinates],
+    ) -> Result<(), RepositoryError> {
+        if coordinates.is_empty() {
+            return Ok(());
+        }
+        let _edit = self.artwork_edits.lock().await;
+        let Some(mut artwork) = self.artworks.find_by_id(id).await? else {
+            return Ok(());
+        };
+        let marked = artwork.mark_dots_painted(coordinates);
+        if marked > 0 {
+            artwork.record_change(
+                ArtworkChange::painted(marked),
+                EventMetadata::new("painting".to_string()),
+            );
+            self.artworks.save(&artwork).await?;
+            info!(
+                "Marked {} dots of artwork {} as painted ({:.1}% complete)",
+                marked,
+                id,
+                artwork.completion_ratio() * 100.0
+            );
+        }
+        Ok(())
+    }
+
+    /// 元ファイルのチェックサムが一致するアートワークのうち、最も古いものを取得する
+    pub(crate) async fn find_by_checksum(
+        &self,
+        checksum: &str,
+    ) -> Result<Option<Artwork>, ErrorResponse> {
+        if checksum.is_empty() {
+            return Ok(None);
+        }
+        let query = ArtworkQuery::by_checksum(checksum.to_string())
+            .with_sort(SortField::CreatedAt, SortOrder::Ascending)
+            .with_pagination(1, 0);
+        Ok(self.artworks.search(&query).await?.artworks.pop())
+    }
+
+    /// `Artwork::record_change` が返したイベントを記録する
+    pub(crate) async fn publish_change(&self, event: Option<ArtworkEvent>) {
+        if let Some(event) = event {
+            info!("{}", event.summary(self.locale));
+            self.events.push(event).await;
+        }
+    }
+
+    /// アートワークを保存し、作成イベントを記録する
+    pub(crate) async fn insert_artwork(
+        &self,
+        artwork: Artwork,
+        event_metadata: EventMetadata,
+    ) -> Result<(), ErrorResponse> {
+        let change = ArtworkChange::created(&artwork.original_format, &artwork.canvas);
+        self.insert_changed_artwork(artwork, change, event_metadata)
+            .await
+    }
+
+    /// アートワークを保存し、`change` を変更履歴の最初の記録と作成イベントにする
+    pub(crate) async fn insert_changed_artwork(
+        &self,
+        mut artwork: Artwork,
+        change: ArtworkChange,
+        event_metadata: EventMetadata,
+    ) -> Result<(), ErrorResponse> {
+        let event = artwork.record_change(change, event_metadata);
+        self.reserve_memory(&artwork)?;
+        if let Err(e) = self.artworks.save(&artwork).await {
+            self.memory_budget.release(&artwork.id);
+            return Err(e.into());
+        }
+        self.publish_change(event).await;
+        Ok(())
+    }
+}
@@ -0,0 +1,122 @@
+//! ハンドラーのテストで使う共通の準備
+//!
+//! `TestClient` は `build_router` で作ったルーターへリクエストを直接渡すため、ソケットを開かずに
+//! ルーティング・リクエストの抽出・認証・エラー応答までを含めて確認できる。
+
+use super::router::build_router;
+use super::state::ArtworkState;
+use crate::domain::artwork::entities::Artwork;
+use crate::infrastructure::hardware::mock_controller::MockController;
+use axum::Router;
+use axum::body::{Body, Bytes};
+use axum::http::{HeaderMap, Method, Request, StatusCode, header};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+/// 遅延の無いMockControllerを使い、アートワークを1件保存した状態
+pub async fn artwork_state_with(artwork: Artwork) -> Arc<ArtworkState> {
+    let state = ArtworkState::new(Arc::new(MockController::new().without_delays()));
+    state.artworks.save(&artwork).await.unwrap();
+    Arc::new(state)
+}
+
+/// `build_router` のルーターへリクエストを送るテスト用のクライアント
+pub struct TestClient {
+    router: Router,
+}
+
+impl TestClient {
+    pub fn new(state: Arc<ArtworkState>) -> Self {
+        Self {
+            router: build_router(state),
+        }
+    }
+
+    pub async fn get(&self, path: &str) -> TestResponse {
+        self.request(Method::GET, path, None).await
+    }
+
+    pub async fn post(&self, path: &str, body: serde_json::Value) -> TestResponse {
+        self.request(Method::POST, path, Some(body)).await
+    }
+
+    /// 本文の無いPOST
+    pub async fn post_empty(&self, path: &str) -> TestResponse {
+        self.request(Method::POST, path, None).await
+    }
+
+    pub async fn put(&self, path: &str, body: serde_json::Value) -> TestResponse {
+        self.request(Method::PUT, path, Some(body)).await
+    }
+
+    pub async fn patch(&self, path: &str, body: serde_json::Value) -> TestResponse {
+        self.request(Method::PATCH, path, Some(body)).await
+    }
+
+    pub async fn delete(&self, path: &str) -> TestResponse {
+        self.request(Method::DELETE, path, None).await
+    }
+
+    /// `body` があればJSONとして送る
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<serde_json::Value>,
+    ) -> TestResponse {
+        let builder = Request::builder().method(method).uri(path);
+        let request = match body {
+            Some(body) => builder
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body.to_string())),
+            None => builder.body(Body::empty()),
+        };
+        self.send(request.unwrap()).await
+    }
+
+    pub async fn send(&self, request: Request<Body>) -> TestResponse {
+        let response = self.router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        TestResponse {
+            status,
+            headers,
+            body,
+        }
+    }
+}
+
+/// ステータス・ヘッダー・本文をまとめた応答
+pub struct TestResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+impl TestResponse {
+    /// 本文をJSONとして読む（JSONでなければパニックする）
+    pub fn json(&self) -> serde_json::Value {
+        serde_json::from_slice(&self.body).unwrap_or_else(|e| {
+            panic!(
+                "{} response is not JSON ({e}): {}",
+                self.status,
+                self.text()
+            )
+        })
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
+    }
+
+    /// `ErrorResponse` の `message`
+    pub fn message(&self) -> String {
+        self.json()["message"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string()
+    }
+}
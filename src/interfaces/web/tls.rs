@@ -0,0 +1,151 @@
+use axum_server::tls_rustls::RustlsConfig;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use tracing::info;
+
+/// HTTPSの証明書設定
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TlsSettings {
+    /// 指定された証明書と秘密鍵を使用
+    Files {
+        cert_path: PathBuf,
+        key_path: PathBuf,
+    },
+    /// データディレクトリに自己署名証明書を生成して使用
+    SelfSigned,
+}
+
+#[derive(Debug, Error)]
+pub enum TlsError {
+    #[error("Failed to create TLS directory {path}: {source}")]
+    CreateDirectory {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to generate self-signed certificate: {0}")]
+    CertificateGeneration(#[from] rcgen::Error),
+    #[error("Failed to write {path}: {source}")]
+    WriteFile {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    #[error("Failed to load certificate {cert_path} / key {key_path}: {source}")]
+    Load {
+        cert_path: PathBuf,
+        key_path: PathBuf,
+        source: std::io::Error,
+    },
+}
+
+impl TlsSettings {
+    /// rustlsの設定を読み込む（自己署名の場合は必要に応じて生成）
+    pub async fn load(
+        &self,
+        data_dir: &Path,
+        subject_alt_names: &[String],
+    ) -> Result<RustlsConfig, TlsError> {
+        let (cert_path, key_path) = match self {
+            TlsSettings::Files {
+                cert_path,
+                key_path,
+            } => (cert_path.clone(), key_path.clone()),
+            TlsSettings::SelfSigned => {
+                ensure_self_signed_certificate(&data_dir.join("tls"), subject_alt_names)?
+            }
+        };
+
+        RustlsConfig::from_pem_file(&cert_path, &key_path)
+            .await
+            .map_err(|source| TlsError::Load {
+                cert_path,
+                key_path,
+                source,
+            })
+    }
+}
+
+/// データディレクトリの自己署名証明書で提供中のHTTPSの設定
+///
+/// `reset-data` で証明書のファイルが消えたら、作り直して再起動せずに読み込み直す。
+#[derive(Clone)]
+pub struct SelfSignedTls {
+    config: RustlsConfig,
+    tls_dir: PathBuf,
+    subject_alt_names: Vec<String>,
+}
+
+impl SelfSignedTls {
+    pub fn new(config: RustlsConfig, data_dir: &Path, subject_alt_names: &[String]) -> Self {
+        Self {
+            config,
+            tls_dir: data_dir.join("tls"),
+            subject_alt_names: subject_alt_names.to_vec(),
+        }
+    }
+
+    /// 証明書が無ければ生成し、提供中の設定に読み込み直す
+    pub async fn reload(&self) -> Result<(), TlsError> {
+        let (cert_path, key_path) =
+            ensure_self_signed_certificate(&self.tls_dir, &self.subject_alt_names)?;
+        self.config
+            .reload_from_pem_file(&cert_path, &key_path)
+            .await
+            .map_err(|source| TlsError::Load {
+                cert_path,
+                key_path,
+                source,
+            })
+    }
+}
+
+/// 自己署名証明書が無ければ生成し、証明書と秘密鍵のパスを返す
+fn ensure_self_signed_certificate(
+    tls_dir: &Path,
+    subject_alt_names: &[String],
+) -> Result<(PathBuf, PathBuf), TlsError> {
+    let cert_path = tls_dir.join("self-signed-cert.pem");
+    let key_path = tls_dir.join("self-signed-key.pem");
+
+    if cert_path.exists() && key_path.exists() {
+        info!(
+            "Using existing self-signed certificate: {}",
+            cert_path.display()
+        );
+        return Ok((cert_path, key_path));
+    }
+
+    if let Some(data_dir) = tls_dir.parent() {
+        crate::infrastructure::data_dir::create_data_dir(data_dir).map_err(|source| {
+            TlsError::CreateDirectory {
+                path: data_dir.to_path_buf(),
+                source,
+            }
+        })?;
+    }
+    std::fs::create_dir_all(tls_dir).map_err(|source| TlsError::CreateDirectory {
+        path: tls_dir.to_path_buf(),
+        source,
+    })?;
+
+    let certified_key = rcgen::generate_simple_self_signed(subject_alt_names.to_vec())?;
+    write_pem(&cert_path, &certified_key.cert.pem(), 0o644)?;
+    write_pem(&key_path, &certified_key.key_pair.serialize_pem(), 0o600)?;
+
+    info!(
+        "Generated self-signed certificate for {:?}: {}",
+        subject_alt_names,
+        cert_path.display()
+    );
+    Ok((cert_path, key_path))
+}
+
+fn write_pem(path: &Path, contents: &str, mode: u32) -> Result<(), TlsError> {
+    use std::io::Write;
+
+    crate::infrastructure::platform::create_with_mode(path, mode)
+        .and_then(|mut file| file.write_all(contents.as_bytes()))
+        .map_err(|source| TlsError::WriteFile {
+            path: path.to_path_buf(),
+            source,
+        })
+}
@@ -1,6 +1,14 @@
 // Application Layer
 pub mod application {
+    pub mod controller_io;
+    pub mod estimate_model;
+    pub mod metrics;
+    pub mod painting_trace;
+    pub mod progress;
+
     pub mod use_cases {
+        pub mod benchmark;
+        pub mod calibrate_controller;
         pub mod cleanup_gadget;
         pub mod cleanup_system;
         pub mod configure_usb_gadget;
@@ -8,13 +16,19 @@ pub mod application {
         pub mod fix_connection;
         pub mod fix_permissions_use_case;
         pub mod paint_artwork;
+        pub mod paint_vector;
+        pub mod reset_data;
         pub mod run_application;
+        pub mod run_painting;
+        pub mod send_controller_input;
         pub mod setup_system;
         pub mod setup_usb_gadget;
         pub mod show_system_info;
         pub mod test_controller;
 
         // Re-exports
+        pub use benchmark::*;
+        pub use calibrate_controller::*;
         pub use cleanup_gadget::*;
         pub use cleanup_system::*;
         pub use configure_usb_gadget::*;
@@ -22,7 +36,11 @@ pub mod application {
         pub use fix_connection::*;
         pub use fix_permissions_use_case::*;
         pub use paint_artwork::*;
+        pub use paint_vector::*;
+        pub use reset_data::*;
         pub use run_application::*;
+        pub use run_painting::*;
+        pub use send_controller_input::*;
         pub use setup_system::*;
         pub use setup_usb_gadget::*;
         pub use show_system_info::*;
@@ -36,7 +54,14 @@ pub mod debug;
 // Domain Layer
 pub mod domain {
     pub mod artwork {
+        pub mod canvas_diff;
+        pub mod canvas_rle;
+        pub mod change_log;
+        pub mod dot_diff;
         pub mod entities;
+        pub mod history;
+        pub mod memory_budget;
+        pub mod patterns;
         pub mod repositories;
         pub mod services;
         pub mod value_objects;
@@ -46,6 +71,7 @@ pub mod domain {
         pub mod emulator;
         pub mod entities;
         pub mod errors;
+        pub mod interlock;
         pub mod repositories;
         pub mod value_objects;
 
@@ -53,6 +79,7 @@ pub mod domain {
         pub use emulator::*;
         pub use entities::*;
         pub use errors::*;
+        pub use interlock::*;
         pub use repositories::*;
         pub use value_objects::*;
     }
@@ -62,23 +89,45 @@ pub mod domain {
     pub mod hardware {
         pub mod entities;
         pub mod errors;
+        pub mod gadget_state;
+        pub mod metrics;
         pub mod repositories;
         pub mod value_objects;
+        pub mod verification;
 
         // Re-exports
         pub use entities::*;
         pub use errors::*;
+        pub use gadget_state::*;
+        pub use metrics::*;
         pub use repositories::*;
         pub use value_objects::*;
+        pub use verification::*;
     }
 
     pub mod painting {
+        pub mod entities;
+        pub mod estimate_model;
+        pub mod fightstick;
+        pub mod init_sequence;
+        pub mod repositories;
+        pub mod sampling;
+        pub mod script_import;
         pub mod services;
         pub mod value_objects;
+        pub mod vector;
 
         // Re-exports
+        pub use entities::*;
+        pub use estimate_model::*;
+        pub use fightstick::*;
+        pub use init_sequence::*;
+        pub use repositories::*;
+        pub use sampling::*;
+        pub use script_import::*;
         pub use services::*;
         pub use value_objects::*;
+        pub use vector::*;
     }
 
     pub mod setup {
@@ -92,6 +141,7 @@ pub mod domain {
 
     pub mod shared {
         pub mod events;
+        pub mod i18n;
         pub mod value_objects;
 
         // Re-exports
@@ -110,36 +160,76 @@ pub mod infrastructure {
         pub mod linux_usb_gadget;
         pub mod linux_usb_gadget_manager;
         pub mod mock_controller;
+        pub mod orange_pi_udc;
         pub mod systemd_service;
     }
 
+    pub mod animation;
+    pub mod data_dir;
+    pub mod mdns;
+    pub mod platform;
+    pub mod safe_mode;
+
+    pub mod persistence {
+        pub mod in_memory_artwork_repository;
+        pub mod in_memory_painting_run_repository;
+        pub mod sqlite_artwork_repository;
+        pub mod sqlite_database;
+        pub mod sqlite_painting_run_repository;
+    }
+
     pub mod setup {
+        mod config_txt;
+        mod jsonl_gadget_audit_log;
         mod linux_board_detector;
         mod linux_boot_configurator;
+        mod linux_connection_repairer;
         mod linux_systemd_manager;
 
         // Re-exports
+        pub use jsonl_gadget_audit_log::*;
         pub use linux_board_detector::*;
         pub use linux_boot_configurator::*;
+        pub use linux_connection_repairer::*;
         pub use linux_systemd_manager::*;
     }
 }
 
 // Interface Layer
 pub mod interfaces {
+    pub mod doctor;
+    pub mod monitor;
+    pub mod painting_client;
     pub mod web {
-        mod artwork_handlers;
+        mod artwork_locks;
+        mod artwork_sets;
+        mod artworks;
+        mod auth;
+        mod calibration;
+        mod connection_monitor;
+        mod controller;
+        mod convert_preview;
+        mod drain;
         pub mod dto;
         pub mod embedded_assets;
         mod error_response;
+        mod etag;
+        mod gallery;
         mod handlers;
+        mod hid_debug;
         pub mod log_streamer;
         mod models;
+        mod openapi;
+        mod painting;
+        mod path_computations;
+        mod router;
+        mod scheduled_painting;
         pub mod server;
-
-        // Internal re-exports
-        pub(crate) use artwork_handlers::*;
-        pub(crate) use handlers::*;
+        mod state;
+        #[cfg(test)]
+        mod test_support;
+        mod tls;
+        mod webhooks;
     }
 }
 
@@ -1,19 +1,51 @@
 mod cli;
 
-use crate::cli::{Cli, Commands};
+use crate::cli::{
+    Cli, Commands, InitPresetArg, JsonStyle, LocaleArg, PaintingActionArg, PauseModeArg,
+    StorageMode, TestPatternArg, TlsMode, WebhookCategoryArg, WebhookSeverityArg,
+};
 use clap::Parser;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 use tracing::{error, info};
 
 use splatoon3_ghost_drawer::application::use_cases::{
-    CleanupGadgetUseCase, CleanupSystemUseCase, ConfigureUsbGadgetUseCase,
-    DiagnoseConnectionUseCase, FixConnectionUseCase, FixPermissionsUseCase, RunApplicationUseCase,
-    SetupSystemUseCase, ShowSystemInfoUseCase, TestControllerUseCase,
+    BenchmarkReport, BenchmarkUseCase, CleanupGadgetUseCase, CleanupSystemUseCase,
+    ConfigureUsbGadgetUseCase, DiagnoseConnectionUseCase, FixConnectionUseCase,
+    FixPermissionsUseCase, MAX_BENCHMARK_DURATION, RESET_DATA_CONFIRMATION, ResetDataUseCase,
+    RunApplicationUseCase, SendControllerInputUseCase, SetupSystemUseCase, ShowSystemInfoUseCase,
+    TestControllerUseCase, format_report,
+};
+use splatoon3_ghost_drawer::debug::{DEFAULT_LOG_FILTER, DebugConfig, LogLevelControl};
+use splatoon3_ghost_drawer::domain::controller::{
+    ControllerEmulator, ControllerInterlock, ManualInput, ManualInputKind,
+};
+use splatoon3_ghost_drawer::domain::events::{EventCategory, EventSeverity};
+use splatoon3_ghost_drawer::domain::hardware::{GadgetState, UsbGadgetManager};
+use splatoon3_ghost_drawer::domain::setup::entities::{
+    AuditInitiator, GadgetAuditEntry, GadgetOperation,
+};
+use splatoon3_ghost_drawer::domain::setup::repositories::{
+    BootConfigurator, ConnectionRepairer, GadgetAuditLog, SetupError, SystemdServiceManager,
 };
-use splatoon3_ghost_drawer::debug::DebugConfig;
+use splatoon3_ghost_drawer::domain::shared::i18n::Locale;
 use splatoon3_ghost_drawer::infrastructure::hardware::linux_usb_gadget_manager::LinuxUsbGadgetManager;
+use splatoon3_ghost_drawer::infrastructure::platform;
+use splatoon3_ghost_drawer::infrastructure::safe_mode::{SafeMode, SafeModeController};
 use splatoon3_ghost_drawer::infrastructure::setup::{
-    LinuxBoardDetector, LinuxBootConfigurator, LinuxSystemdManager,
+    JsonlGadgetAuditLog, LinuxBoardDetector, LinuxBootConfigurator, LinuxConnectionRepairer,
+    LinuxSystemdManager,
+};
+use splatoon3_ghost_drawer::interfaces::doctor::run_doctor;
+use splatoon3_ghost_drawer::interfaces::monitor::{MonitorSettings, run_monitor};
+use splatoon3_ghost_drawer::interfaces::painting_client::{
+    EXIT_FAILED, PaintingAction, PaintingClient, PaintingCommandOutcome,
+};
+use splatoon3_ghost_drawer::interfaces::web::server::{
+    AuthToken, ConnectionMonitorSettings, CreateArtworkRequest, DEFAULT_DATA_DIR, DEFAULT_HOST,
+    GenerateArtworkRequest, InitPreset, PauseMode, PauseSettings, ServerConfig, SleepGuardSettings,
+    StorageBackend, TestPattern, TlsSettings, TwoOptSettings, WebhookSettings, parse_webhook_url,
 };
 
 #[tokio::main]
@@ -25,16 +57,28 @@ async fn main() -> anyhow::Result<()> {
         ..DebugConfig::default()
     };
 
+    let cli = Cli::parse();
+
     // Initialize tracing subscriber with both stdout and our custom capture layer
     use splatoon3_ghost_drawer::interfaces::web::log_streamer::LogCaptureLayer;
-    use tracing_subscriber::{EnvFilter, layer::SubscriberExt, util::SubscriberInitExt};
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-    let env_filter = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info,tokio_tungstenite=warn,tungstenite=warn"));
+    // `--log-level` > `RUST_LOG` > 既定値の順に使い、実行中も変更できるようにする
+    let log_filter = cli
+        .log_level
+        .clone()
+        .or_else(|| {
+            std::env::var("RUST_LOG")
+                .ok()
+                .filter(|value| !value.is_empty())
+        })
+        .unwrap_or_else(|| DEFAULT_LOG_FILTER.to_string());
+    let (filter_layer, log_level_control) = LogLevelControl::new(&log_filter)?;
 
+    // ログは標準エラーに出す（`generate` などの標準出力をパイプで使えるように）
     tracing_subscriber::registry()
-        .with(tracing_subscriber::fmt::layer())
-        .with(env_filter)
+        .with(filter_layer)
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .with(LogCaptureLayer)
         .init();
 
@@ -46,21 +90,51 @@ async fn main() -> anyhow::Result<()> {
         env!("BUILD_TIMESTAMP")
     );
 
-    let cli = Cli::parse();
+    // セーフモードはすべてのコマンドの出力の先頭で知らせる（標準出力のJSONを崩さないよう標準エラーに出す）
+    let safe_mode = cli.safe_mode;
+    if safe_mode {
+        eprintln!(
+            "🛡️  Safe mode: nothing is written to the gadget, boot files, systemd or HID device"
+        );
+    }
 
     // Dependency injection
     let board_detector = Arc::new(LinuxBoardDetector::new());
-    let boot_configurator = Arc::new(LinuxBootConfigurator::new());
-    let systemd_manager = Arc::new(LinuxSystemdManager::new());
-    let usb_gadget_manager = Arc::new(LinuxUsbGadgetManager::new());
+    let mut boot_configurator: Arc<dyn BootConfigurator> = Arc::new(LinuxBootConfigurator::new());
+    let mut systemd_manager: Arc<dyn SystemdServiceManager> = Arc::new(LinuxSystemdManager::new());
+    // ガジェットの状態を変える操作は、実行した経路とともに監査ログに残す
+    let audit_log: Arc<dyn GadgetAuditLog> = Arc::new(JsonlGadgetAuditLog::new(DEFAULT_DATA_DIR));
+    let usb_gadget_manager = Arc::new(
+        LinuxUsbGadgetManager::new()
+            .with_board_detector(board_detector.clone())
+            .with_audit_log(audit_log.clone(), AuditInitiator::Cli),
+    );
+    if safe_mode {
+        boot_configurator = Arc::new(SafeMode::new(boot_configurator));
+        systemd_manager = Arc::new(SafeMode::new(systemd_manager));
+    }
 
     match cli.command {
         Commands::Setup { force } => {
             info!("Executing setup command...");
-            let use_case =
+            let mut use_case =
                 SetupSystemUseCase::new(board_detector, boot_configurator, systemd_manager);
+            if safe_mode {
+                use_case = use_case.with_safe_mode();
+            }
 
-            match use_case.execute(force) {
+            let result = use_case.execute(force);
+            let mut entry =
+                GadgetAuditEntry::new(GadgetOperation::Setup, AuditInitiator::Cli, &result)
+                    .with_parameter("force", force.to_string());
+            if safe_mode {
+                entry = entry.with_parameter("safe_mode", "true");
+            }
+            audit_log.record(entry);
+            match result {
+                Ok(_) if safe_mode => {
+                    println!("✅ System setup finished in safe mode; nothing was changed.");
+                }
                 Ok(_) => {
                     println!("✅ System setup completed successfully!");
                     println!("⚠️  Please reboot your device for the changes to take effect.");
@@ -73,11 +147,162 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
-        Commands::Run { port, host } => {
+        Commands::Run {
+            port,
+            host,
+            tls,
+            tls_cert,
+            tls_key,
+            data_dir,
+            simulate,
+            strict_simulation,
+            no_auth,
+            debug,
+            pause_mode,
+            rehome_on_resume,
+            host_grace_ms,
+            auto_resume,
+            keepalive_idle_ms,
+            max_uninterrupted_minutes,
+            strict_sleep_guard,
+            storage,
+            two_opt_budget_ms,
+            artwork_memory_budget_mb,
+            trash_retention_days,
+            keep_dot_timestamps,
+            init_preset,
+            preflight,
+            locale,
+            assets_dir,
+            no_gallery,
+            no_connection_monitor,
+            connection_monitor_interval_ms,
+            no_mdns,
+            mdns_hostname,
+            webhook_urls,
+            webhook_min_severity,
+            webhook_categories,
+            drain,
+            drain_grace_secs,
+            controller_init_window_secs,
+        } => {
             info!("Starting application...");
             let use_case = RunApplicationUseCase::new();
 
-            match use_case.execute(host, port).await {
+            let mut config = match host {
+                Some(host) => ServerConfig::new(host, port),
+                None => ServerConfig::new(DEFAULT_HOST, port).with_dual_stack(),
+            }
+            .with_data_dir(data_dir);
+            if let (Some(cert_path), Some(key_path)) = (tls_cert, tls_key) {
+                config = config.with_tls(TlsSettings::Files {
+                    cert_path,
+                    key_path,
+                });
+            } else if tls == Some(TlsMode::SelfSigned) {
+                config = config.with_tls(TlsSettings::SelfSigned);
+            }
+            if simulate {
+                config = config.with_simulation(strict_simulation);
+            }
+            if safe_mode {
+                config = config.with_safe_mode();
+            }
+            if no_auth {
+                config = config.without_auth();
+            }
+            if debug {
+                config = config.with_debug_endpoints();
+            }
+            config = config.with_pause_settings(PauseSettings {
+                mode: match pause_mode {
+                    PauseModeArg::Immediate => PauseMode::Immediate,
+                    PauseModeArg::Safe => PauseMode::Safe,
+                },
+                rehome_on_resume,
+                host_grace_ms,
+                auto_resume,
+                keepalive_idle_ms: keepalive_idle_ms.filter(|idle_ms| *idle_ms > 0),
+            });
+            config = config.with_sleep_guard(SleepGuardSettings {
+                max_uninterrupted_minutes,
+                strict: strict_sleep_guard,
+            });
+            config = config.with_storage(match storage {
+                StorageMode::Memory => StorageBackend::Memory,
+                StorageMode::Sqlite => StorageBackend::Sqlite,
+            });
+            config = config.with_two_opt_settings(
+                TwoOptSettings::default().with_time_budget_ms(two_opt_budget_ms),
+            );
+            config = config
+                .with_artwork_memory_budget(artwork_memory_budget_mb.saturating_mul(1024 * 1024));
+            config = config.with_trash_retention(Duration::from_secs(
+                trash_retention_days.saturating_mul(24 * 60 * 60),
+            ));
+            if keep_dot_timestamps {
+                config = config.with_dot_timestamps();
+            }
+            config = config.with_init_preset(match init_preset {
+                InitPresetArg::Splatoon3PostEditor => InitPreset::Splatoon3PostEditor,
+                InitPresetArg::None => InitPreset::None,
+            });
+            if preflight {
+                config = config.with_preflight();
+            }
+            config = config.with_locale(match locale {
+                LocaleArg::Ja => Locale::Ja,
+                LocaleArg::En => Locale::En,
+            });
+            if let Some(assets_dir) = assets_dir {
+                config = config.with_assets_dir(assets_dir);
+            }
+            config = config.with_log_level_control(log_level_control);
+            if no_gallery {
+                config = config.without_gallery();
+            }
+            config = config.with_connection_monitor(if no_connection_monitor {
+                ConnectionMonitorSettings::disabled()
+            } else {
+                ConnectionMonitorSettings::default()
+                    .with_interval(Duration::from_millis(connection_monitor_interval_ms))
+            });
+            config = if no_mdns {
+                config.without_mdns()
+            } else {
+                config.with_mdns_hostname(mdns_hostname)
+            };
+            if let Some(error) = webhook_urls
+                .iter()
+                .find_map(|url| parse_webhook_url(url).err())
+            {
+                eprintln!("❌ --webhook-url: {error}");
+                std::process::exit(2);
+            }
+            let mut webhooks = WebhookSettings::new(webhook_urls).with_categories(
+                webhook_categories
+                    .into_iter()
+                    .map(|category| match category {
+                        WebhookCategoryArg::Artwork => EventCategory::Artwork,
+                        WebhookCategoryArg::Painting => EventCategory::Painting,
+                    })
+                    .collect(),
+            );
+            if let Some(min_severity) = webhook_min_severity {
+                webhooks = webhooks.with_min_severity(match min_severity {
+                    WebhookSeverityArg::Info => EventSeverity::Info,
+                    WebhookSeverityArg::Warning => EventSeverity::Warning,
+                    WebhookSeverityArg::Error => EventSeverity::Error,
+                });
+            }
+            config = config.with_webhooks(webhooks);
+            if drain {
+                config = config.with_drain(Duration::from_secs(drain_grace_secs));
+            }
+            config = config
+                .with_controller_init_window(Duration::from_secs(controller_init_window_secs));
+
+            match use_case.execute(config).await {
                 Ok(_) => {
                     info!("Application terminated normally");
                 }
@@ -88,12 +313,82 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
+        Commands::Generate {
+            pattern,
+            width,
+            height,
+            cell_size,
+            thickness,
+            text,
+            scale,
+            name,
+            server,
+            token,
+            data_dir,
+        } => {
+            let request = GenerateArtworkRequest {
+                width,
+                height,
+                cell_size,
+                thickness,
+                text,
+                scale,
+                name,
+                ..GenerateArtworkRequest::new(match pattern {
+                    TestPatternArg::Checkerboard => TestPattern::Checkerboard,
+                    TestPatternArg::Border => TestPattern::Border,
+                    TestPatternArg::Crosshair => TestPattern::Crosshair,
+                    TestPatternArg::Diagonal => TestPattern::Diagonal,
+                    TestPatternArg::Text => TestPattern::Text,
+                })
+            };
+
+            let Some(server) = server else {
+                match request.generate_canvas() {
+                    Ok(canvas) => {
+                        let artwork =
+                            CreateArtworkRequest::from_canvas(request.artwork_name(), &canvas);
+                        println!("{}", serde_json::to_string_pretty(&artwork)?);
+                    }
+                    Err(e) => {
+                        eprintln!("❌ {e}");
+                        std::process::exit(1);
+                    }
+                }
+                return Ok(());
+            };
+
+            // 変更系APIなのでアクセストークンを付ける（--no-auth のサーバーでは不要）
+            let token = token.or_else(|| {
+                AuthToken::load(&data_dir)
+                    .ok()
+                    .flatten()
+                    .map(|token| token.as_str().to_string())
+            });
+            let body = serde_json::to_string(&request)?;
+            match post_json(&server, "/api/artworks/generate", token.as_deref(), &body).await {
+                Ok((200, response)) => println!("{response}"),
+                Ok((status, response)) => {
+                    eprintln!("❌ Server returned {status}: {response}");
+                    std::process::exit(1);
+                }
+                Err(e) => {
+                    eprintln!("❌ Failed to reach {server}: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
         Commands::Cleanup { gadget_only } => {
             info!("Executing cleanup command (gadget_only: {})", gadget_only);
+            // 後片付けはconfigfsやユニットファイルを直接消すため、ログに出すだけでは済ませられない
+            if safe_mode {
+                refuse_in_safe_mode("cleaning up the USB gadget and system configuration");
+            }
 
             if gadget_only {
                 // USB Gadgetのみクリーンアップ
-                let use_case = CleanupGadgetUseCase::new();
+                let use_case = CleanupGadgetUseCase::new()
+                    .with_audit_log(audit_log.clone(), AuditInitiator::Cli);
                 match use_case.execute() {
                     Ok(_) => {
                         println!("✅ USB Gadget cleanup completed successfully!");
@@ -109,7 +404,13 @@ async fn main() -> anyhow::Result<()> {
                 let use_case =
                     CleanupSystemUseCase::new(board_detector, boot_configurator, systemd_manager);
 
-                match use_case.execute() {
+                let result = use_case.execute();
+                audit_log.record(GadgetAuditEntry::new(
+                    GadgetOperation::CleanupSystem,
+                    AuditInitiator::Cli,
+                    &result,
+                ));
+                match result {
                     Ok(_) => {
                         println!("✅ System cleanup completed successfully!");
                         println!("⚠️  Please reboot your device for the changes to take effect.");
@@ -123,12 +424,276 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
-        Commands::Info { verbose } => {
+        Commands::ResetData {
+            data_dir,
+            confirm,
+            server,
+            token,
+        } => {
+            let use_case = ResetDataUseCase::new(&data_dir);
+            if confirm.as_deref() != Some(RESET_DATA_CONFIRMATION) {
+                match use_case.entries() {
+                    Ok(entries) if entries.is_empty() => {
+                        println!("ℹ️  {} has no application data", data_dir.display());
+                    }
+                    Ok(entries) => {
+                        println!(
+                            "The following entries in {} would be removed:",
+                            data_dir.display()
+                        );
+                        for entry in entries {
+                            println!("   {entry}");
+                        }
+                    }
+                    Err(e) => {
+                        eprintln!("❌ {e}");
+                        std::process::exit(1);
+                    }
+                }
+                println!("\nRun again with --confirm {RESET_DATA_CONFIRMATION} to delete them.");
+                if confirm.is_some() {
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+
+            if let Some(server) = server {
+                let token = token.or_else(|| {
+                    AuthToken::load(&data_dir)
+                        .ok()
+                        .flatten()
+                        .map(|token| token.as_str().to_string())
+                });
+                let body = serde_json::json!({ "confirm": RESET_DATA_CONFIRMATION }).to_string();
+                match post_json(&server, "/api/system/reset-data", token.as_deref(), &body).await {
+                    Ok((200, response)) => println!("{response}"),
+                    Ok((status, response)) => {
+                        eprintln!("❌ Server returned {status}: {response}");
+                        std::process::exit(1);
+                    }
+                    Err(e) => {
+                        eprintln!("❌ Failed to reach {server}: {e}");
+                        std::process::exit(1);
+                    }
+                }
+                return Ok(());
+            }
+
+            // 起動中のサーバーはデータベースを開いたままなので、ファイルを直接消さずにAPIを使わせる
+            if web_service_is_active() {
+                eprintln!(
+                    "❌ The web server (splatoon3-ghost-drawer.service) is running. \
+                     Stop it first or pass --server to reset through it."
+                );
+                std::process::exit(1);
+            }
+            match use_case.execute() {
+                Ok(report) => {
+                    for entry in &report.removed {
+                        println!("   🗑️  {}", entry.path);
+                    }
+                    println!(
+                        "✅ Removed {} entries ({} bytes) from {}",
+                        report.removed.len(),
+                        report.bytes_removed,
+                        report.data_dir
+                    );
+                }
+                Err(e) => {
+                    error!("Data reset failed: {}", e);
+                    eprintln!("❌ Data reset failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Benchmark {
+            data_dir,
+            simulate,
+            server,
+            token,
+            json,
+        } => {
+            let report = if let Some(server) = server {
+                let token = token.or_else(|| {
+                    AuthToken::load(&data_dir)
+                        .ok()
+                        .flatten()
+                        .map(|token| token.as_str().to_string())
+                });
+                match post_json(&server, "/api/system/benchmark", token.as_deref(), "{}").await {
+                    Ok((200, response)) => serde_json::from_str::<BenchmarkReport>(&response)?,
+                    Ok((status, response)) => {
+                        eprintln!("❌ Server returned {status}: {response}");
+                        std::process::exit(1);
+                    }
+                    Err(e) => {
+                        eprintln!("❌ Failed to reach {server}: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            } else {
+                // 起動中のサーバーがHIDデバイスへ書き込むため、APIを通して描画と重ならないようにさせる
+                if web_service_is_active() {
+                    eprintln!(
+                        "❌ The web server (splatoon3-ghost-drawer.service) is running. \
+                         Pass --server to run the benchmark through it."
+                    );
+                    std::process::exit(1);
+                }
+                let mut use_case = BenchmarkUseCase::new(&data_dir);
+                if simulate || safe_mode {
+                    use_case = use_case.simulated();
+                }
+                if json.is_none() {
+                    println!(
+                        "⏱️  Running the benchmark (up to {}s)...",
+                        MAX_BENCHMARK_DURATION.as_secs()
+                    );
+                }
+                match tokio::task::spawn_blocking(move || use_case.execute()).await? {
+                    Ok(report) => report,
+                    Err(e) => {
+                        error!("Benchmark failed: {}", e);
+                        eprintln!("❌ Benchmark failed: {e}");
+                        std::process::exit(1);
+                    }
+                }
+            };
+            match json {
+                Some(style) => print_json(&report, style)?,
+                None => print_benchmark(&report),
+            }
+        }
+        Commands::Doctor { no_timing } => {
+            println!(
+                "{} v{} self-test",
+                env!("CARGO_PKG_NAME"),
+                env!("CARGO_PKG_VERSION")
+            );
+            let report = run_doctor().await;
+            for line in report.table_lines(!no_timing) {
+                println!("{line}");
+            }
+            std::process::exit(report.exit_code());
+        }
+        Commands::Monitor { host, port } => {
+            run_monitor(MonitorSettings::new(host, port)).await?;
+        }
+        Commands::Painting {
+            action,
+            host,
+            port,
+            token,
+            data_dir,
+            json,
+        } => {
+            let token = token.or_else(|| {
+                AuthToken::load(&data_dir)
+                    .ok()
+                    .flatten()
+                    .map(|token| token.as_str().to_string())
+            });
+            let client = PaintingClient::new(MonitorSettings::new(host, port).base_url(), token);
+            let action = match action {
+                PaintingActionArg::Status => PaintingAction::Status,
+                PaintingActionArg::Pause => PaintingAction::Pause,
+                PaintingActionArg::Resume => PaintingAction::Resume,
+                PaintingActionArg::Stop => PaintingAction::Stop,
+            };
+            let outcome = match client.run(action).await {
+                Ok(outcome) => outcome,
+                Err(e) => {
+                    eprintln!("❌ {e}");
+                    std::process::exit(EXIT_FAILED);
+                }
+            };
+            match (json, &outcome) {
+                (Some(style), PaintingCommandOutcome::Status { status, .. }) => {
+                    print_json(status, style)?
+                }
+                (Some(style), PaintingCommandOutcome::Signal(response)) => {
+                    print_json(response, style)?
+                }
+                (None, PaintingCommandOutcome::Signal(_)) if !outcome.affected() => {
+                    for line in outcome.summary_lines() {
+                        eprintln!("❌ {line}");
+                    }
+                }
+                (None, _) => {
+                    for line in outcome.summary_lines() {
+                        println!("{line}");
+                    }
+                }
+            }
+            if outcome.exit_code() != 0 {
+                std::process::exit(outcome.exit_code());
+            }
+        }
+        Commands::Info {
+            data_dir,
+            json,
+            audit: Some(limit),
+            ..
+        } => match JsonlGadgetAuditLog::new(&data_dir).recent(limit) {
+            Ok(entries) => match json {
+                Some(style) => print_json(&entries, style)?,
+                None => print_audit_entries(&entries),
+            },
+            Err(e) => {
+                error!("Failed to read the audit log: {}", e);
+                eprintln!("❌ Failed to read the audit log: {e}");
+                std::process::exit(1);
+            }
+        },
+        Commands::Info {
+            json, gadget: true, ..
+        } => match usb_gadget_manager.read_gadget_state() {
+            Ok(state) => match json {
+                Some(style) => print_json(&state, style)?,
+                None => print_gadget_state(&state),
+            },
+            Err(e) => {
+                error!("Failed to read the gadget state: {}", e);
+                eprintln!("❌ Failed to read the gadget state: {e}");
+                std::process::exit(1);
+            }
+        },
+        Commands::Info {
+            verbose,
+            data_dir,
+            json: Some(style),
+            ..
+        } => {
+            let use_case = ShowSystemInfoUseCase::new(board_detector, usb_gadget_manager);
+            match use_case.collect(verbose) {
+                Ok(mut report) => {
+                    report.access_token_generated =
+                        AuthToken::load(&data_dir).ok().map(|token| token.is_some());
+                    print_json(&report, style)?;
+                }
+                Err(e) => {
+                    error!("Failed to collect system info: {}", e);
+                    eprintln!("❌ Failed to collect system info: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Info {
+            verbose, data_dir, ..
+        } => {
             info!("Showing system information...");
             let use_case = ShowSystemInfoUseCase::new(board_detector, usb_gadget_manager);
 
             match use_case.execute(verbose) {
                 Ok(_) => {
+                    println!("\n🔑 Web Access Token:");
+                    match AuthToken::load(&data_dir) {
+                        Ok(Some(token)) => println!("   {}", token.as_str()),
+                        Ok(None) => {
+                            println!("   Not generated yet (created on the first `run`)")
+                        }
+                        Err(e) => println!("   ❌ {e}"),
+                    }
                     info!("System information displayed successfully");
                 }
                 Err(e) => {
@@ -142,20 +707,46 @@ async fn main() -> anyhow::Result<()> {
             info!("Starting controller test...");
 
             // Check if we have proper permissions
-            if !nix::unistd::Uid::effective().is_root() {
+            if !safe_mode && !platform::is_root() {
                 eprintln!("❌ Error: This command requires root privileges.");
                 eprintln!("   Please run with sudo: sudo splatoon3-ghost-drawer test");
                 std::process::exit(1);
             }
 
             // Create controller emulator
-            use splatoon3_ghost_drawer::infrastructure::hardware::linux_hid_controller::LinuxHidController;
-            let controller = Arc::new(LinuxHidController::new());
-            let use_case = TestControllerUseCase::new(controller);
+            let controller = hid_controller(safe_mode);
+            // テストは明示的な実行なので暗黙にアームする（WebUIでは /api/controller/arm が必要）
+            let interlock = ControllerInterlock::new();
+            interlock.arm("cli test command", None);
+            println!("🔓 Controller armed for this test (inputs will be sent to the Switch)");
+            // Ctrl-Cでは操作の区切りで止め、ニュートラルのレポートを送ってから終了する
+            let cancel = Arc::new(AtomicBool::new(false));
+            let interrupt = cancel.clone();
+            tokio::spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    println!("\n⏹️  Interrupted, releasing all inputs...");
+                    interrupt.store(true, Ordering::SeqCst);
+                }
+            });
+            let use_case = TestControllerUseCase::new(controller)
+                .with_interlock(interlock)
+                .with_cancel_signal(cancel);
 
             match use_case.execute(duration, &mode).await {
-                Ok(_) => {
-                    println!("✅ Controller test completed successfully!");
+                Ok(report) if report.interrupted => {
+                    println!(
+                        "⏹️  Controller test interrupted after {} commands ({:.1}s)",
+                        report.commands_sent,
+                        report.elapsed.as_secs_f64()
+                    );
+                    std::process::exit(130);
+                }
+                Ok(report) => {
+                    println!(
+                        "✅ Controller test completed successfully! ({} commands, {:.1}s)",
+                        report.commands_sent,
+                        report.elapsed.as_secs_f64()
+                    );
                 }
                 Err(e) => {
                     error!("Controller test failed: {}", e);
@@ -164,25 +755,87 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
-        Commands::Diagnose => {
+        Commands::Input {
+            button,
+            dpad,
+            stick,
+            duration,
+        } => {
+            // Check if we have proper permissions
+            if !safe_mode && !platform::is_root() {
+                eprintln!("❌ Error: This command requires root privileges.");
+                eprintln!("   Please run with sudo: sudo splatoon3-ghost-drawer input");
+                std::process::exit(1);
+            }
+
+            let (kind, value) = match (button, dpad, stick) {
+                (Some(button), _, _) => (ManualInputKind::Button, button),
+                (_, Some(dpad), _) => (ManualInputKind::Dpad, dpad),
+                (_, _, Some(stick)) => (ManualInputKind::Stick, stick),
+                _ => unreachable!("clap requires one of --button, --dpad or --stick"),
+            };
+            let input = match ManualInput::parse(kind, &value) {
+                Ok(input) => input,
+                Err(e) => {
+                    eprintln!("❌ {e}");
+                    std::process::exit(1);
+                }
+            };
+
+            let controller = hid_controller(safe_mode);
+            let use_case = SendControllerInputUseCase::new(controller.clone());
+            match controller
+                .initialize()
+                .and_then(|_| use_case.execute(input, duration))
+            {
+                Ok(report) => {
+                    println!("✅ Sent {value} for {}ms", report.duration_ms);
+                    for bytes in &report.reports {
+                        println!("   Report: {}", format_report(bytes));
+                    }
+                }
+                Err(e) => {
+                    error!("Manual input failed: {}", e);
+                    eprintln!("❌ Manual input failed: {e}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Diagnose { json } => {
             info!("Running connection diagnostics...");
 
             // Check if we have proper permissions
-            if !nix::unistd::Uid::effective().is_root() {
+            if !platform::is_root() {
                 eprintln!("❌ Error: This command requires root privileges.");
                 eprintln!("   Please run with sudo: sudo splatoon3-ghost-drawer diagnose");
                 std::process::exit(1);
             }
 
-            let use_case = DiagnoseConnectionUseCase::new();
-            match use_case.execute() {
-                Ok(_) => {
-                    info!("Diagnostics completed");
+            let use_case = DiagnoseConnectionUseCase::new(board_detector);
+            if let Some(style) = json {
+                match use_case.collect() {
+                    Ok(report) => {
+                        print_json(&report, style)?;
+                        if !report.healthy {
+                            std::process::exit(1);
+                        }
+                    }
+                    Err(e) => {
+                        error!("Diagnostics failed: {}", e);
+                        eprintln!("❌ Diagnostics failed: {e}");
+                        std::process::exit(1);
+                    }
                 }
-                Err(e) => {
-                    error!("Diagnostics failed: {}", e);
-                    eprintln!("❌ Diagnostics failed: {e}");
-                    std::process::exit(1);
+            } else {
+                match use_case.execute() {
+                    Ok(_) => {
+                        info!("Diagnostics completed");
+                    }
+                    Err(e) => {
+                        error!("Diagnostics failed: {}", e);
+                        eprintln!("❌ Diagnostics failed: {e}");
+                        std::process::exit(1);
+                    }
                 }
             }
         }
@@ -190,13 +843,19 @@ async fn main() -> anyhow::Result<()> {
             info!("Fixing USB connection...");
 
             // Check if we have proper permissions
-            if !nix::unistd::Uid::effective().is_root() {
+            if !platform::is_root() {
                 eprintln!("❌ Error: This command requires root privileges.");
                 eprintln!("   Please run with sudo: sudo splatoon3-ghost-drawer fix-connection");
                 std::process::exit(1);
             }
 
-            let use_case = FixConnectionUseCase::new(usb_gadget_manager.clone());
+            let mut repairer: Arc<dyn ConnectionRepairer> =
+                Arc::new(LinuxConnectionRepairer::new(usb_gadget_manager.clone()));
+            if safe_mode {
+                repairer = Arc::new(SafeMode::new(repairer));
+            }
+            let use_case = FixConnectionUseCase::new(repairer)
+                .with_audit_log(audit_log.clone(), AuditInitiator::Cli);
             match use_case.execute() {
                 Ok(_) => {
                     println!("✅ Connection fix completed!");
@@ -210,15 +869,19 @@ async fn main() -> anyhow::Result<()> {
         }
         Commands::FixPermissions => {
             info!("Fixing HID device permissions...");
+            if safe_mode {
+                refuse_in_safe_mode("changing the permissions of the HID device");
+            }
 
             // Check if we have proper permissions
-            if !nix::unistd::Uid::effective().is_root() {
+            if !platform::is_root() {
                 eprintln!("❌ Error: This command requires root privileges.");
                 eprintln!("   Please run with sudo: sudo splatoon3-ghost-drawer fix-permissions");
                 std::process::exit(1);
             }
 
-            let use_case = FixPermissionsUseCase::new(usb_gadget_manager.clone());
+            let use_case = FixPermissionsUseCase::new(usb_gadget_manager.clone())
+                .with_audit_log(audit_log.clone(), AuditInitiator::Cli);
             match use_case.execute() {
                 Ok(_) => {
                     println!("✅ Permissions fix completed!");
@@ -230,16 +893,43 @@ async fn main() -> anyhow::Result<()> {
                 }
             }
         }
-        Commands::InternalConfigureGadget => {
+        Commands::InternalConfigureGadget {
+            udc,
+            device_timeout_secs,
+            wait_udc_secs,
+        } => {
             info!("Configuring USB gadget...");
+            let mut usb_gadget_manager: Arc<dyn UsbGadgetManager> = Arc::new(
+                LinuxUsbGadgetManager::new()
+                    .with_board_detector(board_detector)
+                    .with_udc(udc)
+                    .with_device_node_timeout(Duration::from_secs(device_timeout_secs))
+                    .with_udc_wait(Duration::from_secs(wait_udc_secs))
+                    .with_audit_log(audit_log, AuditInitiator::Systemd),
+            );
+            if safe_mode {
+                usb_gadget_manager = Arc::new(SafeMode::new(usb_gadget_manager));
+            }
             let use_case = ConfigureUsbGadgetUseCase::new(usb_gadget_manager);
 
             match use_case.execute() {
-                Ok(_) => {
+                Ok(verification) => {
                     info!("USB gadget configured successfully");
+                    println!("🔍 Gadget verification:");
+                    for check in &verification.checks {
+                        println!("   {check}");
+                    }
+                    if !verification.is_ok() {
+                        eprintln!("❌ USB gadget verification failed");
+                        std::process::exit(1);
+                    }
                 }
                 Err(e) => {
-                    error!("USB gadget configuration failed: {}", e);
+                    if let SetupError::UdcUnavailable { reason, .. } = &e {
+                        error!(udc_unavailable = %reason, "USB gadget configuration failed: {}", e);
+                    } else {
+                        error!("USB gadget configuration failed: {}", e);
+                    }
                     eprintln!("❌ USB gadget configuration failed: {e}");
                     std::process::exit(1);
                 }
@@ -249,3 +939,203 @@ async fn main() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// HIDデバイスに書き込むコントローラー（セーフモードではデバイスを開かず、送る内容をログに出す）
+fn hid_controller(safe_mode: bool) -> Arc<dyn ControllerEmulator> {
+    use splatoon3_ghost_drawer::infrastructure::hardware::linux_hid_controller::LinuxHidController;
+
+    if safe_mode {
+        Arc::new(SafeModeController::new())
+    } else {
+        Arc::new(LinuxHidController::new())
+    }
+}
+
+/// セーフモードでは実行できないコマンドを終了する
+fn refuse_in_safe_mode(operation: &str) -> ! {
+    let error = SetupError::SafeModeBlocked(operation.to_string());
+    error!("{}", error);
+    eprintln!("❌ {error}");
+    eprintln!("   Run without --safe-mode (or SPLATOON3_SAFE_MODE) to make this change.");
+    std::process::exit(1);
+}
+
+/// ベンチマークの結果を表示する
+fn print_benchmark(report: &BenchmarkReport) {
+    let writes = &report.report_writes;
+    let report_loop = &report.report_loop;
+    let path = &report.path_generation;
+    let timing = &report.suggested_timing;
+    println!(
+        "⏱️  Benchmark of {}{} ({}ms{})",
+        report.target,
+        if report.simulated { " (simulated)" } else { "" },
+        report.duration_ms,
+        if report.truncated { ", truncated" } else { "" }
+    );
+    println!(
+        "   Report writes: {}/{} written, {:.0} reports/s, latency p50 {}us / p99 {}us / max {}us",
+        writes.written,
+        writes.attempted,
+        writes.reports_per_second,
+        writes.latency.p50_us,
+        writes.latency.p99_us,
+        writes.latency.max_us
+    );
+    println!(
+        "   {}ms report loop under load: {} ticks, mean interval {}us, jitter p99 {}us / max {}us",
+        report_loop.interval_ms,
+        report_loop.ticks,
+        report_loop.mean_interval_us,
+        report_loop.jitter.p99_us,
+        report_loop.jitter.max_us
+    );
+    println!(
+        "   Path generation: {} dots in {}ms ({:.0} dots/s)",
+        path.dots, path.elapsed_ms, path.dots_per_second
+    );
+    println!(
+        "✅ Suggested minimum timing: press {}ms, release {}ms, wait {}ms",
+        timing.press_ms, timing.release_ms, timing.wait_ms
+    );
+}
+
+/// 監査ログを1件1行で表示する
+fn print_audit_entries(entries: &[GadgetAuditEntry]) {
+    println!("📜 Gadget audit log ({} entries):", entries.len());
+    for entry in entries {
+        let mark = if entry.success { "✅" } else { "❌" };
+        let mut line = format!(
+            "   {mark} {} {} by {}",
+            entry.timestamp, entry.operation, entry.initiator
+        );
+        for (key, value) in &entry.parameters {
+            line.push_str(&format!(" {key}={value}"));
+        }
+        if let Some(error) = &entry.error {
+            line.push_str(&format!(": {error}"));
+        }
+        println!("{line}");
+    }
+}
+
+fn print_gadget_state(state: &GadgetState) {
+    println!("🔌 USB gadget ({}):", state.gadget_path);
+    if !state.configured {
+        println!("   Not configured");
+        return;
+    }
+    let show = |label: &str, value: Option<String>| {
+        println!("   {label:<18} {}", value.as_deref().unwrap_or("-"));
+    };
+    show("idVendor", state.id_vendor.clone());
+    show("idProduct", state.id_product.clone());
+    show("bcdDevice", state.bcd_device.clone());
+    show("bcdUSB", state.bcd_usb.clone());
+    show("Manufacturer", state.manufacturer.clone());
+    show("Product", state.product.clone());
+    show("Serial number", state.serial_number.clone());
+    show("Configuration", state.configuration.clone());
+    show("MaxPower (mA)", state.max_power_ma.map(|ma| ma.to_string()));
+    show(
+        "report_length",
+        state.report_length.map(|len| len.to_string()),
+    );
+    show("UDC", state.udc.clone());
+    show("UDC state", state.udc_state.clone());
+    match &state.report_descriptor {
+        Some(descriptor) => {
+            let mark = if descriptor.matches_expected {
+                "✅"
+            } else {
+                "❌"
+            };
+            println!(
+                "   Report descriptor  {} bytes {mark} (matches expected: {})",
+                descriptor.length, descriptor.matches_expected
+            );
+            println!("      hex:    {}", descriptor.hex);
+            println!("      base64: {}", descriptor.base64);
+        }
+        None => println!("   Report descriptor  -"),
+    }
+    if state.device_nodes.is_empty() {
+        println!("   Device nodes       none");
+    }
+    for node in &state.device_nodes {
+        println!(
+            "   {} {} dev={} mode={} owner={}:{}",
+            if node.char_device { "✅" } else { "❌" },
+            node.path,
+            node.device_number.as_deref().unwrap_or("-"),
+            node.mode.as_deref().unwrap_or("-"),
+            node.uid.map_or("-".to_string(), |uid| uid.to_string()),
+            node.gid.map_or("-".to_string(), |gid| gid.to_string()),
+        );
+    }
+}
+
+/// Webサーバーのサービスが起動中か（systemdが無い環境では起動していないとみなす）
+fn web_service_is_active() -> bool {
+    std::process::Command::new("systemctl")
+        .args(["is-active", "--quiet", "splatoon3-ghost-drawer.service"])
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// `--json` の出力を標準出力に書く
+fn print_json(value: &impl serde::Serialize, style: JsonStyle) -> anyhow::Result<()> {
+    let json = match style {
+        JsonStyle::Pretty => serde_json::to_string_pretty(value)?,
+        JsonStyle::Compact => serde_json::to_string(value)?,
+    };
+    println!("{json}");
+    Ok(())
+}
+
+/// 起動中のサーバーにJSONをPOSTし、ステータスコードと本文を返す（HTTPのみ対応）
+async fn post_json(
+    server: &str,
+    path: &str,
+    token: Option<&str>,
+    body: &str,
+) -> anyhow::Result<(u16, String)> {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let authority = match server.strip_prefix("http://") {
+        Some(rest) => rest.split('/').next().unwrap_or(rest),
+        None if server.starts_with("https://") => {
+            anyhow::bail!("HTTPS servers are not supported; use the HTTP address")
+        }
+        None => server,
+    };
+    let address = if authority.contains(':') {
+        authority.to_string()
+    } else {
+        format!("{authority}:80")
+    };
+
+    let mut stream = tokio::net::TcpStream::connect(&address).await?;
+    let authorization = token
+        .map(|token| format!("Authorization: Bearer {token}\r\n"))
+        .unwrap_or_default();
+    let request = format!(
+        "POST {path} HTTP/1.1\r\nHost: {authority}\r\nContent-Type: application/json\r\n\
+         Content-Length: {}\r\n{authorization}Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).await?;
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await?;
+    let (head, body) = response
+        .split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow::anyhow!("Malformed HTTP response"))?;
+    let status = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| anyhow::anyhow!("Malformed HTTP status line"))?;
+    Ok((status, body.to_string()))
+}
@@ -0,0 +1,32 @@
+//! `splatoon3-ghost-drawer doctor` の自己診断をビルドしたコマンドで実行するテスト
+//!
+//! CIでそのまま比較できるよう、`--no-timing` の出力が実行ごとに変わらないことを確かめる。
+
+use std::process::Command;
+
+fn doctor() -> (Option<i32>, String) {
+    let output = Command::new(env!("CARGO_BIN_EXE_splatoon3-ghost-drawer"))
+        .args(["doctor", "--no-timing"])
+        .output()
+        .unwrap();
+    (
+        output.status.code(),
+        String::from_utf8_lossy(&output.stdout).to_string(),
+    )
+}
+
+#[test]
+fn test_doctor_passes_with_identical_output() {
+    let (code, first) = doctor();
+    assert_eq!(code, Some(0), "{first}");
+    assert!(first.ends_with("All 9 stages passed\n"), "{first}");
+    assert!(
+        first.contains("paint (preview)      PASS    32 dots painted"),
+        "{first}"
+    );
+    assert!(!first.contains("127.0.0.1"), "{first}");
+
+    let (code, second) = doctor();
+    assert_eq!(code, Some(0));
+    assert_eq!(second, first);
+}
@@ -0,0 +1,154 @@
+//! `splatoon3-ghost-drawer run --drain` をビルドしたコマンドで実行するテスト
+//!
+//! シミュレーション（モックコントローラー）のサーバーを子プロセスで起動し、2つのアートワークを
+//! 続けて描画したあと、猶予時間が過ぎたらプロセスが終了コード0で終わることを確かめる。
+
+use splatoon3_ghost_drawer::interfaces::web::server::{GenerateArtworkRequest, TestPattern};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// 描画の無い状態からサーバーが終了するまでの猶予時間
+const DRAIN_GRACE_SECS: u64 = 2;
+/// サーバーの起動・描画・終了を待つ上限
+const TIMEOUT: Duration = Duration::from_secs(60);
+
+/// テストが失敗しても子プロセスを残さない
+struct ServerProcess(Child);
+
+impl Drop for ServerProcess {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+async fn post(http: &reqwest::Client, url: String, body: String) -> serde_json::Value {
+    let response = http
+        .post(&url)
+        .header("content-type", "application/json")
+        .body(body)
+        .send()
+        .await
+        .unwrap();
+    assert!(
+        response.status().is_success(),
+        "{url}: {}",
+        response.status()
+    );
+    response.json().await.unwrap()
+}
+
+async fn wait_until_idle(http: &reqwest::Client, base: &str) {
+    let started = Instant::now();
+    loop {
+        let status: serde_json::Value = http
+            .get(format!("{base}/api/painting/status"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        if status["active"] == false {
+            return;
+        }
+        assert!(started.elapsed() < TIMEOUT, "painting did not finish");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_drain_exits_after_queued_artworks_are_painted() {
+    let data_dir = std::env::temp_dir().join(format!("drain-cli-{}", uuid::Uuid::new_v4()));
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let mut server = ServerProcess(
+        Command::new(env!("CARGO_BIN_EXE_splatoon3-ghost-drawer"))
+            .args(["run", "--host", "127.0.0.1", "--port", &port.to_string()])
+            .args(["--simulate", "--no-auth", "--storage", "memory"])
+            .args(["--no-mdns", "--no-gallery", "--no-connection-monitor"])
+            .args(["--init-preset", "none"])
+            .args([
+                "--drain",
+                "--drain-grace-secs",
+                &DRAIN_GRACE_SECS.to_string(),
+            ])
+            .arg("--data-dir")
+            .arg(&data_dir)
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    let base = format!("http://127.0.0.1:{port}");
+    let http = reqwest::Client::new();
+    let started = Instant::now();
+    while !http
+        .get(format!("{base}/api/health"))
+        .send()
+        .await
+        .is_ok_and(|response| response.status().is_success())
+    {
+        assert!(started.elapsed() < TIMEOUT, "server did not start");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+
+    post(
+        &http,
+        format!("{base}/api/controller/arm"),
+        "{}".to_string(),
+    )
+    .await;
+    let mut ids = Vec::new();
+    for name in ["first", "second"] {
+        let request = GenerateArtworkRequest {
+            width: 6,
+            height: 4,
+            name: Some(name.to_string()),
+            ..GenerateArtworkRequest::new(TestPattern::Checkerboard)
+        };
+        let artwork = post(
+            &http,
+            format!("{base}/api/artworks/generate"),
+            serde_json::to_string(&request).unwrap(),
+        )
+        .await;
+        ids.push(artwork["id"].as_str().unwrap().to_string());
+    }
+
+    // 描画中は猶予時間を過ぎても終了せず、次の描画の開始でカウントダウンをやり直す
+    for id in &ids {
+        post(
+            &http,
+            format!("{base}/api/artworks/{id}/paint"),
+            "{}".to_string(),
+        )
+        .await;
+        wait_until_idle(&http, &base).await;
+        assert!(server.0.try_wait().unwrap().is_none(), "exited before {id}");
+    }
+    let runs: serde_json::Value = http
+        .get(format!("{base}/api/painting/runs"))
+        .send()
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+    assert_eq!(runs.as_array().unwrap().len(), 2, "{runs}");
+
+    let idle_since = Instant::now();
+    let status = loop {
+        if let Some(status) = server.0.try_wait().unwrap() {
+            break status;
+        }
+        assert!(idle_since.elapsed() < TIMEOUT, "server did not exit");
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    };
+    assert!(status.success(), "{status}");
+    assert!(idle_since.elapsed() < Duration::from_secs(DRAIN_GRACE_SECS + 10));
+    let _ = std::fs::remove_dir_all(&data_dir);
+}
@@ -0,0 +1,261 @@
+//! dummy_hcdを使ったUSBガジェットのループバックテスト
+//!
+//! `LinuxUsbGadgetManager` でテスト用の名前のガジェットを作って dummy_hcd のUDCにバインドし、
+//! 同じマシンのホスト側に現れたhidrawデバイスから、`LinuxHidController` が送ったレポートを読み取って確認する。
+//! 実機なしで本物のガジェットの経路（configfs・f_hid・レポートディスクリプタ）を通せる。
+//!
+//! ## 前提条件
+//!
+//! - Linuxでroot権限があること
+//! - カーネルモジュール `libcomposite`・`usb_f_hid`・`dummy_hcd` が読み込めること
+//!   （`CONFIG_USB_DUMMY_HCD`・`CONFIG_USB_CONFIGFS_F_HID`。ホスト側の `usbhid` と `CONFIG_HIDRAW` も必要）
+//! - configfsが `/sys/kernel/config` にマウントされていること
+//!
+//! モジュールはテストが `modprobe` で読み込みを試みる。条件を満たさない場合は理由を表示してスキップする（失敗にはしない）。
+//!
+//! ```text
+//! sudo modprobe dummy_hcd
+//! sudo -E cargo test --test gadget_loopback -- --ignored --nocapture
+//! ```
+//!
+//! 本番の `nintendo_controller` とは別の名前でガジェットを作り、テストの終了時（失敗時も含む）に削除する。
+
+#![cfg(target_os = "linux")]
+
+use splatoon3_ghost_drawer::domain::controller::{
+    Button, ControllerAction, ControllerCommand, ControllerEmulator, DPad, StickPosition,
+};
+use splatoon3_ghost_drawer::domain::hardware::repositories::UsbGadgetManager;
+use splatoon3_ghost_drawer::infrastructure::hardware::linux_hid_controller::LinuxHidController;
+use splatoon3_ghost_drawer::infrastructure::hardware::linux_usb_gadget_manager::LinuxUsbGadgetManager;
+use splatoon3_ghost_drawer::infrastructure::platform;
+use std::fs;
+use std::io::Read;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// テスト用のガジェット名
+const TEST_GADGET_NAME: &str = "ghost_drawer_loopback";
+/// ガジェットのVID:PID（hidrawの `HID_ID` の形式）
+const HID_ID: &str = "0003:00000F0D:00000092";
+/// ホスト側にhidrawが現れるまで待つ時間
+const ENUMERATION_TIMEOUT: Duration = Duration::from_secs(5);
+
+const NEUTRAL: [u8; 8] = [0x00, 0x00, 0x08, 0x80, 0x80, 0x80, 0x80, 0x00];
+
+/// ループバックテストを実行できる環境なら、使えるdummy_hcdのUDC名を返す
+///
+/// 実行できない場合は理由を表示して `None` を返す。
+fn loopback_udc() -> Option<String> {
+    let skip = |reason: &str| {
+        eprintln!("skipping gadget loopback test: {reason}");
+        None
+    };
+
+    if !platform::is_root() {
+        return skip("root privileges are required");
+    }
+    for module in ["libcomposite", "usb_f_hid", "dummy_hcd"] {
+        let loaded = Command::new("modprobe")
+            .arg(module)
+            .status()
+            .is_ok_and(|status| status.success());
+        if !loaded {
+            return skip(&format!("kernel module {module} is not available"));
+        }
+    }
+    if !Path::new("/sys/kernel/config/usb_gadget").exists() {
+        return skip("configfs is not mounted at /sys/kernel/config");
+    }
+
+    // 他のガジェットにバインドされていないdummy_hcdのUDCを選ぶ
+    let bound: Vec<String> = fs::read_dir("/sys/kernel/config/usb_gadget")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|gadget| fs::read_to_string(gadget.path().join("UDC")).ok())
+        .map(|udc| udc.trim().to_string())
+        .collect();
+    let mut candidates: Vec<String> = fs::read_dir("/sys/class/udc")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.starts_with("dummy_udc") && !bound.contains(name))
+        .collect();
+    candidates.sort();
+    match candidates.into_iter().next() {
+        Some(udc) => Some(udc),
+        None => skip("no unbound dummy_udc found in /sys/class/udc"),
+    }
+}
+
+/// テスト用のガジェット（破棄時に削除する）
+struct TestGadget {
+    manager: LinuxUsbGadgetManager,
+}
+
+impl TestGadget {
+    fn configure(udc: &str) -> Self {
+        let gadget = Self {
+            manager: LinuxUsbGadgetManager::new()
+                .with_gadget_name(TEST_GADGET_NAME)
+                .with_udc(Some(udc.to_string())),
+        };
+        gadget
+            .manager
+            .configure_as_pro_controller()
+            .expect("configure test gadget");
+        gadget
+    }
+
+    /// HID functionの `dev` から、ガジェット側のデバイスファイルを求める
+    fn device_path(&self) -> String {
+        let dev = fs::read_to_string(format!("{}/dev", self.manager.hid_function_path()))
+            .expect("read HID function dev");
+        let uevent = fs::read_to_string(format!("/sys/dev/char/{}/uevent", dev.trim()))
+            .expect("read device uevent");
+        let name = uevent
+            .lines()
+            .find_map(|line| line.strip_prefix("DEVNAME="))
+            .expect("DEVNAME in uevent");
+        format!("/dev/{name}")
+    }
+}
+
+impl Drop for TestGadget {
+    fn drop(&mut self) {
+        self.manager.remove_gadget();
+    }
+}
+
+/// dummy_hcdのホスト側に現れたテスト用ガジェットのhidrawデバイスを待つ
+fn wait_for_hidraw() -> Option<PathBuf> {
+    let deadline = Instant::now() + ENUMERATION_TIMEOUT;
+    while Instant::now() < deadline {
+        let found = fs::read_dir("/sys/class/hidraw")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .find(|entry| {
+                let on_dummy_hcd = fs::canonicalize(entry.path())
+                    .is_ok_and(|path| path.to_string_lossy().contains("dummy_hcd"));
+                let matches_id =
+                    fs::read_to_string(entry.path().join("device/uevent")).is_ok_and(|uevent| {
+                        uevent
+                            .lines()
+                            .any(|line| line == format!("HID_ID={HID_ID}"))
+                    });
+                on_dummy_hcd && matches_id
+            });
+        if let Some(entry) = found {
+            return Some(Path::new("/dev").join(entry.file_name()));
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    None
+}
+
+/// hidrawから届いたレポートを別スレッドで読み続ける
+struct ReportReader {
+    stop: Arc<AtomicBool>,
+    handle: thread::JoinHandle<Vec<Vec<u8>>>,
+}
+
+impl ReportReader {
+    fn start(path: &Path) -> Self {
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(path)
+            .expect("open hidraw");
+        let stop = Arc::new(AtomicBool::new(false));
+        let stop_reading = stop.clone();
+        let handle = thread::spawn(move || {
+            let mut reports = Vec::new();
+            let mut buffer = [0u8; 64];
+            while !stop_reading.load(Ordering::SeqCst) {
+                match file.read(&mut buffer) {
+                    Ok(len) => reports.push(buffer[..len].to_vec()),
+                    Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                        thread::sleep(Duration::from_millis(1));
+                    }
+                    Err(e) => panic!("failed to read hidraw: {e}"),
+                }
+            }
+            reports
+        });
+        Self { stop, handle }
+    }
+
+    /// 残りのレポートを受け取ってから止め、連続する同じレポートをまとめて返す
+    fn finish(self) -> Vec<Vec<u8>> {
+        thread::sleep(Duration::from_millis(100));
+        self.stop.store(true, Ordering::SeqCst);
+        let mut reports = self.handle.join().expect("reader thread");
+        reports.dedup();
+        reports
+    }
+}
+
+#[test]
+#[ignore = "requires root and dummy_hcd; run with -- --ignored"]
+fn test_controller_reports_reach_host_through_dummy_hcd() {
+    let Some(udc) = loopback_udc() else {
+        return;
+    };
+    let gadget = TestGadget::configure(&udc);
+    let device_path = gadget.device_path();
+
+    // バインド後の検証が実際のガジェットで通ること
+    let verification = LinuxUsbGadgetManager::new()
+        .with_gadget_name(TEST_GADGET_NAME)
+        .with_hid_device_path(device_path.clone())
+        .verify_gadget()
+        .expect("verify test gadget");
+    for check in &verification.checks {
+        println!("{check}");
+    }
+    assert!(verification.is_ok());
+
+    let hidraw = wait_for_hidraw().expect("hidraw device for the test gadget on dummy_hcd");
+    // usbhidがポーリングを始めるよう、送信前にホスト側を開いておく
+    let reader = ReportReader::start(&hidraw);
+
+    let controller =
+        LinuxHidController::new().with_gadget(gadget.manager.gadget_path(), device_path);
+    controller.initialize().expect("initialize controller");
+    assert!(controller.is_connected().unwrap());
+
+    let command = ControllerCommand::new("loopback smoke test")
+        .add_action(ControllerAction::press_button(Button::A, 50))
+        .add_action(ControllerAction::release_button(Button::A, 50))
+        .add_action(ControllerAction::set_dpad(DPad::UP, 50))
+        .add_action(ControllerAction::set_dpad(DPad::NEUTRAL, 50))
+        .add_action(ControllerAction::move_left_stick(
+            StickPosition::new(0xFF, 0x80),
+            50,
+        ));
+    controller.execute_command(&command).unwrap();
+    controller.shutdown().unwrap();
+
+    let reports = reader.finish();
+    let expected: Vec<Vec<u8>> = [
+        NEUTRAL,
+        [0x04, 0x00, 0x08, 0x80, 0x80, 0x80, 0x80, 0x00],
+        NEUTRAL,
+        [0x00, 0x00, 0x00, 0x80, 0x80, 0x80, 0x80, 0x00],
+        NEUTRAL,
+        [0x00, 0x00, 0x08, 0xFF, 0x80, 0x80, 0x80, 0x00],
+        NEUTRAL,
+    ]
+    .iter()
+    .map(|report| report.to_vec())
+    .collect();
+    assert_eq!(reports, expected);
+}
@@ -0,0 +1,214 @@
+//! `splatoon3-ghost-drawer painting` のコマンドを起動中のサーバーに対して実行するテスト
+//!
+//! シミュレーション（モックコントローラー）のサーバーを同じプロセスで起動し、Web UIと同じAPIで描画を開始してから、
+//! ビルドしたコマンドを子プロセスとして実行して、表示・JSON・終了コードを確認する。
+//! アクセストークンはサーバーがデータディレクトリに作ったものを、コマンドが `--data-dir` から読む。
+
+use splatoon3_ghost_drawer::interfaces::web::dto::PaintingStatus;
+use splatoon3_ghost_drawer::interfaces::web::server::{
+    AuthToken, ConnectionMonitorSettings, GenerateArtworkRequest, ServerConfig, StorageBackend,
+    TestPattern, create_server,
+};
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::time::{Duration, Instant};
+
+/// サーバーが応答するまで待つ上限
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+struct TestServer {
+    port: u16,
+    data_dir: PathBuf,
+    token: String,
+    http: reqwest::Client,
+}
+
+impl TestServer {
+    async fn start() -> Self {
+        let data_dir = std::env::temp_dir().join(format!("painting-cli-{}", uuid::Uuid::new_v4()));
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        let config = ServerConfig::new("127.0.0.1", port)
+            .with_simulation(false)
+            .with_data_dir(&data_dir)
+            .with_storage(StorageBackend::Memory)
+            .with_connection_monitor(ConnectionMonitorSettings::disabled())
+            .without_mdns()
+            .without_gallery();
+        tokio::spawn(async move { create_server(config).await.unwrap() });
+
+        let http = reqwest::Client::new();
+        let started = Instant::now();
+        loop {
+            let response = http
+                .get(format!("http://127.0.0.1:{port}/api/painting/status"))
+                .send()
+                .await;
+            if response.is_ok_and(|response| response.status().is_success()) {
+                break;
+            }
+            assert!(started.elapsed() < STARTUP_TIMEOUT, "server did not start");
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        let token = AuthToken::load(&data_dir)
+            .unwrap()
+            .unwrap()
+            .as_str()
+            .to_string();
+        Self {
+            port,
+            data_dir,
+            token,
+            http,
+        }
+    }
+
+    async fn post(&self, path: &str, body: String) -> serde_json::Value {
+        let response = self
+            .http
+            .post(format!("http://127.0.0.1:{}{path}", self.port))
+            .bearer_auth(&self.token)
+            .header("content-type", "application/json")
+            .body(body)
+            .send()
+            .await
+            .unwrap();
+        assert!(
+            response.status().is_success(),
+            "{path}: {}",
+            response.status()
+        );
+        response.json().await.unwrap()
+    }
+
+    /// Web UIと同じ手順で、時間のかかる描画を開始する
+    async fn start_painting(&self) {
+        self.post("/api/controller/arm", "{}".to_string()).await;
+        let request = GenerateArtworkRequest {
+            name: Some("CLI checkerboard".to_string()),
+            ..GenerateArtworkRequest::new(TestPattern::Checkerboard)
+        };
+        let artwork = self
+            .post(
+                "/api/artworks/generate",
+                serde_json::to_string(&request).unwrap(),
+            )
+            .await;
+        let id = artwork["id"].as_str().unwrap();
+        self.post(&format!("/api/artworks/{id}/paint"), "{}".to_string())
+            .await;
+    }
+
+    /// コマンドを実行する（子プロセスの間はサーバーのタスクが動けるよう、ブロッキング用のスレッドで待つ）
+    async fn painting(&self, args: &[&str]) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_splatoon3-ghost-drawer"));
+        command
+            .arg("painting")
+            .args(args)
+            .args(["--port", &self.port.to_string()])
+            .arg("--data-dir")
+            .arg(&self.data_dir)
+            .env_remove("SPLATOON3_TOKEN");
+        tokio::task::spawn_blocking(move || command.output().unwrap())
+            .await
+            .unwrap()
+    }
+}
+
+impl Drop for TestServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.data_dir);
+    }
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).to_string()
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_painting_commands_control_a_web_started_run() {
+    let server = TestServer::start().await;
+
+    let output = server.painting(&["stop"]).await;
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert!(stderr(&output).contains("No active painting"));
+    let output = server.painting(&["status"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stdout(&output).contains("idle"), "{}", stdout(&output));
+
+    server.start_painting().await;
+    let output = server.painting(&["status"]).await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let text = stdout(&output);
+    assert!(text.contains("Painting: running"), "{text}");
+    assert!(text.contains("CLI checkerboard"), "{text}");
+    assert!(text.contains("Progress: "), "{text}");
+
+    let output = server.painting(&["pause"]).await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Painting paused"));
+    let output = server.painting(&["pause"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("already paused"));
+
+    let output = server.painting(&["status", "--json", "compact"]).await;
+    assert_eq!(output.status.code(), Some(0));
+    let status: PaintingStatus = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(status.active && status.paused);
+    assert!(status.total_dots.unwrap() > 0);
+
+    let output = server.painting(&["resume"]).await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(stdout(&output).contains("Painting resumed"));
+
+    // トークンが違えば拒否され、描画は続く
+    let output = server.painting(&["stop", "--token", "wrong"]).await;
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).contains("401"), "{}", stderr(&output));
+
+    let output = server.painting(&["stop"]).await;
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("Painting stopped"),
+        "{}",
+        stdout(&output)
+    );
+
+    let output = server.painting(&["stop"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr(&output).contains("No active painting"));
+    let output = server.painting(&["status"]).await;
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        stdout(&output).contains("Last run: "),
+        "{}",
+        stdout(&output)
+    );
+}
+
+#[tokio::test]
+async fn test_unreachable_server_exits_with_2() {
+    let port = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let output = Command::new(env!("CARGO_BIN_EXE_splatoon3-ghost-drawer"))
+        .args(["painting", "status", "--port", &port.to_string()])
+        .args(["--token", "unused"])
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(2));
+    assert!(
+        stderr(&output).contains("Failed to reach"),
+        "{}",
+        stderr(&output)
+    );
+}
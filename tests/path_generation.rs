@@ -0,0 +1,77 @@
+//! 高密度キャンバスの描画パス生成の所要時間と確保回数の確認
+//!
+//! 確保回数を数えるためにグローバルアロケータを差し替えるので、独立したテストバイナリにしている。
+//! 所要時間の目安はリリースビルド（`cargo test --release --test path_generation`）のもの。
+
+use splatoon3_ghost_drawer::domain::artwork::entities::{Canvas, Dot};
+use splatoon3_ghost_drawer::domain::painting::services::ArtworkToCommandConverter;
+use splatoon3_ghost_drawer::domain::painting::value_objects::{
+    DrawingCanvasConfig, DrawingStrategy,
+};
+use splatoon3_ghost_drawer::domain::shared::value_objects::Coordinates;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// 320x180の全ドットを描画対象にしたキャンバス（57,600ドット）
+fn dense_canvas() -> Canvas {
+    let mut canvas = Canvas::new(320, 180);
+    for y in 0..180 {
+        for x in 0..320 {
+            canvas
+                .set_dot(Coordinates::new(x, y), Dot::black())
+                .unwrap();
+        }
+    }
+    canvas
+}
+
+#[test]
+fn test_dense_canvas_path_generation_scales() {
+    let canvas = dense_canvas();
+
+    for (strategy, release_limit) in [
+        (DrawingStrategy::ZigZag, Duration::from_millis(200)),
+        (DrawingStrategy::NearestNeighbor, Duration::from_secs(1)),
+        (DrawingStrategy::GreedyTwoOpt, Duration::from_secs(3)),
+    ] {
+        let converter = ArtworkToCommandConverter::new(DrawingCanvasConfig::default(), strategy);
+
+        let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+        let started = Instant::now();
+        let path = converter.create_drawing_path(&canvas);
+        let elapsed = started.elapsed();
+        let allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+
+        println!("{strategy:?}: {elapsed:?}, {allocations} allocations");
+        assert_eq!(path.coordinates.len(), 320 * 180, "{strategy:?}");
+        // 確保はドット数に比例せず、座標列とグリッドの作業領域の数回に収まる
+        assert!(allocations < 64, "{strategy:?}: {allocations} allocations");
+        if !cfg!(debug_assertions) {
+            assert!(elapsed < release_limit, "{strategy:?}: {elapsed:?}");
+        }
+    }
+}
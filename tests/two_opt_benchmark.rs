@@ -0,0 +1,133 @@
+//! 2-opt最適化の従来版（固定ウィンドウ500点・最大50反復）と適応版の比較
+//!
+//! 時間を計るためリリースビルドで実行する。
+//!
+//! ```text
+//! cargo test --release --test two_opt_benchmark -- --ignored --nocapture
+//! ```
+//!
+//! 320x180のキャンバスにドットを散らした場合の結果（x86_64のリリースビルドでの測定例）:
+//!
+//! | ドット数 | 従来版（距離、反復、時間） | 適応版（距離、反復、時間、ウィンドウ） |
+//! |---|---|---|
+//! | 1,000 | 9,185 → 7,641、8回、11ms | 9,185 → 7,535、5回、8ms、全域 |
+//! | 10,000 | 29,304 → 25,912、9回、161ms | 29,304 → 25,014、7回、629ms、2,880 |
+//! | 40,000 | 55,644 → 51,706、7回、508ms | 55,644 → 51,670、4回、417ms、720 |
+//!
+//! 疎なアートワークはウィンドウを広げる分だけ時間を使って短い経路になり、
+//! 密なアートワークは短縮率が0.1%を下回った時点で打ち切るため従来版より速く終わる。
+
+use splatoon3_ghost_drawer::domain::artwork::entities::{Canvas, Dot};
+use splatoon3_ghost_drawer::domain::painting::services::ArtworkToCommandConverter;
+use splatoon3_ghost_drawer::domain::painting::value_objects::{
+    DrawingCanvasConfig, DrawingStrategy, TwoOptSettings,
+};
+use splatoon3_ghost_drawer::domain::shared::value_objects::Coordinates;
+use std::time::{Duration, Instant};
+
+/// 320x180のキャンバスに `dots` 個のドットを決定的に散らす
+fn scattered_canvas(dots: usize) -> Canvas {
+    let mut canvas = Canvas::new(320, 180);
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    while canvas.dots.len() < dots {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        let x = (state % 320) as u16;
+        let y = ((state >> 16) % 180) as u16;
+        canvas
+            .set_dot(Coordinates::new(x, y), Dot::black())
+            .unwrap();
+    }
+    canvas
+}
+
+fn distance(path: &[Coordinates]) -> u64 {
+    path.windows(2)
+        .map(|pair| pair[0].manhattan_distance_to(&pair[1]) as u64)
+        .sum()
+}
+
+/// 変更前の2-opt（固定ウィンドウ500点・最大50反復）
+fn legacy_two_opt(path: &mut [Coordinates]) -> usize {
+    const MAX_ITERATIONS: usize = 50;
+    const WINDOW_SIZE: usize = 500;
+    let n = path.len();
+    let mut improved = true;
+    let mut iterations = 0;
+    while improved && iterations < MAX_ITERATIONS {
+        improved = false;
+        iterations += 1;
+        for i in 0..n - 2 {
+            let end_j = std::cmp::min(i + WINDOW_SIZE, n - 1);
+            for j in i + 2..end_j {
+                let (p1, p2, p3, p4) = (path[i], path[i + 1], path[j], path[j + 1]);
+                let current = p1.manhattan_distance_to(&p2) + p3.manhattan_distance_to(&p4);
+                let swapped = p1.manhattan_distance_to(&p3) + p2.manhattan_distance_to(&p4);
+                if swapped < current {
+                    path[i + 1..=j].reverse();
+                    improved = true;
+                }
+            }
+        }
+    }
+    iterations
+}
+
+#[test]
+#[ignore = "benchmark; run with --release -- --ignored --nocapture"]
+fn benchmark_two_opt_legacy_vs_adaptive() {
+    let settings = TwoOptSettings::default();
+    println!(
+        "dots | legacy: distance (iterations, time) | adaptive: distance (iterations, time, window, stop)"
+    );
+
+    for dots in [1_000, 10_000, 40_000] {
+        let canvas = scattered_canvas(dots);
+
+        // 従来版は最近傍探索の結果に2-optを掛ける
+        let mut legacy = ArtworkToCommandConverter::new(
+            DrawingCanvasConfig::default(),
+            DrawingStrategy::NearestNeighbor,
+        )
+        .create_drawing_path(&canvas)
+        .coordinates;
+        let before = distance(&legacy);
+        let started = Instant::now();
+        let legacy_iterations = legacy_two_opt(&mut legacy);
+        let legacy_elapsed = started.elapsed();
+        let legacy_distance = distance(&legacy);
+
+        let adaptive = ArtworkToCommandConverter::new(
+            DrawingCanvasConfig::default(),
+            DrawingStrategy::GreedyTwoOpt,
+        )
+        .with_two_opt_settings(settings)
+        .create_drawing_path(&canvas);
+        let stats = adaptive.two_opt.unwrap();
+        assert_eq!(stats.distance_before, before);
+        assert_eq!(stats.distance_after, distance(&adaptive.coordinates));
+
+        println!(
+            "{dots:>7} | {before} -> {legacy_distance} ({legacy_iterations}, {legacy_elapsed:?}) | {} -> {} ({}, {}ms, {}, {:?})",
+            stats.distance_before,
+            stats.distance_after,
+            stats.iterations,
+            stats.elapsed_ms,
+            stats.window,
+            stats.stop_reason
+        );
+
+        // 時間の上限を守る（確認間隔の分だけ超えることがある）
+        assert!(
+            Duration::from_millis(stats.elapsed_ms)
+                < settings.time_budget + Duration::from_millis(250),
+            "{dots} dots: {}ms",
+            stats.elapsed_ms
+        );
+        if dots <= 1_000 {
+            // 少ないドットは全域を探索するので従来版より短くなる
+            assert!(stats.distance_after <= legacy_distance, "{dots} dots");
+        }
+    }
+}
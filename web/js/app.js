@@ -1,6 +1,56 @@
 // Splatoon3 Ghost Drawer - Web UI
 // 公式Splatoonフォント対応とUI改善
 
+// 変更系APIが401を返したらアクセストークンでログインし、同じリクエストを再送する
+// （ログイン後はセッションCookieで認証される）
+(() => {
+    const originalFetch = window.fetch.bind(window);
+
+    window.fetch = async (input, init) => {
+        const response = await originalFetch(input, init);
+        const url = typeof input === 'string' ? input : input.url;
+        if (response.status !== 401 || url.includes('/api/auth/login')) {
+            return response;
+        }
+
+        const token = window.prompt('アクセストークンを入力してください（`splatoon3-ghost-drawer info` で確認できます）');
+        if (!token) {
+            return response;
+        }
+
+        const login = await originalFetch('/api/auth/login', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ token: token.trim() })
+        });
+        return login.ok ? originalFetch(input, init) : login;
+    };
+})();
+
+// コントローラー出力がロックされていて423が返ったら、確認の上でアームして同じリクエストを再送する
+(() => {
+    const authenticatedFetch = window.fetch;
+    const AUTO_DISARM_MS = 10 * 60 * 1000;
+
+    window.fetch = async (input, init) => {
+        const response = await authenticatedFetch(input, init);
+        if (response.status !== 423) {
+            return response;
+        }
+
+        if (!window.confirm('コントローラー出力はロックされています。アームしてSwitchに入力を送りますか？（10分後に自動で解除されます）')) {
+            return response;
+        }
+
+        const arm = await authenticatedFetch('/api/controller/arm', {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({ auto_disarm_ms: AUTO_DISARM_MS })
+        });
+        return arm.ok ? authenticatedFetch(input, init) : arm;
+    };
+})();
+
 class GhostDrawerApp {
     constructor() {
         this.currentFile = null;
@@ -12,6 +62,9 @@ class GhostDrawerApp {
         this.connectionCheckInterval = null;
         this.abortController = null;
         this.imageProcessor = new ImageProcessor();
+        // 描画プレビューの背景色とドットの色（暗い背景のアートワークでは反転する）
+        this.canvasBackground = '#FFFFFF';
+        this.canvasInk = '#000000';
         this.threshold = 128;
         this.brightness = 0;
         this.contrast = 0;
@@ -22,6 +75,7 @@ class GhostDrawerApp {
         this.blackPoint = 0;
         this.whitePoint = 255;
         this.previewMode = false; // 2値化前プレビューモード
+        this.toneMode = 'binary'; // 階調モード（binary / stipple2 / stipple4）
         this.previewTimeout = null;
         this.cropMode = false;
         this.cropArea = null;
@@ -292,6 +346,16 @@ class GhostDrawerApp {
             });
         }
 
+        // 階調モード
+        const toneModeSelect = document.getElementById('toneModeSelect');
+        if (toneModeSelect) {
+            toneModeSelect.addEventListener('change', (e) => {
+                this.toneMode = e.target.value;
+                this.addLog(`階調モードを変更しました: ${e.target.selectedOptions[0].textContent}`, 'info');
+                this.debouncedUpdatePreview();
+            });
+        }
+
         // リセットボタン
         const resetAdjustmentsButton = document.getElementById('resetAdjustmentsButton');
         if (resetAdjustmentsButton) {
@@ -409,7 +473,7 @@ class GhostDrawerApp {
         const repeatsInput = document.getElementById('paint-repeats');
         if (repeatsInput) {
             repeatsInput.addEventListener('input', () => {
-                this.renderStrategyStats();
+                this.fetchStrategyStats();
             });
         }
 
@@ -551,6 +615,7 @@ class GhostDrawerApp {
         const serverStatus = document.getElementById('serverStatus');
         serverStatus.textContent = '接続済み';
         serverStatus.className = 'text-sm font-semibold status-connected';
+        document.getElementById('safeModeBanner').classList.toggle('hidden', !data.safe_mode);
     }
 
     updateHardwareStatus(data) {
@@ -705,6 +770,16 @@ class GhostDrawerApp {
         const ctx = convertedCanvas.getContext('2d');
         ctx.drawImage(scaledCanvas, 0, 0);
 
+        // 原点（左上、描画を始める位置）の印。描く前に上下・左右の向きを確かめられるようにする
+        const marker = Math.max(6, Math.round(Math.min(convertedCanvas.width, convertedCanvas.height) / 12));
+        ctx.fillStyle = 'rgba(255, 176, 0, 0.85)';
+        ctx.beginPath();
+        ctx.moveTo(0, 0);
+        ctx.lineTo(marker, 0);
+        ctx.lineTo(0, marker);
+        ctx.closePath();
+        ctx.fill();
+
         // 表示を切り替え
         convertedArea.classList.add('hidden');
         convertedImageArea.classList.remove('hidden');
@@ -757,7 +832,8 @@ class GhostDrawerApp {
                 shadows: this.shadows,
                 blackPoint: this.blackPoint,
                 whitePoint: this.whitePoint,
-                previewMode: this.previewMode
+                previewMode: this.previewMode,
+                toneMode: this.toneMode
             };
             
             // 切り取り範囲がある場合は、画像の表示サイズ情報を追加
@@ -790,7 +866,8 @@ class GhostDrawerApp {
             const dots = this.imageProcessor.convertToDotData(
                 processedData.binaryData,
                 processedData.width,
-                processedData.height
+                processedData.height,
+                this.toneMode === 'binary' ? null : processedData.imageData
             );
             
             if (dots.length === 0) {
@@ -809,7 +886,8 @@ class GhostDrawerApp {
                 name: this.currentFile.name.replace(/\.[^/.]+$/, '') || 'Untitled',
                 width: processedData.width,
                 height: processedData.height,
-                dots: dots
+                dots: dots,
+                tone_mode: this.toneMode
             };
             
             // デバッグ用にリクエストデータをログ出力
@@ -843,10 +921,15 @@ class GhostDrawerApp {
 
             const result = await response.json();
             this.currentArtworkId = result.id;
+            this.setCanvasBackground(result.artwork && result.artwork.background_color);
             
             this.updateProgress(100, '変換完了');
             this.addLog('画像変換が完了しました', 'success');
             this.addLog(`アートワークID: ${result.id}`, 'info');
+            if (result.estimated_painting_seconds != null) {
+                this.addLog(`推定描画時間: 約${Math.ceil(result.estimated_painting_seconds / 60)}分`, 'info');
+            }
+            (result.warnings || []).forEach((warning) => this.addLog(warning, 'warning'));
             
             // 変換後の画像を表示
             this.displayProcessedCanvas(processedData.canvas);
@@ -1130,8 +1213,8 @@ class GhostDrawerApp {
         paintingCanvas.width = 320;
         paintingCanvas.height = 120;
         
-        // 背景を白で塗りつぶし
-        ctx.fillStyle = '#FFFFFF';
+        // 背景を塗りつぶし
+        ctx.fillStyle = this.canvasBackground;
         ctx.fillRect(0, 0, 320, 120);
         
         // カーソルを初期位置に表示
@@ -1166,7 +1249,7 @@ class GhostDrawerApp {
         if (is_paint !== false) {
             const paintingCanvas = document.getElementById('paintingCanvas');
             const ctx = paintingCanvas.getContext('2d');
-            ctx.fillStyle = '#000000';
+            ctx.fillStyle = this.canvasInk;
             ctx.fillRect(x, y, 1, 1);
         }
 
@@ -1314,7 +1397,19 @@ class GhostDrawerApp {
         tbody.innerHTML = '<tr class="bg-gray-800 border-b border-gray-700"><td colspan="4" class="px-3 py-2 text-center">読み込み中...</td></tr>';
 
         try {
-            const response = await fetch(`/api/artworks/${this.currentArtworkId}/strategies`);
+            // 描画開始時と同じ条件で見積もる
+            const repeatsInput = document.getElementById('paint-repeats');
+            const repeats = repeatsInput ? parseInt(repeatsInput.value, 10) || 1 : 1;
+            const timing = window.calibrationManager ? window.calibrationManager.getTimingValues() : { pressMs: 100, releaseMs: 60, waitMs: 40 };
+            const params = new URLSearchParams({
+                press_ms: timing.pressMs,
+                release_ms: timing.releaseMs,
+                wait_ms: timing.waitMs,
+                repeats,
+                // 大きなアートワークは間引いて見積もる（小さければ全ドットで計算される）
+                mode: 'fast',
+            });
+            const response = await fetch(`/api/artworks/${this.currentArtworkId}/strategies?${params}`);
             if (!response.ok) throw new Error('Failed to fetch strategy stats');
             
             this.strategyData = await response.json();
@@ -1332,22 +1427,13 @@ class GhostDrawerApp {
         const tbody = document.getElementById('strategyComparisonBody');
         if (!tbody) return;
         
-        // 描画回数を取得
-        const repeatsInput = document.getElementById('paint-repeats');
-        const repeats = repeatsInput ? parseInt(repeatsInput.value, 10) || 1 : 1;
-
-        // キャリブレーション値を取得
-        const timing = window.calibrationManager ? window.calibrationManager.getTimingValues() : { pressMs: 100, releaseMs: 60, waitMs: 40 };
-        const cycleSec = (timing.pressMs + timing.releaseMs + timing.waitMs) / 1000;
-
         // 最も推定時間が短い戦略を見つける
         let minTime = Infinity;
         let bestStrategy = null;
 
         this.strategyData.strategies.forEach(stat => {
-            // 時間を計算 (キャリブレーション値に基づく)
-            // Aボタン回数 * サイクル時間 * リピート回数 + 十字キー回数 * サイクル時間
-            const estimatedTime = (stat.a_button_presses * cycleSec * repeats) + (stat.dpad_operations * cycleSec);
+            // サーバー側のシミュレーション結果（方向転換・ニュートラル挟み込みを含む）
+            const estimatedTime = stat.estimated_time_seconds;
             
             if (estimatedTime < minTime) {
                 minTime = estimatedTime;
@@ -1381,20 +1467,25 @@ class GhostDrawerApp {
                 tr.classList.add('bg-gray-700', 'font-semibold');
             }
 
-            // 時間を計算 (キャリブレーション値に基づく)
-            // Aボタン回数 * サイクル時間 * リピート回数 + 十字キー回数 * サイクル時間
-            const estimatedTime = (stat.a_button_presses * cycleSec * repeats) + (stat.dpad_operations * cycleSec);
+            // サーバー側のシミュレーション結果（方向転換・ニュートラル挟み込みを含む）
+            const estimatedTime = stat.estimated_time_seconds;
             
             // 時間をフォーマット (分:秒)
             const minutes = Math.floor(estimatedTime / 60);
             const seconds = Math.floor(estimatedTime % 60);
             const timeStr = `${minutes}分${seconds.toString().padStart(2, '0')}秒`;
 
+            // 間引いて見積もった値は推定値として表示する
+            const approx = this.strategyData.approximate ? '約' : '';
+            if (this.strategyData.approximate && this.strategyData.accuracy) {
+                tr.title = `${this.strategyData.sample_dots.toLocaleString()} / ${this.strategyData.total_dots.toLocaleString()} ドットからの推定（誤差の目安 ±${this.strategyData.accuracy.typical_time_error_percent}%）`;
+            }
+
             tr.innerHTML = `
                 <td class="px-3 py-2">${strategyNames[stat.strategy] || stat.strategy}</td>
-                <td class="px-3 py-2 text-right">${stat.dpad_operations.toLocaleString()}</td>
-                <td class="px-3 py-2 text-right">${stat.a_button_presses.toLocaleString()}</td>
-                <td class="px-3 py-2 text-right">${timeStr}</td>
+                <td class="px-3 py-2 text-right">${approx}${stat.dpad_operations.toLocaleString()}</td>
+                <td class="px-3 py-2 text-right">${approx}${stat.a_button_presses.toLocaleString()}</td>
+                <td class="px-3 py-2 text-right">${approx}${timeStr}</td>
             `;
             
             // 行クリックで戦略を選択
@@ -1516,7 +1607,9 @@ class GhostDrawerApp {
                     wait_ms: timing.waitMs,
                     preview: false,
                     strategy: strategy,
-                    repeats: repeats
+                    repeats: repeats,
+                    // 前回の描画で描画済みになったドットも含めて描き直す
+                    reset_progress: true
                 })
             });
 
@@ -1603,7 +1696,8 @@ class GhostDrawerApp {
                     },
                     body: JSON.stringify({
                         speed: this.paintingSpeed,
-                        preview: false
+                        preview: false,
+                        reset_progress: true
                     })
                 });
 
@@ -1694,7 +1788,7 @@ class GhostDrawerApp {
             case 'draw':
                 // ドットを描画
                 const ctx = paintingCanvas.getContext('2d');
-                ctx.fillStyle = '#000000';
+                ctx.fillStyle = this.canvasInk;
                 ctx.fillRect(operation.position.x, operation.position.y, 1, 1);
                 
                 this.paintedDots.push(operation.position);
@@ -1744,7 +1838,7 @@ class GhostDrawerApp {
             // ペンが下がっている場合は移動軌跡を描画
             if (this.penState === 'down') {
                 const ctx = paintingCanvas.getContext('2d');
-                ctx.strokeStyle = '#000000';
+                ctx.strokeStyle = this.canvasInk;
                 ctx.lineWidth = 1;
                 ctx.beginPath();
                 ctx.moveTo(lastX, lastY);
@@ -1960,9 +2054,9 @@ class GhostDrawerApp {
         const paintingCanvas = document.getElementById('paintingCanvas');
         const ctx = paintingCanvas.getContext('2d');
         
-        // 背景を白で塗りつぶし
+        // 背景を塗りつぶし
         ctx.clearRect(0, 0, paintingCanvas.width, paintingCanvas.height);
-        ctx.fillStyle = '#FFFFFF';
+        ctx.fillStyle = this.canvasBackground;
         ctx.fillRect(0, 0, paintingCanvas.width, paintingCanvas.height);
         
         // 描画済みドットの配列をリセット
@@ -1978,7 +2072,7 @@ class GhostDrawerApp {
             const op = this.paintingOperations[i];
             
             if (op.type === 'draw') {
-                ctx.fillStyle = '#000000';
+                ctx.fillStyle = this.canvasInk;
                 ctx.fillRect(op.position.x, op.position.y, 1, 1);
                 this.paintedDots.push(op.position);
                 currentDotIndex++;
@@ -2121,6 +2215,7 @@ class GhostDrawerApp {
         this.blackPoint = 0;
         this.whitePoint = 255;
         this.previewMode = false;
+        this.toneMode = 'binary';
         
         // UIを更新
         document.getElementById('thresholdSlider').value = 128;
@@ -2156,6 +2251,11 @@ class GhostDrawerApp {
             previewModeToggle.checked = false;
         }
         
+        const toneModeSelect = document.getElementById('toneModeSelect');
+        if (toneModeSelect) {
+            toneModeSelect.value = 'binary';
+        }
+        
         this.addLog('調整値をリセットしました', 'info');
         
         // プレビューを更新
@@ -2652,7 +2752,8 @@ class GhostDrawerApp {
                 shadows: this.shadows,
                 blackPoint: this.blackPoint,
                 whitePoint: this.whitePoint,
-                previewMode: this.previewMode
+                previewMode: this.previewMode,
+                toneMode: this.toneMode
             };
             
             // 切り取り範囲がある場合は、画像の表示サイズ情報を追加
@@ -2680,7 +2781,8 @@ class GhostDrawerApp {
             const dots = this.imageProcessor.convertToDotData(
                 processedData.binaryData,
                 processedData.width,
-                processedData.height
+                processedData.height,
+                this.toneMode === 'binary' ? null : processedData.imageData
             );
             
             if (dots.length === 0) {
@@ -2704,13 +2806,15 @@ class GhostDrawerApp {
                     name: this.currentFile.name.replace(/\.[^/.]+$/, '') || 'Untitled',
                     width: processedData.width,
                     height: processedData.height,
-                    dots: dots
+                    dots: dots,
+                    tone_mode: this.toneMode
                 })
             });
 
             if (response.ok) {
                 const result = await response.json();
                 this.currentArtworkId = result.id;
+                this.setCanvasBackground(result.artwork && result.artwork.background_color);
             }
             
         } catch (error) {
@@ -2718,6 +2822,17 @@ class GhostDrawerApp {
         }
     }
 
+    // アートワークの背景色に合わせてプレビューの配色を切り替える
+    setCanvasBackground(backgroundColor) {
+        this.canvasBackground = backgroundColor || '#FFFFFF';
+        const hex = this.canvasBackground.replace('#', '');
+        const r = parseInt(hex.substring(0, 2), 16);
+        const g = parseInt(hex.substring(2, 4), 16);
+        const b = parseInt(hex.substring(4, 6), 16);
+        const isDark = 0.2126 * r + 0.7152 * g + 0.0722 * b < 128;
+        this.canvasInk = isDark ? '#FFFFFF' : '#000000';
+    }
+
     // クリーンアップ
     destroy() {
         if (this.connectionCheckInterval) {
@@ -2915,8 +3030,8 @@ class CalibrationManager {
         }
 
         // 戦略比較テーブルも更新
-        if (window.ghostDrawerApp && window.ghostDrawerApp.renderStrategyStats) {
-            window.ghostDrawerApp.renderStrategyStats();
+        if (window.ghostDrawerApp && window.ghostDrawerApp.fetchStrategyStats) {
+            window.ghostDrawerApp.fetchStrategyStats();
         }
 
         // シミュレーション画面のスライダーも更新
@@ -38,6 +38,49 @@ class DebugManager {
                         if (window.ghostDrawerApp && typeof window.ghostDrawerApp.updatePaintingProgress === 'function') {
                             window.ghostDrawerApp.updatePaintingProgress(logData);
                         }
+                    } else if (logData.type === 'stats') {
+                        // 自動調整で実効タイミングが変わったときだけ表示
+                        if (logData.adaptive && logData.wait_ms !== this.lastEffectiveWaitMs) {
+                            this.lastEffectiveWaitMs = logData.wait_ms;
+                            this.addLogFromBackend({
+                                type: 'log',
+                                timestamp: new Date().toISOString(),
+                                level: 'INFO',
+                                message: `実効タイミング: ${logData.press_ms}+${logData.release_ms}+${logData.wait_ms}ms (平均書き込み遅延 ${logData.latency_ewma_ms.toFixed(1)}ms)`,
+                                target: 'painting'
+                            });
+                        }
+                    } else if (logData.type === 'layer_complete') {
+                        // レイヤー境界の通知（次のレイヤーの前に左上へ戻る）
+                        this.addLogFromBackend({
+                            type: 'log',
+                            timestamp: new Date().toISOString(),
+                            level: 'INFO',
+                            message: `レイヤー${logData.layer}の描画が完了しました (${logData.layer_index}/${logData.layer_count})`,
+                            target: 'painting'
+                        });
+                    } else if (logData.type === 'completion_report') {
+                        // 描画終了時のドットごとの結果（スキップしたドットがあれば警告として表示）
+                        const report = logData.report;
+                        const skipped = report.skipped_dots.map(dot => `(${dot.x}, ${dot.y})`).join(', ');
+                        this.addLogFromBackend({
+                            type: 'log',
+                            timestamp: new Date().toISOString(),
+                            level: report.skipped > 0 ? 'WARN' : 'INFO',
+                            message: `描画結果: ${report.succeeded}/${report.total_dots}ドット成功、スキップ ${report.skipped}、再試行 ${report.total_retries}回、${(report.duration_ms / 1000).toFixed(1)}秒`
+                                + (report.skipped > 0 ? ` (スキップしたドット: ${skipped}${report.skipped > report.skipped_dots.length ? ' ほか' : ''})` : '')
+                                + (logData.reason === 'limit_reached' ? ` (stop_after の上限 ${report.limit_reached} に達したため停止)` : ''),
+                            target: 'painting'
+                        });
+                    } else if (logData.type === 'paused_at') {
+                        // 一時停止した位置（再開時はここから描画を続ける）
+                        this.addLogFromBackend({
+                            type: 'log',
+                            timestamp: new Date().toISOString(),
+                            level: 'INFO',
+                            message: `(${logData.x}, ${logData.y}) で一時停止しました (次のドット: ${logData.next_index + 1})`,
+                            target: 'painting'
+                        });
                     } else if (logData.type === 'calibration_complete') {
                         // キャリブレーション完了通知を処理
                         if (window.calibrationManager) {
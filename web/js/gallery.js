@@ -0,0 +1,108 @@
+/**
+ * 読み取り専用のギャラリー
+ * /api/gallery/state で全体を定期的に取り直し、その間のカーソル移動は /ws/gallery で受け取る
+ */
+class Gallery {
+    constructor() {
+        this.canvas = document.getElementById('canvas');
+        this.ctx = this.canvas.getContext('2d');
+        this.state = null;
+        this.cursor = null;
+        this.refresh();
+        setInterval(() => this.refresh(), 5000);
+        this.connect();
+    }
+
+    async refresh() {
+        try {
+            const response = await fetch('/api/gallery/state');
+            if (!response.ok) {
+                this.showUnavailable();
+                return;
+            }
+            this.state = await response.json();
+            this.cursor = this.state.cursor;
+            this.render();
+        } catch (error) {
+            console.warn('Failed to load gallery state', error);
+        }
+    }
+
+    connect() {
+        const protocol = window.location.protocol === 'https:' ? 'wss' : 'ws';
+        const ws = new WebSocket(`${protocol}://${window.location.host}/ws/gallery`);
+        ws.onmessage = (event) => {
+            const message = JSON.parse(event.data);
+            if (message.type === 'progress') {
+                this.cursor = { x: message.x, y: message.y };
+                this.render();
+            } else if (message.type === 'paused') {
+                document.getElementById('status').textContent = '一時停止中';
+            } else if (message.type === 'completion') {
+                this.refresh();
+            }
+        };
+        // ギャラリーが無効にされた場合もしばらくしてから繋ぎ直す
+        ws.onclose = () => setTimeout(() => this.connect(), 5000);
+    }
+
+    render() {
+        const state = this.state;
+        if (!state) {
+            return;
+        }
+        document.getElementById('title').textContent = state.artwork_name || 'Splatoon3 Ghost Drawer';
+        document.getElementById('status').textContent =
+            state.active ? (state.paused ? '一時停止中' : '描画中') : '待機中';
+        const percent = Math.round(state.completion_ratio * 100);
+        document.getElementById('ratio').textContent = `${percent}%`;
+        document.getElementById('progress-bar').style.width = `${percent}%`;
+        document.getElementById('eta').textContent =
+            state.eta_seconds != null ? `残り約${Math.ceil(state.eta_seconds / 60)}分` : '';
+
+        const thumbnail = state.thumbnail;
+        if (thumbnail) {
+            this.canvas.width = thumbnail.width;
+            this.canvas.height = thumbnail.height;
+            const colors = { o: '#d0d0d0', '#': '#000000' };
+            this.ctx.fillStyle = '#ffffff';
+            this.ctx.fillRect(0, 0, thumbnail.width, thumbnail.height);
+            thumbnail.rows.forEach((row, y) => {
+                [...row].forEach((cell, x) => {
+                    if (colors[cell]) {
+                        this.ctx.fillStyle = colors[cell];
+                        this.ctx.fillRect(x, y, 1, 1);
+                    }
+                });
+            });
+            // 原点（左上）の印。上下・左右が反転していないことを確かめられるようにする
+            this.ctx.fillStyle = '#FFB000';
+            this.ctx.fillRect(0, 0, 1, 1);
+            if (state.active && this.cursor) {
+                this.ctx.fillStyle = '#FF3399';
+                this.ctx.fillRect(
+                    Math.floor(this.cursor.x / thumbnail.scale),
+                    Math.floor(this.cursor.y / thumbnail.scale),
+                    1,
+                    1
+                );
+            }
+        }
+
+        const recent = document.getElementById('recent');
+        recent.replaceChildren(...state.recent_completions.map((run) => {
+            const item = document.createElement('li');
+            const finished = new Date(run.finished_at).toLocaleString();
+            item.textContent = `${finished} ${run.artwork_name || '(削除済み)'}: ${run.outcome} (${run.dots_painted} dots)`;
+            return item;
+        }));
+    }
+
+    showUnavailable() {
+        this.state = null;
+        document.getElementById('title').textContent = 'ギャラリーは公開されていません';
+        document.getElementById('status').textContent = '';
+    }
+}
+
+document.addEventListener('DOMContentLoaded', () => new Gallery());
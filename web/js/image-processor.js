@@ -154,8 +154,22 @@ class ImageProcessor {
         const height = imageData.height;
         const binaryData = new Array(width * height);
         
-        // 適応的2値化の場合
-        if (adjustments.adaptiveThreshold) {
+        // 組織的ディザ（点描）の場合はサーバーと同じ閾値で判定する
+        const matrix = ImageProcessor.BAYER_MATRICES[adjustments.toneMode];
+        if (matrix) {
+            const size = matrix.length;
+            const cells = size * size;
+            for (let y = 0; y < height; y++) {
+                for (let x = 0; x < width; x++) {
+                    const pixelIndex = y * width + x;
+                    const i = pixelIndex * 4;
+                    // Rust側のColor::to_grayscaleと同じBT.709係数・切り捨て
+                    const gray = Math.floor(0.2126 * data[i] + 0.7152 * data[i + 1] + 0.0722 * data[i + 2]);
+                    const cellThreshold = Math.floor((matrix[y % size][x % size] * 256 + 128) / cells);
+                    binaryData[pixelIndex] = gray < cellThreshold;
+                }
+            }
+        } else if (adjustments.adaptiveThreshold) {
             const blockSize = adjustments.adaptiveBlockSize || 11;
             const constant = adjustments.adaptiveConstant || 2;
             const halfBlock = Math.floor(blockSize / 2);
@@ -214,9 +228,10 @@ class ImageProcessor {
      * @param {Array<boolean>} binaryData - 2値化データ
      * @param {number} width - 幅
      * @param {number} height - 高さ
+     * @param {ImageData} [imageData] - 指定時は元の色をそのまま送信（点描モード用）
      * @returns {Array} - ドットデータの配列
      */
-    convertToDotData(binaryData, width, height) {
+    convertToDotData(binaryData, width, height, imageData = null) {
         const dots = [];
         
         for (let y = 0; y < height; y++) {
@@ -226,7 +241,7 @@ class ImageProcessor {
                     dots.push({
                         x: x,
                         y: y,
-                        color: '#000000'
+                        color: imageData ? this.pixelToHex(imageData.data, index * 4) : '#000000'
                     });
                 }
             }
@@ -235,6 +250,18 @@ class ImageProcessor {
         return dots;
     }
 
+    /**
+     * RGBA配列の指定位置の色を#RRGGBB形式に変換
+     * @param {Uint8ClampedArray} data - ピクセルデータ
+     * @param {number} offset - 先頭インデックス
+     * @returns {string} - 16進カラー
+     */
+    pixelToHex(data, offset) {
+        return '#' + [data[offset], data[offset + 1], data[offset + 2]]
+            .map(v => v.toString(16).padStart(2, '0'))
+            .join('');
+    }
+
     /**
      * 画像調整を適用
      * @param {ImageData} imageData - 画像データ
@@ -354,5 +381,19 @@ class ImageProcessor {
     }
 }
 
+// 点描モードのBayer行列（サーバー側のOrderedMatrixSizeと同一）
+ImageProcessor.BAYER_MATRICES = {
+    stipple2: [
+        [0, 2],
+        [3, 1]
+    ],
+    stipple4: [
+        [0, 8, 2, 10],
+        [12, 4, 14, 6],
+        [3, 11, 1, 9],
+        [15, 7, 13, 5]
+    ]
+};
+
 // グローバルに公開
 window.ImageProcessor = ImageProcessor;
\ No newline at end of file